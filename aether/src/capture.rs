@@ -0,0 +1,427 @@
+//! Screen capture
+//!
+//! Backs wlr-screencopy, ext-image-copy-capture and the screencast portal.
+//! Every capture session starts out pending: Guardian has to allow
+//! `display:capture` for the specific output or window before a single
+//! frame is copied, and if Guardian cannot be reached the request is
+//! refused. While any session is active herald shows a persistent
+//! indicator naming who is capturing what, with an action that stops all
+//! capture at once.
+
+use crate::security::SecurityManager;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::UnixStream;
+use tokio::runtime::Handle;
+use tokio::sync::{mpsc, watch};
+use tracing::{debug, info, warn};
+
+/// Indicator action that ends every capture session
+const STOP_ACTION: &str = "stop-capture";
+
+/// What a session captures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CaptureSource {
+    Output { id: u32 },
+    Window { id: u64 },
+}
+
+/// How the client asked to capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureProtocol {
+    /// zwlr_screencopy_manager_v1
+    WlrScreencopy,
+    /// ext_image_copy_capture_manager_v1
+    ExtImageCopy,
+    /// xdg-desktop-portal ScreenCast/Screenshot via the control socket
+    Portal,
+    /// Remote desktop viewer
+    Remote,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionState {
+    /// Waiting on Guardian
+    PendingConsent,
+    /// Allowed; frames may be copied
+    Active,
+}
+
+/// A client's capture session
+#[derive(Debug, Clone)]
+pub struct CaptureSession {
+    pub id: u64,
+    pub client_id: u32,
+    pub client_path: String,
+    pub source: CaptureSource,
+    /// Human-readable source, e.g. `output HDMI-A-1`
+    pub label: String,
+    pub protocol: CaptureProtocol,
+    /// Whether the cursor is composited into frames
+    pub overlay_cursor: bool,
+    pub state: SessionState,
+    pub started_at: Instant,
+    pub frames: u64,
+}
+
+/// Session changes the Wayland frontend has to pass on to clients
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptureEvent {
+    /// Guardian allowed the session; buffer constraints can be sent
+    Granted { session: u64 },
+    /// Guardian refused; the frame or session fails
+    Denied { session: u64 },
+    /// The session ended (by the user, the source going away or the client)
+    Stopped { session: u64 },
+}
+
+/// Guardian's answer for a pending session
+struct Decision {
+    session: u64,
+    allowed: bool,
+}
+
+/// Capture sessions and their consent state
+pub struct CaptureManager {
+    security: Arc<SecurityManager>,
+    runtime: Handle,
+    sessions: HashMap<u64, CaptureSession>,
+    next_id: u64,
+    decisions_tx: mpsc::UnboundedSender<Decision>,
+    decisions_rx: mpsc::UnboundedReceiver<Decision>,
+    stop_rx: mpsc::UnboundedReceiver<()>,
+    /// Indicator lines; an empty list withdraws the indicator
+    indicator: Option<watch::Sender<Vec<String>>>,
+}
+
+impl CaptureManager {
+    /// Create a capture manager
+    ///
+    /// With `herald_socket` set, active capture is announced through a
+    /// herald notification.
+    pub fn new(security: Arc<SecurityManager>, runtime: Handle, herald_socket: Option<PathBuf>) -> Self {
+        let (decisions_tx, decisions_rx) = mpsc::unbounded_channel();
+        let (stop_tx, stop_rx) = mpsc::unbounded_channel();
+
+        let indicator = herald_socket.map(|socket| {
+            let (tx, rx) = watch::channel(Vec::new());
+            runtime.spawn(run_indicator(socket, rx, stop_tx));
+            tx
+        });
+
+        Self {
+            security,
+            runtime,
+            sessions: HashMap::new(),
+            next_id: 1,
+            decisions_tx,
+            decisions_rx,
+            stop_rx,
+            indicator,
+        }
+    }
+
+    /// Start a capture session and ask Guardian about it
+    ///
+    /// `resource` names the source for Guardian (`output:HDMI-A-1`,
+    /// `window:42`). The session stays pending until `poll` reports the
+    /// decision.
+    #[allow(clippy::too_many_arguments)]
+    pub fn request(
+        &mut self,
+        client_id: u32,
+        client_path: &str,
+        source: CaptureSource,
+        resource: String,
+        label: String,
+        protocol: CaptureProtocol,
+        overlay_cursor: bool,
+    ) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.sessions.insert(id, CaptureSession {
+            id,
+            client_id,
+            client_path: client_path.to_string(),
+            source,
+            label,
+            protocol,
+            overlay_cursor,
+            state: SessionState::PendingConsent,
+            started_at: Instant::now(),
+            frames: 0,
+        });
+
+        let security = Arc::clone(&self.security);
+        let decisions = self.decisions_tx.clone();
+        let client_path = client_path.to_string();
+        self.runtime.spawn(async move {
+            let allowed = match protocol {
+                CaptureProtocol::Remote => security.can_export_remote(&client_path, &resource).await,
+                _ => security.can_capture_resource(client_id, &client_path, &resource).await,
+            };
+            let _ = decisions.send(Decision { session: id, allowed });
+        });
+
+        debug!("Capture session {} requested by client {} ({:?})", id, client_id, protocol);
+        id
+    }
+
+    /// Collect Guardian decisions and indicator stop requests
+    pub fn poll(&mut self) -> Vec<CaptureEvent> {
+        let mut events = Vec::new();
+
+        while let Ok(decision) = self.decisions_rx.try_recv() {
+            let Some(session) = self.sessions.get_mut(&decision.session) else {
+                // Ended while Guardian was deciding
+                continue;
+            };
+
+            if decision.allowed {
+                session.state = SessionState::Active;
+                info!("Capture of {} granted to {}", session.label, session.client_path);
+                events.push(CaptureEvent::Granted { session: decision.session });
+            } else {
+                warn!("Capture of {} denied to {}", session.label, session.client_path);
+                self.sessions.remove(&decision.session);
+                events.push(CaptureEvent::Denied { session: decision.session });
+            }
+        }
+
+        let mut stop_all = false;
+        while self.stop_rx.try_recv().is_ok() {
+            stop_all = true;
+        }
+        if stop_all {
+            info!("Capture stopped from the indicator");
+            let ids: Vec<u64> = self.sessions.keys().copied().collect();
+            for id in ids {
+                self.sessions.remove(&id);
+                events.push(CaptureEvent::Stopped { session: id });
+            }
+        }
+
+        if !events.is_empty() {
+            self.update_indicator();
+        }
+        events
+    }
+
+    /// Account for a frame about to be copied
+    ///
+    /// Fails unless Guardian has allowed the session.
+    pub fn begin_frame(&mut self, session_id: u64) -> Result<&CaptureSession> {
+        let session = self.sessions
+            .get_mut(&session_id)
+            .ok_or_else(|| anyhow!("Unknown capture session {}", session_id))?;
+
+        if session.state != SessionState::Active {
+            return Err(anyhow!("Capture session {} is awaiting consent", session_id));
+        }
+
+        session.frames += 1;
+        Ok(session)
+    }
+
+    /// End a session
+    pub fn stop(&mut self, session_id: u64) -> Option<CaptureEvent> {
+        let session = self.sessions.remove(&session_id)?;
+        debug!("Capture session {} ended after {} frames", session_id, session.frames);
+        self.update_indicator();
+        Some(CaptureEvent::Stopped { session: session_id })
+    }
+
+    /// End every session matching `predicate`
+    fn stop_where(&mut self, predicate: impl Fn(&CaptureSession) -> bool) -> Vec<CaptureEvent> {
+        let ids: Vec<u64> = self.sessions
+            .values()
+            .filter(|s| predicate(s))
+            .map(|s| s.id)
+            .collect();
+
+        ids.into_iter().filter_map(|id| self.stop(id)).collect()
+    }
+
+    /// End a disconnected client's sessions
+    pub fn client_disconnected(&mut self, client_id: u32) -> Vec<CaptureEvent> {
+        self.stop_where(|s| s.client_id == client_id)
+    }
+
+    /// End sessions capturing a source that went away
+    pub fn source_removed(&mut self, source: CaptureSource) -> Vec<CaptureEvent> {
+        self.stop_where(|s| s.source == source)
+    }
+
+    pub fn get(&self, session_id: u64) -> Option<&CaptureSession> {
+        self.sessions.get(&session_id)
+    }
+
+    /// All sessions, oldest first
+    pub fn sessions(&self) -> Vec<&CaptureSession> {
+        let mut sessions: Vec<_> = self.sessions.values().collect();
+        sessions.sort_by_key(|s| s.id);
+        sessions
+    }
+
+    /// Show who is capturing what, or withdraw the indicator
+    fn update_indicator(&self) {
+        let Some(indicator) = &self.indicator else {
+            return;
+        };
+
+        let mut lines: Vec<String> = self.sessions()
+            .into_iter()
+            .filter(|s| s.state == SessionState::Active)
+            .map(|s| format!("{} is capturing {}", client_name(&s.client_path), s.label))
+            .collect();
+        lines.dedup();
+
+        indicator.send_if_modified(|current| {
+            if *current == lines {
+                return false;
+            }
+            *current = lines;
+            true
+        });
+    }
+}
+
+fn client_name(path: &str) -> &str {
+    Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(path)
+}
+
+/// Connection to herald holding the indicator notification
+struct Indicator {
+    id: u64,
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    _writer: OwnedWriteHalf,
+}
+
+/// Keep the herald indicator in step with active sessions
+async fn run_indicator(
+    socket: PathBuf,
+    mut lines_rx: watch::Receiver<Vec<String>>,
+    stop: mpsc::UnboundedSender<()>,
+) {
+    let mut current: Option<Indicator> = None;
+
+    loop {
+        tokio::select! {
+            changed = lines_rx.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let lines = lines_rx.borrow_and_update().clone();
+
+                if lines.is_empty() {
+                    if let Some(indicator) = current.take() {
+                        if let Err(e) = close_indicator(&socket, indicator.id).await {
+                            warn!("Failed to withdraw capture indicator: {}", e);
+                        }
+                    }
+                } else {
+                    let replaces = current.as_ref().map(|i| i.id);
+                    match post_indicator(&socket, &lines, replaces).await {
+                        Ok(indicator) => current = Some(indicator),
+                        Err(e) => warn!("Failed to show capture indicator: {}", e),
+                    }
+                }
+            }
+            action = next_action(current.as_mut()) => match action {
+                Some(action) if action == STOP_ACTION => {
+                    let _ = stop.send(());
+                }
+                Some(_) => {}
+                None => current = None,
+            }
+        }
+    }
+
+    if let Some(indicator) = current {
+        let _ = close_indicator(&socket, indicator.id).await;
+    }
+}
+
+async fn post_indicator(socket: &Path, lines: &[String], replaces_id: Option<u64>) -> Result<Indicator> {
+    let request = json!({
+        "type": "Notify",
+        "data": {
+            "app_name": "Aether",
+            "summary": "Screen is being captured",
+            "body": lines.join("\n"),
+            "icon": "screen-recording",
+            "urgency": "critical",
+            "timeout": 0,
+            "replaces_id": replaces_id,
+            "actions": [{ "id": STOP_ACTION, "label": "Stop capture" }],
+        }
+    });
+
+    let (lines, writer, response) = send_herald(socket, &request).await?;
+    let id = response["data"]["id"]
+        .as_u64()
+        .ok_or_else(|| anyhow!("Herald rejected the indicator: {}", response))?;
+
+    Ok(Indicator {
+        id,
+        lines,
+        _writer: writer,
+    })
+}
+
+async fn close_indicator(socket: &Path, id: u64) -> Result<()> {
+    let request = json!({ "type": "CloseNotification", "data": { "id": id } });
+    let _ = send_herald(socket, &request).await?;
+    Ok(())
+}
+
+async fn send_herald(
+    socket: &Path,
+    request: &Value,
+) -> Result<(Lines<BufReader<OwnedReadHalf>>, OwnedWriteHalf, Value)> {
+    let stream = UnixStream::connect(socket).await?;
+    let (reader, mut writer) = stream.into_split();
+
+    writer.write_all(serde_json::to_string(request)?.as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+    writer.flush().await?;
+
+    let mut lines = BufReader::new(reader).lines();
+    let line = lines
+        .next_line()
+        .await?
+        .ok_or_else(|| anyhow!("Herald closed the connection"))?;
+
+    Ok((lines, writer, serde_json::from_str(&line)?))
+}
+
+/// Next action invoked on the indicator; pends forever without one
+async fn next_action(indicator: Option<&mut Indicator>) -> Option<String> {
+    let Some(indicator) = indicator else {
+        return std::future::pending().await;
+    };
+
+    while let Ok(Some(line)) = indicator.lines.next_line().await {
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        if message["status"] == "Action" && message["event"]["id"].as_u64() == Some(indicator.id) {
+            return message["event"]["action_id"].as_str().map(String::from);
+        }
+    }
+    None
+}
@@ -2,34 +2,58 @@
 //!
 //! Main compositor state and event loop.
 
+use crate::capture::{CaptureEvent, CaptureManager, CaptureProtocol, CaptureSource, SessionState};
 use crate::config::AetherConfig;
 use crate::input::InputState;
-use crate::output::OutputManager;
+use crate::ipc::{CaptureInfo, WindowInfo};
+use crate::output::{OutputManager, XdgOutputInfo};
+use crate::remote::{RemoteCommand, RemoteDisplay, RemoteInput, RemoteServer};
 use crate::render::Renderer;
+use crate::rules::WindowRules;
 use crate::security::SecurityManager;
-use crate::shell::ShellManager;
-use crate::window::WindowManager;
-use anyhow::{Context, Result};
+use crate::shell::{CommitOutcome, ShellManager, XdgRole, XdgToplevel};
+use crate::toplevel::{ForeignToplevels, ToplevelEvent, ToplevelInfo, ToplevelRequest, MANAGER_GLOBAL};
+use crate::window::{WindowGeometry, WindowManager, WindowState};
+use anyhow::{anyhow, Context, Result};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
+use tokio::sync::oneshot;
 use tracing::{debug, error, info, warn};
 
 /// Compositor state
 pub struct Compositor {
     /// Configuration
     config: AetherConfig,
+    /// Runtime for Guardian and herald requests
+    runtime: tokio::runtime::Runtime,
     /// Security manager (Guardian integration)
-    security: SecurityManager,
+    security: Arc<SecurityManager>,
+    /// Screen capture sessions
+    capture: CaptureManager,
+    /// Window rules
+    rules: WindowRules,
+    /// Remote desktop listener, when enabled
+    remote: Option<RemoteServer>,
+    /// Remote viewers waiting on Guardian, by capture session
+    remote_pending: HashMap<u64, oneshot::Sender<Result<RemoteDisplay>>>,
+    /// Output shown to each remote viewer, by capture session
+    remote_sessions: HashMap<u64, u32>,
     /// Output manager
     outputs: OutputManager,
     /// Window manager
     windows: WindowManager,
     /// Shell manager (xdg-shell, layer-shell, etc.)
     shell: ShellManager,
+    /// Foreign toplevel management clients and announced windows
+    toplevels: ForeignToplevels,
     /// Input state
     input: InputState,
+    /// Per-output area left for windows after layer-shell exclusive zones
+    usable_areas: HashMap<u32, WindowGeometry>,
+    /// xdg-output state last advertised per output
+    xdg_outputs: HashMap<u32, XdgOutputInfo>,
     /// Renderer
     renderer: Renderer,
     /// Running state
@@ -56,7 +80,35 @@ impl Compositor {
         info!("Initializing Aether compositor");
 
         // Initialize security manager
-        let security = SecurityManager::new(&config.security, guardian_socket)?;
+        let security = Arc::new(SecurityManager::new(&config.security, guardian_socket)?);
+
+        // Guardian and herald are reached asynchronously off the main loop
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .context("Failed to start async runtime")?;
+
+        // Initialize capture manager
+        let herald_socket = config.security.capture_indicator
+            .then(|| config.security.herald_socket.clone());
+        let capture = CaptureManager::new(Arc::clone(&security), runtime.handle().clone(), herald_socket);
+
+        // Load window rules and follow grimoire for changes
+        let rules = WindowRules::new(&config.rules, runtime.handle());
+
+        // Start remote desktop export if configured
+        let remote = if config.remote.enabled {
+            match RemoteServer::start(&config.remote, runtime.handle()) {
+                Ok(server) => Some(server),
+                Err(e) => {
+                    warn!("Remote desktop disabled: {:#}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
 
         // Initialize output manager
         let outputs = OutputManager::new(&config.display)?;
@@ -75,19 +127,30 @@ impl Compositor {
 
         info!("Compositor initialized successfully");
 
+        let xwayland_enabled = xwayland && config.xwayland.enabled;
+
         Ok(Self {
             config,
+            runtime,
             security,
+            capture,
+            rules,
+            remote,
+            remote_pending: HashMap::new(),
+            remote_sessions: HashMap::new(),
             outputs,
             windows,
             shell,
+            toplevels: ForeignToplevels::new(),
             input,
+            usable_areas: HashMap::new(),
+            xdg_outputs: HashMap::new(),
             renderer,
             running: false,
             start_time: Instant::now(),
             frame_count: 0,
             windowed,
-            xwayland_enabled: xwayland && config.xwayland.enabled,
+            xwayland_enabled,
         })
     }
 
@@ -162,9 +225,648 @@ impl Compositor {
         // Process DRM events (mode changes, hotplug)
         // Process XWayland events (if enabled)
 
+        self.handle_output_changes();
+        if self.rules.poll() {
+            self.reapply_rules();
+        }
+        self.flush_configures();
+        self.publish_toplevels();
+        self.handle_remote_commands();
+        for event in self.capture.poll() {
+            self.send_capture_event(event);
+        }
+
         Ok(())
     }
 
+    /// Advertise changed outputs over xdg-output and re-arrange their layers
+    fn handle_output_changes(&mut self) {
+        for output_id in self.outputs.take_changed() {
+            let Some(output) = self.outputs.get(output_id) else {
+                continue;
+            };
+
+            let info = output.xdg_output();
+            if self.xdg_outputs.get(&output_id) != Some(&info) {
+                debug!(
+                    "xdg-output {}: logical {:?} {:?}",
+                    info.name, info.logical_position, info.logical_size
+                );
+                self.xdg_outputs.insert(output_id, info);
+            }
+
+            self.arrange_output(output_id);
+        }
+    }
+
+    /// Lay out an output's layer surfaces and fit maximized windows to
+    /// what is left
+    fn arrange_output(&mut self, output_id: u32) {
+        let Some(output) = self.outputs.get(output_id) else {
+            return;
+        };
+        let (x, y, width, height) = output.logical_area();
+        let area = WindowGeometry { x, y, width, height };
+
+        let usable = self.shell.arrange_layers(output_id, area);
+        if self.usable_areas.insert(output_id, usable) == Some(usable) {
+            return;
+        }
+
+        let maximized: Vec<(u64, u64)> = self.shell.toplevels()
+            .filter(|(_, _, toplevel)| toplevel.maximized)
+            .map(|(surface_id, window_id, _)| (surface_id, window_id))
+            .collect();
+
+        for (surface_id, window_id) in maximized {
+            if self.output_for_window(window_id) == Some(output_id) {
+                self.maximize(surface_id, true);
+            }
+        }
+    }
+
+    /// Remove a disconnected output
+    pub fn remove_output(&mut self, output_id: u32) {
+        for event in self.capture.source_removed(CaptureSource::Output { id: output_id }) {
+            self.send_capture_event(event);
+        }
+        self.outputs.remove_output(output_id);
+        self.shell.output_removed(output_id);
+        self.usable_areas.remove(&output_id);
+        self.xdg_outputs.remove(&output_id);
+    }
+
+    /// Area available to windows on an output
+    pub fn usable_area(&self, output_id: u32) -> Option<WindowGeometry> {
+        self.usable_areas.get(&output_id).copied()
+    }
+
+    /// Output a window is on: the one containing its centre, else the first
+    fn output_for_window(&self, window_id: u64) -> Option<u32> {
+        let g = self.windows.get(window_id)?.geometry;
+        let (cx, cy) = (g.x + g.width as i32 / 2, g.y + g.height as i32 / 2);
+
+        self.outputs.enabled()
+            .find(|o| o.contains_point(cx, cy))
+            .or_else(|| self.outputs.enabled().min_by_key(|o| o.id))
+            .map(|o| o.id)
+    }
+
+    /// Handle a surface commit from a client
+    ///
+    /// An error is a protocol violation and the client should be
+    /// disconnected.
+    pub fn handle_commit(&mut self, surface_id: u64, has_buffer: bool) -> Result<()> {
+        match self.shell.commit(surface_id, has_buffer)? {
+            CommitOutcome::Unchanged => {}
+            CommitOutcome::NeedsInitialConfigure => self.initial_configure(surface_id)?,
+            CommitOutcome::Rearrange(output_id) => self.arrange_output(output_id),
+            CommitOutcome::Applied => self.sync_window(surface_id, has_buffer),
+        }
+        Ok(())
+    }
+
+    /// First configure of an xdg surface; the client picks its own size
+    fn initial_configure(&mut self, surface_id: u64) -> Result<()> {
+        let Some(surface) = self.shell.get_xdg(surface_id) else {
+            return Ok(());
+        };
+
+        match &surface.role {
+            XdgRole::Toplevel(toplevel) => {
+                let states = toplevel.states();
+                let size = self.place_window(surface_id).unwrap_or((0, 0));
+                self.shell.configure_toplevel(surface_id, size, states)?;
+            }
+            XdgRole::Popup(popup) => {
+                // Keep popups on the parent's output, in parent coordinates
+                let parent_window = self.shell.get_xdg(popup.parent).map(|p| p.window_id);
+                let parent = parent_window.and_then(|w| self.windows.get(w)).map(|w| w.geometry);
+                let output = parent_window.and_then(|w| self.output_for_window(w));
+                let mut bounds = output
+                    .and_then(|o| self.usable_area(o))
+                    .unwrap_or_default();
+                if let Some(parent) = parent {
+                    bounds.x -= parent.x;
+                    bounds.y -= parent.y;
+                }
+                self.shell.configure_popup(surface_id, bounds, None)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Mirror an applied toplevel configure into the window manager
+    fn sync_window(&mut self, surface_id: u64, has_buffer: bool) {
+        let Some(surface) = self.shell.get_xdg(surface_id) else {
+            return;
+        };
+        let window_id = surface.window_id;
+        let XdgRole::Toplevel(toplevel) = &surface.role else {
+            return;
+        };
+
+        // xdg-shell has no minimized state; it lasts until restored
+        let state = match self.windows.get(window_id) {
+            Some(window) if window.state == WindowState::Minimized => WindowState::Minimized,
+            _ => window_state(toplevel),
+        };
+        let (width, height) = toplevel.size;
+
+        let mut renamed = false;
+        if let Some(window) = self.windows.get_mut(window_id) {
+            if width > 0 && height > 0 {
+                window.geometry.width = width;
+                window.geometry.height = height;
+            }
+            renamed = toplevel.title.as_ref().is_some_and(|t| *t != window.title)
+                || toplevel.app_id != window.app_id;
+            if let Some(title) = &toplevel.title {
+                window.title = title.clone();
+            }
+            window.app_id = toplevel.app_id.clone();
+        }
+        if renamed {
+            self.apply_rules(window_id);
+        }
+        self.windows.set_state(window_id, state);
+        if has_buffer {
+            self.windows.map(window_id);
+        } else {
+            self.windows.unmap(window_id);
+        }
+    }
+
+    /// Apply window rules to a new toplevel
+    ///
+    /// Sets its workspace and grants rule capabilities, and returns the
+    /// rule size (if any) for the initial configure.
+    fn place_window(&mut self, surface_id: u64) -> Option<(u32, u32)> {
+        let surface = self.shell.get_xdg(surface_id)?;
+        let window_id = surface.window_id;
+        let XdgRole::Toplevel(toplevel) = &surface.role else {
+            return None;
+        };
+
+        let window = self.windows.get_mut(window_id)?;
+        if let Some(title) = &toplevel.title {
+            window.title = title.clone();
+        }
+        window.app_id = toplevel.app_id.clone();
+
+        let resolved = self.rules.resolve(window.app_id.as_deref(), &window.title);
+        if !resolved.matched.is_empty() {
+            debug!("Window {} matched rules {:?}", window_id, resolved.matched);
+        }
+        let size = resolved.size.map(|size| toplevel.constrain(size));
+        if let Some(workspace) = resolved.workspace {
+            window.workspace = workspace;
+        }
+
+        let client_id = window.client_id;
+        for capability in &resolved.grant {
+            self.runtime.block_on(self.security.grant_capability(client_id, capability));
+        }
+
+        self.apply_rules(window_id);
+        size
+    }
+
+    /// Apply the rules that can change while a window is open
+    fn apply_rules(&mut self, window_id: u64) {
+        let Some(window) = self.windows.get_mut(window_id) else {
+            return;
+        };
+
+        let resolved = self.rules.resolve(window.app_id.as_deref(), &window.title);
+        window.floating = resolved.floating.unwrap_or(false);
+        window.opacity = resolved.opacity.unwrap_or(1.0);
+    }
+
+    /// Re-apply reloaded rules to every open toplevel
+    fn reapply_rules(&mut self) {
+        let window_ids: Vec<u64> = self.shell.toplevels().map(|(_, window_id, _)| window_id).collect();
+        for window_id in window_ids {
+            self.apply_rules(window_id);
+        }
+    }
+
+    /// Maximize or restore a toplevel to its output's usable area
+    pub fn maximize(&mut self, surface_id: u64, maximized: bool) {
+        let Some((_, window_id, toplevel)) = self.shell.toplevels().find(|(id, _, _)| *id == surface_id) else {
+            return;
+        };
+        let mut states = toplevel.states();
+        states.maximized = maximized;
+
+        let size = if maximized {
+            let area = self.output_for_window(window_id).and_then(|o| self.usable_area(o));
+            match area {
+                Some(area) => {
+                    if let Some(window) = self.windows.get_mut(window_id) {
+                        window.geometry.x = area.x;
+                        window.geometry.y = area.y;
+                    }
+                    toplevel.constrain((area.width, area.height))
+                }
+                None => (0, 0),
+            }
+        } else {
+            (0, 0)
+        };
+
+        if let Err(e) = self.shell.configure_toplevel(surface_id, size, states) {
+            warn!("Failed to configure surface {}: {}", surface_id, e);
+        }
+    }
+
+    /// A client binds the foreign toplevel manager
+    ///
+    /// Returns false when Guardian does not allow it, in which case the
+    /// frontend does not create the manager resource.
+    pub fn bind_foreign_toplevels(&mut self, client_id: u32, client_path: &str) -> bool {
+        let allowed = self.runtime.block_on(
+            self.security.can_use_protocol(client_id, client_path, MANAGER_GLOBAL),
+        );
+        if allowed {
+            self.toplevels.bind(client_id);
+        } else {
+            warn!("Client {} ({}) denied foreign toplevel management", client_id, client_path);
+        }
+        allowed
+    }
+
+    /// The client stopped listening on its foreign toplevel manager
+    pub fn stop_foreign_toplevels(&mut self, client_id: u32) {
+        self.toplevels.stop(client_id);
+    }
+
+    /// Handle a request on a foreign toplevel handle
+    pub fn handle_toplevel_request(&mut self, client_id: u32, window_id: u64, request: ToplevelRequest) {
+        if !self.toplevels.is_bound(client_id) {
+            return;
+        }
+        let Some(surface_id) = self.surface_for_window(window_id) else {
+            // Inert handle for a window that already closed
+            return;
+        };
+        debug!("Client {} asked for {:?} on window {}", client_id, request, window_id);
+
+        match request {
+            ToplevelRequest::Activate => self.activate_window(window_id),
+            ToplevelRequest::SetMinimized => {
+                let focused = self.windows.focused();
+                self.windows.minimize(window_id);
+                for id in [focused, self.windows.focused()].into_iter().flatten() {
+                    self.configure_activated(id);
+                }
+            }
+            ToplevelRequest::UnsetMinimized => self.restore_window(window_id),
+            ToplevelRequest::SetMaximized => self.maximize(surface_id, true),
+            ToplevelRequest::UnsetMaximized => self.maximize(surface_id, false),
+            ToplevelRequest::SetFullscreen | ToplevelRequest::UnsetFullscreen => {
+                debug!("Fullscreen over foreign toplevel is not supported");
+            }
+            ToplevelRequest::Close => self.shell.close(surface_id),
+        }
+    }
+
+    /// Restore, raise and focus a window, updating the activated state of
+    /// it and the window that had focus
+    fn activate_window(&mut self, window_id: u64) {
+        self.restore_window(window_id);
+        let previous = self.windows.focused();
+        self.windows.focus(window_id);
+
+        for id in [previous, Some(window_id)].into_iter().flatten() {
+            self.configure_activated(id);
+        }
+    }
+
+    /// Undo a minimize, back to the state the client last configured
+    fn restore_window(&mut self, window_id: u64) {
+        let state = self.shell.toplevels()
+            .find(|(_, id, _)| *id == window_id)
+            .map(|(_, _, toplevel)| window_state(toplevel))
+            .unwrap_or(WindowState::Normal);
+        self.windows.restore(window_id, state);
+    }
+
+    /// Configure a toplevel whose focus changed
+    fn configure_activated(&mut self, window_id: u64) {
+        let activated = self.windows.focused() == Some(window_id);
+        let Some((surface_id, _, toplevel)) = self.shell.toplevels().find(|(_, id, _)| *id == window_id) else {
+            return;
+        };
+        if toplevel.activated == activated {
+            return;
+        }
+        let mut states = toplevel.states();
+        states.activated = activated;
+        let size = toplevel.size;
+
+        if let Err(e) = self.shell.configure_toplevel(surface_id, size, states) {
+            warn!("Failed to configure surface {}: {}", surface_id, e);
+        }
+    }
+
+    /// xdg surface of a toplevel window
+    fn surface_for_window(&self, window_id: u64) -> Option<u64> {
+        self.shell.toplevels()
+            .find(|(_, id, _)| *id == window_id)
+            .map(|(surface_id, _, _)| surface_id)
+    }
+
+    /// Tell foreign toplevel clients about mapped windows that appeared,
+    /// changed or went away
+    fn publish_toplevels(&mut self) {
+        let focused = self.windows.focused();
+        let mut live = Vec::new();
+
+        for (_, window_id, toplevel) in self.shell.toplevels() {
+            let Some(window) = self.windows.get(window_id).filter(|w| w.mapped) else {
+                continue;
+            };
+            live.push(window_id);
+            self.toplevels.publish(window_id, ToplevelInfo {
+                title: window.title.clone(),
+                app_id: window.app_id.clone().unwrap_or_default(),
+                activated: focused == Some(window_id),
+                minimized: window.state == WindowState::Minimized,
+                maximized: toplevel.maximized,
+                fullscreen: toplevel.fullscreen,
+            });
+        }
+        self.toplevels.retain(|window_id| live.contains(&window_id));
+
+        for event in self.toplevels.drain() {
+            // Handed to the Wayland frontend: handle events, each batch
+            // ending in done
+            match &event {
+                ToplevelEvent::Announce { info, .. } | ToplevelEvent::Changed { info, .. } => {
+                    debug!("foreign toplevel {:?} states={:?}", event, info.states());
+                }
+                ToplevelEvent::Closed { .. } | ToplevelEvent::Finished { .. } => {
+                    debug!("foreign toplevel {:?}", event);
+                }
+            }
+        }
+    }
+
+    /// Send queued configures to clients
+    fn flush_configures(&mut self) {
+        for configure in self.shell.drain_configures() {
+            // Handed to the Wayland frontend for delivery
+            debug!(
+                "configure surface={} serial={} {:?}",
+                configure.surface_id, configure.serial, configure.state
+            );
+        }
+    }
+
+    /// Mapped windows for the control socket
+    pub fn list_windows(&self) -> Vec<WindowInfo> {
+        let focused = self.windows.focused();
+        self.shell.toplevels()
+            .filter_map(|(_, window_id, _)| self.windows.get(window_id))
+            .filter(|w| w.mapped)
+            .map(|w| WindowInfo {
+                id: w.id,
+                title: w.title.clone(),
+                app_id: w.app_id.clone(),
+                x: w.geometry.x,
+                y: w.geometry.y,
+                width: w.geometry.width,
+                height: w.geometry.height,
+                state: format!("{:?}", w.state).to_lowercase(),
+                focused: focused == Some(w.id),
+                workspace: w.workspace,
+                client_pid: Some(w.client_id),
+            })
+            .collect()
+    }
+
+    /// Move a window to another workspace, e.g. when it is dragged there
+    /// in the shell's overview
+    pub fn move_to_workspace(&mut self, window_id: u64, workspace: u32) -> Result<()> {
+        if self.windows.get(window_id).is_none() {
+            return Err(anyhow!("No such window: {}", window_id));
+        }
+        if workspace == 0 {
+            return Err(anyhow!("Workspaces are numbered from 1"));
+        }
+        self.windows.set_workspace(window_id, workspace);
+        Ok(())
+    }
+
+    /// Start a capture session for a client
+    ///
+    /// Nothing can be copied until Guardian allows it; the outcome arrives
+    /// as a capture event.
+    pub fn request_capture(
+        &mut self,
+        client_id: u32,
+        client_path: &str,
+        source: CaptureSource,
+        protocol: CaptureProtocol,
+        overlay_cursor: bool,
+    ) -> Result<u64> {
+        let (resource, label) = match source {
+            CaptureSource::Output { id } => {
+                let output = self.outputs.get(id).ok_or_else(|| anyhow!("No such output: {}", id))?;
+                (format!("output:{}", output.name), format!("output {}", output.name))
+            }
+            CaptureSource::Window { id } => {
+                let window = self.windows.get(id).ok_or_else(|| anyhow!("No such window: {}", id))?;
+                (format!("window:{}", id), format!("window \"{}\"", window.title))
+            }
+        };
+
+        Ok(self.capture.request(client_id, client_path, source, resource, label, protocol, overlay_cursor))
+    }
+
+    /// Copy the next frame of an allowed capture session
+    pub fn capture_frame(&mut self, session_id: u64) -> Result<CapturedFrame> {
+        let session = self.capture.begin_frame(session_id)?;
+        let (source, overlay_cursor) = (session.source, session.overlay_cursor);
+
+        let region = match source {
+            CaptureSource::Output { id } => {
+                let output = self.outputs.get(id).ok_or_else(|| anyhow!("Output {} is gone", id))?;
+                WindowGeometry {
+                    x: output.position.0,
+                    y: output.position.1,
+                    width: output.resolution.0,
+                    height: output.resolution.1,
+                }
+            }
+            CaptureSource::Window { id } => {
+                self.windows.get(id).ok_or_else(|| anyhow!("Window {} is gone", id))?.geometry
+            }
+        };
+
+        let cursor = overlay_cursor.then_some(&self.input);
+        let data = self.renderer.read_region(region, cursor)?;
+
+        Ok(CapturedFrame {
+            width: region.width,
+            height: region.height,
+            format: "RGBA8888",
+            data,
+        })
+    }
+
+    /// End a capture session at the client's request
+    pub fn stop_capture(&mut self, session_id: u64) {
+        if let Some(event) = self.capture.stop(session_id) {
+            self.send_capture_event(event);
+        }
+    }
+
+    /// Capture sessions for the control socket
+    pub fn list_captures(&self) -> Vec<CaptureInfo> {
+        self.capture.sessions()
+            .into_iter()
+            .map(|s| CaptureInfo {
+                session: s.id,
+                client_pid: s.client_id,
+                client_path: s.client_path.clone(),
+                source: s.source,
+                protocol: s.protocol,
+                state: s.state,
+                frames: s.frames,
+                duration_secs: s.started_at.elapsed().as_secs(),
+            })
+            .collect()
+    }
+
+    /// Clean up after a client that went away
+    pub fn client_disconnected(&mut self, client_id: u32) {
+        for event in self.capture.client_disconnected(client_id) {
+            self.send_capture_event(event);
+        }
+        self.toplevels.client_disconnected(client_id);
+        self.runtime.block_on(self.security.client_disconnected(client_id));
+    }
+
+    /// Pass a capture session change on to its client
+    fn send_capture_event(&mut self, event: CaptureEvent) {
+        match event {
+            CaptureEvent::Granted { session } => {
+                if let Some(reply) = self.remote_pending.remove(&session) {
+                    let _ = reply.send(self.remote_display(session));
+                    return;
+                }
+            }
+            CaptureEvent::Denied { session } | CaptureEvent::Stopped { session } => {
+                self.remote_sessions.remove(&session);
+                if let Some(reply) = self.remote_pending.remove(&session) {
+                    let _ = reply.send(Err(anyhow!("Remote access was not allowed")));
+                    return;
+                }
+            }
+        }
+
+        // Handed to the Wayland frontend: buffer constraints on grant,
+        // failed/stopped otherwise
+        debug!("capture {:?}", event);
+    }
+
+    /// Serve requests from remote desktop viewers
+    fn handle_remote_commands(&mut self) {
+        let Some(remote) = &mut self.remote else {
+            return;
+        };
+        let mut commands = Vec::new();
+        while let Some(command) = remote.try_recv() {
+            commands.push(command);
+        }
+
+        for command in commands {
+            match command {
+                RemoteCommand::Start { identity, reply } => {
+                    let Some(output_id) = self.remote_output() else {
+                        let _ = reply.send(Err(anyhow!("No output to export")));
+                        continue;
+                    };
+                    let source = CaptureSource::Output { id: output_id };
+                    match self.request_capture(0, &identity, source, CaptureProtocol::Remote, true) {
+                        Ok(session) => {
+                            self.remote_sessions.insert(session, output_id);
+                            self.remote_pending.insert(session, reply);
+                        }
+                        Err(e) => {
+                            let _ = reply.send(Err(e));
+                        }
+                    }
+                }
+                RemoteCommand::Frame { session, reply } => {
+                    let frame = if self.remote_sessions.contains_key(&session) {
+                        self.capture_frame(session)
+                    } else {
+                        Err(anyhow!("Remote session {} has ended", session))
+                    };
+                    let _ = reply.send(frame);
+                }
+                RemoteCommand::Input { session, input } => {
+                    let active = self.capture.get(session).is_some_and(|s| s.state == SessionState::Active);
+                    if let (true, Some(&output_id)) = (active, self.remote_sessions.get(&session)) {
+                        self.remote_input(output_id, input);
+                    }
+                }
+                RemoteCommand::Stop { session } => {
+                    self.remote_sessions.remove(&session);
+                    self.remote_pending.remove(&session);
+                    self.stop_capture(session);
+                }
+            }
+        }
+    }
+
+    /// Output remote viewers see: the first configured one that is
+    /// enabled, else the first enabled output
+    fn remote_output(&self) -> Option<u32> {
+        let allowed = &self.config.remote.outputs;
+        if allowed.is_empty() {
+            return self.outputs.enabled().min_by_key(|o| o.id).map(|o| o.id);
+        }
+
+        allowed.iter()
+            .find_map(|name| self.outputs.enabled().find(|o| &o.name == name))
+            .map(|o| o.id)
+    }
+
+    fn remote_display(&self, session: u64) -> Result<RemoteDisplay> {
+        let output = self.remote_sessions
+            .get(&session)
+            .and_then(|&id| self.outputs.get(id))
+            .ok_or_else(|| anyhow!("Exported output is gone"))?;
+
+        Ok(RemoteDisplay {
+            session,
+            name: format!("Nyx {}", output.name),
+            width: output.resolution.0,
+            height: output.resolution.1,
+        })
+    }
+
+    /// Feed remote input in as if it came from local devices
+    fn remote_input(&mut self, output_id: u32, input: RemoteInput) {
+        match input {
+            RemoteInput::Motion { x, y } => {
+                let Some(output) = self.outputs.get(output_id) else {
+                    return;
+                };
+                let (ox, oy) = output.position;
+                self.input.pointer_motion(ox as f64 + x as f64, oy as f64 + y as f64);
+            }
+            RemoteInput::Key { keycode, pressed: true } => self.input.key_press(keycode),
+            RemoteInput::Key { keycode, pressed: false } => self.input.key_release(keycode),
+            RemoteInput::Button { button, pressed: true } => self.input.pointer_button_press(button),
+            RemoteInput::Button { button, pressed: false } => self.input.pointer_button_release(button),
+        }
+    }
+
     /// Render a frame
     fn render_frame(&mut self) -> Result<()> {
         // Start frame
@@ -217,6 +919,17 @@ impl Compositor {
     }
 }
 
+/// Window state matching a toplevel's xdg states
+fn window_state(toplevel: &XdgToplevel) -> WindowState {
+    if toplevel.fullscreen {
+        WindowState::Fullscreen
+    } else if toplevel.maximized {
+        WindowState::Maximized
+    } else {
+        WindowState::Normal
+    }
+}
+
 /// Compositor events
 #[derive(Debug, Clone)]
 pub enum CompositorEvent {
@@ -240,6 +953,15 @@ pub enum CompositorEvent {
     VrrChanged { output_id: u32, enabled: bool },
 }
 
+/// A copied capture frame
+#[derive(Debug, Clone)]
+pub struct CapturedFrame {
+    pub width: u32,
+    pub height: u32,
+    pub format: &'static str,
+    pub data: Vec<u8>,
+}
+
 /// Client information
 #[derive(Debug, Clone)]
 pub struct ClientInfo {
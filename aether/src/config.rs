@@ -2,7 +2,7 @@
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing::info;
 
 /// Aether configuration
@@ -31,6 +31,14 @@ pub struct AetherConfig {
     /// XWayland configuration
     #[serde(default)]
     pub xwayland: XWaylandConfig,
+
+    /// Window rules
+    #[serde(default)]
+    pub rules: RulesConfig,
+
+    /// Remote desktop export
+    #[serde(default)]
+    pub remote: RemoteConfig,
 }
 
 impl Default for AetherConfig {
@@ -42,6 +50,8 @@ impl Default for AetherConfig {
             security: SecurityConfig::default(),
             windows: WindowConfig::default(),
             xwayland: XWaylandConfig::default(),
+            rules: RulesConfig::default(),
+            remote: RemoteConfig::default(),
         }
     }
 }
@@ -421,6 +431,14 @@ pub struct SecurityConfig {
     /// Allow privileged Wayland protocols
     #[serde(default)]
     pub privileged_protocols: Vec<String>,
+
+    /// Show a herald indicator while anything captures the screen
+    #[serde(default = "default_true")]
+    pub capture_indicator: bool,
+
+    /// Herald socket for the capture indicator
+    #[serde(default = "default_herald_socket")]
+    pub herald_socket: PathBuf,
 }
 
 impl Default for SecurityConfig {
@@ -431,10 +449,16 @@ impl Default for SecurityConfig {
             input_grab_requires_cap: true,
             wm_requires_cap: false,
             privileged_protocols: Vec::new(),
+            capture_indicator: true,
+            herald_socket: default_herald_socket(),
         }
     }
 }
 
+fn default_herald_socket() -> PathBuf {
+    PathBuf::from("/run/herald/herald.sock")
+}
+
 /// Window management configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WindowConfig {
@@ -503,6 +527,176 @@ fn default_inactive_color() -> String {
     "#404040".into()
 }
 
+/// Window rules configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RulesConfig {
+    /// Initial rules (grimoire settings take over once synced)
+    #[serde(default)]
+    pub rules: Vec<WindowRule>,
+
+    /// Keep rules in sync with the grimoire settings store
+    #[serde(default = "default_true")]
+    pub grimoire_sync: bool,
+
+    /// Grimoire socket path
+    #[serde(default = "default_grimoire_socket")]
+    pub grimoire_socket: String,
+
+    /// Settings path holding the rule list
+    #[serde(default = "default_rules_setting")]
+    pub setting_path: String,
+
+    /// How often to poll grimoire for changes (seconds)
+    #[serde(default = "default_sync_interval")]
+    pub sync_interval_secs: u64,
+}
+
+impl Default for RulesConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            grimoire_sync: true,
+            grimoire_socket: default_grimoire_socket(),
+            setting_path: default_rules_setting(),
+            sync_interval_secs: default_sync_interval(),
+        }
+    }
+}
+
+/// A window rule
+///
+/// Every rule whose matchers all match applies, in order, so later rules
+/// override earlier ones field by field.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WindowRule {
+    /// Name shown when explaining which rules matched
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// App ID glob (`*` and `?`), e.g. `org.mozilla.*`
+    #[serde(default)]
+    pub app_id: Option<String>,
+
+    /// Title glob
+    #[serde(default)]
+    pub title: Option<String>,
+
+    /// Workspace to open on
+    #[serde(default)]
+    pub workspace: Option<u32>,
+
+    /// Float instead of tiling
+    #[serde(default)]
+    pub floating: Option<bool>,
+
+    /// Initial size
+    #[serde(default)]
+    pub size: Option<(u32, u32)>,
+
+    /// Opacity (0.0 - 1.0)
+    #[serde(default)]
+    pub opacity: Option<f32>,
+
+    /// Capabilities granted up front, e.g. `display:fullscreen`
+    #[serde(default)]
+    pub grant: Vec<String>,
+}
+
+fn default_grimoire_socket() -> String {
+    "/run/grimoire/grimoire.sock".to_string()
+}
+
+fn default_rules_setting() -> String {
+    "display.window_rules".to_string()
+}
+
+fn default_sync_interval() -> u64 {
+    5
+}
+
+/// Remote desktop configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteConfig {
+    /// Export outputs to remote viewers
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Remote desktop protocol
+    #[serde(default)]
+    pub protocol: RemoteProtocol,
+
+    /// Listen address
+    #[serde(default = "default_remote_listen")]
+    pub listen: String,
+
+    /// Allow listening beyond loopback; credentials then cross the network
+    /// unencrypted, so only enable this on trusted networks
+    #[serde(default)]
+    pub allow_unencrypted: bool,
+
+    /// Outputs that may be exported; the first connected one is shown.
+    /// Empty means the first enabled output.
+    #[serde(default)]
+    pub outputs: Vec<String>,
+
+    /// Forward remote keyboard and pointer input
+    #[serde(default = "default_true")]
+    pub allow_input: bool,
+
+    /// Simultaneous viewers
+    #[serde(default = "default_remote_clients")]
+    pub max_clients: usize,
+
+    /// Frame rate cap per viewer
+    #[serde(default = "default_remote_fps")]
+    pub max_fps: u32,
+
+    /// Spectre socket used to check viewer credentials
+    #[serde(default = "default_spectre_socket")]
+    pub spectre_socket: PathBuf,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            protocol: RemoteProtocol::Vnc,
+            listen: default_remote_listen(),
+            allow_unencrypted: false,
+            outputs: Vec::new(),
+            allow_input: true,
+            max_clients: default_remote_clients(),
+            max_fps: default_remote_fps(),
+            spectre_socket: default_spectre_socket(),
+        }
+    }
+}
+
+/// Remote desktop protocol
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteProtocol {
+    #[default]
+    Vnc,
+    Rdp,
+}
+
+fn default_remote_listen() -> String {
+    "127.0.0.1:5900".to_string()
+}
+
+fn default_remote_clients() -> usize {
+    1
+}
+
+fn default_remote_fps() -> u32 {
+    30
+}
+
+fn default_spectre_socket() -> PathBuf {
+    PathBuf::from("/run/spectre/spectre.sock")
+}
+
 /// XWayland configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct XWaylandConfig {
@@ -2,6 +2,7 @@
 //!
 //! Control interface for Aether compositor.
 
+use crate::capture::{CaptureProtocol, CaptureSource, SessionState};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -34,8 +35,24 @@ pub enum AetherRequest {
     ResizeWindow { id: u64, width: u32, height: u32 },
     /// Set window state
     SetWindowState { id: u64, state: String },
+    /// Move window to another workspace
+    MoveToWorkspace { id: u64, workspace: u32 },
     /// Take screenshot
     Screenshot { output: Option<String> },
+    /// Start a capture session on behalf of a portal client
+    RequestCapture {
+        client_pid: u32,
+        client_path: String,
+        source: CaptureSource,
+        #[serde(default)]
+        cursor: bool,
+    },
+    /// Copy the next frame of a granted capture session
+    CaptureFrame { session: u64 },
+    /// End a capture session
+    StopCapture { session: u64 },
+    /// List capture sessions
+    ListCaptures,
     /// Set DPMS state
     SetDpms { output: Option<String>, state: String },
     /// Reload configuration
@@ -73,6 +90,14 @@ pub enum AetherResponse {
         format: String,
         data: String, // Base64 encoded
     },
+    /// Capture session started; Guardian's decision follows as an event
+    CaptureRequested {
+        session: u64,
+    },
+    /// Capture sessions
+    Captures {
+        captures: Vec<CaptureInfo>,
+    },
     /// Success
     Ok {
         message: String,
@@ -110,9 +135,23 @@ pub struct WindowInfo {
     pub height: u32,
     pub state: String,
     pub focused: bool,
+    pub workspace: u32,
     pub client_pid: Option<u32>,
 }
 
+/// Capture session info for IPC
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureInfo {
+    pub session: u64,
+    pub client_pid: u32,
+    pub client_path: String,
+    pub source: CaptureSource,
+    pub protocol: CaptureProtocol,
+    pub state: SessionState,
+    pub frames: u64,
+    pub duration_secs: u64,
+}
+
 /// Event types sent to clients
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -131,6 +170,12 @@ pub enum AetherEvent {
     WindowChanged { window: WindowInfo },
     /// Window focused
     WindowFocused { id: u64 },
+    /// Capture session allowed
+    CaptureGranted { session: u64 },
+    /// Capture session refused by Guardian
+    CaptureDenied { session: u64 },
+    /// Capture session ended
+    CaptureStopped { session: u64 },
     /// Compositor shutdown
     Shutdown,
 }
@@ -11,8 +11,13 @@
 //! ## Features
 //!
 //! - **Wayland Native**: Full Wayland protocol support
+//! - **Shell Protocols**: xdg-shell, wlr-layer-shell panels, xdg-output and
+//!   wlr-foreign-toplevel-management for the shell's window list
 //! - **XWayland**: X11 application compatibility
 //! - **Security Integration**: Guardian-mediated window permissions
+//! - **Screen Capture**: wlr-screencopy, ext-image-copy-capture and portal
+//!   capture, each session consented by Guardian and announced via herald
+//! - **Remote Desktop**: Optional VNC export with PAM logins via spectre
 //! - **GPU Acceleration**: Hardware-accelerated rendering
 //! - **Multi-Output**: Multiple monitor support
 //! - **HDR Ready**: High dynamic range display support
@@ -47,13 +52,17 @@
 //!    └───────────┘      └───────────┘      └───────────┘
 //! ```
 
+mod capture;
 mod config;
 mod compositor;
 mod input;
 mod output;
 mod shell;
+mod toplevel;
 mod window;
+mod remote;
 mod render;
+mod rules;
 mod security;
 mod ipc;
 
@@ -15,6 +15,8 @@ pub struct OutputManager {
     outputs: HashMap<u32, Output>,
     /// Next output ID
     next_id: u32,
+    /// Outputs whose geometry changed since the last `take_changed`
+    changed: Vec<u32>,
 }
 
 impl OutputManager {
@@ -24,6 +26,7 @@ impl OutputManager {
             config: config.clone(),
             outputs: HashMap::new(),
             next_id: 1,
+            changed: Vec::new(),
         })
     }
 
@@ -54,6 +57,7 @@ impl OutputManager {
 
         info!("Output added: {} ({}x{}@{}Hz)", name, output.resolution.0, output.resolution.1, output.refresh_rate);
         self.outputs.insert(id, output);
+        self.mark_changed(id);
         id
     }
 
@@ -100,6 +104,7 @@ impl OutputManager {
         output.refresh_rate = refresh;
 
         info!("Output {} mode changed to {}x{}@{}Hz", output.name, width, height, refresh);
+        self.mark_changed(id);
         Ok(())
     }
 
@@ -110,6 +115,7 @@ impl OutputManager {
 
         output.position = (x, y);
         debug!("Output {} position set to ({}, {})", output.name, x, y);
+        self.mark_changed(id);
         Ok(())
     }
 
@@ -120,6 +126,7 @@ impl OutputManager {
 
         output.scale_factor = scale;
         debug!("Output {} scale set to {}", output.name, scale);
+        self.mark_changed(id);
         Ok(())
     }
 
@@ -133,6 +140,29 @@ impl OutputManager {
         Ok(())
     }
 
+    /// Set output transform
+    pub fn set_transform(&mut self, id: u32, transform: Transform) -> Result<()> {
+        let output = self.outputs.get_mut(&id)
+            .ok_or_else(|| anyhow::anyhow!("Output not found"))?;
+
+        output.transform = transform;
+        debug!("Output {} transform set to {:?}", output.name, transform);
+        self.mark_changed(id);
+        Ok(())
+    }
+
+    fn mark_changed(&mut self, id: u32) {
+        if !self.changed.contains(&id) {
+            self.changed.push(id);
+        }
+    }
+
+    /// Outputs whose logical geometry changed, for xdg-output updates and
+    /// layer re-arrangement
+    pub fn take_changed(&mut self) -> Vec<u32> {
+        std::mem::take(&mut self.changed)
+    }
+
     /// Get total desktop area
     pub fn total_area(&self) -> (i32, i32, u32, u32) {
         let mut min_x = i32::MAX;
@@ -141,8 +171,7 @@ impl OutputManager {
         let mut max_y = i32::MIN;
 
         for output in self.enabled() {
-            let (x, y) = output.position;
-            let (w, h) = output.resolution;
+            let (x, y, w, h) = output.logical_area();
             min_x = min_x.min(x);
             min_y = min_y.min(y);
             max_x = max_x.max(x + w as i32);
@@ -190,12 +219,31 @@ pub struct Output {
 
 impl Output {
     /// Get output area in logical coordinates
+    ///
+    /// Accounts for scale and for rotations that swap width and height.
     pub fn logical_area(&self) -> (i32, i32, u32, u32) {
-        let scaled_width = (self.resolution.0 as f32 / self.scale_factor) as u32;
-        let scaled_height = (self.resolution.1 as f32 / self.scale_factor) as u32;
+        let (width, height) = match self.transform {
+            Transform::Rotate90 | Transform::Rotate270 | Transform::Flipped90 | Transform::Flipped270 => {
+                (self.resolution.1, self.resolution.0)
+            }
+            _ => self.resolution,
+        };
+        let scaled_width = (width as f32 / self.scale_factor) as u32;
+        let scaled_height = (height as f32 / self.scale_factor) as u32;
         (self.position.0, self.position.1, scaled_width, scaled_height)
     }
 
+    /// Description advertised through xdg-output
+    pub fn xdg_output(&self) -> XdgOutputInfo {
+        let (x, y, width, height) = self.logical_area();
+        XdgOutputInfo {
+            name: self.name.clone(),
+            description: format!("{} {} ({})", self.make, self.model, self.name),
+            logical_position: (x, y),
+            logical_size: (width as i32, height as i32),
+        }
+    }
+
     /// Check if a point is within this output
     pub fn contains_point(&self, x: i32, y: i32) -> bool {
         let (ox, oy, ow, oh) = self.logical_area();
@@ -203,6 +251,18 @@ impl Output {
     }
 }
 
+/// zxdg_output_v1 state for an output
+///
+/// Clients (panels, screenshot tools) use this instead of wl_output to
+/// learn where an output sits in the compositor's logical space.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XdgOutputInfo {
+    pub name: String,
+    pub description: String,
+    pub logical_position: (i32, i32),
+    pub logical_size: (i32, i32),
+}
+
 /// Output mode
 #[derive(Debug, Clone)]
 pub struct OutputMode {
@@ -0,0 +1,557 @@
+//! Remote desktop
+//!
+//! Exports an output to remote viewers over VNC (RFB 3.8) so headless
+//! machines can be administered graphically. Viewers log in with a system
+//! username and password (VeNCrypt Plain), which spectre checks through
+//! PAM. Frames come from the same capture path as screencopy, so each
+//! viewer is a capture session that Guardian has to allow
+//! (`display:remote` on the output) and that shows up in the herald
+//! capture indicator.
+//!
+//! RFB Plain sends credentials in the clear, so by default the server only
+//! listens on loopback; reach it through an SSH tunnel.
+
+use crate::compositor::CapturedFrame;
+use crate::config::{RemoteConfig, RemoteProtocol};
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream, UnixStream};
+use tokio::runtime::Handle;
+use tokio::sync::{mpsc, oneshot, Semaphore};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+/// RFB version we speak
+const RFB_VERSION: &[u8; 12] = b"RFB 003.008\n";
+/// VeNCrypt security type
+const SECURITY_VENCRYPT: u8 = 19;
+/// VeNCrypt Plain subtype (username and password, no TLS)
+const VENCRYPT_PLAIN: u32 = 256;
+/// Longest username or password accepted
+const MAX_CREDENTIAL_LEN: usize = 1024;
+/// Longest clipboard text read (and discarded)
+const MAX_CUT_TEXT: usize = 1 << 20;
+
+/// Requests from viewer connections to the compositor
+#[derive(Debug)]
+pub enum RemoteCommand {
+    /// A viewer has authenticated; start capturing for it
+    Start {
+        identity: String,
+        reply: oneshot::Sender<Result<RemoteDisplay>>,
+    },
+    /// Copy the next frame
+    Frame {
+        session: u64,
+        reply: oneshot::Sender<Result<CapturedFrame>>,
+    },
+    /// Viewer input, in output coordinates
+    Input { session: u64, input: RemoteInput },
+    /// The viewer disconnected
+    Stop { session: u64 },
+}
+
+/// The output shown to a viewer
+#[derive(Debug, Clone)]
+pub struct RemoteDisplay {
+    pub session: u64,
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteInput {
+    /// Key with its evdev keycode
+    Key { keycode: u32, pressed: bool },
+    Motion { x: u32, y: u32 },
+    /// Button with its evdev code
+    Button { button: u32, pressed: bool },
+}
+
+/// Remote desktop listener
+pub struct RemoteServer {
+    commands: mpsc::UnboundedReceiver<RemoteCommand>,
+}
+
+impl RemoteServer {
+    /// Start listening for viewers
+    pub fn start(config: &RemoteConfig, runtime: &Handle) -> Result<Self> {
+        if config.protocol == RemoteProtocol::Rdp {
+            bail!("RDP export is not supported yet, use the vnc protocol");
+        }
+
+        let addr: SocketAddr = config.listen
+            .parse()
+            .with_context(|| format!("Invalid remote listen address {}", config.listen))?;
+        if !addr.ip().is_loopback() && !config.allow_unencrypted {
+            bail!(
+                "Refusing to listen on {} without encryption; tunnel to loopback or set allow_unencrypted",
+                addr
+            );
+        }
+
+        let listener = std::net::TcpListener::bind(addr)
+            .with_context(|| format!("Failed to bind {}", addr))?;
+        listener.set_nonblocking(true)?;
+        info!("Remote desktop (VNC) listening on {}", addr);
+
+        let (tx, commands) = mpsc::unbounded_channel();
+        runtime.spawn(accept_loop(listener, config.clone(), tx));
+
+        Ok(Self { commands })
+    }
+
+    /// Next pending request from a viewer
+    pub fn try_recv(&mut self) -> Option<RemoteCommand> {
+        self.commands.try_recv().ok()
+    }
+}
+
+async fn accept_loop(
+    listener: std::net::TcpListener,
+    config: RemoteConfig,
+    commands: mpsc::UnboundedSender<RemoteCommand>,
+) {
+    let listener = match TcpListener::from_std(listener) {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!("Remote desktop listener failed: {}", e);
+            return;
+        }
+    };
+    let slots = Arc::new(Semaphore::new(config.max_clients.max(1)));
+    let config = Arc::new(config);
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("Remote desktop accept error: {}", e);
+                continue;
+            }
+        };
+
+        let Ok(slot) = Arc::clone(&slots).try_acquire_owned() else {
+            info!("Rejecting remote viewer {}: too many viewers", peer);
+            continue;
+        };
+
+        let config = Arc::clone(&config);
+        let commands = commands.clone();
+        tokio::spawn(async move {
+            let mut viewer = Viewer::new(stream, peer, config, commands);
+            if let Err(e) = viewer.run().await {
+                debug!("Remote viewer {} closed: {}", peer, e);
+            }
+            if let Some(session) = viewer.session {
+                let _ = viewer.commands.send(RemoteCommand::Stop { session });
+            }
+            info!("Remote viewer {} disconnected", peer);
+            drop(slot);
+        });
+    }
+}
+
+/// RFB pixel format
+#[derive(Debug, Clone, Copy)]
+struct PixelFormat {
+    bits_per_pixel: u8,
+    depth: u8,
+    big_endian: bool,
+    true_colour: bool,
+    max: [u16; 3],
+    shift: [u8; 3],
+}
+
+impl PixelFormat {
+    /// 32-bit RGBX, matching the byte order frames are captured in
+    fn native() -> Self {
+        Self {
+            bits_per_pixel: 32,
+            depth: 24,
+            big_endian: false,
+            true_colour: true,
+            max: [255, 255, 255],
+            shift: [0, 8, 16],
+        }
+    }
+
+    fn parse(b: &[u8; 16]) -> Result<Self> {
+        let format = Self {
+            bits_per_pixel: b[0],
+            depth: b[1],
+            big_endian: b[2] != 0,
+            true_colour: b[3] != 0,
+            max: [
+                u16::from_be_bytes([b[4], b[5]]),
+                u16::from_be_bytes([b[6], b[7]]),
+                u16::from_be_bytes([b[8], b[9]]),
+            ],
+            shift: [b[10], b[11], b[12]],
+        };
+
+        if !format.true_colour || ![8, 16, 32].contains(&format.bits_per_pixel) {
+            bail!("Unsupported pixel format {:?}", format);
+        }
+        Ok(format)
+    }
+
+    fn encode(&self) -> [u8; 16] {
+        let mut b = [0u8; 16];
+        b[0] = self.bits_per_pixel;
+        b[1] = self.depth;
+        b[2] = self.big_endian as u8;
+        b[3] = self.true_colour as u8;
+        b[4..6].copy_from_slice(&self.max[0].to_be_bytes());
+        b[6..8].copy_from_slice(&self.max[1].to_be_bytes());
+        b[8..10].copy_from_slice(&self.max[2].to_be_bytes());
+        b[10..13].copy_from_slice(&self.shift);
+        b
+    }
+
+    /// Convert RGBA pixels to this format
+    fn convert(&self, rgba: &[u8], out: &mut Vec<u8>) {
+        let bytes = self.bits_per_pixel as usize / 8;
+
+        for px in rgba.chunks_exact(4) {
+            let mut value = 0u32;
+            for ((&component, &max), &shift) in px.iter().zip(&self.max).zip(&self.shift) {
+                let scaled = component as u32 * max as u32 / 255;
+                value |= scaled.checked_shl(shift as u32).unwrap_or(0);
+            }
+
+            let encoded = if self.big_endian { value.to_be_bytes() } else { value.to_le_bytes() };
+            if self.big_endian {
+                out.extend_from_slice(&encoded[4 - bytes..]);
+            } else {
+                out.extend_from_slice(&encoded[..bytes]);
+            }
+        }
+    }
+}
+
+/// One connected viewer
+struct Viewer {
+    stream: TcpStream,
+    peer: SocketAddr,
+    config: Arc<RemoteConfig>,
+    commands: mpsc::UnboundedSender<RemoteCommand>,
+    session: Option<u64>,
+    format: PixelFormat,
+    width: u32,
+    height: u32,
+    buttons: u8,
+    last_frame: Option<Instant>,
+}
+
+impl Viewer {
+    fn new(
+        stream: TcpStream,
+        peer: SocketAddr,
+        config: Arc<RemoteConfig>,
+        commands: mpsc::UnboundedSender<RemoteCommand>,
+    ) -> Self {
+        Self {
+            stream,
+            peer,
+            config,
+            commands,
+            session: None,
+            format: PixelFormat::native(),
+            width: 0,
+            height: 0,
+            buttons: 0,
+            last_frame: None,
+        }
+    }
+
+    async fn run(&mut self) -> Result<()> {
+        let username = self.handshake().await?;
+        let identity = format!("remote:{}@{}", username, self.peer.ip());
+
+        // ClientInit: shared flag, ignored
+        self.stream.read_u8().await?;
+
+        let (reply, started) = oneshot::channel();
+        self.commands.send(RemoteCommand::Start { identity: identity.clone(), reply })?;
+        let shown = started.await??;
+        info!("Remote viewer {} is viewing {}", identity, shown.name);
+
+        self.session = Some(shown.session);
+        self.width = shown.width;
+        self.height = shown.height;
+        self.server_init(&shown.name).await?;
+
+        self.message_loop(shown.session).await
+    }
+
+    /// Version and security negotiation; returns the authenticated user
+    async fn handshake(&mut self) -> Result<String> {
+        self.stream.write_all(RFB_VERSION).await?;
+        let mut version = [0u8; 12];
+        self.stream.read_exact(&mut version).await?;
+        if &version != RFB_VERSION {
+            bail!("Unsupported RFB version {:?}", String::from_utf8_lossy(&version));
+        }
+
+        self.stream.write_all(&[1, SECURITY_VENCRYPT]).await?;
+        if self.stream.read_u8().await? != SECURITY_VENCRYPT {
+            bail!("Viewer chose an unsupported security type");
+        }
+
+        // VeNCrypt 0.2 with the Plain subtype only
+        self.stream.write_all(&[0, 2]).await?;
+        let mut vencrypt = [0u8; 2];
+        self.stream.read_exact(&mut vencrypt).await?;
+        if vencrypt != [0, 2] {
+            self.stream.write_u8(1).await?;
+            bail!("Unsupported VeNCrypt version {:?}", vencrypt);
+        }
+        self.stream.write_u8(0).await?;
+        self.stream.write_u8(1).await?;
+        self.stream.write_u32(VENCRYPT_PLAIN).await?;
+        if self.stream.read_u32().await? != VENCRYPT_PLAIN {
+            bail!("Viewer chose an unsupported VeNCrypt subtype");
+        }
+
+        let username_len = self.stream.read_u32().await? as usize;
+        let password_len = self.stream.read_u32().await? as usize;
+        if username_len > MAX_CREDENTIAL_LEN || password_len > MAX_CREDENTIAL_LEN {
+            bail!("Credentials too long");
+        }
+        let mut username = vec![0u8; username_len];
+        let mut password = vec![0u8; password_len];
+        self.stream.read_exact(&mut username).await?;
+        self.stream.read_exact(&mut password).await?;
+        let username = String::from_utf8(username).map_err(|_| anyhow!("Invalid username"))?;
+        let password = String::from_utf8(password).map_err(|_| anyhow!("Invalid password"))?;
+
+        match authenticate(&self.config.spectre_socket, &username, &password).await {
+            Ok(()) => {
+                self.stream.write_u32(0).await?;
+                Ok(username)
+            }
+            Err(e) => {
+                warn!("Remote login for {} from {} failed: {}", username, self.peer, e);
+                let reason = b"Authentication failed";
+                self.stream.write_u32(1).await?;
+                self.stream.write_u32(reason.len() as u32).await?;
+                self.stream.write_all(reason).await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn server_init(&mut self, name: &str) -> Result<()> {
+        let mut msg = Vec::with_capacity(24 + name.len());
+        msg.extend_from_slice(&(self.width.min(u16::MAX as u32) as u16).to_be_bytes());
+        msg.extend_from_slice(&(self.height.min(u16::MAX as u32) as u16).to_be_bytes());
+        msg.extend_from_slice(&self.format.encode());
+        msg.extend_from_slice(&(name.len() as u32).to_be_bytes());
+        msg.extend_from_slice(name.as_bytes());
+        self.stream.write_all(&msg).await?;
+        Ok(())
+    }
+
+    async fn message_loop(&mut self, session: u64) -> Result<()> {
+        loop {
+            match self.stream.read_u8().await? {
+                // SetPixelFormat
+                0 => {
+                    let mut buf = [0u8; 19];
+                    self.stream.read_exact(&mut buf).await?;
+                    let format: &[u8; 16] = buf[3..].try_into()?;
+                    self.format = PixelFormat::parse(format)?;
+                }
+                // SetEncodings: only Raw is ever sent
+                2 => {
+                    self.stream.read_u8().await?;
+                    let count = self.stream.read_u16().await? as usize;
+                    let mut encodings = vec![0u8; count * 4];
+                    self.stream.read_exact(&mut encodings).await?;
+                }
+                // FramebufferUpdateRequest
+                3 => {
+                    let mut buf = [0u8; 9];
+                    self.stream.read_exact(&mut buf).await?;
+                    self.send_frame(session).await?;
+                }
+                // KeyEvent
+                4 => {
+                    let pressed = self.stream.read_u8().await? != 0;
+                    self.stream.read_u16().await?;
+                    let keysym = self.stream.read_u32().await?;
+                    if let Some(keycode) = keysym_to_keycode(keysym) {
+                        self.input(session, RemoteInput::Key { keycode, pressed })?;
+                    }
+                }
+                // PointerEvent
+                5 => {
+                    let mask = self.stream.read_u8().await?;
+                    let x = self.stream.read_u16().await? as u32;
+                    let y = self.stream.read_u16().await? as u32;
+                    self.pointer(session, mask, x, y)?;
+                }
+                // ClientCutText: clipboard is not shared
+                6 => {
+                    let mut pad = [0u8; 3];
+                    self.stream.read_exact(&mut pad).await?;
+                    let len = self.stream.read_u32().await? as usize;
+                    if len > MAX_CUT_TEXT {
+                        bail!("Clipboard text too long");
+                    }
+                    let mut text = vec![0u8; len];
+                    self.stream.read_exact(&mut text).await?;
+                }
+                other => bail!("Unknown client message {}", other),
+            }
+        }
+    }
+
+    async fn send_frame(&mut self, session: u64) -> Result<()> {
+        let interval = Duration::from_secs(1) / self.config.max_fps.max(1);
+        if let Some(last) = self.last_frame {
+            tokio::time::sleep_until(last + interval).await;
+        }
+        self.last_frame = Some(Instant::now());
+
+        let (reply, frame) = oneshot::channel();
+        self.commands.send(RemoteCommand::Frame { session, reply })?;
+        let frame = frame.await??;
+
+        // The viewer was told the size at init; pad or crop to it
+        let (width, height) = (self.width as usize, self.height as usize);
+        let mut rgba = vec![0u8; width * height * 4];
+        let copy_width = width.min(frame.width as usize) * 4;
+        for row in 0..height.min(frame.height as usize) {
+            let src = row * frame.width as usize * 4;
+            if let Some(line) = frame.data.get(src..src + copy_width) {
+                rgba[row * width * 4..row * width * 4 + copy_width].copy_from_slice(line);
+            }
+        }
+
+        let mut msg = Vec::with_capacity(16 + width * height * 4);
+        msg.extend_from_slice(&[0, 0]);
+        msg.extend_from_slice(&1u16.to_be_bytes());
+        msg.extend_from_slice(&0u16.to_be_bytes());
+        msg.extend_from_slice(&0u16.to_be_bytes());
+        msg.extend_from_slice(&(width as u16).to_be_bytes());
+        msg.extend_from_slice(&(height as u16).to_be_bytes());
+        msg.extend_from_slice(&0i32.to_be_bytes());
+        self.format.convert(&rgba, &mut msg);
+
+        self.stream.write_all(&msg).await?;
+        Ok(())
+    }
+
+    fn pointer(&mut self, session: u64, mask: u8, x: u32, y: u32) -> Result<()> {
+        self.input(session, RemoteInput::Motion { x, y })?;
+
+        // Bits 0-2 are left, middle, right; 3 and up are scroll steps
+        const BUTTONS: [(u8, u32); 3] = [(0x01, 0x110), (0x02, 0x112), (0x04, 0x111)];
+        for (bit, button) in BUTTONS {
+            if (mask ^ self.buttons) & bit != 0 {
+                self.input(session, RemoteInput::Button { button, pressed: mask & bit != 0 })?;
+            }
+        }
+        self.buttons = mask;
+        Ok(())
+    }
+
+    fn input(&self, session: u64, input: RemoteInput) -> Result<()> {
+        if self.config.allow_input {
+            self.commands.send(RemoteCommand::Input { session, input })?;
+        }
+        Ok(())
+    }
+}
+
+/// Check viewer credentials with spectre (PAM)
+async fn authenticate(socket: &Path, username: &str, password: &str) -> Result<()> {
+    let request = json!({
+        "type": "Authenticate",
+        "data": { "username": username, "password": password },
+    });
+
+    let mut stream = UnixStream::connect(socket).await?;
+    stream.write_all(format!("{}\n", request).as_bytes()).await?;
+    stream.flush().await?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).await?;
+    let response: Value = serde_json::from_str(&line)?;
+
+    match response["status"].as_str() {
+        Some("Success") => Ok(()),
+        _ => Err(anyhow!(
+            "{}",
+            response["message"].as_str().unwrap_or("Authentication failed")
+        )),
+    }
+}
+
+/// Map an X keysym to an evdev keycode on a US layout
+///
+/// Shifted symbols map to their base key; the viewer sends Shift itself.
+fn keysym_to_keycode(keysym: u32) -> Option<u32> {
+    const ROW_1: &[u8] = b"1234567890-=";
+    const ROW_1_SHIFTED: &[u8] = b"!@#$%^&*()_+";
+    const LETTERS: &[u8] = b"qwertyuiop[]asdfghjkl;'`\\zxcvbnm,./";
+    const LETTERS_SHIFTED: &[u8] = b"QWERTYUIOP{}ASDFGHJKL:\"~|ZXCVBNM<>?";
+    // Keycodes for LETTERS, in order
+    const LETTER_CODES: [u32; 35] = [
+        16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27,
+        30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41,
+        43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53,
+    ];
+
+    if let Ok(c) = u8::try_from(keysym) {
+        if c == b' ' {
+            return Some(57);
+        }
+        if let Some(i) = ROW_1.iter().position(|&k| k == c).or_else(|| ROW_1_SHIFTED.iter().position(|&k| k == c)) {
+            return Some(2 + i as u32);
+        }
+        if let Some(i) = LETTERS.iter().position(|&k| k == c).or_else(|| LETTERS_SHIFTED.iter().position(|&k| k == c)) {
+            return Some(LETTER_CODES[i]);
+        }
+        return None;
+    }
+
+    let code = match keysym {
+        0xff08 => 14,  // BackSpace
+        0xff09 => 15,  // Tab
+        0xff0d => 28,  // Return
+        0xff1b => 1,   // Escape
+        0xff50 => 102, // Home
+        0xff51 => 105, // Left
+        0xff52 => 103, // Up
+        0xff53 => 106, // Right
+        0xff54 => 108, // Down
+        0xff55 => 104, // Page_Up
+        0xff56 => 109, // Page_Down
+        0xff57 => 107, // End
+        0xff63 => 110, // Insert
+        0xffff => 111, // Delete
+        0xffbe..=0xffc7 => 59 + (keysym - 0xffbe), // F1-F10
+        0xffc8 => 87,  // F11
+        0xffc9 => 88,  // F12
+        0xffe1 => 42,  // Shift_L
+        0xffe2 => 54,  // Shift_R
+        0xffe3 => 29,  // Control_L
+        0xffe4 => 97,  // Control_R
+        0xffe5 => 58,  // Caps_Lock
+        0xffe9 => 56,  // Alt_L
+        0xffea => 100, // Alt_R
+        0xffeb => 125, // Super_L
+        0xffec => 126, // Super_R
+        _ => return None,
+    };
+    Some(code)
+}
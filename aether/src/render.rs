@@ -4,7 +4,7 @@
 
 use crate::config::RenderConfig;
 use crate::input::InputState;
-use crate::window::Window;
+use crate::window::{Window, WindowGeometry};
 use anyhow::Result;
 use tracing::debug;
 
@@ -116,6 +116,20 @@ impl Renderer {
         // Return as RGBA bytes
         Ok(Vec::new())
     }
+
+    /// Read back a region of the last frame for capture
+    ///
+    /// The cursor is drawn into the copy only when `cursor` is given.
+    pub fn read_region(&self, region: WindowGeometry, cursor: Option<&InputState>) -> Result<Vec<u8>> {
+        // Read pixels from the region of the framebuffer
+        // Blend the cursor sprite over them if requested
+        // Return as RGBA bytes
+        if let Some(input) = cursor {
+            let (_x, _y) = input.pointer_position();
+        }
+        debug!("Reading back {}x{} at {},{}", region.width, region.height, region.x, region.y);
+        Ok(Vec::new())
+    }
 }
 
 /// Texture handle
@@ -0,0 +1,184 @@
+//! Window rules
+//!
+//! Rules match windows by app ID and title and set where and how they
+//! open: workspace, floating, size, opacity and capabilities granted up
+//! front. They live in the grimoire settings store (`display.window_rules`
+//! by default) so they can be edited while aether runs; the list is
+//! polled and changes are applied to open windows.
+
+use crate::config::{RulesConfig, WindowRule};
+use anyhow::Result;
+use grimoire_client::GrimoireClient;
+use tokio::runtime::Handle;
+use tokio::sync::watch;
+use tracing::{debug, info};
+
+/// Capabilities rules may never grant; these always need Guardian
+const UNGRANTABLE: &[&str] = &["display:capture", "input:grab", "*"];
+
+/// Everything the matching rules set for a window
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResolvedRules {
+    pub workspace: Option<u32>,
+    pub floating: Option<bool>,
+    pub size: Option<(u32, u32)>,
+    pub opacity: Option<f32>,
+    pub grant: Vec<String>,
+    /// Names (or indices) of the rules that matched
+    pub matched: Vec<String>,
+}
+
+/// Current rules, kept in step with grimoire
+pub struct WindowRules {
+    rules: Vec<WindowRule>,
+    updates: Option<watch::Receiver<Vec<WindowRule>>>,
+}
+
+impl WindowRules {
+    /// Start with the configured rules and follow grimoire from then on
+    pub fn new(config: &RulesConfig, runtime: &Handle) -> Self {
+        let updates = config.grimoire_sync.then(|| {
+            let (tx, rx) = watch::channel(config.rules.clone());
+            runtime.spawn(sync_loop(config.clone(), tx));
+            rx
+        });
+
+        Self {
+            rules: config.rules.clone(),
+            updates,
+        }
+    }
+
+    /// Pick up reloaded rules; true if they changed
+    pub fn poll(&mut self) -> bool {
+        let Some(updates) = &mut self.updates else {
+            return false;
+        };
+        if !updates.has_changed().unwrap_or(false) {
+            return false;
+        }
+
+        let rules = updates.borrow_and_update().clone();
+        if rules == self.rules {
+            return false;
+        }
+        info!("Loaded {} window rules", rules.len());
+        self.rules = rules;
+        true
+    }
+
+    pub fn list(&self) -> &[WindowRule] {
+        &self.rules
+    }
+
+    /// Merge every rule matching a window
+    pub fn resolve(&self, app_id: Option<&str>, title: &str) -> ResolvedRules {
+        let mut resolved = ResolvedRules::default();
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            if !rule_matches(rule, app_id, title) {
+                continue;
+            }
+
+            resolved.workspace = rule.workspace.or(resolved.workspace);
+            resolved.floating = rule.floating.or(resolved.floating);
+            resolved.size = rule.size.or(resolved.size);
+            resolved.opacity = rule.opacity.map(|o| o.clamp(0.0, 1.0)).or(resolved.opacity);
+            for cap in &rule.grant {
+                if UNGRANTABLE.contains(&cap.as_str()) {
+                    debug!("Window rules cannot grant {}", cap);
+                } else if !resolved.grant.contains(cap) {
+                    resolved.grant.push(cap.clone());
+                }
+            }
+            resolved.matched.push(rule.name.clone().unwrap_or_else(|| format!("#{}", index)));
+        }
+
+        resolved
+    }
+}
+
+/// A rule with no matchers matches nothing rather than every window
+fn rule_matches(rule: &WindowRule, app_id: Option<&str>, title: &str) -> bool {
+    if rule.app_id.is_none() && rule.title.is_none() {
+        return false;
+    }
+
+    let app_id_ok = match (&rule.app_id, app_id) {
+        (Some(pattern), Some(app_id)) => glob_match(pattern, app_id),
+        (Some(_), None) => false,
+        (None, _) => true,
+    };
+    let title_ok = rule.title.as_ref().is_none_or(|pattern| glob_match(pattern, title));
+
+    app_id_ok && title_ok
+}
+
+/// Poll grimoire for rule changes
+async fn sync_loop(config: RulesConfig, tx: watch::Sender<Vec<WindowRule>>) {
+    let mut interval =
+        tokio::time::interval(std::time::Duration::from_secs(config.sync_interval_secs.max(1)));
+
+    loop {
+        interval.tick().await;
+
+        match fetch(&config).await {
+            Ok(Some(rules)) => {
+                tx.send_if_modified(|current| {
+                    if *current == rules {
+                        return false;
+                    }
+                    *current = rules;
+                    true
+                });
+            }
+            Ok(None) => {}
+            Err(e) => debug!("Window rule sync with grimoire failed: {}", e),
+        }
+
+        if tx.is_closed() {
+            break;
+        }
+    }
+}
+
+async fn fetch(config: &RulesConfig) -> Result<Option<Vec<WindowRule>>> {
+    let client = GrimoireClient::connect(&config.grimoire_socket).await?;
+    let value = match client.get_setting(&config.setting_path).await {
+        Ok(value) => value,
+        Err(grimoire_client::ClientError::NotFound(_)) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    if value.is_null() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_value(value)?))
+}
+
+/// Case-insensitive glob match with `*` and `?` wildcards
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
@@ -55,6 +55,30 @@ impl SecurityManager {
         self.check_capability(client_id, client_path, "display:capture", None).await
     }
 
+    /// Check if a client can capture a specific output or window
+    ///
+    /// Decisions are per source and never cached, and an unreachable
+    /// Guardian means no capture.
+    pub async fn can_capture_resource(&self, client_id: u32, client_path: &str, resource: &str) -> bool {
+        if !self.config.capture_requires_cap {
+            return true;
+        }
+
+        self.query_guardian(client_id, client_path, capabilities::CAPTURE, Some(resource))
+            .await
+            .unwrap_or(false)
+    }
+
+    /// Check if a remote viewer may see (and drive) an output
+    ///
+    /// `identity` is the authenticated viewer, e.g. `remote:alice@10.0.0.5`.
+    /// Like capture, this is never cached and fails closed.
+    pub async fn can_export_remote(&self, identity: &str, resource: &str) -> bool {
+        self.query_guardian(0, identity, capabilities::REMOTE, Some(resource))
+            .await
+            .unwrap_or(false)
+    }
+
     /// Check if a client can grab input
     pub async fn can_grab_input(&self, client_id: u32, client_path: &str) -> bool {
         if !self.config.input_grab_requires_cap {
@@ -78,7 +102,11 @@ impl SecurityManager {
     fn is_privileged_protocol(&self, protocol: &str) -> bool {
         let privileged = [
             "zwlr_screencopy_manager",
+            "ext_image_copy_capture_manager",
+            "ext_output_image_capture_source_manager",
+            "ext_foreign_toplevel_image_capture_source_manager",
             "zwlr_export_dmabuf_manager",
+            "zwlr_foreign_toplevel_manager",
             "zwlr_input_inhibitor_manager",
             "zwp_input_method_manager",
             "zwp_virtual_keyboard_manager",
@@ -112,7 +140,36 @@ impl SecurityManager {
             }
         }
 
-        // Check with Guardian
+        match self.query_guardian(client_id, client_path, capability, resource).await {
+            Some(allowed) => {
+                // Cache if allowed
+                if allowed {
+                    let mut cache = self.client_caps.write().await;
+                    cache.entry(client_id)
+                        .or_insert_with(Vec::new)
+                        .push(capability.to_string());
+                }
+                allowed
+            }
+            None => {
+                warn!("Guardian unavailable - allowing {} by default", capability);
+                true
+            }
+        }
+    }
+
+    /// Ask Guardian for a decision; `None` if it could not be reached
+    async fn query_guardian(
+        &self,
+        client_id: u32,
+        client_path: &str,
+        capability: &str,
+        resource: Option<&str>,
+    ) -> Option<bool> {
+        if !self.config.guardian_enabled {
+            return Some(true);
+        }
+
         let mut guardian = self.guardian.write().await;
 
         if guardian.is_none() {
@@ -120,39 +177,27 @@ impl SecurityManager {
             *guardian = Some(GuardianClient::with_socket(&self.guardian_socket));
         }
 
-        if let Some(ref mut client) = *guardian {
-            let mut request = CapabilityRequest::new(capability);
-            request.process_path = client_path.to_string();
-            request.pid = client_id;
-            if let Some(res) = resource {
-                request = request.with_resource(res);
-            }
+        let client = guardian.as_mut()?;
+        let mut request = CapabilityRequest::new(capability);
+        request.process_path = client_path.to_string();
+        request.pid = client_id;
+        if let Some(res) = resource {
+            request = request.with_resource(res);
+        }
 
-            match client.check_capability_full(request).await {
-                Ok(decision) => {
-                    let allowed = matches!(decision.decision, Decision::Allow | Decision::Sandbox);
-
-                    // Cache if allowed
-                    if allowed {
-                        let mut cache = self.client_caps.write().await;
-                        cache.entry(client_id)
-                            .or_insert_with(Vec::new)
-                            .push(capability.to_string());
-                    }
-
-                    debug!(
-                        "Capability check: client={}, cap={}, result={}",
-                        client_id, capability, allowed
-                    );
-                    allowed
-                }
-                Err(e) => {
-                    warn!("Guardian check failed: {} - allowing by default", e);
-                    true
-                }
+        match client.check_capability_full(request).await {
+            Ok(decision) => {
+                let allowed = matches!(decision.decision, Decision::Allow | Decision::Sandbox);
+                debug!(
+                    "Capability check: client={}, cap={}, resource={:?}, result={}",
+                    client_id, capability, resource, allowed
+                );
+                Some(allowed)
+            }
+            Err(e) => {
+                warn!("Guardian check failed: {}", e);
+                None
             }
-        } else {
-            true // Allow if Guardian unavailable
         }
     }
 
@@ -182,6 +227,7 @@ impl SecurityManager {
 /// Display capability types
 pub mod capabilities {
     pub const CAPTURE: &str = "display:capture";
+    pub const REMOTE: &str = "display:remote";
     pub const INPUT_GRAB: &str = "input:grab";
     pub const FULLSCREEN: &str = "display:fullscreen";
     pub const LAYER_SHELL: &str = "display:layer_shell";
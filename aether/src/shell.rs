@@ -1,9 +1,17 @@
 //! Shell management
 //!
 //! Implements Wayland shell protocols (xdg-shell, layer-shell, etc.)
+//!
+//! Both protocols are double-buffered. The compositor sends a configure
+//! carrying a serial, the client acks that serial, and the acked state only
+//! takes effect on the client's next commit. A surface may not attach a
+//! buffer before it has acked its first configure. The shell manager tracks
+//! outstanding serials per surface so acks and commits can be validated,
+//! and queues configures for the compositor to flush to clients.
 
-use anyhow::Result;
-use std::collections::HashMap;
+use crate::window::WindowGeometry;
+use anyhow::{anyhow, bail, Result};
+use std::collections::{HashMap, VecDeque};
 use tracing::debug;
 
 /// Shell manager
@@ -12,6 +20,10 @@ pub struct ShellManager {
     xdg_surfaces: HashMap<u64, XdgSurface>,
     /// Layer shell surfaces
     layer_surfaces: HashMap<u64, LayerSurface>,
+    /// Configures waiting to be sent
+    outgoing: Vec<Configure>,
+    /// Next configure serial
+    next_serial: u32,
 }
 
 impl ShellManager {
@@ -20,6 +32,8 @@ impl ShellManager {
         Ok(Self {
             xdg_surfaces: HashMap::new(),
             layer_surfaces: HashMap::new(),
+            outgoing: Vec::new(),
+            next_serial: 1,
         })
     }
 
@@ -28,28 +42,49 @@ impl ShellManager {
         self.xdg_surfaces.insert(surface_id, XdgSurface {
             window_id,
             role: XdgRole::Toplevel(XdgToplevel::default()),
+            configures: ConfigureQueue::default(),
         });
     }
 
     /// Register an xdg-popup
-    pub fn register_xdg_popup(&mut self, surface_id: u64, window_id: u64, parent: u64) {
+    pub fn register_xdg_popup(
+        &mut self,
+        surface_id: u64,
+        window_id: u64,
+        parent: u64,
+        positioner: Positioner,
+    ) {
         self.xdg_surfaces.insert(surface_id, XdgSurface {
             window_id,
             role: XdgRole::Popup(XdgPopup {
                 parent,
                 position: (0, 0),
+                positioner,
             }),
+            configures: ConfigureQueue::default(),
         });
     }
 
-    /// Register a layer surface
-    pub fn register_layer_surface(&mut self, surface_id: u64, layer: Layer, anchor: Anchor) {
-        self.layer_surfaces.insert(surface_id, LayerSurface {
+    /// Register a layer surface on an output
+    pub fn register_layer_surface(
+        &mut self,
+        surface_id: u64,
+        output: u32,
+        layer: Layer,
+        namespace: String,
+    ) {
+        let state = LayerState {
             layer,
-            anchor,
-            exclusive_zone: 0,
-            margin: (0, 0, 0, 0),
-            keyboard_interactivity: KeyboardInteractivity::None,
+            ..LayerState::default()
+        };
+        self.layer_surfaces.insert(surface_id, LayerSurface {
+            namespace,
+            output,
+            pending: state.clone(),
+            current: state,
+            geometry: WindowGeometry::default(),
+            mapped: false,
+            configures: ConfigureQueue::default(),
         });
     }
 
@@ -63,18 +98,396 @@ impl ShellManager {
         self.layer_surfaces.get(&surface_id)
     }
 
+    /// Get layer surface for a client request (updates pending state)
+    pub fn get_layer_mut(&mut self, surface_id: u64) -> Option<&mut LayerState> {
+        self.layer_surfaces.get_mut(&surface_id).map(|s| &mut s.pending)
+    }
+
+    /// Get the toplevel role of a surface
+    pub fn toplevel_mut(&mut self, surface_id: u64) -> Option<&mut XdgToplevel> {
+        match self.xdg_surfaces.get_mut(&surface_id)?.role {
+            XdgRole::Toplevel(ref mut toplevel) => Some(toplevel),
+            XdgRole::Popup(_) => None,
+        }
+    }
+
+    /// Toplevel surfaces with their window IDs
+    pub fn toplevels(&self) -> impl Iterator<Item = (u64, u64, &XdgToplevel)> {
+        self.xdg_surfaces.iter().filter_map(|(id, s)| match &s.role {
+            XdgRole::Toplevel(toplevel) => Some((*id, s.window_id, toplevel)),
+            XdgRole::Popup(_) => None,
+        })
+    }
+
     /// Remove surface
-    pub fn remove(&mut self, surface_id: u64) {
+    pub fn remove(&mut self, surface_id: u64) -> Option<u32> {
         self.xdg_surfaces.remove(&surface_id);
-        self.layer_surfaces.remove(&surface_id);
+        self.outgoing.retain(|c| c.surface_id != surface_id);
+        self.layer_surfaces.remove(&surface_id).map(|s| s.output)
+    }
+
+    fn serial(&mut self) -> u32 {
+        let serial = self.next_serial;
+        self.next_serial = self.next_serial.wrapping_add(1).max(1);
+        serial
+    }
+
+    /// Send a toplevel configure
+    ///
+    /// `size` of (0, 0) lets the client pick its own size.
+    pub fn configure_toplevel(&mut self, surface_id: u64, size: (u32, u32), states: ToplevelStates) -> Result<u32> {
+        let serial = self.serial();
+        let surface = self.xdg_surfaces.get_mut(&surface_id)
+            .ok_or_else(|| anyhow!("Unknown xdg surface {}", surface_id))?;
+        if !matches!(surface.role, XdgRole::Toplevel(_)) {
+            bail!("Surface {} is not a toplevel", surface_id);
+        }
+
+        let state = ConfigureState::Toplevel { size, states };
+        surface.configures.sent.push_back((serial, state.clone()));
+        self.outgoing.push(Configure { surface_id, serial, state });
+        Ok(serial)
+    }
+
+    /// Position a popup against its parent and send its configure
+    ///
+    /// `bounds` is the area the popup must stay within, relative to the
+    /// parent's window geometry.
+    pub fn configure_popup(&mut self, surface_id: u64, bounds: WindowGeometry, reposition_token: Option<u32>) -> Result<u32> {
+        let serial = self.serial();
+        let surface = self.xdg_surfaces.get_mut(&surface_id)
+            .ok_or_else(|| anyhow!("Unknown xdg surface {}", surface_id))?;
+        let XdgRole::Popup(ref popup) = surface.role else {
+            bail!("Surface {} is not a popup", surface_id);
+        };
+
+        let geometry = popup.positioner.place(&bounds);
+        let state = ConfigureState::Popup { geometry, reposition_token };
+        surface.configures.sent.push_back((serial, state.clone()));
+        self.outgoing.push(Configure { surface_id, serial, state });
+        Ok(serial)
+    }
+
+    /// Replace a popup's positioner (xdg_popup.reposition)
+    pub fn reposition_popup(&mut self, surface_id: u64, positioner: Positioner, bounds: WindowGeometry, token: u32) -> Result<u32> {
+        match self.xdg_surfaces.get_mut(&surface_id).map(|s| &mut s.role) {
+            Some(XdgRole::Popup(popup)) => popup.positioner = positioner,
+            _ => bail!("Surface {} is not a popup", surface_id),
+        }
+        self.configure_popup(surface_id, bounds, Some(token))
+    }
+
+    /// Ask a toplevel to close
+    pub fn close(&mut self, surface_id: u64) {
+        self.outgoing.push(Configure {
+            surface_id,
+            serial: 0,
+            state: ConfigureState::Closed,
+        });
+    }
+
+    /// Handle ack_configure from an xdg or layer surface
+    pub fn ack_configure(&mut self, surface_id: u64, serial: u32) -> Result<()> {
+        let queue = if let Some(surface) = self.xdg_surfaces.get_mut(&surface_id) {
+            &mut surface.configures
+        } else if let Some(surface) = self.layer_surfaces.get_mut(&surface_id) {
+            &mut surface.configures
+        } else {
+            bail!("Unknown surface {}", surface_id);
+        };
+
+        queue.ack(serial)
+            .map_err(|e| anyhow!("Surface {}: {}", surface_id, e))
+    }
+
+    /// Handle a surface commit
+    ///
+    /// Errors are protocol errors; the compositor should disconnect the
+    /// client.
+    pub fn commit(&mut self, surface_id: u64, has_buffer: bool) -> Result<CommitOutcome> {
+        if let Some(surface) = self.xdg_surfaces.get_mut(&surface_id) {
+            return Self::commit_xdg(surface, has_buffer);
+        }
+
+        let surface = self.layer_surfaces.get_mut(&surface_id)
+            .ok_or_else(|| anyhow!("Unknown surface {}", surface_id))?;
+        Self::commit_layer(surface_id, surface, has_buffer)
+    }
+
+    fn commit_xdg(surface: &mut XdgSurface, has_buffer: bool) -> Result<CommitOutcome> {
+        if !surface.configures.configured {
+            if has_buffer {
+                bail!("unconfigured_buffer: buffer attached before the first ack_configure");
+            }
+            if surface.configures.sent.is_empty() {
+                return Ok(CommitOutcome::NeedsInitialConfigure);
+            }
+            return Ok(CommitOutcome::Unchanged);
+        }
+
+        let Some(state) = surface.configures.acked.take() else {
+            return Ok(CommitOutcome::Unchanged);
+        };
+
+        match (&mut surface.role, state) {
+            (XdgRole::Toplevel(toplevel), ConfigureState::Toplevel { size, states }) => {
+                toplevel.size = size;
+                toplevel.maximized = states.maximized;
+                toplevel.fullscreen = states.fullscreen;
+                toplevel.resizing = states.resizing;
+                toplevel.activated = states.activated;
+            }
+            (XdgRole::Popup(popup), ConfigureState::Popup { geometry, .. }) => {
+                popup.position = (geometry.x, geometry.y);
+            }
+            _ => {}
+        }
+
+        Ok(CommitOutcome::Applied)
+    }
+
+    fn commit_layer(surface_id: u64, surface: &mut LayerSurface, has_buffer: bool) -> Result<CommitOutcome> {
+        let pending = &surface.pending;
+        if pending.desired_size.0 == 0 && !(pending.anchor.left && pending.anchor.right) {
+            bail!("invalid_size: width 0 requires anchoring to left and right");
+        }
+        if pending.desired_size.1 == 0 && !(pending.anchor.top && pending.anchor.bottom) {
+            bail!("invalid_size: height 0 requires anchoring to top and bottom");
+        }
+
+        if !surface.configures.configured && has_buffer {
+            bail!("unconfigured_buffer: buffer attached before the first ack_configure");
+        }
+
+        let state_changed = surface.pending != surface.current;
+        surface.current = surface.pending.clone();
+
+        let was_mapped = surface.mapped;
+        surface.mapped = has_buffer && surface.configures.configured;
+        if let Some(ConfigureState::Layer { size }) = surface.configures.acked.take() {
+            surface.geometry.width = size.0;
+            surface.geometry.height = size.1;
+        }
+
+        if !surface.configures.configured && surface.configures.sent.is_empty() {
+            debug!("Layer surface {} ({}) needs its initial configure", surface_id, surface.namespace);
+            return Ok(CommitOutcome::Rearrange(surface.output));
+        }
+        if state_changed || was_mapped != surface.mapped {
+            return Ok(CommitOutcome::Rearrange(surface.output));
+        }
+        Ok(CommitOutcome::Applied)
+    }
+
+    /// Lay out an output's layer surfaces
+    ///
+    /// Surfaces are placed from the top layer down, exclusive surfaces
+    /// first, each shrinking the usable area by its exclusive zone. Returns
+    /// the area left for ordinary windows, and queues a configure for every
+    /// surface whose size changed or that is still waiting for its first.
+    pub fn arrange_layers(&mut self, output: u32, area: WindowGeometry) -> WindowGeometry {
+        let mut usable = area;
+
+        let mut ids: Vec<u64> = self.layer_surfaces.iter()
+            .filter(|(_, s)| s.output == output)
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort_unstable();
+
+        let mut configures = Vec::new();
+
+        for layer in [Layer::Overlay, Layer::Top, Layer::Bottom, Layer::Background] {
+            for exclusive in [true, false] {
+                for id in &ids {
+                    let surface = self.layer_surfaces.get_mut(id).expect("id collected above");
+                    let state = &surface.current;
+                    if state.layer != layer || (state.exclusive_zone > 0) != exclusive {
+                        continue;
+                    }
+                    // Unmapped surfaces that were already configured take no space
+                    if !surface.mapped && surface.configures.configured {
+                        continue;
+                    }
+
+                    let bounds = if state.exclusive_zone == -1 { area } else { usable };
+                    let geometry = state.place(&bounds);
+
+                    if state.exclusive_zone > 0 {
+                        if let Some(edge) = state.anchor.exclusive_edge() {
+                            usable = shrink(usable, edge, state.exclusive_zone, state.margin);
+                        }
+                    }
+
+                    let size = (geometry.width, geometry.height);
+                    let size_changed = (surface.geometry.width, surface.geometry.height) != size;
+                    let awaiting_first = !surface.configures.configured && surface.configures.sent.is_empty();
+                    surface.geometry = geometry;
+                    if size_changed || awaiting_first {
+                        configures.push((*id, size));
+                    }
+                }
+            }
+        }
+
+        for (surface_id, size) in configures {
+            let serial = self.serial();
+            let state = ConfigureState::Layer { size };
+            if let Some(surface) = self.layer_surfaces.get_mut(&surface_id) {
+                surface.configures.sent.push_back((serial, state.clone()));
+            }
+            self.outgoing.push(Configure { surface_id, serial, state });
+        }
+
+        debug!("Output {} usable area after layers: {:?}", output, usable);
+        usable
+    }
+
+    /// Close every layer surface on a removed output
+    pub fn output_removed(&mut self, output: u32) {
+        let closed: Vec<u64> = self.layer_surfaces.iter()
+            .filter(|(_, s)| s.output == output)
+            .map(|(id, _)| *id)
+            .collect();
+
+        for surface_id in closed {
+            self.outgoing.push(Configure {
+                surface_id,
+                serial: 0,
+                state: ConfigureState::Closed,
+            });
+        }
+    }
+
+    /// Layer surface that should hold keyboard focus, if any
+    ///
+    /// The topmost mapped surface on the top or overlay layer that asked
+    /// for exclusive keyboard interactivity (e.g. a launcher or lock
+    /// screen) takes focus away from windows.
+    pub fn exclusive_keyboard_focus(&self) -> Option<u64> {
+        [Layer::Overlay, Layer::Top].iter().find_map(|layer| {
+            self.layer_surfaces.iter()
+                .filter(|(_, s)| {
+                    s.mapped
+                        && s.current.layer == *layer
+                        && s.current.keyboard_interactivity == KeyboardInteractivity::Exclusive
+                })
+                .map(|(id, _)| *id)
+                .max()
+        })
+    }
+
+    /// Mapped layer surfaces of an output on one layer, in stacking order
+    pub fn layer_surfaces_on(&self, output: u32, layer: Layer) -> Vec<(u64, &LayerSurface)> {
+        let mut surfaces: Vec<_> = self.layer_surfaces.iter()
+            .filter(|(_, s)| s.output == output && s.mapped && s.current.layer == layer)
+            .map(|(id, s)| (*id, s))
+            .collect();
+        surfaces.sort_unstable_by_key(|(id, _)| *id);
+        surfaces
+    }
+
+    /// Take the configures waiting to be sent
+    pub fn drain_configures(&mut self) -> Vec<Configure> {
+        std::mem::take(&mut self.outgoing)
+    }
+}
+
+/// Shrink `area` by an exclusive zone on `edge`
+fn shrink(area: WindowGeometry, edge: Edge, zone: i32, margin: (i32, i32, i32, i32)) -> WindowGeometry {
+    let (top, right, bottom, left) = margin;
+    let mut area = area;
+
+    match edge {
+        Edge::Top => {
+            let taken = (zone + top).max(0);
+            area.y += taken;
+            area.height = area.height.saturating_sub(taken as u32);
+        }
+        Edge::Bottom => {
+            let taken = (zone + bottom).max(0);
+            area.height = area.height.saturating_sub(taken as u32);
+        }
+        Edge::Left => {
+            let taken = (zone + left).max(0);
+            area.x += taken;
+            area.width = area.width.saturating_sub(taken as u32);
+        }
+        Edge::Right => {
+            let taken = (zone + right).max(0);
+            area.width = area.width.saturating_sub(taken as u32);
+        }
+    }
+
+    area
+}
+
+/// Outstanding configures of one surface
+#[derive(Debug, Clone, Default)]
+pub struct ConfigureQueue {
+    /// Sent but not yet acked, oldest first
+    sent: VecDeque<(u32, ConfigureState)>,
+    /// Acked, applied on the next commit
+    acked: Option<ConfigureState>,
+    /// Whether the client has acked at least one configure
+    configured: bool,
+}
+
+impl ConfigureQueue {
+    /// Ack a serial, discarding older configures
+    fn ack(&mut self, serial: u32) -> Result<()> {
+        let pos = self.sent.iter().position(|(s, _)| *s == serial)
+            .ok_or_else(|| anyhow!("invalid_serial: {} was never sent or already acked", serial))?;
+
+        let (_, state) = self.sent.drain(..=pos).next_back().expect("drained at least one");
+        self.acked = Some(state);
+        self.configured = true;
+        Ok(())
+    }
+
+    /// Whether the client has acked at least one configure
+    pub fn is_configured(&self) -> bool {
+        self.configured
     }
 }
 
+/// A configure event for a client
+#[derive(Debug, Clone)]
+pub struct Configure {
+    pub surface_id: u64,
+    /// 0 for events without a serial (close)
+    pub serial: u32,
+    pub state: ConfigureState,
+}
+
+/// State carried by a configure
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigureState {
+    Toplevel { size: (u32, u32), states: ToplevelStates },
+    Popup { geometry: WindowGeometry, reposition_token: Option<u32> },
+    Layer { size: (u32, u32) },
+    /// xdg_toplevel.close / zwlr_layer_surface_v1.closed
+    Closed,
+}
+
+/// What the compositor must do after a commit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitOutcome {
+    /// Nothing changed
+    Unchanged,
+    /// Acked state was applied
+    Applied,
+    /// First commit of an xdg surface; send its initial configure
+    NeedsInitialConfigure,
+    /// Layer state changed; re-arrange this output
+    Rearrange(u32),
+}
+
 /// xdg-shell surface
 #[derive(Debug, Clone)]
 pub struct XdgSurface {
     pub window_id: u64,
     pub role: XdgRole,
+    pub configures: ConfigureQueue,
 }
 
 /// xdg surface role
@@ -90,6 +503,8 @@ pub struct XdgToplevel {
     pub title: Option<String>,
     pub app_id: Option<String>,
     pub parent: Option<u64>,
+    /// Size from the last applied configure
+    pub size: (u32, u32),
     pub maximized: bool,
     pub fullscreen: bool,
     pub resizing: bool,
@@ -98,21 +513,279 @@ pub struct XdgToplevel {
     pub max_size: Option<(u32, u32)>,
 }
 
+impl XdgToplevel {
+    /// Current states, as a base for the next configure
+    pub fn states(&self) -> ToplevelStates {
+        ToplevelStates {
+            maximized: self.maximized,
+            fullscreen: self.fullscreen,
+            resizing: self.resizing,
+            activated: self.activated,
+        }
+    }
+
+    /// Clamp a size to the client's min/max hints (0 means unset)
+    pub fn constrain(&self, size: (u32, u32)) -> (u32, u32) {
+        let (mut w, mut h) = size;
+        if let Some((min_w, min_h)) = self.min_size {
+            w = w.max(min_w);
+            h = h.max(min_h);
+        }
+        if let Some((max_w, max_h)) = self.max_size {
+            if max_w > 0 {
+                w = w.min(max_w);
+            }
+            if max_h > 0 {
+                h = h.min(max_h);
+            }
+        }
+        (w, h)
+    }
+}
+
+/// xdg_toplevel states sent in a configure
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ToplevelStates {
+    pub maximized: bool,
+    pub fullscreen: bool,
+    pub resizing: bool,
+    pub activated: bool,
+}
+
 /// xdg-popup state
 #[derive(Debug, Clone)]
 pub struct XdgPopup {
     pub parent: u64,
+    /// Position relative to the parent, from the last applied configure
     pub position: (i32, i32),
+    pub positioner: Positioner,
+}
+
+/// xdg_positioner rules
+#[derive(Debug, Clone, Default)]
+pub struct Positioner {
+    pub size: (u32, u32),
+    /// Rectangle on the parent the popup is anchored to
+    pub anchor_rect: WindowGeometry,
+    pub anchor: Gravity,
+    pub gravity: Gravity,
+    pub offset: (i32, i32),
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub slide_x: bool,
+    pub slide_y: bool,
+}
+
+impl Positioner {
+    /// Place the popup, flipping then sliding it to stay within `bounds`
+    pub fn place(&self, bounds: &WindowGeometry) -> WindowGeometry {
+        let mut geometry = self.unconstrained(self.anchor, self.gravity);
+
+        if self.flip_x && !fits_x(&geometry, bounds) {
+            let flipped = self.unconstrained(self.anchor.flip_x(), self.gravity.flip_x());
+            if fits_x(&flipped, bounds) {
+                geometry.x = flipped.x;
+            }
+        }
+        if self.flip_y && !fits_y(&geometry, bounds) {
+            let flipped = self.unconstrained(self.anchor.flip_y(), self.gravity.flip_y());
+            if fits_y(&flipped, bounds) {
+                geometry.y = flipped.y;
+            }
+        }
+
+        if self.slide_x {
+            let max_x = bounds.x + bounds.width as i32 - geometry.width as i32;
+            geometry.x = geometry.x.min(max_x).max(bounds.x);
+        }
+        if self.slide_y {
+            let max_y = bounds.y + bounds.height as i32 - geometry.height as i32;
+            geometry.y = geometry.y.min(max_y).max(bounds.y);
+        }
+
+        geometry
+    }
+
+    fn unconstrained(&self, anchor: Gravity, gravity: Gravity) -> WindowGeometry {
+        let rect = &self.anchor_rect;
+        let (ax, ay) = anchor.point(rect);
+        let (w, h) = (self.size.0 as i32, self.size.1 as i32);
+
+        let x = match gravity.horizontal() {
+            -1 => ax - w,
+            1 => ax,
+            _ => ax - w / 2,
+        };
+        let y = match gravity.vertical() {
+            -1 => ay - h,
+            1 => ay,
+            _ => ay - h / 2,
+        };
+
+        WindowGeometry {
+            x: x + self.offset.0,
+            y: y + self.offset.1,
+            width: self.size.0,
+            height: self.size.1,
+        }
+    }
+}
+
+fn fits_x(geometry: &WindowGeometry, bounds: &WindowGeometry) -> bool {
+    geometry.x >= bounds.x && geometry.x + geometry.width as i32 <= bounds.x + bounds.width as i32
+}
+
+fn fits_y(geometry: &WindowGeometry, bounds: &WindowGeometry) -> bool {
+    geometry.y >= bounds.y && geometry.y + geometry.height as i32 <= bounds.y + bounds.height as i32
+}
+
+/// Positioner anchor or gravity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Gravity {
+    #[default]
+    None,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    BottomLeft,
+    TopRight,
+    BottomRight,
+}
+
+impl Gravity {
+    /// -1 for left, 1 for right, 0 for centered
+    fn horizontal(self) -> i32 {
+        match self {
+            Gravity::Left | Gravity::TopLeft | Gravity::BottomLeft => -1,
+            Gravity::Right | Gravity::TopRight | Gravity::BottomRight => 1,
+            _ => 0,
+        }
+    }
+
+    /// -1 for top, 1 for bottom, 0 for centered
+    fn vertical(self) -> i32 {
+        match self {
+            Gravity::Top | Gravity::TopLeft | Gravity::TopRight => -1,
+            Gravity::Bottom | Gravity::BottomLeft | Gravity::BottomRight => 1,
+            _ => 0,
+        }
+    }
+
+    fn from_parts(horizontal: i32, vertical: i32) -> Self {
+        match (horizontal, vertical) {
+            (-1, -1) => Gravity::TopLeft,
+            (1, -1) => Gravity::TopRight,
+            (-1, 1) => Gravity::BottomLeft,
+            (1, 1) => Gravity::BottomRight,
+            (-1, _) => Gravity::Left,
+            (1, _) => Gravity::Right,
+            (_, -1) => Gravity::Top,
+            (_, 1) => Gravity::Bottom,
+            _ => Gravity::None,
+        }
+    }
+
+    fn flip_x(self) -> Self {
+        Self::from_parts(-self.horizontal(), self.vertical())
+    }
+
+    fn flip_y(self) -> Self {
+        Self::from_parts(self.horizontal(), -self.vertical())
+    }
+
+    /// Point on `rect` this anchor refers to
+    fn point(self, rect: &WindowGeometry) -> (i32, i32) {
+        let x = match self.horizontal() {
+            -1 => rect.x,
+            1 => rect.x + rect.width as i32,
+            _ => rect.x + rect.width as i32 / 2,
+        };
+        let y = match self.vertical() {
+            -1 => rect.y,
+            1 => rect.y + rect.height as i32,
+            _ => rect.y + rect.height as i32 / 2,
+        };
+        (x, y)
+    }
 }
 
 /// Layer surface
 #[derive(Debug, Clone)]
 pub struct LayerSurface {
+    /// Client-chosen purpose, e.g. "panel" or "dock"
+    pub namespace: String,
+    pub output: u32,
+    /// State set by client requests, applied on commit
+    pub pending: LayerState,
+    /// Committed state
+    pub current: LayerState,
+    /// Placement from the last arrangement, in global coordinates
+    pub geometry: WindowGeometry,
+    pub mapped: bool,
+    pub configures: ConfigureQueue,
+}
+
+/// Double-buffered layer surface state
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerState {
     pub layer: Layer,
     pub anchor: Anchor,
+    /// Pixels reserved along the anchored edge; 0 to avoid other
+    /// exclusive zones, -1 to ignore them
     pub exclusive_zone: i32,
     pub margin: (i32, i32, i32, i32), // top, right, bottom, left
     pub keyboard_interactivity: KeyboardInteractivity,
+    /// Requested size; 0 stretches between opposite anchors
+    pub desired_size: (u32, u32),
+}
+
+impl Default for LayerState {
+    fn default() -> Self {
+        Self {
+            layer: Layer::Top,
+            anchor: Anchor::default(),
+            exclusive_zone: 0,
+            margin: (0, 0, 0, 0),
+            keyboard_interactivity: KeyboardInteractivity::None,
+            desired_size: (0, 0),
+        }
+    }
+}
+
+impl LayerState {
+    /// Place the surface within `bounds`
+    fn place(&self, bounds: &WindowGeometry) -> WindowGeometry {
+        let (top, right, bottom, left) = self.margin;
+        let a = self.anchor;
+
+        let width = if self.desired_size.0 == 0 {
+            (bounds.width as i32 - left - right).max(0) as u32
+        } else {
+            self.desired_size.0
+        };
+        let height = if self.desired_size.1 == 0 {
+            (bounds.height as i32 - top - bottom).max(0) as u32
+        } else {
+            self.desired_size.1
+        };
+
+        let x = match (a.left, a.right) {
+            (true, false) => bounds.x + left,
+            (false, true) => bounds.x + bounds.width as i32 - width as i32 - right,
+            (true, true) if self.desired_size.0 == 0 => bounds.x + left,
+            _ => bounds.x + (bounds.width as i32 - width as i32) / 2,
+        };
+        let y = match (a.top, a.bottom) {
+            (true, false) => bounds.y + top,
+            (false, true) => bounds.y + bounds.height as i32 - height as i32 - bottom,
+            (true, true) if self.desired_size.1 == 0 => bounds.y + top,
+            _ => bounds.y + (bounds.height as i32 - height as i32) / 2,
+        };
+
+        WindowGeometry { x, y, width, height }
+    }
 }
 
 /// Layer shell layer
@@ -125,7 +798,7 @@ pub enum Layer {
 }
 
 /// Layer shell anchor
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Anchor {
     pub top: bool,
     pub bottom: bool,
@@ -133,6 +806,32 @@ pub struct Anchor {
     pub right: bool,
 }
 
+impl Anchor {
+    /// Edge an exclusive zone applies to
+    ///
+    /// Per the protocol that is the single anchored edge, or the edge
+    /// anchored alongside both of its perpendicular edges (a panel
+    /// spanning the screen). Other combinations reserve nothing.
+    pub fn exclusive_edge(&self) -> Option<Edge> {
+        match (self.top, self.bottom, self.left, self.right) {
+            (true, false, false, false) | (true, false, true, true) => Some(Edge::Top),
+            (false, true, false, false) | (false, true, true, true) => Some(Edge::Bottom),
+            (false, false, true, false) | (true, true, true, false) => Some(Edge::Left),
+            (false, false, false, true) | (true, true, false, true) => Some(Edge::Right),
+            _ => None,
+        }
+    }
+}
+
+/// Output edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
 /// Keyboard interactivity
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KeyboardInteractivity {
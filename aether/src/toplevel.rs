@@ -0,0 +1,166 @@
+//! Foreign toplevel management
+//!
+//! Backs wlr-foreign-toplevel-management, which nyx-shell uses for the
+//! dock's window list and the Alt+Tab switcher. Window titles say a lot
+//! about what the user is doing, so the manager global is privileged and
+//! only clients Guardian allows get to bind it. Bound clients are told
+//! about every mapped toplevel and every change to its title, app ID or
+//! state, and can ask for a window to be activated, minimized, maximized
+//! or closed.
+
+use std::collections::{BTreeSet, HashMap};
+use tracing::debug;
+
+/// Global name for the security check
+pub const MANAGER_GLOBAL: &str = "zwlr_foreign_toplevel_manager";
+
+/// Highest zwlr_foreign_toplevel_manager_v1 version offered
+pub const MANAGER_VERSION: u32 = 3;
+
+/// What a bound client is told about a window
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToplevelInfo {
+    pub title: String,
+    pub app_id: String,
+    pub activated: bool,
+    pub minimized: bool,
+    pub maximized: bool,
+    pub fullscreen: bool,
+}
+
+impl ToplevelInfo {
+    /// Values for the handle's `state` array event
+    pub fn states(&self) -> Vec<u32> {
+        [
+            (self.maximized, 0),
+            (self.minimized, 1),
+            (self.activated, 2),
+            (self.fullscreen, 3),
+        ]
+        .into_iter()
+        .filter_map(|(set, value)| set.then_some(value))
+        .collect()
+    }
+}
+
+/// Event for a bound client, delivered by the Wayland frontend
+#[derive(Debug, Clone)]
+pub enum ToplevelEvent {
+    /// New handle, followed by title, app_id, state and done
+    Announce { client_id: u32, window_id: u64, info: ToplevelInfo },
+    /// Changed fields of an announced window, followed by done
+    Changed { client_id: u32, window_id: u64, info: ToplevelInfo },
+    /// The window is gone; the handle is inert from here on
+    Closed { client_id: u32, window_id: u64 },
+    /// The manager is going away (zwlr_foreign_toplevel_manager_v1.finished)
+    Finished { client_id: u32 },
+}
+
+/// Request on a toplevel handle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToplevelRequest {
+    Activate,
+    SetMinimized,
+    UnsetMinimized,
+    SetMaximized,
+    UnsetMaximized,
+    SetFullscreen,
+    UnsetFullscreen,
+    Close,
+}
+
+/// Bound clients and the window state last announced to them
+#[derive(Default)]
+pub struct ForeignToplevels {
+    /// Clients bound to the manager
+    clients: BTreeSet<u32>,
+    /// Last announced state by window
+    published: HashMap<u64, ToplevelInfo>,
+    /// Events waiting for the Wayland frontend
+    outgoing: Vec<ToplevelEvent>,
+}
+
+impl ForeignToplevels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A client bound the manager; it is told about every current window
+    pub fn bind(&mut self, client_id: u32) {
+        if !self.clients.insert(client_id) {
+            return;
+        }
+        debug!("Client {} bound foreign toplevel manager", client_id);
+
+        for (&window_id, info) in &self.published {
+            self.outgoing.push(ToplevelEvent::Announce {
+                client_id,
+                window_id,
+                info: info.clone(),
+            });
+        }
+    }
+
+    /// Whether a client may send handle requests
+    pub fn is_bound(&self, client_id: u32) -> bool {
+        self.clients.contains(&client_id)
+    }
+
+    /// The client called zwlr_foreign_toplevel_manager_v1.stop
+    pub fn stop(&mut self, client_id: u32) {
+        if self.clients.remove(&client_id) {
+            self.outgoing.push(ToplevelEvent::Finished { client_id });
+        }
+    }
+
+    /// Forget a client that went away
+    pub fn client_disconnected(&mut self, client_id: u32) {
+        self.clients.remove(&client_id);
+        self.outgoing.retain(|event| match event {
+            ToplevelEvent::Announce { client_id: c, .. }
+            | ToplevelEvent::Changed { client_id: c, .. }
+            | ToplevelEvent::Closed { client_id: c, .. }
+            | ToplevelEvent::Finished { client_id: c } => *c != client_id,
+        });
+    }
+
+    /// Announce a window or its changes to every bound client
+    pub fn publish(&mut self, window_id: u64, info: ToplevelInfo) {
+        let known = match self.published.get(&window_id) {
+            Some(previous) if *previous == info => return,
+            Some(_) => true,
+            None => false,
+        };
+
+        for &client_id in &self.clients {
+            let info = info.clone();
+            self.outgoing.push(if known {
+                ToplevelEvent::Changed { client_id, window_id, info }
+            } else {
+                ToplevelEvent::Announce { client_id, window_id, info }
+            });
+        }
+        self.published.insert(window_id, info);
+    }
+
+    /// Drop announced windows that are no longer live, telling every
+    /// bound client they closed
+    pub fn retain(&mut self, live: impl Fn(u64) -> bool) {
+        let closed: Vec<u64> = self.published.keys()
+            .copied()
+            .filter(|&window_id| !live(window_id))
+            .collect();
+
+        for window_id in closed {
+            self.published.remove(&window_id);
+            for &client_id in &self.clients {
+                self.outgoing.push(ToplevelEvent::Closed { client_id, window_id });
+            }
+        }
+    }
+
+    /// Take the events waiting for delivery
+    pub fn drain(&mut self) -> Vec<ToplevelEvent> {
+        std::mem::take(&mut self.outgoing)
+    }
+}
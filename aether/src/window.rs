@@ -42,8 +42,12 @@ impl WindowManager {
             id,
             client_id,
             title,
+            app_id: None,
             geometry: WindowGeometry::default(),
             state: WindowState::Normal,
+            workspace: 1,
+            floating: false,
+            opacity: 1.0,
             decorations: self.config.decorations,
             visible: true,
             mapped: false,
@@ -116,6 +120,14 @@ impl WindowManager {
         }
     }
 
+    /// Move window to a workspace
+    pub fn set_workspace(&mut self, id: u64, workspace: u32) {
+        if let Some(window) = self.windows.get_mut(&id) {
+            window.workspace = workspace;
+            debug!("Window {} moved to workspace {}", id, workspace);
+        }
+    }
+
     /// Map window (make visible)
     pub fn map(&mut self, id: u64) {
         if let Some(window) = self.windows.get_mut(&id) {
@@ -130,6 +142,33 @@ impl WindowManager {
         }
     }
 
+    /// Minimize a window, handing focus to the topmost visible window
+    pub fn minimize(&mut self, id: u64) {
+        let Some(window) = self.windows.get_mut(&id) else {
+            return;
+        };
+        window.state = WindowState::Minimized;
+        window.visible = false;
+
+        if self.focused == Some(id) {
+            self.focused = self.stacking.iter().rev()
+                .copied()
+                .find(|wid| self.windows.get(wid).is_some_and(|w| w.visible && w.mapped));
+        }
+        debug!("Window minimized: {}", id);
+    }
+
+    /// Bring a minimized window back in the given state
+    pub fn restore(&mut self, id: u64, state: WindowState) {
+        if let Some(window) = self.windows.get_mut(&id) {
+            if window.state == WindowState::Minimized {
+                window.state = state;
+                window.visible = true;
+                debug!("Window restored: {}", id);
+            }
+        }
+    }
+
     /// Find window at position
     pub fn window_at(&self, x: i32, y: i32) -> Option<u64> {
         // Search from top to bottom
@@ -153,10 +192,18 @@ pub struct Window {
     pub client_id: u32,
     /// Window title
     pub title: String,
+    /// Application ID
+    pub app_id: Option<String>,
     /// Geometry
     pub geometry: WindowGeometry,
     /// State
     pub state: WindowState,
+    /// Workspace
+    pub workspace: u32,
+    /// Floating rather than tiled
+    pub floating: bool,
+    /// Opacity (0.0 - 1.0)
+    pub opacity: f32,
     /// Has decorations
     pub decorations: bool,
     /// Is visible
@@ -175,7 +222,7 @@ impl Window {
 }
 
 /// Window geometry
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct WindowGeometry {
     pub x: i32,
     pub y: i32,
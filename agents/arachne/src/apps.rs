@@ -0,0 +1,120 @@
+//! Application identity for egress rules
+//!
+//! nftables matches sockets by the cgroup v2 path of their owner, so app
+//! rules are expressed in cgroups. Rules naming a binary are resolved to
+//! the cgroups its running processes belong to; nyx-serviced and archon
+//! give each service and app its own cgroup, so this does not catch
+//! unrelated processes. Resolution is repeated periodically and the
+//! ruleset re-applied when an app starts or moves.
+
+use crate::config::AppRule;
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// How often binary rules are re-resolved
+pub const RESCAN_SECS: u64 = 10;
+
+/// App name -> cgroup paths its rules apply to
+pub type Resolved = BTreeMap<String, Vec<String>>;
+
+/// Resolve every app rule to cgroup paths
+pub fn resolve(apps: &[AppRule]) -> Resolved {
+    let needs_scan = apps.iter().any(|app| app.cgroup.is_none() && app.binary.is_some());
+    let processes = if needs_scan { scan_processes() } else { Vec::new() };
+
+    let mut resolved = Resolved::new();
+    for app in apps {
+        let cgroups = match (&app.cgroup, &app.binary) {
+            (Some(cgroup), _) => vec![cgroup.trim_matches('/').to_string()],
+            (None, Some(binary)) => processes
+                .iter()
+                .filter(|(exe, _)| exe == binary)
+                .map(|(_, cgroup)| cgroup.clone())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+            (None, None) => Vec::new(),
+        };
+        resolved.insert(app.name.clone(), cgroups);
+    }
+    resolved
+}
+
+/// Cgroups the running processes of `binaries` belong to
+pub fn resolve_binaries(binaries: &[String]) -> Vec<String> {
+    if binaries.is_empty() {
+        return Vec::new();
+    }
+    scan_processes()
+        .into_iter()
+        .filter(|(exe, _)| binaries.contains(exe))
+        .map(|(_, cgroup)| cgroup)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Check an app rule is well formed
+pub fn validate(app: &AppRule) -> Result<()> {
+    match (&app.cgroup, &app.binary) {
+        (Some(cgroup), _) => validate_cgroup(cgroup.trim_matches('/')),
+        (None, Some(binary)) if Path::new(binary).is_absolute() => Ok(()),
+        (None, Some(binary)) => Err(anyhow!("app {}: binary {} is not absolute", app.name, binary)),
+        (None, None) => Err(anyhow!("app {}: needs a cgroup or binary", app.name)),
+    }
+}
+
+/// Cgroup paths are quoted into nft syntax, so keep them plain
+pub fn validate_cgroup(path: &str) -> Result<()> {
+    let plain = path
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_.@:/".contains(c));
+    if path.is_empty() || !plain || path.split('/').any(|c| c.is_empty() || c == "..") {
+        return Err(anyhow!("invalid cgroup path {:?}", path));
+    }
+    Ok(())
+}
+
+/// Depth of a cgroup below the root, as `socket cgroupv2 level` wants
+pub fn level(path: &str) -> usize {
+    path.split('/').count()
+}
+
+/// (executable, cgroup) of every process outside the root cgroup
+fn scan_processes() -> Vec<(String, String)> {
+    let mut processes = Vec::new();
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return processes;
+    };
+
+    for entry in entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|p| p.parse::<u32>().ok()) else {
+            continue;
+        };
+        let Ok(exe) = std::fs::read_link(format!("/proc/{}/exe", pid)) else {
+            continue;
+        };
+        let Ok(cgroups) = std::fs::read_to_string(format!("/proc/{}/cgroup", pid)) else {
+            continue;
+        };
+
+        // Unified hierarchy entry: `0::/path`
+        let Some(cgroup) = cgroups
+            .lines()
+            .find_map(|line| line.strip_prefix("0::"))
+            .map(|path| path.trim_matches('/'))
+        else {
+            continue;
+        };
+        if cgroup.is_empty() || validate_cgroup(cgroup).is_err() {
+            continue;
+        }
+
+        let exe = exe.to_string_lossy();
+        let exe = exe.strip_suffix(" (deleted)").unwrap_or(&exe);
+        processes.push((exe.to_string(), cgroup.to_string()));
+    }
+
+    processes
+}
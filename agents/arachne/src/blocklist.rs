@@ -0,0 +1,282 @@
+//! Blocklist subscriptions
+//!
+//! Lists are fetched from their URLs, parsed as hosts files or Adblock Plus
+//! domain rules and filed under their subscription's category. Every
+//! fetch is cached on disk so filtering works from startup, before (or
+//! without) the network.
+
+use crate::config::{BlocklistFormat, BlocklistSubscription};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Hosts-file names that are not blocklist entries
+const HOSTS_NOISE: &[&str] = &[
+    "localhost", "localhost.localdomain", "local", "broadcasthost",
+    "ip6-localhost", "ip6-loopback", "0.0.0.0",
+];
+
+/// Loaded subscriptions
+pub struct Blocklists {
+    dir: PathBuf,
+    lists: HashMap<String, List>,
+}
+
+struct List {
+    subscription: BlocklistSubscription,
+    domains: HashSet<String>,
+    /// ABP `@@` rules: never blocked by any list
+    exceptions: HashSet<String>,
+    updated: Option<DateTime<Utc>>,
+    error: Option<String>,
+}
+
+/// A subscription and how its last fetch went
+#[derive(Debug, Clone)]
+pub struct ListStatus {
+    pub name: String,
+    pub url: String,
+    pub category: String,
+    pub domains: usize,
+    pub updated: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+/// Domains parsed from a list
+#[derive(Debug, Default)]
+pub struct Parsed {
+    pub domains: HashSet<String>,
+    pub exceptions: HashSet<String>,
+}
+
+/// Which list blocked a name
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub list: String,
+    pub category: String,
+}
+
+impl Blocklists {
+    /// Set up `subscriptions`, loading whatever was cached last time
+    pub fn load(dir: &Path, subscriptions: &[BlocklistSubscription]) -> Self {
+        let mut lists = Self { dir: dir.to_path_buf(), lists: HashMap::new() };
+        for subscription in subscriptions {
+            if let Err(e) = lists.add(subscription.clone()) {
+                tracing::warn!("Skipping blocklist {}: {}", subscription.name, e);
+            }
+        }
+        lists
+    }
+
+    /// Add or replace a subscription; cached contents are used until fetched
+    pub fn add(&mut self, subscription: BlocklistSubscription) -> Result<()> {
+        validate_name(&subscription.name)?;
+        if !subscription.url.starts_with("https://") && !subscription.url.starts_with("http://") {
+            return Err(anyhow!("blocklist {}: unsupported URL {}", subscription.name, subscription.url));
+        }
+
+        let cache = self.cache_path(&subscription.name);
+        let (parsed, updated) = match std::fs::read_to_string(&cache) {
+            Ok(text) => {
+                let modified = std::fs::metadata(&cache).and_then(|m| m.modified()).ok();
+                (parse(&text, subscription.format), modified.map(DateTime::<Utc>::from))
+            }
+            Err(_) => (Parsed::default(), None),
+        };
+
+        self.lists.insert(subscription.name.clone(), List {
+            subscription,
+            domains: parsed.domains,
+            exceptions: parsed.exceptions,
+            updated,
+            error: None,
+        });
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let _ = std::fs::remove_file(self.cache_path(name));
+        self.lists.remove(name).is_some()
+    }
+
+    pub fn subscriptions(&self) -> Vec<BlocklistSubscription> {
+        self.lists.values().map(|l| l.subscription.clone()).collect()
+    }
+
+    /// Install freshly fetched contents (or record why the fetch failed)
+    pub fn install(&mut self, name: &str, fetched: Result<String>) {
+        let dir = self.dir.clone();
+        let Some(list) = self.lists.get_mut(name) else { return };
+
+        match fetched {
+            Ok(text) => {
+                let parsed = parse(&text, list.subscription.format);
+                if let Err(e) = std::fs::create_dir_all(&dir)
+                    .and_then(|_| std::fs::write(dir.join(format!("{}.txt", name)), &text))
+                {
+                    tracing::warn!("Failed to cache blocklist {}: {}", name, e);
+                }
+                tracing::info!("Blocklist {} updated: {} domains", name, parsed.domains.len());
+                list.domains = parsed.domains;
+                list.exceptions = parsed.exceptions;
+                list.updated = Some(Utc::now());
+                list.error = None;
+            }
+            Err(e) => {
+                // Keep the previous contents; a failed fetch should not unblock
+                tracing::warn!("Failed to fetch blocklist {}: {}", name, e);
+                list.error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// First list in a blocked category listing `name` or a parent domain
+    pub fn lookup(&self, name: &str, blocked: impl Fn(&str) -> bool) -> Option<Hit> {
+        let suffixes: Vec<&str> = suffixes(name).collect();
+
+        let excepted = self.lists.values().any(|list| {
+            suffixes.iter().any(|s| list.exceptions.contains(*s))
+        });
+        if excepted {
+            return None;
+        }
+
+        self.lists
+            .values()
+            .filter(|list| blocked(&list.subscription.category))
+            .find(|list| suffixes.iter().any(|s| list.domains.contains(*s)))
+            .map(|list| Hit {
+                list: list.subscription.name.clone(),
+                category: list.subscription.category.clone(),
+            })
+    }
+
+    pub fn status(&self) -> Vec<ListStatus> {
+        let mut status: Vec<_> = self.lists.values().map(|list| ListStatus {
+            name: list.subscription.name.clone(),
+            url: list.subscription.url.clone(),
+            category: list.subscription.category.clone(),
+            domains: list.domains.len(),
+            updated: list.updated,
+            error: list.error.clone(),
+        }).collect();
+        status.sort_by(|a, b| a.name.cmp(&b.name));
+        status
+    }
+
+    /// Total domains across all lists
+    pub fn len(&self) -> usize {
+        self.lists.values().map(|l| l.domains.len()).sum()
+    }
+
+    fn cache_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.txt", name))
+    }
+}
+
+/// Download a subscription's list
+pub async fn fetch(client: &reqwest::Client, subscription: &BlocklistSubscription) -> Result<String> {
+    let response = client
+        .get(&subscription.url)
+        .timeout(Duration::from_secs(60))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response.text().await?)
+}
+
+/// Parse a list in the given (or detected) format
+pub fn parse(text: &str, format: BlocklistFormat) -> Parsed {
+    let format = match format {
+        BlocklistFormat::Auto => detect(text),
+        other => other,
+    };
+
+    let mut parsed = Parsed::default();
+    for line in text.lines() {
+        let line = line.trim();
+        match format {
+            BlocklistFormat::Abp => parse_abp_line(line, &mut parsed),
+            _ => parse_hosts_line(line, &mut parsed),
+        }
+    }
+    parsed
+}
+
+fn detect(text: &str) -> BlocklistFormat {
+    let abp = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .take(50)
+        .any(|l| l.starts_with("[Adblock") || l.starts_with("||") || l.starts_with("@@||"));
+    if abp { BlocklistFormat::Abp } else { BlocklistFormat::Hosts }
+}
+
+fn parse_hosts_line(line: &str, parsed: &mut Parsed) {
+    let line = line.split('#').next().unwrap_or_default();
+    let mut fields = line.split_whitespace();
+    let Some(first) = fields.next() else { return };
+
+    if first.parse::<std::net::IpAddr>().is_ok() {
+        for name in fields {
+            if let Some(domain) = normalize(name) {
+                if !HOSTS_NOISE.contains(&domain.as_str()) {
+                    parsed.domains.insert(domain);
+                }
+            }
+        }
+    } else if let Some(domain) = normalize(first) {
+        parsed.domains.insert(domain);
+    }
+}
+
+/// Only whole-domain rules mean anything to a resolver:
+/// `||example.com^` and `@@||example.com^`, optionally `$important`
+fn parse_abp_line(line: &str, parsed: &mut Parsed) {
+    if line.starts_with('!') || line.starts_with('[') {
+        return;
+    }
+
+    let (exception, rule) = match line.strip_prefix("@@") {
+        Some(rule) => (true, rule),
+        None => (false, line),
+    };
+    let Some(rule) = rule.strip_prefix("||") else { return };
+    let rule = match rule.split_once('$') {
+        Some((rule, "important")) => rule,
+        Some(_) => return,
+        None => rule,
+    };
+    let Some(domain) = rule.strip_suffix('^').and_then(normalize) else { return };
+
+    if exception {
+        parsed.exceptions.insert(domain);
+    } else {
+        parsed.domains.insert(domain);
+    }
+}
+
+/// Lowercase a domain, rejecting anything that is not one
+pub fn normalize(name: &str) -> Option<String> {
+    let name = name.trim().trim_end_matches('.').to_ascii_lowercase();
+    let valid = name.contains('.')
+        && !name.starts_with('.')
+        && name.len() <= 253
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    valid.then_some(name)
+}
+
+/// `a.b.c` yields `a.b.c`, `b.c`, `c`
+pub fn suffixes(name: &str) -> impl Iterator<Item = &str> {
+    std::iter::successors(Some(name), |n| n.split_once('.').map(|(_, rest)| rest))
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)) || name.starts_with('.') {
+        return Err(anyhow!("invalid blocklist name {:?}", name));
+    }
+    Ok(())
+}
@@ -14,6 +14,8 @@ pub struct ArachneConfig {
     pub monitor: MonitorConfig,
     #[serde(default)]
     pub vpn: VpnConfig,
+    #[serde(default)]
+    pub ids: IdsConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +28,11 @@ pub struct FirewallConfig {
     pub rules: Vec<FirewallRule>,
     #[serde(default = "default_true")]
     pub log_blocked: bool,
+    #[serde(default)]
+    pub apply: ApplyConfig,
+    /// Per-application egress rules
+    #[serde(default)]
+    pub apps: Vec<AppRule>,
 }
 
 impl Default for FirewallConfig {
@@ -35,10 +42,39 @@ impl Default for FirewallConfig {
             default_policy: DefaultPolicy::Drop,
             rules: default_firewall_rules(),
             log_blocked: true,
+            apply: ApplyConfig::default(),
+            apps: Vec::new(),
+        }
+    }
+}
+
+/// Safeguards when swapping in a new ruleset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplyConfig {
+    /// `host:port` endpoints probed after a change; if none answer the
+    /// previous ruleset is restored. Empty skips the check.
+    #[serde(default)]
+    pub check_targets: Vec<String>,
+    #[serde(default = "default_check_timeout")]
+    pub check_timeout_secs: u64,
+    /// Roll back unless the change is confirmed over IPC within this many
+    /// seconds (0 disables)
+    #[serde(default)]
+    pub confirm_timeout_secs: u64,
+}
+
+impl Default for ApplyConfig {
+    fn default() -> Self {
+        Self {
+            check_targets: Vec::new(),
+            check_timeout_secs: default_check_timeout(),
+            confirm_timeout_secs: 0,
         }
     }
 }
 
+fn default_check_timeout() -> u64 { 5 }
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum DefaultPolicy { Accept, #[default] Drop, Reject }
@@ -56,8 +92,44 @@ pub struct FirewallRule {
     pub source: Option<String>,
     #[serde(default)]
     pub destination: Option<String>,
+    /// Conntrack states to match (new, established, related, invalid)
+    #[serde(default)]
+    pub ct_state: Vec<String>,
+}
+
+/// Egress rule for one application
+///
+/// The app is identified by its cgroup (as created by nyx-serviced or
+/// archon) or by its executable, which is resolved to the cgroups its
+/// processes run in. Exceptions are accepted before `action` applies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppRule {
+    pub name: String,
+    /// Cgroup v2 path below the hierarchy root, e.g. `nyx.slice/foo.scope`
+    #[serde(default)]
+    pub cgroup: Option<String>,
+    /// Absolute path of the executable
+    #[serde(default)]
+    pub binary: Option<String>,
+    #[serde(default = "default_app_action")]
+    pub action: Action,
+    #[serde(default)]
+    pub except: Vec<AppException>,
+}
+
+/// Traffic an app rule lets through
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppException {
+    #[serde(default)]
+    pub protocol: Option<String>,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub destination: Option<String>,
 }
 
+fn default_app_action() -> Action { Action::Drop }
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Direction { In, Out, Both }
@@ -75,6 +147,7 @@ fn default_firewall_rules() -> Vec<FirewallRule> {
             protocol: None, port: None,
             source: Some("127.0.0.0/8".into()),
             destination: Some("127.0.0.0/8".into()),
+            ct_state: Vec::new(),
         },
         FirewallRule {
             name: "allow-established".into(),
@@ -82,6 +155,7 @@ fn default_firewall_rules() -> Vec<FirewallRule> {
             action: Action::Accept,
             protocol: None, port: None,
             source: None, destination: None,
+            ct_state: vec!["established".into(), "related".into()],
         },
     ]
 }
@@ -102,6 +176,22 @@ pub struct DnsConfig {
     pub cache_size: usize,
     #[serde(default)]
     pub blocklist: Vec<String>,
+    /// Blocklists fetched and refreshed from URLs
+    #[serde(default)]
+    pub subscriptions: Vec<BlocklistSubscription>,
+    #[serde(default = "default_refresh_hours")]
+    pub refresh_hours: u64,
+    /// Where fetched lists are kept so they survive restarts offline
+    #[serde(default = "default_blocklist_dir")]
+    pub blocklist_dir: String,
+    /// Policy for clients no other policy matches
+    #[serde(default)]
+    pub default_policy: DnsPolicy,
+    /// Per-client overrides, first match wins
+    #[serde(default)]
+    pub clients: Vec<ClientPolicy>,
+    #[serde(default)]
+    pub query_log: QueryLogConfig,
 }
 
 impl Default for DnsConfig {
@@ -114,10 +204,107 @@ impl Default for DnsConfig {
             cache_enabled: true,
             cache_size: default_cache_size(),
             blocklist: Vec::new(),
+            subscriptions: Vec::new(),
+            refresh_hours: default_refresh_hours(),
+            blocklist_dir: default_blocklist_dir(),
+            default_policy: DnsPolicy::default(),
+            clients: Vec::new(),
+            query_log: QueryLogConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlocklistSubscription {
+    pub name: String,
+    pub url: String,
+    /// Category the list's domains are filed under (ads, malware, ...)
+    pub category: String,
+    #[serde(default)]
+    pub format: BlocklistFormat,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BlocklistFormat {
+    /// Detect from contents
+    #[default]
+    Auto,
+    /// `0.0.0.0 example.com` or bare domains
+    Hosts,
+    /// Adblock Plus domain rules (`||example.com^`)
+    Abp,
+}
+
+/// What a client may resolve
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DnsPolicy {
+    /// Blocked categories; `None` blocks every subscribed category
+    #[serde(default)]
+    pub block_categories: Option<Vec<String>>,
+    /// Domains always allowed (with subdomains)
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Domains always blocked (with subdomains)
+    #[serde(default)]
+    pub block: Vec<String>,
+    /// Skip filtering entirely
+    #[serde(default)]
+    pub bypass: bool,
+}
+
+/// Policy for clients matched by source address or local user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientPolicy {
+    pub name: String,
+    /// Source addresses or networks
+    #[serde(default)]
+    pub sources: Vec<String>,
+    /// Local user names
+    #[serde(default)]
+    pub users: Vec<String>,
+    #[serde(flatten)]
+    pub policy: DnsPolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryLogConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Also log queries that were allowed, not just blocked ones
+    #[serde(default)]
+    pub log_allowed: bool,
+    #[serde(default)]
+    pub client_detail: ClientDetail,
+    #[serde(default = "default_query_log_entries")]
+    pub max_entries: usize,
+}
+
+impl Default for QueryLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            log_allowed: false,
+            client_detail: ClientDetail::default(),
+            max_entries: default_query_log_entries(),
         }
     }
 }
 
+/// How much of the client is recorded in the query log
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientDetail {
+    Full,
+    /// Network only (/24 or /48) and no user
+    #[default]
+    Truncated,
+    None,
+}
+
+fn default_refresh_hours() -> u64 { 24 }
+fn default_blocklist_dir() -> String { "/var/cache/arachne/blocklists".into() }
+fn default_query_log_entries() -> usize { 1000 }
 fn default_dns_port() -> u16 { 53 }
 fn default_upstream() -> Vec<String> { vec!["1.1.1.1".into(), "8.8.8.8".into()] }
 fn default_cache_size() -> usize { 10000 }
@@ -151,8 +338,95 @@ fn default_interval() -> u64 { 5 }
 pub struct VpnConfig {
     #[serde(default)]
     pub wireguard: Option<WireGuardConfig>,
+    #[serde(default)]
+    pub kill_switch: KillSwitchConfig,
+    #[serde(default)]
+    pub split_tunnel: SplitTunnelConfig,
+}
+
+/// Block egress outside the tunnel while a VPN is supposed to be up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillSwitchConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Engage even with no Arachne VPN connected, e.g. for tunnels
+    /// brought up by wraith
+    #[serde(default)]
+    pub always: bool,
+    /// Tunnel interfaces besides the WireGuard one configured here
+    #[serde(default)]
+    pub interfaces: Vec<String>,
+    /// `address:port` VPN servers reachable outside the tunnel, besides
+    /// the configured WireGuard peers
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+    #[serde(default = "default_true")]
+    pub allow_lan: bool,
+}
+
+impl Default for KillSwitchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            always: false,
+            interfaces: Vec::new(),
+            endpoints: Vec::new(),
+            allow_lan: true,
+        }
+    }
 }
 
+/// Policy routing of selected traffic around or into the tunnel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitTunnelConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub mode: SplitMode,
+    /// Cgroup v2 paths of apps to split
+    #[serde(default)]
+    pub cgroups: Vec<String>,
+    /// Executables to split, resolved to their cgroups
+    #[serde(default)]
+    pub binaries: Vec<String>,
+    /// Destination networks to split
+    #[serde(default)]
+    pub destinations: Vec<String>,
+    /// Routing table for split traffic
+    #[serde(default = "default_split_table")]
+    pub table: u32,
+    /// Firewall mark selecting the table
+    #[serde(default = "default_split_mark")]
+    pub mark: u32,
+}
+
+impl Default for SplitTunnelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: SplitMode::default(),
+            cgroups: Vec::new(),
+            binaries: Vec::new(),
+            destinations: Vec::new(),
+            table: default_split_table(),
+            mark: default_split_mark(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitMode {
+    /// Selected traffic bypasses the tunnel
+    #[default]
+    Exclude,
+    /// Only selected traffic uses the tunnel
+    Include,
+}
+
+fn default_split_table() -> u32 { 51820 }
+fn default_split_mark() -> u32 { 0x4e59 }
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WireGuardConfig {
     pub interface: String,
@@ -168,6 +442,86 @@ pub struct WireGuardPeer {
     pub allowed_ips: Vec<String>,
 }
 
+/// Intrusion detection over the monitor's connections and flows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdsConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Many local ports probed by one remote address
+    #[serde(default)]
+    pub port_scan: RuleConfig,
+    /// Sockets that start listening after Arachne's first scan
+    #[serde(default)]
+    pub unexpected_listener: RuleConfig,
+    /// Outbound connections opened at suspiciously regular intervals
+    #[serde(default)]
+    pub beaconing: RuleConfig,
+    /// Listeners never reported, as process names or ports
+    #[serde(default)]
+    pub allowed_listeners: Vec<String>,
+    /// Periodic traffic never reported, as process names, remote
+    /// addresses or ports
+    #[serde(default = "default_allowed_beacons")]
+    pub allowed_beacons: Vec<String>,
+    /// Quiet period before the same rule fires again for the same subject
+    #[serde(default = "default_ids_cooldown")]
+    pub cooldown_secs: u64,
+    /// Report detections to Guardian's pattern learner
+    #[serde(default = "default_true")]
+    pub report_guardian: bool,
+    /// Raise detections as herald notifications
+    #[serde(default = "default_true")]
+    pub notify: bool,
+    #[serde(default = "default_herald_socket")]
+    pub herald_socket: String,
+}
+
+impl Default for IdsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            port_scan: RuleConfig::default(),
+            unexpected_listener: RuleConfig::default(),
+            beaconing: RuleConfig::default(),
+            allowed_listeners: Vec::new(),
+            allowed_beacons: default_allowed_beacons(),
+            cooldown_secs: default_ids_cooldown(),
+            report_guardian: true,
+            notify: true,
+            herald_socket: default_herald_socket(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RuleConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub sensitivity: Sensitivity,
+}
+
+impl Default for RuleConfig {
+    fn default() -> Self {
+        Self { enabled: true, sensitivity: Sensitivity::default() }
+    }
+}
+
+/// How readily a rule fires; higher catches more at the cost of noise
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Sensitivity {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+// DNS and NTP are periodic by design
+fn default_allowed_beacons() -> Vec<String> { vec!["53".into(), "123".into()] }
+fn default_ids_cooldown() -> u64 { 600 }
+fn default_herald_socket() -> String { "/run/herald/herald.sock".into() }
+
 fn default_true() -> bool { true }
 
 pub async fn load_config(path: &Path) -> Result<ArachneConfig> {
@@ -0,0 +1,350 @@
+//! Connection tracking flows
+//!
+//! Reads the kernel's conntrack table (`/proc/net/nf_conntrack`, or the
+//! `conntrack` tool where procfs does not expose it), orients each flow
+//! from this host's point of view and attributes it to the process owning
+//! the local socket. Byte counters need `net.netfilter.nf_conntrack_acct`.
+
+use crate::monitor::{Connection, Protocol};
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::net::IpAddr;
+use std::process::Command;
+use std::time::Instant;
+
+/// A tracked flow seen from this host
+#[derive(Debug, Clone)]
+pub struct Flow {
+    pub protocol: String,
+    /// TCP state, if any
+    pub state: Option<String>,
+    pub direction: FlowDirection,
+    pub local_addr: IpAddr,
+    pub local_port: Option<u16>,
+    pub remote_addr: IpAddr,
+    pub remote_port: Option<u16>,
+    pub pid: Option<u32>,
+    pub process: Option<String>,
+    pub tx_bytes: u64,
+    pub rx_bytes: u64,
+    /// Bytes per second since the previous sample
+    pub tx_rate: f64,
+    pub rx_rate: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlowDirection {
+    Outbound,
+    Inbound,
+    /// Routed through this host
+    Forwarded,
+}
+
+/// Traffic of one process across its flows
+#[derive(Debug, Clone)]
+pub struct AppUsage {
+    pub process: String,
+    pub pids: BTreeSet<u32>,
+    pub flows: usize,
+    pub tx_bytes: u64,
+    pub rx_bytes: u64,
+    pub tx_rate: f64,
+    pub rx_rate: f64,
+}
+
+/// Traffic with one remote address
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    pub address: IpAddr,
+    pub scope: &'static str,
+    pub flows: usize,
+    pub ports: BTreeSet<u16>,
+    pub processes: BTreeSet<String>,
+    pub tx_bytes: u64,
+    pub rx_bytes: u64,
+    pub tx_rate: f64,
+    pub rx_rate: f64,
+}
+
+/// One conntrack entry, original and reply tuples
+#[derive(Debug, Clone)]
+struct Entry {
+    protocol: String,
+    state: Option<String>,
+    src: IpAddr,
+    dst: IpAddr,
+    sport: Option<u16>,
+    dport: Option<u16>,
+    orig_bytes: u64,
+    reply_bytes: u64,
+}
+
+impl Entry {
+    fn key(&self) -> FlowKey {
+        (self.protocol.clone(), self.src, self.sport, self.dst, self.dport)
+    }
+}
+
+type FlowKey = (String, IpAddr, Option<u16>, IpAddr, Option<u16>);
+
+/// Turns conntrack samples into flows with rates
+pub struct FlowTracker {
+    previous: HashMap<FlowKey, (u64, u64)>,
+    last_sample: Option<Instant>,
+}
+
+impl FlowTracker {
+    pub fn new() -> Self {
+        Self { previous: HashMap::new(), last_sample: None }
+    }
+
+    /// Read conntrack and build flows, attributing them via `connections`
+    pub fn sample(&mut self, local: &HashSet<IpAddr>, connections: &[Connection]) -> Result<Vec<Flow>> {
+        let entries = read_entries()?;
+        let now = Instant::now();
+        let elapsed = self
+            .last_sample
+            .map(|t| now.duration_since(t).as_secs_f64())
+            .filter(|e| *e > 0.0);
+
+        let owners = socket_owners(connections);
+        let mut previous = HashMap::with_capacity(entries.len());
+        let mut flows = Vec::with_capacity(entries.len());
+
+        for entry in entries {
+            let key = entry.key();
+            let (orig_rate, reply_rate) = match (elapsed, self.previous.get(&key)) {
+                (Some(elapsed), Some(&(orig, reply))) => (
+                    entry.orig_bytes.saturating_sub(orig) as f64 / elapsed,
+                    entry.reply_bytes.saturating_sub(reply) as f64 / elapsed,
+                ),
+                _ => (0.0, 0.0),
+            };
+            previous.insert(key, (entry.orig_bytes, entry.reply_bytes));
+
+            let direction = if local.contains(&entry.src) {
+                FlowDirection::Outbound
+            } else if local.contains(&entry.dst) {
+                FlowDirection::Inbound
+            } else {
+                FlowDirection::Forwarded
+            };
+
+            // Outbound and forwarded flows read as initiator -> peer
+            let mut flow = match direction {
+                FlowDirection::Inbound => Flow {
+                    protocol: entry.protocol,
+                    state: entry.state,
+                    direction,
+                    local_addr: entry.dst,
+                    local_port: entry.dport,
+                    remote_addr: entry.src,
+                    remote_port: entry.sport,
+                    pid: None,
+                    process: None,
+                    tx_bytes: entry.reply_bytes,
+                    rx_bytes: entry.orig_bytes,
+                    tx_rate: reply_rate,
+                    rx_rate: orig_rate,
+                },
+                _ => Flow {
+                    protocol: entry.protocol,
+                    state: entry.state,
+                    direction,
+                    local_addr: entry.src,
+                    local_port: entry.sport,
+                    remote_addr: entry.dst,
+                    remote_port: entry.dport,
+                    pid: None,
+                    process: None,
+                    tx_bytes: entry.orig_bytes,
+                    rx_bytes: entry.reply_bytes,
+                    tx_rate: orig_rate,
+                    rx_rate: reply_rate,
+                },
+            };
+
+            if direction != FlowDirection::Forwarded {
+                if let Some(port) = flow.local_port {
+                    let owner = owners
+                        .get(&(flow.protocol.clone(), Some(flow.local_addr), port))
+                        .or_else(|| owners.get(&(flow.protocol.clone(), None, port)));
+                    if let Some((pid, name)) = owner {
+                        flow.pid = Some(*pid);
+                        flow.process = Some(name.clone());
+                    }
+                }
+            }
+
+            flows.push(flow);
+        }
+
+        self.previous = previous;
+        self.last_sample = Some(now);
+        Ok(flows)
+    }
+}
+
+/// Sum flows per process; forwarded and unattributed flows are grouped
+pub fn app_usage(flows: &[Flow]) -> Vec<AppUsage> {
+    let mut apps: HashMap<String, AppUsage> = HashMap::new();
+    for flow in flows {
+        let process = match (flow.direction, &flow.process) {
+            (FlowDirection::Forwarded, _) => "(forwarded)".to_string(),
+            (_, Some(process)) => process.clone(),
+            (_, None) => "(unknown)".to_string(),
+        };
+        let app = apps.entry(process.clone()).or_insert_with(|| AppUsage {
+            process,
+            pids: BTreeSet::new(),
+            flows: 0,
+            tx_bytes: 0,
+            rx_bytes: 0,
+            tx_rate: 0.0,
+            rx_rate: 0.0,
+        });
+        app.pids.extend(flow.pid);
+        app.flows += 1;
+        app.tx_bytes += flow.tx_bytes;
+        app.rx_bytes += flow.rx_bytes;
+        app.tx_rate += flow.tx_rate;
+        app.rx_rate += flow.rx_rate;
+    }
+
+    let mut apps: Vec<_> = apps.into_values().collect();
+    apps.sort_by(|a, b| (b.tx_rate + b.rx_rate).total_cmp(&(a.tx_rate + a.rx_rate)).then(b.flows.cmp(&a.flows)));
+    apps
+}
+
+/// Sum flows per remote address, busiest first
+pub fn endpoints(flows: &[Flow]) -> Vec<Endpoint> {
+    let mut endpoints: HashMap<IpAddr, Endpoint> = HashMap::new();
+    for flow in flows.iter().filter(|f| !f.remote_addr.is_loopback()) {
+        let endpoint = endpoints.entry(flow.remote_addr).or_insert_with(|| Endpoint {
+            address: flow.remote_addr,
+            scope: scope(&flow.remote_addr),
+            flows: 0,
+            ports: BTreeSet::new(),
+            processes: BTreeSet::new(),
+            tx_bytes: 0,
+            rx_bytes: 0,
+            tx_rate: 0.0,
+            rx_rate: 0.0,
+        });
+        endpoint.flows += 1;
+        endpoint.ports.extend(flow.remote_port);
+        endpoint.processes.extend(flow.process.clone());
+        endpoint.tx_bytes += flow.tx_bytes;
+        endpoint.rx_bytes += flow.rx_bytes;
+        endpoint.tx_rate += flow.tx_rate;
+        endpoint.rx_rate += flow.rx_rate;
+    }
+
+    let mut endpoints: Vec<_> = endpoints.into_values().collect();
+    endpoints.sort_by(|a, b| {
+        (b.tx_rate + b.rx_rate)
+            .total_cmp(&(a.tx_rate + a.rx_rate))
+            .then((b.tx_bytes + b.rx_bytes).cmp(&(a.tx_bytes + a.rx_bytes)))
+    });
+    endpoints
+}
+
+/// Coarse address classification in place of geolocation
+pub fn scope(addr: &IpAddr) -> &'static str {
+    match addr {
+        IpAddr::V4(v4) if v4.is_loopback() => "loopback",
+        IpAddr::V4(v4) if v4.is_private() => "private",
+        IpAddr::V4(v4) if v4.is_link_local() => "link-local",
+        // 100.64.0.0/10
+        IpAddr::V4(v4) if v4.octets()[0] == 100 && (v4.octets()[1] & 0xC0) == 64 => "cgnat",
+        IpAddr::V4(v4) if v4.is_multicast() || v4.is_broadcast() => "multicast",
+        IpAddr::V6(v6) if v6.is_loopback() => "loopback",
+        IpAddr::V6(v6) if (v6.segments()[0] & 0xFE00) == 0xFC00 => "private",
+        IpAddr::V6(v6) if (v6.segments()[0] & 0xFFC0) == 0xFE80 => "link-local",
+        IpAddr::V6(v6) if v6.is_multicast() => "multicast",
+        _ => "public",
+    }
+}
+
+/// Map (protocol, local address or wildcard, port) to the owning process
+fn socket_owners(connections: &[Connection]) -> HashMap<(String, Option<IpAddr>, u16), (u32, String)> {
+    let mut owners = HashMap::new();
+    for conn in connections {
+        let (Some(pid), Some(name)) = (conn.pid, &conn.process_name) else { continue };
+        let protocol = match conn.protocol {
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+            _ => continue,
+        };
+        let addr = (!conn.local_addr.is_unspecified()).then_some(conn.local_addr);
+        owners.insert((protocol.to_string(), addr, conn.local_port), (pid, name.clone()));
+    }
+    owners
+}
+
+fn read_entries() -> Result<Vec<Entry>> {
+    let text = match std::fs::read_to_string("/proc/net/nf_conntrack") {
+        Ok(text) => text,
+        Err(_) => {
+            let output = Command::new("conntrack")
+                .args(["-L", "-o", "extended"])
+                .output()
+                .map_err(|e| anyhow!("conntrack unavailable: {}", e))?;
+            if !output.status.success() {
+                return Err(anyhow!("conntrack failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()));
+            }
+            String::from_utf8_lossy(&output.stdout).to_string()
+        }
+    };
+
+    Ok(text.lines().filter_map(parse_entry).collect())
+}
+
+/// `ipv4 2 tcp 6 431999 ESTABLISHED src=.. dst=.. sport=.. dport=..
+/// packets=.. bytes=.. src=.. dst=.. sport=.. dport=.. packets=.. bytes=.. [ASSURED] ...`
+fn parse_entry(line: &str) -> Option<Entry> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let protocol = fields.get(2)?.to_string();
+
+    let state = fields
+        .get(5)
+        .filter(|f| !f.contains('=') && !f.starts_with('['))
+        .map(|f| f.to_string());
+
+    let mut src = None;
+    let mut dst = None;
+    let mut sport = None;
+    let mut dport = None;
+    let mut bytes = Vec::new();
+    let mut tuple = 0;
+
+    for field in &fields[3..] {
+        let Some((key, value)) = field.split_once('=') else { continue };
+        match key {
+            // A second `src=` starts the reply tuple
+            "src" => {
+                tuple += 1;
+                if tuple == 1 {
+                    src = value.parse().ok();
+                }
+            }
+            "dst" if tuple == 1 => dst = value.parse().ok(),
+            "sport" if tuple == 1 => sport = value.parse().ok(),
+            "dport" if tuple == 1 => dport = value.parse().ok(),
+            "bytes" => bytes.push(value.parse().unwrap_or(0)),
+            _ => {}
+        }
+    }
+
+    Some(Entry {
+        protocol,
+        state,
+        src: src?,
+        dst: dst?,
+        sport,
+        dport,
+        orig_bytes: bytes.first().copied().unwrap_or(0),
+        reply_bytes: bytes.get(1).copied().unwrap_or(0),
+    })
+}
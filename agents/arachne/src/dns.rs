@@ -1,9 +1,21 @@
-//! DNS resolution and caching
-
-use crate::config::DnsConfig;
+//! DNS resolution, caching and filtering
+//!
+//! Queries are checked against the policy of the client asking: the first
+//! client policy matching its source address or local user, otherwise the
+//! default policy. Policies pick which blocklist categories apply and can
+//! allow or block domains of their own. With `server_enabled` the resolver
+//! also answers on UDP, forwarding allowed queries upstream and answering
+//! blocked ones with NXDOMAIN.
+
+use crate::blocklist::{self, Blocklists, ListStatus};
+use crate::config::{BlocklistSubscription, ClientDetail, ClientPolicy, DnsConfig, DnsPolicy};
 use anyhow::{anyhow, Result};
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use ipnetwork::IpNetwork;
+use std::collections::{HashMap, VecDeque};
 use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
@@ -13,6 +25,38 @@ pub struct DnsResolver {
     config: DnsConfig,
     cache: Arc<RwLock<DnsCache>>,
     blocklist: Arc<RwLock<Vec<String>>>,
+    lists: RwLock<Blocklists>,
+    clients: RwLock<Vec<ClientPolicy>>,
+    query_log: RwLock<VecDeque<QueryLogEntry>>,
+    queries: AtomicU64,
+    blocked: AtomicU64,
+    http: reqwest::Client,
+}
+
+/// Who is asking
+#[derive(Debug, Clone, Default)]
+pub struct DnsClient {
+    pub addr: Option<IpAddr>,
+    pub user: Option<String>,
+}
+
+/// Outcome of filtering a query
+#[derive(Debug, Clone)]
+pub struct Verdict {
+    /// Policy that decided: a client policy's name or `default`
+    pub policy: String,
+    /// Why it was blocked; `None` if allowed
+    pub blocked: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryLogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub name: String,
+    /// Client as far as `client_detail` allows
+    pub client: Option<String>,
+    pub policy: String,
+    pub blocked: Option<String>,
 }
 
 struct DnsCache {
@@ -56,16 +100,21 @@ impl DnsResolver {
                 max_size: config.cache_size,
             })),
             blocklist: Arc::new(RwLock::new(config.blocklist.clone())),
+            lists: RwLock::new(Blocklists::load(Path::new(&config.blocklist_dir), &config.subscriptions)),
+            clients: RwLock::new(config.clients.clone()),
+            query_log: RwLock::new(VecDeque::new()),
+            queries: AtomicU64::new(0),
+            blocked: AtomicU64::new(0),
+            http: reqwest::Client::new(),
             config,
         }
     }
 
-    /// Resolve a hostname to IP addresses
-    pub async fn resolve(&self, hostname: &str) -> Result<Vec<IpAddr>> {
-        // Check blocklist
-        if self.is_blocked(hostname).await {
-            tracing::warn!("Blocked DNS query for: {}", hostname);
-            return Err(anyhow!("Domain blocked: {}", hostname));
+    /// Resolve a hostname to IP addresses under the policy for `client`
+    pub async fn resolve(&self, hostname: &str, client: &DnsClient) -> Result<Vec<IpAddr>> {
+        if let Some(reason) = self.filter(hostname, client).await.blocked {
+            tracing::debug!("Blocked DNS query for {}: {}", hostname, reason);
+            return Err(anyhow!("Domain blocked: {} ({})", hostname, reason));
         }
 
         // Check cache
@@ -175,6 +224,222 @@ impl DnsResolver {
         parse_dns_response(&buf)
     }
 
+    /// Decide a query without counting or logging it
+    pub async fn check(&self, hostname: &str, client: &DnsClient) -> Verdict {
+        let name = blocklist::normalize(hostname).unwrap_or_else(|| hostname.to_ascii_lowercase());
+        let clients = self.clients.read().await;
+        let (policy_name, policy) = match clients.iter().find(|c| client_matches(c, client)) {
+            Some(c) => (c.name.clone(), &c.policy),
+            None => ("default".to_string(), &self.config.default_policy),
+        };
+        let verdict = |blocked: Option<String>| Verdict { policy: policy_name.clone(), blocked };
+
+        if policy.bypass || domain_listed(&policy.allow, &name) {
+            return verdict(None);
+        }
+        if domain_listed(&policy.block, &name) {
+            return verdict(Some(format!("policy {}", policy_name)));
+        }
+        if self.is_blocked(&name).await {
+            return verdict(Some("blocklist".to_string()));
+        }
+
+        let lists = self.lists.read().await;
+        let hit = lists.lookup(&name, |category| category_blocked(policy, category));
+        verdict(hit.map(|hit| format!("{} ({})", hit.category, hit.list)))
+    }
+
+    /// Decide a query, counting and logging it
+    async fn filter(&self, hostname: &str, client: &DnsClient) -> Verdict {
+        let verdict = self.check(hostname, client).await;
+
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        if verdict.blocked.is_some() {
+            self.blocked.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let log = &self.config.query_log;
+        if log.enabled && (log.log_allowed || verdict.blocked.is_some()) {
+            let mut entries = self.query_log.write().await;
+            while entries.len() >= log.max_entries.max(1) {
+                entries.pop_front();
+            }
+            entries.push_back(QueryLogEntry {
+                timestamp: Utc::now(),
+                name: hostname.to_string(),
+                client: describe_client(client, log.client_detail),
+                policy: verdict.policy.clone(),
+                blocked: verdict.blocked.clone(),
+            });
+        }
+
+        verdict
+    }
+
+    /// Most recent query log entries, newest first
+    pub async fn query_log(&self, limit: usize, blocked_only: bool) -> Vec<QueryLogEntry> {
+        self.query_log
+            .read()
+            .await
+            .iter()
+            .rev()
+            .filter(|e| !blocked_only || e.blocked.is_some())
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub async fn clear_query_log(&self) {
+        self.query_log.write().await.clear();
+    }
+
+    /// Add a blocklist subscription and fetch it
+    pub async fn subscribe(&self, subscription: BlocklistSubscription) -> Result<()> {
+        let name = subscription.name.clone();
+        self.lists.write().await.add(subscription)?;
+        self.refresh_lists(Some(&name)).await?;
+        Ok(())
+    }
+
+    pub async fn unsubscribe(&self, name: &str) -> bool {
+        self.lists.write().await.remove(name)
+    }
+
+    /// Fetch subscriptions again (all, or the one named); returns how many
+    /// were fetched successfully
+    pub async fn refresh_lists(&self, name: Option<&str>) -> Result<usize> {
+        let subscriptions: Vec<_> = self
+            .lists
+            .read()
+            .await
+            .subscriptions()
+            .into_iter()
+            .filter(|s| name.is_none_or(|n| s.name == n))
+            .collect();
+        if let (Some(name), true) = (name, subscriptions.is_empty()) {
+            return Err(anyhow!("No blocklist subscription named {}", name));
+        }
+
+        let mut fetched = 0;
+        for subscription in subscriptions {
+            // Fetch without holding the lock so lookups carry on
+            let result = blocklist::fetch(&self.http, &subscription).await;
+            fetched += result.is_ok() as usize;
+            self.lists.write().await.install(&subscription.name, result);
+        }
+        Ok(fetched)
+    }
+
+    /// Keep subscriptions fresh
+    pub async fn refresh_loop(&self) {
+        let period = Duration::from_secs(self.config.refresh_hours.max(1) * 3600);
+        let stale = chrono::Duration::from_std(period).unwrap_or(chrono::Duration::days(1));
+
+        // Lists cached recently enough wait for the next round
+        let due = self.lists.read().await.status().into_iter().any(|l| {
+            l.updated.is_none_or(|updated| Utc::now() - updated > stale)
+        });
+        if due {
+            let _ = self.refresh_lists(None).await;
+        }
+
+        let mut interval = tokio::time::interval(period);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let _ = self.refresh_lists(None).await;
+        }
+    }
+
+    pub async fn list_status(&self) -> Vec<ListStatus> {
+        self.lists.read().await.status()
+    }
+
+    pub async fn policies(&self) -> (DnsPolicy, Vec<ClientPolicy>) {
+        (self.config.default_policy.clone(), self.clients.read().await.clone())
+    }
+
+    /// Add or replace a client policy
+    pub async fn set_client_policy(&self, policy: ClientPolicy) -> Result<()> {
+        if policy.name == "default" {
+            return Err(anyhow!("\"default\" is reserved for the default policy"));
+        }
+        for source in &policy.sources {
+            source
+                .parse::<IpNetwork>()
+                .map_err(|_| anyhow!("Invalid source {:?}", source))?;
+        }
+
+        let mut clients = self.clients.write().await;
+        match clients.iter_mut().find(|c| c.name == policy.name) {
+            Some(existing) => *existing = policy,
+            None => clients.push(policy),
+        }
+        Ok(())
+    }
+
+    pub async fn remove_client_policy(&self, name: &str) -> bool {
+        let mut clients = self.clients.write().await;
+        let before = clients.len();
+        clients.retain(|c| c.name != name);
+        clients.len() != before
+    }
+
+    /// Answer DNS on UDP, filtering per client and forwarding upstream
+    pub async fn serve(self: Arc<Self>) -> Result<()> {
+        let socket = Arc::new(tokio::net::UdpSocket::bind(("0.0.0.0", self.config.port)).await?);
+        tracing::info!("DNS server listening on port {}", self.config.port);
+
+        let mut buf = vec![0u8; 4096];
+        loop {
+            let (len, peer) = match socket.recv_from(&mut buf).await {
+                Ok(received) => received,
+                Err(e) => {
+                    tracing::warn!("DNS receive failed: {}", e);
+                    continue;
+                }
+            };
+            let query = buf[..len].to_vec();
+            let resolver = Arc::clone(&self);
+            let socket = Arc::clone(&socket);
+
+            tokio::spawn(async move {
+                match resolver.answer(&query, peer).await {
+                    Ok(reply) => {
+                        let _ = socket.send_to(&reply, peer).await;
+                    }
+                    Err(e) => tracing::debug!("DNS query from {} failed: {}", peer, e),
+                }
+            });
+        }
+    }
+
+    async fn answer(&self, query: &[u8], peer: SocketAddr) -> Result<Vec<u8>> {
+        let name = parse_question(query).ok_or_else(|| anyhow!("Malformed query"))?;
+        let client = DnsClient {
+            addr: Some(peer.ip()),
+            user: peer
+                .ip()
+                .is_loopback()
+                .then(|| local_udp_uid(peer.port()))
+                .flatten()
+                .and_then(user_name),
+        };
+
+        if self.filter(&name, &client).await.blocked.is_some() {
+            return nxdomain(query);
+        }
+
+        let mut last_error = anyhow!("No upstream DNS servers");
+        for upstream in &self.config.upstream {
+            match forward(upstream, query).await {
+                Ok(reply) => return Ok(reply),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
     async fn is_blocked(&self, hostname: &str) -> bool {
         let blocklist = self.blocklist.read().await;
 
@@ -264,6 +529,9 @@ impl DnsResolver {
             cache_max: cache.max_size,
             total_hits,
             blocklist_size: self.blocklist.read().await.len(),
+            subscribed_domains: self.lists.read().await.len(),
+            queries: self.queries.load(Ordering::Relaxed),
+            queries_blocked: self.blocked.load(Ordering::Relaxed),
         }
     }
 }
@@ -274,6 +542,122 @@ pub struct DnsStats {
     pub cache_max: usize,
     pub total_hits: u64,
     pub blocklist_size: usize,
+    pub subscribed_domains: usize,
+    pub queries: u64,
+    pub queries_blocked: u64,
+}
+
+fn client_matches(policy: &ClientPolicy, client: &DnsClient) -> bool {
+    let by_source = client.addr.is_some_and(|addr| {
+        policy
+            .sources
+            .iter()
+            .filter_map(|s| s.parse::<IpNetwork>().ok())
+            .any(|net| net.contains(addr))
+    });
+    let by_user = client
+        .user
+        .as_ref()
+        .is_some_and(|user| policy.users.contains(user));
+    by_source || by_user
+}
+
+fn category_blocked(policy: &DnsPolicy, category: &str) -> bool {
+    policy
+        .block_categories
+        .as_ref()
+        .is_none_or(|categories| categories.iter().any(|c| c == category))
+}
+
+/// Whether `name` or a parent domain is in `domains`
+fn domain_listed(domains: &[String], name: &str) -> bool {
+    blocklist::suffixes(name).any(|s| domains.iter().any(|d| d.eq_ignore_ascii_case(s)))
+}
+
+fn describe_client(client: &DnsClient, detail: ClientDetail) -> Option<String> {
+    match detail {
+        ClientDetail::None => None,
+        ClientDetail::Truncated => client.addr.map(|addr| {
+            let prefix = if addr.is_ipv4() { 24 } else { 48 };
+            IpNetwork::new(addr, prefix)
+                .map(|net| format!("{}/{}", net.network(), prefix))
+                .unwrap_or_default()
+        }),
+        ClientDetail::Full => match (&client.user, client.addr) {
+            (Some(user), Some(addr)) => Some(format!("{}@{}", user, addr)),
+            (Some(user), None) => Some(user.clone()),
+            (None, Some(addr)) => Some(addr.to_string()),
+            (None, None) => None,
+        },
+    }
+}
+
+/// Owner of a local UDP socket bound to `port`, from /proc/net/udp{,6}
+fn local_udp_uid(port: u16) -> Option<u32> {
+    let port = format!("{:04X}", port);
+    ["/proc/net/udp", "/proc/net/udp6"].iter().find_map(|path| {
+        let table = std::fs::read_to_string(path).ok()?;
+        table.lines().skip(1).find_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let local_port = fields.get(1)?.rsplit(':').next()?;
+            (local_port == port).then(|| fields.get(7)?.parse().ok()).flatten()
+        })
+    })
+}
+
+/// Look a uid up in /etc/passwd
+pub fn user_name(uid: u32) -> Option<String> {
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        let name = fields.next()?;
+        let id: u32 = fields.nth(1)?.parse().ok()?;
+        (id == uid).then(|| name.to_string())
+    })
+}
+
+/// Name asked about in a query packet
+fn parse_question(packet: &[u8]) -> Option<String> {
+    if packet.len() < 12 || u16::from_be_bytes([packet[4], packet[5]]) == 0 {
+        return None;
+    }
+    let (name, _) = parse_dns_name(packet, 12).ok()?;
+    (!name.is_empty()).then_some(name)
+}
+
+/// NXDOMAIN reply to `query`, echoing its question
+fn nxdomain(query: &[u8]) -> Result<Vec<u8>> {
+    let mut pos = 12;
+    while pos < query.len() && query[pos] != 0 {
+        pos += query[pos] as usize + 1;
+    }
+    let end = pos + 5; // terminator, type, class
+    if end > query.len() {
+        return Err(anyhow!("Malformed query"));
+    }
+
+    let mut reply = query[..end].to_vec();
+    // QR, keep opcode and RD, set RA, RCODE 3
+    reply[2] = 0x80 | (query[2] & 0x79);
+    reply[3] = 0x80 | 0x03;
+    // One question, no other records
+    reply[4..12].copy_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+    Ok(reply)
+}
+
+/// Relay a raw query to an upstream server
+async fn forward(server: &str, query: &[u8]) -> Result<Vec<u8>> {
+    let server_addr: SocketAddr = format!("{}:53", server).parse()?;
+    let bind = if server_addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+    let socket = tokio::net::UdpSocket::bind(bind).await?;
+    socket.send_to(query, server_addr).await?;
+
+    let mut buf = vec![0u8; 4096];
+    let (len, _) = tokio::time::timeout(Duration::from_secs(5), socket.recv_from(&mut buf))
+        .await
+        .map_err(|_| anyhow!("Upstream {} timed out", server))??;
+    buf.truncate(len);
+    Ok(buf)
 }
 
 /// Build a DNS query packet
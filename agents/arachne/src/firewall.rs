@@ -5,22 +5,66 @@
 //! - iptables (legacy fallback)
 //! - Windows Firewall via PowerShell (WSL)
 //! - Logging-only mode (containers/restricted)
-
-use crate::config::{Action, DefaultPolicy, Direction, FirewallConfig, FirewallRule};
+//!
+//! With nftables every change recompiles the full ruleset and swaps it in
+//! atomically. The new ruleset is verified and a connectivity self-check
+//! run; if either fails, or a change needing confirmation is not confirmed
+//! in time, the previous ruleset is restored.
+
+use crate::apps::{self, Resolved};
+use crate::config::{Action, ApplyConfig, DefaultPolicy, Direction, FirewallConfig, FirewallRule};
+use crate::nftables::{self, Ruleset};
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
 use libnyx_platform::{Platform, PlatformCapabilities, compat::FirewallBackend};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::process::Command;
-use tokio::sync::RwLock;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
 
 /// Firewall manager with platform-aware backend
 pub struct Firewall {
     config: RwLock<FirewallConfig>,
+    /// Rules added over IPC, kept across reloads; shared with rollback
+    /// timers
+    runtime_rules: Arc<RwLock<Vec<FirewallRule>>>,
+    /// Cgroups app rules were last compiled against
+    resolved: RwLock<Resolved>,
     active_rules: RwLock<HashMap<String, ActiveRule>>,
+    /// Serializes ruleset changes; shared with rollback timers
+    applied: Arc<Mutex<Applied>>,
     backend: FirewallBackend,
     platform: Platform,
 }
 
+#[derive(Default)]
+struct Applied {
+    generation: u64,
+    /// Runtime rules of the live table
+    rules: Vec<FirewallRule>,
+    pending: Option<Pending>,
+}
+
+/// An applied change awaiting confirmation
+struct Pending {
+    generation: u64,
+    /// Last confirmed table (`None` if there was none)
+    previous: Option<String>,
+    /// Runtime rules of the last confirmed table
+    previous_rules: Vec<FirewallRule>,
+    deadline: DateTime<Utc>,
+}
+
+/// Result of applying a ruleset
+#[derive(Debug, Clone)]
+pub struct ApplyOutcome {
+    pub generation: u64,
+    pub rules: usize,
+    /// Rolled back unless confirmed before this
+    pub confirm_by: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ActiveRule {
     pub name: String,
@@ -28,6 +72,27 @@ pub struct ActiveRule {
     pub chain: String,
     pub packets: u64,
     pub bytes: u64,
+    pub verdict: Option<&'static str>,
+}
+
+/// An app rule and what it currently applies to
+#[derive(Debug, Clone)]
+pub struct AppStatus {
+    pub name: String,
+    pub cgroup: Option<String>,
+    pub binary: Option<String>,
+    pub action: Action,
+    /// Cgroups matched at the last apply
+    pub cgroups: Vec<String>,
+    /// Exceptions first, the app's verdict last
+    pub rules: Vec<RuleCounters>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RuleCounters {
+    pub name: String,
+    pub packets: u64,
+    pub bytes: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -53,7 +118,10 @@ impl Firewall {
 
         Self {
             config: RwLock::new(config),
+            runtime_rules: Arc::new(RwLock::new(Vec::new())),
+            resolved: RwLock::new(Resolved::new()),
             active_rules: RwLock::new(HashMap::new()),
+            applied: Arc::new(Mutex::new(Applied::default())),
             backend,
             platform,
         }
@@ -80,7 +148,13 @@ impl Firewall {
 
         match self.backend {
             FirewallBackend::Nftables => {
-                self.setup_nftables().await?;
+                drop(config);
+                let outcome = self.apply().await?;
+                tracing::info!(
+                    "Firewall initialized with {} rules using nftables",
+                    outcome.rules
+                );
+                return Ok(());
             }
             FirewallBackend::Iptables => {
                 self.setup_iptables().await?;
@@ -112,25 +186,240 @@ impl Firewall {
         Ok(())
     }
 
-    /// Setup nftables backend
-    async fn setup_nftables(&self) -> Result<()> {
-        let commands = r#"
-            table inet nyx {
-                chain input {
-                    type filter hook input priority 0; policy drop;
-                    ct state established,related accept
-                    iif lo accept
-                }
-                chain output {
-                    type filter hook output priority 0; policy accept;
-                }
-                chain forward {
-                    type filter hook forward priority 0; policy drop;
+    /// Compile the current rules and swap them in atomically
+    ///
+    /// The ruleset is dry-run first, then applied in one transaction,
+    /// verified against the live table and self-checked. Failures restore
+    /// the previous table and return an error; the runtime rules are reset
+    /// to the ones of the table left in place.
+    pub async fn apply(&self) -> Result<ApplyOutcome> {
+        if !matches!(self.backend, FirewallBackend::Nftables) {
+            return Err(anyhow!("atomic apply requires nftables (backend: {:?})", self.backend));
+        }
+
+        let config = self.config.read().await.clone();
+        let resolved = apps::resolve(&config.apps);
+
+        let mut state = self.applied.lock().await;
+        let rules = self.runtime_rules.read().await.clone();
+        let checked = nftables::compile(&config, &rules, &resolved).and_then(|ruleset| {
+            nftables::nft(&["-c", "-f", "-"], Some(&ruleset.script()))?;
+            Ok(ruleset)
+        });
+        let ruleset = match checked {
+            Ok(ruleset) => ruleset,
+            Err(e) => {
+                *self.runtime_rules.write().await = state.rules.clone();
+                return Err(e);
+            }
+        };
+        let script = ruleset.script();
+
+        // While a change is unconfirmed, failures fall back past it
+        let (previous, previous_rules) = match &state.pending {
+            Some(pending) => (pending.previous.clone(), pending.previous_rules.clone()),
+            None => (nftables::snapshot(), state.rules.clone()),
+        };
+
+        nftables::nft(&["-f", "-"], Some(&script))?;
+
+        let checked = match verify(&ruleset) {
+            Ok(()) => self_check(&config.apply).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = checked {
+            state.pending = None;
+            restore(previous.as_deref())?;
+            *self.runtime_rules.write().await = previous_rules.clone();
+            state.rules = previous_rules;
+            tracing::warn!("Firewall change rolled back: {}", e);
+            return Err(anyhow!("ruleset rolled back: {}", e));
+        }
+
+        state.generation += 1;
+        state.rules = rules;
+        let generation = state.generation;
+        *self.resolved.write().await = resolved;
+        let confirm_by = if config.apply.confirm_timeout_secs > 0 {
+            let timeout = Duration::from_secs(config.apply.confirm_timeout_secs);
+            let deadline = Utc::now() + chrono::Duration::seconds(timeout.as_secs() as i64);
+            state.pending = Some(Pending {
+                generation,
+                previous,
+                previous_rules,
+                deadline,
+            });
+            self.arm_rollback(generation, timeout);
+            Some(deadline)
+        } else {
+            state.pending = None;
+            None
+        };
+        drop(state);
+
+        if let Err(e) = self.refresh_rules().await {
+            tracing::debug!("Failed to read back rules: {}", e);
+        }
+
+        tracing::info!(
+            "Applied firewall ruleset #{} ({} rules{})",
+            generation,
+            ruleset.comments.len(),
+            if confirm_by.is_some() { ", awaiting confirmation" } else { "" }
+        );
+        Ok(ApplyOutcome { generation, rules: ruleset.comments.len(), confirm_by })
+    }
+
+    /// Restore the previous ruleset if `generation` is still unconfirmed
+    /// once `timeout` passes
+    fn arm_rollback(&self, generation: u64, timeout: Duration) {
+        let applied = Arc::clone(&self.applied);
+        let runtime_rules = Arc::clone(&self.runtime_rules);
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+
+            let mut state = applied.lock().await;
+            if state.pending.as_ref().map(|p| p.generation) != Some(generation) {
+                return;
+            }
+            if let Some(pending) = state.pending.take() {
+                match restore(pending.previous.as_deref()) {
+                    Ok(()) => {
+                        *runtime_rules.write().await = pending.previous_rules.clone();
+                        state.rules = pending.previous_rules;
+                        tracing::warn!("Firewall ruleset #{} not confirmed, rolled back", generation);
+                    }
+                    Err(e) => tracing::error!("Firewall rollback failed: {}", e),
                 }
             }
-        "#;
+        });
+    }
+
+    /// Keep the pending change
+    pub async fn confirm(&self) -> Result<u64> {
+        let mut state = self.applied.lock().await;
+        let pending = state
+            .pending
+            .take()
+            .ok_or_else(|| anyhow!("no firewall change awaiting confirmation"))?;
+
+        tracing::info!("Firewall ruleset #{} confirmed", pending.generation);
+        Ok(pending.generation)
+    }
+
+    /// Drop the pending change and restore the last confirmed ruleset
+    pub async fn rollback(&self) -> Result<u64> {
+        let mut state = self.applied.lock().await;
+        let pending = state
+            .pending
+            .take()
+            .ok_or_else(|| anyhow!("no firewall change awaiting confirmation"))?;
+
+        restore(pending.previous.as_deref())?;
+        *self.runtime_rules.write().await = pending.previous_rules.clone();
+        state.rules = pending.previous_rules;
+        drop(state);
+        let _ = self.refresh_rules().await;
+
+        tracing::info!("Firewall ruleset #{} rolled back", pending.generation);
+        Ok(pending.generation)
+    }
+
+    /// Deadline of the change awaiting confirmation, if any
+    pub async fn pending_confirmation(&self) -> Option<(u64, DateTime<Utc>)> {
+        let state = self.applied.lock().await;
+        state.pending.as_ref().map(|p| (p.generation, p.deadline))
+    }
+
+    /// The ruleset the current rules compile to
+    pub async fn preview(&self) -> Result<String> {
+        let config = self.config.read().await;
+        let resolved = apps::resolve(&config.apps);
+        Ok(nftables::compile(&config, &self.runtime_rules.read().await, &resolved)?.table)
+    }
+
+    /// Re-apply when binaries named by app rules start, stop or move
+    /// between cgroups
+    pub async fn watch_apps(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(apps::RESCAN_SECS));
+        loop {
+            interval.tick().await;
+
+            let config = self.config.read().await;
+            if !config.enabled || !config.apps.iter().any(|a| a.cgroup.is_none()) {
+                continue;
+            }
+            let resolved = apps::resolve(&config.apps);
+            drop(config);
+
+            // Leave an unconfirmed change alone; it settles first
+            if resolved == *self.resolved.read().await || self.pending_confirmation().await.is_some() {
+                continue;
+            }
+            tracing::info!("App cgroups changed, re-applying firewall");
+            if let Err(e) = self.apply().await {
+                tracing::warn!("Failed to re-apply firewall for app changes: {}", e);
+            }
+        }
+    }
+
+    /// Per-app cgroups and counters from the live table
+    pub async fn app_status(&self) -> Result<Vec<AppStatus>> {
+        let config = self.config.read().await;
+        let resolved = self.resolved.read().await;
+        let live = if matches!(self.backend, FirewallBackend::Nftables) {
+            nftables::live_rules()?
+        } else {
+            Vec::new()
+        };
+
+        let mut status = Vec::new();
+        for app in &config.apps {
+            let rules = nftables::app_comments(app)
+                .into_iter()
+                .map(|comment| {
+                    let (packets, bytes) = live
+                        .iter()
+                        .filter(|r| r.comment.as_deref() == Some(comment.as_str()))
+                        .fold((0, 0), |(p, b), r| (p + r.packets, b + r.bytes));
+                    RuleCounters { name: comment, packets, bytes }
+                })
+                .collect();
+
+            status.push(AppStatus {
+                name: app.name.clone(),
+                cgroup: app.cgroup.clone(),
+                binary: app.binary.clone(),
+                action: app.action,
+                cgroups: resolved.get(&app.name).cloned().unwrap_or_default(),
+                rules,
+            });
+        }
+        Ok(status)
+    }
+
+    /// Re-read rule handles and counters from the live table
+    async fn refresh_rules(&self) -> Result<()> {
+        if !matches!(self.backend, FirewallBackend::Nftables) {
+            return Ok(());
+        }
 
-        self.nft_command(&["-f", "-"], Some(commands)).await?;
+        let rules = nftables::live_rules()?
+            .into_iter()
+            .filter_map(|rule| {
+                let name = rule.comment?;
+                Some((name.clone(), ActiveRule {
+                    name,
+                    handle: rule.handle,
+                    chain: rule.chain,
+                    packets: rule.packets,
+                    bytes: rule.bytes,
+                    verdict: rule.verdict,
+                }))
+            })
+            .collect();
+
+        *self.active_rules.write().await = rules;
         Ok(())
     }
 
@@ -188,10 +477,10 @@ impl Firewall {
 
         match self.backend {
             FirewallBackend::Nftables => {
-                self.nft_command(
+                nftables::nft(
                     &["chain", "inet", "nyx", "input", &format!("{{ policy {} }}", policy_str)],
                     None
-                ).await?;
+                )?;
             }
             FirewallBackend::Iptables => {
                 let target = if policy_str == "accept" { "ACCEPT" } else { "DROP" };
@@ -218,6 +507,21 @@ impl Firewall {
 
     /// Add a firewall rule
     pub async fn add_rule(&self, rule: &FirewallRule) -> Result<()> {
+        if matches!(self.backend, FirewallBackend::Nftables) {
+            nftables::validate(rule)?;
+            if self.config.read().await.rules.iter().any(|r| r.name == rule.name) {
+                return Err(anyhow!("rule {} is defined in the configuration", rule.name));
+            }
+
+            {
+                let mut runtime = self.runtime_rules.write().await;
+                runtime.retain(|r| r.name != rule.name);
+                runtime.push(rule.clone());
+            }
+            // A failed apply resets the runtime rules
+            return self.apply().await.map(|_| ());
+        }
+
         let chain = match rule.direction {
             Direction::In => "input",
             Direction::Out => "output",
@@ -272,7 +576,7 @@ impl Firewall {
         // Comment
         nft_rule.push_str(&format!(" comment \"{}\"", rule.name));
 
-        self.nft_command(&["add", "rule", "inet", "nyx", chain, &nft_rule], None).await?;
+        nftables::nft(&["add", "rule", "inet", "nyx", chain, &nft_rule], None)?;
 
         tracing::debug!("Added rule {} to chain {}", rule.name, chain);
         Ok(())
@@ -280,13 +584,28 @@ impl Firewall {
 
     /// Remove a firewall rule by name
     pub async fn remove_rule(&self, name: &str) -> Result<()> {
+        if matches!(self.backend, FirewallBackend::Nftables) {
+            {
+                let mut runtime = self.runtime_rules.write().await;
+                let before = runtime.len();
+                runtime.retain(|r| r.name != name);
+                if runtime.len() == before {
+                    return Ok(());
+                }
+            }
+            // A failed apply resets the runtime rules
+            self.apply().await?;
+            tracing::info!("Removed rule: {}", name);
+            return Ok(());
+        }
+
         let rules = self.active_rules.read().await;
 
         if let Some(rule) = rules.get(name) {
-            self.nft_command(&[
+            nftables::nft(&[
                 "delete", "rule", "inet", "nyx", &rule.chain,
                 "handle", &rule.handle.to_string()
-            ], None).await?;
+            ], None)?;
 
             drop(rules);
             self.active_rules.write().await.remove(name);
@@ -307,6 +626,7 @@ impl Firewall {
             port: None,
             source: Some(ip.to_string()),
             destination: None,
+            ct_state: Vec::new(),
         };
 
         self.add_rule(&rule).await?;
@@ -330,6 +650,7 @@ impl Firewall {
             port: Some(port),
             source: None,
             destination: None,
+            ct_state: Vec::new(),
         };
 
         self.add_rule(&rule).await
@@ -337,31 +658,50 @@ impl Firewall {
 
     /// Get firewall statistics
     pub async fn get_stats(&self) -> Result<FirewallStats> {
-        let output = self.nft_command(&["list", "table", "inet", "nyx", "-j"], None).await?;
+        self.refresh_rules().await?;
+        let rules = self.active_rules.read().await;
 
-        // Parse JSON output for statistics
-        let stats = FirewallStats {
+        let mut stats = FirewallStats {
             enabled: self.config.read().await.enabled,
-            rules_count: self.active_rules.read().await.len(),
-            packets_accepted: 0, // Would parse from nft output
+            rules_count: rules.len(),
+            packets_accepted: 0,
             packets_dropped: 0,
             packets_rejected: 0,
             bytes_total: 0,
         };
+        for rule in rules.values() {
+            match rule.verdict {
+                Some("accept") => stats.packets_accepted += rule.packets,
+                Some("drop") => stats.packets_dropped += rule.packets,
+                Some("reject") => stats.packets_rejected += rule.packets,
+                _ => {}
+            }
+            stats.bytes_total += rule.bytes;
+        }
 
         Ok(stats)
     }
 
     /// List all rules
     pub async fn list_rules(&self) -> Result<Vec<ActiveRule>> {
+        self.refresh_rules().await?;
         let rules = self.active_rules.read().await;
         Ok(rules.values().cloned().collect())
     }
 
     /// Reload configuration
     pub async fn reload(&self, config: FirewallConfig) -> Result<()> {
+        if matches!(self.backend, FirewallBackend::Nftables) {
+            let previous = std::mem::replace(&mut *self.config.write().await, config);
+            if let Err(e) = self.apply().await {
+                *self.config.write().await = previous;
+                return Err(e);
+            }
+            return Ok(());
+        }
+
         // Flush existing rules
-        self.nft_command(&["flush", "table", "inet", "nyx"], None).await?;
+        nftables::nft(&["flush", "table", "inet", "nyx"], None)?;
 
         // Update config
         *self.config.write().await = config;
@@ -404,41 +744,6 @@ impl Firewall {
         // Fall back to default policy
         matches!(config.default_policy, DefaultPolicy::Accept)
     }
-
-    async fn nft_command(&self, args: &[&str], stdin: Option<&str>) -> Result<String> {
-        let mut cmd = Command::new("nft");
-        cmd.args(args);
-
-        if let Some(input) = stdin {
-            use std::io::Write;
-            use std::process::Stdio;
-
-            cmd.stdin(Stdio::piped());
-            let mut child = cmd.spawn()?;
-
-            if let Some(mut child_stdin) = child.stdin.take() {
-                child_stdin.write_all(input.as_bytes())?;
-            }
-
-            let output = child.wait_with_output()?;
-
-            if !output.status.success() {
-                return Err(anyhow!("nft command failed: {}",
-                    String::from_utf8_lossy(&output.stderr)));
-            }
-
-            return Ok(String::from_utf8_lossy(&output.stdout).to_string());
-        }
-
-        let output = cmd.output()?;
-
-        if !output.status.success() {
-            return Err(anyhow!("nft command failed: {}",
-                String::from_utf8_lossy(&output.stderr)));
-        }
-
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
-    }
 }
 
 #[derive(Debug, Clone)]
@@ -451,6 +756,68 @@ pub struct FirewallStats {
     pub bytes_total: u64,
 }
 
+/// Check every compiled rule made it into the live table
+fn verify(ruleset: &Ruleset) -> Result<()> {
+    let live: HashSet<String> = nftables::live_rules()?
+        .into_iter()
+        .filter_map(|rule| rule.comment)
+        .collect();
+
+    let missing: Vec<&str> = ruleset
+        .comments
+        .iter()
+        .filter(|c| !live.contains(*c))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        return Err(anyhow!("rules missing after apply: {}", missing.join(", ")));
+    }
+    Ok(())
+}
+
+/// Probe the check targets; passes if any answers in time
+async fn self_check(apply: &ApplyConfig) -> Result<()> {
+    if apply.check_targets.is_empty() {
+        return Ok(());
+    }
+
+    let mut probes = tokio::task::JoinSet::new();
+    for target in apply.check_targets.clone() {
+        probes.spawn(async move {
+            let result = tokio::net::TcpStream::connect(target.as_str()).await;
+            (target, result)
+        });
+    }
+
+    let timeout = Duration::from_secs(apply.check_timeout_secs.max(1));
+    let reachable = tokio::time::timeout(timeout, async {
+        while let Some(probe) = probes.join_next().await {
+            match probe {
+                Ok((_, Ok(_))) => return true,
+                Ok((target, Err(e))) => tracing::debug!("Self-check {} failed: {}", target, e),
+                Err(_) => {}
+            }
+        }
+        false
+    })
+    .await
+    .unwrap_or(false);
+
+    if !reachable {
+        return Err(anyhow!(
+            "connectivity self-check failed: none of {} reachable",
+            apply.check_targets.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Put back a table captured before a change
+fn restore(previous: Option<&str>) -> Result<()> {
+    nftables::nft(&["-f", "-"], Some(&nftables::replace_script(previous)))?;
+    Ok(())
+}
+
 /// Check if an IP matches a CIDR pattern
 fn ip_matches(ip: &str, pattern: &str) -> bool {
     if pattern.contains('/') {
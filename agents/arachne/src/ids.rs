@@ -0,0 +1,505 @@
+//! Intrusion detection
+//!
+//! A lightweight rule layer over the monitor's socket and conntrack
+//! samples: port scans against this host, sockets that start listening
+//! after Arachne's first scan, and outbound connections opened at
+//! clockwork intervals, the way implants check in with their controller.
+//! Detections are kept in a short history, reported to Guardian so the
+//! offending app's capability requests score as more anomalous, and
+//! raised as herald notifications.
+
+use crate::config::{IdsConfig, RuleConfig, Sensitivity};
+use crate::conntrack::{Flow, FlowDirection};
+use crate::monitor::{Connection, ConnectionState, NetworkMonitor, Protocol};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use libnyx_ipc::guardian::{GuardianClient, ThreatReport};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+use tokio::sync::RwLock;
+
+/// Application name used for herald notifications
+const APP_NAME: &str = "Arachne";
+/// Detections kept for `IdsEvents`
+const MAX_EVENTS: usize = 500;
+/// Probes of distinct ports within this window count towards a scan
+const SCAN_WINDOW: Duration = Duration::from_secs(60);
+/// Connection starts remembered per beacon candidate
+const BEACON_HISTORY: usize = 16;
+/// Faster periods can't be told apart from chatty protocols at the
+/// monitor's sampling rate
+const BEACON_MIN_PERIOD: Duration = Duration::from_secs(15);
+/// Beacon candidates quiet this long are forgotten
+const BEACON_IDLE: Duration = Duration::from_secs(6 * 3600);
+
+/// Detection rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    PortScan,
+    UnexpectedListener,
+    Beaconing,
+}
+
+impl Rule {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::PortScan => "port_scan",
+            Self::UnexpectedListener => "unexpected_listener",
+            Self::Beaconing => "beaconing",
+        }
+    }
+
+    /// Severity reported to Guardian (0.0-1.0)
+    fn severity(self) -> f32 {
+        match self {
+            Self::PortScan => 0.4,
+            Self::UnexpectedListener => 0.5,
+            Self::Beaconing => 0.7,
+        }
+    }
+
+    fn summary(self) -> &'static str {
+        match self {
+            Self::PortScan => "Port scan detected",
+            Self::UnexpectedListener => "Unexpected listening socket",
+            Self::Beaconing => "Possible beaconing",
+        }
+    }
+}
+
+/// A rule firing
+#[derive(Debug, Clone)]
+pub struct IdsEvent {
+    pub rule: Rule,
+    pub severity: f32,
+    pub pid: Option<u32>,
+    pub process: Option<String>,
+    pub remote: Option<String>,
+    pub description: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Intrusion detection engine
+pub struct Ids {
+    config: RwLock<IdsConfig>,
+    events: RwLock<VecDeque<IdsEvent>>,
+    interval: Duration,
+}
+
+impl Ids {
+    pub fn new(config: IdsConfig, interval_secs: u64) -> Self {
+        Self {
+            config: RwLock::new(config),
+            events: RwLock::new(VecDeque::new()),
+            interval: Duration::from_secs(interval_secs.max(1)),
+        }
+    }
+
+    pub async fn config(&self) -> IdsConfig {
+        self.config.read().await.clone()
+    }
+
+    /// Enable, disable or retune a rule until Arachne restarts
+    pub async fn set_rule(&self, rule: Rule, enabled: Option<bool>, sensitivity: Option<Sensitivity>) -> RuleConfig {
+        let mut config = self.config.write().await;
+        let rule_config = match rule {
+            Rule::PortScan => &mut config.port_scan,
+            Rule::UnexpectedListener => &mut config.unexpected_listener,
+            Rule::Beaconing => &mut config.beaconing,
+        };
+        if let Some(enabled) = enabled {
+            rule_config.enabled = enabled;
+        }
+        if let Some(sensitivity) = sensitivity {
+            rule_config.sensitivity = sensitivity;
+        }
+        *rule_config
+    }
+
+    /// Recent detections, newest first
+    pub async fn events(&self, limit: usize) -> Vec<IdsEvent> {
+        self.events.read().await.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Evaluate monitor samples until Arachne exits
+    pub async fn run(&self, monitor: &NetworkMonitor) {
+        let mut detector = Detector::default();
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            interval.tick().await;
+
+            let config = self.config.read().await.clone();
+            if !config.enabled {
+                // Start from a fresh baseline when re-enabled
+                detector = Detector::default();
+                continue;
+            }
+
+            let connections = monitor.get_connections().await;
+            let flows = monitor.get_flows().await;
+            for event in detector.sample(&config, &connections, &flows, Instant::now()) {
+                self.raise(&config, event).await;
+            }
+        }
+    }
+
+    async fn raise(&self, config: &IdsConfig, event: IdsEvent) {
+        tracing::warn!("IDS {}: {}", event.rule.as_str(), event.description);
+
+        {
+            let mut events = self.events.write().await;
+            if events.len() >= MAX_EVENTS {
+                events.pop_front();
+            }
+            events.push_back(event.clone());
+        }
+
+        if config.report_guardian {
+            let event = event.clone();
+            tokio::spawn(async move {
+                if let Err(e) = report_guardian(&event).await {
+                    tracing::warn!("Failed to report detection to Guardian: {}", e);
+                }
+            });
+        }
+
+        if config.notify {
+            let socket = config.herald_socket.clone();
+            tokio::spawn(async move {
+                if let Err(e) = notify(&socket, &event).await {
+                    tracing::warn!("Failed to deliver detection notification: {}", e);
+                }
+            });
+        }
+    }
+}
+
+/// Identity of a conntrack flow across samples
+type FlowKey = (String, IpAddr, Option<u16>, IpAddr, Option<u16>);
+
+fn flow_key(flow: &Flow) -> FlowKey {
+    (flow.protocol.clone(), flow.local_addr, flow.local_port, flow.remote_addr, flow.remote_port)
+}
+
+/// A process talking to one remote service
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct BeaconKey {
+    process: String,
+    remote: IpAddr,
+    port: Option<u16>,
+}
+
+/// Rule state carried between samples
+#[derive(Default)]
+struct Detector {
+    /// Whether the baseline has been taken
+    primed: bool,
+    /// Flows in the previous sample
+    flows: HashSet<FlowKey>,
+    /// Remote -> probed local ports and when
+    probes: HashMap<IpAddr, Vec<(u16, Instant)>>,
+    /// Listening sockets already accounted for
+    listeners: HashSet<(IpAddr, u16)>,
+    /// Connection starts per beacon candidate
+    beacons: HashMap<BeaconKey, VecDeque<Instant>>,
+    /// When each (rule, subject) last fired
+    cooldowns: HashMap<(Rule, String), Instant>,
+}
+
+impl Detector {
+    /// Run every rule over one monitor sample
+    fn sample(
+        &mut self,
+        config: &IdsConfig,
+        connections: &[Connection],
+        flows: &[Flow],
+        now: Instant,
+    ) -> Vec<IdsEvent> {
+        let listening: Vec<&Connection> = connections
+            .iter()
+            .filter(|c| c.protocol == Protocol::Tcp && c.state == ConnectionState::Listen)
+            .collect();
+        let new_flows: Vec<&Flow> = flows.iter().filter(|f| !self.flows.contains(&flow_key(f))).collect();
+        self.flows = flows.iter().map(flow_key).collect();
+
+        // Whatever exists at the first sample is the baseline
+        if !self.primed {
+            self.primed = true;
+            self.listeners.extend(listening.iter().map(|c| (c.local_addr, c.local_port)));
+            return Vec::new();
+        }
+
+        let mut fired = Vec::new();
+        self.port_scans(&config.port_scan, &new_flows, now, &mut fired);
+        self.new_listeners(config, &listening, &mut fired);
+        self.beacons(config, &new_flows, now, &mut fired);
+
+        let cooldown = Duration::from_secs(config.cooldown_secs);
+        self.cooldowns.retain(|_, at| now.duration_since(*at) < cooldown);
+        fired
+            .into_iter()
+            .filter_map(|(subject, event)| match self.cooldowns.entry((event.rule, subject)) {
+                Entry::Occupied(_) => None,
+                Entry::Vacant(entry) => {
+                    entry.insert(now);
+                    Some(event)
+                }
+            })
+            .collect()
+    }
+
+    fn port_scans(&mut self, rule: &RuleConfig, new_flows: &[&Flow], now: Instant, fired: &mut Vec<(String, IdsEvent)>) {
+        for flow in new_flows {
+            if flow.direction != FlowDirection::Inbound || flow.remote_addr.is_loopback() {
+                continue;
+            }
+            if let Some(port) = flow.local_port {
+                self.probes.entry(flow.remote_addr).or_default().push((port, now));
+            }
+        }
+
+        self.probes.retain(|_, probes| {
+            probes.retain(|(_, at)| now.duration_since(*at) < SCAN_WINDOW);
+            !probes.is_empty()
+        });
+        if !rule.enabled {
+            return;
+        }
+
+        let threshold = scan_threshold(rule.sensitivity);
+        for (remote, probes) in &self.probes {
+            let ports: HashSet<u16> = probes.iter().map(|(port, _)| *port).collect();
+            if ports.len() >= threshold {
+                fired.push((remote.to_string(), event(
+                    Rule::PortScan,
+                    None,
+                    None,
+                    Some(remote.to_string()),
+                    format!("{} probed {} ports within {}s", remote, ports.len(), SCAN_WINDOW.as_secs()),
+                )));
+            }
+        }
+    }
+
+    fn new_listeners(&mut self, config: &IdsConfig, listening: &[&Connection], fired: &mut Vec<(String, IdsEvent)>) {
+        let rule = &config.unexpected_listener;
+        for conn in listening {
+            if !self.listeners.insert((conn.local_addr, conn.local_port)) {
+                continue;
+            }
+            if !rule.enabled
+                || !exposed(conn.local_addr, rule.sensitivity)
+                || allowed(&config.allowed_listeners, conn.process_name.as_deref(), None, Some(conn.local_port))
+            {
+                continue;
+            }
+
+            let socket = socket_string(conn.local_addr, conn.local_port);
+            fired.push((socket.clone(), event(
+                Rule::UnexpectedListener,
+                conn.pid,
+                conn.process_name.clone(),
+                None,
+                format!(
+                    "{} started listening on {}",
+                    conn.process_name.as_deref().unwrap_or("An unknown process"),
+                    socket
+                ),
+            )));
+        }
+    }
+
+    fn beacons(&mut self, config: &IdsConfig, new_flows: &[&Flow], now: Instant, fired: &mut Vec<(String, IdsEvent)>) {
+        let rule = &config.beaconing;
+        let (min_samples, max_variation) = beacon_limits(rule.sensitivity);
+
+        for flow in new_flows {
+            if flow.direction != FlowDirection::Outbound || flow.remote_addr.is_loopback() {
+                continue;
+            }
+            let process = flow.process.as_deref().unwrap_or("unknown");
+            if allowed(&config.allowed_beacons, Some(process), Some(flow.remote_addr), flow.remote_port) {
+                continue;
+            }
+
+            let key = BeaconKey {
+                process: process.to_string(),
+                remote: flow.remote_addr,
+                port: flow.remote_port,
+            };
+            let starts = self.beacons.entry(key.clone()).or_default();
+            starts.push_back(now);
+            if starts.len() > BEACON_HISTORY {
+                starts.pop_front();
+            }
+
+            if !rule.enabled {
+                continue;
+            }
+            let Some((period, variation)) = regularity(starts) else {
+                continue;
+            };
+            if starts.len() > min_samples && period >= BEACON_MIN_PERIOD.as_secs_f64() && variation <= max_variation {
+                let remote = match flow.remote_port {
+                    Some(port) => socket_string(flow.remote_addr, port),
+                    None => flow.remote_addr.to_string(),
+                };
+                fired.push((format!("{}>{}", key.process, remote), event(
+                    Rule::Beaconing,
+                    flow.pid,
+                    flow.process.clone(),
+                    Some(remote.clone()),
+                    format!(
+                        "{} connected to {} every {:.0}s ({} times, {:.0}% jitter)",
+                        key.process,
+                        remote,
+                        period,
+                        starts.len(),
+                        variation * 100.0
+                    ),
+                )));
+            }
+        }
+
+        self.beacons.retain(|_, starts| {
+            starts.back().is_some_and(|last| now.duration_since(*last) < BEACON_IDLE)
+        });
+    }
+}
+
+fn event(rule: Rule, pid: Option<u32>, process: Option<String>, remote: Option<String>, description: String) -> IdsEvent {
+    IdsEvent {
+        rule,
+        severity: rule.severity(),
+        pid,
+        process,
+        remote,
+        description,
+        timestamp: Utc::now(),
+    }
+}
+
+/// Distinct ports one remote must probe within the window
+fn scan_threshold(sensitivity: Sensitivity) -> usize {
+    match sensitivity {
+        Sensitivity::Low => 100,
+        Sensitivity::Medium => 30,
+        Sensitivity::High => 10,
+    }
+}
+
+/// Whether a new listener on `addr` is worth reporting
+fn exposed(addr: IpAddr, sensitivity: Sensitivity) -> bool {
+    match sensitivity {
+        Sensitivity::Low => addr.is_unspecified(),
+        Sensitivity::Medium => !addr.is_loopback(),
+        Sensitivity::High => true,
+    }
+}
+
+/// Intervals needed, and the largest coefficient of variation accepted
+fn beacon_limits(sensitivity: Sensitivity) -> (usize, f64) {
+    match sensitivity {
+        Sensitivity::Low => (10, 0.05),
+        Sensitivity::Medium => (6, 0.1),
+        Sensitivity::High => (4, 0.2),
+    }
+}
+
+/// Mean interval between connection starts, in seconds, and its
+/// coefficient of variation
+fn regularity(starts: &VecDeque<Instant>) -> Option<(f64, f64)> {
+    if starts.len() < 3 {
+        return None;
+    }
+    let intervals: Vec<f64> = starts
+        .iter()
+        .zip(starts.iter().skip(1))
+        .map(|(a, b)| b.duration_since(*a).as_secs_f64())
+        .collect();
+    let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+    if mean <= 0.0 {
+        return None;
+    }
+    let variance = intervals.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / intervals.len() as f64;
+    Some((mean, variance.sqrt() / mean))
+}
+
+/// Allowlist entries match a process name, a remote address or a port
+fn allowed(list: &[String], process: Option<&str>, remote: Option<IpAddr>, port: Option<u16>) -> bool {
+    list.iter().any(|entry| {
+        Some(entry.as_str()) == process
+            || (remote.is_some() && entry.parse::<IpAddr>().ok() == remote)
+            || (port.is_some() && entry.parse::<u16>().ok() == port)
+    })
+}
+
+fn socket_string(addr: IpAddr, port: u16) -> String {
+    match addr {
+        IpAddr::V6(v6) => format!("[{}]:{}", v6, port),
+        IpAddr::V4(v4) => format!("{}:{}", v4, port),
+    }
+}
+
+/// Feed a detection to Guardian's pattern learner
+async fn report_guardian(event: &IdsEvent) -> Result<()> {
+    let process_path = event
+        .pid
+        .and_then(|pid| std::fs::read_link(format!("/proc/{}/exe", pid)).ok())
+        .map(|path| path.to_string_lossy().into_owned());
+
+    let mut client = GuardianClient::connect().await?;
+    client
+        .report_threat(ThreatReport {
+            source: "arachne".into(),
+            rule: event.rule.as_str().into(),
+            severity: event.severity,
+            process_path,
+            pid: event.pid,
+            remote: event.remote.clone(),
+            description: event.description.clone(),
+        })
+        .await?;
+    Ok(())
+}
+
+/// Send a notification through herald
+async fn notify(socket: &str, event: &IdsEvent) -> Result<()> {
+    let request = json!({
+        "type": "Notify",
+        "data": {
+            "app_name": APP_NAME,
+            "summary": event.rule.summary(),
+            "body": event.description,
+            "icon": "security-low",
+            "urgency": if event.severity >= 0.6 { "critical" } else { "normal" },
+            "timeout": null,
+        }
+    });
+
+    let mut stream = UnixStream::connect(socket).await?;
+    stream.write_all(request.to_string().as_bytes()).await?;
+    stream.write_all(b"\n").await?;
+    stream.flush().await?;
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+
+    let response: Value = serde_json::from_str(&line)?;
+    if response.get("status").and_then(|s| s.as_str()) == Some("Error") {
+        let message = response
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown error");
+        return Err(anyhow::anyhow!("herald: {}", message));
+    }
+    Ok(())
+}
@@ -1,12 +1,17 @@
 //! IPC server for Arachne
 
-use crate::dns::DnsResolver;
-use crate::firewall::Firewall;
+use crate::config::{BlocklistFormat, BlocklistSubscription, ClientPolicy, Direction, FirewallRule, Sensitivity};
+use crate::dns::{DnsClient, DnsResolver, Verdict};
+use crate::conntrack::{AppUsage, Endpoint, Flow};
+use crate::firewall::{ApplyOutcome, Firewall};
+use crate::ids::{Ids, IdsEvent, Rule};
 use crate::interfaces::InterfaceManager;
 use crate::monitor::NetworkMonitor;
 use crate::routing::RoutingTable;
+use crate::tunnel::TunnelState;
 use crate::vpn::VpnManager;
 use anyhow::Result;
+use libnyx_ipc::Hello;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::sync::Arc;
@@ -25,6 +30,11 @@ pub enum IpcRequest {
     FirewallBlockIp { ip: String, reason: String },
     FirewallUnblockIp { ip: String },
     FirewallAllowPort { port: u16, protocol: String },
+    FirewallApply,
+    FirewallConfirm,
+    FirewallRollback,
+    FirewallPreview,
+    FirewallAppRules,
 
     // DNS operations
     DnsResolve { hostname: String },
@@ -32,6 +42,37 @@ pub enum IpcRequest {
     DnsUnblockDomain { domain: String },
     DnsClearCache,
     DnsStats,
+    /// How a query would be filtered, without resolving it
+    DnsCheck {
+        domain: String,
+        #[serde(default)]
+        client: Option<String>,
+        #[serde(default)]
+        user: Option<String>,
+    },
+    DnsSubscriptions,
+    DnsSubscribe {
+        name: String,
+        url: String,
+        category: String,
+        #[serde(default)]
+        format: BlocklistFormat,
+    },
+    DnsUnsubscribe { name: String },
+    DnsRefreshLists {
+        #[serde(default)]
+        name: Option<String>,
+    },
+    DnsPolicies,
+    DnsSetClientPolicy { policy: ClientPolicy },
+    DnsRemoveClientPolicy { name: String },
+    DnsQueryLog {
+        #[serde(default = "default_log_limit")]
+        limit: usize,
+        #[serde(default)]
+        blocked_only: bool,
+    },
+    DnsClearQueryLog,
 
     // Interface operations
     InterfaceList,
@@ -51,14 +92,54 @@ pub enum IpcRequest {
     GetBandwidth { interface: String },
     GetConnectionStats,
     FindPortOwner { port: u16, protocol: String },
+    GetFlows {
+        #[serde(default = "default_flow_limit")]
+        limit: usize,
+    },
+    GetAppUsage,
+    GetEndpoints {
+        #[serde(default = "default_flow_limit")]
+        limit: usize,
+    },
+    /// Stream flows, per-app usage and endpoints until the client hangs up
+    WatchActivity {
+        #[serde(default = "default_watch_interval")]
+        interval_secs: u64,
+        #[serde(default = "default_flow_limit")]
+        limit: usize,
+    },
 
     // VPN operations
     VpnList,
     VpnConnect { name: String },
     VpnDisconnect { name: String },
     VpnStatus { name: String },
+    /// Block egress outside the tunnel while a VPN should be up
+    VpnKillSwitch { enabled: bool },
+    /// Route selected apps or destinations around (or into) the tunnel
+    VpnSplitTunnel { enabled: bool },
+    VpnGuardStatus,
+
+    // Intrusion detection
+    IdsStatus,
+    IdsEvents {
+        #[serde(default = "default_log_limit")]
+        limit: usize,
+    },
+    /// Enable, disable or retune a detection rule until restart
+    IdsSetRule {
+        rule: Rule,
+        #[serde(default)]
+        enabled: Option<bool>,
+        #[serde(default)]
+        sensitivity: Option<Sensitivity>,
+    },
 }
 
+fn default_log_limit() -> usize { 100 }
+fn default_flow_limit() -> usize { 50 }
+fn default_watch_interval() -> u64 { 2 }
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FirewallRuleSpec {
     pub direction: String,
@@ -67,6 +148,23 @@ pub struct FirewallRuleSpec {
     pub port: Option<u16>,
     pub source: Option<String>,
     pub destination: Option<String>,
+    #[serde(default)]
+    pub ct_state: Vec<String>,
+}
+
+impl FirewallRuleSpec {
+    fn into_rule(self, name: String) -> Result<FirewallRule> {
+        Ok(FirewallRule {
+            name,
+            direction: serde_json::from_value(serde_json::Value::String(self.direction))?,
+            action: serde_json::from_value(serde_json::Value::String(self.action))?,
+            protocol: self.protocol,
+            port: self.port,
+            source: self.source,
+            destination: self.destination,
+            ct_state: self.ct_state,
+        })
+    }
 }
 
 /// IPC response
@@ -85,6 +183,7 @@ pub struct IpcServer {
     routing: Arc<RwLock<RoutingTable>>,
     monitor: Arc<NetworkMonitor>,
     vpn: Arc<VpnManager>,
+    ids: Arc<Ids>,
 }
 
 impl IpcServer {
@@ -95,6 +194,7 @@ impl IpcServer {
         routing: Arc<RwLock<RoutingTable>>,
         monitor: Arc<NetworkMonitor>,
         vpn: Arc<VpnManager>,
+        ids: Arc<Ids>,
     ) -> Self {
         Self {
             firewall,
@@ -103,6 +203,7 @@ impl IpcServer {
             routing,
             monitor,
             vpn,
+            ids,
         }
     }
 
@@ -123,10 +224,11 @@ impl IpcServer {
                     let routing = Arc::clone(&self.routing);
                     let monitor = Arc::clone(&self.monitor);
                     let vpn = Arc::clone(&self.vpn);
+                    let ids = Arc::clone(&self.ids);
 
                     tokio::spawn(async move {
                         if let Err(e) = handle_client(
-                            stream, firewall, dns, interfaces, routing, monitor, vpn
+                            stream, firewall, dns, interfaces, routing, monitor, vpn, ids
                         ).await {
                             tracing::error!("Client error: {}", e);
                         }
@@ -140,6 +242,7 @@ impl IpcServer {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_client(
     stream: UnixStream,
     firewall: Arc<Firewall>,
@@ -148,30 +251,44 @@ async fn handle_client(
     routing: Arc<RwLock<RoutingTable>>,
     monitor: Arc<NetworkMonitor>,
     vpn: Arc<VpnManager>,
+    ids: Arc<Ids>,
 ) -> Result<()> {
+    // DNS policies can be per user, so requests resolve as the caller
+    let caller = stream.peer_cred().ok().map(|cred| cred.uid());
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
 
+    let hello = Hello::new("arachne", env!("CARGO_PKG_VERSION")).with_features(&["watch-activity", "vpn", "ids"]);
+
     while reader.read_line(&mut line).await? > 0 {
-        let response = match serde_json::from_str::<IpcRequest>(&line) {
-            Ok(request) => {
-                process_request(
-                    request,
-                    &firewall,
-                    &dns,
-                    &interfaces,
-                    &routing,
-                    &monitor,
-                    &vpn,
-                ).await
-            }
-            Err(e) => IpcResponse::Error {
-                message: format!("Invalid request: {}", e),
-            },
+        let response_json = match hello.respond(&line) {
+            Some(reply) => reply,
+            None => {
+                let response = match serde_json::from_str::<IpcRequest>(&line) {
+                    Ok(IpcRequest::WatchActivity { interval_secs, limit }) => {
+                        return watch_activity(reader, writer, &monitor, interval_secs, limit).await;
+                    }
+                    Ok(request) => {
+                        process_request(
+                            request,
+                            caller,
+                            &firewall,
+                            &dns,
+                            &interfaces,
+                            &routing,
+                            &monitor,
+                            &vpn,
+                            &ids,
+                        ).await
+                    }
+                    Err(e) => IpcResponse::Error {
+                        message: format!("Invalid request: {}", e),
+                    },
+                };
+                serde_json::to_string(&response)?
+            }
         };
-
-        let response_json = serde_json::to_string(&response)?;
         writer.write_all(response_json.as_bytes()).await?;
         writer.write_all(b"\n").await?;
         writer.flush().await?;
@@ -182,14 +299,123 @@ async fn handle_client(
     Ok(())
 }
 
+/// Send activity snapshots until the client disconnects
+async fn watch_activity(
+    mut reader: BufReader<tokio::net::unix::OwnedReadHalf>,
+    mut writer: tokio::net::unix::OwnedWriteHalf,
+    monitor: &NetworkMonitor,
+    interval_secs: u64,
+    limit: usize,
+) -> Result<()> {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs.max(1)));
+    let mut line = String::new();
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let response = IpcResponse::Success {
+                    data: activity_json(monitor, limit).await,
+                };
+                let response_json = serde_json::to_string(&response)?;
+                writer.write_all(response_json.as_bytes()).await?;
+                writer.write_all(b"\n").await?;
+                writer.flush().await?;
+            }
+            read = reader.read_line(&mut line) => {
+                // Anything but EOF is ignored while watching
+                if read? == 0 {
+                    return Ok(());
+                }
+                line.clear();
+            }
+        }
+    }
+}
+
+async fn activity_json(monitor: &NetworkMonitor, limit: usize) -> serde_json::Value {
+    let flows = monitor.get_flows().await;
+    serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "flows_total": flows.len(),
+        "flows": flows.iter().take(limit).map(flow_json).collect::<Vec<_>>(),
+        "apps": monitor.get_app_usage().await.iter().map(app_json).collect::<Vec<_>>(),
+        "endpoints": monitor.get_endpoints().await.iter().take(limit).map(endpoint_json).collect::<Vec<_>>(),
+    })
+}
+
+fn flow_json(flow: &Flow) -> serde_json::Value {
+    serde_json::json!({
+        "protocol": flow.protocol,
+        "state": flow.state,
+        "direction": format!("{:?}", flow.direction),
+        "local": socket_string(flow.local_addr, flow.local_port),
+        "remote": socket_string(flow.remote_addr, flow.remote_port),
+        "pid": flow.pid,
+        "process": flow.process,
+        "tx_bytes": flow.tx_bytes,
+        "rx_bytes": flow.rx_bytes,
+        "tx_rate": flow.tx_rate,
+        "rx_rate": flow.rx_rate,
+    })
+}
+
+fn app_json(app: &AppUsage) -> serde_json::Value {
+    serde_json::json!({
+        "process": app.process,
+        "pids": app.pids,
+        "flows": app.flows,
+        "tx_bytes": app.tx_bytes,
+        "rx_bytes": app.rx_bytes,
+        "tx_rate": app.tx_rate,
+        "rx_rate": app.rx_rate,
+    })
+}
+
+fn endpoint_json(endpoint: &Endpoint) -> serde_json::Value {
+    serde_json::json!({
+        "address": endpoint.address.to_string(),
+        "scope": endpoint.scope,
+        "flows": endpoint.flows,
+        "ports": endpoint.ports,
+        "processes": endpoint.processes,
+        "tx_bytes": endpoint.tx_bytes,
+        "rx_bytes": endpoint.rx_bytes,
+        "tx_rate": endpoint.tx_rate,
+        "rx_rate": endpoint.rx_rate,
+    })
+}
+
+fn ids_event_json(event: &IdsEvent) -> serde_json::Value {
+    serde_json::json!({
+        "rule": event.rule,
+        "severity": event.severity,
+        "pid": event.pid,
+        "process": event.process,
+        "remote": event.remote,
+        "description": event.description,
+        "timestamp": event.timestamp.to_rfc3339(),
+    })
+}
+
+fn socket_string(addr: std::net::IpAddr, port: Option<u16>) -> String {
+    match (addr, port) {
+        (std::net::IpAddr::V6(v6), Some(port)) => format!("[{}]:{}", v6, port),
+        (addr, Some(port)) => format!("{}:{}", addr, port),
+        (addr, None) => addr.to_string(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn process_request(
     request: IpcRequest,
+    caller: Option<u32>,
     firewall: &Firewall,
     dns: &DnsResolver,
     interfaces: &RwLock<InterfaceManager>,
     routing: &RwLock<RoutingTable>,
     monitor: &NetworkMonitor,
     vpn: &VpnManager,
+    ids: &Ids,
 ) -> IpcResponse {
     match request {
         // Firewall operations
@@ -201,12 +427,110 @@ async fn process_request(
                         "rules_count": stats.rules_count,
                         "packets_accepted": stats.packets_accepted,
                         "packets_dropped": stats.packets_dropped,
+                        "packets_rejected": stats.packets_rejected,
+                        "bytes_total": stats.bytes_total,
+                        "pending": firewall.pending_confirmation().await.map(|(generation, deadline)| {
+                            serde_json::json!({"generation": generation, "confirm_by": deadline.to_rfc3339()})
+                        }),
                     }),
                 },
                 Err(e) => IpcResponse::Error { message: e.to_string() },
             }
         }
 
+        IpcRequest::FirewallAddRule { name, rule } => {
+            let result = match rule.into_rule(name.clone()) {
+                Ok(rule) => firewall.add_rule(&rule).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => IpcResponse::Success {
+                    data: serde_json::json!({"added": name}),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::FirewallRemoveRule { name } => {
+            match firewall.remove_rule(&name).await {
+                Ok(()) => IpcResponse::Success {
+                    data: serde_json::json!({"removed": name}),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::FirewallAllowPort { port, protocol } => {
+            match firewall.allow_port(port, &protocol, Direction::In).await {
+                Ok(()) => IpcResponse::Success {
+                    data: serde_json::json!({"allowed": port, "protocol": protocol}),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::FirewallApply => {
+            match firewall.apply().await {
+                Ok(outcome) => IpcResponse::Success { data: apply_json(&outcome) },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::FirewallConfirm => {
+            match firewall.confirm().await {
+                Ok(generation) => IpcResponse::Success {
+                    data: serde_json::json!({"confirmed": generation}),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::FirewallRollback => {
+            match firewall.rollback().await {
+                Ok(generation) => IpcResponse::Success {
+                    data: serde_json::json!({"rolled_back": generation}),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::FirewallAppRules => {
+            match firewall.app_status().await {
+                Ok(apps) => {
+                    let apps: Vec<_> = apps.iter().map(|app| {
+                        serde_json::json!({
+                            "name": app.name,
+                            "cgroup": app.cgroup,
+                            "binary": app.binary,
+                            "action": app.action,
+                            "cgroups": app.cgroups,
+                            "rules": app.rules.iter().map(|r| {
+                                serde_json::json!({
+                                    "name": r.name,
+                                    "packets": r.packets,
+                                    "bytes": r.bytes,
+                                })
+                            }).collect::<Vec<_>>(),
+                        })
+                    }).collect();
+
+                    IpcResponse::Success {
+                        data: serde_json::json!({"apps": apps}),
+                    }
+                }
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::FirewallPreview => {
+            match firewall.preview().await {
+                Ok(ruleset) => IpcResponse::Success {
+                    data: serde_json::json!({"ruleset": ruleset}),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
         IpcRequest::FirewallBlockIp { ip, reason } => {
             match firewall.block_ip(&ip, &reason).await {
                 Ok(()) => IpcResponse::Success {
@@ -227,7 +551,11 @@ async fn process_request(
 
         // DNS operations
         IpcRequest::DnsResolve { hostname } => {
-            match dns.resolve(&hostname).await {
+            let client = DnsClient {
+                addr: None,
+                user: caller.and_then(crate::dns::user_name),
+            };
+            match dns.resolve(&hostname, &client).await {
                 Ok(ips) => IpcResponse::Success {
                     data: serde_json::json!({
                         "hostname": hostname,
@@ -267,10 +595,128 @@ async fn process_request(
                     "cache_max": stats.cache_max,
                     "total_hits": stats.total_hits,
                     "blocklist_size": stats.blocklist_size,
+                    "subscribed_domains": stats.subscribed_domains,
+                    "queries": stats.queries,
+                    "queries_blocked": stats.queries_blocked,
                 }),
             }
         }
 
+        IpcRequest::DnsCheck { domain, client, user } => {
+            let addr = match client.as_deref().map(str::parse).transpose() {
+                Ok(addr) => addr,
+                Err(_) => return IpcResponse::Error {
+                    message: format!("Invalid client address: {}", client.unwrap_or_default()),
+                },
+            };
+            let client = DnsClient {
+                addr,
+                user: user.or_else(|| caller.and_then(crate::dns::user_name)),
+            };
+            let verdict = dns.check(&domain, &client).await;
+            IpcResponse::Success { data: verdict_json(&domain, &verdict) }
+        }
+
+        IpcRequest::DnsSubscriptions => {
+            let lists: Vec<_> = dns.list_status().await.iter().map(|l| {
+                serde_json::json!({
+                    "name": l.name,
+                    "url": l.url,
+                    "category": l.category,
+                    "domains": l.domains,
+                    "updated": l.updated.map(|t| t.to_rfc3339()),
+                    "error": l.error,
+                })
+            }).collect();
+
+            IpcResponse::Success {
+                data: serde_json::json!({"subscriptions": lists}),
+            }
+        }
+
+        IpcRequest::DnsSubscribe { name, url, category, format } => {
+            let subscription = BlocklistSubscription { name: name.clone(), url, category, format };
+            match dns.subscribe(subscription).await {
+                Ok(()) => IpcResponse::Success {
+                    data: serde_json::json!({"subscribed": name}),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::DnsUnsubscribe { name } => {
+            if dns.unsubscribe(&name).await {
+                IpcResponse::Success {
+                    data: serde_json::json!({"unsubscribed": name}),
+                }
+            } else {
+                IpcResponse::Error {
+                    message: format!("No blocklist subscription named {}", name),
+                }
+            }
+        }
+
+        IpcRequest::DnsRefreshLists { name } => {
+            match dns.refresh_lists(name.as_deref()).await {
+                Ok(fetched) => IpcResponse::Success {
+                    data: serde_json::json!({"fetched": fetched}),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::DnsPolicies => {
+            let (default, clients) = dns.policies().await;
+            IpcResponse::Success {
+                data: serde_json::json!({"default": default, "clients": clients}),
+            }
+        }
+
+        IpcRequest::DnsSetClientPolicy { policy } => {
+            let name = policy.name.clone();
+            match dns.set_client_policy(policy).await {
+                Ok(()) => IpcResponse::Success {
+                    data: serde_json::json!({"policy": name}),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::DnsRemoveClientPolicy { name } => {
+            if dns.remove_client_policy(&name).await {
+                IpcResponse::Success {
+                    data: serde_json::json!({"removed": name}),
+                }
+            } else {
+                IpcResponse::Error {
+                    message: format!("No client policy named {}", name),
+                }
+            }
+        }
+
+        IpcRequest::DnsQueryLog { limit, blocked_only } => {
+            let entries: Vec<_> = dns.query_log(limit, blocked_only).await.iter().map(|e| {
+                serde_json::json!({
+                    "timestamp": e.timestamp.to_rfc3339(),
+                    "name": e.name,
+                    "client": e.client,
+                    "policy": e.policy,
+                    "blocked": e.blocked,
+                })
+            }).collect();
+
+            IpcResponse::Success {
+                data: serde_json::json!({"entries": entries}),
+            }
+        }
+
+        IpcRequest::DnsClearQueryLog => {
+            dns.clear_query_log().await;
+            IpcResponse::Success {
+                data: serde_json::json!({"cleared": true}),
+            }
+        }
+
         // Interface operations
         IpcRequest::InterfaceList => {
             let manager = interfaces.read().await;
@@ -375,6 +821,36 @@ async fn process_request(
             }
         }
 
+        IpcRequest::GetFlows { limit } => {
+            let flows = monitor.get_flows().await;
+            IpcResponse::Success {
+                data: serde_json::json!({
+                    "total": flows.len(),
+                    "flows": flows.iter().take(limit).map(flow_json).collect::<Vec<_>>(),
+                }),
+            }
+        }
+
+        IpcRequest::GetAppUsage => {
+            let apps = monitor.get_app_usage().await;
+            IpcResponse::Success {
+                data: serde_json::json!({"apps": apps.iter().map(app_json).collect::<Vec<_>>()}),
+            }
+        }
+
+        IpcRequest::GetEndpoints { limit } => {
+            let endpoints = monitor.get_endpoints().await;
+            IpcResponse::Success {
+                data: serde_json::json!({
+                    "endpoints": endpoints.iter().take(limit).map(endpoint_json).collect::<Vec<_>>(),
+                }),
+            }
+        }
+
+        IpcRequest::WatchActivity { .. } => IpcResponse::Error {
+            message: "WatchActivity requires a streaming connection".to_string(),
+        },
+
         // VPN operations
         IpcRequest::VpnList => {
             let connections = vpn.list().await;
@@ -434,12 +910,104 @@ async fn process_request(
             }
         }
 
+        IpcRequest::VpnKillSwitch { enabled } => {
+            match vpn.set_kill_switch(enabled).await {
+                Ok(state) => IpcResponse::Success { data: tunnel_json(&state) },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::VpnSplitTunnel { enabled } => {
+            match vpn.set_split_tunnel(enabled).await {
+                Ok(state) => IpcResponse::Success { data: tunnel_json(&state) },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::VpnGuardStatus => {
+            let status = vpn.guard_status().await;
+            let mut data = tunnel_json(&status.state);
+            data["kill_switch"] = serde_json::json!({
+                "enabled": status.kill_switch.enabled,
+                "always": status.kill_switch.always,
+                "allow_lan": status.kill_switch.allow_lan,
+                "blocked_packets": status.blocked_packets,
+            });
+            data["split_tunnel"] = serde_json::json!({
+                "enabled": status.split_tunnel.enabled,
+                "mode": status.split_tunnel.mode,
+                "cgroups": status.split_tunnel.cgroups,
+                "binaries": status.split_tunnel.binaries,
+                "destinations": status.split_tunnel.destinations,
+            });
+            data["wanted"] = serde_json::json!(status.wanted);
+            IpcResponse::Success { data }
+        }
+
+        // Intrusion detection
+        IpcRequest::IdsStatus => {
+            let config = ids.config().await;
+            IpcResponse::Success {
+                data: serde_json::json!({
+                    "enabled": config.enabled,
+                    "rules": {
+                        "port_scan": config.port_scan,
+                        "unexpected_listener": config.unexpected_listener,
+                        "beaconing": config.beaconing,
+                    },
+                    "allowed_listeners": config.allowed_listeners,
+                    "allowed_beacons": config.allowed_beacons,
+                    "cooldown_secs": config.cooldown_secs,
+                    "report_guardian": config.report_guardian,
+                    "notify": config.notify,
+                }),
+            }
+        }
+
+        IpcRequest::IdsEvents { limit } => IpcResponse::Success {
+            data: serde_json::json!({
+                "events": ids.events(limit).await.iter().map(ids_event_json).collect::<Vec<_>>(),
+            }),
+        },
+
+        IpcRequest::IdsSetRule { rule, enabled, sensitivity } => {
+            let config = ids.set_rule(rule, enabled, sensitivity).await;
+            IpcResponse::Success {
+                data: serde_json::json!({"rule": rule, "config": config}),
+            }
+        }
+
         _ => IpcResponse::Error {
             message: "Not implemented".to_string(),
         },
     }
 }
 
+fn verdict_json(domain: &str, verdict: &Verdict) -> serde_json::Value {
+    serde_json::json!({
+        "domain": domain,
+        "policy": verdict.policy,
+        "blocked": verdict.blocked.is_some(),
+        "reason": verdict.blocked,
+    })
+}
+
+fn tunnel_json(state: &TunnelState) -> serde_json::Value {
+    serde_json::json!({
+        "kill_switch_engaged": state.kill_switch_engaged,
+        "split_active": state.split_active,
+        "split_cgroups": state.split_cgroups,
+    })
+}
+
+fn apply_json(outcome: &ApplyOutcome) -> serde_json::Value {
+    serde_json::json!({
+        "generation": outcome.generation,
+        "rules": outcome.rules,
+        "confirm_by": outcome.confirm_by.map(|d| d.to_rfc3339()),
+    })
+}
+
 /// IPC client for other components
 pub struct IpcClient {
     socket_path: std::path::PathBuf,
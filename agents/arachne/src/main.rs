@@ -8,12 +8,19 @@
 //! - **DNS**: Local resolver with caching and filtering
 //! - **VPN**: WireGuard integration
 //! - **Network Monitoring**: Connection tracking and bandwidth
+//! - **Intrusion Detection**: Port scans, new listeners and beaconing
 
+mod apps;
+mod blocklist;
 mod config;
+mod conntrack;
 mod firewall;
+mod ids;
+mod nftables;
 mod dns;
 mod interfaces;
 mod routing;
+mod tunnel;
 mod monitor;
 mod vpn;
 mod ipc;
@@ -61,6 +68,39 @@ async fn main() -> Result<()> {
     let routing = Arc::new(tokio::sync::RwLock::new(routing::RoutingTable::new()));
     let monitor = Arc::new(monitor::NetworkMonitor::new(interfaces.clone(), config.monitor.interval_secs));
     let vpn = Arc::new(vpn::VpnManager::new(config.vpn.clone()));
+    let ids = Arc::new(ids::Ids::new(config.ids.clone(), config.monitor.interval_secs));
+
+    if let Err(e) = firewall.init().await {
+        error!("Firewall initialization failed: {}", e);
+    }
+
+    let firewall_clone = firewall.clone();
+    tokio::spawn(async move {
+        firewall_clone.watch_apps().await;
+    });
+
+    if let Err(e) = vpn.init().await {
+        error!("VPN initialization failed: {}", e);
+    }
+
+    let vpn_clone = vpn.clone();
+    tokio::spawn(async move {
+        vpn_clone.watch_split().await;
+    });
+
+    let dns_clone = dns_resolver.clone();
+    tokio::spawn(async move {
+        dns_clone.refresh_loop().await;
+    });
+
+    if config.dns.enabled && config.dns.server_enabled {
+        let dns_clone = dns_resolver.clone();
+        tokio::spawn(async move {
+            if let Err(e) = dns_clone.serve().await {
+                error!("DNS server failed: {}", e);
+            }
+        });
+    }
 
     // Start network monitor
     let monitor_clone = monitor.clone();
@@ -68,6 +108,13 @@ async fn main() -> Result<()> {
         monitor_clone.start().await;
     });
 
+    // Start intrusion detection over the monitor's samples
+    let ids_clone = ids.clone();
+    let monitor_clone = monitor.clone();
+    tokio::spawn(async move {
+        ids_clone.run(&monitor_clone).await;
+    });
+
     // Start IPC server
     let server = ipc::IpcServer::new(
         firewall,
@@ -76,6 +123,7 @@ async fn main() -> Result<()> {
         routing,
         monitor,
         vpn,
+        ids,
     );
 
     info!("Arachne ready");
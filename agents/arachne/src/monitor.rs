@@ -1,8 +1,9 @@
 //! Network monitoring and statistics
 
+use crate::conntrack::{self, AppUsage, Endpoint, Flow, FlowTracker};
 use crate::interfaces::{InterfaceManager, InterfaceStats};
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -13,6 +14,7 @@ pub struct NetworkMonitor {
     interface_manager: Arc<RwLock<InterfaceManager>>,
     connections: Arc<RwLock<Vec<Connection>>>,
     bandwidth_history: Arc<RwLock<HashMap<String, Vec<BandwidthSample>>>>,
+    flows: Arc<RwLock<Vec<Flow>>>,
     interval: Duration,
 }
 
@@ -28,6 +30,8 @@ pub struct Connection {
     pub process_name: Option<String>,
     pub rx_bytes: u64,
     pub tx_bytes: u64,
+    /// Socket inode, for finding the owning process
+    pub inode: u64,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -69,6 +73,7 @@ impl NetworkMonitor {
             interface_manager,
             connections: Arc::new(RwLock::new(Vec::new())),
             bandwidth_history: Arc::new(RwLock::new(HashMap::new())),
+            flows: Arc::new(RwLock::new(Vec::new())),
             interval: Duration::from_secs(interval_secs),
         }
     }
@@ -78,11 +83,14 @@ impl NetworkMonitor {
         let connections = Arc::clone(&self.connections);
         let bandwidth = Arc::clone(&self.bandwidth_history);
         let iface_manager = Arc::clone(&self.interface_manager);
+        let flows = Arc::clone(&self.flows);
         let interval = self.interval;
 
         tokio::spawn(async move {
             let mut previous_stats: HashMap<String, InterfaceStats> = HashMap::new();
             let mut last_sample = Instant::now();
+            let mut tracker = FlowTracker::new();
+            let mut conntrack_warned = false;
 
             loop {
                 // Update connections
@@ -133,6 +141,20 @@ impl NetworkMonitor {
                     }
                 }
 
+                // Update flows
+                {
+                    let local = local_addresses(&*iface_manager.read().await);
+                    let conns = connections.read().await.clone();
+                    match tracker.sample(&local, &conns) {
+                        Ok(sampled) => *flows.write().await = sampled,
+                        Err(e) if !conntrack_warned => {
+                            tracing::warn!("Flow tracking unavailable: {}", e);
+                            conntrack_warned = true;
+                        }
+                        Err(_) => {}
+                    }
+                }
+
                 tokio::time::sleep(interval).await;
             }
         });
@@ -143,6 +165,23 @@ impl NetworkMonitor {
         self.connections.read().await.clone()
     }
 
+    /// Active conntrack flows, busiest first
+    pub async fn get_flows(&self) -> Vec<Flow> {
+        let mut flows = self.flows.read().await.clone();
+        flows.sort_by(|a, b| (b.tx_rate + b.rx_rate).total_cmp(&(a.tx_rate + a.rx_rate)));
+        flows
+    }
+
+    /// Flow traffic per process
+    pub async fn get_app_usage(&self) -> Vec<AppUsage> {
+        conntrack::app_usage(&self.flows.read().await)
+    }
+
+    /// Flow traffic per remote address
+    pub async fn get_endpoints(&self) -> Vec<Endpoint> {
+        conntrack::endpoints(&self.flows.read().await)
+    }
+
     /// Get connections for a specific process
     pub async fn get_process_connections(&self, pid: u32) -> Vec<Connection> {
         self.connections
@@ -302,10 +341,23 @@ fn parse_proc_net(content: &str, protocol: Protocol, connections: &mut Vec<Conne
             process_name: None,
             rx_bytes: 0,
             tx_bytes: 0,
+            inode,
         });
     }
 }
 
+/// Addresses that belong to this host
+fn local_addresses(manager: &InterfaceManager) -> HashSet<IpAddr> {
+    let mut local = HashSet::new();
+    for iface in manager.list() {
+        local.extend(iface.ipv4_addresses.iter().map(|a| IpAddr::V4(a.address)));
+        local.extend(iface.ipv6_addresses.iter().map(|a| IpAddr::V6(a.address)));
+    }
+    local.insert(IpAddr::V4(std::net::Ipv4Addr::LOCALHOST));
+    local.insert(IpAddr::V6(std::net::Ipv6Addr::LOCALHOST));
+    local
+}
+
 fn parse_addr_port(s: &str) -> Option<(IpAddr, u16)> {
     let parts: Vec<&str> = s.split(':').collect();
     if parts.len() != 2 {
@@ -326,12 +378,13 @@ fn parse_addr_port(s: &str) -> Option<(IpAddr, u16)> {
         ];
         IpAddr::V4(std::net::Ipv4Addr::from(bytes))
     } else if addr_hex.len() == 32 {
-        // IPv6
+        // IPv6: four 32-bit words, each in host (little-endian) order
         let mut bytes = [0u8; 16];
-        for i in 0..16 {
-            bytes[i] = u8::from_str_radix(&addr_hex[i * 2..i * 2 + 2], 16).ok()?;
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            let word = i / 4;
+            let offset = word * 8 + (3 - i % 4) * 2;
+            *byte = u8::from_str_radix(&addr_hex[offset..offset + 2], 16).ok()?;
         }
-        // Handle endianness
         IpAddr::V6(std::net::Ipv6Addr::from(bytes))
     } else {
         return None;
@@ -357,7 +410,7 @@ fn parse_tcp_state(hex: &str) -> ConnectionState {
     }
 }
 
-async fn resolve_connection_pids(connections: &mut Vec<Connection>) {
+async fn resolve_connection_pids(connections: &mut [Connection]) {
     // Build inode -> pid map from /proc
     let mut inode_pid: HashMap<u64, (u32, String)> = HashMap::new();
 
@@ -399,6 +452,10 @@ async fn resolve_connection_pids(connections: &mut Vec<Connection>) {
         }
     }
 
-    // Note: We'd need to track inodes during parsing to complete this
-    // This is a simplified version
+    for conn in connections.iter_mut() {
+        if let Some((pid, name)) = inode_pid.get(&conn.inode) {
+            conn.pid = Some(*pid);
+            conn.process_name = Some(name.clone());
+        }
+    }
 }
@@ -0,0 +1,323 @@
+//! nftables ruleset compiler
+//!
+//! Renders the firewall configuration as one complete `table inet nyx`.
+//! The script replaces any existing table, so `nft -f` swaps the whole
+//! ruleset in a single transaction: either every rule lands or none do.
+//! Rule fields are validated here rather than pasted into nft syntax.
+
+use crate::apps::{self, Resolved};
+use crate::config::{Action, AppRule, DefaultPolicy, Direction, FirewallConfig, FirewallRule};
+use anyhow::{anyhow, Result};
+use ipnetwork::IpNetwork;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Family and name of the table Arachne owns
+pub const TABLE: [&str; 2] = ["inet", "nyx"];
+
+const PROTOCOLS: &[&str] = &["tcp", "udp", "sctp", "icmp", "icmpv6"];
+const CT_STATES: &[&str] = &["new", "established", "related", "invalid", "untracked"];
+
+/// A compiled ruleset
+#[derive(Debug, Clone)]
+pub struct Ruleset {
+    /// `table inet nyx { ... }`
+    pub table: String,
+    /// Rule comments expected in the applied table
+    pub comments: Vec<String>,
+}
+
+impl Ruleset {
+    /// Transaction that replaces the live table with this one
+    pub fn script(&self) -> String {
+        replace_script(Some(&self.table))
+    }
+}
+
+/// Transaction replacing the table with `table`, or removing it
+pub fn replace_script(table: Option<&str>) -> String {
+    replace_table_script("inet nyx", table)
+}
+
+/// Transaction replacing table `name` (`family name`) with `table`, or
+/// removing it
+pub fn replace_table_script(name: &str, table: Option<&str>) -> String {
+    // Adding first makes the delete succeed when no table exists yet
+    let mut script = format!("add table {}\ndelete table {}\n", name, name);
+    if let Some(table) = table {
+        script.push_str(table);
+        script.push('\n');
+    }
+    script
+}
+
+/// Compile the configured rules plus rules added at runtime, with app
+/// rules applied to the cgroups in `resolved`
+pub fn compile(config: &FirewallConfig, extra: &[FirewallRule], resolved: &Resolved) -> Result<Ruleset> {
+    let mut input = vec![
+        "ct state established,related accept".to_string(),
+        "iif lo accept".to_string(),
+    ];
+    let mut output = Vec::new();
+    let mut comments = Vec::new();
+
+    // App rules go first so general egress rules cannot let them out
+    for app in &config.apps {
+        apps::validate(app)?;
+        for cgroup in resolved.get(&app.name).into_iter().flatten() {
+            compile_app(app, cgroup, &mut output)?;
+        }
+        if resolved.get(&app.name).is_some_and(|c| !c.is_empty()) {
+            comments.extend(app_comments(app));
+        }
+    }
+
+    for rule in config.rules.iter().chain(extra) {
+        match rule.direction {
+            Direction::In => input.push(compile_rule(rule, &rule.name, false)?),
+            Direction::Out => output.push(compile_rule(rule, &rule.name, false)?),
+            Direction::Both => {
+                let (name_in, name_out) = (format!("{}-in", rule.name), format!("{}-out", rule.name));
+                input.push(compile_rule(rule, &name_in, false)?);
+                // The output copy matches traffic going back the other way
+                output.push(compile_rule(rule, &name_out, true)?);
+                comments.push(name_in);
+                comments.push(name_out);
+                continue;
+            }
+        }
+        comments.push(rule.name.clone());
+    }
+
+    if config.log_blocked && !matches!(config.default_policy, DefaultPolicy::Accept) {
+        input.push("limit rate 10/second log prefix \"nyx-fw drop: \"".to_string());
+    }
+    let policy = match config.default_policy {
+        DefaultPolicy::Accept => "accept",
+        DefaultPolicy::Drop => "drop",
+        DefaultPolicy::Reject => {
+            // Chains only take accept/drop policies
+            input.push("reject".to_string());
+            "drop"
+        }
+    };
+
+    let mut table = String::from("table inet nyx {\n");
+    push_chain(&mut table, "input", &format!("type filter hook input priority 0; policy {};", policy), &input);
+    push_chain(&mut table, "output", "type filter hook output priority 0; policy accept;", &output);
+    push_chain(&mut table, "forward", "type filter hook forward priority 0; policy drop;", &[]);
+    table.push('}');
+
+    Ok(Ruleset { table, comments })
+}
+
+/// Check a rule compiles without building a ruleset
+pub fn validate(rule: &FirewallRule) -> Result<()> {
+    compile_rule(rule, &rule.name, false).map(|_| ())
+}
+
+/// Comments of the rules an app compiles to: exceptions, then the verdict
+pub fn app_comments(app: &AppRule) -> Vec<String> {
+    let mut comments: Vec<String> = (0..app.except.len())
+        .map(|i| format!("app-{}-except-{}", app.name, i))
+        .collect();
+    comments.push(format!("app-{}", app.name));
+    comments
+}
+
+fn compile_app(app: &AppRule, cgroup: &str, output: &mut Vec<String>) -> Result<()> {
+    apps::validate_cgroup(cgroup)?;
+    let owner = format!("socket cgroupv2 level {} \"{}\"", apps::level(cgroup), cgroup);
+    let comments = app_comments(app);
+
+    for (exception, comment) in app.except.iter().zip(&comments) {
+        let rule = FirewallRule {
+            name: app.name.clone(),
+            direction: Direction::Out,
+            action: Action::Accept,
+            protocol: exception.protocol.clone(),
+            port: exception.port,
+            source: None,
+            destination: exception.destination.clone(),
+            ct_state: Vec::new(),
+        };
+        output.push(format!("{} {}", owner, compile_rule(&rule, comment, false)?));
+    }
+
+    let rule = FirewallRule {
+        name: app.name.clone(),
+        direction: Direction::Out,
+        action: app.action,
+        protocol: None,
+        port: None,
+        source: None,
+        destination: None,
+        ct_state: Vec::new(),
+    };
+    output.push(format!("{} {}", owner, compile_rule(&rule, &comments[comments.len() - 1], false)?));
+    Ok(())
+}
+
+fn push_chain(table: &mut String, name: &str, hook: &str, rules: &[String]) {
+    table.push_str(&format!("    chain {} {{\n        {}\n", name, hook));
+    for rule in rules {
+        table.push_str(&format!("        {}\n", rule));
+    }
+    table.push_str("    }\n");
+}
+
+fn compile_rule(rule: &FirewallRule, comment: &str, reverse: bool) -> Result<String> {
+    if comment.is_empty()
+        || !comment.chars().all(|c| c.is_ascii_alphanumeric() || "-_.:".contains(c))
+    {
+        return Err(anyhow!("invalid rule name {:?}", rule.name));
+    }
+
+    let mut parts = Vec::new();
+
+    let (source, destination) = if reverse {
+        (&rule.destination, &rule.source)
+    } else {
+        (&rule.source, &rule.destination)
+    };
+    let source = source.as_deref().map(parse_network).transpose()?;
+    let destination = destination.as_deref().map(parse_network).transpose()?;
+    if let (Some(s), Some(d)) = (&source, &destination) {
+        if s.is_ipv4() != d.is_ipv4() {
+            return Err(anyhow!("rule {}: source and destination families differ", rule.name));
+        }
+    }
+    if let Some(net) = source {
+        parts.push(format!("{} saddr {}", family(&net), net));
+    }
+    if let Some(net) = destination {
+        parts.push(format!("{} daddr {}", family(&net), net));
+    }
+
+    let protocol = rule.protocol.as_deref().map(str::to_ascii_lowercase);
+    if let Some(ref proto) = protocol {
+        if !PROTOCOLS.contains(&proto.as_str()) {
+            return Err(anyhow!("rule {}: unsupported protocol {}", rule.name, proto));
+        }
+    }
+    match (protocol.as_deref(), rule.port) {
+        (Some(proto @ ("tcp" | "udp" | "sctp")), Some(port)) => {
+            parts.push(format!("{} dport {}", proto, port));
+        }
+        (Some(proto), Some(_)) => {
+            return Err(anyhow!("rule {}: {} has no ports", rule.name, proto));
+        }
+        (Some(proto), None) => parts.push(format!("meta l4proto {}", proto)),
+        (None, Some(port)) => parts.push(format!("meta l4proto {{ tcp, udp }} th dport {}", port)),
+        (None, None) => {}
+    }
+
+    if !rule.ct_state.is_empty() {
+        let mut states = Vec::new();
+        for state in &rule.ct_state {
+            let state = state.to_ascii_lowercase();
+            if !CT_STATES.contains(&state.as_str()) {
+                return Err(anyhow!("rule {}: unknown conntrack state {}", rule.name, state));
+            }
+            states.push(state);
+        }
+        parts.push(format!("ct state {{ {} }}", states.join(", ")));
+    }
+
+    parts.push("counter".to_string());
+    parts.push(match rule.action {
+        Action::Accept => "accept".to_string(),
+        Action::Drop => "drop".to_string(),
+        Action::Reject => "reject".to_string(),
+        Action::Log => format!("log prefix \"nyx-fw {}: \"", comment),
+    });
+    parts.push(format!("comment \"{}\"", comment));
+
+    Ok(parts.join(" "))
+}
+
+fn parse_network(value: &str) -> Result<IpNetwork> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("invalid address {:?}", value))
+}
+
+fn family(net: &IpNetwork) -> &'static str {
+    if net.is_ipv4() { "ip" } else { "ip6" }
+}
+
+/// Run nft with optional script input
+pub fn nft(args: &[&str], stdin: Option<&str>) -> Result<String> {
+    let mut cmd = Command::new("nft");
+    cmd.args(args).stdout(Stdio::piped()).stderr(Stdio::piped());
+    if stdin.is_some() {
+        cmd.stdin(Stdio::piped());
+    }
+
+    let mut child = cmd.spawn()?;
+    if let (Some(input), Some(mut child_stdin)) = (stdin, child.stdin.take()) {
+        child_stdin.write_all(input.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!("nft command failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// The live table in nft syntax, if it exists
+pub fn snapshot() -> Option<String> {
+    nft(&["list", "table", TABLE[0], TABLE[1]], None).ok()
+}
+
+/// Rules of the live table as reported by `nft -j`
+pub fn live_rules() -> Result<Vec<LiveRule>> {
+    live_rules_in(TABLE[0], TABLE[1])
+}
+
+/// Rules of another live table
+pub fn live_rules_in(family: &str, table: &str) -> Result<Vec<LiveRule>> {
+    let output = nft(&["-j", "list", "table", family, table], None)?;
+    let json: serde_json::Value = serde_json::from_str(&output)?;
+
+    let mut rules = Vec::new();
+    for item in json["nftables"].as_array().into_iter().flatten() {
+        let Some(rule) = item.get("rule") else { continue };
+        let mut live = LiveRule {
+            chain: rule["chain"].as_str().unwrap_or_default().to_string(),
+            handle: rule["handle"].as_u64().unwrap_or_default(),
+            comment: rule["comment"].as_str().map(str::to_string),
+            packets: 0,
+            bytes: 0,
+            verdict: None,
+        };
+        for expr in rule["expr"].as_array().into_iter().flatten() {
+            if let Some(counter) = expr.get("counter") {
+                live.packets = counter["packets"].as_u64().unwrap_or_default();
+                live.bytes = counter["bytes"].as_u64().unwrap_or_default();
+            }
+            for verdict in ["accept", "drop", "reject"] {
+                if expr.get(verdict).is_some() {
+                    live.verdict = Some(verdict);
+                }
+            }
+        }
+        rules.push(live);
+    }
+    Ok(rules)
+}
+
+/// A rule in the live table
+#[derive(Debug, Clone)]
+pub struct LiveRule {
+    pub chain: String,
+    pub handle: u64,
+    pub comment: Option<String>,
+    pub packets: u64,
+    pub bytes: u64,
+    pub verdict: Option<&'static str>,
+}
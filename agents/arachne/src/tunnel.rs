@@ -0,0 +1,256 @@
+//! VPN kill switch and split tunneling
+//!
+//! Both live in their own nftables table, `inet nyx_vpn`, so firewall
+//! applies and rollbacks never touch them. The kill switch rejects egress
+//! that does not leave through a tunnel interface, except to the VPN
+//! servers themselves, link maintenance (DHCP, neighbour discovery) and
+//! optionally the LAN. Split tunneling marks selected traffic and a policy
+//! rule sends marked packets to their own routing table: around the tunnel
+//! in `exclude` mode, into it in `include` mode.
+
+use crate::apps;
+use crate::config::{KillSwitchConfig, SplitMode, SplitTunnelConfig};
+use crate::nftables;
+use anyhow::{anyhow, Result};
+use ipnetwork::IpNetwork;
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
+use std::process::Command;
+
+const TABLE: &str = "inet nyx_vpn";
+
+/// Priority of the split tunnel policy rule, ahead of `main` (32766)
+const RULE_PRIORITY: &str = "5200";
+
+const LAN_V4: &str = "10.0.0.0/8, 172.16.0.0/12, 192.168.0.0/16, 169.254.0.0/16";
+const LAN_V6: &str = "fe80::/10, fc00::/7";
+
+/// What the kill switch and split tunnel are applied from
+#[derive(Debug, Clone)]
+pub struct TunnelPlan {
+    pub kill_switch: KillSwitchConfig,
+    pub split: SplitTunnelConfig,
+    /// Tunnel interfaces, the first being the default for `include` mode
+    pub interfaces: Vec<String>,
+    /// VPN servers that must stay reachable
+    pub endpoints: Vec<SocketAddr>,
+    /// Whether a VPN is supposed to be up
+    pub vpn_wanted: bool,
+}
+
+/// Result of applying a plan
+#[derive(Debug, Clone, Default)]
+pub struct TunnelState {
+    pub kill_switch_engaged: bool,
+    pub split_active: bool,
+    /// Cgroups split traffic was selected from
+    pub split_cgroups: Vec<String>,
+}
+
+impl TunnelPlan {
+    fn engage_kill_switch(&self) -> bool {
+        self.kill_switch.enabled && (self.kill_switch.always || self.vpn_wanted)
+    }
+
+    /// Split tunneling needs somewhere to send `include` traffic
+    fn split_active(&self) -> bool {
+        self.split.enabled && (self.split.mode == SplitMode::Exclude || !self.interfaces.is_empty())
+    }
+}
+
+/// Cgroups the split tunnel currently selects
+pub fn split_cgroups(split: &SplitTunnelConfig) -> Vec<String> {
+    let mut cgroups: BTreeSet<String> = split
+        .cgroups
+        .iter()
+        .map(|c| c.trim_matches('/').to_string())
+        .collect();
+    cgroups.extend(apps::resolve_binaries(&split.binaries));
+    cgroups.into_iter().collect()
+}
+
+/// Bring nftables and policy routing in line with `plan`
+pub fn apply(plan: &TunnelPlan) -> Result<TunnelState> {
+    let state = TunnelState {
+        kill_switch_engaged: plan.engage_kill_switch(),
+        split_active: plan.split_active(),
+        split_cgroups: if plan.split_active() { split_cgroups(&plan.split) } else { Vec::new() },
+    };
+
+    let table = compile(plan, &state)?;
+    nftables::nft(&["-f", "-"], Some(&nftables::replace_table_script(TABLE, table.as_deref())))?;
+
+    if state.split_active {
+        install_routes(plan)?;
+    } else {
+        remove_routes(&plan.split);
+    }
+
+    tracing::info!(
+        "VPN guard: kill switch {}, split tunnel {}",
+        if state.kill_switch_engaged { "engaged" } else { "off" },
+        if state.split_active { "active" } else { "off" }
+    );
+    Ok(state)
+}
+
+/// Packets the kill switch has rejected
+pub fn blocked_packets() -> u64 {
+    let (family, table) = TABLE.split_once(' ').unwrap_or_default();
+    nftables::live_rules_in(family, table)
+        .map(|rules| {
+            rules
+                .iter()
+                .filter(|r| r.comment.as_deref() == Some("kill-switch"))
+                .map(|r| r.packets)
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+fn compile(plan: &TunnelPlan, state: &TunnelState) -> Result<Option<String>> {
+    if !state.kill_switch_engaged && !state.split_active {
+        return Ok(None);
+    }
+    for interface in &plan.interfaces {
+        validate_interface(interface)?;
+    }
+
+    let mark = format!("0x{:x}", plan.split.mark);
+    let mut table = String::from("table inet nyx_vpn {\n");
+
+    if state.split_active {
+        table.push_str("    chain route {\n        type route hook output priority mangle; policy accept;\n");
+        for cgroup in &state.split_cgroups {
+            apps::validate_cgroup(cgroup)?;
+            table.push_str(&format!(
+                "        socket cgroupv2 level {} \"{}\" meta mark set {}\n",
+                apps::level(cgroup), cgroup, mark
+            ));
+        }
+        for destination in &plan.split.destinations {
+            let net: IpNetwork = destination
+                .parse()
+                .map_err(|_| anyhow!("Invalid split tunnel destination {:?}", destination))?;
+            let family = if net.is_ipv4() { "ip" } else { "ip6" };
+            table.push_str(&format!("        {} daddr {} meta mark set {}\n", family, net, mark));
+        }
+        table.push_str("    }\n");
+    }
+
+    if state.kill_switch_engaged {
+        table.push_str("    chain kill_switch {\n        type filter hook output priority 0; policy accept;\n");
+        let mut rules = vec!["oifname \"lo\" accept".to_string()];
+        if !plan.interfaces.is_empty() {
+            let names: Vec<String> = plan.interfaces.iter().map(|i| format!("\"{}\"", i)).collect();
+            rules.push(format!("oifname {{ {} }} accept", names.join(", ")));
+        }
+        for endpoint in &plan.endpoints {
+            let family = if endpoint.is_ipv4() { "ip" } else { "ip6" };
+            rules.push(format!("{} daddr {} udp dport {} accept", family, endpoint.ip(), endpoint.port()));
+            rules.push(format!("{} daddr {} tcp dport {} accept", family, endpoint.ip(), endpoint.port()));
+        }
+        rules.push("udp sport 68 udp dport 67 accept".to_string());
+        rules.push("udp sport 546 udp dport 547 accept".to_string());
+        rules.push("icmpv6 type { nd-router-solicit, nd-neighbor-solicit, nd-neighbor-advert } accept".to_string());
+        if plan.kill_switch.allow_lan {
+            rules.push(format!("ip daddr {{ {} }} accept", LAN_V4));
+            rules.push(format!("ip6 daddr {{ {} }} accept", LAN_V6));
+        }
+        if state.split_active {
+            // Excluded traffic may leave outside the tunnel; in include
+            // mode only the selected traffic is held to it
+            rules.push(match plan.split.mode {
+                SplitMode::Exclude => format!("meta mark {} accept", mark),
+                SplitMode::Include => format!("meta mark != {} accept", mark),
+            });
+        }
+        rules.push("counter reject comment \"kill-switch\"".to_string());
+
+        for rule in rules {
+            table.push_str(&format!("        {}\n", rule));
+        }
+        table.push_str("    }\n");
+    }
+
+    table.push('}');
+    Ok(Some(table))
+}
+
+fn install_routes(plan: &TunnelPlan) -> Result<()> {
+    let split = &plan.split;
+    let table = split.table.to_string();
+
+    let route: Vec<String> = match split.mode {
+        SplitMode::Include => vec!["dev".into(), plan.interfaces[0].clone()],
+        SplitMode::Exclude => physical_default_route(&plan.interfaces)?,
+    };
+
+    let mut args = vec!["route", "replace", "default"];
+    args.extend(route.iter().map(String::as_str));
+    args.extend(["table", &table]);
+    ip(&args)?;
+
+    // Replace rather than stack rules across re-applies
+    remove_rule(split);
+    let mark = format!("0x{:x}", split.mark);
+    ip(&["rule", "add", "priority", RULE_PRIORITY, "fwmark", &mark, "table", &table])?;
+    ip(&["-6", "rule", "add", "priority", RULE_PRIORITY, "fwmark", &mark, "table", &table]).ok();
+    Ok(())
+}
+
+fn remove_routes(split: &SplitTunnelConfig) {
+    remove_rule(split);
+    let table = split.table.to_string();
+    let _ = ip(&["route", "flush", "table", &table]);
+}
+
+fn remove_rule(split: &SplitTunnelConfig) {
+    let mark = format!("0x{:x}", split.mark);
+    let table = split.table.to_string();
+    while ip(&["rule", "del", "fwmark", &mark, "table", &table]).is_ok() {}
+    while ip(&["-6", "rule", "del", "fwmark", &mark, "table", &table]).is_ok() {}
+}
+
+/// `via GW dev IF` of the main table's default route that does not go
+/// through a tunnel
+fn physical_default_route(tunnels: &[String]) -> Result<Vec<String>> {
+    let output = ip(&["route", "show", "default"])?;
+    for line in output.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let field = |name: &str| {
+            parts.iter().position(|p| *p == name).and_then(|i| parts.get(i + 1)).copied()
+        };
+        let Some(dev) = field("dev") else { continue };
+        if tunnels.iter().any(|t| t == dev) {
+            continue;
+        }
+
+        let mut route = Vec::new();
+        if let Some(gateway) = field("via") {
+            route.extend(["via".to_string(), gateway.to_string()]);
+        }
+        route.extend(["dev".to_string(), dev.to_string()]);
+        return Ok(route);
+    }
+    Err(anyhow!("No default route outside the tunnel to exclude traffic through"))
+}
+
+fn validate_interface(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 15
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    if !valid {
+        return Err(anyhow!("Invalid interface name {:?}", name));
+    }
+    Ok(())
+}
+
+fn ip(args: &[&str]) -> Result<String> {
+    let output = Command::new("ip").args(args).output()?;
+    if !output.status.success() {
+        return Err(anyhow!("ip {} failed: {}", args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
@@ -1,15 +1,31 @@
 //! VPN management (WireGuard)
 
-use crate::config::{VpnConfig, WireGuardConfig, WireGuardPeer};
+use crate::config::{KillSwitchConfig, SplitTunnelConfig, VpnConfig, WireGuardConfig, WireGuardPeer};
+use crate::tunnel::{self, TunnelPlan, TunnelState};
 use anyhow::{anyhow, Result};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::net::SocketAddr;
 use std::process::Command;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
 /// VPN manager
 pub struct VpnManager {
     config: RwLock<VpnConfig>,
     active_connections: RwLock<HashMap<String, VpnConnection>>,
+    /// VPNs the user wants up, whether or not their tunnel is
+    wanted: RwLock<BTreeSet<String>>,
+    guard: RwLock<TunnelState>,
+}
+
+/// Kill switch and split tunnel settings and what is in effect
+#[derive(Debug, Clone)]
+pub struct GuardStatus {
+    pub kill_switch: KillSwitchConfig,
+    pub split_tunnel: SplitTunnelConfig,
+    pub state: TunnelState,
+    pub wanted: Vec<String>,
+    pub blocked_packets: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -53,6 +69,8 @@ impl VpnManager {
         Self {
             config: RwLock::new(config),
             active_connections: RwLock::new(HashMap::new()),
+            wanted: RwLock::new(BTreeSet::new()),
+            guard: RwLock::new(TunnelState::default()),
         }
     }
 
@@ -61,14 +79,96 @@ impl VpnManager {
         let config = self.config.read().await;
 
         if let Some(ref wg) = config.wireguard {
+            // Wanted even if setup fails, so the kill switch holds
+            self.wanted.write().await.insert(wg.interface.clone());
             if let Err(e) = self.setup_wireguard(wg).await {
                 tracing::warn!("Failed to initialize WireGuard: {}", e);
             }
         }
+        drop(config);
 
+        self.apply_guard().await?;
         Ok(())
     }
 
+    /// Apply the kill switch and split tunnel for the current state
+    pub async fn apply_guard(&self) -> Result<TunnelState> {
+        let config = self.config.read().await.clone();
+
+        let mut interfaces = Vec::new();
+        let mut endpoints = config.kill_switch.endpoints.clone();
+        if let Some(ref wg) = config.wireguard {
+            interfaces.push(wg.interface.clone());
+            endpoints.extend(wg.peers.iter().filter_map(|p| p.endpoint.clone()));
+        }
+        interfaces.extend(config.kill_switch.interfaces.iter().cloned());
+
+        let plan = TunnelPlan {
+            endpoints: resolve_endpoints(&endpoints).await,
+            interfaces,
+            vpn_wanted: !self.wanted.read().await.is_empty(),
+            kill_switch: config.kill_switch,
+            split: config.split_tunnel,
+        };
+
+        let state = tunnel::apply(&plan)?;
+        *self.guard.write().await = state.clone();
+        Ok(state)
+    }
+
+    /// Turn the kill switch on or off
+    pub async fn set_kill_switch(&self, enabled: bool) -> Result<TunnelState> {
+        let previous = std::mem::replace(&mut self.config.write().await.kill_switch.enabled, enabled);
+        let result = self.apply_guard().await;
+        if result.is_err() {
+            self.config.write().await.kill_switch.enabled = previous;
+        }
+        result
+    }
+
+    /// Turn split tunneling on or off
+    pub async fn set_split_tunnel(&self, enabled: bool) -> Result<TunnelState> {
+        let previous = std::mem::replace(&mut self.config.write().await.split_tunnel.enabled, enabled);
+        let result = self.apply_guard().await;
+        if result.is_err() {
+            self.config.write().await.split_tunnel.enabled = previous;
+            // Put back whatever the previous settings installed
+            let _ = self.apply_guard().await;
+        }
+        result
+    }
+
+    pub async fn guard_status(&self) -> GuardStatus {
+        let config = self.config.read().await;
+        GuardStatus {
+            kill_switch: config.kill_switch.clone(),
+            split_tunnel: config.split_tunnel.clone(),
+            state: self.guard.read().await.clone(),
+            wanted: self.wanted.read().await.iter().cloned().collect(),
+            blocked_packets: tunnel::blocked_packets(),
+        }
+    }
+
+    /// Re-apply when binaries selected for split tunneling start or stop
+    pub async fn watch_split(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(crate::apps::RESCAN_SECS));
+        loop {
+            interval.tick().await;
+
+            let split = self.config.read().await.split_tunnel.clone();
+            if !split.enabled || split.binaries.is_empty() {
+                continue;
+            }
+            let state = self.guard.read().await.clone();
+            if !state.split_active || tunnel::split_cgroups(&split) == state.split_cgroups {
+                continue;
+            }
+            if let Err(e) = self.apply_guard().await {
+                tracing::warn!("Failed to update split tunnel: {}", e);
+            }
+        }
+    }
+
     /// Setup WireGuard interface
     async fn setup_wireguard(&self, config: &WireGuardConfig) -> Result<()> {
         let interface = &config.interface;
@@ -180,8 +280,13 @@ impl VpnManager {
 
         if let Some(ref wg) = config.wireguard {
             if wg.interface == name {
-                self.setup_wireguard(wg).await?;
-                return Ok(());
+                self.wanted.write().await.insert(name.to_string());
+                let result = self.setup_wireguard(wg).await;
+                drop(config);
+                if let Err(e) = self.apply_guard().await {
+                    tracing::warn!("Failed to apply VPN kill switch: {}", e);
+                }
+                return result;
             }
         }
 
@@ -202,6 +307,12 @@ impl VpnManager {
 
         self.active_connections.write().await.remove(name);
         tracing::info!("Disconnected VPN: {}", name);
+
+        // A deliberate disconnect lifts the kill switch
+        self.wanted.write().await.remove(name);
+        if let Err(e) = self.apply_guard().await {
+            tracing::warn!("Failed to update VPN kill switch: {}", e);
+        }
         Ok(())
     }
 
@@ -345,3 +456,15 @@ impl VpnManager {
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 }
+
+/// Resolve `host:port` endpoints; unresolvable ones are skipped
+async fn resolve_endpoints(endpoints: &[String]) -> Vec<SocketAddr> {
+    let mut resolved = Vec::new();
+    for endpoint in endpoints {
+        match tokio::net::lookup_host(endpoint.as_str()).await {
+            Ok(addrs) => resolved.extend(addrs),
+            Err(e) => tracing::warn!("Cannot resolve VPN endpoint {}: {}", endpoint, e),
+        }
+    }
+    resolved
+}
@@ -9,6 +9,9 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use tracing::{debug, info, warn};
 
+/// cpu.weight of a cgroup nobody has tuned
+pub const DEFAULT_CPU_WEIGHT: u32 = 100;
+
 /// Cgroup manager for v2 cgroups
 pub struct CgroupManager {
     /// Configuration
@@ -143,6 +146,31 @@ impl CgroupManager {
         Ok(())
     }
 
+    /// CPU weight of a process's cgroup; the default when it has none
+    pub fn cpu_weight(&self, pid: u32) -> u32 {
+        let path = self.root_path.join(format!("pid_{}", pid)).join("cpu.weight");
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|w| w.trim().parse().ok())
+            .unwrap_or(DEFAULT_CPU_WEIGHT)
+    }
+
+    /// Set the CPU weight of a process, giving it a cgroup if needed
+    pub fn set_cpu_weight(&self, pid: u32, weight: u32) -> Result<()> {
+        if !self.available {
+            return Ok(());
+        }
+
+        let cgroup_name = format!("pid_{}", pid);
+        let cgroup_path = self.create_cgroup(&cgroup_name)?;
+        self.add_process(&cgroup_name, pid)?;
+
+        fs::write(cgroup_path.join("cpu.weight"), weight.clamp(1, 10000).to_string())
+            .context("Failed to set CPU weight")?;
+        debug!("Set CPU weight of pid {} to {}", pid, weight);
+        Ok(())
+    }
+
     /// Apply resource limits to a process via cgroups
     pub fn apply_limits(&self, pid: u32, limits: &ResourceLimits) -> Result<()> {
         if !self.available {
@@ -23,6 +23,10 @@ pub struct ArchonConfig {
     /// Statistics collection configuration
     #[serde(default)]
     pub stats: StatsConfig,
+
+    /// Process class tuning per slumber power profile
+    #[serde(default)]
+    pub power: PowerPolicyConfig,
 }
 
 impl Default for ArchonConfig {
@@ -32,6 +36,7 @@ impl Default for ArchonConfig {
             resources: ResourceConfig::default(),
             cgroups: CgroupConfig::default(),
             stats: StatsConfig::default(),
+            power: PowerPolicyConfig::default(),
         }
     }
 }
@@ -366,6 +371,100 @@ fn default_controllers() -> Vec<String> {
     ]
 }
 
+/// Power-profile-driven scheduling configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerPolicyConfig {
+    /// Follow slumber's power profile
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Slumber socket path
+    #[serde(default = "default_slumber_socket")]
+    pub slumber_socket: String,
+
+    /// Process classes (first match wins)
+    #[serde(default = "default_process_classes")]
+    pub classes: Vec<ProcessClass>,
+
+    /// Class adjustments per power profile
+    #[serde(default = "default_power_policies")]
+    pub policies: Vec<PowerPolicy>,
+}
+
+impl Default for PowerPolicyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            slumber_socket: default_slumber_socket(),
+            classes: default_process_classes(),
+            policies: default_power_policies(),
+        }
+    }
+}
+
+fn default_slumber_socket() -> String {
+    "/run/slumber/slumber.sock".into()
+}
+
+/// A class of processes, matched by resource profile, name or executable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessClass {
+    /// Class name
+    pub name: String,
+    /// Resource profiles in this class
+    #[serde(default)]
+    pub resource_profiles: Vec<String>,
+    /// Process names in this class
+    #[serde(default)]
+    pub names: Vec<String>,
+    /// Executable paths in this class
+    #[serde(default)]
+    pub executables: Vec<String>,
+}
+
+/// Adjustments applied while a power profile is active
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerPolicy {
+    /// Slumber profile name
+    pub profile: String,
+    /// Per-class adjustments
+    #[serde(default)]
+    pub adjustments: Vec<ClassAdjustment>,
+}
+
+/// Scheduling changes for one class
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassAdjustment {
+    /// Class name
+    pub class: String,
+    /// Nice value (-20 to 19)
+    #[serde(default)]
+    pub nice: Option<i32>,
+    /// cgroup cpu.weight (1 to 10000, 100 = default)
+    #[serde(default)]
+    pub cpu_weight: Option<u32>,
+}
+
+fn default_process_classes() -> Vec<ProcessClass> {
+    vec![ProcessClass {
+        name: "background".into(),
+        resource_profiles: vec!["minimal".into()],
+        names: Vec::new(),
+        executables: Vec::new(),
+    }]
+}
+
+fn default_power_policies() -> Vec<PowerPolicy> {
+    vec![PowerPolicy {
+        profile: "powersave".into(),
+        adjustments: vec![ClassAdjustment {
+            class: "background".into(),
+            nice: Some(10),
+            cpu_weight: Some(25),
+        }],
+    }]
+}
+
 /// Statistics collection configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StatsConfig {
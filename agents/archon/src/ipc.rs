@@ -5,6 +5,7 @@
 use crate::orchestrator::Orchestrator;
 use crate::process::{ProcessInfo, ProcessState, SpawnRequest, StdioConfig};
 use anyhow::{Context, Result};
+use libnyx_ipc::{trace, Hello};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -33,6 +34,9 @@ pub enum ArchonRequest {
         #[serde(default)]
         capabilities: Vec<String>,
         sandbox: Option<String>,
+        /// Wraith network namespace to run in
+        #[serde(default)]
+        network_namespace: Option<String>,
     },
     /// Get process info
     GetProcess {
@@ -82,6 +86,8 @@ pub enum ArchonRequest {
     GetSystemResources,
     /// List resource profiles
     ListResourceProfiles,
+    /// Get power profile policy state
+    GetPowerPolicies,
     /// Get Archon status
     Status,
 }
@@ -126,6 +132,10 @@ pub enum ArchonResponse {
     ResourceProfiles {
         profiles: Vec<String>,
     },
+    /// Power profile policy state
+    PowerPolicies {
+        status: crate::power::PowerPolicyStatus,
+    },
     /// Archon status
     Status {
         version: String,
@@ -248,6 +258,8 @@ impl ArchonServer {
         let mut reader = BufReader::new(reader);
         let mut line = String::new();
 
+        let hello = Hello::new("archon", env!("CARGO_PKG_VERSION"));
+
         loop {
             line.clear();
             let bytes_read = reader.read_line(&mut line).await?;
@@ -255,6 +267,11 @@ impl ArchonServer {
                 break;
             }
 
+            if let Some(reply) = hello.respond(&line) {
+                writer.write_all((reply + "\n").as_bytes()).await?;
+                continue;
+            }
+
             let request: ArchonRequest = match serde_json::from_str(&line) {
                 Ok(req) => req,
                 Err(e) => {
@@ -267,7 +284,7 @@ impl ArchonServer {
                 }
             };
 
-            let response = Self::handle_request(request, &orchestrator, start_time).await;
+            let response = trace::traced(&line, Self::handle_request(request, &orchestrator, start_time)).await;
             let json = serde_json::to_string(&response)? + "\n";
             writer.write_all(json.as_bytes()).await?;
         }
@@ -291,6 +308,7 @@ impl ArchonServer {
                 resource_profile,
                 capabilities,
                 sandbox,
+                network_namespace,
             } => {
                 let spawn_request = SpawnRequest {
                     name,
@@ -303,6 +321,7 @@ impl ArchonServer {
                     capabilities,
                     sandbox,
                     parent_id: None,
+                    network_namespace,
                     stdin: StdioConfig::Null,
                     stdout: StdioConfig::Null,
                     stderr: StdioConfig::Null,
@@ -438,6 +457,11 @@ impl ArchonServer {
                 ArchonResponse::ResourceProfiles { profiles }
             }
 
+            ArchonRequest::GetPowerPolicies => {
+                let status = orchestrator.power_policies().await;
+                ArchonResponse::PowerPolicies { status }
+            }
+
             ArchonRequest::Status => {
                 ArchonResponse::Status {
                     version: env!("CARGO_PKG_VERSION").to_string(),
@@ -471,6 +495,7 @@ mod tests {
             resource_profile: Some("standard".into()),
             capabilities: vec!["filesystem:read".into()],
             sandbox: None,
+            network_namespace: Some("vpn-only".into()),
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -15,6 +15,7 @@
 //! - **Process Groups**: Manage related processes together
 //! - **Guardian Integration**: Capability checks before process actions
 //! - **Statistics**: Real-time process monitoring and metrics
+//! - **Power Policies**: Process class tuning per slumber power profile
 //!
 //! ## Architecture
 //!
@@ -51,6 +52,7 @@ mod resource;
 mod cgroup;
 mod stats;
 mod orchestrator;
+mod power;
 mod ipc;
 
 use anyhow::Result;
@@ -114,11 +116,19 @@ async fn main() -> Result<()> {
         stats::StatsCollector::new(&config.stats, process_manager.clone())?
     );
 
+    // Initialize power policies
+    let power_policies = Arc::new(power::PowerPolicies::new(
+        &config.power,
+        process_manager.clone(),
+        cgroup_manager.clone(),
+    ));
+
     // Create orchestrator
     let orchestrator = Arc::new(orchestrator::Orchestrator::new(
         process_manager.clone(),
         resource_manager.clone(),
         stats_collector.clone(),
+        power_policies,
         args.guardian_socket.clone(),
     ).await?);
 
@@ -2,6 +2,7 @@
 //!
 //! Coordinates process management with Guardian security checks.
 
+use crate::power::{PowerPolicies, PowerPolicyStatus};
 use crate::process::{ProcessInfo, ProcessManager, ProcessState, SpawnRequest};
 use crate::resource::ResourceManager;
 use crate::stats::StatsCollector;
@@ -24,6 +25,8 @@ pub struct Orchestrator {
     resource_manager: Arc<RwLock<ResourceManager>>,
     /// Stats collector
     stats_collector: Arc<StatsCollector>,
+    /// Power profile policies
+    power_policies: Arc<PowerPolicies>,
     /// Guardian client
     guardian_client: RwLock<Option<GuardianClient>>,
     /// Guardian socket path
@@ -38,6 +41,7 @@ impl Orchestrator {
         process_manager: Arc<RwLock<ProcessManager>>,
         resource_manager: Arc<RwLock<ResourceManager>>,
         stats_collector: Arc<StatsCollector>,
+        power_policies: Arc<PowerPolicies>,
         guardian_socket: PathBuf,
     ) -> Result<Self> {
         // Try to connect to Guardian
@@ -59,6 +63,7 @@ impl Orchestrator {
             process_manager,
             resource_manager,
             stats_collector,
+            power_policies,
             guardian_client: RwLock::new(guardian_client),
             guardian_socket,
             guardian_enabled: true,
@@ -238,6 +243,11 @@ impl Orchestrator {
         self.stats_collector.aggregate(duration_secs).await
     }
 
+    /// Get power policy state
+    pub async fn power_policies(&self) -> PowerPolicyStatus {
+        self.power_policies.status().await
+    }
+
     /// Run background tasks
     pub async fn run_background_tasks(&self) -> Result<()> {
         let pm = self.process_manager.clone();
@@ -274,6 +284,12 @@ impl Orchestrator {
             }
         });
 
+        // Power profile follower
+        let power = self.power_policies.clone();
+        tokio::spawn(async move {
+            power.run().await;
+        });
+
         info!("Background tasks started");
         Ok(())
     }
@@ -0,0 +1,362 @@
+//! Power-aware scheduling
+//!
+//! Follows slumber's power profile and retunes process classes for it.
+//! Each policy names a profile and the nice value and cgroup CPU weight
+//! its classes get while it is active, e.g. background work niced down
+//! on battery. A switch reaches every managed process or, if any write
+//! fails, none of them; processes return to their own settings once no
+//! policy covers them.
+
+use crate::cgroup::CgroupManager;
+use crate::config::{ClassAdjustment, PowerPolicyConfig};
+use crate::process::{ProcessInfo, ProcessManager, ProcessState};
+use anyhow::{Context, Result};
+use libnyx_ipc::PowerClient;
+use nix::errno::Errno;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::{interval, sleep, Duration};
+use tracing::{debug, info, warn};
+
+/// How often processes spawned since the last switch are picked up
+const RESCAN_INTERVAL: Duration = Duration::from_secs(10);
+/// Wait before resubscribing after slumber goes away
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Scheduling settings Archon controls
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Settings {
+    pub nice: i32,
+    pub cpu_weight: u32,
+}
+
+/// A process currently running with adjusted settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdjustedProcess {
+    pub pid: u32,
+    pub name: String,
+    pub class: String,
+    pub settings: Settings,
+    /// What it goes back to
+    pub original: Settings,
+}
+
+/// Power policy state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerPolicyStatus {
+    /// Power profile last applied
+    pub profile: Option<String>,
+    pub adjusted: Vec<AdjustedProcess>,
+}
+
+#[derive(Default)]
+struct PolicyState {
+    profile: Option<String>,
+    /// Settings before the first adjustment, by pid
+    originals: HashMap<u32, Settings>,
+    /// Settings currently applied, by pid
+    applied: HashMap<u32, (String, Settings)>,
+}
+
+/// One process's change within a switch
+struct Change {
+    pid: u32,
+    class: Option<String>,
+    original: Settings,
+    from: Settings,
+    to: Settings,
+}
+
+/// Applies power policies to managed processes
+pub struct PowerPolicies {
+    config: PowerPolicyConfig,
+    process_manager: Arc<RwLock<ProcessManager>>,
+    cgroup_manager: Arc<CgroupManager>,
+    state: Mutex<PolicyState>,
+}
+
+impl PowerPolicies {
+    /// Create power policies
+    pub fn new(
+        config: &PowerPolicyConfig,
+        process_manager: Arc<RwLock<ProcessManager>>,
+        cgroup_manager: Arc<CgroupManager>,
+    ) -> Self {
+        Self {
+            config: config.clone(),
+            process_manager,
+            cgroup_manager,
+            state: Mutex::new(PolicyState::default()),
+        }
+    }
+
+    /// Class a process belongs to
+    pub fn classify(&self, info: &ProcessInfo) -> Option<&str> {
+        self.config
+            .classes
+            .iter()
+            .find(|class| {
+                info.resource_profile.as_ref().is_some_and(|p| class.resource_profiles.contains(p))
+                    || class.names.contains(&info.name)
+                    || class.executables.iter().any(|e| Path::new(e) == info.executable)
+            })
+            .map(|class| class.name.as_str())
+    }
+
+    fn adjustment(&self, profile: &str, class: &str) -> Option<&ClassAdjustment> {
+        self.config
+            .policies
+            .iter()
+            .find(|policy| policy.profile == profile)?
+            .adjustments
+            .iter()
+            .find(|adjustment| adjustment.class == class)
+    }
+
+    /// Follow slumber's profile switches until Archon exits
+    pub async fn run(&self) {
+        if !self.config.enabled || self.config.policies.is_empty() {
+            return;
+        }
+
+        let client = PowerClient::with_socket(&self.config.slumber_socket);
+        loop {
+            match client.subscribe_profiles().await {
+                Ok(mut events) => {
+                    let mut rescan = interval(RESCAN_INTERVAL);
+                    loop {
+                        tokio::select! {
+                            event = events.next() => match event {
+                                Ok(profiles) => self.switch(&profiles.current).await,
+                                Err(e) => {
+                                    warn!("Lost slumber profile subscription: {}", e);
+                                    break;
+                                }
+                            },
+                            _ = rescan.tick() => {
+                                let profile = self.state.lock().await.profile.clone();
+                                if let Some(profile) = profile {
+                                    self.switch(&profile).await;
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(e) => debug!("Slumber unavailable: {}", e),
+            }
+            sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    async fn switch(&self, profile: &str) {
+        match self.apply(profile).await {
+            Ok(0) => {}
+            Ok(changed) => info!("Retuned {} processes for power profile {}", changed, profile),
+            Err(e) => warn!("Power profile {} not applied, keeping previous settings: {:#}", profile, e),
+        }
+    }
+
+    /// Retune every managed process for `profile`, all or nothing;
+    /// returns how many changed
+    pub async fn apply(&self, profile: &str) -> Result<usize> {
+        let processes = self.process_manager.read().await.list_by_state(ProcessState::Running);
+        let mut state = self.state.lock().await;
+
+        let running: HashSet<u32> = processes.iter().map(|p| p.pid).collect();
+        state.originals.retain(|pid, _| running.contains(pid));
+        state.applied.retain(|pid, _| running.contains(pid));
+
+        let mut changes = Vec::new();
+        for info in &processes {
+            let class = self.classify(info);
+            let adjustment = class.and_then(|class| self.adjustment(profile, class));
+            let original = match state.originals.get(&info.pid) {
+                Some(original) => *original,
+                None if adjustment.is_some() => match self.read(info.pid) {
+                    Ok(settings) => settings,
+                    Err(e) => {
+                        debug!("Skipping pid {}: {}", info.pid, e);
+                        continue;
+                    }
+                },
+                // Never adjusted and nothing to do
+                None => continue,
+            };
+
+            let to = Settings {
+                nice: adjustment.and_then(|a| a.nice).unwrap_or(original.nice),
+                cpu_weight: adjustment.and_then(|a| a.cpu_weight).unwrap_or(original.cpu_weight),
+            };
+            let from = state.applied.get(&info.pid).map(|(_, s)| *s).unwrap_or(original);
+            if to != from {
+                changes.push(Change {
+                    pid: info.pid,
+                    class: class.map(String::from),
+                    original,
+                    from,
+                    to,
+                });
+            }
+        }
+
+        let mut done: Vec<&Change> = Vec::new();
+        for change in &changes {
+            if let Err(e) = self.write(change.pid, change.from, change.to) {
+                if !process_exists(change.pid) {
+                    continue;
+                }
+                for change in done.iter().rev() {
+                    if let Err(e) = self.write(change.pid, change.to, change.from) {
+                        warn!("Failed to roll back pid {}: {}", change.pid, e);
+                    }
+                }
+                return Err(e.context(format!("Failed to retune pid {}", change.pid)));
+            }
+            done.push(change);
+        }
+
+        let changed = done.len();
+        for change in changes {
+            if change.to == change.original {
+                state.originals.remove(&change.pid);
+                state.applied.remove(&change.pid);
+            } else {
+                state.originals.entry(change.pid).or_insert(change.original);
+                state
+                    .applied
+                    .insert(change.pid, (change.class.unwrap_or_default(), change.to));
+            }
+        }
+        state.profile = Some(profile.to_string());
+        Ok(changed)
+    }
+
+    /// Current profile and adjusted processes
+    pub async fn status(&self) -> PowerPolicyStatus {
+        let pm = self.process_manager.read().await;
+        let state = self.state.lock().await;
+        let mut adjusted: Vec<AdjustedProcess> = state
+            .applied
+            .iter()
+            .map(|(pid, (class, settings))| AdjustedProcess {
+                pid: *pid,
+                name: pm.get_by_pid(*pid).map(|p| p.name).unwrap_or_default(),
+                class: class.clone(),
+                settings: *settings,
+                original: state.originals.get(pid).copied().unwrap_or(*settings),
+            })
+            .collect();
+        adjusted.sort_by_key(|p| p.pid);
+
+        PowerPolicyStatus {
+            profile: state.profile.clone(),
+            adjusted,
+        }
+    }
+
+    fn read(&self, pid: u32) -> Result<Settings> {
+        Errno::clear();
+        // SAFETY: getpriority has no memory effects
+        let nice = unsafe { libc::getpriority(libc::PRIO_PROCESS, pid as libc::id_t) };
+        if nice == -1 && Errno::last_raw() != 0 {
+            return Err(std::io::Error::last_os_error()).context("Failed to read nice value");
+        }
+
+        Ok(Settings {
+            nice,
+            cpu_weight: self.cgroup_manager.cpu_weight(pid),
+        })
+    }
+
+    /// Move one process from `from` to `to`, leaving it at `from` on failure
+    fn write(&self, pid: u32, from: Settings, to: Settings) -> Result<()> {
+        if from.nice != to.nice {
+            set_nice(pid, to.nice)?;
+        }
+        if from.cpu_weight != to.cpu_weight {
+            if let Err(e) = self.cgroup_manager.set_cpu_weight(pid, to.cpu_weight) {
+                if from.nice != to.nice {
+                    let _ = set_nice(pid, from.nice);
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Renice every thread of a process; setpriority only covers one
+fn set_nice(pid: u32, nice: i32) -> Result<()> {
+    let nice = nice.clamp(-20, 19);
+    let tids: Vec<u32> = std::fs::read_dir(format!("/proc/{}/task", pid))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok()?.file_name().to_str()?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_else(|_| vec![pid]);
+
+    for tid in tids {
+        // SAFETY: setpriority has no memory effects
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, nice) } != 0 {
+            let err = std::io::Error::last_os_error();
+            // Threads exit while we walk them
+            if err.raw_os_error() != Some(libc::ESRCH) {
+                return Err(err).with_context(|| format!("Failed to set nice of thread {}", tid));
+            }
+        }
+    }
+    debug!("Set nice of pid {} to {}", pid, nice);
+    Ok(())
+}
+
+fn process_exists(pid: u32) -> bool {
+    Path::new(&format!("/proc/{}", pid)).exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CgroupConfig, ProcessConfig, ResourceConfig};
+    use crate::resource::ResourceManager;
+    use std::path::PathBuf;
+
+    #[tokio::test]
+    async fn test_classify_by_resource_profile() {
+        let cgroups = Arc::new(CgroupManager::new(&CgroupConfig { enabled: false, ..CgroupConfig::default() }).unwrap());
+        let resources = Arc::new(RwLock::new(ResourceManager::new(&ResourceConfig::default(), cgroups.clone()).unwrap()));
+        let processes = Arc::new(RwLock::new(ProcessManager::new(&ProcessConfig::default(), resources).unwrap()));
+        let policies = PowerPolicies::new(&PowerPolicyConfig::default(), processes, cgroups);
+
+        let mut info = ProcessInfo {
+            id: uuid::Uuid::new_v4(),
+            pid: 42,
+            name: "indexer".into(),
+            executable: PathBuf::from("/usr/bin/indexer"),
+            args: Vec::new(),
+            cwd: None,
+            env: HashMap::new(),
+            user: "user".into(),
+            state: ProcessState::Running,
+            parent_id: None,
+            resource_profile: Some("minimal".into()),
+            cgroup_path: None,
+            capabilities: Vec::new(),
+            network_namespace: None,
+            created_at: chrono::Utc::now(),
+            started_at: None,
+            exited_at: None,
+            exit_code: None,
+            exit_signal: None,
+        };
+        assert_eq!(policies.classify(&info), Some("background"));
+        assert_eq!(policies.adjustment("powersave", "background").and_then(|a| a.nice), Some(10));
+        assert!(policies.adjustment("balanced", "background").is_none());
+
+        info.resource_profile = Some("standard".into());
+        assert_eq!(policies.classify(&info), None);
+    }
+}
@@ -7,11 +7,13 @@ use crate::resource::ResourceManager;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use dashmap::DashMap;
+use libnyx_ipc::network::{join_namespace, NetworkClient};
 use nix::sys::signal::{self, Signal};
 use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
 use nix::unistd::Pid;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::os::fd::AsRawFd;
 use std::path::PathBuf;
 use std::process::Stdio;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -70,6 +72,9 @@ pub struct ProcessInfo {
     pub cgroup_path: Option<String>,
     /// Capabilities granted
     pub capabilities: Vec<String>,
+    /// Network namespace it runs in
+    #[serde(default)]
+    pub network_namespace: Option<String>,
     /// Creation time
     pub created_at: DateTime<Utc>,
     /// Start time
@@ -108,6 +113,9 @@ pub struct SpawnRequest {
     pub sandbox: Option<String>,
     /// Parent process ID
     pub parent_id: Option<Uuid>,
+    /// Wraith network namespace to run in
+    #[serde(default)]
+    pub network_namespace: Option<String>,
     /// Stdin handling
     #[serde(default)]
     pub stdin: StdioConfig,
@@ -210,6 +218,7 @@ impl ProcessManager {
             resource_profile: request.resource_profile.clone(),
             cgroup_path: None,
             capabilities: request.capabilities.clone(),
+            network_namespace: request.network_namespace.clone(),
             created_at: Utc::now(),
             started_at: None,
             exited_at: None,
@@ -249,8 +258,28 @@ impl ProcessManager {
             StdioConfig::Null => Stdio::null(),
         });
 
+        // Join the network namespace between fork and exec; the namespace
+        // file is opened here so the child only makes the one syscall
+        let netns = match &request.network_namespace {
+            Some(name) => Some(
+                NetworkClient::new()
+                    .open_namespace(name)
+                    .await
+                    .with_context(|| format!("Network namespace {} unavailable", name))?,
+            ),
+            None => None,
+        };
+        if let Some(netns) = netns.as_ref().map(|f| f.as_raw_fd()) {
+            // SAFETY: join_namespace is async-signal-safe and touches no
+            // parent state
+            unsafe {
+                cmd.pre_exec(move || join_namespace(netns));
+            }
+        }
+
         // Spawn process
         let child = cmd.spawn().context("Failed to spawn process")?;
+        drop(netns);
 
         let pid = child.id().ok_or_else(|| anyhow::anyhow!("Failed to get PID"))?;
         info.pid = pid;
@@ -485,6 +514,7 @@ mod tests {
             capabilities: vec![],
             sandbox: None,
             parent_id: None,
+            network_namespace: None,
             stdin: StdioConfig::Null,
             stdout: StdioConfig::Null,
             stderr: StdioConfig::Null,
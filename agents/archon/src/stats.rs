@@ -271,7 +271,9 @@ impl StatsCollector {
 
             prev_times.insert(pid, (utime, stime));
 
-            let (memory_bytes, num_threads) = if let Ok(stat) = std::fs::read_to_string(&stat_path) {
+            let (memory_bytes, num_threads) = if let Some(usage) = native_memory(pid) {
+                usage
+            } else if let Ok(stat) = std::fs::read_to_string(&stat_path) {
                 let parts: Vec<&str> = stat.split_whitespace().collect();
                 let rss_pages: u64 = parts.get(23).and_then(|s| s.parse().ok()).unwrap_or(0);
                 let num_threads: u32 = parts.get(19).and_then(|s| s.parse().ok()).unwrap_or(1);
@@ -387,6 +389,19 @@ impl StatsCollector {
     }
 }
 
+/// Resident bytes and thread count from the Nyx kernel
+#[cfg(feature = "native")]
+fn native_memory(pid: u32) -> Option<(u64, u32)> {
+    let stat = libnyx::process::stat(Some(libnyx::process::ProcessId(pid as u64))).ok()?;
+    Some((stat.resident_bytes, stat.threads as u32))
+}
+
+/// Without the Nyx kernel, memory comes from /proc
+#[cfg(not(feature = "native"))]
+fn native_memory(_pid: u32) -> Option<(u64, u32)> {
+    None
+}
+
 /// Aggregate statistics over a time period
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AggregateStats {
@@ -0,0 +1,321 @@
+//! Signed persona bundles
+//!
+//! Exports are signed with this daemon's Ed25519 key, generated on first
+//! use. Imports must verify, and the signer must be this daemon or listed
+//! in the trust store (one hex public key per line, `#` comments) unless
+//! the caller explicitly accepts an untrusted bundle.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use base64::Engine;
+use grimoire_core::{
+    BundleContents, ImportConflict, ImportReport, MemoryScope, PersonaBundle, PersonaId, RitualId,
+    BUNDLE_FORMAT,
+};
+use ring::digest::{digest, SHA256};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use tracing::info;
+
+use crate::GrimoireDaemon;
+
+/// Domain separator for bundle signatures
+const BUNDLE_CONTEXT: &str = "grimoire-persona-bundle";
+
+/// Why an export or import failed
+#[derive(Debug, thiserror::Error)]
+pub enum BundleError {
+    #[error("Persona not found: {0}")]
+    NotFound(PersonaId),
+    #[error("Unsupported bundle format {0}")]
+    UnsupportedFormat(u32),
+    #[error("Malformed bundle: {0}")]
+    Malformed(String),
+    #[error("Bundle signature does not verify")]
+    BadSignature,
+    #[error("Bundle signer {0} is not trusted")]
+    Untrusted(String),
+    #[error("Persona already exists: {0}")]
+    Conflict(String),
+    #[error("Cannot replace built-in persona: {0}")]
+    Builtin(String),
+    #[error(transparent)]
+    Storage(#[from] anyhow::Error),
+}
+
+/// This daemon's signing key and the signers it trusts
+pub struct BundleKeys {
+    signing_key: Ed25519KeyPair,
+    trust_file: PathBuf,
+}
+
+impl BundleKeys {
+    /// Load the keys kept in `dir`, generating the signing key if missing
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        Ok(Self {
+            signing_key: load_signing_key(&dir.join("signing.key"))?,
+            trust_file: dir.join("trusted_keys"),
+        })
+    }
+
+    /// Sign bundle contents
+    pub fn sign(&self, contents: &BundleContents) -> anyhow::Result<PersonaBundle> {
+        let json = serde_json::to_vec(contents)?;
+        let contents = base64::engine::general_purpose::STANDARD.encode(json);
+        let signature = self.signing_key.sign(signed_message(&contents).as_bytes());
+        Ok(PersonaBundle {
+            format: BUNDLE_FORMAT,
+            contents,
+            signer: hex::encode(self.signing_key.public_key().as_ref()),
+            signature: hex::encode(signature.as_ref()),
+        })
+    }
+
+    /// Verify a bundle and decode its contents. Returns the contents, the
+    /// signer's fingerprint and whether the signer is trusted.
+    pub fn open(&self, bundle: &PersonaBundle) -> Result<(BundleContents, String, bool), BundleError> {
+        if bundle.format != BUNDLE_FORMAT {
+            return Err(BundleError::UnsupportedFormat(bundle.format));
+        }
+
+        let signer = hex::decode(&bundle.signer).map_err(|e| BundleError::Malformed(e.to_string()))?;
+        let signature = hex::decode(&bundle.signature).map_err(|e| BundleError::Malformed(e.to_string()))?;
+        UnparsedPublicKey::new(&ED25519, &signer)
+            .verify(signed_message(&bundle.contents).as_bytes(), &signature)
+            .map_err(|_| BundleError::BadSignature)?;
+
+        let json = base64::engine::general_purpose::STANDARD
+            .decode(&bundle.contents)
+            .map_err(|e| BundleError::Malformed(e.to_string()))?;
+        let contents = serde_json::from_slice(&json).map_err(|e| BundleError::Malformed(e.to_string()))?;
+
+        Ok((contents, key_id(&signer), self.is_trusted(&signer)))
+    }
+
+    fn is_trusted(&self, signer: &[u8]) -> bool {
+        if signer == self.signing_key.public_key().as_ref() {
+            return true;
+        }
+        let Ok(trusted) = std::fs::read_to_string(&self.trust_file) else {
+            return false;
+        };
+        trusted
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter_map(|key| hex::decode(key).ok())
+            .any(|key| key == signer)
+    }
+}
+
+/// Package a persona for sharing
+pub async fn export(
+    daemon: &GrimoireDaemon,
+    id: PersonaId,
+    include_memory: bool,
+    include_rituals: bool,
+) -> Result<PersonaBundle, BundleError> {
+    let persona = daemon
+        .persona_store
+        .get_persona(id)
+        .await
+        .ok_or(BundleError::NotFound(id))?;
+
+    // Session memory stays behind; only what the persona keeps travels
+    let memory = if include_memory && persona.privacy.memory_scope == MemoryScope::Persistent {
+        daemon.persona_store.get_memory(id).await.map(|mut memory| {
+            memory.short_term.clear();
+            memory
+        })
+    } else {
+        None
+    };
+    let rituals = if include_rituals {
+        daemon.ritual_store.read().await.list_persona_rituals(id)
+    } else {
+        Vec::new()
+    };
+
+    let contents = BundleContents {
+        persona,
+        memory,
+        rituals,
+        exported_at: chrono::Utc::now(),
+    };
+    Ok(daemon.bundle_keys.sign(&contents)?)
+}
+
+/// Verify a bundle and register what it carries
+pub async fn import(
+    daemon: &GrimoireDaemon,
+    bundle: &PersonaBundle,
+    on_conflict: ImportConflict,
+    allow_untrusted: bool,
+) -> Result<ImportReport, BundleError> {
+    let (contents, signer_key_id, trusted) = daemon.bundle_keys.open(bundle)?;
+    if !trusted && !allow_untrusted {
+        return Err(BundleError::Untrusted(signer_key_id));
+    }
+
+    let store = &daemon.persona_store;
+    let mut persona = contents.persona;
+    let existing = match store.get_persona(persona.id).await {
+        Some(existing) => Some(existing),
+        None => store.get_persona_by_name(&persona.name).await,
+    };
+
+    let mut replaced = None;
+    match (existing, on_conflict) {
+        (None, _) => {}
+        (Some(existing), ImportConflict::Fail) => return Err(BundleError::Conflict(existing.name)),
+        (Some(existing), ImportConflict::Replace) => {
+            if existing.is_builtin() {
+                return Err(BundleError::Builtin(existing.name));
+            }
+            store.remove_persona(existing.id).await?;
+            persona.id = existing.id;
+            replaced = Some(existing.id);
+        }
+        (Some(_), ImportConflict::Rename) => {
+            persona.id = PersonaId::new();
+            persona.name = free_name(daemon, &persona.name).await;
+        }
+    }
+    let persona_id = store.register_persona(persona.clone()).await?;
+
+    let mut rituals = daemon.ritual_store.write().await;
+    let imported_rituals = contents.rituals.len();
+    for mut ritual in contents.rituals {
+        if let Some(current) = rituals.get_ritual(ritual.id) {
+            if replaced == Some(current.persona_id) {
+                rituals.remove_ritual(ritual.id).await?;
+            } else {
+                ritual.id = RitualId::new();
+            }
+        }
+        ritual.persona_id = persona_id;
+        rituals.register_ritual(ritual).await?;
+    }
+    drop(rituals);
+
+    let memory = contents.memory.is_some();
+    if let Some(mut imported) = contents.memory {
+        imported.persona_id = persona_id;
+        store.restore_memory(imported).await?;
+    }
+
+    info!(
+        "Imported persona {} ({}) signed by {}{}",
+        persona.name,
+        persona_id,
+        signer_key_id,
+        if trusted { "" } else { " (untrusted)" }
+    );
+
+    Ok(ImportReport {
+        persona_id,
+        name: persona.name,
+        signer_key_id,
+        trusted,
+        replaced: replaced.is_some(),
+        rituals: imported_rituals,
+        memory,
+    })
+}
+
+/// First of `name`, `name (2)`, `name (3)`, ... not taken
+async fn free_name(daemon: &GrimoireDaemon, name: &str) -> String {
+    let mut n = 2;
+    loop {
+        let candidate = format!("{} ({})", name, n);
+        if daemon.persona_store.get_persona_by_name(&candidate).await.is_none() {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+fn signed_message(contents: &str) -> String {
+    format!("{}:{}", BUNDLE_CONTEXT, contents)
+}
+
+fn key_id(public_key: &[u8]) -> String {
+    hex::encode(&digest(&SHA256, public_key).as_ref()[..8])
+}
+
+/// Load the bundle signing key, generating it on first use
+fn load_signing_key(path: &Path) -> anyhow::Result<Ed25519KeyPair> {
+    let pkcs8 = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let document = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                .map_err(|_| anyhow!("key generation failed"))?;
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            use std::os::unix::fs::OpenOptionsExt;
+            let mut file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .mode(0o600)
+                .open(path)?;
+            file.write_all(document.as_ref())?;
+            info!("Generated bundle signing key at {}", path.display());
+            document.as_ref().to_vec()
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    Ed25519KeyPair::from_pkcs8(&pkcs8).map_err(|e| anyhow!("invalid signing key {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grimoire_core::Persona;
+    use tempfile::tempdir;
+
+    fn contents() -> BundleContents {
+        BundleContents {
+            persona: Persona::new("Hecate"),
+            memory: None,
+            rituals: Vec::new(),
+            exported_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_sign_and_open() {
+        let dir = tempdir().unwrap();
+        let keys = BundleKeys::load(dir.path()).unwrap();
+        let bundle = keys.sign(&contents()).unwrap();
+
+        let (opened, _, trusted) = keys.open(&bundle).unwrap();
+        assert_eq!(opened.persona.name, "Hecate");
+        assert!(trusted);
+
+        let mut tampered = bundle.clone();
+        tampered.contents = keys.sign(&BundleContents { persona: Persona::new("Trivia"), ..contents() })
+            .unwrap()
+            .contents;
+        assert!(matches!(keys.open(&tampered), Err(BundleError::BadSignature)));
+    }
+
+    #[test]
+    fn test_trust_store() {
+        let ours = tempdir().unwrap();
+        let theirs = tempdir().unwrap();
+        let keys = BundleKeys::load(ours.path()).unwrap();
+        let bundle = BundleKeys::load(theirs.path()).unwrap().sign(&contents()).unwrap();
+
+        let (_, key_id, trusted) = keys.open(&bundle).unwrap();
+        assert!(!trusted);
+        assert_eq!(key_id.len(), 16);
+
+        std::fs::write(ours.path().join("trusted_keys"), format!("# marketplace\n{} # hecate\n", bundle.signer)).unwrap();
+        assert!(keys.open(&bundle).unwrap().2);
+    }
+}
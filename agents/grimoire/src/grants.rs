@@ -0,0 +1,101 @@
+//! Guardian execution tokens for persona-driven rituals
+//!
+//! A ritual runs with its persona's capabilities, not the user's. Before it
+//! starts, the persona's declared grants are turned into scopes and sent to
+//! Guardian, which issues a token covering the ones its policy allows.
+//! Steps check their actions against that token; it is revoked when the
+//! ritual ends. Without Guardian no token can be issued and rituals don't
+//! run.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use grimoire_core::{CapabilityScope, ExecutionToken, Persona, Ritual};
+use libnyx_ipc::guardian::{GuardianClient, TokenScope};
+use tracing::{debug, warn};
+
+/// Requests and revokes tokens from Guardian
+pub struct GrantIssuer {
+    guardian_socket: PathBuf,
+}
+
+impl GrantIssuer {
+    pub fn new(guardian_socket: PathBuf) -> Self {
+        Self { guardian_socket }
+    }
+
+    /// Request a token for `persona` running `ritual`
+    ///
+    /// The token lives as long as the ritual's timeout.
+    pub async fn issue(&self, persona: &Persona, ritual: &Ritual) -> Result<ExecutionToken> {
+        let scopes = persona
+            .capabilities
+            .scopes()
+            .into_iter()
+            .map(|scope| TokenScope {
+                capability: scope.capability,
+                resource: scope.resource,
+            })
+            .collect();
+        let user = std::env::var("USER").unwrap_or_else(|_| "root".to_string());
+
+        let mut client = self.connect().await?;
+        let issued = client
+            .issue_token(
+                format!("persona:{}", persona.name),
+                user,
+                scopes,
+                ritual.timeout_secs,
+            )
+            .await?;
+
+        let expires_at = chrono::DateTime::parse_from_rfc3339(&issued.expires_at)
+            .context("Guardian sent a bad token expiry")?
+            .with_timezone(&chrono::Utc);
+        if !issued.refused.is_empty() {
+            warn!(
+                "Guardian refused {} of {}'s scope(s) for ritual {}",
+                issued.refused.len(),
+                persona.name,
+                ritual.name
+            );
+        }
+        debug!("Ritual {} runs under token {}", ritual.name, issued.id);
+
+        Ok(ExecutionToken {
+            id: issued.id,
+            expires_at,
+            granted: issued.granted.into_iter().map(scope).collect(),
+            refused: issued.refused.into_iter().map(scope).collect(),
+        })
+    }
+
+    /// Revoke a token once its ritual has ended
+    ///
+    /// Failures are only logged; the token expires on its own.
+    pub async fn revoke(&self, token: &ExecutionToken) {
+        let result = match self.connect().await {
+            Ok(mut client) => client.revoke_token(token.id).await.map_err(Into::into),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            warn!("Failed to revoke token {}: {}", token.id, e);
+        }
+    }
+
+    async fn connect(&self) -> Result<GuardianClient> {
+        let mut client = GuardianClient::with_socket(&self.guardian_socket);
+        client
+            .connect_internal()
+            .await
+            .with_context(|| format!("Guardian unreachable at {:?}", self.guardian_socket))?;
+        Ok(client)
+    }
+}
+
+fn scope(scope: TokenScope) -> CapabilityScope {
+    CapabilityScope {
+        capability: scope.capability,
+        resource: scope.resource,
+    }
+}
@@ -0,0 +1,153 @@
+//! Replay protection for keyed writes
+//!
+//! Clients that queue writes while the daemon is down resend them on
+//! reconnect, and a write that reached us just before a restart may come
+//! again. Requests carrying an idempotency key are applied once: the
+//! successful response is remembered and returned for any repeat.
+//!
+//! Keys are appended to a JSON-lines log so they survive restarts. Only the
+//! newest [`CAPACITY`] are kept; the log is compacted once it holds twice
+//! that many.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use grimoire_core::GrimoireResponse;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Keys remembered
+pub const CAPACITY: usize = 4096;
+
+#[derive(Serialize, Deserialize)]
+struct Record {
+    key: Uuid,
+    response: GrimoireResponse,
+}
+
+struct Inner {
+    responses: HashMap<Uuid, GrimoireResponse>,
+    /// Keys oldest first
+    order: VecDeque<Uuid>,
+    /// Lines in the log file
+    logged: usize,
+}
+
+/// Responses to keyed writes already applied
+pub struct IdempotencyLog {
+    path: PathBuf,
+    inner: Mutex<Inner>,
+}
+
+impl IdempotencyLog {
+    /// Load the log at `path`, starting empty if there is none
+    pub fn open(path: &Path) -> Self {
+        let mut inner = Inner {
+            responses: HashMap::new(),
+            order: VecDeque::new(),
+            logged: 0,
+        };
+
+        if let Ok(text) = std::fs::read_to_string(path) {
+            for line in text.lines() {
+                inner.logged += 1;
+                match serde_json::from_str::<Record>(line) {
+                    Ok(record) => inner.insert(record.key, record.response),
+                    Err(e) => warn!("Skipping bad idempotency record: {}", e),
+                }
+            }
+        }
+
+        Self {
+            path: path.to_path_buf(),
+            inner: Mutex::new(inner),
+        }
+    }
+
+    /// The response to an already applied key
+    pub async fn get(&self, key: Uuid) -> Option<GrimoireResponse> {
+        self.inner.lock().await.responses.get(&key).cloned()
+    }
+
+    /// Remember the response to a key
+    ///
+    /// Only successes are kept: a write that failed may be retried.
+    pub async fn record(&self, key: Uuid, response: &GrimoireResponse) {
+        if !matches!(response, GrimoireResponse::Success { .. }) {
+            return;
+        }
+
+        let mut inner = self.inner.lock().await;
+        inner.insert(key, response.clone());
+
+        let result = if inner.logged >= 2 * CAPACITY {
+            self.compact(&inner).map(|()| inner.logged = inner.order.len())
+        } else {
+            self.append(&Record { key, response: response.clone() })
+                .map(|()| inner.logged += 1)
+        };
+        if let Err(e) = result {
+            warn!("Failed to write idempotency log {:?}: {}", self.path, e);
+        }
+    }
+
+    fn append(&self, record: &Record) -> std::io::Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)
+    }
+
+    /// Rewrite the log with only the remembered keys
+    fn compact(&self, inner: &Inner) -> std::io::Result<()> {
+        let mut text = String::new();
+        for key in &inner.order {
+            let record = Record { key: *key, response: inner.responses[key].clone() };
+            text.push_str(&serde_json::to_string(&record)?);
+            text.push('\n');
+        }
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, text)?;
+        std::fs::rename(&tmp, &self.path)
+    }
+}
+
+impl Inner {
+    fn insert(&mut self, key: Uuid, response: GrimoireResponse) {
+        if self.responses.insert(key, response).is_none() {
+            self.order.push_back(key);
+        }
+        while self.order.len() > CAPACITY {
+            if let Some(old) = self.order.pop_front() {
+                self.responses.remove(&old);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_keys_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("idempotency.jsonl");
+        let key = Uuid::new_v4();
+
+        let log = IdempotencyLog::open(&path);
+        log.record(key, &GrimoireResponse::ok()).await;
+        log.record(Uuid::new_v4(), &GrimoireResponse::error(
+            grimoire_core::ErrorCode::Unavailable,
+            "locked",
+        )).await;
+
+        let reopened = IdempotencyLog::open(&path);
+        assert!(matches!(reopened.get(key).await, Some(GrimoireResponse::Success { .. })));
+        assert_eq!(reopened.inner.lock().await.order.len(), 1);
+    }
+}
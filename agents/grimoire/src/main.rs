@@ -6,8 +6,10 @@
 //!
 //! - **Persona Management**: Register, load, and manage AI personas
 //! - **Persona Memory**: Per-persona encrypted memory (via Cipher)
+//! - **Persona Bundles**: Signed export/import for sharing personas
 //! - **Ritual Execution**: Automated multi-step workflows
-//! - **Hierarchical Config**: System -> User -> App settings
+//! - **Model Routing**: Per-persona backend selection with fallbacks and budgets
+//! - **Hierarchical Config**: System -> User -> App settings, with system locks
 //! - **Live Reload**: Watch for changes and notify subscribers
 //! - **Schema Validation**: Validate settings against schemas
 //!
@@ -45,6 +47,11 @@ mod ipc;
 mod persona_store;
 mod persona_ipc;
 mod ritual_store;
+mod model_router;
+mod bundle;
+mod memory_keys;
+mod idempotency;
+mod grants;
 
 use anyhow::Result;
 use clap::Parser;
@@ -76,6 +83,18 @@ struct Args {
     /// Skip loading built-in personas
     #[arg(long)]
     no_builtin: bool,
+
+    /// Abaddon (local tensor runtime) socket
+    #[arg(long, default_value = "/run/infernum/abaddon.sock")]
+    abaddon_socket: PathBuf,
+
+    /// Malphas (model router) socket
+    #[arg(long, default_value = "/run/infernum/malphas.sock")]
+    malphas_socket: PathBuf,
+
+    /// Guardian socket, for ritual execution tokens
+    #[arg(long, default_value = libnyx_ipc::paths::GUARDIAN_SOCKET)]
+    guardian_socket: PathBuf,
 }
 
 /// Daemon state
@@ -84,10 +103,18 @@ pub struct GrimoireDaemon {
     pub persona_store: Arc<persona_store::PersonaStore>,
     /// Ritual store
     pub ritual_store: Arc<RwLock<ritual_store::RitualStore>>,
-    /// Settings store
-    pub settings_store: Arc<RwLock<store::SettingsStore>>,
+    /// Layered settings
+    pub settings_store: Arc<store::LayeredStore>,
     /// Schema registry
     pub schemas: Arc<schema::SchemaRegistry>,
+    /// Model backend routing and budgets
+    pub model_router: Arc<model_router::ModelRouter>,
+    /// Persona bundle signing and trust
+    pub bundle_keys: bundle::BundleKeys,
+    /// Keyed writes already applied
+    pub idempotency: idempotency::IdempotencyLog,
+    /// Guardian tokens for ritual runs
+    pub grants: grants::GrantIssuer,
     /// Start time
     pub started_at: std::time::Instant,
 }
@@ -101,12 +128,31 @@ impl GrimoireDaemon {
             ritual_count: self.ritual_store.read().await.ritual_count(),
             active_executions: self.ritual_store.read().await.active_execution_count(),
             uptime_secs: self.started_at.elapsed().as_secs(),
-            memory_bytes: 0, // TODO: Track memory usage
+            memory_bytes: resident_memory_bytes(),
             cipher_available: self.persona_store.cipher_available(),
         }
     }
 }
 
+/// Resident memory of this daemon, or 0 if unavailable
+#[cfg(feature = "native")]
+fn resident_memory_bytes() -> u64 {
+    libnyx::process::stat(None)
+        .map(|stat| stat.resident_bytes)
+        .unwrap_or(0)
+}
+
+/// Resident memory of this daemon, or 0 if unavailable
+#[cfg(not(feature = "native"))]
+fn resident_memory_bytes() -> u64 {
+    // statm: size resident shared text lib data dt, in pages
+    std::fs::read_to_string("/proc/self/statm")
+        .ok()
+        .and_then(|statm| statm.split_whitespace().nth(1)?.parse::<u64>().ok())
+        .map(|pages| pages * 4096)
+        .unwrap_or(0)
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
@@ -149,11 +195,13 @@ async fn main() -> Result<()> {
     ritual_store.write().await.init().await?;
     info!("Ritual store initialized: {} rituals", ritual_store.read().await.ritual_count());
 
-    // Initialize settings store
-    let settings_store = Arc::new(RwLock::new(
-        store::SettingsStore::new(args.base_dir.join("settings.yaml"))
+    // Initialize settings layers: system policy, the user, app namespaces
+    let settings_store = Arc::new(store::LayeredStore::new(
+        args.base_dir.join("settings.yaml"),
+        user_dir.join("settings.yaml"),
+        user_dir.join("apps"),
     ));
-    settings_store.write().await.load().await?;
+    settings_store.load().await?;
     info!("Settings store initialized");
 
     // Load schemas
@@ -168,7 +216,11 @@ async fn main() -> Result<()> {
     // Start file watcher for settings
     let settings_clone = settings_store.clone();
     let watcher = watcher::SettingsWatcher::new(
-        vec![args.base_dir.join("settings"), user_dir.join("settings")],
+        vec![
+            args.base_dir.clone(),
+            user_dir.clone(),
+            settings_store.apps_dir().to_path_buf(),
+        ],
         settings_clone,
     );
     if let Ok(w) = watcher {
@@ -177,12 +229,29 @@ async fn main() -> Result<()> {
         });
     }
 
+    // Probe model backends
+    let model_router = Arc::new(model_router::ModelRouter::new(
+        args.abaddon_socket,
+        args.malphas_socket,
+    ));
+    let router_clone = model_router.clone();
+    tokio::spawn(async move {
+        router_clone.run_health_checks().await;
+    });
+
+    // Load the persona bundle signing key
+    let bundle_keys = bundle::BundleKeys::load(&args.base_dir.join("bundles"))?;
+
     // Create daemon state
     let daemon = Arc::new(GrimoireDaemon {
         persona_store,
         ritual_store,
         settings_store,
         schemas,
+        model_router,
+        bundle_keys,
+        idempotency: idempotency::IdempotencyLog::open(&args.base_dir.join("idempotency.jsonl")),
+        grants: grants::GrantIssuer::new(args.guardian_socket),
         started_at: std::time::Instant::now(),
     });
 
@@ -0,0 +1,266 @@
+//! Persona memory encryption with keys held by cipher
+//!
+//! Each persona's memory file is sealed with AES-256-GCM under a key that
+//! lives in the user's cipher keyring (collection `grimoire`), fetched over
+//! a cipher session whenever a file is read or written. Nothing is cached,
+//! so locking the keyring makes memory read-only at once.
+//!
+//! Rotating a persona's key keeps the old one as `previous` until its file
+//! has been re-encrypted under the new one. Files carry the key version
+//! they were sealed with:
+//!
+//! ```text
+//! "GRMEM" 0x01 | key version (u32 LE) | nonce (12) | ciphertext + tag
+//! ```
+
+use grimoire_core::PersonaId;
+use libnyx_ipc::{Error as IpcError, SecretsClient};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+/// Cipher collection holding the memory keys
+const COLLECTION: &str = "grimoire";
+
+/// Header of an encrypted memory file
+const MAGIC: &[u8; 6] = b"GRMEM\x01";
+
+const HEADER_LEN: usize = MAGIC.len() + 4 + NONCE_LEN;
+
+/// Why memory couldn't be sealed or opened
+#[derive(Debug, thiserror::Error)]
+pub enum KeyError {
+    #[error("Cipher keyring is locked; persona memory is read-only until it is unlocked")]
+    Locked,
+    #[error("Cipher is unavailable: {0}")]
+    Unavailable(String),
+    #[error("No memory key for persona {0}")]
+    Missing(PersonaId),
+    #[error("Memory file does not decrypt: {0}")]
+    Corrupt(String),
+}
+
+impl From<IpcError> for KeyError {
+    fn from(e: IpcError) -> Self {
+        match e {
+            IpcError::RequestFailed(message) if message.contains("locked") => Self::Locked,
+            e => Self::Unavailable(e.to_string()),
+        }
+    }
+}
+
+/// One version of a persona's key
+#[derive(Clone, Serialize, Deserialize)]
+struct VersionedKey {
+    version: u32,
+    /// Hex AES-256 key
+    key: String,
+}
+
+/// What cipher stores for a persona
+#[derive(Clone, Serialize, Deserialize)]
+struct KeyRecord {
+    current: VersionedKey,
+    /// Key being rotated away from, until re-encryption finishes
+    #[serde(default)]
+    previous: Option<VersionedKey>,
+}
+
+/// Seals and opens persona memory with keys kept in cipher
+pub struct MemoryKeys {
+    client: SecretsClient,
+    rng: SystemRandom,
+}
+
+impl MemoryKeys {
+    pub fn new(client: SecretsClient) -> Self {
+        Self {
+            client,
+            rng: SystemRandom::new(),
+        }
+    }
+
+    /// Whether file contents are sealed (as opposed to legacy plaintext)
+    pub fn is_sealed(data: &[u8]) -> bool {
+        data.starts_with(MAGIC)
+    }
+
+    /// Check that Cipher answers and the keyring is unlocked
+    pub async fn probe(&self) -> Result<(), KeyError> {
+        self.client.lookup(COLLECTION, "memory-key:probe").await.map(drop).map_err(Into::into)
+    }
+
+    /// Fail with [`KeyError::Locked`] unless memory can be written now
+    pub async fn check_writable(&self, persona_id: PersonaId) -> Result<(), KeyError> {
+        self.record(persona_id).await.map(drop)
+    }
+
+    /// Encrypt memory under the persona's current key, creating the key
+    /// on first use
+    pub async fn seal(&self, persona_id: PersonaId, plaintext: &[u8]) -> Result<Vec<u8>, KeyError> {
+        let record = match self.record(persona_id).await? {
+            Some(record) => record,
+            None => {
+                let record = KeyRecord {
+                    current: self.generate(1)?,
+                    previous: None,
+                };
+                self.store(persona_id, &record).await?;
+                record
+            }
+        };
+
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng.fill(&mut nonce).map_err(|_| KeyError::Corrupt("no randomness".into()))?;
+        seal_with(&record.current, persona_id, nonce, plaintext)
+    }
+
+    /// Decrypt a sealed memory file
+    pub async fn open(&self, persona_id: PersonaId, sealed: &[u8]) -> Result<Vec<u8>, KeyError> {
+        let record = self.record(persona_id).await?.ok_or(KeyError::Missing(persona_id))?;
+        open_with(&record, persona_id, sealed)
+    }
+
+    /// Start rotating a persona's key; files sealed under the old key stay
+    /// readable until [`MemoryKeys::retire_previous`]. Returns the new
+    /// key version.
+    pub async fn rotate(&self, persona_id: PersonaId) -> Result<u32, KeyError> {
+        let record = self.record(persona_id).await?;
+        let (version, previous) = match record {
+            // A rotation that never finished keeps its older key: files
+            // sealed under it haven't been re-encrypted yet
+            Some(KeyRecord { current, previous: Some(previous) }) => (current.version + 1, previous),
+            Some(KeyRecord { current, previous: None }) => (current.version + 1, current),
+            None => return Err(KeyError::Missing(persona_id)),
+        };
+        let record = KeyRecord {
+            current: self.generate(version)?,
+            previous: Some(previous),
+        };
+        self.store(persona_id, &record).await?;
+        Ok(version)
+    }
+
+    /// Forget the key rotated away from, once nothing is sealed under it
+    pub async fn retire_previous(&self, persona_id: PersonaId) -> Result<(), KeyError> {
+        if let Some(mut record) = self.record(persona_id).await? {
+            if record.previous.take().is_some() {
+                self.store(persona_id, &record).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop a persona's key, e.g. when the persona is removed
+    pub async fn forget(&self, persona_id: PersonaId) -> Result<(), KeyError> {
+        Ok(self.client.delete(COLLECTION, &secret_id(persona_id)).await?)
+    }
+
+    async fn record(&self, persona_id: PersonaId) -> Result<Option<KeyRecord>, KeyError> {
+        match self.client.lookup(COLLECTION, &secret_id(persona_id)).await? {
+            Some(json) => serde_json::from_str(&json)
+                .map(Some)
+                .map_err(|e| KeyError::Corrupt(format!("key record: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    async fn store(&self, persona_id: PersonaId, record: &KeyRecord) -> Result<(), KeyError> {
+        let json = serde_json::to_string(record).map_err(|e| KeyError::Corrupt(e.to_string()))?;
+        let label = format!("Grimoire memory key ({})", persona_id);
+        Ok(self.client.store(COLLECTION, &secret_id(persona_id), &label, &json).await?)
+    }
+
+    fn generate(&self, version: u32) -> Result<VersionedKey, KeyError> {
+        let mut key = [0u8; 32];
+        self.rng.fill(&mut key).map_err(|_| KeyError::Corrupt("no randomness".into()))?;
+        Ok(VersionedKey {
+            version,
+            key: hex::encode(key),
+        })
+    }
+}
+
+/// Encrypt under one key
+fn seal_with(
+    key: &VersionedKey,
+    persona_id: PersonaId,
+    nonce: [u8; NONCE_LEN],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, KeyError> {
+    let mut sealed = Vec::with_capacity(HEADER_LEN + plaintext.len() + AES_256_GCM.tag_len());
+    sealed.extend_from_slice(MAGIC);
+    sealed.extend_from_slice(&key.version.to_le_bytes());
+    sealed.extend_from_slice(&nonce);
+
+    let mut body = plaintext.to_vec();
+    cipher_key(key)?
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), aad(persona_id), &mut body)
+        .map_err(|_| KeyError::Corrupt("encryption failed".into()))?;
+    sealed.extend_from_slice(&body);
+    Ok(sealed)
+}
+
+/// Decrypt with whichever of the record's keys the file names
+fn open_with(record: &KeyRecord, persona_id: PersonaId, sealed: &[u8]) -> Result<Vec<u8>, KeyError> {
+    if sealed.len() < HEADER_LEN || !MemoryKeys::is_sealed(sealed) {
+        return Err(KeyError::Corrupt("bad header".into()));
+    }
+    let version = u32::from_le_bytes(sealed[MAGIC.len()..MAGIC.len() + 4].try_into().unwrap());
+    let nonce: [u8; NONCE_LEN] = sealed[MAGIC.len() + 4..HEADER_LEN].try_into().unwrap();
+
+    let key = [Some(&record.current), record.previous.as_ref()]
+        .into_iter()
+        .flatten()
+        .find(|key| key.version == version)
+        .ok_or_else(|| KeyError::Corrupt(format!("sealed with unknown key version {}", version)))?;
+
+    let mut body = sealed[HEADER_LEN..].to_vec();
+    let plaintext = cipher_key(key)?
+        .open_in_place(Nonce::assume_unique_for_key(nonce), aad(persona_id), &mut body)
+        .map_err(|_| KeyError::Corrupt("authentication failed".into()))?;
+    Ok(plaintext.to_vec())
+}
+
+fn secret_id(persona_id: PersonaId) -> String {
+    format!("memory-key:{}", persona_id)
+}
+
+/// Binds a file to its persona, so files can't be swapped between them
+fn aad(persona_id: PersonaId) -> Aad<Vec<u8>> {
+    Aad::from(persona_id.to_string().into_bytes())
+}
+
+fn cipher_key(key: &VersionedKey) -> Result<LessSafeKey, KeyError> {
+    let bytes = hex::decode(&key.key).map_err(|e| KeyError::Corrupt(e.to_string()))?;
+    let key = UnboundKey::new(&AES_256_GCM, &bytes).map_err(|_| KeyError::Corrupt("bad key length".into()))?;
+    Ok(LessSafeKey::new(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(version: u32, byte: u8) -> VersionedKey {
+        VersionedKey {
+            version,
+            key: hex::encode([byte; 32]),
+        }
+    }
+
+    #[test]
+    fn test_seal_and_rotate() {
+        let persona = PersonaId::new();
+        let old = seal_with(&key(1, 0x11), persona, [1; NONCE_LEN], b"remember this").unwrap();
+        assert!(MemoryKeys::is_sealed(&old));
+
+        // Mid-rotation both versions open; afterwards only the new one
+        let rotating = KeyRecord { current: key(2, 0x22), previous: Some(key(1, 0x11)) };
+        assert_eq!(open_with(&rotating, persona, &old).unwrap(), b"remember this");
+        let rotated = KeyRecord { current: key(2, 0x22), previous: None };
+        assert!(matches!(open_with(&rotated, persona, &old), Err(KeyError::Corrupt(_))));
+
+        // Files are bound to their persona
+        assert!(open_with(&rotating, PersonaId::new(), &old).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! Persona model routing
+//!
+//! Resolves a persona's model configuration to the backend that should
+//! serve its next request: the first one in its chain (local model, remote
+//! model, then fallbacks) that is healthy and that the persona's privacy
+//! settings let it reach. Each persona's requests and tokens are counted
+//! against its budget over sliding windows.
+//!
+//! The local tensor runtime (Abaddon) and the Malphas router are probed on
+//! their sockets. Remote APIs can't be probed without leaking traffic, so
+//! they are marked down when a client reports a failed request and tried
+//! again after a cooldown.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use grimoire_core::{ModelBackend, ModelRoute, Persona, PersonaId};
+use libnyx_ipc::{HealthClient, HealthStatus};
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+/// How often the local runtime and Malphas are probed
+pub const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long a failed remote backend is skipped
+const REMOTE_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Window for `requests_per_minute`
+const REQUEST_WINDOW: Duration = Duration::from_secs(60);
+
+/// Window for `tokens_per_hour`
+const TOKEN_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Why no backend was picked
+#[derive(Debug, thiserror::Error)]
+pub enum RouteError {
+    #[error("{0} is over its model budget")]
+    BudgetExhausted(String),
+    #[error("No model backend available for {0}")]
+    NoBackend(String),
+}
+
+/// Last known state of a backend
+#[derive(Debug, Clone, Copy)]
+struct BackendHealth {
+    healthy: bool,
+    since: Instant,
+}
+
+/// A persona's recent model usage
+#[derive(Debug, Default)]
+struct Usage {
+    requests: VecDeque<Instant>,
+    tokens: VecDeque<(Instant, u64)>,
+}
+
+impl Usage {
+    /// Drop entries that fell out of their windows
+    fn prune(&mut self, now: Instant) {
+        while self.requests.front().is_some_and(|t| now.duration_since(*t) >= REQUEST_WINDOW) {
+            self.requests.pop_front();
+        }
+        while self.tokens.front().is_some_and(|(t, _)| now.duration_since(*t) >= TOKEN_WINDOW) {
+            self.tokens.pop_front();
+        }
+    }
+
+    fn tokens_used(&self) -> u64 {
+        self.tokens.iter().map(|(_, n)| n).sum()
+    }
+}
+
+/// Picks backends for persona model requests
+pub struct ModelRouter {
+    /// Abaddon (local tensor runtime) socket
+    abaddon_socket: PathBuf,
+    /// Malphas (model router) socket
+    malphas_socket: PathBuf,
+    /// Backend health, keyed by [`health_key`]
+    health: RwLock<HashMap<String, BackendHealth>>,
+    /// Usage per persona
+    usage: RwLock<HashMap<PersonaId, Usage>>,
+}
+
+impl ModelRouter {
+    /// Create a router probing the given daemon sockets
+    pub fn new(abaddon_socket: PathBuf, malphas_socket: PathBuf) -> Self {
+        Self {
+            abaddon_socket,
+            malphas_socket,
+            health: RwLock::new(HashMap::new()),
+            usage: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Pick the backend for a persona's next request and count it
+    pub async fn resolve(&self, persona: &Persona) -> Result<ModelRoute, RouteError> {
+        let now = Instant::now();
+        let budget = persona.model.budget;
+
+        let mut usage = self.usage.write().await;
+        let usage = usage.entry(persona.id).or_default();
+        usage.prune(now);
+
+        if budget.requests_per_minute.is_some_and(|limit| usage.requests.len() >= limit as usize) {
+            return Err(RouteError::BudgetExhausted(persona.name.clone()));
+        }
+        let tokens_remaining = budget
+            .tokens_per_hour
+            .map(|limit| limit.saturating_sub(usage.tokens_used()));
+        if tokens_remaining == Some(0) {
+            return Err(RouteError::BudgetExhausted(persona.name.clone()));
+        }
+
+        let health = self.health.read().await;
+        for (depth, backend) in persona.model.backends().into_iter().enumerate() {
+            let routing = match backend {
+                ModelBackend::Remote { .. } => {
+                    match persona.privacy.remote_route(persona.model.remote_over_tor) {
+                        Some(routing) => Some(routing),
+                        None => continue,
+                    }
+                }
+                _ => None,
+            };
+            if !is_available(&backend, health.get(&health_key(&backend)), now) {
+                debug!("Skipping {} for {}", backend, persona.name);
+                continue;
+            }
+
+            usage.requests.push_back(now);
+            return Ok(ModelRoute {
+                backend,
+                routing,
+                fallback_depth: depth,
+                tokens_remaining,
+            });
+        }
+
+        Err(RouteError::NoBackend(persona.name.clone()))
+    }
+
+    /// Record how a request on a resolved backend went
+    pub async fn report(&self, persona_id: PersonaId, backend: &ModelBackend, tokens: u64, success: bool) {
+        let now = Instant::now();
+        if tokens > 0 {
+            let mut usage = self.usage.write().await;
+            let usage = usage.entry(persona_id).or_default();
+            usage.prune(now);
+            usage.tokens.push_back((now, tokens));
+        }
+        self.set_health(health_key(backend), success, now).await;
+    }
+
+    /// Probe the local runtime and Malphas
+    pub async fn check_health(&self) {
+        let now = Instant::now();
+        for (key, socket) in [("abaddon", &self.abaddon_socket), ("malphas", &self.malphas_socket)] {
+            let healthy = match HealthClient::new(socket).health().await {
+                Ok(health) => health.status != HealthStatus::Unhealthy,
+                Err(_) => false,
+            };
+            self.set_health(key.to_string(), healthy, now).await;
+        }
+    }
+
+    /// Probe backends until the daemon exits
+    pub async fn run_health_checks(&self) {
+        let mut interval = tokio::time::interval(HEALTH_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            self.check_health().await;
+        }
+    }
+
+    async fn set_health(&self, key: String, healthy: bool, now: Instant) {
+        let mut health = self.health.write().await;
+        let previous = health.get(&key).map(|h| h.healthy);
+        if previous != Some(healthy) {
+            if healthy {
+                info!("Model backend {} is up", key);
+            } else {
+                warn!("Model backend {} is down", key);
+            }
+        }
+        // Every failure restarts the remote cooldown
+        if previous != Some(healthy) || !healthy {
+            health.insert(key, BackendHealth { healthy, since: now });
+        }
+    }
+}
+
+/// Health entry a backend shares: local models share Abaddon, remote
+/// models share their provider
+fn health_key(backend: &ModelBackend) -> String {
+    match backend {
+        ModelBackend::Local { .. } => "abaddon".to_string(),
+        ModelBackend::Malphas { .. } => "malphas".to_string(),
+        ModelBackend::Remote { provider, .. } => format!("remote:{}", provider),
+    }
+}
+
+/// Whether a backend may take a request. Backends not checked yet are
+/// assumed up.
+fn is_available(backend: &ModelBackend, health: Option<&BackendHealth>, now: Instant) -> bool {
+    if let ModelBackend::Local { path: Some(path), .. } = backend {
+        if !path.exists() {
+            return false;
+        }
+    }
+    match (backend, health) {
+        (_, None) => true,
+        (_, Some(h)) if h.healthy => true,
+        (ModelBackend::Remote { .. }, Some(h)) => now.duration_since(h.since) >= REMOTE_RETRY_AFTER,
+        _ => false,
+    }
+}
@@ -6,9 +6,10 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use anyhow::Result;
+use libnyx_ipc::{trace, Hello};
 use grimoire_core::{
     GrimoireRequest, GrimoireResponse, ResponseData, ErrorCode, PersonaEvent,
-    MemoryQuery,
+    MemoryQuery, SettingsLayer, RequestEnvelope,
 };
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
@@ -16,6 +17,10 @@ use tokio::sync::RwLock;
 use tracing::{info, warn, error, debug};
 
 use crate::GrimoireDaemon;
+use crate::bundle::{self, BundleError};
+use crate::memory_keys::KeyError;
+use crate::model_router::RouteError;
+use crate::store::LayerError;
 
 /// Unified Grimoire IPC server
 pub struct UnifiedGrimoireServer {
@@ -102,6 +107,8 @@ async fn handle_client(
     daemon: Arc<GrimoireDaemon>,
     subscribers: Arc<RwLock<Vec<Subscription>>>,
 ) -> Result<()> {
+    // Only root may write system policy
+    let caller = stream.peer_cred().ok().map(|cred| cred.uid());
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
@@ -120,45 +127,63 @@ async fn handle_client(
         }
     });
 
-    while reader.read_line(&mut line).await? > 0 {
-        let response = match serde_json::from_str::<GrimoireRequest>(&line) {
-            Ok(request) => {
-                debug!("Received request: {:?}", request);
-
-                // Handle subscription specially
-                if let GrimoireRequest::SubscribePersona { persona_id } = &request {
-                    let id = rand::random::<u64>();
-                    subscription_id = Some(id);
-
-                    subscribers.write().await.push(Subscription {
-                        id,
-                        persona_filter: Some(*persona_id),
-                        tx: notify_tx.clone(),
-                    });
-
-                    GrimoireResponse::success(ResponseData::Subscription { id })
-                } else if matches!(request, GrimoireRequest::SubscribeAll) {
-                    let id = rand::random::<u64>();
-                    subscription_id = Some(id);
-
-                    subscribers.write().await.push(Subscription {
-                        id,
-                        persona_filter: None,
-                        tx: notify_tx.clone(),
-                    });
+    let hello = Hello::new("grimoire", env!("CARGO_PKG_VERSION")).with_features(&["subscribe"]);
 
-                    GrimoireResponse::success(ResponseData::Subscription { id })
-                } else {
-                    process_request(request, &daemon).await
-                }
-            }
-            Err(e) => {
-                warn!("Invalid request: {}", e);
-                GrimoireResponse::error(ErrorCode::InvalidRequest, format!("Parse error: {}", e))
+    while reader.read_line(&mut line).await? > 0 {
+        let response_json = match hello.respond(&line) {
+            Some(reply) => reply,
+            None => {
+                let response = match serde_json::from_str::<RequestEnvelope>(&line) {
+                    Ok(RequestEnvelope { request, idempotency_key, .. }) => {
+                        debug!("Received request: {:?}", request);
+
+                        // Handle subscription specially
+                        if let GrimoireRequest::SubscribePersona { persona_id } = &request {
+                            let id = rand::random::<u64>();
+                            subscription_id = Some(id);
+
+                            subscribers.write().await.push(Subscription {
+                                id,
+                                persona_filter: Some(*persona_id),
+                                tx: notify_tx.clone(),
+                            });
+
+                            GrimoireResponse::success(ResponseData::Subscription { id })
+                        } else if matches!(request, GrimoireRequest::SubscribeAll) {
+                            let id = rand::random::<u64>();
+                            subscription_id = Some(id);
+
+                            subscribers.write().await.push(Subscription {
+                                id,
+                                persona_filter: None,
+                                tx: notify_tx.clone(),
+                            });
+
+                            GrimoireResponse::success(ResponseData::Subscription { id })
+                        } else if let Some(key) = idempotency_key {
+                            match daemon.idempotency.get(key).await {
+                                Some(response) => {
+                                    debug!("Request {} already applied", key);
+                                    response
+                                }
+                                None => {
+                                    let response = trace::traced(&line, process_request(request, &daemon, caller)).await;
+                                    daemon.idempotency.record(key, &response).await;
+                                    response
+                                }
+                            }
+                        } else {
+                            trace::traced(&line, process_request(request, &daemon, caller)).await
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Invalid request: {}", e);
+                        GrimoireResponse::error(ErrorCode::InvalidRequest, format!("Parse error: {}", e))
+                    }
+                };
+                serde_json::to_string(&response)?
             }
         };
-
-        let response_json = serde_json::to_string(&response)?;
         writer.write_all(response_json.as_bytes()).await?;
         writer.write_all(b"\n").await?;
         writer.flush().await?;
@@ -177,6 +202,7 @@ async fn handle_client(
 async fn process_request(
     request: GrimoireRequest,
     daemon: &GrimoireDaemon,
+    caller: Option<u32>,
 ) -> GrimoireResponse {
     match request {
         // ========== Persona Operations ==========
@@ -226,6 +252,20 @@ async fn process_request(
             GrimoireResponse::success(ResponseData::Personas(personas))
         }
 
+        GrimoireRequest::ExportPersona { id, include_memory, include_rituals } => {
+            match bundle::export(daemon, id, include_memory, include_rituals).await {
+                Ok(bundle) => GrimoireResponse::success(ResponseData::Bundle(bundle)),
+                Err(e) => bundle_error(e),
+            }
+        }
+
+        GrimoireRequest::ImportPersona { bundle, on_conflict, allow_untrusted } => {
+            match bundle::import(daemon, &bundle, on_conflict, allow_untrusted).await {
+                Ok(report) => GrimoireResponse::success(ResponseData::Imported(report)),
+                Err(e) => bundle_error(e),
+            }
+        }
+
         // ========== Memory Operations ==========
 
         GrimoireRequest::GetMemory { persona_id } => {
@@ -238,7 +278,7 @@ async fn process_request(
         GrimoireRequest::AddMemory { persona_id, entry } => {
             match daemon.persona_store.add_memory(persona_id, entry).await {
                 Ok(()) => GrimoireResponse::ok(),
-                Err(e) => GrimoireResponse::error(ErrorCode::InternalError, e.to_string()),
+                Err(e) => memory_error(e),
             }
         }
 
@@ -254,24 +294,62 @@ async fn process_request(
         GrimoireRequest::ClearSessionMemory { persona_id } => {
             match daemon.persona_store.clear_session_memory(persona_id).await {
                 Ok(()) => GrimoireResponse::ok(),
-                Err(e) => GrimoireResponse::error(ErrorCode::InternalError, e.to_string()),
+                Err(e) => memory_error(e),
             }
         }
 
         GrimoireRequest::ClearAllMemory { persona_id } => {
             match daemon.persona_store.clear_all_memory(persona_id).await {
                 Ok(()) => GrimoireResponse::ok(),
-                Err(e) => GrimoireResponse::error(ErrorCode::InternalError, e.to_string()),
+                Err(e) => memory_error(e),
             }
         }
 
         GrimoireRequest::PersistMemory { persona_id } => {
             match daemon.persona_store.persist_memory(persona_id).await {
                 Ok(()) => GrimoireResponse::ok(),
-                Err(e) => GrimoireResponse::error(ErrorCode::InternalError, e.to_string()),
+                Err(e) => memory_error(e),
+            }
+        }
+
+        GrimoireRequest::RotateMemoryKeys { persona_id } => {
+            match daemon.persona_store.rotate_memory_keys(persona_id).await {
+                Ok(rotated) => GrimoireResponse::success(ResponseData::RotatedKeys(rotated)),
+                Err(e) => memory_error(e),
+            }
+        }
+
+        // ========== Conversation Operations ==========
+
+        GrimoireRequest::Converse { persona_id, .. } => {
+            match daemon.persona_store.get_persona(persona_id).await {
+                // Inference clients are not wired into the daemon yet
+                Some(persona) => match daemon.model_router.resolve(&persona).await {
+                    Ok(route) => GrimoireResponse::error(
+                        ErrorCode::Unavailable,
+                        format!("Conversations over {} are not supported yet", route.backend),
+                    ),
+                    Err(e) => route_error(e),
+                },
+                None => GrimoireResponse::not_found(format!("Persona not found: {}", persona_id)),
+            }
+        }
+
+        GrimoireRequest::ResolveModel { persona_id } => {
+            match daemon.persona_store.get_persona(persona_id).await {
+                Some(persona) => match daemon.model_router.resolve(&persona).await {
+                    Ok(route) => GrimoireResponse::success(ResponseData::ModelRoute(route)),
+                    Err(e) => route_error(e),
+                },
+                None => GrimoireResponse::not_found(format!("Persona not found: {}", persona_id)),
             }
         }
 
+        GrimoireRequest::ReportModelUsage { persona_id, backend, tokens, success } => {
+            daemon.model_router.report(persona_id, &backend, tokens, success).await;
+            GrimoireResponse::ok()
+        }
+
         // ========== Ritual Operations ==========
 
         GrimoireRequest::ListRituals => {
@@ -313,7 +391,34 @@ async fn process_request(
         }
 
         GrimoireRequest::ExecuteRitual { ritual_id, parameters } => {
-            match daemon.ritual_store.write().await.start_execution(ritual_id, parameters) {
+            let ritual = daemon.ritual_store.read().await.get_ritual(ritual_id);
+            let Some(ritual) = ritual else {
+                return GrimoireResponse::not_found(format!("Ritual not found: {}", ritual_id));
+            };
+            let Some(persona) = daemon.persona_store.get_persona(ritual.persona_id).await else {
+                return GrimoireResponse::not_found(format!("Persona not found: {}", ritual.persona_id));
+            };
+            if !persona.capabilities.can_execute_rituals {
+                return GrimoireResponse::error(
+                    ErrorCode::PermissionDenied,
+                    format!("{} may not execute rituals", persona.name),
+                );
+            }
+
+            // Steps run under a Guardian token; without one the ritual doesn't run
+            let token = match daemon.grants.issue(&persona, &ritual).await {
+                Ok(token) => token,
+                Err(e) => {
+                    return GrimoireResponse::error(
+                        ErrorCode::Unavailable,
+                        format!("No execution token: {:#}", e),
+                    )
+                }
+            };
+
+            let started = daemon.ritual_store.write().await
+                .start_execution(ritual_id, parameters, Some(token.clone()));
+            match started {
                 Ok(execution_id) => {
                     // TODO: Actually execute the ritual steps in background
                     let execution = daemon.ritual_store.read().await.get_execution(execution_id);
@@ -322,7 +427,10 @@ async fn process_request(
                         None => GrimoireResponse::internal_error("Failed to get execution"),
                     }
                 }
-                Err(e) => GrimoireResponse::error(ErrorCode::InternalError, e.to_string()),
+                Err(e) => {
+                    daemon.grants.revoke(&token).await;
+                    GrimoireResponse::error(ErrorCode::InternalError, e.to_string())
+                }
             }
         }
 
@@ -334,8 +442,15 @@ async fn process_request(
         }
 
         GrimoireRequest::CancelRitual { execution_id } => {
-            match daemon.ritual_store.write().await.cancel_execution(execution_id) {
-                Ok(()) => GrimoireResponse::ok(),
+            let cancelled = daemon.ritual_store.write().await.cancel_execution(execution_id);
+            match cancelled {
+                Ok(()) => {
+                    let execution = daemon.ritual_store.read().await.get_execution(execution_id);
+                    if let Some(token) = execution.and_then(|e| e.token) {
+                        daemon.grants.revoke(&token).await;
+                    }
+                    GrimoireResponse::ok()
+                }
                 Err(e) => GrimoireResponse::error(ErrorCode::InternalError, e.to_string()),
             }
         }
@@ -347,36 +462,48 @@ async fn process_request(
 
         // ========== Settings Operations ==========
 
-        GrimoireRequest::GetSetting { path } => {
-            match daemon.settings_store.read().await.get(&path).await {
-                Some(value) => GrimoireResponse::success(ResponseData::Setting(value)),
-                None => GrimoireResponse::not_found(format!("Setting not found: {}", path)),
+        GrimoireRequest::GetSetting { path, app } => {
+            match daemon.settings_store.get(&path, app.as_deref()).await {
+                Ok(Some(setting)) => GrimoireResponse::success(ResponseData::Setting(setting)),
+                Ok(None) => GrimoireResponse::not_found(format!("Setting not found: {}", path)),
+                Err(e) => layer_error(e),
             }
         }
 
-        GrimoireRequest::SetSetting { path, value } => {
-            match daemon.settings_store.write().await.set(&path, value).await {
+        GrimoireRequest::SetSetting { path, value, layer } => {
+            if layer == SettingsLayer::System && caller != Some(0) {
+                return GrimoireResponse::error(
+                    ErrorCode::PermissionDenied,
+                    "Only root may write system settings",
+                );
+            }
+            match daemon.settings_store.set(&layer, &path, value).await {
                 Ok(()) => GrimoireResponse::ok(),
-                Err(e) => GrimoireResponse::error(ErrorCode::InternalError, e.to_string()),
+                Err(e) => layer_error(e),
             }
         }
 
-        GrimoireRequest::GetSettings { paths } => {
+        GrimoireRequest::GetSettings { paths, app } => {
             let mut settings = std::collections::HashMap::new();
-            let store = daemon.settings_store.read().await;
 
             for path in paths {
-                if let Some(value) = store.get(&path).await {
-                    settings.insert(path, value);
+                match daemon.settings_store.get(&path, app.as_deref()).await {
+                    Ok(Some(setting)) => {
+                        settings.insert(path, setting.value);
+                    }
+                    Ok(None) => {}
+                    Err(e) => return layer_error(e),
                 }
             }
 
             GrimoireResponse::success(ResponseData::Settings(settings))
         }
 
-        GrimoireRequest::ListSettings { category: _ } => {
-            let settings = daemon.settings_store.read().await.flatten().await;
-            GrimoireResponse::success(ResponseData::Settings(settings))
+        GrimoireRequest::ListSettings { category: _, app } => {
+            match daemon.settings_store.flatten(app.as_deref()).await {
+                Ok(settings) => GrimoireResponse::success(ResponseData::Settings(settings)),
+                Err(e) => layer_error(e),
+            }
         }
 
         // ========== System Operations ==========
@@ -414,3 +541,41 @@ async fn process_request(
         }
     }
 }
+
+fn route_error(error: RouteError) -> GrimoireResponse {
+    let code = match error {
+        RouteError::BudgetExhausted(_) => ErrorCode::RateLimited,
+        RouteError::NoBackend(_) => ErrorCode::Unavailable,
+    };
+    GrimoireResponse::error(code, error.to_string())
+}
+
+fn memory_error(error: anyhow::Error) -> GrimoireResponse {
+    let code = match error.downcast_ref::<KeyError>() {
+        Some(KeyError::Locked | KeyError::Unavailable(_)) => ErrorCode::Unavailable,
+        Some(KeyError::Missing(_) | KeyError::Corrupt(_)) | None => ErrorCode::InternalError,
+    };
+    GrimoireResponse::error(code, error.to_string())
+}
+
+fn layer_error(error: LayerError) -> GrimoireResponse {
+    let code = match error {
+        LayerError::Locked(_) => ErrorCode::PermissionDenied,
+        LayerError::InvalidApp(_) => ErrorCode::InvalidRequest,
+        LayerError::Storage(_) => ErrorCode::InternalError,
+    };
+    GrimoireResponse::error(code, error.to_string())
+}
+
+fn bundle_error(error: BundleError) -> GrimoireResponse {
+    let code = match error {
+        BundleError::NotFound(_) => ErrorCode::NotFound,
+        BundleError::UnsupportedFormat(_) | BundleError::Malformed(_) | BundleError::BadSignature => {
+            ErrorCode::ValidationError
+        }
+        BundleError::Untrusted(_) | BundleError::Builtin(_) => ErrorCode::PermissionDenied,
+        BundleError::Conflict(_) => ErrorCode::AlreadyExists,
+        BundleError::Storage(_) => ErrorCode::InternalError,
+    };
+    GrimoireResponse::error(code, error.to_string())
+}
@@ -2,9 +2,14 @@
 //!
 //! Manages personas on disk and in memory, integrating with Cipher
 //! for encrypted persona memory persistence.
+//!
+//! With the `cipher` feature, memory files are sealed with per-persona keys
+//! held by Cipher (see [`crate::memory_keys`]). While the keyring is locked
+//! memory stays readable if it was loaded, but nothing is written.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use anyhow::{anyhow, Result};
@@ -12,9 +17,12 @@ use grimoire_core::{
     Persona, PersonaId, PersonaMemory, MemoryEntry,
     builtin, GrimoireError,
 };
+use libnyx_ipc::SecretsClient;
 use tokio::sync::RwLock;
 use tracing::{info, warn, error, debug};
 
+use crate::memory_keys::{KeyError, MemoryKeys};
+
 /// Persona store managing all registered personas
 pub struct PersonaStore {
     /// Loaded personas
@@ -26,7 +34,11 @@ pub struct PersonaStore {
     /// Memory storage directory
     memory_dir: PathBuf,
     /// Whether Cipher integration is available
-    cipher_available: bool,
+    cipher_available: AtomicBool,
+    /// Memory encryption keys, when built with Cipher integration
+    keys: Option<MemoryKeys>,
+    /// Personas whose memory files couldn't be decrypted yet
+    sealed: RwLock<HashSet<PersonaId>>,
 }
 
 impl PersonaStore {
@@ -37,7 +49,9 @@ impl PersonaStore {
             memories: Arc::new(RwLock::new(HashMap::new())),
             personas_dir: base_dir.join("personas"),
             memory_dir: base_dir.join("memory"),
-            cipher_available: false, // Will be set during init
+            cipher_available: AtomicBool::new(false), // Will be set during init
+            keys: cfg!(feature = "cipher").then(|| MemoryKeys::new(SecretsClient::new())),
+            sealed: RwLock::new(HashSet::new()),
         }
     }
 
@@ -110,9 +124,18 @@ impl PersonaStore {
 
     /// Check if Cipher daemon is available
     async fn check_cipher_availability(&self) {
-        // TODO: Actually check for Cipher socket
-        // For now, assume not available
-        // self.cipher_available = cipher_client::is_available().await;
+        let Some(keys) = &self.keys else {
+            return;
+        };
+        // A locked keyring is still there; it just can't be used yet
+        let available = match keys.probe().await {
+            Ok(()) | Err(KeyError::Locked) => true,
+            Err(e) => {
+                warn!("Cipher unavailable, persona memory is read-only: {}", e);
+                false
+            }
+        };
+        self.cipher_available.store(available, Ordering::Relaxed);
     }
 
     /// Load persisted memories
@@ -132,6 +155,13 @@ impl PersonaStore {
                         self.memories.write().await.insert(memory.persona_id, memory);
                     }
                     Err(e) => {
+                        // Keep the file untouched and try again once Cipher
+                        // can hand out the key
+                        if let (Some(KeyError::Locked | KeyError::Unavailable(_)), Some(id)) =
+                            (e.downcast_ref::<KeyError>(), memory_file_persona(&path))
+                        {
+                            self.sealed.write().await.insert(id);
+                        }
                         warn!("Failed to load memory from {:?}: {}", path, e);
                     }
                 }
@@ -143,15 +173,40 @@ impl PersonaStore {
 
     /// Load a single memory file
     async fn load_memory_file(&self, path: &Path) -> Result<PersonaMemory> {
-        let content = tokio::fs::read(path).await?;
-
-        // TODO: If Cipher is available, decrypt the content first
-        // let decrypted = cipher_client::decrypt(&content).await?;
+        let mut content = tokio::fs::read(path).await?;
+
+        // Plaintext files from before encryption are sealed on next persist
+        if MemoryKeys::is_sealed(&content) {
+            let keys = self
+                .keys
+                .as_ref()
+                .ok_or_else(|| anyhow!("Memory is encrypted but Cipher support is not built in"))?;
+            let id = memory_file_persona(path)
+                .ok_or_else(|| anyhow!("Memory file name is not a persona ID"))?;
+            content = keys.open(id, &content).await?;
+        }
 
         PersonaMemory::deserialize(&content)
             .map_err(|e| anyhow!("Parse error: {}", e))
     }
 
+    /// Fail unless memory can be written now. Memory left sealed at load
+    /// is decrypted first, so it isn't overwritten with an empty one.
+    async fn ensure_writable(&self, persona_id: PersonaId) -> Result<()> {
+        let Some(keys) = &self.keys else {
+            return Ok(());
+        };
+        keys.check_writable(persona_id).await?;
+
+        if self.sealed.read().await.contains(&persona_id) {
+            let memory = self.load_memory_file(&self.memory_path(persona_id)).await?;
+            self.memories.write().await.insert(persona_id, memory);
+            self.sealed.write().await.remove(&persona_id);
+            info!("Unsealed memory for persona: {}", persona_id);
+        }
+        Ok(())
+    }
+
     // ========== Persona Operations ==========
 
     /// List all personas
@@ -238,7 +293,11 @@ impl PersonaStore {
             tokio::fs::remove_file(&path).await?;
         }
 
-        // Remove memory
+        // Remove memory and its key
+        if let Some(keys) = &self.keys {
+            keys.forget(id).await?;
+        }
+        self.sealed.write().await.remove(&id);
         let memory_path = self.memory_path(id);
         if memory_path.exists() {
             tokio::fs::remove_file(&memory_path).await?;
@@ -282,6 +341,7 @@ impl PersonaStore {
 
     /// Add a memory entry
     pub async fn add_memory(&self, persona_id: PersonaId, entry: MemoryEntry) -> Result<()> {
+        self.ensure_writable(persona_id).await?;
         let mut memories = self.memories.write().await;
 
         let memory = memories
@@ -292,6 +352,16 @@ impl PersonaStore {
         Ok(())
     }
 
+    /// Replace a persona's memory, e.g. with one from an imported bundle,
+    /// and persist it
+    pub async fn restore_memory(&self, memory: PersonaMemory) -> Result<()> {
+        let persona_id = memory.persona_id;
+        self.ensure_writable(persona_id).await?;
+        self.sealed.write().await.remove(&persona_id);
+        self.memories.write().await.insert(persona_id, memory);
+        self.persist_memory(persona_id).await
+    }
+
     /// Recall memories matching a query
     pub async fn recall_memory(
         &self,
@@ -310,6 +380,7 @@ impl PersonaStore {
 
     /// Clear session memory for a persona
     pub async fn clear_session_memory(&self, persona_id: PersonaId) -> Result<()> {
+        self.ensure_writable(persona_id).await?;
         let mut memories = self.memories.write().await;
 
         if let Some(memory) = memories.get_mut(&persona_id) {
@@ -321,6 +392,7 @@ impl PersonaStore {
 
     /// Clear all memory for a persona
     pub async fn clear_all_memory(&self, persona_id: PersonaId) -> Result<()> {
+        self.ensure_writable(persona_id).await?;
         let mut memories = self.memories.write().await;
 
         if let Some(memory) = memories.get_mut(&persona_id) {
@@ -338,21 +410,66 @@ impl PersonaStore {
 
     /// Persist memory to disk
     pub async fn persist_memory(&self, persona_id: PersonaId) -> Result<()> {
-        let memories = self.memories.read().await;
+        self.ensure_writable(persona_id).await?;
 
-        if let Some(memory) = memories.get(&persona_id) {
-            let data = memory.serialize().map_err(|e| anyhow!("{}", e))?;
+        let data = match self.memories.read().await.get(&persona_id) {
+            Some(memory) => memory.serialize().map_err(|e| anyhow!("{}", e))?,
+            None => return Ok(()),
+        };
+        let data = match &self.keys {
+            Some(keys) => keys.seal(persona_id, &data).await?,
+            None => data,
+        };
+
+        let path = self.memory_path(persona_id);
+        tokio::fs::write(&path, &data).await?;
 
-            // TODO: If Cipher is available, encrypt the data
-            // let encrypted = cipher_client::encrypt(&data).await?;
+        debug!("Persisted memory for persona: {}", persona_id);
+        Ok(())
+    }
 
-            let path = self.memory_path(persona_id);
-            tokio::fs::write(&path, &data).await?;
+    /// Rotate the keys sealing persona memory, for one persona or all of
+    /// them, and re-encrypt their files in the background. Personas that
+    /// never had memory sealed have no key and are skipped.
+    pub async fn rotate_memory_keys(self: &Arc<Self>, persona_id: Option<PersonaId>) -> Result<Vec<PersonaId>> {
+        let keys = self.keys.as_ref().ok_or_else(|| anyhow!("Cipher support is not built in"))?;
+        let targets = match persona_id {
+            Some(id) => vec![id],
+            None => self.personas.read().await.keys().cloned().collect(),
+        };
 
-            debug!("Persisted memory for persona: {}", persona_id);
+        let mut rotated = Vec::new();
+        for id in targets {
+            match keys.rotate(id).await {
+                Ok(version) => {
+                    info!("Rotated memory key for persona {} to version {}", id, version);
+                    rotated.push(id);
+                }
+                Err(KeyError::Missing(_)) => {}
+                Err(e) => return Err(e.into()),
+            }
         }
 
-        Ok(())
+        let store = Arc::clone(self);
+        let ids = rotated.clone();
+        tokio::spawn(async move {
+            for id in ids {
+                // The old key stays until the file no longer needs it
+                let result = match store.persist_memory(id).await {
+                    Ok(()) => match &store.keys {
+                        Some(keys) => keys.retire_previous(id).await.map_err(Into::into),
+                        None => Ok(()),
+                    },
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(()) => debug!("Re-encrypted memory for persona: {}", id),
+                    Err(e) => warn!("Failed to re-encrypt memory for {}: {}", id, e),
+                }
+            }
+        });
+
+        Ok(rotated)
     }
 
     /// Persist all memories to disk
@@ -377,7 +494,7 @@ impl PersonaStore {
 
     /// Check if Cipher is available
     pub fn cipher_available(&self) -> bool {
-        self.cipher_available
+        self.cipher_available.load(Ordering::Relaxed)
     }
 
     /// Get builtin personas
@@ -386,6 +503,11 @@ impl PersonaStore {
     }
 }
 
+/// Persona a memory file belongs to, from its `<id>.memory` name
+fn memory_file_persona(path: &Path) -> Option<PersonaId> {
+    path.file_stem()?.to_str()?.parse().ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
-use grimoire_core::{Ritual, RitualId, RitualExecution, ExecutionStatus};
+use grimoire_core::{Ritual, RitualId, RitualExecution, ExecutionStatus, ExecutionToken};
 use tracing::{info, warn, debug};
 use uuid::Uuid;
 
@@ -164,6 +164,7 @@ impl RitualStore {
         &mut self,
         ritual_id: RitualId,
         parameters: HashMap<String, serde_json::Value>,
+        token: Option<ExecutionToken>,
     ) -> Result<Uuid> {
         // Check if ritual exists
         if !self.rituals.contains_key(&ritual_id) {
@@ -173,6 +174,7 @@ impl RitualStore {
         // Create execution
         let mut execution = RitualExecution::new(ritual_id);
         execution.variables = parameters;
+        execution.token = token;
         execution.status = ExecutionStatus::Running;
 
         let execution_id = execution.id;
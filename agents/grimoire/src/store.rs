@@ -1,6 +1,7 @@
 //! Settings storage backend
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
+use grimoire_core::{EffectiveSetting, SettingsLayer};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -21,6 +22,9 @@ pub struct SettingsNode {
     pub value: Option<Value>,
     #[serde(default)]
     pub children: HashMap<String, SettingsNode>,
+    /// Lower layers may not override this node or anything under it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
     #[serde(skip)]
     pub metadata: Option<NodeMetadata>,
 }
@@ -152,6 +156,22 @@ impl SettingsStore {
         }
     }
 
+    /// Whether a path or one of its parents is locked
+    pub async fn is_locked(&self, path: &str) -> bool {
+        let root = self.root.read().await;
+        let mut node = &*root;
+        for key in path.split('.') {
+            if node.locked {
+                return true;
+            }
+            match node.children.get(key) {
+                Some(child) => node = child,
+                None => return false,
+            }
+        }
+        node.locked
+    }
+
     /// Check if a path exists
     pub async fn exists(&self, path: &str) -> bool {
         self.get(path).await.is_some()
@@ -248,53 +268,194 @@ impl SettingsStore {
     }
 }
 
-/// Multi-layer settings with overrides
+/// Why a layered write was refused
+#[derive(Debug, thiserror::Error)]
+pub enum LayerError {
+    #[error("{0} is locked by system policy")]
+    Locked(String),
+    #[error("Invalid app namespace: {0}")]
+    InvalidApp(String),
+    #[error(transparent)]
+    Storage(#[from] anyhow::Error),
+}
+
+/// System, user and per-app settings, each in its own file
+///
+/// Reads resolve system → user → app, the later layer winning, except that
+/// a setting the system layer locks always takes the system value and
+/// can't be written in the other layers.
 pub struct LayeredStore {
-    layers: Vec<(String, Arc<SettingsStore>)>,
+    system: SettingsStore,
+    user: SettingsStore,
+    apps_dir: PathBuf,
+    apps: RwLock<HashMap<String, Arc<SettingsStore>>>,
 }
 
 impl LayeredStore {
-    pub fn new() -> Self {
-        Self { layers: Vec::new() }
+    /// Create a store over the system and user files and the directory
+    /// holding one `<app>.yaml` per app namespace
+    pub fn new(system_file: PathBuf, user_file: PathBuf, apps_dir: PathBuf) -> Self {
+        Self {
+            system: SettingsStore::new(system_file),
+            user: SettingsStore::new(user_file),
+            apps_dir,
+            apps: RwLock::new(HashMap::new()),
+        }
     }
 
-    /// Add a layer (later layers override earlier ones)
-    pub fn add_layer(&mut self, name: &str, store: Arc<SettingsStore>) {
-        self.layers.push((name.to_string(), store));
+    /// Directory holding the app namespaces
+    pub fn apps_dir(&self) -> &Path {
+        &self.apps_dir
     }
 
-    /// Get value from highest-priority layer that has it
-    pub async fn get(&self, path: &str) -> Option<Value> {
-        for (_, store) in self.layers.iter().rev() {
+    /// Load every layer from disk
+    pub async fn load(&self) -> Result<()> {
+        tokio::fs::create_dir_all(&self.apps_dir).await?;
+        self.system.load().await?;
+        self.user.load().await?;
+        for store in self.apps.read().await.values() {
+            store.load().await?;
+        }
+        Ok(())
+    }
+
+    /// Effective value of a setting, as seen by `app` if given
+    pub async fn get(&self, path: &str, app: Option<&str>) -> Result<Option<EffectiveSetting>, LayerError> {
+        if self.system.is_locked(path).await {
+            return Ok(self.system.get(path).await.map(|value| EffectiveSetting {
+                value,
+                layer: SettingsLayer::System,
+                locked: true,
+            }));
+        }
+
+        if let Some(app) = app {
+            if let Some(value) = self.app(app).await?.get(path).await {
+                return Ok(Some(EffectiveSetting {
+                    value,
+                    layer: SettingsLayer::App(app.to_string()),
+                    locked: false,
+                }));
+            }
+        }
+
+        for (layer, store) in [(SettingsLayer::User, &self.user), (SettingsLayer::System, &self.system)] {
             if let Some(value) = store.get(path).await {
-                return Some(value);
+                return Ok(Some(EffectiveSetting { value, layer, locked: false }));
             }
         }
-        None
+        Ok(None)
     }
 
-    /// Set value in the top layer
-    pub async fn set(&self, path: &str, value: Value) -> Result<()> {
-        if let Some((_, store)) = self.layers.last() {
-            store.set(path, value).await
-        } else {
-            Err(anyhow!("No layers configured"))
+    /// Write a setting in one layer and save that layer
+    pub async fn set(&self, layer: &SettingsLayer, path: &str, value: Value) -> Result<(), LayerError> {
+        let store = match layer {
+            SettingsLayer::System => {
+                self.system.set(path, value).await?;
+                self.system.save().await?;
+                return Ok(());
+            }
+            SettingsLayer::User => None,
+            SettingsLayer::App(app) => Some(self.app(app).await?),
+        };
+
+        if self.system.is_locked(path).await {
+            return Err(LayerError::Locked(path.to_string()));
         }
+        let store = store.as_deref().unwrap_or(&self.user);
+        store.set(path, value).await?;
+        store.save().await?;
+        Ok(())
     }
 
-    /// Get the layer name that provides a value
-    pub async fn get_source(&self, path: &str) -> Option<String> {
-        for (name, store) in self.layers.iter().rev() {
-            if store.exists(path).await {
-                return Some(name.clone());
+    /// Effective values of all settings, as seen by `app` if given
+    pub async fn flatten(&self, app: Option<&str>) -> Result<HashMap<String, Value>, LayerError> {
+        let mut settings = self.system.flatten().await;
+        let mut overrides = self.user.flatten().await;
+        if let Some(app) = app {
+            overrides.extend(self.app(app).await?.flatten().await);
+        }
+        for (path, value) in overrides {
+            if !self.system.is_locked(&path).await {
+                settings.insert(path, value);
             }
         }
-        None
+        Ok(settings)
+    }
+
+    /// An app's namespace, loaded from disk on first use
+    async fn app(&self, app: &str) -> Result<Arc<SettingsStore>, LayerError> {
+        let valid = !app.is_empty()
+            && !app.starts_with('.')
+            && app.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+        if !valid {
+            return Err(LayerError::InvalidApp(app.to_string()));
+        }
+
+        if let Some(store) = self.apps.read().await.get(app) {
+            return Ok(Arc::clone(store));
+        }
+
+        let mut apps = self.apps.write().await;
+        if let Some(store) = apps.get(app) {
+            return Ok(Arc::clone(store));
+        }
+        let store = Arc::new(SettingsStore::new(self.apps_dir.join(format!("{}.yaml", app))));
+        store.load().await?;
+        apps.insert(app.to_string(), Arc::clone(&store));
+        Ok(store)
     }
 }
 
-impl Default for LayeredStore {
-    fn default() -> Self {
-        Self::new()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn layered(dir: &Path) -> LayeredStore {
+        LayeredStore::new(dir.join("system.yaml"), dir.join("user.yaml"), dir.join("apps"))
+    }
+
+    #[tokio::test]
+    async fn test_layer_precedence() {
+        let dir = tempdir().unwrap();
+        let store = layered(dir.path());
+        store.load().await.unwrap();
+
+        store.set(&SettingsLayer::System, "display.theme", "light".into()).await.unwrap();
+        store.set(&SettingsLayer::User, "display.theme", "dark".into()).await.unwrap();
+        let aether = SettingsLayer::App("aether".to_string());
+        store.set(&aether, "display.theme", "amoled".into()).await.unwrap();
+
+        let user = store.get("display.theme", None).await.unwrap().unwrap();
+        assert_eq!(user.layer, SettingsLayer::User);
+        assert_eq!(user.value, "dark");
+
+        let app = store.get("display.theme", Some("aether")).await.unwrap().unwrap();
+        assert_eq!(app.layer, aether);
+
+        // Layers are stored separately and survive a reload
+        let reloaded = layered(dir.path());
+        reloaded.load().await.unwrap();
+        assert_eq!(reloaded.get("display.theme", Some("aether")).await.unwrap(), Some(app));
+        assert!(reloaded.get("display.theme", Some("../user")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_system_lock() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("system.yaml"),
+            "children:\n  network:\n    locked: true\n    children:\n      proxy:\n        value: tor\n",
+        ).unwrap();
+        let store = layered(dir.path());
+        store.load().await.unwrap();
+
+        let result = store.set(&SettingsLayer::User, "network.proxy", "none".into()).await;
+        assert!(matches!(result, Err(LayerError::Locked(_))));
+
+        let proxy = store.get("network.proxy", Some("sitra")).await.unwrap().unwrap();
+        assert_eq!(proxy.layer, SettingsLayer::System);
+        assert!(proxy.locked);
     }
 }
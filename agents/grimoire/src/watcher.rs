@@ -243,14 +243,14 @@ pub struct DirectoryWatcher {
 /// Settings watcher that automatically reloads the settings store
 pub struct SettingsWatcher {
     directories: Vec<PathBuf>,
-    store: Arc<RwLock<crate::store::SettingsStore>>,
+    store: Arc<crate::store::LayeredStore>,
 }
 
 impl SettingsWatcher {
     /// Create a new settings watcher
     pub fn new(
         directories: Vec<PathBuf>,
-        store: Arc<RwLock<crate::store::SettingsStore>>,
+        store: Arc<crate::store::LayeredStore>,
     ) -> Result<Self> {
         Ok(Self { directories, store })
     }
@@ -278,7 +278,7 @@ impl SettingsWatcher {
             match event {
                 ConfigEvent::Modified(path) | ConfigEvent::Created(path) => {
                     tracing::info!("Config changed: {:?}", path);
-                    if let Err(e) = self.store.write().await.load().await {
+                    if let Err(e) = self.store.load().await {
                         tracing::error!("Failed to reload settings: {}", e);
                     }
                 }
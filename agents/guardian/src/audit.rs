@@ -1,21 +1,34 @@
 //! Audit logging - tamper-evident security audit trail
 //!
-//! All security decisions are logged for forensics and compliance.
+//! All security decisions are logged for forensics and compliance. Every
+//! entry carries the SHA-256 of its predecessor, so removing or editing a
+//! record breaks the chain. Periodically (and before each rotation) a
+//! checkpoint entry signs the chain head with Guardian's Ed25519 key, which
+//! pins the chain so it cannot simply be recomputed after an edit. The chain
+//! continues across rotated files and daemon restarts.
 
 use crate::config::AuditConfig;
 use crate::policy::CapabilityRequest;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
+use libnyx_ipc::audit::{self as bus, AuditOutcome};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fs::{File, OpenOptions};
-use std::io::{BufWriter, Write};
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use tokio::sync::mpsc;
-use tracing::{debug, error, info, warn};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
+/// `prev_hash` of the very first entry
+const GENESIS: &str = "genesis";
+
+/// Domain separator for checkpoint signatures
+const CHECKPOINT_CONTEXT: &str = "guardian-audit-checkpoint";
+
 /// Audit event types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -42,6 +55,16 @@ pub enum AuditEvent {
         reason: String,
         user_approved: bool,
     },
+    /// User was prompted for a decision
+    Prompt {
+        request_id: Uuid,
+        request: CapabilityRequest,
+        approved: bool,
+        remembered: bool,
+        /// herald, ipc or timeout
+        answered_via: String,
+        waited_ms: u64,
+    },
     /// Policy violation detected
     Violation {
         request: CapabilityRequest,
@@ -81,6 +104,105 @@ pub enum AuditEvent {
         message: String,
         context: std::collections::HashMap<String, String>,
     },
+    /// Time-limited grant issued, renewed, expired or revoked
+    Lease {
+        lease_id: Uuid,
+        request: CapabilityRequest,
+        action: String,
+        expires_at: Option<DateTime<Utc>>,
+    },
+    /// Execution token issued, revoked or expired
+    Token {
+        token_id: Uuid,
+        subject: String,
+        user: String,
+        action: String,
+        /// Scopes granted (on issue) or held
+        scopes: Vec<String>,
+        expires_at: DateTime<Utc>,
+    },
+    /// Signed checkpoint over the chain so far
+    Checkpoint {
+        /// Sequence number of the last entry covered
+        through_seq: u64,
+        /// Hash of that entry
+        chain_hash: String,
+        /// Hex Ed25519 signature
+        signature: String,
+        /// Fingerprint of the signing key
+        key_id: String,
+    },
+}
+
+impl AuditEvent {
+    /// Event type name, as in the `type` tag
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AuditEvent::Started { .. } => "Started",
+            AuditEvent::Stopped { .. } => "Stopped",
+            AuditEvent::Request { .. } => "Request",
+            AuditEvent::Decision { .. } => "Decision",
+            AuditEvent::Prompt { .. } => "Prompt",
+            AuditEvent::Violation { .. } => "Violation",
+            AuditEvent::Anomaly { .. } => "Anomaly",
+            AuditEvent::ConfigChanged { .. } => "ConfigChanged",
+            AuditEvent::Override { .. } => "Override",
+            AuditEvent::PatternLearned { .. } => "PatternLearned",
+            AuditEvent::Alert { .. } => "Alert",
+            AuditEvent::Lease { .. } => "Lease",
+            AuditEvent::Token { .. } => "Token",
+            AuditEvent::Checkpoint { .. } => "Checkpoint",
+        }
+    }
+
+    /// The decision as reported on the system audit bus, for events that
+    /// record one
+    fn to_bus(&self) -> Option<bus::AuditEvent> {
+        let capability = |request: &CapabilityRequest, action: &str, outcome| {
+            let object = match &request.resource {
+                Some(resource) => format!("{}:{}", request.capability, resource),
+                None => request.capability.clone(),
+            };
+            bus::AuditEvent::new("guardian", request.user.as_str(), action, object, outcome)
+                .with_detail("process", &request.process_path)
+                .with_detail("pid", request.pid)
+        };
+        let outcome = |denied: bool| if denied { AuditOutcome::Denied } else { AuditOutcome::Success };
+        let denies = |decision: &str| decision.to_ascii_lowercase().starts_with("deny");
+
+        let event = match self {
+            AuditEvent::Decision { request, decision, reason, .. } => {
+                capability(request, "capability.check", outcome(denies(decision)))
+                    .with_reason(reason.as_str())
+                    .with_detail("decision", decision)
+            }
+            AuditEvent::Prompt { request, approved, answered_via, .. } => {
+                capability(request, "capability.prompt", outcome(!approved))
+                    .with_detail("answered_via", answered_via)
+            }
+            AuditEvent::Override { request, override_decision, reason, .. } => {
+                capability(request, "capability.override", outcome(denies(override_decision)))
+                    .with_reason(reason.as_str())
+            }
+            AuditEvent::Violation { request, violation_type, .. } => {
+                capability(request, "capability.violation", AuditOutcome::Denied)
+                    .with_reason(violation_type.as_str())
+            }
+            AuditEvent::Lease { request, action, .. } => {
+                capability(request, &format!("lease.{}", action), AuditOutcome::Success)
+            }
+            AuditEvent::Token { subject, user, action, scopes, .. } => {
+                bus::AuditEvent::new("guardian", user.as_str(), &format!("token.{}", action), subject.as_str(), AuditOutcome::Success)
+                    .with_detail("scopes", scopes.join(", "))
+            }
+            AuditEvent::ConfigChanged { component, change_type, .. } => {
+                bus::AuditEvent::new("guardian", "guardian", "config.change", component.as_str(), AuditOutcome::Success)
+                    .with_detail("change", change_type)
+            }
+            _ => return None,
+        };
+        Some(event)
+    }
 }
 
 /// Violation severity levels
@@ -134,6 +256,26 @@ pub struct AuditEntry {
     pub hash: String,
 }
 
+/// Audit export formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One JSON entry per line, exactly as logged
+    Jsonl,
+    /// ArcSight Common Event Format
+    Cef,
+}
+
+/// Head of the hash chain
+struct ChainState {
+    /// Next sequence number
+    seq: u64,
+    /// Hash of the last entry written
+    last_hash: String,
+    /// Entries since the last checkpoint
+    since_checkpoint: u64,
+}
+
 /// Audit logger
 pub struct AuditLogger {
     /// Whether logging is enabled
@@ -144,38 +286,59 @@ pub struct AuditLogger {
     rotate_size_mb: u64,
     /// Retention days
     retention_days: u32,
-    /// Current sequence number
-    seq: AtomicU64,
+    /// Try to keep the active file append-only
+    append_only: bool,
+    /// Entries between checkpoints
+    checkpoint_every: u64,
     /// Session ID
     session_id: Uuid,
     /// Machine ID
     machine_id: String,
-    /// Last entry hash (for chaining)
-    last_hash: Mutex<String>,
+    /// Chain head; held while an entry is written so file order matches the chain
+    chain: Mutex<ChainState>,
     /// Log writer
     writer: Mutex<Option<BufWriter<File>>>,
-    /// Async channel for background writing
-    tx: Option<mpsc::UnboundedSender<AuditEntry>>,
+    /// Checkpoint signing key
+    signing_key: Option<Ed25519KeyPair>,
 }
 
 impl AuditLogger {
-    /// Create a new audit logger
+    /// Create a new audit logger, continuing the chain of any existing log
     pub fn new(config: &AuditConfig) -> Result<Self> {
         let session_id = Uuid::new_v4();
         let machine_id = get_machine_id();
 
+        let mut chain = ChainState {
+            seq: 0,
+            last_hash: GENESIS.to_string(),
+            since_checkpoint: 0,
+        };
+
         let writer = if config.enabled {
             // Ensure directory exists
             if let Some(parent) = config.output_path.parent() {
                 std::fs::create_dir_all(parent)?;
             }
 
-            let file = OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(&config.output_path)?;
+            if let Some(last) = last_entry(&config.output_path) {
+                chain.seq = last.seq + 1;
+                chain.last_hash = last.hash;
+                info!("Continuing audit chain at entry {}", chain.seq);
+            }
 
-            Some(BufWriter::new(file))
+            Some(open_log(&config.output_path, config.append_only)?)
+        } else {
+            None
+        };
+
+        let signing_key = if config.enabled {
+            match load_signing_key(&config.signing_key_path) {
+                Ok(key) => Some(key),
+                Err(e) => {
+                    warn!("Audit checkpoints will not be signed: {}", e);
+                    None
+                }
+            }
         } else {
             None
         };
@@ -185,12 +348,13 @@ impl AuditLogger {
             output_path: config.output_path.clone(),
             rotate_size_mb: config.rotate_size_mb,
             retention_days: config.retention_days,
-            seq: AtomicU64::new(0),
+            append_only: config.append_only,
+            checkpoint_every: config.checkpoint_every,
             session_id,
             machine_id,
-            last_hash: Mutex::new(String::from("genesis")),
+            chain: Mutex::new(chain),
             writer: Mutex::new(writer),
-            tx: None,
+            signing_key,
         })
     }
 
@@ -200,40 +364,64 @@ impl AuditLogger {
             return;
         }
 
-        let seq = self.seq.fetch_add(1, Ordering::SeqCst);
-        let timestamp = Utc::now();
+        if let Some(operation) = event.to_bus() {
+            bus::record(operation);
+        }
 
-        // Get previous hash for chaining
-        let prev_hash = {
-            let guard = self.last_hash.lock().unwrap();
-            guard.clone()
-        };
+        let mut chain = self.chain.lock().unwrap();
+        self.append(&mut chain, event);
+
+        if self.checkpoint_every > 0 && chain.since_checkpoint >= self.checkpoint_every {
+            self.checkpoint(&mut chain);
+        }
+
+        // Check for rotation
+        if self.should_rotate() {
+            // Seal the outgoing file with a checkpoint
+            self.checkpoint(&mut chain);
+            self.rotate();
+        }
+    }
 
-        // Create entry
+    /// Chain and write one entry
+    fn append(&self, chain: &mut ChainState, event: AuditEvent) {
         let mut entry = AuditEntry {
-            seq,
-            timestamp,
+            seq: chain.seq,
+            timestamp: Utc::now(),
             machine_id: self.machine_id.clone(),
             session_id: self.session_id,
             event,
-            prev_hash,
+            prev_hash: chain.last_hash.clone(),
             hash: String::new(),
         };
-
-        // Compute hash
         entry.hash = compute_entry_hash(&entry);
 
-        // Update last hash
-        {
-            let mut guard = self.last_hash.lock().unwrap();
-            *guard = entry.hash.clone();
-        }
-
-        // Write entry
         self.write_entry(&entry);
 
-        // Check for rotation
-        self.maybe_rotate();
+        chain.seq += 1;
+        chain.last_hash = entry.hash;
+        chain.since_checkpoint += 1;
+    }
+
+    /// Sign the current chain head
+    fn checkpoint(&self, chain: &mut ChainState) {
+        let Some(key) = &self.signing_key else {
+            return;
+        };
+        if chain.seq == 0 || chain.since_checkpoint == 0 {
+            return;
+        }
+
+        let through_seq = chain.seq - 1;
+        let signature = key.sign(checkpoint_message(through_seq, &chain.last_hash).as_bytes());
+        let event = AuditEvent::Checkpoint {
+            through_seq,
+            chain_hash: chain.last_hash.clone(),
+            signature: hex::encode(signature.as_ref()),
+            key_id: key_id(key.public_key().as_ref()),
+        };
+        self.append(chain, event);
+        chain.since_checkpoint = 0;
     }
 
     /// Log a capability request
@@ -309,6 +497,8 @@ impl AuditLogger {
             reason: reason.to_string(),
             uptime_secs,
         });
+        let mut chain = self.chain.lock().unwrap();
+        self.checkpoint(&mut chain);
     }
 
     fn write_entry(&self, entry: &AuditEntry) {
@@ -330,14 +520,10 @@ impl AuditLogger {
         }
     }
 
-    fn maybe_rotate(&self) {
-        // Check file size
-        if let Ok(metadata) = std::fs::metadata(&self.output_path) {
-            let size_mb = metadata.len() / (1024 * 1024);
-            if size_mb >= self.rotate_size_mb {
-                self.rotate();
-            }
-        }
+    fn should_rotate(&self) -> bool {
+        std::fs::metadata(&self.output_path)
+            .map(|metadata| metadata.len() / (1024 * 1024) >= self.rotate_size_mb)
+            .unwrap_or(false)
     }
 
     fn rotate(&self) {
@@ -346,6 +532,13 @@ impl AuditLogger {
         // Close current writer
         *guard = None;
 
+        // The kernel refuses to rename an append-only file
+        if self.append_only {
+            if let Ok(file) = File::open(&self.output_path) {
+                let _ = set_append_only(&file, false);
+            }
+        }
+
         // Rename current file
         let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
         let rotated_name = format!(
@@ -362,13 +555,9 @@ impl AuditLogger {
         info!("Rotated audit log to {}", rotated_name);
 
         // Open new file
-        match OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&self.output_path)
-        {
-            Ok(file) => {
-                *guard = Some(BufWriter::new(file));
+        match open_log(&self.output_path, self.append_only) {
+            Ok(writer) => {
+                *guard = Some(writer);
             }
             Err(e) => {
                 error!("Failed to create new audit log: {}", e);
@@ -380,86 +569,153 @@ impl AuditLogger {
     }
 
     fn cleanup_old_logs(&self) {
-        let retention_secs = self.retention_days as i64 * 24 * 60 * 60;
-        let cutoff = Utc::now().timestamp() - retention_secs;
-
-        if let Some(parent) = self.output_path.parent() {
-            if let Ok(entries) = std::fs::read_dir(parent) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.extension().map_or(false, |e| e == "log") {
-                        if let Ok(metadata) = entry.metadata() {
-                            if let Ok(modified) = metadata.modified() {
-                                if let Ok(duration) = modified.elapsed() {
-                                    if duration.as_secs() > retention_secs as u64 {
-                                        if let Err(e) = std::fs::remove_file(&path) {
-                                            warn!("Failed to remove old log {}: {}", path.display(), e);
-                                        } else {
-                                            info!("Removed old audit log: {}", path.display());
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+        let retention_secs = self.retention_days as u64 * 24 * 60 * 60;
+
+        for path in rotated_logs(&self.output_path) {
+            let expired = std::fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age.as_secs() > retention_secs);
+            if !expired {
+                continue;
+            }
+
+            if let Err(e) = std::fs::remove_file(&path) {
+                warn!("Failed to remove old log {}: {}", path.display(), e);
+            } else {
+                info!("Removed old audit log: {}", path.display());
             }
         }
     }
 
-    /// Verify audit log integrity
+    /// Verify the whole log, rotated files included
     pub fn verify_integrity(&self) -> Result<IntegrityReport> {
-        let file = File::open(&self.output_path)?;
-        let reader = std::io::BufReader::new(file);
+        self.verify_files(&log_files(&self.output_path))
+    }
 
-        let mut entries_checked = 0u64;
-        let mut errors = Vec::new();
-        let mut prev_hash = String::from("genesis");
+    /// Verify a single log file
+    pub fn verify_file(&self, path: &Path) -> Result<IntegrityReport> {
+        self.verify_files(&[path.to_path_buf()])
+    }
 
-        use std::io::BufRead;
-        for (line_num, line) in reader.lines().enumerate() {
-            let line = line?;
-            if line.is_empty() {
-                continue;
-            }
+    fn verify_files(&self, paths: &[PathBuf]) -> Result<IntegrityReport> {
+        let public_key = self.signing_key.as_ref().map(|k| k.public_key().as_ref().to_vec());
+
+        let mut report = IntegrityReport {
+            entries_checked: 0,
+            checkpoints_verified: 0,
+            first_seq: None,
+            last_seq: None,
+            unsigned_tail: 0,
+            errors: Vec::new(),
+        };
+        let mut prev: Option<(u64, String)> = None;
 
-            match serde_json::from_str::<AuditEntry>(&line) {
-                Ok(entry) => {
-                    // Verify chain
-                    if entry.prev_hash != prev_hash {
-                        errors.push(IntegrityError::ChainBroken {
-                            line: line_num,
+        for path in paths {
+            let reader = BufReader::new(File::open(path)?);
+            let file = path.display().to_string();
+
+            for (line_num, text) in reader.lines().enumerate() {
+                let text = text?;
+                if text.is_empty() {
+                    continue;
+                }
+                let line = line_num + 1;
+
+                let entry = match serde_json::from_str::<AuditEntry>(&text) {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        report.errors.push(IntegrityError::ParseError {
+                            file: file.clone(),
+                            line,
+                            error: e.to_string(),
+                        });
+                        continue;
+                    }
+                };
+
+                // Verify chain; the first entry anchors it, since older files may have expired
+                if let Some((prev_seq, prev_hash)) = &prev {
+                    if entry.prev_hash != *prev_hash || entry.seq != prev_seq + 1 {
+                        report.errors.push(IntegrityError::ChainBroken {
+                            file: file.clone(),
+                            line,
                             expected: prev_hash.clone(),
                             found: entry.prev_hash.clone(),
                         });
                     }
+                } else {
+                    report.first_seq = Some(entry.seq);
+                }
 
-                    // Verify hash
-                    let computed_hash = compute_entry_hash(&entry);
-                    if entry.hash != computed_hash {
-                        errors.push(IntegrityError::HashMismatch {
-                            line: line_num,
-                            expected: computed_hash,
-                            found: entry.hash.clone(),
-                        });
+                // Verify hash
+                let computed_hash = compute_entry_hash(&entry);
+                if entry.hash != computed_hash {
+                    report.errors.push(IntegrityError::HashMismatch {
+                        file: file.clone(),
+                        line,
+                        expected: computed_hash,
+                        found: entry.hash.clone(),
+                    });
+                }
+
+                if let AuditEvent::Checkpoint { through_seq, chain_hash, signature, .. } = &entry.event {
+                    match check_checkpoint(public_key.as_deref(), prev.as_ref(), *through_seq, chain_hash, signature) {
+                        Ok(()) => {
+                            report.checkpoints_verified += 1;
+                            report.unsigned_tail = 0;
+                        }
+                        Err(reason) => report.errors.push(IntegrityError::BadCheckpoint {
+                            file: file.clone(),
+                            line,
+                            reason,
+                        }),
                     }
+                } else {
+                    report.unsigned_tail += 1;
+                }
+
+                report.last_seq = Some(entry.seq);
+                prev = Some((entry.seq, entry.hash));
+                report.entries_checked += 1;
+            }
+        }
+
+        Ok(report)
+    }
 
-                    prev_hash = entry.hash;
-                    entries_checked += 1;
+    /// Export entries in `[from, to]` from all log files
+    pub fn export(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        format: ExportFormat,
+    ) -> Result<(usize, String)> {
+        let mut out = String::new();
+        let mut count = 0;
+
+        for path in log_files(&self.output_path) {
+            let reader = BufReader::new(File::open(&path)?);
+            for line in reader.lines() {
+                let line = line?;
+                let Ok(entry) = serde_json::from_str::<AuditEntry>(&line) else {
+                    continue;
+                };
+                if from.is_some_and(|from| entry.timestamp < from) || to.is_some_and(|to| entry.timestamp > to) {
+                    continue;
                 }
-                Err(e) => {
-                    errors.push(IntegrityError::ParseError {
-                        line: line_num,
-                        error: e.to_string(),
-                    });
+
+                match format {
+                    ExportFormat::Jsonl => out.push_str(&line),
+                    ExportFormat::Cef => out.push_str(&to_cef(&entry)),
                 }
+                out.push('\n');
+                count += 1;
             }
         }
 
-        Ok(IntegrityReport {
-            entries_checked,
-            errors,
-        })
+        Ok((count, out))
     }
 }
 
@@ -467,6 +723,14 @@ impl AuditLogger {
 #[derive(Debug)]
 pub struct IntegrityReport {
     pub entries_checked: u64,
+    /// Signed checkpoints that verified
+    pub checkpoints_verified: u64,
+    /// First sequence number seen (the chain anchor)
+    pub first_seq: Option<u64>,
+    /// Last sequence number seen
+    pub last_seq: Option<u64>,
+    /// Entries after the last verified checkpoint, covered by the chain only
+    pub unsigned_tail: u64,
     pub errors: Vec<IntegrityError>,
 }
 
@@ -480,21 +744,48 @@ impl IntegrityReport {
 #[derive(Debug)]
 pub enum IntegrityError {
     ChainBroken {
+        file: String,
         line: usize,
         expected: String,
         found: String,
     },
     HashMismatch {
+        file: String,
         line: usize,
         expected: String,
         found: String,
     },
+    BadCheckpoint {
+        file: String,
+        line: usize,
+        reason: String,
+    },
     ParseError {
+        file: String,
         line: usize,
         error: String,
     },
 }
 
+impl std::fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntegrityError::ChainBroken { file, line, expected, found } => {
+                write!(f, "{}:{}: chain broken (expected prev {}, found {})", file, line, expected, found)
+            }
+            IntegrityError::HashMismatch { file, line, expected, found } => {
+                write!(f, "{}:{}: hash mismatch (computed {}, recorded {})", file, line, expected, found)
+            }
+            IntegrityError::BadCheckpoint { file, line, reason } => {
+                write!(f, "{}:{}: bad checkpoint: {}", file, line, reason)
+            }
+            IntegrityError::ParseError { file, line, error } => {
+                write!(f, "{}:{}: unreadable entry: {}", file, line, error)
+            }
+        }
+    }
+}
+
 fn get_machine_id() -> String {
     // Try to read machine-id
     if let Ok(id) = std::fs::read_to_string("/etc/machine-id") {
@@ -511,29 +802,259 @@ fn get_machine_id() -> String {
 }
 
 fn compute_entry_hash(entry: &AuditEntry) -> String {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
+    // serde_json maps are ordered, so the encoding is stable across a round trip
+    let event = serde_json::to_value(&entry.event).unwrap_or_default();
+    let fields = (
+        entry.seq,
+        entry.timestamp,
+        &entry.machine_id,
+        entry.session_id,
+        event,
+        &entry.prev_hash,
+    );
+
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(&fields).unwrap_or_default());
+    hex::encode(hasher.finalize())
+}
 
-    let mut hasher = DefaultHasher::new();
+fn compute_config_hash(config: &crate::config::GuardianConfig) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{:?}", config));
+    hex::encode(hasher.finalize())
+}
 
-    // Hash relevant fields (excluding the hash itself)
-    entry.seq.hash(&mut hasher);
-    entry.timestamp.to_rfc3339().hash(&mut hasher);
-    entry.machine_id.hash(&mut hasher);
-    entry.session_id.to_string().hash(&mut hasher);
-    format!("{:?}", entry.event).hash(&mut hasher);
-    entry.prev_hash.hash(&mut hasher);
+fn checkpoint_message(through_seq: u64, chain_hash: &str) -> String {
+    format!("{}:{}:{}", CHECKPOINT_CONTEXT, through_seq, chain_hash)
+}
 
-    format!("{:016x}", hasher.finish())
+fn key_id(public_key: &[u8]) -> String {
+    hex::encode(&Sha256::digest(public_key)[..8])
 }
 
-fn compute_config_hash(config: &crate::config::GuardianConfig) -> String {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
+/// Check a checkpoint against the entry before it and the signing key
+fn check_checkpoint(
+    public_key: Option<&[u8]>,
+    prev: Option<&(u64, String)>,
+    through_seq: u64,
+    chain_hash: &str,
+    signature: &str,
+) -> std::result::Result<(), String> {
+    match prev {
+        Some((seq, hash)) if *seq == through_seq && hash == chain_hash => {}
+        _ => return Err(format!("does not cover the preceding entry (claims {})", through_seq)),
+    }
+
+    let public_key = public_key.ok_or("no signing key to verify against")?;
+    let signature = hex::decode(signature).map_err(|e| e.to_string())?;
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(checkpoint_message(through_seq, chain_hash).as_bytes(), &signature)
+        .map_err(|_| "signature does not verify".to_string())
+}
+
+/// Load the checkpoint key, generating it on first use
+fn load_signing_key(path: &Path) -> Result<Ed25519KeyPair> {
+    let pkcs8 = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let document = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                .map_err(|_| anyhow!("key generation failed"))?;
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            use std::os::unix::fs::OpenOptionsExt;
+            let mut file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .mode(0o600)
+                .open(path)?;
+            file.write_all(document.as_ref())?;
+            info!("Generated audit signing key at {}", path.display());
+            document.as_ref().to_vec()
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    Ed25519KeyPair::from_pkcs8(&pkcs8).map_err(|e| anyhow!("invalid signing key {}: {}", path.display(), e))
+}
+
+fn open_log(path: &Path, append_only: bool) -> Result<BufWriter<File>> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    if append_only {
+        if let Err(e) = set_append_only(&file, true) {
+            warn!("Could not mark {} append-only: {}", path.display(), e);
+        }
+    }
+
+    Ok(BufWriter::new(file))
+}
+
+/// Toggle the filesystem append-only attribute (`chattr +a`)
+fn set_append_only(file: &File, enable: bool) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    const FS_APPEND_FL: libc::c_long = 0x20;
+
+    let fd = file.as_raw_fd();
+    let mut flags: libc::c_long = 0;
+    // SAFETY: fd is open for the duration of the calls and flags outlives them
+    unsafe {
+        if libc::ioctl(fd, libc::FS_IOC_GETFLAGS, &mut flags) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        if enable {
+            flags |= FS_APPEND_FL;
+        } else {
+            flags &= !FS_APPEND_FL;
+        }
+        if libc::ioctl(fd, libc::FS_IOC_SETFLAGS, &flags) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Rotated files for `output_path`, oldest first
+fn rotated_logs(output_path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (output_path.parent(), output_path.file_name()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", name.to_string_lossy());
+
+    let mut rotated: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .is_some_and(|n| n.to_string_lossy().starts_with(&prefix))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    // Timestamp suffixes sort chronologically
+    rotated.sort();
+    rotated
+}
+
+/// Every log file in chain order
+fn log_files(output_path: &Path) -> Vec<PathBuf> {
+    let mut files = rotated_logs(output_path);
+    if output_path.exists() {
+        files.push(output_path.to_path_buf());
+    }
+    files
+}
+
+/// Last entry across the log files, to continue the chain from
+fn last_entry(output_path: &Path) -> Option<AuditEntry> {
+    log_files(output_path).iter().rev().find_map(|path| {
+        let reader = BufReader::new(File::open(path).ok()?);
+        reader
+            .lines()
+            .map_while(|line| line.ok())
+            .filter(|line| !line.is_empty())
+            .last()
+            .and_then(|line| serde_json::from_str(&line).ok())
+    })
+}
+
+/// Render an entry as a CEF record
+fn to_cef(entry: &AuditEntry) -> String {
+    let (name, severity) = match &entry.event {
+        AuditEvent::Decision { decision, .. } => {
+            let denied = decision.eq_ignore_ascii_case("deny");
+            (format!("Decision {}", decision), if denied { 5 } else { 1 })
+        }
+        AuditEvent::Prompt { approved, .. } => {
+            (format!("Prompt {}", if *approved { "approved" } else { "denied" }), 3)
+        }
+        AuditEvent::Violation { violation_type, severity, .. } => (
+            format!("Violation {}", violation_type),
+            match severity {
+                ViolationSeverity::Info => 1,
+                ViolationSeverity::Low => 3,
+                ViolationSeverity::Medium => 5,
+                ViolationSeverity::High => 8,
+                ViolationSeverity::Critical => 10,
+            },
+        ),
+        AuditEvent::Anomaly { anomaly_type, score, .. } => {
+            (format!("Anomaly {}", anomaly_type), (score * 10.0).round().clamp(1.0, 10.0) as u8)
+        }
+        AuditEvent::Alert { alert_type, .. } => (format!("Alert {:?}", alert_type), 9),
+        AuditEvent::ConfigChanged { component, .. } => (format!("Config changed {}", component), 3),
+        other => (other.kind().to_string(), 1),
+    };
+
+    let mut extension = vec![
+        ("rt", entry.timestamp.timestamp_millis().to_string()),
+        ("dvchost", entry.machine_id.clone()),
+        ("externalId", entry.seq.to_string()),
+    ];
+    let request = match &entry.event {
+        AuditEvent::Request { request, .. }
+        | AuditEvent::Decision { request, .. }
+        | AuditEvent::Prompt { request, .. }
+        | AuditEvent::Violation { request, .. }
+        | AuditEvent::Override { request, .. }
+        | AuditEvent::Lease { request, .. } => Some(request),
+        _ => None,
+    };
+    if let Some(request) = request {
+        extension.push(("spid", request.pid.to_string()));
+        extension.push(("sproc", request.process_path.clone()));
+        extension.push(("suser", request.user.clone()));
+        extension.push(("cs1Label", "capability".into()));
+        extension.push(("cs1", request.capability.clone()));
+        if let Some(resource) = &request.resource {
+            extension.push(("fname", resource.clone()));
+        }
+    }
+    match &entry.event {
+        AuditEvent::Decision { decision, reason, .. } => {
+            extension.push(("act", decision.clone()));
+            extension.push(("reason", reason.clone()));
+        }
+        AuditEvent::Anomaly { process_path, explanation, .. } => {
+            extension.push(("sproc", process_path.clone()));
+            extension.push(("msg", explanation.clone()));
+        }
+        AuditEvent::Alert { message, .. } => extension.push(("msg", message.clone())),
+        _ => {}
+    }
+    extension.push(("cs2Label", "hash".into()));
+    extension.push(("cs2", entry.hash.clone()));
+
+    let extension = extension
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, cef_escape_value(value)))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!(
+        "CEF:0|Daemoniorum|Guardian|{}|{}|{}|{}|{}",
+        env!("CARGO_PKG_VERSION"),
+        entry.event.kind(),
+        cef_escape_header(&name),
+        severity,
+        extension
+    )
+}
 
-    let mut hasher = DefaultHasher::new();
-    format!("{:?}", config).hash(&mut hasher);
-    format!("{:016x}", hasher.finish())
+fn cef_escape_header(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+fn cef_escape_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n")
 }
 
 #[cfg(test)]
@@ -551,6 +1072,9 @@ mod tests {
             output_path: log_path.clone(),
             rotate_size_mb: 100,
             retention_days: 7,
+            append_only: false,
+            signing_key_path: dir.path().join("audit.key"),
+            ..AuditConfig::default()
         };
 
         let logger = AuditLogger::new(&config).unwrap();
@@ -591,6 +1115,9 @@ mod tests {
             output_path: log_path.clone(),
             rotate_size_mb: 100,
             retention_days: 7,
+            append_only: false,
+            signing_key_path: dir.path().join("audit.key"),
+            ..AuditConfig::default()
         };
 
         let logger = AuditLogger::new(&config).unwrap();
@@ -609,4 +1136,95 @@ mod tests {
         assert!(report.is_valid());
         assert_eq!(report.entries_checked, 10);
     }
+
+    fn test_config(dir: &Path) -> AuditConfig {
+        AuditConfig {
+            output_path: dir.join("audit.log"),
+            append_only: false,
+            checkpoint_every: 4,
+            signing_key_path: dir.join("audit.key"),
+            ..AuditConfig::default()
+        }
+    }
+
+    fn alert(i: usize) -> AuditEvent {
+        AuditEvent::Alert {
+            alert_type: AlertType::SuspiciousActivity,
+            message: format!("Test alert {}", i),
+            context: std::collections::HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_checkpoints_and_restart() {
+        let dir = tempdir().unwrap();
+        let config = test_config(dir.path());
+
+        let logger = AuditLogger::new(&config).unwrap();
+        for i in 0..5 {
+            logger.log(alert(i));
+        }
+        drop(logger);
+
+        // A restarted logger continues the same chain
+        let logger = AuditLogger::new(&config).unwrap();
+        for i in 5..8 {
+            logger.log(alert(i));
+        }
+
+        let report = logger.verify_integrity().unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
+        assert_eq!(report.checkpoints_verified, 1);
+        assert_eq!(report.first_seq, Some(0));
+        assert_eq!(report.last_seq, Some(8));
+        assert_eq!(report.unsigned_tail, 4);
+    }
+
+    #[test]
+    fn test_tampering_detected() {
+        let dir = tempdir().unwrap();
+        let config = test_config(dir.path());
+
+        let logger = AuditLogger::new(&config).unwrap();
+        for i in 0..6 {
+            logger.log(alert(i));
+        }
+
+        let content = std::fs::read_to_string(&config.output_path).unwrap();
+        std::fs::write(&config.output_path, content.replace("Test alert 2", "Test alert X")).unwrap();
+        let report = logger.verify_integrity().unwrap();
+        assert!(matches!(report.errors.as_slice(), [IntegrityError::HashMismatch { line: 3, .. }]));
+
+        // Dropping a line breaks the chain
+        let lines: Vec<_> = content.lines().filter(|l| !l.contains("Test alert 1")).collect();
+        std::fs::write(&config.output_path, lines.join("\n")).unwrap();
+        let report = logger.verify_integrity().unwrap();
+        assert!(report.errors.iter().any(|e| matches!(e, IntegrityError::ChainBroken { .. })));
+    }
+
+    #[test]
+    fn test_export_cef() {
+        let dir = tempdir().unwrap();
+        let logger = AuditLogger::new(&test_config(dir.path())).unwrap();
+
+        let request = CapabilityRequest {
+            pid: 42,
+            process_path: "/usr/bin/te|st".into(),
+            user: "user".into(),
+            capability: "network:raw".into(),
+            resource: None,
+            context: std::collections::HashMap::new(),
+        };
+        logger.log_decision(&request, "deny", "a=b", false);
+
+        let (count, cef) = logger.export(None, None, ExportFormat::Cef).unwrap();
+        assert_eq!(count, 1);
+        assert!(cef.starts_with("CEF:0|Daemoniorum|Guardian|"));
+        assert!(cef.contains("|Decision|Decision deny|5|"));
+        assert!(cef.contains("sproc=/usr/bin/te|st"));
+        assert!(cef.contains("reason=a\\=b"));
+
+        let (count, _) = logger.export(Some(Utc::now() + chrono::Duration::hours(1)), None, ExportFormat::Jsonl).unwrap();
+        assert_eq!(count, 0);
+    }
 }
@@ -23,6 +23,18 @@ pub struct GuardianConfig {
     /// Audit configuration
     #[serde(default)]
     pub audit: AuditConfig,
+
+    /// Interactive prompt configuration
+    #[serde(default)]
+    pub prompts: PromptConfig,
+
+    /// Time-limited grant configuration
+    #[serde(default)]
+    pub leases: LeaseConfig,
+
+    /// Context feeds for policy conditions
+    #[serde(default)]
+    pub context: ContextConfig,
 }
 
 impl Default for GuardianConfig {
@@ -32,6 +44,9 @@ impl Default for GuardianConfig {
             intent: IntentConfig::default(),
             patterns: PatternConfig::default(),
             audit: AuditConfig::default(),
+            prompts: PromptConfig::default(),
+            leases: LeaseConfig::default(),
+            context: ContextConfig::default(),
         }
     }
 }
@@ -54,6 +69,10 @@ pub struct PolicyConfig {
     /// Sandbox configurations
     #[serde(default)]
     pub sandboxes: Vec<SandboxProfile>,
+
+    /// Where "always allow" / "always deny" prompt answers are kept
+    #[serde(default = "default_remembered_path")]
+    pub remembered_path: PathBuf,
 }
 
 impl Default for PolicyConfig {
@@ -74,10 +93,15 @@ impl Default for PolicyConfig {
             ],
             capability_rules: Vec::new(),
             sandboxes: default_sandboxes(),
+            remembered_path: default_remembered_path(),
         }
     }
 }
 
+fn default_remembered_path() -> PathBuf {
+    PathBuf::from("/var/lib/guardian/remembered.json")
+}
+
 /// Default policy for unknown requests
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
@@ -125,12 +149,24 @@ pub enum RuleCondition {
     AppPath(String),
     /// User matches
     User(String),
-    /// Time window
-    TimeWindow { start: String, end: String },
+    /// Local time between `start` and `end` (HH:MM), wrapping past
+    /// midnight when `end` is earlier; optionally only on some days
+    TimeWindow {
+        start: String,
+        end: String,
+        #[serde(default)]
+        days: Vec<chrono::Weekday>,
+    },
     /// Resource matches pattern
     ResourcePath(String),
     /// Intent matches
     Intent(String),
+    /// A VPN is connected (true) or not (false), per wraith
+    Vpn(bool),
+    /// The active session is locked (true) or unlocked (false), per spectre
+    SessionLocked(bool),
+    /// Running on battery (true) or AC power (false), per slumber
+    OnBattery(bool),
 }
 
 /// Rule action
@@ -267,37 +303,93 @@ pub enum RiskLevel {
 }
 
 /// Pattern learning configuration
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PatternConfig {
     /// Enable pattern learning
     #[serde(default = "default_true")]
     pub enabled: bool,
 
-    /// Pattern database path
+    /// Where learned behavior profiles are persisted
     #[serde(default = "default_pattern_db")]
     pub database_path: String,
 
-    /// Anomaly detection threshold (0.0 - 1.0)
+    /// Anomaly detection threshold (0.0 - 1.0); at or above it the user is prompted
     #[serde(default = "default_anomaly_threshold")]
     pub anomaly_threshold: f32,
 
+    /// Scores at or above this are audited as anomalies without changing the decision
+    #[serde(default = "default_warn_threshold")]
+    pub warn_threshold: f32,
+
+    /// Scores at or above this are denied outright
+    #[serde(default = "default_block_threshold")]
+    pub block_threshold: f32,
+
     /// Learning rate
     #[serde(default = "default_learning_rate")]
     pub learning_rate: f32,
+
+    /// Observations needed before an app's profile is trusted for scoring
+    #[serde(default = "default_min_observations")]
+    pub min_observations: u32,
+
+    /// Learned behavior loses half its weight over this many days
+    #[serde(default = "default_decay_half_life_days")]
+    pub decay_half_life_days: f32,
+
+    /// How often decay is applied and profiles are saved
+    #[serde(default = "default_pattern_save_interval")]
+    pub save_interval_secs: u64,
+}
+
+impl Default for PatternConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            database_path: default_pattern_db(),
+            anomaly_threshold: default_anomaly_threshold(),
+            warn_threshold: default_warn_threshold(),
+            block_threshold: default_block_threshold(),
+            learning_rate: default_learning_rate(),
+            min_observations: default_min_observations(),
+            decay_half_life_days: default_decay_half_life_days(),
+            save_interval_secs: default_pattern_save_interval(),
+        }
+    }
 }
 
 fn default_pattern_db() -> String {
-    "/var/lib/guardian/patterns.db".into()
+    "/var/lib/guardian/patterns.json".into()
 }
 
 fn default_anomaly_threshold() -> f32 {
     0.8
 }
 
+fn default_warn_threshold() -> f32 {
+    0.6
+}
+
+fn default_block_threshold() -> f32 {
+    0.95
+}
+
 fn default_learning_rate() -> f32 {
     0.1
 }
 
+fn default_min_observations() -> u32 {
+    20
+}
+
+fn default_decay_half_life_days() -> f32 {
+    30.0
+}
+
+fn default_pattern_save_interval() -> u64 {
+    300
+}
+
 /// Audit configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditConfig {
@@ -328,6 +420,18 @@ pub struct AuditConfig {
     /// Real-time alerts for critical events
     #[serde(default)]
     pub alerts: AlertConfig,
+
+    /// Mark the active log append-only on the filesystem (needs CAP_LINUX_IMMUTABLE)
+    #[serde(default = "default_true")]
+    pub append_only: bool,
+
+    /// Write a signed checkpoint after this many entries
+    #[serde(default = "default_checkpoint_every")]
+    pub checkpoint_every: u64,
+
+    /// Ed25519 key (PKCS#8) used to sign checkpoints; created if missing
+    #[serde(default = "default_signing_key_path")]
+    pub signing_key_path: PathBuf,
 }
 
 impl Default for AuditConfig {
@@ -340,6 +444,9 @@ impl Default for AuditConfig {
             log_decisions: true,
             log_capability_usage: true,
             alerts: AlertConfig::default(),
+            append_only: true,
+            checkpoint_every: default_checkpoint_every(),
+            signing_key_path: default_signing_key_path(),
         }
     }
 }
@@ -356,6 +463,14 @@ fn default_retention_days() -> u32 {
     90
 }
 
+fn default_checkpoint_every() -> u64 {
+    1000
+}
+
+fn default_signing_key_path() -> PathBuf {
+    PathBuf::from("/var/lib/guardian/audit-signing.key")
+}
+
 /// Alert configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AlertConfig {
@@ -376,6 +491,175 @@ pub struct AlertConfig {
     pub on_critical_capability: bool,
 }
 
+/// Interactive prompt configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptConfig {
+    /// Ask the user through herald and wait for the answer
+    ///
+    /// When disabled, prompts are returned to the caller as `PromptRequired`.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Herald socket path
+    #[serde(default = "default_herald_socket")]
+    pub herald_socket: PathBuf,
+
+    /// How long the requester is blocked waiting for an answer
+    #[serde(default = "default_prompt_timeout")]
+    pub timeout_secs: u32,
+
+    /// Executables (e.g. a shell dialog) allowed to answer prompts over
+    /// IPC; by default only herald actions answer
+    #[serde(default)]
+    pub answer_clients: Vec<PathBuf>,
+}
+
+impl Default for PromptConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            herald_socket: default_herald_socket(),
+            timeout_secs: default_prompt_timeout(),
+            answer_clients: Vec::new(),
+        }
+    }
+}
+
+fn default_herald_socket() -> PathBuf {
+    PathBuf::from("/run/herald/herald.sock")
+}
+
+fn default_prompt_timeout() -> u32 {
+    30
+}
+
+/// Time-limited grant configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaseConfig {
+    /// Issue leases for grants that have a TTL
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// TTL for grants no rule matches; none means they never expire
+    #[serde(default)]
+    pub default_ttl_secs: Option<u64>,
+
+    /// TTL for one-time prompt approvals
+    #[serde(default = "default_prompt_ttl")]
+    pub prompt_ttl_secs: Option<u64>,
+
+    /// Per-capability TTLs, first match wins
+    #[serde(default)]
+    pub rules: Vec<LeaseRule>,
+
+    /// Renewals allowed before a full re-evaluation is required
+    #[serde(default = "default_max_renewals")]
+    pub max_renewals: u32,
+
+    /// Longest lifetime of an execution token
+    #[serde(default = "default_max_token_ttl")]
+    pub max_token_ttl_secs: u64,
+}
+
+impl Default for LeaseConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            default_ttl_secs: None,
+            prompt_ttl_secs: default_prompt_ttl(),
+            rules: Vec::new(),
+            max_renewals: default_max_renewals(),
+            max_token_ttl_secs: default_max_token_ttl(),
+        }
+    }
+}
+
+/// TTL for capabilities matching a pattern
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaseRule {
+    /// Capability pattern (glob)
+    pub capability: String,
+    /// Lease length
+    pub ttl_secs: u64,
+}
+
+fn default_prompt_ttl() -> Option<u64> {
+    Some(3600)
+}
+
+fn default_max_token_ttl() -> u64 {
+    4 * 3600
+}
+
+fn default_max_renewals() -> u32 {
+    24
+}
+
+/// Context feed configuration
+///
+/// Conditions on VPN, session and power state are evaluated against the
+/// last reading from each daemon; one older than `max_age_secs` counts as
+/// unknown and the condition does not hold.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextConfig {
+    /// Poll wraith, spectre and slumber
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// How often each feed is read
+    #[serde(default = "default_context_poll_interval")]
+    pub poll_interval_secs: u64,
+
+    /// Oldest reading a condition is evaluated against
+    #[serde(default = "default_context_max_age")]
+    pub max_age_secs: u64,
+
+    /// Wraith socket path
+    #[serde(default = "default_wraith_socket")]
+    pub wraith_socket: PathBuf,
+
+    /// Spectre socket path
+    #[serde(default = "default_spectre_socket")]
+    pub spectre_socket: PathBuf,
+
+    /// Slumber socket path
+    #[serde(default = "default_slumber_socket")]
+    pub slumber_socket: PathBuf,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_interval_secs: default_context_poll_interval(),
+            max_age_secs: default_context_max_age(),
+            wraith_socket: default_wraith_socket(),
+            spectre_socket: default_spectre_socket(),
+            slumber_socket: default_slumber_socket(),
+        }
+    }
+}
+
+fn default_context_poll_interval() -> u64 {
+    5
+}
+
+fn default_context_max_age() -> u64 {
+    30
+}
+
+fn default_wraith_socket() -> PathBuf {
+    PathBuf::from("/run/wraith/wraith.sock")
+}
+
+fn default_spectre_socket() -> PathBuf {
+    PathBuf::from("/run/spectre/spectre.sock")
+}
+
+fn default_slumber_socket() -> PathBuf {
+    PathBuf::from("/run/slumber/slumber.sock")
+}
+
 /// Load configuration from file
 pub async fn load_config(path: &Path) -> Result<GuardianConfig> {
     if path.exists() {
@@ -0,0 +1,107 @@
+//! Context feeds for policy conditions
+//!
+//! Rules can depend on state other daemons own: whether a VPN is connected
+//! (wraith), whether the active session is locked (spectre) and whether
+//! the machine runs on battery (slumber). Each is polled in the background
+//! and cached with the time it was read, so evaluating a rule never waits
+//! on another daemon. A reading older than the freshness bound counts as
+//! unknown.
+
+use crate::config::ContextConfig;
+use libnyx_ipc::{NetworkClient, PowerClient, SessionClient};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// A piece of context a condition can depend on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feed {
+    /// A VPN is connected
+    Vpn,
+    /// The active session is locked
+    SessionLocked,
+    /// Running on battery
+    OnBattery,
+}
+
+impl Feed {
+    /// Name used in rule conditions
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feed::Vpn => "vpn",
+            Feed::SessionLocked => "session_locked",
+            Feed::OnBattery => "on_battery",
+        }
+    }
+}
+
+/// Last reading of every feed
+pub struct ContextFeeds {
+    config: ContextConfig,
+    readings: RwLock<HashMap<Feed, (bool, Instant)>>,
+}
+
+impl ContextFeeds {
+    /// Create feeds with nothing read yet
+    pub fn new(config: &ContextConfig) -> Self {
+        Self {
+            config: config.clone(),
+            readings: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// The feed's value, if it was read within the freshness bound
+    pub fn get(&self, feed: Feed) -> Option<bool> {
+        let max_age = Duration::from_secs(self.config.max_age_secs);
+        self.readings
+            .read()
+            .unwrap()
+            .get(&feed)
+            .filter(|(_, read_at)| read_at.elapsed() <= max_age)
+            .map(|(value, _)| *value)
+    }
+
+    /// Record a reading taken at `read_at`
+    pub fn record(&self, feed: Feed, value: bool, read_at: Instant) {
+        let previous = self.readings.write().unwrap().insert(feed, (value, read_at));
+        if previous.map(|(value, _)| value) != Some(value) {
+            info!("Context {} is now {}", feed.name(), value);
+        }
+    }
+
+    /// Poll every feed until Guardian exits
+    pub async fn run(&self) {
+        if !self.config.enabled {
+            return;
+        }
+
+        let mut ticker = tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs.max(1)));
+        loop {
+            ticker.tick().await;
+            tokio::join!(self.poll_vpn(), self.poll_session(), self.poll_power());
+        }
+    }
+
+    async fn poll_vpn(&self) {
+        match NetworkClient::with_socket(&self.config.wraith_socket).status().await {
+            Ok(status) => self.record(Feed::Vpn, status.vpn_connected(), Instant::now()),
+            Err(e) => debug!("No network state from wraith: {}", e),
+        }
+    }
+
+    async fn poll_session(&self) {
+        // Fails while nobody is logged in on the seat, leaving the state unknown
+        match SessionClient::with_socket(&self.config.spectre_socket).active_session(None).await {
+            Ok(session) => self.record(Feed::SessionLocked, session.is_locked(), Instant::now()),
+            Err(e) => debug!("No session state from spectre: {}", e),
+        }
+    }
+
+    async fn poll_power(&self) {
+        match PowerClient::with_socket(&self.config.slumber_socket).power_supply().await {
+            Ok(supply) => self.record(Feed::OnBattery, !supply.on_ac_power, Instant::now()),
+            Err(e) => debug!("No power state from slumber: {}", e),
+        }
+    }
+}
@@ -4,8 +4,8 @@ use crate::audit::{AuditEvent, AuditLogger};
 use crate::config::RiskLevel;
 use crate::intent::{AnalyzedIntent, IntentAnalyzer};
 use crate::pattern::{PatternAnalysis, PatternLearner};
-use crate::policy::{CapabilityRequest, PolicyDecision, PolicyEngine, PolicyResult};
-use std::sync::Arc;
+use crate::policy::{CapabilityRequest, PolicyDecision, PolicyEngine, PolicyResult, RuleTrace};
+use std::sync::{Arc, RwLock};
 use tracing::{debug, info, warn};
 
 /// Final security decision
@@ -53,7 +53,8 @@ pub enum SandboxLevel {
 
 /// Decision engine
 pub struct DecisionEngine {
-    policy_engine: Arc<PolicyEngine>,
+    /// Active policies, swapped on reload
+    policy_engine: RwLock<Arc<PolicyEngine>>,
     intent_analyzer: Arc<IntentAnalyzer>,
     pattern_learner: Arc<PatternLearner>,
     audit_logger: Arc<AuditLogger>,
@@ -70,7 +71,7 @@ impl DecisionEngine {
         permissive_mode: bool,
     ) -> Self {
         Self {
-            policy_engine,
+            policy_engine: RwLock::new(policy_engine),
             intent_analyzer,
             pattern_learner,
             audit_logger,
@@ -78,12 +79,51 @@ impl DecisionEngine {
         }
     }
 
+    /// Active policy engine
+    pub fn policies(&self) -> Arc<PolicyEngine> {
+        self.policy_engine.read().unwrap().clone()
+    }
+
+    /// Swap in a newly loaded policy set
+    pub fn replace_policies(&self, policy_engine: Arc<PolicyEngine>) {
+        *self.policy_engine.write().unwrap() = policy_engine;
+        info!("Policy set replaced");
+    }
+
     /// Evaluate a capability request and make a decision
     pub async fn evaluate(&self, request: &CapabilityRequest) -> SecurityDecision {
+        let policies = self.policies();
+        let decision = self.evaluate_with(&policies, request).await;
+
+        if let Some(pattern) = &decision.pattern {
+            if pattern.anomaly_score >= self.pattern_learner.warn_threshold() {
+                self.audit_logger.log_anomaly(
+                    &request.process_path,
+                    "behavior",
+                    pattern.anomaly_score,
+                    &pattern.explanation,
+                );
+            }
+        }
+
+        decision
+    }
+
+    /// Decide a request against `candidate` policies without recording anything
+    pub async fn dry_run(
+        &self,
+        candidate: &PolicyEngine,
+        request: &CapabilityRequest,
+    ) -> (SecurityDecision, Vec<RuleTrace>) {
+        let (_, trace) = candidate.explain(request);
+        (self.evaluate_with(candidate, request).await, trace)
+    }
+
+    async fn evaluate_with(&self, policy_engine: &PolicyEngine, request: &CapabilityRequest) -> SecurityDecision {
         debug!("Evaluating request: {:?}", request);
 
         // Step 1: Policy evaluation
-        let policy_result = self.policy_engine.evaluate(request);
+        let policy_result = policy_engine.evaluate(request);
         debug!("Policy result: {:?}", policy_result.decision);
 
         // Fast path: explicit allow/deny from policy
@@ -156,6 +196,20 @@ impl DecisionEngine {
             );
         }
 
+        // Far outside learned behavior
+        if pattern.anomaly_score >= self.pattern_learner.block_threshold() {
+            if self.permissive_mode {
+                return (
+                    FinalDecision::Sandbox(SandboxLevel::Maximum),
+                    format!("Anomalous behavior - sandboxing (permissive mode): {}", pattern.explanation),
+                );
+            }
+            return (
+                FinalDecision::Deny,
+                format!("Anomalous behavior: {}", pattern.explanation),
+            );
+        }
+
         // High risk + anomalous pattern = sandbox or prompt
         if matches!(intent.risk_level, RiskLevel::High) {
             if pattern.anomaly_score > pattern.is_known as u8 as f32 * 0.5 + 0.5 {
@@ -233,6 +287,34 @@ impl DecisionEngine {
         }
     }
 
+    /// Lightweight re-check for lease renewals: static policy only
+    ///
+    /// Only an outright or remembered allow renews; a lease first granted
+    /// through a prompt or sandbox has to be requested (and answered) again.
+    pub fn recheck(&self, request: &CapabilityRequest) -> bool {
+        self.policies().evaluate(request).decision == PolicyDecision::Allow
+    }
+
+    /// Whether static policy allows a request outright, without prompting
+    /// or sandboxing (for pre-authorizing execution tokens)
+    pub fn preauthorize(&self, request: &CapabilityRequest) -> bool {
+        self.policies().evaluate(request).decision == PolicyDecision::Allow
+    }
+
+    /// Persist a user's "always" answer in policy storage
+    pub fn remember_choice(&self, request: &CapabilityRequest, allow: bool) {
+        self.policies().remember(request, allow);
+    }
+
+    /// Record a threat another agent detected, making the offending app's
+    /// future requests score as more anomalous
+    pub fn report_threat(&self, process_path: Option<&str>, anomaly_type: &str, severity: f32, explanation: &str) {
+        self.audit_logger.log_anomaly(process_path.unwrap_or_default(), anomaly_type, severity, explanation);
+        if let Some(process_path) = process_path {
+            self.pattern_learner.flag(process_path, severity);
+        }
+    }
+
     /// Record a decision for learning
     pub fn record_decision(&self, request: &CapabilityRequest, decision: &SecurityDecision, user_approved: bool) {
         // Log to audit
@@ -288,4 +370,40 @@ mod tests {
         let decision = engine.evaluate(&request).await;
         assert_eq!(decision.decision, FinalDecision::Allow);
     }
+
+    #[tokio::test]
+    async fn test_recheck_needs_allow() {
+        let policy_config = PolicyConfig {
+            default_policy: DefaultPolicy::Prompt,
+            trusted_apps: vec![],
+            remembered_path: std::env::temp_dir().join("guardian-test-recheck.json"),
+            ..PolicyConfig::default()
+        };
+        let _ = std::fs::remove_file(&policy_config.remembered_path);
+
+        let engine = DecisionEngine::new(
+            Arc::new(PolicyEngine::new(&policy_config).unwrap()),
+            Arc::new(IntentAnalyzer::new(&IntentConfig::default()).unwrap()),
+            Arc::new(PatternLearner::new(&PatternConfig::default()).unwrap()),
+            Arc::new(AuditLogger::new(&AuditConfig::default()).unwrap()),
+            false,
+        );
+
+        let request = CapabilityRequest {
+            pid: 1234,
+            process_path: "/usr/bin/editor".into(),
+            user: "user".into(),
+            capability: "cap:camera".into(),
+            resource: None,
+            context: HashMap::new(),
+        };
+
+        // Granted through a prompt: renewing needs another answer
+        assert!(!engine.recheck(&request));
+
+        engine.remember_choice(&request, true);
+        assert!(engine.recheck(&request));
+
+        let _ = std::fs::remove_file(&policy_config.remembered_path);
+    }
 }
@@ -3,11 +3,18 @@
 //! Guardian listens for capability requests from the kernel and other processes
 //! via a Unix socket. This provides the interface for the security decision flow.
 
-use crate::audit::AuditLogger;
+use crate::audit::{AuditEvent, AuditLogger, ExportFormat};
+use crate::config;
+use crate::context::ContextFeeds;
 use crate::decision::{DecisionEngine, FinalDecision, SecurityDecision};
-use crate::policy::CapabilityRequest;
+use crate::lease::{Lease, LeaseManager, RevokeReason};
+use crate::policy::{CapabilityRequest, PolicyEngine, RuleTrace};
+use crate::prompt::{AnswerSource, PromptBroker};
 use crate::sandbox::{SandboxConfig, SandboxEnforcer};
+use crate::token::{ExecutionToken, TokenManager, TokenScope};
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use libnyx_ipc::{trace, Hello};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -56,6 +63,74 @@ pub enum GuardianRequest {
     },
     /// Reload configuration
     ReloadConfig,
+    /// Reload the policy set from the configuration file
+    ReloadPolicies,
+    /// Evaluate a request without enforcing or recording anything
+    TestPolicy {
+        request: CapabilityRequest,
+        /// Candidate configuration file; the active policies when absent
+        candidate_path: Option<PathBuf>,
+    },
+    /// Verify the audit log's hash chain and checkpoints
+    VerifyAuditLog {
+        /// A single file to check; the whole log when absent
+        path: Option<PathBuf>,
+    },
+    /// Export audit entries in a time range
+    ExportAuditLog {
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        format: ExportFormat,
+    },
+    /// List outstanding leases
+    ListLeases,
+    /// Extend a lease after a policy re-check
+    RenewLease {
+        lease_id: Uuid,
+    },
+    /// End a lease early
+    RevokeLease {
+        lease_id: Uuid,
+    },
+    /// Stream lease revocations on this connection
+    WatchLeases,
+    /// Pre-authorize work done on someone's behalf; scopes static policy
+    /// doesn't allow outright are refused
+    IssueToken {
+        /// Whose behalf the work runs on, e.g. `persona:lilith`; policies
+        /// see it as the process path
+        subject: String,
+        user: String,
+        /// Process asking for the token
+        pid: u32,
+        process_path: String,
+        scopes: Vec<TokenScope>,
+        ttl_secs: u64,
+    },
+    /// Check an action against a token
+    CheckToken {
+        token_id: Uuid,
+        capability: String,
+        resource: Option<String>,
+    },
+    /// End a token once the work is done
+    RevokeToken {
+        token_id: Uuid,
+    },
+    /// Report suspicious behavior detected by another agent
+    ReportThreat {
+        /// Reporting agent, e.g. "arachne"
+        source: String,
+        /// Detection rule that fired
+        rule: String,
+        /// 0.0 (informational) to 1.0 (certain compromise)
+        severity: f32,
+        process_path: Option<String>,
+        pid: Option<u32>,
+        /// Remote party involved, if any
+        remote: Option<String>,
+        description: String,
+    },
     /// Shutdown Guardian
     Shutdown,
 }
@@ -71,6 +146,9 @@ pub enum GuardianResponse {
         reason: String,
         sandbox_config: Option<SandboxConfig>,
         recommended_action: Option<String>,
+        /// Present when the grant is time-limited
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lease: Option<LeaseGrant>,
     },
     /// Prompt needed - waiting for user
     PromptRequired {
@@ -94,6 +172,58 @@ pub enum GuardianResponse {
     SandboxProfile {
         config: SandboxConfig,
     },
+    /// Dry-run evaluation result
+    PolicyTest {
+        decision: String,
+        reason: String,
+        matched_rule: Option<String>,
+        trace: Vec<RuleTrace>,
+    },
+    /// Audit log verification result
+    AuditVerification {
+        valid: bool,
+        entries_checked: u64,
+        checkpoints_verified: u64,
+        first_seq: Option<u64>,
+        last_seq: Option<u64>,
+        unsigned_tail: u64,
+        errors: Vec<String>,
+    },
+    /// Exported audit entries
+    AuditExport {
+        format: ExportFormat,
+        count: usize,
+        data: String,
+    },
+    /// Outstanding leases
+    Leases {
+        leases: Vec<Lease>,
+    },
+    /// Lease granted or renewed
+    LeaseRenewed {
+        lease: LeaseGrant,
+    },
+    /// A lease ended; withdraw the capability (sent to watchers)
+    LeaseRevoked {
+        lease_id: Uuid,
+        pid: u32,
+        process_path: String,
+        capability: String,
+        resource: Option<String>,
+        reason: RevokeReason,
+    },
+    /// Token issued with the scopes policy allowed
+    TokenIssued {
+        token_id: Uuid,
+        expires_at: DateTime<Utc>,
+        granted: Vec<TokenScope>,
+        refused: Vec<TokenScope>,
+    },
+    /// Whether a token allows an action
+    TokenCheck {
+        allowed: bool,
+        reason: String,
+    },
     /// Generic success
     Ok {
         message: String,
@@ -105,6 +235,22 @@ pub enum GuardianResponse {
     },
 }
 
+/// Time-limited grant attached to a decision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaseGrant {
+    pub lease_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<&Lease> for LeaseGrant {
+    fn from(lease: &Lease) -> Self {
+        Self {
+            lease_id: lease.id,
+            expires_at: lease.expires_at,
+        }
+    }
+}
+
 /// Prompt details for user confirmation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PromptDetails {
@@ -138,26 +284,36 @@ struct PendingPrompt {
     request: CapabilityRequest,
     decision: SecurityDecision,
     response_tx: mpsc::Sender<(bool, bool)>,
+    /// A requester is blocked on this prompt and will resolve it
+    awaited: bool,
 }
 
-/// Guardian IPC server
-pub struct GuardianServer {
-    /// Socket path
-    socket_path: PathBuf,
-    /// Decision engine
+/// State shared by all connections
+#[derive(Clone)]
+struct Shared {
     decision_engine: Arc<DecisionEngine>,
-    /// Audit logger
     audit_logger: Arc<AuditLogger>,
-    /// Sandbox enforcer
     sandbox_enforcer: Arc<RwLock<SandboxEnforcer>>,
-    /// Pending prompts
     pending_prompts: Arc<RwLock<HashMap<Uuid, PendingPrompt>>>,
-    /// Statistics
+    prompt_broker: Arc<PromptBroker>,
+    lease_manager: Arc<LeaseManager>,
+    token_manager: Arc<TokenManager>,
     stats: Arc<RwLock<ServerStats>>,
+    start_time: std::time::Instant,
+    /// Configuration file policies are reloaded from
+    config_path: PathBuf,
+    /// Hash of the configuration the active policies came from
+    config_hash: Arc<RwLock<String>>,
+}
+
+/// Guardian IPC server
+pub struct GuardianServer {
+    /// Socket path
+    socket_path: PathBuf,
+    /// Handles shared with connections
+    shared: Shared,
     /// Shutdown signal
     shutdown_tx: broadcast::Sender<()>,
-    /// Start time
-    start_time: std::time::Instant,
 }
 
 /// Server statistics
@@ -175,20 +331,33 @@ impl GuardianServer {
     /// Create a new Guardian server
     pub fn new(
         socket_path: impl Into<PathBuf>,
+        config_path: impl Into<PathBuf>,
         decision_engine: Arc<DecisionEngine>,
         audit_logger: Arc<AuditLogger>,
+        prompt_broker: Arc<PromptBroker>,
+        lease_manager: Arc<LeaseManager>,
+        token_manager: Arc<TokenManager>,
     ) -> Self {
         let (shutdown_tx, _) = broadcast::channel(1);
+        let config_path = config_path.into();
+        let config_hash = hash_file(&config_path);
 
         Self {
             socket_path: socket_path.into(),
-            decision_engine,
-            audit_logger,
-            sandbox_enforcer: Arc::new(RwLock::new(SandboxEnforcer::new())),
-            pending_prompts: Arc::new(RwLock::new(HashMap::new())),
-            stats: Arc::new(RwLock::new(ServerStats::default())),
+            shared: Shared {
+                decision_engine,
+                audit_logger,
+                sandbox_enforcer: Arc::new(RwLock::new(SandboxEnforcer::new())),
+                pending_prompts: Arc::new(RwLock::new(HashMap::new())),
+                prompt_broker,
+                lease_manager,
+                token_manager,
+                stats: Arc::new(RwLock::new(ServerStats::default())),
+                start_time: std::time::Instant::now(),
+                config_path,
+                config_hash: Arc::new(RwLock::new(config_hash)),
+            },
             shutdown_tx,
-            start_time: std::time::Instant::now(),
         }
     }
 
@@ -248,51 +417,33 @@ impl GuardianServer {
     }
 
     async fn handle_connection(&self, stream: UnixStream) {
-        let decision_engine = self.decision_engine.clone();
-        let audit_logger = self.audit_logger.clone();
-        let sandbox_enforcer = self.sandbox_enforcer.clone();
-        let pending_prompts = self.pending_prompts.clone();
-        let stats = self.stats.clone();
-        let start_time = self.start_time;
+        let shared = self.shared.clone();
 
         // Update connection count
         {
-            let mut s = stats.write().await;
+            let mut s = shared.stats.write().await;
             s.active_connections += 1;
         }
 
         tokio::spawn(async move {
-            if let Err(e) = Self::process_connection(
-                stream,
-                decision_engine,
-                audit_logger,
-                sandbox_enforcer,
-                pending_prompts,
-                stats.clone(),
-                start_time,
-            ).await {
+            if let Err(e) = Self::process_connection(stream, &shared).await {
                 debug!("Connection closed: {}", e);
             }
 
             // Update connection count
-            let mut s = stats.write().await;
+            let mut s = shared.stats.write().await;
             s.active_connections = s.active_connections.saturating_sub(1);
         });
     }
 
-    async fn process_connection(
-        stream: UnixStream,
-        decision_engine: Arc<DecisionEngine>,
-        audit_logger: Arc<AuditLogger>,
-        sandbox_enforcer: Arc<RwLock<SandboxEnforcer>>,
-        pending_prompts: Arc<RwLock<HashMap<Uuid, PendingPrompt>>>,
-        stats: Arc<RwLock<ServerStats>>,
-        start_time: std::time::Instant,
-    ) -> Result<()> {
+    async fn process_connection(stream: UnixStream, shared: &Shared) -> Result<()> {
+        let peer_pid = stream.peer_cred().ok().and_then(|cred| cred.pid());
         let (reader, mut writer) = stream.into_split();
         let mut reader = BufReader::new(reader);
         let mut line = String::new();
 
+        let hello = Hello::new("guardian", env!("CARGO_PKG_VERSION")).with_features(&["leases", "prompts"]);
+
         loop {
             line.clear();
             let bytes_read = reader.read_line(&mut line).await?;
@@ -300,6 +451,11 @@ impl GuardianServer {
                 break; // Connection closed
             }
 
+            if let Some(reply) = hello.respond(&line) {
+                writer.write_all((reply + "\n").as_bytes()).await?;
+                continue;
+            }
+
             let request: GuardianRequest = match serde_json::from_str(&line) {
                 Ok(req) => req,
                 Err(e) => {
@@ -313,32 +469,80 @@ impl GuardianServer {
                 }
             };
 
-            let response = Self::handle_request(
-                request,
-                &decision_engine,
-                &audit_logger,
-                &sandbox_enforcer,
-                &pending_prompts,
-                &stats,
-                start_time,
-            ).await;
+            let watch = matches!(request, GuardianRequest::WatchLeases);
+            let response = trace::traced(&line, Self::handle_request(request, peer_pid, shared)).await;
 
             let json = serde_json::to_string(&response)? + "\n";
             writer.write_all(json.as_bytes()).await?;
+
+            if watch {
+                return Self::stream_revocations(reader, writer, shared).await;
+            }
         }
 
         Ok(())
     }
 
-    async fn handle_request(
-        request: GuardianRequest,
-        decision_engine: &Arc<DecisionEngine>,
-        audit_logger: &Arc<AuditLogger>,
-        sandbox_enforcer: &Arc<RwLock<SandboxEnforcer>>,
-        pending_prompts: &Arc<RwLock<HashMap<Uuid, PendingPrompt>>>,
-        stats: &Arc<RwLock<ServerStats>>,
-        start_time: std::time::Instant,
-    ) -> GuardianResponse {
+    /// Forward lease revocations until the watcher disconnects
+    async fn stream_revocations(
+        mut reader: BufReader<tokio::net::unix::OwnedReadHalf>,
+        mut writer: tokio::net::unix::OwnedWriteHalf,
+        shared: &Shared,
+    ) -> Result<()> {
+        let mut revocations = shared.lease_manager.watch();
+        let mut line = String::new();
+
+        loop {
+            tokio::select! {
+                revocation = revocations.recv() => {
+                    let revocation = match revocation {
+                        Ok(revocation) => revocation,
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            warn!("Lease watcher missed {} revocations", missed);
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                    let request = revocation.lease.request;
+                    let event = GuardianResponse::LeaseRevoked {
+                        lease_id: revocation.lease.id,
+                        pid: request.pid,
+                        process_path: request.process_path,
+                        capability: request.capability,
+                        resource: request.resource,
+                        reason: revocation.reason,
+                    };
+                    let json = serde_json::to_string(&event)? + "\n";
+                    writer.write_all(json.as_bytes()).await?;
+                }
+                read = reader.read_line(&mut line) => {
+                    // Watchers only listen; anything else (or EOF) ends the stream
+                    if read? == 0 {
+                        break;
+                    }
+                    line.clear();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_request(request: GuardianRequest, peer_pid: Option<i32>, shared: &Shared) -> GuardianResponse {
+        let Shared {
+            decision_engine,
+            audit_logger,
+            sandbox_enforcer,
+            pending_prompts,
+            prompt_broker,
+            lease_manager,
+            token_manager,
+            stats,
+            start_time,
+            config_path,
+            config_hash,
+        } = shared;
+
         match request {
             GuardianRequest::CheckCapability { request_id, request } => {
                 // Log the request
@@ -369,6 +573,7 @@ impl GuardianServer {
                             reason: decision.reason.clone(),
                             sandbox_config: None,
                             recommended_action: decision.recommended_action.clone(),
+                            lease: Self::lease(shared, &request, false).await,
                         }
                     }
                     FinalDecision::Deny => {
@@ -379,6 +584,7 @@ impl GuardianServer {
                             reason: decision.reason.clone(),
                             sandbox_config: None,
                             recommended_action: decision.recommended_action.clone(),
+                            lease: None,
                         }
                     }
                     FinalDecision::Sandbox(level) => {
@@ -390,10 +596,11 @@ impl GuardianServer {
 
                         GuardianResponse::Decision {
                             request_id,
-                            decision: format!("sandbox:{:?}", level).to_lowercase(),
+                            decision: decision_label(&decision.decision),
                             reason: decision.reason.clone(),
                             sandbox_config: Some(config),
                             recommended_action: decision.recommended_action.clone(),
+                            lease: Self::lease(shared, &request, false).await,
                         }
                     }
                     FinalDecision::Prompt => {
@@ -407,11 +614,13 @@ impl GuardianServer {
                                 .map(|i| format!("{:?}", i.risk_level))
                                 .unwrap_or_else(|| "Unknown".into()),
                             explanation: decision.reason.clone(),
-                            timeout_secs: 30,
+                            timeout_secs: prompt_broker.timeout_secs(),
                         };
 
+                        let message = format!("{} wants to {}", request.process_path, request.capability);
+
                         // Store pending prompt
-                        let (tx, mut rx) = mpsc::channel(1);
+                        let (tx, rx) = mpsc::channel(1);
                         {
                             let mut prompts = pending_prompts.write().await;
                             prompts.insert(request_id, PendingPrompt {
@@ -419,14 +628,54 @@ impl GuardianServer {
                                 request: request.clone(),
                                 decision: decision.clone(),
                                 response_tx: tx,
+                                awaited: prompt_broker.enabled(),
                             });
                         }
 
-                        // Return prompt required (client should wait for user response)
-                        GuardianResponse::PromptRequired {
+                        if !prompt_broker.enabled() {
+                            // Return prompt required (client should wait for user response)
+                            return GuardianResponse::PromptRequired {
+                                request_id,
+                                message,
+                                details,
+                            };
+                        }
+
+                        // Block the requester until the user answers or the prompt times out
+                        let answer = prompt_broker.ask(request_id, &message, &details, rx).await;
+                        pending_prompts.write().await.remove(&request_id);
+
+                        decision_engine.record_decision(&request, &decision, answer.approved);
+                        if answer.remember {
+                            decision_engine.remember_choice(&request, answer.approved);
+                        }
+                        audit_logger.log(AuditEvent::Prompt {
+                            request_id,
+                            request: request.clone(),
+                            approved: answer.approved,
+                            remembered: answer.remember,
+                            answered_via: format!("{:?}", answer.source).to_lowercase(),
+                            waited_ms: answer.waited.as_millis() as u64,
+                        });
+
+                        let lease = if answer.approved {
+                            Self::lease(shared, &request, !answer.remember).await
+                        } else {
+                            None
+                        };
+
+                        GuardianResponse::Decision {
                             request_id,
-                            message: format!("{} wants to {}", request.process_path, request.capability),
-                            details,
+                            decision: if answer.approved { "allow" } else { "deny" }.into(),
+                            reason: match (answer.source, answer.approved, answer.remember) {
+                                (AnswerSource::Timeout, _, _) => "No answer before prompt timed out".into(),
+                                (_, true, true) => "User approved (always)".into(),
+                                (_, true, false) => "User approved (once)".into(),
+                                (_, false, _) => "User denied".into(),
+                            },
+                            sandbox_config: None,
+                            recommended_action: None,
+                            lease,
                         }
                     }
                 }
@@ -435,11 +684,43 @@ impl GuardianServer {
             GuardianRequest::UserResponse { request_id, approved, remember } => {
                 let mut prompts = pending_prompts.write().await;
 
-                if let Some(pending) = prompts.remove(&request_id) {
-                    // Record decision
-                    decision_engine.record_decision(&pending.request, &pending.decision, approved);
+                // Anyone can reach the socket, including the requester, so
+                // only configured UI clients may answer, and IPC answers are
+                // never made permanent
+                if let Some(pending) = prompts.get(&request_id) {
+                    if !prompt_broker.accepts_answer_from(peer_pid, pending.request.pid) {
+                        warn!("Rejected prompt answer for {} from pid {:?}", request_id, peer_pid);
+                        return GuardianResponse::Error {
+                            code: ErrorCode::PermissionDenied,
+                            message: "Not allowed to answer prompts".into(),
+                        };
+                    }
+                }
+                if remember {
+                    debug!("Ignoring remember on IPC answer for {}", request_id);
+                }
+                let remember = false;
 
-                    // TODO: If remember is true, update policy
+                if let Some(pending) = prompts.remove(&request_id) {
+                    let mut lease = None;
+                    if !pending.awaited {
+                        // Nobody is blocked on this prompt, so resolve it here
+                        decision_engine.record_decision(&pending.request, &pending.decision, approved);
+                        if remember {
+                            decision_engine.remember_choice(&pending.request, approved);
+                        }
+                        audit_logger.log(AuditEvent::Prompt {
+                            request_id: pending.request_id,
+                            request: pending.request.clone(),
+                            approved,
+                            remembered: remember,
+                            answered_via: "ipc".into(),
+                            waited_ms: 0,
+                        });
+                        if approved {
+                            lease = Self::lease(shared, &pending.request, !remember).await;
+                        }
+                    }
 
                     // Notify waiting request
                     let _ = pending.response_tx.send((approved, remember)).await;
@@ -455,6 +736,7 @@ impl GuardianServer {
                         },
                         sandbox_config: None,
                         recommended_action: None,
+                        lease,
                     }
                 } else {
                     GuardianResponse::Error {
@@ -517,14 +799,256 @@ impl GuardianServer {
                 GuardianResponse::SandboxProfile { config }
             }
 
-            GuardianRequest::ReloadConfig => {
-                // TODO: Implement config reload
-                info!("Configuration reload requested");
+            GuardianRequest::ReloadConfig | GuardianRequest::ReloadPolicies => {
+                // Policies are the only section that can change without a restart
+                info!("Policy reload requested");
+                match load_policies(config_path, decision_engine.policies().context()).await {
+                    Ok(policy_engine) => {
+                        let new_hash = hash_file(config_path);
+                        let old_hash = std::mem::replace(&mut *config_hash.write().await, new_hash.clone());
+                        decision_engine.replace_policies(Arc::new(policy_engine));
+                        audit_logger.log(AuditEvent::ConfigChanged {
+                            component: "policies".into(),
+                            change_type: "reload".into(),
+                            old_hash,
+                            new_hash,
+                        });
+                        GuardianResponse::Ok {
+                            message: "Policies reloaded".into(),
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Policy reload failed, keeping active policies: {:#}", e);
+                        GuardianResponse::Error {
+                            code: ErrorCode::InvalidRequest,
+                            message: format!("Policy reload failed: {:#}", e),
+                        }
+                    }
+                }
+            }
+
+            GuardianRequest::TestPolicy { request, candidate_path } => {
+                let candidate = match candidate_path {
+                    Some(path) => match load_policies(&path, decision_engine.policies().context()).await {
+                        Ok(policy_engine) => Arc::new(policy_engine),
+                        Err(e) => {
+                            return GuardianResponse::Error {
+                                code: ErrorCode::InvalidRequest,
+                                message: format!("Invalid candidate policies: {:#}", e),
+                            };
+                        }
+                    },
+                    None => decision_engine.policies(),
+                };
+
+                let (decision, trace) = decision_engine.dry_run(&candidate, &request).await;
+                GuardianResponse::PolicyTest {
+                    decision: decision_label(&decision.decision),
+                    reason: decision.reason,
+                    matched_rule: decision.policy_result.matched_rule,
+                    trace,
+                }
+            }
+
+            GuardianRequest::VerifyAuditLog { path } => {
+                let logger = audit_logger.clone();
+                let result = tokio::task::spawn_blocking(move || match path {
+                    Some(path) => logger.verify_file(&path),
+                    None => logger.verify_integrity(),
+                })
+                .await;
+
+                match result {
+                    Ok(Ok(report)) => GuardianResponse::AuditVerification {
+                        valid: report.is_valid(),
+                        entries_checked: report.entries_checked,
+                        checkpoints_verified: report.checkpoints_verified,
+                        first_seq: report.first_seq,
+                        last_seq: report.last_seq,
+                        unsigned_tail: report.unsigned_tail,
+                        errors: report.errors.iter().map(|e| e.to_string()).collect(),
+                    },
+                    Ok(Err(e)) => GuardianResponse::Error {
+                        code: ErrorCode::NotFound,
+                        message: format!("Could not read audit log: {}", e),
+                    },
+                    Err(e) => GuardianResponse::Error {
+                        code: ErrorCode::InternalError,
+                        message: e.to_string(),
+                    },
+                }
+            }
+
+            GuardianRequest::ExportAuditLog { from, to, format } => {
+                let logger = audit_logger.clone();
+                match tokio::task::spawn_blocking(move || logger.export(from, to, format)).await {
+                    Ok(Ok((count, data))) => GuardianResponse::AuditExport { format, count, data },
+                    Ok(Err(e)) => GuardianResponse::Error {
+                        code: ErrorCode::NotFound,
+                        message: format!("Could not read audit log: {}", e),
+                    },
+                    Err(e) => GuardianResponse::Error {
+                        code: ErrorCode::InternalError,
+                        message: e.to_string(),
+                    },
+                }
+            }
+
+            GuardianRequest::ListLeases => GuardianResponse::Leases {
+                leases: lease_manager.list().await,
+            },
+
+            GuardianRequest::RenewLease { lease_id } => {
+                let Some(lease) = lease_manager.get(lease_id).await else {
+                    return GuardianResponse::Error {
+                        code: ErrorCode::NotFound,
+                        message: "No such lease".into(),
+                    };
+                };
+
+                // Renewals only re-check static policy; anything short of an
+                // allow sends the client back through a fresh request
+                if !decision_engine.recheck(&lease.request) {
+                    lease_manager.revoke(lease_id, RevokeReason::PolicyChanged).await;
+                    audit_logger.log(AuditEvent::Lease {
+                        lease_id,
+                        request: lease.request,
+                        action: "revoked".into(),
+                        expires_at: None,
+                    });
+                    return GuardianResponse::Error {
+                        code: ErrorCode::PermissionDenied,
+                        message: "Policy does not allow renewing this lease; request the capability again".into(),
+                    };
+                }
+
+                match lease_manager.renew(lease_id).await {
+                    Ok(lease) => {
+                        audit_logger.log(AuditEvent::Lease {
+                            lease_id,
+                            request: lease.request.clone(),
+                            action: "renewed".into(),
+                            expires_at: Some(lease.expires_at),
+                        });
+                        GuardianResponse::LeaseRenewed {
+                            lease: LeaseGrant::from(&lease),
+                        }
+                    }
+                    Err(e) => GuardianResponse::Error {
+                        code: ErrorCode::PermissionDenied,
+                        message: e.to_string(),
+                    },
+                }
+            }
+
+            GuardianRequest::RevokeLease { lease_id } => {
+                match lease_manager.revoke(lease_id, RevokeReason::Revoked).await {
+                    Some(lease) => {
+                        audit_logger.log(AuditEvent::Lease {
+                            lease_id,
+                            request: lease.request,
+                            action: "revoked".into(),
+                            expires_at: None,
+                        });
+                        GuardianResponse::Ok {
+                            message: "Lease revoked".into(),
+                        }
+                    }
+                    None => GuardianResponse::Error {
+                        code: ErrorCode::NotFound,
+                        message: "No such lease".into(),
+                    },
+                }
+            }
+
+            GuardianRequest::ReportThreat { source, rule, severity, process_path, pid, remote, description } => {
+                if !(0.0..=1.0).contains(&severity) {
+                    return GuardianResponse::Error {
+                        code: ErrorCode::InvalidRequest,
+                        message: format!("Severity must be between 0 and 1, got {}", severity),
+                    };
+                }
+
+                let mut explanation = description;
+                if let Some(pid) = pid {
+                    explanation.push_str(&format!(" (pid {})", pid));
+                }
+                if let Some(remote) = remote {
+                    explanation.push_str(&format!(" [remote {}]", remote));
+                }
+                warn!("Threat reported by {}: {}", source, explanation);
+                decision_engine.report_threat(
+                    process_path.as_deref(),
+                    &format!("{}:{}", source, rule),
+                    severity,
+                    &explanation,
+                );
                 GuardianResponse::Ok {
-                    message: "Configuration reloaded".into(),
+                    message: "Threat recorded".into(),
                 }
             }
 
+            GuardianRequest::IssueToken { subject, user, pid, process_path, scopes, ttl_secs } => {
+                let (granted, refused): (Vec<_>, Vec<_>) = scopes.into_iter().partition(|scope| {
+                    let request = CapabilityRequest {
+                        pid,
+                        process_path: subject.clone(),
+                        user: user.clone(),
+                        capability: scope.capability.clone(),
+                        resource: scope.resource.clone(),
+                        context: HashMap::from([("on_behalf_of".to_string(), process_path.clone())]),
+                    };
+                    decision_engine.preauthorize(&request)
+                });
+                if !refused.is_empty() {
+                    info!(
+                        "Token for {} refused: {}",
+                        subject,
+                        refused.iter().map(TokenScope::describe).collect::<Vec<_>>().join(", ")
+                    );
+                }
+
+                let token = token_manager.issue(&subject, &user, granted, ttl_secs).await;
+                Self::log_token(audit_logger, &token, "issued");
+                GuardianResponse::TokenIssued {
+                    token_id: token.id,
+                    expires_at: token.expires_at,
+                    granted: token.scopes,
+                    refused,
+                }
+            }
+
+            GuardianRequest::CheckToken { token_id, capability, resource } => {
+                match token_manager.check(token_id, &capability, resource.as_deref(), Utc::now()).await {
+                    Ok(token) => GuardianResponse::TokenCheck {
+                        allowed: true,
+                        reason: format!("Granted to {}", token.subject),
+                    },
+                    Err(denial) => GuardianResponse::TokenCheck {
+                        allowed: false,
+                        reason: denial.to_string(),
+                    },
+                }
+            }
+
+            GuardianRequest::RevokeToken { token_id } => match token_manager.revoke(token_id).await {
+                Some(token) => {
+                    Self::log_token(audit_logger, &token, "revoked");
+                    GuardianResponse::Ok {
+                        message: "Token revoked".into(),
+                    }
+                }
+                None => GuardianResponse::Error {
+                    code: ErrorCode::NotFound,
+                    message: "No such token".into(),
+                },
+            },
+
+            // Handled by the connection loop, which switches to streaming
+            GuardianRequest::WatchLeases => GuardianResponse::Ok {
+                message: "Watching leases".into(),
+            },
+
             GuardianRequest::Shutdown => {
                 info!("Shutdown requested via IPC");
                 // TODO: Signal main loop
@@ -535,6 +1059,55 @@ impl GuardianServer {
         }
     }
 
+    /// Issue a lease for a grant if its capability is time-limited
+    async fn lease(shared: &Shared, request: &CapabilityRequest, prompted_once: bool) -> Option<LeaseGrant> {
+        let ttl = shared.lease_manager.ttl_for(&request.capability, prompted_once)?;
+        let lease = shared.lease_manager.grant(request, ttl).await;
+        shared.audit_logger.log(AuditEvent::Lease {
+            lease_id: lease.id,
+            request: request.clone(),
+            action: "granted".into(),
+            expires_at: Some(lease.expires_at),
+        });
+        Some(LeaseGrant::from(&lease))
+    }
+
+    fn log_token(audit_logger: &AuditLogger, token: &ExecutionToken, action: &str) {
+        audit_logger.log(AuditEvent::Token {
+            token_id: token.id,
+            subject: token.subject.clone(),
+            user: token.user.clone(),
+            action: action.into(),
+            scopes: token.scopes.iter().map(TokenScope::describe).collect(),
+            expires_at: token.expires_at,
+        });
+    }
+
+    /// Revoke expired leases and tokens until shutdown
+    pub async fn expire_leases(&self) {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(1));
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    for lease in self.shared.lease_manager.expire(Utc::now()).await {
+                        self.shared.audit_logger.log(AuditEvent::Lease {
+                            lease_id: lease.id,
+                            request: lease.request,
+                            action: "expired".into(),
+                            expires_at: Some(lease.expires_at),
+                        });
+                    }
+                    for token in self.shared.token_manager.expire(Utc::now()).await {
+                        Self::log_token(&self.shared.audit_logger, &token, "expired");
+                    }
+                }
+                _ = shutdown_rx.recv() => break,
+            }
+        }
+    }
+
     /// Shutdown the server
     pub fn shutdown(&self) {
         let _ = self.shutdown_tx.send(());
@@ -542,7 +1115,7 @@ impl GuardianServer {
 
     /// Get server statistics
     pub async fn get_stats(&self) -> (u64, u64, u64, u64, u64, u32) {
-        let s = self.stats.read().await;
+        let s = self.shared.stats.read().await;
         (
             s.requests_processed,
             s.decisions_allow,
@@ -554,6 +1127,34 @@ impl GuardianServer {
     }
 }
 
+/// Load and strictly validate the policy section of a configuration file
+async fn load_policies(path: &Path, context: Arc<ContextFeeds>) -> Result<PolicyEngine> {
+    if !path.exists() {
+        anyhow::bail!("{} does not exist", path.display());
+    }
+    let config = config::load_config(path).await?;
+    Ok(PolicyEngine::new_strict(&config.policies)?.with_context(context))
+}
+
+/// Content hash of a file, for change auditing
+fn hash_file(path: &Path) -> String {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    std::fs::read(path).unwrap_or_default().hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Wire name of a decision
+fn decision_label(decision: &FinalDecision) -> String {
+    match decision {
+        FinalDecision::Allow => "allow".into(),
+        FinalDecision::Deny => "deny".into(),
+        FinalDecision::Sandbox(level) => format!("sandbox:{:?}", level).to_lowercase(),
+        FinalDecision::Prompt => "prompt".into(),
+    }
+}
+
 /// Guardian IPC client (for other processes to use)
 pub struct GuardianClient {
     socket_path: PathBuf,
@@ -611,6 +1212,36 @@ impl GuardianConnection {
     pub async fn respond_to_prompt(&mut self, request_id: Uuid, approved: bool, remember: bool) -> Result<GuardianResponse> {
         self.request(GuardianRequest::UserResponse { request_id, approved, remember }).await
     }
+
+    /// Verify the audit log
+    pub async fn verify_audit_log(&mut self, path: Option<PathBuf>) -> Result<GuardianResponse> {
+        self.request(GuardianRequest::VerifyAuditLog { path }).await
+    }
+
+    /// Export audit entries in a time range
+    pub async fn export_audit_log(
+        &mut self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        format: ExportFormat,
+    ) -> Result<GuardianResponse> {
+        self.request(GuardianRequest::ExportAuditLog { from, to, format }).await
+    }
+
+    /// Extend a lease
+    pub async fn renew_lease(&mut self, lease_id: Uuid) -> Result<GuardianResponse> {
+        self.request(GuardianRequest::RenewLease { lease_id }).await
+    }
+
+    /// Reload policies from the configuration file
+    pub async fn reload_policies(&mut self) -> Result<GuardianResponse> {
+        self.request(GuardianRequest::ReloadPolicies).await
+    }
+
+    /// Dry-run a request against the active or a candidate policy set
+    pub async fn test_policy(&mut self, request: CapabilityRequest, candidate_path: Option<PathBuf>) -> Result<GuardianResponse> {
+        self.request(GuardianRequest::TestPolicy { request, candidate_path }).await
+    }
 }
 
 #[cfg(test)]
@@ -644,6 +1275,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_prompt_answer_clients() {
+        let pid = std::process::id();
+        let exe = std::env::current_exe().unwrap();
+
+        // Nobody answers over IPC unless configured
+        let broker = PromptBroker::new(&config::PromptConfig::default());
+        assert!(!broker.accepts_answer_from(Some(pid as i32), pid + 1));
+
+        let broker = PromptBroker::new(&config::PromptConfig {
+            answer_clients: vec![exe],
+            ..Default::default()
+        });
+        assert!(broker.accepts_answer_from(Some(pid as i32), pid + 1));
+        // The requester cannot answer its own prompt
+        assert!(!broker.accepts_answer_from(Some(pid as i32), pid));
+        assert!(!broker.accepts_answer_from(None, pid + 1));
+    }
+
     #[tokio::test]
     async fn test_response_serialization() {
         let response = GuardianResponse::Decision {
@@ -652,6 +1302,7 @@ mod tests {
             reason: "Trusted application".into(),
             sandbox_config: None,
             recommended_action: None,
+            lease: None,
         };
 
         let json = serde_json::to_string(&response).unwrap();
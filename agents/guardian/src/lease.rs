@@ -0,0 +1,244 @@
+//! Capability leases - time-limited grants
+//!
+//! Grants whose capability has a TTL are issued as leases. Guardian keeps
+//! track of every outstanding lease and, when one expires or is revoked,
+//! broadcasts a revocation to watchers (the kernel bridge and the daemons
+//! that enforce the capability) so they can withdraw it. Holders extend a
+//! lease with a renewal, which only re-checks static policy instead of
+//! running the full decision pipeline.
+
+use crate::config::LeaseConfig;
+use crate::policy::{glob_to_regex, CapabilityRequest};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// An outstanding time-limited grant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lease {
+    pub id: Uuid,
+    pub request: CapabilityRequest,
+    pub granted_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Lease length, reapplied on renewal
+    pub ttl_secs: u64,
+    pub renewals: u32,
+}
+
+/// Why a lease ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevokeReason {
+    Expired,
+    /// Revoked explicitly over IPC
+    Revoked,
+    /// Policy no longer allows it at renewal
+    PolicyChanged,
+}
+
+/// Revocation broadcast to watchers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revocation {
+    pub lease: Lease,
+    pub reason: RevokeReason,
+}
+
+/// Lease manager
+pub struct LeaseManager {
+    enabled: bool,
+    default_ttl_secs: Option<u64>,
+    prompt_ttl_secs: Option<u64>,
+    rules: Vec<(Regex, u64)>,
+    max_renewals: u32,
+    leases: RwLock<HashMap<Uuid, Lease>>,
+    revocations: broadcast::Sender<Revocation>,
+}
+
+impl LeaseManager {
+    /// Create a new lease manager
+    pub fn new(config: &LeaseConfig) -> Result<Self> {
+        let rules = config
+            .rules
+            .iter()
+            .map(|rule| Ok((glob_to_regex(&rule.capability)?, rule.ttl_secs)))
+            .collect::<Result<_>>()?;
+        let (revocations, _) = broadcast::channel(64);
+
+        Ok(Self {
+            enabled: config.enabled,
+            default_ttl_secs: config.default_ttl_secs,
+            prompt_ttl_secs: config.prompt_ttl_secs,
+            rules,
+            max_renewals: config.max_renewals,
+            leases: RwLock::new(HashMap::new()),
+            revocations,
+        })
+    }
+
+    /// TTL for a grant of `capability`, if it should be leased
+    pub fn ttl_for(&self, capability: &str, prompted_once: bool) -> Option<u64> {
+        if !self.enabled {
+            return None;
+        }
+
+        let rule_ttl = self
+            .rules
+            .iter()
+            .find(|(pattern, _)| pattern.is_match(capability))
+            .map(|(_, ttl)| *ttl);
+        let fallback = if prompted_once { self.prompt_ttl_secs } else { self.default_ttl_secs };
+
+        rule_ttl.or(fallback).filter(|ttl| *ttl > 0)
+    }
+
+    /// Issue a lease
+    pub async fn grant(&self, request: &CapabilityRequest, ttl_secs: u64) -> Lease {
+        let now = Utc::now();
+        let lease = Lease {
+            id: Uuid::new_v4(),
+            request: request.clone(),
+            granted_at: now,
+            expires_at: now + Duration::seconds(ttl_secs as i64),
+            ttl_secs,
+            renewals: 0,
+        };
+
+        debug!(
+            "Lease {} for {} on {} expires {}",
+            lease.id, request.process_path, request.capability, lease.expires_at
+        );
+        self.leases.write().await.insert(lease.id, lease.clone());
+        lease
+    }
+
+    /// Look up a lease
+    pub async fn get(&self, id: Uuid) -> Option<Lease> {
+        self.leases.read().await.get(&id).cloned()
+    }
+
+    /// Outstanding leases, soonest expiry first
+    pub async fn list(&self) -> Vec<Lease> {
+        let mut leases: Vec<_> = self.leases.read().await.values().cloned().collect();
+        leases.sort_by_key(|l| l.expires_at);
+        leases
+    }
+
+    /// Extend a lease by its TTL
+    ///
+    /// The caller is responsible for the policy re-check.
+    pub async fn renew(&self, id: Uuid) -> Result<Lease> {
+        let mut leases = self.leases.write().await;
+        let lease = leases.get_mut(&id).ok_or_else(|| anyhow!("No such lease"))?;
+
+        if lease.renewals >= self.max_renewals {
+            return Err(anyhow!(
+                "Renewal limit reached; request the capability again"
+            ));
+        }
+
+        lease.renewals += 1;
+        lease.expires_at = Utc::now() + Duration::seconds(lease.ttl_secs as i64);
+        Ok(lease.clone())
+    }
+
+    /// End a lease and tell watchers to withdraw it
+    pub async fn revoke(&self, id: Uuid, reason: RevokeReason) -> Option<Lease> {
+        let lease = self.leases.write().await.remove(&id)?;
+        self.announce(&lease, reason);
+        Some(lease)
+    }
+
+    /// Revoke every lease that has expired by `now`
+    pub async fn expire(&self, now: DateTime<Utc>) -> Vec<Lease> {
+        let expired: Vec<Lease> = {
+            let mut leases = self.leases.write().await;
+            let ids: Vec<Uuid> = leases
+                .values()
+                .filter(|l| l.expires_at <= now)
+                .map(|l| l.id)
+                .collect();
+            ids.iter().filter_map(|id| leases.remove(id)).collect()
+        };
+
+        for lease in &expired {
+            self.announce(lease, RevokeReason::Expired);
+        }
+        expired
+    }
+
+    /// Subscribe to revocations
+    pub fn watch(&self) -> broadcast::Receiver<Revocation> {
+        self.revocations.subscribe()
+    }
+
+    fn announce(&self, lease: &Lease, reason: RevokeReason) {
+        info!(
+            "Revoking {} for {} (pid {}): {:?}",
+            lease.request.capability, lease.request.process_path, lease.request.pid, reason
+        );
+        if self
+            .revocations
+            .send(Revocation {
+                lease: lease.clone(),
+                reason,
+            })
+            .is_err()
+        {
+            warn!("No watchers to enforce revocation of lease {}", lease.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LeaseRule;
+
+    fn request(capability: &str) -> CapabilityRequest {
+        CapabilityRequest {
+            pid: 7,
+            process_path: "/usr/bin/app".into(),
+            user: "user".into(),
+            capability: capability.into(),
+            resource: None,
+            context: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lease_lifecycle() {
+        let config = LeaseConfig {
+            rules: vec![LeaseRule {
+                capability: "camera:*".into(),
+                ttl_secs: 60,
+            }],
+            max_renewals: 1,
+            ..LeaseConfig::default()
+        };
+        let manager = LeaseManager::new(&config).unwrap();
+
+        assert_eq!(manager.ttl_for("camera:capture", false), Some(60));
+        assert_eq!(manager.ttl_for("filesystem:read", false), None);
+        assert_eq!(manager.ttl_for("filesystem:read", true), Some(3600));
+
+        let mut watcher = manager.watch();
+        let lease = manager.grant(&request("camera:capture"), 60).await;
+
+        assert!(manager.renew(lease.id).await.is_ok());
+        assert!(manager.renew(lease.id).await.is_err());
+
+        assert!(manager.expire(Utc::now()).await.is_empty());
+        let expired = manager.expire(Utc::now() + Duration::seconds(61)).await;
+        assert_eq!(expired.len(), 1);
+
+        let revocation = watcher.recv().await.unwrap();
+        assert_eq!(revocation.lease.id, lease.id);
+        assert_eq!(revocation.reason, RevokeReason::Expired);
+        assert!(manager.get(lease.id).await.is_none());
+    }
+}
@@ -55,9 +55,15 @@ mod audit;
 mod sandbox;
 mod ipc;
 mod config;
+mod prompt;
+mod lease;
+mod token;
+mod context;
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::{info, error};
@@ -81,12 +87,87 @@ struct Args {
     /// Permissive mode (log but don't deny)
     #[arg(long)]
     permissive: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Manage policies on a running Guardian
+    Policy {
+        #[command(subcommand)]
+        action: PolicyCommand,
+    },
+    /// Inspect the audit log of a running Guardian
+    Audit {
+        #[command(subcommand)]
+        action: AuditCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AuditCommand {
+    /// Check the hash chain and signed checkpoints
+    Verify {
+        /// Check one file instead of the whole log
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+    /// Export entries in a time range
+    Export {
+        /// Start of the range (RFC 3339)
+        #[arg(long)]
+        from: Option<DateTime<Utc>>,
+        /// End of the range (RFC 3339)
+        #[arg(long)]
+        to: Option<DateTime<Utc>>,
+        /// Output format: jsonl or cef
+        #[arg(long, default_value = "jsonl", value_parser = parse_format)]
+        format: audit::ExportFormat,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PolicyCommand {
+    /// Reload policies from the configuration file
+    Reload,
+    /// Evaluate a request without enforcing it
+    Test {
+        /// Requesting program path
+        #[arg(long)]
+        subject: String,
+        /// Capability being requested
+        #[arg(long)]
+        capability: String,
+        /// Context entries as key=value (repeatable)
+        #[arg(long, value_parser = parse_context)]
+        context: Vec<(String, String)>,
+        /// Target resource
+        #[arg(long)]
+        resource: Option<String>,
+        /// Requesting user
+        #[arg(long, default_value = "root")]
+        user: String,
+        /// Candidate configuration to test instead of the active policies
+        #[arg(long)]
+        candidate: Option<PathBuf>,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
 
+    match args.command {
+        Some(Command::Policy { action }) => return policy_command(&args.socket, action).await,
+        Some(Command::Audit { action }) => return audit_command(&args.socket, action).await,
+        None => {}
+    }
+
     // Initialize logging
     let log_level = if args.debug { "debug" } else { "info" };
     tracing_subscriber::fmt()
@@ -103,10 +184,15 @@ async fn main() -> Result<()> {
     let config = config::load_config(&args.config).await?;
 
     // Initialize components
-    let policy_engine = Arc::new(policy::PolicyEngine::new(&config.policies)?);
+    let context_feeds = Arc::new(context::ContextFeeds::new(&config.context));
+    let policy_engine = Arc::new(policy::PolicyEngine::new(&config.policies)?.with_context(context_feeds.clone()));
     let intent_analyzer = Arc::new(intent::IntentAnalyzer::new(&config.intent)?);
     let pattern_learner = Arc::new(pattern::PatternLearner::new(&config.patterns)?);
     let audit_logger = Arc::new(audit::AuditLogger::new(&config.audit)?);
+    let prompt_broker = Arc::new(prompt::PromptBroker::new(&config.prompts));
+    let lease_manager = Arc::new(lease::LeaseManager::new(&config.leases)?);
+    let token_manager = Arc::new(token::TokenManager::new(config.leases.max_token_ttl_secs));
+    audit_logger.log_started(env!("CARGO_PKG_VERSION"), &config);
 
     // Create decision engine
     let decision_engine = Arc::new(decision::DecisionEngine::new(
@@ -117,15 +203,149 @@ async fn main() -> Result<()> {
         args.permissive,
     ));
 
+    // Keep VPN, session and power state fresh for context conditions
+    tokio::spawn(async move {
+        context_feeds.run().await;
+    });
+
+    // Age and persist learned behavior in the background
+    let save_interval = std::time::Duration::from_secs(config.patterns.save_interval_secs.max(1));
+    let learner = pattern_learner.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(save_interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            learner.decay();
+            if let Err(e) = learner.save().await {
+                error!("Failed to save behavior profiles: {}", e);
+            }
+        }
+    });
+
     // Start IPC server
     let server = ipc::GuardianServer::new(
         args.socket,
+        args.config,
         decision_engine.clone(),
         audit_logger.clone(),
+        prompt_broker,
+        lease_manager,
+        token_manager,
     );
 
     info!("Guardian ready");
 
     // Run server
-    server.run().await
+    tokio::select! {
+        result = server.run() => result,
+        _ = server.expire_leases() => Ok(()),
+    }
+}
+
+/// Run a `policy` subcommand against the running daemon
+async fn policy_command(socket: &std::path::Path, action: PolicyCommand) -> Result<()> {
+    let mut conn = ipc::GuardianClient::new(socket).connect().await?;
+
+    let response = match action {
+        PolicyCommand::Reload => conn.reload_policies().await?,
+        PolicyCommand::Test { subject, capability, context, resource, user, candidate } => {
+            let request = policy::CapabilityRequest {
+                pid: 0,
+                process_path: subject,
+                user,
+                capability,
+                resource,
+                context: context.into_iter().collect::<HashMap<_, _>>(),
+            };
+            let candidate = candidate.map(|p| std::fs::canonicalize(&p).unwrap_or(p));
+            conn.test_policy(request, candidate).await?
+        }
+    };
+
+    match response {
+        ipc::GuardianResponse::Ok { message } => println!("{}", message),
+        ipc::GuardianResponse::PolicyTest { decision, reason, matched_rule, trace } => {
+            for step in &trace {
+                let mark = if step.matched { "+" } else { "-" };
+                println!("{} {:<32} {}", mark, step.rule, step.detail);
+            }
+            println!();
+            println!("Matched rule: {}", matched_rule.as_deref().unwrap_or("(none)"));
+            println!("Decision:     {}", decision);
+            println!("Reason:       {}", reason);
+        }
+        ipc::GuardianResponse::Error { message, .. } => bail!("{}", message),
+        other => bail!("Unexpected response: {:?}", other),
+    }
+
+    Ok(())
+}
+
+/// Run an `audit` subcommand against the running daemon
+async fn audit_command(socket: &std::path::Path, action: AuditCommand) -> Result<()> {
+    let mut conn = ipc::GuardianClient::new(socket).connect().await?;
+
+    match action {
+        AuditCommand::Verify { file } => {
+            let file = file.map(|p| std::fs::canonicalize(&p).unwrap_or(p));
+            match conn.verify_audit_log(file).await? {
+                ipc::GuardianResponse::AuditVerification {
+                    valid,
+                    entries_checked,
+                    checkpoints_verified,
+                    first_seq,
+                    last_seq,
+                    unsigned_tail,
+                    errors,
+                } => {
+                    for error in &errors {
+                        println!("{}", error);
+                    }
+                    if let (Some(first), Some(last)) = (first_seq, last_seq) {
+                        println!("Entries {}..={}", first, last);
+                    }
+                    println!("Checked {} entries, {} signed checkpoints", entries_checked, checkpoints_verified);
+                    if unsigned_tail > 0 {
+                        println!("{} entries after the last checkpoint", unsigned_tail);
+                    }
+                    if !valid {
+                        bail!("Audit log integrity check FAILED ({} problems)", errors.len());
+                    }
+                    println!("Audit log intact");
+                }
+                ipc::GuardianResponse::Error { message, .. } => bail!("{}", message),
+                other => bail!("Unexpected response: {:?}", other),
+            }
+        }
+        AuditCommand::Export { from, to, format, output } => match conn.export_audit_log(from, to, format).await? {
+            ipc::GuardianResponse::AuditExport { count, data, .. } => match output {
+                Some(path) => {
+                    std::fs::write(&path, data)?;
+                    println!("Exported {} entries to {}", count, path.display());
+                }
+                None => print!("{}", data),
+            },
+            ipc::GuardianResponse::Error { message, .. } => bail!("{}", message),
+            other => bail!("Unexpected response: {:?}", other),
+        },
+    }
+
+    Ok(())
+}
+
+/// Parse an export format name
+fn parse_format(s: &str) -> Result<audit::ExportFormat, String> {
+    match s.to_lowercase().as_str() {
+        "jsonl" | "json" => Ok(audit::ExportFormat::Jsonl),
+        "cef" => Ok(audit::ExportFormat::Cef),
+        _ => Err(format!("unknown format '{}', expected jsonl or cef", s)),
+    }
+}
+
+/// Parse a `key=value` context entry
+fn parse_context(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .ok_or_else(|| format!("expected key=value, got '{}'", s))
 }
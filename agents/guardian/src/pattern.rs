@@ -1,14 +1,24 @@
 //! Pattern learner - learns normal behavior and detects anomalies
 //!
 //! Guardian learns the normal patterns of capability usage and flags anomalies.
+//! Each application gets a behavior profile (capability and resource
+//! frequencies, hour-of-day and day-of-week histograms) that is persisted
+//! across restarts. Observations decay exponentially so profiles follow
+//! gradual changes in behavior instead of remembering it forever.
 
 use crate::config::PatternConfig;
 use crate::policy::CapabilityRequest;
 use anyhow::Result;
+use chrono::{DateTime, Datelike, Local, Timelike, Utc};
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::time::{Duration, SystemTime};
-use tracing::{debug, warn};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::{debug, info};
+
+/// Weights below this are dropped when decaying
+const MIN_WEIGHT: f64 = 0.01;
 
 /// Pattern learning result
 #[derive(Debug, Clone)]
@@ -29,56 +39,113 @@ pub struct PatternLearner {
     enabled: bool,
     /// Anomaly threshold
     anomaly_threshold: f32,
+    /// Threshold for auditing an anomaly
+    warn_threshold: f32,
+    /// Threshold for denying outright
+    block_threshold: f32,
     /// Learning rate
     learning_rate: f32,
-    /// App-to-capability frequency map
-    app_capabilities: DashMap<String, CapabilityProfile>,
-    /// Time-based patterns
-    time_patterns: DashMap<String, TimePattern>,
-    /// Resource access patterns
-    resource_patterns: DashMap<String, ResourceProfile>,
+    /// Observations before a profile is used for scoring
+    min_observations: f64,
+    /// Half-life of learned behavior, in days
+    decay_half_life_days: f64,
+    /// Where profiles are persisted (empty = in memory only)
+    database_path: PathBuf,
+    /// Per-application behavior profiles
+    profiles: DashMap<String, AppProfile>,
+    /// When decay was last applied
+    last_decay: Mutex<DateTime<Utc>>,
 }
 
-/// Capability usage profile for an app
-#[derive(Debug, Clone, Default)]
-struct CapabilityProfile {
-    /// Capability -> count
-    capabilities: HashMap<String, u64>,
-    /// Total requests
-    total_requests: u64,
+/// Learned behavior of one application
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AppProfile {
+    /// Capability -> weight
+    capabilities: HashMap<String, f64>,
+    /// Resource path -> weight
+    resources: HashMap<String, f64>,
+    /// Hour -> weight (0-23)
+    hourly_distribution: [f64; 24],
+    /// Day of week -> weight (0-6, Sunday = 0)
+    weekly_distribution: [f64; 7],
+    /// Total weight of observed requests
+    total_requests: f64,
     /// First seen
-    first_seen: Option<SystemTime>,
+    first_seen: DateTime<Utc>,
     /// Last seen
-    last_seen: Option<SystemTime>,
+    last_seen: DateTime<Utc>,
+    /// Suspicion from threat reports (0.0-1.0), decaying like the rest
+    #[serde(default)]
+    suspicion: f64,
 }
 
-/// Time-based access pattern
-#[derive(Debug, Clone, Default)]
-struct TimePattern {
-    /// Hour -> count (0-23)
-    hourly_distribution: [u64; 24],
-    /// Day of week -> count (0-6, Sunday = 0)
-    weekly_distribution: [u64; 7],
+impl Default for AppProfile {
+    fn default() -> Self {
+        let now = Utc::now();
+        Self {
+            capabilities: HashMap::new(),
+            resources: HashMap::new(),
+            hourly_distribution: [0.0; 24],
+            weekly_distribution: [0.0; 7],
+            total_requests: 0.0,
+            first_seen: now,
+            last_seen: now,
+            suspicion: 0.0,
+        }
+    }
 }
 
-/// Resource access profile
-#[derive(Debug, Clone, Default)]
-struct ResourceProfile {
-    /// Resource path -> count
-    resources: HashMap<String, u64>,
+impl AppProfile {
+    /// Scale every weight by `factor`, dropping ones that fade out
+    fn decay(&mut self, factor: f64) {
+        for weights in [&mut self.capabilities, &mut self.resources] {
+            weights.values_mut().for_each(|w| *w *= factor);
+            weights.retain(|_, w| *w >= MIN_WEIGHT);
+        }
+        self.hourly_distribution.iter_mut().for_each(|w| *w *= factor);
+        self.weekly_distribution.iter_mut().for_each(|w| *w *= factor);
+        self.total_requests *= factor;
+        self.suspicion *= factor;
+    }
+}
+
+/// On-disk form of the learned profiles
+#[derive(Serialize, Deserialize)]
+struct PatternSnapshot {
+    last_decay: DateTime<Utc>,
+    profiles: HashMap<String, AppProfile>,
 }
 
 impl PatternLearner {
-    /// Create a new pattern learner
+    /// Create a new pattern learner, loading any persisted profiles
     pub fn new(config: &PatternConfig) -> Result<Self> {
-        Ok(Self {
+        let mut learner = Self {
             enabled: config.enabled,
             anomaly_threshold: config.anomaly_threshold,
+            warn_threshold: config.warn_threshold,
+            block_threshold: config.block_threshold,
             learning_rate: config.learning_rate,
-            app_capabilities: DashMap::new(),
-            time_patterns: DashMap::new(),
-            resource_patterns: DashMap::new(),
-        })
+            min_observations: config.min_observations as f64,
+            decay_half_life_days: config.decay_half_life_days as f64,
+            database_path: PathBuf::from(&config.database_path),
+            profiles: DashMap::new(),
+            last_decay: Mutex::new(Utc::now()),
+        };
+
+        if learner.enabled && !config.database_path.is_empty() {
+            match std::fs::read_to_string(&learner.database_path) {
+                Ok(contents) => {
+                    let snapshot: PatternSnapshot = serde_json::from_str(&contents)?;
+                    *learner.last_decay.lock().unwrap() = snapshot.last_decay;
+                    learner.profiles.extend(snapshot.profiles);
+                    info!("Loaded {} behavior profiles", learner.profiles.len());
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(learner)
     }
 
     /// Analyze a request against learned patterns
@@ -92,9 +159,37 @@ impl PatternLearner {
             };
         }
 
-        let mut anomaly_score: f32 = 0.0;
+        let (observations, suspicion) = self
+            .profiles
+            .get(&request.process_path)
+            .map(|p| (p.total_requests, p.suspicion as f32))
+            .unwrap_or((0.0, 0.0));
+        if observations < self.min_observations {
+            // Not enough history to tell normal from unusual yet
+            let mut explanation = format!(
+                "Still learning this app ({:.0} of {:.0} observations)",
+                observations, self.min_observations
+            );
+            if suspicion >= MIN_WEIGHT as f32 {
+                explanation.push_str(&format!("; flagged by threat reports (score: {:.2})", suspicion));
+            }
+            return PatternAnalysis {
+                is_known: false,
+                anomaly_score: 1.0 - 0.5 * (1.0 - suspicion),
+                similar_patterns: self.find_similar_patterns(request),
+                explanation,
+            };
+        }
+
+        let mut scores = Vec::new();
         let mut explanations = Vec::new();
 
+        // Threat reports from other agents, e.g. Arachne's intrusion detection
+        if suspicion >= MIN_WEIGHT as f32 {
+            explanations.push(format!("Flagged by threat reports (score: {:.2})", suspicion));
+            scores.push(suspicion);
+        }
+
         // Check app capability pattern
         let app_anomaly = self.check_app_pattern(request);
         if app_anomaly > 0.5 {
@@ -103,7 +198,7 @@ impl PatternLearner {
                 request.capability, app_anomaly
             ));
         }
-        anomaly_score = anomaly_score.max(app_anomaly);
+        scores.push(app_anomaly);
 
         // Check time pattern
         let time_anomaly = self.check_time_pattern(request);
@@ -113,7 +208,7 @@ impl PatternLearner {
                 time_anomaly
             ));
         }
-        anomaly_score = anomaly_score.max(time_anomaly);
+        scores.push(time_anomaly);
 
         // Check resource pattern
         if let Some(ref resource) = request.resource {
@@ -124,9 +219,11 @@ impl PatternLearner {
                     resource, resource_anomaly
                 ));
             }
-            anomaly_score = anomaly_score.max(resource_anomaly);
+            scores.push(resource_anomaly);
         }
 
+        // Independent signals reinforce each other
+        let anomaly_score = 1.0 - scores.iter().map(|s| 1.0 - s).product::<f32>();
         let is_known = anomaly_score < self.anomaly_threshold;
 
         PatternAnalysis {
@@ -142,109 +239,89 @@ impl PatternLearner {
     }
 
     fn check_app_pattern(&self, request: &CapabilityRequest) -> f32 {
-        let profile = self.app_capabilities.get(&request.process_path);
-
-        match profile {
-            Some(profile) => {
-                // Check if this capability has been used before
-                let cap_count = profile.capabilities.get(&request.capability).copied().unwrap_or(0);
-
-                if profile.total_requests == 0 {
-                    return 0.5; // No history, neutral
-                }
+        let Some(profile) = self.profiles.get(&request.process_path) else {
+            return 0.5;
+        };
+
+        // Check if this capability has been used before
+        let cap_weight = profile.capabilities.get(&request.capability).copied().unwrap_or(0.0);
+        if profile.total_requests <= 0.0 {
+            return 0.5; // No history, neutral
+        }
 
-                // Calculate how unusual this capability is
-                let cap_frequency = cap_count as f32 / profile.total_requests as f32;
-
-                // New capability is more anomalous
-                if cap_count == 0 {
-                    0.8
-                } else if cap_frequency < 0.01 {
-                    0.6
-                } else if cap_frequency < 0.1 {
-                    0.3
-                } else {
-                    0.1
-                }
-            }
-            None => {
-                // First time seeing this app
-                0.5
-            }
+        // Calculate how unusual this capability is
+        let cap_frequency = cap_weight / profile.total_requests;
+
+        // New capability is more anomalous
+        if cap_weight < MIN_WEIGHT {
+            0.8
+        } else if cap_frequency < 0.01 {
+            0.6
+        } else if cap_frequency < 0.1 {
+            0.3
+        } else {
+            0.1
         }
     }
 
     fn check_time_pattern(&self, request: &CapabilityRequest) -> f32 {
-        let now = chrono::Local::now();
+        let now = Local::now();
         let hour = now.hour() as usize;
         let day = now.weekday().num_days_from_sunday() as usize;
 
-        let pattern = self.time_patterns.get(&request.process_path);
-
-        match pattern {
-            Some(pattern) => {
-                let total_hourly: u64 = pattern.hourly_distribution.iter().sum();
-                let total_weekly: u64 = pattern.weekly_distribution.iter().sum();
+        let Some(profile) = self.profiles.get(&request.process_path) else {
+            return 0.3;
+        };
 
-                if total_hourly == 0 {
-                    return 0.3;
-                }
+        let total_hourly: f64 = profile.hourly_distribution.iter().sum();
+        let total_weekly: f64 = profile.weekly_distribution.iter().sum();
+        if total_hourly <= 0.0 {
+            return 0.3;
+        }
 
-                let hour_frequency = pattern.hourly_distribution[hour] as f32 / total_hourly as f32;
-                let day_frequency = pattern.weekly_distribution[day] as f32 / total_weekly.max(1) as f32;
+        let hour_frequency = profile.hourly_distribution[hour] / total_hourly;
+        let day_frequency = profile.weekly_distribution[day] / total_weekly.max(1.0);
 
-                // Check for unusual time
-                let hour_anomaly = if hour_frequency < 0.01 { 0.7 } else { hour_frequency.recip().min(1.0) * 0.3 };
-                let day_anomaly = if day_frequency < 0.01 { 0.5 } else { 0.1 };
+        // Check for unusual time
+        let hour_anomaly = if hour_frequency < 0.01 { 0.7 } else { 0.1 };
+        let day_anomaly = if day_frequency < 0.01 { 0.5 } else { 0.1 };
 
-                (hour_anomaly + day_anomaly) / 2.0
-            }
-            None => 0.3,
-        }
+        (hour_anomaly + day_anomaly) / 2.0
     }
 
     fn check_resource_pattern(&self, request: &CapabilityRequest, resource: &str) -> f32 {
-        let profile = self.resource_patterns.get(&request.process_path);
+        let Some(profile) = self.profiles.get(&request.process_path) else {
+            return 0.5;
+        };
 
-        match profile {
-            Some(profile) => {
-                // Check if this resource has been accessed before
-                let resource_count = profile.resources.get(resource).copied().unwrap_or(0);
-                let total: u64 = profile.resources.values().sum();
+        if profile.resources.is_empty() {
+            return 0.5;
+        }
 
-                if total == 0 {
-                    return 0.5;
-                }
+        // Check if this resource has been accessed before
+        if profile.resources.contains_key(resource) {
+            return 0.1;
+        }
 
-                if resource_count == 0 {
-                    // New resource - check if similar resources accessed
-                    let similar = profile.resources.keys()
-                        .filter(|r| has_common_prefix(r, resource))
-                        .count();
+        // New resource - check if similar resources accessed
+        let similar = profile
+            .resources
+            .keys()
+            .any(|r| has_common_prefix(r, resource));
 
-                    if similar > 0 { 0.4 } else { 0.7 }
-                } else {
-                    0.1
-                }
-            }
-            None => 0.5,
-        }
+        if similar { 0.4 } else { 0.7 }
     }
 
     fn find_similar_patterns(&self, request: &CapabilityRequest) -> Vec<String> {
-        let mut similar = Vec::new();
-
-        // Find apps that use similar capabilities
-        for entry in self.app_capabilities.iter() {
-            if entry.key() != &request.process_path {
-                if entry.value().capabilities.contains_key(&request.capability) {
-                    similar.push(entry.key().clone());
-                }
-            }
-        }
-
-        similar.truncate(5);
-        similar
+        self.profiles
+            .iter()
+            .filter(|entry| {
+                entry.key() != &request.process_path
+                    && entry.value().capabilities.contains_key(&request.capability)
+            })
+            .map(|entry| entry.key().clone())
+            .take(5)
+            .collect()
     }
 
     /// Learn from an approved request
@@ -255,41 +332,100 @@ impl PatternLearner {
 
         debug!("Learning pattern from: {:?}", request);
 
-        // Update app capability profile
-        let mut profile = self.app_capabilities
-            .entry(request.process_path.clone())
-            .or_default();
-        *profile.capabilities.entry(request.capability.clone()).or_insert(0) += 1;
-        profile.total_requests += 1;
-        profile.last_seen = Some(SystemTime::now());
-        if profile.first_seen.is_none() {
-            profile.first_seen = Some(SystemTime::now());
+        let now = Local::now();
+        let mut profile = self.profiles.entry(request.process_path.clone()).or_default();
+
+        *profile.capabilities.entry(request.capability.clone()).or_insert(0.0) += 1.0;
+        if let Some(ref resource) = request.resource {
+            *profile.resources.entry(resource.clone()).or_insert(0.0) += 1.0;
         }
+        profile.hourly_distribution[now.hour() as usize] += 1.0;
+        profile.weekly_distribution[now.weekday().num_days_from_sunday() as usize] += 1.0;
+        profile.total_requests += 1.0;
+        profile.last_seen = Utc::now();
+    }
 
-        // Update time pattern
-        let now = chrono::Local::now();
-        let hour = now.hour() as usize;
-        let day = now.weekday().num_days_from_sunday() as usize;
+    /// Raise an app's suspicion after a threat report of `severity` (0.0-1.0)
+    pub fn flag(&self, process_path: &str, severity: f32) {
+        if !self.enabled {
+            return;
+        }
 
-        let mut time_pattern = self.time_patterns
-            .entry(request.process_path.clone())
-            .or_default();
-        time_pattern.hourly_distribution[hour] += 1;
-        time_pattern.weekly_distribution[day] += 1;
+        let severity = severity.clamp(0.0, 1.0) as f64;
+        let mut profile = self.profiles.entry(process_path.to_string()).or_default();
+        // Repeated reports reinforce each other like independent signals
+        profile.suspicion = 1.0 - (1.0 - profile.suspicion) * (1.0 - severity);
+        debug!("Flagged {} (suspicion {:.2})", process_path, profile.suspicion);
+    }
 
-        // Update resource pattern
-        if let Some(ref resource) = request.resource {
-            let mut resource_profile = self.resource_patterns
-                .entry(request.process_path.clone())
-                .or_default();
-            *resource_profile.resources.entry(resource.clone()).or_insert(0) += 1;
+    /// Age learned behavior by the time elapsed since the last decay
+    pub fn decay(&self) {
+        self.decay_at(Utc::now());
+    }
+
+    fn decay_at(&self, now: DateTime<Utc>) {
+        if self.decay_half_life_days <= 0.0 {
+            return;
+        }
+
+        let mut last_decay = self.last_decay.lock().unwrap();
+        let elapsed_days = (now - *last_decay).num_seconds().max(0) as f64 / 86_400.0;
+        *last_decay = now;
+
+        let factor = 0.5f64.powf(elapsed_days / self.decay_half_life_days);
+        for mut profile in self.profiles.iter_mut() {
+            profile.decay(factor);
+        }
+
+        // Forget apps whose behavior has faded out entirely
+        let before = self.profiles.len();
+        self.profiles.retain(|_, p| p.total_requests >= MIN_WEIGHT || p.suspicion >= MIN_WEIGHT);
+        if self.profiles.len() != before {
+            debug!("Dropped {} faded behavior profiles", before - self.profiles.len());
+        }
+    }
+
+    /// Persist learned profiles
+    pub async fn save(&self) -> Result<()> {
+        if !self.enabled || self.database_path.as_os_str().is_empty() {
+            return Ok(());
+        }
+
+        let snapshot = PatternSnapshot {
+            last_decay: *self.last_decay.lock().unwrap(),
+            profiles: self
+                .profiles
+                .iter()
+                .map(|entry| (entry.key().clone(), entry.value().clone()))
+                .collect(),
+        };
+
+        if let Some(parent) = self.database_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
         }
+
+        // Write then rename so a crash never leaves a truncated database
+        let tmp = self.database_path.with_extension("tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec(&snapshot)?).await?;
+        tokio::fs::rename(&tmp, &self.database_path).await?;
+        debug!("Saved {} behavior profiles", snapshot.profiles.len());
+        Ok(())
     }
 
     /// Get anomaly threshold
     pub fn threshold(&self) -> f32 {
         self.anomaly_threshold
     }
+
+    /// Score at which anomalies are audited
+    pub fn warn_threshold(&self) -> f32 {
+        self.warn_threshold
+    }
+
+    /// Score at which requests are denied outright
+    pub fn block_threshold(&self) -> f32 {
+        self.block_threshold
+    }
 }
 
 fn has_common_prefix(a: &str, b: &str) -> bool {
@@ -304,4 +440,90 @@ fn has_common_prefix(a: &str, b: &str) -> bool {
     common >= 2
 }
 
-use chrono::{Datelike, Timelike};
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn request(capability: &str) -> CapabilityRequest {
+        CapabilityRequest {
+            pid: 1,
+            process_path: "/usr/bin/editor".into(),
+            user: "user".into(),
+            capability: capability.into(),
+            resource: None,
+            context: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_new_capability_is_anomalous() {
+        let config = PatternConfig {
+            database_path: String::new(),
+            min_observations: 5,
+            ..PatternConfig::default()
+        };
+        let learner = PatternLearner::new(&config).unwrap();
+
+        for _ in 0..10 {
+            learner.learn(&request("filesystem:read"));
+        }
+
+        let normal = learner.analyze(&request("filesystem:read"));
+        let unusual = learner.analyze(&request("network:raw"));
+        assert!(normal.anomaly_score < config.warn_threshold);
+        assert!(unusual.anomaly_score >= config.anomaly_threshold);
+    }
+
+    #[test]
+    fn test_threat_reports_raise_anomaly_score() {
+        let config = PatternConfig {
+            database_path: String::new(),
+            min_observations: 5,
+            decay_half_life_days: 1.0,
+            ..PatternConfig::default()
+        };
+        let learner = PatternLearner::new(&config).unwrap();
+
+        for _ in 0..10 {
+            learner.learn(&request("filesystem:read"));
+        }
+        let before = learner.analyze(&request("filesystem:read")).anomaly_score;
+
+        learner.flag("/usr/bin/editor", 0.9);
+        let flagged = learner.analyze(&request("filesystem:read"));
+        assert!(flagged.anomaly_score >= 0.9);
+        assert!(flagged.anomaly_score > before);
+        assert!(flagged.explanation.contains("threat reports"));
+
+        // Suspicion fades with the rest of the profile
+        let later = *learner.last_decay.lock().unwrap() + chrono::Duration::days(10);
+        learner.decay_at(later);
+        assert!(learner.profiles.get("/usr/bin/editor").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_profiles_persist_and_decay() {
+        let dir = tempdir().unwrap();
+        let config = PatternConfig {
+            database_path: dir.path().join("patterns.json").to_string_lossy().into(),
+            decay_half_life_days: 1.0,
+            ..PatternConfig::default()
+        };
+
+        let learner = PatternLearner::new(&config).unwrap();
+        for _ in 0..8 {
+            learner.learn(&request("filesystem:read"));
+        }
+        learner.save().await.unwrap();
+
+        let reloaded = PatternLearner::new(&config).unwrap();
+        let total = |l: &PatternLearner| l.profiles.get("/usr/bin/editor").unwrap().total_requests;
+        assert_eq!(total(&reloaded), 8.0);
+
+        // Two half-lives later a quarter of the weight remains
+        let later = *reloaded.last_decay.lock().unwrap() + chrono::Duration::days(2);
+        reloaded.decay_at(later);
+        assert!((total(&reloaded) - 2.0).abs() < 1e-6);
+    }
+}
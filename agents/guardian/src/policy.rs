@@ -1,10 +1,20 @@
 //! Policy engine - evaluates static policies
-
-use crate::config::{CapabilityRule, DefaultPolicy, PolicyConfig, RuleAction, RuleCondition, TrustedApp};
-use anyhow::Result;
+//!
+//! Rule conditions on VPN, session and power state read the cached
+//! context feeds; a condition whose feed has no fresh reading does not
+//! hold, so a rule never applies on a guess.
+
+use crate::config::{
+    CapabilityRule, ContextConfig, DefaultPolicy, PolicyConfig, RuleAction, RuleCondition, TrustedApp,
+};
+use crate::context::{ContextFeeds, Feed};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, NaiveTime, Utc, Weekday};
 use regex::Regex;
 use std::collections::HashMap;
-use tracing::debug;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tracing::{debug, info, warn};
 
 /// Capability request to evaluate
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -51,6 +61,15 @@ pub enum PolicyDecision {
     Sandbox,
 }
 
+/// A user's persistent answer to a prompt
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RememberedChoice {
+    pub process_path: String,
+    pub capability: String,
+    pub allow: bool,
+    pub decided_at: DateTime<Utc>,
+}
+
 /// Policy engine
 pub struct PolicyEngine {
     /// Default policy
@@ -59,6 +78,23 @@ pub struct PolicyEngine {
     trusted_apps: Vec<CompiledTrustedApp>,
     /// Capability rules (compiled)
     capability_rules: Vec<CompiledRule>,
+    /// Remembered prompt answers
+    remembered: RwLock<Vec<RememberedChoice>>,
+    /// Where remembered answers are persisted
+    remembered_path: PathBuf,
+    /// VPN, session and power state for context conditions
+    context: Arc<ContextFeeds>,
+}
+
+/// One step of a policy evaluation trace
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RuleTrace {
+    /// Rule name
+    pub rule: String,
+    /// Whether the rule applied
+    pub matched: bool,
+    /// Why it did or did not apply
+    pub detail: String,
 }
 
 struct CompiledTrustedApp {
@@ -67,6 +103,16 @@ struct CompiledTrustedApp {
     capabilities: Vec<Regex>,
 }
 
+impl CompiledTrustedApp {
+    fn allows(&self, request: &CapabilityRequest) -> bool {
+        self.path_pattern.is_match(&request.process_path)
+            && self
+                .capabilities
+                .iter()
+                .any(|cap| cap.is_match(&request.capability) || cap.as_str() == ".*")
+    }
+}
+
 struct CompiledRule {
     name: String,
     capability_pattern: Regex,
@@ -77,9 +123,92 @@ struct CompiledRule {
 enum CompiledCondition {
     AppPath(Regex),
     User(String),
-    TimeWindow { start: String, end: String },
+    TimeWindow { start: NaiveTime, end: NaiveTime, days: Vec<Weekday> },
     ResourcePath(Regex),
     Intent(String),
+    Context(Feed, bool),
+}
+
+impl CompiledRule {
+    /// Why the rule does not apply, or `None` if it does
+    fn mismatch(&self, request: &CapabilityRequest, context: &ContextFeeds) -> Option<String> {
+        if !self.capability_pattern.is_match(&request.capability) {
+            return Some("capability does not match".into());
+        }
+        self.conditions
+            .iter()
+            .find(|cond| !cond.holds(request, context))
+            .map(|cond| match cond {
+                CompiledCondition::Context(feed, _) if context.get(*feed).is_none() => {
+                    format!("condition not met: {} (no recent {} reading)", cond.describe(), feed.name())
+                }
+                _ => format!("condition not met: {}", cond.describe()),
+            })
+    }
+}
+
+impl CompiledCondition {
+    fn holds(&self, request: &CapabilityRequest, context: &ContextFeeds) -> bool {
+        match self {
+            CompiledCondition::AppPath(pattern) => {
+                pattern.is_match(&request.process_path)
+            }
+            CompiledCondition::User(user) => {
+                &request.user == user || user == "*"
+            }
+            CompiledCondition::TimeWindow { start, end, days } => {
+                in_window(*start, *end, days, Local::now().naive_local())
+            }
+            CompiledCondition::ResourcePath(pattern) => {
+                request.resource.as_ref()
+                    .map(|r| pattern.is_match(r))
+                    .unwrap_or(false)
+            }
+            CompiledCondition::Intent(intent) => {
+                request.context.get("intent")
+                    .map(|i| i == intent)
+                    .unwrap_or(false)
+            }
+            CompiledCondition::Context(feed, expected) => {
+                context.get(*feed) == Some(*expected)
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            CompiledCondition::AppPath(pattern) => format!("app_path {}", pattern.as_str()),
+            CompiledCondition::User(user) => format!("user {}", user),
+            CompiledCondition::TimeWindow { start, end, days } if days.is_empty() => {
+                format!("time_window {}-{}", start.format("%H:%M"), end.format("%H:%M"))
+            }
+            CompiledCondition::TimeWindow { start, end, days } => format!(
+                "time_window {}-{} on {}",
+                start.format("%H:%M"),
+                end.format("%H:%M"),
+                days.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(",")
+            ),
+            CompiledCondition::ResourcePath(pattern) => format!("resource_path {}", pattern.as_str()),
+            CompiledCondition::Intent(intent) => format!("intent {}", intent),
+            CompiledCondition::Context(feed, expected) => format!("{} {}", feed.name(), expected),
+        }
+    }
+}
+
+/// Whether `now` falls in the window; one that wraps past midnight
+/// counts as the day it started on
+fn in_window(start: NaiveTime, end: NaiveTime, days: &[Weekday], now: NaiveDateTime) -> bool {
+    let time = now.time();
+    let started_on = if start <= end {
+        (start <= time && time < end).then(|| now.weekday())
+    } else if time >= start {
+        Some(now.weekday())
+    } else if time < end {
+        Some(now.weekday().pred())
+    } else {
+        None
+    };
+    started_on.is_some_and(|day| days.is_empty() || days.contains(&day))
 }
 
 impl PolicyEngine {
@@ -99,13 +228,46 @@ impl PolicyEngine {
             .filter_map(|rule| compile_rule(rule).ok())
             .collect();
 
+        let remembered = match std::fs::read_to_string(&config.remembered_path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
         Ok(Self {
             default_policy: config.default_policy,
             trusted_apps,
             capability_rules,
+            remembered: RwLock::new(remembered),
+            remembered_path: config.remembered_path.clone(),
+            context: Arc::new(ContextFeeds::new(&ContextConfig::default())),
         })
     }
 
+    /// Evaluate context conditions against `context` instead of feeds
+    /// that are never read
+    pub fn with_context(mut self, context: Arc<ContextFeeds>) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Context feeds conditions are evaluated against
+    pub fn context(&self) -> Arc<ContextFeeds> {
+        self.context.clone()
+    }
+
+    /// Like `new`, but fail on any entry that does not compile instead of
+    /// skipping it; used when reloading or testing a candidate policy set
+    pub fn new_strict(config: &PolicyConfig) -> Result<Self> {
+        for app in &config.trusted_apps {
+            compile_trusted_app(app).with_context(|| format!("Trusted app '{}'", app.name))?;
+        }
+        for rule in &config.capability_rules {
+            compile_rule(rule).with_context(|| format!("Rule '{}'", rule.name))?;
+        }
+        Self::new(config)
+    }
+
     /// Evaluate a capability request
     pub fn evaluate(&self, request: &CapabilityRequest) -> PolicyResult {
         debug!(
@@ -118,6 +280,11 @@ impl PolicyEngine {
             return result;
         }
 
+        // Then what the user told us to remember
+        if let Some(result) = self.check_remembered(request) {
+            return result;
+        }
+
         // Check explicit rules
         if let Some(result) = self.check_rules(request) {
             return result;
@@ -128,80 +295,151 @@ impl PolicyEngine {
     }
 
     fn check_trusted_apps(&self, request: &CapabilityRequest) -> Option<PolicyResult> {
-        for app in &self.trusted_apps {
-            if app.path_pattern.is_match(&request.process_path) {
-                // Check if capability is allowed for this trusted app
-                for cap_pattern in &app.capabilities {
-                    if cap_pattern.is_match(&request.capability) || cap_pattern.as_str() == ".*" {
-                        return Some(PolicyResult {
-                            decision: PolicyDecision::Allow,
-                            matched_rule: Some(format!("trusted_app:{}", app.name)),
-                            reason: format!("Trusted application: {}", app.name),
-                            sandbox_profile: None,
-                        });
-                    }
-                }
-            }
+        let app = self.trusted_apps.iter().find(|app| app.allows(request))?;
+        Some(PolicyResult {
+            decision: PolicyDecision::Allow,
+            matched_rule: Some(format!("trusted_app:{}", app.name)),
+            reason: format!("Trusted application: {}", app.name),
+            sandbox_profile: None,
+        })
+    }
+
+    fn check_remembered(&self, request: &CapabilityRequest) -> Option<PolicyResult> {
+        let remembered = self.remembered.read().unwrap();
+        let choice = remembered
+            .iter()
+            .find(|c| c.process_path == request.process_path && c.capability == request.capability)?;
+
+        Some(PolicyResult {
+            decision: if choice.allow { PolicyDecision::Allow } else { PolicyDecision::Deny },
+            matched_rule: Some("remembered".into()),
+            reason: format!(
+                "User chose to always {} on {}",
+                if choice.allow { "allow" } else { "deny" },
+                choice.decided_at.format("%Y-%m-%d")
+            ),
+            sandbox_profile: None,
+        })
+    }
+
+    /// Remember a user's answer for this app and capability
+    pub fn remember(&self, request: &CapabilityRequest, allow: bool) {
+        let snapshot = {
+            let mut remembered = self.remembered.write().unwrap();
+            remembered.retain(|c| {
+                !(c.process_path == request.process_path && c.capability == request.capability)
+            });
+            remembered.push(RememberedChoice {
+                process_path: request.process_path.clone(),
+                capability: request.capability.clone(),
+                allow,
+                decided_at: Utc::now(),
+            });
+            remembered.clone()
+        };
+
+        info!(
+            "Remembering {} of '{}' for {}",
+            if allow { "allow" } else { "deny" },
+            request.capability,
+            request.process_path
+        );
+
+        if let Err(e) = self.save_remembered(&snapshot) {
+            warn!("Failed to persist remembered choices: {}", e);
         }
-        None
+    }
+
+    fn save_remembered(&self, remembered: &[RememberedChoice]) -> Result<()> {
+        if let Some(parent) = self.remembered_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.remembered_path, serde_json::to_string_pretty(remembered)?)?;
+        Ok(())
     }
 
     fn check_rules(&self, request: &CapabilityRequest) -> Option<PolicyResult> {
-        for rule in &self.capability_rules {
-            if !rule.capability_pattern.is_match(&request.capability) {
-                continue;
+        let rule = self
+            .capability_rules
+            .iter()
+            .find(|rule| rule.mismatch(request, &self.context).is_none())?;
+
+        let decision = match rule.action {
+            RuleAction::Allow => PolicyDecision::Allow,
+            RuleAction::Deny => PolicyDecision::Deny,
+            RuleAction::Prompt => PolicyDecision::Prompt,
+            RuleAction::AllowOnce => PolicyDecision::Prompt, // Prompt but only allow once
+            RuleAction::DenyWithMessage => PolicyDecision::Deny,
+            RuleAction::Sandbox => PolicyDecision::Sandbox,
+        };
+
+        Some(PolicyResult {
+            decision,
+            matched_rule: Some(rule.name.clone()),
+            reason: format!("Matched rule: {}", rule.name),
+            sandbox_profile: if decision == PolicyDecision::Sandbox {
+                Some("strict".into())
+            } else {
+                None
+            },
+        })
+    }
+
+    /// Evaluate a request and report every rule considered along the way
+    pub fn explain(&self, request: &CapabilityRequest) -> (PolicyResult, Vec<RuleTrace>) {
+        let mut trace = Vec::new();
+
+        for app in &self.trusted_apps {
+            let matched = app.allows(request);
+            trace.push(RuleTrace {
+                rule: format!("trusted_app:{}", app.name),
+                matched,
+                detail: if matched {
+                    "path and capability allowed".into()
+                } else if !app.path_pattern.is_match(&request.process_path) {
+                    "path does not match".into()
+                } else {
+                    "capability not granted to this app".into()
+                },
+            });
+            if matched {
+                return (self.evaluate(request), trace);
             }
+        }
 
-            // Check all conditions
-            let conditions_met = rule.conditions.iter().all(|cond| {
-                match cond {
-                    CompiledCondition::AppPath(pattern) => {
-                        pattern.is_match(&request.process_path)
-                    }
-                    CompiledCondition::User(user) => {
-                        &request.user == user || user == "*"
-                    }
-                    CompiledCondition::TimeWindow { start, end } => {
-                        // TODO: Implement time window check
-                        let _ = (start, end);
-                        true
-                    }
-                    CompiledCondition::ResourcePath(pattern) => {
-                        request.resource.as_ref()
-                            .map(|r| pattern.is_match(r))
-                            .unwrap_or(false)
-                    }
-                    CompiledCondition::Intent(intent) => {
-                        request.context.get("intent")
-                            .map(|i| i == intent)
-                            .unwrap_or(false)
-                    }
-                }
+        if let Some(result) = self.check_remembered(request) {
+            trace.push(RuleTrace {
+                rule: "remembered".into(),
+                matched: true,
+                detail: result.reason.clone(),
             });
+            return (result, trace);
+        }
 
-            if conditions_met {
-                let decision = match rule.action {
-                    RuleAction::Allow => PolicyDecision::Allow,
-                    RuleAction::Deny => PolicyDecision::Deny,
-                    RuleAction::Prompt => PolicyDecision::Prompt,
-                    RuleAction::AllowOnce => PolicyDecision::Prompt, // Prompt but only allow once
-                    RuleAction::DenyWithMessage => PolicyDecision::Deny,
-                    RuleAction::Sandbox => PolicyDecision::Sandbox,
-                };
-
-                return Some(PolicyResult {
-                    decision,
-                    matched_rule: Some(rule.name.clone()),
-                    reason: format!("Matched rule: {}", rule.name),
-                    sandbox_profile: if decision == PolicyDecision::Sandbox {
-                        Some("strict".into())
-                    } else {
-                        None
-                    },
-                });
+        for rule in &self.capability_rules {
+            let mismatch = rule.mismatch(request, &self.context);
+            trace.push(RuleTrace {
+                rule: rule.name.clone(),
+                matched: mismatch.is_none(),
+                detail: match &mismatch {
+                    None => format!("action {:?}", rule.action).to_lowercase(),
+                    Some(reason) => reason.clone(),
+                },
+            });
+            if mismatch.is_none() {
+                break;
             }
         }
-        None
+
+        let result = self.evaluate(request);
+        if result.matched_rule.is_none() {
+            trace.push(RuleTrace {
+                rule: "default".into(),
+                matched: true,
+                detail: result.reason.clone(),
+            });
+        }
+        (result, trace)
     }
 
     fn apply_default_policy(&self, request: &CapabilityRequest) -> PolicyResult {
@@ -247,14 +485,18 @@ fn compile_rule(rule: &CapabilityRule) -> Result<CompiledRule> {
                 Ok(CompiledCondition::AppPath(glob_to_regex(pattern)?))
             }
             RuleCondition::User(user) => Ok(CompiledCondition::User(user.clone())),
-            RuleCondition::TimeWindow { start, end } => Ok(CompiledCondition::TimeWindow {
-                start: start.clone(),
-                end: end.clone(),
+            RuleCondition::TimeWindow { start, end, days } => Ok(CompiledCondition::TimeWindow {
+                start: parse_time(start)?,
+                end: parse_time(end)?,
+                days: days.clone(),
             }),
             RuleCondition::ResourcePath(pattern) => {
                 Ok(CompiledCondition::ResourcePath(glob_to_regex(pattern)?))
             }
             RuleCondition::Intent(intent) => Ok(CompiledCondition::Intent(intent.clone())),
+            RuleCondition::Vpn(on) => Ok(CompiledCondition::Context(Feed::Vpn, *on)),
+            RuleCondition::SessionLocked(locked) => Ok(CompiledCondition::Context(Feed::SessionLocked, *locked)),
+            RuleCondition::OnBattery(on) => Ok(CompiledCondition::Context(Feed::OnBattery, *on)),
         })
         .collect::<Result<Vec<_>>>()?;
 
@@ -266,7 +508,11 @@ fn compile_rule(rule: &CapabilityRule) -> Result<CompiledRule> {
     })
 }
 
-fn glob_to_regex(pattern: &str) -> Result<Regex> {
+fn parse_time(time: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(time, "%H:%M").with_context(|| format!("Invalid time '{}', expected HH:MM", time))
+}
+
+pub(crate) fn glob_to_regex(pattern: &str) -> Result<Regex> {
     let escaped = regex::escape(pattern);
     let regex_pattern = escaped
         .replace(r"\*", ".*")
@@ -289,6 +535,7 @@ mod tests {
             }],
             capability_rules: vec![],
             sandboxes: vec![],
+            remembered_path: std::env::temp_dir().join("guardian-test-remembered.json"),
         };
 
         let engine = PolicyEngine::new(&config).unwrap();
@@ -305,4 +552,109 @@ mod tests {
         let result = engine.evaluate(&request);
         assert_eq!(result.decision, PolicyDecision::Allow);
     }
+
+    #[test]
+    fn test_explain_trace() {
+        let config = PolicyConfig {
+            default_policy: DefaultPolicy::Prompt,
+            trusted_apps: vec![],
+            capability_rules: vec![
+                CapabilityRule {
+                    name: "browsers-network".into(),
+                    capability: "network:*".into(),
+                    conditions: vec![RuleCondition::AppPath("/usr/bin/firefox".into())],
+                    action: RuleAction::Allow,
+                },
+                CapabilityRule {
+                    name: "no-raw-network".into(),
+                    capability: "network:raw".into(),
+                    conditions: vec![],
+                    action: RuleAction::Deny,
+                },
+            ],
+            sandboxes: vec![],
+            remembered_path: std::env::temp_dir().join("guardian-test-explain.json"),
+        };
+
+        let engine = PolicyEngine::new_strict(&config).unwrap();
+
+        let request = CapabilityRequest {
+            pid: 1234,
+            process_path: "/usr/bin/curl".into(),
+            user: "user".into(),
+            capability: "network:raw".into(),
+            resource: None,
+            context: HashMap::new(),
+        };
+
+        let (result, trace) = engine.explain(&request);
+        assert_eq!(result.decision, PolicyDecision::Deny);
+        assert_eq!(result.matched_rule.as_deref(), Some("no-raw-network"));
+        assert_eq!(trace.len(), 2);
+        assert!(!trace[0].matched);
+        assert!(trace[0].detail.starts_with("condition not met"));
+        assert!(trace[1].matched);
+    }
+
+    #[test]
+    fn test_time_window() {
+        let at = |date: &str| NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M").unwrap();
+        let time = |t: &str| parse_time(t).unwrap();
+
+        assert!(in_window(time("09:00"), time("17:00"), &[], at("2026-10-16 12:00")));
+        assert!(!in_window(time("09:00"), time("17:00"), &[], at("2026-10-16 17:00")));
+
+        // Friday night's window runs into Saturday morning
+        let fri = [Weekday::Fri];
+        assert!(in_window(time("22:00"), time("06:00"), &fri, at("2026-10-16 23:30")));
+        assert!(in_window(time("22:00"), time("06:00"), &fri, at("2026-10-17 02:00")));
+        assert!(!in_window(time("22:00"), time("06:00"), &fri, at("2026-10-17 23:30")));
+        assert!(!in_window(time("22:00"), time("06:00"), &fri, at("2026-10-16 12:00")));
+
+        assert!(parse_time("25:00").is_err());
+    }
+
+    #[test]
+    fn test_context_conditions() {
+        let config = PolicyConfig {
+            default_policy: DefaultPolicy::Prompt,
+            trusted_apps: vec![],
+            capability_rules: vec![CapabilityRule {
+                name: "no-camera-when-locked".into(),
+                capability: "device:camera".into(),
+                conditions: vec![RuleCondition::SessionLocked(true)],
+                action: RuleAction::Deny,
+            }],
+            sandboxes: vec![],
+            remembered_path: std::env::temp_dir().join("guardian-test-context.json"),
+        };
+
+        let context = Arc::new(ContextFeeds::new(&ContextConfig::default()));
+        let engine = PolicyEngine::new_strict(&config).unwrap().with_context(context.clone());
+
+        let request = CapabilityRequest {
+            pid: 1234,
+            process_path: "/usr/bin/recorder".into(),
+            user: "user".into(),
+            capability: "device:camera".into(),
+            resource: None,
+            context: HashMap::new(),
+        };
+
+        // Nothing read yet
+        let (result, trace) = engine.explain(&request);
+        assert_eq!(result.decision, PolicyDecision::Prompt);
+        assert!(trace[0].detail.contains("no recent session_locked reading"));
+
+        // A stale reading is as good as none
+        let stale = std::time::Instant::now().checked_sub(std::time::Duration::from_secs(60)).unwrap();
+        context.record(Feed::SessionLocked, true, stale);
+        assert_eq!(engine.evaluate(&request).decision, PolicyDecision::Prompt);
+
+        context.record(Feed::SessionLocked, true, std::time::Instant::now());
+        assert_eq!(engine.evaluate(&request).decision, PolicyDecision::Deny);
+
+        context.record(Feed::SessionLocked, false, std::time::Instant::now());
+        assert_eq!(engine.evaluate(&request).decision, PolicyDecision::Prompt);
+    }
 }
@@ -0,0 +1,225 @@
+//! Interactive prompts
+//!
+//! When a decision needs the user, Guardian posts a notification through
+//! herald with Allow Once / Always Allow / Deny actions and blocks the
+//! requester until an answer arrives or the prompt times out. Answers may
+//! also come over Guardian's own IPC (`UserResponse`) from a configured UI
+//! client, e.g. a shell dialog; whichever arrives first wins. IPC answers
+//! are never remembered.
+
+use crate::config::PromptConfig;
+use crate::ipc::PromptDetails;
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::UnixStream;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Herald action ids
+const ACTION_ALLOW_ONCE: &str = "allow_once";
+const ACTION_ALLOW_ALWAYS: &str = "allow_always";
+const ACTION_DENY: &str = "deny";
+
+/// Where an answer came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnswerSource {
+    /// Notification action in herald
+    Herald,
+    /// `UserResponse` over Guardian IPC
+    Ipc,
+    /// Nobody answered in time
+    Timeout,
+}
+
+/// The user's answer to a prompt
+#[derive(Debug, Clone, Copy)]
+pub struct PromptAnswer {
+    pub approved: bool,
+    /// Keep this answer for future requests
+    pub remember: bool,
+    pub source: AnswerSource,
+    pub waited: Duration,
+}
+
+/// Prompt broker
+pub struct PromptBroker {
+    config: PromptConfig,
+}
+
+/// Notification posted in herald, with its action stream
+struct HeraldPrompt {
+    id: u64,
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+impl PromptBroker {
+    /// Create a new prompt broker
+    pub fn new(config: &PromptConfig) -> Self {
+        Self {
+            config: config.clone(),
+        }
+    }
+
+    /// Whether prompts block for an answer
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Prompt timeout
+    pub fn timeout_secs(&self) -> u32 {
+        self.config.timeout_secs
+    }
+
+    /// Whether a peer may answer prompts over IPC
+    ///
+    /// The peer's executable must be a configured answer client, and the
+    /// process asking for the capability may never answer for itself.
+    pub fn accepts_answer_from(&self, peer_pid: Option<i32>, requester_pid: u32) -> bool {
+        let Some(pid) = peer_pid else {
+            return false;
+        };
+        if pid as u32 == requester_pid {
+            return false;
+        }
+        match std::fs::read_link(format!("/proc/{}/exe", pid)) {
+            Ok(exe) => self.config.answer_clients.contains(&exe),
+            Err(_) => false,
+        }
+    }
+
+    /// Ask the user and wait for an answer
+    ///
+    /// Denies on timeout. `ipc_answers` receives `(approved, remember)` from
+    /// `UserResponse` requests for this prompt.
+    pub async fn ask(
+        &self,
+        request_id: Uuid,
+        message: &str,
+        details: &PromptDetails,
+        mut ipc_answers: mpsc::Receiver<(bool, bool)>,
+    ) -> PromptAnswer {
+        let started = Instant::now();
+        let deadline = tokio::time::sleep(Duration::from_secs(self.config.timeout_secs as u64));
+        tokio::pin!(deadline);
+
+        let mut herald = match self.post(request_id, message, details).await {
+            Ok(prompt) => Some(prompt),
+            Err(e) => {
+                warn!("Could not prompt through herald: {}", e);
+                None
+            }
+        };
+
+        let (approved, remember, source) = loop {
+            tokio::select! {
+                answer = ipc_answers.recv() => match answer {
+                    Some((approved, remember)) => break (approved, remember, AnswerSource::Ipc),
+                    // Pending prompt was dropped without an answer
+                    None => break (false, false, AnswerSource::Timeout),
+                },
+                action = next_action(herald.as_mut()) => match action {
+                    Some(action) => match action.as_str() {
+                        ACTION_ALLOW_ONCE => break (true, false, AnswerSource::Herald),
+                        ACTION_ALLOW_ALWAYS => break (true, true, AnswerSource::Herald),
+                        ACTION_DENY => break (false, false, AnswerSource::Herald),
+                        other => debug!("Ignoring prompt action '{}'", other),
+                    },
+                    // Herald went away; keep waiting for an IPC answer
+                    None => herald = None,
+                },
+                _ = &mut deadline => break (false, false, AnswerSource::Timeout),
+            }
+        };
+
+        // Take the notification down if it was answered elsewhere
+        if source != AnswerSource::Herald {
+            if let Some(prompt) = herald.as_mut() {
+                let close = json!({ "type": "CloseNotification", "data": { "id": prompt.id } });
+                let _ = prompt.writer.write_all(format!("{}\n", close).as_bytes()).await;
+            }
+        }
+
+        PromptAnswer {
+            approved,
+            remember,
+            source,
+            waited: started.elapsed(),
+        }
+    }
+
+    /// Post the prompt notification
+    async fn post(&self, request_id: Uuid, message: &str, details: &PromptDetails) -> Result<HeraldPrompt> {
+        let stream = UnixStream::connect(&self.config.herald_socket).await?;
+        let (reader, mut writer) = stream.into_split();
+
+        let mut body = format!("Risk: {}\n{}", details.risk_level, details.explanation);
+        if let Some(resource) = &details.resource {
+            body = format!("{}\nResource: {}", body, resource);
+        }
+
+        let notify = json!({
+            "type": "Notify",
+            "data": {
+                "app_name": "guardian",
+                "summary": message,
+                "body": body,
+                "icon": "security-high",
+                "urgency": "critical",
+                "timeout": (self.config.timeout_secs * 1000) as i32,
+                "actions": [
+                    { "id": ACTION_ALLOW_ONCE, "label": "Allow Once" },
+                    { "id": ACTION_ALLOW_ALWAYS, "label": "Always Allow" },
+                    { "id": ACTION_DENY, "label": "Deny" },
+                ],
+                "token": request_id.to_string(),
+            }
+        });
+        writer.write_all(format!("{}\n", notify).as_bytes()).await?;
+        writer.flush().await?;
+
+        let mut lines = BufReader::new(reader).lines();
+        let line = lines
+            .next_line()
+            .await?
+            .ok_or_else(|| anyhow!("Herald closed the connection"))?;
+        let response: Value = serde_json::from_str(&line)?;
+
+        match response["status"].as_str() {
+            Some("Success") => {
+                if response["data"]["outcome"] == "denied" {
+                    return Err(anyhow!("Herald policy denies guardian notifications"));
+                }
+                let id = response["data"]["id"]
+                    .as_u64()
+                    .ok_or_else(|| anyhow!("No notification id from herald"))?;
+                Ok(HeraldPrompt { id, lines, writer })
+            }
+            _ => Err(anyhow!(
+                "Herald rejected prompt: {}",
+                response["message"].as_str().unwrap_or("unknown error")
+            )),
+        }
+    }
+}
+
+/// Next action invoked on the prompt; pends forever without a herald connection
+async fn next_action(prompt: Option<&mut HeraldPrompt>) -> Option<String> {
+    let Some(prompt) = prompt else {
+        return std::future::pending().await;
+    };
+
+    while let Ok(Some(line)) = prompt.lines.next_line().await {
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        if message["status"] == "Action" && message["event"]["id"].as_u64() == Some(prompt.id) {
+            return message["event"]["action_id"].as_str().map(String::from);
+        }
+    }
+    None
+}
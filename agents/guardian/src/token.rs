@@ -0,0 +1,208 @@
+//! Execution tokens - pre-authorized capability scopes
+//!
+//! A daemon that runs work on someone else's behalf (grimoire running a
+//! persona's ritual) asks for a token covering what that work declared it
+//! needs. Guardian grants the scopes static policy allows outright and
+//! refuses the rest; nothing is prompted for, since nobody is there to
+//! answer. Enforcement points then check each action against the token
+//! instead of running the full decision pipeline per action.
+//!
+//! Tokens are short-lived and revoked when the work ends.
+
+use crate::policy::glob_to_regex;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use tracing::debug;
+use uuid::Uuid;
+
+/// One capability a token covers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenScope {
+    /// Capability, e.g. `filesystem:read`
+    pub capability: String,
+    /// Resource pattern (glob); any resource when absent
+    #[serde(default)]
+    pub resource: Option<String>,
+}
+
+impl TokenScope {
+    /// Whether this scope covers a capability on a resource
+    pub fn covers(&self, capability: &str, resource: Option<&str>) -> bool {
+        if self.capability != capability {
+            return false;
+        }
+        match (&self.resource, resource) {
+            (None, _) => true,
+            (Some(pattern), Some(resource)) => {
+                glob_to_regex(pattern).is_ok_and(|regex| regex.is_match(resource))
+            }
+            (Some(_), None) => false,
+        }
+    }
+
+    /// `capability` or `capability resource`, for logs
+    pub fn describe(&self) -> String {
+        match &self.resource {
+            Some(resource) => format!("{} {}", self.capability, resource),
+            None => self.capability.clone(),
+        }
+    }
+}
+
+/// An issued token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionToken {
+    pub id: Uuid,
+    /// Whose behalf the work runs on, e.g. `persona:lilith`
+    pub subject: String,
+    /// User the work runs for
+    pub user: String,
+    pub scopes: Vec<TokenScope>,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Why a token doesn't allow an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum TokenDenial {
+    #[error("No such token")]
+    Unknown,
+    #[error("Token has expired")]
+    Expired,
+    #[error("Action is outside the token's scopes")]
+    OutOfScope,
+}
+
+/// Token manager
+pub struct TokenManager {
+    max_ttl_secs: u64,
+    tokens: RwLock<HashMap<Uuid, ExecutionToken>>,
+}
+
+impl TokenManager {
+    /// Create a token manager issuing tokens of at most `max_ttl_secs`
+    pub fn new(max_ttl_secs: u64) -> Self {
+        Self {
+            max_ttl_secs,
+            tokens: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Issue a token for scopes that already passed policy
+    pub async fn issue(
+        &self,
+        subject: &str,
+        user: &str,
+        scopes: Vec<TokenScope>,
+        ttl_secs: u64,
+    ) -> ExecutionToken {
+        let now = Utc::now();
+        let ttl = ttl_secs.clamp(1, self.max_ttl_secs.max(1));
+        let token = ExecutionToken {
+            id: Uuid::new_v4(),
+            subject: subject.to_string(),
+            user: user.to_string(),
+            scopes,
+            issued_at: now,
+            expires_at: now + Duration::seconds(ttl as i64),
+        };
+
+        debug!(
+            "Token {} for {} with {} scope(s) expires {}",
+            token.id,
+            subject,
+            token.scopes.len(),
+            token.expires_at
+        );
+        self.tokens.write().await.insert(token.id, token.clone());
+        token
+    }
+
+    /// Check an action against a token
+    pub async fn check(
+        &self,
+        id: Uuid,
+        capability: &str,
+        resource: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<ExecutionToken, TokenDenial> {
+        let tokens = self.tokens.read().await;
+        let token = tokens.get(&id).ok_or(TokenDenial::Unknown)?;
+        if token.expires_at <= now {
+            return Err(TokenDenial::Expired);
+        }
+        if !token.scopes.iter().any(|scope| scope.covers(capability, resource)) {
+            return Err(TokenDenial::OutOfScope);
+        }
+        Ok(token.clone())
+    }
+
+    /// End a token early
+    pub async fn revoke(&self, id: Uuid) -> Option<ExecutionToken> {
+        self.tokens.write().await.remove(&id)
+    }
+
+    /// Drop every token that has expired by `now`
+    pub async fn expire(&self, now: DateTime<Utc>) -> Vec<ExecutionToken> {
+        let mut tokens = self.tokens.write().await;
+        let ids: Vec<Uuid> = tokens
+            .values()
+            .filter(|t| t.expires_at <= now)
+            .map(|t| t.id)
+            .collect();
+        ids.iter().filter_map(|id| tokens.remove(id)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(capability: &str, resource: Option<&str>) -> TokenScope {
+        TokenScope {
+            capability: capability.into(),
+            resource: resource.map(Into::into),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_scopes() {
+        let manager = TokenManager::new(3600);
+        let token = manager
+            .issue(
+                "persona:lilith",
+                "user",
+                vec![
+                    scope("filesystem:read", Some("/home/user/notes/*")),
+                    scope("network:connect", None),
+                ],
+                7200,
+            )
+            .await;
+        let now = Utc::now();
+
+        assert_eq!(token.expires_at - token.issued_at, Duration::seconds(3600));
+        assert!(manager.check(token.id, "filesystem:read", Some("/home/user/notes/a.md"), now).await.is_ok());
+        assert!(manager.check(token.id, "network:connect", Some("example.com:443"), now).await.is_ok());
+        assert_eq!(
+            manager.check(token.id, "filesystem:read", Some("/etc/shadow"), now).await.unwrap_err(),
+            TokenDenial::OutOfScope
+        );
+        assert_eq!(
+            manager.check(token.id, "filesystem:write", Some("/home/user/notes/a.md"), now).await.unwrap_err(),
+            TokenDenial::OutOfScope
+        );
+        assert_eq!(
+            manager.check(token.id, "network:connect", None, now + Duration::seconds(3601)).await.unwrap_err(),
+            TokenDenial::Expired
+        );
+
+        assert!(manager.revoke(token.id).await.is_some());
+        assert_eq!(
+            manager.check(token.id, "network:connect", None, now).await.unwrap_err(),
+            TokenDenial::Unknown
+        );
+    }
+}
@@ -0,0 +1,380 @@
+//! Access control for shared secrets
+//!
+//! The keyring owner (the daemon's user, and root) has full access. Anyone
+//! else only reaches what has been shared with them: a grant names a
+//! collection or a single item, a principal (a user by uid, or a service by
+//! the uid it runs as and its executable path), the rights given and
+//! optionally when they lapse.
+//! Callers are identified from the peer credentials of the IPC socket.
+//! Secret accesses and refusals are recorded in an access log and reported
+//! on the system audit bus.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use libnyx_ipc::audit::{self as bus, AuditOutcome};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::storage::SecureFile;
+
+/// Access log entries kept in memory
+const LOG_ENTRIES: usize = 1000;
+
+/// Who is calling, from the socket's peer credentials
+#[derive(Debug, Clone)]
+pub struct Peer {
+    pub uid: u32,
+    pub pid: Option<u32>,
+    pub exe: Option<String>,
+}
+
+impl Peer {
+    pub fn new(uid: u32, pid: Option<u32>) -> Self {
+        let exe = pid
+            .and_then(|pid| std::fs::read_link(format!("/proc/{}/exe", pid)).ok())
+            .map(|path| path.to_string_lossy().into_owned());
+        Self { uid, pid, exe }
+    }
+}
+
+/// Who a secret is shared with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Principal {
+    User { uid: u32 },
+    /// A service identified by the user it runs as and its executable, so
+    /// other users cannot claim the grant by running the same binary
+    Service { uid: u32, exe: String },
+}
+
+impl Principal {
+    pub fn matches(&self, peer: &Peer) -> bool {
+        match self {
+            Self::User { uid } => peer.uid == *uid,
+            Self::Service { uid, exe } => peer.uid == *uid && peer.exe.as_deref() == Some(exe.as_str()),
+        }
+    }
+}
+
+/// What a grant allows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rights {
+    #[default]
+    ReadOnly,
+    ReadWrite,
+}
+
+/// What a request needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+impl Rights {
+    fn allows(self, access: Access) -> bool {
+        matches!((self, access), (Self::ReadWrite, _) | (Self::ReadOnly, Access::Read))
+    }
+}
+
+/// A secret, or a whole collection, shared with a principal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Grant {
+    pub id: String,
+    pub collection: String,
+    /// None shares the whole collection
+    pub item: Option<String>,
+    pub principal: Principal,
+    pub rights: Rights,
+    pub expires: Option<DateTime<Utc>>,
+    pub granted_by: u32,
+    pub created: DateTime<Utc>,
+}
+
+impl Grant {
+    fn covers(&self, collection: &str, item: Option<&str>) -> bool {
+        self.collection == collection
+            && match (&self.item, item) {
+                (None, _) => true,
+                (Some(granted), Some(item)) => granted == item,
+                (Some(_), None) => false,
+            }
+    }
+
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+}
+
+/// One access decision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessEntry {
+    pub time: DateTime<Utc>,
+    pub uid: u32,
+    pub pid: Option<u32>,
+    pub exe: Option<String>,
+    pub operation: String,
+    pub collection: Option<String>,
+    pub item: Option<String>,
+    pub allowed: bool,
+}
+
+/// Grants and the access log of a keyring
+pub struct AccessControl {
+    data_dir: PathBuf,
+    owner_uid: u32,
+    grants: Vec<Grant>,
+    log: VecDeque<AccessEntry>,
+}
+
+impl AccessControl {
+    /// Load grants; `owner_uid` always has full access
+    pub fn load(data_dir: &Path, owner_uid: u32) -> Result<Self> {
+        let path = data_dir.join("grants.json");
+        let grants = if path.exists() {
+            serde_json::from_slice(&SecureFile::read(&path)?)?
+        } else {
+            Vec::new()
+        };
+
+        let mut acl = Self {
+            data_dir: data_dir.to_path_buf(),
+            owner_uid,
+            grants,
+            log: VecDeque::new(),
+        };
+        acl.prune();
+        Ok(acl)
+    }
+
+    pub fn is_owner(&self, peer: &Peer) -> bool {
+        peer.uid == 0 || peer.uid == self.owner_uid
+    }
+
+    /// Whether `peer` may access an item (or, with no item, a collection)
+    pub fn check(&self, peer: &Peer, collection: &str, item: Option<&str>, access: Access) -> bool {
+        if self.is_owner(peer) {
+            return true;
+        }
+
+        let now = Utc::now();
+        self.grants.iter().any(|grant| {
+            !grant.is_expired(now)
+                && grant.covers(collection, item)
+                && grant.principal.matches(peer)
+                && grant.rights.allows(access)
+        })
+    }
+
+    /// Share a collection or item
+    pub fn share(
+        &mut self,
+        granted_by: u32,
+        collection: &str,
+        item: Option<&str>,
+        principal: Principal,
+        rights: Rights,
+        expires: Option<DateTime<Utc>>,
+    ) -> Result<Grant> {
+        if let Principal::Service { ref exe, .. } = principal {
+            if !Path::new(exe).is_absolute() {
+                return Err(anyhow!("Service executable must be an absolute path: {}", exe));
+            }
+        }
+
+        let mut id = [0u8; 8];
+        rand::thread_rng().fill_bytes(&mut id);
+        let grant = Grant {
+            id: id.iter().map(|b| format!("{:02x}", b)).collect(),
+            collection: collection.to_string(),
+            item: item.map(str::to_string),
+            principal,
+            rights,
+            expires,
+            granted_by,
+            created: Utc::now(),
+        };
+
+        self.grants.push(grant.clone());
+        self.save()?;
+        Ok(grant)
+    }
+
+    /// Withdraw a grant
+    pub fn revoke(&mut self, id: &str) -> Result<Grant> {
+        let index = self.grants.iter().position(|g| g.id == id)
+            .ok_or_else(|| anyhow!("Grant not found: {}", id))?;
+        let grant = self.grants.remove(index);
+        self.save()?;
+        Ok(grant)
+    }
+
+    /// Grants still in force, optionally for one collection
+    pub fn grants(&mut self, collection: Option<&str>) -> Vec<Grant> {
+        self.prune();
+        self.grants
+            .iter()
+            .filter(|g| collection.is_none_or(|c| g.collection == c))
+            .cloned()
+            .collect()
+    }
+
+    /// Drop grants on a collection or item that no longer exists
+    pub fn forget(&mut self, collection: &str, item: Option<&str>) -> Result<()> {
+        let before = self.grants.len();
+        self.grants.retain(|g| {
+            g.collection != collection || item.is_some_and(|item| g.item.as_deref() != Some(item))
+        });
+        if self.grants.len() != before {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Record an access decision
+    pub fn record(&mut self, peer: &Peer, operation: &str, collection: Option<&str>, item: Option<&str>, allowed: bool) {
+        let entry = AccessEntry {
+            time: Utc::now(),
+            uid: peer.uid,
+            pid: peer.pid,
+            exe: peer.exe.clone(),
+            operation: operation.to_string(),
+            collection: collection.map(str::to_string),
+            item: item.map(str::to_string),
+            allowed,
+        };
+
+        if let Err(e) = self.append(&entry) {
+            tracing::warn!("Failed to write access log: {}", e);
+        }
+
+        let object = match (collection, item) {
+            (Some(collection), Some(item)) => format!("{}/{}", collection, item),
+            (Some(collection), None) => collection.to_string(),
+            (None, _) => "*".to_string(),
+        };
+        let outcome = if allowed { AuditOutcome::Success } else { AuditOutcome::Denied };
+        let mut event = bus::AuditEvent::new("cipher", bus::AuditEvent::uid(peer.uid), &format!("secret.{}", operation), object, outcome);
+        if let Some(exe) = &peer.exe {
+            event = event.with_detail("exe", exe);
+        }
+        bus::record(event);
+
+        self.log.push_back(entry);
+        while self.log.len() > LOG_ENTRIES {
+            self.log.pop_front();
+        }
+    }
+
+    /// Most recent access log entries, newest first
+    pub fn log(&self, limit: usize) -> Vec<AccessEntry> {
+        self.log.iter().rev().take(limit).cloned().collect()
+    }
+
+    fn prune(&mut self) {
+        let now = Utc::now();
+        let before = self.grants.len();
+        self.grants.retain(|g| !g.is_expired(now));
+        if self.grants.len() != before {
+            if let Err(e) = self.save() {
+                tracing::warn!("Failed to save grants: {}", e);
+            }
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        SecureFile::write(&self.data_dir.join("grants.json"), &serde_json::to_vec_pretty(&self.grants)?)
+    }
+
+    fn append(&self, entry: &AccessEntry) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(self.data_dir.join("access.log"))?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(uid: u32, exe: Option<&str>) -> Peer {
+        Peer { uid, pid: None, exe: exe.map(str::to_string) }
+    }
+
+    #[test]
+    fn test_owner_and_root_have_full_access() {
+        let dir = tempfile::tempdir().unwrap();
+        let acl = AccessControl::load(dir.path(), 1000).unwrap();
+
+        assert!(acl.check(&peer(1000, None), "default", Some("wifi"), Access::Write));
+        assert!(acl.check(&peer(0, None), "default", Some("wifi"), Access::Write));
+        assert!(!acl.check(&peer(1001, None), "default", Some("wifi"), Access::Read));
+    }
+
+    #[test]
+    fn test_read_only_item_grant() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut acl = AccessControl::load(dir.path(), 1000).unwrap();
+        acl.share(1000, "default", Some("wifi"), Principal::User { uid: 1001 }, Rights::ReadOnly, None)
+            .unwrap();
+
+        let guest = peer(1001, None);
+        assert!(acl.check(&guest, "default", Some("wifi"), Access::Read));
+        assert!(!acl.check(&guest, "default", Some("wifi"), Access::Write));
+        assert!(!acl.check(&guest, "default", Some("vpn"), Access::Read));
+        assert!(!acl.check(&guest, "default", None, Access::Read));
+    }
+
+    #[test]
+    fn test_service_collection_grant() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut acl = AccessControl::load(dir.path(), 1000).unwrap();
+        let principal = Principal::Service { uid: 998, exe: "/usr/bin/backup".to_string() };
+        acl.share(1000, "backup", None, principal, Rights::ReadWrite, None).unwrap();
+
+        assert!(acl.check(&peer(998, Some("/usr/bin/backup")), "backup", Some("s3"), Access::Write));
+        assert!(!acl.check(&peer(998, Some("/usr/bin/other")), "backup", Some("s3"), Access::Read));
+        // Same binary run by another user
+        assert!(!acl.check(&peer(1001, Some("/usr/bin/backup")), "backup", Some("s3"), Access::Read));
+        let relative = Principal::Service { uid: 998, exe: "backup".into() };
+        assert!(acl.share(1000, "backup", None, relative, Rights::ReadOnly, None).is_err());
+    }
+
+    #[test]
+    fn test_expired_grants_lapse() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut acl = AccessControl::load(dir.path(), 1000).unwrap();
+        let past = Utc::now() - chrono::Duration::seconds(1);
+        acl.share(1000, "default", None, Principal::User { uid: 1001 }, Rights::ReadOnly, Some(past))
+            .unwrap();
+
+        assert!(!acl.check(&peer(1001, None), "default", Some("wifi"), Access::Read));
+        assert!(acl.grants(None).is_empty());
+    }
+
+    #[test]
+    fn test_grants_persist_and_revoke() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut acl = AccessControl::load(dir.path(), 1000).unwrap();
+        let grant = acl
+            .share(1000, "default", Some("wifi"), Principal::User { uid: 1001 }, Rights::ReadOnly, None)
+            .unwrap();
+
+        let mut reloaded = AccessControl::load(dir.path(), 1000).unwrap();
+        assert_eq!(reloaded.grants(Some("default")).len(), 1);
+
+        reloaded.revoke(&grant.id).unwrap();
+        assert!(!reloaded.check(&peer(1001, None), "default", Some("wifi"), Access::Read));
+        assert!(reloaded.revoke(&grant.id).is_err());
+    }
+}
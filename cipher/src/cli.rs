@@ -7,6 +7,8 @@ use std::io::{self, Write};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixStream;
 
+use nyx_cipher::acl::{Principal, Rights};
+use nyx_cipher::crypto::{CipherAlgorithm, KdfParams};
 use nyx_cipher::ipc::{IpcRequest, IpcResponse};
 
 #[derive(Parser)]
@@ -30,7 +32,11 @@ enum Commands {
     Init,
 
     /// Unlock keyring
-    Unlock,
+    Unlock {
+        /// Try an enrolled TPM2 seal or FIDO2 token before the passphrase
+        #[arg(long)]
+        hardware: bool,
+    },
 
     /// Lock keyring
     Lock,
@@ -103,6 +109,113 @@ enum Commands {
         #[arg(long, short)]
         attr: Vec<String>,
     },
+
+    /// Enroll hardware to unlock the keyring
+    Enroll {
+        #[command(subcommand)]
+        method: EnrollMethod,
+    },
+
+    /// Remove a hardware enrollment
+    Unenroll {
+        /// tpm2 or fido2
+        method: String,
+
+        /// Token name (fido2)
+        name: Option<String>,
+    },
+
+    /// List hardware enrollments
+    Enrollments,
+
+    /// Re-seal to the current PCRs and re-wrap enrolled tokens
+    Rotate,
+
+    /// Share a collection or secret with another user or a service
+    Share {
+        /// Collection
+        collection: String,
+
+        /// Item ID (default: the whole collection)
+        id: Option<String>,
+
+        /// User to share with, or the user the service runs as
+        #[arg(long)]
+        uid: u32,
+
+        /// Share only with this executable running as --uid
+        #[arg(long)]
+        service: Option<String>,
+
+        /// Allow storing and deleting, not only reading
+        #[arg(long)]
+        write: bool,
+
+        /// Lapse after this many seconds
+        #[arg(long)]
+        expires_in: Option<u64>,
+    },
+
+    /// Withdraw a grant
+    Revoke {
+        /// Grant ID
+        grant: String,
+    },
+
+    /// List grants
+    Grants {
+        /// Only grants on this collection
+        collection: Option<String>,
+    },
+
+    /// Show recent secret accesses
+    AccessLog {
+        /// Number of entries
+        #[arg(long, short = 'n', default_value = "50")]
+        limit: usize,
+    },
+
+    /// Strengthen key derivation and cipher, re-encrypting existing secrets
+    Upgrade {
+        /// Argon2id memory in KiB
+        #[arg(long, default_value_t = KdfParams::recommended().memory_kib)]
+        memory_kib: u32,
+
+        /// Argon2id iterations
+        #[arg(long, default_value_t = KdfParams::recommended().iterations)]
+        iterations: u32,
+
+        /// Argon2id lanes
+        #[arg(long, default_value_t = KdfParams::recommended().parallelism)]
+        parallelism: u32,
+
+        /// Use ChaCha20-Poly1305 instead of XChaCha20-Poly1305
+        #[arg(long)]
+        chacha20: bool,
+    },
+
+    /// Show protection parameters and re-encryption progress
+    Migration,
+}
+
+#[derive(Subcommand)]
+enum EnrollMethod {
+    /// Seal the master key to the TPM2
+    Tpm2 {
+        /// PCRs to bind to
+        #[arg(long, value_delimiter = ',', default_value = "7")]
+        pcrs: Vec<u8>,
+    },
+
+    /// Wrap the master key with a FIDO2 token (touch required)
+    Fido2 {
+        /// Name for the token
+        name: String,
+
+        /// Device path (default: first token found)
+        #[arg(long)]
+        device: Option<String>,
+    },
 }
 
 #[tokio::main]
@@ -124,7 +237,18 @@ async fn main() -> Result<()> {
             IpcRequest::Initialize { password }
         }
 
-        Commands::Unlock => {
+        Commands::Unlock { hardware } => {
+            if hardware {
+                println!("Trying hardware unlock (touch your token if it blinks)...");
+                match send_request(&cli.socket, IpcRequest::UnlockHardware { tpm_only: false }).await? {
+                    IpcResponse::Error { message } => eprintln!("{}", message),
+                    response => {
+                        print_response(&response);
+                        return Ok(());
+                    }
+                }
+            }
+
             let password = read_password("Enter password: ")?;
             IpcRequest::Unlock { password }
         }
@@ -184,6 +308,52 @@ async fn main() -> Result<()> {
 
             IpcRequest::Search { collection, attributes }
         }
+
+        Commands::Enroll { method: EnrollMethod::Tpm2 { pcrs } } => IpcRequest::EnrollTpm2 { pcrs },
+
+        Commands::Enroll { method: EnrollMethod::Fido2 { name, device } } => {
+            println!("Touch your token when it blinks (twice)...");
+            IpcRequest::EnrollFido2 { name, device }
+        }
+
+        Commands::Unenroll { method, name } => IpcRequest::RemoveEnrollment { method, name },
+
+        Commands::Enrollments => IpcRequest::ListEnrollments,
+
+        Commands::Rotate => IpcRequest::RotateEnrollments,
+
+        Commands::Share { collection, id, uid, service, write, expires_in } => {
+            let principal = match service {
+                Some(exe) => Principal::Service { uid, exe },
+                None => Principal::User { uid },
+            };
+            let rights = if write { Rights::ReadWrite } else { Rights::ReadOnly };
+
+            IpcRequest::Share { collection, item: id, principal, rights, expires_in }
+        }
+
+        Commands::Revoke { grant } => IpcRequest::Revoke { grant },
+
+        Commands::Grants { collection } => IpcRequest::ListGrants { collection },
+
+        Commands::AccessLog { limit } => IpcRequest::AccessLog { limit },
+
+        Commands::Upgrade { memory_kib, iterations, parallelism, chacha20 } => {
+            let password = read_password("Enter password: ")?;
+            let cipher = if chacha20 {
+                CipherAlgorithm::ChaCha20Poly1305
+            } else {
+                CipherAlgorithm::XChaCha20Poly1305
+            };
+
+            IpcRequest::UpgradeProtection {
+                password,
+                kdf: Some(KdfParams { memory_kib, iterations, parallelism }),
+                cipher: Some(cipher),
+            }
+        }
+
+        Commands::Migration => IpcRequest::MigrationStatus,
     };
 
     let response = send_request(&cli.socket, request).await?;
@@ -277,6 +447,70 @@ fn print_response(response: &IpcResponse) {
             }
         }
 
+        IpcResponse::Enrollments(enrollments) => {
+            if enrollments.is_empty() {
+                println!("No hardware enrolled");
+            } else {
+                println!("{:<8} {:<20} {:<16} {:<30}", "METHOD", "NAME", "DETAIL", "ENROLLED");
+                for e in enrollments {
+                    println!("{:<8} {:<20} {:<16} {:<30}", e.method, e.name, e.detail, e.enrolled);
+                }
+            }
+        }
+
+        IpcResponse::Grants(grants) => {
+            if grants.is_empty() {
+                println!("Nothing shared");
+            } else {
+                println!("{:<18} {:<24} {:<28} {:<10} {:<26}", "ID", "SHARED", "WITH", "RIGHTS", "EXPIRES");
+                for g in grants {
+                    let shared = match g.item {
+                        Some(ref item) => format!("{}/{}", g.collection, item),
+                        None => format!("{}/*", g.collection),
+                    };
+                    let with = match g.principal {
+                        Principal::User { uid } => format!("uid {}", uid),
+                        Principal::Service { uid, ref exe } => format!("{} (uid {})", exe, uid),
+                    };
+                    let rights = match g.rights {
+                        Rights::ReadOnly => "read",
+                        Rights::ReadWrite => "read-write",
+                    };
+                    let expires = g.expires.map(|e| e.to_rfc3339()).unwrap_or_else(|| "never".to_string());
+                    println!("{:<18} {:<24} {:<28} {:<10} {:<26}", g.id, shared, with, rights, expires);
+                }
+            }
+        }
+
+        IpcResponse::AccessLog(entries) => {
+            println!("{:<26} {:<8} {:<8} {:<8} {:<24} {:<6}", "TIME", "UID", "PID", "OP", "SECRET", "RESULT");
+            for e in entries {
+                let pid = e.pid.map(|p| p.to_string()).unwrap_or_else(|| "-".to_string());
+                let secret = match (&e.collection, &e.item) {
+                    (Some(c), Some(i)) => format!("{}/{}", c, i),
+                    (Some(c), None) => format!("{}/*", c),
+                    _ => "-".to_string(),
+                };
+                let result = if e.allowed { "ok" } else { "denied" };
+                println!("{:<26} {:<8} {:<8} {:<8} {:<24} {:<6}",
+                    e.time.to_rfc3339(), e.uid, pid, e.operation, secret, result);
+            }
+        }
+
+        IpcResponse::Migration(status) => {
+            println!("Protection:");
+            println!("  KDF:      {}", status.target.kdf);
+            println!("  Cipher:   {}", status.target.cipher);
+            println!("  Pending:  {}", status.pending);
+            println!("  Migrated: {}", status.migrated);
+            if status.needs_passphrase {
+                println!("  Unlock with the passphrase to finish migrating");
+            }
+            if let Some(ref error) = status.last_error {
+                println!("  Error:    {}", error);
+            }
+        }
+
         IpcResponse::Status { initialized, locked, collections, sessions } => {
             println!("Keyring Status:");
             println!("  Initialized: {}", if *initialized { "yes" } else { "no" });
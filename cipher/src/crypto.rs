@@ -1,12 +1,13 @@
 //! Cryptographic operations
 
-use argon2::{Argon2, PasswordHasher, PasswordHash, PasswordVerifier};
+use argon2::{Algorithm, Argon2, Params, PasswordHasher, PasswordHash, PasswordVerifier, Version};
 use argon2::password_hash::SaltString;
 use chacha20poly1305::{
-    aead::{Aead, KeyInit},
-    ChaCha20Poly1305, Nonce,
+    aead::{generic_array::{typenum::Unsigned, GenericArray}, Aead, AeadCore, KeyInit},
+    ChaCha20Poly1305, XChaCha20Poly1305,
 };
 use rand::RngCore;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
@@ -40,6 +41,87 @@ pub enum CryptoError {
 
 type Result<T> = std::result::Result<T, CryptoError>;
 
+/// AEAD a secret is encrypted with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CipherAlgorithm {
+    /// 96-bit random nonces; what keyrings were first written with
+    #[default]
+    ChaCha20Poly1305,
+    /// 192-bit random nonces, safe for any number of encryptions
+    XChaCha20Poly1305,
+}
+
+impl CipherAlgorithm {
+    /// Algorithms from weakest to strongest
+    fn rank(self) -> u8 {
+        match self {
+            Self::ChaCha20Poly1305 => 0,
+            Self::XChaCha20Poly1305 => 1,
+        }
+    }
+
+    pub fn is_weaker_than(self, other: Self) -> bool {
+        self.rank() < other.rank()
+    }
+}
+
+impl std::fmt::Display for CipherAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ChaCha20Poly1305 => write!(f, "chacha20-poly1305"),
+            Self::XChaCha20Poly1305 => write!(f, "xchacha20-poly1305"),
+        }
+    }
+}
+
+/// Argon2id cost parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl KdfParams {
+    /// What new keyrings get and upgrades move to
+    pub fn recommended() -> Self {
+        Self {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 4,
+        }
+    }
+
+    /// Whether either cost is lower than `other`'s
+    pub fn is_weaker_than(&self, other: &Self) -> bool {
+        self.memory_kib < other.memory_kib || self.iterations < other.iterations
+    }
+
+    fn argon2(&self) -> Result<Argon2<'static>> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|e| CryptoError::KeyDerivation(e.to_string()))?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+impl Default for KdfParams {
+    /// The argon2 crate defaults keyrings were first written with
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl std::fmt::Display for KdfParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "argon2id m={}KiB t={} p={}", self.memory_kib, self.iterations, self.parallelism)
+    }
+}
+
 /// Encryption key (zeroized on drop)
 #[derive(Clone, ZeroizeOnDrop)]
 pub struct EncryptionKey {
@@ -55,9 +137,24 @@ impl EncryptionKey {
         Self { key }
     }
 
+    /// Wrap existing key material
+    pub fn from_bytes(key: [u8; 32]) -> Self {
+        Self { key }
+    }
+
+    /// Raw key material, for sealing to hardware
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.key
+    }
+
     /// Derive key from password
     pub fn derive_from_password(password: &str, salt: &[u8]) -> Result<Self> {
-        let argon2 = Argon2::default();
+        Self::derive_with_params(password, salt, &KdfParams::default())
+    }
+
+    /// Derive key from password with explicit Argon2id costs
+    pub fn derive_with_params(password: &str, salt: &[u8], params: &KdfParams) -> Result<Self> {
+        let argon2 = params.argon2()?;
 
         let mut key = [0u8; 32];
         argon2.hash_password_into(
@@ -71,40 +168,62 @@ impl EncryptionKey {
 
     /// Encrypt data
     pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
-        let cipher = ChaCha20Poly1305::new_from_slice(&self.key)
-            .map_err(|_| CryptoError::InvalidKeyLength { expected: 32, actual: self.key.len() })?;
-
-        let mut nonce_bytes = [0u8; 12];
-        rand::thread_rng().fill_bytes(&mut nonce_bytes);
-        let nonce = Nonce::from_slice(&nonce_bytes);
-
-        let ciphertext = cipher.encrypt(nonce, plaintext)
-            .map_err(|e| CryptoError::Encryption(e.to_string()))?;
-
-        // Prepend nonce to ciphertext
-        let mut result = nonce_bytes.to_vec();
-        result.extend(ciphertext);
-
-        Ok(result)
+        self.encrypt_with(CipherAlgorithm::ChaCha20Poly1305, plaintext)
     }
 
     /// Decrypt data
     pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
-        if ciphertext.len() < 12 {
-            return Err(CryptoError::CiphertextTooShort { min_bytes: 12 });
+        self.decrypt_with(CipherAlgorithm::ChaCha20Poly1305, ciphertext)
+    }
+
+    /// Encrypt data with the given algorithm
+    pub fn encrypt_with(&self, algorithm: CipherAlgorithm, plaintext: &[u8]) -> Result<Vec<u8>> {
+        match algorithm {
+            CipherAlgorithm::ChaCha20Poly1305 => seal::<ChaCha20Poly1305>(&self.key, plaintext),
+            CipherAlgorithm::XChaCha20Poly1305 => seal::<XChaCha20Poly1305>(&self.key, plaintext),
+        }
+    }
+
+    /// Decrypt data written with the given algorithm
+    pub fn decrypt_with(&self, algorithm: CipherAlgorithm, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        match algorithm {
+            CipherAlgorithm::ChaCha20Poly1305 => open::<ChaCha20Poly1305>(&self.key, ciphertext),
+            CipherAlgorithm::XChaCha20Poly1305 => open::<XChaCha20Poly1305>(&self.key, ciphertext),
         }
+    }
+}
+
+/// Encrypt under a random nonce, which is prepended to the ciphertext
+fn seal<C: Aead + KeyInit>(key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let cipher = C::new_from_slice(key)
+        .map_err(|_| CryptoError::InvalidKeyLength { expected: 32, actual: key.len() })?;
+
+    let mut nonce_bytes = vec![0u8; <C as AeadCore>::NonceSize::USIZE];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let nonce = GenericArray::from_slice(&nonce_bytes);
 
-        let cipher = ChaCha20Poly1305::new_from_slice(&self.key)
-            .map_err(|_| CryptoError::InvalidKeyLength { expected: 32, actual: self.key.len() })?;
+    let ciphertext = cipher.encrypt(nonce, plaintext)
+        .map_err(|e| CryptoError::Encryption(e.to_string()))?;
 
-        let nonce = Nonce::from_slice(&ciphertext[..12]);
-        let data = &ciphertext[12..];
+    // Prepend nonce to ciphertext
+    let mut result = nonce_bytes;
+    result.extend(ciphertext);
 
-        let plaintext = cipher.decrypt(nonce, data)
-            .map_err(|_| CryptoError::Decryption { reason: "authentication failed or corrupted data" })?;
+    Ok(result)
+}
 
-        Ok(plaintext)
+fn open<C: Aead + KeyInit>(key: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    let nonce_len = <C as AeadCore>::NonceSize::USIZE;
+    if ciphertext.len() < nonce_len {
+        return Err(CryptoError::CiphertextTooShort { min_bytes: nonce_len });
     }
+
+    let cipher = C::new_from_slice(key)
+        .map_err(|_| CryptoError::InvalidKeyLength { expected: 32, actual: key.len() })?;
+
+    let (nonce, data) = ciphertext.split_at(nonce_len);
+    cipher.decrypt(GenericArray::from_slice(nonce), data)
+        .map_err(|_| CryptoError::Decryption { reason: "authentication failed or corrupted data" })
 }
 
 /// Generate a random salt
@@ -237,6 +356,43 @@ mod tests {
         assert_eq!(key1.key, key2.key);
     }
 
+    #[test]
+    fn test_xchacha_roundtrip() {
+        let key = EncryptionKey::generate();
+        let ciphertext = key.encrypt_with(CipherAlgorithm::XChaCha20Poly1305, b"Hello, Nyx!").unwrap();
+
+        // 24-byte nonce + 11 bytes + 16-byte tag
+        assert_eq!(ciphertext.len(), 24 + 11 + 16);
+        let decrypted = key.decrypt_with(CipherAlgorithm::XChaCha20Poly1305, &ciphertext).unwrap();
+        assert_eq!(decrypted, b"Hello, Nyx!");
+
+        // Read as the other algorithm, it does not authenticate
+        assert!(key.decrypt_with(CipherAlgorithm::ChaCha20Poly1305, &ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_default_kdf_params_match_legacy_derivation() {
+        let salt = generate_salt();
+        let legacy = {
+            let mut key = [0u8; 32];
+            Argon2::default().hash_password_into(b"password", &salt, &mut key).unwrap();
+            key
+        };
+
+        let derived = EncryptionKey::derive_with_params("password", &salt, &KdfParams::default()).unwrap();
+        assert_eq!(derived.key, legacy);
+    }
+
+    #[test]
+    fn test_kdf_params_strength() {
+        let legacy = KdfParams::default();
+        let recommended = KdfParams::recommended();
+
+        assert!(legacy.is_weaker_than(&recommended));
+        assert!(!recommended.is_weaker_than(&legacy));
+        assert!(CipherAlgorithm::ChaCha20Poly1305.is_weaker_than(CipherAlgorithm::XChaCha20Poly1305));
+    }
+
     #[test]
     fn test_secret_redacts_debug() {
         let secret = Secret::from_str("super_secret");
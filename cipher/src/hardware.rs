@@ -0,0 +1,367 @@
+//! Hardware-backed unlock
+//!
+//! The master key can additionally be sealed to the TPM2 under a PCR
+//! policy, so it is only released while the measured boot state matches,
+//! or wrapped with the hmac-secret of a FIDO2 token, so unlocking takes a
+//! touch. Both release the same key the passphrase derives; the passphrase
+//! keeps working and is the fallback whenever hardware unlock fails.
+//!
+//! The TPM is driven through tpm2-tools and FIDO2 tokens through libfido2's
+//! `fido2-token`, `fido2-cred` and `fido2-assert`.
+
+use anyhow::{anyhow, Context, Result};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::{info, warn};
+use zeroize::Zeroizing;
+
+use crate::crypto::EncryptionKey;
+use crate::storage::SecureFile;
+
+/// Relying party FIDO2 credentials are made for
+const RELYING_PARTY: &str = "io.nyx.cipher";
+
+/// PCRs sealed to when none are given: the Secure Boot policy
+pub const DEFAULT_PCRS: &[u8] = &[7];
+
+/// Master key sealed to the TPM
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tpm2Seal {
+    pub pcrs: Vec<u8>,
+    /// `TPM2B_PUBLIC` and `TPM2B_PRIVATE` of the sealed object, base64
+    public: String,
+    private: String,
+    pub enrolled: chrono::DateTime<chrono::Utc>,
+}
+
+/// Master key wrapped with a FIDO2 token's hmac-secret
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fido2Token {
+    pub name: String,
+    credential_id: String,
+    salt: String,
+    wrapped_key: String,
+    pub enrolled: chrono::DateTime<chrono::Utc>,
+}
+
+/// Hardware enrollments of a keyring
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Enrollments {
+    pub tpm2: Option<Tpm2Seal>,
+    #[serde(default)]
+    pub fido2: Vec<Fido2Token>,
+}
+
+impl Enrollments {
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        let path = Self::path(data_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_slice(&SecureFile::read(&path)?)?)
+    }
+
+    pub fn save(&self, data_dir: &Path) -> Result<()> {
+        SecureFile::write(&Self::path(data_dir), &serde_json::to_vec_pretty(self)?)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tpm2.is_none() && self.fido2.is_empty()
+    }
+
+    fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join("hardware.json")
+    }
+
+    /// Recover the master key from whichever enrollment works, TPM first
+    /// since it needs no interaction. `tpm_only` skips FIDO2 tokens.
+    pub fn unlock(&self, tpm_only: bool) -> Result<EncryptionKey> {
+        let mut failures = Vec::new();
+
+        if let Some(ref seal) = self.tpm2 {
+            match unseal_tpm2(seal) {
+                Ok(key) => return Ok(key),
+                Err(e) => failures.push(format!("tpm2: {}", e)),
+            }
+        }
+
+        if !tpm_only {
+            for token in &self.fido2 {
+                match unwrap_fido2(token) {
+                    Ok(key) => return Ok(key),
+                    Err(e) => failures.push(format!("fido2 {}: {}", token.name, e)),
+                }
+            }
+        }
+
+        if failures.is_empty() {
+            return Err(anyhow!("No hardware unlock enrolled"));
+        }
+        Err(anyhow!("{}", failures.join("; ")))
+    }
+}
+
+/// Seal `key` to the TPM under a policy on the current values of `pcrs`
+pub fn seal_tpm2(key: &EncryptionKey, pcrs: &[u8]) -> Result<Tpm2Seal> {
+    let pcr_list = pcr_list(pcrs)?;
+    let dir = tempfile::tempdir()?;
+    let file = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+
+    create_primary(&file("primary.ctx"))?;
+    tpm2(&["tpm2_createpolicy", "-Q", "--policy-pcr", "-l", &pcr_list, "-L", &file("policy.dat")], None)?;
+    tpm2(
+        &[
+            "tpm2_create", "-Q",
+            "-C", &file("primary.ctx"),
+            "-L", &file("policy.dat"),
+            "-i", "-",
+            "-u", &file("seal.pub"),
+            "-r", &file("seal.priv"),
+        ],
+        Some(key.as_bytes()),
+    )?;
+
+    let seal = Tpm2Seal {
+        pcrs: pcrs.to_vec(),
+        public: BASE64.encode(std::fs::read(file("seal.pub"))?),
+        private: BASE64.encode(std::fs::read(file("seal.priv"))?),
+        enrolled: chrono::Utc::now(),
+    };
+    info!("Master key sealed to TPM2 PCRs {}", pcr_list);
+    Ok(seal)
+}
+
+/// Unseal the master key; fails if the PCRs moved since sealing
+pub fn unseal_tpm2(seal: &Tpm2Seal) -> Result<EncryptionKey> {
+    let pcr_list = pcr_list(&seal.pcrs)?;
+    let dir = tempfile::tempdir()?;
+    let file = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+
+    std::fs::write(file("seal.pub"), BASE64.decode(&seal.public)?)?;
+    std::fs::write(file("seal.priv"), BASE64.decode(&seal.private)?)?;
+
+    create_primary(&file("primary.ctx"))?;
+    tpm2(
+        &[
+            "tpm2_load", "-Q",
+            "-C", &file("primary.ctx"),
+            "-u", &file("seal.pub"),
+            "-r", &file("seal.priv"),
+            "-c", &file("seal.ctx"),
+        ],
+        None,
+    )?;
+    let policy = format!("pcr:{}", pcr_list);
+    let key = Zeroizing::new(tpm2(&["tpm2_unseal", "-c", &file("seal.ctx"), "-p", &policy], None)?);
+
+    key_from_slice(&key)
+}
+
+/// Make a credential on a FIDO2 token and wrap `key` with its hmac-secret.
+/// Takes a touch on the token, twice.
+pub fn enroll_fido2(key: &EncryptionKey, name: &str, device: Option<&str>) -> Result<Fido2Token> {
+    let device = match device {
+        Some(device) => device.to_string(),
+        None => fido2_devices()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No FIDO2 token found"))?,
+    };
+
+    let mut user_id = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut user_id);
+    let input = format!(
+        "{}\n{}\n{}\n{}\n",
+        BASE64.encode(random_32()),
+        RELYING_PARTY,
+        name,
+        BASE64.encode(user_id),
+    );
+    let output = fido2(&["fido2-cred", "-M", "-h", &device], &input)?;
+    // client data hash, rp id, format, authdata, credential id, ...
+    let credential_id = output
+        .lines()
+        .nth(4)
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("fido2-cred returned no credential"))?;
+
+    let salt = BASE64.encode(random_32());
+    let secret = hmac_secret(&device, &credential_id, &salt)?;
+    let wrapped = EncryptionKey::from_bytes(*secret).encrypt(key.as_bytes())?;
+
+    info!("Master key wrapped with FIDO2 token {} on {}", name, device);
+    Ok(Fido2Token {
+        name: name.to_string(),
+        credential_id,
+        salt,
+        wrapped_key: BASE64.encode(wrapped),
+        enrolled: chrono::Utc::now(),
+    })
+}
+
+/// Re-wrap an enrolled token's key under a fresh salt
+pub fn rewrap_fido2(key: &EncryptionKey, token: &Fido2Token) -> Result<Fido2Token> {
+    let salt = BASE64.encode(random_32());
+    let secret = with_token(token, |device| hmac_secret(device, &token.credential_id, &salt))?;
+    let wrapped = EncryptionKey::from_bytes(*secret).encrypt(key.as_bytes())?;
+
+    Ok(Fido2Token {
+        salt,
+        wrapped_key: BASE64.encode(wrapped),
+        enrolled: chrono::Utc::now(),
+        ..token.clone()
+    })
+}
+
+/// Unwrap the master key with a token; takes a touch
+pub fn unwrap_fido2(token: &Fido2Token) -> Result<EncryptionKey> {
+    let secret = with_token(token, |device| hmac_secret(device, &token.credential_id, &token.salt))?;
+    let wrapped = BASE64.decode(&token.wrapped_key)?;
+    let key = Zeroizing::new(
+        EncryptionKey::from_bytes(*secret)
+            .decrypt(&wrapped)
+            .map_err(|_| anyhow!("token does not hold this key"))?,
+    );
+    key_from_slice(&key)
+}
+
+/// Connected FIDO2 devices
+pub fn fido2_devices() -> Result<Vec<String>> {
+    let output = run(&["fido2-token", "-L"], None)?;
+    Ok(String::from_utf8_lossy(&output)
+        .lines()
+        .filter_map(|line| line.split_once(": ").map(|(device, _)| device.to_string()))
+        .collect())
+}
+
+/// Run `f` against each connected device until one holds the credential
+fn with_token<T>(token: &Fido2Token, f: impl Fn(&str) -> Result<T>) -> Result<T> {
+    let mut last = anyhow!("No FIDO2 token found");
+    for device in fido2_devices()? {
+        match f(&device) {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                warn!("FIDO2 token {} not usable on {}: {}", token.name, device, e);
+                last = e;
+            }
+        }
+    }
+    Err(last)
+}
+
+fn hmac_secret(device: &str, credential_id: &str, salt: &str) -> Result<Zeroizing<[u8; 32]>> {
+    let input = format!(
+        "{}\n{}\n{}\n{}\n",
+        BASE64.encode(random_32()),
+        RELYING_PARTY,
+        credential_id,
+        salt,
+    );
+    let output = Zeroizing::new(fido2(&["fido2-assert", "-G", "-h", device], &input)?);
+    // The hmac-secret is the last line with -h
+    let line = output
+        .lines()
+        .rfind(|l| !l.trim().is_empty())
+        .ok_or_else(|| anyhow!("fido2-assert returned no hmac-secret"))?;
+    let secret = Zeroizing::new(BASE64.decode(line.trim())?);
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    if secret.len() != key.len() {
+        return Err(anyhow!("unexpected hmac-secret length {}", secret.len()));
+    }
+    key.copy_from_slice(&secret);
+    Ok(key)
+}
+
+fn create_primary(context: &str) -> Result<()> {
+    // Same template every time, so the same primary key is recreated
+    tpm2(&["tpm2_createprimary", "-Q", "-C", "o", "-g", "sha256", "-G", "ecc", "-c", context], None)?;
+    Ok(())
+}
+
+fn pcr_list(pcrs: &[u8]) -> Result<String> {
+    if pcrs.is_empty() || pcrs.iter().any(|&p| p > 23) {
+        return Err(anyhow!("PCRs must be between 0 and 23"));
+    }
+    let list: Vec<String> = pcrs.iter().map(u8::to_string).collect();
+    Ok(format!("sha256:{}", list.join(",")))
+}
+
+fn key_from_slice(bytes: &[u8]) -> Result<EncryptionKey> {
+    let key: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| anyhow!("unexpected key length {}", bytes.len()))?;
+    Ok(EncryptionKey::from_bytes(key))
+}
+
+fn random_32() -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+fn tpm2(args: &[&str], stdin: Option<&[u8]>) -> Result<Vec<u8>> {
+    run(args, stdin).with_context(|| format!("{} failed", args[0]))
+}
+
+fn fido2(args: &[&str], stdin: &str) -> Result<String> {
+    let output = run(args, Some(stdin.as_bytes())).with_context(|| format!("{} failed", args[0]))?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+fn run(args: &[&str], stdin: Option<&[u8]>) -> Result<Vec<u8>> {
+    let mut child = Command::new(args[0])
+        .args(&args[1..])
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("{} not available", args[0]))?;
+
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input)?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pcr_list() {
+        assert_eq!(pcr_list(&[0, 7]).unwrap(), "sha256:0,7");
+        assert!(pcr_list(&[]).is_err());
+        assert!(pcr_list(&[24]).is_err());
+    }
+
+    #[test]
+    fn test_enrollments_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Enrollments::load(dir.path()).unwrap().is_empty());
+
+        let enrollments = Enrollments {
+            tpm2: Some(Tpm2Seal {
+                pcrs: vec![7],
+                public: "cHVi".to_string(),
+                private: "cHJpdg==".to_string(),
+                enrolled: chrono::Utc::now(),
+            }),
+            fido2: Vec::new(),
+        };
+        enrollments.save(dir.path()).unwrap();
+
+        let loaded = Enrollments::load(dir.path()).unwrap();
+        assert_eq!(loaded.tpm2.unwrap().pcrs, vec![7]);
+        assert!(loaded.fido2.is_empty());
+    }
+}
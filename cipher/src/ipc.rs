@@ -1,17 +1,21 @@
 //! IPC interface for Cipher daemon
 
 use anyhow::Result;
+use libnyx_ipc::Hello;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::RwLock;
 use tracing::{info, error, debug};
 
+use crate::acl::{Access, AccessEntry, Grant, Peer, Principal, Rights};
 use crate::state::CipherState;
-use crate::crypto::Secret;
-use crate::keyring::SearchAttributes;
+use crate::crypto::{CipherAlgorithm, EncryptionKey, KdfParams, Secret};
+use crate::hardware::{self, Enrollments};
+use crate::keyring::{MigrationStatus, Protection, SearchAttributes};
 
 /// IPC request
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,6 +75,87 @@ pub enum IpcRequest {
         collection: String,
         attributes: HashMap<String, String>,
     },
+
+    /// Unlock with an enrolled TPM2 seal or FIDO2 token
+    UnlockHardware {
+        #[serde(default)]
+        tpm_only: bool,
+    },
+
+    /// Seal the master key to the TPM2 under the given PCRs
+    EnrollTpm2 {
+        #[serde(default = "default_pcrs")]
+        pcrs: Vec<u8>,
+    },
+
+    /// Wrap the master key with a FIDO2 token
+    EnrollFido2 {
+        name: String,
+        #[serde(default)]
+        device: Option<String>,
+    },
+
+    /// Remove a hardware enrollment ("tpm2", or "fido2" and a token name)
+    RemoveEnrollment {
+        method: String,
+        #[serde(default)]
+        name: Option<String>,
+    },
+
+    /// Re-seal to the current PCRs and re-wrap tokens under fresh salts
+    RotateEnrollments,
+
+    /// List hardware enrollments
+    ListEnrollments,
+
+    /// Share a collection, or one item in it, with a user or service
+    Share {
+        collection: String,
+        #[serde(default)]
+        item: Option<String>,
+        principal: Principal,
+        #[serde(default)]
+        rights: Rights,
+        /// Seconds until the grant lapses
+        #[serde(default)]
+        expires_in: Option<u64>,
+    },
+
+    /// Withdraw a grant
+    Revoke { grant: String },
+
+    /// List grants (only those naming the caller, unless the caller is the owner)
+    ListGrants {
+        #[serde(default)]
+        collection: Option<String>,
+    },
+
+    /// Recent secret accesses
+    AccessLog {
+        #[serde(default = "default_log_limit")]
+        limit: usize,
+    },
+
+    /// Strengthen key derivation and cipher; unset fields keep the
+    /// recommended values. Existing items are re-encrypted in the background.
+    UpgradeProtection {
+        password: String,
+        #[serde(default)]
+        kdf: Option<KdfParams>,
+        #[serde(default)]
+        cipher: Option<CipherAlgorithm>,
+    },
+
+    /// Current parameters and re-encryption progress
+    MigrationStatus,
+}
+
+fn default_log_limit() -> usize {
+    50
+}
+
+fn default_pcrs() -> Vec<u8> {
+    hardware::DEFAULT_PCRS.to_vec()
 }
 
 /// IPC response
@@ -83,6 +168,10 @@ pub enum IpcResponse {
     Collections(Vec<CollectionInfo>),
     Items(Vec<ItemInfo>),
     SearchResults(Vec<ItemInfo>),
+    Enrollments(Vec<EnrollmentInfo>),
+    Grants(Vec<Grant>),
+    AccessLog(Vec<AccessEntry>),
+    Migration(MigrationStatus),
     Status {
         initialized: bool,
         locked: bool,
@@ -107,6 +196,14 @@ pub struct ItemInfo {
     pub attributes: HashMap<String, String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnrollmentInfo {
+    pub method: String,
+    pub name: String,
+    pub detail: String,
+    pub enrolled: String,
+}
+
 /// IPC server
 pub struct CipherServer {
     socket_path: String,
@@ -130,10 +227,14 @@ impl CipherServer {
 
         let listener = UnixListener::bind(&self.socket_path)?;
 
-        // Restrict socket permissions
+        // World-connectable so users and services secrets are shared with
+        // can reach their grants. Access is enforced per request from the
+        // peer credentials (see `authorize`): only status, sessions and
+        // listings filtered to readable items are open, secrets need a
+        // grant, and everything else is reserved to the keyring owner.
         std::fs::set_permissions(
             &self.socket_path,
-            std::os::unix::fs::PermissionsExt::from_mode(0o600),
+            std::os::unix::fs::PermissionsExt::from_mode(0o666),
         )?;
 
         info!("Cipher IPC listening on {}", self.socket_path);
@@ -158,17 +259,27 @@ async fn handle_client(
     stream: UnixStream,
     state: Arc<RwLock<CipherState>>,
 ) -> Result<()> {
+    let cred = stream.peer_cred()?;
+    let peer = Peer::new(cred.uid(), cred.pid().map(|pid| pid as u32));
+    debug!("Client connected: uid {} pid {:?}", peer.uid, peer.pid);
+
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
 
+    let hello = Hello::new("cipher", env!("CARGO_PKG_VERSION")).with_features(&["grants"]);
+
     while reader.read_line(&mut line).await? > 0 {
-        let response = match serde_json::from_str::<IpcRequest>(&line) {
-            Ok(request) => process_request(request, &state).await,
-            Err(e) => IpcResponse::Error { message: e.to_string() },
+        let json = match hello.respond(&line) {
+            Some(reply) => reply,
+            None => {
+                let response = match serde_json::from_str::<IpcRequest>(&line) {
+                    Ok(request) => process_request(request, &state, &peer).await,
+                    Err(e) => IpcResponse::Error { message: e.to_string() },
+                };
+                serde_json::to_string(&response)?
+            }
         };
-
-        let json = serde_json::to_string(&response)?;
         writer.write_all(json.as_bytes()).await?;
         writer.write_all(b"\n").await?;
         writer.flush().await?;
@@ -182,7 +293,12 @@ async fn handle_client(
 async fn process_request(
     request: IpcRequest,
     state: &RwLock<CipherState>,
+    peer: &Peer,
 ) -> IpcResponse {
+    if let Some(refusal) = authorize(&request, state, peer).await {
+        return refusal;
+    }
+
     match request {
         IpcRequest::Initialize { password } => {
             let mut state = state.write().await;
@@ -224,7 +340,7 @@ async fn process_request(
 
         IpcRequest::OpenSession => {
             let mut state = state.write().await;
-            let token = state.sessions.create_session(None, None);
+            let token = state.sessions.create_session(Some(peer.uid), peer.pid, peer.exe.clone());
             IpcResponse::Session { token: token.to_string() }
         }
 
@@ -265,6 +381,7 @@ async fn process_request(
             match state.keyring.list_items(&collection) {
                 Ok(items) => {
                     let infos: Vec<ItemInfo> = items.iter()
+                        .filter(|i| state.acl.check(peer, &collection, Some(&i.id), Access::Read))
                         .map(|i| ItemInfo {
                             id: i.id.clone(),
                             label: i.label.clone(),
@@ -292,7 +409,7 @@ async fn process_request(
             let mut state = state.write().await;
 
             // Validate session
-            if let Err(e) = state.sessions.validate(&session) {
+            if let Err(e) = state.sessions.validate_for(&session, peer.uid) {
                 return IpcResponse::Error { message: e.to_string() };
             }
 
@@ -309,7 +426,9 @@ async fn process_request(
 
         IpcRequest::DeleteSecret { collection, id } => {
             let mut state = state.write().await;
-            match state.keyring.delete_secret(&collection, &id) {
+            let result = state.keyring.delete_secret(&collection, &id)
+                .and_then(|()| state.acl.forget(&collection, Some(&id)));
+            match result {
                 Ok(()) => IpcResponse::Success {
                     message: "Secret deleted".to_string(),
                 },
@@ -323,6 +442,7 @@ async fn process_request(
             match state.keyring.search(&collection, &attrs) {
                 Ok(items) => {
                     let infos: Vec<ItemInfo> = items.iter()
+                        .filter(|i| state.acl.check(peer, &collection, Some(&i.id), Access::Read))
                         .map(|i| ItemInfo {
                             id: i.id.clone(),
                             label: i.label.clone(),
@@ -334,5 +454,279 @@ async fn process_request(
                 Err(e) => IpcResponse::Error { message: e.to_string() },
             }
         }
+
+        IpcRequest::UnlockHardware { tpm_only } => {
+            let data_dir = state.read().await.keyring.data_dir().to_path_buf();
+            let key = blocking(move || Enrollments::load(&data_dir)?.unlock(tpm_only)).await;
+
+            match key {
+                Ok(key) => match state.write().await.keyring.unlock_with_key(key) {
+                    Ok(()) => IpcResponse::Success {
+                        message: "Keyring unlocked".to_string(),
+                    },
+                    Err(e) => IpcResponse::Error { message: e.to_string() },
+                },
+                Err(e) => IpcResponse::Error {
+                    message: format!("Hardware unlock failed ({}); unlock with the passphrase", e),
+                },
+            }
+        }
+
+        IpcRequest::EnrollTpm2 { pcrs } => {
+            let (key, data_dir) = match master_key(state).await {
+                Ok(found) => found,
+                Err(response) => return response,
+            };
+
+            let result = blocking(move || {
+                let mut enrollments = Enrollments::load(&data_dir)?;
+                enrollments.tpm2 = Some(hardware::seal_tpm2(&key, &pcrs)?);
+                enrollments.save(&data_dir)
+            }).await;
+
+            match result {
+                Ok(()) => IpcResponse::Success {
+                    message: "Master key sealed to TPM2".to_string(),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::EnrollFido2 { name, device } => {
+            let (key, data_dir) = match master_key(state).await {
+                Ok(found) => found,
+                Err(response) => return response,
+            };
+
+            let result = blocking(move || {
+                let mut enrollments = Enrollments::load(&data_dir)?;
+                if enrollments.fido2.iter().any(|t| t.name == name) {
+                    return Err(anyhow::anyhow!("FIDO2 token already enrolled: {}", name));
+                }
+                let token = hardware::enroll_fido2(&key, &name, device.as_deref())?;
+                enrollments.fido2.push(token);
+                enrollments.save(&data_dir)
+            }).await;
+
+            match result {
+                Ok(()) => IpcResponse::Success {
+                    message: "FIDO2 token enrolled".to_string(),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::RemoveEnrollment { method, name } => {
+            let data_dir = state.read().await.keyring.data_dir().to_path_buf();
+            let result = Enrollments::load(&data_dir).and_then(|mut enrollments| {
+                let removed = match (method.as_str(), name) {
+                    ("tpm2", _) => enrollments.tpm2.take().is_some(),
+                    ("fido2", Some(name)) => {
+                        let before = enrollments.fido2.len();
+                        enrollments.fido2.retain(|t| t.name != name);
+                        enrollments.fido2.len() != before
+                    }
+                    ("fido2", None) => return Err(anyhow::anyhow!("Token name required")),
+                    (other, _) => return Err(anyhow::anyhow!("Unknown method: {}", other)),
+                };
+                if !removed {
+                    return Err(anyhow::anyhow!("Not enrolled"));
+                }
+                enrollments.save(&data_dir)
+            });
+
+            match result {
+                Ok(()) => IpcResponse::Success {
+                    message: "Enrollment removed".to_string(),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::RotateEnrollments => {
+            let (key, data_dir) = match master_key(state).await {
+                Ok(found) => found,
+                Err(response) => return response,
+            };
+
+            // Nothing is saved unless every enrollment was redone
+            let result = blocking(move || {
+                let mut enrollments = Enrollments::load(&data_dir)?;
+                if let Some(ref seal) = enrollments.tpm2 {
+                    enrollments.tpm2 = Some(hardware::seal_tpm2(&key, &seal.pcrs)?);
+                }
+                for token in enrollments.fido2.iter_mut() {
+                    *token = hardware::rewrap_fido2(&key, token)?;
+                }
+                enrollments.save(&data_dir)
+            }).await;
+
+            match result {
+                Ok(()) => IpcResponse::Success {
+                    message: "Enrollments rotated".to_string(),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::ListEnrollments => {
+            let data_dir = state.read().await.keyring.data_dir().to_path_buf();
+            match Enrollments::load(&data_dir) {
+                Ok(enrollments) => {
+                    let mut infos: Vec<EnrollmentInfo> = enrollments.tpm2.iter()
+                        .map(|seal| EnrollmentInfo {
+                            method: "tpm2".to_string(),
+                            name: "tpm2".to_string(),
+                            detail: format!("PCRs {:?}", seal.pcrs),
+                            enrolled: seal.enrolled.to_rfc3339(),
+                        })
+                        .collect();
+                    infos.extend(enrollments.fido2.iter().map(|token| EnrollmentInfo {
+                        method: "fido2".to_string(),
+                        name: token.name.clone(),
+                        detail: "hmac-secret".to_string(),
+                        enrolled: token.enrolled.to_rfc3339(),
+                    }));
+                    IpcResponse::Enrollments(infos)
+                }
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::Share { collection, item, principal, rights, expires_in } => {
+            let mut state = state.write().await;
+
+            let exists = match item {
+                Some(ref id) => state.keyring.get_item(&collection, id).map(|_| ()),
+                None => state.keyring.get_collection(&collection)
+                    .map(|_| ())
+                    .ok_or_else(|| anyhow::anyhow!("Collection not found: {}", collection)),
+            };
+            if let Err(e) = exists {
+                return IpcResponse::Error { message: e.to_string() };
+            }
+
+            let expires = expires_in.map(|secs| chrono::Utc::now() + chrono::Duration::seconds(secs as i64));
+            match state.acl.share(peer.uid, &collection, item.as_deref(), principal, rights, expires) {
+                Ok(grant) => {
+                    state.acl.record(peer, "share", Some(&collection), item.as_deref(), true);
+                    IpcResponse::Grants(vec![grant])
+                }
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::Revoke { grant } => {
+            let mut state = state.write().await;
+            match state.acl.revoke(&grant) {
+                Ok(revoked) => {
+                    state.acl.record(peer, "revoke", Some(&revoked.collection), revoked.item.as_deref(), true);
+                    IpcResponse::Success {
+                        message: format!("Grant {} revoked", revoked.id),
+                    }
+                }
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::ListGrants { collection } => {
+            let mut state = state.write().await;
+            let owner = state.acl.is_owner(peer);
+            let grants = state.acl.grants(collection.as_deref())
+                .into_iter()
+                .filter(|g| owner || g.principal.matches(peer))
+                .collect();
+            IpcResponse::Grants(grants)
+        }
+
+        IpcRequest::AccessLog { limit } => {
+            let state = state.read().await;
+            IpcResponse::AccessLog(state.acl.log(limit))
+        }
+
+        IpcRequest::UpgradeProtection { password, kdf, cipher } => {
+            let recommended = Protection::recommended();
+            let target = Protection {
+                kdf: kdf.unwrap_or(recommended.kdf),
+                cipher: cipher.unwrap_or(recommended.cipher),
+            };
+
+            let mut state = state.write().await;
+            let rekeyed = target.kdf != state.keyring.protection().kdf;
+            match state.keyring.upgrade(&password, target) {
+                Ok(pending) => {
+                    let mut message = format!("Protection upgraded; re-encrypting {} items", pending);
+                    let enrolled = Enrollments::load(state.keyring.data_dir())
+                        .map(|e| e.tpm2.is_some() || !e.fido2.is_empty())
+                        .unwrap_or(false);
+                    if rekeyed && enrolled {
+                        message.push_str("; the master key changed, run rotate to re-enroll hardware");
+                    }
+                    IpcResponse::Success { message }
+                }
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::MigrationStatus => {
+            let state = state.read().await;
+            IpcResponse::Migration(state.keyring.migration_status())
+        }
     }
 }
+
+/// Check a request against the caller's grants, logging secret accesses.
+/// Returns the refusal if the caller may not make it.
+async fn authorize(
+    request: &IpcRequest,
+    state: &RwLock<CipherState>,
+    peer: &Peer,
+) -> Option<IpcResponse> {
+    let (operation, collection, id, access) = match request {
+        // Open to everyone; listings are filtered to what the caller can read
+        IpcRequest::Status
+        | IpcRequest::OpenSession
+        | IpcRequest::CloseSession { .. }
+        | IpcRequest::ListItems { .. }
+        | IpcRequest::Search { .. }
+        | IpcRequest::ListGrants { .. } => return None,
+
+        IpcRequest::GetSecret { collection, id, .. } => ("get", collection, id, Access::Read),
+        IpcRequest::StoreSecret { collection, id, .. } => ("store", collection, id, Access::Write),
+        IpcRequest::DeleteSecret { collection, id } => ("delete", collection, id, Access::Write),
+
+        // Everything else manages the keyring itself
+        _ => {
+            if state.read().await.acl.is_owner(peer) {
+                return None;
+            }
+            return Some(IpcResponse::Error {
+                message: "Only the keyring owner can do that".to_string(),
+            });
+        }
+    };
+
+    let mut state = state.write().await;
+    let allowed = state.acl.check(peer, collection, Some(id), access);
+    state.acl.record(peer, operation, Some(collection), Some(id), allowed);
+
+    (!allowed).then(|| IpcResponse::Error {
+        message: format!("Access denied to {}/{}", collection, id),
+    })
+}
+
+/// The unlocked master key and where the keyring lives
+async fn master_key(
+    state: &RwLock<CipherState>,
+) -> std::result::Result<(EncryptionKey, PathBuf), IpcResponse> {
+    let state = state.read().await;
+    let key = state.keyring.master_key().cloned().ok_or_else(|| IpcResponse::Error {
+        message: "Keyring is locked".to_string(),
+    })?;
+    Ok((key, state.keyring.data_dir().to_path_buf()))
+}
+
+/// Run hardware operations (tool invocations, token touches) off the runtime
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(f).await?
+}
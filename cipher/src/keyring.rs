@@ -6,7 +6,13 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tracing::{info, debug};
 
-use crate::crypto::{EncryptionKey, Secret, generate_salt, hash_password, verify_password};
+use crate::crypto::{
+    CipherAlgorithm, EncryptionKey, KdfParams, Secret, generate_salt, hash_password, verify_password,
+};
+
+/// Encrypted under the master key so a key released by hardware can be
+/// checked without the passphrase
+const KEY_CHECK: &[u8] = b"nyx-cipher master key check";
 
 /// A keyring collection
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,7 +22,7 @@ pub struct Collection {
     pub locked: bool,
     pub created: chrono::DateTime<chrono::Utc>,
     pub modified: chrono::DateTime<chrono::Utc>,
-    #[serde(skip)]
+    #[serde(default)]
     items: HashMap<String, Item>,
 }
 
@@ -28,9 +34,49 @@ pub struct Item {
     pub attributes: HashMap<String, String>,
     pub created: chrono::DateTime<chrono::Utc>,
     pub modified: chrono::DateTime<chrono::Utc>,
+    /// MIME type of the secret, as the Secret Service reports it
+    #[serde(default = "default_content_type")]
+    pub content_type: String,
+    /// How the secret is encrypted; items from before this was recorded
+    /// used the original parameters
+    #[serde(default)]
+    pub protection: Protection,
     encrypted_secret: Vec<u8>,
 }
 
+/// The master key derivation and cipher a secret is protected with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Protection {
+    pub kdf: KdfParams,
+    pub cipher: CipherAlgorithm,
+}
+
+impl Protection {
+    pub fn recommended() -> Self {
+        Self {
+            kdf: KdfParams::recommended(),
+            cipher: CipherAlgorithm::XChaCha20Poly1305,
+        }
+    }
+}
+
+/// Progress re-encrypting items under new parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationStatus {
+    pub target: Protection,
+    /// Items still protected with older parameters
+    pub pending: usize,
+    /// Items re-encrypted since the daemon started or the upgrade began
+    pub migrated: usize,
+    /// Items need the previous key, which only a passphrase unlock derives
+    pub needs_passphrase: bool,
+    pub last_error: Option<String>,
+}
+
+fn default_content_type() -> String {
+    "text/plain".to_string()
+}
+
 /// Item search attributes
 #[derive(Debug, Clone)]
 pub struct SearchAttributes {
@@ -44,6 +90,19 @@ pub struct Keyring {
     master_key: Option<EncryptionKey>,
     master_salt: [u8; 16],
     master_hash: Option<String>,
+    key_check: Option<Vec<u8>>,
+    /// Alias -> collection name
+    aliases: HashMap<String, String>,
+    /// Bumped on every change to collections or items
+    generation: u64,
+    /// Parameters new secrets are written with
+    protection: Protection,
+    /// Master key derivation being migrated away from
+    previous: Option<PreviousMaster>,
+    /// Key derived from `previous` while items still need it
+    previous_key: Option<EncryptionKey>,
+    migrated: usize,
+    migration_error: Option<String>,
 }
 
 impl Keyring {
@@ -58,6 +117,14 @@ impl Keyring {
             master_key: None,
             master_salt: [0u8; 16],
             master_hash: None,
+            key_check: None,
+            aliases: HashMap::new(),
+            generation: 0,
+            protection: Protection::default(),
+            previous: None,
+            previous_key: None,
+            migrated: 0,
+            migration_error: None,
         };
 
         // Load master key salt and hash
@@ -67,9 +134,19 @@ impl Keyring {
             let master: MasterKeyData = serde_json::from_str(&content)?;
             keyring.master_salt = master.salt;
             keyring.master_hash = Some(master.password_hash);
+            keyring.key_check = master.key_check;
+            keyring.protection = master.protection;
+            keyring.previous = master.previous;
         } else {
             // Generate new salt
             keyring.master_salt = generate_salt();
+            keyring.protection = Protection::recommended();
+        }
+
+        let aliases_file = data_dir.join("aliases.json");
+        if aliases_file.exists() {
+            let content = std::fs::read_to_string(&aliases_file)?;
+            keyring.aliases = serde_json::from_str(&content)?;
         }
 
         // Load collections
@@ -119,11 +196,10 @@ impl Keyring {
         }
 
         let hash = hash_password(password)?;
+        let key = EncryptionKey::derive_with_params(password, &self.master_salt, &self.protection.kdf)?;
         self.master_hash = Some(hash.clone());
-        self.master_key = Some(EncryptionKey::derive_from_password(
-            password,
-            &self.master_salt,
-        )?);
+        self.key_check = Some(key.encrypt(KEY_CHECK)?);
+        self.master_key = Some(key);
 
         // Save master data
         self.save_master()?;
@@ -141,23 +217,62 @@ impl Keyring {
             return Err(anyhow!("Invalid password"));
         }
 
-        self.master_key = Some(EncryptionKey::derive_from_password(
-            password,
-            &self.master_salt,
-        )?);
+        let key = EncryptionKey::derive_with_params(password, &self.master_salt, &self.protection.kdf)?;
 
-        // Unlock all collections
-        for collection in self.collections.values_mut() {
-            collection.locked = false;
+        // Keyrings created before hardware unlock have no check value yet
+        if self.key_check.is_none() {
+            self.key_check = Some(key.encrypt(KEY_CHECK)?);
+            self.save_master()?;
         }
 
+        // A migration is unfinished; items it hasn't reached need the old key
+        if let Some(ref previous) = self.previous {
+            self.previous_key = Some(EncryptionKey::derive_with_params(password, &previous.salt, &previous.kdf)?);
+        }
+
+        self.set_unlocked(key);
         info!("Keyring unlocked");
         Ok(())
     }
 
+    /// Unlock with a master key released by hardware
+    pub fn unlock_with_key(&mut self, key: EncryptionKey) -> Result<()> {
+        let check = self.key_check.as_ref()
+            .ok_or_else(|| anyhow!("Keyring must be unlocked with the passphrase once first"))?;
+
+        match key.decrypt(check) {
+            Ok(plain) if plain == KEY_CHECK => {}
+            _ => return Err(anyhow!("Key does not unlock this keyring")),
+        }
+
+        self.set_unlocked(key);
+        info!("Keyring unlocked with hardware key");
+        Ok(())
+    }
+
+    /// The master key while unlocked, for sealing to hardware
+    pub fn master_key(&self) -> Option<&EncryptionKey> {
+        self.master_key.as_ref()
+    }
+
+    /// Directory the keyring is stored in
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    fn set_unlocked(&mut self, key: EncryptionKey) {
+        self.master_key = Some(key);
+
+        // Unlock all collections
+        for collection in self.collections.values_mut() {
+            collection.locked = false;
+        }
+    }
+
     /// Lock keyring
     pub fn lock(&mut self) {
         self.master_key = None;
+        self.previous_key = None;
 
         for collection in self.collections.values_mut() {
             collection.locked = true;
@@ -188,10 +303,93 @@ impl Keyring {
 
         self.collections.insert(name.to_string(), collection);
         self.save_collection(name)?;
+        self.generation += 1;
+
+        Ok(())
+    }
+
+    /// Delete a collection and everything in it
+    pub fn delete_collection(&mut self, name: &str) -> Result<()> {
+        self.collections.remove(name)
+            .ok_or_else(|| anyhow!("Collection not found: {}", name))?;
+
+        let path = self.data_dir.join("collections").join(format!("{}.json", name));
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+
+        let before = self.aliases.len();
+        self.aliases.retain(|_, target| target != name);
+        if self.aliases.len() != before {
+            self.save_aliases()?;
+        }
+
+        self.generation += 1;
+        info!("Deleted collection {}", name);
+        Ok(())
+    }
+
+    /// Get a collection
+    pub fn get_collection(&self, name: &str) -> Option<&Collection> {
+        self.collections.get(name)
+    }
+
+    /// Relabel a collection
+    pub fn set_collection_label(&mut self, name: &str, label: &str) -> Result<()> {
+        let coll = self.collections.get_mut(name)
+            .ok_or_else(|| anyhow!("Collection not found: {}", name))?;
+
+        coll.label = label.to_string();
+        coll.modified = chrono::Utc::now();
+        self.save_collection(name)?;
+        self.generation += 1;
+
+        Ok(())
+    }
+
+    /// Collection an alias points to. `default` falls back to the
+    /// collection named `default`.
+    pub fn alias(&self, alias: &str) -> Option<&str> {
+        match self.aliases.get(alias) {
+            Some(name) => Some(name.as_str()),
+            None if alias == "default" && self.collections.contains_key("default") => Some("default"),
+            None => None,
+        }
+    }
+
+    /// Point an alias at a collection, or remove it
+    pub fn set_alias(&mut self, alias: &str, collection: Option<&str>) -> Result<()> {
+        match collection {
+            Some(name) => {
+                if !self.collections.contains_key(name) {
+                    return Err(anyhow!("Collection not found: {}", name));
+                }
+                self.aliases.insert(alias.to_string(), name.to_string());
+            }
+            None => {
+                self.aliases.remove(alias);
+            }
+        }
 
+        self.save_aliases()?;
+        self.generation += 1;
         Ok(())
     }
 
+    /// Aliases that currently resolve, including the implicit `default`
+    pub fn aliases(&self) -> HashMap<String, String> {
+        let mut aliases = self.aliases.clone();
+        if let Some(name) = self.alias("default") {
+            aliases.insert("default".to_string(), name.to_string());
+        }
+        aliases
+    }
+
+    /// Counter that changes whenever collections, items or aliases do
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
     /// Store a secret
     pub fn store_secret(
         &mut self,
@@ -200,28 +398,46 @@ impl Keyring {
         label: &str,
         secret: &Secret,
         attributes: HashMap<String, String>,
+    ) -> Result<()> {
+        self.store_item(collection, id, label, secret, attributes, &default_content_type())
+    }
+
+    /// Store a secret with its content type. Replacing an item keeps its
+    /// creation time.
+    pub fn store_item(
+        &mut self,
+        collection: &str,
+        id: &str,
+        label: &str,
+        secret: &Secret,
+        attributes: HashMap<String, String>,
+        content_type: &str,
     ) -> Result<()> {
         let key = self.master_key.as_ref()
             .ok_or_else(|| anyhow!("Keyring is locked"))?;
 
-        let encrypted = key.encrypt(secret.as_bytes())?;
+        let encrypted = key.encrypt_with(self.protection.cipher, secret.as_bytes())?;
 
+        let coll = self.collections.get_mut(collection)
+            .ok_or_else(|| anyhow!("Collection not found: {}", collection))?;
+
+        let now = chrono::Utc::now();
         let item = Item {
             id: id.to_string(),
             label: label.to_string(),
             attributes,
-            created: chrono::Utc::now(),
-            modified: chrono::Utc::now(),
+            created: coll.items.get(id).map(|i| i.created).unwrap_or(now),
+            modified: now,
+            content_type: content_type.to_string(),
+            protection: self.protection,
             encrypted_secret: encrypted,
         };
 
-        let coll = self.collections.get_mut(collection)
-            .ok_or_else(|| anyhow!("Collection not found: {}", collection))?;
-
         coll.items.insert(id.to_string(), item);
-        coll.modified = chrono::Utc::now();
+        coll.modified = now;
 
         self.save_collection(collection)?;
+        self.generation += 1;
 
         debug!("Stored secret {} in {}", id, collection);
         Ok(())
@@ -229,8 +445,9 @@ impl Keyring {
 
     /// Retrieve a secret
     pub fn get_secret(&self, collection: &str, id: &str) -> Result<Secret> {
-        let key = self.master_key.as_ref()
-            .ok_or_else(|| anyhow!("Keyring is locked"))?;
+        if self.master_key.is_none() {
+            return Err(anyhow!("Keyring is locked"));
+        }
 
         let coll = self.collections.get(collection)
             .ok_or_else(|| anyhow!("Collection not found: {}", collection))?;
@@ -238,9 +455,218 @@ impl Keyring {
         let item = coll.items.get(id)
             .ok_or_else(|| anyhow!("Item not found: {}", id))?;
 
-        let decrypted = key.decrypt(&item.encrypted_secret)?;
+        self.decrypt_item(item)
+    }
+
+    fn decrypt_item(&self, item: &Item) -> Result<Secret> {
+        let key = self.key_for(&item.protection.kdf).ok_or_else(|| {
+            anyhow!("Item {} awaits migration; unlock with the passphrase to read it", item.id)
+        })?;
+
+        Ok(Secret::new(key.decrypt_with(item.protection.cipher, &item.encrypted_secret)?))
+    }
+
+    /// The master key derived with `kdf`, if it is held
+    fn key_for(&self, kdf: &KdfParams) -> Option<&EncryptionKey> {
+        if *kdf == self.protection.kdf {
+            self.master_key.as_ref()
+        } else if self.previous.as_ref().is_some_and(|p| p.kdf == *kdf) {
+            self.previous_key.as_ref()
+        } else {
+            None
+        }
+    }
+
+    /// Parameters new secrets are written with
+    pub fn protection(&self) -> Protection {
+        self.protection
+    }
+
+    /// Move to stronger parameters. A new KDF re-derives the master key
+    /// under a fresh salt; the old one is kept until every item has been
+    /// re-encrypted by `migrate`. Returns the number of items to migrate.
+    pub fn upgrade(&mut self, password: &str, target: Protection) -> Result<usize> {
+        let hash = self.master_hash.as_ref()
+            .ok_or_else(|| anyhow!("Keyring not initialized"))?;
+
+        if !verify_password(password, hash)? {
+            return Err(anyhow!("Invalid password"));
+        }
+        if target == self.protection {
+            return Err(anyhow!("Keyring already uses these parameters"));
+        }
+        if target.kdf.is_weaker_than(&self.protection.kdf) || target.cipher.is_weaker_than(self.protection.cipher) {
+            return Err(anyhow!("Refusing to weaken protection from {} to {}",
+                describe(&self.protection), describe(&target)));
+        }
+        if target.kdf != self.protection.kdf && self.previous.is_some() {
+            return Err(anyhow!("A key migration is still in progress"));
+        }
+
+        let current = EncryptionKey::derive_with_params(password, &self.master_salt, &self.protection.kdf)?;
+
+        if target.kdf == self.protection.kdf {
+            // Only the cipher changes; the master key stays
+            self.set_unlocked(current);
+        } else {
+            let salt = generate_salt();
+            let key = EncryptionKey::derive_with_params(password, &salt, &target.kdf)?;
+
+            self.previous = Some(PreviousMaster { salt: self.master_salt, kdf: self.protection.kdf });
+            self.previous_key = Some(current);
+            self.master_salt = salt;
+            self.key_check = Some(key.encrypt(KEY_CHECK)?);
+            self.set_unlocked(key);
+        }
+
+        self.protection = target;
+        self.migrated = 0;
+        self.migration_error = None;
+        self.save_master()?;
+
+        let pending = self.pending_items();
+        info!("Upgrading keyring to {}: {} items to re-encrypt", describe(&target), pending);
+        Ok(pending)
+    }
+
+    /// Re-encrypt up to `batch` items still under older parameters.
+    /// Returns how many were migrated.
+    pub fn migrate(&mut self, batch: usize) -> Result<usize> {
+        if self.master_key.is_none() {
+            return Ok(0);
+        }
+
+        let target = self.protection;
+        let mut work = Vec::new();
+        for coll in self.collections.values() {
+            for item in coll.items.values() {
+                if work.len() == batch {
+                    break;
+                }
+                if item.protection != target && self.key_for(&item.protection.kdf).is_some() {
+                    work.push((coll.name.clone(), item.id.clone()));
+                }
+            }
+        }
+
+        let mut touched: Vec<String> = Vec::new();
+        let mut migrated = 0;
+        let mut result = Ok(());
+        for (collection, id) in &work {
+            if let Err(e) = self.migrate_item(collection, id) {
+                result = Err(e);
+                break;
+            }
+            migrated += 1;
+            if !touched.contains(collection) {
+                touched.push(collection.clone());
+            }
+        }
+
+        for collection in &touched {
+            self.save_collection(collection)?;
+        }
+        self.migrated += migrated;
+
+        if let Err(e) = result {
+            self.migration_error = Some(e.to_string());
+            return Err(e);
+        }
+
+        if migrated > 0 {
+            self.migration_error = None;
+            if self.pending_items() == 0 {
+                info!("Keyring migration to {} complete", describe(&target));
+            }
+        }
+
+        // Nothing left needs the previous key
+        if self.previous.is_some() && self.pending_items() == 0 {
+            self.previous = None;
+            self.previous_key = None;
+            self.save_master()?;
+        }
+
+        Ok(migrated)
+    }
+
+    fn migrate_item(&mut self, collection: &str, id: &str) -> Result<()> {
+        let target = self.protection;
+        let item = self.get_item(collection, id)?;
+        let secret = self.decrypt_item(item)?;
+
+        let key = self.master_key.as_ref().ok_or_else(|| anyhow!("Keyring is locked"))?;
+        let encrypted = key.encrypt_with(target.cipher, secret.as_bytes())?;
+
+        // Never replace a secret with something that doesn't read back
+        if key.decrypt_with(target.cipher, &encrypted)? != secret.as_bytes() {
+            return Err(anyhow!("Re-encrypted {} did not verify", id));
+        }
+
+        let item = self.collections.get_mut(collection)
+            .and_then(|c| c.items.get_mut(id))
+            .ok_or_else(|| anyhow!("Item not found: {}", id))?;
+        item.protection = target;
+        item.encrypted_secret = encrypted;
+
+        debug!("Migrated {}/{} to {}", collection, id, describe(&target));
+        Ok(())
+    }
+
+    fn pending_items(&self) -> usize {
+        self.collections.values()
+            .flat_map(|c| c.items.values())
+            .filter(|i| i.protection != self.protection)
+            .count()
+    }
+
+    /// Progress moving items to the current parameters
+    pub fn migration_status(&self) -> MigrationStatus {
+        let pending = self.pending_items();
+        MigrationStatus {
+            target: self.protection,
+            pending,
+            migrated: self.migrated,
+            needs_passphrase: pending > 0 && self.previous.is_some() && self.previous_key.is_none(),
+            last_error: self.migration_error.clone(),
+        }
+    }
+
+    /// Get an item's metadata
+    pub fn get_item(&self, collection: &str, id: &str) -> Result<&Item> {
+        let coll = self.collections.get(collection)
+            .ok_or_else(|| anyhow!("Collection not found: {}", collection))?;
+
+        coll.items.get(id)
+            .ok_or_else(|| anyhow!("Item not found: {}", id))
+    }
+
+    /// Change an item's label or attributes without touching the secret
+    pub fn update_item(
+        &mut self,
+        collection: &str,
+        id: &str,
+        label: Option<&str>,
+        attributes: Option<HashMap<String, String>>,
+    ) -> Result<()> {
+        let coll = self.collections.get_mut(collection)
+            .ok_or_else(|| anyhow!("Collection not found: {}", collection))?;
+
+        let item = coll.items.get_mut(id)
+            .ok_or_else(|| anyhow!("Item not found: {}", id))?;
+
+        if let Some(label) = label {
+            item.label = label.to_string();
+        }
+        if let Some(attributes) = attributes {
+            item.attributes = attributes;
+        }
+        item.modified = chrono::Utc::now();
+        coll.modified = item.modified;
 
-        Ok(Secret::new(decrypted))
+        self.save_collection(collection)?;
+        self.generation += 1;
+        Ok(())
     }
 
     /// Search for items
@@ -269,6 +695,7 @@ impl Keyring {
 
         coll.modified = chrono::Utc::now();
         self.save_collection(collection)?;
+        self.generation += 1;
 
         Ok(())
     }
@@ -290,6 +717,9 @@ impl Keyring {
         let master = MasterKeyData {
             salt: self.master_salt,
             password_hash: self.master_hash.clone().unwrap_or_default(),
+            key_check: self.key_check.clone(),
+            protection: self.protection,
+            previous: self.previous.clone(),
         };
 
         let content = serde_json::to_string_pretty(&master)?;
@@ -298,6 +728,13 @@ impl Keyring {
         Ok(())
     }
 
+    fn save_aliases(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.aliases)?;
+        std::fs::write(self.data_dir.join("aliases.json"), &content)?;
+
+        Ok(())
+    }
+
     fn save_collection(&self, name: &str) -> Result<()> {
         let coll = self.collections.get(name)
             .ok_or_else(|| anyhow!("Collection not found: {}", name))?;
@@ -317,4 +754,101 @@ impl Keyring {
 struct MasterKeyData {
     salt: [u8; 16],
     password_hash: String,
+    #[serde(default)]
+    key_check: Option<Vec<u8>>,
+    /// Keyrings from before parameters were recorded used the defaults
+    #[serde(default)]
+    protection: Protection,
+    #[serde(default)]
+    previous: Option<PreviousMaster>,
+}
+
+/// Salt and KDF of the master key a migration is moving away from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PreviousMaster {
+    salt: [u8; 16],
+    kdf: KdfParams,
+}
+
+fn describe(protection: &Protection) -> String {
+    format!("{}, {}", protection.kdf, protection.cipher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheaper than recommended so debug builds stay quick
+    fn stronger() -> Protection {
+        Protection {
+            kdf: KdfParams { memory_kib: 32 * 1024, iterations: 2, parallelism: 1 },
+            cipher: CipherAlgorithm::XChaCha20Poly1305,
+        }
+    }
+
+    fn legacy_keyring(dir: &Path) -> Keyring {
+        let mut keyring = Keyring::load(dir.to_str().unwrap()).unwrap();
+        keyring.protection = Protection::default();
+        keyring.initialize("password").unwrap();
+        for id in ["a", "b", "c"] {
+            keyring.store_secret("default", id, id, &Secret::from_str(id), HashMap::new()).unwrap();
+        }
+        keyring
+    }
+
+    #[test]
+    fn test_upgrade_migrates_and_resumes_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut keyring = legacy_keyring(dir.path());
+
+        assert_eq!(keyring.upgrade("password", stronger()).unwrap(), 3);
+        // Old items stay readable through the previous key
+        assert_eq!(keyring.get_secret("default", "a").unwrap().as_str().unwrap(), "a");
+
+        assert_eq!(keyring.migrate(2).unwrap(), 2);
+        assert_eq!(keyring.migration_status().pending, 1);
+
+        // Restart: a passphrase unlock brings the previous key back
+        let mut keyring = Keyring::load(dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(keyring.protection(), stronger());
+        keyring.unlock("password").unwrap();
+        assert_eq!(keyring.migrate(16).unwrap(), 1);
+
+        let status = keyring.migration_status();
+        assert_eq!(status.pending, 0);
+        assert!(keyring.previous.is_none());
+        for id in ["a", "b", "c"] {
+            assert_eq!(keyring.get_secret("default", id).unwrap().as_str().unwrap(), id);
+            assert_eq!(keyring.get_item("default", id).unwrap().protection, stronger());
+        }
+    }
+
+    #[test]
+    fn test_upgrade_refuses_weaker_parameters() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut keyring = legacy_keyring(dir.path());
+
+        let weaker = Protection {
+            kdf: KdfParams { memory_kib: 8 * 1024, ..KdfParams::default() },
+            cipher: CipherAlgorithm::XChaCha20Poly1305,
+        };
+        assert!(keyring.upgrade("password", weaker).is_err());
+        assert!(keyring.upgrade("wrong", stronger()).is_err());
+        assert!(keyring.upgrade("password", Protection::default()).is_err());
+    }
+
+    #[test]
+    fn test_cipher_only_upgrade_keeps_master_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut keyring = legacy_keyring(dir.path());
+        let key = keyring.master_key.clone().unwrap();
+
+        let target = Protection { cipher: CipherAlgorithm::XChaCha20Poly1305, ..Protection::default() };
+        assert_eq!(keyring.upgrade("password", target).unwrap(), 3);
+        assert!(keyring.previous.is_none());
+        assert_eq!(keyring.master_key.as_ref().unwrap().as_bytes(), key.as_bytes());
+
+        assert_eq!(keyring.migrate(16).unwrap(), 3);
+        assert_eq!(keyring.migration_status().pending, 0);
+    }
 }
@@ -3,12 +3,19 @@
 //! Secure storage for passwords, keys, and secrets with:
 //! - Memory-safe secret handling (zeroize)
 //! - Strong encryption (ChaCha20-Poly1305)
-//! - Key derivation (Argon2id)
+//! - Key derivation (Argon2id) with in-place parameter upgrades
 //! - Session-based unlocking
+//! - Sharing secrets with other users and services
+//! - TPM2 and FIDO2 hardware unlock
+//! - freedesktop.org Secret Service over D-Bus
 
+pub mod acl;
 pub mod crypto;
+pub mod hardware;
 pub mod keyring;
+pub mod migration;
 pub mod session;
 pub mod storage;
 pub mod ipc;
+pub mod secret_service;
 pub mod state;
@@ -11,12 +11,14 @@ use anyhow::Result;
 use clap::Parser;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::info;
+use tracing::{error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+use nyx_cipher::hardware::Enrollments;
 use nyx_cipher::keyring::Keyring;
 use nyx_cipher::session::SessionManager;
 use nyx_cipher::ipc::CipherServer;
+use nyx_cipher::acl::AccessControl;
 use nyx_cipher::state::CipherState;
 
 #[derive(Parser)]
@@ -34,6 +36,14 @@ struct Args {
     /// User socket path (for per-user access)
     #[arg(long)]
     user_socket: Option<String>,
+
+    /// Serve org.freedesktop.secrets on the session bus
+    #[arg(long)]
+    secret_service: bool,
+
+    /// Try unlocking with the TPM2 at startup
+    #[arg(long)]
+    hardware_unlock: bool,
 }
 
 #[tokio::main]
@@ -49,15 +59,40 @@ async fn main() -> Result<()> {
     info!("Starting Cipher secrets daemon");
 
     // Initialize keyring
-    let keyring = Keyring::load(&args.data_dir)?;
+    let mut keyring = Keyring::load(&args.data_dir)?;
+
+    if args.hardware_unlock {
+        let unlocked = Enrollments::load(keyring.data_dir())
+            .and_then(|enrollments| enrollments.unlock(true))
+            .and_then(|key| keyring.unlock_with_key(key));
+        if let Err(e) = unlocked {
+            warn!("TPM2 unlock failed, passphrase required: {}", e);
+        }
+    }
     let sessions = SessionManager::new();
 
+    // Whoever runs the daemon owns the keyring
+    let owner_uid = unsafe { libc::geteuid() };
+    let acl = AccessControl::load(keyring.data_dir(), owner_uid)?;
+
     let state = Arc::new(RwLock::new(CipherState {
         keyring,
         sessions,
+        acl,
         data_dir: args.data_dir.clone(),
     }));
 
+    tokio::spawn(nyx_cipher::migration::run(state.clone()));
+
+    if args.secret_service {
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = nyx_cipher::secret_service::serve(state).await {
+                error!("Secret Service unavailable: {}", e);
+            }
+        });
+    }
+
     // Start IPC server
     let server = CipherServer::new(&args.socket, state.clone());
 
@@ -0,0 +1,43 @@
+//! Background re-encryption after a protection upgrade
+//!
+//! `Keyring::upgrade` only switches the parameters new secrets get; this
+//! worker moves existing items over a batch at a time, so the keyring lock
+//! is never held for long and an interrupted migration resumes on the next
+//! unlock.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+use crate::state::CipherState;
+
+/// Items re-encrypted per pass
+const BATCH: usize = 16;
+
+/// Pause between passes while there is work
+const BUSY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Pause while there is nothing (or nothing possible) to do
+const IDLE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Migrate items whenever the keyring is unlocked and some are pending
+pub async fn run(state: Arc<RwLock<CipherState>>) {
+    loop {
+        let result = state.write().await.keyring.migrate(BATCH);
+
+        let pause = match result {
+            Ok(0) => IDLE_INTERVAL,
+            Ok(migrated) => {
+                debug!("Re-encrypted {} items", migrated);
+                BUSY_INTERVAL
+            }
+            Err(e) => {
+                warn!("Keyring migration stalled: {}", e);
+                IDLE_INTERVAL
+            }
+        };
+
+        tokio::time::sleep(pause).await;
+    }
+}
@@ -0,0 +1,408 @@
+//! freedesktop.org Secret Service
+//!
+//! Serves `org.freedesktop.secrets` on the session bus on top of the
+//! keyring, so libsecret consumers, browsers and network tools store their
+//! secrets in Cipher. Collections and items are exported as D-Bus objects
+//! mirroring the keyring; the mirror is resynced after every change made
+//! over D-Bus and whenever the keyring changes through Cipher's own IPC.
+//!
+//! The keyring locks and unlocks as a whole, so every collection reports
+//! the keyring's lock state. Unlocking over D-Bus returns a prompt that
+//! completes once the keyring is unlocked (`cipherctl unlock` or the
+//! session's unlock dialog).
+
+mod objects;
+pub mod transfer;
+
+use futures::StreamExt;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{debug, info, warn};
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Type};
+use zbus::Connection;
+
+use crate::crypto::Secret;
+use crate::state::CipherState;
+use objects::{CollectionObject, ItemObject, ServiceObject};
+use transfer::Transfer;
+
+/// Well-known bus name
+pub const BUS_NAME: &str = "org.freedesktop.secrets";
+
+/// Object path of the service
+pub const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+
+/// How often keyring changes made outside D-Bus are picked up
+const SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+const ITEM_LABEL: &str = "org.freedesktop.Secret.Item.Label";
+const ITEM_ATTRIBUTES: &str = "org.freedesktop.Secret.Item.Attributes";
+const COLLECTION_LABEL: &str = "org.freedesktop.Secret.Collection.Label";
+
+/// Secret Service errors
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "org.freedesktop.Secret.Error")]
+pub enum ServiceError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    IsLocked(String),
+    NoSession(String),
+    NoSuchObject(String),
+}
+
+impl ServiceError {
+    fn failed(e: impl std::fmt::Display) -> Self {
+        Self::ZBus(zbus::fdo::Error::Failed(e.to_string()).into())
+    }
+
+    fn invalid_args(e: impl std::fmt::Display) -> Self {
+        Self::ZBus(zbus::fdo::Error::InvalidArgs(e.to_string()).into())
+    }
+
+    fn locked() -> Self {
+        Self::IsLocked("Keyring is locked".to_string())
+    }
+}
+
+type Result<T> = std::result::Result<T, ServiceError>;
+
+/// A secret as it crosses the bus: `(oayays)`
+#[derive(Debug, serde::Serialize, serde::Deserialize, Type)]
+pub struct SecretValue {
+    pub session: OwnedObjectPath,
+    pub parameters: Vec<u8>,
+    pub value: Vec<u8>,
+    pub content_type: String,
+}
+
+/// A client's transfer session
+struct TransferSession {
+    /// Unique bus name of the client that opened it
+    owner: String,
+    transfer: Transfer,
+}
+
+/// Objects currently exported, by path
+#[derive(Default)]
+struct Registry {
+    generation: Option<u64>,
+    collections: BTreeMap<String, String>,
+    items: BTreeMap<String, (String, String)>,
+    /// Alias -> collection name
+    aliases: BTreeMap<String, String>,
+}
+
+/// State shared by every exported object
+pub struct SecretService {
+    state: Arc<RwLock<CipherState>>,
+    sessions: std::sync::Mutex<HashMap<String, TransferSession>>,
+    registry: Mutex<Registry>,
+    next_object: AtomicU64,
+}
+
+/// Serve the Secret Service on the session bus until the connection drops
+pub async fn serve(state: Arc<RwLock<CipherState>>) -> anyhow::Result<()> {
+    let service = Arc::new(SecretService {
+        state,
+        sessions: std::sync::Mutex::new(HashMap::new()),
+        registry: Mutex::new(Registry::default()),
+        next_object: AtomicU64::new(1),
+    });
+
+    let connection = zbus::connection::Builder::session()?
+        .serve_at(SERVICE_PATH, ServiceObject::new(service.clone()))?
+        .build()
+        .await?;
+    service.sync(&connection).await?;
+    connection.request_name(BUS_NAME).await?;
+    info!("Secret Service available as {}", BUS_NAME);
+
+    let dbus = zbus::fdo::DBusProxy::new(&connection).await?;
+    let mut owner_changes = dbus.receive_name_owner_changed().await?;
+    let mut interval = tokio::time::interval(SYNC_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                if let Err(e) = service.sync(&connection).await {
+                    warn!("Secret Service sync failed: {}", e);
+                }
+            }
+            change = owner_changes.next() => {
+                let Some(change) = change else { break };
+                let Ok(args) = change.args() else { continue };
+                // Sessions end with the client that opened them
+                if args.new_owner().is_none() {
+                    service.close_sessions_of(&connection, args.name().as_str()).await;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl SecretService {
+    fn next_path(&self, kind: &str) -> OwnedObjectPath {
+        let n = self.next_object.fetch_add(1, Ordering::Relaxed);
+        object_path(format!("{}/{}/{}", SERVICE_PATH, kind, n))
+    }
+
+    /// Collection name behind a collection or alias path
+    async fn collection_at(&self, path: &str) -> Result<String> {
+        let registry = self.registry.lock().await;
+        if let Some(name) = registry.collections.get(path) {
+            return Ok(name.clone());
+        }
+        path.strip_prefix(&format!("{}/aliases/", SERVICE_PATH))
+            .and_then(|alias| registry.aliases.get(alias))
+            .cloned()
+            .ok_or_else(|| ServiceError::NoSuchObject(path.to_string()))
+    }
+
+    /// (collection, id) of an item path
+    async fn item_at(&self, path: &str) -> Option<(String, String)> {
+        self.registry.lock().await.items.get(path).cloned()
+    }
+
+    /// Package a secret for `session`
+    fn encode(&self, session: &OwnedObjectPath, secret: &Secret, content_type: &str) -> Result<SecretValue> {
+        let sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        let open = sessions
+            .get(session.as_str())
+            .ok_or_else(|| ServiceError::NoSession(session.to_string()))?;
+
+        let (parameters, value) = open.transfer.encrypt(secret.as_bytes()).map_err(ServiceError::failed)?;
+        Ok(SecretValue {
+            session: session.clone(),
+            parameters,
+            value,
+            content_type: content_type.to_string(),
+        })
+    }
+
+    /// Unpack a secret a client sent
+    fn decode(&self, secret: &SecretValue) -> Result<Secret> {
+        let sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        let open = sessions
+            .get(secret.session.as_str())
+            .ok_or_else(|| ServiceError::NoSession(secret.session.to_string()))?;
+
+        let plain = open
+            .transfer
+            .decrypt(&secret.parameters, &secret.value)
+            .map_err(ServiceError::invalid_args)?;
+        Ok(Secret::new(plain))
+    }
+
+    fn open_session(&self, path: &OwnedObjectPath, owner: String, transfer: Transfer) {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.insert(path.to_string(), TransferSession { owner, transfer });
+    }
+
+    /// Forget a session; true if it was open
+    fn close_session(&self, path: &str) -> bool {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.remove(path).is_some()
+    }
+
+    async fn close_sessions_of(&self, connection: &Connection, owner: &str) {
+        let paths: Vec<String> = {
+            let sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+            sessions
+                .iter()
+                .filter(|(_, s)| s.owner == owner)
+                .map(|(path, _)| path.clone())
+                .collect()
+        };
+
+        for path in paths {
+            self.close_session(&path);
+            let _ = connection
+                .object_server()
+                .remove::<objects::SessionObject, _>(path.as_str())
+                .await;
+            debug!("Closed Secret Service session {} of {}", path, owner);
+        }
+    }
+
+    /// Bring exported objects in line with the keyring, announcing what
+    /// appeared and disappeared
+    async fn sync(self: &Arc<Self>, connection: &Connection) -> zbus::Result<()> {
+        let mut registry = self.registry.lock().await;
+
+        let (generation, collections, items, aliases) = {
+            let state = self.state.read().await;
+            let keyring = &state.keyring;
+            if registry.generation == Some(keyring.generation()) {
+                return Ok(());
+            }
+
+            let mut collections = BTreeMap::new();
+            let mut items = BTreeMap::new();
+            for collection in keyring.list_collections() {
+                collections.insert(collection_path(&collection.name).to_string(), collection.name.clone());
+                for item in keyring.list_items(&collection.name).unwrap_or_default() {
+                    items.insert(
+                        item_path(&collection.name, &item.id).to_string(),
+                        (collection.name.clone(), item.id.clone()),
+                    );
+                }
+            }
+            let aliases: BTreeMap<String, String> = keyring.aliases().into_iter().collect();
+            (keyring.generation(), collections, items, aliases)
+        };
+
+        let server = connection.object_server();
+        // Nobody can be listening for the initial export
+        let announce = registry.generation.is_some();
+
+        for (path, (collection, _)) in &registry.items {
+            if items.contains_key(path) {
+                continue;
+            }
+            server.remove::<ItemObject, _>(path.as_str()).await?;
+            if announce && collections.values().any(|name| name == collection) {
+                let emitter = SignalEmitter::new(connection, collection_path(collection))?;
+                CollectionObject::item_deleted(&emitter, object_path(path.clone())).await?;
+            }
+        }
+
+        for (alias, name) in &registry.aliases {
+            if aliases.get(alias) != Some(name) {
+                server.remove::<CollectionObject, _>(alias_path(alias)).await?;
+            }
+        }
+
+        let service = SignalEmitter::new(connection, SERVICE_PATH)?;
+        for path in registry.collections.keys() {
+            if collections.contains_key(path) {
+                continue;
+            }
+            server.remove::<CollectionObject, _>(path.as_str()).await?;
+            if announce {
+                ServiceObject::collection_deleted(&service, object_path(path.clone())).await?;
+            }
+        }
+
+        for (path, name) in &collections {
+            if registry.collections.contains_key(path) {
+                continue;
+            }
+            server.at(path.as_str(), CollectionObject::new(self.clone(), name.clone())).await?;
+            if announce {
+                ServiceObject::collection_created(&service, object_path(path.clone())).await?;
+            }
+        }
+
+        for (alias, name) in &aliases {
+            if registry.aliases.get(alias) != Some(name) {
+                server.at(alias_path(alias), CollectionObject::new(self.clone(), name.clone())).await?;
+            }
+        }
+
+        for (path, (collection, id)) in &items {
+            if registry.items.contains_key(path) {
+                continue;
+            }
+            server
+                .at(path.as_str(), ItemObject::new(self.clone(), collection.clone(), id.clone()))
+                .await?;
+            if announce {
+                let emitter = SignalEmitter::new(connection, collection_path(collection))?;
+                CollectionObject::item_created(&emitter, object_path(path.clone())).await?;
+            }
+        }
+
+        *registry = Registry {
+            generation: Some(generation),
+            collections,
+            items,
+            aliases,
+        };
+        Ok(())
+    }
+}
+
+fn collection_path(name: &str) -> OwnedObjectPath {
+    object_path(format!("{}/collection/{}", SERVICE_PATH, escape(name)))
+}
+
+fn item_path(collection: &str, id: &str) -> OwnedObjectPath {
+    object_path(format!("{}/collection/{}/{}", SERVICE_PATH, escape(collection), escape(id)))
+}
+
+fn alias_path(alias: &str) -> OwnedObjectPath {
+    object_path(format!("{}/aliases/{}", SERVICE_PATH, escape(alias)))
+}
+
+/// "/" stands for "no prompt needed" and "no such object"
+fn no_object() -> OwnedObjectPath {
+    object_path("/".to_string())
+}
+
+fn object_path(path: String) -> OwnedObjectPath {
+    OwnedObjectPath::try_from(path).expect("object path elements are escaped")
+}
+
+/// Object path elements only allow `[A-Za-z0-9_]`; anything else becomes
+/// `_xx`
+fn escape(name: &str) -> String {
+    if name.is_empty() {
+        return "_".to_string();
+    }
+    name.bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() {
+                (b as char).to_string()
+            } else {
+                format!("_{:02x}", b)
+            }
+        })
+        .collect()
+}
+
+/// A string property from a property dict
+fn string_property(properties: &HashMap<String, OwnedValue>, key: &str) -> Result<Option<String>> {
+    properties
+        .get(key)
+        .map(|value| {
+            value
+                .downcast_ref::<&str>()
+                .map(str::to_string)
+                .map_err(|_| ServiceError::invalid_args(format!("{} must be a string", key)))
+        })
+        .transpose()
+}
+
+/// The attributes property from a property dict
+fn attributes_property(properties: &HashMap<String, OwnedValue>) -> Result<Option<HashMap<String, String>>> {
+    properties
+        .get(ITEM_ATTRIBUTES)
+        .map(|value| {
+            value
+                .try_clone()
+                .ok()
+                .and_then(|value| HashMap::<String, String>::try_from(value).ok())
+                .ok_or_else(|| ServiceError::invalid_args(format!("{} must be a{{ss}}", ITEM_ATTRIBUTES)))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_keeps_paths_valid() {
+        assert_eq!(escape("default"), "default");
+        assert_eq!(escape("my keys"), "my_20keys");
+        assert_eq!(escape("a_b"), "a_5fb");
+        assert_eq!(escape(""), "_");
+        assert_eq!(item_path("login", "wifi/home").as_str(), "/org/freedesktop/secrets/collection/login/wifi_2fhome");
+    }
+}
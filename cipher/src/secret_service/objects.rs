@@ -0,0 +1,570 @@
+//! Exported Secret Service objects
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use rand::RngCore;
+use tokio::sync::Notify;
+use tracing::info;
+use zbus::message::Header;
+use zbus::object_server::{ObjectServer, SignalEmitter};
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::{interface, Connection};
+
+use super::transfer::{Transfer, TransferError};
+use super::{
+    attributes_property, collection_path, item_path, no_object, string_property, SecretService,
+    Result, SecretValue, ServiceError, COLLECTION_LABEL, ITEM_LABEL,
+};
+use crate::keyring::SearchAttributes;
+
+/// How long an unlock prompt waits for the keyring
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// `org.freedesktop.Secret.Service` at `/org/freedesktop/secrets`
+pub struct ServiceObject {
+    service: Arc<SecretService>,
+}
+
+impl ServiceObject {
+    pub fn new(service: Arc<SecretService>) -> Self {
+        Self { service }
+    }
+}
+
+#[interface(name = "org.freedesktop.Secret.Service")]
+impl ServiceObject {
+    #[zbus(out_args("output", "result"))]
+    async fn open_session(
+        &self,
+        algorithm: &str,
+        input: OwnedValue,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> Result<(OwnedValue, OwnedObjectPath)> {
+        let input = match algorithm {
+            super::transfer::PLAIN => Vec::new(),
+            _ => Vec::<u8>::try_from(input).map_err(ServiceError::invalid_args)?,
+        };
+
+        let (transfer, output) = Transfer::negotiate(algorithm, &input).map_err(|e| match e {
+            // libsecret falls back to plain on the standard error
+            TransferError::Unsupported(_) => {
+                ServiceError::ZBus(zbus::fdo::Error::NotSupported(e.to_string()).into())
+            }
+            e => ServiceError::invalid_args(e),
+        })?;
+        let output = if transfer.is_plain() { Value::from("") } else { Value::from(output) };
+
+        let path = self.service.next_path("session");
+        let owner = header.sender().map(|s| s.to_string()).unwrap_or_default();
+        self.service.open_session(&path, owner, transfer);
+        server
+            .at(&path, SessionObject { service: self.service.clone(), path: path.clone() })
+            .await?;
+
+        Ok((output.try_into().map_err(zbus::Error::from)?, path))
+    }
+
+    #[zbus(out_args("collection", "prompt"))]
+    async fn create_collection(
+        &self,
+        properties: HashMap<String, OwnedValue>,
+        alias: &str,
+        #[zbus(connection)] connection: &Connection,
+    ) -> Result<(OwnedObjectPath, OwnedObjectPath)> {
+        let label = string_property(&properties, COLLECTION_LABEL)?.unwrap_or_default();
+
+        let name = {
+            let mut state = self.service.state.write().await;
+            let keyring = &mut state.keyring;
+
+            if !alias.is_empty() {
+                if let Some(existing) = keyring.alias(alias) {
+                    return Ok((collection_path(existing), no_object()));
+                }
+            }
+
+            let base = if alias.is_empty() { collection_name(&label) } else { collection_name(alias) };
+            let mut name = base.clone();
+            let mut n = 1;
+            while keyring.get_collection(&name).is_some() {
+                n += 1;
+                name = format!("{}-{}", base, n);
+            }
+
+            keyring.create_collection(&name, &label).map_err(ServiceError::failed)?;
+            if !alias.is_empty() {
+                keyring.set_alias(alias, Some(&name)).map_err(ServiceError::failed)?;
+            }
+            name
+        };
+
+        self.service.sync(connection).await?;
+        info!("Secret Service created collection {}", name);
+        Ok((collection_path(&name), no_object()))
+    }
+
+    #[zbus(out_args("unlocked", "locked"))]
+    async fn search_items(
+        &self,
+        attributes: HashMap<String, String>,
+    ) -> Result<(Vec<OwnedObjectPath>, Vec<OwnedObjectPath>)> {
+        let state = self.service.state.read().await;
+        let keyring = &state.keyring;
+        let attrs = SearchAttributes { attributes };
+
+        let mut found = Vec::new();
+        for collection in keyring.list_collections() {
+            for item in keyring.search(&collection.name, &attrs).unwrap_or_default() {
+                found.push(item_path(&collection.name, &item.id));
+            }
+        }
+
+        if keyring.is_unlocked() {
+            Ok((found, Vec::new()))
+        } else {
+            Ok((Vec::new(), found))
+        }
+    }
+
+    #[zbus(out_args("unlocked", "prompt"))]
+    async fn unlock(
+        &self,
+        objects: Vec<OwnedObjectPath>,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> Result<(Vec<OwnedObjectPath>, OwnedObjectPath)> {
+        if self.service.state.read().await.keyring.is_unlocked() {
+            return Ok((objects, no_object()));
+        }
+
+        let path = self.service.next_path("prompt");
+        let prompt = PromptObject {
+            service: self.service.clone(),
+            path: path.clone(),
+            objects,
+            dismissed: Arc::new(Notify::new()),
+        };
+        server.at(&path, prompt).await?;
+        Ok((Vec::new(), path))
+    }
+
+    #[zbus(out_args("locked", "Prompt"))]
+    async fn lock(&self, objects: Vec<OwnedObjectPath>) -> Result<(Vec<OwnedObjectPath>, OwnedObjectPath)> {
+        // Collections share the keyring's key, so locking any locks all
+        self.service.state.write().await.keyring.lock();
+        Ok((objects, no_object()))
+    }
+
+    async fn get_secrets(
+        &self,
+        items: Vec<OwnedObjectPath>,
+        session: OwnedObjectPath,
+    ) -> Result<HashMap<OwnedObjectPath, SecretValue>> {
+        let mut secrets = HashMap::new();
+        for path in items {
+            let Some((collection, id)) = self.service.item_at(path.as_str()).await else {
+                continue;
+            };
+
+            let state = self.service.state.read().await;
+            if !state.keyring.is_unlocked() {
+                return Err(ServiceError::locked());
+            }
+            let (Ok(secret), Ok(item)) = (
+                state.keyring.get_secret(&collection, &id),
+                state.keyring.get_item(&collection, &id),
+            ) else {
+                continue;
+            };
+            let value = self.service.encode(&session, &secret, &item.content_type)?;
+            secrets.insert(path, value);
+        }
+        Ok(secrets)
+    }
+
+    async fn read_alias(&self, name: &str) -> OwnedObjectPath {
+        let state = self.service.state.read().await;
+        state.keyring.alias(name).map(collection_path).unwrap_or_else(no_object)
+    }
+
+    async fn set_alias(
+        &self,
+        name: &str,
+        collection: OwnedObjectPath,
+        #[zbus(connection)] connection: &Connection,
+    ) -> Result<()> {
+        let target = match collection.as_str() {
+            "/" => None,
+            path => Some(self.service.collection_at(path).await?),
+        };
+
+        self.service
+            .state
+            .write()
+            .await
+            .keyring
+            .set_alias(name, target.as_deref())
+            .map_err(ServiceError::failed)?;
+        self.service.sync(connection).await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    async fn collections(&self) -> Vec<OwnedObjectPath> {
+        let state = self.service.state.read().await;
+        state.keyring.list_collections().iter().map(|c| collection_path(&c.name)).collect()
+    }
+
+    #[zbus(signal)]
+    pub async fn collection_created(emitter: &SignalEmitter<'_>, collection: OwnedObjectPath) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    pub async fn collection_deleted(emitter: &SignalEmitter<'_>, collection: OwnedObjectPath) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    pub async fn collection_changed(emitter: &SignalEmitter<'_>, collection: OwnedObjectPath) -> zbus::Result<()>;
+}
+
+/// `org.freedesktop.Secret.Collection`, also exported at alias paths
+pub struct CollectionObject {
+    service: Arc<SecretService>,
+    name: String,
+}
+
+impl CollectionObject {
+    pub fn new(service: Arc<SecretService>, name: String) -> Self {
+        Self { service, name }
+    }
+}
+
+#[interface(name = "org.freedesktop.Secret.Collection")]
+impl CollectionObject {
+    async fn delete(&self, #[zbus(connection)] connection: &Connection) -> Result<OwnedObjectPath> {
+        {
+            let mut state = self.service.state.write().await;
+            state.keyring.delete_collection(&self.name).map_err(ServiceError::failed)?;
+            state.acl.forget(&self.name, None).map_err(ServiceError::failed)?;
+        }
+        self.service.sync(connection).await?;
+        Ok(no_object())
+    }
+
+    async fn search_items(&self, attributes: HashMap<String, String>) -> Result<Vec<OwnedObjectPath>> {
+        let state = self.service.state.read().await;
+        let attrs = SearchAttributes { attributes };
+        let items = state.keyring.search(&self.name, &attrs).map_err(ServiceError::failed)?;
+        Ok(items.iter().map(|item| item_path(&self.name, &item.id)).collect())
+    }
+
+    #[zbus(out_args("item", "prompt"))]
+    async fn create_item(
+        &self,
+        properties: HashMap<String, OwnedValue>,
+        secret: SecretValue,
+        replace: bool,
+        #[zbus(connection)] connection: &Connection,
+    ) -> Result<(OwnedObjectPath, OwnedObjectPath)> {
+        let plain = self.service.decode(&secret)?;
+        let label = string_property(&properties, ITEM_LABEL)?.unwrap_or_default();
+        let attributes = attributes_property(&properties)?.unwrap_or_default();
+
+        let id = {
+            let mut state = self.service.state.write().await;
+            let keyring = &mut state.keyring;
+            if !keyring.is_unlocked() {
+                return Err(ServiceError::locked());
+            }
+
+            // Replacing means "the item with exactly these attributes"
+            let existing = if replace {
+                keyring
+                    .list_items(&self.name)
+                    .map_err(ServiceError::failed)?
+                    .into_iter()
+                    .find(|item| item.attributes == attributes)
+                    .map(|item| item.id.clone())
+            } else {
+                None
+            };
+            let id = existing.unwrap_or_else(new_item_id);
+
+            keyring
+                .store_item(&self.name, &id, &label, &plain, attributes, &secret.content_type)
+                .map_err(ServiceError::failed)?;
+            id
+        };
+
+        self.service.sync(connection).await?;
+        Ok((item_path(&self.name, &id), no_object()))
+    }
+
+    #[zbus(property)]
+    async fn items(&self) -> Vec<OwnedObjectPath> {
+        let state = self.service.state.read().await;
+        state
+            .keyring
+            .list_items(&self.name)
+            .unwrap_or_default()
+            .iter()
+            .map(|item| item_path(&self.name, &item.id))
+            .collect()
+    }
+
+    #[zbus(property)]
+    async fn label(&self) -> String {
+        let state = self.service.state.read().await;
+        state.keyring.get_collection(&self.name).map(|c| c.label.clone()).unwrap_or_default()
+    }
+
+    #[zbus(property)]
+    async fn set_label(&self, label: String) -> zbus::Result<()> {
+        self.service
+            .state
+            .write()
+            .await
+            .keyring
+            .set_collection_label(&self.name, &label)
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()).into())
+    }
+
+    #[zbus(property)]
+    async fn locked(&self) -> bool {
+        !self.service.state.read().await.keyring.is_unlocked()
+    }
+
+    #[zbus(property)]
+    async fn created(&self) -> u64 {
+        let state = self.service.state.read().await;
+        state.keyring.get_collection(&self.name).map(|c| c.created.timestamp() as u64).unwrap_or_default()
+    }
+
+    #[zbus(property)]
+    async fn modified(&self) -> u64 {
+        let state = self.service.state.read().await;
+        state.keyring.get_collection(&self.name).map(|c| c.modified.timestamp() as u64).unwrap_or_default()
+    }
+
+    #[zbus(signal)]
+    pub async fn item_created(emitter: &SignalEmitter<'_>, item: OwnedObjectPath) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    pub async fn item_deleted(emitter: &SignalEmitter<'_>, item: OwnedObjectPath) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    pub async fn item_changed(emitter: &SignalEmitter<'_>, item: OwnedObjectPath) -> zbus::Result<()>;
+}
+
+/// `org.freedesktop.Secret.Item`
+pub struct ItemObject {
+    service: Arc<SecretService>,
+    collection: String,
+    id: String,
+}
+
+impl ItemObject {
+    pub fn new(service: Arc<SecretService>, collection: String, id: String) -> Self {
+        Self { service, collection, id }
+    }
+
+    async fn changed(&self, connection: &Connection) -> zbus::Result<()> {
+        let emitter = SignalEmitter::new(connection, collection_path(&self.collection))?;
+        CollectionObject::item_changed(&emitter, item_path(&self.collection, &self.id)).await
+    }
+}
+
+#[interface(name = "org.freedesktop.Secret.Item")]
+impl ItemObject {
+    async fn delete(&self, #[zbus(connection)] connection: &Connection) -> Result<OwnedObjectPath> {
+        {
+            let mut state = self.service.state.write().await;
+            state.keyring.delete_secret(&self.collection, &self.id).map_err(ServiceError::failed)?;
+            state.acl.forget(&self.collection, Some(&self.id)).map_err(ServiceError::failed)?;
+        }
+        self.service.sync(connection).await?;
+        Ok(no_object())
+    }
+
+    async fn get_secret(&self, session: OwnedObjectPath) -> Result<SecretValue> {
+        let state = self.service.state.read().await;
+        if !state.keyring.is_unlocked() {
+            return Err(ServiceError::locked());
+        }
+
+        let secret = state.keyring.get_secret(&self.collection, &self.id).map_err(ServiceError::failed)?;
+        let item = state.keyring.get_item(&self.collection, &self.id).map_err(ServiceError::failed)?;
+        self.service.encode(&session, &secret, &item.content_type)
+    }
+
+    async fn set_secret(
+        &self,
+        secret: SecretValue,
+        #[zbus(connection)] connection: &Connection,
+    ) -> Result<()> {
+        let plain = self.service.decode(&secret)?;
+        {
+            let mut state = self.service.state.write().await;
+            let keyring = &mut state.keyring;
+            if !keyring.is_unlocked() {
+                return Err(ServiceError::locked());
+            }
+
+            let item = keyring.get_item(&self.collection, &self.id).map_err(ServiceError::failed)?;
+            let (label, attributes) = (item.label.clone(), item.attributes.clone());
+            keyring
+                .store_item(&self.collection, &self.id, &label, &plain, attributes, &secret.content_type)
+                .map_err(ServiceError::failed)?;
+        }
+        self.changed(connection).await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    async fn locked(&self) -> bool {
+        !self.service.state.read().await.keyring.is_unlocked()
+    }
+
+    #[zbus(property)]
+    async fn attributes(&self) -> HashMap<String, String> {
+        let state = self.service.state.read().await;
+        state
+            .keyring
+            .get_item(&self.collection, &self.id)
+            .map(|item| item.attributes.clone())
+            .unwrap_or_default()
+    }
+
+    #[zbus(property)]
+    async fn set_attributes(&self, attributes: HashMap<String, String>) -> zbus::Result<()> {
+        self.service
+            .state
+            .write()
+            .await
+            .keyring
+            .update_item(&self.collection, &self.id, None, Some(attributes))
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()).into())
+    }
+
+    #[zbus(property)]
+    async fn label(&self) -> String {
+        let state = self.service.state.read().await;
+        state
+            .keyring
+            .get_item(&self.collection, &self.id)
+            .map(|item| item.label.clone())
+            .unwrap_or_default()
+    }
+
+    #[zbus(property)]
+    async fn set_label(&self, label: String) -> zbus::Result<()> {
+        self.service
+            .state
+            .write()
+            .await
+            .keyring
+            .update_item(&self.collection, &self.id, Some(&label), None)
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()).into())
+    }
+
+    #[zbus(property)]
+    async fn created(&self) -> u64 {
+        let state = self.service.state.read().await;
+        state
+            .keyring
+            .get_item(&self.collection, &self.id)
+            .map(|item| item.created.timestamp() as u64)
+            .unwrap_or_default()
+    }
+
+    #[zbus(property)]
+    async fn modified(&self) -> u64 {
+        let state = self.service.state.read().await;
+        state
+            .keyring
+            .get_item(&self.collection, &self.id)
+            .map(|item| item.modified.timestamp() as u64)
+            .unwrap_or_default()
+    }
+}
+
+/// `org.freedesktop.Secret.Session`
+pub struct SessionObject {
+    service: Arc<SecretService>,
+    path: OwnedObjectPath,
+}
+
+#[interface(name = "org.freedesktop.Secret.Session")]
+impl SessionObject {
+    async fn close(&self, #[zbus(object_server)] server: &ObjectServer) -> zbus::fdo::Result<()> {
+        self.service.close_session(self.path.as_str());
+        server.remove::<SessionObject, _>(&self.path).await?;
+        Ok(())
+    }
+}
+
+/// `org.freedesktop.Secret.Prompt` for an unlock
+pub struct PromptObject {
+    service: Arc<SecretService>,
+    path: OwnedObjectPath,
+    objects: Vec<OwnedObjectPath>,
+    dismissed: Arc<Notify>,
+}
+
+#[interface(name = "org.freedesktop.Secret.Prompt")]
+impl PromptObject {
+    async fn prompt(&self, _window_id: &str, #[zbus(connection)] connection: &Connection) -> zbus::fdo::Result<()> {
+        info!("Secret Service unlock requested; waiting for the keyring to be unlocked");
+
+        let service = self.service.clone();
+        let connection = connection.clone();
+        let path = self.path.clone();
+        let objects = self.objects.clone();
+        let dismissed = self.dismissed.clone();
+
+        tokio::spawn(async move {
+            let unlocked = tokio::select! {
+                unlocked = tokio::time::timeout(PROMPT_TIMEOUT, wait_unlocked(&service)) => unlocked.is_ok(),
+                _ = dismissed.notified() => false,
+            };
+
+            let result = if unlocked { objects } else { Vec::new() };
+            if let Ok(emitter) = SignalEmitter::new(&connection, &path) {
+                let _ = PromptObject::completed(&emitter, !unlocked, Value::from(result)).await;
+            }
+            let _ = connection.object_server().remove::<PromptObject, _>(&path).await;
+        });
+
+        Ok(())
+    }
+
+    async fn dismiss(&self) {
+        self.dismissed.notify_one();
+    }
+
+    #[zbus(signal)]
+    async fn completed(emitter: &SignalEmitter<'_>, dismissed: bool, result: Value<'_>) -> zbus::Result<()>;
+}
+
+async fn wait_unlocked(service: &SecretService) {
+    while !service.state.read().await.keyring.is_unlocked() {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// Keyring name for a collection created by label or alias
+fn collection_name(label: &str) -> String {
+    let name: String = label
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let name = name.trim_matches('-');
+    if name.is_empty() { "collection".to_string() } else { name.to_string() }
+}
+
+fn new_item_id() -> String {
+    let mut bytes = [0u8; 8];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
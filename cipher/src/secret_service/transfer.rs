@@ -0,0 +1,231 @@
+//! Secret transfer algorithms
+//!
+//! Secret Service clients pick how secrets cross the bus when they open a
+//! session. `plain` sends them as-is. `dh-ietf1024-sha256-aes128-cbc-pkcs7`
+//! agrees on a key over the 1024-bit MODP group of RFC 2409, stretches the
+//! shared secret with HKDF-SHA256 into an AES-128 key and sends each secret
+//! CBC-encrypted with a fresh IV in the parameters field.
+
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::md::Md;
+use openssl::pkey::Id;
+use openssl::pkey_ctx::PkeyCtx;
+use openssl::symm::{self, Cipher};
+use rand::RngCore;
+use thiserror::Error;
+use zeroize::{ZeroizeOnDrop, Zeroizing};
+
+pub const PLAIN: &str = "plain";
+pub const DH_AES: &str = "dh-ietf1024-sha256-aes128-cbc-pkcs7";
+
+/// Bytes in a group element
+const PRIME_BYTES: i32 = 128;
+
+/// Transfer errors
+#[derive(Error, Debug)]
+pub enum TransferError {
+    #[error("unsupported algorithm: {0}")]
+    Unsupported(String),
+
+    #[error("invalid public key")]
+    InvalidPublicKey,
+
+    #[error("invalid secret parameters: expected a {expected}-byte IV")]
+    InvalidParameters { expected: usize },
+
+    #[error("secret could not be decrypted")]
+    Decryption,
+
+    #[error("crypto failure: {0}")]
+    Crypto(#[from] openssl::error::ErrorStack),
+}
+
+type Result<T> = std::result::Result<T, TransferError>;
+
+/// How secrets are protected in one session
+#[derive(ZeroizeOnDrop)]
+pub enum Transfer {
+    Plain,
+    Aes { key: [u8; 16] },
+}
+
+impl Transfer {
+    /// Set up `algorithm` from the client's input, returning the transfer
+    /// and the service's output (our public key for DH, empty for plain)
+    pub fn negotiate(algorithm: &str, input: &[u8]) -> Result<(Self, Vec<u8>)> {
+        match algorithm {
+            PLAIN => Ok((Self::Plain, Vec::new())),
+            DH_AES => {
+                let (public, shared) = dh_agree(input)?;
+                let key = derive_key(&shared)?;
+                Ok((Self::Aes { key }, public))
+            }
+            other => Err(TransferError::Unsupported(other.to_string())),
+        }
+    }
+
+    pub fn is_plain(&self) -> bool {
+        matches!(self, Self::Plain)
+    }
+
+    /// Protect a secret for sending: (parameters, value)
+    pub fn encrypt(&self, secret: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        match self {
+            Self::Plain => Ok((Vec::new(), secret.to_vec())),
+            Self::Aes { key } => {
+                let mut iv = [0u8; 16];
+                rand::thread_rng().fill_bytes(&mut iv);
+                let value = symm::encrypt(Cipher::aes_128_cbc(), key, Some(&iv), secret)?;
+                Ok((iv.to_vec(), value))
+            }
+        }
+    }
+
+    /// Recover a secret a client sent
+    pub fn decrypt(&self, parameters: &[u8], value: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Plain => Ok(value.to_vec()),
+            Self::Aes { key } => {
+                if parameters.len() != 16 {
+                    return Err(TransferError::InvalidParameters { expected: 16 });
+                }
+                symm::decrypt(Cipher::aes_128_cbc(), key, Some(parameters), value)
+                    .map_err(|_| TransferError::Decryption)
+            }
+        }
+    }
+}
+
+/// Our public key and the shared secret for the client's public key
+fn dh_agree(client_public: &[u8]) -> Result<(Vec<u8>, Zeroizing<Vec<u8>>)> {
+    let prime = BigNum::get_rfc2409_prime_1024()?;
+    let generator = BigNum::from_u32(2)?;
+    let one = BigNum::from_u32(1)?;
+    let mut ctx = BigNumContext::new()?;
+
+    let mut upper = BigNum::new()?;
+    upper.checked_sub(&prime, &one)?;
+
+    // 1 < y < p - 1, or the shared secret is guessable
+    let theirs = BigNum::from_slice(client_public)?;
+    if theirs <= one || theirs >= upper {
+        return Err(TransferError::InvalidPublicKey);
+    }
+
+    let mut private = BigNum::new()?;
+    upper.rand_range(&mut private)?;
+    let mut ours = BigNum::new()?;
+    ours.mod_exp(&generator, &private, &prime, &mut ctx)?;
+
+    let mut shared = BigNum::new()?;
+    shared.mod_exp(&theirs, &private, &prime, &mut ctx)?;
+    private.clear();
+
+    let secret = Zeroizing::new(shared.to_vec_padded(PRIME_BYTES)?);
+    shared.clear();
+    Ok((ours.to_vec_padded(PRIME_BYTES)?, secret))
+}
+
+/// HKDF-SHA256 without salt or info, as libsecret and gnome-keyring do
+fn derive_key(shared: &[u8]) -> Result<[u8; 16]> {
+    let mut ctx = PkeyCtx::new_id(Id::HKDF)?;
+    ctx.derive_init()?;
+    ctx.set_hkdf_md(Md::sha256())?;
+    ctx.set_hkdf_key(shared)?;
+
+    let mut key = [0u8; 16];
+    ctx.derive(Some(&mut key))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Client half of the exchange: (private, public)
+    fn client_keys() -> (BigNum, Vec<u8>) {
+        let prime = BigNum::get_rfc2409_prime_1024().unwrap();
+        let mut private = BigNum::new().unwrap();
+        prime.rand_range(&mut private).unwrap();
+        let mut public = BigNum::new().unwrap();
+        let mut ctx = BigNumContext::new().unwrap();
+        public.mod_exp(&BigNum::from_u32(2).unwrap(), &private, &prime, &mut ctx).unwrap();
+        (private, public.to_vec())
+    }
+
+    fn client_key(private: &BigNum, service_public: &[u8]) -> [u8; 16] {
+        let prime = BigNum::get_rfc2409_prime_1024().unwrap();
+        let theirs = BigNum::from_slice(service_public).unwrap();
+        let mut shared = BigNum::new().unwrap();
+        let mut ctx = BigNumContext::new().unwrap();
+        shared.mod_exp(&theirs, private, &prime, &mut ctx).unwrap();
+        derive_key(&shared.to_vec_padded(PRIME_BYTES).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_plain_passes_secret_through() {
+        let (transfer, output) = Transfer::negotiate(PLAIN, &[]).unwrap();
+        assert!(output.is_empty());
+
+        let (parameters, value) = transfer.encrypt(b"hunter2").unwrap();
+        assert!(parameters.is_empty());
+        assert_eq!(value, b"hunter2");
+        assert_eq!(transfer.decrypt(&parameters, &value).unwrap(), b"hunter2");
+    }
+
+    #[test]
+    fn test_dh_both_sides_agree() {
+        let (private, public) = client_keys();
+        let (transfer, output) = Transfer::negotiate(DH_AES, &public).unwrap();
+        assert_eq!(output.len(), PRIME_BYTES as usize);
+
+        let key = client_key(&private, &output);
+        let Transfer::Aes { key: service_key } = &transfer else {
+            panic!("expected an AES transfer");
+        };
+        assert_eq!(&key, service_key);
+
+        // What the client sends, the service reads back
+        let iv = [7u8; 16];
+        let sent = symm::encrypt(Cipher::aes_128_cbc(), &key, Some(&iv), b"s3cret").unwrap();
+        assert_eq!(transfer.decrypt(&iv, &sent).unwrap(), b"s3cret");
+
+        // And the other way round
+        let (parameters, value) = transfer.encrypt(b"s3cret").unwrap();
+        let received = symm::decrypt(Cipher::aes_128_cbc(), &key, Some(&parameters), &value).unwrap();
+        assert_eq!(received, b"s3cret");
+    }
+
+    #[test]
+    fn test_dh_rejects_degenerate_public_keys() {
+        let prime = BigNum::get_rfc2409_prime_1024().unwrap();
+        let mut upper = BigNum::new().unwrap();
+        upper.checked_sub(&prime, &BigNum::from_u32(1).unwrap()).unwrap();
+
+        for public in [vec![0u8], vec![1u8], upper.to_vec(), prime.to_vec()] {
+            assert!(matches!(
+                Transfer::negotiate(DH_AES, &public),
+                Err(TransferError::InvalidPublicKey)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_aes_rejects_bad_parameters() {
+        let (_, public) = client_keys();
+        let (transfer, _) = Transfer::negotiate(DH_AES, &public).unwrap();
+
+        assert!(matches!(
+            transfer.decrypt(&[0u8; 8], &[0u8; 16]),
+            Err(TransferError::InvalidParameters { expected: 16 })
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_algorithm() {
+        assert!(matches!(
+            Transfer::negotiate("rot13", &[]),
+            Err(TransferError::Unsupported(_))
+        ));
+    }
+}
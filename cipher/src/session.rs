@@ -14,6 +14,9 @@ pub enum SessionError {
 
     #[error("session expired after {timeout_secs}s of inactivity")]
     SessionExpired { timeout_secs: u64 },
+
+    #[error("session belongs to another user")]
+    WrongUser,
 }
 
 type Result<T> = std::result::Result<T, SessionError>;
@@ -44,6 +47,7 @@ impl std::fmt::Display for SessionToken {
 #[derive(Debug)]
 pub struct Session {
     pub token: SessionToken,
+    pub client_uid: Option<u32>,
     pub client_pid: Option<u32>,
     pub client_exe: Option<String>,
     pub created: Instant,
@@ -57,6 +61,7 @@ impl Session {
         let now = Instant::now();
         Self {
             token: SessionToken::generate(),
+            client_uid: None,
             client_pid: None,
             client_exe: None,
             created: now,
@@ -94,8 +99,9 @@ impl SessionManager {
     }
 
     /// Create a new session
-    pub fn create_session(&mut self, pid: Option<u32>, exe: Option<String>) -> SessionToken {
+    pub fn create_session(&mut self, uid: Option<u32>, pid: Option<u32>, exe: Option<String>) -> SessionToken {
         let mut session = Session::new(self.default_timeout);
+        session.client_uid = uid;
         session.client_pid = pid;
         session.client_exe = exe;
 
@@ -127,6 +133,15 @@ impl SessionManager {
         Ok(session)
     }
 
+    /// Validate a session for the user presenting it
+    pub fn validate_for(&mut self, token: &str, uid: u32) -> Result<&mut Session> {
+        let session = self.validate(token)?;
+        if session.client_uid.is_some_and(|owner| owner != uid) {
+            return Err(SessionError::WrongUser);
+        }
+        Ok(session)
+    }
+
     /// Close a session
     pub fn close_session(&mut self, token: &str) {
         self.sessions.remove(token);
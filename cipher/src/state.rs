@@ -1,5 +1,6 @@
 //! Cipher daemon state
 
+use crate::acl::AccessControl;
 use crate::keyring::Keyring;
 use crate::session::SessionManager;
 
@@ -7,5 +8,6 @@ use crate::session::SessionManager;
 pub struct CipherState {
     pub keyring: Keyring,
     pub sessions: SessionManager,
+    pub acl: AccessControl,
     pub data_dir: String,
 }
@@ -0,0 +1,142 @@
+//! Action callback routing
+//!
+//! When a notification's action is invoked the event is delivered back to
+//! whoever can act on it: the IPC connection that posted the notification
+//! (if it is still open) and the handler command registered for the app in
+//! the herald config. Clients may attach a correlation token when posting so
+//! they can match events to their own state. D-Bus clients are covered by
+//! the `ActionInvoked` signal instead.
+
+use crate::config::ActionConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::sync::{mpsc, Mutex};
+use tracing::{debug, info, warn};
+
+/// Routes older than this are dropped
+const ROUTE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// An invoked action, as delivered to clients and handlers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionEvent {
+    pub id: u32,
+    pub action_id: String,
+    pub app_name: String,
+    /// Correlation token supplied when the notification was posted
+    pub token: Option<String>,
+}
+
+/// Where to deliver a notification's actions
+struct Route {
+    app_name: String,
+    token: Option<String>,
+    origin: Option<mpsc::Sender<ActionEvent>>,
+    registered: Instant,
+}
+
+/// Action callback router
+pub struct CallbackRouter {
+    handlers: HashMap<String, String>,
+    routes: Mutex<HashMap<u32, Route>>,
+}
+
+impl CallbackRouter {
+    pub fn new(config: &ActionConfig) -> Self {
+        Self {
+            handlers: config
+                .handlers
+                .iter()
+                .map(|h| (h.app_name.to_lowercase(), h.command.clone()))
+                .collect(),
+            routes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Remember who to notify when an action on `id` is invoked
+    pub async fn register(
+        &self,
+        id: u32,
+        app_name: &str,
+        token: Option<String>,
+        origin: Option<mpsc::Sender<ActionEvent>>,
+    ) {
+        let mut routes = self.routes.lock().await;
+        routes.retain(|_, r| r.registered.elapsed() < ROUTE_TTL);
+        routes.insert(
+            id,
+            Route {
+                app_name: app_name.to_string(),
+                token,
+                origin,
+                registered: Instant::now(),
+            },
+        );
+    }
+
+    /// Forget a notification, e.g. once it has closed
+    pub async fn forget(&self, id: u32) {
+        self.routes.lock().await.remove(&id);
+    }
+
+    /// Deliver an invoked action to the originating client and app handler
+    pub async fn deliver(&self, id: u32, action_id: &str, app_name: Option<&str>) {
+        let (event, origin) = {
+            let routes = self.routes.lock().await;
+            let route = routes.get(&id);
+            let Some(app_name) = route.map(|r| r.app_name.as_str()).or(app_name) else {
+                debug!("No route for action on notification {}", id);
+                return;
+            };
+
+            let event = ActionEvent {
+                id,
+                action_id: action_id.to_string(),
+                app_name: app_name.to_string(),
+                token: route.and_then(|r| r.token.clone()),
+            };
+            (event, route.and_then(|r| r.origin.clone()))
+        };
+
+        if let Some(origin) = origin {
+            if origin.send(event.clone()).await.is_err() {
+                debug!("Client for notification {} has disconnected", id);
+            }
+        }
+
+        if let Some(command) = self.handlers.get(&event.app_name.to_lowercase()) {
+            self.run_handler(command, &event);
+        }
+    }
+
+    /// Run a registered handler command with the event in its environment
+    fn run_handler(&self, command: &str, event: &ActionEvent) {
+        let spawned = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .env("HERALD_NOTIFICATION_ID", event.id.to_string())
+            .env("HERALD_ACTION", &event.action_id)
+            .env("HERALD_APP", &event.app_name)
+            .env("HERALD_TOKEN", event.token.as_deref().unwrap_or(""))
+            .kill_on_drop(false)
+            .spawn();
+
+        match spawned {
+            Ok(mut child) => {
+                info!("Running action handler for {}", event.app_name);
+                let app_name = event.app_name.clone();
+                tokio::spawn(async move {
+                    match child.wait().await {
+                        Ok(status) if !status.success() => {
+                            warn!("Action handler for {} exited with {}", app_name, status)
+                        }
+                        Err(e) => warn!("Action handler for {} failed: {}", app_name, e),
+                        _ => {}
+                    }
+                });
+            }
+            Err(e) => warn!("Failed to start action handler for {}: {}", event.app_name, e),
+        }
+    }
+}
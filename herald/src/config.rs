@@ -1,5 +1,6 @@
 //! Herald configuration
 
+use crate::notification::Urgency;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -14,6 +15,14 @@ pub struct HeraldConfig {
     pub dnd: DndConfig,
     #[serde(default)]
     pub sounds: SoundConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub policies: PolicyConfig,
+    #[serde(default)]
+    pub reminders: ReminderConfig,
+    #[serde(default)]
+    pub actions: ActionConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +62,9 @@ pub struct HistoryConfig {
     pub max_size: usize,
     #[serde(default = "default_retention")]
     pub retention_days: u32,
+    /// Per-app retention overriding the limits above
+    #[serde(default)]
+    pub apps: Vec<HistoryRetention>,
 }
 
 impl Default for HistoryConfig {
@@ -61,10 +73,24 @@ impl Default for HistoryConfig {
             enabled: true,
             max_size: default_history_size(),
             retention_days: default_retention(),
+            apps: Vec::new(),
         }
     }
 }
 
+/// History retention for one application ("*" matches any app without
+/// its own entry)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryRetention {
+    pub app_name: String,
+    /// Days the app's entries are kept (default: `retention_days`)
+    #[serde(default)]
+    pub retention_days: Option<u32>,
+    /// Most entries kept for the app, newest first
+    #[serde(default)]
+    pub max_entries: Option<usize>,
+}
+
 fn default_history_size() -> usize { 1000 }
 fn default_retention() -> u32 { 7 }
 
@@ -105,6 +131,135 @@ impl Default for SoundConfig {
     }
 }
 
+/// Per-application notification policies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyConfig {
+    /// Initial policies (grimoire settings take over once synced)
+    #[serde(default)]
+    pub apps: Vec<AppPolicy>,
+    /// Keep policies in sync with the grimoire settings store
+    #[serde(default = "default_true")]
+    pub grimoire_sync: bool,
+    #[serde(default = "default_grimoire_socket")]
+    pub grimoire_socket: String,
+    /// Settings path holding the policy list
+    #[serde(default = "default_policy_setting")]
+    pub setting_path: String,
+    #[serde(default = "default_sync_interval")]
+    pub sync_interval_secs: u64,
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        Self {
+            apps: Vec::new(),
+            grimoire_sync: true,
+            grimoire_socket: default_grimoire_socket(),
+            setting_path: default_policy_setting(),
+            sync_interval_secs: default_sync_interval(),
+        }
+    }
+}
+
+fn default_grimoire_socket() -> String { "/run/grimoire/grimoire.sock".to_string() }
+fn default_policy_setting() -> String { "notifications.policies".to_string() }
+fn default_sync_interval() -> u64 { 5 }
+
+/// What to do with an app's notifications
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    /// Show normally
+    #[default]
+    Allow,
+    /// Drop entirely
+    Deny,
+    /// Record in history without showing a popup
+    Silent,
+}
+
+/// Policy for one application ("*" matches any app without its own policy)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppPolicy {
+    pub app_name: String,
+    #[serde(default)]
+    pub action: PolicyAction,
+    /// Show even while Do Not Disturb is active
+    #[serde(default)]
+    pub bypass_dnd: bool,
+    /// Force this urgency for the app's notifications
+    #[serde(default)]
+    pub urgency: Option<Urgency>,
+}
+
+/// Per-application rate limiting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Notifications an app may show per window before being summarized
+    #[serde(default = "default_rate_max")]
+    pub max_per_window: usize,
+    #[serde(default = "default_rate_window")]
+    pub window_secs: u64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_per_window: default_rate_max(),
+            window_secs: default_rate_window(),
+        }
+    }
+}
+
+fn default_rate_max() -> usize { 5 }
+fn default_rate_window() -> u64 { 60 }
+
+/// Scheduled reminders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReminderConfig {
+    #[serde(default = "default_reminder_path")]
+    pub file_path: String,
+    /// Fire reminders that came due while herald was not running
+    #[serde(default = "default_true")]
+    pub fire_missed: bool,
+    #[serde(default = "default_reminder_check")]
+    pub check_interval_secs: u64,
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        Self {
+            file_path: default_reminder_path(),
+            fire_missed: true,
+            check_interval_secs: default_reminder_check(),
+        }
+    }
+}
+
+fn default_reminder_path() -> String { "/var/lib/herald/reminders.json".to_string() }
+fn default_reminder_check() -> u64 { 1 }
+
+/// Notification action delivery
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ActionConfig {
+    /// Commands run when an app's notification action is invoked
+    #[serde(default)]
+    pub handlers: Vec<ActionHandler>,
+}
+
+/// Handler command for one app's actions
+///
+/// Runs via `sh -c` with `HERALD_NOTIFICATION_ID`, `HERALD_ACTION`,
+/// `HERALD_APP` and `HERALD_TOKEN` set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionHandler {
+    pub app_name: String,
+    pub command: String,
+}
+
 fn default_true() -> bool { true }
 
 pub fn load_config(path: &Path) -> Result<HeraldConfig> {
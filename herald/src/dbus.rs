@@ -1,14 +1,31 @@
 //! D-Bus interface for freedesktop notifications
+//!
+//! Serves `org.freedesktop.Notifications` (spec 1.2) on the session bus.
+//! Method calls are forwarded to the daemon as [`DbusEvent`]s; the daemon
+//! answers `Notify` with the assigned ID and feeds [`DbusSignal`]s back to be
+//! emitted as `NotificationClosed` / `ActionInvoked`.
 
 use crate::notification::{CloseReason, HintValue, Notification, NotificationAction, Urgency};
 use anyhow::Result;
 use std::collections::HashMap;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::Value;
+
+/// Well-known bus name
+pub const BUS_NAME: &str = "org.freedesktop.Notifications";
+
+/// Object path of the notification service
+pub const OBJECT_PATH: &str = "/org/freedesktop/Notifications";
 
 /// D-Bus notification events
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub enum DbusEvent {
-    Notify(NotifyRequest),
+    /// New or replacing notification; the assigned ID is sent on `reply`
+    Notify {
+        request: NotifyRequest,
+        reply: oneshot::Sender<u32>,
+    },
     CloseNotification(u32),
     GetCapabilities,
     GetServerInformation,
@@ -54,6 +71,45 @@ impl NotificationDbusServer {
         (server, event_rx, signal_tx)
     }
 
+    /// Claim the bus name, export the interface and start emitting signals
+    pub async fn serve(mut self) -> Result<zbus::Connection> {
+        let interface = NotificationsInterface {
+            event_tx: self.event_tx.clone(),
+        };
+
+        let connection = zbus::connection::Builder::session()?
+            .name(BUS_NAME)?
+            .serve_at(OBJECT_PATH, interface)?
+            .build()
+            .await?;
+
+        let iface_ref = connection
+            .object_server()
+            .interface::<_, NotificationsInterface>(OBJECT_PATH)
+            .await?;
+
+        if let Some(mut signal_rx) = self.signal_rx.take() {
+            tokio::spawn(async move {
+                let emitter = iface_ref.signal_emitter();
+                while let Some(signal) = signal_rx.recv().await {
+                    let result = match &signal {
+                        DbusSignal::NotificationClosed { id, reason } => {
+                            NotificationsInterface::notification_closed(emitter, *id, *reason).await
+                        }
+                        DbusSignal::ActionInvoked { id, action_key } => {
+                            NotificationsInterface::action_invoked(emitter, *id, action_key).await
+                        }
+                    };
+                    if let Err(e) = result {
+                        tracing::warn!("Failed to emit {:?}: {}", signal, e);
+                    }
+                }
+            });
+        }
+
+        Ok(connection)
+    }
+
     /// Get server capabilities
     pub fn capabilities() -> Vec<&'static str> {
         vec![
@@ -70,10 +126,10 @@ impl NotificationDbusServer {
     /// Get server information
     pub fn server_info() -> (&'static str, &'static str, &'static str, &'static str) {
         (
-            "Herald",            // Name
-            "Nyx",               // Vendor
-            "0.1.0",             // Version
-            "1.2",               // Spec version
+            "Herald",                    // Name
+            "Nyx",                       // Vendor
+            env!("CARGO_PKG_VERSION"),   // Version
+            "1.2",                       // Spec version
         )
     }
 
@@ -97,6 +153,38 @@ impl NotificationDbusServer {
         (urgency, hints.clone())
     }
 
+    /// Convert a D-Bus variant hint into a [`HintValue`]
+    pub fn hint_from_value(value: &Value<'_>) -> Option<HintValue> {
+        match value {
+            Value::U8(b) => Some(HintValue::Byte(*b)),
+            Value::Bool(b) => Some(HintValue::Bool(*b)),
+            Value::I16(i) => Some(HintValue::Int(*i as i64)),
+            Value::I32(i) => Some(HintValue::Int(*i as i64)),
+            Value::I64(i) => Some(HintValue::Int(*i)),
+            Value::U16(u) => Some(HintValue::Uint(*u as u64)),
+            Value::U32(u) => Some(HintValue::Uint(*u as u64)),
+            Value::U64(u) => Some(HintValue::Uint(*u)),
+            Value::Str(s) => Some(HintValue::String(s.to_string())),
+            Value::ObjectPath(p) => Some(HintValue::String(p.to_string())),
+            Value::Value(inner) => Self::hint_from_value(inner),
+            Value::Array(array) => array
+                .iter()
+                .map(|v| match v {
+                    Value::U8(b) => Some(*b),
+                    _ => None,
+                })
+                .collect::<Option<Vec<u8>>>()
+                .map(HintValue::ByteArray),
+            // image-data / icon_data: (iiibiiay); keep the pixel data
+            Value::Structure(structure) => structure
+                .fields()
+                .last()
+                .and_then(Self::hint_from_value)
+                .filter(|v| matches!(v, HintValue::ByteArray(_))),
+            _ => None,
+        }
+    }
+
     /// Convert NotifyRequest to Notification
     pub fn to_notification(request: NotifyRequest, id: u32) -> Notification {
         let (urgency, hints) = Self::parse_hints(&request.hints);
@@ -135,6 +223,15 @@ impl NotificationDbusServer {
         if let Some(HintValue::Bool(true)) = notification.hints.get("transient") {
             notification.transient = true;
         }
+        // Older clients pass the icon only as a hint
+        if notification.app_icon.is_none() {
+            for key in ["image-path", "image_path"] {
+                if let Some(HintValue::String(path)) = notification.hints.get(key) {
+                    notification.app_icon = Some(path.clone());
+                    break;
+                }
+            }
+        }
 
         notification
     }
@@ -163,6 +260,79 @@ impl Default for NotificationDbusServer {
     }
 }
 
+/// The exported `org.freedesktop.Notifications` object
+struct NotificationsInterface {
+    event_tx: mpsc::Sender<DbusEvent>,
+}
+
+#[zbus::interface(name = "org.freedesktop.Notifications")]
+impl NotificationsInterface {
+    #[allow(clippy::too_many_arguments)]
+    async fn notify(
+        &self,
+        app_name: String,
+        replaces_id: u32,
+        app_icon: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        hints: HashMap<String, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::fdo::Result<u32> {
+        let hints = hints
+            .iter()
+            .filter_map(|(key, value)| {
+                NotificationDbusServer::hint_from_value(value).map(|v| (key.clone(), v))
+            })
+            .collect();
+
+        let request = NotifyRequest {
+            app_name,
+            replaces_id,
+            app_icon,
+            summary,
+            body,
+            actions,
+            hints,
+            expire_timeout,
+        };
+
+        let (reply, id_rx) = oneshot::channel();
+        self.event_tx
+            .send(DbusEvent::Notify { request, reply })
+            .await
+            .map_err(|_| zbus::fdo::Error::Failed("Herald is shutting down".into()))?;
+
+        id_rx
+            .await
+            .map_err(|_| zbus::fdo::Error::Failed("Notification was not accepted".into()))
+    }
+
+    async fn close_notification(&self, id: u32) {
+        let _ = self.event_tx.send(DbusEvent::CloseNotification(id)).await;
+    }
+
+    async fn get_capabilities(&self) -> Vec<String> {
+        let _ = self.event_tx.send(DbusEvent::GetCapabilities).await;
+        NotificationDbusServer::capabilities()
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    async fn get_server_information(&self) -> (String, String, String, String) {
+        let _ = self.event_tx.send(DbusEvent::GetServerInformation).await;
+        let (name, vendor, version, spec) = NotificationDbusServer::server_info();
+        (name.into(), vendor.into(), version.into(), spec.into())
+    }
+
+    #[zbus(signal)]
+    async fn notification_closed(emitter: &SignalEmitter<'_>, id: u32, reason: u32) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn action_invoked(emitter: &SignalEmitter<'_>, id: u32, action_key: &str) -> zbus::Result<()>;
+}
+
 /// Simplified D-Bus mock for non-systemd environments
 /// In production, this would use zbus or dbus-rs
 pub mod mock {
@@ -231,7 +401,13 @@ pub mod mock {
                                                     .unwrap_or(-1) as i32,
                                             };
 
-                                            let _ = event_tx.send(DbusEvent::Notify(request)).await;
+                                            let (reply, id_rx) = oneshot::channel();
+                                            let _ = event_tx.send(DbusEvent::Notify { request, reply }).await;
+                                            if let Ok(id) = id_rx.await {
+                                                let response = serde_json::json!({ "id": id });
+                                                let _ = writer.write_all(response.to_string().as_bytes()).await;
+                                                let _ = writer.write_all(b"\n").await;
+                                            }
                                         }
                                     }
                                     Some("CloseNotification") => {
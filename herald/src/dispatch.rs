@@ -0,0 +1,148 @@
+//! Notification intake
+//!
+//! Every new notification, whether it arrives over D-Bus or Herald IPC, goes
+//! through [`Dispatcher::submit`], which applies the app's policy, Do Not
+//! Disturb and the per-app rate limit before queueing it and recording it in
+//! history. Shown notifications are published on the event bus.
+
+use crate::config::PolicyAction;
+use crate::dnd::DndManager;
+use crate::events::EventBus;
+use crate::history::NotificationHistory;
+use crate::notification::{Notification, NotificationQueue};
+use crate::policy::PolicyStore;
+use crate::ratelimit::{RateDecision, RateLimiter};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+
+/// What happened to a submitted notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Shown,
+    /// Dropped by the app's policy
+    Denied,
+    /// Recorded in history only, per the app's policy
+    Silenced,
+    /// Held back by Do Not Disturb
+    Suppressed,
+    /// Held back by the rate limit and summarized in a digest
+    RateLimited,
+}
+
+/// Result of [`Dispatcher::submit`]
+#[derive(Debug, Clone, Copy)]
+pub struct Dispatched {
+    pub id: u32,
+    pub outcome: Outcome,
+}
+
+/// Shared notification intake path
+pub struct Dispatcher {
+    queue: Arc<RwLock<NotificationQueue>>,
+    history: Arc<RwLock<NotificationHistory>>,
+    dnd: Arc<DndManager>,
+    policies: Arc<PolicyStore>,
+    limiter: Mutex<RateLimiter>,
+    events: EventBus,
+}
+
+impl Dispatcher {
+    pub fn new(
+        queue: Arc<RwLock<NotificationQueue>>,
+        history: Arc<RwLock<NotificationHistory>>,
+        dnd: Arc<DndManager>,
+        policies: Arc<PolicyStore>,
+        limiter: RateLimiter,
+        events: EventBus,
+    ) -> Self {
+        Self {
+            queue,
+            history,
+            dnd,
+            policies,
+            limiter: Mutex::new(limiter),
+            events,
+        }
+    }
+
+    /// Queue a notification, subject to policy, DND and rate limiting
+    pub async fn submit(&self, mut notification: Notification) -> Dispatched {
+        let policy = self.policies.lookup(&notification.app_name).await;
+        if let Some(urgency) = policy.as_ref().and_then(|p| p.urgency) {
+            notification.urgency = urgency;
+        }
+        let action = policy.as_ref().map(|p| p.action).unwrap_or_default();
+        let bypass_dnd = policy.as_ref().is_some_and(|p| p.bypass_dnd);
+
+        if action == PolicyAction::Deny {
+            let id = self.queue.write().await.reserve_id();
+            return Dispatched { id, outcome: Outcome::Denied };
+        }
+
+        // Replacing a visible notification (progress updates) is never throttled
+        let replacing = match notification.replaces_id {
+            Some(id) if id > 0 => self.queue.read().await.get(id).is_some(),
+            _ => false,
+        };
+
+        let outcome = if action == PolicyAction::Silent {
+            Outcome::Silenced
+        } else if !bypass_dnd && !self.dnd.should_show(&notification).await {
+            Outcome::Suppressed
+        } else if replacing {
+            Outcome::Shown
+        } else {
+            let decision = self.limiter.lock().await.check(&notification);
+            match decision {
+                RateDecision::Allow => Outcome::Shown,
+                RateDecision::Limited { suppressed, digest_id } => {
+                    let digest = RateLimiter::digest(&notification.app_name, suppressed, digest_id);
+                    let digest_id = self.queue.write().await.add(digest);
+                    self.publish_posted(digest_id).await;
+                    self.limiter
+                        .lock()
+                        .await
+                        .set_digest(&notification.app_name, digest_id);
+                    Outcome::RateLimited
+                }
+            }
+        };
+
+        let id = match outcome {
+            Outcome::Shown => self.queue.write().await.add(notification.clone()),
+            Outcome::Denied | Outcome::Silenced | Outcome::Suppressed | Outcome::RateLimited => {
+                // Held-back notifications get their own history entry
+                notification.replaces_id = None;
+                self.queue.write().await.reserve_id()
+            }
+        };
+        notification.id = id;
+        if outcome == Outcome::Shown {
+            self.publish_posted(id).await;
+        }
+
+        // Transient notifications bypass persistence
+        if !notification.transient {
+            let mut history = self.history.write().await;
+            history.add(notification);
+            if outcome != Outcome::Shown {
+                history.mark_held(
+                    id,
+                    outcome == Outcome::RateLimited,
+                    matches!(outcome, Outcome::Suppressed | Outcome::Silenced),
+                );
+            }
+        }
+
+        Dispatched { id, outcome }
+    }
+
+    /// Announce a queued notification as it now stands
+    async fn publish_posted(&self, id: u32) {
+        if let Some(notification) = self.queue.read().await.get(id) {
+            self.events.posted(notification);
+        }
+    }
+}
@@ -4,16 +4,136 @@
 //! - Freedesktop D-Bus notifications (native Linux, WSL with WSLg)
 //! - Windows Toast notifications (WSL with interop)
 //! - Console fallback (headless/SSH)
-
-use crate::notification::{Notification, Urgency};
-use anyhow::Result;
+//!
+//! Windows toasts go through one long-lived PowerShell process (the toast
+//! bridge). Herald writes `show <id> <xml>` and `close <id>` lines to it;
+//! when the user clicks a toast or one of its buttons the bridge writes
+//! `action <id> <action id>` back, which is fed into the action channel as
+//! if the action had been invoked over D-Bus. Clicking the toast itself
+//! invokes `default`.
+
+use crate::notification::{HintValue, Notification, Urgency};
+use anyhow::{anyhow, Result};
 use libnyx_platform::{Platform, compat::NotificationBackend, wsl};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin};
+use tokio::sync::{mpsc, Mutex};
+
+/// AppUserModelID toasts are raised under. Unpackaged processes can only
+/// raise toasts under a registered ID; PowerShell's is always present.
+const TOAST_APP_ID: &str = r"{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe";
+
+/// Toast group, so herald's toasts can be removed by ID
+const TOAST_GROUP: &str = "herald";
+
+/// Functions shared by the bridge and one-shot toasts
+const TOAST_PRELUDE: &str = r#"
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null
+[Windows.Data.Xml.Dom.XmlDocument, Windows.Data.Xml.Dom.XmlDocument, ContentType = WindowsRuntime] | Out-Null
+$notifier = [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('@APP_ID@')
+function Show-HeraldToast($id, $xmlText) {
+    $xml = New-Object Windows.Data.Xml.Dom.XmlDocument
+    $xml.LoadXml($xmlText)
+    $toast = [Windows.UI.Notifications.ToastNotification]::new($xml)
+    $toast.Tag = $id
+    $toast.Group = '@GROUP@'
+    $notifier.Show($toast)
+    return $toast
+}
+"#;
+
+/// Bridge loop: shows toasts read from stdin and reports activations.
+/// Event handlers run between polls of stdin.
+const TOAST_BRIDGE: &str = r#"
+$history = [Windows.UI.Notifications.ToastNotificationManager]::History
+$pending = [Console]::In.ReadLineAsync()
+while ($true) {
+    if (-not $pending.Wait(200)) { continue }
+    $line = $pending.Result
+    if ($null -eq $line) { break }
+    $pending = [Console]::In.ReadLineAsync()
+    $parts = $line.Split(' ', 3)
+    try {
+        switch ($parts[0]) {
+            'show' {
+                $toast = Show-HeraldToast $parts[1] $parts[2]
+                Register-ObjectEvent -InputObject $toast -EventName Activated -Action {
+                    $activated = [Windows.UI.Notifications.ToastActivatedEventArgs]$Event.SourceArgs[1]
+                    [Console]::Out.WriteLine("action $($activated.Arguments)")
+                    [Console]::Out.Flush()
+                } | Out-Null
+            }
+            'close' { $history.Remove($parts[1], '@GROUP@', '@APP_ID@') }
+        }
+    } catch {
+        [Console]::Error.WriteLine($_)
+    }
+}
+"#;
+
+/// Prepend the prelude to `body` and fill in the app ID and group
+fn toast_script(body: &str) -> String {
+    format!("{}{}", TOAST_PRELUDE, body)
+        .replace("@APP_ID@", TOAST_APP_ID)
+        .replace("@GROUP@", TOAST_GROUP)
+}
+
+/// Long-lived PowerShell process showing toasts and reporting activations
+struct ToastBridge {
+    stdin: Mutex<ChildStdin>,
+    _child: Child,
+}
+
+impl ToastBridge {
+    /// Start the bridge; activations are sent to `action_tx`
+    fn spawn(action_tx: mpsc::Sender<(u32, String)>) -> Result<Self> {
+        let script = toast_script(TOAST_BRIDGE);
+        let mut child = tokio::process::Command::new("powershell.exe")
+            .args(["-NoProfile", "-NonInteractive", "-WindowStyle", "Hidden", "-Command", &script])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let stdin = child.stdin.take().ok_or_else(|| anyhow!("toast bridge has no stdin"))?;
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("toast bridge has no stdout"))?;
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                match parse_activation(&line) {
+                    Some(action) => {
+                        if action_tx.send(action).await.is_err() {
+                            break;
+                        }
+                    }
+                    None => tracing::debug!("Toast bridge: {}", line),
+                }
+            }
+            tracing::warn!("Toast bridge exited; toast buttons no longer work");
+        });
+
+        Ok(Self {
+            stdin: Mutex::new(stdin),
+            _child: child,
+        })
+    }
+
+    async fn send(&self, line: &str) -> Result<()> {
+        let mut stdin = self.stdin.lock().await;
+        stdin.write_all(line.as_bytes()).await?;
+        stdin.write_all(b"\n").await?;
+        stdin.flush().await?;
+        Ok(())
+    }
+}
 
 /// Notification display handler
 pub struct NotificationDisplay {
     backend: NotificationBackend,
     platform: Platform,
+    /// Toast bridge, when showing Windows toasts with actions
+    toasts: Option<ToastBridge>,
 }
 
 impl NotificationDisplay {
@@ -27,7 +147,19 @@ impl NotificationDisplay {
             backend
         );
 
-        Self { backend, platform }
+        Self { backend, platform, toasts: None }
+    }
+
+    /// Create a display whose toast buttons invoke actions on `action_tx`
+    pub fn with_actions(action_tx: mpsc::Sender<(u32, String)>) -> Self {
+        let mut display = Self::new();
+        if matches!(display.backend, NotificationBackend::WindowsToast) {
+            match ToastBridge::spawn(action_tx) {
+                Ok(bridge) => display.toasts = Some(bridge),
+                Err(e) => tracing::warn!("Toast bridge unavailable, toasts will have no buttons: {}", e),
+            }
+        }
+        display
     }
 
     /// Display a notification
@@ -37,7 +169,7 @@ impl NotificationDisplay {
                 self.show_freedesktop(notification).await
             }
             NotificationBackend::WindowsToast => {
-                self.show_windows_toast(notification)
+                self.show_windows_toast(notification).await
             }
             NotificationBackend::Console => {
                 self.show_console(notification)
@@ -98,85 +230,28 @@ impl NotificationDisplay {
         Ok(())
     }
 
-    /// Show Windows Toast notification via PowerShell
-    fn show_windows_toast(&self, notification: &Notification) -> Result<()> {
-        let title = notification.summary.replace('"', "'").replace('\n', " ");
-        let body = notification.body
-            .as_ref()
-            .map(|b| b.replace('"', "'").replace('\n', " "))
-            .unwrap_or_default();
-
-        // Build PowerShell script for toast notification
-        let ps_script = format!(
-            r#"
-[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null
-[Windows.Data.Xml.Dom.XmlDocument, Windows.Data.Xml.Dom.XmlDocument, ContentType = WindowsRuntime] | Out-Null
-
-$template = @"
-<toast>
-    <visual>
-        <binding template="ToastText02">
-            <text id="1">{}</text>
-            <text id="2">{}</text>
-        </binding>
-    </visual>
-    <audio src="ms-winsoundevent:Notification.Default"/>
-</toast>
-"@
-
-$xml = New-Object Windows.Data.Xml.Dom.XmlDocument
-$xml.LoadXml($template)
-$toast = [Windows.UI.Notifications.ToastNotification]::new($xml)
-$notifier = [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier("Nyx Herald")
-$notifier.Show($toast)
-"#,
-            title, body
-        );
-
-        let output = Command::new("powershell.exe")
-            .args(["-NoProfile", "-WindowStyle", "Hidden", "-Command", &ps_script])
-            .output()?;
-
-        if !output.status.success() {
-            // Fallback to BurntToast if available
-            self.try_burnt_toast(notification)?;
+    /// Show a Windows toast through the bridge, or one-shot (without
+    /// working buttons) if the bridge couldn't be started
+    async fn show_windows_toast(&self, notification: &Notification) -> Result<()> {
+        let xml = toast_xml(notification);
+        match &self.toasts {
+            Some(bridge) => bridge.send(&format!("show {} {}", notification.id, xml)).await,
+            None => {
+                let script = toast_script(&format!(
+                    "Show-HeraldToast '{}' '{}' | Out-Null",
+                    notification.id,
+                    xml.replace('\'', "''")
+                ));
+                let output = tokio::process::Command::new("powershell.exe")
+                    .args(["-NoProfile", "-NonInteractive", "-WindowStyle", "Hidden", "-Command", &script])
+                    .output()
+                    .await?;
+                if !output.status.success() {
+                    tracing::warn!("Toast failed: {}", String::from_utf8_lossy(&output.stderr));
+                }
+                Ok(())
+            }
         }
-
-        Ok(())
-    }
-
-    /// Try using BurntToast PowerShell module
-    fn try_burnt_toast(&self, notification: &Notification) -> Result<()> {
-        let title = notification.summary.replace('"', "'");
-        let body = notification.body
-            .as_ref()
-            .map(|b| b.replace('"', "'"))
-            .unwrap_or_default();
-
-        let ps_script = format!(
-            r#"
-if (Get-Module -ListAvailable -Name BurntToast) {{
-    Import-Module BurntToast
-    New-BurntToastNotification -Text "{}", "{}" -AppLogo $null
-}} else {{
-    # Fallback to basic notification
-    [System.Reflection.Assembly]::LoadWithPartialName('System.Windows.Forms') | Out-Null
-    $balloon = New-Object System.Windows.Forms.NotifyIcon
-    $balloon.Icon = [System.Drawing.SystemIcons]::Information
-    $balloon.BalloonTipTitle = "{}"
-    $balloon.BalloonTipText = "{}"
-    $balloon.Visible = $true
-    $balloon.ShowBalloonTip(5000)
-}}
-"#,
-            title, body, title, body
-        );
-
-        Command::new("powershell.exe")
-            .args(["-NoProfile", "-WindowStyle", "Hidden", "-Command", &ps_script])
-            .output()?;
-
-        Ok(())
     }
 
     /// Console fallback for headless environments
@@ -209,8 +284,10 @@ if (Get-Module -ListAvailable -Name BurntToast) {{
                 tracing::debug!("Close notification {} via D-Bus", id);
             }
             NotificationBackend::WindowsToast => {
-                // Windows toasts auto-dismiss; no manual close needed
-                tracing::debug!("Windows toast {} will auto-dismiss", id);
+                // Without the bridge toasts just time out in the Action Center
+                if let Some(bridge) = &self.toasts {
+                    bridge.send(&format!("close {}", id)).await?;
+                }
             }
             NotificationBackend::Console => {
                 // Console notifications can't be closed
@@ -226,7 +303,11 @@ if (Get-Module -ListAvailable -Name BurntToast) {{
 
     /// Check if notification actions are supported
     pub fn supports_actions(&self) -> bool {
-        matches!(self.backend, NotificationBackend::Freedesktop)
+        match self.backend {
+            NotificationBackend::Freedesktop => true,
+            NotificationBackend::WindowsToast => self.toasts.is_some(),
+            NotificationBackend::Console => false,
+        }
     }
 
     /// Check if notification sounds are supported
@@ -244,6 +325,94 @@ impl Default for NotificationDisplay {
     }
 }
 
+/// Build toast XML for a notification. Kept on one line so it can be
+/// passed to the bridge as a single command.
+fn toast_xml(notification: &Notification) -> String {
+    let id = notification.id;
+    let scenario = if notification.is_critical() { r#" scenario="urgent""# } else { "" };
+    let mut xml = format!(
+        r#"<toast launch="{} default" duration="{}"{}><visual><binding template="ToastGeneric">"#,
+        id,
+        if notification.resident || notification.timeout == 0 { "long" } else { "short" },
+        scenario
+    );
+
+    xml.push_str(&format!("<text>{}</text>", escape_xml(&notification.summary)));
+    if let Some(body) = &notification.body {
+        xml.push_str(&format!("<text>{}</text>", escape_xml(body)));
+    }
+    xml.push_str(&format!(r#"<text placement="attribution">{}</text>"#, escape_xml(&notification.app_name)));
+
+    // Icons named by theme can't be resolved on the Windows side
+    if let Some(icon) = notification.app_icon.as_deref().and_then(windows_image_uri) {
+        xml.push_str(&format!(r#"<image placement="appLogoOverride" src="{}"/>"#, escape_xml(&icon)));
+    }
+    if let Some(HintValue::String(path)) = notification.hints.get("image-path") {
+        if let Some(image) = windows_image_uri(path) {
+            xml.push_str(&format!(r#"<image placement="hero" src="{}"/>"#, escape_xml(&image)));
+        }
+    }
+    xml.push_str("</binding></visual>");
+
+    // Toasts show at most five buttons; `default` is the toast body itself
+    let buttons: Vec<_> = notification.actions.iter().filter(|a| a.id != "default").take(5).collect();
+    if !buttons.is_empty() {
+        xml.push_str("<actions>");
+        for action in buttons {
+            xml.push_str(&format!(
+                r#"<action content="{}" arguments="{} {}" activationType="foreground"/>"#,
+                escape_xml(&action.label),
+                id,
+                escape_xml(&action.id)
+            ));
+        }
+        xml.push_str("</actions>");
+    }
+
+    match notification.urgency {
+        Urgency::Low => xml.push_str(r#"<audio silent="true"/>"#),
+        Urgency::Normal => xml.push_str(r#"<audio src="ms-winsoundevent:Notification.Default"/>"#),
+        Urgency::Critical => xml.push_str(r#"<audio src="ms-winsoundevent:Notification.Looping.Alarm" loop="true"/>"#),
+    }
+    xml.push_str("</toast>");
+    xml
+}
+
+/// `file:///` URI Windows can load for a local image path
+fn windows_image_uri(path: &str) -> Option<String> {
+    let path = path.strip_prefix("file://").unwrap_or(path);
+    if !path.starts_with('/') {
+        return None;
+    }
+    let windows = wsl::to_windows_path(path)?;
+    Some(format!("file:///{}", windows.replace('\\', "/")))
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Parse a bridge `action <id> <action id>` line
+fn parse_activation(line: &str) -> Option<(u32, String)> {
+    let mut parts = line.trim().strip_prefix("action ")?.splitn(2, ' ');
+    let id = parts.next()?.parse().ok()?;
+    let action = parts.next().filter(|a| !a.is_empty())?;
+    Some((id, action.to_string()))
+}
+
 /// Check if we can display GUI notifications
 pub fn can_display_gui() -> bool {
     let platform = Platform::detect();
@@ -271,3 +440,35 @@ pub fn can_display_gui() -> bool {
 
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notification::NotificationAction;
+
+    #[test]
+    fn test_toast_xml() {
+        let mut notification = Notification::new(7, "Mail", "New <message>");
+        notification.body = Some("From: \"Ada\"\nSubject: hi".to_string());
+        notification.actions = vec![
+            NotificationAction { id: "default".into(), label: "Open".into() },
+            NotificationAction { id: "reply".into(), label: "Reply & send".into() },
+        ];
+
+        let xml = toast_xml(&notification);
+        assert!(!xml.contains('\n'));
+        assert!(xml.starts_with(r#"<toast launch="7 default""#));
+        assert!(xml.contains("<text>New &lt;message&gt;</text>"));
+        assert!(xml.contains("From: &quot;Ada&quot;&#10;Subject: hi"));
+        assert!(xml.contains(r#"<action content="Reply &amp; send" arguments="7 reply""#));
+        assert!(!xml.contains(r#"arguments="7 default""#));
+    }
+
+    #[test]
+    fn test_parse_activation() {
+        assert_eq!(parse_activation("action 7 default"), Some((7, "default".to_string())));
+        assert_eq!(parse_activation("action 7 snooze 10m"), Some((7, "snooze 10m".to_string())));
+        assert_eq!(parse_activation("action x reply"), None);
+        assert_eq!(parse_activation("warning: something"), None);
+    }
+}
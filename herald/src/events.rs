@@ -0,0 +1,98 @@
+//! Notification events for subscribers
+//!
+//! The shell draws popups and the notification center from these rather
+//! than polling: every notification that is shown, every close and every
+//! manual Do Not Disturb change is published on the bus and streamed to
+//! connections that sent `Subscribe`.
+
+use crate::notification::{CloseReason, Notification, NotificationAction, Urgency};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// Events a slow subscriber may fall behind by before it misses some
+const EVENT_BACKLOG: usize = 64;
+
+/// A shown notification, as subscribers see it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationInfo {
+    pub id: u32,
+    pub app_name: String,
+    pub summary: String,
+    pub body: Option<String>,
+    pub icon: Option<String>,
+    pub urgency: Urgency,
+    pub actions: Vec<NotificationAction>,
+    pub resident: bool,
+    pub timestamp: u64,
+}
+
+impl From<&Notification> for NotificationInfo {
+    fn from(n: &Notification) -> Self {
+        Self {
+            id: n.id,
+            app_name: n.app_name.clone(),
+            summary: n.summary.clone(),
+            body: n.body.clone(),
+            icon: n.app_icon.clone(),
+            urgency: n.urgency,
+            actions: n.actions.clone(),
+            resident: n.resident,
+            timestamp: n.timestamp,
+        }
+    }
+}
+
+/// Pushed to subscribed clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum HeraldEvent {
+    /// A notification was shown, or replaced in place under the same ID
+    Posted { notification: NotificationInfo },
+    /// A notification left the screen
+    Closed { id: u32, reason: CloseReason },
+    /// Do Not Disturb turned on or off
+    Dnd { active: bool },
+}
+
+/// Broadcast of notification events
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<HeraldEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            tx: broadcast::channel(EVENT_BACKLOG).0,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<HeraldEvent> {
+        self.tx.subscribe()
+    }
+
+    pub fn posted(&self, notification: &Notification) {
+        self.publish(HeraldEvent::Posted {
+            notification: notification.into(),
+        });
+    }
+
+    pub fn closed(&self, id: u32, reason: CloseReason) {
+        self.publish(HeraldEvent::Closed { id, reason });
+    }
+
+    pub fn dnd(&self, active: bool) {
+        self.publish(HeraldEvent::Dnd { active });
+    }
+
+    fn publish(&self, event: HeraldEvent) {
+        // No subscribers is the normal case without a shell
+        let _ = self.tx.send(event);
+    }
+}
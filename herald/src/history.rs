@@ -1,9 +1,16 @@
 //! Notification history management
+//!
+//! Entries are kept newest first. Besides the global size and age limits,
+//! apps can have their own retention (see `HistoryRetention`), applied as
+//! entries arrive and by the periodic prune. History can be searched by
+//! text, app, urgency and time, and exported as JSON or CSV.
 
+use crate::config::HistoryRetention;
 use crate::notification::{CloseReason, Notification, Urgency};
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
 
 /// Historical notification entry
@@ -14,6 +21,46 @@ pub struct HistoryEntry {
     pub closed_at: Option<u64>,
     pub close_reason: Option<CloseReason>,
     pub action_invoked: Option<String>,
+    /// Times the notification was replaced in place (progress updates)
+    #[serde(default)]
+    pub updates: u32,
+    /// Held back by the per-app rate limit
+    #[serde(default)]
+    pub rate_limited: bool,
+    /// Held back by Do Not Disturb
+    #[serde(default)]
+    pub suppressed: bool,
+}
+
+/// Filters for searching history; unset fields match everything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryQuery {
+    /// Words and "quoted phrases" that must all appear in the summary or
+    /// body, ignoring case
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub app_name: Option<String>,
+    #[serde(default)]
+    pub urgency: Option<Urgency>,
+    /// Shown at or after this time
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+    /// Shown before this time
+    #[serde(default)]
+    pub until: Option<DateTime<Utc>>,
+    /// Newest entries to return
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// History export format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
 }
 
 /// Notification history manager
@@ -21,6 +68,7 @@ pub struct NotificationHistory {
     entries: VecDeque<HistoryEntry>,
     max_size: usize,
     retention_days: u32,
+    app_retention: Vec<HistoryRetention>,
     file_path: PathBuf,
 }
 
@@ -30,17 +78,24 @@ impl NotificationHistory {
             entries: VecDeque::new(),
             max_size,
             retention_days,
+            app_retention: Vec::new(),
             file_path,
         }
     }
 
+    /// Apply per-app retention on top of the global limits
+    pub fn with_app_retention(mut self, app_retention: Vec<HistoryRetention>) -> Self {
+        self.app_retention = app_retention;
+        self
+    }
+
     /// Load history from file
     pub async fn load(&mut self) -> Result<()> {
         if self.file_path.exists() {
             let content = tokio::fs::read_to_string(&self.file_path).await?;
             let entries: Vec<HistoryEntry> = serde_json::from_str(&content)?;
             self.entries = entries.into_iter().collect();
-            self.cleanup_old();
+            self.prune();
         }
         Ok(())
     }
@@ -64,20 +119,91 @@ impl NotificationHistory {
             .map(|d| d.as_secs())
             .unwrap_or(0);
 
+        // Replacements update the existing entry instead of adding one per step
+        if let Some(replaces_id) = notification.replaces_id {
+            if let Some(entry) = self.entries.iter_mut()
+                .find(|e| e.notification.id == replaces_id && e.closed_at.is_none())
+            {
+                entry.notification = notification;
+                entry.displayed_at = now;
+                entry.updates += 1;
+                return;
+            }
+        }
+
         let entry = HistoryEntry {
             notification,
             displayed_at: now,
             closed_at: None,
             close_reason: None,
             action_invoked: None,
+            updates: 0,
+            rate_limited: false,
+            suppressed: false,
         };
 
+        let app_name = entry.notification.app_name.clone();
         self.entries.push_front(entry);
 
         // Trim to max size
         while self.entries.len() > self.max_size {
             self.entries.pop_back();
         }
+
+        // And to the app's own limit
+        if let Some(max) = retention_for(&self.app_retention, &app_name).and_then(|r| r.max_entries) {
+            let mut seen = 0;
+            self.entries.retain(|e| {
+                if e.notification.app_name != app_name {
+                    return true;
+                }
+                seen += 1;
+                seen <= max
+            });
+        }
+    }
+
+    /// Flag the newest entry with `id` as held back
+    pub fn mark_held(&mut self, id: u32, rate_limited: bool, suppressed: bool) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.notification.id == id) {
+            entry.rate_limited = rate_limited;
+            entry.suppressed = suppressed;
+        }
+    }
+
+    /// Per-app summary with collapse and rate-limit counts
+    pub fn groups(&self) -> Vec<HistoryGroup> {
+        let mut groups: Vec<HistoryGroup> = Vec::new();
+
+        // Entries are newest first, so the first one seen is the latest
+        for entry in &self.entries {
+            let group = match groups.iter_mut().position(|g| g.app_name == entry.notification.app_name) {
+                Some(pos) => &mut groups[pos],
+                None => {
+                    groups.push(HistoryGroup {
+                        app_name: entry.notification.app_name.clone(),
+                        total: 0,
+                        unread: 0,
+                        rate_limited: 0,
+                        latest_summary: entry.notification.summary.clone(),
+                        latest_at: entry.displayed_at,
+                    });
+                    groups.last_mut().unwrap()
+                }
+            };
+
+            group.total += 1;
+            if entry.rate_limited {
+                group.rate_limited += 1;
+            }
+            if entry.close_reason != Some(CloseReason::Dismissed)
+                && entry.close_reason != Some(CloseReason::ActionInvoked)
+            {
+                group.unread += 1;
+            }
+        }
+
+        groups
     }
 
     /// Record notification closure
@@ -131,15 +257,36 @@ impl NotificationHistory {
 
     /// Search history by summary/body
     pub fn search(&self, query: &str) -> Vec<&HistoryEntry> {
-        let query_lower = query.to_lowercase();
+        self.query(&HistoryQuery {
+            text: Some(query.to_string()),
+            ..HistoryQuery::default()
+        })
+    }
+
+    /// Entries matching every filter in `query`, newest first
+    pub fn query(&self, query: &HistoryQuery) -> Vec<&HistoryEntry> {
+        let terms = query.text.as_deref().map(search_terms).unwrap_or_default();
+        let since = query.since.map(|t| t.timestamp().max(0) as u64);
+        let until = query.until.map(|t| t.timestamp().max(0) as u64);
 
         self.entries.iter()
+            .filter(|e| query.app_name.as_ref().is_none_or(|app| &e.notification.app_name == app))
+            .filter(|e| query.urgency.is_none_or(|u| e.notification.urgency == u))
+            .filter(|e| since.is_none_or(|t| e.displayed_at >= t))
+            .filter(|e| until.is_none_or(|t| e.displayed_at < t))
             .filter(|e| {
-                e.notification.summary.to_lowercase().contains(&query_lower) ||
-                e.notification.body.as_ref()
-                    .map(|b| b.to_lowercase().contains(&query_lower))
-                    .unwrap_or(false)
+                if terms.is_empty() {
+                    return true;
+                }
+                let text = format!(
+                    "{}\n{}",
+                    e.notification.summary,
+                    e.notification.body.as_deref().unwrap_or("")
+                )
+                .to_lowercase();
+                terms.iter().all(|term| text.contains(term.as_str()))
             })
+            .take(query.limit.unwrap_or(usize::MAX))
             .collect()
     }
 
@@ -163,16 +310,34 @@ impl NotificationHistory {
         self.entries.retain(|e| e.notification.app_name != app_name);
     }
 
-    /// Remove old entries based on retention policy
-    fn cleanup_old(&mut self) {
+    /// Remove entries past their app's retention (or the global one);
+    /// returns how many were removed
+    pub fn prune(&mut self) -> usize {
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .map(|d| d.as_secs())
             .unwrap_or(0);
 
-        let cutoff = now.saturating_sub(self.retention_days as u64 * 24 * 3600);
-
-        self.entries.retain(|e| e.displayed_at >= cutoff);
+        let before = self.entries.len();
+        let mut kept: HashMap<String, usize> = HashMap::new();
+        self.entries.retain(|e| {
+            let retention = retention_for(&self.app_retention, &e.notification.app_name);
+            let days = retention.and_then(|r| r.retention_days).unwrap_or(self.retention_days);
+            if e.displayed_at < now.saturating_sub(days as u64 * 24 * 3600) {
+                return false;
+            }
+
+            // Newest first, so the oldest go over the limit
+            match retention.and_then(|r| r.max_entries) {
+                Some(max) => {
+                    let count = kept.entry(e.notification.app_name.clone()).or_default();
+                    *count += 1;
+                    *count <= max
+                }
+                None => true,
+            }
+        });
+        before - self.entries.len()
     }
 
     /// Get statistics
@@ -215,6 +380,95 @@ impl NotificationHistory {
     }
 }
 
+/// Retention settings for an app: its own entry, else the "*" one
+fn retention_for<'a>(retention: &'a [HistoryRetention], app_name: &str) -> Option<&'a HistoryRetention> {
+    retention.iter()
+        .find(|r| r.app_name == app_name)
+        .or_else(|| retention.iter().find(|r| r.app_name == "*"))
+}
+
+/// Lowercased search terms: "quoted phrases" whole, other words one by one
+fn search_terms(text: &str) -> Vec<String> {
+    text.split('"')
+        .enumerate()
+        .flat_map(|(i, part)| {
+            if i % 2 == 1 {
+                vec![part.trim().to_string()]
+            } else {
+                part.split_whitespace().map(String::from).collect()
+            }
+        })
+        .filter(|term| !term.is_empty())
+        .map(|term| term.to_lowercase())
+        .collect()
+}
+
+/// Render entries in an export format
+pub fn export(entries: &[&HistoryEntry], format: ExportFormat) -> Result<String> {
+    match format {
+        ExportFormat::Json => Ok(serde_json::to_string_pretty(entries)?),
+        ExportFormat::Csv => {
+            let mut out = String::from(
+                "id,app_name,urgency,summary,body,displayed_at,closed_at,close_reason,action_invoked,updates,rate_limited,suppressed\n",
+            );
+            for e in entries {
+                let fields = [
+                    e.notification.id.to_string(),
+                    e.notification.app_name.clone(),
+                    format!("{:?}", e.notification.urgency).to_lowercase(),
+                    e.notification.summary.clone(),
+                    e.notification.body.clone().unwrap_or_default(),
+                    rfc3339(e.displayed_at),
+                    e.closed_at.map(rfc3339).unwrap_or_default(),
+                    e.close_reason.map(|r| r.as_str().to_string()).unwrap_or_default(),
+                    e.action_invoked.clone().unwrap_or_default(),
+                    e.updates.to_string(),
+                    e.rate_limited.to_string(),
+                    e.suppressed.to_string(),
+                ];
+                let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                out.push_str(&row.join(","));
+                out.push('\n');
+            }
+            Ok(out)
+        }
+    }
+}
+
+fn rfc3339(secs: u64) -> String {
+    DateTime::from_timestamp(secs as i64, 0)
+        .map(|t| t.to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// Quote a CSV field when needed
+///
+/// Notification text comes from any app, so fields a spreadsheet would
+/// read as a formula get a leading `'`.
+fn csv_field(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    };
+
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryGroup {
+    pub app_name: String,
+    pub total: usize,
+    pub unread: usize,
+    pub rate_limited: usize,
+    pub latest_summary: String,
+    pub latest_at: u64,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct HistoryStats {
     pub total: usize,
@@ -230,13 +484,7 @@ impl Serialize for CloseReason {
     where
         S: serde::Serializer,
     {
-        let s = match self {
-            CloseReason::Expired => "expired",
-            CloseReason::Dismissed => "dismissed",
-            CloseReason::ActionInvoked => "action_invoked",
-            CloseReason::Closed => "closed",
-        };
-        serializer.serialize_str(s)
+        serializer.serialize_str(self.as_str())
     }
 }
 
@@ -255,3 +503,74 @@ impl<'de> Deserialize<'de> for CloseReason {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> NotificationHistory {
+        NotificationHistory::new(100, 7, PathBuf::from("/nonexistent/history.json"))
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let mut history = history();
+        history.add(Notification::new(1, "Mail", "New message").with_body("From: Ada, about the build"));
+        history.add(Notification::new(2, "Builds", "Build failed"));
+        let mut critical = Notification::new(3, "Builds", "Disk almost full");
+        critical.urgency = Urgency::Critical;
+        history.add(critical);
+
+        let ids = |query: &HistoryQuery| -> Vec<u32> {
+            history.query(query).iter().map(|e| e.notification.id).collect()
+        };
+        let text = |t: &str| HistoryQuery { text: Some(t.to_string()), ..HistoryQuery::default() };
+
+        assert_eq!(ids(&text("BUILD")), vec![2, 1]);
+        assert_eq!(ids(&text("ada build")), vec![1]);
+        assert_eq!(ids(&text("\"the build\"")), vec![1]);
+        assert_eq!(ids(&text("\"build ada\"")), Vec::<u32>::new());
+        assert_eq!(
+            ids(&HistoryQuery { app_name: Some("Builds".into()), limit: Some(1), ..HistoryQuery::default() }),
+            vec![3]
+        );
+        assert_eq!(
+            ids(&HistoryQuery { urgency: Some(Urgency::Critical), ..HistoryQuery::default() }),
+            vec![3]
+        );
+        assert!(history.query(&HistoryQuery {
+            until: Some(Utc::now() - chrono::Duration::hours(1)),
+            ..HistoryQuery::default()
+        })
+        .is_empty());
+    }
+
+    #[test]
+    fn test_app_retention() {
+        let mut history = history().with_app_retention(vec![
+            HistoryRetention { app_name: "Chat".into(), retention_days: None, max_entries: Some(2) },
+            HistoryRetention { app_name: "*".into(), retention_days: Some(1), max_entries: None },
+        ]);
+        for id in 1..=4 {
+            history.add(Notification::new(id, "Chat", "hi"));
+        }
+        history.add(Notification::new(5, "Mail", "old"));
+        history.entries[0].displayed_at -= 2 * 24 * 3600;
+
+        let ids: Vec<u32> = history.all().iter().map(|e| e.notification.id).collect();
+        assert_eq!(ids, vec![5, 4, 3]);
+        assert_eq!(history.prune(), 1);
+        assert_eq!(history.len(), 2);
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let mut history = history();
+        history.add(Notification::new(1, "Mail", "=HYPERLINK(\"x\")").with_body("line one\nline, two"));
+
+        let csv = export(&history.all(), ExportFormat::Csv).unwrap();
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("id,app_name,urgency,summary,body,"));
+        assert!(csv.contains("1,Mail,normal,\"'=HYPERLINK(\"\"x\"\")\",\"line one\nline, two\","));
+    }
+}
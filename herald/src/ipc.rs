@@ -1,15 +1,26 @@
 //! IPC server for Herald
 
+use crate::callback::{ActionEvent, CallbackRouter};
+use crate::config::AppPolicy;
+use crate::dbus::{DbusSignal, NotificationDbusServer};
+use crate::dispatch::{Dispatcher, Outcome};
 use crate::dnd::DndManager;
-use crate::history::NotificationHistory;
-use crate::notification::{CloseReason, Notification, NotificationQueue, Urgency};
+use crate::events::{EventBus, HeraldEvent};
+use crate::history::{self, ExportFormat, HistoryQuery, NotificationHistory};
+use crate::notification::{CloseReason, Notification, NotificationAction, NotificationQueue, Urgency};
+use crate::policy::PolicyStore;
+use crate::reminder::{Reminder, ReminderStore, Repeat};
 use anyhow::Result;
+use libnyx_ipc::HealthReporter;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
+
+/// How often subscribers are checked for scheduled Do Not Disturb changes
+const DND_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
 
 /// IPC request types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,17 +34,39 @@ pub enum IpcRequest {
         icon: Option<String>,
         urgency: Option<String>,
         timeout: Option<i32>,
+        /// Update an existing notification in place (e.g. progress)
+        #[serde(default)]
+        replaces_id: Option<u32>,
+        #[serde(default)]
+        actions: Vec<NotificationAction>,
+        /// Correlation token echoed back in action events
+        #[serde(default)]
+        token: Option<String>,
     },
     CloseNotification { id: u32 },
     GetNotifications,
     GetNotification { id: u32 },
+    /// Visible notifications grouped per app
+    GetGroups,
 
     // History operations
     GetHistory { limit: Option<usize> },
     GetHistoryByApp { app_name: String },
     SearchHistory { query: String },
     ClearHistory,
+    ClearHistoryByApp { app_name: String },
     GetHistoryStats,
+    /// Per-app history summary with collapse and rate-limit counts
+    GetHistoryGroups,
+    /// History filtered by text, app, urgency and time
+    QueryHistory { query: HistoryQuery },
+    /// Filtered history rendered as JSON or CSV
+    ExportHistory {
+        #[serde(default)]
+        query: HistoryQuery,
+        #[serde(default)]
+        format: ExportFormat,
+    },
 
     // DND operations
     GetDndStatus,
@@ -42,12 +75,44 @@ pub enum IpcRequest {
     EnableDndFor { minutes: u32 },
     ToggleDnd,
 
+    // Policy operations
+    GetPolicies,
+    SetPolicy { policy: AppPolicy },
+    RemovePolicy { app_name: String },
+
+    // Reminder operations
+    /// Show a notification at a later time, optionally repeating
+    Schedule {
+        #[serde(default = "default_schedule_app")]
+        app_name: String,
+        at: chrono::DateTime<chrono::Utc>,
+        #[serde(default)]
+        repeat: Option<Repeat>,
+        summary: String,
+        #[serde(default)]
+        body: Option<String>,
+        #[serde(default)]
+        actions: Vec<NotificationAction>,
+        #[serde(default)]
+        urgency: Option<String>,
+    },
+    ListSchedules,
+    CancelSchedule { id: u32 },
+
     // Action operations
     InvokeAction { id: u32, action_id: String },
 
     // Status
     GetCapabilities,
     GetServerInfo,
+
+    /// Keep the connection open and push a `HeraldEvent` for every
+    /// notification shown or closed and every Do Not Disturb change
+    Subscribe,
+}
+
+fn default_schedule_app() -> String {
+    "herald".to_string()
 }
 
 /// IPC response
@@ -56,31 +121,78 @@ pub enum IpcRequest {
 pub enum IpcResponse {
     Success { data: serde_json::Value },
     Error { message: String },
+    /// Action invoked on a notification posted over this connection
+    Action { event: ActionEvent },
 }
 
 /// IPC server
 pub struct HeraldIpcServer {
+    state: ServerState,
+}
+
+/// Handles shared with each client connection
+#[derive(Clone)]
+struct ServerState {
     queue: Arc<RwLock<NotificationQueue>>,
     history: Arc<RwLock<NotificationHistory>>,
     dnd: Arc<DndManager>,
+    dispatcher: Arc<Dispatcher>,
+    policies: Arc<PolicyStore>,
+    reminders: Arc<RwLock<ReminderStore>>,
     action_tx: tokio::sync::mpsc::Sender<(u32, String)>,
+    signal_tx: Option<tokio::sync::mpsc::Sender<DbusSignal>>,
+    callbacks: Option<Arc<CallbackRouter>>,
+    events: EventBus,
+    health: HealthReporter,
 }
 
 impl HeraldIpcServer {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         queue: Arc<RwLock<NotificationQueue>>,
         history: Arc<RwLock<NotificationHistory>>,
         dnd: Arc<DndManager>,
+        dispatcher: Arc<Dispatcher>,
+        policies: Arc<PolicyStore>,
+        reminders: Arc<RwLock<ReminderStore>>,
         action_tx: tokio::sync::mpsc::Sender<(u32, String)>,
+        events: EventBus,
     ) -> Self {
         Self {
-            queue,
-            history,
-            dnd,
-            action_tx,
+            state: ServerState {
+                queue,
+                history,
+                dnd,
+                dispatcher,
+                policies,
+                reminders,
+                action_tx,
+                signal_tx: None,
+                callbacks: None,
+                events,
+                health: HealthReporter::new("herald", env!("CARGO_PKG_VERSION")),
+            },
         }
     }
 
+    /// Emit D-Bus signals for closes made over IPC
+    pub fn with_signals(mut self, signal_tx: Option<tokio::sync::mpsc::Sender<DbusSignal>>) -> Self {
+        self.state.signal_tx = signal_tx;
+        self
+    }
+
+    /// Route invoked actions back to the connections that posted them
+    pub fn with_callbacks(mut self, callbacks: Arc<CallbackRouter>) -> Self {
+        self.state.callbacks = Some(callbacks);
+        self
+    }
+
+    /// Answer health and metrics probes from this reporter
+    pub fn with_health(mut self, health: HealthReporter) -> Self {
+        self.state.health = health;
+        self
+    }
+
     pub async fn start(&self, socket_path: &Path) -> Result<()> {
         let _ = std::fs::remove_file(socket_path);
 
@@ -90,13 +202,10 @@ impl HeraldIpcServer {
         loop {
             match listener.accept().await {
                 Ok((stream, _)) => {
-                    let queue = Arc::clone(&self.queue);
-                    let history = Arc::clone(&self.history);
-                    let dnd = Arc::clone(&self.dnd);
-                    let action_tx = self.action_tx.clone();
+                    let state = self.state.clone();
 
                     tokio::spawn(async move {
-                        if let Err(e) = handle_client(stream, queue, history, dnd, action_tx).await {
+                        if let Err(e) = handle_client(stream, state).await {
                             tracing::error!("Client error: {}", e);
                         }
                     });
@@ -109,76 +218,129 @@ impl HeraldIpcServer {
     }
 }
 
-async fn handle_client(
-    stream: UnixStream,
-    queue: Arc<RwLock<NotificationQueue>>,
-    history: Arc<RwLock<NotificationHistory>>,
-    dnd: Arc<DndManager>,
-    action_tx: tokio::sync::mpsc::Sender<(u32, String)>,
-) -> Result<()> {
+async fn handle_client(stream: UnixStream, state: ServerState) -> Result<()> {
     let (reader, mut writer) = stream.into_split();
-    let mut reader = BufReader::new(reader);
-    let mut line = String::new();
-
-    while reader.read_line(&mut line).await? > 0 {
-        let response = match serde_json::from_str::<IpcRequest>(&line) {
-            Ok(request) => process_request(request, &queue, &history, &dnd, &action_tx).await,
-            Err(e) => IpcResponse::Error {
-                message: format!("Invalid request: {}", e),
-            },
+    let mut lines = BufReader::new(reader).lines();
+    let (event_tx, mut event_rx) = tokio::sync::mpsc::channel::<ActionEvent>(16);
+
+    loop {
+        let response = tokio::select! {
+            line = lines.next_line() => {
+                let Some(line) = line? else {
+                    break;
+                };
+                if let Some(reply) = state.health.respond(&line) {
+                    writer.write_all(reply.as_bytes()).await?;
+                    writer.write_all(b"\n").await?;
+                    writer.flush().await?;
+                    continue;
+                }
+                match serde_json::from_str::<IpcRequest>(&line) {
+                    Ok(IpcRequest::Subscribe) => return stream_events(writer, &state).await,
+                    Ok(request) => process_request(request, &state, &event_tx).await,
+                    Err(e) => IpcResponse::Error {
+                        message: format!("Invalid request: {}", e),
+                    },
+                }
+            }
+            Some(event) = event_rx.recv() => IpcResponse::Action { event },
         };
 
         let response_json = serde_json::to_string(&response)?;
         writer.write_all(response_json.as_bytes()).await?;
         writer.write_all(b"\n").await?;
         writer.flush().await?;
-
-        line.clear();
     }
 
     Ok(())
 }
 
+/// Send the Do Not Disturb state, then every event, until the client goes away
+async fn stream_events(
+    mut writer: tokio::net::unix::OwnedWriteHalf,
+    state: &ServerState,
+) -> Result<()> {
+    let mut events = state.events.subscribe();
+    let mut dnd_active = state.dnd.is_active().await;
+    let mut event = HeraldEvent::Dnd { active: dnd_active };
+
+    // Schedules and timed DND end without a request to publish them
+    let mut dnd_check = tokio::time::interval(DND_CHECK_INTERVAL);
+    dnd_check.tick().await;
+
+    loop {
+        let json = serde_json::to_string(&event)?;
+        writer.write_all(json.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+        writer.flush().await?;
+
+        event = loop {
+            let next = tokio::select! {
+                next = events.recv() => match next {
+                    Ok(next) => next,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!("Subscriber fell behind by {} events", missed);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                },
+                _ = dnd_check.tick() => HeraldEvent::Dnd { active: state.dnd.is_active().await },
+            };
+
+            match next {
+                HeraldEvent::Dnd { active } if active == dnd_active => continue,
+                HeraldEvent::Dnd { active } => dnd_active = active,
+                _ => {}
+            }
+            break next;
+        };
+    }
+}
+
 async fn process_request(
     request: IpcRequest,
-    queue: &RwLock<NotificationQueue>,
-    history: &RwLock<NotificationHistory>,
-    dnd: &DndManager,
-    action_tx: &tokio::sync::mpsc::Sender<(u32, String)>,
+    state: &ServerState,
+    origin: &tokio::sync::mpsc::Sender<ActionEvent>,
 ) -> IpcResponse {
+    let ServerState { queue, history, dnd, dispatcher, policies, reminders, action_tx, signal_tx, callbacks, events, .. } = state;
+
     match request {
-        IpcRequest::Notify { app_name, summary, body, icon, urgency, timeout } => {
-            let urgency = urgency.map(|u| match u.to_lowercase().as_str() {
-                "low" => Urgency::Low,
-                "critical" => Urgency::Critical,
-                _ => Urgency::Normal,
-            }).unwrap_or(Urgency::Normal);
+        IpcRequest::Notify { app_name, summary, body, icon, urgency, timeout, replaces_id, actions, token } => {
+            let urgency = parse_urgency(urgency);
 
             let mut notification = Notification::new(0, &app_name, &summary);
             notification.body = body;
             notification.app_icon = icon;
             notification.urgency = urgency;
             notification.timeout = timeout.unwrap_or(-1);
+            notification.replaces_id = replaces_id.filter(|id| *id > 0);
+            notification.actions = actions;
 
-            // Check DND
-            if !dnd.should_show(&notification).await {
-                return IpcResponse::Success {
-                    data: serde_json::json!({ "id": 0, "suppressed": true }),
-                };
-            }
-
-            let id = queue.write().await.add(notification.clone());
+            let wants_callbacks = token.is_some() || !notification.actions.is_empty();
+            let dispatched = dispatcher.submit(notification).await;
 
-            // Add to history
-            history.write().await.add(notification);
+            if let Some(callbacks) = callbacks {
+                if wants_callbacks && dispatched.outcome != Outcome::Denied {
+                    callbacks.register(dispatched.id, &app_name, token, Some(origin.clone())).await;
+                }
+            }
 
             IpcResponse::Success {
-                data: serde_json::json!({ "id": id }),
+                data: serde_json::json!({
+                    "id": dispatched.id,
+                    "suppressed": dispatched.outcome == Outcome::Suppressed,
+                    "outcome": dispatched.outcome,
+                }),
             }
         }
 
         IpcRequest::CloseNotification { id } => {
-            queue.write().await.remove(id);
+            if queue.write().await.remove(id).is_some() {
+                events.closed(id, CloseReason::Closed);
+                if let Some(signal_tx) = signal_tx {
+                    NotificationDbusServer::emit_closed(signal_tx, id, CloseReason::Closed).await;
+                }
+            }
             history.write().await.record_close(id, CloseReason::Closed, None);
 
             IpcResponse::Success {
@@ -227,6 +389,13 @@ async fn process_request(
             }
         }
 
+        IpcRequest::GetGroups => {
+            let groups = queue.read().await.groups();
+            IpcResponse::Success {
+                data: serde_json::json!({ "groups": groups }),
+            }
+        }
+
         IpcRequest::GetHistory { limit } => {
             let history_guard = history.read().await;
             let entries: Vec<_> = history_guard.all()
@@ -240,6 +409,9 @@ async fn process_request(
                         "body": e.notification.body,
                         "timestamp": e.displayed_at,
                         "closed_at": e.closed_at,
+                        "updates": e.updates,
+                        "rate_limited": e.rate_limited,
+                        "suppressed": e.suppressed,
                     })
                 })
                 .collect();
@@ -286,6 +458,45 @@ async fn process_request(
             }
         }
 
+        IpcRequest::QueryHistory { query } => {
+            let history_guard = history.read().await;
+            let entries: Vec<_> = history_guard.query(&query)
+                .into_iter()
+                .map(|e| {
+                    serde_json::json!({
+                        "id": e.notification.id,
+                        "app_name": e.notification.app_name,
+                        "urgency": e.notification.urgency,
+                        "summary": e.notification.summary,
+                        "body": e.notification.body,
+                        "timestamp": e.displayed_at,
+                        "closed_at": e.closed_at,
+                        "close_reason": e.close_reason,
+                        "action_invoked": e.action_invoked,
+                    })
+                })
+                .collect();
+
+            IpcResponse::Success {
+                data: serde_json::json!({ "results": entries }),
+            }
+        }
+
+        IpcRequest::ExportHistory { query, format } => {
+            let history_guard = history.read().await;
+            let entries = history_guard.query(&query);
+            match history::export(&entries, format) {
+                Ok(content) => IpcResponse::Success {
+                    data: serde_json::json!({
+                        "format": format,
+                        "count": entries.len(),
+                        "content": content,
+                    }),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
         IpcRequest::ClearHistory => {
             history.write().await.clear();
             IpcResponse::Success {
@@ -293,6 +504,13 @@ async fn process_request(
             }
         }
 
+        IpcRequest::ClearHistoryByApp { app_name } => {
+            history.write().await.clear_app(&app_name);
+            IpcResponse::Success {
+                data: serde_json::json!({ "cleared": app_name }),
+            }
+        }
+
         IpcRequest::GetHistoryStats => {
             let stats = history.read().await.stats();
             IpcResponse::Success {
@@ -306,6 +524,13 @@ async fn process_request(
             }
         }
 
+        IpcRequest::GetHistoryGroups => {
+            let groups = history.read().await.groups();
+            IpcResponse::Success {
+                data: serde_json::json!({ "groups": groups }),
+            }
+        }
+
         IpcRequest::GetDndStatus => {
             let status = dnd.status().await;
             IpcResponse::Success {
@@ -320,6 +545,7 @@ async fn process_request(
 
         IpcRequest::EnableDnd => {
             dnd.enable().await;
+            events.dnd(dnd.is_active().await);
             IpcResponse::Success {
                 data: serde_json::json!({ "enabled": true }),
             }
@@ -327,6 +553,7 @@ async fn process_request(
 
         IpcRequest::DisableDnd => {
             dnd.disable().await;
+            events.dnd(dnd.is_active().await);
             IpcResponse::Success {
                 data: serde_json::json!({ "disabled": true }),
             }
@@ -334,6 +561,7 @@ async fn process_request(
 
         IpcRequest::EnableDndFor { minutes } => {
             dnd.enable_for(minutes).await;
+            events.dnd(dnd.is_active().await);
             IpcResponse::Success {
                 data: serde_json::json!({ "enabled_for": minutes }),
             }
@@ -341,11 +569,91 @@ async fn process_request(
 
         IpcRequest::ToggleDnd => {
             let enabled = dnd.toggle().await;
+            events.dnd(dnd.is_active().await);
             IpcResponse::Success {
                 data: serde_json::json!({ "enabled": enabled }),
             }
         }
 
+        IpcRequest::GetPolicies => {
+            IpcResponse::Success {
+                data: serde_json::json!({ "policies": policies.list().await }),
+            }
+        }
+
+        IpcRequest::SetPolicy { policy } => {
+            if policy.app_name.is_empty() {
+                return IpcResponse::Error {
+                    message: "Policy app_name must not be empty".to_string(),
+                };
+            }
+            policies.set(policy).await;
+            IpcResponse::Success {
+                data: serde_json::json!({ "set": true }),
+            }
+        }
+
+        IpcRequest::RemovePolicy { app_name } => {
+            if policies.remove(&app_name).await {
+                IpcResponse::Success {
+                    data: serde_json::json!({ "removed": true }),
+                }
+            } else {
+                IpcResponse::Error {
+                    message: format!("No policy for '{}'", app_name),
+                }
+            }
+        }
+
+        IpcRequest::Schedule { app_name, at, repeat, summary, body, actions, urgency } => {
+            let reminder = Reminder {
+                id: 0,
+                app_name,
+                at,
+                repeat,
+                summary,
+                body,
+                actions,
+                urgency: parse_urgency(urgency),
+                created_at: chrono::Utc::now(),
+            };
+
+            let mut store = reminders.write().await;
+            match store.add(reminder) {
+                Ok(reminder) => {
+                    if let Err(e) = store.save().await {
+                        tracing::warn!("Failed to save reminders: {}", e);
+                    }
+                    IpcResponse::Success {
+                        data: serde_json::json!({ "id": reminder.id, "at": reminder.at.to_rfc3339() }),
+                    }
+                }
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::ListSchedules => {
+            IpcResponse::Success {
+                data: serde_json::json!({ "reminders": reminders.read().await.list() }),
+            }
+        }
+
+        IpcRequest::CancelSchedule { id } => {
+            let mut store = reminders.write().await;
+            if store.cancel(id) {
+                if let Err(e) = store.save().await {
+                    tracing::warn!("Failed to save reminders: {}", e);
+                }
+                IpcResponse::Success {
+                    data: serde_json::json!({ "cancelled": true }),
+                }
+            } else {
+                IpcResponse::Error {
+                    message: format!("Reminder {} not found", id),
+                }
+            }
+        }
+
         IpcRequest::InvokeAction { id, action_id } => {
             let _ = action_tx.send((id, action_id.clone())).await;
             history.write().await.record_close(id, CloseReason::ActionInvoked, Some(action_id));
@@ -358,27 +666,57 @@ async fn process_request(
         IpcRequest::GetCapabilities => {
             IpcResponse::Success {
                 data: serde_json::json!({
-                    "capabilities": [
-                        "actions", "body", "body-hyperlinks", "body-markup",
-                        "icon-static", "persistence", "sound"
-                    ]
+                    "capabilities": NotificationDbusServer::capabilities()
                 }),
             }
         }
 
+        IpcRequest::Subscribe => IpcResponse::Error {
+            message: "Subscribe must be sent on its own connection".to_string(),
+        },
+
         IpcRequest::GetServerInfo => {
+            let (name, vendor, version, spec_version) = NotificationDbusServer::server_info();
             IpcResponse::Success {
                 data: serde_json::json!({
-                    "name": "Herald",
-                    "vendor": "Nyx",
-                    "version": "0.1.0",
-                    "spec_version": "1.2"
+                    "name": name,
+                    "vendor": vendor,
+                    "version": version,
+                    "spec_version": spec_version
                 }),
             }
         }
     }
 }
 
+fn parse_urgency(urgency: Option<String>) -> Urgency {
+    urgency.map(|u| match u.to_lowercase().as_str() {
+        "low" => Urgency::Low,
+        "critical" => Urgency::Critical,
+        _ => Urgency::Normal,
+    }).unwrap_or(Urgency::Normal)
+}
+
+/// Action events for a notification posted with [`HeraldClient::notify_with_actions`]
+pub struct ActionStream {
+    pub id: u32,
+    lines: tokio::io::Lines<BufReader<tokio::net::unix::OwnedReadHalf>>,
+    // Dropping the write half would shut down our side of the socket
+    _writer: tokio::net::unix::OwnedWriteHalf,
+}
+
+impl ActionStream {
+    /// Wait for the next invoked action; `None` once herald closes the connection
+    pub async fn next(&mut self) -> Result<Option<ActionEvent>> {
+        while let Some(line) = self.lines.next_line().await? {
+            if let IpcResponse::Action { event } = serde_json::from_str(&line)? {
+                return Ok(Some(event));
+            }
+        }
+        Ok(None)
+    }
+}
+
 /// IPC client
 pub struct HeraldClient {
     socket_path: std::path::PathBuf,
@@ -399,6 +737,9 @@ impl HeraldClient {
             icon: None,
             urgency: None,
             timeout: None,
+            replaces_id: None,
+            actions: Vec::new(),
+            token: None,
         }).await?;
 
         match response {
@@ -409,6 +750,50 @@ impl HeraldClient {
                     .ok_or_else(|| anyhow::anyhow!("No ID in response"))
             }
             IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+            IpcResponse::Action { .. } => Err(anyhow::anyhow!("Unexpected action event")),
+        }
+    }
+
+    /// Post a notification with actions and keep the connection open for its events
+    pub async fn notify_with_actions(
+        &self,
+        app_name: &str,
+        summary: &str,
+        body: Option<&str>,
+        actions: Vec<NotificationAction>,
+        token: Option<String>,
+    ) -> Result<ActionStream> {
+        let stream = UnixStream::connect(&self.socket_path).await?;
+        let (reader, mut writer) = stream.into_split();
+
+        let request = IpcRequest::Notify {
+            app_name: app_name.to_string(),
+            summary: summary.to_string(),
+            body: body.map(|s| s.to_string()),
+            icon: None,
+            urgency: None,
+            timeout: None,
+            replaces_id: None,
+            actions,
+            token,
+        };
+        writer.write_all(serde_json::to_string(&request)?.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+        writer.flush().await?;
+
+        let mut lines = BufReader::new(reader).lines();
+        let line = lines.next_line().await?.ok_or_else(|| anyhow::anyhow!("Connection closed"))?;
+
+        match serde_json::from_str(&line)? {
+            IpcResponse::Success { data } => {
+                let id = data.get("id")
+                    .and_then(|v| v.as_u64())
+                    .map(|id| id as u32)
+                    .ok_or_else(|| anyhow::anyhow!("No ID in response"))?;
+                Ok(ActionStream { id, lines, _writer: writer })
+            }
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+            IpcResponse::Action { .. } => Err(anyhow::anyhow!("Unexpected action event")),
         }
     }
 
@@ -418,6 +803,7 @@ impl HeraldClient {
         match response {
             IpcResponse::Success { .. } => Ok(()),
             IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+            IpcResponse::Action { .. } => Err(anyhow::anyhow!("Unexpected action event")),
         }
     }
 
@@ -429,6 +815,121 @@ impl HeraldClient {
                 Ok(data.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false))
             }
             IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+            IpcResponse::Action { .. } => Err(anyhow::anyhow!("Unexpected action event")),
+        }
+    }
+
+    pub async fn policies(&self) -> Result<Vec<AppPolicy>> {
+        let response = self.send(IpcRequest::GetPolicies).await?;
+
+        match response {
+            IpcResponse::Success { data } => {
+                Ok(serde_json::from_value(data.get("policies").cloned().unwrap_or_default())?)
+            }
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+            IpcResponse::Action { .. } => Err(anyhow::anyhow!("Unexpected action event")),
+        }
+    }
+
+    pub async fn set_policy(&self, policy: AppPolicy) -> Result<()> {
+        let response = self.send(IpcRequest::SetPolicy { policy }).await?;
+
+        match response {
+            IpcResponse::Success { .. } => Ok(()),
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+            IpcResponse::Action { .. } => Err(anyhow::anyhow!("Unexpected action event")),
+        }
+    }
+
+    pub async fn remove_policy(&self, app_name: &str) -> Result<()> {
+        let response = self.send(IpcRequest::RemovePolicy {
+            app_name: app_name.to_string(),
+        }).await?;
+
+        match response {
+            IpcResponse::Success { .. } => Ok(()),
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+            IpcResponse::Action { .. } => Err(anyhow::anyhow!("Unexpected action event")),
+        }
+    }
+
+    pub async fn schedule(
+        &self,
+        at: chrono::DateTime<chrono::Utc>,
+        repeat: Option<Repeat>,
+        summary: &str,
+        body: Option<&str>,
+    ) -> Result<u32> {
+        let response = self.send(IpcRequest::Schedule {
+            app_name: "herald-cli".to_string(),
+            at,
+            repeat,
+            summary: summary.to_string(),
+            body: body.map(|s| s.to_string()),
+            actions: Vec::new(),
+            urgency: None,
+        }).await?;
+
+        match response {
+            IpcResponse::Success { data } => {
+                data.get("id")
+                    .and_then(|v| v.as_u64())
+                    .map(|id| id as u32)
+                    .ok_or_else(|| anyhow::anyhow!("No ID in response"))
+            }
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+            IpcResponse::Action { .. } => Err(anyhow::anyhow!("Unexpected action event")),
+        }
+    }
+
+    pub async fn schedules(&self) -> Result<Vec<Reminder>> {
+        let response = self.send(IpcRequest::ListSchedules).await?;
+
+        match response {
+            IpcResponse::Success { data } => {
+                Ok(serde_json::from_value(data.get("reminders").cloned().unwrap_or_default())?)
+            }
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+            IpcResponse::Action { .. } => Err(anyhow::anyhow!("Unexpected action event")),
+        }
+    }
+
+    pub async fn cancel_schedule(&self, id: u32) -> Result<()> {
+        let response = self.send(IpcRequest::CancelSchedule { id }).await?;
+
+        match response {
+            IpcResponse::Success { .. } => Ok(()),
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+            IpcResponse::Action { .. } => Err(anyhow::anyhow!("Unexpected action event")),
+        }
+    }
+
+    pub async fn query_history(&self, query: HistoryQuery) -> Result<Vec<serde_json::Value>> {
+        let response = self.send(IpcRequest::QueryHistory { query }).await?;
+
+        match response {
+            IpcResponse::Success { data } => {
+                Ok(serde_json::from_value(data.get("results").cloned().unwrap_or_default())?)
+            }
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+            IpcResponse::Action { .. } => Err(anyhow::anyhow!("Unexpected action event")),
+        }
+    }
+
+    /// Exported history and the number of entries in it
+    pub async fn export_history(&self, query: HistoryQuery, format: ExportFormat) -> Result<(String, usize)> {
+        let response = self.send(IpcRequest::ExportHistory { query, format }).await?;
+
+        match response {
+            IpcResponse::Success { data } => {
+                let content = data.get("content")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("No content in response"))?;
+                let count = data.get("count").and_then(|v| v.as_u64()).unwrap_or(0);
+                Ok((content.to_string(), count as usize))
+            }
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+            IpcResponse::Action { .. } => Err(anyhow::anyhow!("Unexpected action event")),
         }
     }
 
@@ -6,7 +6,8 @@
 //!
 //! - **Freedesktop Notifications**: D-Bus notification spec (Linux/WSLg)
 //! - **Windows Toast**: Native Windows notifications (WSL)
-//! - **Notification History**: Persistent notification log
+//! - **Notification History**: Persistent notification log with search,
+//!   export and per-app retention
 //! - **Do Not Disturb**: Scheduling and manual modes
 //! - **Priority Levels**: Urgent, normal, low
 //! - **Actions**: Interactive notification buttons
@@ -18,15 +19,23 @@ mod dnd;
 mod dbus;
 mod display;
 mod ipc;
+mod ratelimit;
+mod dispatch;
+mod events;
+mod policy;
+mod reminder;
+mod callback;
 
 use libnyx_platform::{Platform, compat::NotificationBackend};
+use libnyx_ipc::{HealthReporter, HealthStatus};
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{info, error};
+use notification::CloseReason;
+use tokio::sync::{mpsc, RwLock};
+use tracing::{info, error, warn};
 
 /// Herald - Notification system
 #[derive(Parser, Debug)]
@@ -52,11 +61,126 @@ struct Args {
     #[arg(long, default_value = "normal")]
     urgency: String,
 
+    /// Notification action as id:label (repeatable)
+    #[arg(long = "action")]
+    actions: Vec<String>,
+
+    /// Wait for an action to be invoked and print its id
+    #[arg(long)]
+    wait: bool,
+
     /// Enable debug logging
     #[arg(short, long)]
     debug: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Manage per-app notification policies
+    Policy {
+        #[command(subcommand)]
+        action: PolicyCommand,
+    },
+    /// Manage scheduled reminders
+    Reminder {
+        #[command(subcommand)]
+        action: ReminderCommand,
+    },
+    /// Search and export notification history
+    History {
+        #[command(subcommand)]
+        action: HistoryCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PolicyCommand {
+    /// List policies
+    List,
+    /// Set an app's policy ("*" for the fallback)
+    Set {
+        app: String,
+        /// allow, deny or silent
+        #[arg(long, default_value = "allow")]
+        action: String,
+        /// Show even while Do Not Disturb is active
+        #[arg(long)]
+        bypass_dnd: bool,
+        /// Force an urgency (low, normal, critical)
+        #[arg(long)]
+        urgency: Option<String>,
+    },
+    /// Remove an app's policy
+    Remove { app: String },
+}
+
+#[derive(Subcommand, Debug)]
+enum ReminderCommand {
+    /// Schedule a reminder
+    Add {
+        summary: String,
+        /// When to fire: RFC 3339 time, HH:MM (next occurrence) or +N[m|h|d]
+        #[arg(long)]
+        at: String,
+        #[arg(long)]
+        body: Option<String>,
+        /// hourly, daily, weekdays, weekly or an interval in minutes
+        #[arg(long)]
+        repeat: Option<String>,
+    },
+    /// List scheduled reminders
+    List,
+    /// Cancel a reminder
+    Cancel { id: u32 },
 }
 
+/// Filters shared by history commands
+#[derive(clap::Args, Debug)]
+struct HistoryFilter {
+    /// Words or "quoted phrases" that must all appear in summary or body
+    text: Option<String>,
+    #[arg(long)]
+    app: Option<String>,
+    /// low, normal or critical
+    #[arg(long)]
+    urgency: Option<String>,
+    /// Shown at or after: RFC 3339 time, YYYY-MM-DD or -N[m|h|d]
+    #[arg(long, allow_hyphen_values = true)]
+    since: Option<String>,
+    /// Shown before: RFC 3339 time, YYYY-MM-DD or -N[m|h|d]
+    #[arg(long, allow_hyphen_values = true)]
+    until: Option<String>,
+    /// Newest entries to include
+    #[arg(long)]
+    limit: Option<usize>,
+}
+
+#[derive(Subcommand, Debug)]
+enum HistoryCommand {
+    /// Search notification history
+    Search {
+        #[command(flatten)]
+        filter: HistoryFilter,
+    },
+    /// Export notification history
+    Export {
+        #[command(flatten)]
+        filter: HistoryFilter,
+        /// json or csv
+        #[arg(long, default_value = "json")]
+        format: String,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// How often history retention is applied
+const HISTORY_PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(600);
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
@@ -66,11 +190,55 @@ async fn main() -> Result<()> {
         .with_env_filter(log_level)
         .init();
 
+    match args.command {
+        Some(Command::Policy { action }) => {
+            return policy_command(&ipc::HeraldClient::new(&args.socket), action).await;
+        }
+        Some(Command::Reminder { action }) => {
+            return reminder_command(&ipc::HeraldClient::new(&args.socket), action).await;
+        }
+        Some(Command::History { action }) => {
+            return history_command(&ipc::HeraldClient::new(&args.socket), action).await;
+        }
+        None => {}
+    }
+
     // CLI notification mode
     if let Some(summary) = args.notify {
         let client = ipc::HeraldClient::new(&args.socket);
-        let id = client.notify("herald-cli", &summary, args.body.as_deref()).await?;
-        println!("Notification sent: {}", id);
+
+        if args.actions.is_empty() && !args.wait {
+            let id = client.notify("herald-cli", &summary, args.body.as_deref()).await?;
+            println!("Notification sent: {}", id);
+            return Ok(());
+        }
+
+        let actions = args
+            .actions
+            .iter()
+            .map(|a| {
+                let (id, label) = a.split_once(':').unwrap_or((a.as_str(), a.as_str()));
+                notification::NotificationAction { id: id.to_string(), label: label.to_string() }
+            })
+            .collect();
+        let mut stream = client
+            .notify_with_actions(
+                "herald-cli",
+                &summary,
+                args.body.as_deref(),
+                actions,
+                Some(uuid::Uuid::new_v4().to_string()),
+            )
+            .await?;
+
+        if !args.wait {
+            println!("Notification sent: {}", stream.id);
+            return Ok(());
+        }
+        match stream.next().await? {
+            Some(event) => println!("{}", event.action_id),
+            None => anyhow::bail!("Herald closed the connection before an action was invoked"),
+        }
         return Ok(());
     }
 
@@ -89,55 +257,508 @@ async fn main() -> Result<()> {
 
     // Initialize components
     let history_path = std::path::PathBuf::from("/var/lib/herald/history.json");
-    let history = Arc::new(RwLock::new(history::NotificationHistory::new(
-        config.history.max_size,
-        config.history.retention_days,
-        history_path,
-    )));
+    let history = Arc::new(RwLock::new(
+        history::NotificationHistory::new(
+            config.history.max_size,
+            config.history.retention_days,
+            history_path,
+        )
+        .with_app_retention(config.history.apps.clone()),
+    ));
     let dnd_manager = Arc::new(dnd::DndManager::new(config.dnd.clone()));
     let queue = Arc::new(RwLock::new(notification::NotificationQueue::default()));
+    let policies = Arc::new(policy::PolicyStore::new(config.policies.clone()));
+    let policy_sync = policies.clone();
+    tokio::spawn(async move { policy_sync.sync_loop().await });
 
-    // Create action channel
-    let (action_tx, mut action_rx) = tokio::sync::mpsc::channel::<(u32, String)>(100);
+    let mut reminder_store = reminder::ReminderStore::new(&config.reminders);
+    if let Err(e) = reminder_store.load().await {
+        error!("Failed to load reminders: {}", e);
+    }
+    let reminders = Arc::new(RwLock::new(reminder_store));
+    let events = events::EventBus::new();
 
-    // Handle actions in background
-    tokio::spawn(async move {
-        while let Some((id, action)) = action_rx.recv().await {
-            info!("Action invoked: notification={}, action={}", id, action);
-        }
-    });
+    let dispatcher = Arc::new(dispatch::Dispatcher::new(
+        queue.clone(),
+        history.clone(),
+        dnd_manager.clone(),
+        policies.clone(),
+        ratelimit::RateLimiter::new(config.rate_limit.clone()),
+        events.clone(),
+    ));
+
+    let health = HealthReporter::new("herald", env!("CARGO_PKG_VERSION"))
+        .with_features(&["subscribe", "actions", "history-search", "history-export", "schedules"]);
 
     // Start D-Bus service only on native Linux or WSLg
-    if matches!(backend, NotificationBackend::Freedesktop) {
-        let (dbus_server, mut event_rx, signal_tx) = dbus::NotificationDbusServer::new();
-        let queue_clone = queue.clone();
-        let history_clone = history.clone();
-
-        tokio::spawn(async move {
-            while let Some(event) = event_rx.recv().await {
-                match event {
-                    dbus::DbusEvent::Notify(request) => {
-                        let mut q = queue_clone.write().await;
-                        let notification = dbus::NotificationDbusServer::to_notification(request, 0);
-                        let id = q.add(notification.clone());
-                        history_clone.write().await.add(notification);
-                        info!("D-Bus notification: id={}", id);
-                    }
-                    dbus::DbusEvent::CloseNotification(id) => {
-                        queue_clone.write().await.remove(id);
-                    }
-                    _ => {}
-                }
+    // The connection owns the bus name, so it is held for the daemon's lifetime
+    let (_dbus_connection, signal_tx) = if matches!(backend, NotificationBackend::Freedesktop) {
+        let (dbus_server, event_rx, signal_tx) = dbus::NotificationDbusServer::new();
+
+        match dbus_server.serve().await {
+            Ok(connection) => {
+                tokio::spawn(handle_dbus_events(
+                    event_rx,
+                    dispatcher.clone(),
+                    queue.clone(),
+                    history.clone(),
+                    signal_tx.clone(),
+                    events.clone(),
+                ));
+                info!("D-Bus notification service registered as {}", dbus::BUS_NAME);
+                health.set_check("dbus", HealthStatus::Healthy, None);
+                (Some(connection), Some(signal_tx))
+            }
+            Err(e) => {
+                error!("Failed to register D-Bus notification service: {}", e);
+                // Apps cannot post notifications at all without the bus name
+                health.set_check("dbus", HealthStatus::Unhealthy, Some(e.to_string()));
+                (None, None)
             }
-        });
-        info!("D-Bus notification handler started");
+        }
     } else {
         info!("D-Bus service skipped (not using Freedesktop backend)");
+        (None, None)
+    };
+
+    // Create action channel
+    let (action_tx, action_rx) = tokio::sync::mpsc::channel::<(u32, String)>(100);
+    let callbacks = Arc::new(callback::CallbackRouter::new(&config.actions));
+
+    // Handle actions in background
+    tokio::spawn(handle_actions(
+        action_rx,
+        queue.clone(),
+        callbacks.clone(),
+        signal_tx.clone(),
+        events.clone(),
+    ));
+
+    // Windows has no notification daemon to hand off to; show toasts ourselves
+    if matches!(backend, NotificationBackend::WindowsToast) {
+        let display = display::NotificationDisplay::with_actions(action_tx.clone());
+        tokio::spawn(show_toasts(display, queue.clone(), events.subscribe()));
     }
 
+    // Expire notifications whose timeout elapsed
+    tokio::spawn(expire_notifications(
+        queue.clone(),
+        history.clone(),
+        signal_tx.clone(),
+        events.clone(),
+        config.display.default_timeout_ms,
+    ));
+
+    // Apply history retention
+    tokio::spawn(prune_history(history.clone()));
+
+    // Fire reminders as they come due
+    tokio::spawn(fire_reminders(
+        reminders.clone(),
+        dispatcher.clone(),
+        config.reminders.check_interval_secs,
+    ));
+
     // Start IPC server
-    let server = ipc::HeraldIpcServer::new(queue, history, dnd_manager, action_tx);
+    let server = ipc::HeraldIpcServer::new(
+        queue,
+        history,
+        dnd_manager,
+        dispatcher,
+        policies,
+        reminders,
+        action_tx,
+        events,
+    )
+    .with_signals(signal_tx)
+    .with_callbacks(callbacks)
+    .with_health(health);
 
     info!("Herald ready");
     server.start(&args.socket).await
 }
+
+/// Apply D-Bus method calls to the queue
+async fn handle_dbus_events(
+    mut event_rx: mpsc::Receiver<dbus::DbusEvent>,
+    dispatcher: Arc<dispatch::Dispatcher>,
+    queue: Arc<RwLock<notification::NotificationQueue>>,
+    history: Arc<RwLock<history::NotificationHistory>>,
+    signal_tx: mpsc::Sender<dbus::DbusSignal>,
+    events: events::EventBus,
+) {
+    while let Some(event) = event_rx.recv().await {
+        match event {
+            dbus::DbusEvent::Notify { request, reply } => {
+                let notification = dbus::NotificationDbusServer::to_notification(request, 0);
+                let dispatched = dispatcher.submit(notification).await;
+
+                let _ = reply.send(dispatched.id);
+                info!("D-Bus notification: id={} ({:?})", dispatched.id, dispatched.outcome);
+            }
+            dbus::DbusEvent::CloseNotification(id) => {
+                if queue.write().await.remove(id).is_some() {
+                    history.write().await.record_close(id, CloseReason::Closed, None);
+                    events.closed(id, CloseReason::Closed);
+                    dbus::NotificationDbusServer::emit_closed(&signal_tx, id, CloseReason::Closed).await;
+                }
+            }
+            dbus::DbusEvent::GetCapabilities | dbus::DbusEvent::GetServerInformation => {}
+        }
+    }
+}
+
+/// Emit `ActionInvoked` and close non-resident notifications
+async fn handle_actions(
+    mut action_rx: mpsc::Receiver<(u32, String)>,
+    queue: Arc<RwLock<notification::NotificationQueue>>,
+    callbacks: Arc<callback::CallbackRouter>,
+    signal_tx: Option<mpsc::Sender<dbus::DbusSignal>>,
+    events: events::EventBus,
+) {
+    while let Some((id, action)) = action_rx.recv().await {
+        info!("Action invoked: notification={}, action={}", id, action);
+
+        let (resident, app_name) = queue
+            .read()
+            .await
+            .get(id)
+            .map(|n| (n.resident, Some(n.app_name.clone())))
+            .unwrap_or((false, None));
+
+        callbacks.deliver(id, &action, app_name.as_deref()).await;
+
+        if !resident {
+            if queue.write().await.remove(id).is_some() {
+                events.closed(id, CloseReason::ActionInvoked);
+            }
+            callbacks.forget(id).await;
+        }
+
+        if let Some(signal_tx) = &signal_tx {
+            dbus::NotificationDbusServer::emit_action(signal_tx, id, &action).await;
+            if !resident {
+                dbus::NotificationDbusServer::emit_closed(signal_tx, id, CloseReason::ActionInvoked).await;
+            }
+        }
+    }
+}
+
+/// Mirror posted and closed notifications as Windows toasts
+async fn show_toasts(
+    display: display::NotificationDisplay,
+    queue: Arc<RwLock<notification::NotificationQueue>>,
+    mut events: tokio::sync::broadcast::Receiver<events::HeraldEvent>,
+) {
+    use tokio::sync::broadcast::error::RecvError;
+
+    loop {
+        let result = match events.recv().await {
+            Ok(events::HeraldEvent::Posted { notification }) => {
+                // The event omits hints, so show from the queued notification
+                let queued = queue.read().await.get(notification.id).cloned();
+                match queued {
+                    Some(notification) => display.show(&notification).await,
+                    None => continue,
+                }
+            }
+            Ok(events::HeraldEvent::Closed { id, .. }) => display.close(id).await,
+            Ok(events::HeraldEvent::Dnd { .. }) => continue,
+            Err(RecvError::Lagged(missed)) => {
+                warn!("Toast display missed {} events", missed);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        if let Err(e) = result {
+            warn!("Toast display failed: {}", e);
+        }
+    }
+}
+
+/// Submit due reminders through the dispatcher
+async fn fire_reminders(
+    reminders: Arc<RwLock<reminder::ReminderStore>>,
+    dispatcher: Arc<dispatch::Dispatcher>,
+    check_interval_secs: u64,
+) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(check_interval_secs.max(1)));
+
+    loop {
+        interval.tick().await;
+
+        let due = {
+            let mut store = reminders.write().await;
+            let due = store.take_due(chrono::Utc::now());
+            if !due.is_empty() {
+                if let Err(e) = store.save().await {
+                    error!("Failed to save reminders: {}", e);
+                }
+            }
+            due
+        };
+
+        for reminder in due {
+            let dispatched = dispatcher.submit(reminder.to_notification()).await;
+            info!("Reminder {} fired as notification {}", reminder.id, dispatched.id);
+        }
+    }
+}
+
+/// Drop history entries past their retention
+async fn prune_history(history: Arc<RwLock<history::NotificationHistory>>) {
+    let mut interval = tokio::time::interval(HISTORY_PRUNE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let removed = history.write().await.prune();
+        if removed > 0 {
+            info!("Pruned {} history entries past retention", removed);
+        }
+    }
+}
+
+/// Close notifications whose timeout has elapsed
+async fn expire_notifications(
+    queue: Arc<RwLock<notification::NotificationQueue>>,
+    history: Arc<RwLock<history::NotificationHistory>>,
+    signal_tx: Option<mpsc::Sender<dbus::DbusSignal>>,
+    events: events::EventBus,
+    default_timeout_ms: u64,
+) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_millis(500));
+
+    loop {
+        interval.tick().await;
+
+        let expired = queue.write().await.take_expired(default_timeout_ms);
+        for notification in expired {
+            history.write().await.record_close(notification.id, CloseReason::Expired, None);
+            events.closed(notification.id, CloseReason::Expired);
+            if let Some(signal_tx) = &signal_tx {
+                dbus::NotificationDbusServer::emit_closed(signal_tx, notification.id, CloseReason::Expired).await;
+            }
+        }
+    }
+}
+
+async fn policy_command(client: &ipc::HeraldClient, command: PolicyCommand) -> Result<()> {
+    match command {
+        PolicyCommand::List => {
+            let policies = client.policies().await?;
+            if policies.is_empty() {
+                println!("No notification policies");
+            }
+            for policy in policies {
+                let mut line = format!("{:<24} {:?}", policy.app_name, policy.action).to_lowercase();
+                if policy.bypass_dnd {
+                    line.push_str("  bypass-dnd");
+                }
+                if let Some(urgency) = policy.urgency {
+                    line.push_str(&format!("  urgency={:?}", urgency).to_lowercase());
+                }
+                println!("{}", line);
+            }
+        }
+        PolicyCommand::Set { app, action, bypass_dnd, urgency } => {
+            let action = match action.to_lowercase().as_str() {
+                "allow" => config::PolicyAction::Allow,
+                "deny" => config::PolicyAction::Deny,
+                "silent" => config::PolicyAction::Silent,
+                other => anyhow::bail!("Unknown policy action '{}' (expected allow, deny or silent)", other),
+            };
+            let urgency = match urgency.as_deref().map(str::to_lowercase).as_deref() {
+                None => None,
+                Some("low") => Some(notification::Urgency::Low),
+                Some("normal") => Some(notification::Urgency::Normal),
+                Some("critical") => Some(notification::Urgency::Critical),
+                Some(other) => anyhow::bail!("Unknown urgency '{}'", other),
+            };
+
+            client.set_policy(config::AppPolicy {
+                app_name: app.clone(),
+                action,
+                bypass_dnd,
+                urgency,
+            }).await?;
+            println!("Policy set for {}", app);
+        }
+        PolicyCommand::Remove { app } => {
+            client.remove_policy(&app).await?;
+            println!("Policy removed for {}", app);
+        }
+    }
+
+    Ok(())
+}
+
+async fn reminder_command(client: &ipc::HeraldClient, command: ReminderCommand) -> Result<()> {
+    match command {
+        ReminderCommand::Add { summary, at, body, repeat } => {
+            let at = parse_when(&at)?;
+            let repeat = repeat.as_deref().map(parse_repeat).transpose()?;
+            let id = client.schedule(at, repeat, &summary, body.as_deref()).await?;
+            println!(
+                "Reminder {} scheduled for {}",
+                id,
+                at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+            );
+        }
+        ReminderCommand::List => {
+            let reminders = client.schedules().await?;
+            if reminders.is_empty() {
+                println!("No reminders scheduled");
+            }
+            for r in reminders {
+                let repeat = r.repeat.map(|rep| format!("  ({:?})", rep).to_lowercase()).unwrap_or_default();
+                println!(
+                    "{:>4}  {}  {}{}",
+                    r.id,
+                    r.at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+                    r.summary,
+                    repeat
+                );
+            }
+        }
+        ReminderCommand::Cancel { id } => {
+            client.cancel_schedule(id).await?;
+            println!("Reminder {} cancelled", id);
+        }
+    }
+
+    Ok(())
+}
+
+async fn history_command(client: &ipc::HeraldClient, command: HistoryCommand) -> Result<()> {
+    match command {
+        HistoryCommand::Search { filter } => {
+            let results = client.query_history(history_query(filter)?).await?;
+            if results.is_empty() {
+                println!("No matching notifications");
+            }
+            for entry in results {
+                let at = entry["timestamp"]
+                    .as_i64()
+                    .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+                    .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+                println!(
+                    "{:>5}  {}  {:<16} {}",
+                    entry["id"],
+                    at,
+                    entry["app_name"].as_str().unwrap_or(""),
+                    entry["summary"].as_str().unwrap_or("")
+                );
+            }
+        }
+        HistoryCommand::Export { filter, format, output } => {
+            let format = match format.to_lowercase().as_str() {
+                "json" => history::ExportFormat::Json,
+                "csv" => history::ExportFormat::Csv,
+                other => anyhow::bail!("Unknown export format '{}' (expected json or csv)", other),
+            };
+            let (content, count) = client.export_history(history_query(filter)?, format).await?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, content)?;
+                    println!("Exported {} notifications to {}", count, path.display());
+                }
+                None => print!("{}", content),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn history_query(filter: HistoryFilter) -> Result<history::HistoryQuery> {
+    let urgency = match filter.urgency.as_deref().map(str::to_lowercase).as_deref() {
+        None => None,
+        Some("low") => Some(notification::Urgency::Low),
+        Some("normal") => Some(notification::Urgency::Normal),
+        Some("critical") => Some(notification::Urgency::Critical),
+        Some(other) => anyhow::bail!("Unknown urgency '{}'", other),
+    };
+
+    Ok(history::HistoryQuery {
+        text: filter.text,
+        app_name: filter.app,
+        urgency,
+        since: filter.since.as_deref().map(parse_since).transpose()?,
+        until: filter.until.as_deref().map(parse_since).transpose()?,
+        limit: filter.limit,
+    })
+}
+
+/// Parse an RFC 3339 time, a local YYYY-MM-DD (midnight) or -N[m|h|d] ago
+fn parse_since(since: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    use chrono::TimeZone;
+
+    if let Ok(at) = chrono::DateTime::parse_from_rfc3339(since) {
+        return Ok(at.with_timezone(&chrono::Utc));
+    }
+
+    if let Some(relative) = since.strip_prefix('-') {
+        return Ok(chrono::Utc::now() - parse_offset(relative, since)?);
+    }
+
+    let date = chrono::NaiveDate::parse_from_str(since, "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("Invalid time '{}'", since))?;
+    chrono::Local
+        .from_local_datetime(&date.and_time(chrono::NaiveTime::MIN))
+        .earliest()
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .ok_or_else(|| anyhow::anyhow!("'{}' does not exist in local time", since))
+}
+
+/// Parse N[m|h|d]; `when` is the full argument, for errors
+fn parse_offset(relative: &str, when: &str) -> Result<chrono::Duration> {
+    let (amount, unit) = relative.split_at(relative.len().saturating_sub(1));
+    let amount: i64 = amount.parse().map_err(|_| anyhow::anyhow!("Invalid time '{}'", when))?;
+    Ok(match unit {
+        "m" => chrono::Duration::minutes(amount),
+        "h" => chrono::Duration::hours(amount),
+        "d" => chrono::Duration::days(amount),
+        _ => anyhow::bail!("Invalid time unit in '{}' (expected m, h or d)", when),
+    })
+}
+
+/// Parse an RFC 3339 time, a local HH:MM or a relative +N[m|h|d]
+fn parse_when(when: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    use chrono::TimeZone;
+
+    if let Ok(at) = chrono::DateTime::parse_from_rfc3339(when) {
+        return Ok(at.with_timezone(&chrono::Utc));
+    }
+
+    if let Some(relative) = when.strip_prefix('+') {
+        return Ok(chrono::Utc::now() + parse_offset(relative, when)?);
+    }
+
+    let time = chrono::NaiveTime::parse_from_str(when, "%H:%M")
+        .map_err(|_| anyhow::anyhow!("Invalid time '{}'", when))?;
+    let now = chrono::Local::now();
+    let mut date = now.date_naive();
+    if time <= now.time() {
+        date = date.succ_opt().unwrap_or(date);
+    }
+    chrono::Local
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .ok_or_else(|| anyhow::anyhow!("'{}' does not exist in local time", when))
+}
+
+fn parse_repeat(repeat: &str) -> Result<reminder::Repeat> {
+    Ok(match repeat.to_lowercase().as_str() {
+        "hourly" => reminder::Repeat::Hourly,
+        "daily" => reminder::Repeat::Daily,
+        "weekdays" => reminder::Repeat::Weekdays,
+        "weekly" => reminder::Repeat::Weekly,
+        other => match other.parse() {
+            Ok(minutes) => reminder::Repeat::Every { minutes },
+            Err(_) => anyhow::bail!("Unknown repeat '{}'", repeat),
+        },
+    })
+}
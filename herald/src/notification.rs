@@ -2,6 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 /// Notification urgency level
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
@@ -186,19 +187,42 @@ pub enum CloseReason {
 }
 
 impl CloseReason {
+    /// Reason code for the `NotificationClosed` signal
     pub fn to_code(&self) -> u32 {
         match self {
             CloseReason::Expired => 1,
-            CloseReason::Dismissed => 2,
-            CloseReason::ActionInvoked => 3,
-            CloseReason::Closed => 4,
+            // The spec has no separate code for closing via an action
+            CloseReason::Dismissed | CloseReason::ActionInvoked => 2,
+            CloseReason::Closed => 3,
+        }
+    }
+
+    /// Name used in history and exports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CloseReason::Expired => "expired",
+            CloseReason::Dismissed => "dismissed",
+            CloseReason::ActionInvoked => "action_invoked",
+            CloseReason::Closed => "closed",
         }
     }
 }
 
+/// Notifications from one app, collapsed for display
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationGroup {
+    pub app_name: String,
+    pub count: usize,
+    pub latest_id: u32,
+    pub latest_summary: String,
+    pub ids: Vec<u32>,
+}
+
 /// Notification queue manager
 pub struct NotificationQueue {
     notifications: Vec<(Notification, NotificationState)>,
+    /// When each notification was (last) posted, for expiry
+    posted: HashMap<u32, Instant>,
     next_id: u32,
     max_visible: usize,
 }
@@ -207,6 +231,7 @@ impl NotificationQueue {
     pub fn new(max_visible: usize) -> Self {
         Self {
             notifications: Vec::new(),
+            posted: HashMap::new(),
             next_id: 1,
             max_visible,
         }
@@ -220,22 +245,47 @@ impl NotificationQueue {
                 if let Some(pos) = self.notifications.iter().position(|(n, _)| n.id == replaces_id) {
                     notification.id = replaces_id;
                     self.notifications[pos] = (notification, NotificationState::Pending);
+                    self.posted.insert(replaces_id, Instant::now());
                     return replaces_id;
                 }
             }
         }
 
         // Assign new ID
-        let id = self.next_id;
-        self.next_id += 1;
+        let id = self.reserve_id();
         notification.id = id;
 
         self.notifications.push((notification, NotificationState::Pending));
+        self.posted.insert(id, Instant::now());
         id
     }
 
+    /// Allocate an ID without queueing (e.g. for notifications suppressed by DND)
+    pub fn reserve_id(&mut self) -> u32 {
+        let id = self.next_id;
+        // IDs are never 0; that value means "no notification" on D-Bus
+        self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+        id
+    }
+
+    /// Remove and return notifications whose timeout has elapsed.
+    /// Critical notifications and those with a zero timeout never expire.
+    pub fn take_expired(&mut self, default_ms: u64) -> Vec<Notification> {
+        let expired: Vec<u32> = self.notifications.iter()
+            .filter(|(n, _)| !n.is_critical())
+            .filter_map(|(n, _)| {
+                let timeout = n.effective_timeout(default_ms)?;
+                let posted = self.posted.get(&n.id)?;
+                (posted.elapsed() >= Duration::from_millis(timeout)).then_some(n.id)
+            })
+            .collect();
+
+        expired.into_iter().filter_map(|id| self.remove(id)).collect()
+    }
+
     /// Remove notification
     pub fn remove(&mut self, id: u32) -> Option<Notification> {
+        self.posted.remove(&id);
         if let Some(pos) = self.notifications.iter().position(|(n, _)| n.id == id) {
             Some(self.notifications.remove(pos).0)
         } else {
@@ -286,6 +336,33 @@ impl NotificationQueue {
         self.notifications.iter().map(|(n, _)| n).collect()
     }
 
+    /// Group queued notifications by app, most recently posted group first
+    pub fn groups(&self) -> Vec<NotificationGroup> {
+        let mut groups: Vec<NotificationGroup> = Vec::new();
+
+        for (n, _) in &self.notifications {
+            match groups.iter_mut().find(|g| g.app_name == n.app_name) {
+                Some(group) => {
+                    group.count += 1;
+                    group.latest_id = n.id;
+                    group.latest_summary = n.summary.clone();
+                    group.ids.push(n.id);
+                }
+                None => groups.push(NotificationGroup {
+                    app_name: n.app_name.clone(),
+                    count: 1,
+                    latest_id: n.id,
+                    latest_summary: n.summary.clone(),
+                    ids: vec![n.id],
+                }),
+            }
+        }
+
+        let posted = |g: &NotificationGroup| self.posted.get(&g.latest_id).copied();
+        groups.sort_by(|a, b| posted(b).cmp(&posted(a)));
+        groups
+    }
+
     /// Count by urgency
     pub fn count_by_urgency(&self, urgency: Urgency) -> usize {
         self.notifications.iter()
@@ -298,6 +375,8 @@ impl NotificationQueue {
         self.notifications.retain(|(_, state)| {
             !matches!(state, NotificationState::Closed(_))
         });
+        let live: Vec<u32> = self.notifications.iter().map(|(n, _)| n.id).collect();
+        self.posted.retain(|id, _| live.contains(id));
     }
 }
 
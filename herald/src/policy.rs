@@ -0,0 +1,158 @@
+//! Per-application notification policies
+//!
+//! Policies live in the grimoire settings store (`notifications.policies` by
+//! default) so the Notifications page in nyx-settings can edit them while
+//! herald is running. Herald polls the setting and writes its own changes
+//! (from `herald policy` / IPC) back, so both sides stay in sync. A local
+//! change that could not be written back is retried before remote state is
+//! accepted again, so it is not lost to the next poll.
+
+use crate::config::{AppPolicy, PolicyConfig};
+use anyhow::Result;
+use grimoire_client::GrimoireClient;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+/// Policy store
+pub struct PolicyStore {
+    config: PolicyConfig,
+    policies: RwLock<Vec<AppPolicy>>,
+    /// Local changes not yet written to grimoire
+    dirty: AtomicBool,
+}
+
+impl PolicyStore {
+    pub fn new(config: PolicyConfig) -> Self {
+        Self {
+            policies: RwLock::new(config.apps.clone()),
+            dirty: AtomicBool::new(false),
+            config,
+        }
+    }
+
+    /// Policy for an app, falling back to the "*" policy
+    pub async fn lookup(&self, app_name: &str) -> Option<AppPolicy> {
+        let policies = self.policies.read().await;
+        policies
+            .iter()
+            .find(|p| p.app_name.eq_ignore_ascii_case(app_name))
+            .or_else(|| policies.iter().find(|p| p.app_name == "*"))
+            .cloned()
+    }
+
+    /// All policies
+    pub async fn list(&self) -> Vec<AppPolicy> {
+        self.policies.read().await.clone()
+    }
+
+    /// Add or replace an app's policy
+    pub async fn set(&self, policy: AppPolicy) {
+        {
+            let mut policies = self.policies.write().await;
+            policies.retain(|p| !p.app_name.eq_ignore_ascii_case(&policy.app_name));
+            info!("Notification policy for '{}': {:?}", policy.app_name, policy.action);
+            policies.push(policy);
+        }
+        self.publish().await;
+    }
+
+    /// Remove an app's policy; returns whether one existed
+    pub async fn remove(&self, app_name: &str) -> bool {
+        let removed = {
+            let mut policies = self.policies.write().await;
+            let before = policies.len();
+            policies.retain(|p| !p.app_name.eq_ignore_ascii_case(app_name));
+            policies.len() != before
+        };
+        if removed {
+            self.publish().await;
+        }
+        removed
+    }
+
+    /// Poll grimoire for policy changes
+    pub async fn sync_loop(&self) {
+        if !self.config.grimoire_sync {
+            return;
+        }
+
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(self.config.sync_interval_secs.max(1)));
+        let mut seeded = false;
+
+        loop {
+            interval.tick().await;
+
+            // Local changes win until grimoire has them
+            if self.dirty.load(Ordering::SeqCst) && !self.publish().await {
+                continue;
+            }
+
+            match self.fetch().await {
+                Ok(Some(remote)) => {
+                    let mut policies = self.policies.write().await;
+                    if *policies != remote {
+                        info!("Loaded {} notification policies from grimoire", remote.len());
+                        *policies = remote;
+                    }
+                    seeded = true;
+                }
+                Ok(None) if !seeded => {
+                    // Nothing stored yet: seed grimoire with the configured policies
+                    self.publish().await;
+                    seeded = true;
+                }
+                Ok(None) => {}
+                Err(e) => debug!("Policy sync with grimoire failed: {}", e),
+            }
+        }
+    }
+
+    async fn fetch(&self) -> Result<Option<Vec<AppPolicy>>> {
+        let client = GrimoireClient::connect(&self.config.grimoire_socket).await?;
+        let value = match client.get_setting(&self.config.setting_path).await {
+            Ok(value) => value,
+            Err(grimoire_client::ClientError::NotFound(_)) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        if value.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_value(value)?))
+    }
+
+    /// Write current policies to grimoire, returning whether they were
+    /// written; on failure the store stays dirty for `sync_loop` to retry
+    async fn publish(&self) -> bool {
+        if !self.config.grimoire_sync {
+            return true;
+        }
+
+        self.dirty.store(false, Ordering::SeqCst);
+        let value = match serde_json::to_value(&*self.policies.read().await) {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("Failed to serialize notification policies: {}", e);
+                self.dirty.store(true, Ordering::SeqCst);
+                return false;
+            }
+        };
+
+        let result = async {
+            let client = GrimoireClient::connect(&self.config.grimoire_socket).await?;
+            client.set_setting(&self.config.setting_path, value).await
+        }
+        .await;
+
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                debug!("Could not publish notification policies to grimoire: {}", e);
+                self.dirty.store(true, Ordering::SeqCst);
+                false
+            }
+        }
+    }
+}
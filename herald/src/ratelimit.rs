@@ -0,0 +1,120 @@
+//! Per-application rate limiting
+//!
+//! Apps that post more than `max_per_window` notifications within a window
+//! are throttled: further notifications go to history only, and a single
+//! digest notification per app is kept up to date with the number held back.
+
+use crate::config::RateLimitConfig;
+use crate::notification::{Notification, Urgency};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Category set on digest notifications, for display only
+pub const DIGEST_CATEGORY: &str = "herald.digest";
+
+/// Outcome of a rate limit check
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateDecision {
+    Allow,
+    /// Over the limit; `suppressed` notifications held back in this burst
+    Limited { suppressed: u32, digest_id: Option<u32> },
+}
+
+#[derive(Debug, Default)]
+struct AppWindow {
+    recent: VecDeque<Instant>,
+    suppressed: u32,
+    digest_id: Option<u32>,
+}
+
+/// Sliding-window rate limiter keyed by app name
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    apps: HashMap<String, AppWindow>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            apps: HashMap::new(),
+        }
+    }
+
+    /// Check whether a notification may be shown
+    pub fn check(&mut self, notification: &Notification) -> RateDecision {
+        // Critical notifications are never held back. Digests are queued by
+        // the dispatcher without a check, so the category exempts nothing:
+        // clients can set it too.
+        if !self.config.enabled || notification.urgency == Urgency::Critical {
+            return RateDecision::Allow;
+        }
+
+        let window = Duration::from_secs(self.config.window_secs);
+        let now = Instant::now();
+        let app = self.apps.entry(notification.app_name.clone()).or_default();
+
+        while app.recent.front().is_some_and(|t| now.duration_since(*t) >= window) {
+            app.recent.pop_front();
+        }
+
+        if app.recent.len() < self.config.max_per_window {
+            if app.recent.is_empty() {
+                // A quiet window ends the burst; the next one gets a fresh digest
+                app.suppressed = 0;
+                app.digest_id = None;
+            }
+            app.recent.push_back(now);
+            RateDecision::Allow
+        } else {
+            app.suppressed += 1;
+            RateDecision::Limited {
+                suppressed: app.suppressed,
+                digest_id: app.digest_id,
+            }
+        }
+    }
+
+    /// Remember the digest notification shown for an app
+    pub fn set_digest(&mut self, app_name: &str, id: u32) {
+        if let Some(app) = self.apps.get_mut(app_name) {
+            app.digest_id = Some(id);
+        }
+    }
+
+    /// Build (or update) the digest notification for an app
+    pub fn digest(app_name: &str, suppressed: u32, replaces_id: Option<u32>) -> Notification {
+        let mut digest = Notification::new(0, app_name, &format!(
+            "{} more notification{} from {}",
+            suppressed,
+            if suppressed == 1 { "" } else { "s" },
+            app_name
+        ))
+        .with_body("Open notification history to see them")
+        .with_urgency(Urgency::Low)
+        .with_category(DIGEST_CATEGORY);
+        digest.replaces_id = replaces_id;
+        digest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_category_is_not_exempt() {
+        let mut limiter = RateLimiter::new(RateLimitConfig {
+            enabled: true,
+            max_per_window: 1,
+            ..RateLimitConfig::default()
+        });
+        let spoofed = Notification::new(0, "spammer", "hi").with_category(DIGEST_CATEGORY);
+
+        assert_eq!(limiter.check(&spoofed), RateDecision::Allow);
+        assert!(matches!(limiter.check(&spoofed), RateDecision::Limited { suppressed: 1, .. }));
+
+        let critical = Notification::new(0, "spammer", "hi").with_urgency(Urgency::Critical);
+        assert_eq!(limiter.check(&critical), RateDecision::Allow);
+    }
+}
@@ -0,0 +1,202 @@
+//! Scheduled reminders
+//!
+//! Reminders are persisted to disk and survive daemon restarts. When one
+//! comes due it is submitted through the dispatcher like any other
+//! notification, so policies, DND and rate limiting still apply. Due times
+//! are compared against the wall clock on every check, so clock steps and
+//! timezone changes made by chronos take effect without rescheduling; daily
+//! and weekly repeats are computed in local time so they keep their
+//! wall-clock time across DST changes.
+
+use crate::config::ReminderConfig;
+use crate::notification::{Notification, NotificationAction, Urgency};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration, Local, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::{debug, info};
+
+/// Notification category for fired reminders
+pub const REMINDER_CATEGORY: &str = "herald.reminder";
+
+/// Reminders due longer ago than this count as missed
+const MISSED_GRACE_SECS: i64 = 60;
+
+/// How a reminder repeats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Repeat {
+    Hourly,
+    Daily,
+    /// Monday to Friday
+    Weekdays,
+    Weekly,
+    Every { minutes: u32 },
+}
+
+impl Repeat {
+    /// Next occurrence after `at`
+    fn next(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Repeat::Hourly => at + Duration::hours(1),
+            Repeat::Every { minutes } => at + Duration::minutes((*minutes).max(1) as i64),
+            Repeat::Daily => add_local_days(at, 1),
+            Repeat::Weekly => add_local_days(at, 7),
+            Repeat::Weekdays => {
+                let mut next = add_local_days(at, 1);
+                while matches!(next.with_timezone(&Local).weekday(), Weekday::Sat | Weekday::Sun) {
+                    next = add_local_days(next, 1);
+                }
+                next
+            }
+        }
+    }
+}
+
+/// Shift by whole days keeping the local wall-clock time
+fn add_local_days(at: DateTime<Utc>, days: i64) -> DateTime<Utc> {
+    let local = at.with_timezone(&Local).naive_local() + Duration::days(days);
+    Local
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+        // The wall-clock time does not exist that day (DST gap)
+        .unwrap_or(at + Duration::days(days))
+}
+
+/// A scheduled reminder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reminder {
+    pub id: u32,
+    pub app_name: String,
+    /// Next time the reminder fires
+    pub at: DateTime<Utc>,
+    #[serde(default)]
+    pub repeat: Option<Repeat>,
+    pub summary: String,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub actions: Vec<NotificationAction>,
+    #[serde(default)]
+    pub urgency: Urgency,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Reminder {
+    /// Notification to show when the reminder fires
+    pub fn to_notification(&self) -> Notification {
+        let mut notification = Notification::new(0, &self.app_name, &self.summary);
+        notification.body = self.body.clone();
+        notification.actions = self.actions.clone();
+        notification.urgency = self.urgency;
+        notification.category = Some(REMINDER_CATEGORY.to_string());
+        notification
+    }
+}
+
+/// Persistent reminder store
+pub struct ReminderStore {
+    reminders: Vec<Reminder>,
+    next_id: u32,
+    file_path: PathBuf,
+    fire_missed: bool,
+}
+
+impl ReminderStore {
+    pub fn new(config: &ReminderConfig) -> Self {
+        Self {
+            reminders: Vec::new(),
+            next_id: 1,
+            file_path: PathBuf::from(&config.file_path),
+            fire_missed: config.fire_missed,
+        }
+    }
+
+    /// Load reminders from file
+    pub async fn load(&mut self) -> Result<()> {
+        if self.file_path.exists() {
+            let content = tokio::fs::read_to_string(&self.file_path).await?;
+            self.reminders = serde_json::from_str(&content)?;
+            self.next_id = self.reminders.iter().map(|r| r.id).max().unwrap_or(0) + 1;
+            info!("Loaded {} reminders", self.reminders.len());
+        }
+        Ok(())
+    }
+
+    /// Save reminders to file
+    pub async fn save(&self) -> Result<()> {
+        if let Some(parent) = self.file_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let content = serde_json::to_string_pretty(&self.reminders)?;
+        tokio::fs::write(&self.file_path, content).await?;
+        Ok(())
+    }
+
+    /// Schedule a reminder; its id and creation time are assigned here
+    pub fn add(&mut self, mut reminder: Reminder) -> Result<Reminder> {
+        if reminder.summary.is_empty() {
+            return Err(anyhow!("Reminder summary must not be empty"));
+        }
+        if reminder.repeat == Some(Repeat::Every { minutes: 0 }) {
+            return Err(anyhow!("Repeat interval must be at least one minute"));
+        }
+
+        reminder.id = self.next_id;
+        reminder.created_at = Utc::now();
+        self.next_id += 1;
+
+        info!("Reminder {} scheduled for {}", reminder.id, reminder.at.to_rfc3339());
+        self.reminders.push(reminder.clone());
+        Ok(reminder)
+    }
+
+    /// Cancel a reminder; returns whether it existed
+    pub fn cancel(&mut self, id: u32) -> bool {
+        let before = self.reminders.len();
+        self.reminders.retain(|r| r.id != id);
+        self.reminders.len() != before
+    }
+
+    /// Reminders ordered by next due time
+    pub fn list(&self) -> Vec<Reminder> {
+        let mut reminders = self.reminders.clone();
+        reminders.sort_by_key(|r| r.at);
+        reminders
+    }
+
+    /// Remove or reschedule reminders due at `now`, returning those to fire
+    ///
+    /// A repeating reminder that missed several occurrences fires once and
+    /// moves to its next future occurrence.
+    pub fn take_due(&mut self, now: DateTime<Utc>) -> Vec<Reminder> {
+        let mut fired = Vec::new();
+        let missed_before = now - Duration::seconds(MISSED_GRACE_SECS);
+
+        self.reminders.retain_mut(|reminder| {
+            if reminder.at > now {
+                return true;
+            }
+
+            if self.fire_missed || reminder.at >= missed_before {
+                fired.push(reminder.clone());
+            } else {
+                debug!("Skipping missed reminder {}", reminder.id);
+            }
+
+            match reminder.repeat {
+                Some(repeat) => {
+                    while reminder.at <= now {
+                        reminder.at = repeat.next(reminder.at);
+                    }
+                    true
+                }
+                None => false,
+            }
+        });
+
+        fired
+    }
+}
@@ -3,7 +3,7 @@
 use crate::service::ServiceSpec;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing::{debug, info};
 
 /// Init system configuration
@@ -44,6 +44,10 @@ pub struct SystemConfig {
     /// Guardian integration
     #[serde(default)]
     pub guardian: GuardianConfig,
+
+    /// Where to go when a critical service keeps failing
+    #[serde(default)]
+    pub rescue: RescueConfig,
 }
 
 impl Default for SystemConfig {
@@ -54,6 +58,7 @@ impl Default for SystemConfig {
             watchdog_enabled: false,
             watchdog_timeout_sec: default_watchdog_timeout(),
             guardian: GuardianConfig::default(),
+            rescue: RescueConfig::default(),
         }
     }
 }
@@ -111,6 +116,73 @@ pub enum SecurityPolicy {
     Prompt,
 }
 
+/// Rescue target configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RescueConfig {
+    /// Drop to the rescue target when a critical service gives out;
+    /// otherwise keep running without it
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Shell put on the console
+    #[serde(default = "default_rescue_shell")]
+    pub shell: String,
+
+    /// Arguments for the shell
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Used when the shell will not start, and on the rescue socket
+    #[serde(default = "default_fallback_shell")]
+    pub fallback_shell: String,
+
+    /// Console device
+    #[serde(default = "default_console")]
+    pub console: PathBuf,
+
+    /// Root-only socket handing out fallback shells; none to disable
+    #[serde(default = "default_rescue_socket")]
+    pub socket: Option<PathBuf>,
+
+    /// Recent failures shown in the banner
+    #[serde(default = "default_banner_lines")]
+    pub banner_lines: usize,
+}
+
+impl Default for RescueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            shell: default_rescue_shell(),
+            args: Vec::new(),
+            fallback_shell: default_fallback_shell(),
+            console: default_console(),
+            socket: default_rescue_socket(),
+            banner_lines: default_banner_lines(),
+        }
+    }
+}
+
+fn default_rescue_shell() -> String {
+    "/usr/bin/umbra".into()
+}
+
+fn default_fallback_shell() -> String {
+    "/bin/sh".into()
+}
+
+fn default_console() -> PathBuf {
+    PathBuf::from("/dev/console")
+}
+
+fn default_rescue_socket() -> Option<PathBuf> {
+    Some(PathBuf::from("/run/nyx/rescue.sock"))
+}
+
+fn default_banner_lines() -> usize {
+    20
+}
+
 /// Boot target (group of services)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BootTarget {
@@ -228,6 +300,7 @@ fn default_services() -> Vec<ServiceSpec> {
             dependencies: vec![],
             capabilities: vec!["cap:full".into()],
             environment: Default::default(),
+            critical: true,
             ..Default::default()
         },
         ServiceSpec {
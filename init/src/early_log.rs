@@ -0,0 +1,123 @@
+//! Early log buffer
+//!
+//! Init keeps its latest warnings and errors in memory, so they can be put
+//! on the console when boot goes wrong and nothing is up to read the logs.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// A logged warning or error
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    /// Time since init started
+    pub uptime: Duration,
+    pub level: Level,
+    pub message: String,
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:>9.3}] {:<5} {}", self.uptime.as_secs_f64(), self.level, self.message)
+    }
+}
+
+/// Bounded buffer of the latest warnings and errors
+pub struct EarlyLog {
+    started: Instant,
+    capacity: usize,
+    entries: Mutex<VecDeque<LogEntry>>,
+}
+
+impl EarlyLog {
+    /// Create a buffer keeping the last `capacity` entries
+    pub fn new(capacity: usize) -> Arc<Self> {
+        Arc::new(Self {
+            started: Instant::now(),
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+        })
+    }
+
+    /// Layer that feeds this buffer
+    pub fn layer(self: &Arc<Self>) -> EarlyLogLayer {
+        EarlyLogLayer { log: self.clone() }
+    }
+
+    /// Up to `limit` of the latest entries, oldest first
+    pub fn recent(&self, limit: usize) -> Vec<LogEntry> {
+        let entries = self.entries.lock().unwrap();
+        entries.iter().skip(entries.len().saturating_sub(limit)).cloned().collect()
+    }
+
+    fn push(&self, level: Level, message: String) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(LogEntry {
+            uptime: self.started.elapsed(),
+            level,
+            message,
+        });
+    }
+}
+
+/// Records warnings and errors into an [`EarlyLog`]
+pub struct EarlyLogLayer {
+    log: Arc<EarlyLog>,
+}
+
+impl<S: Subscriber> Layer<S> for EarlyLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let level = *event.metadata().level();
+        if level > Level::WARN {
+            return;
+        }
+
+        let mut message = MessageVisitor(String::new());
+        event.record(&mut message);
+        self.log.push(level, message.0);
+    }
+}
+
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0.insert_str(0, &format!("{:?}", value));
+        } else {
+            self.0.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_keeps_latest_warnings() {
+        let log = EarlyLog::new(2);
+        let subscriber = tracing_subscriber::registry().with(log.layer());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::error!("first");
+            tracing::info!("not kept");
+            tracing::warn!(service = "guardian", "second");
+            tracing::error!("third");
+        });
+
+        let recent = log.recent(10);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].message, "second service=\"guardian\"");
+        assert_eq!(recent[1].level, Level::ERROR);
+        assert!(recent[1].to_string().ends_with("ERROR third"));
+    }
+}
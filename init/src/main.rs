@@ -50,16 +50,23 @@ mod dependency;
 mod health;
 mod ipc;
 mod grimoire;
+mod early_log;
+mod rescue;
 
 pub use config::InitConfig;
 pub use service::{Service, ServiceState, ServiceSpec};
-pub use supervisor::Supervisor;
+pub use supervisor::{Exit, Supervisor};
 pub use dependency::DependencyGraph;
 
 use anyhow::Result;
 use clap::Parser;
 use std::path::PathBuf;
 use tracing::{info, error, warn};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// Warnings and errors kept for the rescue banner
+const EARLY_LOG_CAPACITY: usize = 64;
 
 /// nyx-init - DaemonOS init system
 #[derive(Parser, Debug)]
@@ -86,16 +93,25 @@ struct Args {
 async fn main() -> Result<()> {
     let args = Args::parse();
 
-    // Initialize logging
+    // Initialize logging, keeping recent failures for the rescue banner
     let log_level = if args.debug { "debug" } else { "info" };
-    tracing_subscriber::fmt()
-        .with_env_filter(log_level)
+    let early_log = early_log::EarlyLog::new(EARLY_LOG_CAPACITY);
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::EnvFilter::new(log_level))
+        .with(tracing_subscriber::fmt::layer())
+        .with(early_log.layer())
         .init();
 
     info!("nyx-init v{} starting", env!("CARGO_PKG_VERSION"));
 
     // Load configuration from Grimoire
-    let config = config::load_config(&args.config_dir).await?;
+    let mut config = config::load_config(&args.config_dir).await?;
+
+    // A user session has no console to rescue
+    if args.user_session {
+        config.system.rescue.enabled = false;
+    }
+    let rescue_config = config.system.rescue.clone();
 
     if args.dry_run {
         info!("Dry run mode - validating configuration");
@@ -120,7 +136,10 @@ async fn main() -> Result<()> {
     supervisor.start_all().await?;
 
     // Enter main loop
-    supervisor.run().await
+    match supervisor.run().await? {
+        Exit::Shutdown => Ok(()),
+        Exit::Rescue(reason) => rescue::Rescue::new(&rescue_config, early_log).run(&reason).await,
+    }
 }
 
 async fn setup_pid1_environment() -> Result<()> {
@@ -0,0 +1,173 @@
+//! Rescue target
+//!
+//! When a critical service cannot be kept running, init stops everything
+//! else and puts a shell on the console rather than leaving the machine
+//! hung: umbra, or the fallback shell if umbra will not start, under a
+//! banner with the recent failures from the early log. A root-only socket
+//! hands the fallback shell to whoever connects to it, for machines whose
+//! console is out of reach.
+
+use crate::config::RescueConfig;
+use crate::early_log::EarlyLog;
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::os::fd::OwnedFd;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UnixListener;
+use tokio::process::{Child, Command};
+use tracing::{error, info, warn};
+
+/// Wait before putting a new shell up after one exits
+const RESPAWN_DELAY: Duration = Duration::from_secs(1);
+
+/// The rescue target
+pub struct Rescue {
+    config: RescueConfig,
+    log: Arc<EarlyLog>,
+}
+
+impl Rescue {
+    /// Create the rescue target
+    pub fn new(config: &RescueConfig, log: Arc<EarlyLog>) -> Self {
+        Self {
+            config: config.clone(),
+            log,
+        }
+    }
+
+    /// Keep a shell on the console until the machine is rebooted
+    pub async fn run(&self, reason: &str) -> Result<()> {
+        error!("Entering rescue target: {}", reason);
+        let banner = self.banner(reason);
+
+        if let Some(socket) = self.config.socket.clone() {
+            let banner = banner.clone();
+            let shell = self.config.fallback_shell.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve_socket(&socket, &banner, &shell).await {
+                    error!("Rescue socket unavailable: {:#}", e);
+                }
+            });
+        }
+
+        loop {
+            match self.console_shell(&banner) {
+                Ok(mut child) => match child.wait().await {
+                    Ok(status) => info!("Rescue shell exited with {}", status),
+                    Err(e) => warn!("Lost the rescue shell: {}", e),
+                },
+                Err(e) => error!("No shell on {}: {:#}", self.config.console.display(), e),
+            }
+            tokio::time::sleep(RESPAWN_DELAY).await;
+        }
+    }
+
+    /// Why we are here and what went wrong before
+    pub fn banner(&self, reason: &str) -> String {
+        let mut banner = format!(
+            "\r\n*** nyx-init: rescue mode ***\r\n\r\n{}\r\nAll other services have been stopped.\r\n",
+            reason
+        );
+
+        let recent = self.log.recent(self.config.banner_lines);
+        if !recent.is_empty() {
+            banner.push_str("\r\nRecent failures:\r\n");
+            for entry in recent {
+                banner.push_str(&format!("  {}\r\n", entry));
+            }
+        }
+        banner.push_str("\r\nFix the configuration under /grimoire/system and reboot.\r\n\r\n");
+        banner
+    }
+
+    /// Print the banner on the console and start a shell there
+    fn console_shell(&self, banner: &str) -> Result<Child> {
+        let console = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.config.console)
+            .context("Failed to open the console")?;
+        (&console).write_all(banner.as_bytes())?;
+
+        match spawn_console_shell(&self.config.shell, &self.config.args, &console) {
+            Ok(child) => Ok(child),
+            Err(e) => {
+                warn!(
+                    "{} did not start ({}), falling back to {}",
+                    self.config.shell, e, self.config.fallback_shell
+                );
+                spawn_console_shell(&self.config.fallback_shell, &[], &console)
+                    .with_context(|| format!("Failed to start {}", self.config.fallback_shell))
+            }
+        }
+    }
+}
+
+/// Start `program` in a new session with the console as its terminal
+fn spawn_console_shell(program: &str, args: &[String], console: &File) -> std::io::Result<Child> {
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .env("TERM", "linux")
+        .current_dir("/")
+        .stdin(console.try_clone()?)
+        .stdout(console.try_clone()?)
+        .stderr(console.try_clone()?);
+
+    // SAFETY: setsid and ioctl are async-signal-safe
+    unsafe {
+        cmd.pre_exec(|| {
+            nix::unistd::setsid()?;
+            // Take the console over from whatever session had it, so the
+            // shell gets job control
+            nix::libc::ioctl(0, nix::libc::TIOCSCTTY, 1);
+            Ok(())
+        });
+    }
+    cmd.spawn()
+}
+
+/// Give every connection on `path` a fallback shell
+async fn serve_socket(path: &Path, banner: &str, shell: &str) -> Result<()> {
+    let _ = std::fs::remove_file(path);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(path).with_context(|| format!("Failed to bind {}", path.display()))?;
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    info!("Rescue shell available on {}", path.display());
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let spawned = (|| -> Result<Child> {
+            let stream = stream.into_std()?;
+            stream.set_nonblocking(false)?;
+            (&stream).write_all(banner.as_bytes())?;
+
+            let fd = OwnedFd::from(stream);
+            let child = Command::new(shell)
+                .current_dir("/")
+                .stdin(Stdio::from(fd.try_clone()?))
+                .stdout(Stdio::from(fd.try_clone()?))
+                .stderr(Stdio::from(fd))
+                .spawn()?;
+            Ok(child)
+        })();
+
+        match spawned {
+            Ok(mut child) => {
+                info!("Rescue shell started on {}", path.display());
+                tokio::spawn(async move {
+                    let _ = child.wait().await;
+                });
+            }
+            Err(e) => warn!("Failed to start a rescue shell on {}: {:#}", path.display(), e),
+        }
+    }
+}
@@ -80,6 +80,11 @@ pub struct ServiceSpec {
     /// Timeout for service to become ready
     #[serde(default = "default_ready_timeout")]
     pub ready_timeout_sec: u32,
+
+    /// The system is unusable without it: once it fails for good, init
+    /// drops to the rescue target
+    #[serde(default)]
+    pub critical: bool,
 }
 
 impl Default for ServiceSpec {
@@ -103,6 +108,7 @@ impl Default for ServiceSpec {
             sandbox: SandboxConfig::default(),
             ready_notify: ReadyNotify::default(),
             ready_timeout_sec: default_ready_timeout(),
+            critical: false,
         }
     }
 }
@@ -5,6 +5,7 @@ use crate::dependency::DependencyGraph;
 use crate::service::{Service, ServiceState};
 use anyhow::Result;
 use dashmap::DashMap;
+use std::collections::HashSet;
 use libnyx_ipc::guardian::GuardianClient;
 use libnyx_ipc::protocol::{CapabilityRequest, Decision};
 use std::sync::Arc;
@@ -31,6 +32,15 @@ pub enum SupervisorEvent {
     ShutdownComplete,
 }
 
+/// Why the supervisor stopped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Exit {
+    /// Asked to shut down
+    Shutdown,
+    /// A critical service failed for good; everything else is stopped
+    Rescue(String),
+}
+
 /// Service supervisor
 pub struct Supervisor {
     /// Configuration
@@ -45,6 +55,10 @@ pub struct Supervisor {
     shutdown: Arc<tokio::sync::Notify>,
     /// Guardian client (if enabled)
     guardian: Option<Arc<Mutex<GuardianClient>>>,
+    /// Critical services given up on
+    given_up: HashSet<String>,
+    /// Set once the rescue target is needed
+    rescue: Option<String>,
 }
 
 impl Supervisor {
@@ -59,6 +73,8 @@ impl Supervisor {
             events,
             shutdown: Arc::new(tokio::sync::Notify::new()),
             guardian: None,
+            given_up: HashSet::new(),
+            rescue: None,
         }
     }
 
@@ -126,11 +142,26 @@ impl Supervisor {
                 .cloned();
 
             if let Some(spec) = spec {
-                self.start_service(&spec).await?;
+                if let Err(e) = self.start_service(&spec).await {
+                    error!("Failed to start {}: {:#}", spec.name, e);
+                    let _ = self.events.send(SupervisorEvent::ServiceFailed {
+                        name: spec.name.clone(),
+                        error: e.to_string(),
+                    });
+
+                    // Spawn failures are retried by the main loop; anything
+                    // earlier never will be
+                    if spec.critical && !self.services.contains_key(&spec.name) {
+                        self.give_up(&spec.name, &e.to_string());
+                        if self.rescue.is_some() {
+                            return Ok(());
+                        }
+                    }
+                }
             }
         }
 
-        info!("All services started");
+        info!("Service startup complete");
         let _ = self.events.send(SupervisorEvent::AllServicesReady);
 
         Ok(())
@@ -167,7 +198,7 @@ impl Supervisor {
 
         // Create and start service
         let mut service = Service::new(spec.clone());
-        service.start().await?;
+        let started = service.start().await;
 
         if let Some(pid) = service.pid {
             let _ = self.events.send(SupervisorEvent::ServiceStarted {
@@ -176,10 +207,26 @@ impl Supervisor {
             });
         }
 
-        // Store service
+        // Store service, failed or not, so its restart policy applies
         self.services.insert(name, service);
 
-        Ok(())
+        started
+    }
+
+    /// Stop waiting for a critical service: ask for the rescue target,
+    /// or carry on without it if that is disabled
+    fn give_up(&mut self, name: &str, detail: &str) {
+        if !self.given_up.insert(name.to_string()) {
+            return;
+        }
+
+        let reason = format!("Critical service {} failed: {}", name, detail);
+        if self.config.system.rescue.enabled {
+            error!("{}", reason);
+            self.rescue.get_or_insert(reason);
+        } else {
+            error!("{}; rescue target disabled, continuing without it", reason);
+        }
     }
 
     /// Request capabilities from Guardian
@@ -304,7 +351,7 @@ impl Supervisor {
     }
 
     /// Main supervisor loop
-    pub async fn run(&mut self) -> Result<()> {
+    pub async fn run(&mut self) -> Result<Exit> {
         info!("Supervisor entering main loop");
 
         let mut check_interval = tokio::time::interval(Duration::from_secs(1));
@@ -321,6 +368,11 @@ impl Supervisor {
         )?;
 
         loop {
+            if let Some(reason) = self.rescue.take() {
+                self.stop_all().await?;
+                return Ok(Exit::Rescue(reason));
+            }
+
             tokio::select! {
                 // Check service health
                 _ = check_interval.tick() => {
@@ -355,12 +407,13 @@ impl Supervisor {
         // Graceful shutdown
         self.stop_all().await?;
 
-        Ok(())
+        Ok(Exit::Shutdown)
     }
 
     /// Check service states and restart if needed
     async fn check_services(&mut self) {
         let mut to_restart = Vec::new();
+        let mut failed_critical = Vec::new();
 
         for mut entry in self.services.iter_mut() {
             let name = entry.key().clone();
@@ -370,10 +423,19 @@ impl Supervisor {
             if !service.check_alive().await {
                 if service.should_restart() {
                     to_restart.push(name);
+                } else if service.spec.critical && service.state == ServiceState::Failed {
+                    let detail = service.last_error.clone().unwrap_or_else(|| {
+                        format!("still failing after {} restarts", service.restart_count)
+                    });
+                    failed_critical.push((name, detail));
                 }
             }
         }
 
+        for (name, detail) in failed_critical {
+            self.give_up(&name, &detail);
+        }
+
         // Restart services that need it
         for name in to_restart {
             if let Some(mut service) = self.services.get_mut(&name) {
@@ -3,6 +3,7 @@
 use crate::backlight::BacklightInfo;
 use crate::display::DisplayInfo;
 use anyhow::Result;
+use libnyx_ipc::Hello;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
@@ -150,15 +151,21 @@ async fn handle_client<H: IpcHandler>(stream: UnixStream, handler: Arc<H>) -> Re
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
 
+    let hello = Hello::new("iris", env!("CARGO_PKG_VERSION")).with_features(&["night-light"]);
+
     while reader.read_line(&mut line).await? > 0 {
-        let response = match serde_json::from_str::<IpcRequest>(&line) {
-            Ok(request) => process_request(request, handler.as_ref()).await,
-            Err(e) => IpcResponse::Error {
-                message: format!("Invalid request: {}", e),
-            },
+        let response_json = match hello.respond(&line) {
+            Some(reply) => reply,
+            None => {
+                let response = match serde_json::from_str::<IpcRequest>(&line) {
+                    Ok(request) => process_request(request, handler.as_ref()).await,
+                    Err(e) => IpcResponse::Error {
+                        message: format!("Invalid request: {}", e),
+                    },
+                };
+                serde_json::to_string(&response)?
+            }
         };
-
-        let response_json = serde_json::to_string(&response)?;
         writer.write_all(response_json.as_bytes()).await?;
         writer.write_all(b"\n").await?;
         writer.flush().await?;
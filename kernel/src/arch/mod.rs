@@ -58,6 +58,7 @@ pub mod x86_64 {
         pub fn cpu_count() -> u32 { CPU_COUNT.load(Ordering::Relaxed) }
         pub fn send_ipi_to(_cpu: u32, _vector: u8) {}
         pub fn init_apic_timer(_hz: u32) {}
+        pub fn arm_tsc_deadline(_tsc: u64) {}
         pub fn send_eoi() {}
     }
 
@@ -202,3 +203,42 @@ pub fn start_secondary_cpus() {
     #[cfg(all(feature = "arch-x86_64", not(test)))]
     x86_64::smp::start_aps();
 }
+
+/// CPU hotplug errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotplugError {
+    /// No such CPU
+    InvalidCpu,
+    /// The bootstrap processor always stays online
+    BootCpu,
+    /// CPU is already online
+    AlreadyOnline,
+    /// CPU is already offline
+    AlreadyOffline,
+    /// CPU did not answer the startup sequence
+    StartFailed,
+}
+
+/// Bring a secondary CPU online at runtime
+pub fn cpu_up(cpu_id: u32) -> Result<(), HotplugError> {
+    #[cfg(all(feature = "arch-x86_64", not(test)))]
+    return x86_64::smp::cpu_up(cpu_id);
+
+    #[cfg(not(all(feature = "arch-x86_64", not(test))))]
+    {
+        let _ = cpu_id;
+        Err(HotplugError::InvalidCpu)
+    }
+}
+
+/// Take a secondary CPU offline at runtime
+pub fn cpu_down(cpu_id: u32) -> Result<(), HotplugError> {
+    #[cfg(all(feature = "arch-x86_64", not(test)))]
+    return x86_64::smp::cpu_down(cpu_id);
+
+    #[cfg(not(all(feature = "arch-x86_64", not(test))))]
+    {
+        let _ = cpu_id;
+        Err(HotplugError::InvalidCpu)
+    }
+}
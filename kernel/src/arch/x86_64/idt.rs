@@ -72,6 +72,9 @@ pub fn init() {
         // Syscall interrupt (0x80 for compatibility, but we prefer syscall instruction)
         set_handler(0x80, syscall_interrupt as usize, 3, 0);
 
+        // CPU hotplug: parks a CPU being taken offline
+        set_handler(super::smp::CPU_OFFLINE_VECTOR as usize, ipi_cpu_offline as usize, 0, 0);
+
         // APIC spurious interrupt
         set_handler(0xFF, spurious as usize, 0, 0);
 
@@ -694,18 +697,23 @@ irq_handler!(irq12_mouse, 12);
 irq_handler!(irq13_fpu, 13);
 irq_handler!(irq14_ata1, 14);
 irq_handler!(irq15_ata2, 15);
+irq_handler!(ipi_cpu_offline, super::smp::CPU_OFFLINE_VECTOR as u64);
 
 /// Rust IRQ handler
 extern "C" fn irq_handler_rust(irq: u64) {
     match irq {
         0 => {
-            // Timer tick - trigger scheduler
-            crate::sched::timer_tick();
+            // Timer - expire timer objects, then the scheduler tick
+            crate::time::timer::interrupt();
         }
         1 => {
             // Keyboard - read scancode
             log::trace!("Keyboard IRQ");
         }
+        irq if irq == super::smp::CPU_OFFLINE_VECTOR as u64 => {
+            // Acknowledges on the local APIC and never returns
+            super::smp::park_this_cpu();
+        }
         _ => {
             log::trace!("IRQ {}", irq);
         }
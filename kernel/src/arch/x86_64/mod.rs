@@ -87,6 +87,40 @@ fn cpuid(leaf: u32) -> (u32, u32, u32, u32) {
     (eax, ebx, ecx, edx)
 }
 
+/// Check if RDRAND is supported
+fn has_rdrand() -> bool {
+    let (_, _, ecx, _) = cpuid(1);
+    (ecx & (1 << 30)) != 0 // RDRAND bit
+}
+
+/// Read the hardware random number generator
+///
+/// Returns `None` if the CPU lacks RDRAND or it keeps failing.
+pub fn rdrand() -> Option<u64> {
+    if !has_rdrand() {
+        return None;
+    }
+
+    // RDRAND can transiently run dry; Intel recommends 10 retries
+    for _ in 0..10 {
+        let value: u64;
+        let ok: u8;
+        unsafe {
+            asm!(
+                "rdrand {value}",
+                "setc {ok}",
+                value = out(reg) value,
+                ok = out(reg_byte) ok,
+                options(nomem, nostack),
+            );
+        }
+        if ok != 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
 /// Halt the CPU (wait for interrupt)
 #[inline]
 pub fn halt() {
@@ -111,6 +145,21 @@ pub fn enable_interrupts() {
     }
 }
 
+/// Set the FS segment base of the current CPU
+#[inline]
+pub fn set_fs_base(base: u64) {
+    const IA32_FS_BASE: u32 = 0xC000_0100;
+    unsafe {
+        asm!(
+            "wrmsr",
+            in("ecx") IA32_FS_BASE,
+            in("eax") base as u32,
+            in("edx") (base >> 32) as u32,
+            options(nostack, preserves_flags),
+        );
+    }
+}
+
 /// Read timestamp counter
 #[inline]
 pub fn rdtsc() -> u64 {
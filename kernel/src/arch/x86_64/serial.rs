@@ -387,7 +387,8 @@ macro_rules! serial_println {
 // Log backend implementation
 // ============================================================================
 
-/// Logger that outputs to serial console
+/// Logger that records into the kernel log buffer and echoes to the
+/// serial console
 struct SerialLogger;
 
 impl log::Log for SerialLogger {
@@ -397,6 +398,12 @@ impl log::Log for SerialLogger {
 
     fn log(&self, record: &log::Record) {
         if self.enabled(record.metadata()) {
+            crate::klog::record(record.level(), record.target(), *record.args(), crate::now_ns());
+
+            if record.level() > crate::klog::CONSOLE_LEVEL {
+                return;
+            }
+
             let level_str = match record.level() {
                 log::Level::Error => "\x1b[31mERROR\x1b[0m",
                 log::Level::Warn => "\x1b[33mWARN \x1b[0m",
@@ -419,7 +426,7 @@ impl log::Log for SerialLogger {
 
 static LOGGER: SerialLogger = SerialLogger;
 
-/// Initialize logging to serial console
+/// Initialize logging to the kernel log buffer and serial console
 pub fn init_logging() {
     log::set_logger(&LOGGER)
         .map(|()| log::set_max_level(log::LevelFilter::Trace))
@@ -2,11 +2,16 @@
 //!
 //! Handles starting and managing Application Processors (APs) in a multi-core
 //! system. Uses the INIT-SIPI-SIPI sequence as defined by Intel.
+//!
+//! APs can also be taken offline and brought back at runtime. An offlined
+//! AP is parked with interrupts disabled; bringing it back repeats the
+//! INIT-SIPI-SIPI sequence, so it comes up exactly as it did at boot.
 
 use core::arch::asm;
 use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use spin::Mutex;
 
+use crate::arch::HotplugError;
 use crate::mem::PhysAddr;
 
 /// Maximum number of CPUs supported
@@ -15,10 +20,22 @@ pub const MAX_CPUS: usize = 256;
 /// Number of CPUs online
 static CPU_COUNT: AtomicU32 = AtomicU32::new(1); // BSP is always online
 
+/// Number of CPUs present, online or not
+static POSSIBLE_CPUS: AtomicU32 = AtomicU32::new(1);
+
+/// APIC ID of the bootstrap processor
+static BSP_APIC_ID: AtomicU32 = AtomicU32::new(0);
+
 /// Flag indicating AP startup is complete
 static AP_STARTED: AtomicBool = AtomicBool::new(false);
 
-/// Per-CPU data
+/// Serializes AP startup and shutdown, which share `AP_STARTED`
+static HOTPLUG_LOCK: Mutex<()> = Mutex::new(());
+
+/// IPI that parks a CPU being taken offline
+pub const CPU_OFFLINE_VECTOR: u8 = 0xFC;
+
+/// Per-CPU data, indexed by APIC ID
 static CPU_DATA: Mutex<[CpuData; MAX_CPUS]> = Mutex::new([CpuData::new(); MAX_CPUS]);
 
 /// APIC base address (mapped)
@@ -56,10 +73,11 @@ pub fn init() {
 
     // Initialize BSP's CPU data
     let bsp_apic_id = read_apic_id();
+    BSP_APIC_ID.store(bsp_apic_id, Ordering::SeqCst);
     {
         let mut cpu_data = CPU_DATA.lock();
-        cpu_data[0].apic_id = bsp_apic_id;
-        cpu_data[0].online = true;
+        cpu_data[bsp_apic_id as usize].apic_id = bsp_apic_id;
+        cpu_data[bsp_apic_id as usize].online = true;
     }
 
     log::debug!(
@@ -82,11 +100,13 @@ pub fn start_aps() {
     let trampoline_addr = setup_trampoline();
 
     // Enumerate processors (typically from ACPI MADT)
-    let processor_count = detect_processor_count();
+    let processor_count = detect_processor_count().min(MAX_CPUS);
+    POSSIBLE_CPUS.store(processor_count as u32, Ordering::SeqCst);
 
     log::debug!("SMP: Detected {} processors", processor_count);
 
     // Start each AP
+    let _hotplug = HOTPLUG_LOCK.lock();
     for apic_id in 0..processor_count as u32 {
         if apic_id == bsp_apic_id {
             continue; // Skip BSP
@@ -139,10 +159,10 @@ fn start_ap(apic_id: u32, trampoline_addr: u64) {
     }
 
     if AP_STARTED.load(Ordering::SeqCst) {
-        let count = CPU_COUNT.fetch_add(1, Ordering::SeqCst) + 1;
+        CPU_COUNT.fetch_add(1, Ordering::SeqCst);
         let mut cpu_data = CPU_DATA.lock();
-        cpu_data[count as usize - 1].apic_id = apic_id;
-        cpu_data[count as usize - 1].online = true;
+        cpu_data[apic_id as usize].apic_id = apic_id;
+        cpu_data[apic_id as usize].online = true;
         log::debug!("SMP: AP {} started successfully", apic_id);
     } else {
         log::warn!("SMP: AP {} failed to start", apic_id);
@@ -382,6 +402,88 @@ pub fn cpu_count() -> u32 {
     CPU_COUNT.load(Ordering::SeqCst)
 }
 
+/// Get number of CPUs present, online or not
+pub fn possible_cpus() -> u32 {
+    POSSIBLE_CPUS.load(Ordering::SeqCst)
+}
+
+/// Check whether a CPU (by APIC ID) is online
+pub fn is_online(cpu_id: u32) -> bool {
+    (cpu_id as usize) < MAX_CPUS && CPU_DATA.lock()[cpu_id as usize].online
+}
+
+/// Online CPUs as a bitmask, bit N set for APIC ID N
+pub fn online_mask() -> [u64; MAX_CPUS / 64] {
+    let mut mask = [0u64; MAX_CPUS / 64];
+    let cpu_data = CPU_DATA.lock();
+    for (cpu_id, data) in cpu_data.iter().enumerate() {
+        if data.online {
+            mask[cpu_id / 64] |= 1 << (cpu_id % 64);
+        }
+    }
+    mask
+}
+
+/// Bring an offline AP back with INIT-SIPI-SIPI
+pub fn cpu_up(cpu_id: u32) -> Result<(), HotplugError> {
+    if cpu_id >= possible_cpus() {
+        return Err(HotplugError::InvalidCpu);
+    }
+
+    let _hotplug = HOTPLUG_LOCK.lock();
+    if is_online(cpu_id) {
+        return Err(HotplugError::AlreadyOnline);
+    }
+
+    start_ap(cpu_id, setup_trampoline());
+
+    if is_online(cpu_id) {
+        Ok(())
+    } else {
+        Err(HotplugError::StartFailed)
+    }
+}
+
+/// Take an AP offline
+///
+/// The caller must already have moved the CPU's threads elsewhere; the
+/// AP stops at the next instruction boundary once the IPI lands.
+pub fn cpu_down(cpu_id: u32) -> Result<(), HotplugError> {
+    if cpu_id >= possible_cpus() {
+        return Err(HotplugError::InvalidCpu);
+    }
+    if cpu_id == BSP_APIC_ID.load(Ordering::SeqCst) {
+        return Err(HotplugError::BootCpu);
+    }
+
+    let _hotplug = HOTPLUG_LOCK.lock();
+    {
+        let mut cpu_data = CPU_DATA.lock();
+        if !cpu_data[cpu_id as usize].online {
+            return Err(HotplugError::AlreadyOffline);
+        }
+        cpu_data[cpu_id as usize].online = false;
+    }
+    // Drop it from the count first so TLB shootdowns stop waiting on it
+    CPU_COUNT.fetch_sub(1, Ordering::SeqCst);
+
+    send_ipi_to(cpu_id, CPU_OFFLINE_VECTOR);
+    log::info!("SMP: CPU {} offline", cpu_id);
+    Ok(())
+}
+
+/// Park this CPU after `cpu_down` (called from the offline IPI handler)
+///
+/// Interrupts stay disabled, so only the INIT from `cpu_up` wakes it.
+pub fn park_this_cpu() -> ! {
+    send_eoi();
+    loop {
+        unsafe {
+            asm!("cli", "hlt", options(nomem, nostack));
+        }
+    }
+}
+
 /// Get current CPU's APIC ID
 pub fn current_cpu_id() -> u32 {
     read_apic_id()
@@ -421,7 +523,20 @@ pub fn send_ipi_to(apic_id: u32, vector: u8) {
     wait_ipi_delivery();
 }
 
+/// IA32_TSC_DEADLINE MSR
+const IA32_TSC_DEADLINE: u32 = 0x6E0;
+
+/// Check if the local APIC timer supports TSC-deadline mode
+fn has_tsc_deadline() -> bool {
+    let (_, _, ecx, _) = cpuid(1);
+    (ecx & (1 << 24)) != 0
+}
+
 /// Initialize APIC timer for this CPU
+///
+/// Uses TSC-deadline mode when the CPU has it and the TSC is calibrated,
+/// handing tick pacing to [`crate::time::timer`]; otherwise falls back to
+/// a periodic count.
 pub fn init_apic_timer(frequency_hz: u32) {
     let apic_base = APIC_BASE.load(Ordering::SeqCst) as u64;
 
@@ -430,6 +545,16 @@ pub fn init_apic_timer(frequency_hz: u32) {
     let timer_initial = apic_base + 0x380; // Initial Count
     let timer_divide = apic_base + 0x3E0;  // Divide Configuration
 
+    if has_tsc_deadline() && crate::time::monotonic_to_tsc(0).is_some() {
+        unsafe {
+            core::ptr::write_volatile(timer_lvt as *mut u32, 0x40020); // TSC-deadline, vector 32
+            // The LVT write must land before the first deadline write
+            asm!("mfence", options(nostack, preserves_flags));
+        }
+        crate::time::timer::start_deadline_ticks(frequency_hz);
+        return;
+    }
+
     unsafe {
         // Set divider to 16
         core::ptr::write_volatile(timer_divide as *mut u32, 0x3);
@@ -443,6 +568,21 @@ pub fn init_apic_timer(frequency_hz: u32) {
     }
 }
 
+/// Arm this CPU's local APIC timer to fire when the TSC reaches `tsc`
+///
+/// Ignored by the hardware unless the timer is in TSC-deadline mode.
+pub fn arm_tsc_deadline(tsc: u64) {
+    unsafe {
+        asm!(
+            "wrmsr",
+            in("ecx") IA32_TSC_DEADLINE,
+            in("eax") tsc as u32,
+            in("edx") (tsc >> 32) as u32,
+            options(nostack, preserves_flags),
+        );
+    }
+}
+
 /// Send EOI (End of Interrupt) to local APIC
 pub fn send_eoi() {
     let apic_base = APIC_BASE.load(Ordering::SeqCst) as u64;
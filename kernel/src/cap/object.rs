@@ -86,6 +86,14 @@ pub enum ObjectType {
     IpcRing = 8,
     /// Shared memory region
     SharedMemory = 9,
+    /// Kernel log buffer
+    KernelLog = 10,
+    /// Permission to create writable and executable mappings (JIT)
+    JitMemory = 11,
+    /// Permission to run Linux binaries through the compat syscall layer
+    LinuxCompat = 12,
+    /// Timer signalling a notification on expiry
+    Timer = 13,
 
     // === Hardware Objects (32-63) ===
     /// IRQ handler
@@ -152,6 +160,10 @@ impl ObjectType {
             7 => Some(Self::SchedulerContext),
             8 => Some(Self::IpcRing),
             9 => Some(Self::SharedMemory),
+            10 => Some(Self::KernelLog),
+            11 => Some(Self::JitMemory),
+            12 => Some(Self::LinuxCompat),
+            13 => Some(Self::Timer),
             32 => Some(Self::Interrupt),
             33 => Some(Self::IoPort),
             34 => Some(Self::MmioRegion),
@@ -282,6 +294,10 @@ mod tests {
         assert_eq!(ObjectType::from_u8(6), Some(ObjectType::Process));
         assert_eq!(ObjectType::from_u8(7), Some(ObjectType::SchedulerContext));
         assert_eq!(ObjectType::from_u8(8), Some(ObjectType::IpcRing));
+        assert_eq!(ObjectType::from_u8(10), Some(ObjectType::KernelLog));
+        assert_eq!(ObjectType::from_u8(11), Some(ObjectType::JitMemory));
+        assert_eq!(ObjectType::from_u8(12), Some(ObjectType::LinuxCompat));
+        assert_eq!(ObjectType::from_u8(13), Some(ObjectType::Timer));
     }
 
     #[test]
@@ -195,8 +195,60 @@ pub fn get_acpi_tables() -> Option<AcpiTables> {
     ACPI_TABLES.read().clone()
 }
 
+/// Find a system description table by signature (e.g. `b"DMAR"`)
+///
+/// Returns the table's physical address; the table starts with the
+/// standard 36-byte header, whose length field covers the whole table.
+pub fn find_table(signature: &[u8; 4]) -> Option<u64> {
+    let rsdp_addr = ACPI_TABLES.read().as_ref()?.rsdp_addr;
+    find_table_at(rsdp_addr, signature)
+}
+
+/// Find a system description table under a known RSDP
+///
+/// For early boot code that runs before [`init`] (the memory subsystem
+/// reads the SRAT before the driver framework is up).
+pub fn find_table_at(rsdp_addr: u64, signature: &[u8; 4]) -> Option<u64> {
+    let rsdp = table_ptr(rsdp_addr);
+
+    // XSDT entries are 64-bit, RSDT entries 32-bit
+    let revision = unsafe { *rsdp.add(15) };
+    let (sdt_addr, entry_size) = if revision >= 2 {
+        (unsafe { (rsdp.add(24) as *const u64).read_unaligned() }, 8)
+    } else {
+        (unsafe { (rsdp.add(16) as *const u32).read_unaligned() } as u64, 4)
+    };
+
+    let sdt = table_ptr(sdt_addr);
+    let length = unsafe { (sdt.add(4) as *const u32).read_unaligned() } as usize;
+    let count = length.saturating_sub(36) / entry_size;
+
+    for i in 0..count {
+        let entry = unsafe { sdt.add(36 + i * entry_size) };
+        let table_addr = if entry_size == 8 {
+            unsafe { (entry as *const u64).read_unaligned() }
+        } else {
+            (unsafe { (entry as *const u32).read_unaligned() }) as u64
+        };
+
+        let sig = unsafe { core::slice::from_raw_parts(table_ptr(table_addr), 4) };
+        if sig == signature {
+            return Some(table_addr);
+        }
+    }
+
+    None
+}
+
+/// Kernel pointer to a table at a physical address
+fn table_ptr(phys: u64) -> *const u8 {
+    crate::arch::x86_64::paging::phys_to_virt(
+        crate::mem::PhysAddr::new(phys)
+    ).as_u64() as *const u8
+}
+
 /// Search for RSDP in standard memory locations
-fn find_rsdp() -> Option<u64> {
+pub fn find_rsdp() -> Option<u64> {
     // Search EBDA (Extended BIOS Data Area)
     // Typically at 0x9FC00 - 0xA0000
     if let Some(addr) = search_rsdp_signature(0x9FC00, 0x400) {
@@ -251,6 +251,8 @@ bitflags::bitflags! {
 pub struct DmaRegionInfo {
     /// Physical address
     pub phys_addr: PhysAddr,
+    /// Address the device uses (IOVA; equals `phys_addr` without an IOMMU)
+    pub iova: u64,
     /// Size in bytes
     pub size: u64,
     /// Is coherent DMA
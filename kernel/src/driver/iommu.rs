@@ -0,0 +1,756 @@
+//! IOMMU (Intel VT-d) DMA remapping
+//!
+//! Userspace drivers program their devices with bus addresses, so without
+//! an IOMMU a buggy driver can point a device at any physical page. When
+//! the ACPI DMAR table describes remapping hardware, each device a driver
+//! takes DMA buffers for gets its own domain: a second-level page table
+//! mapping only those buffers, at I/O virtual addresses (IOVAs) handed back
+//! to the driver. Any other access from the device is blocked and recorded
+//! as a fault.
+//!
+//! Devices nobody has taken DMA buffers for stay in pass-through (when the
+//! unit supports it), so devices the kernel still drives itself keep
+//! working. Without remapping hardware, buffers are identity-mapped and
+//! nothing is enforced.
+//!
+//! ARM SMMUs (described by IORT) would sit behind the same `map`/`unmap`
+//! interface once there is an aarch64 port.
+
+use super::device::BusInfo;
+use super::mmio::MmioAccessor;
+use super::{DeviceId, DriverError};
+use crate::mem::{PhysAddr, PAGE_SIZE};
+use alloc::collections::btree_map::Entry;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use spin::RwLock;
+
+/// Active remapping units
+static UNITS: RwLock<Vec<RemappingUnit>> = RwLock::new(Vec::new());
+
+/// Per-device domains (lock after `UNITS`)
+static DOMAINS: RwLock<BTreeMap<DeviceId, Domain>> = RwLock::new(BTreeMap::new());
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Lowest IOVA handed out; low memory stays unmapped to catch stray DMA
+const IOVA_BASE: u64 = 0x10_0000;
+
+/// IOVAs stay below 4 GiB so devices limited to 32-bit DMA work
+const IOVA_LIMIT: u64 = 1 << 32;
+
+/// Domain ID used by pass-through context entries
+const PASSTHROUGH_DOMAIN: u16 = 1;
+
+/// Iterations to wait for the hardware to acknowledge a command
+const SPIN_LIMIT: usize = 1_000_000;
+
+/// Register window; covers the IOTLB and fault-recording registers, whose
+/// offsets are unit-specific
+const REG_SIZE: u64 = 0x4000;
+
+// Register offsets
+const REG_CAP: u64 = 0x08;
+const REG_ECAP: u64 = 0x10;
+const REG_GCMD: u64 = 0x18;
+const REG_GSTS: u64 = 0x1C;
+const REG_RTADDR: u64 = 0x20;
+const REG_CCMD: u64 = 0x28;
+const REG_FSTS: u64 = 0x34;
+
+// Global command and status bits
+const GCMD_TE: u32 = 1 << 31;
+const GCMD_SRTP: u32 = 1 << 30;
+const GCMD_WBF: u32 = 1 << 27;
+const GSTS_WBFS: u32 = 1 << 27;
+/// Status bits that may be written back to GCMD (drops one-shot bits)
+const GSTS_PERSISTENT: u32 = 0x96FF_FFFF;
+
+// Invalidation commands
+const CCMD_ICC: u64 = 1 << 63;
+const CCMD_GLOBAL: u64 = 1 << 61;
+const IOTLB_IVT: u64 = 1 << 63;
+const IOTLB_GLOBAL: u64 = 1 << 60;
+const IOTLB_DOMAIN: u64 = 2 << 60;
+const IOTLB_DRAIN: u64 = (1 << 49) | (1 << 48);
+
+// Fault recording
+const FSTS_PPF: u32 = 1 << 1;
+const FAULT_PENDING: u64 = 1 << 63;
+const FAULT_READ: u64 = 1 << 62;
+
+// Table entry bits
+const ENTRY_PRESENT: u64 = 1 << 0;
+const CONTEXT_PASSTHROUGH: u64 = 2 << 2;
+const PTE_READ: u64 = 1 << 0;
+const PTE_WRITE: u64 = 1 << 1;
+const ADDR_MASK: u64 = 0x000F_FFFF_FFFF_F000;
+
+// DMAR structures
+const DMAR_HEADER_LEN: usize = 48;
+const DMAR_DRHD: u16 = 0;
+const DRHD_INCLUDE_PCI_ALL: u8 = 1 << 0;
+const SCOPE_ENDPOINT: u8 = 1;
+const SCOPE_BRIDGE: u8 = 2;
+
+// PCI bridge configuration
+const PCI_SECONDARY_BUS: u8 = 0x19;
+const PCI_SUBORDINATE_BUS: u8 = 0x1A;
+
+// ============================================================================
+// Remapping Hardware
+// ============================================================================
+
+/// A DMA remapping hardware unit (DRHD)
+struct RemappingUnit {
+    /// Register base (physical)
+    base: u64,
+    regs: MmioAccessor,
+    segment: u16,
+    /// Covers every device on the segment not claimed by another unit
+    include_all: bool,
+    /// Devices listed in the unit's scope (bus, devfn)
+    scope: Vec<(u8, u8)>,
+    /// Bus ranges behind bridges in the unit's scope
+    bridges: Vec<(u8, u8)>,
+    root_table: PhysAddr,
+    /// Second-level page-table depth (3 = 39-bit, 4 = 48-bit)
+    levels: u8,
+    /// Supports pass-through context entries
+    passthrough: bool,
+    /// Page walks snoop the CPU caches
+    coherent: bool,
+    /// Needs write-buffer flushes before invalidation
+    write_buffer_flush: bool,
+    iotlb_offset: u64,
+    fault_offset: u64,
+    fault_records: u64,
+    max_domains: u32,
+    next_domain: u16,
+    free_domains: Vec<u16>,
+    /// Translation is on; invalidations are only issued from then on
+    enabled: bool,
+}
+
+impl RemappingUnit {
+    fn new(base: u64, segment: u16, include_all: bool) -> Result<Self, DriverError> {
+        let regs = MmioAccessor::new(PhysAddr::new(base), REG_SIZE)?;
+        let cap = regs.read_u64(REG_CAP);
+        let ecap = regs.read_u64(REG_ECAP);
+
+        let sagaw = (cap >> 8) & 0x1F;
+        let levels = if sagaw & (1 << 2) != 0 {
+            4
+        } else if sagaw & (1 << 1) != 0 {
+            3
+        } else {
+            return Err(DriverError::HardwareError);
+        };
+
+        Ok(Self {
+            base,
+            regs,
+            segment,
+            include_all,
+            scope: Vec::new(),
+            bridges: Vec::new(),
+            root_table: alloc_table()?,
+            levels,
+            passthrough: ecap & (1 << 6) != 0,
+            coherent: ecap & 1 != 0,
+            write_buffer_flush: cap & (1 << 4) != 0,
+            iotlb_offset: ((ecap >> 8) & 0x3FF) * 16,
+            fault_offset: ((cap >> 24) & 0x3FF) * 16,
+            fault_records: ((cap >> 40) & 0xFF) + 1,
+            max_domains: 1 << (4 + 2 * (cap & 0x7)),
+            next_domain: PASSTHROUGH_DOMAIN + 1,
+            free_domains: Vec::new(),
+            enabled: false,
+        })
+    }
+
+    /// Whether the unit's device scope names a device
+    fn covers(&self, (bus, devfn): (u8, u8)) -> bool {
+        self.scope.contains(&(bus, devfn))
+            || self
+                .bridges
+                .iter()
+                .any(|&(first, last)| bus >= first && bus <= last)
+    }
+
+    /// Context entry address-width field for this unit's page tables
+    fn address_width(&self) -> u64 {
+        (self.levels - 2) as u64
+    }
+
+    /// Point the hardware at the root table and turn translation on
+    fn enable(&mut self) -> Result<(), DriverError> {
+        self.flush_write_buffer()?;
+        self.regs.write_u64(REG_RTADDR, self.root_table.as_u64());
+        self.command(GCMD_SRTP)?;
+
+        self.enabled = true;
+        self.invalidate_context()?;
+        self.invalidate_iotlb(IOTLB_GLOBAL)?;
+        self.command(GCMD_TE)
+    }
+
+    /// Issue a global command and wait for its status bit
+    fn command(&self, bit: u32) -> Result<(), DriverError> {
+        let status = self.regs.read_u32(REG_GSTS) & GSTS_PERSISTENT;
+        self.regs.write_u32(REG_GCMD, status | bit);
+        spin_until(|| self.regs.read_u32(REG_GSTS) & bit != 0)
+    }
+
+    fn flush_write_buffer(&self) -> Result<(), DriverError> {
+        if !self.write_buffer_flush {
+            return Ok(());
+        }
+        let status = self.regs.read_u32(REG_GSTS) & GSTS_PERSISTENT;
+        self.regs.write_u32(REG_GCMD, status | GCMD_WBF);
+        spin_until(|| self.regs.read_u32(REG_GSTS) & GSTS_WBFS == 0)
+    }
+
+    fn invalidate_context(&self) -> Result<(), DriverError> {
+        if !self.enabled {
+            return Ok(());
+        }
+        self.flush_write_buffer()?;
+        self.regs.write_u64(REG_CCMD, CCMD_ICC | CCMD_GLOBAL);
+        spin_until(|| self.regs.read_u64(REG_CCMD) & CCMD_ICC == 0)
+    }
+
+    fn invalidate_iotlb(&self, granularity: u64) -> Result<(), DriverError> {
+        if !self.enabled {
+            return Ok(());
+        }
+        self.flush_write_buffer()?;
+        let reg = self.iotlb_offset + 8;
+        self.regs.write_u64(reg, IOTLB_IVT | IOTLB_DRAIN | granularity);
+        spin_until(|| self.regs.read_u64(reg) & IOTLB_IVT == 0)
+    }
+
+    fn invalidate_domain(&self, id: u16) -> Result<(), DriverError> {
+        self.invalidate_iotlb(IOTLB_DOMAIN | (id as u64) << 32)
+    }
+
+    /// Write table entries back to memory if page walks don't snoop
+    fn flush_cache(&self, ptr: *const u64, len: u64) {
+        if self.coherent {
+            return;
+        }
+        for line in (0..len).step_by(64) {
+            unsafe {
+                core::arch::asm!(
+                    "clflush [{}]",
+                    in(reg) (ptr as u64 + line),
+                    options(nostack, preserves_flags)
+                );
+            }
+        }
+    }
+
+    /// Context entry for a device, allocating its bus's context table
+    fn context_entry(&self, (bus, devfn): (u8, u8)) -> Result<*mut u64, DriverError> {
+        // Root and context entries are 128 bits each
+        let root = unsafe { table(self.root_table).add(bus as usize * 2) };
+        let mut entry = unsafe { core::ptr::read_volatile(root) };
+        if entry & ENTRY_PRESENT == 0 {
+            let context_table = alloc_table()?;
+            self.flush_cache(table(context_table), PAGE_SIZE);
+            entry = context_table.as_u64() | ENTRY_PRESENT;
+            unsafe { core::ptr::write_volatile(root, entry) };
+            self.flush_cache(root, 16);
+        }
+        Ok(unsafe { table(PhysAddr::new(entry & ADDR_MASK)).add(devfn as usize * 2) })
+    }
+
+    /// Replace a device's context entry
+    fn set_context(&self, source: (u8, u8), low: u64, high: u64) -> Result<(), DriverError> {
+        let entry = self.context_entry(source)?;
+
+        // Retire the old entry first so the hardware never sees a torn one
+        if unsafe { core::ptr::read_volatile(entry) } & ENTRY_PRESENT != 0 {
+            unsafe { core::ptr::write_volatile(entry, 0) };
+            self.flush_cache(entry, 16);
+            self.invalidate_context()?;
+            self.invalidate_iotlb(IOTLB_GLOBAL)?;
+        }
+
+        unsafe {
+            core::ptr::write_volatile(entry.add(1), high);
+            core::ptr::write_volatile(entry, low);
+        }
+        self.flush_cache(entry, 16);
+        self.invalidate_context()
+    }
+
+    fn set_passthrough(&self, source: (u8, u8)) -> Result<(), DriverError> {
+        let high = self.address_width() | (PASSTHROUGH_DOMAIN as u64) << 8;
+        self.set_context(source, CONTEXT_PASSTHROUGH | ENTRY_PRESENT, high)
+    }
+
+    fn alloc_domain_id(&mut self) -> Option<u16> {
+        if let Some(id) = self.free_domains.pop() {
+            return Some(id);
+        }
+        if self.next_domain as u32 >= self.max_domains || self.next_domain == u16::MAX {
+            return None;
+        }
+        let id = self.next_domain;
+        self.next_domain += 1;
+        Some(id)
+    }
+
+    /// Log and clear recorded translation faults
+    fn report_faults(&self) {
+        let status = self.regs.read_u32(REG_FSTS);
+        if status & FSTS_PPF == 0 {
+            return;
+        }
+
+        for i in 0..self.fault_records {
+            let record = self.fault_offset + i * 16;
+            if record + 16 > REG_SIZE {
+                break;
+            }
+            let high = self.regs.read_u64(record + 8);
+            if high & FAULT_PENDING == 0 {
+                continue;
+            }
+            let addr = self.regs.read_u64(record) & ADDR_MASK;
+            let source = high as u16;
+            log::warn!(
+                "IOMMU: blocked DMA {} at {:#x} from {:02x}:{:02x}.{} (reason {:#x})",
+                if high & FAULT_READ != 0 { "read" } else { "write" },
+                addr,
+                source >> 8,
+                (source >> 3) & 0x1F,
+                source & 0x7,
+                (high >> 32) as u8
+            );
+            self.regs.write_u64(record + 8, FAULT_PENDING);
+        }
+
+        // Clear overflow and other write-one-to-clear bits
+        self.regs.write_u32(REG_FSTS, status);
+    }
+}
+
+// ============================================================================
+// Domains
+// ============================================================================
+
+/// A device's private DMA address space
+struct Domain {
+    /// Index into `UNITS`
+    unit: usize,
+    id: u16,
+    /// Top-level second-level page table
+    root: PhysAddr,
+    /// Requester (bus, devfn)
+    source: (u8, u8),
+    /// Live mappings: IOVA -> (physical address, size)
+    mappings: BTreeMap<u64, (PhysAddr, u64)>,
+}
+
+impl Domain {
+    /// First free IOVA range of `size` bytes
+    fn alloc_iova(&self, size: u64) -> Option<u64> {
+        let mut candidate = IOVA_BASE;
+        for (&iova, &(_, len)) in &self.mappings {
+            if candidate + size <= iova {
+                break;
+            }
+            candidate = candidate.max(iova + len);
+        }
+        (candidate + size <= IOVA_LIMIT).then_some(candidate)
+    }
+
+    /// Leaf entry for an IOVA, optionally building missing tables
+    fn leaf(
+        &self,
+        unit: &RemappingUnit,
+        iova: u64,
+        create: bool,
+    ) -> Result<Option<*mut u64>, DriverError> {
+        let mut current = self.root;
+        for level in (1..unit.levels).rev() {
+            let index = (iova >> (12 + 9 * level as u64)) & 0x1FF;
+            let entry = unsafe { table(current).add(index as usize) };
+            let mut value = unsafe { core::ptr::read_volatile(entry) };
+            if value & (PTE_READ | PTE_WRITE) == 0 {
+                if !create {
+                    return Ok(None);
+                }
+                let next = alloc_table()?;
+                unit.flush_cache(table(next), PAGE_SIZE);
+                value = next.as_u64() | PTE_READ | PTE_WRITE;
+                unsafe { core::ptr::write_volatile(entry, value) };
+                unit.flush_cache(entry, 8);
+            }
+            current = PhysAddr::new(value & ADDR_MASK);
+        }
+        let index = (iova >> 12) & 0x1FF;
+        Ok(Some(unsafe { table(current).add(index as usize) }))
+    }
+
+    fn map_page(
+        &self,
+        unit: &RemappingUnit,
+        iova: u64,
+        phys: u64,
+        flags: u64,
+    ) -> Result<(), DriverError> {
+        if let Some(entry) = self.leaf(unit, iova, true)? {
+            unsafe { core::ptr::write_volatile(entry, phys | flags) };
+            unit.flush_cache(entry, 8);
+        }
+        Ok(())
+    }
+
+    fn unmap_page(&self, unit: &RemappingUnit, iova: u64) {
+        if let Ok(Some(entry)) = self.leaf(unit, iova, false) {
+            unsafe { core::ptr::write_volatile(entry, 0) };
+            unit.flush_cache(entry, 8);
+        }
+    }
+}
+
+/// Move a device into a fresh domain of its own
+fn attach(units: &mut [RemappingUnit], device_id: DeviceId) -> Result<Domain, DriverError> {
+    let device = super::get_device(device_id).ok_or(DriverError::DeviceNotFound)?;
+    let Some(BusInfo::Pci(pci)) = device.bus_info else {
+        // Only PCI requesters can be told apart by the remapping hardware
+        return Err(DriverError::InvalidConfig);
+    };
+    let source = (pci.bus, devfn(pci.device, pci.function));
+
+    let index = unit_for(units, source).ok_or(DriverError::InvalidConfig)?;
+    let unit = &mut units[index];
+    let id = unit.alloc_domain_id().ok_or(DriverError::OutOfResources)?;
+
+    let root = match alloc_table() {
+        Ok(root) => root,
+        Err(err) => {
+            unit.free_domains.push(id);
+            return Err(err);
+        }
+    };
+    unit.flush_cache(table(root), PAGE_SIZE);
+
+    let high = unit.address_width() | (id as u64) << 8;
+    if let Err(err) = unit.set_context(source, root.as_u64() | ENTRY_PRESENT, high) {
+        crate::mem::free_frame(root);
+        unit.free_domains.push(id);
+        return Err(err);
+    }
+
+    log::debug!(
+        "IOMMU: device {:?} ({:02x}:{:02x}.{}) in domain {}",
+        device_id,
+        pci.bus,
+        pci.device,
+        pci.function,
+        id
+    );
+
+    Ok(Domain {
+        unit: index,
+        id,
+        root,
+        source,
+        mappings: BTreeMap::new(),
+    })
+}
+
+/// The unit translating a requester's DMA
+fn unit_for(units: &[RemappingUnit], source: (u8, u8)) -> Option<usize> {
+    units
+        .iter()
+        .position(|unit| unit.segment == 0 && unit.covers(source))
+        .or_else(|| {
+            units
+                .iter()
+                .position(|unit| unit.segment == 0 && unit.include_all)
+        })
+}
+
+// ============================================================================
+// Public Interface
+// ============================================================================
+
+/// Find remapping hardware and turn translation on
+pub fn init() {
+    let Some(dmar) = super::acpi::find_table(b"DMAR") else {
+        log::info!("IOMMU: no DMAR table, DMA buffers are not isolated");
+        return;
+    };
+
+    let mut units = parse_dmar(dmar);
+    if units.is_empty() {
+        log::warn!("IOMMU: DMAR table lists no usable remapping units");
+        return;
+    }
+
+    // Keep existing devices working until a driver takes DMA buffers
+    for device in super::pci::get_all_devices() {
+        let source = (device.info.bus, devfn(device.info.device, device.info.function));
+        let Some(index) = unit_for(&units, source) else {
+            continue;
+        };
+        let unit = &units[index];
+        if unit.passthrough {
+            if let Err(err) = unit.set_passthrough(source) {
+                log::warn!("IOMMU: pass-through for {:02x}:{:02x} failed: {:?}", source.0, source.1, err);
+            }
+        }
+    }
+
+    units.retain_mut(|unit| match unit.enable() {
+        Ok(()) => {
+            if !unit.passthrough {
+                log::warn!(
+                    "IOMMU: unit at {:#x} has no pass-through; DMA is blocked until granted",
+                    unit.base
+                );
+            }
+            true
+        }
+        Err(err) => {
+            log::warn!("IOMMU: unit at {:#x} failed to enable: {:?}", unit.base, err);
+            false
+        }
+    });
+
+    log::info!("IOMMU: {} VT-d unit(s) active", units.len());
+    *UNITS.write() = units;
+}
+
+/// Whether DMA remapping is active
+pub fn is_enabled() -> bool {
+    !UNITS.read().is_empty()
+}
+
+/// Map a DMA buffer for a device, returning the address the device must use
+///
+/// The first mapping moves the device into its own domain. Without
+/// remapping hardware the buffer's physical address is returned unchanged.
+pub fn map(
+    device_id: DeviceId,
+    phys: PhysAddr,
+    size: u64,
+    writable: bool,
+) -> Result<u64, DriverError> {
+    let mut units = UNITS.write();
+    if units.is_empty() {
+        return Ok(phys.as_u64());
+    }
+
+    let size = (size + PAGE_SIZE - 1) & !(PAGE_SIZE - 1);
+    let mut domains = DOMAINS.write();
+    let domain = match domains.entry(device_id) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(attach(&mut units, device_id)?),
+    };
+
+    let iova = domain.alloc_iova(size).ok_or(DriverError::OutOfResources)?;
+    let flags = PTE_READ | if writable { PTE_WRITE } else { 0 };
+    let unit = &units[domain.unit];
+
+    for offset in (0..size).step_by(PAGE_SIZE as usize) {
+        if let Err(err) = domain.map_page(unit, iova + offset, phys.as_u64() + offset, flags) {
+            for mapped in (0..offset).step_by(PAGE_SIZE as usize) {
+                domain.unmap_page(unit, iova + mapped);
+            }
+            unit.invalidate_domain(domain.id)?;
+            return Err(err);
+        }
+    }
+    domain.mappings.insert(iova, (phys, size));
+
+    // Caching-mode hardware may have cached the not-present entries
+    unit.invalidate_domain(domain.id)?;
+
+    Ok(iova)
+}
+
+/// Remove a buffer from a device's domain
+pub fn unmap(device_id: DeviceId, iova: u64) -> Result<(), DriverError> {
+    let units = UNITS.read();
+    if units.is_empty() {
+        return Ok(());
+    }
+
+    let mut domains = DOMAINS.write();
+    let domain = domains
+        .get_mut(&device_id)
+        .ok_or(DriverError::DmaRegionNotFound)?;
+    let (_, size) = domain
+        .mappings
+        .remove(&iova)
+        .ok_or(DriverError::DmaRegionNotFound)?;
+
+    let unit = &units[domain.unit];
+    for offset in (0..size).step_by(PAGE_SIZE as usize) {
+        domain.unmap_page(unit, iova + offset);
+    }
+    unit.invalidate_domain(domain.id)?;
+    unit.report_faults();
+
+    Ok(())
+}
+
+/// Tear down a device's domain, returning it to pass-through
+pub fn detach(device_id: DeviceId) -> Result<(), DriverError> {
+    let mut units = UNITS.write();
+    let Some(domain) = DOMAINS.write().remove(&device_id) else {
+        return Ok(());
+    };
+
+    let unit = &mut units[domain.unit];
+    if unit.passthrough {
+        unit.set_passthrough(domain.source)?;
+    } else {
+        unit.set_context(domain.source, 0, 0)?;
+    }
+    unit.invalidate_domain(domain.id)?;
+    unit.report_faults();
+
+    free_table(domain.root, unit.levels);
+    unit.free_domains.push(domain.id);
+
+    log::debug!("IOMMU: device {:?} left domain {}", device_id, domain.id);
+
+    Ok(())
+}
+
+// ============================================================================
+// DMAR Parsing
+// ============================================================================
+
+/// Read the remapping units from the DMAR table
+fn parse_dmar(dmar: u64) -> Vec<RemappingUnit> {
+    let base = crate::mem::phys_to_virt(PhysAddr::new(dmar)) as *const u8;
+    let length = read::<u32>(base, 4) as usize;
+
+    let mut units = Vec::new();
+    let mut offset = DMAR_HEADER_LEN;
+    while offset + 4 <= length {
+        let kind = read::<u16>(base, offset);
+        let len = read::<u16>(base, offset + 2) as usize;
+        if len < 4 {
+            break;
+        }
+
+        if kind == DMAR_DRHD && len >= 16 {
+            let flags = read::<u8>(base, offset + 4);
+            let segment = read::<u16>(base, offset + 6);
+            let reg_base = read::<u64>(base, offset + 8);
+
+            match RemappingUnit::new(reg_base, segment, flags & DRHD_INCLUDE_PCI_ALL != 0) {
+                Ok(mut unit) => {
+                    parse_scope(base, offset + 16, offset + len, &mut unit);
+                    units.push(unit);
+                }
+                Err(err) => log::warn!("IOMMU: skipping unit at {:#x}: {:?}", reg_base, err),
+            }
+        }
+
+        offset += len;
+    }
+
+    units
+}
+
+/// Read a DRHD's device scope entries
+fn parse_scope(base: *const u8, mut offset: usize, end: usize, unit: &mut RemappingUnit) {
+    while offset + 6 <= end {
+        let kind = read::<u8>(base, offset);
+        let len = read::<u8>(base, offset + 1) as usize;
+        if len < 6 {
+            break;
+        }
+
+        // The path is (device, function) hops from the start bus; every
+        // hop but the last is a bridge leading to the next bus
+        let mut bus = read::<u8>(base, offset + 5);
+        let hops = (len - 6) / 2;
+        let mut target = None;
+        for hop in 0..hops {
+            let device = read::<u8>(base, offset + 6 + hop * 2);
+            let function = read::<u8>(base, offset + 7 + hop * 2);
+            if hop + 1 < hops {
+                bus = super::pci::config_read(bus, device, function, PCI_SECONDARY_BUS, 1) as u8;
+            } else {
+                target = Some((bus, device, function));
+            }
+        }
+
+        if let Some((bus, device, function)) = target {
+            match kind {
+                SCOPE_ENDPOINT => unit.scope.push((bus, devfn(device, function))),
+                SCOPE_BRIDGE => {
+                    unit.scope.push((bus, devfn(device, function)));
+                    let first = super::pci::config_read(bus, device, function, PCI_SECONDARY_BUS, 1);
+                    let last = super::pci::config_read(bus, device, function, PCI_SUBORDINATE_BUS, 1);
+                    unit.bridges.push((first as u8, last as u8));
+                }
+                // I/O APICs, HPETs and ACPI namespace devices
+                _ => {}
+            }
+        }
+
+        offset += len;
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Poll `cond` until it holds, giving up after `SPIN_LIMIT` tries
+fn spin_until(cond: impl Fn() -> bool) -> Result<(), DriverError> {
+    for _ in 0..SPIN_LIMIT {
+        if cond() {
+            return Ok(());
+        }
+        core::hint::spin_loop();
+    }
+    Err(DriverError::Timeout)
+}
+
+fn devfn(device: u8, function: u8) -> u8 {
+    (device << 3) | (function & 0x7)
+}
+
+fn read<T: Copy>(base: *const u8, offset: usize) -> T {
+    unsafe { (base.add(offset) as *const T).read_unaligned() }
+}
+
+/// Kernel pointer to a table page
+fn table(phys: PhysAddr) -> *mut u64 {
+    crate::mem::phys_to_virt(phys) as *mut u64
+}
+
+/// Allocate a zeroed table page
+fn alloc_table() -> Result<PhysAddr, DriverError> {
+    let frame = crate::mem::alloc_frame().ok_or(DriverError::OutOfResources)?;
+    unsafe { core::ptr::write_bytes(table(frame), 0, 512) };
+    Ok(frame)
+}
+
+/// Free a page table and the tables below it (not the mapped pages)
+fn free_table(phys: PhysAddr, level: u8) {
+    if level > 1 {
+        for i in 0..512 {
+            let entry = unsafe { core::ptr::read_volatile(table(phys).add(i)) };
+            if entry & (PTE_READ | PTE_WRITE) != 0 {
+                free_table(PhysAddr::new(entry & ADDR_MASK), level - 1);
+            }
+        }
+    }
+    crate::mem::free_frame(phys);
+}
@@ -67,7 +67,7 @@ pub fn init() {
 /// Initialize APIC
 fn init_apic() {
     // Basic APIC initialization (details in arch-specific code)
-    crate::arch::x86_64::smp::init_apic_timer(100); // 100Hz
+    crate::arch::x86_64::smp::init_apic_timer(crate::time::TICK_HZ);
 }
 
 /// Validate IRQ number
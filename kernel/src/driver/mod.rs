@@ -30,6 +30,7 @@ pub mod acpi;
 pub mod block;
 pub mod device;
 pub mod devicetree;
+pub mod iommu;
 pub mod irq;
 pub mod mmio;
 pub mod pci;
@@ -85,6 +86,10 @@ pub enum DriverError {
     InvalidConfig,
     /// Hardware error
     HardwareError,
+    /// Hardware did not respond in time
+    Timeout,
+    /// DMA region not found
+    DmaRegionNotFound,
     /// Capability error
     Capability(CapError),
 }
@@ -102,6 +107,8 @@ pub fn init() {
     // Initialize subsystems
     irq::init();
     pci::init();
+    acpi::init();
+    iommu::init();
 
     log::info!("Driver framework initialized");
 }
@@ -150,7 +157,15 @@ pub fn unregister_device(device_id: DeviceId) -> Result<(), DriverError> {
         irq::unregister_irq(irq)?;
     }
 
-    devices.remove(&device_id);
+    let device = devices.remove(&device_id).ok_or(DriverError::DeviceNotFound)?;
+    drop(devices);
+
+    // Release DMA buffers and the device's IOMMU domain
+    for region in &device.dma_regions {
+        iommu::unmap(device_id, region.iova)?;
+        crate::mem::free_contiguous(region.phys_addr, region.size);
+    }
+    iommu::detach(device_id)?;
 
     log::debug!("Unregistered device {:?}", device_id);
 
@@ -236,14 +251,47 @@ pub fn grant_mmio_capability(
 }
 
 /// Grant DMA buffer capability to a process
+///
+/// The buffer is mapped into the device's IOMMU domain, so the device can
+/// reach it and nothing else; the returned region's `iova` is the address
+/// to program into the device.
 pub fn grant_dma_capability(
     process_id: ProcessId,
+    device_id: DeviceId,
     size: u64,
-) -> Result<(Capability, PhysAddr), DriverError> {
+) -> Result<(Capability, device::DmaRegionInfo), DriverError> {
+    if !DEVICES.read().contains_key(&device_id) {
+        return Err(DriverError::DeviceNotFound);
+    }
+
     // Allocate DMA-capable memory
     let phys = crate::mem::alloc_contiguous(size)
         .ok_or(DriverError::OutOfResources)?;
 
+    let iova = match iommu::map(device_id, phys, size, true) {
+        Ok(iova) => iova,
+        Err(err) => {
+            crate::mem::free_contiguous(phys, size);
+            return Err(err);
+        }
+    };
+
+    let region = device::DmaRegionInfo {
+        phys_addr: phys,
+        iova,
+        size,
+        coherent: true,
+    };
+    match DEVICES.write().get_mut(&device_id) {
+        Some(device) => device.dma_regions.push(region.clone()),
+        None => {
+            // Unregistered while we were mapping
+            let _ = iommu::unmap(device_id, iova);
+            crate::mem::free_contiguous(phys, size);
+            return Err(DriverError::DeviceNotFound);
+        }
+    }
+
     // Create DMA capability
     let object_id = ObjectId::new(ObjectType::DmaBuffer);
     let cap = unsafe {
@@ -254,13 +302,37 @@ pub fn grant_dma_capability(
     };
 
     log::debug!(
-        "Granted DMA buffer ({} bytes at {:016x}) to process {:?}",
+        "Granted DMA buffer ({} bytes at {:016x}, iova {:016x}) for {:?} to process {:?}",
         size,
         phys.as_u64(),
+        iova,
+        device_id,
         process_id
     );
 
-    Ok((cap, phys))
+    Ok((cap, region))
+}
+
+/// Release a DMA buffer granted for a device
+pub fn release_dma_buffer(device_id: DeviceId, iova: u64) -> Result<(), DriverError> {
+    let region = {
+        let mut devices = DEVICES.write();
+        let device = devices
+            .get_mut(&device_id)
+            .ok_or(DriverError::DeviceNotFound)?;
+        let index = device
+            .dma_regions
+            .iter()
+            .position(|region| region.iova == iova)
+            .ok_or(DriverError::DmaRegionNotFound)?;
+        device.dma_regions.remove(index)
+    };
+
+    // Unmap before freeing so the device can't reach the reused pages
+    iommu::unmap(device_id, iova)?;
+    crate::mem::free_contiguous(region.phys_addr, region.size);
+
+    Ok(())
 }
 
 /// Grant I/O port capability (x86 specific)
@@ -467,13 +467,19 @@ fn process_poll(entry: &SqEntry, ring: &mut IpcRing) -> Result<(), IpcError> {
 /// params[0] = size in bytes
 /// params[1] = device type (0=CPU, 1=GPU, 2=NPU)
 /// params[2] = alignment
+/// params[3] = NUMA placement hint (see `tensor::Placement::from_raw`)
 fn process_tensor_alloc(entry: &SqEntry, ring: &mut IpcRing) -> Result<(), IpcError> {
     let size = entry.params[0];
     let device_type = entry.params[1] as u32;
     let alignment = entry.params[2];
+    let placement = crate::tensor::Placement::from_raw(entry.params[3]);
 
     // Delegate to tensor subsystem
-    let result = crate::tensor::allocate_buffer(size, device_type, alignment);
+    let result = placement
+        .ok_or(crate::tensor::TensorError::InvalidPlacement)
+        .and_then(|placement| {
+            crate::tensor::allocate_buffer(size, device_type, alignment, placement)
+        });
 
     match result {
         Ok((buffer_id, phys_addr)) => {
@@ -0,0 +1,249 @@
+//! # Kernel Log Buffer
+//!
+//! Every record passed to the `log` facade lands in a fixed ring of
+//! records, where it stays until newer ones overwrite it. Userspace
+//! collectors (scribe) drain the ring with `sys_klog_read`; the serial
+//! console only echoes records at or above the console level.
+//!
+//! Records are handed out as `/dev/kmsg`-style lines, so collectors parse
+//! them the same way on Linux and on native Nyx:
+//!
+//! ```text
+//! <priority>,<sequence>,<timestamp_us>,-;<target>: <message>\n
+//! ```
+//!
+//! The ring is statically sized so logging works before the heap is up.
+//! Messages longer than [`KLOG_TEXT_MAX`] bytes are truncated.
+
+use core::fmt::{self, Write};
+use spin::Mutex;
+
+/// Number of records kept
+pub const KLOG_RECORDS: usize = 1024;
+
+/// Longest message text kept per record
+pub const KLOG_TEXT_MAX: usize = 224;
+
+/// Longest formatted line (header plus text)
+pub const KLOG_LINE_MAX: usize = KLOG_TEXT_MAX + 48;
+
+/// Records at or above this level are echoed to the serial console
+pub const CONSOLE_LEVEL: log::Level = log::Level::Info;
+
+/// The kernel's log ring
+static KLOG: Mutex<KlogBuffer> = Mutex::new(KlogBuffer::new());
+
+/// Record a log message
+pub fn record(level: log::Level, target: &str, args: fmt::Arguments, timestamp_ns: u64) {
+    KLOG.lock().push(level, target, args, timestamp_ns / 1_000);
+}
+
+/// Copy records starting at `from_seq` into `buf` as kmsg lines
+///
+/// Returns the number of bytes written. Records already overwritten are
+/// skipped; readers notice the gap from the sequence numbers.
+pub fn read(from_seq: u64, buf: &mut [u8]) -> usize {
+    KLOG.lock().read(from_seq, buf)
+}
+
+/// Sequence number the next record will get
+pub fn next_seq() -> u64 {
+    KLOG.lock().next_seq()
+}
+
+/// Syslog priority for a log level
+pub fn priority(level: log::Level) -> u8 {
+    match level {
+        log::Level::Error => 3,
+        log::Level::Warn => 4,
+        log::Level::Info => 6,
+        log::Level::Debug | log::Level::Trace => 7,
+    }
+}
+
+// ============================================================================
+// Ring Buffer
+// ============================================================================
+
+#[derive(Clone, Copy)]
+struct Record {
+    seq: u64,
+    priority: u8,
+    timestamp_us: u64,
+    len: u16,
+    text: [u8; KLOG_TEXT_MAX],
+}
+
+impl Record {
+    const EMPTY: Self = Self {
+        seq: 0,
+        priority: 0,
+        timestamp_us: 0,
+        len: 0,
+        text: [0; KLOG_TEXT_MAX],
+    };
+}
+
+/// Fixed-capacity ring of log records
+pub struct KlogBuffer {
+    records: [Record; KLOG_RECORDS],
+    next_seq: u64,
+}
+
+impl KlogBuffer {
+    /// Create an empty buffer
+    pub const fn new() -> Self {
+        Self {
+            records: [Record::EMPTY; KLOG_RECORDS],
+            next_seq: 0,
+        }
+    }
+
+    /// Sequence number of the oldest record still held
+    pub fn first_seq(&self) -> u64 {
+        self.next_seq.saturating_sub(KLOG_RECORDS as u64)
+    }
+
+    /// Sequence number the next record will get
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Append a record, overwriting the oldest when full
+    pub fn push(&mut self, level: log::Level, target: &str, args: fmt::Arguments, timestamp_us: u64) {
+        let seq = self.next_seq;
+        let record = &mut self.records[(seq % KLOG_RECORDS as u64) as usize];
+
+        let mut writer = Truncating::new(&mut record.text);
+        let _ = write!(writer, "{}: {}", target, args);
+        let len = writer.len;
+
+        record.seq = seq;
+        record.priority = priority(level);
+        record.timestamp_us = timestamp_us;
+        record.len = len as u16;
+        self.next_seq += 1;
+    }
+
+    /// Copy whole records starting at `from_seq` into `buf` as kmsg lines
+    pub fn read(&self, from_seq: u64, buf: &mut [u8]) -> usize {
+        let mut written = 0;
+        let mut line = [0u8; KLOG_LINE_MAX];
+
+        for seq in from_seq.max(self.first_seq())..self.next_seq {
+            let record = &self.records[(seq % KLOG_RECORDS as u64) as usize];
+            let text = &record.text[..record.len as usize];
+
+            let mut writer = Truncating::new(&mut line);
+            let _ = write!(writer, "{},{},{},-;", record.priority, record.seq, record.timestamp_us);
+            writer.push_bytes(text);
+            writer.push_bytes(b"\n");
+            let len = writer.len;
+
+            if written + len > buf.len() {
+                break;
+            }
+            buf[written..written + len].copy_from_slice(&line[..len]);
+            written += len;
+        }
+
+        written
+    }
+}
+
+impl Default for KlogBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `fmt::Write` into a fixed buffer, dropping whatever doesn't fit
+struct Truncating<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Truncating<'a> {
+    fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        let n = bytes.len().min(self.buf.len() - self.len);
+        self.buf[self.len..self.len + n].copy_from_slice(&bytes[..n]);
+        self.len += n;
+    }
+}
+
+impl Write for Truncating<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // Cut at a character boundary so the text stays valid UTF-8
+        let room = self.buf.len() - self.len;
+        let mut n = s.len().min(room);
+        while !s.is_char_boundary(n) {
+            n -= 1;
+        }
+        self.push_bytes(&s.as_bytes()[..n]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(buffer: &KlogBuffer, from_seq: u64, capacity: usize) -> std::string::String {
+        let mut buf = std::vec![0u8; capacity];
+        let n = buffer.read(from_seq, &mut buf);
+        std::string::String::from_utf8(buf[..n].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_push_and_read() {
+        let mut buffer = KlogBuffer::new();
+        buffer.push(log::Level::Info, "kernel", format_args!("booted in {} ms", 42), 1_500);
+        buffer.push(log::Level::Error, "driver", format_args!("bad device"), 2_000);
+
+        assert_eq!(
+            lines(&buffer, 0, 4096),
+            "6,0,1500,-;kernel: booted in 42 ms\n3,1,2000,-;driver: bad device\n"
+        );
+        assert_eq!(lines(&buffer, 1, 4096), "3,1,2000,-;driver: bad device\n");
+        assert_eq!(lines(&buffer, 2, 4096), "");
+    }
+
+    #[test]
+    fn test_read_stops_at_whole_records() {
+        let mut buffer = KlogBuffer::new();
+        buffer.push(log::Level::Warn, "a", format_args!("first"), 0);
+        buffer.push(log::Level::Warn, "a", format_args!("second"), 0);
+
+        // Room for the first line only
+        assert_eq!(lines(&buffer, 0, 20), "4,0,0,-;a: first\n");
+        assert_eq!(lines(&buffer, 0, 5), "");
+    }
+
+    #[test]
+    fn test_overwrite_oldest() {
+        let mut buffer = KlogBuffer::new();
+        for i in 0..KLOG_RECORDS as u64 + 3 {
+            buffer.push(log::Level::Debug, "t", format_args!("{}", i), i);
+        }
+
+        assert_eq!(buffer.first_seq(), 3);
+        assert_eq!(buffer.next_seq(), KLOG_RECORDS as u64 + 3);
+        assert!(lines(&buffer, 0, 64).starts_with("7,3,3,-;t: 3\n"));
+    }
+
+    #[test]
+    fn test_truncates_long_messages() {
+        let mut buffer = KlogBuffer::new();
+        let long = "é".repeat(KLOG_TEXT_MAX);
+        buffer.push(log::Level::Info, "t", format_args!("{}", long), 0);
+
+        let text = lines(&buffer, 0, 4096);
+        let message = text.trim_end().split_once(';').unwrap().1;
+        assert!(message.len() <= KLOG_TEXT_MAX);
+        assert!(message.starts_with("t: é"));
+    }
+}
@@ -30,6 +30,7 @@
 //! Level 2:
 //!   - PER_CPU           (per-CPU scheduler state)
 //!   - NOTIFICATIONS     (notification registry)
+//!   - TIMERS            (timer registry; dropped before signalling)
 //!
 //! Level 3 (innermost - acquire last):
 //!   - Individual Process.address_space
@@ -63,6 +64,7 @@ extern crate std as alloc;
 
 pub mod arch;
 pub mod cap;
+pub mod klog;
 pub mod signal;
 pub mod sync;
 pub mod traits;
@@ -217,8 +219,6 @@ pub mod sched {
     pub fn sleep(_duration: Duration) {}
 }
 
-use core::sync::atomic::{AtomicU64, Ordering};
-
 /// Kernel version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -228,13 +228,18 @@ pub const BUILD_TIME: &str = match option_env!("BUILD_TIMESTAMP") {
     None => "unknown",
 };
 
-/// Global tick counter (nanoseconds since boot)
-static TICK_COUNTER: AtomicU64 = AtomicU64::new(0);
+/// Get current timestamp in nanoseconds (monotonic clock)
+#[cfg(not(test))]
+#[inline]
+pub fn now_ns() -> u64 {
+    time::monotonic_ns()
+}
 
-/// Get current timestamp in nanoseconds
+/// Test builds have no clock; time stands still at boot
+#[cfg(test)]
 #[inline]
 pub fn now_ns() -> u64 {
-    TICK_COUNTER.load(Ordering::Relaxed)
+    0
 }
 
 /// Kernel entry point (called from arch-specific boot code)
@@ -280,7 +285,9 @@ pub unsafe fn kernel_main(boot_info: &arch::BootInfo) -> ! {
     log::debug!("Initializing time-travel subsystem");
     timetravel::init();
 
-    // Phase 9: Device driver framework
+    // Phase 9: Clocks and device driver framework (the APIC timer needs
+    // a calibrated TSC)
+    time::init();
     log::debug!("Initializing device driver framework");
     driver::init();
 
@@ -302,6 +309,7 @@ pub unsafe fn kernel_main(boot_info: &arch::BootInfo) -> ! {
     // Phase 13: Start secondary CPUs
     log::debug!("Starting secondary CPUs");
     arch::start_secondary_cpus();
+    sched::sync_online_cpus();
 
     // Phase 14: Load init process
     log::info!("Loading init process");
@@ -318,6 +326,27 @@ fn load_init_process(_boot_info: &arch::BootInfo) -> cap::Capability {
     // Try to spawn /init or /sbin/init
     let init_paths = ["/init", "/sbin/init", "/bin/init"];
 
+    // The kernel log, which init hands on to the logging daemon
+    let klog_cap = cap::register_object(
+        cap::ObjectId::new(cap::ObjectType::KernelLog),
+        cap::ObjectType::KernelLog,
+        cap::Rights::READ | cap::Rights::GRANT,
+    );
+
+    // Permission to spawn JIT runtimes with W^X lifted
+    let jit_cap = cap::register_object(
+        cap::ObjectId::new(cap::ObjectType::JitMemory),
+        cap::ObjectType::JitMemory,
+        cap::Rights::WRITE | cap::Rights::EXECUTE | cap::Rights::GRANT,
+    );
+
+    // Permission to run Linux binaries through the compat layer
+    let linux_cap = cap::register_object(
+        cap::ObjectId::new(cap::ObjectType::LinuxCompat),
+        cap::ObjectType::LinuxCompat,
+        cap::Rights::EXECUTE | cap::Rights::GRANT,
+    );
+
     for path in &init_paths {
         if fs::exists(path) {
             log::info!("Found init at {}", path);
@@ -328,12 +357,14 @@ fn load_init_process(_boot_info: &arch::BootInfo) -> cap::Capability {
                 env: alloc::vec![
                     (alloc::string::String::from("PATH"), alloc::string::String::from("/bin:/sbin:/usr/bin")),
                 ],
-                caps: alloc::vec![],
+                caps: alloc::vec![klog_cap, jit_cap, linux_cap],
                 sched_class: sched::SchedClass::Normal,
                 priority: 0,
                 cwd: Some(alloc::string::String::from("/")),
                 uid: 0,
                 gid: 0,
+                allow_wx: false,
+                personality: process::Personality::Nyx,
             };
 
             match process::spawn(args) {
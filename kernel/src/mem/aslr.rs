@@ -0,0 +1,121 @@
+//! Address space layout randomization
+//!
+//! Every process gets its stack top, heap base and mmap base slid by a
+//! random number of pages at spawn. The regions stay disjoint whatever the
+//! slide:
+//!
+//! ```text
+//! 0x0000_1000_0000_0000  heap base   + up to 1 TiB
+//! 0x0000_2000_0000_0000  mmap base   + up to 1 TiB
+//! 0x0000_7FFF_0000_0000  (end of heap/mmap search)
+//! 0x0000_7FFF_FFFF_8000  stack top   - up to 1 GiB
+//! ```
+//!
+//! Entropy comes from RDRAND, falling back to a mixed TSC where the CPU
+//! lacks it.
+
+use super::{VirtAddr, PAGE_SIZE};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Lowest heap base
+pub const HEAP_BASE: u64 = 0x0000_1000_0000_0000;
+/// Lowest mmap base
+pub const MMAP_BASE: u64 = 0x0000_2000_0000_0000;
+/// Highest address handed out by the heap/mmap allocators
+pub const USER_TOP: u64 = 0x0000_7FFF_0000_0000;
+/// Highest stack top
+pub const STACK_TOP: u64 = 0x0000_7FFF_FFFF_8000;
+
+/// Bits of page-granular entropy for the heap and mmap bases
+const REGION_ENTROPY_BITS: u32 = 28;
+/// Bits of page-granular entropy for the stack top
+const STACK_ENTROPY_BITS: u32 = 18;
+
+/// Base addresses of a process's regions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// Top of the main thread's stack
+    pub stack_top: VirtAddr,
+    /// Where `sys_mem_alloc` starts looking for free space
+    pub heap_base: VirtAddr,
+    /// Where `sys_mem_map` starts looking for free space
+    pub mmap_base: VirtAddr,
+}
+
+impl Layout {
+    /// The unrandomized layout
+    pub const fn fixed() -> Self {
+        Self {
+            stack_top: VirtAddr::new(STACK_TOP),
+            heap_base: VirtAddr::new(HEAP_BASE),
+            mmap_base: VirtAddr::new(MMAP_BASE),
+        }
+    }
+
+    /// A freshly randomized layout
+    pub fn randomized() -> Self {
+        Self::from_seeds(random_u64(), random_u64(), random_u64())
+    }
+
+    /// Layout for the given random values
+    fn from_seeds(stack: u64, heap: u64, mmap: u64) -> Self {
+        Self {
+            stack_top: VirtAddr::new(STACK_TOP - slide(stack, STACK_ENTROPY_BITS)),
+            heap_base: VirtAddr::new(HEAP_BASE + slide(heap, REGION_ENTROPY_BITS)),
+            mmap_base: VirtAddr::new(MMAP_BASE + slide(mmap, REGION_ENTROPY_BITS)),
+        }
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::fixed()
+    }
+}
+
+/// Page-aligned slide of `bits` bits of entropy
+fn slide(random: u64, bits: u32) -> u64 {
+    (random & ((1 << bits) - 1)) * PAGE_SIZE
+}
+
+/// Random 64-bit value for layout decisions
+pub fn random_u64() -> u64 {
+    if let Some(value) = crate::arch::x86_64::rdrand() {
+        return value;
+    }
+
+    // No RDRAND: run the TSC and a counter through splitmix64, so spawns in
+    // quick succession still differ
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut z = crate::arch::x86_64::rdtsc()
+        .wrapping_add(count.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regions_stay_disjoint() {
+        let lowest = Layout::from_seeds(0, 0, 0);
+        let highest = Layout::from_seeds(u64::MAX, u64::MAX, u64::MAX);
+
+        assert_eq!(lowest, Layout::fixed());
+        assert!(highest.heap_base.as_u64() < MMAP_BASE);
+        assert!(highest.mmap_base.as_u64() < USER_TOP);
+        assert!(highest.stack_top.as_u64() > USER_TOP);
+    }
+
+    #[test]
+    fn test_slides_are_page_aligned() {
+        let layout = Layout::from_seeds(0x1234_5678, 0x9ABC_DEF0, 0x0F0F_0F0F);
+
+        assert_eq!(layout.stack_top.as_u64() % PAGE_SIZE, 0);
+        assert_eq!(layout.heap_base.as_u64() % PAGE_SIZE, 0);
+        assert_eq!(layout.mmap_base.as_u64() % PAGE_SIZE, 0);
+    }
+}
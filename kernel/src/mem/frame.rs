@@ -11,6 +11,9 @@ use core::cmp::Ordering;
 /// Maximum buddy order (order 0 = 4KB, order 9 = 2MB, order 10 = 4MB)
 const MAX_ORDER: usize = 11;
 
+/// Largest run of frames one allocation can return
+pub const MAX_CONTIGUOUS_FRAMES: usize = 1 << (MAX_ORDER - 1);
+
 /// Buddy allocator for physical frames
 pub struct FrameAllocator {
     /// Free lists by order (order 0 = 4KB, order 9 = 2MB, etc.)
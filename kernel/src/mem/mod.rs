@@ -6,16 +6,21 @@
 //! - Virtual memory manager (per-process address spaces)
 //! - Kernel heap allocator
 //! - Safe userspace memory access primitives
+//! - Address space layout randomization for user processes
 //! - Memory tagging for spatial safety (ARM MTE / Intel LAM)
 
+pub mod aslr;
 mod frame;
 mod heap;
+pub mod numa;
 pub mod user;
 pub mod virt;
 
-pub use frame::FrameAllocator;
+pub use frame::{FrameAllocator, MAX_CONTIGUOUS_FRAMES};
+pub use numa::NodeId;
 pub use user::{copy_from_user, copy_string_from_user, copy_to_user, UserMemError};
-pub use virt::{AddressSpace, Protection, VirtualMemory};
+pub use aslr::Layout;
+pub use virt::{AddressSpace, PageCounts, Protection, VirtualMemory};
 
 /// Convert physical address to virtual address (identity mapping for kernel)
 #[inline]
@@ -29,16 +34,18 @@ pub fn phys_to_virt(phys: PhysAddr) -> u64 {
 use crate::arch::BootInfo;
 use spin::Mutex;
 
-/// Global frame allocator
-static FRAME_ALLOCATOR: Mutex<Option<FrameAllocator>> = Mutex::new(None);
+/// Frame allocators, one per NUMA node
+static FRAME_ALLOCATORS: [Mutex<Option<FrameAllocator>>; numa::MAX_NUMA_NODES] =
+    [const { Mutex::new(None) }; numa::MAX_NUMA_NODES];
 
 /// Initialize memory subsystem
 pub fn init(boot_info: &BootInfo) {
     log::debug!("Initializing memory subsystem");
 
-    // Initialize frame allocator from memory map
-    let mut allocator = FrameAllocator::new();
+    // Learn which node each physical range belongs to
+    numa::init(boot_info.rsdp_addr);
 
+    // Initialize frame allocators from memory map, split by node
     for region in boot_info.memory_map {
         if region.region_type == crate::arch::MemoryRegionType::Usable {
             log::trace!(
@@ -47,12 +54,15 @@ pub fn init(boot_info: &BootInfo) {
                 region.start + region.size,
                 region.size / 1024 / 1024
             );
-            allocator.add_region(region.start, region.size);
+            numa::split_range(region.start, region.start + region.size, |start, end, node| {
+                FRAME_ALLOCATORS[node as usize]
+                    .lock()
+                    .get_or_insert_with(FrameAllocator::new)
+                    .add_region(start, end - start);
+            });
         }
     }
 
-    *FRAME_ALLOCATOR.lock() = Some(allocator);
-
     // Initialize kernel heap
     heap::init();
 
@@ -133,21 +143,42 @@ pub const PAGE_SIZE: u64 = 4096;
 pub const HUGE_PAGE_SIZE_2M: u64 = 2 * 1024 * 1024;
 pub const HUGE_PAGE_SIZE_1G: u64 = 1024 * 1024 * 1024;
 
-/// Allocate a physical frame
+/// Allocate a physical frame, near the calling CPU
 pub fn alloc_frame() -> Option<PhysAddr> {
-    FRAME_ALLOCATOR.lock().as_mut()?.alloc_frame()
+    alloc_frames(1)
 }
 
 /// Free a physical frame
 pub fn free_frame(addr: PhysAddr) {
-    if let Some(allocator) = FRAME_ALLOCATOR.lock().as_mut() {
-        allocator.free_frame(addr);
-    }
+    free_frames(addr, 1);
 }
 
-/// Allocate contiguous physical frames
+/// Allocate contiguous physical frames, near the calling CPU
 pub fn alloc_frames(count: usize) -> Option<PhysAddr> {
-    FRAME_ALLOCATOR.lock().as_mut()?.alloc_frames(count)
+    alloc_frames_near(numa::current_node(), count)
+}
+
+/// Allocate contiguous physical frames on `node`, falling back to the
+/// other nodes when it is full
+pub fn alloc_frames_near(node: NodeId, count: usize) -> Option<PhysAddr> {
+    alloc_frames_on(node, count).or_else(|| {
+        (0..numa::node_count() as NodeId)
+            .filter(|&other| other != node)
+            .find_map(|other| alloc_frames_on(other, count))
+    })
+}
+
+/// Allocate contiguous physical frames on `node` only
+pub fn alloc_frames_on(node: NodeId, count: usize) -> Option<PhysAddr> {
+    FRAME_ALLOCATORS.get(node as usize)?.lock().as_mut()?.alloc_frames(count)
+}
+
+/// Free contiguous physical frames from one of the `alloc_frames` calls
+pub fn free_frames(addr: PhysAddr, count: usize) {
+    let node = numa::node_of_addr(addr.as_u64());
+    if let Some(allocator) = FRAME_ALLOCATORS[node as usize].lock().as_mut() {
+        allocator.free_frames(addr, count);
+    }
 }
 
 /// Allocate contiguous physical memory of specified size
@@ -168,10 +199,27 @@ pub fn free_contiguous(addr: PhysAddr, size: u64) {
 
 /// Get total system memory in bytes
 pub fn get_total_memory() -> Option<u64> {
-    FRAME_ALLOCATOR.lock().as_ref().map(|a| a.total_memory())
+    sum_nodes(FrameAllocator::total_memory)
 }
 
 /// Get available memory in bytes
 pub fn get_available_memory() -> Option<u64> {
-    FRAME_ALLOCATOR.lock().as_ref().map(|a| a.available_memory())
+    sum_nodes(FrameAllocator::available_memory)
+}
+
+/// Get (total, available) memory in bytes on one node
+pub fn node_memory(node: NodeId) -> Option<(u64, u64)> {
+    let allocator = FRAME_ALLOCATORS.get(node as usize)?.lock();
+    let allocator = allocator.as_ref()?;
+    Some((allocator.total_memory(), allocator.available_memory()))
+}
+
+/// Sum a per-allocator figure over all nodes (`None` before init)
+fn sum_nodes(f: impl Fn(&FrameAllocator) -> u64) -> Option<u64> {
+    FRAME_ALLOCATORS.iter().fold(None, |sum, allocator| {
+        match allocator.lock().as_ref() {
+            Some(allocator) => Some(sum.unwrap_or(0) + f(allocator)),
+            None => sum,
+        }
+    })
 }
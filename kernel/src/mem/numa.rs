@@ -0,0 +1,315 @@
+//! NUMA topology
+//!
+//! Maps physical memory ranges and CPUs to NUMA nodes using the ACPI System
+//! Resource Affinity Table (SRAT). The table is read during memory init,
+//! before the heap or the ACPI driver exist, so the topology lives in fixed
+//! arrays. Machines without an SRAT are a single node 0 covering everything.
+//!
+//! Node IDs are dense (0..[`node_count`]) in the order proximity domains
+//! first appear in the table.
+
+use crate::arch::x86_64::smp::MAX_CPUS;
+use spin::RwLock;
+
+/// NUMA node identifier
+pub type NodeId = u32;
+
+/// Maximum number of NUMA nodes tracked
+pub const MAX_NUMA_NODES: usize = 8;
+
+/// Maximum number of SRAT memory ranges tracked
+const MAX_MEMORY_RANGES: usize = 64;
+
+/// SRAT header plus its two reserved fields
+const SRAT_ENTRIES_OFFSET: usize = 48;
+
+/// SRAT entry types
+const SRAT_CPU_APIC: u8 = 0;
+const SRAT_MEMORY: u8 = 1;
+const SRAT_CPU_X2APIC: u8 = 2;
+
+/// SRAT entry flag: entry is enabled
+const SRAT_ENABLED: u32 = 1 << 0;
+
+/// A physical memory range on one node
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct MemoryRange {
+    start: u64,
+    end: u64,
+    node: NodeId,
+}
+
+/// NUMA topology
+pub struct Topology {
+    /// Proximity domain of each node, indexed by node ID
+    domains: [u32; MAX_NUMA_NODES],
+    /// Number of nodes discovered (0 until the SRAT is parsed)
+    node_count: usize,
+    /// Memory ranges by node
+    ranges: [MemoryRange; MAX_MEMORY_RANGES],
+    /// Number of valid entries in `ranges`
+    range_count: usize,
+    /// Node of each CPU, indexed by APIC ID
+    cpu_nodes: [NodeId; MAX_CPUS],
+}
+
+impl Topology {
+    /// Empty topology (everything on node 0)
+    const fn new() -> Self {
+        Self {
+            domains: [0; MAX_NUMA_NODES],
+            node_count: 0,
+            ranges: [MemoryRange { start: 0, end: 0, node: 0 }; MAX_MEMORY_RANGES],
+            range_count: 0,
+            cpu_nodes: [0; MAX_CPUS],
+        }
+    }
+
+    /// Node ID for a proximity domain, assigning the next free one
+    fn node_for_domain(&mut self, domain: u32) -> Option<NodeId> {
+        if let Some(node) = self.domains[..self.node_count].iter().position(|&d| d == domain) {
+            return Some(node as NodeId);
+        }
+        if self.node_count == MAX_NUMA_NODES {
+            return None;
+        }
+        self.domains[self.node_count] = domain;
+        self.node_count += 1;
+        Some(self.node_count as NodeId - 1)
+    }
+
+    /// Parse an SRAT, given the whole table including its header
+    fn parse_srat(&mut self, table: &[u8]) {
+        let mut offset = SRAT_ENTRIES_OFFSET;
+        while offset + 2 <= table.len() {
+            let kind = table[offset];
+            let len = table[offset + 1] as usize;
+            if len < 2 || offset + len > table.len() {
+                break;
+            }
+            let entry = &table[offset..offset + len];
+            offset += len;
+
+            match kind {
+                SRAT_CPU_APIC if len >= 16 => {
+                    if read_u32(entry, 4) & SRAT_ENABLED == 0 {
+                        continue;
+                    }
+                    let domain = entry[2] as u32
+                        | (entry[9] as u32) << 8
+                        | (entry[10] as u32) << 16
+                        | (entry[11] as u32) << 24;
+                    self.add_cpu(entry[3] as u32, domain);
+                }
+                SRAT_CPU_X2APIC if len >= 24 => {
+                    if read_u32(entry, 12) & SRAT_ENABLED == 0 {
+                        continue;
+                    }
+                    self.add_cpu(read_u32(entry, 8), read_u32(entry, 4));
+                }
+                SRAT_MEMORY if len >= 40 => {
+                    if read_u32(entry, 28) & SRAT_ENABLED == 0 {
+                        continue;
+                    }
+                    let start = read_u64(entry, 8);
+                    let size = read_u64(entry, 16);
+                    self.add_memory(start, start.saturating_add(size), read_u32(entry, 2));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn add_cpu(&mut self, apic_id: u32, domain: u32) {
+        let Some(node) = self.node_for_domain(domain) else {
+            log::warn!("NUMA: too many nodes, ignoring domain {}", domain);
+            return;
+        };
+        if let Some(slot) = self.cpu_nodes.get_mut(apic_id as usize) {
+            *slot = node;
+        }
+    }
+
+    fn add_memory(&mut self, start: u64, end: u64, domain: u32) {
+        let Some(node) = self.node_for_domain(domain) else {
+            log::warn!("NUMA: too many nodes, ignoring domain {}", domain);
+            return;
+        };
+        if start >= end {
+            return;
+        }
+        if self.range_count == MAX_MEMORY_RANGES {
+            log::warn!("NUMA: too many memory ranges, {:#x}-{:#x} falls to node 0", start, end);
+            return;
+        }
+        self.ranges[self.range_count] = MemoryRange { start, end, node };
+        self.range_count += 1;
+    }
+
+    /// Node owning a physical address (node 0 if no range covers it)
+    fn node_of(&self, addr: u64) -> NodeId {
+        self.ranges[..self.range_count]
+            .iter()
+            .find(|range| range.start <= addr && addr < range.end)
+            .map_or(0, |range| range.node)
+    }
+
+    /// Split `[start, end)` at node boundaries
+    ///
+    /// Calls `f(start, end, node)` for each piece in address order. Parts
+    /// no range covers go to node 0.
+    fn split(&self, start: u64, end: u64, mut f: impl FnMut(u64, u64, NodeId)) {
+        let mut addr = start;
+        while addr < end {
+            let node = self.node_of(addr);
+            // The piece ends where the next range starts or this one ends
+            let piece_end = self.ranges[..self.range_count]
+                .iter()
+                .flat_map(|range| [range.start, range.end])
+                .filter(|&boundary| boundary > addr && boundary < end)
+                .min()
+                .unwrap_or(end);
+            f(addr, piece_end, node);
+            addr = piece_end;
+        }
+    }
+}
+
+/// System NUMA topology
+static TOPOLOGY: RwLock<Topology> = RwLock::new(Topology::new());
+
+/// Read the SRAT, if the firmware provides one
+pub fn init(rsdp_addr: Option<u64>) {
+    let srat = rsdp_addr
+        .or_else(crate::driver::acpi::find_rsdp)
+        .and_then(|rsdp| crate::driver::acpi::find_table_at(rsdp, b"SRAT"));
+    let Some(srat) = srat else {
+        log::debug!("NUMA: no SRAT, single node");
+        return;
+    };
+
+    let virt = crate::mem::phys_to_virt(crate::mem::PhysAddr::new(srat)) as *const u8;
+    let table = unsafe {
+        let len = (virt.add(4) as *const u32).read_unaligned() as usize;
+        core::slice::from_raw_parts(virt, len)
+    };
+
+    let mut topology = TOPOLOGY.write();
+    topology.parse_srat(table);
+    log::info!(
+        "NUMA: {} node(s), {} memory range(s)",
+        topology.node_count.max(1),
+        topology.range_count
+    );
+}
+
+/// Number of NUMA nodes (at least 1)
+pub fn node_count() -> usize {
+    TOPOLOGY.read().node_count.max(1)
+}
+
+/// Node owning a physical address
+pub fn node_of_addr(addr: u64) -> NodeId {
+    TOPOLOGY.read().node_of(addr)
+}
+
+/// Node of a CPU, by APIC ID
+pub fn cpu_node(apic_id: u32) -> NodeId {
+    TOPOLOGY
+        .read()
+        .cpu_nodes
+        .get(apic_id as usize)
+        .copied()
+        .unwrap_or(0)
+}
+
+/// Node of the CPU this code runs on
+pub fn current_node() -> NodeId {
+    cpu_node(crate::arch::x86_64::smp::current_cpu_id())
+}
+
+/// Split a physical range at node boundaries (see [`Topology::split`])
+pub fn split_range(start: u64, end: u64, f: impl FnMut(u64, u64, NodeId)) {
+    TOPOLOGY.read().split(start, end, f);
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn memory_entry(domain: u32, start: u64, size: u64) -> Vec<u8> {
+        let mut entry = vec![0u8; 40];
+        entry[0] = SRAT_MEMORY;
+        entry[1] = 40;
+        entry[2..6].copy_from_slice(&domain.to_le_bytes());
+        entry[8..16].copy_from_slice(&start.to_le_bytes());
+        entry[16..24].copy_from_slice(&size.to_le_bytes());
+        entry[28..32].copy_from_slice(&SRAT_ENABLED.to_le_bytes());
+        entry
+    }
+
+    fn cpu_entry(domain: u8, apic_id: u8) -> Vec<u8> {
+        let mut entry = vec![0u8; 16];
+        entry[0] = SRAT_CPU_APIC;
+        entry[1] = 16;
+        entry[2] = domain;
+        entry[3] = apic_id;
+        entry[4..8].copy_from_slice(&SRAT_ENABLED.to_le_bytes());
+        entry
+    }
+
+    fn srat(entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut table = vec![0u8; SRAT_ENTRIES_OFFSET];
+        for entry in entries {
+            table.extend_from_slice(entry);
+        }
+        table
+    }
+
+    #[test]
+    fn test_parse_srat_two_nodes() {
+        let table = srat(&[
+            cpu_entry(7, 0),
+            cpu_entry(9, 1),
+            memory_entry(7, 0, 0x8000_0000),
+            memory_entry(9, 0x8000_0000, 0x8000_0000),
+        ]);
+        let mut topology = Topology::new();
+        topology.parse_srat(&table);
+
+        assert_eq!(topology.node_count, 2);
+        assert_eq!(topology.cpu_nodes[0], 0);
+        assert_eq!(topology.cpu_nodes[1], 1);
+        assert_eq!(topology.node_of(0x1000), 0);
+        assert_eq!(topology.node_of(0x9000_0000), 1);
+    }
+
+    #[test]
+    fn test_split_at_node_boundary() {
+        let table = srat(&[
+            memory_entry(0, 0, 0x1000_0000),
+            memory_entry(1, 0x1000_0000, 0x1000_0000),
+        ]);
+        let mut topology = Topology::new();
+        topology.parse_srat(&table);
+
+        let mut pieces = Vec::new();
+        topology.split(0x0800_0000, 0x1800_0000, |start, end, node| {
+            pieces.push((start, end, node))
+        });
+        assert_eq!(
+            pieces,
+            vec![(0x0800_0000, 0x1000_0000, 0), (0x1000_0000, 0x1800_0000, 1)]
+        );
+    }
+}
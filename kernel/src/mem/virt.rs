@@ -1,6 +1,6 @@
 //! Virtual memory manager
 
-use super::{PhysAddr, VirtAddr, PAGE_SIZE};
+use super::{Layout, PhysAddr, VirtAddr, PAGE_SIZE};
 use crate::arch::x86_64::paging::{flush_tlb_page, PageFlags, PageMapper};
 use crate::cap::ObjectId;
 use alloc::collections::BTreeMap;
@@ -16,6 +16,23 @@ pub struct AddressSpace {
     vmas: BTreeMap<VirtAddr, Vma>,
     /// Page table root (physical address)
     page_table_root: PhysAddr,
+    /// Page accounting
+    pages: PageCounts,
+    /// Region base addresses (randomized per process)
+    layout: Layout,
+    /// Whether writable and executable mappings are allowed (JIT runtimes)
+    allow_wx: bool,
+}
+
+/// Page accounting for an address space
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PageCounts {
+    /// Pages reserved by mappings, faulted in or not
+    pub mapped: u64,
+    /// Pages backed by a physical frame
+    pub resident: u64,
+    /// Resident pages of shared memory regions
+    pub shared: u64,
 }
 
 /// Virtual memory area
@@ -133,7 +150,43 @@ impl AddressSpace {
             id: ObjectId::new(crate::cap::ObjectType::AddressSpace),
             vmas: BTreeMap::new(),
             page_table_root,
+            pages: PageCounts::default(),
+            layout: Layout::fixed(),
+            allow_wx: false,
+        }
+    }
+
+    /// Current page accounting
+    pub fn page_counts(&self) -> PageCounts {
+        self.pages
+    }
+
+    /// Region base addresses
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Set the region base addresses
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+    }
+
+    /// Allow writable and executable mappings, lifting W^X
+    pub fn permit_wx(&mut self) {
+        self.allow_wx = true;
+    }
+
+    /// Whether writable and executable mappings are allowed
+    pub fn wx_allowed(&self) -> bool {
+        self.allow_wx
+    }
+
+    /// Refuse mappings that are both writable and executable (W^X)
+    fn check_wx(&self, protection: Protection) -> Result<(), VmError> {
+        if protection.contains(Protection::WRITE | Protection::EXECUTE) && !self.allow_wx {
+            return Err(VmError::WriteExecute);
         }
+        Ok(())
     }
 
     /// Map a region
@@ -144,6 +197,8 @@ impl AddressSpace {
         protection: Protection,
         backing: VmaBacking,
     ) -> Result<(), VmError> {
+        self.check_wx(protection)?;
+
         let end = VirtAddr::new(start.as_u64() + size);
 
         // Check for overlaps
@@ -162,28 +217,63 @@ impl AddressSpace {
         };
 
         self.vmas.insert(start, vma);
+        self.pages.mapped += size.div_ceil(PAGE_SIZE);
         Ok(())
     }
 
     /// Unmap a region
     pub fn unmap(&mut self, start: VirtAddr, size: u64) -> Result<(), VmError> {
-        let end = start.as_u64() + size;
+        let mut first = start.as_u64() & !(PAGE_SIZE - 1);
+        let mut end = start.as_u64() + size;
 
-        // Find and remove overlapping VMAs
+        // Find overlapping VMAs; they go as a whole
         let to_remove: Vec<_> = self
             .vmas
             .range(..VirtAddr::new(end))
             .filter(|(_, vma)| vma.end.as_u64() > start.as_u64())
-            .map(|(k, _)| *k)
+            .map(|(k, vma)| {
+                first = first.min(vma.start.as_u64());
+                end = end.max(vma.end.as_u64());
+                *k
+            })
             .collect();
 
+        // Drop the pages already faulted in
+        let mut addr = first;
+        while addr < end {
+            let virt = VirtAddr::new(addr);
+            if self.unmap_page(virt).is_ok() {
+                flush_tlb_page(virt);
+                let shared = self
+                    .vma_at(virt)
+                    .map(|vma| matches!(vma.backing, VmaBacking::Shared { .. }));
+                match shared {
+                    None => self.pages.mapped = self.pages.mapped.saturating_sub(1),
+                    Some(true) => self.pages.shared = self.pages.shared.saturating_sub(1),
+                    Some(false) => {}
+                }
+            }
+            addr += PAGE_SIZE;
+        }
+
         for key in to_remove {
-            self.vmas.remove(&key);
+            if let Some(vma) = self.vmas.remove(&key) {
+                self.pages.mapped = self.pages.mapped.saturating_sub(vma.size().div_ceil(PAGE_SIZE));
+            }
         }
 
         Ok(())
     }
 
+    /// The VMA containing an address
+    fn vma_at(&self, addr: VirtAddr) -> Option<&Vma> {
+        self.vmas
+            .range(..=addr)
+            .next_back()
+            .map(|(_, vma)| vma)
+            .filter(|vma| vma.end.as_u64() > addr.as_u64())
+    }
+
     /// Handle page fault
     pub fn handle_fault(&mut self, addr: VirtAddr, write: bool) -> Result<(), VmError> {
         // Find VMA containing the address
@@ -241,6 +331,7 @@ impl AddressSpace {
                     .ok_or(VmError::OutOfMemory)?;
 
                 self.map_page(addr, frame, vma.protection)?;
+                self.pages.shared += 1;
             }
             VmaBacking::Tensor { tensor, offset } => {
                 // Tensor buffer: map from tensor's device memory
@@ -264,6 +355,8 @@ impl AddressSpace {
         phys: PhysAddr,
         prot: Protection,
     ) -> Result<(), VmError> {
+        self.check_wx(prot)?;
+
         // Convert protection flags to page flags
         let mut flags = PageFlags::PRESENT;
 
@@ -296,6 +389,12 @@ impl AddressSpace {
         // Flush TLB for this page
         flush_tlb_page(virt);
 
+        // Pages mapped directly, outside any VMA, count as mapped too
+        self.pages.resident += 1;
+        if self.vma_at(virt).is_none() {
+            self.pages.mapped += 1;
+        }
+
         Ok(())
     }
 
@@ -303,10 +402,13 @@ impl AddressSpace {
     fn unmap_page(&mut self, virt: VirtAddr) -> Result<PhysAddr, VmError> {
         let mut mapper = PageMapper::new(self.page_table_root);
 
-        mapper.unmap_page(virt).map_err(|e| match e {
+        let phys = mapper.unmap_page(virt).map_err(|e| match e {
             crate::arch::x86_64::paging::MapError::NotMapped => VmError::NotMapped,
             _ => VmError::NotImplemented,
-        })
+        })?;
+        self.pages.resident = self.pages.resident.saturating_sub(1);
+
+        Ok(phys)
     }
 
     /// Get the page table root physical address
@@ -445,4 +547,6 @@ pub enum VmError {
     DeviceMemory,
     /// I/O error
     IoError,
+    /// Mapping would be both writable and executable
+    WriteExecute,
 }
@@ -56,6 +56,16 @@ pub enum ProcessState {
     Dead,
 }
 
+/// Which system call ABI a process speaks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Personality {
+    /// Native Nyx syscalls
+    #[default]
+    Nyx,
+    /// Linux x86_64 syscalls, translated by `syscall::compat`
+    Linux,
+}
+
 /// Tracked memory allocation
 #[derive(Clone, Debug)]
 pub struct TrackedAllocation {
@@ -103,12 +113,15 @@ pub struct Process {
     pub fd_table: BTreeMap<i32, u32>,
     /// Next file descriptor
     pub next_fd: i32,
-    /// Memory statistics
+    /// Text and data sizes recorded by the loader; page counts live in the
+    /// address space (see `memory_stats`)
     pub mem_stats: MemoryStats,
     /// Tracked memory allocations (for ownership verification)
     allocations: BTreeMap<u64, TrackedAllocation>,
     /// Threads waiting to join on this process's threads
     pub join_waiters: BTreeMap<ThreadId, Vec<ThreadId>>,
+    /// System call ABI
+    pub personality: Personality,
 }
 
 /// Memory usage statistics
@@ -150,6 +163,7 @@ impl Process {
             mem_stats: MemoryStats::default(),
             allocations: BTreeMap::new(),
             join_waiters: BTreeMap::new(),
+            personality: Personality::Nyx,
         }
     }
 
@@ -197,6 +211,17 @@ impl Process {
         self.fd_table.remove(&fd)
     }
 
+    /// Memory usage, with live page counts from the address space
+    pub fn memory_stats(&self) -> MemoryStats {
+        let pages = self.address_space.page_counts();
+        MemoryStats {
+            vm_size: pages.mapped * PAGE_SIZE,
+            rss: pages.resident * PAGE_SIZE,
+            shared: pages.shared * PAGE_SIZE,
+            ..self.mem_stats
+        }
+    }
+
     /// Insert a capability and return its slot
     pub fn insert_cap(&mut self, cap: Capability) -> u32 {
         self.cspace.insert_next(cap).unwrap_or(0)
@@ -298,6 +323,13 @@ pub struct SpawnArgs {
     pub uid: u32,
     /// Group ID
     pub gid: u32,
+    /// Lift W^X so the process may map memory writable and executable.
+    /// Only for JIT runtimes; the spawn syscall requires a `JitMemory`
+    /// capability to set it.
+    pub allow_wx: bool,
+    /// System call ABI. The spawn syscall requires a `LinuxCompat`
+    /// capability for `Personality::Linux`.
+    pub personality: Personality,
 }
 
 impl Default for SpawnArgs {
@@ -312,6 +344,8 @@ impl Default for SpawnArgs {
             cwd: None,
             uid: 0,
             gid: 0,
+            allow_wx: false,
+            personality: Personality::Nyx,
         }
     }
 }
@@ -363,8 +397,17 @@ pub fn spawn(args: SpawnArgs) -> Result<ProcessId, SpawnError> {
     proc.uid = args.uid;
     proc.gid = args.gid;
 
+    // Randomize the layout and settle W^X before anything is mapped
+    let layout = crate::mem::Layout::randomized();
+    proc.address_space.set_layout(layout);
+    if args.allow_wx {
+        proc.address_space.permit_wx();
+    }
+
+    proc.personality = args.personality;
+
     // Load executable
-    let entry_point = load_executable(&args.path, &mut proc)?;
+    let image = load_executable(&args.path, &mut proc)?;
 
     // Grant initial capabilities
     for cap in args.caps {
@@ -372,15 +415,26 @@ pub fn spawn(args: SpawnArgs) -> Result<ProcessId, SpawnError> {
     }
 
     // Set up user stack
-    let stack_base = VirtAddr::new(0x0000_7FFF_FFFF_0000); // Below kernel
     let stack_size = 8 * PAGE_SIZE; // 32KB stack
+    let stack_top = layout.stack_top.as_u64();
+    let stack_base = VirtAddr::new(stack_top - stack_size);
     setup_user_stack(&mut proc, stack_base, stack_size, &args.args)?;
 
+    // Linux binaries expect argc, argv, envp and the aux vector on the stack
+    let stack_pointer = match proc.personality {
+        Personality::Nyx => stack_top,
+        Personality::Linux => {
+            let env: Vec<_> = proc.env.iter().map(|(k, v)| alloc::format!("{}={}", k, v)).collect();
+            let sp = crate::syscall::compat::setup_stack(&proc, stack_base, stack_top, &image, &args.args, &env)?;
+            crate::syscall::compat::attach(&proc, image.end);
+            sp
+        }
+    };
+
     // Create main thread
-    let stack_top = stack_base.as_u64() + stack_size;
     let thread = Thread::new_user(
-        entry_point,
-        stack_top,
+        image.entry,
+        stack_pointer,
         proc.address_space.clone(),
         proc.pid,
     );
@@ -417,13 +471,34 @@ pub fn spawn(args: SpawnArgs) -> Result<ProcessId, SpawnError> {
     Ok(pid)
 }
 
+/// Where an executable landed in a process address space
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoadedImage {
+    /// Entry point
+    pub entry: u64,
+    /// Address of the program headers, if a segment maps them
+    pub phdr: u64,
+    /// Number of program headers
+    pub phnum: u16,
+    /// Size of a program header
+    pub phentsize: u16,
+    /// First page past the highest segment
+    pub end: u64,
+}
+
 /// Load an executable into a process address space
-fn load_executable(path: &str, proc: &mut Process) -> Result<u64, SpawnError> {
+fn load_executable(path: &str, proc: &mut Process) -> Result<LoadedImage, SpawnError> {
     // Try to load from initrd or filesystem
     let data = crate::fs::read_file(path).map_err(|_| SpawnError::NotFound)?;
 
     // Parse ELF
     let elf = Elf::parse(&data).map_err(|_| SpawnError::InvalidFormat)?;
+    let mut image = LoadedImage {
+        entry: elf.entry(),
+        phnum: elf.phnum,
+        phentsize: elf.phentsize,
+        ..LoadedImage::default()
+    };
 
     // Load program headers
     for phdr in elf.program_headers() {
@@ -431,6 +506,10 @@ fn load_executable(path: &str, proc: &mut Process) -> Result<u64, SpawnError> {
             continue;
         }
 
+        if elf.phoff >= phdr.p_offset && elf.phoff < phdr.p_offset + phdr.p_filesz {
+            image.phdr = phdr.p_vaddr + (elf.phoff - phdr.p_offset);
+        }
+
         let vaddr = VirtAddr::new(phdr.p_vaddr);
         let memsz = phdr.p_memsz;
         let filesz = phdr.p_filesz;
@@ -449,9 +528,16 @@ fn load_executable(path: &str, proc: &mut Process) -> Result<u64, SpawnError> {
         }
         prot |= crate::mem::virt::Protection::USER;
 
+        // W^X: a segment may be writable or executable, not both
+        if phdr.p_flags & PF_W != 0 && phdr.p_flags & PF_X != 0 && !proc.address_space.wx_allowed() {
+            log::warn!("{}: refusing writable and executable segment at {:#x}", path, phdr.p_vaddr);
+            return Err(SpawnError::PermissionDenied);
+        }
+
         // Map pages
         let start_page = vaddr.align_down(PAGE_SIZE);
         let end_page = VirtAddr::new(vaddr.as_u64() + memsz).align_up(PAGE_SIZE);
+        image.end = image.end.max(end_page.as_u64());
         let page_count = ((end_page.as_u64() - start_page.as_u64()) / PAGE_SIZE) as usize;
 
         for i in 0..page_count {
@@ -503,7 +589,6 @@ fn load_executable(path: &str, proc: &mut Process) -> Result<u64, SpawnError> {
         }
 
         // Update memory stats
-        proc.mem_stats.vm_size += memsz;
         if phdr.p_flags & PF_X != 0 {
             proc.mem_stats.text += memsz;
         } else {
@@ -511,7 +596,7 @@ fn load_executable(path: &str, proc: &mut Process) -> Result<u64, SpawnError> {
         }
     }
 
-    Ok(elf.entry())
+    Ok(image)
 }
 
 /// Set up the user stack with arguments
@@ -547,7 +632,6 @@ fn setup_user_stack(
             .map_err(|_| SpawnError::OutOfMemory)?;
     }
 
-    proc.mem_stats.vm_size += stack_size;
     proc.mem_stats.data += stack_size;
 
     Ok(())
@@ -593,6 +677,10 @@ pub fn exit(exit_code: i32) {
         }
     }
 
+    // Close whatever a Linux binary left open, and stop its timers
+    crate::syscall::compat::release(pid);
+    crate::time::timer::release(pid);
+
     // Signal parent that child exited (SIGCHLD)
     if let Some(parent_pid) = get_process(pid).and_then(|p| p.parent) {
         send_sigchld_to_parent(parent_pid, pid, exit_code, false);
@@ -729,6 +817,17 @@ pub fn current_process_id() -> Option<ProcessId> {
     None
 }
 
+/// System call ABI of the current process
+pub fn current_personality() -> Personality {
+    let thread_id = crate::sched::current_thread_id();
+    PROCESSES
+        .read()
+        .values()
+        .find(|proc| proc.threads.contains(&thread_id))
+        .map(|proc| proc.personality)
+        .unwrap_or_default()
+}
+
 /// Get a process by ID
 pub fn get_process(pid: ProcessId) -> Option<Process> {
     PROCESSES.read().get(&pid).cloned()
@@ -786,6 +885,7 @@ impl Clone for Process {
             mem_stats: self.mem_stats,
             allocations: BTreeMap::new(), // Allocations are not cloned (fresh address space)
             join_waiters: BTreeMap::new(), // Join waiters are not cloned
+            personality: self.personality,
         }
     }
 }
@@ -931,6 +1031,9 @@ pub fn terminate(pid: ProcessId, exit_code: i32) {
         parent
     };
 
+    crate::syscall::compat::release(pid);
+    crate::time::timer::release(pid);
+
     // Send SIGCHLD to parent
     if let Some(parent_pid) = parent_pid {
         send_sigchld_to_parent(parent_pid, pid, exit_code, dumped_core);
@@ -38,6 +38,11 @@ impl CfsQueue {
         Some(thread_id)
     }
 
+    /// Remove every thread, lowest vruntime first
+    pub fn drain(&mut self) -> impl Iterator<Item = ThreadId> {
+        core::mem::take(&mut self.tree).into_values()
+    }
+
     /// Check if queue is empty
     pub fn is_empty(&self) -> bool {
         self.tree.is_empty()
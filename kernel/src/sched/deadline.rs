@@ -62,6 +62,11 @@ impl DeadlineQueue {
         self.heap.pop().map(|e| e.thread_id)
     }
 
+    /// Remove every entry
+    pub fn drain(&mut self) -> impl Iterator<Item = DeadlineEntry> + '_ {
+        self.heap.drain()
+    }
+
     /// Peek at earliest deadline
     pub fn peek(&self) -> Option<&DeadlineEntry> {
         self.heap.peek()
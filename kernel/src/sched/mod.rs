@@ -7,6 +7,7 @@
 //! - Real-time deadline scheduling (SCHED_DEADLINE)
 //! - Energy-aware scheduling (big.LITTLE / P-core/E-core)
 //! - Priority inheritance for mutex holders
+//! - CPU hotplug with rebalancing when CPUs go offline or come back
 
 mod cfs;
 mod deadline;
@@ -15,7 +16,7 @@ mod thread;
 
 pub use thread::{BlockReason, RegisterState, Thread, ThreadId, ThreadState};
 
-use crate::arch::BootInfo;
+use crate::arch::{BootInfo, HotplugError};
 use crate::cap::Capability;
 use alloc::collections::{BTreeMap, BinaryHeap};
 use core::arch::asm;
@@ -126,16 +127,19 @@ fn switch_to(next_id: ThreadId) {
             next.state = ThreadState::Running;
             let regs = next.registers;
             let page_table_root = next.address_space.page_table_root();
-            Some((regs, page_table_root))
+            Some((regs, page_table_root, next.fs_base))
         } else {
             None
         }
     };
 
     // Update current thread and perform context switch
-    if let Some((next_regs, page_table_root)) = switch_info {
+    if let Some((next_regs, page_table_root, fs_base)) = switch_info {
         CURRENT_THREAD.store(next_id.0, Ordering::SeqCst);
 
+        // Linux binaries keep their thread pointer in FS
+        crate::arch::x86_64::set_fs_base(fs_base);
+
         // Perform actual context switch with address space switch
         // SAFETY: next_regs is valid, page_table_root points to valid page tables
         unsafe {
@@ -252,6 +256,11 @@ pub fn sleep(duration: core::time::Duration) {
     schedule();
 }
 
+/// Scheduler ticks since boot
+pub fn tick_count() -> u64 {
+    TICK_COUNT.load(Ordering::Relaxed)
+}
+
 /// Timer tick handler (called from IRQ)
 pub fn timer_tick() {
     let tick = TICK_COUNT.fetch_add(1, Ordering::SeqCst) + 1;
@@ -332,6 +341,15 @@ pub fn block(reason: BlockReason) {
     schedule();
 }
 
+/// Switch away from a current thread the caller has marked blocked
+///
+/// For waits whose caller sets the blocked state under the same lock its
+/// waker takes, so a wake in between is not lost.
+pub fn reschedule() {
+    NEED_RESCHED.store(true, Ordering::SeqCst);
+    schedule();
+}
+
 /// Wake a blocked thread
 pub fn wake(thread_id: ThreadId) {
     let cpu_id = current_cpu_id();
@@ -419,6 +437,8 @@ impl PartialOrd for TimerEntry {
 /// Per-CPU scheduler state
 pub struct CpuScheduler {
     cpu_id: u32,
+    /// Whether the CPU takes work; offline CPUs have empty queues
+    online: bool,
     /// Currently running thread
     current: Option<ThreadId>,
     /// CFS run queue (normal priority)
@@ -435,6 +455,7 @@ impl CpuScheduler {
     fn new(cpu_id: u32) -> Self {
         Self {
             cpu_id,
+            online: true,
             current: None,
             cfs_queue: cfs::CfsQueue::new(),
             deadline_queue: deadline::DeadlineQueue::new(),
@@ -528,6 +549,34 @@ impl CpuScheduler {
         self.cfs_queue.pick_next()
     }
 
+    /// Empty every queue, for moving the threads to another CPU
+    ///
+    /// Returns the running and runnable threads, deadline entries (which
+    /// keep their parameters), and sleeping threads with their wake ticks.
+    fn drain(
+        &mut self,
+    ) -> (
+        alloc::vec::Vec<ThreadId>,
+        alloc::vec::Vec<deadline::DeadlineEntry>,
+        alloc::vec::Vec<TimerEntry>,
+    ) {
+        let mut runnable: alloc::vec::Vec<ThreadId> = self
+            .current
+            .take()
+            .filter(|current| Some(*current) != self.idle_thread)
+            .into_iter()
+            .collect();
+        runnable.extend(self.cfs_queue.drain());
+        let deadline = self.deadline_queue.drain().collect();
+        let sleeping = self.timer_queue.drain().collect();
+        (runnable, deadline, sleeping)
+    }
+
+    /// Check whether this CPU takes work
+    pub fn is_online(&self) -> bool {
+        self.online
+    }
+
     /// Check if this CPU is idle
     pub fn is_idle(&self) -> bool {
         self.cfs_queue.is_empty() && self.deadline_queue.is_empty()
@@ -590,7 +639,7 @@ pub fn load_balance() {
     let mut idlest_cpu = 0;
     let mut idlest_load = usize::MAX;
 
-    for (i, sched) in per_cpu.iter().enumerate() {
+    for (i, sched) in per_cpu.iter().enumerate().filter(|(_, sched)| sched.online) {
         let load = sched.queue_len();
         if load > busiest_load {
             busiest_load = load;
@@ -674,7 +723,7 @@ pub fn try_steal_work() -> Option<ThreadId> {
     for offset in 1..cpu_count {
         let target_cpu = (current_cpu + offset) % cpu_count;
         if let Some(target_sched) = per_cpu.get_mut(target_cpu) {
-            if target_sched.queue_len() > 1 {
+            if target_sched.online && target_sched.queue_len() > 1 {
                 if let Some(thread_id) = target_sched.steal_thread() {
                     log::trace!(
                         "CPU {} stole thread {:?} from CPU {}",
@@ -702,15 +751,173 @@ pub fn periodic_load_balance(tick: u64) {
 pub fn enqueue_on_least_loaded(thread_id: ThreadId) {
     let mut per_cpu = PER_CPU.write();
 
-    // Find CPU with lowest load
-    let best_cpu = per_cpu
+    let best_cpu = least_loaded_online(&per_cpu);
+
+    if let Some(sched) = per_cpu.get_mut(best_cpu) {
+        sched.enqueue(thread_id);
+    }
+}
+
+/// Online CPU with the lowest load; CPU 0 never goes offline
+fn least_loaded_online(per_cpu: &[CpuScheduler]) -> usize {
+    per_cpu
         .iter()
         .enumerate()
+        .filter(|(_, sched)| sched.online)
         .min_by_key(|(_, sched)| sched.queue_len())
         .map(|(i, _)| i)
-        .unwrap_or(0);
+        .unwrap_or(0)
+}
 
-    if let Some(sched) = per_cpu.get_mut(best_cpu) {
-        sched.enqueue(thread_id);
+// ============================================================================
+// CPU Hotplug
+// ============================================================================
+
+/// Take a CPU offline, moving its threads to the CPUs that remain
+///
+/// Used by slumber's power profiles to shed cores when saving power.
+pub fn cpu_offline(cpu_id: u32) -> Result<(), HotplugError> {
+    // Stop new work landing on the CPU before it goes away
+    {
+        let mut per_cpu = PER_CPU.write();
+        let sched = per_cpu
+            .get_mut(cpu_id as usize)
+            .ok_or(HotplugError::InvalidCpu)?;
+        if !sched.online {
+            return Err(HotplugError::AlreadyOffline);
+        }
+        sched.online = false;
+    }
+
+    if let Err(e) = crate::arch::cpu_down(cpu_id) {
+        if let Some(sched) = PER_CPU.write().get_mut(cpu_id as usize) {
+            sched.online = true;
+        }
+        return Err(e);
+    }
+
+    migrate_from(cpu_id as usize);
+    Ok(())
+}
+
+/// Bring a CPU back online and let it take its share of the work
+pub fn cpu_online(cpu_id: u32) -> Result<(), HotplugError> {
+    {
+        let per_cpu = PER_CPU.read();
+        let sched = per_cpu.get(cpu_id as usize).ok_or(HotplugError::InvalidCpu)?;
+        if sched.online {
+            return Err(HotplugError::AlreadyOnline);
+        }
+    }
+
+    crate::arch::cpu_up(cpu_id)?;
+
+    if let Some(sched) = PER_CPU.write().get_mut(cpu_id as usize) {
+        sched.online = true;
+    }
+    rebalance_onto(cpu_id as usize);
+    Ok(())
+}
+
+/// Match the scheduler to the CPUs that actually started at boot
+pub fn sync_online_cpus() {
+    let offline: alloc::vec::Vec<usize> = {
+        let mut per_cpu = PER_CPU.write();
+        per_cpu
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(cpu_id, sched)| {
+                sched.online = cpu_id == 0 || crate::arch::x86_64::smp::is_online(cpu_id as u32);
+                (!sched.online).then_some(cpu_id)
+            })
+            .collect()
+    };
+
+    for cpu_id in offline {
+        log::warn!("CPU {} did not come up; scheduling around it", cpu_id);
+        migrate_from(cpu_id);
+    }
+}
+
+/// Move every thread queued on an offline CPU to the least loaded online ones
+fn migrate_from(cpu_id: usize) {
+    let mut per_cpu = PER_CPU.write();
+    let Some(sched) = per_cpu.get_mut(cpu_id) else {
+        return;
+    };
+    let (runnable, deadline, sleeping) = sched.drain();
+    let moved = runnable.len() + deadline.len() + sleeping.len();
+
+    {
+        // The thread that was running there is runnable again
+        let mut threads = THREADS.write();
+        for thread_id in &runnable {
+            if let Some(thread) = threads.get_mut(thread_id) {
+                if thread.state == ThreadState::Running {
+                    thread.state = ThreadState::Ready;
+                }
+            }
+        }
+    }
+
+    for thread_id in runnable {
+        let target = least_loaded_online(&per_cpu);
+        per_cpu[target].enqueue(thread_id);
+    }
+    for entry in deadline {
+        let target = least_loaded_online(&per_cpu);
+        per_cpu[target].deadline_queue.enqueue(entry);
+    }
+    for entry in sleeping {
+        let target = least_loaded_online(&per_cpu);
+        per_cpu[target].add_to_timer_queue(entry.thread_id, entry.wake_tick);
+    }
+
+    if moved > 0 {
+        log::debug!("Moved {} threads off CPU {}", moved, cpu_id);
+    }
+}
+
+/// Pull threads onto a CPU that just came online until it carries its
+/// share of the load, stealing from the busiest CPUs first
+fn rebalance_onto(cpu_id: usize) {
+    let mut per_cpu = PER_CPU.write();
+
+    let online = per_cpu.iter().filter(|sched| sched.online).count();
+    if online <= 1 || cpu_id >= per_cpu.len() {
+        return;
+    }
+    let total: usize = per_cpu
+        .iter()
+        .filter(|sched| sched.online)
+        .map(CpuScheduler::queue_len)
+        .sum();
+    let share = total / online;
+
+    let mut stolen = 0;
+    while per_cpu[cpu_id].queue_len() < share {
+        let busiest = per_cpu
+            .iter()
+            .enumerate()
+            .filter(|(i, sched)| *i != cpu_id && sched.online)
+            .max_by_key(|(_, sched)| sched.queue_len())
+            .map(|(i, _)| i);
+        let Some(busiest) = busiest else {
+            break;
+        };
+        if per_cpu[busiest].queue_len() <= share {
+            break;
+        }
+        let Some(thread_id) = per_cpu[busiest].steal_thread() else {
+            break;
+        };
+        per_cpu[cpu_id].enqueue(thread_id);
+        stolen += 1;
+    }
+    drop(per_cpu);
+
+    if stolen > 0 {
+        log::debug!("CPU {} took {} threads after coming online", cpu_id, stolen);
+        crate::arch::x86_64::smp::send_ipi_to(cpu_id as u32, RESCHEDULE_IPI_VECTOR);
     }
 }
@@ -96,6 +96,8 @@ pub struct Thread {
     pub exit_code: i32,
     /// Thread we're waiting to join (if any)
     pub join_target: Option<ThreadId>,
+    /// FS segment base (thread pointer of Linux binaries)
+    pub fs_base: u64,
 
     // =========================================================================
     // Process Accounting
@@ -166,6 +168,7 @@ impl Thread {
             user_stack: 0,
             exit_code: 0,
             join_target: None,
+            fs_base: 0,
             // Accounting
             utime_ns: 0,
             stime_ns: 0,
@@ -201,6 +204,7 @@ impl Thread {
             user_stack: stack,
             exit_code: 0,
             join_target: None,
+            fs_base: 0,
             // Accounting
             utime_ns: 0,
             stime_ns: 0,
@@ -288,6 +292,7 @@ impl Thread {
             user_stack: 0,
             exit_code: 0,
             join_target: None,
+            fs_base: 0,
             // Accounting (kernel threads only accumulate stime)
             utime_ns: 0,
             stime_ns: 0,
@@ -729,6 +734,7 @@ mod tests {
             user_stack: 0,
             exit_code: 0,
             join_target: None,
+            fs_base: 0,
             utime_ns: 0,
             stime_ns: 0,
             user_start_ns: 0,
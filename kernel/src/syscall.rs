@@ -16,12 +16,14 @@ use crate::cap::{Capability, ObjectId, ObjectType, Rights};
 use crate::ipc;
 use crate::mem::user::{copy_from_user, copy_string_from_user, copy_to_user, UserMemError};
 use crate::mem::{VirtAddr, PAGE_SIZE};
-use crate::process::{ProcessId, SpawnArgs, SpawnError};
+use crate::process::{Personality, ProcessId, SpawnArgs, SpawnError};
 use crate::sched::{BlockReason, SchedClass, ThreadId, ThreadState};
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::time::Duration;
 
+pub(crate) mod compat;
+
 /// Maximum message size for IPC operations (4 KB)
 const MAX_IPC_MSG_SIZE: usize = 4096;
 
@@ -31,6 +33,9 @@ const MAX_PATH_LEN: usize = 4096;
 /// Maximum debug message length
 const MAX_DEBUG_MSG_LEN: usize = 1024;
 
+/// Maximum bytes returned by one klog read
+const MAX_KLOG_READ_LEN: usize = 64 * 1024;
+
 /// System call numbers
 #[repr(u64)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -52,6 +57,7 @@ pub enum Syscall {
     CapIdentify = 18,
     CapGrant = 19,
     CapDrop = 20,
+    NotifyCreate = 21,
 
     // Memory (32-63)
     MemMap = 32,
@@ -60,6 +66,12 @@ pub enum Syscall {
     MemAlloc = 35,
     MemFree = 36,
 
+    // Shared memory (40-47)
+    ShmCreate = 40,
+    ShmMap = 41,
+    ShmUnmap = 42,
+    ShmGrant = 43,
+
     // Threads (64-79)
     ThreadCreate = 64,
     ThreadExit = 65,
@@ -73,6 +85,7 @@ pub enum Syscall {
     ProcessWait = 82,
     ProcessGetPid = 83,
     ProcessGetPpid = 84,
+    ProcessStat = 85,
 
     // File system (96-111) - reserved for future vfs
     FsOpen = 96,
@@ -99,12 +112,27 @@ pub enum Syscall {
     // System (240-255)
     Debug = 240,
     GetTime = 241,
+    CpuGetOnline = 242,
+    CpuSetOnline = 243,
+    KlogRead = 244,
+    ClockGet = 245,
+    TimerCreate = 246,
+    TimerSet = 247,
+    TimerCancel = 248,
+    TimerRead = 249,
+    TimerDestroy = 250,
     Reboot = 254,
     Shutdown = 255,
 }
 
 /// System call handler (called from arch-specific entry)
 pub fn syscall_handler(regs: &mut SyscallRegs) {
+    // Linux binaries get the compat layer's numbering and errno values
+    if crate::process::current_personality() == Personality::Linux {
+        compat::syscall_handler(regs);
+        return;
+    }
+
     let syscall_num = regs.syscall_num;
 
     let result = match syscall_num {
@@ -125,6 +153,7 @@ pub fn syscall_handler(regs: &mut SyscallRegs) {
         18 => handle_cap_identify(regs),
         19 => handle_cap_grant(regs),
         20 => handle_cap_drop(regs),
+        21 => handle_notify_create(regs),
 
         // Memory syscalls
         32 => handle_mem_map(regs),
@@ -133,6 +162,12 @@ pub fn syscall_handler(regs: &mut SyscallRegs) {
         35 => handle_mem_alloc(regs),
         36 => handle_mem_free(regs),
 
+        // Shared memory
+        40 => handle_shm_create(regs),
+        41 => handle_shm_map(regs),
+        42 => handle_shm_unmap(regs),
+        43 => handle_shm_grant(regs),
+
         // Thread syscalls
         64 => handle_thread_create(regs),
         65 => handle_thread_exit(regs),
@@ -146,6 +181,7 @@ pub fn syscall_handler(regs: &mut SyscallRegs) {
         82 => handle_process_wait(regs),
         83 => handle_process_getpid(regs),
         84 => handle_process_getppid(regs),
+        85 => handle_process_stat(regs),
 
         // Filesystem syscalls
         96 => handle_fs_open(regs),
@@ -172,6 +208,15 @@ pub fn syscall_handler(regs: &mut SyscallRegs) {
         // System syscalls
         240 => handle_debug(regs),
         241 => handle_gettime(regs),
+        242 => handle_cpu_get_online(regs),
+        243 => handle_cpu_set_online(regs),
+        244 => handle_klog_read(regs),
+        245 => handle_clock_get(regs),
+        246 => handle_timer_create(regs),
+        247 => handle_timer_set(regs),
+        248 => handle_timer_cancel(regs),
+        249 => handle_timer_read(regs),
+        250 => handle_timer_destroy(regs),
 
         _ => Err(SyscallError::InvalidSyscall),
     };
@@ -193,6 +238,9 @@ pub struct SyscallRegs {
     pub arg4: u64,
     pub arg5: u64,
     pub result: i64,
+    /// User instruction pointer at entry, where a thread created by the
+    /// compat `clone` resumes
+    pub rip: u64,
 }
 
 /// Syscall errors
@@ -441,6 +489,16 @@ fn handle_cap_grant(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
     }
 }
 
+/// Create a notification object
+///
+/// Returns: notification ID
+fn handle_notify_create(_regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
+    match ipc::create_notification() {
+        Ok(cap) => Ok(cap.object_id.as_u64()),
+        Err(_) => Err(SyscallError::OutOfMemory),
+    }
+}
+
 fn handle_cap_drop(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
     let cap_id = regs.arg0;
 
@@ -477,7 +535,8 @@ fn handle_mem_map(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
     // Find suitable address
     let addr = if addr_hint == 0 {
         // Use the process's next available address
-        find_free_region(&proc_guard.address_space, length)?
+        let base = proc_guard.address_space.layout().mmap_base;
+        find_free_region(&proc_guard.address_space, base, length)?
     } else {
         VirtAddr::new(addr_hint)
     };
@@ -493,7 +552,7 @@ fn handle_mem_map(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
     proc_guard
         .address_space
         .map(addr, length, protection, backing)
-        .map_err(|_| SyscallError::OutOfMemory)?;
+        .map_err(vm_error)?;
 
     Ok(addr.as_u64())
 }
@@ -563,7 +622,7 @@ fn handle_mem_protect(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
             proc_guard
                 .address_space
                 .map_page(VirtAddr::new(current), phys, protection)
-                .map_err(|_| SyscallError::OutOfMemory)?;
+                .map_err(vm_error)?;
         }
         current = current.saturating_add(PAGE_SIZE);
     }
@@ -597,7 +656,8 @@ fn handle_mem_alloc(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
 
     // Find a free virtual address region for this allocation
     let aligned_size = (size + PAGE_SIZE - 1) & !(PAGE_SIZE - 1);
-    let virt_addr = find_free_region(&proc_guard.address_space, aligned_size)?;
+    let base = proc_guard.address_space.layout().heap_base;
+    let virt_addr = find_free_region(&proc_guard.address_space, base, aligned_size)?;
 
     // Determine protection (RW for allocated memory, user-accessible)
     let protection = crate::mem::virt::Protection::READ
@@ -658,10 +718,6 @@ fn handle_mem_alloc(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
         proc_guard.track_allocation(virt_addr, aligned_size, false);
     }
 
-    // Update memory statistics
-    proc_guard.mem_stats.vm_size += aligned_size;
-    proc_guard.mem_stats.rss += aligned_size;
-
     // Return the VIRTUAL address (not physical!) - this is the secure approach
     Ok(virt_addr.as_u64())
 }
@@ -718,30 +774,35 @@ fn handle_mem_free(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
     // Remove from allocation tracking
     proc_guard.untrack_allocation(virt_addr);
 
-    // Update memory statistics
-    proc_guard.mem_stats.vm_size = proc_guard.mem_stats.vm_size.saturating_sub(aligned_size);
-    proc_guard.mem_stats.rss = proc_guard.mem_stats.rss.saturating_sub(aligned_size);
-
     Ok(0)
 }
 
-/// Find a free region in the address space for the given size
+/// Map a VM error onto a syscall error
+fn vm_error(err: crate::mem::virt::VmError) -> SyscallError {
+    match err {
+        crate::mem::virt::VmError::WriteExecute => SyscallError::PermissionDenied,
+        _ => SyscallError::OutOfMemory,
+    }
+}
+
+/// Find a free region at or above `base` for the given size
 fn find_free_region(
     addr_space: &crate::mem::AddressSpace,
+    base: VirtAddr,
     size: u64,
 ) -> Result<VirtAddr, SyscallError> {
-    // Start searching from a reasonable base address
-    const USER_BASE: u64 = 0x0000_1000_0000_0000;
-    const USER_TOP: u64 = 0x0000_7FFF_0000_0000;
-
     let aligned_size = (size + PAGE_SIZE - 1) & !(PAGE_SIZE - 1);
-    let mut candidate = USER_BASE;
+    let mut candidate = base.as_u64();
 
     // Simple first-fit allocator - walk through VMAs to find a gap
     let mut regions: Vec<_> = addr_space.regions().collect();
     regions.sort_by_key(|vma| vma.start.as_u64());
 
     for vma in regions {
+        if vma.end.as_u64() <= candidate {
+            // Entirely below the search base
+            continue;
+        }
         if candidate + aligned_size <= vma.start.as_u64() {
             // Found a gap
             return Ok(VirtAddr::new(candidate));
@@ -751,13 +812,132 @@ fn find_free_region(
     }
 
     // Check if there's space after the last VMA
-    if candidate + aligned_size <= USER_TOP {
+    if candidate + aligned_size <= crate::mem::aslr::USER_TOP {
         return Ok(VirtAddr::new(candidate));
     }
 
     Err(SyscallError::OutOfMemory)
 }
 
+// ============================================================================
+// Shared Memory Syscall Handlers
+// ============================================================================
+
+/// Create a shared memory region
+///
+/// Args:
+/// - arg0: size in bytes
+/// - arg1: flags (`SharedFlags`)
+///
+/// Returns: region ID
+fn handle_shm_create(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
+    let size = regs.arg0;
+    let flags = ipc::SharedFlags::from_bits_truncate(regs.arg1 as u32);
+
+    if size == 0 || size > 1024 * 1024 * 1024 {
+        return Err(SyscallError::InvalidArgument);
+    }
+
+    match ipc::shm::create(size, flags) {
+        Ok(cap) => Ok(cap.object_id.as_u64()),
+        Err(_) => Err(SyscallError::OutOfMemory),
+    }
+}
+
+/// Map a shared memory region into the caller
+///
+/// Args:
+/// - arg0: region ID
+/// - arg1: address hint (0 = kernel chooses)
+/// - arg2: size in bytes, at most the region's size
+/// - arg3: protection (read 1, write 2, exec 4)
+///
+/// Returns: mapped address
+fn handle_shm_map(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
+    let region = ObjectId::from_raw(regs.arg0);
+    let addr_hint = regs.arg1;
+    let size = regs.arg2;
+    let protection = crate::mem::virt::Protection::from_bits_truncate(regs.arg3 as u8)
+        | crate::mem::virt::Protection::USER;
+
+    let region_size = ipc::shm::get_size(region).ok_or(SyscallError::InvalidCapability)?;
+    if size == 0 || size > region_size {
+        return Err(SyscallError::InvalidArgument);
+    }
+
+    let pid = crate::process::current_process_id().ok_or(SyscallError::InvalidCapability)?;
+    let mut proc_guard =
+        crate::process::get_process_mut(pid).ok_or(SyscallError::InvalidCapability)?;
+
+    let addr = if addr_hint == 0 {
+        let base = proc_guard.address_space.layout().mmap_base;
+        find_free_region(&proc_guard.address_space, base, size)?
+    } else {
+        VirtAddr::new(addr_hint)
+    };
+
+    // Pages are faulted in from the region's frames
+    proc_guard
+        .address_space
+        .map(addr, size, protection, crate::mem::virt::VmaBacking::Shared { region })
+        .map_err(vm_error)?;
+    ipc::shm::add_ref(region);
+
+    Ok(addr.as_u64())
+}
+
+/// Unmap a shared memory mapping
+///
+/// Args:
+/// - arg0: mapped address, as returned by `sys_shm_map`
+/// - arg1: size in bytes
+///
+/// Returns: 0
+fn handle_shm_unmap(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
+    let addr = VirtAddr::new(regs.arg0);
+    let size = regs.arg1;
+
+    let pid = crate::process::current_process_id().ok_or(SyscallError::InvalidCapability)?;
+    let mut proc_guard =
+        crate::process::get_process_mut(pid).ok_or(SyscallError::InvalidCapability)?;
+
+    let region = proc_guard
+        .address_space
+        .regions()
+        .find(|vma| vma.start == addr)
+        .and_then(|vma| match vma.backing {
+            crate::mem::virt::VmaBacking::Shared { region } => Some(region),
+            _ => None,
+        })
+        .ok_or(SyscallError::BadAddress)?;
+
+    proc_guard
+        .address_space
+        .unmap(addr, size)
+        .map_err(|_| SyscallError::BadAddress)?;
+    ipc::shm::release_ref(region);
+
+    Ok(0)
+}
+
+/// Grant another process access to a shared memory region
+///
+/// Args:
+/// - arg0: region ID
+/// - arg1: target process or endpoint capability
+/// - arg2: protection the target may map with
+///
+/// Returns: region ID for the target to map
+fn handle_shm_grant(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
+    let region = ObjectId::from_raw(regs.arg0);
+
+    // Regions are addressed by ID, like the other IPC objects; per-process
+    // rights arrive with CSpace-based lookup
+    ipc::shm::get_size(region).ok_or(SyscallError::InvalidCapability)?;
+
+    Ok(region.as_u64())
+}
+
 // ============================================================================
 // Thread Syscall Handlers
 // ============================================================================
@@ -984,12 +1164,40 @@ fn handle_thread_join(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
 // Process Syscall Handlers
 // ============================================================================
 
+/// Process spawn flags
+mod spawn_flags {
+    /// Lift W^X for the child; the low half of arg5 names a `JitMemory`
+    /// capability slot
+    pub const ALLOW_WX: u32 = 1 << 0;
+    /// Run the child through the Linux compat layer; the high half of arg5
+    /// names a `LinuxCompat` capability slot
+    pub const LINUX_COMPAT: u32 = 1 << 1;
+}
+
+/// Check that the current process holds a live capability of `kind` with
+/// `rights` in `slot`
+fn require_cap(slot: u32, kind: ObjectType, rights: Rights) -> Result<(), SyscallError> {
+    let pid = crate::process::current_process_id().ok_or(SyscallError::InvalidCapability)?;
+    let cap = crate::process::get_process(pid)
+        .and_then(|process| process.get_cap(slot).copied())
+        .ok_or(SyscallError::InvalidCapability)?;
+    if !crate::cap::is_object_valid(cap.object_id, cap.generation)
+        || crate::cap::object_type(cap.object_id) != Some(kind)
+    {
+        return Err(SyscallError::InvalidCapability);
+    }
+    if !cap.has_rights(rights) {
+        return Err(SyscallError::PermissionDenied);
+    }
+    Ok(())
+}
+
 fn handle_process_spawn(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
     let path_ptr = regs.arg0 as *const u8;
     let path_len = regs.arg1 as usize;
     let _args_ptr = regs.arg2 as *const u8;
     let _args_len = regs.arg3 as usize;
-    let _flags = regs.arg4 as u32;
+    let flags = regs.arg4 as u32;
 
     // Validate path length
     if path_len > MAX_PATH_LEN || path_len == 0 {
@@ -1013,6 +1221,20 @@ fn handle_process_spawn(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
         (0, 0)
     };
 
+    // JIT runtimes must prove they may have writable code
+    let allow_wx = flags & spawn_flags::ALLOW_WX != 0;
+    if allow_wx {
+        require_cap(regs.arg5 as u32, ObjectType::JitMemory, Rights::WRITE | Rights::EXECUTE)?;
+    }
+
+    // So must anything running Linux binaries
+    let personality = if flags & spawn_flags::LINUX_COMPAT != 0 {
+        require_cap((regs.arg5 >> 32) as u32, ObjectType::LinuxCompat, Rights::EXECUTE)?;
+        Personality::Linux
+    } else {
+        Personality::Nyx
+    };
+
     let args = SpawnArgs {
         path: path.clone(),
         args: alloc::vec![path],
@@ -1023,6 +1245,8 @@ fn handle_process_spawn(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
         cwd: Some(String::from("/")),
         uid,
         gid,
+        allow_wx,
+        personality,
     };
 
     match crate::process::spawn(args) {
@@ -1076,6 +1300,55 @@ fn handle_process_getppid(_regs: &mut SyscallRegs) -> Result<u64, SyscallError>
     Ok(proc.parent.map(|p| p.0).unwrap_or(0))
 }
 
+/// Read a process's memory usage
+///
+/// Args:
+/// - arg0: process ID, or 0 for the caller
+/// - arg1: buffer pointer; receives six u64s: mapped, resident, shared,
+///   text and data bytes, then the thread count
+/// - arg2: buffer length in bytes
+///
+/// Returns: bytes written
+fn handle_process_stat(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
+    const STAT_LEN: usize = 6 * core::mem::size_of::<u64>();
+
+    let buf_ptr = regs.arg1 as *mut u8;
+    if (regs.arg2 as usize) < STAT_LEN {
+        return Err(SyscallError::InvalidArgument);
+    }
+
+    let caller = crate::process::current_process_id().ok_or(SyscallError::InvalidCapability)?;
+    let pid = if regs.arg0 == 0 { caller } else { ProcessId(regs.arg0) };
+    let caller_uid = crate::process::get_process(caller)
+        .map(|proc| proc.uid)
+        .ok_or(SyscallError::InvalidCapability)?;
+
+    // Take the guard: a cloned Process carries a fresh address space, so
+    // its page counts would read zero
+    let words = {
+        let proc = crate::process::get_process_mut(pid).ok_or(SyscallError::NotFound)?;
+
+        // Other users' processes are only visible to root
+        if pid != caller && caller_uid != 0 && caller_uid != proc.uid {
+            return Err(SyscallError::PermissionDenied);
+        }
+
+        let stats = proc.memory_stats();
+        [
+            stats.vm_size,
+            stats.rss,
+            stats.shared,
+            stats.text,
+            stats.data,
+            proc.threads.len() as u64,
+        ]
+    };
+    let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_ne_bytes()).collect();
+    copy_to_user(buf_ptr, &bytes)?;
+
+    Ok(STAT_LEN as u64)
+}
+
 // ============================================================================
 // Tensor/AI Syscall Handlers
 // ============================================================================
@@ -1084,6 +1357,8 @@ fn handle_tensor_alloc(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
     let size = regs.arg0;
     let device_type = regs.arg1 as u32;
     let alignment = regs.arg2;
+    let placement =
+        crate::tensor::Placement::from_raw(regs.arg3).ok_or(SyscallError::InvalidArgument)?;
 
     // Validate size (max 16 GB for single tensor)
     const MAX_TENSOR_SIZE: u64 = 16 * 1024 * 1024 * 1024;
@@ -1096,8 +1371,9 @@ fn handle_tensor_alloc(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
         return Err(SyscallError::InvalidArgument);
     }
 
-    match crate::tensor::allocate_buffer(size, device_type, alignment) {
+    match crate::tensor::allocate_buffer(size, device_type, alignment, placement) {
         Ok((buffer_id, _phys_addr)) => Ok(buffer_id),
+        Err(crate::tensor::TensorError::InvalidPlacement) => Err(SyscallError::InvalidArgument),
         Err(_) => Err(SyscallError::OutOfMemory),
     }
 }
@@ -1643,6 +1919,203 @@ fn handle_gettime(_regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
     Ok(crate::now_ns())
 }
 
+/// Read the online CPU mask
+///
+/// Args:
+/// - arg0: buffer pointer (u64 words, bit N set when CPU N is online)
+/// - arg1: buffer length in bytes
+///
+/// Returns: number of CPUs present, online or not
+fn handle_cpu_get_online(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
+    let buf_ptr = regs.arg0 as *mut u8;
+    let buf_len = regs.arg1 as usize;
+
+    let possible = crate::arch::x86_64::smp::possible_cpus();
+    let needed = (possible as usize).div_ceil(64) * core::mem::size_of::<u64>();
+    if buf_len < needed {
+        return Err(SyscallError::InvalidArgument);
+    }
+
+    let bytes: Vec<u8> = crate::arch::x86_64::smp::online_mask()
+        .iter()
+        .flat_map(|word| word.to_ne_bytes())
+        .take(needed)
+        .collect();
+    copy_to_user(buf_ptr, &bytes)?;
+
+    Ok(possible as u64)
+}
+
+/// Take a CPU offline or bring it back
+///
+/// Args:
+/// - arg0: capability slot of a scheduler context with SCHEDULE rights
+/// - arg1: CPU ID
+/// - arg2: 1 to bring online, 0 to take offline
+///
+/// Returns: 0 on success or negative error
+fn handle_cpu_set_online(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
+    let cap_slot = regs.arg0 as u32;
+    let cpu_id = regs.arg1 as u32;
+    let online = match regs.arg2 {
+        0 => false,
+        1 => true,
+        _ => return Err(SyscallError::InvalidArgument),
+    };
+
+    // Changing the CPU set affects every process, so it takes a scheduler
+    // capability rather than just a running process
+    let pid = crate::process::current_process_id().ok_or(SyscallError::InvalidCapability)?;
+    let cap = crate::process::get_process(pid)
+        .and_then(|process| process.get_cap(cap_slot).copied())
+        .ok_or(SyscallError::InvalidCapability)?;
+    if !crate::cap::is_object_valid(cap.object_id, cap.generation)
+        || crate::cap::object_type(cap.object_id) != Some(ObjectType::SchedulerContext)
+    {
+        return Err(SyscallError::InvalidCapability);
+    }
+    if !cap.has_rights(Rights::SCHEDULE) {
+        return Err(SyscallError::PermissionDenied);
+    }
+
+    let result = if online {
+        crate::sched::cpu_online(cpu_id)
+    } else {
+        crate::sched::cpu_offline(cpu_id)
+    };
+
+    match result {
+        Ok(()) => Ok(0),
+        Err(crate::arch::HotplugError::InvalidCpu) => Err(SyscallError::NotFound),
+        Err(crate::arch::HotplugError::BootCpu) => Err(SyscallError::PermissionDenied),
+        Err(crate::arch::HotplugError::AlreadyOnline | crate::arch::HotplugError::AlreadyOffline) => {
+            Err(SyscallError::InvalidArgument)
+        }
+        Err(crate::arch::HotplugError::StartFailed) => Err(SyscallError::IoError),
+    }
+}
+
+/// Read kernel log records
+///
+/// Args:
+/// - arg0: capability slot of the kernel log with READ rights
+/// - arg1: sequence number of the first record wanted
+/// - arg2: buffer pointer
+/// - arg3: buffer length in bytes
+///
+/// Returns: bytes written, as whole `/dev/kmsg`-style lines; 0 when no
+/// record at or after the sequence number exists yet
+fn handle_klog_read(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
+    let cap_slot = regs.arg0 as u32;
+    let from_seq = regs.arg1;
+    let buf_ptr = regs.arg2 as *mut u8;
+    let buf_len = (regs.arg3 as usize).min(MAX_KLOG_READ_LEN);
+
+    let pid = crate::process::current_process_id().ok_or(SyscallError::InvalidCapability)?;
+    let cap = crate::process::get_process(pid)
+        .and_then(|process| process.get_cap(cap_slot).copied())
+        .ok_or(SyscallError::InvalidCapability)?;
+    if !crate::cap::is_object_valid(cap.object_id, cap.generation)
+        || crate::cap::object_type(cap.object_id) != Some(ObjectType::KernelLog)
+    {
+        return Err(SyscallError::InvalidCapability);
+    }
+    if !cap.has_rights(Rights::READ) {
+        return Err(SyscallError::PermissionDenied);
+    }
+
+    let mut buf = alloc::vec![0u8; buf_len];
+    let written = crate::klog::read(from_seq, &mut buf);
+    if written == 0 && from_seq < crate::klog::next_seq() {
+        // The next record doesn't fit
+        return Err(SyscallError::InvalidArgument);
+    }
+    copy_to_user(buf_ptr, &buf[..written])?;
+
+    Ok(written as u64)
+}
+
+/// Convert timer errors to syscall errors
+impl From<crate::time::timer::TimerError> for SyscallError {
+    fn from(err: crate::time::timer::TimerError) -> Self {
+        use crate::time::timer::TimerError;
+        match err {
+            TimerError::NotFound => SyscallError::InvalidCapability,
+            TimerError::InvalidNotification => SyscallError::InvalidCapability,
+            TimerError::InvalidArgument => SyscallError::InvalidArgument,
+        }
+    }
+}
+
+/// Read a clock
+///
+/// Args:
+/// - arg0: clock (0 = monotonic, 1 = boottime)
+///
+/// Returns: nanoseconds since boot on that clock
+fn handle_clock_get(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
+    let clock = crate::time::Clock::from_raw(regs.arg0).ok_or(SyscallError::InvalidArgument)?;
+    Ok(crate::time::clock_ns(clock))
+}
+
+/// Create a timer
+///
+/// Args:
+/// - arg0: clock (0 = monotonic, 1 = boottime)
+/// - arg1: notification to signal on expiry
+/// - arg2: bits to signal
+///
+/// Returns: timer ID (the timer starts disarmed)
+fn handle_timer_create(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
+    let clock = crate::time::Clock::from_raw(regs.arg0).ok_or(SyscallError::InvalidArgument)?;
+    let notification = ObjectId::from_raw(regs.arg1);
+    let bits = regs.arg2;
+
+    let cap = crate::time::timer::create(clock, notification, bits)?;
+    Ok(cap.object_id.as_u64())
+}
+
+/// Arm or disarm a timer
+///
+/// Args:
+/// - arg0: timer ID
+/// - arg1: first expiry in nanoseconds (0 disarms)
+/// - arg2: reload interval in nanoseconds (0 = one-shot)
+/// - arg3: flags (bit 0: arg1 is absolute on the timer's clock)
+fn handle_timer_set(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
+    let timer = ObjectId::from_raw(regs.arg0);
+    crate::time::timer::set(timer, regs.arg1, regs.arg2, regs.arg3 as u32)?;
+    Ok(0)
+}
+
+/// Disarm a timer
+///
+/// Args:
+/// - arg0: timer ID
+fn handle_timer_cancel(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
+    crate::time::timer::cancel(ObjectId::from_raw(regs.arg0))?;
+    Ok(0)
+}
+
+/// Take a timer's expiration count
+///
+/// Args:
+/// - arg0: timer ID
+///
+/// Returns: expirations since the last read
+fn handle_timer_read(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
+    Ok(crate::time::timer::read(ObjectId::from_raw(regs.arg0))?)
+}
+
+/// Destroy a timer
+///
+/// Args:
+/// - arg0: timer ID
+fn handle_timer_destroy(regs: &mut SyscallRegs) -> Result<u64, SyscallError> {
+    crate::time::timer::destroy(ObjectId::from_raw(regs.arg0))?;
+    Ok(0)
+}
+
 // ============================================================================
 // Time-Travel Syscall Handlers
 // ============================================================================
@@ -0,0 +1,1208 @@
+//! Linux compatibility layer
+//!
+//! Lets static Linux x86_64 binaries run while Nyx userspace is still being
+//! written. A process spawned with the Linux personality (which takes a
+//! `LinuxCompat` capability) has its system calls routed here instead of
+//! the native table. Each call is translated onto Nyx objects: files go
+//! through the VFS, memory through the process address space, threads
+//! through the scheduler. Errors come back as negated Linux errno values.
+//!
+//! The subset is what a static libc needs to start, do file I/O, allocate
+//! and run threads:
+//!
+//! - File descriptors 0-2 are the console. Reads see end of file, and
+//!   writes go to the kernel log.
+//! - Other descriptors are VFS files. The VFS is read-only for now, so
+//!   anything opened for writing fails with `EROFS`.
+//! - `mmap` covers anonymous memory only. `brk` grows a heap from the end
+//!   of the image.
+//! - `clone` creates threads (`CLONE_VM | CLONE_THREAD`), but not
+//!   processes.
+//! - Futexes are private to the process. Waits end on a wake or when
+//!   their timeout expires.
+//! - Signal calls are accepted so libc can start, but handlers are never
+//!   run.
+//!
+//! Anything else fails with `ENOSYS`.
+
+use super::{SyscallError, SyscallRegs};
+use crate::mem::user::{
+    copy_from_user, copy_to_user, copy_value_from_user, copy_value_to_user, UserMemError,
+};
+use crate::mem::virt::{Protection, VmaBacking};
+use crate::mem::{VirtAddr, PAGE_SIZE};
+use crate::process::{LoadedImage, Process, ProcessId, SpawnError};
+use crate::sched::{BlockReason, ThreadId, ThreadState};
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
+use alloc::vec::Vec;
+use spin::{Mutex, RwLock};
+
+/// Linux error number, returned negated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Errno(pub i64);
+
+impl Errno {
+    pub const EPERM: Self = Self(1);
+    pub const ENOENT: Self = Self(2);
+    pub const ESRCH: Self = Self(3);
+    pub const EINTR: Self = Self(4);
+    pub const EIO: Self = Self(5);
+    pub const EBADF: Self = Self(9);
+    pub const ECHILD: Self = Self(10);
+    pub const EAGAIN: Self = Self(11);
+    pub const ENOMEM: Self = Self(12);
+    pub const EACCES: Self = Self(13);
+    pub const EFAULT: Self = Self(14);
+    pub const EEXIST: Self = Self(17);
+    pub const ENODEV: Self = Self(19);
+    pub const ENOTDIR: Self = Self(20);
+    pub const EISDIR: Self = Self(21);
+    pub const EINVAL: Self = Self(22);
+    pub const EMFILE: Self = Self(24);
+    pub const ENOTTY: Self = Self(25);
+    pub const ENOSPC: Self = Self(28);
+    pub const ESPIPE: Self = Self(29);
+    pub const EROFS: Self = Self(30);
+    pub const ENOSYS: Self = Self(38);
+    pub const ETIMEDOUT: Self = Self(110);
+}
+
+impl From<UserMemError> for Errno {
+    fn from(err: UserMemError) -> Self {
+        match err {
+            UserMemError::SizeTooLarge => Errno::EINVAL,
+            _ => Errno::EFAULT,
+        }
+    }
+}
+
+impl From<SyscallError> for Errno {
+    fn from(err: SyscallError) -> Self {
+        match err {
+            SyscallError::Success => Errno(0),
+            SyscallError::InvalidSyscall => Errno::ENOSYS,
+            SyscallError::InvalidCapability => Errno::EBADF,
+            SyscallError::PermissionDenied => Errno::EACCES,
+            SyscallError::OutOfMemory => Errno::ENOMEM,
+            SyscallError::InvalidArgument => Errno::EINVAL,
+            SyscallError::WouldBlock => Errno::EAGAIN,
+            SyscallError::Timeout => Errno::ETIMEDOUT,
+            SyscallError::Interrupted => Errno::EINTR,
+            SyscallError::NotFound => Errno::ENOENT,
+            SyscallError::InvalidFormat => Errno::EINVAL,
+            SyscallError::IoError => Errno::EIO,
+            SyscallError::TooManyProcesses => Errno::EAGAIN,
+            SyscallError::NoChild => Errno::ECHILD,
+            SyscallError::BadAddress => Errno::EFAULT,
+        }
+    }
+}
+
+impl From<crate::fs::FsError> for Errno {
+    fn from(err: crate::fs::FsError) -> Self {
+        use crate::fs::FsError;
+        match err {
+            FsError::NotFound | FsError::NotMounted => Errno::ENOENT,
+            FsError::PermissionDenied => Errno::EACCES,
+            FsError::IsDirectory => Errno::EISDIR,
+            FsError::NotDirectory => Errno::ENOTDIR,
+            FsError::Exists => Errno::EEXIST,
+            FsError::ReadOnly => Errno::EROFS,
+            FsError::NoSpace => Errno::ENOSPC,
+            FsError::InvalidArgument => Errno::EINVAL,
+            FsError::IoError => Errno::EIO,
+            FsError::NotImplemented => Errno::ENOSYS,
+        }
+    }
+}
+
+type LinuxResult = Result<u64, Errno>;
+
+/// Linux x86_64 system call numbers handled here
+mod nr {
+    pub const READ: u64 = 0;
+    pub const WRITE: u64 = 1;
+    pub const OPEN: u64 = 2;
+    pub const CLOSE: u64 = 3;
+    pub const STAT: u64 = 4;
+    pub const FSTAT: u64 = 5;
+    pub const LSTAT: u64 = 6;
+    pub const LSEEK: u64 = 8;
+    pub const MMAP: u64 = 9;
+    pub const MPROTECT: u64 = 10;
+    pub const MUNMAP: u64 = 11;
+    pub const BRK: u64 = 12;
+    pub const RT_SIGACTION: u64 = 13;
+    pub const RT_SIGPROCMASK: u64 = 14;
+    pub const IOCTL: u64 = 16;
+    pub const READV: u64 = 19;
+    pub const WRITEV: u64 = 20;
+    pub const SCHED_YIELD: u64 = 24;
+    pub const NANOSLEEP: u64 = 35;
+    pub const GETPID: u64 = 39;
+    pub const CLONE: u64 = 56;
+    pub const EXIT: u64 = 60;
+    pub const UNAME: u64 = 63;
+    pub const GETUID: u64 = 102;
+    pub const GETGID: u64 = 104;
+    pub const GETEUID: u64 = 107;
+    pub const GETEGID: u64 = 108;
+    pub const GETPPID: u64 = 110;
+    pub const SIGALTSTACK: u64 = 131;
+    pub const ARCH_PRCTL: u64 = 158;
+    pub const GETTID: u64 = 186;
+    pub const FUTEX: u64 = 202;
+    pub const SET_TID_ADDRESS: u64 = 218;
+    pub const CLOCK_GETTIME: u64 = 228;
+    pub const EXIT_GROUP: u64 = 231;
+    pub const OPENAT: u64 = 257;
+    pub const NEWFSTATAT: u64 = 262;
+    pub const GETRANDOM: u64 = 318;
+}
+
+/// Largest single read or write
+const MAX_IO: usize = 1024 * 1024;
+
+/// Longest path accepted
+const PATH_MAX: usize = 4096;
+
+/// Most descriptors a process may hold
+const MAX_FDS: i32 = 1024;
+
+/// Most entries in one `readv`/`writev`
+const IOV_MAX: u64 = 1024;
+
+/// `openat` directory meaning the working directory
+const AT_FDCWD: i64 = -100;
+
+/// `newfstatat` flag: stat the descriptor itself
+const AT_EMPTY_PATH: u64 = 0x1000;
+
+/// Timer ticks per second (the scheduler runs at 100 Hz)
+const TICKS_PER_SEC: u64 = 100;
+
+/// An open descriptor
+enum LinuxFile {
+    /// The console: stdin, stdout and stderr
+    Console,
+    /// A VFS file
+    File(crate::fs::FileHandle),
+}
+
+/// Compat state of a Linux process
+struct LinuxProcess {
+    /// Process name, for console output
+    name: String,
+    /// Open descriptors
+    files: BTreeMap<i32, LinuxFile>,
+    /// Working directory, for relative paths
+    cwd: String,
+    /// Start of the heap, just past the image
+    brk_start: u64,
+    /// Current program break
+    brk: u64,
+    /// Per-thread `clear_child_tid` addresses
+    clear_tid: BTreeMap<ThreadId, u64>,
+}
+
+impl LinuxProcess {
+    /// Lowest free descriptor
+    fn alloc_fd(&self) -> Result<i32, Errno> {
+        (0..MAX_FDS)
+            .find(|fd| !self.files.contains_key(fd))
+            .ok_or(Errno::EMFILE)
+    }
+}
+
+/// Compat state of every Linux process
+static LINUX: RwLock<BTreeMap<ProcessId, LinuxProcess>> = RwLock::new(BTreeMap::new());
+
+/// Threads waiting on each futex word
+static FUTEXES: Mutex<BTreeMap<(ProcessId, u64), VecDeque<ThreadId>>> =
+    Mutex::new(BTreeMap::new());
+
+/// Set up compat state for a newly loaded Linux process
+pub fn attach(proc: &Process, image_end: u64) {
+    let mut files = BTreeMap::new();
+    for fd in 0..3 {
+        files.insert(fd, LinuxFile::Console);
+    }
+
+    LINUX.write().insert(
+        proc.pid,
+        LinuxProcess {
+            name: proc.name.clone(),
+            files,
+            cwd: String::from("/"),
+            brk_start: image_end,
+            brk: image_end,
+            clear_tid: BTreeMap::new(),
+        },
+    );
+}
+
+/// Drop a process's compat state when it exits
+pub fn release(pid: ProcessId) {
+    if LINUX.write().remove(&pid).is_some() {
+        FUTEXES.lock().retain(|(owner, _), _| *owner != pid);
+    }
+}
+
+/// Run `f` on the current process's compat state
+fn with_current<R>(f: impl FnOnce(&mut LinuxProcess) -> Result<R, Errno>) -> Result<R, Errno> {
+    let pid = crate::process::current_process_id().ok_or(Errno::ESRCH)?;
+    let mut linux = LINUX.write();
+    let state = linux.get_mut(&pid).ok_or(Errno::ESRCH)?;
+    f(state)
+}
+
+/// System call entry for Linux processes
+pub fn syscall_handler(regs: &mut SyscallRegs) {
+    let result = match regs.syscall_num {
+        nr::READ => sys_read(regs),
+        nr::WRITE => sys_write(regs),
+        nr::OPEN => sys_openat(AT_FDCWD, regs.arg0, regs.arg1),
+        nr::CLOSE => sys_close(regs),
+        nr::STAT | nr::LSTAT => sys_stat(regs),
+        nr::FSTAT => sys_fstat(regs.arg0 as i32, regs.arg1),
+        nr::LSEEK => sys_lseek(regs),
+        nr::MMAP => sys_mmap(regs),
+        nr::MPROTECT => sys_mprotect(regs),
+        nr::MUNMAP => super::handle_mem_unmap(regs).map_err(Errno::from),
+        nr::BRK => sys_brk(regs),
+        nr::RT_SIGACTION => sys_rt_sigaction(regs),
+        nr::RT_SIGPROCMASK => sys_rt_sigprocmask(regs),
+        nr::SIGALTSTACK => Ok(0),
+        nr::IOCTL => sys_ioctl(regs),
+        nr::READV => sys_readv(regs),
+        nr::WRITEV => sys_writev(regs),
+        nr::SCHED_YIELD => {
+            crate::sched::yield_now();
+            Ok(0)
+        }
+        nr::NANOSLEEP => sys_nanosleep(regs),
+        nr::GETPID => crate::process::current_process_id()
+            .map(|pid| pid.0)
+            .ok_or(Errno::ESRCH),
+        nr::GETPPID => super::handle_process_getppid(regs).map_err(Errno::from),
+        nr::GETTID => Ok(crate::sched::current_thread_id().0),
+        nr::GETUID | nr::GETEUID => current_ids().map(|(uid, _)| uid as u64),
+        nr::GETGID | nr::GETEGID => current_ids().map(|(_, gid)| gid as u64),
+        nr::CLONE => sys_clone(regs),
+        nr::EXIT => sys_exit(regs),
+        nr::EXIT_GROUP => {
+            crate::process::exit(regs.arg0 as i32);
+            Ok(0)
+        }
+        nr::UNAME => sys_uname(regs),
+        nr::ARCH_PRCTL => sys_arch_prctl(regs),
+        nr::FUTEX => sys_futex(regs),
+        nr::SET_TID_ADDRESS => sys_set_tid_address(regs),
+        nr::CLOCK_GETTIME => sys_clock_gettime(regs),
+        nr::OPENAT => sys_openat(regs.arg0 as i64, regs.arg1, regs.arg2),
+        nr::NEWFSTATAT => sys_newfstatat(regs),
+        nr::GETRANDOM => sys_getrandom(regs),
+        other => {
+            log::debug!(
+                "linux: unimplemented syscall {} in PID {:?}",
+                other,
+                crate::process::current_process_id()
+            );
+            Err(Errno::ENOSYS)
+        }
+    };
+
+    regs.result = match result {
+        Ok(val) => val as i64,
+        Err(errno) => -errno.0,
+    };
+}
+
+// ============================================================================
+// Files
+// ============================================================================
+
+fn sys_read(regs: &mut SyscallRegs) -> LinuxResult {
+    let fd = regs.arg0 as i32;
+    let buf = regs.arg1 as *mut u8;
+    let len = (regs.arg2 as usize).min(MAX_IO);
+    read_fd(fd, buf, len)
+}
+
+fn read_fd(fd: i32, buf: *mut u8, len: usize) -> LinuxResult {
+    let data = with_current(|state| match state.files.get_mut(&fd) {
+        // Nothing feeds the console yet
+        Some(LinuxFile::Console) => Ok(Vec::new()),
+        Some(LinuxFile::File(handle)) => {
+            let mut data = alloc::vec![0u8; len];
+            let read = crate::fs::read(handle, &mut data)?;
+            data.truncate(read);
+            Ok(data)
+        }
+        None => Err(Errno::EBADF),
+    })?;
+
+    copy_to_user(buf, &data)?;
+    Ok(data.len() as u64)
+}
+
+fn sys_write(regs: &mut SyscallRegs) -> LinuxResult {
+    let fd = regs.arg0 as i32;
+    let buf = regs.arg1 as *const u8;
+    let len = (regs.arg2 as usize).min(MAX_IO);
+    write_fd(fd, buf, len)
+}
+
+fn write_fd(fd: i32, buf: *const u8, len: usize) -> LinuxResult {
+    let data = copy_from_user(buf, len)?;
+    with_current(|state| match state.files.get(&fd) {
+        Some(LinuxFile::Console) => {
+            let text = String::from_utf8_lossy(&data);
+            for line in text.lines() {
+                log::info!("[{}] {}", state.name, line);
+            }
+            Ok(data.len() as u64)
+        }
+        // Only read-only files can be opened
+        Some(LinuxFile::File(_)) => Err(Errno::EBADF),
+        None => Err(Errno::EBADF),
+    })
+}
+
+/// A `struct iovec`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IoVec {
+    base: u64,
+    len: u64,
+}
+
+fn read_iovecs(ptr: u64, count: u64) -> Result<Vec<IoVec>, Errno> {
+    if count > IOV_MAX {
+        return Err(Errno::EINVAL);
+    }
+    (0..count)
+        .map(|i| {
+            let iov = (ptr + i * core::mem::size_of::<IoVec>() as u64) as *const IoVec;
+            copy_value_from_user(iov).map_err(Errno::from)
+        })
+        .collect()
+}
+
+fn sys_readv(regs: &mut SyscallRegs) -> LinuxResult {
+    let fd = regs.arg0 as i32;
+    let mut total = 0;
+    for iov in read_iovecs(regs.arg1, regs.arg2)? {
+        let want = (iov.len as usize).min(MAX_IO);
+        let read = read_fd(fd, iov.base as *mut u8, want)?;
+        total += read;
+        if (read as usize) < want {
+            break;
+        }
+    }
+    Ok(total)
+}
+
+fn sys_writev(regs: &mut SyscallRegs) -> LinuxResult {
+    let fd = regs.arg0 as i32;
+    let mut total = 0;
+    for iov in read_iovecs(regs.arg1, regs.arg2)? {
+        if iov.len > 0 {
+            total += write_fd(fd, iov.base as *const u8, (iov.len as usize).min(MAX_IO))?;
+        }
+    }
+    Ok(total)
+}
+
+/// Linux `open` flags
+mod open_flags {
+    pub const O_ACCMODE: u64 = 0o3;
+    pub const O_WRONLY: u64 = 0o1;
+    pub const O_RDWR: u64 = 0o2;
+    pub const O_CREAT: u64 = 0o100;
+    pub const O_EXCL: u64 = 0o200;
+    pub const O_TRUNC: u64 = 0o1000;
+    pub const O_APPEND: u64 = 0o2000;
+    pub const O_NONBLOCK: u64 = 0o4000;
+    pub const O_DIRECTORY: u64 = 0o200000;
+}
+
+fn sys_openat(dirfd: i64, path: u64, flags: u64) -> LinuxResult {
+    use crate::fs::OpenFlags;
+    use open_flags::*;
+
+    let path = copy_cstr_from_user(path as *const u8)?;
+    if path.is_empty() {
+        return Err(Errno::ENOENT);
+    }
+
+    let mut open = match flags & O_ACCMODE {
+        O_WRONLY => OpenFlags::WRITE,
+        O_RDWR => OpenFlags::READ | OpenFlags::WRITE,
+        _ => OpenFlags::READ,
+    };
+    for (linux, nyx) in [
+        (O_CREAT, OpenFlags::CREATE),
+        (O_EXCL, OpenFlags::EXCL),
+        (O_TRUNC, OpenFlags::TRUNCATE),
+        (O_APPEND, OpenFlags::APPEND),
+        (O_NONBLOCK, OpenFlags::NONBLOCK),
+        (O_DIRECTORY, OpenFlags::DIRECTORY),
+    ] {
+        if flags & linux != 0 {
+            open |= nyx;
+        }
+    }
+
+    with_current(|state| {
+        let path = resolve(state, dirfd, &path)?;
+        let handle = crate::fs::open(&path, open)?;
+        let fd = state.alloc_fd()?;
+        state.files.insert(fd, LinuxFile::File(handle));
+        Ok(fd as u64)
+    })
+}
+
+/// Absolute path of `path`, relative to `dirfd` or the working directory
+fn resolve(state: &LinuxProcess, dirfd: i64, path: &str) -> Result<String, Errno> {
+    if path.starts_with('/') {
+        return Ok(String::from(path));
+    }
+
+    let base = if dirfd == AT_FDCWD {
+        state.cwd.as_str()
+    } else {
+        match state.files.get(&(dirfd as i32)) {
+            Some(LinuxFile::File(handle)) => {
+                if handle.stat.file_type != crate::fs::FileType::Directory {
+                    return Err(Errno::ENOTDIR);
+                }
+                handle.path.as_str()
+            }
+            Some(LinuxFile::Console) => return Err(Errno::ENOTDIR),
+            None => return Err(Errno::EBADF),
+        }
+    };
+    Ok(alloc::format!("{}/{}", base.trim_end_matches('/'), path))
+}
+
+fn sys_close(regs: &mut SyscallRegs) -> LinuxResult {
+    let fd = regs.arg0 as i32;
+    with_current(|state| state.files.remove(&fd).map(|_| 0).ok_or(Errno::EBADF))
+}
+
+fn sys_lseek(regs: &mut SyscallRegs) -> LinuxResult {
+    let fd = regs.arg0 as i32;
+    let offset = regs.arg1 as i64;
+    let whence = match regs.arg2 {
+        0 => crate::fs::SeekFrom::Start,
+        1 => crate::fs::SeekFrom::Current,
+        2 => crate::fs::SeekFrom::End,
+        _ => return Err(Errno::EINVAL),
+    };
+
+    with_current(|state| match state.files.get_mut(&fd) {
+        Some(LinuxFile::File(handle)) => Ok(crate::fs::seek(handle, offset, whence)?),
+        Some(LinuxFile::Console) => Err(Errno::ESPIPE),
+        None => Err(Errno::EBADF),
+    })
+}
+
+fn sys_ioctl(regs: &mut SyscallRegs) -> LinuxResult {
+    const TIOCGWINSZ: u64 = 0x5413;
+
+    let fd = regs.arg0 as i32;
+    let console = with_current(|state| match state.files.get(&fd) {
+        Some(LinuxFile::Console) => Ok(true),
+        Some(LinuxFile::File(_)) => Ok(false),
+        None => Err(Errno::EBADF),
+    })?;
+
+    // Enough terminal for libc to line-buffer the console
+    if console && regs.arg1 == TIOCGWINSZ {
+        let winsize: [u16; 4] = [25, 80, 0, 0];
+        copy_value_to_user(regs.arg2 as *mut [u16; 4], winsize)?;
+        return Ok(0);
+    }
+    Err(Errno::ENOTTY)
+}
+
+/// Linux `struct stat` on x86_64
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct LinuxStat {
+    dev: u64,
+    ino: u64,
+    nlink: u64,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    _pad0: u32,
+    rdev: u64,
+    size: i64,
+    blksize: i64,
+    blocks: i64,
+    atime: i64,
+    atime_nsec: i64,
+    mtime: i64,
+    mtime_nsec: i64,
+    ctime: i64,
+    ctime_nsec: i64,
+    _reserved: [i64; 3],
+}
+
+impl From<&crate::fs::FileStat> for LinuxStat {
+    fn from(stat: &crate::fs::FileStat) -> Self {
+        use crate::fs::FileType;
+        let kind = match stat.file_type {
+            FileType::Regular => 0o100000,
+            FileType::Directory => 0o040000,
+            FileType::Symlink => 0o120000,
+            FileType::CharDevice => 0o020000,
+            FileType::BlockDevice => 0o060000,
+            FileType::Fifo => 0o010000,
+            FileType::Socket => 0o140000,
+        };
+        Self {
+            dev: stat.dev,
+            ino: stat.ino,
+            nlink: stat.nlink,
+            mode: kind | (stat.mode & 0o7777),
+            uid: stat.uid,
+            gid: stat.gid,
+            size: stat.size as i64,
+            blksize: PAGE_SIZE as i64,
+            blocks: stat.size.div_ceil(512) as i64,
+            atime: stat.atime as i64,
+            mtime: stat.mtime as i64,
+            ctime: stat.ctime as i64,
+            ..Self::default()
+        }
+    }
+}
+
+/// What `fstat` reports for the console
+fn console_stat() -> LinuxStat {
+    LinuxStat {
+        nlink: 1,
+        mode: 0o020620,
+        rdev: 0x0501,
+        blksize: 1024,
+        ..LinuxStat::default()
+    }
+}
+
+fn sys_stat(regs: &mut SyscallRegs) -> LinuxResult {
+    stat_path(AT_FDCWD, regs.arg0, regs.arg1)
+}
+
+fn sys_fstat(fd: i32, buf: u64) -> LinuxResult {
+    let stat = with_current(|state| match state.files.get(&fd) {
+        Some(LinuxFile::File(handle)) => Ok(LinuxStat::from(&handle.stat)),
+        Some(LinuxFile::Console) => Ok(console_stat()),
+        None => Err(Errno::EBADF),
+    })?;
+    copy_value_to_user(buf as *mut LinuxStat, stat)?;
+    Ok(0)
+}
+
+fn sys_newfstatat(regs: &mut SyscallRegs) -> LinuxResult {
+    let dirfd = regs.arg0 as i64;
+    if regs.arg3 & AT_EMPTY_PATH != 0 && copy_value_from_user(regs.arg1 as *const u8)? == 0 {
+        return sys_fstat(dirfd as i32, regs.arg2);
+    }
+    stat_path(dirfd, regs.arg1, regs.arg2)
+}
+
+fn stat_path(dirfd: i64, path: u64, buf: u64) -> LinuxResult {
+    let path = copy_cstr_from_user(path as *const u8)?;
+    if path.is_empty() {
+        return Err(Errno::ENOENT);
+    }
+    let path = with_current(|state| resolve(state, dirfd, &path))?;
+    let stat = crate::fs::stat(&path)?;
+    copy_value_to_user(buf as *mut LinuxStat, LinuxStat::from(&stat))?;
+    Ok(0)
+}
+
+/// Copy a NUL-terminated string in, a page at a time so the read never
+/// runs past the end of the mapping holding it
+fn copy_cstr_from_user(ptr: *const u8) -> Result<String, Errno> {
+    let mut bytes = Vec::new();
+    let mut addr = ptr as u64;
+    while bytes.len() < PATH_MAX {
+        let chunk = ((PAGE_SIZE - (addr & (PAGE_SIZE - 1))) as usize).min(PATH_MAX - bytes.len());
+        let data = copy_from_user(addr as *const u8, chunk)?;
+        if let Some(end) = data.iter().position(|&b| b == 0) {
+            bytes.extend_from_slice(&data[..end]);
+            return String::from_utf8(bytes).map_err(|_| Errno::EINVAL);
+        }
+        bytes.extend_from_slice(&data);
+        addr += chunk as u64;
+    }
+    Err(Errno::EINVAL)
+}
+
+// ============================================================================
+// Memory
+// ============================================================================
+
+/// Linux `mmap` flags
+mod map_flags {
+    pub const MAP_FIXED: u64 = 0x10;
+    pub const MAP_ANONYMOUS: u64 = 0x20;
+}
+
+/// Nyx protection for Linux `PROT_*` bits, which share the low three bits
+fn protection(prot: u64) -> Protection {
+    Protection::from_bits_truncate((prot & 0x7) as u8) | Protection::USER
+}
+
+fn sys_mmap(regs: &mut SyscallRegs) -> LinuxResult {
+    use map_flags::*;
+
+    let hint = regs.arg0;
+    let length = regs.arg1;
+    let prot = protection(regs.arg2);
+    let flags = regs.arg3;
+
+    if length == 0 || length > 1024 * 1024 * 1024 {
+        return Err(Errno::EINVAL);
+    }
+    if flags & MAP_ANONYMOUS == 0 {
+        // The VFS has no pager for file mappings yet
+        return Err(Errno::ENODEV);
+    }
+    let length = length.div_ceil(PAGE_SIZE) * PAGE_SIZE;
+
+    let pid = crate::process::current_process_id().ok_or(Errno::ESRCH)?;
+    let mut proc = crate::process::get_process_mut(pid).ok_or(Errno::ESRCH)?;
+
+    let addr = if flags & MAP_FIXED != 0 {
+        if hint & (PAGE_SIZE - 1) != 0 || hint < PAGE_SIZE || hint + length > crate::mem::aslr::USER_TOP {
+            return Err(Errno::EINVAL);
+        }
+        // A fixed mapping replaces whatever was there
+        let _ = proc.address_space.unmap(VirtAddr::new(hint), length);
+        VirtAddr::new(hint)
+    } else {
+        let base = proc.address_space.layout().mmap_base;
+        super::find_free_region(&proc.address_space, base, length)?
+    };
+
+    proc.address_space
+        .map(addr, length, prot, VmaBacking::Anonymous)
+        .map_err(super::vm_error)?;
+    Ok(addr.as_u64())
+}
+
+fn sys_mprotect(regs: &mut SyscallRegs) -> LinuxResult {
+    regs.arg2 = protection(regs.arg2).bits() as u64;
+    super::handle_mem_protect(regs).map_err(Errno::from)
+}
+
+fn sys_brk(regs: &mut SyscallRegs) -> LinuxResult {
+    let requested = regs.arg0;
+    let pid = crate::process::current_process_id().ok_or(Errno::ESRCH)?;
+
+    let mut linux = LINUX.write();
+    let state = linux.get_mut(&pid).ok_or(Errno::ESRCH)?;
+
+    // Linux reports failure by returning the unchanged break
+    if requested < state.brk_start {
+        return Ok(state.brk);
+    }
+
+    let mapped_end = VirtAddr::new(state.brk).align_up(PAGE_SIZE).as_u64();
+    let new_end = VirtAddr::new(requested).align_up(PAGE_SIZE).as_u64();
+    if new_end != mapped_end {
+        let mut proc = crate::process::get_process_mut(pid).ok_or(Errno::ESRCH)?;
+        let result = if new_end > mapped_end {
+            proc.address_space.map(
+                VirtAddr::new(mapped_end),
+                new_end - mapped_end,
+                Protection::READ | Protection::WRITE | Protection::USER,
+                VmaBacking::Anonymous,
+            )
+        } else {
+            proc.address_space
+                .unmap(VirtAddr::new(new_end), mapped_end - new_end)
+        };
+        if result.is_err() {
+            return Ok(state.brk);
+        }
+    }
+
+    state.brk = requested;
+    Ok(requested)
+}
+
+// ============================================================================
+// Threads
+// ============================================================================
+
+/// Linux `clone` flags
+mod clone_flags {
+    pub const CLONE_VM: u64 = 0x100;
+    pub const CLONE_SIGHAND: u64 = 0x800;
+    pub const CLONE_THREAD: u64 = 0x10000;
+    pub const CLONE_SETTLS: u64 = 0x80000;
+    pub const CLONE_PARENT_SETTID: u64 = 0x100000;
+    pub const CLONE_CHILD_CLEARTID: u64 = 0x200000;
+    pub const CLONE_CHILD_SETTID: u64 = 0x1000000;
+}
+
+/// Create a thread
+///
+/// The child resumes where the parent returns, on its own stack, with the
+/// parent's argument registers and a return value of 0.
+fn sys_clone(regs: &mut SyscallRegs) -> LinuxResult {
+    use clone_flags::*;
+
+    let flags = regs.arg0;
+    let stack = regs.arg1;
+    let parent_tid = regs.arg2;
+    let child_tid = regs.arg3;
+    let tls = regs.arg4;
+
+    // No fork: only threads sharing everything with the parent
+    let thread_flags = CLONE_VM | CLONE_SIGHAND | CLONE_THREAD;
+    if flags & thread_flags != thread_flags {
+        return Err(Errno::ENOSYS);
+    }
+    if stack == 0 || stack >= 0x0000_8000_0000_0000 {
+        return Err(Errno::EINVAL);
+    }
+
+    let pid = crate::process::current_process_id().ok_or(Errno::ESRCH)?;
+    let address_space = crate::process::get_process_mut(pid)
+        .ok_or(Errno::ESRCH)?
+        .address_space
+        .clone();
+
+    let mut thread = crate::sched::Thread::new_user(regs.rip, stack, address_space, pid);
+    thread.registers.rdi = regs.arg0;
+    thread.registers.rsi = regs.arg1;
+    thread.registers.rdx = regs.arg2;
+    thread.registers.r10 = regs.arg3;
+    thread.registers.r8 = regs.arg4;
+    thread.registers.r9 = regs.arg5;
+    thread.registers.rax = 0;
+    if flags & CLONE_SETTLS != 0 {
+        thread.fs_base = tls;
+    }
+    let tid = thread.id;
+
+    // The tid words live in the shared address space, so the parent can
+    // fill them in before the child first runs
+    if flags & CLONE_PARENT_SETTID != 0 {
+        copy_value_to_user(parent_tid as *mut u32, tid.0 as u32)?;
+    }
+    if flags & CLONE_CHILD_SETTID != 0 {
+        copy_value_to_user(child_tid as *mut u32, tid.0 as u32)?;
+    }
+    if flags & CLONE_CHILD_CLEARTID != 0 {
+        with_current(|state| {
+            state.clear_tid.insert(tid, child_tid);
+            Ok(())
+        })?;
+    }
+
+    crate::sched::THREADS.write().insert(tid, thread);
+    if let Some(mut proc) = crate::process::get_process_mut(pid) {
+        proc.add_thread(tid);
+    }
+    crate::sched::enqueue_on_least_loaded(tid);
+
+    Ok(tid.0)
+}
+
+fn sys_set_tid_address(regs: &mut SyscallRegs) -> LinuxResult {
+    let tid = crate::sched::current_thread_id();
+    with_current(|state| {
+        state.clear_tid.insert(tid, regs.arg0);
+        Ok(tid.0)
+    })
+}
+
+/// Exit the calling thread
+fn sys_exit(regs: &mut SyscallRegs) -> LinuxResult {
+    let tid = crate::sched::current_thread_id();
+
+    // Tell whoever joins this thread that it is gone
+    if let Ok(Some(addr)) = with_current(|state| Ok(state.clear_tid.remove(&tid))) {
+        if addr != 0 && copy_value_to_user(addr as *mut u32, 0).is_ok() {
+            if let Some(pid) = crate::process::current_process_id() {
+                futex_wake(pid, addr, 1);
+            }
+        }
+    }
+
+    super::handle_thread_exit(regs).map_err(Errno::from)
+}
+
+fn sys_arch_prctl(regs: &mut SyscallRegs) -> LinuxResult {
+    const ARCH_SET_FS: u64 = 0x1002;
+    const ARCH_GET_FS: u64 = 0x1003;
+
+    let tid = crate::sched::current_thread_id();
+    match regs.arg0 {
+        ARCH_SET_FS => {
+            let base = regs.arg1;
+            if base >= 0x0000_8000_0000_0000 {
+                return Err(Errno::EPERM);
+            }
+            if let Some(thread) = crate::sched::THREADS.write().get_mut(&tid) {
+                thread.fs_base = base;
+            }
+            crate::arch::x86_64::set_fs_base(base);
+            Ok(0)
+        }
+        ARCH_GET_FS => {
+            let base = crate::sched::THREADS
+                .read()
+                .get(&tid)
+                .map(|thread| thread.fs_base)
+                .unwrap_or(0);
+            copy_value_to_user(regs.arg1 as *mut u64, base)?;
+            Ok(0)
+        }
+        _ => Err(Errno::EINVAL),
+    }
+}
+
+// ============================================================================
+// Futexes
+// ============================================================================
+
+/// Futex operations
+mod futex_op {
+    pub const FUTEX_WAIT: u64 = 0;
+    pub const FUTEX_WAKE: u64 = 1;
+    pub const FUTEX_WAIT_BITSET: u64 = 9;
+    pub const FUTEX_WAKE_BITSET: u64 = 10;
+    /// Private and realtime-clock flags, which change nothing here
+    pub const FUTEX_CMD_MASK: u64 = !(128 | 256);
+}
+
+fn sys_futex(regs: &mut SyscallRegs) -> LinuxResult {
+    use futex_op::*;
+
+    let uaddr = regs.arg0;
+    let val = regs.arg2 as u32;
+    if uaddr & 3 != 0 {
+        return Err(Errno::EINVAL);
+    }
+    let pid = crate::process::current_process_id().ok_or(Errno::ESRCH)?;
+
+    match regs.arg1 & FUTEX_CMD_MASK {
+        // FUTEX_WAIT times out relative to now, FUTEX_WAIT_BITSET at an
+        // absolute time on the monotonic clock
+        op @ (FUTEX_WAIT | FUTEX_WAIT_BITSET) => {
+            let deadline = if regs.arg3 == 0 {
+                None
+            } else {
+                let timeout = read_timespec(regs.arg3)?;
+                let now = crate::now_ns();
+                Some(if op == FUTEX_WAIT {
+                    now.saturating_add(timeout)
+                } else {
+                    timeout
+                })
+            };
+            futex_wait(pid, uaddr, val, deadline)
+        }
+        FUTEX_WAKE | FUTEX_WAKE_BITSET => Ok(futex_wake(pid, uaddr, val as usize)),
+        _ => Err(Errno::ENOSYS),
+    }
+}
+
+/// Sleep on `uaddr` as long as it holds `val`
+fn futex_wait(pid: ProcessId, uaddr: u64, val: u32, deadline_ns: Option<u64>) -> LinuxResult {
+    let tid = crate::sched::current_thread_id();
+    let key = (pid, uaddr);
+
+    {
+        // Checking the word, queueing and blocking all happen under the
+        // lock wakers take, so a wake cannot slip in between
+        let mut futexes = FUTEXES.lock();
+        if copy_value_from_user(uaddr as *const u32)? != val {
+            return Err(Errno::EAGAIN);
+        }
+        futexes.entry(key).or_default().push_back(tid);
+        if let Some(thread) = crate::sched::THREADS.write().get_mut(&tid) {
+            thread.state = ThreadState::Blocked(BlockReason::Futex);
+        }
+    }
+
+    if let Some(deadline) = deadline_ns {
+        let remaining = deadline.saturating_sub(crate::now_ns());
+        let wake_tick = crate::sched::get_tick_count() + remaining / (1_000_000_000 / TICKS_PER_SEC);
+        let cpu_id = crate::sched::current_cpu_id() as usize;
+        if let Some(cpu_sched) = crate::sched::PER_CPU.write().get_mut(cpu_id) {
+            cpu_sched.add_to_timer_queue(tid, wake_tick);
+        }
+    }
+
+    crate::sched::reschedule();
+
+    // A waker takes us off the queue; still being on it means the timer
+    // fired first
+    let mut futexes = FUTEXES.lock();
+    let queue = futexes.get_mut(&key);
+    let timed_out = match queue {
+        Some(queue) => match queue.iter().position(|&waiter| waiter == tid) {
+            Some(index) => {
+                queue.remove(index);
+                true
+            }
+            None => false,
+        },
+        None => false,
+    };
+    if futexes.get(&key).is_some_and(VecDeque::is_empty) {
+        futexes.remove(&key);
+    }
+
+    if timed_out {
+        Err(Errno::ETIMEDOUT)
+    } else {
+        Ok(0)
+    }
+}
+
+/// Wake up to `count` threads sleeping on `uaddr`
+fn futex_wake(pid: ProcessId, uaddr: u64, count: usize) -> u64 {
+    let mut futexes = FUTEXES.lock();
+    let Some(queue) = futexes.get_mut(&(pid, uaddr)) else {
+        return 0;
+    };
+
+    let mut woken = 0;
+    while woken < count {
+        let Some(tid) = queue.pop_front() else {
+            break;
+        };
+        crate::sched::wake(tid);
+        woken += 1;
+    }
+    if queue.is_empty() {
+        futexes.remove(&(pid, uaddr));
+    }
+    woken as u64
+}
+
+// ============================================================================
+// Signals
+// ============================================================================
+
+/// Size of the kernel `struct sigaction`
+const SIGACTION_SIZE: usize = 32;
+
+/// Handlers are recorded nowhere and never run; old actions read as
+/// defaults
+fn sys_rt_sigaction(regs: &mut SyscallRegs) -> LinuxResult {
+    let old = regs.arg2;
+    if old != 0 {
+        copy_to_user(old as *mut u8, &[0u8; SIGACTION_SIZE])?;
+    }
+    Ok(0)
+}
+
+/// Every signal reads as unblocked
+fn sys_rt_sigprocmask(regs: &mut SyscallRegs) -> LinuxResult {
+    let old = regs.arg2;
+    if old != 0 {
+        copy_value_to_user(old as *mut u64, 0)?;
+    }
+    Ok(0)
+}
+
+// ============================================================================
+// Time and system information
+// ============================================================================
+
+/// Read a `struct timespec` as nanoseconds
+fn read_timespec(ptr: u64) -> Result<u64, Errno> {
+    let [sec, nsec]: [i64; 2] = copy_value_from_user(ptr as *const [i64; 2])?;
+    if sec < 0 || !(0..1_000_000_000).contains(&nsec) {
+        return Err(Errno::EINVAL);
+    }
+    Ok((sec as u64).saturating_mul(1_000_000_000).saturating_add(nsec as u64))
+}
+
+fn sys_nanosleep(regs: &mut SyscallRegs) -> LinuxResult {
+    let duration = read_timespec(regs.arg0)?;
+    crate::sched::sleep(core::time::Duration::from_nanos(duration));
+    Ok(0)
+}
+
+fn sys_clock_gettime(regs: &mut SyscallRegs) -> LinuxResult {
+    const CLOCK_REALTIME: u64 = 0;
+
+    let now = crate::now_ns();
+    let (sec, nsec) = match regs.arg0 {
+        CLOCK_REALTIME => (crate::time::get_unix_timestamp().unwrap_or(0), now % 1_000_000_000),
+        // Monotonic, boot time and CPU clocks all count from boot
+        _ => (now / 1_000_000_000, now % 1_000_000_000),
+    };
+    copy_value_to_user(regs.arg1 as *mut [i64; 2], [sec as i64, nsec as i64])?;
+    Ok(0)
+}
+
+fn sys_uname(regs: &mut SyscallRegs) -> LinuxResult {
+    const FIELD: usize = 65;
+
+    // Linux libcs parse the release to decide which calls to try, so
+    // claim a kernel they know
+    let fields = ["Linux", "nyx", "5.15.0-nyx", crate::VERSION, "x86_64", "(none)"];
+    let mut utsname = [0u8; FIELD * 6];
+    for (i, field) in fields.iter().enumerate() {
+        let len = field.len().min(FIELD - 1);
+        utsname[i * FIELD..i * FIELD + len].copy_from_slice(&field.as_bytes()[..len]);
+    }
+    copy_to_user(regs.arg0 as *mut u8, &utsname)?;
+    Ok(0)
+}
+
+fn sys_getrandom(regs: &mut SyscallRegs) -> LinuxResult {
+    let len = (regs.arg1 as usize).min(MAX_IO);
+    let mut data = Vec::with_capacity(len + 8);
+    while data.len() < len {
+        data.extend_from_slice(&crate::mem::aslr::random_u64().to_le_bytes());
+    }
+    data.truncate(len);
+    copy_to_user(regs.arg0 as *mut u8, &data)?;
+    Ok(len as u64)
+}
+
+fn current_ids() -> Result<(u32, u32), Errno> {
+    let pid = crate::process::current_process_id().ok_or(Errno::ESRCH)?;
+    let proc = crate::process::PROCESSES.read();
+    proc.get(&pid).map(|proc| (proc.uid, proc.gid)).ok_or(Errno::ESRCH)
+}
+
+// ============================================================================
+// Process startup
+// ============================================================================
+
+/// Auxiliary vector entry types
+mod auxv {
+    pub const AT_NULL: u64 = 0;
+    pub const AT_PHDR: u64 = 3;
+    pub const AT_PHENT: u64 = 4;
+    pub const AT_PHNUM: u64 = 5;
+    pub const AT_PAGESZ: u64 = 6;
+    pub const AT_BASE: u64 = 7;
+    pub const AT_ENTRY: u64 = 9;
+    pub const AT_UID: u64 = 11;
+    pub const AT_EUID: u64 = 12;
+    pub const AT_GID: u64 = 13;
+    pub const AT_EGID: u64 = 14;
+    pub const AT_PLATFORM: u64 = 15;
+    pub const AT_CLKTCK: u64 = 17;
+    pub const AT_SECURE: u64 = 23;
+    pub const AT_RANDOM: u64 = 25;
+}
+
+/// Lay out argc, argv, envp and the aux vector below `stack_top` the way
+/// the Linux ELF loader does. Returns the initial stack pointer.
+pub fn setup_stack(
+    proc: &Process,
+    stack_base: VirtAddr,
+    stack_top: u64,
+    image: &LoadedImage,
+    args: &[String],
+    env: &[String],
+) -> Result<u64, SpawnError> {
+    use auxv::*;
+
+    // Strings first, at the very top
+    let mut strings = Vec::new();
+    let mut random = [0u8; 16];
+    random[..8].copy_from_slice(&crate::mem::aslr::random_u64().to_le_bytes());
+    random[8..].copy_from_slice(&crate::mem::aslr::random_u64().to_le_bytes());
+    strings.extend_from_slice(&random);
+    let platform_offset = strings.len();
+    strings.extend_from_slice(b"x86_64\0");
+
+    let mut push_all = |list: &[String]| {
+        list.iter()
+            .map(|s| {
+                let offset = strings.len();
+                strings.extend_from_slice(s.as_bytes());
+                strings.push(0);
+                offset
+            })
+            .collect::<Vec<_>>()
+    };
+    let arg_offsets = push_all(args);
+    let env_offsets = push_all(env);
+
+    let strings_base = (stack_top - strings.len() as u64) & !15;
+
+    // Then the pointer table, 16-byte aligned at argc
+    let mut words = Vec::new();
+    words.push(args.len() as u64);
+    words.extend(arg_offsets.iter().map(|&o| strings_base + o as u64));
+    words.push(0);
+    words.extend(env_offsets.iter().map(|&o| strings_base + o as u64));
+    words.push(0);
+    for (kind, value) in [
+        (AT_PHDR, image.phdr),
+        (AT_PHENT, image.phentsize as u64),
+        (AT_PHNUM, image.phnum as u64),
+        (AT_PAGESZ, PAGE_SIZE),
+        (AT_BASE, 0),
+        (AT_ENTRY, image.entry),
+        (AT_UID, proc.uid as u64),
+        (AT_EUID, proc.uid as u64),
+        (AT_GID, proc.gid as u64),
+        (AT_EGID, proc.gid as u64),
+        (AT_PLATFORM, strings_base + platform_offset as u64),
+        (AT_CLKTCK, TICKS_PER_SEC),
+        (AT_SECURE, 0),
+        (AT_RANDOM, strings_base),
+        (AT_NULL, 0),
+    ] {
+        words.push(kind);
+        words.push(value);
+    }
+
+    let sp = (strings_base - (words.len() * 8) as u64) & !15;
+
+    // Leave the program at least a page of stack
+    if sp < stack_base.as_u64() + PAGE_SIZE {
+        return Err(SpawnError::InvalidArgument);
+    }
+
+    let table: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+    write_process(proc, strings_base, &strings)?;
+    write_process(proc, sp, &table)?;
+    Ok(sp)
+}
+
+/// Copy into another process's memory, through the frames backing it
+fn write_process(proc: &Process, addr: u64, data: &[u8]) -> Result<(), SpawnError> {
+    let mut done = 0;
+    while done < data.len() {
+        let at = addr + done as u64;
+        let page = at & !(PAGE_SIZE - 1);
+        let offset = at - page;
+        let chunk = ((PAGE_SIZE - offset) as usize).min(data.len() - done);
+
+        let frame = proc
+            .address_space
+            .translate(VirtAddr::new(page))
+            .ok_or(SpawnError::OutOfMemory)?;
+        // SAFETY: the frame is mapped in the kernel's physical map and
+        // the copy stays within its page
+        unsafe {
+            let dst = (crate::mem::phys_to_virt(frame) + offset) as *mut u8;
+            core::ptr::copy_nonoverlapping(data[done..].as_ptr(), dst, chunk);
+        }
+        done += chunk;
+    }
+    Ok(())
+}
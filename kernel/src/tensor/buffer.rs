@@ -1,6 +1,7 @@
 //! Tensor buffer types and operations
 
 use crate::cap::ObjectId;
+use crate::mem::NodeId;
 use alloc::vec::Vec;
 use bitflags::bitflags;
 
@@ -21,10 +22,44 @@ pub struct TensorBuffer {
     pub device_ptr: u64,
     /// Host memory pointer (for unified memory or host-resident)
     pub host_ptr: Option<*mut u8>,
+    /// Physical base of each `CPU_CHUNK_BYTES` chunk backing a CPU tensor
+    /// (empty for device memory)
+    pub frames: Vec<u64>,
     /// Tensor flags
     pub flags: TensorFlags,
 }
 
+/// Bytes per physically contiguous chunk of a CPU tensor
+pub const CPU_CHUNK_BYTES: u64 = crate::mem::MAX_CONTIGUOUS_FRAMES as u64 * crate::mem::PAGE_SIZE;
+
+/// Where a CPU tensor's memory goes across NUMA nodes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Placement {
+    /// Near the allocating CPU, spilling to other nodes when full
+    #[default]
+    Local,
+    /// On the given node, spilling to other nodes when full
+    Preferred(NodeId),
+    /// On the given node only
+    Bind(NodeId),
+    /// Chunks spread round-robin over all nodes, for bandwidth
+    Interleave,
+}
+
+impl Placement {
+    /// Decode the raw syscall value: policy in bits 0-7, node in bits 8-15
+    pub fn from_raw(raw: u64) -> Option<Self> {
+        let node = ((raw >> 8) & 0xFF) as NodeId;
+        match raw & 0xFF {
+            0 => Some(Self::Local),
+            1 => Some(Self::Preferred(node)),
+            2 => Some(Self::Bind(node)),
+            3 => Some(Self::Interleave),
+            _ => None,
+        }
+    }
+}
+
 // TensorBuffer contains raw pointer but we control access via capabilities
 unsafe impl Send for TensorBuffer {}
 unsafe impl Sync for TensorBuffer {}
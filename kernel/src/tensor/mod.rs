@@ -27,7 +27,7 @@ mod inference;
 pub mod migration;
 mod queue;
 
-pub use buffer::{TensorBuffer, TensorShape, DType};
+pub use buffer::{TensorBuffer, TensorShape, DType, Placement, CPU_CHUNK_BYTES};
 pub use device::{ComputeDevice, DeviceCapabilities, AcceleratorType};
 pub use inference::{InferenceContext, InferenceConfig, InferenceRequest};
 pub use queue::{ComputeQueue, ComputeCommand};
@@ -502,10 +502,13 @@ fn devices_count() -> usize {
 }
 
 /// Allocate a tensor buffer
+///
+/// `placement` steers CPU tensors across NUMA nodes; device memory ignores it.
 pub fn tensor_alloc(
     shape: &TensorShape,
     dtype: DType,
     device_id: u32,
+    placement: Placement,
 ) -> Result<Capability, TensorError> {
     let devices = DEVICES.read();
     let device = devices
@@ -549,7 +552,17 @@ pub fn tensor_alloc(
     }
 
     // Allocate device memory (device-specific)
-    let device_ptr = allocate_device_memory(device_id, size)?;
+    let (device_ptr, frames) = match allocate_device_memory(device_id, size, placement) {
+        Ok(allocation) => allocation,
+        Err(err) => {
+            // Give back the reservation
+            if let Some(stats) = DEVICE_MEMORY.write().get_mut(&device_id) {
+                stats.allocated_bytes -= size;
+                stats.allocation_count -= 1;
+            }
+            return Err(err);
+        }
+    };
 
     let buffer = TensorBuffer {
         id: ObjectId::new(ObjectType::TensorBuffer),
@@ -559,6 +572,7 @@ pub fn tensor_alloc(
         size_bytes: size,
         device_ptr,
         host_ptr: None,
+        frames,
         flags: buffer::TensorFlags::empty(),
     };
 
@@ -586,7 +600,14 @@ pub fn tensor_alloc(
 }
 
 /// Allocate memory on a specific device
-fn allocate_device_memory(device_id: u32, size: u64) -> Result<u64, TensorError> {
+///
+/// Returns the device pointer and, for CPU tensors, the physical chunks
+/// backing it.
+fn allocate_device_memory(
+    device_id: u32,
+    size: u64,
+    placement: Placement,
+) -> Result<(u64, Vec<u64>), TensorError> {
     let devices = DEVICES.read();
     let device = devices
         .iter()
@@ -595,35 +616,83 @@ fn allocate_device_memory(device_id: u32, size: u64) -> Result<u64, TensorError>
 
     match device.device_type {
         AcceleratorType::Cpu => {
-            // CPU: use kernel heap allocation
-            // In a real implementation, this would use a dedicated tensor heap
-            // For now, return a placeholder address
-            Ok(0x1000_0000 + (size & 0xFFFF_0000))
+            // CPU: physical frames placed by NUMA node
+            let frames = allocate_cpu_chunks(size, placement)?;
+            Ok((frames[0], frames))
         }
         AcceleratorType::NvidiaCuda => {
             // CUDA: would call cuMemAlloc
             // Placeholder: return fake device pointer
-            Ok(0x7F00_0000_0000 + (size & 0xFFFF_0000))
+            Ok((0x7F00_0000_0000 + (size & 0xFFFF_0000), Vec::new()))
         }
         AcceleratorType::AppleMetal => {
             // Metal: would create MTLBuffer
             // Placeholder: return fake device pointer
-            Ok(0x8000_0000_0000 + (size & 0xFFFF_0000))
+            Ok((0x8000_0000_0000 + (size & 0xFFFF_0000), Vec::new()))
         }
         _ => {
             // Generic fallback
-            Ok(0x9000_0000_0000 + (size & 0xFFFF_0000))
+            Ok((0x9000_0000_0000 + (size & 0xFFFF_0000), Vec::new()))
         }
     }
 }
 
+/// Frames in the `index`th chunk of a CPU tensor of `size` bytes
+fn cpu_chunk_frames(size: u64, index: usize) -> usize {
+    let chunk = (size - index as u64 * CPU_CHUNK_BYTES).min(CPU_CHUNK_BYTES);
+    chunk.div_ceil(crate::mem::PAGE_SIZE) as usize
+}
+
+/// Allocate the physical chunks of a CPU tensor
+fn allocate_cpu_chunks(size: u64, placement: Placement) -> Result<Vec<u64>, TensorError> {
+    let nodes = crate::mem::numa::node_count() as crate::mem::NodeId;
+    if let Placement::Preferred(node) | Placement::Bind(node) = placement {
+        if node >= nodes {
+            return Err(TensorError::InvalidPlacement);
+        }
+    }
+
+    let local = crate::mem::numa::current_node();
+    let count = size.div_ceil(CPU_CHUNK_BYTES) as usize;
+    let mut frames = Vec::with_capacity(count);
+
+    for index in 0..count {
+        let chunk_frames = cpu_chunk_frames(size, index);
+        let frame = match placement {
+            Placement::Local => crate::mem::alloc_frames_near(local, chunk_frames),
+            Placement::Preferred(node) => crate::mem::alloc_frames_near(node, chunk_frames),
+            Placement::Bind(node) => crate::mem::alloc_frames_on(node, chunk_frames),
+            Placement::Interleave => {
+                crate::mem::alloc_frames_near(index as crate::mem::NodeId % nodes, chunk_frames)
+            }
+        };
+
+        match frame {
+            Some(frame) => frames.push(frame.as_u64()),
+            None => {
+                free_cpu_chunks(size, &frames);
+                return Err(TensorError::OutOfMemory);
+            }
+        }
+    }
+
+    Ok(frames)
+}
+
+/// Free the physical chunks of a CPU tensor
+fn free_cpu_chunks(size: u64, frames: &[u64]) {
+    for (index, &frame) in frames.iter().enumerate() {
+        crate::mem::free_frames(crate::mem::PhysAddr::new(frame), cpu_chunk_frames(size, index));
+    }
+}
+
 /// Free memory on a specific device
-fn free_device_memory(device_id: u32, device_ptr: u64, size: u64) {
+fn free_device_memory(device_id: u32, device_ptr: u64, size: u64, frames: &[u64]) {
     let devices = DEVICES.read();
     if let Some(device) = devices.iter().find(|d| d.id == device_id) {
         match device.device_type {
             AcceleratorType::Cpu => {
-                // CPU: would free from kernel heap
+                free_cpu_chunks(size, frames);
                 log::trace!("Free CPU tensor memory at 0x{:x}", device_ptr);
             }
             AcceleratorType::NvidiaCuda => {
@@ -639,7 +708,6 @@ fn free_device_memory(device_id: u32, device_ptr: u64, size: u64) {
             }
         }
     }
-    let _ = (device_ptr, size); // Suppress unused warnings in placeholder impl
 }
 
 /// Free a tensor buffer
@@ -650,7 +718,7 @@ pub fn tensor_free(cap: Capability) -> Result<(), TensorError> {
     let buffer = tensors.remove(&cap.object_id).ok_or(TensorError::NotFound)?;
 
     // Free device memory
-    free_device_memory(buffer.device_id, buffer.device_ptr, buffer.size_bytes);
+    free_device_memory(buffer.device_id, buffer.device_ptr, buffer.size_bytes, &buffer.frames);
 
     // Update memory tracking
     {
@@ -737,6 +805,8 @@ pub enum TensorError {
     Capability(CapError),
     /// Request queue is full
     QueueFull,
+    /// Placement hint names a node that doesn't exist
+    InvalidPlacement,
 }
 
 impl From<CapError> for TensorError {
@@ -755,6 +825,7 @@ pub fn allocate_buffer(
     size: u64,
     device_type: u32,
     _alignment: u64,
+    placement: Placement,
 ) -> Result<(u64, u64), TensorError> {
     // Find appropriate device
     let device_id = match device_type {
@@ -768,7 +839,7 @@ pub fn allocate_buffer(
     let shape = TensorShape::vector(size as u32);
 
     // Allocate buffer
-    let cap = tensor_alloc(&shape, DType::U8, device_id)?;
+    let cap = tensor_alloc(&shape, DType::U8, device_id, placement)?;
 
     // Get the buffer's physical address
     let tensors = TENSORS.read();
@@ -999,11 +1070,10 @@ pub fn get_tensor_frame(tensor_id: ObjectId, offset: u64) -> Option<crate::mem::
 
     // For CPU tensors, we can compute the physical address directly
     if tensor.device_id == 0 {
-        // CPU tensor: device_ptr is the base physical address
-        // Calculate the page-aligned address
-        let page_offset = offset & !(crate::mem::PAGE_SIZE - 1);
-        let phys_addr = tensor.device_ptr + page_offset;
-        return Some(crate::mem::PhysAddr::new(phys_addr));
+        // CPU tensor: find the chunk, then the page within it
+        let chunk = tensor.frames.get((offset / CPU_CHUNK_BYTES) as usize)?;
+        let page_offset = (offset % CPU_CHUNK_BYTES) & !(crate::mem::PAGE_SIZE - 1);
+        return Some(crate::mem::PhysAddr::new(chunk + page_offset));
     }
 
     // For GPU/NPU tensors, we need to access through the host pointer
@@ -1,25 +1,66 @@
 //! Time management subsystem
 //!
 //! Provides time-related functionality including:
-//! - System uptime tracking
+//! - Monotonic and boot clocks (TSC, calibrated against the PIT)
 //! - Wall clock time (via RTC)
-//! - Timer management for sleep and alarms
+//! - Timer objects for sleep and alarms (see [`timer`])
+
+pub mod timer;
 
 use core::sync::atomic::{AtomicU64, Ordering};
 
 /// System boot timestamp (set from RTC during init)
 static BOOT_TIMESTAMP: AtomicU64 = AtomicU64::new(0);
 
-/// Ticks since boot (incremented by timer interrupt)
-static TICKS_SINCE_BOOT: AtomicU64 = AtomicU64::new(0);
+/// TSC value at calibration, the zero point of the monotonic clock
+static TSC_BASE: AtomicU64 = AtomicU64::new(0);
+
+/// Calibrated TSC frequency in kHz (0 until calibrated)
+static TSC_KHZ: AtomicU64 = AtomicU64::new(0);
+
+/// Scheduler tick frequency in Hz
+pub const TICK_HZ: u32 = 100;
 
-/// Timer frequency in Hz
-const TIMER_FREQ_HZ: u64 = 1000;
+/// PIT input clock in Hz
+const PIT_HZ: u64 = 1_193_182;
+
+/// Length of the TSC calibration window in milliseconds
+const CALIBRATION_MS: u64 = 10;
+
+/// Clocks a timer can be set against
+#[repr(u64)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Clock {
+    /// Time since boot, excluding time spent suspended
+    Monotonic = 0,
+    /// Time since boot, including time spent suspended
+    Boottime = 1,
+}
+
+impl Clock {
+    /// Convert from the raw syscall value
+    pub fn from_raw(raw: u64) -> Option<Self> {
+        match raw {
+            0 => Some(Self::Monotonic),
+            1 => Some(Self::Boottime),
+            _ => None,
+        }
+    }
+}
 
 /// Initialize the time subsystem
 pub fn init() {
     log::info!("Initializing time subsystem");
 
+    match calibrate_tsc() {
+        Some(khz) => {
+            TSC_BASE.store(crate::arch::x86_64::rdtsc(), Ordering::SeqCst);
+            TSC_KHZ.store(khz, Ordering::SeqCst);
+            log::info!("TSC: {}.{:03} MHz", khz / 1000, khz % 1000);
+        }
+        None => log::warn!("TSC calibration failed, falling back to the scheduler tick"),
+    }
+
     // Read initial time from RTC
     if let Some(rtc_time) = read_rtc() {
         BOOT_TIMESTAMP.store(rtc_time, Ordering::SeqCst);
@@ -29,29 +70,52 @@ pub fn init() {
     }
 }
 
+/// Nanoseconds since boot on the monotonic clock
+///
+/// Derived from the TSC once calibrated; before that (or without a usable
+/// TSC) it advances in whole scheduler ticks.
+pub fn monotonic_ns() -> u64 {
+    let khz = TSC_KHZ.load(Ordering::Relaxed);
+    if khz == 0 {
+        return crate::sched::tick_count() * (1_000_000_000 / TICK_HZ as u64);
+    }
+    let delta = crate::arch::x86_64::rdtsc().wrapping_sub(TSC_BASE.load(Ordering::Relaxed));
+    (delta as u128 * 1_000_000 / khz as u128) as u64
+}
+
+/// Read a clock in nanoseconds
+pub fn clock_ns(clock: Clock) -> u64 {
+    match clock {
+        Clock::Monotonic => monotonic_ns(),
+        // Nothing suspends yet, so the boot clock never runs ahead
+        Clock::Boottime => monotonic_ns(),
+    }
+}
+
+/// TSC value at which the monotonic clock reads `ns`, if the TSC is calibrated
+pub fn monotonic_to_tsc(ns: u64) -> Option<u64> {
+    let khz = TSC_KHZ.load(Ordering::Relaxed);
+    if khz == 0 {
+        return None;
+    }
+    let cycles = (ns as u128 * khz as u128 / 1_000_000).min(u64::MAX as u128) as u64;
+    Some(TSC_BASE.load(Ordering::Relaxed).saturating_add(cycles))
+}
+
 /// Get current Unix timestamp (seconds since 1970-01-01 00:00:00 UTC)
 pub fn get_unix_timestamp() -> Option<u64> {
     let boot_ts = BOOT_TIMESTAMP.load(Ordering::Relaxed);
-    let ticks = TICKS_SINCE_BOOT.load(Ordering::Relaxed);
-    let elapsed_secs = ticks / TIMER_FREQ_HZ;
-
-    Some(boot_ts + elapsed_secs)
+    Some(boot_ts + monotonic_ns() / 1_000_000_000)
 }
 
 /// Get system uptime in milliseconds
 pub fn uptime_ms() -> u64 {
-    let ticks = TICKS_SINCE_BOOT.load(Ordering::Relaxed);
-    ticks * 1000 / TIMER_FREQ_HZ
+    monotonic_ns() / 1_000_000
 }
 
 /// Get system uptime in seconds
 pub fn uptime_secs() -> u64 {
-    TICKS_SINCE_BOOT.load(Ordering::Relaxed) / TIMER_FREQ_HZ
-}
-
-/// Called by timer interrupt handler to increment tick count
-pub fn tick() {
-    TICKS_SINCE_BOOT.fetch_add(1, Ordering::Relaxed);
+    monotonic_ns() / 1_000_000_000
 }
 
 /// Set the boot timestamp (called during RTC initialization)
@@ -59,6 +123,41 @@ pub fn set_boot_timestamp(timestamp: u64) {
     BOOT_TIMESTAMP.store(timestamp, Ordering::SeqCst);
 }
 
+/// Measure the TSC frequency against PIT channel 2
+///
+/// Returns the frequency in kHz, or `None` if the PIT never counted down.
+fn calibrate_tsc() -> Option<u64> {
+    let latch = PIT_HZ * CALIBRATION_MS / 1000;
+
+    unsafe {
+        // Gate channel 2 on with the speaker output off
+        let port61 = inb(0x61);
+        outb(0x61, (port61 & !0x02) | 0x01);
+
+        // Channel 2, lobyte/hibyte, mode 0 (interrupt on terminal count)
+        outb(0x43, 0xB0);
+        outb(0x42, latch as u8);
+        outb(0x42, (latch >> 8) as u8);
+
+        let start = crate::arch::x86_64::rdtsc();
+        let mut spins: u64 = 0;
+        while inb(0x61) & 0x20 == 0 {
+            spins += 1;
+            if spins > 100_000_000 {
+                outb(0x61, port61);
+                return None;
+            }
+            core::hint::spin_loop();
+        }
+        let end = crate::arch::x86_64::rdtsc();
+
+        outb(0x61, port61);
+
+        let khz = end.wrapping_sub(start) / CALIBRATION_MS;
+        (khz != 0).then_some(khz)
+    }
+}
+
 /// Read time from RTC hardware
 fn read_rtc() -> Option<u64> {
     // Read CMOS RTC registers
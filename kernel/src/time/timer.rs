@@ -0,0 +1,256 @@
+//! Timer objects
+//!
+//! A timer expires at a deadline on one of the kernel clocks and reports
+//! expiry by signalling bits on a notification object, so a thread waits on
+//! timers the same way it waits on any other event. Timers are one-shot or
+//! periodic; a periodic timer that falls behind counts the expirations it
+//! missed instead of queueing signals.
+//!
+//! Only the boot CPU takes the timer interrupt. When its local APIC supports
+//! TSC-deadline mode the interrupt is programmed for the earlier of the next
+//! scheduler tick and the nearest timer deadline, so timers fire with TSC
+//! resolution; otherwise they are checked on every periodic scheduler tick.
+
+use super::{clock_ns, monotonic_ns, Clock};
+use crate::cap::{Capability, ObjectId, ObjectType, Rights};
+use crate::process::ProcessId;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+use spin::Mutex;
+
+/// Timer set flags
+pub mod flags {
+    /// The expiry value is an absolute time on the timer's clock rather
+    /// than an offset from now
+    pub const ABSOLUTE: u32 = 1 << 0;
+}
+
+/// Timer errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerError {
+    /// No timer with this ID
+    NotFound,
+    /// The notification to signal doesn't exist
+    InvalidNotification,
+    /// Bad clock, bits or flags
+    InvalidArgument,
+}
+
+/// A timer object
+struct Timer {
+    /// Process that created the timer
+    owner: ProcessId,
+    /// Clock the deadline is measured on
+    clock: Clock,
+    /// Notification signalled on expiry
+    notification: ObjectId,
+    /// Bits signalled on expiry
+    bits: u64,
+    /// Next expiry in nanoseconds on `clock` (`None` while disarmed)
+    deadline: Option<u64>,
+    /// Reload interval in nanoseconds (0 for one-shot)
+    interval: u64,
+    /// Expirations since the last read
+    expirations: u64,
+}
+
+/// Timer registry
+static TIMERS: Mutex<BTreeMap<ObjectId, Timer>> = Mutex::new(BTreeMap::new());
+
+/// Scheduler tick period in nanoseconds (0 while the APIC timer is periodic)
+static TICK_PERIOD_NS: AtomicU64 = AtomicU64::new(0);
+
+/// Monotonic time of the next scheduler tick in TSC-deadline mode
+static NEXT_TICK_NS: AtomicU64 = AtomicU64::new(0);
+
+/// Create a timer that signals `bits` on `notification` when it expires
+///
+/// The timer starts disarmed.
+pub fn create(clock: Clock, notification: ObjectId, bits: u64) -> Result<Capability, TimerError> {
+    if bits == 0 {
+        return Err(TimerError::InvalidArgument);
+    }
+    if crate::ipc::poll(notification, 0).is_err() {
+        return Err(TimerError::InvalidNotification);
+    }
+    let owner = crate::process::current_process_id().ok_or(TimerError::InvalidArgument)?;
+
+    let object_id = ObjectId::new(ObjectType::Timer);
+    TIMERS.lock().insert(
+        object_id,
+        Timer {
+            owner,
+            clock,
+            notification,
+            bits,
+            deadline: None,
+            interval: 0,
+            expirations: 0,
+        },
+    );
+
+    let cap = unsafe {
+        Capability::new_unchecked(object_id, Rights::READ | Rights::WRITE | Rights::GRANT)
+    };
+
+    Ok(cap)
+}
+
+/// Arm or disarm a timer
+///
+/// `value_ns` is the first expiry, relative to now unless `flags::ABSOLUTE`
+/// is set; 0 disarms the timer. A non-zero `interval_ns` makes the timer
+/// periodic. Re-arming discards any unread expirations.
+pub fn set(id: ObjectId, value_ns: u64, interval_ns: u64, flags: u32) -> Result<(), TimerError> {
+    if flags & !flags::ABSOLUTE != 0 {
+        return Err(TimerError::InvalidArgument);
+    }
+
+    let deadline = {
+        let mut timers = TIMERS.lock();
+        let timer = timers.get_mut(&id).ok_or(TimerError::NotFound)?;
+
+        timer.deadline = match value_ns {
+            0 => None,
+            _ if flags & flags::ABSOLUTE != 0 => Some(value_ns),
+            _ => Some(clock_ns(timer.clock).saturating_add(value_ns)),
+        };
+        timer.interval = interval_ns;
+        timer.expirations = 0;
+        timer.deadline
+    };
+
+    // A deadline before the next programmed interrupt needs an earlier one
+    if deadline.is_some() {
+        program_deadline();
+    }
+    Ok(())
+}
+
+/// Disarm a timer, keeping any unread expirations
+pub fn cancel(id: ObjectId) -> Result<(), TimerError> {
+    let mut timers = TIMERS.lock();
+    let timer = timers.get_mut(&id).ok_or(TimerError::NotFound)?;
+    timer.deadline = None;
+    Ok(())
+}
+
+/// Take the number of expirations since the last read
+pub fn read(id: ObjectId) -> Result<u64, TimerError> {
+    let mut timers = TIMERS.lock();
+    let timer = timers.get_mut(&id).ok_or(TimerError::NotFound)?;
+    Ok(core::mem::take(&mut timer.expirations))
+}
+
+/// Destroy a timer
+pub fn destroy(id: ObjectId) -> Result<(), TimerError> {
+    TIMERS.lock().remove(&id).map(|_| ()).ok_or(TimerError::NotFound)
+}
+
+/// Destroy every timer a process created (called on process exit)
+pub fn release(pid: ProcessId) {
+    TIMERS.lock().retain(|_, timer| timer.owner != pid);
+}
+
+/// Drive scheduler ticks from the TSC deadline instead of a periodic count
+///
+/// Called once the boot CPU's local APIC is in TSC-deadline mode.
+pub fn start_deadline_ticks(frequency_hz: u32) {
+    let period = 1_000_000_000 / frequency_hz as u64;
+    NEXT_TICK_NS.store(monotonic_ns() + period, Ordering::SeqCst);
+    TICK_PERIOD_NS.store(period, Ordering::SeqCst);
+    program_deadline();
+}
+
+/// Timer interrupt handler
+///
+/// Signals expired timers, then runs the scheduler tick if one is due.
+pub fn interrupt() {
+    expire();
+
+    let period = TICK_PERIOD_NS.load(Ordering::Relaxed);
+    if period == 0 {
+        // Periodic APIC timer: every interrupt is a tick
+        crate::sched::timer_tick();
+        return;
+    }
+
+    let now = monotonic_ns();
+    let next_tick = NEXT_TICK_NS.load(Ordering::Relaxed);
+    let tick_due = now >= next_tick;
+    if tick_due {
+        // Skip ticks rather than fire a burst after a long stall
+        let mut next = next_tick + period;
+        if next <= now {
+            next = now + period;
+        }
+        NEXT_TICK_NS.store(next, Ordering::Relaxed);
+    }
+
+    program_deadline();
+
+    if tick_due {
+        crate::sched::timer_tick();
+    }
+}
+
+/// Signal every timer whose deadline has passed
+fn expire() {
+    // Interrupt context: a syscall on this CPU may hold the lock, so leave
+    // contended passes to the next interrupt rather than deadlock
+    let Some(mut timers) = TIMERS.try_lock() else {
+        return;
+    };
+
+    let mut fired = Vec::new();
+    for timer in timers.values_mut() {
+        let Some(deadline) = timer.deadline else {
+            continue;
+        };
+        let now = clock_ns(timer.clock);
+        if now < deadline {
+            continue;
+        }
+
+        if timer.interval == 0 {
+            timer.expirations += 1;
+            timer.deadline = None;
+        } else {
+            let missed = (now - deadline) / timer.interval;
+            timer.expirations += missed + 1;
+            timer.deadline = Some(deadline + (missed + 1) * timer.interval);
+        }
+        fired.push((timer.notification, timer.bits));
+    }
+    drop(timers);
+
+    for (notification, bits) in fired {
+        // The notification may have been destroyed since; nothing to tell
+        let _ = crate::ipc::signal(notification, bits);
+    }
+}
+
+/// Program the next TSC-deadline interrupt
+///
+/// Does nothing with a periodic APIC timer, or off the boot CPU, where the
+/// deadline is picked up by the next tick instead.
+fn program_deadline() {
+    if TICK_PERIOD_NS.load(Ordering::Relaxed) == 0
+        || crate::arch::x86_64::smp::current_cpu_id() != 0
+    {
+        return;
+    }
+
+    let mut next = NEXT_TICK_NS.load(Ordering::Relaxed);
+    if let Some(timers) = TIMERS.try_lock() {
+        // Both clocks read the same until suspend exists
+        if let Some(earliest) = timers.values().filter_map(|timer| timer.deadline).min() {
+            next = next.min(earliest);
+        }
+    }
+
+    if let Some(tsc) = super::monotonic_to_tsc(next) {
+        crate::arch::x86_64::smp::arm_tsc_deadline(tsc);
+    }
+}
@@ -384,6 +384,8 @@ fn create_from_checkpoint(checkpoint: &Checkpoint) -> Result<ProcessId, TimeTrav
         cwd: None,
         uid: 0,
         gid: 0,
+        allow_wx: false,
+        personality: crate::process::Personality::Nyx,
     };
 
     // Use spawn but override with checkpoint state
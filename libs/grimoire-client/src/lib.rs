@@ -40,20 +40,43 @@
 //! ```
 //!
 //! This provides a built-in mock that doesn't require the daemon.
+//!
+//! ## Offline Writes
+//!
+//! A client reconnects by itself after the daemon restarts. Memory and
+//! setting writes made while it is away fail, unless the client has a
+//! spool: then they are queued on disk and replayed in order on reconnect.
+//!
+//! ```rust,no_run
+//! # async fn example() -> grimoire_client::Result<()> {
+//! let client = grimoire_client::GrimoireClient::connect_default()
+//!     .await?
+//!     .with_spool("/var/lib/sitra/grimoire.spool")?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod spool;
 
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use grimoire_core::{
-    GrimoireRequest, GrimoireResponse, ResponseData, ErrorCode,
+    GrimoireRequest, GrimoireResponse, ResponseData, ErrorCode, RequestEnvelope, TraceIds,
     Persona, PersonaId, PersonaMemory, MemoryEntry, MemoryQuery,
     Ritual, RitualId, RitualExecution, DaemonStatus, PersonaEvent,
+    ModelBackend, ModelRoute, EffectiveSetting, SettingsLayer,
+    PersonaBundle, ImportConflict, ImportReport,
 };
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixStream;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, OwnedMutexGuard};
 use tracing::{debug, warn};
 
+use crate::spool::{Spool, SpooledWrite};
+pub use crate::spool::SPOOL_CAPACITY;
+
 /// Error types for the client
 #[derive(Debug, thiserror::Error)]
 pub enum ClientError {
@@ -89,6 +112,11 @@ pub type Result<T> = std::result::Result<T, ClientError>;
 pub struct GrimoireClient {
     stream: Arc<Mutex<BufReader<UnixStream>>>,
     socket_path: String,
+    trace: Option<TraceIds>,
+    /// Set when the connection failed; the next request reconnects first
+    broken: Arc<AtomicBool>,
+    /// Writes waiting for the daemon, if offline writes are enabled
+    spool: Option<Spool>,
 }
 
 impl GrimoireClient {
@@ -107,6 +135,9 @@ impl GrimoireClient {
         Ok(Self {
             stream: Arc::new(Mutex::new(BufReader::new(stream))),
             socket_path: path.to_string_lossy().to_string(),
+            trace: None,
+            broken: Arc::new(AtomicBool::new(false)),
+            spool: None,
         })
     }
 
@@ -115,27 +146,159 @@ impl GrimoireClient {
         Self::connect("/run/grimoire/grimoire.sock").await
     }
 
+    /// Send requests as part of an existing trace instead of starting a
+    /// new trace for each
+    pub fn with_trace(mut self, trace: TraceIds) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    /// Queue memory and setting writes in a spool at `path` while the
+    /// daemon is unreachable, and replay them on reconnect
+    ///
+    /// Writes left in the spool by an earlier run are replayed with the
+    /// next write, or by [`flush_spool`](Self::flush_spool).
+    pub fn with_spool(mut self, path: impl AsRef<Path>) -> Result<Self> {
+        self.spool = Some(Spool::open(path.as_ref())?);
+        Ok(self)
+    }
+
+    /// Number of writes waiting in the spool
+    pub fn pending_writes(&self) -> usize {
+        self.spool.as_ref().map_or(0, Spool::len)
+    }
+
+    /// Replay spooled writes now
+    ///
+    /// Returns how many are still waiting.
+    pub async fn flush_spool(&self) -> Result<usize> {
+        let Some(spool) = &self.spool else {
+            return Ok(0);
+        };
+        let mut stream = self.stream.lock().await;
+        self.ensure_connected(&mut stream).await?;
+        self.replay(spool, &mut stream).await;
+        Ok(spool.len())
+    }
+
+    /// Serialize a request with its trace
+    fn encode(&self, request: GrimoireRequest, key: Option<uuid::Uuid>) -> Result<String> {
+        let mut envelope = RequestEnvelope::new(request, self.trace.clone());
+        envelope.idempotency_key = key;
+        serde_json::to_string(&envelope).map_err(|e| ClientError::ParseError(e.to_string()))
+    }
+
     /// Send a request and receive a response
     async fn request(&self, request: GrimoireRequest) -> Result<GrimoireResponse> {
+        let request_json = self.encode(request, None)?;
         let mut stream = self.stream.lock().await;
+        self.ensure_connected(&mut stream).await?;
+        self.exchange(&mut stream, &request_json).await
+    }
 
-        // Serialize and send request
-        let request_json = serde_json::to_string(&request)
-            .map_err(|e| ClientError::ParseError(e.to_string()))?;
+    /// Send a write that may be spooled
+    ///
+    /// Returns `None` if the daemon is unreachable and the write was queued.
+    async fn write(&self, request: GrimoireRequest) -> Result<Option<GrimoireResponse>> {
+        let Some(spool) = &self.spool else {
+            return self.request(request).await.map(Some);
+        };
 
-        stream.get_mut().write_all(request_json.as_bytes()).await?;
-        stream.get_mut().write_all(b"\n").await?;
-        stream.get_mut().flush().await?;
+        let key = uuid::Uuid::new_v4();
+        let request_json = self.encode(request, Some(key))?;
+        let mut stream = self.stream.lock().await;
 
-        // Read response
-        let mut line = String::new();
-        stream.read_line(&mut line).await?;
+        let result = match self.ensure_connected(&mut stream).await {
+            Ok(()) => {
+                // Earlier writes go first
+                self.replay(spool, &mut stream).await;
+                if spool.is_empty() {
+                    self.exchange(&mut stream, &request_json).await
+                } else {
+                    Err(ClientError::ConnectionFailed("Spooled writes still pending".to_string()))
+                }
+            }
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Err(e) if is_connection_error(&e) => {
+                if !spool.push(SpooledWrite { key, line: request_json }) {
+                    return Err(e);
+                }
+                warn!("Grimoire unreachable, queued write {} ({})", key, e);
+                Ok(None)
+            }
+            result => result.map(Some),
+        }
+    }
+
+    /// Reconnect if the last request lost the connection
+    async fn ensure_connected(&self, stream: &mut BufReader<UnixStream>) -> Result<()> {
+        if !self.broken.load(Ordering::Acquire) {
+            return Ok(());
+        }
+
+        let fresh = UnixStream::connect(&self.socket_path).await.map_err(|e| {
+            ClientError::ConnectionFailed(format!(
+                "Failed to reconnect to {}: {}",
+                self.socket_path, e
+            ))
+        })?;
+        *stream = BufReader::new(fresh);
+        self.broken.store(false, Ordering::Release);
+        debug!("Reconnected to Grimoire daemon at {}", self.socket_path);
+
+        if let Some(spool) = &self.spool {
+            self.replay(spool, stream).await;
+        }
+        Ok(())
+    }
+
+    /// Send one request line and read the response, marking the
+    /// connection broken if either fails
+    async fn exchange(
+        &self,
+        stream: &mut BufReader<UnixStream>,
+        request_json: &str,
+    ) -> Result<GrimoireResponse> {
+        let result = async {
+            stream.get_mut().write_all(request_json.as_bytes()).await?;
+            stream.get_mut().write_all(b"\n").await?;
+            stream.get_mut().flush().await?;
+
+            let mut line = String::new();
+            if stream.read_line(&mut line).await? == 0 {
+                return Err(ClientError::ConnectionFailed(
+                    "Daemon closed the connection".to_string(),
+                ));
+            }
 
-        // Parse response
-        let response: GrimoireResponse = serde_json::from_str(&line)
-            .map_err(|e| ClientError::ParseError(e.to_string()))?;
+            serde_json::from_str(&line).map_err(|e| ClientError::ParseError(e.to_string()))
+        }
+        .await;
 
-        Ok(response)
+        if matches!(&result, Err(e) if is_connection_error(e)) {
+            self.broken.store(true, Ordering::Release);
+        }
+        result
+    }
+
+    /// Send spooled writes in order until one can't be delivered
+    async fn replay(&self, spool: &Spool, stream: &mut BufReader<UnixStream>) {
+        while let Some(write) = spool.front() {
+            match self.exchange(stream, &write.line).await {
+                // Try again later, e.g. once cipher is unlocked
+                Ok(GrimoireResponse::Error { code: ErrorCode::Unavailable, .. }) => return,
+                Ok(GrimoireResponse::Error { code, message }) => {
+                    warn!("Dropping queued write {}: {:?}: {}", write.key, code, message);
+                }
+                Ok(_) => debug!("Replayed queued write {}", write.key),
+                Err(e) if is_connection_error(&e) => return,
+                Err(e) => warn!("Dropping queued write {}: {}", write.key, e),
+            }
+            spool.pop_front();
+        }
     }
 
     /// Extract data from a response or return an error
@@ -257,6 +420,52 @@ impl GrimoireClient {
         })
     }
 
+    /// Export a persona as a signed bundle
+    pub async fn export_persona(
+        &self,
+        id: PersonaId,
+        include_memory: bool,
+        include_rituals: bool,
+    ) -> Result<PersonaBundle> {
+        let response = self
+            .request(GrimoireRequest::ExportPersona {
+                id,
+                include_memory,
+                include_rituals,
+            })
+            .await?;
+        Self::extract_response(response, |data| {
+            if let ResponseData::Bundle(bundle) = data {
+                Some(bundle)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Import a signed persona bundle
+    pub async fn import_persona(
+        &self,
+        bundle: PersonaBundle,
+        on_conflict: ImportConflict,
+        allow_untrusted: bool,
+    ) -> Result<ImportReport> {
+        let response = self
+            .request(GrimoireRequest::ImportPersona {
+                bundle,
+                on_conflict,
+                allow_untrusted,
+            })
+            .await?;
+        Self::extract_response(response, |data| {
+            if let ResponseData::Imported(report) = data {
+                Some(report)
+            } else {
+                None
+            }
+        })
+    }
+
     // ========== Memory Operations ==========
 
     /// Get memory for a persona
@@ -274,10 +483,16 @@ impl GrimoireClient {
     }
 
     /// Add a memory entry
+    ///
+    /// With a spool, an entry the daemon can't be reached for is queued
+    /// and this returns `Ok`.
     pub async fn add_memory(&self, persona_id: PersonaId, entry: MemoryEntry) -> Result<()> {
-        let response = self
-            .request(GrimoireRequest::AddMemory { persona_id, entry })
-            .await?;
+        let Some(response) = self
+            .write(GrimoireRequest::AddMemory { persona_id, entry })
+            .await?
+        else {
+            return Ok(());
+        };
         Self::extract_response(response, |data| {
             if let ResponseData::Empty = data {
                 Some(())
@@ -355,6 +570,95 @@ impl GrimoireClient {
         })
     }
 
+    /// Rotate memory keys for one persona, or all of them
+    pub async fn rotate_memory_keys(&self, persona_id: Option<PersonaId>) -> Result<Vec<PersonaId>> {
+        let response = self
+            .request(GrimoireRequest::RotateMemoryKeys { persona_id })
+            .await?;
+        Self::extract_response(response, |data| {
+            if let ResponseData::RotatedKeys(ids) = data {
+                Some(ids)
+            } else {
+                None
+            }
+        })
+    }
+
+    // ========== Conversation Operations ==========
+
+    /// Send a message to a persona and stream its reply
+    ///
+    /// The connection is held by the returned stream until the reply is
+    /// complete, so other requests on this client wait for it.
+    pub async fn converse(&self, persona_id: PersonaId, message: &str) -> Result<ReplyStream> {
+        let request_json = self.encode(
+            GrimoireRequest::Converse {
+                persona_id,
+                message: message.to_string(),
+            },
+            None,
+        )?;
+
+        let mut stream = Arc::clone(&self.stream).lock_owned().await;
+        self.ensure_connected(&mut stream).await?;
+
+        let sent = async {
+            stream.get_mut().write_all(request_json.as_bytes()).await?;
+            stream.get_mut().write_all(b"\n").await?;
+            stream.get_mut().flush().await
+        }
+        .await;
+        if let Err(e) = sent {
+            self.broken.store(true, Ordering::Release);
+            return Err(e.into());
+        }
+
+        Ok(ReplyStream {
+            stream,
+            broken: Arc::clone(&self.broken),
+            done: false,
+        })
+    }
+
+    /// Pick the backend for a persona's next model request
+    pub async fn resolve_model(&self, persona_id: PersonaId) -> Result<ModelRoute> {
+        let response = self
+            .request(GrimoireRequest::ResolveModel { persona_id })
+            .await?;
+        Self::extract_response(response, |data| {
+            if let ResponseData::ModelRoute(route) = data {
+                Some(route)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Report the outcome of a model request made on a resolved backend
+    pub async fn report_model_usage(
+        &self,
+        persona_id: PersonaId,
+        backend: ModelBackend,
+        tokens: u64,
+        success: bool,
+    ) -> Result<()> {
+        let response = self
+            .request(GrimoireRequest::ReportModelUsage {
+                persona_id,
+                backend,
+                tokens,
+                success,
+            })
+            .await?;
+        Self::extract_response(response, |data| {
+            if let ResponseData::Empty = data {
+                Some(())
+            } else {
+                None
+            }
+        })
+    }
+
     // ========== Ritual Operations ==========
 
     /// List all rituals
@@ -369,6 +673,22 @@ impl GrimoireClient {
         })
     }
 
+    /// Get a ritual by name
+    pub async fn get_ritual_by_name(&self, name: &str) -> Result<Ritual> {
+        let response = self
+            .request(GrimoireRequest::GetRitualByName {
+                name: name.to_string(),
+            })
+            .await?;
+        Self::extract_response(response, |data| {
+            if let ResponseData::Ritual(ritual) = data {
+                Some(ritual)
+            } else {
+                None
+            }
+        })
+    }
+
     /// Get rituals for a persona
     pub async fn list_persona_rituals(&self, persona_id: PersonaId) -> Result<Vec<Ritual>> {
         let response = self
@@ -434,30 +754,54 @@ impl GrimoireClient {
 
     // ========== Settings Operations ==========
 
-    /// Get a setting value
+    /// Get the effective value of a setting
     pub async fn get_setting(&self, path: &str) -> Result<serde_json::Value> {
+        Ok(self.get_effective_setting(path, None).await?.value)
+    }
+
+    /// Get the effective value of a setting and the layer it comes from,
+    /// with `app`'s namespace layered on top if given
+    pub async fn get_effective_setting(&self, path: &str, app: Option<&str>) -> Result<EffectiveSetting> {
         let response = self
             .request(GrimoireRequest::GetSetting {
                 path: path.to_string(),
+                app: app.map(str::to_string),
             })
             .await?;
         Self::extract_response(response, |data| {
-            if let ResponseData::Setting(value) = data {
-                Some(value)
+            if let ResponseData::Setting(setting) = data {
+                Some(setting)
             } else {
                 None
             }
         })
     }
 
-    /// Set a setting value
+    /// Set a setting value in the user layer
     pub async fn set_setting(&self, path: &str, value: serde_json::Value) -> Result<()> {
-        let response = self
-            .request(GrimoireRequest::SetSetting {
+        self.set_setting_in(SettingsLayer::User, path, value).await
+    }
+
+    /// Set a setting value in a specific layer
+    ///
+    /// With a spool, a value the daemon can't be reached for is queued and
+    /// this returns `Ok`.
+    pub async fn set_setting_in(
+        &self,
+        layer: SettingsLayer,
+        path: &str,
+        value: serde_json::Value,
+    ) -> Result<()> {
+        let Some(response) = self
+            .write(GrimoireRequest::SetSetting {
                 path: path.to_string(),
                 value,
+                layer,
             })
-            .await?;
+            .await?
+        else {
+            return Ok(());
+        };
         Self::extract_response(response, |data| {
             if let ResponseData::Empty = data {
                 Some(())
@@ -514,6 +858,79 @@ impl GrimoireClient {
     }
 }
 
+/// A persona reply being streamed from the daemon
+///
+/// Read it to the end: dropping it early leaves the rest of the reply on
+/// the connection.
+pub struct ReplyStream {
+    stream: OwnedMutexGuard<BufReader<UnixStream>>,
+    /// The client's broken-connection flag
+    broken: Arc<AtomicBool>,
+    done: bool,
+}
+
+impl ReplyStream {
+    /// Next chunk of the reply, or None once it is complete
+    pub async fn next_token(&mut self) -> Option<Result<String>> {
+        if self.done {
+            return None;
+        }
+
+        let mut line = String::new();
+        match self.stream.read_line(&mut line).await {
+            Ok(0) => {
+                self.done = true;
+                self.broken.store(true, Ordering::Release);
+                return Some(Err(ClientError::ConnectionFailed(
+                    "Daemon closed the connection mid-reply".to_string(),
+                )));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                self.done = true;
+                self.broken.store(true, Ordering::Release);
+                return Some(Err(e.into()));
+            }
+        }
+
+        let response: GrimoireResponse = match serde_json::from_str(&line) {
+            Ok(response) => response,
+            Err(e) => {
+                self.done = true;
+                return Some(Err(ClientError::ParseError(e.to_string())));
+            }
+        };
+
+        let token = GrimoireClient::extract_response(response, |data| {
+            if let ResponseData::Token { text, done } = data {
+                Some((text, done))
+            } else {
+                None
+            }
+        });
+        Some(match token {
+            Ok((text, done)) => {
+                self.done = done;
+                Ok(text)
+            }
+            Err(e) => {
+                self.done = true;
+                Err(e)
+            }
+        })
+    }
+
+    /// Whether the whole reply has been read
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+}
+
+/// Whether an error means the daemon couldn't be reached
+fn is_connection_error(error: &ClientError) -> bool {
+    matches!(error, ClientError::ConnectionFailed(_) | ClientError::IoError(_))
+}
+
 /// Mock client for testing without the daemon
 #[cfg(feature = "mock")]
 pub mod mock {
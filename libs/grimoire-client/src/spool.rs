@@ -0,0 +1,146 @@
+//! Local spool for writes made while the daemon is unreachable
+//!
+//! Each queued write is the exact request line to send, including the
+//! idempotency key it was first tried with, so replaying a write the daemon
+//! did receive before the connection dropped is harmless. The spool is a
+//! JSON-lines file rewritten on every change; it only ever holds writes
+//! from the time the daemon was away.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uuid::Uuid;
+
+/// Writes held before new ones are refused
+pub const SPOOL_CAPACITY: usize = 10_000;
+
+/// A write waiting for the daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SpooledWrite {
+    /// Idempotency key the write is sent under
+    pub key: Uuid,
+    /// Encoded request envelope
+    pub line: String,
+}
+
+/// Writes waiting for the daemon, oldest first
+pub(crate) struct Spool {
+    path: PathBuf,
+    queue: Mutex<VecDeque<SpooledWrite>>,
+}
+
+impl Spool {
+    /// Open the spool at `path`, picking up writes left by an earlier run
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let mut queue = VecDeque::new();
+        match std::fs::read_to_string(path) {
+            Ok(text) => {
+                for line in text.lines() {
+                    match serde_json::from_str(line) {
+                        Ok(write) => queue.push_back(write),
+                        Err(e) => warn!("Skipping bad spooled write: {}", e),
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            queue: Mutex::new(queue),
+        })
+    }
+
+    /// Number of writes waiting
+    pub fn len(&self) -> usize {
+        self.queue.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Queue a write; false if the spool is full or can't be saved
+    pub fn push(&self, write: SpooledWrite) -> bool {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.len() >= SPOOL_CAPACITY {
+            return false;
+        }
+        queue.push_back(write);
+        if let Err(e) = self.save(&queue) {
+            warn!("Failed to save write spool {:?}: {}", self.path, e);
+            queue.pop_back();
+            return false;
+        }
+        true
+    }
+
+    /// The oldest write waiting
+    pub fn front(&self) -> Option<SpooledWrite> {
+        self.queue.lock().unwrap().front().cloned()
+    }
+
+    /// Drop the oldest write once it has been delivered
+    pub fn pop_front(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.pop_front();
+        if let Err(e) = self.save(&queue) {
+            // Replaying a delivered write again is harmless
+            warn!("Failed to save write spool {:?}: {}", self.path, e);
+        }
+    }
+
+    fn save(&self, queue: &VecDeque<SpooledWrite>) -> std::io::Result<()> {
+        if queue.is_empty() {
+            return match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+
+        let mut text = String::new();
+        for write in queue {
+            text.push_str(&serde_json::to_string(write)?);
+            text.push('\n');
+        }
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, text)?;
+        std::fs::rename(&tmp, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(n: u32) -> SpooledWrite {
+        SpooledWrite {
+            key: Uuid::new_v4(),
+            line: format!("{{\"type\":\"ping\",\"n\":{}}}", n),
+        }
+    }
+
+    #[test]
+    fn test_spool_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("grimoire.spool");
+
+        let spool = Spool::open(&path).unwrap();
+        let first = write(1);
+        assert!(spool.push(first.clone()));
+        assert!(spool.push(write(2)));
+
+        let reopened = Spool::open(&path).unwrap();
+        assert_eq!(reopened.len(), 2);
+        assert_eq!(reopened.front().unwrap().key, first.key);
+
+        reopened.pop_front();
+        reopened.pop_front();
+        assert!(reopened.is_empty());
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,107 @@
+//! Persona bundles
+//!
+//! A bundle packages a persona, optionally with its long-term memory and
+//! rituals, into one signed document that can be shared and imported on
+//! another machine. The exporting daemon signs the encoded contents with
+//! its Ed25519 key; the importing daemon checks the signature and whether
+//! it trusts the signer before registering anything.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{Persona, PersonaId, PersonaMemory, Ritual};
+
+/// Current bundle format
+pub const BUNDLE_FORMAT: u32 = 1;
+
+/// File extension for bundles written to disk
+pub const BUNDLE_EXTENSION: &str = "grimoire-bundle";
+
+/// A signed persona bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersonaBundle {
+    /// Bundle format version
+    pub format: u32,
+    /// JSON-encoded [`BundleContents`], base64
+    pub contents: String,
+    /// Signer's Ed25519 public key, hex
+    pub signer: String,
+    /// Ed25519 signature over the contents, hex
+    pub signature: String,
+}
+
+impl PersonaBundle {
+    /// Parse a bundle file
+    pub fn from_json(json: &str) -> Result<Self, crate::GrimoireError> {
+        serde_json::from_str(json).map_err(|e| crate::GrimoireError::ParseError(e.to_string()))
+    }
+
+    /// Serialize for writing to a file
+    pub fn to_json(&self) -> Result<String, crate::GrimoireError> {
+        serde_json::to_string_pretty(self).map_err(|e| crate::GrimoireError::ParseError(e.to_string()))
+    }
+}
+
+/// What a bundle carries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleContents {
+    pub persona: Persona,
+    /// Long-term memory, if exported
+    #[serde(default)]
+    pub memory: Option<PersonaMemory>,
+    /// Rituals the persona runs, if exported
+    #[serde(default)]
+    pub rituals: Vec<Ritual>,
+    pub exported_at: DateTime<Utc>,
+}
+
+/// What to do when an imported persona clashes with an existing one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportConflict {
+    /// Refuse the import
+    #[default]
+    Fail,
+    /// Import under a new ID and a free name
+    Rename,
+    /// Overwrite the existing persona (never a built-in)
+    Replace,
+}
+
+/// Outcome of an import
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub persona_id: PersonaId,
+    /// Name the persona was registered under
+    pub name: String,
+    /// Fingerprint of the signing key
+    pub signer_key_id: String,
+    /// Whether the signer is in the trust store
+    pub trusted: bool,
+    /// Whether an existing persona was overwritten
+    pub replaced: bool,
+    /// Number of rituals imported
+    pub rituals: usize,
+    /// Whether memory was imported
+    pub memory: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let bundle = PersonaBundle {
+            format: BUNDLE_FORMAT,
+            contents: "e30=".to_string(),
+            signer: "ab".repeat(32),
+            signature: "cd".repeat(64),
+        };
+        let parsed = PersonaBundle::from_json(&bundle.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, bundle);
+
+        let conflict: ImportConflict = serde_json::from_str(r#""rename""#).unwrap();
+        assert_eq!(conflict, ImportConflict::Rename);
+    }
+}
@@ -8,7 +8,8 @@ use serde_json::Value;
 
 use crate::{
     Persona, PersonaId, PersonaMemory, MemoryEntry, MemoryQuery,
-    Ritual, RitualId, RitualExecution,
+    ModelBackend, Ritual, RitualId, RitualExecution, RoutingMode,
+    PersonaBundle, ImportConflict, ImportReport,
 };
 
 /// Request types for Grimoire IPC
@@ -38,6 +39,25 @@ pub enum GrimoireRequest {
     /// Get all built-in personas
     GetBuiltinPersonas,
 
+    /// Export a persona as a bundle signed by this daemon
+    ExportPersona {
+        id: PersonaId,
+        #[serde(default)]
+        include_memory: bool,
+        #[serde(default)]
+        include_rituals: bool,
+    },
+
+    /// Import a persona bundle. Bundles from signers outside the trust
+    /// store are refused unless `allow_untrusted` is set.
+    ImportPersona {
+        bundle: PersonaBundle,
+        #[serde(default)]
+        on_conflict: ImportConflict,
+        #[serde(default)]
+        allow_untrusted: bool,
+    },
+
     // ========== Memory Operations ==========
 
     /// Get memory for a persona
@@ -64,6 +84,33 @@ pub enum GrimoireRequest {
     /// Persist memory to Cipher-encrypted storage
     PersistMemory { persona_id: PersonaId },
 
+    /// Rotate the Cipher keys protecting persona memory (all personas if
+    /// none is given). Files are re-encrypted in the background.
+    RotateMemoryKeys { persona_id: Option<PersonaId> },
+
+    // ========== Conversation Operations ==========
+
+    /// Send a message to a persona. The reply is streamed back as a series
+    /// of `Token` responses on the same connection, the last one with
+    /// `done` set.
+    Converse {
+        persona_id: PersonaId,
+        message: String,
+    },
+
+    /// Pick the backend to run a persona's next model request on. Counts
+    /// as one request against the persona's budget.
+    ResolveModel { persona_id: PersonaId },
+
+    /// Report how a model request on a resolved backend went, so its
+    /// tokens count against the budget and failures mark the backend down
+    ReportModelUsage {
+        persona_id: PersonaId,
+        backend: ModelBackend,
+        tokens: u64,
+        success: bool,
+    },
+
     // ========== Ritual Operations ==========
 
     /// List all rituals
@@ -101,17 +148,35 @@ pub enum GrimoireRequest {
 
     // ========== Settings Operations ==========
 
-    /// Get a setting value
-    GetSetting { path: String },
+    /// Get the effective value of a setting and the layer it comes from.
+    /// With `app`, that app's namespace is layered on top.
+    GetSetting {
+        path: String,
+        #[serde(default)]
+        app: Option<String>,
+    },
 
-    /// Set a setting value
-    SetSetting { path: String, value: Value },
+    /// Set a setting value in one layer (the user layer by default)
+    SetSetting {
+        path: String,
+        value: Value,
+        #[serde(default)]
+        layer: SettingsLayer,
+    },
 
-    /// Get multiple settings
-    GetSettings { paths: Vec<String> },
+    /// Get the effective values of multiple settings
+    GetSettings {
+        paths: Vec<String>,
+        #[serde(default)]
+        app: Option<String>,
+    },
 
-    /// List all settings
-    ListSettings { category: Option<String> },
+    /// List the effective values of all settings
+    ListSettings {
+        category: Option<String>,
+        #[serde(default)]
+        app: Option<String>,
+    },
 
     // ========== Subscription Operations ==========
 
@@ -139,6 +204,67 @@ pub enum GrimoireRequest {
     Ping,
 }
 
+/// Trace a request belongs to
+///
+/// Same wire format as libnyx-ipc's trace context, so the daemon's log
+/// lines for a request join the trace of whatever made it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceIds {
+    /// Shared by every span of one user action
+    pub trace_id: String,
+    /// The span that sent the request
+    pub span_id: String,
+}
+
+impl TraceIds {
+    /// Start a new trace
+    pub fn new() -> Self {
+        let mut span_id = uuid::Uuid::new_v4().simple().to_string();
+        span_id.truncate(16);
+        Self {
+            trace_id: uuid::Uuid::new_v4().simple().to_string(),
+            span_id,
+        }
+    }
+}
+
+impl Default for TraceIds {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A request as sent on the wire, with the trace it belongs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestEnvelope {
+    #[serde(flatten)]
+    pub request: GrimoireRequest,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<TraceIds>,
+    /// Set on writes a client may send more than once (e.g. replayed after
+    /// a reconnect); the daemon applies each key once and answers repeats
+    /// with the original response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<uuid::Uuid>,
+}
+
+impl RequestEnvelope {
+    /// Wrap a request, starting a new trace if none is given
+    pub fn new(request: GrimoireRequest, trace: Option<TraceIds>) -> Self {
+        Self {
+            request,
+            trace: Some(trace.unwrap_or_default()),
+            idempotency_key: None,
+        }
+    }
+
+    /// Mark the request as safe to resend under `key`
+    pub fn with_idempotency_key(mut self, key: uuid::Uuid) -> Self {
+        self.idempotency_key = Some(key);
+        self
+    }
+}
+
 /// Response types for Grimoire IPC
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "status", rename_all = "snake_case")]
@@ -169,12 +295,27 @@ pub enum ResponseData {
     /// Persona ID (for registration)
     PersonaId(PersonaId),
 
+    /// Exported persona bundle
+    Bundle(PersonaBundle),
+
+    /// Result of a persona import
+    Imported(ImportReport),
+
     /// Persona memory
     Memory(PersonaMemory),
 
     /// List of memory entries
     MemoryEntries(Vec<MemoryEntry>),
 
+    /// Personas whose memory keys were rotated
+    RotatedKeys(Vec<PersonaId>),
+
+    /// Chunk of a streamed persona reply
+    Token { text: String, done: bool },
+
+    /// Backend chosen for a persona's model request
+    ModelRoute(ModelRoute),
+
     /// Single ritual
     Ritual(Ritual),
 
@@ -190,8 +331,8 @@ pub enum ResponseData {
     /// List of executions
     Executions(Vec<RitualExecution>),
 
-    /// Setting value
-    Setting(Value),
+    /// Effective setting value
+    Setting(EffectiveSetting),
 
     /// Multiple settings
     Settings(std::collections::HashMap<String, Value>),
@@ -291,6 +432,56 @@ pub enum PersonaEvent {
     },
 }
 
+/// Layer of the settings hierarchy, lowest precedence first
+///
+/// User settings override system ones and an app's namespace overrides
+/// both, except where the system layer locks a setting.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingsLayer {
+    /// System-wide settings and policy
+    System,
+    /// The user's settings
+    #[default]
+    User,
+    /// One app's namespace
+    App(String),
+}
+
+impl std::fmt::Display for SettingsLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::System => write!(f, "system"),
+            Self::User => write!(f, "user"),
+            Self::App(app) => write!(f, "app:{}", app),
+        }
+    }
+}
+
+/// A setting's value after layering, with where it came from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectiveSetting {
+    pub value: Value,
+    /// Layer the value was taken from
+    pub layer: SettingsLayer,
+    /// Whether system policy locks the setting against lower layers
+    #[serde(default)]
+    pub locked: bool,
+}
+
+/// Where a persona's model request should go
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelRoute {
+    /// Backend to send the request to
+    pub backend: ModelBackend,
+    /// Network route, for remote backends
+    pub routing: Option<RoutingMode>,
+    /// Position in the persona's backend chain (0 = first choice)
+    pub fallback_depth: usize,
+    /// Tokens left in the persona's hourly budget, if it has one
+    pub tokens_remaining: Option<u64>,
+}
+
 /// Daemon status information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DaemonStatus {
@@ -372,6 +563,33 @@ mod tests {
         assert!(matches!(parsed, GrimoireRequest::ListPersonas));
     }
 
+    #[test]
+    fn test_request_envelope() {
+        let trace = TraceIds::new();
+        let envelope = RequestEnvelope::new(
+            GrimoireRequest::GetPersonaByName { name: "nyx".to_string() },
+            Some(trace.clone()),
+        );
+        let json = serde_json::to_string(&envelope).unwrap();
+
+        // Daemons that only know the bare request still parse it
+        let bare: GrimoireRequest = serde_json::from_str(&json).unwrap();
+        assert!(matches!(bare, GrimoireRequest::GetPersonaByName { ref name } if name == "nyx"));
+
+        let parsed: RequestEnvelope = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.trace, Some(trace));
+
+        let untraced: RequestEnvelope = serde_json::from_str(r#"{"type":"list_personas"}"#).unwrap();
+        assert!(matches!(untraced.request, GrimoireRequest::ListPersonas));
+        assert!(untraced.trace.is_none());
+        assert!(untraced.idempotency_key.is_none());
+
+        let key = uuid::Uuid::new_v4();
+        let keyed = RequestEnvelope::new(GrimoireRequest::Ping, None).with_idempotency_key(key);
+        let parsed: RequestEnvelope = serde_json::from_str(&serde_json::to_string(&keyed).unwrap()).unwrap();
+        assert_eq!(parsed.idempotency_key, Some(key));
+    }
+
     #[test]
     fn test_response_serialization() {
         let response = GrimoireResponse::ok();
@@ -382,4 +600,35 @@ mod tests {
         let json = serde_json::to_string(&error).unwrap();
         assert!(json.contains("not_found"));
     }
+
+    #[test]
+    fn test_set_setting_layer() {
+        let request: GrimoireRequest = serde_json::from_str(
+            r#"{"type":"set_setting","data":{"path":"display.theme","value":"dark"}}"#,
+        ).unwrap();
+        assert!(matches!(request, GrimoireRequest::SetSetting { layer: SettingsLayer::User, .. }));
+
+        let request = GrimoireRequest::SetSetting {
+            path: "display.theme".to_string(),
+            value: Value::from("dark"),
+            layer: SettingsLayer::App("aether".to_string()),
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        assert!(json.contains(r#""layer":{"app":"aether"}"#));
+    }
+
+    #[test]
+    fn test_token_serialization() {
+        let response = GrimoireResponse::success(ResponseData::Token {
+            text: "Hel".to_string(),
+            done: false,
+        });
+        let json = serde_json::to_string(&response).unwrap();
+
+        let parsed: GrimoireResponse = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            parsed,
+            GrimoireResponse::Success { data: ResponseData::Token { ref text, done: false } } if text == "Hel"
+        ));
+    }
 }
@@ -33,19 +33,21 @@ mod persona;
 mod memory;
 mod ritual;
 mod ipc;
+mod bundle;
 mod error;
 
 pub use persona::*;
 pub use memory::*;
 pub use ritual::*;
 pub use ipc::*;
+pub use bundle::*;
 pub use error::*;
 
 /// Re-export common types
 pub mod prelude {
     pub use crate::persona::{
         Persona, PersonaId, PersonaAppearance, PersonaVoice,
-        PersonaPrivacy, PersonaCapabilities, ModelConfig,
+        PersonaPrivacy, PersonaCapabilities, ModelConfig, ModelBackend, ModelBudget,
         RoutingMode, MemoryScope, Tone, Formality, Verbosity,
     };
     pub use crate::memory::{
@@ -47,6 +47,14 @@ impl std::fmt::Display for PersonaId {
     }
 }
 
+impl std::str::FromStr for PersonaId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(Self)
+    }
+}
+
 /// Complete persona definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Persona {
@@ -80,6 +88,25 @@ pub struct Persona {
 }
 
 impl Persona {
+    /// Create a user persona with default voice, capabilities, privacy and model
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            id: PersonaId::new(),
+            name: name.into(),
+            version: semver::Version::new(1, 0, 0),
+            description: String::new(),
+            appearance: PersonaAppearance::default(),
+            voice: PersonaVoice::default(),
+            capabilities: PersonaCapabilities::default(),
+            privacy: PersonaPrivacy::default(),
+            model: ModelConfig::default(),
+            system_prompt: String::new(),
+            tools: Vec::new(),
+            rituals: Vec::new(),
+            metadata: std::collections::HashMap::new(),
+        }
+    }
+
     /// Load persona from a .grimoire file
     pub fn from_file(path: &std::path::Path) -> Result<Self, crate::GrimoireError> {
         let content = std::fs::read_to_string(path)?;
@@ -227,6 +254,91 @@ pub struct PersonaCapabilities {
     pub max_context_tokens: u32,
     /// Maximum output tokens per response
     pub max_output_tokens: u32,
+    /// What the flags above reach, enforced by Guardian during rituals
+    #[serde(default)]
+    pub grants: CapabilityGrants,
+}
+
+impl PersonaCapabilities {
+    /// Scopes to request from Guardian for a ritual run by this persona
+    ///
+    /// Filesystem and command grants only count when the matching flag is
+    /// set. Browsing without any listed hosts asks for any host.
+    pub fn scopes(&self) -> Vec<CapabilityScope> {
+        let mut scopes = Vec::new();
+
+        if self.can_access_files {
+            for path in &self.grants.read_paths {
+                scopes.push(CapabilityScope::new("filesystem:read", Some(path)));
+            }
+            for path in &self.grants.write_paths {
+                scopes.push(CapabilityScope::new("filesystem:write", Some(path)));
+            }
+        }
+
+        if self.can_browse {
+            if self.grants.network_hosts.is_empty() {
+                scopes.push(CapabilityScope::new("network:connect", None));
+            }
+            for host in &self.grants.network_hosts {
+                scopes.push(CapabilityScope::new("network:connect", Some(host)));
+            }
+        }
+
+        for service in &self.grants.services {
+            scopes.push(CapabilityScope::new("ipc:connect", Some(service)));
+        }
+
+        if self.can_execute_commands {
+            for command in &self.grants.commands {
+                scopes.push(CapabilityScope::new("process:exec", Some(command)));
+            }
+        }
+
+        scopes
+    }
+}
+
+/// Resources a persona's capabilities extend to
+///
+/// Entries are glob patterns, matched by Guardian the way its policy rules
+/// are.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CapabilityGrants {
+    /// Paths that may be read
+    #[serde(default)]
+    pub read_paths: Vec<String>,
+    /// Paths that may be written
+    #[serde(default)]
+    pub write_paths: Vec<String>,
+    /// Hosts that may be connected to (`host:port`)
+    #[serde(default)]
+    pub network_hosts: Vec<String>,
+    /// Services that may be called over IPC
+    #[serde(default)]
+    pub services: Vec<String>,
+    /// Commands that may be run
+    #[serde(default)]
+    pub commands: Vec<String>,
+}
+
+/// One capability requested from Guardian, e.g. `filesystem:read` on
+/// `/home/user/notes/*`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityScope {
+    pub capability: String,
+    /// Resource pattern; any resource when absent
+    #[serde(default)]
+    pub resource: Option<String>,
+}
+
+impl CapabilityScope {
+    pub fn new(capability: &str, resource: Option<&str>) -> Self {
+        Self {
+            capability: capability.to_string(),
+            resource: resource.map(str::to_string),
+        }
+    }
 }
 
 impl Default for PersonaCapabilities {
@@ -239,6 +351,7 @@ impl Default for PersonaCapabilities {
             can_execute_commands: false,
             max_context_tokens: 8192,
             max_output_tokens: 4096,
+            grants: CapabilityGrants::default(),
         }
     }
 }
@@ -271,6 +384,23 @@ impl Default for PersonaPrivacy {
     }
 }
 
+impl PersonaPrivacy {
+    /// Route for requests to a remote model API, or `None` if this persona
+    /// may not reach one
+    ///
+    /// Remote APIs live on the clearnet. `over_tor` keeps a persona that
+    /// otherwise connects directly from reaching them in the clear.
+    pub fn remote_route(&self, over_tor: bool) -> Option<RoutingMode> {
+        if self.onion_only || !self.clearnet_allowed {
+            return None;
+        }
+        match self.routing {
+            RoutingMode::Direct if over_tor => Some(RoutingMode::Tor),
+            routing => Some(routing),
+        }
+    }
+}
+
 /// Network routing mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -331,6 +461,38 @@ pub struct ModelConfig {
     /// Number of GPU layers (0 = CPU only)
     #[serde(default)]
     pub gpu_layers: u32,
+    /// Backends to try, in order, after the local and remote models
+    #[serde(default)]
+    pub fallbacks: Vec<ModelBackend>,
+    /// Usage limits the daemon enforces for this persona
+    #[serde(default)]
+    pub budget: ModelBudget,
+}
+
+impl ModelConfig {
+    /// Backends in the order they are tried: the local model, the remote
+    /// model, then the fallbacks
+    pub fn backends(&self) -> Vec<ModelBackend> {
+        let mut backends = Vec::new();
+        if let Some(model) = &self.local_model {
+            backends.push(ModelBackend::Local {
+                model: model.clone(),
+                path: self.local_model_path.clone(),
+            });
+        }
+        if let (Some(provider), Some(model)) = (&self.remote_provider, &self.remote_model) {
+            backends.push(ModelBackend::Remote {
+                provider: provider.clone(),
+                model: model.clone(),
+            });
+        }
+        for backend in &self.fallbacks {
+            if !backends.contains(backend) {
+                backends.push(backend.clone());
+            }
+        }
+        backends
+    }
 }
 
 fn default_temperature() -> f32 {
@@ -352,10 +514,53 @@ impl Default for ModelConfig {
             temperature: 0.7,
             top_p: 0.9,
             gpu_layers: 0,
+            fallbacks: Vec::new(),
+            budget: ModelBudget::default(),
+        }
+    }
+}
+
+/// Concrete backend serving a persona's model
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ModelBackend {
+    /// Local tensor runtime (Abaddon)
+    Local {
+        model: String,
+        #[serde(default)]
+        path: Option<PathBuf>,
+    },
+    /// Remote API provider
+    Remote { provider: String, model: String },
+    /// Malphas router, which picks a model itself unless one is named
+    Malphas {
+        #[serde(default)]
+        model: Option<String>,
+    },
+}
+
+impl std::fmt::Display for ModelBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Local { model, .. } => write!(f, "local:{}", model),
+            Self::Remote { provider, model } => write!(f, "{}:{}", provider, model),
+            Self::Malphas { model: Some(model) } => write!(f, "malphas:{}", model),
+            Self::Malphas { model: None } => write!(f, "malphas"),
         }
     }
 }
 
+/// Per-persona model usage limits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelBudget {
+    /// Model requests allowed in any minute
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+    /// Prompt and completion tokens allowed in any hour
+    #[serde(default)]
+    pub tokens_per_hour: Option<u64>,
+}
+
 /// Built-in personas
 pub mod builtin {
     use super::*;
@@ -393,6 +598,7 @@ pub mod builtin {
                 can_execute_commands: false,
                 max_context_tokens: 8192,
                 max_output_tokens: 4096,
+                grants: CapabilityGrants::default(),
             },
             privacy: PersonaPrivacy {
                 routing: RoutingMode::Tor,
@@ -410,6 +616,8 @@ pub mod builtin {
                 temperature: 0.7,
                 top_p: 0.9,
                 gpu_layers: 0,
+                fallbacks: vec![ModelBackend::Malphas { model: None }],
+                budget: ModelBudget::default(),
             },
             system_prompt: r#"You are Lilith, a research daemon within the Sitra browser.
 Your purpose is to help the user find truth through rigorous analysis.
@@ -476,6 +684,7 @@ You have access to the current page content and can browse the web through Tor."
                 can_execute_commands: false,
                 max_context_tokens: 4096,
                 max_output_tokens: 2048,
+                grants: CapabilityGrants::default(),
             },
             privacy: PersonaPrivacy {
                 routing: RoutingMode::Tor,
@@ -493,6 +702,8 @@ You have access to the current page content and can browse the web through Tor."
                 temperature: 0.5,
                 top_p: 0.9,
                 gpu_layers: 0,
+                fallbacks: Vec::new(),
+                budget: ModelBudget::default(),
             },
             system_prompt: r#"You are Mammon, a commerce daemon within the Sitra browser.
 Your purpose is to help users find the best deals and make smart purchases.
@@ -555,6 +766,7 @@ When analyzing products:
                 can_execute_commands: false,
                 max_context_tokens: 8192,
                 max_output_tokens: 4096,
+                grants: CapabilityGrants::default(),
             },
             privacy: PersonaPrivacy {
                 routing: RoutingMode::Tor,
@@ -572,6 +784,8 @@ When analyzing products:
                 temperature: 0.3,
                 top_p: 0.9,
                 gpu_layers: 0,
+                fallbacks: Vec::new(),
+                budget: ModelBudget::default(),
             },
             system_prompt: r#"You are Leviathan, a security daemon within the Sitra browser.
 Your purpose is to protect the user's privacy and security.
@@ -614,6 +828,34 @@ You ONLY operate over Tor and refuse clearnet connections."#
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_capability_scopes() {
+        let mut caps = PersonaCapabilities {
+            grants: CapabilityGrants {
+                read_paths: vec!["/home/user/notes/*".to_string()],
+                network_hosts: vec!["*.wikipedia.org:443".to_string()],
+                services: vec!["scribe".to_string()],
+                commands: vec!["/usr/bin/pandoc".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Files and commands are off by default
+        let scopes = caps.scopes();
+        assert_eq!(scopes, vec![
+            CapabilityScope::new("network:connect", Some("*.wikipedia.org:443")),
+            CapabilityScope::new("ipc:connect", Some("scribe")),
+        ]);
+
+        caps.can_access_files = true;
+        caps.grants.network_hosts.clear();
+        let scopes = caps.scopes();
+        assert!(scopes.contains(&CapabilityScope::new("filesystem:read", Some("/home/user/notes/*"))));
+        assert!(scopes.contains(&CapabilityScope::new("network:connect", None)));
+        assert!(!scopes.iter().any(|s| s.capability == "process:exec"));
+    }
+
     #[test]
     fn test_persona_id_deterministic() {
         let id1 = PersonaId::from_name("lilith");
@@ -634,6 +876,15 @@ mod tests {
         assert!(lilith.is_builtin());
     }
 
+    #[test]
+    fn test_new_persona() {
+        let persona = Persona::new("Hecate");
+        assert_eq!(persona.name, "Hecate");
+        assert!(!persona.is_builtin());
+        assert_ne!(persona.id, Persona::new("Hecate").id);
+        assert!(persona.rituals.is_empty());
+    }
+
     #[test]
     fn test_persona_serialization() {
         let lilith = builtin::lilith();
@@ -641,5 +892,32 @@ mod tests {
         let parsed = Persona::from_toml(&toml).unwrap();
         assert_eq!(parsed.id, lilith.id);
         assert_eq!(parsed.name, lilith.name);
+        assert_eq!(parsed.model.fallbacks, lilith.model.fallbacks);
+    }
+
+    #[test]
+    fn test_model_backends() {
+        let lilith = builtin::lilith();
+        let backends = lilith.model.backends();
+        assert_eq!(backends.len(), 3);
+        assert!(matches!(backends[0], ModelBackend::Local { .. }));
+        assert_eq!(backends[1].to_string(), "anthropic:claude-3-haiku");
+        assert_eq!(backends[2], ModelBackend::Malphas { model: None });
+
+        let mut model = ModelConfig::default();
+        model.fallbacks = vec![model.backends()[0].clone()];
+        assert_eq!(model.backends().len(), 1);
+    }
+
+    #[test]
+    fn test_remote_route() {
+        let mut privacy = PersonaPrivacy::default();
+        assert_eq!(privacy.remote_route(false), Some(RoutingMode::Tor));
+
+        privacy.routing = RoutingMode::Direct;
+        assert_eq!(privacy.remote_route(false), Some(RoutingMode::Direct));
+        assert_eq!(privacy.remote_route(true), Some(RoutingMode::Tor));
+
+        assert_eq!(builtin::leviathan().privacy.remote_route(true), None);
     }
 }
@@ -6,7 +6,7 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::PersonaId;
+use crate::{CapabilityScope, PersonaId};
 
 /// Unique identifier for a ritual
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -363,6 +363,32 @@ pub struct RitualExecution {
     pub error: Option<String>,
     /// Return value if completed
     pub result: Option<serde_json::Value>,
+    /// Guardian token the steps run under
+    #[serde(default)]
+    pub token: Option<ExecutionToken>,
+}
+
+/// Guardian execution token covering a ritual run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionToken {
+    /// Token ID, passed to Guardian to check each action
+    pub id: Uuid,
+    /// When Guardian stops honouring the token
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    /// Scopes Guardian granted
+    pub granted: Vec<CapabilityScope>,
+    /// Scopes the persona declared but Guardian refused
+    pub refused: Vec<CapabilityScope>,
+}
+
+impl ExecutionToken {
+    /// Whether the token was granted a scope for `capability`
+    ///
+    /// Resource patterns are left to Guardian; this only lets a step fail
+    /// early when its capability was never granted.
+    pub fn grants(&self, capability: &str) -> bool {
+        self.granted.iter().any(|scope| scope.capability == capability)
+    }
 }
 
 impl RitualExecution {
@@ -378,6 +404,7 @@ impl RitualExecution {
             ended_at: None,
             error: None,
             result: None,
+            token: None,
         }
     }
 }
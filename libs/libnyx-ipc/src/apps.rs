@@ -0,0 +1,94 @@
+//! Applications IPC client
+//!
+//! Client for the summoner launcher's default application settings. Summoner
+//! owns `mimeapps.list` and opens files with what is chosen here right away.
+
+use crate::request::{parse, request, REQUEST_TIMEOUT};
+use crate::{paths, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+use std::path::PathBuf;
+
+/// Installed application
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppInfo {
+    /// Desktop file name without `.desktop`
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+impl fmt::Display for AppInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// Default chosen by the user for one MIME type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MimeDefault {
+    pub mime: String,
+    pub app_id: String,
+}
+
+/// Applications client
+pub struct AppsClient {
+    socket_path: PathBuf,
+}
+
+impl Default for AppsClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AppsClient {
+    /// Create a client with default socket path
+    pub fn new() -> Self {
+        Self::with_socket(paths::SUMMONER_SOCKET)
+    }
+
+    /// Create a client with custom socket path
+    pub fn with_socket(path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: path.into(),
+        }
+    }
+
+    /// App that opens a MIME type, chosen or not
+    pub async fn default_app(&self, mime: &str) -> Result<Option<AppInfo>> {
+        let reply = self.send(json!({ "type": "GetDefaultApp", "data": { "mime": mime } })).await?;
+        parse(reply["data"]["app"].clone())
+    }
+
+    /// Make an app the default for a MIME type; None drops the choice
+    pub async fn set_default_app(&self, mime: &str, app_id: Option<&str>) -> Result<()> {
+        self.send(json!({ "type": "SetDefaultApp", "data": { "mime": mime, "app_id": app_id } }))
+            .await
+            .map(drop)
+    }
+
+    /// Apps that can open a MIME type, by name
+    pub async fn handlers(&self, mime: &str) -> Result<Vec<AppInfo>> {
+        let reply = self.send(json!({ "type": "ListHandlers", "data": { "mime": mime } })).await?;
+        parse(reply["data"]["apps"].clone())
+    }
+
+    /// Every default the user chose
+    pub async fn defaults(&self) -> Result<Vec<MimeDefault>> {
+        let reply = self.send(json!({ "type": "ListDefaults" })).await?;
+        parse(reply["data"]["defaults"].clone())
+    }
+
+    /// MIME types installed apps can open
+    pub async fn mime_types(&self) -> Result<Vec<String>> {
+        let reply = self.send(json!({ "type": "ListMimeTypes" })).await?;
+        parse(reply["data"]["mime_types"].clone())
+    }
+
+    async fn send(&self, body: Value) -> Result<Value> {
+        request(&self.socket_path, body, REQUEST_TIMEOUT).await
+    }
+}
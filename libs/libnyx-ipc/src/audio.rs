@@ -0,0 +1,234 @@
+//! Audio IPC client
+//!
+//! Client for the vesper audio server: master volume, output devices,
+//! per-application streams and Bluetooth audio. Vesper handles the volume
+//! keys itself and pushes an on-screen display event per press, which the
+//! shell follows with [`AudioClient::subscribe_osd`].
+
+use crate::request::{check, parse, request, Connection, REQUEST_TIMEOUT};
+use crate::{paths, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+/// Audio server status
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioStatus {
+    pub default_sink: String,
+    pub default_source: String,
+    pub stream_count: usize,
+    pub master_volume: u32,
+    pub muted: bool,
+    #[serde(default)]
+    pub bluetooth_enabled: bool,
+    #[serde(default)]
+    pub bluetooth_device: Option<String>,
+}
+
+/// Audio device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioDevice {
+    pub name: String,
+    pub description: String,
+    /// playback, capture or duplex
+    pub device_type: String,
+    pub state: String,
+    pub is_default: bool,
+}
+
+impl AudioDevice {
+    /// Can play sound
+    pub fn is_output(&self) -> bool {
+        matches!(self.device_type.as_str(), "playback" | "duplex")
+    }
+}
+
+/// An application's playback or capture stream
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioStream {
+    pub id: u32,
+    pub name: String,
+    pub app_name: String,
+    pub pid: Option<u32>,
+    /// playback or capture
+    pub direction: String,
+    pub state: String,
+    pub volume: u32,
+    pub muted: bool,
+    pub sink: String,
+}
+
+impl AudioStream {
+    pub fn is_playback(&self) -> bool {
+        self.direction == "playback"
+    }
+}
+
+/// Pushed by vesper when a volume key was pressed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum OsdEvent {
+    /// Output volume or mute changed
+    Volume {
+        sink: String,
+        description: String,
+        /// Percent
+        volume: u32,
+        muted: bool,
+        /// Safety limit of this output, percent
+        max_volume: u32,
+        /// The press asked for more than `max_volume`
+        limited: bool,
+    },
+    /// Microphone muted or unmuted
+    MicMute { source: String, muted: bool },
+}
+
+/// On-screen display events pushed by vesper
+pub struct OsdEvents {
+    connection: Connection,
+}
+
+impl OsdEvents {
+    /// Wait for the next event; fails once vesper goes away
+    pub async fn next(&mut self) -> Result<OsdEvent> {
+        parse(check(self.connection.receive().await?)?)
+    }
+}
+
+/// Audio client
+pub struct AudioClient {
+    socket_path: PathBuf,
+}
+
+impl Default for AudioClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioClient {
+    /// Create a client with default socket path
+    pub fn new() -> Self {
+        Self::with_socket(paths::VESPER_SOCKET)
+    }
+
+    /// Create a client with custom socket path
+    pub fn with_socket(path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: path.into(),
+        }
+    }
+
+    /// Get volume, mute and Bluetooth state
+    pub async fn status(&self) -> Result<AudioStatus> {
+        parse(self.send(json!({ "type": "GetStatus" })).await?)
+    }
+
+    /// List audio devices
+    pub async fn devices(&self) -> Result<Vec<AudioDevice>> {
+        let reply = self.send(json!({ "type": "ListDevices" })).await?;
+        parse(reply["devices"].clone())
+    }
+
+    /// List application streams
+    pub async fn streams(&self) -> Result<Vec<AudioStream>> {
+        let reply = self.send(json!({ "type": "ListStreams" })).await?;
+        parse(reply["streams"].clone())
+    }
+
+    /// Set master volume (percent)
+    pub async fn set_master_volume(&self, volume: u32) -> Result<()> {
+        self.send(json!({ "type": "SetMasterVolume", "data": { "volume": volume } }))
+            .await
+            .map(drop)
+    }
+
+    /// Mute or unmute everything
+    pub async fn set_master_mute(&self, muted: bool) -> Result<()> {
+        self.send(json!({ "type": "SetMasterMute", "data": { "muted": muted } }))
+            .await
+            .map(drop)
+    }
+
+    /// Set one stream's volume (percent)
+    pub async fn set_stream_volume(&self, id: u32, volume: u32) -> Result<()> {
+        self.send(json!({ "type": "SetStreamVolume", "data": { "id": id, "volume": volume } }))
+            .await
+            .map(drop)
+    }
+
+    /// Mute or unmute one stream
+    pub async fn set_stream_mute(&self, id: u32, muted: bool) -> Result<()> {
+        self.send(json!({ "type": "SetStreamMute", "data": { "id": id, "muted": muted } }))
+            .await
+            .map(drop)
+    }
+
+    /// Route playback to a device
+    pub async fn set_default_sink(&self, name: &str) -> Result<()> {
+        self.send(json!({ "type": "SetDefaultSink", "data": { "name": name } }))
+            .await
+            .map(drop)
+    }
+
+    /// Turn Bluetooth audio on or off
+    pub async fn set_bluetooth(&self, enabled: bool) -> Result<()> {
+        self.send(json!({ "type": "SetBluetooth", "data": { "enabled": enabled } }))
+            .await
+            .map(drop)
+    }
+
+    /// Follow volume key presses, to show them on screen
+    pub async fn subscribe_osd(&self) -> Result<OsdEvents> {
+        let mut connection = Connection::open(&self.socket_path).await?;
+        connection.send(&json!({ "type": "SubscribeOsd" })).await?;
+        Ok(OsdEvents { connection })
+    }
+
+    async fn send(&self, body: Value) -> Result<Value> {
+        request(&self.socket_path, body, REQUEST_TIMEOUT).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_without_bluetooth_fields() {
+        let status: AudioStatus = serde_json::from_value(json!({
+            "status": "Status",
+            "default_sink": "speakers",
+            "default_source": "mic",
+            "stream_count": 2,
+            "master_volume": 70,
+            "muted": false,
+        }))
+        .unwrap();
+
+        assert_eq!(status.master_volume, 70);
+        assert!(!status.bluetooth_enabled);
+        assert!(status.bluetooth_device.is_none());
+    }
+
+    #[test]
+    fn test_osd_event() {
+        let event: OsdEvent = parse(check(json!({
+            "event": "Volume",
+            "sink": "alsa_output.pci-0.analog-stereo",
+            "description": "HDA Intel PCH (Analog Output)",
+            "volume": 60,
+            "muted": false,
+            "max_volume": 60,
+            "limited": true,
+        })).unwrap()).unwrap();
+        match event {
+            OsdEvent::Volume { volume, limited, .. } => {
+                assert_eq!(volume, 60);
+                assert!(limited);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Audit bus for privileged operations
+//!
+//! Daemons that grant or use privileges (guardian, vault, cipher, spectre,
+//! nexus) describe each decision as an [`AuditEvent`] and hand it to
+//! [`record`], which sends it to scribe without holding up the operation.
+//! Scribe keeps the events in the journal under the `audit` identifier
+//! with `audit.*` fields, so `nyx-audit` can filter them by actor, object
+//! and outcome across every daemon.
+
+use crate::request::request;
+use crate::trace::TraceContext;
+use crate::{paths, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Journal identifier audit events are recorded under
+pub const AUDIT_IDENTIFIER: &str = "audit";
+
+/// How long scribe gets to take an event
+const AUDIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How a privileged operation ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Success,
+    /// Refused by policy, credentials or access rules
+    Denied,
+    /// Allowed but failed
+    Failure,
+}
+
+impl fmt::Display for AuditOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Success => "success",
+            Self::Denied => "denied",
+            Self::Failure => "failure",
+        })
+    }
+}
+
+/// One privileged operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    /// Daemon that made the decision
+    pub source: String,
+    /// Who asked: a user name, `uid:<n>`, or an executable path
+    pub actor: String,
+    /// What was done, as `<area>.<verb>` (e.g. `vault.unlock`)
+    pub action: String,
+    /// What it was done to (capability, secret, session, packages)
+    pub object: String,
+    pub outcome: AuditOutcome,
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub details: BTreeMap<String, String>,
+    /// Trace of the request that led to the operation
+    pub trace_id: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(
+        source: &str,
+        actor: impl Into<String>,
+        action: &str,
+        object: impl Into<String>,
+        outcome: AuditOutcome,
+    ) -> Self {
+        Self {
+            source: source.to_string(),
+            actor: actor.into(),
+            action: action.to_string(),
+            object: object.into(),
+            outcome,
+            reason: None,
+            details: BTreeMap::new(),
+            trace_id: TraceContext::current().map(|trace| trace.trace_id),
+        }
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    pub fn with_detail(mut self, key: &str, value: impl ToString) -> Self {
+        self.details.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Actor for a caller known only by its uid
+    pub fn uid(uid: u32) -> String {
+        format!("uid:{}", uid)
+    }
+
+    /// One-line description for the journal message
+    pub fn message(&self) -> String {
+        let mut message = format!("{} {} {}: {}", self.actor, self.action, self.object, self.outcome);
+        if let Some(reason) = &self.reason {
+            message.push_str(&format!(" ({})", reason));
+        }
+        message
+    }
+
+    /// Journal fields the event is stored and queried by
+    pub fn fields(&self) -> BTreeMap<String, String> {
+        let mut fields = BTreeMap::from([
+            ("audit.source".to_string(), self.source.clone()),
+            ("audit.actor".to_string(), self.actor.clone()),
+            ("audit.action".to_string(), self.action.clone()),
+            ("audit.object".to_string(), self.object.clone()),
+            ("audit.outcome".to_string(), self.outcome.to_string()),
+        ]);
+        if let Some(reason) = &self.reason {
+            fields.insert("audit.reason".to_string(), reason.clone());
+        }
+        for (key, value) in &self.details {
+            fields.insert(format!("audit.detail.{}", key), value.clone());
+        }
+        if let Some(trace_id) = &self.trace_id {
+            fields.insert("trace_id".to_string(), trace_id.clone());
+        }
+        fields
+    }
+}
+
+/// Sends audit events to scribe
+pub struct AuditClient {
+    socket_path: PathBuf,
+}
+
+impl AuditClient {
+    pub fn new() -> Self {
+        Self::with_socket(paths::SCRIBE_SOCKET)
+    }
+
+    pub fn with_socket(path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: path.into(),
+        }
+    }
+
+    pub async fn send(&self, event: &AuditEvent) -> Result<()> {
+        let body = json!({ "type": "Audit", "data": { "event": event } });
+        request(&self.socket_path, body, AUDIT_TIMEOUT).await?;
+        Ok(())
+    }
+}
+
+impl Default for AuditClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Send an event to scribe in the background
+///
+/// Outside a tokio runtime the event is only logged.
+pub fn record(event: AuditEvent) {
+    tracing::debug!(target: "audit", "{}", event.message());
+
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    runtime.spawn(async move {
+        if let Err(e) = AuditClient::new().send(&event).await {
+            tracing::warn!("Failed to send audit event to scribe: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields() {
+        let event = AuditEvent::new("vault", AuditEvent::uid(1000), "vault.unlock", "vault", AuditOutcome::Denied)
+            .with_reason("wrong password")
+            .with_detail("attempts", 3);
+
+        let fields = event.fields();
+        assert_eq!(fields["audit.actor"], "uid:1000");
+        assert_eq!(fields["audit.outcome"], "denied");
+        assert_eq!(fields["audit.detail.attempts"], "3");
+        assert!(!fields.contains_key("trace_id"));
+        assert_eq!(event.message(), "uid:1000 vault.unlock vault: denied (wrong password)");
+    }
+}
@@ -0,0 +1,219 @@
+//! Compositor IPC client
+//!
+//! Client for aether's control socket: outputs, the window list with
+//! workspaces, moving windows between workspaces, and window capture for
+//! thumbnails.
+//! Aether tags its replies with `type` rather than `status`, so errors are
+//! checked here instead of by the shared request helper.
+
+use crate::request::{parse, request, REQUEST_TIMEOUT};
+use crate::{paths, Error, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Copying a frame reads back a whole window, which takes longer than a
+/// status request
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A window as aether reports it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompositorWindow {
+    pub id: u64,
+    pub title: String,
+    pub app_id: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub state: String,
+    pub focused: bool,
+    pub workspace: u32,
+}
+
+/// A monitor as aether reports it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompositorOutput {
+    pub id: u32,
+    /// Connector name, e.g. "HDMI-A-1"
+    pub name: String,
+    pub enabled: bool,
+    pub position: (i32, i32),
+    pub resolution: (u32, u32),
+    pub scale: f32,
+}
+
+/// A copied frame, RGBA8888
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+/// Compositor client
+pub struct CompositorClient {
+    socket_path: PathBuf,
+}
+
+impl Default for CompositorClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CompositorClient {
+    /// Create a client with default socket path
+    pub fn new() -> Self {
+        Self::with_socket(paths::AETHER_SOCKET)
+    }
+
+    /// Create a client with custom socket path
+    pub fn with_socket(path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: path.into(),
+        }
+    }
+
+    /// Connected outputs
+    pub async fn outputs(&self) -> Result<Vec<CompositorOutput>> {
+        let reply = self.send(json!({ "type": "ListOutputs" })).await?;
+        parse(reply["outputs"].clone())
+    }
+
+    /// Mapped windows on every workspace
+    pub async fn windows(&self) -> Result<Vec<CompositorWindow>> {
+        let reply = self.send(json!({ "type": "ListWindows" })).await?;
+        parse(reply["windows"].clone())
+    }
+
+    /// Raise and focus a window
+    pub async fn focus_window(&self, id: u64) -> Result<()> {
+        self.send(json!({ "type": "FocusWindow", "id": id }))
+            .await
+            .map(drop)
+    }
+
+    /// Move a window to another workspace
+    pub async fn move_to_workspace(&self, id: u64, workspace: u32) -> Result<()> {
+        self.send(json!({ "type": "MoveToWorkspace", "id": id, "workspace": workspace }))
+            .await
+            .map(drop)
+    }
+
+    /// Start capturing a window for this process
+    ///
+    /// Frames can only be copied once Guardian allows `display:capture`
+    /// for the window; until then `capture_frame` fails.
+    pub async fn capture_window(&self, id: u64) -> Result<u64> {
+        let exe = std::env::current_exe()?;
+        let reply = self
+            .send(json!({
+                "type": "RequestCapture",
+                "client_pid": std::process::id(),
+                "client_path": exe.to_string_lossy(),
+                "source": { "kind": "window", "id": id },
+                "cursor": false,
+            }))
+            .await?;
+        reply["session"]
+            .as_u64()
+            .ok_or_else(|| Error::ProtocolError("Missing capture session".into()))
+    }
+
+    /// Copy the next frame of a capture session
+    pub async fn capture_frame(&self, session: u64) -> Result<Frame> {
+        let body = json!({ "type": "CaptureFrame", "session": session });
+        let reply = check(request(&self.socket_path, body, CAPTURE_TIMEOUT).await?)?;
+        decode_frame(&reply)
+    }
+
+    /// End a capture session
+    pub async fn stop_capture(&self, session: u64) -> Result<()> {
+        self.send(json!({ "type": "StopCapture", "session": session }))
+            .await
+            .map(drop)
+    }
+
+    async fn send(&self, body: Value) -> Result<Value> {
+        check(request(&self.socket_path, body, REQUEST_TIMEOUT).await?)
+    }
+}
+
+/// Turn aether's error reply into `RequestFailed`
+fn check(reply: Value) -> Result<Value> {
+    if reply["type"] == "Error" {
+        Err(Error::RequestFailed(
+            reply["message"]
+                .as_str()
+                .unwrap_or("Request failed")
+                .to_string(),
+        ))
+    } else {
+        Ok(reply)
+    }
+}
+
+/// Decode a `Screenshot` reply
+fn decode_frame(reply: &Value) -> Result<Frame> {
+    let missing = |field: &str| Error::ProtocolError(format!("Missing frame {}", field));
+    let width = reply["width"].as_u64().ok_or_else(|| missing("width"))? as u32;
+    let height = reply["height"].as_u64().ok_or_else(|| missing("height"))? as u32;
+    if reply["format"] != "RGBA8888" {
+        return Err(Error::ProtocolError(format!(
+            "Unsupported frame format {}",
+            reply["format"]
+        )));
+    }
+
+    let rgba = base64::engine::general_purpose::STANDARD
+        .decode(reply["data"].as_str().ok_or_else(|| missing("data"))?)
+        .map_err(|e| Error::ProtocolError(format!("Invalid frame data: {}", e)))?;
+    if rgba.len() != width as usize * height as usize * 4 {
+        return Err(Error::ProtocolError("Frame size does not match its data".into()));
+    }
+
+    Ok(Frame {
+        width,
+        height,
+        rgba,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_reply() {
+        let err = check(json!({ "type": "Error", "message": "No such window: 7" })).unwrap_err();
+        assert!(matches!(err, Error::RequestFailed(ref m) if m == "No such window: 7"));
+        assert!(check(json!({ "type": "Ok", "message": "done" })).is_ok());
+    }
+
+    #[test]
+    fn test_decode_frame() {
+        let data = base64::engine::general_purpose::STANDARD.encode([255u8; 8]);
+        let frame = decode_frame(&json!({
+            "type": "Screenshot",
+            "width": 2,
+            "height": 1,
+            "format": "RGBA8888",
+            "data": data,
+        }))
+        .unwrap();
+        assert_eq!((frame.width, frame.height), (2, 1));
+        assert_eq!(frame.rgba.len(), 8);
+
+        // Truncated data is rejected rather than drawn as garbage
+        assert!(decode_frame(&json!({
+            "width": 4,
+            "height": 4,
+            "format": "RGBA8888",
+            "data": data,
+        }))
+        .is_err());
+    }
+}
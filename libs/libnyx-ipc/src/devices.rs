@@ -0,0 +1,94 @@
+//! Device IPC client
+//!
+//! Client for phantom's device database: the devices the kernel reported,
+//! with their sysfs paths, device nodes and uevent properties. Daemons that
+//! need a class of device (vesper its keyboards, for instance) ask phantom
+//! instead of walking sysfs themselves.
+
+use crate::request::{parse, request, REQUEST_TIMEOUT};
+use crate::{paths, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// A device as phantom knows it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Device {
+    /// e.g. `/sys/devices/platform/i8042/serio0/input/input3/event3`
+    pub syspath: String,
+    pub devpath: String,
+    pub subsystem: Option<String>,
+    pub devtype: Option<String>,
+    /// e.g. `/dev/input/event3`
+    pub devnode: Option<String>,
+    pub driver: Option<String>,
+    pub sysname: String,
+    #[serde(default)]
+    pub properties: HashMap<String, String>,
+}
+
+/// Device database client
+pub struct DeviceClient {
+    socket_path: PathBuf,
+}
+
+impl Default for DeviceClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeviceClient {
+    /// Create a client with default socket path
+    pub fn new() -> Self {
+        Self::with_socket(paths::PHANTOM_SOCKET)
+    }
+
+    /// Create a client with custom socket path
+    pub fn with_socket(path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: path.into(),
+        }
+    }
+
+    /// Devices, all of them or those of one subsystem (`input`, `block`, ...)
+    pub async fn list(&self, subsystem: Option<&str>) -> Result<Vec<Device>> {
+        let reply = request(
+            &self.socket_path,
+            json!({ "type": "ListDevices", "data": { "subsystem": subsystem } }),
+            REQUEST_TIMEOUT,
+        )
+        .await?;
+        parse(reply["devices"].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::check;
+
+    #[test]
+    fn test_devices_reply() {
+        let reply = check(json!({
+            "status": "Devices",
+            "devices": [{
+                "syspath": "/sys/devices/platform/i8042/serio0/input/input3/event3",
+                "devpath": "/devices/platform/i8042/serio0/input/input3/event3",
+                "subsystem": "input",
+                "devtype": null,
+                "devnode": "/dev/input/event3",
+                "driver": null,
+                "sysname": "event3",
+                "properties": { "MAJOR": "13", "MINOR": "67" },
+            }],
+        }))
+        .unwrap();
+
+        let devices: Vec<Device> = parse(reply["devices"].clone()).unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].devnode.as_deref(), Some("/dev/input/event3"));
+        assert_eq!(devices[0].properties["MINOR"], "67");
+    }
+}
@@ -0,0 +1,101 @@
+//! Display IPC client
+//!
+//! Client for the iris display daemon: backlight brightness and night light.
+
+use crate::request::{parse, request, REQUEST_TIMEOUT};
+use crate::{paths, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+/// Backlight device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backlight {
+    pub name: String,
+    pub percent: u8,
+}
+
+/// Night light state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NightLight {
+    pub enabled: bool,
+    /// Enabled and inside its schedule
+    pub active: bool,
+    pub temperature: u32,
+}
+
+/// Display daemon status
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisplayStatus {
+    /// None without a backlight (desktop monitors)
+    pub backlight: Option<Backlight>,
+    pub night_light: NightLight,
+}
+
+/// Display client
+pub struct DisplayClient {
+    socket_path: PathBuf,
+}
+
+impl Default for DisplayClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DisplayClient {
+    /// Create a client with default socket path
+    pub fn new() -> Self {
+        Self::with_socket(paths::IRIS_SOCKET)
+    }
+
+    /// Create a client with custom socket path
+    pub fn with_socket(path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: path.into(),
+        }
+    }
+
+    /// Get backlight and night light state
+    pub async fn status(&self) -> Result<DisplayStatus> {
+        let reply = self.send(json!({ "type": "GetStatus" })).await?;
+        parse(reply["data"].clone())
+    }
+
+    /// Set backlight brightness (percent)
+    pub async fn set_brightness(&self, percent: u8) -> Result<()> {
+        self.send(json!({ "type": "SetBrightness", "percent": percent }))
+            .await
+            .map(drop)
+    }
+
+    /// Turn night light on or off
+    pub async fn set_night_light(&self, enabled: bool) -> Result<()> {
+        self.send(json!({ "type": "SetNightLight", "enabled": enabled }))
+            .await
+            .map(drop)
+    }
+
+    async fn send(&self, body: Value) -> Result<Value> {
+        request(&self.socket_path, body, REQUEST_TIMEOUT).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_without_backlight() {
+        let status: DisplayStatus = serde_json::from_value(json!({
+            "version": "0.1.0",
+            "displays": [],
+            "backlight": null,
+            "night_light": { "enabled": true, "active": false, "temperature": 4000 },
+        }))
+        .unwrap();
+
+        assert!(status.backlight.is_none());
+        assert!(status.night_light.enabled);
+    }
+}
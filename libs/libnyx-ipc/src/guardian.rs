@@ -3,19 +3,20 @@
 //! Client for communicating with the Guardian security agent.
 
 use crate::protocol::{CapabilityDecision, CapabilityRequest, Decision};
+use crate::request::{connect, encode};
+use crate::transport::Stream;
 use crate::{paths, Error, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::UnixStream;
 use tracing::{debug, warn};
 use uuid::Uuid;
 
 /// Guardian client
 pub struct GuardianClient {
     socket_path: PathBuf,
-    stream: Option<UnixStream>,
+    stream: Option<Stream>,
 }
 
 impl GuardianClient {
@@ -44,17 +45,7 @@ impl GuardianClient {
 
     /// Connect to Guardian
     pub async fn connect_internal(&mut self) -> Result<()> {
-        let stream = UnixStream::connect(&self.socket_path)
-            .await
-            .map_err(|e| {
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    Error::ServiceUnavailable
-                } else {
-                    Error::ConnectionFailed(e.to_string())
-                }
-            })?;
-
-        self.stream = Some(stream);
+        self.stream = Some(connect(&self.socket_path).await?);
         Ok(())
     }
 
@@ -207,6 +198,85 @@ impl GuardianClient {
         }
     }
 
+    /// Report suspicious behavior to Guardian's pattern learner
+    pub async fn report_threat(&mut self, report: ThreatReport) -> Result<()> {
+        let response: GuardianResponse = self.send_request(&GuardianRequest::ReportThreat(report)).await?;
+
+        match response {
+            GuardianResponse::Ok { .. } => Ok(()),
+            GuardianResponse::Error { message, .. } => Err(Error::RequestFailed(message)),
+            _ => Err(Error::ProtocolError("Unexpected response type".into())),
+        }
+    }
+
+    /// Ask for a token pre-authorizing work done on `subject`'s behalf
+    ///
+    /// Guardian grants the scopes its policy allows outright and lists the
+    /// rest as refused; it never prompts for a token.
+    pub async fn issue_token(
+        &mut self,
+        subject: impl Into<String>,
+        user: impl Into<String>,
+        scopes: Vec<TokenScope>,
+        ttl_secs: u64,
+    ) -> Result<ExecutionToken> {
+        let process_path = std::env::current_exe()
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let message = GuardianRequest::IssueToken {
+            subject: subject.into(),
+            user: user.into(),
+            pid: std::process::id(),
+            process_path,
+            scopes,
+            ttl_secs,
+        };
+
+        match self.send_request(&message).await? {
+            GuardianResponse::TokenIssued { token_id, expires_at, granted, refused } => {
+                Ok(ExecutionToken {
+                    id: token_id,
+                    expires_at,
+                    granted,
+                    refused,
+                })
+            }
+            GuardianResponse::Error { message, .. } => Err(Error::RequestFailed(message)),
+            _ => Err(Error::ProtocolError("Unexpected response type".into())),
+        }
+    }
+
+    /// Check an action against a token
+    ///
+    /// Returns whether it is allowed and why.
+    pub async fn check_token(
+        &mut self,
+        token_id: Uuid,
+        capability: impl Into<String>,
+        resource: Option<&str>,
+    ) -> Result<(bool, String)> {
+        let message = GuardianRequest::CheckToken {
+            token_id,
+            capability: capability.into(),
+            resource: resource.map(Into::into),
+        };
+
+        match self.send_request(&message).await? {
+            GuardianResponse::TokenCheck { allowed, reason } => Ok((allowed, reason)),
+            GuardianResponse::Error { message, .. } => Err(Error::RequestFailed(message)),
+            _ => Err(Error::ProtocolError("Unexpected response type".into())),
+        }
+    }
+
+    /// End a token once the work it covers is done
+    pub async fn revoke_token(&mut self, token_id: Uuid) -> Result<()> {
+        match self.send_request(&GuardianRequest::RevokeToken { token_id }).await? {
+            GuardianResponse::Ok { .. } => Ok(()),
+            GuardianResponse::Error { message, .. } => Err(Error::RequestFailed(message)),
+            _ => Err(Error::ProtocolError("Unexpected response type".into())),
+        }
+    }
+
     async fn send_request<R: for<'de> Deserialize<'de>>(
         &mut self,
         request: &impl Serialize,
@@ -219,9 +289,7 @@ impl GuardianClient {
         let stream = self.stream.as_mut().ok_or(Error::ServiceUnavailable)?;
 
         // Serialize and send
-        let json = serde_json::to_string(request)
-            .map_err(|e| Error::ProtocolError(e.to_string()))?;
-        let message = json + "\n";
+        let message = encode(request)?;
 
         stream
             .write_all(message.as_bytes())
@@ -280,6 +348,23 @@ enum GuardianRequest {
         level: String,
     },
     ReloadConfig,
+    ReportThreat(ThreatReport),
+    IssueToken {
+        subject: String,
+        user: String,
+        pid: u32,
+        process_path: String,
+        scopes: Vec<TokenScope>,
+        ttl_secs: u64,
+    },
+    CheckToken {
+        token_id: Uuid,
+        capability: String,
+        resource: Option<String>,
+    },
+    RevokeToken {
+        token_id: Uuid,
+    },
     Shutdown,
 }
 
@@ -312,6 +397,16 @@ enum GuardianResponse {
     SandboxProfile {
         config: serde_json::Value,
     },
+    TokenIssued {
+        token_id: Uuid,
+        expires_at: String,
+        granted: Vec<TokenScope>,
+        refused: Vec<TokenScope>,
+    },
+    TokenCheck {
+        allowed: bool,
+        reason: String,
+    },
     Ok {
         message: String,
     },
@@ -330,6 +425,58 @@ pub struct GuardianStatus {
     pub active_processes: u32,
 }
 
+/// One capability an execution token covers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenScope {
+    /// Capability, e.g. `filesystem:read`
+    pub capability: String,
+    /// Resource pattern (glob); any resource when absent
+    #[serde(default)]
+    pub resource: Option<String>,
+}
+
+impl TokenScope {
+    pub fn new(capability: impl Into<String>) -> Self {
+        Self {
+            capability: capability.into(),
+            resource: None,
+        }
+    }
+
+    pub fn with_resource(mut self, resource: impl Into<String>) -> Self {
+        self.resource = Some(resource.into());
+        self
+    }
+}
+
+/// A token issued by Guardian
+#[derive(Debug, Clone)]
+pub struct ExecutionToken {
+    pub id: Uuid,
+    /// RFC 3339 expiry
+    pub expires_at: String,
+    /// Scopes the token covers
+    pub granted: Vec<TokenScope>,
+    /// Requested scopes policy didn't allow outright
+    pub refused: Vec<TokenScope>,
+}
+
+/// Suspicious behavior detected by another agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreatReport {
+    /// Reporting agent, e.g. "arachne"
+    pub source: String,
+    /// Detection rule that fired
+    pub rule: String,
+    /// 0.0 (informational) to 1.0 (certain compromise)
+    pub severity: f32,
+    pub process_path: Option<String>,
+    pub pid: Option<u32>,
+    /// Remote party involved, if any
+    pub remote: Option<String>,
+    pub description: String,
+}
+
 /// Convenience function to check a capability
 pub async fn check_capability(
     capability: impl Into<String>,
@@ -364,4 +511,43 @@ mod tests {
         assert_eq!(req.resource, Some("/etc/passwd".into()));
         assert_eq!(req.context.get("reason"), Some(&"testing".into()));
     }
+
+    #[test]
+    fn test_threat_report_wire_format() {
+        let request = GuardianRequest::ReportThreat(ThreatReport {
+            source: "arachne".into(),
+            rule: "port_scan".into(),
+            severity: 0.5,
+            process_path: None,
+            pid: None,
+            remote: Some("203.0.113.7".into()),
+            description: "Probed 40 ports".into(),
+        });
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["type"], "ReportThreat");
+        assert_eq!(json["rule"], "port_scan");
+        assert_eq!(json["remote"], "203.0.113.7");
+    }
+
+    #[test]
+    fn test_token_wire_format() {
+        let request = GuardianRequest::IssueToken {
+            subject: "persona:lilith".into(),
+            user: "user".into(),
+            pid: 42,
+            process_path: "/usr/bin/grimoired".into(),
+            scopes: vec![TokenScope::new("filesystem:read").with_resource("/home/user/*")],
+            ttl_secs: 600,
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["type"], "IssueToken");
+        assert_eq!(json["scopes"][0]["resource"], "/home/user/*");
+
+        let response: GuardianResponse = serde_json::from_str(
+            r#"{"type":"TokenIssued","token_id":"6f1c2a1e-8a62-4c4e-9f51-3c1f0b0f6a11","expires_at":"2026-01-01T00:00:00Z","granted":[{"capability":"network:connect"}],"refused":[]}"#,
+        )
+        .unwrap();
+        assert!(matches!(response, GuardianResponse::TokenIssued { ref granted, .. } if granted[0].resource.is_none()));
+    }
 }
@@ -0,0 +1,243 @@
+//! Health and metrics convention for daemons
+//!
+//! Every daemon answers `{"type": "GetHealth"}` and `{"type": "GetMetrics"}`
+//! on its own socket, next to its other requests, so nyx-serviced's
+//! watchdog and sentinel can probe any service the same way. A daemon
+//! keeps a [`HealthReporter`], records its checks, counters and gauges on
+//! it, and hands each request line to [`HealthReporter::respond`] before
+//! parsing it as one of its own requests:
+//!
+//! ```ignore
+//! let reply = match health.respond(&line) {
+//!     Some(reply) => reply,
+//!     None => serde_json::to_string(&process_request(&line).await)?,
+//! };
+//! ```
+//!
+//! The reporter also answers the [`hello`](crate::hello) handshake with
+//! the features given to [`HealthReporter::with_features`].
+//!
+//! Probes use [`HealthClient`] with the daemon's socket path.
+
+use crate::hello::Hello;
+use crate::request::{parse, request};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a daemon gets to answer a probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Counter of request lines a daemon handled, kept for every daemon
+pub const IPC_REQUESTS: &str = "ipc_requests";
+
+/// How well a daemon or one of its parts is doing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    #[default]
+    Healthy,
+    /// Working, with something missing (e.g. no Bluetooth adapter)
+    Degraded,
+    /// Not doing its job; the watchdog may restart it
+    Unhealthy,
+}
+
+/// One part of a daemon, e.g. its D-Bus connection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub name: String,
+    pub status: HealthStatus,
+    pub message: Option<String>,
+}
+
+/// Reply to `GetHealth`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Health {
+    pub service: String,
+    pub version: String,
+    /// The worst of the checks
+    pub status: HealthStatus,
+    pub uptime_secs: u64,
+    pub checks: Vec<HealthCheck>,
+}
+
+/// Reply to `GetMetrics`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    pub service: String,
+    pub uptime_secs: u64,
+    /// Totals since the daemon started
+    pub counters: BTreeMap<String, u64>,
+    /// Current values
+    pub gauges: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Default)]
+struct Recorded {
+    checks: BTreeMap<String, (HealthStatus, Option<String>)>,
+    counters: BTreeMap<String, u64>,
+    gauges: BTreeMap<String, f64>,
+}
+
+/// A daemon's health and metrics, shared between its tasks
+#[derive(Debug, Clone)]
+pub struct HealthReporter {
+    service: Arc<str>,
+    version: Arc<str>,
+    hello: Arc<Hello>,
+    started: Instant,
+    recorded: Arc<Mutex<Recorded>>,
+}
+
+impl HealthReporter {
+    pub fn new(service: &str, version: &str) -> Self {
+        Self {
+            service: service.into(),
+            version: version.into(),
+            hello: Arc::new(Hello::new(service, version)),
+            started: Instant::now(),
+            recorded: Arc::default(),
+        }
+    }
+
+    /// Optional request groups to announce in the handshake
+    pub fn with_features(mut self, features: &[&str]) -> Self {
+        self.hello = Arc::new(Hello::clone(&self.hello).with_features(features));
+        self
+    }
+
+    /// Record the outcome of one check, replacing its previous outcome
+    pub fn set_check(&self, name: &str, status: HealthStatus, message: Option<String>) {
+        self.recorded().checks.insert(name.to_string(), (status, message));
+    }
+
+    /// Add to a counter
+    pub fn count(&self, name: &str, n: u64) {
+        *self.recorded().counters.entry(name.to_string()).or_default() += n;
+    }
+
+    pub fn set_gauge(&self, name: &str, value: f64) {
+        self.recorded().gauges.insert(name.to_string(), value);
+    }
+
+    pub fn health(&self) -> Health {
+        let recorded = self.recorded();
+        let checks: Vec<HealthCheck> = recorded
+            .checks
+            .iter()
+            .map(|(name, (status, message))| HealthCheck {
+                name: name.clone(),
+                status: *status,
+                message: message.clone(),
+            })
+            .collect();
+
+        Health {
+            service: self.service.to_string(),
+            version: self.version.to_string(),
+            status: checks.iter().map(|c| c.status).max().unwrap_or_default(),
+            uptime_secs: self.started.elapsed().as_secs(),
+            checks,
+        }
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        let recorded = self.recorded();
+        Metrics {
+            service: self.service.to_string(),
+            uptime_secs: self.started.elapsed().as_secs(),
+            counters: recorded.counters.clone(),
+            gauges: recorded.gauges.clone(),
+        }
+    }
+
+    /// The reply line to a health, metrics or hello request; None for the
+    /// daemon's own requests, which are counted
+    pub fn respond(&self, line: &str) -> Option<String> {
+        if let Some(reply) = self.hello.respond(line) {
+            return Some(reply);
+        }
+        let kind = serde_json::from_str::<Value>(line)
+            .ok()
+            .and_then(|request| request["type"].as_str().map(String::from));
+
+        let reply = match kind.as_deref() {
+            Some("GetHealth") => json!({ "status": "Health", "health": self.health() }),
+            Some("GetMetrics") => json!({ "status": "Metrics", "metrics": self.metrics() }),
+            _ => {
+                self.count(IPC_REQUESTS, 1);
+                return None;
+            }
+        };
+        Some(reply.to_string())
+    }
+
+    fn recorded(&self) -> std::sync::MutexGuard<'_, Recorded> {
+        // Every update is a single insert, so a poisoned map is still whole
+        self.recorded.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Probes a daemon's health and metrics
+pub struct HealthClient {
+    socket_path: PathBuf,
+}
+
+impl HealthClient {
+    pub fn new(socket_path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: socket_path.into(),
+        }
+    }
+
+    pub async fn health(&self) -> Result<Health> {
+        let reply = self.send("GetHealth").await?;
+        parse(field(reply, "health")?)
+    }
+
+    pub async fn metrics(&self) -> Result<Metrics> {
+        let reply = self.send("GetMetrics").await?;
+        parse(field(reply, "metrics")?)
+    }
+
+    async fn send(&self, kind: &str) -> Result<Value> {
+        request(&self.socket_path, json!({ "type": kind }), PROBE_TIMEOUT).await
+    }
+}
+
+/// Take one field out of a reply
+fn field(mut reply: Value, name: &str) -> Result<Value> {
+    match reply.get_mut(name) {
+        Some(value) => Ok(value.take()),
+        None => Err(Error::ProtocolError(format!("No {} in reply", name))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_respond() {
+        let health = HealthReporter::new("vesper", "0.1.0");
+        health.set_check("pipewire", HealthStatus::Healthy, None);
+        health.set_check("bluetooth", HealthStatus::Degraded, Some("No adapter".into()));
+
+        assert!(health.respond(r#"{"type": "ListDevices"}"#).is_none());
+        assert!(health.respond("not json").is_none());
+
+        let reply: Value = serde_json::from_str(&health.respond(r#"{"type": "GetHealth"}"#).unwrap()).unwrap();
+        let report: Health = parse(field(reply, "health").unwrap()).unwrap();
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert_eq!(report.checks.len(), 2);
+
+        let reply: Value = serde_json::from_str(&health.respond(r#"{"type": "GetMetrics"}"#).unwrap()).unwrap();
+        let metrics: Metrics = parse(field(reply, "metrics").unwrap()).unwrap();
+        assert_eq!(metrics.counters[IPC_REQUESTS], 2);
+    }
+}
@@ -0,0 +1,187 @@
+//! Protocol version and feature handshake
+//!
+//! Daemons are upgraded one at a time, so a client may talk to a daemon
+//! older or newer than itself. Before relying on anything added later, a
+//! client sends `{"type": "Hello", "protocol": N}` and the daemon answers
+//! with the range of protocol versions it speaks and the optional request
+//! groups ("features") it understands. The client decides whether the two
+//! ranges overlap:
+//!
+//! ```json
+//! {"status": "Hello", "hello": {"service": "vesper", "version": "0.1.0",
+//!  "protocol": 1, "min_protocol": 1, "features": ["bluetooth", "streams"]}}
+//! ```
+//!
+//! Daemons that keep a [`HealthReporter`](crate::HealthReporter) answer it
+//! through [`HealthReporter::respond`](crate::HealthReporter::respond);
+//! others hand each line to [`Hello::respond`] first. A daemon from before
+//! the handshake rejects the request like any unknown one, which
+//! [`HelloClient`] reports as a [`Peer`] offering no features, so callers
+//! can skip what the daemon doesn't have instead of failing outright.
+
+use crate::request::{parse, request};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Protocol version spoken by this build
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest protocol version this build still speaks
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// How long a daemon gets to answer
+const HELLO_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// What a daemon announces about itself
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hello {
+    pub service: String,
+    pub version: String,
+    /// Newest protocol version spoken
+    pub protocol: u32,
+    /// Oldest protocol version still spoken
+    pub min_protocol: u32,
+    /// Optional request groups understood, e.g. `subscribe`
+    #[serde(default)]
+    pub features: BTreeSet<String>,
+}
+
+impl Hello {
+    pub fn new(service: &str, version: &str) -> Self {
+        Self {
+            service: service.to_string(),
+            version: version.to_string(),
+            protocol: PROTOCOL_VERSION,
+            min_protocol: MIN_PROTOCOL_VERSION,
+            features: BTreeSet::new(),
+        }
+    }
+
+    pub fn with_features(mut self, features: &[&str]) -> Self {
+        self.features.extend(features.iter().map(|f| f.to_string()));
+        self
+    }
+
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.contains(feature)
+    }
+
+    /// Whether a client speaking protocols `min_protocol..=protocol` can
+    /// talk to this daemon
+    pub fn compatible(&self, min_protocol: u32, protocol: u32) -> bool {
+        min_protocol <= self.protocol && self.min_protocol <= protocol
+    }
+
+    /// The reply line to a `Hello` request; None for any other request
+    pub fn respond(&self, line: &str) -> Option<String> {
+        let request: Value = serde_json::from_str(line).ok()?;
+        if request["type"] != "Hello" {
+            return None;
+        }
+        Some(json!({ "status": "Hello", "hello": self }).to_string())
+    }
+}
+
+/// A daemon as seen by a client after the handshake
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Peer {
+    /// None for a daemon from before the handshake
+    pub hello: Option<Hello>,
+}
+
+impl Peer {
+    /// Protocol version to speak; 1 for a daemon from before the handshake
+    pub fn protocol(&self) -> u32 {
+        self.hello
+            .as_ref()
+            .map_or(1, |hello| hello.protocol.min(PROTOCOL_VERSION))
+    }
+
+    /// Whether the daemon understands a feature; a daemon from before the
+    /// handshake is assumed to understand none
+    pub fn supports(&self, feature: &str) -> bool {
+        self.hello.as_ref().is_some_and(|hello| hello.supports(feature))
+    }
+
+    /// Fail with `Unsupported` unless the daemon understands a feature
+    pub fn require(&self, feature: &str) -> Result<()> {
+        if self.supports(feature) {
+            return Ok(());
+        }
+        let service = self.hello.as_ref().map_or("daemon", |hello| hello.service.as_str());
+        Err(Error::Unsupported(format!("{} has no {} support", service, feature)))
+    }
+}
+
+/// Says hello to a daemon
+pub struct HelloClient {
+    socket_path: PathBuf,
+}
+
+impl HelloClient {
+    pub fn new(socket_path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: socket_path.into(),
+        }
+    }
+
+    /// Exchange versions; a daemon that doesn't know the request is a
+    /// [`Peer`] without a hello, and one whose protocol range doesn't
+    /// overlap ours is `Unsupported`
+    pub async fn hello(&self) -> Result<Peer> {
+        let body = json!({
+            "type": "Hello",
+            "protocol": PROTOCOL_VERSION,
+            "min_protocol": MIN_PROTOCOL_VERSION,
+        });
+        let reply = match request(&self.socket_path, body, HELLO_TIMEOUT).await {
+            Ok(reply) => reply,
+            Err(Error::RequestFailed(_)) => return Ok(Peer::default()),
+            Err(e) => return Err(e),
+        };
+
+        // Any other shape is an old daemon that took the line for a
+        // different request
+        let Some(hello) = reply.get("hello") else {
+            return Ok(Peer::default());
+        };
+        let hello: Hello = parse(hello.clone())?;
+        if !hello.compatible(MIN_PROTOCOL_VERSION, PROTOCOL_VERSION) {
+            return Err(Error::Unsupported(format!(
+                "{} speaks protocol {}-{}, we speak {}-{}",
+                hello.service, hello.min_protocol, hello.protocol, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+            )));
+        }
+        Ok(Peer { hello: Some(hello) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handshake() {
+        let hello = Hello::new("vesper", "0.1.0").with_features(&["streams"]);
+        assert!(hello.respond(r#"{"type": "ListDevices"}"#).is_none());
+
+        let reply: Value = serde_json::from_str(&hello.respond(r#"{"type": "Hello", "protocol": 1}"#).unwrap()).unwrap();
+        let peer = Peer {
+            hello: Some(parse(reply["hello"].clone()).unwrap()),
+        };
+        assert!(peer.supports("streams"));
+        assert!(peer.require("bluetooth").is_err());
+        assert_eq!(peer.protocol(), PROTOCOL_VERSION);
+
+        assert!(hello.compatible(MIN_PROTOCOL_VERSION, PROTOCOL_VERSION));
+        assert!(!hello.compatible(PROTOCOL_VERSION + 1, PROTOCOL_VERSION + 2));
+
+        let legacy = Peer::default();
+        assert!(!legacy.supports("streams"));
+        assert_eq!(legacy.protocol(), 1);
+    }
+}
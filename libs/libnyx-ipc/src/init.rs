@@ -3,18 +3,19 @@
 //! Client for communicating with nyx-init service manager.
 
 use crate::protocol::{ServiceRegistration, ServiceState, ServiceStatus, ServiceType};
+use crate::request::{connect, encode};
+use crate::transport::Stream;
 use crate::{paths, Error, Result};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::UnixStream;
 use tracing::debug;
 use uuid::Uuid;
 
 /// Init client
 pub struct InitClient {
     socket_path: PathBuf,
-    stream: Option<UnixStream>,
+    stream: Option<Stream>,
 }
 
 impl InitClient {
@@ -43,17 +44,7 @@ impl InitClient {
 
     /// Connect to init
     async fn connect_internal(&mut self) -> Result<()> {
-        let stream = UnixStream::connect(&self.socket_path)
-            .await
-            .map_err(|e| {
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    Error::ServiceUnavailable
-                } else {
-                    Error::ConnectionFailed(e.to_string())
-                }
-            })?;
-
-        self.stream = Some(stream);
+        self.stream = Some(connect(&self.socket_path).await?);
         Ok(())
     }
 
@@ -241,9 +232,7 @@ impl InitClient {
         let stream = self.stream.as_mut().ok_or(Error::ServiceUnavailable)?;
 
         // Serialize and send
-        let json = serde_json::to_string(request)
-            .map_err(|e| Error::ProtocolError(e.to_string()))?;
-        let message = json + "\n";
+        let message = encode(request)?;
 
         stream
             .write_all(message.as_bytes())
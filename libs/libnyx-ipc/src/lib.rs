@@ -16,14 +16,63 @@
 //! let init = InitClient::connect().await?;
 //! init.register_service("my-agent", pid).await?;
 //! ```
+//!
+//! Desktop daemons (network, VPN, audio, display, notifications, power,
+//! secrets, default applications), spectre and the compositor have one-shot clients used by the shell, Control and Settings.
+//! Removable media mounted by phantom is followed through [`media`], and
+//! the rest of its device database is listed through [`devices`].
+//!
+//! Every daemon answers the same health and metrics requests; see [`health`].
+//! Clients check a daemon's protocol version and features with the
+//! [`hello`] handshake before using anything it may not have yet.
+//! Requests carry the trace they belong to; see [`trace`]. Privileged
+//! operations are reported to scribe as [`audit`] events.
+//!
+//! Connections go over Unix sockets on hosted Linux and over shared memory
+//! rings on native Nyx (the `native` feature); see [`transport`].
 
+pub mod apps;
+pub mod audio;
+pub mod audit;
+pub mod compositor;
+pub mod devices;
+pub mod display;
 pub mod guardian;
+pub mod health;
+pub mod hello;
 pub mod init;
+pub mod media;
+pub mod network;
+pub mod notifications;
+pub mod power;
 pub mod protocol;
+mod request;
+pub mod secrets;
+pub mod session;
+pub mod trace;
+pub mod transport;
+pub mod vpn;
 
+pub use apps::AppsClient;
+pub use audio::AudioClient;
+pub use audit::{AuditClient, AuditEvent, AuditOutcome};
+pub use compositor::CompositorClient;
+pub use devices::DeviceClient;
+pub use display::DisplayClient;
 pub use guardian::GuardianClient;
+pub use health::{HealthClient, HealthReporter, HealthStatus};
+pub use hello::{Hello, HelloClient, Peer};
 pub use init::InitClient;
+pub use media::MediaClient;
+pub use network::NetworkClient;
+pub use notifications::NotificationsClient;
+pub use power::PowerClient;
 pub use protocol::{Message, Response};
+pub use secrets::SecretsClient;
+pub use session::SessionClient;
+pub use trace::TraceContext;
+pub use transport::{Listener, Stream, Transport};
+pub use vpn::VpnClient;
 
 /// Default socket paths
 pub mod paths {
@@ -31,6 +80,30 @@ pub mod paths {
     pub const GUARDIAN_SOCKET: &str = "/run/guardian/guardian.sock";
     /// Init control socket path
     pub const INIT_SOCKET: &str = "/run/nyx/init.sock";
+    /// wraith (network) socket path
+    pub const WRAITH_SOCKET: &str = "/run/wraith/wraith.sock";
+    /// vesper (audio) socket path
+    pub const VESPER_SOCKET: &str = "/run/vesper/vesper.sock";
+    /// iris (display) socket path
+    pub const IRIS_SOCKET: &str = "/run/iris/iris.sock";
+    /// herald (notifications) socket path
+    pub const HERALD_SOCKET: &str = "/run/herald/herald.sock";
+    /// slumber (power) socket path
+    pub const SLUMBER_SOCKET: &str = "/run/slumber/slumber.sock";
+    /// arachne (network agent, VPN) socket path
+    pub const ARACHNE_SOCKET: &str = "/run/arachne/arachne.sock";
+    /// cipher (secrets) socket path
+    pub const CIPHER_SOCKET: &str = "/run/cipher/cipher.sock";
+    /// summoner (launcher, default applications) socket path
+    pub const SUMMONER_SOCKET: &str = "/run/summoner/summoner.sock";
+    /// spectre (sessions, lock screen) socket path
+    pub const SPECTRE_SOCKET: &str = "/run/spectre/spectre.sock";
+    /// aether (compositor) control socket path
+    pub const AETHER_SOCKET: &str = "/run/aether/aether.sock";
+    /// scribe (journal, audit bus) socket path
+    pub const SCRIBE_SOCKET: &str = "/run/scribe/scribe.sock";
+    /// phantom (devices, removable media) socket path
+    pub const PHANTOM_SOCKET: &str = "/run/phantom/phantom.sock";
 }
 
 /// Common errors
@@ -46,6 +119,8 @@ pub enum Error {
     PermissionDenied(String),
     #[error("Service unavailable")]
     ServiceUnavailable,
+    #[error("Not supported: {0}")]
+    Unsupported(String),
     #[error("Protocol error: {0}")]
     ProtocolError(String),
     #[error("IO error: {0}")]
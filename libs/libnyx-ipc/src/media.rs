@@ -0,0 +1,147 @@
+//! Removable media IPC client
+//!
+//! Client for phantom's removable-media subsystem: the volumes it has
+//! found, mounting and unmounting them, and the event stream the file
+//! manager and the shell follow to show drives as they come and go.
+
+use crate::request::{check, parse, request, Connection, REQUEST_TIMEOUT};
+use crate::{paths, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Mounting waits on the filesystem driver, which can be slow on large
+/// or dirty media
+const MOUNT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A filesystem on removable media
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Volume {
+    /// Device node, e.g. `/dev/sdb1`
+    pub device: String,
+    pub fs_type: String,
+    pub label: Option<String>,
+    pub uuid: Option<String>,
+    /// Bytes
+    pub size: u64,
+    /// `usb`, `mmc`, or unknown
+    pub bus: Option<String>,
+    pub mount_point: Option<String>,
+    /// User the volume is mounted for; unset for system mounts
+    pub owner: Option<String>,
+}
+
+/// Pushed by phantom to subscribers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum MediaEvent {
+    /// Media with a readable filesystem appeared
+    Added { volume: Volume },
+    Mounted { volume: Volume },
+    Unmounted { device: String, mount_point: String },
+    /// The media was pulled or ejected
+    Removed { device: String },
+}
+
+/// Events pushed by phantom
+pub struct MediaEvents {
+    connection: Connection,
+}
+
+impl MediaEvents {
+    /// Wait for the next event, starting with an `Added` (and `Mounted`)
+    /// per volume already present; fails once phantom goes away
+    pub async fn next(&mut self) -> Result<MediaEvent> {
+        parse(check(self.connection.receive().await?)?)
+    }
+}
+
+/// Removable media client
+pub struct MediaClient {
+    socket_path: PathBuf,
+}
+
+impl Default for MediaClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MediaClient {
+    /// Create a client with default socket path
+    pub fn new() -> Self {
+        Self::with_socket(paths::PHANTOM_SOCKET)
+    }
+
+    /// Create a client with custom socket path
+    pub fn with_socket(path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: path.into(),
+        }
+    }
+
+    /// Volumes on removable media, mounted or not
+    pub async fn list(&self) -> Result<Vec<Volume>> {
+        let reply = self.send(json!({ "type": "ListMedia" }), REQUEST_TIMEOUT).await?;
+        parse(reply["volumes"].clone())
+    }
+
+    /// Mount a volume for the active session, or where its policy says
+    pub async fn mount(&self, device: &str) -> Result<Volume> {
+        let reply = self
+            .send(json!({ "type": "Mount", "data": { "device": device } }), MOUNT_TIMEOUT)
+            .await?;
+        parse(reply["volume"].clone())
+    }
+
+    /// Unmount a volume; fails with `RequestFailed` while it is in use
+    pub async fn unmount(&self, device: &str) -> Result<()> {
+        self.send(json!({ "type": "Unmount", "data": { "device": device } }), MOUNT_TIMEOUT)
+            .await
+            .map(drop)
+    }
+
+    /// Follow media as it is added, mounted, unmounted and removed
+    pub async fn subscribe(&self) -> Result<MediaEvents> {
+        let mut connection = Connection::open(&self.socket_path).await?;
+        connection.send(&json!({ "type": "SubscribeMedia" })).await?;
+        Ok(MediaEvents { connection })
+    }
+
+    async fn send(&self, body: Value, timeout: Duration) -> Result<Value> {
+        request(&self.socket_path, body, timeout).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events() {
+        let mounted: MediaEvent = parse(check(json!({
+            "event": "Mounted",
+            "volume": {
+                "device": "/dev/sdb1",
+                "fs_type": "vfat",
+                "label": "CAMERA",
+                "uuid": "1A2B-3C4D",
+                "size": 31914983424u64,
+                "bus": "usb",
+                "mount_point": "/run/media/ada/CAMERA",
+                "owner": "ada",
+            },
+        })).unwrap()).unwrap();
+        match mounted {
+            MediaEvent::Mounted { volume } => {
+                assert_eq!(volume.label.as_deref(), Some("CAMERA"));
+                assert_eq!(volume.owner.as_deref(), Some("ada"));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        let removed: MediaEvent = parse(check(json!({ "event": "Removed", "device": "/dev/sdb1" })).unwrap()).unwrap();
+        assert_eq!(removed, MediaEvent::Removed { device: "/dev/sdb1".into() });
+    }
+}
@@ -0,0 +1,583 @@
+//! Network IPC client
+//!
+//! Client for the wraith network manager: radio switches, interface status,
+//! Wi-Fi scanning and connections, connection profiles (including 802.1X
+//! ones for enterprise networks), network namespaces and a status stream.
+
+use crate::request::{check, parse, request, Connection, REQUEST_TIMEOUT};
+use crate::{paths, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fs::File;
+use std::os::fd::RawFd;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Scanning waits on the radio; connecting on association and DHCP
+const WIFI_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// 802.1X logins wait on the authentication server, then DHCP
+const AUTH_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Network interface as reported by wraith
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterfaceInfo {
+    pub name: String,
+    pub mac_address: Option<String>,
+    pub addresses: Vec<String>,
+    pub up: bool,
+    pub running: bool,
+    /// Ethernet, Wireless, Loopback, ...
+    pub interface_type: String,
+}
+
+impl InterfaceInfo {
+    pub fn is_wireless(&self) -> bool {
+        self.interface_type == "Wireless"
+    }
+
+    /// tun/tap and WireGuard interfaces
+    pub fn is_tunnel(&self) -> bool {
+        self.interface_type == "Tunnel"
+    }
+
+    /// Up and holding an address
+    pub fn is_connected(&self) -> bool {
+        self.running && !self.addresses.is_empty()
+    }
+}
+
+/// Overall network status
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkStatus {
+    pub interfaces: Vec<InterfaceInfo>,
+    pub dns_servers: Vec<String>,
+    pub hostname: String,
+    #[serde(default)]
+    pub wifi_enabled: bool,
+    #[serde(default)]
+    pub airplane_mode: bool,
+    /// 802.1X logins
+    #[serde(default)]
+    pub auth: Vec<AuthStatus>,
+}
+
+impl NetworkStatus {
+    /// First wireless interface, the one Wi-Fi requests go to
+    pub fn wireless_interface(&self) -> Option<&InterfaceInfo> {
+        self.interfaces.iter().find(|iface| iface.is_wireless())
+    }
+
+    /// Whether traffic can go out through a VPN: a tunnel is up and addressed
+    pub fn vpn_connected(&self) -> bool {
+        self.interfaces.iter().any(|iface| iface.is_tunnel() && iface.is_connected())
+    }
+}
+
+/// Where an interface's 802.1X login is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthStatus {
+    pub interface: String,
+    pub profile: String,
+    /// tls, peap or ttls
+    pub method: String,
+    /// Starting, Associating, Authenticating, Handshake, Authenticated or Failed
+    pub phase: String,
+    /// Why it failed
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl AuthStatus {
+    pub fn is_authenticated(&self) -> bool {
+        self.phase == "Authenticated"
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.phase == "Failed"
+    }
+}
+
+/// Wi-Fi network from a scan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    /// Signal in dBm
+    pub signal: i32,
+    pub security: String,
+    pub connected: bool,
+    #[serde(default)]
+    pub saved: bool,
+}
+
+impl WifiNetwork {
+    /// Signal strength as 0-100
+    pub fn signal_percent(&self) -> u8 {
+        (2 * (self.signal + 100)).clamp(0, 100) as u8
+    }
+
+    /// Needs a password to join
+    pub fn is_secured(&self) -> bool {
+        self.security != "Open"
+    }
+}
+
+/// How a profile configures addresses
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum IpConfig {
+    Dhcp,
+    Static {
+        /// Address in CIDR notation
+        address: String,
+        gateway: Option<String>,
+        dns: Vec<String>,
+    },
+}
+
+/// Profile options; ones this client doesn't edit are passed through
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileOptions {
+    #[serde(default)]
+    pub metered: bool,
+    #[serde(default)]
+    pub mtu: Option<u32>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// How a profile authenticates with 802.1X
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EapConfig {
+    /// tls, peap or ttls
+    pub method: String,
+    /// Inner method for PEAP and TTLS: mschapv2, gtc or pap
+    #[serde(default)]
+    pub phase2: Option<String>,
+    #[serde(default)]
+    pub anonymous_identity: Option<String>,
+    /// Only trust an authentication server in this domain
+    #[serde(default)]
+    pub domain_match: Option<String>,
+    /// Enterprise Wi-Fi network; unset for wired 802.1X
+    #[serde(default)]
+    pub ssid: Option<String>,
+}
+
+/// 802.1X credentials; wraith keeps them in cipher and never returns them
+#[derive(Clone, Default, Serialize)]
+pub struct EapCredentials {
+    pub identity: String,
+    pub password: Option<String>,
+    /// PEM
+    pub ca_cert: Option<String>,
+    /// PEM, for TLS
+    pub client_cert: Option<String>,
+    /// PEM, for TLS
+    pub private_key: Option<String>,
+    pub private_key_password: Option<String>,
+}
+
+/// Connection profile applied to matching interfaces
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkProfile {
+    pub name: String,
+    /// Interface name or pattern such as `eth*`
+    pub interface_match: String,
+    pub config: IpConfig,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub options: ProfileOptions,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eap: Option<EapConfig>,
+}
+
+/// Profile summary from a listing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub interface_match: String,
+    /// DHCP or Static
+    pub config_type: String,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub metered: bool,
+}
+
+/// How a network namespace is set up
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceConfig {
+    pub name: String,
+    /// Host interfaces moved in
+    #[serde(default)]
+    pub interfaces: Vec<NamespaceLink>,
+    /// A veth pair back to the host
+    #[serde(default)]
+    pub veth: Option<VethLink>,
+    /// Default route through this address
+    #[serde(default)]
+    pub gateway: Option<String>,
+    /// Default route through this interface, e.g. a tunnel
+    #[serde(default)]
+    pub default_interface: Option<String>,
+}
+
+/// An interface moved into a namespace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceLink {
+    pub name: String,
+    /// Address in CIDR notation
+    #[serde(default)]
+    pub address: Option<String>,
+}
+
+/// Point-to-point link between the host and a namespace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VethLink {
+    pub host_address: String,
+    pub namespace_address: String,
+}
+
+/// A network namespace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceInfo {
+    pub name: String,
+    /// Open this and `setns` to it to join
+    pub path: String,
+    pub active: bool,
+    /// Saved and set up at boot; ephemeral ones go once nothing runs in them
+    pub persistent: bool,
+    /// Processes inside
+    #[serde(default)]
+    pub pids: Vec<u32>,
+}
+
+/// Status pushed by wraith whenever it changes
+pub struct NetworkEvents {
+    connection: Connection,
+}
+
+impl NetworkEvents {
+    /// Wait for the next status, starting with the current one; fails
+    /// once wraith goes away
+    pub async fn next(&mut self) -> Result<NetworkStatus> {
+        loop {
+            let event = check(self.connection.receive().await?)?;
+            if event["event"] == "Status" {
+                return parse(event);
+            }
+        }
+    }
+}
+
+/// Network client
+pub struct NetworkClient {
+    socket_path: PathBuf,
+}
+
+impl Default for NetworkClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NetworkClient {
+    /// Create a client with default socket path
+    pub fn new() -> Self {
+        Self::with_socket(paths::WRAITH_SOCKET)
+    }
+
+    /// Create a client with custom socket path
+    pub fn with_socket(path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: path.into(),
+        }
+    }
+
+    /// Get interfaces, DNS and radio state
+    pub async fn status(&self) -> Result<NetworkStatus> {
+        let reply = self.send(json!({ "type": "GetStatus" }), REQUEST_TIMEOUT).await?;
+        parse(reply)
+    }
+
+    /// Turn the Wi-Fi radios on or off
+    pub async fn set_wifi_enabled(&self, enabled: bool) -> Result<()> {
+        self.send(
+            json!({ "type": "SetWifiEnabled", "data": { "enabled": enabled } }),
+            REQUEST_TIMEOUT,
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Block or unblock every radio
+    pub async fn set_airplane_mode(&self, enabled: bool) -> Result<()> {
+        self.send(
+            json!({ "type": "SetAirplaneMode", "data": { "enabled": enabled } }),
+            REQUEST_TIMEOUT,
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Scan for Wi-Fi networks, strongest first
+    pub async fn wifi_scan(&self, interface: &str) -> Result<Vec<WifiNetwork>> {
+        let reply = self
+            .send(
+                json!({ "type": "WifiScan", "data": { "interface": interface } }),
+                WIFI_TIMEOUT,
+            )
+            .await?;
+        parse(reply["networks"].clone())
+    }
+
+    /// Join a network; without a password a saved network is reused
+    pub async fn wifi_connect(&self, interface: &str, ssid: &str, password: Option<&str>) -> Result<()> {
+        self.send(
+            json!({
+                "type": "WifiConnect",
+                "data": { "interface": interface, "ssid": ssid, "password": password },
+            }),
+            WIFI_TIMEOUT,
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Leave the current network
+    pub async fn wifi_disconnect(&self, interface: &str) -> Result<()> {
+        self.send(
+            json!({ "type": "WifiDisconnect", "data": { "interface": interface } }),
+            REQUEST_TIMEOUT,
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Delete a saved network's credentials
+    pub async fn wifi_forget(&self, interface: &str, ssid: &str) -> Result<()> {
+        self.send(
+            json!({ "type": "WifiForget", "data": { "interface": interface, "ssid": ssid } }),
+            REQUEST_TIMEOUT,
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Replace the DNS servers
+    pub async fn set_dns(&self, servers: &[String]) -> Result<()> {
+        self.send(
+            json!({ "type": "SetDns", "data": { "servers": servers } }),
+            REQUEST_TIMEOUT,
+        )
+        .await
+        .map(drop)
+    }
+
+    /// List connection profiles
+    pub async fn profiles(&self) -> Result<Vec<ProfileInfo>> {
+        let reply = self.send(json!({ "type": "ListProfiles" }), REQUEST_TIMEOUT).await?;
+        parse(reply["profiles"].clone())
+    }
+
+    /// Get a profile in full
+    pub async fn profile(&self, name: &str) -> Result<NetworkProfile> {
+        let reply = self
+            .send(json!({ "type": "GetProfile", "data": { "name": name } }), REQUEST_TIMEOUT)
+            .await?;
+        parse(reply["profile"].clone())
+    }
+
+    /// Create or replace a profile
+    pub async fn save_profile(&self, profile: &NetworkProfile) -> Result<()> {
+        self.send(
+            json!({ "type": "SaveProfile", "data": { "profile": profile } }),
+            REQUEST_TIMEOUT,
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Apply a profile to an interface; DHCP, and 802.1X before it, can
+    /// take a while
+    pub async fn apply_profile(&self, interface: &str, profile: &str) -> Result<()> {
+        self.send(
+            json!({ "type": "ApplyProfile", "data": { "interface": interface, "profile": profile } }),
+            AUTH_TIMEOUT,
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Save the credentials an 802.1X profile logs in with
+    pub async fn set_eap_credentials(&self, profile: &str, credentials: &EapCredentials) -> Result<()> {
+        self.send(
+            json!({ "type": "SetEapCredentials", "data": { "profile": profile, "credentials": credentials } }),
+            REQUEST_TIMEOUT,
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Log an interface out of 802.1X
+    pub async fn eap_disconnect(&self, interface: &str) -> Result<()> {
+        self.send(
+            json!({ "type": "EapDisconnect", "data": { "interface": interface } }),
+            REQUEST_TIMEOUT,
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Network namespaces, set up or saved
+    pub async fn namespaces(&self) -> Result<Vec<NamespaceInfo>> {
+        let reply = self.send(json!({ "type": "ListNamespaces" }), REQUEST_TIMEOUT).await?;
+        parse(reply["namespaces"].clone())
+    }
+
+    /// Set up a network namespace
+    pub async fn create_namespace(&self, namespace: &NamespaceConfig, persistent: bool) -> Result<NamespaceInfo> {
+        let reply = self
+            .send(
+                json!({ "type": "CreateNamespace", "data": { "namespace": namespace, "persistent": persistent } }),
+                REQUEST_TIMEOUT,
+            )
+            .await?;
+        parse(reply["namespace"].clone())
+    }
+
+    /// A namespace to start a process in, set up from its saved definition
+    /// if it isn't yet
+    pub async fn enter_namespace(&self, name: &str) -> Result<NamespaceInfo> {
+        let reply = self
+            .send(json!({ "type": "EnterNamespace", "data": { "name": name } }), REQUEST_TIMEOUT)
+            .await?;
+        parse(reply["namespace"].clone())
+    }
+
+    /// Enter a namespace and open it for a process about to start
+    ///
+    /// Keep the file until the process has spawned and join it from
+    /// `pre_exec` with [`join_namespace`].
+    pub async fn open_namespace(&self, name: &str) -> Result<File> {
+        let namespace = self.enter_namespace(name).await?;
+        File::open(&namespace.path).map_err(|e| {
+            std::io::Error::new(e.kind(), format!("Failed to open {}: {}", namespace.path, e)).into()
+        })
+    }
+
+    /// Tear down and forget a namespace; `force` kills what still runs in it
+    pub async fn delete_namespace(&self, name: &str, force: bool) -> Result<()> {
+        self.send(
+            json!({ "type": "DeleteNamespace", "data": { "name": name, "force": force } }),
+            REQUEST_TIMEOUT,
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Follow status changes
+    pub async fn subscribe(&self) -> Result<NetworkEvents> {
+        let mut connection = Connection::open(&self.socket_path).await?;
+        connection.send(&json!({ "type": "Subscribe" })).await?;
+        Ok(NetworkEvents { connection })
+    }
+
+    async fn send(&self, body: Value, timeout: Duration) -> Result<Value> {
+        request(&self.socket_path, body, timeout).await
+    }
+}
+
+/// Move the calling thread into the network namespace open at `fd`
+///
+/// Makes the one syscall and allocates nothing, so it is safe between fork
+/// and exec.
+pub fn join_namespace(fd: RawFd) -> std::io::Result<()> {
+    // SAFETY: setns only reads the descriptor
+    if unsafe { libc::setns(fd, libc::CLONE_NEWNET) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wifi_network() {
+        let network: WifiNetwork = serde_json::from_value(json!({
+            "ssid": "Nyx",
+            "signal": -55,
+            "security": "Wpa2Psk",
+            "connected": true,
+        }))
+        .unwrap();
+
+        assert_eq!(network.signal_percent(), 90);
+        assert!(network.is_secured());
+        assert!(!network.saved);
+    }
+
+    #[test]
+    fn test_profile_keeps_unedited_options() {
+        let stored = json!({
+            "name": "Wired Connection",
+            "interface_match": "eth*",
+            "config": { "type": "Static", "address": "10.0.0.2/24", "gateway": "10.0.0.1", "dns": [] },
+            "priority": 100,
+            "options": { "mtu": null, "ipv6": "Auto", "routes": [], "metered": false },
+        });
+        let mut profile: NetworkProfile = serde_json::from_value(stored).unwrap();
+        profile.options.metered = true;
+
+        let saved = serde_json::to_value(&profile).unwrap();
+        assert_eq!(saved["options"]["metered"], true);
+        assert_eq!(saved["options"]["ipv6"], "Auto");
+        assert_eq!(saved["config"]["type"], "Static");
+        assert!(saved.get("eap").is_none());
+    }
+
+    #[test]
+    fn test_enterprise_profile_and_status() {
+        let profile: NetworkProfile = serde_json::from_value(json!({
+            "name": "Corp",
+            "interface_match": "wl*",
+            "config": { "type": "Dhcp" },
+            "eap": { "method": "peap", "domain_match": "corp.example", "ssid": "Corp" },
+        }))
+        .unwrap();
+        let eap = profile.eap.unwrap();
+        assert_eq!(eap.method, "peap");
+        assert_eq!(eap.phase2, None);
+
+        let status: NetworkStatus = serde_json::from_value(json!({
+            "interfaces": [],
+            "dns_servers": [],
+            "hostname": "nyx",
+            "auth": [{ "interface": "wlan0", "profile": "Corp", "method": "peap", "phase": "Failed", "message": "The server rejected the credentials" }],
+        }))
+        .unwrap();
+        assert!(status.auth[0].is_failed());
+    }
+
+    #[test]
+    fn test_namespace_reply() {
+        let reply = check(json!({
+            "status": "Namespace",
+            "namespace": { "name": "vpn-only", "path": "/run/netns/vpn-only", "active": true, "persistent": true, "pids": [] },
+        }))
+        .unwrap();
+        let namespace: NamespaceInfo = parse(reply["namespace"].clone()).unwrap();
+        assert_eq!(namespace.path, "/run/netns/vpn-only");
+
+        let config = NamespaceConfig {
+            name: "vpn-only".into(),
+            default_interface: Some("wg0".into()),
+            ..Default::default()
+        };
+        assert_eq!(serde_json::to_value(&config).unwrap()["interfaces"], json!([]));
+    }
+}
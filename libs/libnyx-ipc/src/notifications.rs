@@ -0,0 +1,203 @@
+//! Notifications IPC client
+//!
+//! Client for the herald notification daemon: Do Not Disturb, history,
+//! closing notifications and invoking their actions, and the event stream
+//! the shell draws popups from.
+
+use crate::request::{check, parse, request, Connection, REQUEST_TIMEOUT};
+use crate::{paths, Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+/// Notification urgency
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// A button on a notification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationAction {
+    pub id: String,
+    pub label: String,
+}
+
+/// A notification herald is showing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: u32,
+    pub app_name: String,
+    pub summary: String,
+    pub body: Option<String>,
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub urgency: Urgency,
+    #[serde(default)]
+    pub actions: Vec<NotificationAction>,
+    /// Stays after an action is invoked
+    #[serde(default)]
+    pub resident: bool,
+    /// Unix seconds
+    pub timestamp: u64,
+}
+
+/// A notification in herald's history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: u32,
+    pub app_name: String,
+    pub summary: String,
+    pub body: Option<String>,
+    /// Unix seconds
+    pub timestamp: u64,
+    pub closed_at: Option<u64>,
+}
+
+/// Pushed by herald to subscribers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum NotificationEvent {
+    /// Shown, or replaced in place under the same ID
+    Posted { notification: Notification },
+    /// Expired, dismissed, closed, or closed by invoking an action
+    Closed { id: u32, reason: String },
+    Dnd { active: bool },
+}
+
+/// Events pushed by herald
+pub struct NotificationEvents {
+    connection: Connection,
+}
+
+impl NotificationEvents {
+    /// Wait for the next event, starting with the Do Not Disturb state;
+    /// fails once herald goes away
+    pub async fn next(&mut self) -> Result<NotificationEvent> {
+        parse(check(self.connection.receive().await?)?)
+    }
+}
+
+/// Notifications client
+pub struct NotificationsClient {
+    socket_path: PathBuf,
+}
+
+impl Default for NotificationsClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NotificationsClient {
+    /// Create a client with default socket path
+    pub fn new() -> Self {
+        Self::with_socket(paths::HERALD_SOCKET)
+    }
+
+    /// Create a client with custom socket path
+    pub fn with_socket(path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: path.into(),
+        }
+    }
+
+    /// Whether Do Not Disturb is on, manually or by schedule
+    pub async fn dnd_active(&self) -> Result<bool> {
+        let reply = self.send(json!({ "type": "GetDndStatus" })).await?;
+        reply["data"]["active"]
+            .as_bool()
+            .ok_or_else(|| Error::ProtocolError("Missing DND state".into()))
+    }
+
+    /// Turn Do Not Disturb on or off
+    pub async fn set_dnd(&self, enabled: bool) -> Result<()> {
+        let kind = if enabled { "EnableDnd" } else { "DisableDnd" };
+        self.send(json!({ "type": kind })).await.map(drop)
+    }
+
+    /// Most recent notifications first
+    pub async fn history(&self, limit: usize) -> Result<Vec<HistoryEntry>> {
+        let reply = self
+            .send(json!({ "type": "GetHistory", "data": { "limit": limit } }))
+            .await?;
+        parse(reply["data"]["history"].clone())
+    }
+
+    /// Forget one app's notifications
+    pub async fn clear_app(&self, app_name: &str) -> Result<()> {
+        self.send(json!({ "type": "ClearHistoryByApp", "data": { "app_name": app_name } }))
+            .await
+            .map(drop)
+    }
+
+    /// Dismiss a notification
+    pub async fn close(&self, id: u32) -> Result<()> {
+        self.send(json!({ "type": "CloseNotification", "data": { "id": id } }))
+            .await
+            .map(drop)
+    }
+
+    /// Press one of a notification's buttons
+    pub async fn invoke_action(&self, id: u32, action_id: &str) -> Result<()> {
+        self.send(json!({ "type": "InvokeAction", "data": { "id": id, "action_id": action_id } }))
+            .await
+            .map(drop)
+    }
+
+    /// Follow notifications as they are shown and closed
+    pub async fn subscribe(&self) -> Result<NotificationEvents> {
+        let mut connection = Connection::open(&self.socket_path).await?;
+        connection.send(&json!({ "type": "Subscribe" })).await?;
+        Ok(NotificationEvents { connection })
+    }
+
+    async fn send(&self, body: Value) -> Result<Value> {
+        request(&self.socket_path, body, REQUEST_TIMEOUT).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events() {
+        let posted: NotificationEvent = serde_json::from_value(json!({
+            "event": "Posted",
+            "notification": {
+                "id": 4,
+                "app_name": "mail",
+                "summary": "New message",
+                "body": null,
+                "icon": null,
+                "urgency": "critical",
+                "actions": [{ "id": "open", "label": "Open" }],
+                "resident": false,
+                "timestamp": 1700000000,
+            },
+        }))
+        .unwrap();
+        match posted {
+            NotificationEvent::Posted { notification } => {
+                assert_eq!(notification.urgency, Urgency::Critical);
+                assert_eq!(notification.actions[0].label, "Open");
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        let closed: NotificationEvent =
+            serde_json::from_value(json!({ "event": "Closed", "id": 4, "reason": "expired" })).unwrap();
+        assert_eq!(
+            closed,
+            NotificationEvent::Closed {
+                id: 4,
+                reason: "expired".into()
+            }
+        );
+    }
+}
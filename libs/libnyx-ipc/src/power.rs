@@ -0,0 +1,97 @@
+//! Power IPC client
+//!
+//! Client for the slumber power daemon: power profiles, the power supply
+//! and suspend.
+
+use crate::request::{check, parse, request, Connection, REQUEST_TIMEOUT};
+use crate::{paths, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+/// Power profile state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerProfiles {
+    pub current: String,
+    pub available: Vec<String>,
+}
+
+/// Where power comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerSupply {
+    pub on_ac_power: bool,
+    /// Combined battery charge, percent
+    pub total_capacity: u8,
+}
+
+/// Profile switches pushed by slumber
+pub struct ProfileEvents {
+    connection: Connection,
+}
+
+impl ProfileEvents {
+    /// The active profile first, then each switch; fails once slumber goes away
+    pub async fn next(&mut self) -> Result<PowerProfiles> {
+        parse(check(self.connection.receive().await?)?)
+    }
+}
+
+/// Power client
+pub struct PowerClient {
+    socket_path: PathBuf,
+}
+
+impl Default for PowerClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PowerClient {
+    /// Create a client with default socket path
+    pub fn new() -> Self {
+        Self::with_socket(paths::SLUMBER_SOCKET)
+    }
+
+    /// Create a client with custom socket path
+    pub fn with_socket(path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: path.into(),
+        }
+    }
+
+    /// Get the current and available power profiles
+    pub async fn profiles(&self) -> Result<PowerProfiles> {
+        let reply = self.send(json!({ "type": "GetProfile" })).await?;
+        parse(reply["data"].clone())
+    }
+
+    /// Whether the machine is on AC power, and the battery charge
+    pub async fn power_supply(&self) -> Result<PowerSupply> {
+        let reply = self.send(json!({ "type": "GetPowerStatus" })).await?;
+        parse(reply["data"].clone())
+    }
+
+    /// Switch power profile
+    pub async fn set_profile(&self, name: &str) -> Result<()> {
+        self.send(json!({ "type": "SetProfile", "name": name }))
+            .await
+            .map(drop)
+    }
+
+    /// Suspend to RAM
+    pub async fn suspend(&self) -> Result<()> {
+        self.send(json!({ "type": "Suspend" })).await.map(drop)
+    }
+
+    /// Follow profile switches, including automatic ones on AC changes
+    pub async fn subscribe_profiles(&self) -> Result<ProfileEvents> {
+        let mut connection = Connection::open(&self.socket_path).await?;
+        connection.send(&json!({ "type": "SubscribeProfile" })).await?;
+        Ok(ProfileEvents { connection })
+    }
+
+    async fn send(&self, body: Value) -> Result<Value> {
+        request(&self.socket_path, body, REQUEST_TIMEOUT).await
+    }
+}
@@ -1,5 +1,6 @@
 //! Common protocol types for Nyx IPC
 
+use crate::trace::TraceContext;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -14,6 +15,9 @@ pub struct Message {
     pub msg_type: String,
     /// Message payload
     pub payload: serde_json::Value,
+    /// Trace the message belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<TraceContext>,
 }
 
 impl Message {
@@ -23,6 +27,7 @@ impl Message {
             id: Uuid::new_v4(),
             msg_type: msg_type.into(),
             payload: serde_json::to_value(payload).unwrap_or(serde_json::Value::Null),
+            trace: Some(TraceContext::outgoing()),
         }
     }
 
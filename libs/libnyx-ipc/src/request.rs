@@ -0,0 +1,111 @@
+//! Requests to system daemons
+//!
+//! The desktop daemons (wraith, vesper, iris, herald, slumber, arachne,
+//! cipher) answer one line of JSON with one line of JSON, and report
+//! failures as `{"status": "Error", "message": ...}`.
+
+use crate::trace::TraceContext;
+use crate::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+use std::time::Duration;
+use crate::transport::{self, Stream};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// How long a daemon gets to answer a quick request
+pub(crate) const REQUEST_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Connect to a daemon; a missing or refusing socket means the daemon isn't running
+pub(crate) async fn connect(socket: &Path) -> Result<Stream> {
+    transport::connect(socket).await.map_err(|e| {
+        if matches!(e.kind(), std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused) {
+            Error::ServiceUnavailable
+        } else {
+            Error::ConnectionFailed(e.to_string())
+        }
+    })
+}
+
+/// Connection for several requests in a row (sessions, subscriptions)
+pub(crate) struct Connection {
+    stream: BufReader<Stream>,
+}
+
+impl Connection {
+    pub(crate) async fn open(socket: &Path) -> Result<Self> {
+        Ok(Self {
+            stream: BufReader::new(connect(socket).await?),
+        })
+    }
+
+    /// Send one request line, with daemon errors as `RequestFailed`
+    pub(crate) async fn call(&mut self, request: &Value) -> Result<Value> {
+        self.send(request).await?;
+        check(self.receive().await?)
+    }
+
+    pub(crate) async fn send(&mut self, request: &Value) -> Result<()> {
+        let stream = self.stream.get_mut();
+        stream.write_all(encode(request)?.as_bytes()).await?;
+        stream.flush().await?;
+        Ok(())
+    }
+
+    /// Next line from the daemon
+    pub(crate) async fn receive(&mut self) -> Result<Value> {
+        let mut line = String::new();
+        if self.stream.read_line(&mut line).await? == 0 {
+            return Err(Error::ConnectionFailed("Connection closed".into()));
+        }
+        serde_json::from_str(&line).map_err(|e| Error::ProtocolError(format!("Invalid reply: {}", e)))
+    }
+}
+
+/// Send a request and return the reply, with daemon errors as `RequestFailed`
+pub(crate) async fn request(socket: &Path, request: Value, timeout: Duration) -> Result<Value> {
+    let send = async { Connection::open(socket).await?.call(&request).await };
+    tokio::time::timeout(timeout, send)
+        .await
+        .map_err(|_| Error::Timeout)?
+}
+
+/// A request line carrying the current trace
+pub(crate) fn encode(request: &impl Serialize) -> Result<String> {
+    let mut request = serde_json::to_value(request).map_err(|e| Error::ProtocolError(e.to_string()))?;
+    TraceContext::outgoing().inject(&mut request);
+    Ok(request.to_string() + "\n")
+}
+
+/// Turn an error reply into `RequestFailed`
+pub(crate) fn check(response: Value) -> Result<Value> {
+    if response["status"] == "Error" {
+        Err(Error::RequestFailed(
+            response["message"]
+                .as_str()
+                .unwrap_or("Request failed")
+                .to_string(),
+        ))
+    } else {
+        Ok(response)
+    }
+}
+
+/// Deserialize part of a reply
+pub(crate) fn parse<T: DeserializeOwned>(value: Value) -> Result<T> {
+    serde_json::from_value(value).map_err(|e| Error::ProtocolError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_error_reply() {
+        let err = check(json!({ "status": "Error", "message": "No such stream" })).unwrap_err();
+        assert!(matches!(err, Error::RequestFailed(ref m) if m == "No such stream"));
+        assert!(check(json!({ "status": "Success", "message": "ok" })).is_ok());
+    }
+}
@@ -0,0 +1,105 @@
+//! Secrets IPC client
+//!
+//! Client for the cipher keyring, for applications that keep credentials
+//! (Wi-Fi passwords and the like) in the user's keyring rather than in
+//! their own files. The keyring must be unlocked.
+
+use crate::request::{Connection, REQUEST_TIMEOUT};
+use crate::{paths, Error, Result};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Secrets client
+pub struct SecretsClient {
+    socket_path: PathBuf,
+}
+
+impl Default for SecretsClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SecretsClient {
+    /// Create a client with default socket path
+    pub fn new() -> Self {
+        Self::with_socket(paths::CIPHER_SOCKET)
+    }
+
+    /// Create a client with custom socket path
+    pub fn with_socket(path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: path.into(),
+        }
+    }
+
+    /// Store a secret, creating its collection on first use
+    pub async fn store(&self, collection: &str, id: &str, label: &str, secret: &str) -> Result<()> {
+        let mut connection = self.connect().await?;
+        let create = json!({
+            "type": "CreateCollection",
+            "data": { "name": collection, "label": collection },
+        });
+        match connection.call(&create).await {
+            Ok(_) => {}
+            Err(Error::RequestFailed(message)) if message.contains("already exists") => {}
+            Err(e) => return Err(e),
+        }
+
+        let store = json!({
+            "type": "StoreSecret",
+            "data": {
+                "collection": collection,
+                "id": id,
+                "label": label,
+                "secret": secret,
+                "attributes": HashMap::<String, String>::new(),
+            },
+        });
+        connection.call(&store).await.map(drop)
+    }
+
+    /// Look a secret up; None if it was never stored
+    pub async fn lookup(&self, collection: &str, id: &str) -> Result<Option<String>> {
+        let mut connection = self.connect().await?;
+        let session = connection.call(&json!({ "type": "OpenSession" })).await?;
+        let token = session["token"]
+            .as_str()
+            .ok_or_else(|| Error::ProtocolError("No session token".into()))?
+            .to_string();
+
+        let secret = connection
+            .call(&json!({
+                "type": "GetSecret",
+                "data": { "collection": collection, "id": id, "session": token },
+            }))
+            .await;
+        let _ = connection
+            .call(&json!({ "type": "CloseSession", "data": { "token": token } }))
+            .await;
+
+        match secret {
+            Ok(reply) => Ok(reply["value"].as_str().map(str::to_string)),
+            Err(Error::RequestFailed(message)) if message.contains("not found") => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Delete a secret; deleting one that isn't there is not an error
+    pub async fn delete(&self, collection: &str, id: &str) -> Result<()> {
+        let mut connection = self.connect().await?;
+        let delete = json!({ "type": "DeleteSecret", "data": { "collection": collection, "id": id } });
+        match connection.call(&delete).await {
+            Ok(_) => Ok(()),
+            Err(Error::RequestFailed(message)) if message.contains("not found") => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn connect(&self) -> Result<Connection> {
+        tokio::time::timeout(REQUEST_TIMEOUT, Connection::open(&self.socket_path))
+            .await
+            .map_err(|_| Error::Timeout)?
+    }
+}
@@ -0,0 +1,238 @@
+//! Session IPC client
+//!
+//! Client for the spectre session manager: locking a session, unlocking it
+//! with its user's password, the lock/unlock event stream the shell puts
+//! the lock screen up from, which user is in front of a seat, and the
+//! login screen's users and theme.
+
+use crate::request::{check, parse, request, Connection, REQUEST_TIMEOUT};
+use crate::{paths, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// A password check goes through PAM and may be delayed after failures
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pushed by spectre to subscribers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum SessionEvent {
+    /// The session must be covered by a lock screen
+    Locked { session: String },
+    Unlocked { session: String },
+}
+
+/// A logged-in user's session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserSession {
+    pub id: String,
+    pub username: String,
+    pub uid: u32,
+    pub seat: String,
+    /// starting, active, locked, closing or ended
+    #[serde(default)]
+    pub state: String,
+}
+
+impl UserSession {
+    pub fn is_locked(&self) -> bool {
+        self.state == "locked"
+    }
+}
+
+/// A user the login screen offers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoginUser {
+    pub username: String,
+    pub display_name: String,
+    pub uid: u32,
+    /// Path to the user's picture
+    pub avatar: Option<String>,
+}
+
+/// Login screen theme as spectre resolved it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GreeterTheme {
+    pub name: String,
+    pub background: GreeterBackground,
+    pub colors: GreeterColors,
+}
+
+/// What fills the login screen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum GreeterBackground {
+    /// `#RRGGBB`
+    Color(String),
+    /// Image path
+    Image(String),
+}
+
+/// Login screen colors, as `#RRGGBB`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GreeterColors {
+    pub background: String,
+    pub surface: String,
+    pub text: String,
+    pub text_muted: String,
+    pub accent: String,
+    pub error: String,
+}
+
+/// Events pushed by spectre
+pub struct SessionEvents {
+    connection: Connection,
+}
+
+impl SessionEvents {
+    /// Wait for the next event, starting with the sessions already locked;
+    /// fails once spectre goes away
+    pub async fn next(&mut self) -> Result<SessionEvent> {
+        parse(check(self.connection.receive().await?)?)
+    }
+}
+
+/// Session client
+pub struct SessionClient {
+    socket_path: PathBuf,
+}
+
+impl Default for SessionClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionClient {
+    /// Create a client with default socket path
+    pub fn new() -> Self {
+        Self::with_socket(paths::SPECTRE_SOCKET)
+    }
+
+    /// Create a client with custom socket path
+    pub fn with_socket(path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: path.into(),
+        }
+    }
+
+    /// Lock a session, or the active one
+    pub async fn lock(&self, id: Option<&str>) -> Result<()> {
+        request(
+            &self.socket_path,
+            json!({ "type": "LockSession", "data": { "id": id } }),
+            REQUEST_TIMEOUT,
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Unlock a locked session; fails with `RequestFailed` on a wrong password
+    pub async fn unlock(&self, id: &str, password: &str) -> Result<()> {
+        request(
+            &self.socket_path,
+            json!({ "type": "UnlockWithPassword", "data": { "id": id, "password": password } }),
+            AUTH_TIMEOUT,
+        )
+        .await
+        .map(drop)
+    }
+
+    /// The session in the foreground of `seat` (`seat0` by default); fails
+    /// with `RequestFailed` if nobody is logged in there
+    pub async fn active_session(&self, seat: Option<&str>) -> Result<UserSession> {
+        let reply = request(
+            &self.socket_path,
+            json!({ "type": "GetActiveSession", "data": { "seat": seat } }),
+            REQUEST_TIMEOUT,
+        )
+        .await?;
+        parse(reply)
+    }
+
+    /// Users the login screen offers
+    pub async fn login_users(&self) -> Result<Vec<LoginUser>> {
+        let reply = request(&self.socket_path, json!({ "type": "ListUsers" }), REQUEST_TIMEOUT).await?;
+        parse(reply["users"].clone())
+    }
+
+    /// Theme names the login screen can use
+    pub async fn greeter_themes(&self) -> Result<Vec<String>> {
+        let reply = request(&self.socket_path, json!({ "type": "ListGreeterThemes" }), REQUEST_TIMEOUT).await?;
+        parse(reply["themes"].clone())
+    }
+
+    /// The login screen's current theme
+    pub async fn greeter_theme(&self) -> Result<GreeterTheme> {
+        let reply = request(&self.socket_path, json!({ "type": "GetGreeterTheme" }), REQUEST_TIMEOUT).await?;
+        parse(reply["theme"].clone())
+    }
+
+    /// What the login screen would look like with a theme and background
+    /// (`#RRGGBB` or an image path), without changing it
+    pub async fn preview_greeter_theme(&self, theme: &str, background: Option<&str>) -> Result<GreeterTheme> {
+        let reply = request(
+            &self.socket_path,
+            json!({ "type": "PreviewGreeterTheme", "data": { "theme": theme, "background": background } }),
+            REQUEST_TIMEOUT,
+        )
+        .await?;
+        parse(reply["theme"].clone())
+    }
+
+    /// Change the login screen's theme; only root or the user at the seat
+    /// may
+    pub async fn set_greeter_theme(&self, theme: &str, background: Option<&str>) -> Result<GreeterTheme> {
+        let reply = request(
+            &self.socket_path,
+            json!({ "type": "SetGreeterTheme", "data": { "theme": theme, "background": background } }),
+            REQUEST_TIMEOUT,
+        )
+        .await?;
+        parse(reply["theme"].clone())
+    }
+
+    /// Follow sessions as they are locked and unlocked
+    pub async fn subscribe(&self) -> Result<SessionEvents> {
+        let mut connection = Connection::open(&self.socket_path).await?;
+        connection.send(&json!({ "type": "Subscribe" })).await?;
+        Ok(SessionEvents { connection })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events() {
+        let locked: SessionEvent = parse(check(json!({ "event": "Locked", "session": "3" })).unwrap()).unwrap();
+        assert_eq!(locked, SessionEvent::Locked { session: "3".into() });
+
+        let session: UserSession = parse(check(json!({
+            "status": "Session", "id": "2", "username": "ada", "uid": 1000, "seat": "seat0",
+            "state": "active", "session_type": "wayland"
+        })).unwrap()).unwrap();
+        assert_eq!(session.uid, 1000);
+        assert!(!session.is_locked());
+
+        let reply = check(json!({
+            "status": "GreeterTheme",
+            "theme": {
+                "name": "nord",
+                "background": { "kind": "image", "value": "/grimoire/wallpapers/fjord.jpg" },
+                "colors": {
+                    "background": "#2E3440", "surface": "#3B4252", "text": "#ECEFF4",
+                    "text_muted": "#D8DEE9", "accent": "#88C0D0", "error": "#BF616A"
+                }
+            }
+        })).unwrap();
+        let theme: GreeterTheme = parse(reply["theme"].clone()).unwrap();
+        assert_eq!(theme.background, GreeterBackground::Image("/grimoire/wallpapers/fjord.jpg".into()));
+
+        let refused = check(json!({ "status": "Error", "message": "Subscribe must be sent on its own connection" }));
+        assert!(refused.is_err());
+    }
+}
@@ -0,0 +1,163 @@
+//! Trace propagation across IPC
+//!
+//! A request line may carry the trace it belongs to as
+//! `"trace": {"trace_id": ..., "span_id": ...}` next to its other fields,
+//! which daemons' request types ignore. Clients in this crate attach the
+//! current trace to every request they send, starting a new trace when
+//! there is none, and a daemon runs each handler under a child span:
+//!
+//! ```ignore
+//! let response = trace::traced(&line, process_request(&line)).await;
+//! ```
+//!
+//! The handler's log lines then carry `trace_id` and `span_id`, and any
+//! request it makes in turn continues the same trace, so one user action
+//! (e.g. installing a package) can be followed across daemons in the
+//! journal with `scribe query trace_id=<id>`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::future::Future;
+use tracing::Instrument;
+use uuid::Uuid;
+
+tokio::task_local! {
+    static CURRENT: TraceContext;
+}
+
+/// Where a request sits in a trace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceContext {
+    /// Shared by every span of one user action
+    pub trace_id: String,
+    /// This span
+    pub span_id: String,
+    /// The span that made the request; not sent on the wire
+    #[serde(default, skip_serializing)]
+    pub parent_span_id: Option<String>,
+}
+
+impl TraceContext {
+    /// Start a new trace
+    pub fn root() -> Self {
+        Self {
+            trace_id: Uuid::new_v4().simple().to_string(),
+            span_id: new_span_id(),
+            parent_span_id: None,
+        }
+    }
+
+    /// A span within this one
+    pub fn child(&self) -> Self {
+        Self {
+            trace_id: self.trace_id.clone(),
+            span_id: new_span_id(),
+            parent_span_id: Some(self.span_id.clone()),
+        }
+    }
+
+    /// The trace the running task is part of
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// The trace for a request about to be sent: the current one, or a new one
+    pub fn outgoing() -> Self {
+        Self::current().unwrap_or_else(Self::root)
+    }
+
+    /// The trace a request carries
+    pub fn extract(request: &Value) -> Option<Self> {
+        serde_json::from_value(request.get("trace")?.clone()).ok()
+    }
+
+    /// Attach this trace to a request
+    pub fn inject(&self, request: &mut Value) {
+        if let (Value::Object(fields), Ok(trace)) = (request, serde_json::to_value(self)) {
+            fields.insert("trace".to_string(), trace);
+        }
+    }
+
+    /// A tracing span recording this trace, for a request of some kind
+    pub fn span(&self, request: &str) -> tracing::Span {
+        tracing::info_span!(
+            "ipc",
+            request,
+            trace_id = %self.trace_id,
+            span_id = %self.span_id,
+            parent_span_id = self.parent_span_id.as_deref(),
+        )
+    }
+
+    /// Run a future as part of this trace
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+}
+
+/// Handle one request line in a child span of the trace it carries, or in
+/// a new trace if it carries none
+pub async fn traced<F: Future>(line: &str, handler: F) -> F::Output {
+    let request: Value = serde_json::from_str(line).unwrap_or_default();
+    let context = match TraceContext::extract(&request) {
+        Some(caller) => caller.child(),
+        None => TraceContext::root(),
+    };
+    let kind = request["type"].as_str().unwrap_or("request");
+    let span = context.span(kind);
+    context.scope(handler.instrument(span)).await
+}
+
+fn new_span_id() -> String {
+    let mut id = Uuid::new_v4().simple().to_string();
+    id.truncate(16);
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Deserialize)]
+    #[serde(tag = "type")]
+    enum Request {
+        Status,
+        Install { name: String },
+    }
+
+    #[derive(Deserialize)]
+    #[serde(tag = "type", content = "data")]
+    enum AdjacentRequest {
+        Status,
+        Install { name: String },
+    }
+
+    #[tokio::test]
+    async fn test_propagation() {
+        let caller = TraceContext::root();
+        let mut install = json!({ "type": "Install", "name": "ripgrep" });
+        caller.inject(&mut install);
+
+        // Daemon request types ignore the field
+        assert!(matches!(
+            serde_json::from_value(install.clone()).unwrap(),
+            Request::Install { name } if name == "ripgrep"
+        ));
+        let mut status = json!({ "type": "Status" });
+        caller.inject(&mut status);
+        assert!(matches!(serde_json::from_value(status.clone()).unwrap(), Request::Status));
+        assert!(matches!(serde_json::from_value(status).unwrap(), AdjacentRequest::Status));
+        let mut install_data = json!({ "type": "Install", "data": { "name": "ripgrep" } });
+        caller.inject(&mut install_data);
+        assert!(matches!(
+            serde_json::from_value(install_data).unwrap(),
+            AdjacentRequest::Install { name } if name == "ripgrep"
+        ));
+
+        let handler = traced(&install.to_string(), async { TraceContext::outgoing() }).await;
+        assert_eq!(handler.trace_id, caller.trace_id);
+        assert_eq!(handler.parent_span_id, Some(caller.span_id));
+        assert!(TraceContext::current().is_none());
+    }
+}
@@ -0,0 +1,230 @@
+//! Connections to system daemons
+//!
+//! Clients and daemons talk over a [`Stream`] without caring how it is
+//! carried. On hosted Linux that is the Unix socket at the daemon's path.
+//! Built with the `native` feature for Nyx, paths listed in the endpoint
+//! [`Directory`] (which init passes down in `NYX_IPC_ENDPOINTS`) are served
+//! over kernel IPC instead: each connection gets a shared memory region
+//! holding one byte ring per direction, with a notification as doorbell.
+//! Paths missing from the directory keep using Unix sockets.
+//!
+//! ```rust,ignore
+//! // Daemon
+//! let listener = Listener::bind(Path::new(paths::HERALD_SOCKET))?;
+//! let stream = listener.accept().await?;
+//!
+//! // Client
+//! let stream = transport::connect(Path::new(paths::HERALD_SOCKET)).await?;
+//! ```
+
+#[cfg(feature = "native")]
+mod ring;
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{UnixListener, UnixStream};
+
+/// Environment variable holding the endpoint directory
+pub const ENDPOINTS_ENV: &str = "NYX_IPC_ENDPOINTS";
+
+/// How connections to a daemon path are carried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// Unix socket at the path
+    Unix,
+    /// Shared memory rings, set up through a kernel IPC endpoint
+    Ring {
+        /// Endpoint capability
+        endpoint: u64,
+    },
+}
+
+impl Transport {
+    /// Transport for a daemon path in this environment
+    #[cfg_attr(not(feature = "native"), allow(unused_variables))]
+    pub fn select(socket: &Path) -> Self {
+        #[cfg(feature = "native")]
+        if let Some(endpoint) = Directory::from_env().lookup(socket) {
+            return Self::Ring { endpoint };
+        }
+        Self::Unix
+    }
+}
+
+/// Daemon paths served over kernel IPC, with their endpoints
+///
+/// Written as comma-separated `path=endpoint` pairs, e.g.
+/// `/run/herald/herald.sock=12,/run/vesper/vesper.sock=13`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Directory {
+    endpoints: HashMap<PathBuf, u64>,
+}
+
+impl Directory {
+    /// Parse a directory, skipping malformed entries
+    pub fn parse(spec: &str) -> Self {
+        let endpoints = spec
+            .split(',')
+            .filter_map(|entry| {
+                let (path, endpoint) = entry.trim().rsplit_once('=')?;
+                Some((PathBuf::from(path), endpoint.parse().ok()?))
+            })
+            .collect();
+        Self { endpoints }
+    }
+
+    /// The directory passed down by init, empty when there is none
+    pub fn from_env() -> Self {
+        std::env::var(ENDPOINTS_ENV)
+            .map(|spec| Self::parse(&spec))
+            .unwrap_or_default()
+    }
+
+    /// Endpoint serving a daemon path
+    pub fn lookup(&self, socket: &Path) -> Option<u64> {
+        self.endpoints.get(socket).copied()
+    }
+}
+
+/// Connect to a daemon
+pub async fn connect(socket: &Path) -> io::Result<Stream> {
+    match Transport::select(socket) {
+        Transport::Unix => Ok(Stream::Unix(UnixStream::connect(socket).await?)),
+        #[cfg(feature = "native")]
+        Transport::Ring { endpoint } => Ok(Stream::Ring(ring::connect(endpoint).await?)),
+        #[cfg(not(feature = "native"))]
+        Transport::Ring { .. } => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "kernel IPC transport needs the native feature",
+        )),
+    }
+}
+
+/// A connection to or from a daemon
+pub enum Stream {
+    /// Unix socket
+    Unix(UnixStream),
+    /// Shared memory rings
+    #[cfg(feature = "native")]
+    Ring(ring::RingStream),
+}
+
+impl AsyncRead for Stream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(feature = "native")]
+            Self::Ring(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Stream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "native")]
+            Self::Ring(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Unix(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "native")]
+            Self::Ring(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(feature = "native")]
+            Self::Ring(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+/// Where a daemon accepts connections
+pub enum Listener {
+    /// Unix socket
+    Unix(UnixListener),
+    /// Kernel IPC endpoint
+    #[cfg(feature = "native")]
+    Ring(ring::RingListener),
+}
+
+impl Listener {
+    /// Listen at a daemon path, replacing a stale socket
+    pub fn bind(socket: &Path) -> io::Result<Self> {
+        match Transport::select(socket) {
+            Transport::Unix => {
+                if socket.exists() {
+                    std::fs::remove_file(socket)?;
+                }
+                if let Some(parent) = socket.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                Ok(Self::Unix(UnixListener::bind(socket)?))
+            }
+            #[cfg(feature = "native")]
+            Transport::Ring { endpoint } => Ok(Self::Ring(ring::RingListener::new(endpoint))),
+            #[cfg(not(feature = "native"))]
+            Transport::Ring { .. } => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "kernel IPC transport needs the native feature",
+            )),
+        }
+    }
+
+    /// Wait for the next connection
+    pub async fn accept(&self) -> io::Result<Stream> {
+        match self {
+            Self::Unix(listener) => Ok(Stream::Unix(listener.accept().await?.0)),
+            #[cfg(feature = "native")]
+            Self::Ring(listener) => Ok(Stream::Ring(listener.accept().await?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    #[test]
+    fn test_directory_parse() {
+        let directory = Directory::parse("/run/herald/herald.sock=12, /run/vesper/vesper.sock=13,bogus,/x=y");
+
+        assert_eq!(directory.lookup(Path::new("/run/herald/herald.sock")), Some(12));
+        assert_eq!(directory.lookup(Path::new("/run/vesper/vesper.sock")), Some(13));
+        assert_eq!(directory.lookup(Path::new("/x")), None);
+        assert_eq!(directory.lookup(Path::new("/run/iris/iris.sock")), None);
+    }
+
+    #[tokio::test]
+    async fn test_unix_round_trip() {
+        let socket = std::env::temp_dir().join(format!("nyx-transport-{}.sock", uuid::Uuid::new_v4()));
+        let listener = Listener::bind(&socket).unwrap();
+
+        let server = tokio::spawn(async move {
+            let mut stream = BufReader::new(listener.accept().await.unwrap());
+            let mut line = String::new();
+            stream.read_line(&mut line).await.unwrap();
+            stream.get_mut().write_all(line.to_uppercase().as_bytes()).await.unwrap();
+        });
+
+        let mut stream = BufReader::new(connect(&socket).await.unwrap());
+        stream.get_mut().write_all(b"ping\n").await.unwrap();
+        let mut line = String::new();
+        stream.read_line(&mut line).await.unwrap();
+        server.await.unwrap();
+        let _ = std::fs::remove_file(&socket);
+
+        assert_eq!(line, "PING\n");
+    }
+}
@@ -0,0 +1,376 @@
+//! Shared memory ring connections over kernel IPC
+//!
+//! The client creates the connection: a shared region and a doorbell
+//! notification, which it hands to the daemon's endpoint in a 32-byte
+//! handshake (the region view, then the doorbell). The region holds a
+//! header page and two byte rings, client-to-daemon first. Each ring has
+//! one writer and one reader, which keep running byte counts in the header;
+//! after moving bytes either side rings the other's doorbell bit.
+//!
+//! Blocking kernel waits run on tokio's blocking pool, in slices so that
+//! dropped connections and listeners don't strand threads.
+
+use libnyx::cap::Capability;
+use libnyx::ipc::{self, shm_prot, MappedView, SharedRegion, SharedView};
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Bytes per ring
+const RING_CAPACITY: usize = 64 * 1024;
+
+/// Header page at the start of the region
+const HEADER_SIZE: usize = 4096;
+
+/// Whole region: header, then one ring per direction
+const REGION_SIZE: usize = HEADER_SIZE + 2 * RING_CAPACITY;
+
+/// Handshake: region view, then doorbell capability
+const HANDSHAKE_LEN: usize = 32;
+
+/// Longest single kernel wait
+const WAIT_SLICE: Duration = Duration::from_millis(100);
+
+/// How long a daemon gets to accept a connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// End of a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Client = 0,
+    Server = 1,
+}
+
+impl Side {
+    /// Ring this side writes; it reads the other one
+    fn tx(self) -> usize {
+        self as usize
+    }
+
+    fn rx(self) -> usize {
+        1 - self as usize
+    }
+
+    /// Doorbell bit this side waits on
+    fn bell(self) -> u64 {
+        1 << (self as u64)
+    }
+
+    fn peer(self) -> Side {
+        match self {
+            Self::Client => Self::Server,
+            Self::Server => Self::Client,
+        }
+    }
+}
+
+/// Region header, shared by both sides
+#[repr(C)]
+struct Header {
+    /// Bytes ever written into each ring
+    written: [AtomicU32; 2],
+    /// Bytes ever read out of each ring
+    read: [AtomicU32; 2],
+    /// Whether each side has hung up
+    closed: [AtomicU32; 2],
+    /// Set by the daemon once it has mapped the region
+    accepted: AtomicU32,
+}
+
+/// Keeps the region mapped
+enum Mapping {
+    Created(SharedRegion),
+    Granted(MappedView),
+}
+
+/// One end's view of a connection
+struct Shared {
+    _mapping: Mapping,
+    base: *mut u8,
+    doorbell: Capability,
+    side: Side,
+    wakers: Mutex<Vec<Waker>>,
+    waiting: AtomicBool,
+}
+
+// SAFETY: each ring has a single writer and a single reader, which hand over
+// bytes through the header's atomics; the mapping lives as long as `base`
+unsafe impl Send for Shared {}
+unsafe impl Sync for Shared {}
+
+impl Shared {
+    fn new(mut mapping: Mapping, doorbell: Capability, side: Side) -> Self {
+        let base = match &mut mapping {
+            Mapping::Created(region) => region.as_mut_ptr(),
+            Mapping::Granted(view) => view.as_mut_slice().as_mut_ptr(),
+        };
+        Self {
+            _mapping: mapping,
+            base,
+            doorbell,
+            side,
+            wakers: Mutex::new(Vec::new()),
+            waiting: AtomicBool::new(false),
+        }
+    }
+
+    fn header(&self) -> &Header {
+        // SAFETY: the region starts with a zeroed header page
+        unsafe { &*(self.base as *const Header) }
+    }
+
+    fn ring(&self, index: usize) -> *mut u8 {
+        // SAFETY: both rings lie inside the region
+        unsafe { self.base.add(HEADER_SIZE + index * RING_CAPACITY) }
+    }
+
+    /// Copy as much of `data` as fits into our outgoing ring
+    fn write(&self, data: &[u8]) -> usize {
+        let header = self.header();
+        let tx = self.side.tx();
+        let written = header.written[tx].load(Ordering::Relaxed);
+        let read = header.read[tx].load(Ordering::Acquire);
+        let free = RING_CAPACITY - written.wrapping_sub(read) as usize;
+
+        let n = data.len().min(free);
+        if n == 0 {
+            return 0;
+        }
+        let start = written as usize % RING_CAPACITY;
+        let first = n.min(RING_CAPACITY - start);
+        // SAFETY: the free span belongs to the writer until `written` moves
+        unsafe {
+            std::ptr::copy_nonoverlapping(data.as_ptr(), self.ring(tx).add(start), first);
+            std::ptr::copy_nonoverlapping(data[first..].as_ptr(), self.ring(tx), n - first);
+        }
+        header.written[tx].store(written.wrapping_add(n as u32), Ordering::Release);
+        self.ring_peer();
+        n
+    }
+
+    /// Copy as much as is waiting in our incoming ring into `buf`
+    fn read(&self, buf: &mut [u8]) -> usize {
+        let header = self.header();
+        let rx = self.side.rx();
+        let read = header.read[rx].load(Ordering::Relaxed);
+        let written = header.written[rx].load(Ordering::Acquire);
+        let available = written.wrapping_sub(read) as usize;
+
+        let n = buf.len().min(available);
+        if n == 0 {
+            return 0;
+        }
+        let start = read as usize % RING_CAPACITY;
+        let first = n.min(RING_CAPACITY - start);
+        // SAFETY: the filled span belongs to the reader until `read` moves
+        unsafe {
+            std::ptr::copy_nonoverlapping(self.ring(rx).add(start), buf.as_mut_ptr(), first);
+            std::ptr::copy_nonoverlapping(self.ring(rx), buf[first..].as_mut_ptr(), n - first);
+        }
+        header.read[rx].store(read.wrapping_add(n as u32), Ordering::Release);
+        self.ring_peer();
+        n
+    }
+
+    fn peer_closed(&self) -> bool {
+        self.header().closed[self.side.peer() as usize].load(Ordering::Acquire) != 0
+    }
+
+    fn close(&self) {
+        self.header().closed[self.side as usize].store(1, Ordering::Release);
+        self.ring_peer();
+    }
+
+    fn ring_peer(&self) {
+        let _ = ipc::signal(self.doorbell, self.side.peer().bell());
+    }
+
+    /// Wake `cx` once the peer rings, or after a wait slice
+    fn wait(self: &Arc<Self>, cx: &mut Context<'_>) {
+        {
+            let mut wakers = self.wakers.lock().unwrap();
+            if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                wakers.push(cx.waker().clone());
+            }
+        }
+        if self.waiting.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let shared = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let _ = ipc::wait(shared.doorbell, shared.side.bell(), Some(WAIT_SLICE.as_nanos() as u64));
+            shared.waiting.store(false, Ordering::Release);
+            for waker in shared.wakers.lock().unwrap().drain(..) {
+                waker.wake();
+            }
+        });
+    }
+}
+
+/// A shared memory ring connection
+pub struct RingStream {
+    shared: Arc<Shared>,
+}
+
+impl Drop for RingStream {
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
+
+impl AsyncRead for RingStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let shared = &self.shared;
+        let n = shared.read(buf.initialize_unfilled());
+        if n > 0 {
+            buf.advance(n);
+            return Poll::Ready(Ok(()));
+        }
+        if shared.peer_closed() {
+            // End of stream
+            return Poll::Ready(Ok(()));
+        }
+        shared.wait(cx);
+        Poll::Pending
+    }
+}
+
+impl AsyncWrite for RingStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let shared = &self.shared;
+        if shared.peer_closed() {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        let n = shared.write(buf);
+        if n > 0 || buf.is_empty() {
+            return Poll::Ready(Ok(n));
+        }
+        shared.wait(cx);
+        Poll::Pending
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // Bytes are visible to the peer as soon as they are written
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.shared.close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Open a connection through a daemon's endpoint
+pub async fn connect(endpoint: u64) -> io::Result<RingStream> {
+    tokio::task::spawn_blocking(move || connect_blocking(Capability::from_raw(endpoint)))
+        .await
+        .map_err(io::Error::other)?
+}
+
+fn connect_blocking(endpoint: Capability) -> io::Result<RingStream> {
+    let mut region = SharedRegion::new(REGION_SIZE).map_err(nyx_error)?;
+    region.as_mut_slice()[..HEADER_SIZE].fill(0);
+    let doorbell = ipc::create_notification().map_err(nyx_error)?;
+    let view = region
+        .grant(endpoint, shm_prot::READ | shm_prot::WRITE)
+        .map_err(nyx_error)?;
+
+    let mut handshake = [0u8; HANDSHAKE_LEN];
+    handshake[..24].copy_from_slice(&view.to_bytes());
+    handshake[24..].copy_from_slice(&doorbell.as_raw().to_le_bytes());
+    ipc::send(endpoint, &handshake, Some(CONNECT_TIMEOUT.as_nanos() as u64)).map_err(nyx_error)?;
+
+    let shared = Shared::new(Mapping::Created(region), doorbell, Side::Client);
+    let deadline = Instant::now() + CONNECT_TIMEOUT;
+    while shared.header().accepted.load(Ordering::Acquire) == 0 {
+        if Instant::now() >= deadline {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        let _ = ipc::wait(doorbell, Side::Client.bell(), Some(WAIT_SLICE.as_nanos() as u64));
+    }
+
+    Ok(RingStream { shared: Arc::new(shared) })
+}
+
+/// A daemon's endpoint, accepting ring connections
+pub struct RingListener {
+    endpoint: Capability,
+}
+
+impl RingListener {
+    /// Accept on an endpoint init created for the daemon
+    pub fn new(endpoint: u64) -> Self {
+        Self {
+            endpoint: Capability::from_raw(endpoint),
+        }
+    }
+
+    /// Wait for the next handshake and map its region
+    pub async fn accept(&self) -> io::Result<RingStream> {
+        // Stops the blocking receive loop if this future is dropped
+        struct Cancel(Arc<AtomicBool>);
+        impl Drop for Cancel {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::Release);
+            }
+        }
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let _cancel = Cancel(cancelled.clone());
+        let endpoint = self.endpoint;
+
+        tokio::task::spawn_blocking(move || {
+            let mut handshake = [0u8; HANDSHAKE_LEN];
+            loop {
+                if cancelled.load(Ordering::Acquire) {
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+                match ipc::receive(endpoint, &mut handshake, Some(WAIT_SLICE.as_nanos() as u64)) {
+                    Ok(HANDSHAKE_LEN) => return accept_blocking(&handshake),
+                    Ok(_) => tracing::warn!("Ignoring malformed ring handshake"),
+                    Err(libnyx::syscall::Error::Timeout | libnyx::syscall::Error::WouldBlock) => {}
+                    Err(e) => return Err(nyx_error(e)),
+                }
+            }
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+}
+
+fn accept_blocking(handshake: &[u8; HANDSHAKE_LEN]) -> io::Result<RingStream> {
+    let view = SharedView::from_bytes(handshake[..24].try_into().unwrap());
+    let doorbell = Capability::from_raw(u64::from_le_bytes(handshake[24..].try_into().unwrap()));
+
+    let mapped = view.map().map_err(nyx_error)?;
+    if mapped.size() < REGION_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "ring region too small"));
+    }
+
+    let shared = Shared::new(Mapping::Granted(mapped), doorbell, Side::Server);
+    shared.header().accepted.store(1, Ordering::Release);
+    shared.ring_peer();
+
+    Ok(RingStream { shared: Arc::new(shared) })
+}
+
+fn nyx_error(err: libnyx::syscall::Error) -> io::Error {
+    use libnyx::syscall::Error;
+
+    let kind = match err {
+        Error::Timeout => io::ErrorKind::TimedOut,
+        Error::PermissionDenied => io::ErrorKind::PermissionDenied,
+        Error::NotFound | Error::InvalidCapability => io::ErrorKind::NotFound,
+        Error::OutOfMemory => io::ErrorKind::OutOfMemory,
+        Error::InvalidArgument => io::ErrorKind::InvalidInput,
+        Error::WouldBlock => io::ErrorKind::WouldBlock,
+        Error::Interrupted => io::ErrorKind::Interrupted,
+        _ => io::ErrorKind::Other,
+    };
+    io::Error::new(kind, err.to_string())
+}
@@ -0,0 +1,83 @@
+//! VPN IPC client
+//!
+//! Client for the VPN side of the arachne network agent: listing WireGuard
+//! tunnels and bringing them up or down.
+
+use crate::request::{parse, request};
+use crate::{paths, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Bringing a tunnel up waits on the handshake
+const VPN_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// VPN tunnel
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VpnConnection {
+    /// Tunnel interface, also its name
+    pub interface: String,
+    /// WireGuard, ...
+    #[serde(rename = "type")]
+    pub vpn_type: String,
+    /// Connected, Connecting, Disconnected or Error
+    pub status: String,
+    pub address: String,
+    pub peers: usize,
+}
+
+impl VpnConnection {
+    pub fn is_connected(&self) -> bool {
+        self.status == "Connected"
+    }
+}
+
+/// VPN client
+pub struct VpnClient {
+    socket_path: PathBuf,
+}
+
+impl Default for VpnClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VpnClient {
+    /// Create a client with default socket path
+    pub fn new() -> Self {
+        Self::with_socket(paths::ARACHNE_SOCKET)
+    }
+
+    /// Create a client with custom socket path
+    pub fn with_socket(path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: path.into(),
+        }
+    }
+
+    /// List configured tunnels
+    pub async fn list(&self) -> Result<Vec<VpnConnection>> {
+        let reply = self.send(json!({ "type": "VpnList" })).await?;
+        parse(reply["data"]["vpns"].clone())
+    }
+
+    /// Bring a tunnel up
+    pub async fn connect(&self, name: &str) -> Result<()> {
+        self.send(json!({ "type": "VpnConnect", "data": { "name": name } }))
+            .await
+            .map(drop)
+    }
+
+    /// Take a tunnel down
+    pub async fn disconnect(&self, name: &str) -> Result<()> {
+        self.send(json!({ "type": "VpnDisconnect", "data": { "name": name } }))
+            .await
+            .map(drop)
+    }
+
+    async fn send(&self, body: Value) -> Result<Value> {
+        request(&self.socket_path, body, VPN_TIMEOUT).await
+    }
+}
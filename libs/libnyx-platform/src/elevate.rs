@@ -0,0 +1,289 @@
+//! Privilege elevation
+//!
+//! Runs a command with administrator rights using whatever the platform
+//! offers: nothing if already root, polkit's `pkexec` in a graphical
+//! session, `sudo` otherwise, and UAC for Windows executables under WSL.
+//! Output is passed back line by line as it is produced. The user
+//! refusing (or failing) authentication is reported as
+//! [`ElevateError::Denied`], separately from the command itself failing.
+
+use super::*;
+use std::fmt;
+use std::io::{BufRead, BufReader, IsTerminal, Read};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// pkexec exit codes for a dismissed or refused authorization
+const PKEXEC_DISMISSED: i32 = 126;
+const PKEXEC_NOT_AUTHORIZED: i32 = 127;
+
+/// Windows ERROR_CANCELLED, used by the UAC script when the prompt is declined
+const UAC_CANCELLED: i32 = 1223;
+
+/// How often the UAC output file is read while the command runs
+const UAC_POLL: Duration = Duration::from_millis(200);
+
+/// Mechanism used to gain administrator rights
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElevationMethod {
+    /// Already running as root; the command runs directly
+    AlreadyRoot,
+    /// polkit, with the session's authentication agent
+    Pkexec,
+    /// sudo, prompting on the terminal
+    Sudo,
+    /// Windows User Account Control (WSL, Windows executables only)
+    WindowsUac,
+}
+
+/// Stream an output line came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Elevation failure
+#[derive(Debug)]
+pub enum ElevateError {
+    /// The user declined or failed authentication
+    Denied(ElevationMethod),
+    /// No elevation mechanism is available for this command
+    Unavailable,
+    /// The command was empty
+    EmptyCommand,
+    /// Spawning or reading from the command failed
+    Io(std::io::Error),
+}
+
+impl fmt::Display for ElevateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ElevateError::Denied(method) => write!(f, "authorization denied ({:?})", method),
+            ElevateError::Unavailable => write!(f, "no privilege elevation mechanism available"),
+            ElevateError::EmptyCommand => write!(f, "no command given"),
+            ElevateError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ElevateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ElevateError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ElevateError {
+    fn from(e: std::io::Error) -> Self {
+        ElevateError::Io(e)
+    }
+}
+
+/// Pick the elevation mechanism for running `program`
+pub fn elevation_method(program: &str) -> Option<ElevationMethod> {
+    if Platform::detect().is_wsl() && program.ends_with(".exe") {
+        return wsl::interop_enabled().then_some(ElevationMethod::WindowsUac);
+    }
+    if effective_uid() == Some(0) {
+        return Some(ElevationMethod::AlreadyRoot);
+    }
+
+    let graphical = std::env::var("WAYLAND_DISPLAY").is_ok() || std::env::var("DISPLAY").is_ok();
+    let terminal = std::io::stdin().is_terminal();
+    let pkexec = in_path("pkexec");
+    let sudo = in_path("sudo");
+
+    // pkexec falls back to a text agent on a terminal, sudo needs one
+    if pkexec && (graphical || !sudo) {
+        Some(ElevationMethod::Pkexec)
+    } else if sudo && terminal {
+        Some(ElevationMethod::Sudo)
+    } else if pkexec {
+        Some(ElevationMethod::Pkexec)
+    } else {
+        None
+    }
+}
+
+/// Run `command` as administrator and return its exit code.
+///
+/// `reason` is shown in the sudo password prompt; polkit and UAC
+/// describe the command themselves. Each line of output is passed
+/// to `on_output` as it arrives. pkexec runs the command with a clean
+/// environment in root's home directory, so pass absolute paths.
+pub fn elevate(
+    command: &[&str],
+    reason: &str,
+    mut on_output: impl FnMut(OutputStream, &str),
+) -> Result<i32, ElevateError> {
+    let (program, args) = command.split_first().ok_or(ElevateError::EmptyCommand)?;
+    let method = elevation_method(program).ok_or(ElevateError::Unavailable)?;
+
+    match method {
+        ElevationMethod::AlreadyRoot => run_streaming(Command::new(program).args(args), &mut on_output),
+        ElevationMethod::Pkexec => {
+            let code = run_streaming(Command::new("pkexec").arg(program).args(args), &mut on_output)?;
+            match code {
+                PKEXEC_DISMISSED | PKEXEC_NOT_AUTHORIZED => Err(ElevateError::Denied(method)),
+                code => Ok(code),
+            }
+        }
+        ElevationMethod::Sudo => {
+            // Authenticate first so a wrong password isn't confused with
+            // the command failing; the run itself must not prompt again
+            let validated = Command::new("sudo")
+                .args(["-v", "-p"])
+                .arg(format!("{} - password for %u: ", reason))
+                .status()?;
+            if !validated.success() {
+                return Err(ElevateError::Denied(method));
+            }
+            run_streaming(Command::new("sudo").args(["-n", "--"]).arg(program).args(args), &mut on_output)
+        }
+        ElevationMethod::WindowsUac => run_uac(program, args, &mut on_output),
+    }
+}
+
+/// Run a command, passing its output lines on as they arrive
+fn run_streaming(command: &mut Command, on_output: &mut impl FnMut(OutputStream, &str)) -> Result<i32, ElevateError> {
+    let mut child = command
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let (tx, rx) = mpsc::channel();
+    let readers = [
+        child.stdout.take().map(|s| spawn_reader(s, OutputStream::Stdout, tx.clone())),
+        child.stderr.take().map(|s| spawn_reader(s, OutputStream::Stderr, tx.clone())),
+    ];
+    drop(tx);
+
+    for (stream, line) in rx {
+        on_output(stream, &line);
+    }
+    for reader in readers.into_iter().flatten() {
+        let _ = reader.join();
+    }
+
+    Ok(exit_code(child.wait()?))
+}
+
+fn spawn_reader(
+    pipe: impl Read + Send + 'static,
+    stream: OutputStream,
+    tx: mpsc::Sender<(OutputStream, String)>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+            if tx.send((stream, line)).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Run a Windows executable elevated through UAC. An elevated process
+/// can't share our pipes, so its output goes to a file that is followed
+/// until the process exits; both streams arrive as stdout.
+fn run_uac(program: &str, args: &[&str], on_output: &mut impl FnMut(OutputStream, &str)) -> Result<i32, ElevateError> {
+    let log = std::env::temp_dir().join(format!("nyx-elevate-{}.log", std::process::id()));
+    std::fs::write(&log, b"")?;
+    let windows_log = wsl::to_windows_path(&log.to_string_lossy()).ok_or(ElevateError::Unavailable)?;
+
+    let command_line = std::iter::once(program)
+        .chain(args.iter().copied())
+        .map(quote_cmd)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let cmd_args = format!("/c {} > \"{}\" 2>&1", command_line, windows_log);
+    let script = format!(
+        "try {{ $p = Start-Process cmd.exe -Verb RunAs -WindowStyle Hidden -Wait -PassThru \
+         -ArgumentList '{}' }} catch {{ exit {} }}; exit $p.ExitCode",
+        cmd_args.replace('\'', "''"),
+        UAC_CANCELLED
+    );
+    let mut child = Command::new("powershell.exe")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let mut file = std::fs::File::open(&log)?;
+    let mut pending = String::new();
+    let status = loop {
+        let exited = child.try_wait()?;
+        let mut chunk = String::new();
+        file.read_to_string(&mut chunk)?;
+        pending.push_str(&chunk.replace('\r', ""));
+        while let Some(end) = pending.find('\n') {
+            on_output(OutputStream::Stdout, &pending[..end]);
+            pending.drain(..=end);
+        }
+        if let Some(status) = exited {
+            break status;
+        }
+        std::thread::sleep(UAC_POLL);
+    };
+    if !pending.is_empty() {
+        on_output(OutputStream::Stdout, &pending);
+    }
+    let _ = std::fs::remove_file(&log);
+
+    match exit_code(status) {
+        UAC_CANCELLED => Err(ElevateError::Denied(ElevationMethod::WindowsUac)),
+        code => Ok(code),
+    }
+}
+
+/// Quote an argument for cmd.exe
+fn quote_cmd(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"', '&', '|', '<', '>', '^']) {
+        arg.to_string()
+    } else {
+        format!("\"{}\"", arg.replace('"', "\"\""))
+    }
+}
+
+/// Exit code, with death by signal reported shell-style as 128 + signal
+fn exit_code(status: std::process::ExitStatus) -> i32 {
+    use std::os::unix::process::ExitStatusExt;
+    status
+        .code()
+        .or_else(|| status.signal().map(|signal| 128 + signal))
+        .unwrap_or(1)
+}
+
+fn effective_uid() -> Option<u32> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let uids = status.lines().find_map(|line| line.strip_prefix("Uid:"))?;
+    uids.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn in_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_cmd() {
+        assert_eq!(quote_cmd("netsh"), "netsh");
+        assert_eq!(quote_cmd("C:\\Program Files\\x.exe"), "\"C:\\Program Files\\x.exe\"");
+        assert_eq!(quote_cmd("a&b"), "\"a&b\"");
+        assert_eq!(quote_cmd(""), "\"\"");
+    }
+
+    #[test]
+    fn test_empty_command() {
+        assert!(matches!(elevate(&[], "test", |_, _| {}), Err(ElevateError::EmptyCommand)));
+    }
+}
@@ -7,6 +7,8 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::sync::OnceLock;
 
+mod elevate;
+
 /// Detected platform type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Platform {
@@ -468,42 +470,14 @@ pub mod wsl {
         std::env::var("WSL_INTEROP").is_ok() ||
         Path::new("/proc/sys/fs/binfmt_misc/WSLInterop").exists()
     }
-
-    /// Send Windows toast notification
-    pub fn send_toast(title: &str, message: &str) -> std::io::Result<()> {
-        let ps_script = format!(
-            r#"[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null
-[Windows.Data.Xml.Dom.XmlDocument, Windows.Data.Xml.Dom.XmlDocument, ContentType = WindowsRuntime] | Out-Null
-$template = @"
-<toast>
-    <visual>
-        <binding template="ToastText02">
-            <text id="1">{}</text>
-            <text id="2">{}</text>
-        </binding>
-    </visual>
-</toast>
-"@
-$xml = New-Object Windows.Data.Xml.Dom.XmlDocument
-$xml.LoadXml($template)
-$toast = [Windows.UI.Notifications.ToastNotification]::new($xml)
-[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier("Nyx").Show($toast)"#,
-            title.replace('"', "'"),
-            message.replace('"', "'")
-        );
-
-        Command::new("powershell.exe")
-            .args(["-NoProfile", "-Command", &ps_script])
-            .output()?;
-
-        Ok(())
-    }
 }
 
 /// Platform-aware service implementation helpers
 pub mod compat {
     use super::*;
 
+    pub use crate::elevate::{elevate, elevation_method, ElevateError, ElevationMethod, OutputStream};
+
     /// Get appropriate firewall backend
     pub fn firewall_backend() -> FirewallBackend {
         let caps = PlatformCapabilities::detect();
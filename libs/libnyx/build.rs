@@ -137,11 +137,16 @@ fn validate_syscalls(kernel: &HashMap<String, u64>, libnyx: &HashMap<String, u64
         ("CapIdentify", "CAP_IDENTIFY"),
         ("CapGrant", "CAP_GRANT"),
         ("CapDrop", "CAP_DROP"),
+        ("NotifyCreate", "NOTIFY_CREATE"),
         ("MemMap", "MEM_MAP"),
         ("MemUnmap", "MEM_UNMAP"),
         ("MemProtect", "MEM_PROTECT"),
         ("MemAlloc", "MEM_ALLOC"),
         ("MemFree", "MEM_FREE"),
+        ("ShmCreate", "SHM_CREATE"),
+        ("ShmMap", "SHM_MAP"),
+        ("ShmUnmap", "SHM_UNMAP"),
+        ("ShmGrant", "SHM_GRANT"),
         ("ThreadCreate", "THREAD_CREATE"),
         ("ThreadExit", "THREAD_EXIT"),
         ("ThreadYield", "THREAD_YIELD"),
@@ -152,6 +157,7 @@ fn validate_syscalls(kernel: &HashMap<String, u64>, libnyx: &HashMap<String, u64
         ("ProcessWait", "PROCESS_WAIT"),
         ("ProcessGetPid", "PROCESS_GETPID"),
         ("ProcessGetPpid", "PROCESS_GETPPID"),
+        ("ProcessStat", "PROCESS_STAT"),
         ("FsOpen", "FS_OPEN"),
         ("FsClose", "FS_CLOSE"),
         ("FsRead", "FS_READ"),
@@ -170,6 +176,13 @@ fn validate_syscalls(kernel: &HashMap<String, u64>, libnyx: &HashMap<String, u64
         ("RecordStop", "RECORD_STOP"),
         ("Debug", "DEBUG"),
         ("GetTime", "GET_TIME"),
+        ("KlogRead", "KLOG_READ"),
+        ("ClockGet", "CLOCK_GET"),
+        ("TimerCreate", "TIMER_CREATE"),
+        ("TimerSet", "TIMER_SET"),
+        ("TimerCancel", "TIMER_CANCEL"),
+        ("TimerRead", "TIMER_READ"),
+        ("TimerDestroy", "TIMER_DESTROY"),
         ("Reboot", "REBOOT"),
         ("Shutdown", "SHUTDOWN"),
     ]
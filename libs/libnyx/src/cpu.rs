@@ -0,0 +1,41 @@
+//! CPU hotplug
+//!
+//! Anyone may ask which CPUs are online. Taking one offline or bringing it
+//! back affects every process, so it needs a scheduler context capability
+//! with `SCHEDULE` rights. The boot CPU always stays online.
+
+use crate::cap::Capability;
+use crate::syscall::{self, nr, Error};
+
+/// Read which CPUs are online into `mask`, bit N of the bitmap being CPU N
+///
+/// Returns the number of CPUs present, online or not. Fails with
+/// `InvalidArgument` when `mask` has fewer than one word per 64 of them.
+///
+/// # Example
+/// ```no_run
+/// let mut mask = [0u64; 4];
+/// let present = cpu::online(&mut mask)?;
+/// let online = mask.iter().map(|w| w.count_ones()).sum::<u32>();
+/// ```
+pub fn online(mask: &mut [u64]) -> Result<u32, Error> {
+    let result = unsafe {
+        syscall::syscall2(
+            nr::CPU_GET_ONLINE,
+            mask.as_mut_ptr() as u64,
+            core::mem::size_of_val(mask) as u64,
+        )
+    };
+    Error::from_raw(result).map(|n| n as u32)
+}
+
+/// Bring a CPU online or take it offline
+///
+/// Fails with `NotFound` for a CPU that isn't present, `PermissionDenied`
+/// for the boot CPU and `InvalidArgument` when it is already in that state.
+pub fn set_online(sched: Capability, cpu: u32, online: bool) -> Result<(), Error> {
+    let result = unsafe {
+        syscall::syscall3(nr::CPU_SET_ONLINE, sched.as_raw(), cpu as u64, online as u64)
+    };
+    Error::from_raw(result).map(|_| ())
+}
@@ -367,6 +367,15 @@ pub fn reply(reply_cap: Capability, data: &[u8]) -> Result<(), Error> {
 // Notification Functions
 // ============================================================================
 
+/// Create a notification object
+///
+/// The returned capability can signal, wait and poll, and be granted on to
+/// another process.
+pub fn create_notification() -> Result<Capability, Error> {
+    let result = unsafe { syscall::syscall0(nr::NOTIFY_CREATE) };
+    Error::from_raw(result).map(Capability::from_raw)
+}
+
 /// Signal notification bits
 ///
 /// Atomically OR the given bits into the notification object.
@@ -0,0 +1,33 @@
+//! Kernel log
+//!
+//! The kernel keeps its log messages in a ring buffer. Reading it takes the
+//! kernel log capability, which init receives at boot and passes on to the
+//! logging daemon.
+
+use crate::cap::Capability;
+use crate::syscall::{self, nr, Error};
+
+/// Read kernel log records, starting at sequence number `from_seq`
+///
+/// Fills `buf` with whole `/dev/kmsg`-style lines
+/// (`<priority>,<sequence>,<timestamp_us>,-;<message>\n`) and returns the
+/// number of bytes written, or 0 when nothing newer has been logged.
+/// Fails with `InvalidArgument` when `buf` can't hold the next record.
+///
+/// # Example
+/// ```no_run
+/// let mut buf = [0u8; 4096];
+/// let n = klog::read(log_cap, 0, &mut buf)?;
+/// ```
+pub fn read(log: Capability, from_seq: u64, buf: &mut [u8]) -> Result<usize, Error> {
+    let result = unsafe {
+        syscall::syscall4(
+            nr::KLOG_READ,
+            log.as_raw(),
+            from_seq,
+            buf.as_mut_ptr() as u64,
+            buf.len() as u64,
+        )
+    };
+    Error::from_raw(result).map(|n| n as usize)
+}
@@ -10,6 +10,9 @@
 //! - **Memory** - Virtual memory mapping and protection
 //! - **Tensor/AI** - First-class AI/ML acceleration support
 //! - **Time** - Monotonic time and duration measurement
+//! - **Kernel log** - Reading the kernel's log ring (privileged)
+//! - **CPU hotplug** - Which CPUs are online, and taking them offline
+//! - **Crash reporting** - Panics and errors forwarded to the journal
 //!
 //! ## Quick Start
 //!
@@ -60,9 +63,12 @@
 
 // Core modules
 pub mod cap;
+pub mod cpu;
 pub mod ipc;
+pub mod klog;
 pub mod memory;
 pub mod process;
+pub mod report;
 pub mod syscall;
 pub mod tensor;
 pub mod thread;
@@ -92,9 +98,9 @@ pub use ipc::{
 pub use memory::{flags as mmap_flags, prot, PAGE_SIZE};
 pub use process::{ProcessId, WaitResult};
 pub use syscall::Error;
-pub use tensor::{DType, Device, InferenceConfig, TensorBuffer, TensorShape};
+pub use tensor::{DType, Device, InferenceConfig, Placement, TensorBuffer, TensorShape};
 pub use thread::ThreadId;
-pub use time::Instant;
+pub use time::{Instant, Timer};
 pub use timetravel::{CheckpointFlags, CheckpointId, RecordFlags, RecordingId, RestoreFlags};
 
 /// Prelude module for convenient imports
@@ -112,7 +118,7 @@ pub mod prelude {
     pub use crate::syscall::Error;
     pub use crate::tensor::{self, Device, DType, TensorBuffer, TensorShape};
     pub use crate::thread::{self, sleep_ms, sleep_secs, thread_yield, ThreadId};
-    pub use crate::time::{self, now_ms, now_ns, Instant};
+    pub use crate::time::{self, now_ms, now_ns, Instant, Timer};
     pub use crate::timetravel::{
         self, checkpoint, record_start, record_stop, restore,
         CheckpointFlags, CheckpointId, RecordFlags, RecordingId, RestoreFlags,
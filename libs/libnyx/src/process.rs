@@ -2,6 +2,7 @@
 //!
 //! Functions for spawning, managing, and waiting on processes.
 
+use crate::cap::Capability;
 use crate::syscall::{self, nr, Error};
 
 /// Process ID
@@ -69,6 +70,65 @@ pub fn spawn(path: &str) -> Result<ProcessId, Error> {
     Error::from_raw(result).map(ProcessId)
 }
 
+/// Spawn flag lifting W^X for the child
+const SPAWN_ALLOW_WX: u64 = 1 << 0;
+
+/// Spawn a JIT runtime that may map memory writable and executable
+///
+/// Processes are otherwise held to W^X: no mapping may be both writable
+/// and executable. Lifting that takes a `JitMemory` capability with WRITE
+/// and EXECUTE rights.
+///
+/// # Example
+/// ```no_run
+/// let pid = spawn_jit("/bin/wasm-runtime", jit_cap)?;
+/// ```
+pub fn spawn_jit(path: &str, jit: Capability) -> Result<ProcessId, Error> {
+    let result = unsafe {
+        syscall::syscall6(
+            nr::PROCESS_SPAWN,
+            path.as_ptr() as u64,
+            path.len() as u64,
+            0, // args_ptr (not implemented)
+            0, // args_len
+            SPAWN_ALLOW_WX,
+            jit.as_raw(),
+        )
+    };
+
+    Error::from_raw(result).map(ProcessId)
+}
+
+/// Spawn flag running the child through the Linux compat layer
+const SPAWN_LINUX_COMPAT: u64 = 1 << 1;
+
+/// Spawn a static Linux x86_64 binary
+///
+/// The child's system calls go through the kernel's Linux compat layer,
+/// which covers what a static libc needs for file I/O, memory and threads.
+/// Takes a `LinuxCompat` capability with EXECUTE rights.
+///
+/// # Example
+/// ```no_run
+/// let pid = spawn_linux("/opt/linux/bin/busybox", linux_cap)?;
+/// ```
+pub fn spawn_linux(path: &str, linux: Capability) -> Result<ProcessId, Error> {
+    let result = unsafe {
+        syscall::syscall6(
+            nr::PROCESS_SPAWN,
+            path.as_ptr() as u64,
+            path.len() as u64,
+            0, // args_ptr (not implemented)
+            0, // args_len
+            SPAWN_LINUX_COMPAT,
+            // The compat capability goes in the high half
+            linux.as_raw() << 32,
+        )
+    };
+
+    Error::from_raw(result).map(ProcessId)
+}
+
 /// Exit the current process
 ///
 /// This function does not return.
@@ -124,3 +184,48 @@ pub fn wait(pid: Option<ProcessId>) -> Result<WaitResult, Error> {
         }
     })
 }
+
+/// Memory usage of a process
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct ProcessStat {
+    /// Bytes of address space mapped
+    pub mapped_bytes: u64,
+    /// Bytes backed by physical frames (RSS)
+    pub resident_bytes: u64,
+    /// Resident bytes shared with other processes
+    pub shared_bytes: u64,
+    /// Bytes of loaded text segments
+    pub text_bytes: u64,
+    /// Bytes of loaded data segments
+    pub data_bytes: u64,
+    /// Number of threads
+    pub threads: u64,
+}
+
+/// Get a process's memory usage
+///
+/// # Arguments
+/// * `pid` - Process to inspect, or None for the caller
+///
+/// Only root may inspect processes owned by other users.
+///
+/// # Example
+/// ```no_run
+/// let stat = stat(None)?;
+/// println!("RSS: {} bytes", stat.resident_bytes);
+/// ```
+pub fn stat(pid: Option<ProcessId>) -> Result<ProcessStat, Error> {
+    let pid_arg = pid.map(|p| p.0).unwrap_or(0);
+    let mut stat = ProcessStat::default();
+    let result = unsafe {
+        syscall::syscall3(
+            nr::PROCESS_STAT,
+            pid_arg,
+            &mut stat as *mut ProcessStat as u64,
+            core::mem::size_of::<ProcessStat>() as u64,
+        )
+    };
+
+    Error::from_raw(result).map(|_| stat)
+}
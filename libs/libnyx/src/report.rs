@@ -0,0 +1,337 @@
+//! Crash and error reporting
+//!
+//! Panics and fatal errors are sent as small text messages to a report
+//! endpoint, where scribe records them in the journal. A program sets the
+//! endpoint once at startup with [`set_endpoint`]; until then reports are
+//! dropped. With the `panic-handler` feature libnyx installs a
+//! `#[panic_handler]` that reports and exits, otherwise call [`panic`] from
+//! your own.
+//!
+//! Backtraces are collected by walking frame pointers, so build with
+//! `-C force-frame-pointers=yes` to get more than the first frame.
+//!
+//! # Wire format
+//! ```text
+//! NYXREPORT 1
+//! kind=panic
+//! pid=42
+//! message=index out of bounds: the len is 3 but the index is 7
+//! location=src/main.rs:17:9
+//! frame=0x401a2c
+//! frame=0x4010f0
+//! cap=0x1f type=2 rights=0x700000
+//! cap=0x20 invalid
+//! ```
+//! Newlines and backslashes in values are escaped as `\n` and `\\`. A report
+//! that doesn't fit in one message is cut at a line boundary.
+//!
+//! # Example
+//! ```no_run
+//! report::set_endpoint(report_cap);
+//! report::add_context(model_cap);
+//!
+//! if let Err(e) = load_model() {
+//!     report::report_error("model load failed", &[model_cap])?;
+//! }
+//! ```
+
+use crate::cap::Capability;
+use crate::ipc::{self, MAX_MESSAGE_SIZE};
+use crate::syscall::Error;
+use core::fmt::{self, Write};
+use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// First line of every report
+pub const REPORT_MAGIC: &str = "NYXREPORT 1";
+
+/// Exit code of a process that panicked
+pub const PANIC_EXIT_CODE: i32 = 101;
+
+/// Maximum return addresses collected per report
+pub const MAX_FRAMES: usize = 32;
+
+/// Maximum capabilities registered with [`add_context`]
+pub const MAX_CONTEXT_CAPS: usize = 8;
+
+/// How long a report may block on a busy endpoint
+const SEND_TIMEOUT_NS: u64 = 100_000_000;
+
+/// Report endpoint (0 = unset)
+static ENDPOINT: AtomicU64 = AtomicU64::new(0);
+
+/// Capabilities included in every report (0 = free slot)
+static CONTEXT_CAPS: [AtomicU64; MAX_CONTEXT_CAPS] =
+    [const { AtomicU64::new(0) }; MAX_CONTEXT_CAPS];
+
+/// Set while a panic is being reported, to catch panics inside reporting
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+/// What a report describes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportKind {
+    /// The program panicked and exited
+    Panic,
+    /// The program hit an error it chose to report
+    Error,
+}
+
+impl ReportKind {
+    /// Wire name
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReportKind::Panic => "panic",
+            ReportKind::Error => "error",
+        }
+    }
+
+    /// Parse a wire name
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "panic" => Some(ReportKind::Panic),
+            "error" => Some(ReportKind::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Set the endpoint reports are sent to
+pub fn set_endpoint(endpoint: Capability) {
+    ENDPOINT.store(endpoint.as_raw(), Ordering::Release);
+}
+
+/// The endpoint reports are sent to, if set
+pub fn endpoint() -> Option<Capability> {
+    match ENDPOINT.load(Ordering::Acquire) {
+        0 => None,
+        raw => Some(Capability::from_raw(raw)),
+    }
+}
+
+/// Include a capability in every report from now on
+///
+/// Returns false when all [`MAX_CONTEXT_CAPS`] slots are taken.
+pub fn add_context(cap: Capability) -> bool {
+    CONTEXT_CAPS.iter().any(|slot| {
+        slot.compare_exchange(0, cap.as_raw(), Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok()
+    })
+}
+
+/// Stop including a capability in reports
+pub fn remove_context(cap: Capability) {
+    for slot in &CONTEXT_CAPS {
+        let _ = slot.compare_exchange(cap.as_raw(), 0, Ordering::AcqRel, Ordering::Relaxed);
+    }
+}
+
+/// Report an error, with the capabilities involved
+///
+/// Fails with `NotFound` when no endpoint is set.
+pub fn report_error(message: &str, caps: &[Capability]) -> Result<(), Error> {
+    send(ReportKind::Error, format_args!("{}", message), None, caps)
+}
+
+/// Report a panic
+///
+/// Fails with `NotFound` when no endpoint is set.
+pub fn report_panic(info: &PanicInfo) -> Result<(), Error> {
+    let location = info.location().map(|l| (l.file(), l.line(), l.column()));
+    send(ReportKind::Panic, format_args!("{}", info.message()), location, &[])
+}
+
+/// Report a panic and exit with [`PANIC_EXIT_CODE`]
+///
+/// For a program's own `#[panic_handler]`. A panic while reporting exits
+/// straight away.
+pub fn panic(info: &PanicInfo) -> ! {
+    if !PANICKING.swap(true, Ordering::AcqRel) {
+        let _ = report_panic(info);
+    }
+    crate::process::exit(PANIC_EXIT_CODE)
+}
+
+#[cfg(feature = "panic-handler")]
+#[panic_handler]
+fn panic_handler(info: &PanicInfo) -> ! {
+    panic(info)
+}
+
+/// Build a report and send it to the endpoint
+fn send(
+    kind: ReportKind,
+    message: fmt::Arguments,
+    location: Option<(&str, u32, u32)>,
+    caps: &[Capability],
+) -> Result<(), Error> {
+    let endpoint = endpoint().ok_or(Error::NotFound)?;
+
+    let mut buf = [0u8; MAX_MESSAGE_SIZE];
+    let mut frames = [0u64; MAX_FRAMES];
+    let frame_count = capture_frames(&mut frames);
+
+    let mut out = ReportWriter::new(&mut buf);
+    out.line(format_args!("{}", REPORT_MAGIC));
+    out.line(format_args!("kind={}", kind.as_str()));
+    if let Ok(pid) = crate::process::getpid() {
+        out.line(format_args!("pid={}", pid.as_raw()));
+    }
+    out.line(format_args!("message={}", Escaped(message)));
+    if let Some((file, line, column)) = location {
+        let file = Escaped(format_args!("{}", file));
+        out.line(format_args!("location={}:{}:{}", file, line, column));
+    }
+    for frame in &frames[..frame_count] {
+        out.line(format_args!("frame={:#x}", frame));
+    }
+
+    let context = CONTEXT_CAPS
+        .iter()
+        .map(|slot| slot.load(Ordering::Acquire))
+        .filter(|&raw| raw != 0)
+        .map(Capability::from_raw);
+    for cap in caps.iter().copied().chain(context) {
+        match cap.identify() {
+            Ok((obj_type, rights)) => out.line(format_args!(
+                "cap={:#x} type={} rights={:#x}",
+                cap.as_raw(),
+                obj_type,
+                rights.bits()
+            )),
+            Err(_) => out.line(format_args!("cap={:#x} invalid", cap.as_raw())),
+        }
+    }
+
+    let len = out.len;
+    ipc::send(endpoint, &buf[..len], Some(SEND_TIMEOUT_NS))
+}
+
+/// Split a received report into `(key, value)` pairs
+///
+/// Returns `None` unless `data` starts with [`REPORT_MAGIC`]. Values are
+/// still escaped; pass them through [`unescape`].
+pub fn parse(data: &[u8]) -> Option<impl Iterator<Item = (&str, &str)>> {
+    let text = core::str::from_utf8(data).ok()?;
+    let mut lines = text.lines();
+    if lines.next()? != REPORT_MAGIC {
+        return None;
+    }
+    Some(lines.filter_map(|line| line.split_once('=')))
+}
+
+/// Undo the value escaping of the wire format
+pub fn unescape(value: &str, out: &mut impl Write) -> fmt::Result {
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.write_char(c)?;
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.write_char('\n')?,
+            Some(other) => out.write_char(other)?,
+            None => out.write_char('\\')?,
+        }
+    }
+    Ok(())
+}
+
+/// Escapes newlines and backslashes while formatting
+struct Escaped<'a>(fmt::Arguments<'a>);
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Escaper<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+        impl Write for Escaper<'_, '_> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                for c in s.chars() {
+                    match c {
+                        '\n' => self.0.write_str("\\n")?,
+                        '\\' => self.0.write_str("\\\\")?,
+                        c => self.0.write_char(c)?,
+                    }
+                }
+                Ok(())
+            }
+        }
+
+        Escaper(f).write_fmt(self.0)
+    }
+}
+
+/// Line writer over a fixed buffer that drops lines which don't fit
+struct ReportWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    /// Set once a line didn't fit; later lines are dropped too
+    full: bool,
+}
+
+impl<'a> ReportWriter<'a> {
+    fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, len: 0, full: false }
+    }
+
+    fn line(&mut self, args: fmt::Arguments) {
+        if self.full {
+            return;
+        }
+        let start = self.len;
+        if self.write_fmt(args).and_then(|_| self.write_char('\n')).is_err() {
+            self.len = start;
+            self.full = true;
+        }
+    }
+}
+
+impl Write for ReportWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > self.buf.len() {
+            return Err(fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Collect return addresses by walking the frame pointer chain
+///
+/// Stops at the first frame that doesn't look like a userspace stack frame.
+#[cfg(target_arch = "x86_64")]
+fn capture_frames(out: &mut [u64]) -> usize {
+    /// Largest gap between consecutive frames before the chain is distrusted
+    const MAX_FRAME_SIZE: u64 = 1 << 20;
+    /// End of the user half of the address space
+    const USER_END: u64 = 0x0000_8000_0000_0000;
+
+    let mut fp: u64;
+    unsafe {
+        core::arch::asm!("mov {}, rbp", out(reg) fp, options(nomem, nostack));
+    }
+
+    let mut count = 0;
+    while count < out.len() && fp != 0 && fp.is_multiple_of(8) && fp < USER_END - 16 {
+        // SAFETY: fp is an aligned user address in the live frame chain;
+        // [fp] holds the caller's frame pointer, [fp + 8] the return address
+        let (next, ret) = unsafe { (*(fp as *const u64), *((fp + 8) as *const u64)) };
+        if ret == 0 {
+            break;
+        }
+        out[count] = ret;
+        count += 1;
+
+        if next <= fp || next - fp > MAX_FRAME_SIZE {
+            break;
+        }
+        fp = next;
+    }
+    count
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn capture_frames(_out: &mut [u64]) -> usize {
+    0
+}
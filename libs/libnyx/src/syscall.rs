@@ -242,6 +242,10 @@ pub mod nr {
     /// Args: cap_id
     pub const CAP_DROP: u64 = 20;
 
+    /// Create a notification object
+    /// Returns: notification capability ID
+    pub const NOTIFY_CREATE: u64 = 21;
+
     // ========================================================================
     // Memory (32-63)
     // ========================================================================
@@ -349,6 +353,11 @@ pub mod nr {
     /// Returns: ppid
     pub const PROCESS_GETPPID: u64 = 84;
 
+    /// Get a process's memory usage
+    /// Args: pid (0 for self), buf_ptr, buf_len
+    /// Returns: bytes written
+    pub const PROCESS_STAT: u64 = 85;
+
     // ========================================================================
     // File System (96-111) - Reserved for future VFS
     // ========================================================================
@@ -365,7 +374,7 @@ pub mod nr {
     // ========================================================================
 
     /// Allocate a tensor buffer
-    /// Args: size, device_type, alignment
+    /// Args: size, device_type, alignment, placement
     /// Returns: buffer_id or negative error
     pub const TENSOR_ALLOC: u64 = 112;
 
@@ -422,6 +431,48 @@ pub mod nr {
     /// Returns: nanoseconds
     pub const GET_TIME: u64 = 241;
 
+    /// Read which CPUs are online
+    /// Args: buf_ptr (u64 words, bit N set when CPU N is online), buf_len
+    /// Returns: number of CPUs present, online or not
+    pub const CPU_GET_ONLINE: u64 = 242;
+
+    /// Take a CPU offline or bring it back (requires a scheduler context
+    /// with SCHEDULE rights)
+    /// Args: cap, cpu_id, online (1 = online, 0 = offline)
+    pub const CPU_SET_ONLINE: u64 = 243;
+
+    /// Read kernel log records (requires the kernel log capability)
+    /// Args: cap, from_seq, buf_ptr, buf_len
+    /// Returns: bytes written
+    pub const KLOG_READ: u64 = 244;
+
+    /// Read a clock
+    /// Args: clock (0 = monotonic, 1 = boottime)
+    /// Returns: nanoseconds since boot
+    pub const CLOCK_GET: u64 = 245;
+
+    /// Create a timer that signals a notification on expiry
+    /// Args: clock, notification, bits
+    /// Returns: timer handle (disarmed)
+    pub const TIMER_CREATE: u64 = 246;
+
+    /// Arm or disarm a timer
+    /// Args: timer, value_ns (0 disarms), interval_ns (0 = one-shot), flags
+    pub const TIMER_SET: u64 = 247;
+
+    /// Disarm a timer
+    /// Args: timer
+    pub const TIMER_CANCEL: u64 = 248;
+
+    /// Take a timer's expiration count
+    /// Args: timer
+    /// Returns: expirations since the last read
+    pub const TIMER_READ: u64 = 249;
+
+    /// Destroy a timer
+    /// Args: timer
+    pub const TIMER_DESTROY: u64 = 250;
+
     /// Reboot the system (requires privilege)
     pub const REBOOT: u64 = 254;
 
@@ -127,6 +127,32 @@ impl DType {
     }
 }
 
+/// NUMA placement for CPU tensor memory (ignored for device memory)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Placement {
+    /// Near the allocating CPU, spilling to other nodes when full
+    #[default]
+    Local,
+    /// On the given node, spilling to other nodes when full
+    Preferred(u8),
+    /// On the given node only
+    Bind(u8),
+    /// Spread over all nodes, for memory bandwidth
+    Interleave,
+}
+
+impl Placement {
+    /// Encode for the kernel: policy in bits 0-7, node in bits 8-15
+    pub fn as_raw(self) -> u64 {
+        match self {
+            Placement::Local => 0,
+            Placement::Preferred(node) => 1 | (node as u64) << 8,
+            Placement::Bind(node) => 2 | (node as u64) << 8,
+            Placement::Interleave => 3,
+        }
+    }
+}
+
 /// Tensor buffer handle
 #[derive(Clone, Copy, Debug)]
 pub struct TensorBuffer {
@@ -152,8 +178,29 @@ impl TensorBuffer {
     /// let buffer = TensorBuffer::alloc(1024 * 1024, Device::Gpu, 256)?;
     /// ```
     pub fn alloc(size: u64, device: Device, alignment: u64) -> Result<Self, Error> {
-        let result =
-            unsafe { syscall::syscall3(nr::TENSOR_ALLOC, size, device as u64, alignment) };
+        Self::alloc_placed(size, device, alignment, Placement::Local)
+    }
+
+    /// Allocate a tensor buffer with a NUMA placement hint
+    ///
+    /// Large CPU tensors read by inference threads pinned to one node
+    /// belong on that node; `Placement::Interleave` suits tensors read
+    /// from every node.
+    ///
+    /// # Example
+    /// ```no_run
+    /// // 512MB of weights on node 1, nowhere else
+    /// let weights = TensorBuffer::alloc_placed(512 << 20, Device::Cpu, 64, Placement::Bind(1))?;
+    /// ```
+    pub fn alloc_placed(
+        size: u64,
+        device: Device,
+        alignment: u64,
+        placement: Placement,
+    ) -> Result<Self, Error> {
+        let result = unsafe {
+            syscall::syscall4(nr::TENSOR_ALLOC, size, device as u64, alignment, placement.as_raw())
+        };
 
         let id = Error::from_raw(result)?;
 
@@ -1,9 +1,42 @@
 //! Time functions
 //!
-//! Functions for getting the current time and working with durations.
+//! Functions for getting the current time and working with durations, and
+//! timers that signal a notification when they expire.
+//!
+//! # Example
+//! ```no_run
+//! let notif = ipc::create_notification()?;
+//! let timer = Timer::new(Clock::Monotonic, notif, 1)?;
+//! timer.arm_periodic(10_000_000)?; // every 10 ms
+//! loop {
+//!     ipc::wait(notif, 1, None)?;
+//!     let ticks = timer.read()?;
+//!     // ... handle `ticks` expirations ...
+//! }
+//! ```
 
+use crate::cap::Capability;
 use crate::syscall::{self, nr, Error};
 
+/// Timer set flag: the expiry is an absolute time on the timer's clock
+pub const TIMER_ABSOLUTE: u32 = 1 << 0;
+
+/// Clocks that can be read and that timers can be set against
+#[repr(u64)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Clock {
+    /// Time since boot, excluding time spent suspended
+    Monotonic = 0,
+    /// Time since boot, including time spent suspended
+    Boottime = 1,
+}
+
+/// Read a clock in nanoseconds since boot
+pub fn clock_ns(clock: Clock) -> Result<u64, Error> {
+    let result = unsafe { syscall::syscall1(nr::CLOCK_GET, clock as u64) };
+    Error::from_raw(result)
+}
+
 /// Get current time in nanoseconds since boot
 ///
 /// This is a monotonic clock that never goes backwards.
@@ -67,3 +100,91 @@ impl Instant {
         self.ns
     }
 }
+
+/// A kernel timer
+///
+/// Each expiry ORs the timer's bits into its notification, so timers are
+/// waited on with [`crate::ipc::wait`] alongside any other event. The timer
+/// is destroyed when dropped.
+pub struct Timer {
+    /// Timer capability (object ID)
+    handle: Capability,
+}
+
+impl Timer {
+    /// Create a disarmed timer on `clock` that signals `bits` on `notif`
+    pub fn new(clock: Clock, notif: Capability, bits: u64) -> Result<Self, Error> {
+        let result = unsafe {
+            syscall::syscall3(nr::TIMER_CREATE, clock as u64, notif.as_raw(), bits)
+        };
+
+        Error::from_raw(result).map(|id| Self {
+            handle: Capability::from_raw(id),
+        })
+    }
+
+    /// Get the timer's capability handle
+    pub fn handle(&self) -> Capability {
+        self.handle
+    }
+
+    /// Arm or disarm the timer
+    ///
+    /// # Arguments
+    /// * `value_ns` - First expiry, relative to now unless `flags` has
+    ///   [`TIMER_ABSOLUTE`] (0 disarms)
+    /// * `interval_ns` - Reload interval (0 = one-shot)
+    /// * `flags` - Timer set flags
+    ///
+    /// Re-arming discards unread expirations.
+    pub fn set(&self, value_ns: u64, interval_ns: u64, flags: u32) -> Result<(), Error> {
+        let result = unsafe {
+            syscall::syscall4(
+                nr::TIMER_SET,
+                self.handle.as_raw(),
+                value_ns,
+                interval_ns,
+                flags as u64,
+            )
+        };
+        Error::from_raw(result).map(|_| ())
+    }
+
+    /// Expire once, `delay_ns` from now
+    pub fn arm_after(&self, delay_ns: u64) -> Result<(), Error> {
+        self.set(delay_ns.max(1), 0, 0)
+    }
+
+    /// Expire once, when the timer's clock reaches `deadline_ns`
+    pub fn arm_at(&self, deadline_ns: u64) -> Result<(), Error> {
+        self.set(deadline_ns.max(1), 0, TIMER_ABSOLUTE)
+    }
+
+    /// Expire every `period_ns`, starting one period from now
+    pub fn arm_periodic(&self, period_ns: u64) -> Result<(), Error> {
+        let period_ns = period_ns.max(1);
+        self.set(period_ns, period_ns, 0)
+    }
+
+    /// Disarm the timer, keeping unread expirations
+    pub fn cancel(&self) -> Result<(), Error> {
+        let result = unsafe { syscall::syscall1(nr::TIMER_CANCEL, self.handle.as_raw()) };
+        Error::from_raw(result).map(|_| ())
+    }
+
+    /// Take the number of expirations since the last read
+    ///
+    /// A periodic timer that fell behind reports every period it missed.
+    pub fn read(&self) -> Result<u64, Error> {
+        let result = unsafe { syscall::syscall1(nr::TIMER_READ, self.handle.as_raw()) };
+        Error::from_raw(result)
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = syscall::syscall1(nr::TIMER_DESTROY, self.handle.as_raw());
+        }
+    }
+}
@@ -70,6 +70,7 @@ mod process_module {
 
         assert!(types.contains(&"ProcessId".to_string()), "Missing ProcessId type");
         assert!(types.contains(&"WaitResult".to_string()), "Missing WaitResult type");
+        assert!(types.contains(&"ProcessStat".to_string()), "Missing ProcessStat type");
     }
 
     #[test]
@@ -81,8 +82,11 @@ mod process_module {
         assert!(functions.contains(&"getpid".to_string()), "Missing getpid function");
         assert!(functions.contains(&"getppid".to_string()), "Missing getppid function");
         assert!(functions.contains(&"spawn".to_string()), "Missing spawn function");
+        assert!(functions.contains(&"spawn_jit".to_string()), "Missing spawn_jit function");
+        assert!(functions.contains(&"spawn_linux".to_string()), "Missing spawn_linux function");
         assert!(functions.contains(&"exit".to_string()), "Missing exit function");
         assert!(functions.contains(&"wait".to_string()), "Missing wait function");
+        assert!(functions.contains(&"stat".to_string()), "Missing stat function");
     }
 }
 
@@ -209,6 +213,7 @@ mod tensor_module {
         assert!(types.contains(&"TensorShape".to_string()), "Missing TensorShape type");
         assert!(types.contains(&"DType".to_string()), "Missing DType type");
         assert!(types.contains(&"TensorBuffer".to_string()), "Missing TensorBuffer type");
+        assert!(types.contains(&"Placement".to_string()), "Missing Placement type");
         assert!(types.contains(&"InferenceConfig".to_string()), "Missing InferenceConfig type");
     }
 
@@ -233,6 +238,8 @@ mod time_module {
         let (_, types, _) = extract_public_items(&content);
 
         assert!(types.contains(&"Instant".to_string()), "Missing Instant type");
+        assert!(types.contains(&"Timer".to_string()), "Missing Timer type");
+        assert!(types.contains(&"Clock".to_string()), "Missing Clock type");
     }
 
     #[test]
@@ -245,6 +252,26 @@ mod time_module {
         assert!(functions.contains(&"now_us".to_string()), "Missing now_us function");
         assert!(functions.contains(&"now_ms".to_string()), "Missing now_ms function");
         assert!(functions.contains(&"now_secs_f64".to_string()), "Missing now_secs_f64 function");
+        assert!(functions.contains(&"clock_ns".to_string()), "Missing clock_ns function");
+    }
+}
+
+mod report_module {
+    use super::*;
+
+    #[test]
+    fn test_report_functions_exist() {
+        let content = fs::read_to_string("src/report.rs")
+            .expect("Failed to read src/report.rs");
+        let (functions, types, _) = extract_public_items(&content);
+
+        assert!(types.contains(&"ReportKind".to_string()), "Missing ReportKind type");
+        assert!(functions.contains(&"set_endpoint".to_string()), "Missing set_endpoint function");
+        assert!(functions.contains(&"add_context".to_string()), "Missing add_context function");
+        assert!(functions.contains(&"report_error".to_string()), "Missing report_error function");
+        assert!(functions.contains(&"report_panic".to_string()), "Missing report_panic function");
+        assert!(functions.contains(&"panic".to_string()), "Missing panic function");
+        assert!(functions.contains(&"parse".to_string()), "Missing parse function");
     }
 }
 
@@ -29,6 +29,7 @@ mod syscall_numbers {
         pub const CAP_IDENTIFY: u64 = 18;
         pub const CAP_GRANT: u64 = 19;
         pub const CAP_DROP: u64 = 20;
+        pub const NOTIFY_CREATE: u64 = 21;
 
         // Memory (32-63)
         pub const MEM_MAP: u64 = 32;
@@ -36,6 +37,10 @@ mod syscall_numbers {
         pub const MEM_PROTECT: u64 = 34;
         pub const MEM_ALLOC: u64 = 35;
         pub const MEM_FREE: u64 = 36;
+        pub const SHM_CREATE: u64 = 40;
+        pub const SHM_MAP: u64 = 41;
+        pub const SHM_UNMAP: u64 = 42;
+        pub const SHM_GRANT: u64 = 43;
 
         // Threads (64-79)
         pub const THREAD_CREATE: u64 = 64;
@@ -50,6 +55,7 @@ mod syscall_numbers {
         pub const PROCESS_WAIT: u64 = 82;
         pub const PROCESS_GETPID: u64 = 83;
         pub const PROCESS_GETPPID: u64 = 84;
+        pub const PROCESS_STAT: u64 = 85;
 
         // Tensor/AI (112-143)
         pub const TENSOR_ALLOC: u64 = 112;
@@ -68,6 +74,13 @@ mod syscall_numbers {
         // System (240-255)
         pub const DEBUG: u64 = 240;
         pub const GET_TIME: u64 = 241;
+        pub const KLOG_READ: u64 = 244;
+        pub const CLOCK_GET: u64 = 245;
+        pub const TIMER_CREATE: u64 = 246;
+        pub const TIMER_SET: u64 = 247;
+        pub const TIMER_CANCEL: u64 = 248;
+        pub const TIMER_READ: u64 = 249;
+        pub const TIMER_DESTROY: u64 = 250;
         pub const REBOOT: u64 = 254;
         pub const SHUTDOWN: u64 = 255;
     }
@@ -126,6 +139,7 @@ mod syscall_numbers {
         assert_eq!(libnyx.get("CAP_IDENTIFY"), Some(&expected::CAP_IDENTIFY));
         assert_eq!(libnyx.get("CAP_GRANT"), Some(&expected::CAP_GRANT));
         assert_eq!(libnyx.get("CAP_DROP"), Some(&expected::CAP_DROP));
+        assert_eq!(libnyx.get("NOTIFY_CREATE"), Some(&expected::NOTIFY_CREATE));
     }
 
     #[test]
@@ -137,6 +151,10 @@ mod syscall_numbers {
         assert_eq!(libnyx.get("MEM_PROTECT"), Some(&expected::MEM_PROTECT));
         assert_eq!(libnyx.get("MEM_ALLOC"), Some(&expected::MEM_ALLOC));
         assert_eq!(libnyx.get("MEM_FREE"), Some(&expected::MEM_FREE));
+        assert_eq!(libnyx.get("SHM_CREATE"), Some(&expected::SHM_CREATE));
+        assert_eq!(libnyx.get("SHM_MAP"), Some(&expected::SHM_MAP));
+        assert_eq!(libnyx.get("SHM_UNMAP"), Some(&expected::SHM_UNMAP));
+        assert_eq!(libnyx.get("SHM_GRANT"), Some(&expected::SHM_GRANT));
     }
 
     #[test]
@@ -159,6 +177,7 @@ mod syscall_numbers {
         assert_eq!(libnyx.get("PROCESS_WAIT"), Some(&expected::PROCESS_WAIT));
         assert_eq!(libnyx.get("PROCESS_GETPID"), Some(&expected::PROCESS_GETPID));
         assert_eq!(libnyx.get("PROCESS_GETPPID"), Some(&expected::PROCESS_GETPPID));
+        assert_eq!(libnyx.get("PROCESS_STAT"), Some(&expected::PROCESS_STAT));
     }
 
     #[test]
@@ -189,6 +208,13 @@ mod syscall_numbers {
 
         assert_eq!(libnyx.get("DEBUG"), Some(&expected::DEBUG));
         assert_eq!(libnyx.get("GET_TIME"), Some(&expected::GET_TIME));
+        assert_eq!(libnyx.get("KLOG_READ"), Some(&expected::KLOG_READ));
+        assert_eq!(libnyx.get("CLOCK_GET"), Some(&expected::CLOCK_GET));
+        assert_eq!(libnyx.get("TIMER_CREATE"), Some(&expected::TIMER_CREATE));
+        assert_eq!(libnyx.get("TIMER_SET"), Some(&expected::TIMER_SET));
+        assert_eq!(libnyx.get("TIMER_CANCEL"), Some(&expected::TIMER_CANCEL));
+        assert_eq!(libnyx.get("TIMER_READ"), Some(&expected::TIMER_READ));
+        assert_eq!(libnyx.get("TIMER_DESTROY"), Some(&expected::TIMER_DESTROY));
         assert_eq!(libnyx.get("REBOOT"), Some(&expected::REBOOT));
         assert_eq!(libnyx.get("SHUTDOWN"), Some(&expected::SHUTDOWN));
     }
@@ -203,8 +229,8 @@ mod syscall_numbers {
                 n if n.starts_with("RING_") || n == "SEND" || n == "RECEIVE" ||
                      n == "CALL" || n == "REPLY" || n == "SIGNAL" ||
                      n == "WAIT" || n == "POLL" => 0..16,
-                n if n.starts_with("CAP_") => 16..32,
-                n if n.starts_with("MEM_") => 32..64,
+                n if n.starts_with("CAP_") || n == "NOTIFY_CREATE" => 16..32,
+                n if n.starts_with("MEM_") || n.starts_with("SHM_") => 32..64,
                 n if n.starts_with("THREAD_") => 64..80,
                 n if n.starts_with("PROCESS_") => 80..96,
                 n if n.starts_with("FS_") => 96..112,
@@ -212,8 +238,9 @@ mod syscall_numbers {
                      n == "COMPUTE_SUBMIT" => 112..144,
                 n if n == "CHECKPOINT" || n == "RESTORE" ||
                      n.starts_with("RECORD_") => 144..160,
-                n if n == "DEBUG" || n == "GET_TIME" || n == "REBOOT" ||
-                     n == "SHUTDOWN" => 240..256,
+                n if n == "DEBUG" || n == "GET_TIME" || n == "KLOG_READ" ||
+                     n == "CLOCK_GET" || n.starts_with("TIMER_") ||
+                     n == "REBOOT" || n == "SHUTDOWN" => 240..256,
                 _ => continue,
             };
 
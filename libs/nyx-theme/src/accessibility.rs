@@ -0,0 +1,388 @@
+//! Accessibility options honored by every Nyx widget style
+//!
+//! Options are per user and live in the grimoire next to the rest of the
+//! user's settings, falling back to a system-wide file:
+//!
+//! ```toml
+//! # ~/.grimoire/settings/accessibility.toml
+//! contrast = "high"            # standard | more | high
+//! reduce-transparency = true   # opaque surfaces, no blur or soft shadows
+//! text-scale = 1.5
+//! ```
+//!
+//! The options in effect are process-global: once an application calls
+//! [`set`], all styles built by this crate apply them on the next redraw.
+//! [`watch`] reports changes saved by other processes, e.g. the settings app.
+
+use crate::loader::ThemeError;
+use crate::theme::ThemeMode;
+use iced::futures::stream;
+use iced::widget::{button, container, slider, text_input, toggler};
+use iced::{Background, Border, Color, Shadow, Subscription};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
+
+/// System-wide options, used when the user has none
+pub const SYSTEM_SETTINGS: &str = "/grimoire/settings/accessibility.toml";
+
+/// Smallest and largest text scale multiplier
+pub const TEXT_SCALE_RANGE: (f32, f32) = (0.8, 3.0);
+
+/// How often `watch` checks the settings file
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Surfaces at least this opaque count as solid and lose their transparency
+const SOLID_ALPHA: f32 = 0.5;
+
+static CURRENT: RwLock<AccessibilityOptions> = RwLock::new(AccessibilityOptions::DEFAULT);
+
+/// How strongly outlines and text stand out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContrastLevel {
+    #[default]
+    Standard,
+    /// Firmer borders and fully opaque text
+    More,
+    /// The high contrast palette, opaque surfaces and thick outlines
+    High,
+}
+
+/// Accessibility settings of a user
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AccessibilityOptions {
+    pub contrast: ContrastLevel,
+    /// Opaque surfaces instead of glass, no blur or soft shadows
+    pub reduce_transparency: bool,
+    /// Multiplier applied to every text size
+    pub text_scale: f32,
+}
+
+impl Default for AccessibilityOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl AccessibilityOptions {
+    pub const DEFAULT: Self = Self {
+        contrast: ContrastLevel::Standard,
+        reduce_transparency: false,
+        text_scale: 1.0,
+    };
+
+    /// The user's settings file, `~/.grimoire/settings/accessibility.toml`
+    pub fn user_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".into());
+        PathBuf::from(home).join(".grimoire/settings/accessibility.toml")
+    }
+
+    /// Load the user's options, else the system's, else the defaults
+    pub fn load_user() -> Result<Self, ThemeError> {
+        let user = Self::user_path();
+        if user.exists() {
+            Self::load(&user)
+        } else {
+            Self::load(Path::new(SYSTEM_SETTINGS))
+        }
+    }
+
+    /// Load options from `path`; a missing file gives the defaults
+    pub fn load(path: &Path) -> Result<Self, ThemeError> {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::DEFAULT),
+            Err(e) => return Err(e.into()),
+        };
+
+        let options: Self = toml::from_str(&source)?;
+        options.validate()?;
+        Ok(options)
+    }
+
+    /// Save options to `path`, replacing the file atomically
+    pub fn save(&self, path: &Path) -> Result<(), ThemeError> {
+        self.validate()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let source = toml::to_string(self).expect("accessibility options serialize to TOML");
+        let tmp = path.with_extension("toml.tmp");
+        std::fs::write(&tmp, source)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ThemeError> {
+        let (min, max) = TEXT_SCALE_RANGE;
+        if !(min..=max).contains(&self.text_scale) {
+            return Err(ThemeError::OutOfRange {
+                field: "text-scale",
+                value: self.text_scale,
+                min,
+                max,
+            });
+        }
+        Ok(())
+    }
+
+    /// The mode to draw with when the user asked for `mode`
+    pub fn mode(&self, mode: ThemeMode) -> ThemeMode {
+        match self.contrast {
+            ContrastLevel::High => ThemeMode::HighContrast,
+            _ => mode,
+        }
+    }
+
+    /// Scale a `Typography` size
+    pub fn text_size(&self, size: f32) -> f32 {
+        size * self.text_scale
+    }
+
+    fn surface(&self, color: Color) -> Color {
+        let solid = self.reduce_transparency || self.contrast == ContrastLevel::High;
+        if solid && color.a >= SOLID_ALPHA {
+            Color { a: 1.0, ..color }
+        } else {
+            color
+        }
+    }
+
+    fn background(&self, background: Background) -> Background {
+        match background {
+            Background::Color(color) => Background::Color(self.surface(color)),
+            gradient => gradient,
+        }
+    }
+
+    /// Adjust an outline; outlines a style leaves off stay off
+    fn outline(&self, color: Color, width: f32) -> (Color, f32) {
+        if width == 0.0 {
+            return (color, width);
+        }
+        match self.contrast {
+            ContrastLevel::Standard => (color, width),
+            ContrastLevel::More => (Color { a: color.a.max(0.35), ..color }, width),
+            ContrastLevel::High => (Color { a: 1.0, ..color }, width.max(2.0)),
+        }
+    }
+
+    fn border(&self, border: Border) -> Border {
+        let (color, width) = self.outline(border.color, border.width);
+        Border { color, width, ..border }
+    }
+
+    fn text(&self, color: Color) -> Color {
+        match self.contrast {
+            ContrastLevel::Standard => color,
+            _ => Color { a: 1.0, ..color },
+        }
+    }
+
+    fn shadow(&self, shadow: Shadow) -> Shadow {
+        if self.reduce_transparency || self.contrast == ContrastLevel::High {
+            Shadow::default()
+        } else {
+            shadow
+        }
+    }
+}
+
+/// The options in effect for this process
+pub fn current() -> AccessibilityOptions {
+    *CURRENT.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Change the options in effect for this process
+pub fn set(options: AccessibilityOptions) {
+    *CURRENT.write().unwrap_or_else(|e| e.into_inner()) = options;
+}
+
+/// Report the options saved at `path` now and whenever the file changes
+pub fn watch(path: impl Into<PathBuf>) -> Subscription<AccessibilityOptions> {
+    let path = path.into();
+
+    Subscription::run_with_id(
+        ("nyx-theme-accessibility", path.clone()),
+        stream::unfold((path, None::<Option<SystemTime>>), |(path, mut seen)| async move {
+            loop {
+                let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+                if seen != Some(modified) {
+                    let first = seen.is_none();
+                    seen = Some(modified);
+                    // A half-written or invalid file keeps the options in effect
+                    if let Ok(options) = AccessibilityOptions::load(&path) {
+                        if first || options != current() {
+                            return Some((options, (path, seen)));
+                        }
+                    }
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }),
+    )
+}
+
+/// Styles that can be adjusted to accessibility options
+pub trait Accessible: Sized {
+    fn accessible_with(self, options: &AccessibilityOptions) -> Self;
+
+    /// Adjust to the options in effect
+    fn accessible(self) -> Self {
+        self.accessible_with(&current())
+    }
+}
+
+/// Wrap a style function so its styles honor the options in effect
+pub fn accessible<S: Accessible>(style: impl Fn(&iced::Theme) -> S) -> impl Fn(&iced::Theme) -> S {
+    move |theme| style(theme).accessible()
+}
+
+/// Like [`accessible`], for widgets whose style depends on a status
+pub fn accessible_with_status<S: Accessible, Status>(
+    style: impl Fn(&iced::Theme, Status) -> S,
+) -> impl Fn(&iced::Theme, Status) -> S {
+    move |theme, status| style(theme, status).accessible()
+}
+
+impl Accessible for container::Style {
+    fn accessible_with(self, options: &AccessibilityOptions) -> Self {
+        Self {
+            background: self.background.map(|b| options.background(b)),
+            text_color: self.text_color.map(|c| options.text(c)),
+            border: options.border(self.border),
+            shadow: options.shadow(self.shadow),
+        }
+    }
+}
+
+impl Accessible for button::Style {
+    fn accessible_with(self, options: &AccessibilityOptions) -> Self {
+        Self {
+            background: self.background.map(|b| options.background(b)),
+            text_color: options.text(self.text_color),
+            border: options.border(self.border),
+            shadow: options.shadow(self.shadow),
+        }
+    }
+}
+
+impl Accessible for text_input::Style {
+    fn accessible_with(self, options: &AccessibilityOptions) -> Self {
+        Self {
+            background: options.background(self.background),
+            border: options.border(self.border),
+            icon: options.text(self.icon),
+            value: options.text(self.value),
+            ..self
+        }
+    }
+}
+
+impl Accessible for toggler::Style {
+    fn accessible_with(self, options: &AccessibilityOptions) -> Self {
+        let (background_border_color, background_border_width) =
+            options.outline(self.background_border_color, self.background_border_width);
+        Self {
+            background: options.surface(self.background),
+            background_border_color,
+            background_border_width,
+            ..self
+        }
+    }
+}
+
+impl Accessible for slider::Style {
+    fn accessible_with(mut self, options: &AccessibilityOptions) -> Self {
+        self.rail.border = options.border(self.rail.border);
+        let (color, width) = options.outline(self.handle.border_color, self.handle.border_width);
+        self.handle.border_color = color;
+        self.handle.border_width = width;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::NyxColors;
+    use iced::Vector;
+
+    fn options(contrast: ContrastLevel, reduce_transparency: bool) -> AccessibilityOptions {
+        AccessibilityOptions { contrast, reduce_transparency, text_scale: 1.0 }
+    }
+
+    fn dock() -> container::Style {
+        container::Style {
+            background: Some(Background::Color(NyxColors::GLASS_DARK)),
+            text_color: Some(NyxColors::TEXT_BRIGHT),
+            border: Border {
+                color: NyxColors::BORDER_DARK,
+                width: 1.0,
+                radius: 12.0.into(),
+            },
+            shadow: Shadow {
+                color: Color::from_rgba(0.0, 0.0, 0.0, 0.4),
+                offset: Vector::new(0.0, 4.0),
+                blur_radius: 16.0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_standard_options_change_nothing() {
+        let style = dock().accessible_with(&AccessibilityOptions::DEFAULT);
+        assert_eq!(style.background, dock().background);
+        assert_eq!(style.border, dock().border);
+        assert_eq!(style.shadow, dock().shadow);
+    }
+
+    #[test]
+    fn test_reduce_transparency_makes_glass_opaque() {
+        let style = dock().accessible_with(&options(ContrastLevel::Standard, true));
+        assert_eq!(style.background, Some(Background::Color(Color { a: 1.0, ..NyxColors::GLASS_DARK })));
+        assert_eq!(style.shadow, Shadow::default());
+        // Faint hover tints are not surfaces
+        let tint = options(ContrastLevel::Standard, true).surface(Color::from_rgba(1.0, 1.0, 1.0, 0.1));
+        assert_eq!(tint.a, 0.1);
+    }
+
+    #[test]
+    fn test_high_contrast_thickens_outlines() {
+        let style = dock().accessible_with(&options(ContrastLevel::High, false));
+        assert_eq!(style.border.width, 2.0);
+        assert_eq!(style.border.color.a, 1.0);
+
+        let none = options(ContrastLevel::High, false).border(Border::default());
+        assert_eq!(none.width, 0.0);
+    }
+
+    #[test]
+    fn test_high_contrast_selects_mode() {
+        assert_eq!(options(ContrastLevel::High, false).mode(ThemeMode::Light), ThemeMode::HighContrast);
+        assert_eq!(options(ContrastLevel::More, false).mode(ThemeMode::Light), ThemeMode::Light);
+    }
+
+    #[test]
+    fn test_options_round_trip_and_validate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings/accessibility.toml");
+        assert_eq!(AccessibilityOptions::load(&path).unwrap(), AccessibilityOptions::DEFAULT);
+
+        let options = AccessibilityOptions {
+            contrast: ContrastLevel::More,
+            reduce_transparency: true,
+            text_scale: 1.5,
+        };
+        options.save(&path).unwrap();
+        assert_eq!(AccessibilityOptions::load(&path).unwrap(), options);
+        assert!(std::fs::read_to_string(&path).unwrap().contains("reduce-transparency = true"));
+
+        std::fs::write(&path, "text-scale = 9.0").unwrap();
+        assert!(matches!(AccessibilityOptions::load(&path), Err(ThemeError::OutOfRange { .. })));
+    }
+}
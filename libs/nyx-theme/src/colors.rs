@@ -200,8 +200,98 @@ impl ColorPalette {
             glass: NyxColors::GLASS_LIGHT,
         }
     }
+
+    /// Create the high contrast palette: pure black and white with opaque borders
+    pub fn high_contrast() -> Self {
+        Self {
+            background: Color::BLACK,
+            surface: Color::BLACK,
+            surface_elevated: NyxColors::MIDNIGHT,
+
+            text_primary: Color::WHITE,
+            text_secondary: Color::WHITE,
+            text_disabled: NyxColors::TEXT_SECONDARY,
+
+            accent: NyxColors::AURORA_LIGHT,
+            accent_hover: lighten(NyxColors::AURORA_LIGHT, 0.12),
+            accent_pressed: NyxColors::AURORA,
+
+            success: NyxColors::SUCCESS,
+            warning: NyxColors::WARNING,
+            error: NyxColors::ERROR,
+            info: NyxColors::INFO,
+
+            border: Color::WHITE,
+            border_focus: NyxColors::WARNING,
+
+            overlay: Color::from_rgba(0.0, 0.0, 0.0, 0.85),
+            glass: Color::BLACK,
+        }
+    }
+}
+
+/// Declares `PaletteOverrides` with one optional hex color per palette field
+macro_rules! palette_overrides {
+    ($($field:ident),* $(,)?) => {
+        /// Hex colors replacing entries of a base palette, as theme files give them
+        #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+        #[serde(default, deny_unknown_fields)]
+        pub struct PaletteOverrides {
+            $(
+                #[serde(skip_serializing_if = "Option::is_none")]
+                pub $field: Option<String>,
+            )*
+        }
+
+        impl PaletteOverrides {
+            /// Replace the overridden entries of `base`; invalid colors are skipped
+            pub fn apply(&self, mut base: ColorPalette) -> ColorPalette {
+                $(
+                    if let Some(color) = self.$field.as_deref().and_then(crate::theme::parse_hex_color) {
+                        base.$field = color;
+                    }
+                )*
+                base
+            }
+
+            /// Overridden entries as (field, hex)
+            pub fn entries(&self) -> Vec<(&'static str, &str)> {
+                let mut entries = Vec::new();
+                $(
+                    if let Some(ref hex) = self.$field {
+                        entries.push((stringify!($field), hex.as_str()));
+                    }
+                )*
+                entries
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.entries().is_empty()
+            }
+        }
+    };
 }
 
+palette_overrides!(
+    background,
+    surface,
+    surface_elevated,
+    text_primary,
+    text_secondary,
+    text_disabled,
+    accent,
+    accent_hover,
+    accent_pressed,
+    success,
+    warning,
+    error,
+    info,
+    border,
+    border_focus,
+    overlay,
+    glass,
+);
+
 /// Theme accent color options
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum AccentColor {
@@ -272,6 +362,26 @@ pub fn with_alpha(color: Color, alpha: f32) -> Color {
     Color::from_rgba(color.r, color.g, color.b, alpha)
 }
 
+/// WCAG relative luminance of a color (alpha ignored)
+pub fn relative_luminance(color: Color) -> f32 {
+    fn channel(c: f32) -> f32 {
+        if c <= 0.039_28 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    }
+
+    0.2126 * channel(color.r) + 0.7152 * channel(color.g) + 0.0722 * channel(color.b)
+}
+
+/// WCAG contrast ratio between two colors, from 1.0 to 21.0
+pub fn contrast_ratio(a: Color, b: Color) -> f32 {
+    let (la, lb) = (relative_luminance(a), relative_luminance(b));
+    let (light, dark) = if la > lb { (la, lb) } else { (lb, la) };
+    (light + 0.05) / (dark + 0.05)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -373,6 +483,24 @@ mod tests {
         assert!((with_alpha.a - 0.5).abs() < 0.001);
     }
 
+    #[test]
+    fn test_contrast_ratio_extremes() {
+        assert!((contrast_ratio(Color::BLACK, Color::WHITE) - 21.0).abs() < 0.01);
+        assert!((contrast_ratio(Color::WHITE, Color::WHITE) - 1.0).abs() < 0.01);
+        // Order does not matter
+        assert_eq!(
+            contrast_ratio(NyxColors::AURORA, NyxColors::MIDNIGHT),
+            contrast_ratio(NyxColors::MIDNIGHT, NyxColors::AURORA)
+        );
+    }
+
+    #[test]
+    fn test_builtin_palettes_meet_text_contrast() {
+        for palette in [ColorPalette::dark(), ColorPalette::light()] {
+            assert!(contrast_ratio(palette.text_primary, palette.background) >= 4.5);
+        }
+    }
+
     #[test]
     fn test_accent_hover_is_lighter() {
         let base = AccentColor::Aurora.to_color();
@@ -430,4 +558,12 @@ mod tests {
         assert!(NyxColors::WARNING.r > 0.8);
         assert!(NyxColors::WARNING.g > 0.6);
     }
+
+    #[test]
+    fn test_high_contrast_palette_meets_aaa() {
+        let palette = ColorPalette::high_contrast();
+        assert!(contrast_ratio(palette.text_primary, palette.background) >= 7.0);
+        assert!(contrast_ratio(palette.accent, palette.background) >= 4.5);
+        assert_eq!(palette.border.a, 1.0);
+    }
 }
@@ -0,0 +1,333 @@
+//! Dynamic palettes derived from the wallpaper
+//!
+//! The wallpaper is downsampled and quantized with median cut; the most
+//! vivid, well-represented color becomes the accent and the best one of a
+//! clearly different hue the secondary. Both are then nudged in lightness
+//! until they meet the contrast rules against the mode's background, so a
+//! dull or extreme wallpaper still yields a usable palette.
+
+use crate::colors::{
+    contrast_ratio, darken, lighten, AccentColor, ColorPalette, NyxColors, PaletteOverrides,
+};
+use crate::loader::ThemeError;
+use crate::theme::{color_to_hex, NyxTheme, ThemeMode};
+use crate::widgets::variant::on_color;
+use iced::Color;
+use palette::{Hsl, IntoColor, Srgb};
+use std::path::Path;
+
+/// Edge length the wallpaper is downsampled to before quantizing
+const SAMPLE_SIZE: u32 = 64;
+
+/// Colors the wallpaper is reduced to
+const SWATCHES: usize = 16;
+
+/// Minimum contrast of the accent against the background (WCAG non-text)
+pub const MIN_ACCENT_CONTRAST: f32 = 3.0;
+
+/// Minimum contrast of text drawn on the accent
+pub const MIN_ON_ACCENT_CONTRAST: f32 = 4.5;
+
+/// Swatches less saturated than this are treated as gray
+const MIN_SATURATION: f32 = 0.15;
+
+/// Minimum hue distance between accent and secondary, in degrees
+const MIN_HUE_DISTANCE: f32 = 30.0;
+
+/// One quantized color and how many sampled pixels it stands for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Swatch {
+    pub color: Color,
+    pub population: u32,
+}
+
+/// Accent colors extracted from a wallpaper
+#[derive(Debug, Clone)]
+pub struct DynamicPalette {
+    pub mode: ThemeMode,
+    pub accent: Color,
+    pub secondary: Color,
+    /// Quantized colors, most common first
+    pub swatches: Vec<Swatch>,
+}
+
+impl DynamicPalette {
+    /// Decode and analyse a wallpaper
+    ///
+    /// Decoding is blocking; the shell should run this off the UI thread,
+    /// e.g. in a `Task::perform`, when the wallpaper changes.
+    pub fn from_wallpaper(path: &Path, mode: ThemeMode) -> Result<Self, ThemeError> {
+        let image = image::open(path)?.thumbnail(SAMPLE_SIZE, SAMPLE_SIZE).to_rgb8();
+        let pixels: Vec<[u8; 3]> = image.pixels().map(|p| p.0).collect();
+        Ok(Self::from_pixels(&pixels, mode))
+    }
+
+    /// Analyse raw RGB pixels
+    pub fn from_pixels(pixels: &[[u8; 3]], mode: ThemeMode) -> Self {
+        let swatches = quantize(pixels, SWATCHES);
+        let background = base_palette(mode).background;
+
+        let mut candidates: Vec<(Swatch, Hsl)> = swatches
+            .iter()
+            .map(|s| (*s, to_hsl(s.color)))
+            .filter(|(_, hsl)| {
+                hsl.saturation >= MIN_SATURATION && (0.1..=0.92).contains(&hsl.lightness)
+            })
+            .collect();
+        candidates.sort_by(|a, b| score(b).total_cmp(&score(a)));
+
+        let (accent, secondary) = match candidates.first() {
+            Some((first, first_hsl)) => {
+                let secondary = candidates
+                    .iter()
+                    .find(|(_, hsl)| hue_distance(hsl, first_hsl) >= MIN_HUE_DISTANCE)
+                    .map(|(s, _)| s.color)
+                    .unwrap_or_else(|| rotate_hue(first.color, MIN_HUE_DISTANCE * 2.0));
+                (first.color, secondary)
+            }
+            // A grayscale wallpaper has nothing to offer; keep the brand accent
+            None => (NyxColors::AURORA, NyxColors::ETHEREAL),
+        };
+
+        Self {
+            mode,
+            accent: accessible(accent, background),
+            secondary: accessible(secondary, background),
+            swatches,
+        }
+    }
+
+    /// The mode's palette with the extracted accent
+    pub fn palette(&self) -> ColorPalette {
+        let mut palette = base_palette(self.mode);
+        palette.accent = self.accent;
+        palette.accent_hover = lighten(self.accent, 0.08);
+        palette.accent_pressed = darken(self.accent, 0.08);
+        palette.border_focus = Color { a: 0.6, ..self.accent };
+        palette.info = self.secondary;
+        palette
+    }
+
+    /// The accent entries as theme overrides
+    pub fn overrides(&self) -> PaletteOverrides {
+        let palette = self.palette();
+        PaletteOverrides {
+            accent: Some(color_to_hex(palette.accent)),
+            accent_hover: Some(color_to_hex(palette.accent_hover)),
+            accent_pressed: Some(color_to_hex(palette.accent_pressed)),
+            info: Some(color_to_hex(palette.info)),
+            ..Default::default()
+        }
+    }
+}
+
+impl NyxTheme {
+    /// Take the accent from a wallpaper, keeping any other overrides
+    pub fn apply_wallpaper(&mut self, path: &Path) -> Result<DynamicPalette, ThemeError> {
+        let dynamic = DynamicPalette::from_wallpaper(path, self.mode)?;
+        let overrides = dynamic.overrides();
+
+        self.palette_overrides.accent = overrides.accent;
+        self.palette_overrides.accent_hover = overrides.accent_hover;
+        self.palette_overrides.accent_pressed = overrides.accent_pressed;
+        self.palette_overrides.info = overrides.info;
+        self.accent = AccentColor::Custom;
+        self.custom_accent_hex = Some(color_to_hex(dynamic.accent));
+        Ok(dynamic)
+    }
+}
+
+/// Median cut quantization of `pixels` into at most `max_colors` swatches
+pub fn quantize(pixels: &[[u8; 3]], max_colors: usize) -> Vec<Swatch> {
+    if pixels.is_empty() || max_colors == 0 {
+        return Vec::new();
+    }
+
+    let mut boxes = vec![pixels.to_vec()];
+    while boxes.len() < max_colors {
+        // Split the box with the widest channel range
+        let Some((index, channel, _)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| {
+                let (channel, range) = widest_channel(b);
+                (i, channel, range)
+            })
+            .filter(|(_, _, range)| *range > 0)
+            .max_by_key(|(_, _, range)| *range)
+        else {
+            break;
+        };
+
+        let mut b = boxes.swap_remove(index);
+        b.sort_unstable_by_key(|p| p[channel]);
+        // Cut at the median, but never through a run of equal values
+        let pivot = b[b.len() / 2][channel];
+        let mut cut = b.partition_point(|p| p[channel] < pivot);
+        if cut == 0 {
+            cut = b.partition_point(|p| p[channel] <= pivot);
+        }
+        let upper = b.split_off(cut);
+        boxes.push(b);
+        boxes.push(upper);
+    }
+
+    let mut swatches: Vec<Swatch> = boxes.iter().map(|b| average(b)).collect();
+    swatches.sort_by_key(|s| std::cmp::Reverse(s.population));
+    swatches
+}
+
+fn widest_channel(pixels: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|c| {
+            let (min, max) = pixels
+                .iter()
+                .fold((u8::MAX, u8::MIN), |(min, max), p| (min.min(p[c]), max.max(p[c])));
+            (c, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+fn average(pixels: &[[u8; 3]]) -> Swatch {
+    let sum = pixels.iter().fold([0u64; 3], |mut sum, p| {
+        for c in 0..3 {
+            sum[c] += p[c] as u64;
+        }
+        sum
+    });
+    let n = pixels.len() as u64;
+    Swatch {
+        color: Color::from_rgb8((sum[0] / n) as u8, (sum[1] / n) as u8, (sum[2] / n) as u8),
+        population: pixels.len() as u32,
+    }
+}
+
+/// Vivid colors win, but a large area of a calmer color beats a speck
+fn score((swatch, hsl): &(Swatch, Hsl)) -> f32 {
+    let balance = 1.0 - (hsl.lightness - 0.5).abs();
+    hsl.saturation * balance * (swatch.population as f32).sqrt()
+}
+
+/// Adjust lightness until `color` meets the contrast rules on `background`
+///
+/// Moving away from the background's lightness raises both the contrast
+/// against the background and that of the text drawn on the accent, so one
+/// direction satisfies both rules.
+fn accessible(color: Color, background: Color) -> Color {
+    let step = if contrast_ratio(Color::WHITE, background) > contrast_ratio(Color::BLACK, background) {
+        0.02
+    } else {
+        -0.02
+    };
+
+    let mut hsl = to_hsl(color);
+    loop {
+        let candidate = from_hsl(hsl);
+        let readable = contrast_ratio(candidate, background) >= MIN_ACCENT_CONTRAST
+            && contrast_ratio(on_color(candidate), candidate) >= MIN_ON_ACCENT_CONTRAST;
+        if readable || !(0.0..=1.0).contains(&(hsl.lightness + step)) {
+            return candidate;
+        }
+        hsl.lightness += step;
+    }
+}
+
+fn base_palette(mode: ThemeMode) -> ColorPalette {
+    match mode {
+        ThemeMode::Light => ColorPalette::light(),
+        ThemeMode::Dark | ThemeMode::System => ColorPalette::dark(),
+        ThemeMode::HighContrast => ColorPalette::high_contrast(),
+    }
+}
+
+fn to_hsl(color: Color) -> Hsl {
+    Srgb::new(color.r, color.g, color.b).into_color()
+}
+
+fn from_hsl(hsl: Hsl) -> Color {
+    let rgb: Srgb = hsl.into_color();
+    Color::from_rgb(rgb.red.clamp(0.0, 1.0), rgb.green.clamp(0.0, 1.0), rgb.blue.clamp(0.0, 1.0))
+}
+
+fn hue_distance(a: &Hsl, b: &Hsl) -> f32 {
+    let d = (a.hue.into_positive_degrees() - b.hue.into_positive_degrees()).abs();
+    d.min(360.0 - d)
+}
+
+fn rotate_hue(color: Color, degrees: f32) -> Color {
+    let hsl = to_hsl(color);
+    from_hsl(Hsl::new(hsl.hue + degrees, hsl.saturation, hsl.lightness))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(color: [u8; 3], n: usize) -> Vec<[u8; 3]> {
+        vec![color; n]
+    }
+
+    #[test]
+    fn test_quantize_separates_distinct_colors() {
+        let mut pixels = fill([200, 30, 30], 300);
+        pixels.extend(fill([30, 30, 200], 100));
+
+        let swatches = quantize(&pixels, 4);
+        assert_eq!(swatches.len(), 2);
+        assert_eq!(swatches[0].population, 300);
+        assert!(swatches[0].color.r > swatches[0].color.b);
+    }
+
+    #[test]
+    fn test_vivid_color_beats_gray_background() {
+        let mut pixels = fill([90, 90, 90], 900);
+        pixels.extend(fill([20, 160, 90], 100));
+
+        let dynamic = DynamicPalette::from_pixels(&pixels, ThemeMode::Dark);
+        let hsl = to_hsl(dynamic.accent);
+        assert!(dynamic.accent.g > dynamic.accent.r && dynamic.accent.g > dynamic.accent.b);
+        assert!(hsl.saturation > MIN_SATURATION);
+    }
+
+    #[test]
+    fn test_grayscale_wallpaper_keeps_brand_accent() {
+        let dynamic = DynamicPalette::from_pixels(&fill([128, 128, 128], 100), ThemeMode::Dark);
+        let background = ColorPalette::dark().background;
+        assert!(contrast_ratio(dynamic.accent, background) >= MIN_ACCENT_CONTRAST);
+    }
+
+    #[test]
+    fn test_accent_meets_contrast_in_both_modes() {
+        // Very dark navy: unusable on a dark background as-is
+        let pixels = fill([10, 20, 60], 100);
+        for mode in [ThemeMode::Dark, ThemeMode::Light] {
+            let dynamic = DynamicPalette::from_pixels(&pixels, mode);
+            let background = dynamic.palette().background;
+            assert!(contrast_ratio(dynamic.accent, background) >= MIN_ACCENT_CONTRAST, "{:?}", mode);
+            assert!(contrast_ratio(dynamic.secondary, background) >= MIN_ACCENT_CONTRAST, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn test_secondary_hue_differs_from_accent() {
+        let mut pixels = fill([220, 40, 40], 500);
+        pixels.extend(fill([40, 60, 220], 300));
+
+        let dynamic = DynamicPalette::from_pixels(&pixels, ThemeMode::Dark);
+        assert!(hue_distance(&to_hsl(dynamic.accent), &to_hsl(dynamic.secondary)) >= MIN_HUE_DISTANCE);
+    }
+
+    #[test]
+    fn test_apply_wallpaper_overrides_accent() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wallpaper.png");
+        image::RgbImage::from_pixel(8, 8, image::Rgb([200, 60, 20])).save(&path).unwrap();
+
+        let mut theme = NyxTheme::dark();
+        let dynamic = theme.apply_wallpaper(&path).unwrap();
+        assert_eq!(color_to_hex(theme.palette().accent), color_to_hex(dynamic.accent));
+        assert_eq!(theme.palette_overrides.background, None);
+    }
+}
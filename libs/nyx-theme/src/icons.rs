@@ -1,11 +1,80 @@
 //! Icon system for Nyx OS theme
 //!
 //! Provides icon paths and helpers for consistent iconography across
-//! all Nyx OS applications. Uses a combination of custom icons and
-//! Material Design icons.
+//! all Nyx OS applications. Icons come from the user's freedesktop icon
+//! theme (falling back through its parents to hicolor), rendered at the
+//! requested size and, for symbolic icons, recolored to the palette. The
+//! Material Design glyphs remain as a last resort when no theme has one.
 
+pub mod cache;
+pub mod lookup;
+
+pub use cache::{IconCache, IconError};
+pub use lookup::IconLookup;
+
+use iced::widget::image::Handle;
+use iced::widget::{image, text};
+use iced::{Color, Element};
 use serde::{Deserialize, Serialize};
 
+/// Icon theme used when none is configured
+pub const DEFAULT_ICON_THEME: &str = "Adwaita";
+
+/// Themed icons: lookup, rendering and caching in one place
+pub struct IconLoader {
+    lookup: IconLookup,
+    cache: IconCache,
+    /// Output scale factor (2 on HiDPI)
+    scale: u32,
+}
+
+impl IconLoader {
+    pub fn new(theme: &str) -> Self {
+        Self {
+            lookup: IconLookup::new(theme),
+            cache: IconCache::default(),
+            scale: 1,
+        }
+    }
+
+    pub fn with_lookup(lookup: IconLookup) -> Self {
+        Self { lookup, cache: IconCache::default(), scale: 1 }
+    }
+
+    /// Switch icon theme; rendered icons from the old one are dropped
+    pub fn set_theme(&mut self, theme: &str) {
+        self.lookup = IconLookup::new(theme);
+        self.cache.clear();
+    }
+
+    pub fn set_scale(&mut self, scale: u32) {
+        self.scale = scale.max(1);
+    }
+
+    /// Icon `name` at `size` logical pixels; symbolic variants are
+    /// preferred and painted `color`
+    pub fn get(&mut self, name: &str, size: f32, color: Color) -> Option<Handle> {
+        let pixels = size.round().max(1.0) as u32;
+        let (path, symbolic) = self.lookup.find_symbolic(name, pixels, self.scale)?;
+        self.cache
+            .get(&path, pixels * self.scale, symbolic.then_some(color))
+            .ok()
+    }
+
+    /// Themed rendering of a Nyx icon
+    pub fn icon(&mut self, icon: NyxIcon, size: f32, color: Color) -> Option<Handle> {
+        self.get(icon.icon_name(), size, color)
+    }
+
+    /// A Nyx icon as a widget, drawn as its glyph if no theme provides it
+    pub fn view<'a, Message: 'a>(&mut self, icon: NyxIcon, size: f32, color: Color) -> Element<'a, Message> {
+        match self.icon(icon, size, color) {
+            Some(handle) => image(handle).width(size).height(size).into(),
+            None => text(icon.to_char()).size(size).color(color).into(),
+        }
+    }
+}
+
 /// Icon identifier for the Nyx icon system
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[non_exhaustive]
@@ -608,4 +677,138 @@ impl NyxIcon {
             NyxIcon::Screenshot => "screenshot",
         }
     }
+
+    /// Freedesktop icon name; look it up with a `-symbolic` suffix for
+    /// single-color variants (see [`IconLoader`])
+    pub fn icon_name(self) -> &'static str {
+        match self {
+            NyxIcon::NyxLogo => "nyx-logo",
+            NyxIcon::Activities => "view-app-grid",
+            NyxIcon::AppGrid => "view-app-grid",
+            NyxIcon::Search => "system-search",
+            NyxIcon::Settings => "preferences-system",
+            NyxIcon::Power => "system-shutdown",
+            NyxIcon::Lock => "system-lock-screen",
+            NyxIcon::User => "avatar-default",
+            NyxIcon::Menu => "open-menu",
+            NyxIcon::More => "view-more",
+            NyxIcon::Back => "go-previous",
+            NyxIcon::Forward => "go-next",
+            NyxIcon::Up => "go-up",
+            NyxIcon::Down => "go-down",
+            NyxIcon::Home => "go-home",
+            NyxIcon::Close => "window-close",
+            NyxIcon::Maximize => "window-maximize",
+            NyxIcon::Minimize => "window-minimize",
+            NyxIcon::Expand => "pan-down",
+            NyxIcon::Collapse => "pan-up",
+            NyxIcon::Fullscreen => "view-fullscreen",
+            NyxIcon::ExitFullscreen => "view-restore",
+            NyxIcon::Add => "list-add",
+            NyxIcon::Remove => "list-remove",
+            NyxIcon::Edit => "document-edit",
+            NyxIcon::Delete => "edit-delete",
+            NyxIcon::Copy => "edit-copy",
+            NyxIcon::Paste => "edit-paste",
+            NyxIcon::Cut => "edit-cut",
+            NyxIcon::Undo => "edit-undo",
+            NyxIcon::Redo => "edit-redo",
+            NyxIcon::Save => "document-save",
+            NyxIcon::Share => "emblem-shared",
+            NyxIcon::Download => "folder-download",
+            NyxIcon::Upload => "send-to",
+            NyxIcon::Refresh => "view-refresh",
+            NyxIcon::Sync => "emblem-synchronizing",
+            NyxIcon::Send => "mail-send",
+            NyxIcon::Pin => "view-pin",
+            NyxIcon::Unpin => "view-pin",
+            NyxIcon::Star => "non-starred",
+            NyxIcon::StarFilled => "starred",
+            NyxIcon::VolumeHigh => "audio-volume-high",
+            NyxIcon::VolumeMedium => "audio-volume-medium",
+            NyxIcon::VolumeLow => "audio-volume-low",
+            NyxIcon::VolumeMuted => "audio-volume-muted",
+            NyxIcon::Microphone => "audio-input-microphone",
+            NyxIcon::MicrophoneMuted => "microphone-sensitivity-muted",
+            NyxIcon::Headphones => "audio-headphones",
+            NyxIcon::Speaker => "audio-speakers",
+            NyxIcon::BrightnessHigh => "display-brightness",
+            NyxIcon::BrightnessMedium => "display-brightness",
+            NyxIcon::BrightnessLow => "display-brightness",
+            NyxIcon::NightLight => "night-light",
+            NyxIcon::Display => "video-display",
+            NyxIcon::ExternalDisplay => "video-joined-displays",
+            NyxIcon::WifiConnected => "network-wireless-signal-excellent",
+            NyxIcon::WifiWeak => "network-wireless-signal-weak",
+            NyxIcon::WifiDisconnected => "network-wireless-offline",
+            NyxIcon::Ethernet => "network-wired",
+            NyxIcon::BluetoothOn => "bluetooth-active",
+            NyxIcon::BluetoothOff => "bluetooth-disabled",
+            NyxIcon::BluetoothConnected => "bluetooth-active",
+            NyxIcon::AirplaneMode => "airplane-mode",
+            NyxIcon::Vpn => "network-vpn",
+            NyxIcon::Hotspot => "network-wireless-hotspot",
+            NyxIcon::BatteryFull => "battery-level-100",
+            NyxIcon::BatteryHigh => "battery-level-80",
+            NyxIcon::BatteryMedium => "battery-level-50",
+            NyxIcon::BatteryLow => "battery-level-20",
+            NyxIcon::BatteryCritical => "battery-level-0",
+            NyxIcon::BatteryCharging => "battery-level-50-charging",
+            NyxIcon::PowerPlugged => "ac-adapter",
+            NyxIcon::File => "text-x-generic",
+            NyxIcon::Folder => "folder",
+            NyxIcon::FolderOpen => "folder-open",
+            NyxIcon::Image => "image-x-generic",
+            NyxIcon::Video => "video-x-generic",
+            NyxIcon::AudioFile => "audio-x-generic",
+            NyxIcon::Document => "x-office-document",
+            NyxIcon::Code => "text-x-script",
+            NyxIcon::Archive => "package-x-generic",
+            NyxIcon::Cloud => "weather-overcast",
+            NyxIcon::CloudUpload => "send-to",
+            NyxIcon::CloudDownload => "folder-download",
+            NyxIcon::Chat => "user-available",
+            NyxIcon::Email => "mail-unread",
+            NyxIcon::Notification => "preferences-system-notifications",
+            NyxIcon::NotificationOff => "notifications-disabled",
+            NyxIcon::Bell => "preferences-system-notifications",
+            NyxIcon::BellOff => "notifications-disabled",
+            NyxIcon::Assistant => "nyx-assistant",
+            NyxIcon::Brain => "nyx-assistant",
+            NyxIcon::Sparkle => "starred",
+            NyxIcon::Robot => "applications-science",
+            NyxIcon::Wand => "tools-magic",
+            NyxIcon::Lightning => "power-profile-performance",
+            NyxIcon::Command => "utilities-terminal",
+            NyxIcon::Check => "object-select",
+            NyxIcon::CheckCircle => "emblem-ok",
+            NyxIcon::Warning => "dialog-warning",
+            NyxIcon::Error => "dialog-error",
+            NyxIcon::Info => "dialog-information",
+            NyxIcon::Help => "help-about",
+            NyxIcon::Loading => "process-working",
+            NyxIcon::Clock => "preferences-system-time",
+            NyxIcon::Calendar => "x-office-calendar",
+            NyxIcon::WindowClose => "window-close",
+            NyxIcon::WindowMaximize => "window-maximize",
+            NyxIcon::WindowMinimize => "window-minimize",
+            NyxIcon::WindowRestore => "window-restore",
+            NyxIcon::Terminal => "utilities-terminal",
+            NyxIcon::Bug => "applications-engineering",
+            NyxIcon::Key => "dialog-password",
+            NyxIcon::Shield => "security-high",
+            NyxIcon::Eye => "view-reveal",
+            NyxIcon::EyeOff => "view-conceal",
+            NyxIcon::Link => "insert-link",
+            NyxIcon::Unlink => "edit-clear",
+            NyxIcon::QrCode => "qr-code",
+            NyxIcon::Palette => "applications-graphics",
+            NyxIcon::Language => "preferences-desktop-locale",
+            NyxIcon::Keyboard => "input-keyboard",
+            NyxIcon::Mouse => "input-mouse",
+            NyxIcon::Gamepad => "input-gaming",
+            NyxIcon::Print => "printer",
+            NyxIcon::Screenshot => "applets-screenshooter",
+        }
+    }
 }
@@ -0,0 +1,212 @@
+//! Icon rasterization and caching
+//!
+//! SVGs are rendered with resvg at the exact pixel size requested, PNGs are
+//! resized to it. Symbolic icons are recolored by replacing every pixel's
+//! color with the requested one and keeping its alpha, which is what a
+//! single-color symbolic icon needs to follow the palette. Results are kept
+//! in an LRU keyed by file, pixel size and color.
+
+use iced::widget::image::Handle;
+use iced::Color;
+use lru::LruCache;
+use resvg::{tiny_skia, usvg};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+
+/// Rendered icons kept by default
+pub const DEFAULT_CAPACITY: usize = 256;
+
+#[derive(Debug, thiserror::Error)]
+pub enum IconError {
+    #[error("cannot read icon: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid SVG icon: {0}")]
+    Svg(#[from] usvg::Error),
+
+    #[error("invalid image icon: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("cannot render icon at {0}px")]
+    Size(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    path: PathBuf,
+    pixels: u32,
+    /// Recolor target as RGBA8, None for full-color icons
+    color: Option<[u8; 4]>,
+}
+
+/// A rendered icon
+#[derive(Debug, Clone)]
+pub struct RenderedIcon {
+    pub width: u32,
+    pub height: u32,
+    /// Straight (not premultiplied) RGBA8
+    pub rgba: Vec<u8>,
+}
+
+impl RenderedIcon {
+    pub fn handle(&self) -> Handle {
+        Handle::from_rgba(self.width, self.height, self.rgba.clone())
+    }
+}
+
+/// LRU cache of rendered icons
+pub struct IconCache {
+    entries: LruCache<Key, Handle>,
+}
+
+impl Default for IconCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl IconCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: LruCache::new(NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN)),
+        }
+    }
+
+    /// Render `path` at `pixels` square, recolored to `color` if given
+    pub fn get(&mut self, path: &Path, pixels: u32, color: Option<Color>) -> Result<Handle, IconError> {
+        let key = Key {
+            path: path.to_path_buf(),
+            pixels,
+            color: color.map(|c| c.into_rgba8()),
+        };
+        if let Some(handle) = self.entries.get(&key) {
+            return Ok(handle.clone());
+        }
+
+        let mut icon = rasterize(path, pixels)?;
+        if let Some(color) = color {
+            recolor(&mut icon, color);
+        }
+        let handle = icon.handle();
+        self.entries.put(key, handle.clone());
+        Ok(handle)
+    }
+
+    /// Drop everything, e.g. after the icon theme changed
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Render an SVG or PNG icon to fit a `pixels` square, keeping its aspect
+pub fn rasterize(path: &Path, pixels: u32) -> Result<RenderedIcon, IconError> {
+    if pixels == 0 {
+        return Err(IconError::Size(pixels));
+    }
+
+    if path.extension().is_some_and(|ext| ext == "svg") {
+        let data = std::fs::read(path)?;
+        let options = usvg::Options {
+            resources_dir: path.parent().map(Path::to_path_buf),
+            ..Default::default()
+        };
+        let tree = usvg::Tree::from_data(&data, &options)?;
+        render_svg(&tree, pixels)
+    } else {
+        let image = image::open(path)?
+            .resize(pixels, pixels, image::imageops::FilterType::Lanczos3)
+            .to_rgba8();
+        Ok(RenderedIcon {
+            width: image.width(),
+            height: image.height(),
+            rgba: image.into_raw(),
+        })
+    }
+}
+
+fn render_svg(tree: &usvg::Tree, pixels: u32) -> Result<RenderedIcon, IconError> {
+    let size = tree.size();
+    let scale = pixels as f32 / size.width().max(size.height());
+    let width = ((size.width() * scale).round() as u32).max(1);
+    let height = ((size.height() * scale).round() as u32).max(1);
+
+    let mut pixmap = tiny_skia::Pixmap::new(width, height).ok_or(IconError::Size(pixels))?;
+    resvg::render(tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+
+    // tiny-skia works in premultiplied alpha; images want straight alpha
+    let rgba = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|p| {
+            let c = p.demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        })
+        .collect();
+    Ok(RenderedIcon { width, height, rgba })
+}
+
+/// Paint every pixel `color`, keeping the icon's shape in its alpha
+pub fn recolor(icon: &mut RenderedIcon, color: Color) {
+    let [r, g, b, a] = color.into_rgba8();
+    for pixel in icon.rgba.chunks_exact_mut(4) {
+        pixel[0] = r;
+        pixel[1] = g;
+        pixel[2] = b;
+        pixel[3] = ((pixel[3] as u16 * a as u16) / 255) as u8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CIRCLE: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="16" height="16">
+        <circle cx="8" cy="8" r="8" fill="#bebebe"/></svg>"##;
+
+    fn svg(dir: &Path) -> PathBuf {
+        let path = dir.join("circle-symbolic.svg");
+        std::fs::write(&path, CIRCLE).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_svg_renders_at_requested_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let icon = rasterize(&svg(dir.path()), 48).unwrap();
+
+        assert_eq!((icon.width, icon.height), (48, 48));
+        let center = ((24 * 48 + 24) * 4) as usize;
+        assert_eq!(&icon.rgba[center..center + 4], &[0xbe, 0xbe, 0xbe, 0xff]);
+        assert_eq!(icon.rgba[3], 0, "corner is outside the circle");
+    }
+
+    #[test]
+    fn test_recolor_keeps_alpha() {
+        let mut icon = RenderedIcon { width: 2, height: 1, rgba: vec![10, 10, 10, 255, 10, 10, 10, 0] };
+        recolor(&mut icon, Color::from_rgb8(255, 0, 0));
+        assert_eq!(icon.rgba, [255, 0, 0, 255, 255, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = svg(dir.path());
+        let mut cache = IconCache::new(2);
+
+        cache.get(&path, 16, None).unwrap();
+        cache.get(&path, 24, None).unwrap();
+        cache.get(&path, 16, Some(Color::WHITE)).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.entries.peek(&Key { path: path.clone(), pixels: 16, color: None }).is_none());
+
+        assert!(cache.get(&dir.path().join("missing.svg"), 16, None).is_err());
+    }
+}
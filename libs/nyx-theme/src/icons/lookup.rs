@@ -0,0 +1,372 @@
+//! Freedesktop icon theme lookup
+//!
+//! Implements the icon theme specification: a theme's `index.theme` lists
+//! its size directories and the themes it inherits from; a lookup walks
+//! that chain, then `hicolor`, then the loose pixmaps directory. Within a
+//! theme an exact size match wins, otherwise the closest directory. Names
+//! fall back by dropping trailing dash components (`network-wireless-weak`
+//! → `network-wireless` → `network`).
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Theme every other theme implicitly inherits from
+pub const FALLBACK_THEME: &str = "hicolor";
+
+/// File extensions tried, in order of preference
+const EXTENSIONS: [&str; 2] = ["svg", "png"];
+
+/// Suffix of single-color icons meant to be recolored
+pub const SYMBOLIC_SUFFIX: &str = "-symbolic";
+
+/// How a directory's icons may be scaled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SizeType {
+    Fixed,
+    Scalable { min: u32, max: u32 },
+    Threshold(u32),
+}
+
+/// One size directory of a theme
+#[derive(Debug, Clone)]
+struct IconDir {
+    path: String,
+    size: u32,
+    scale: u32,
+    kind: SizeType,
+}
+
+impl IconDir {
+    fn matches(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+        match self.kind {
+            SizeType::Fixed => self.size == size,
+            SizeType::Scalable { min, max } => (min..=max).contains(&size),
+            SizeType::Threshold(t) => self.size.abs_diff(size) <= t,
+        }
+    }
+
+    fn distance(&self, size: u32, scale: u32) -> u32 {
+        let (size, own) = (size * scale, self.size * self.scale);
+        match self.kind {
+            SizeType::Fixed => own.abs_diff(size),
+            SizeType::Scalable { min, max } => {
+                let (min, max) = (min * self.scale, max * self.scale);
+                if size < min {
+                    min - size
+                } else {
+                    size.saturating_sub(max)
+                }
+            }
+            SizeType::Threshold(t) => {
+                let (min, max) = (own.saturating_sub(t * self.scale), own + t * self.scale);
+                if size < min {
+                    min - size
+                } else {
+                    size.saturating_sub(max)
+                }
+            }
+        }
+    }
+}
+
+/// A parsed `index.theme`
+#[derive(Debug, Clone)]
+struct Theme {
+    /// Where the theme's directories live, one per base directory
+    roots: Vec<PathBuf>,
+    inherits: Vec<String>,
+    dirs: Vec<IconDir>,
+}
+
+impl Theme {
+    fn parse(index: &str, roots: Vec<PathBuf>) -> Self {
+        let sections = parse_ini(index);
+        let main = sections.get("Icon Theme");
+        let list = |key: &str| -> Vec<String> {
+            main.and_then(|s| s.get(key))
+                .map(|v| v.split(',').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string).collect())
+                .unwrap_or_default()
+        };
+
+        let mut directories = list("Directories");
+        directories.extend(list("ScaledDirectories"));
+
+        let dirs = directories
+            .into_iter()
+            .filter_map(|path| {
+                let section = sections.get(path.as_str())?;
+                let int = |key: &str| section.get(key).and_then(|v| v.parse::<u32>().ok());
+                let size = int("Size")?;
+                let kind = match section.get("Type").map(String::as_str) {
+                    Some("Fixed") => SizeType::Fixed,
+                    Some("Scalable") => SizeType::Scalable {
+                        min: int("MinSize").unwrap_or(size),
+                        max: int("MaxSize").unwrap_or(size),
+                    },
+                    _ => SizeType::Threshold(int("Threshold").unwrap_or(2)),
+                };
+                Some(IconDir { path, size, scale: int("Scale").unwrap_or(1), kind })
+            })
+            .collect();
+
+        Self { roots, inherits: list("Inherits"), dirs }
+    }
+
+    /// Best file for `name` in this theme
+    fn find(&self, name: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        for dir in self.dirs.iter().filter(|d| d.matches(size, scale)) {
+            if let Some(path) = self.file(dir, name) {
+                return Some(path);
+            }
+        }
+
+        self.dirs
+            .iter()
+            .filter_map(|dir| Some((dir.distance(size, scale), self.file(dir, name)?)))
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, path)| path)
+    }
+
+    fn file(&self, dir: &IconDir, name: &str) -> Option<PathBuf> {
+        self.roots.iter().find_map(|root| {
+            EXTENSIONS
+                .iter()
+                .map(|ext| root.join(&dir.path).join(format!("{}.{}", name, ext)))
+                .find(|path| path.is_file())
+        })
+    }
+}
+
+/// Resolves icon names to files through a theme and its parents
+#[derive(Debug, Clone)]
+pub struct IconLookup {
+    base_dirs: Vec<PathBuf>,
+    pixmaps: Vec<PathBuf>,
+    /// The chain searched, selected theme first and `hicolor` last
+    chain: Vec<Theme>,
+}
+
+impl IconLookup {
+    /// Look icons up in `theme` under the standard XDG base directories
+    pub fn new(theme: &str) -> Self {
+        Self::with_dirs(theme, default_base_dirs(), vec![PathBuf::from("/usr/share/pixmaps")])
+    }
+
+    /// Look icons up in `theme` under the given base directories
+    pub fn with_dirs(theme: &str, base_dirs: Vec<PathBuf>, pixmaps: Vec<PathBuf>) -> Self {
+        let mut lookup = Self { base_dirs, pixmaps, chain: Vec::new() };
+        lookup.load_chain(theme);
+        lookup
+    }
+
+    /// Names of the themes searched, in order
+    pub fn themes(&self) -> Vec<&str> {
+        self.chain
+            .iter()
+            .filter_map(|t| t.roots.first()?.file_name()?.to_str())
+            .collect()
+    }
+
+    /// Find the file for `name` closest to `size` pixels at `scale`
+    pub fn find(&self, name: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        let scale = scale.max(1);
+        fallback_names(name).find_map(|name| {
+            self.chain
+                .iter()
+                .find_map(|theme| theme.find(name, size, scale))
+                .or_else(|| self.pixmap(name))
+        })
+    }
+
+    /// Find the symbolic variant of `name`, else `name` itself
+    pub fn find_symbolic(&self, name: &str, size: u32, scale: u32) -> Option<(PathBuf, bool)> {
+        let symbolic = format!("{}{}", name.trim_end_matches(SYMBOLIC_SUFFIX), SYMBOLIC_SUFFIX);
+        let scale = scale.max(1);
+        // Prefer a symbolic icon anywhere in the chain over a full-color one
+        self.chain
+            .iter()
+            .find_map(|theme| theme.find(&symbolic, size, scale))
+            .map(|path| (path, true))
+            .or_else(|| self.find(name, size, scale).map(|path| (path, false)))
+    }
+
+    fn pixmap(&self, name: &str) -> Option<PathBuf> {
+        self.pixmaps.iter().find_map(|dir| {
+            EXTENSIONS
+                .iter()
+                .map(|ext| dir.join(format!("{}.{}", name, ext)))
+                .find(|path| path.is_file())
+        })
+    }
+
+    fn load_chain(&mut self, theme: &str) {
+        let mut pending = vec![theme.to_string()];
+        let mut seen = Vec::new();
+
+        while let Some(name) = pending.pop() {
+            if seen.contains(&name) {
+                continue;
+            }
+            seen.push(name.clone());
+
+            let Some(theme) = self.load_theme(&name) else {
+                continue;
+            };
+            // Parents are searched in the order listed
+            pending.extend(theme.inherits.iter().rev().cloned());
+            self.chain.push(theme);
+        }
+
+        if !seen.iter().any(|n| n == FALLBACK_THEME) {
+            if let Some(hicolor) = self.load_theme(FALLBACK_THEME) {
+                self.chain.push(hicolor);
+            }
+        }
+    }
+
+    fn load_theme(&self, name: &str) -> Option<Theme> {
+        let roots: Vec<PathBuf> = self
+            .base_dirs
+            .iter()
+            .map(|base| base.join(name))
+            .filter(|root| root.is_dir())
+            .collect();
+
+        let index = roots.iter().find_map(|root| std::fs::read_to_string(root.join("index.theme")).ok())?;
+        Some(Theme::parse(&index, roots))
+    }
+}
+
+/// `$XDG_DATA_HOME/icons`, `~/.icons` and `$XDG_DATA_DIRS/icons`
+pub fn default_base_dirs() -> Vec<PathBuf> {
+    let home = std::env::var("HOME").ok().map(PathBuf::from);
+    let data_home = std::env::var("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .ok()
+        .or_else(|| home.as_ref().map(|h| h.join(".local/share")));
+    let data_dirs = std::env::var("XDG_DATA_DIRS").unwrap_or_else(|_| "/usr/local/share:/usr/share".into());
+
+    let mut dirs = Vec::new();
+    dirs.extend(data_home.map(|d| d.join("icons")));
+    dirs.extend(home.map(|h| h.join(".icons")));
+    dirs.extend(data_dirs.split(':').filter(|d| !d.is_empty()).map(|d| Path::new(d).join("icons")));
+    dirs
+}
+
+/// `a-b-c`, `a-b`, `a`; a symbolic suffix stays on each candidate
+fn fallback_names(name: &str) -> impl Iterator<Item = &str> {
+    let stem = name.strip_suffix(SYMBOLIC_SUFFIX).unwrap_or(name);
+    let symbolic = stem.len() != name.len();
+
+    let mut candidates = vec![name];
+    if !symbolic {
+        let mut rest = stem;
+        while let Some(index) = rest.rfind('-') {
+            rest = &rest[..index];
+            candidates.push(rest);
+        }
+    }
+    candidates.into_iter()
+}
+
+/// Sections of an ini-style file, keyed by name
+fn parse_ini(source: &str) -> HashMap<&str, HashMap<&str, String>> {
+    let mut sections: HashMap<&str, HashMap<&str, String>> = HashMap::new();
+    let mut current = None;
+
+    for line in source.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = Some(name);
+            sections.entry(name).or_default();
+        } else if let (Some(section), Some((key, value))) = (current, line.split_once('=')) {
+            sections.entry(section).or_default().insert(key.trim(), value.trim().to_string());
+        }
+    }
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn theme(base: &Path, name: &str, index: &str) {
+        fs::create_dir_all(base.join(name)).unwrap();
+        fs::write(base.join(name).join("index.theme"), index).unwrap();
+    }
+
+    fn icon(base: &Path, theme: &str, dir: &str, file: &str) -> PathBuf {
+        let path = base.join(theme).join(dir).join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "<svg/>").unwrap();
+        path
+    }
+
+    fn fixture() -> (tempfile::TempDir, IconLookup) {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("icons");
+        theme(
+            &base,
+            "Nyx",
+            "[Icon Theme]\nName=Nyx\nInherits=Adwaita\nDirectories=16x16/apps,scalable/apps\n\n\
+             [16x16/apps]\nSize=16\nType=Fixed\n\n\
+             [scalable/apps]\nSize=48\nMinSize=8\nMaxSize=512\nType=Scalable\n",
+        );
+        theme(
+            &base,
+            "Adwaita",
+            "[Icon Theme]\nDirectories=symbolic/status\n\n[symbolic/status]\nSize=16\nType=Scalable\nMinSize=8\nMaxSize=512\n",
+        );
+        theme(&base, "hicolor", "[Icon Theme]\nDirectories=48x48/apps\n\n[48x48/apps]\nSize=48\nType=Threshold\n");
+
+        let lookup = IconLookup::with_dirs("Nyx", vec![base], vec![dir.path().join("pixmaps")]);
+        (dir, lookup)
+    }
+
+    #[test]
+    fn test_chain_ends_with_hicolor() {
+        let (_dir, lookup) = fixture();
+        assert_eq!(lookup.themes(), ["Nyx", "Adwaita", "hicolor"]);
+    }
+
+    #[test]
+    fn test_exact_size_beats_scalable() {
+        let (dir, lookup) = fixture();
+        let base = dir.path().join("icons");
+        let fixed = icon(&base, "Nyx", "16x16/apps", "terminal.png");
+        let scalable = icon(&base, "Nyx", "scalable/apps", "terminal.svg");
+
+        assert_eq!(lookup.find("terminal", 16, 1), Some(fixed));
+        assert_eq!(lookup.find("terminal", 64, 1), Some(scalable));
+    }
+
+    #[test]
+    fn test_falls_back_through_parents_and_hicolor() {
+        let (dir, lookup) = fixture();
+        let base = dir.path().join("icons");
+        let status = icon(&base, "Adwaita", "symbolic/status", "dialog-warning-symbolic.svg");
+        let app = icon(&base, "hicolor", "48x48/apps", "firefox.png");
+
+        assert_eq!(lookup.find_symbolic("dialog-warning", 16, 1), Some((status, true)));
+        assert_eq!(lookup.find("firefox", 32, 1), Some(app));
+        assert_eq!(lookup.find("missing", 16, 1), None);
+    }
+
+    #[test]
+    fn test_generic_name_fallback() {
+        let (dir, lookup) = fixture();
+        let generic = icon(&dir.path().join("icons"), "Nyx", "scalable/apps", "network-wireless.svg");
+
+        assert_eq!(lookup.find("network-wireless-signal-weak", 24, 1), Some(generic));
+        assert_eq!(
+            fallback_names("network-wireless-symbolic").collect::<Vec<_>>(),
+            ["network-wireless-symbolic"]
+        );
+    }
+}
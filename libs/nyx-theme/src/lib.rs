@@ -8,16 +8,25 @@
 //! - Spacing and layout constants
 //! - Glassmorphism and modern visual effects
 //! - Reusable styled widgets
+//! - Themes loaded from TOML at runtime, with live reload
+//! - Accent palettes derived from the wallpaper
+//! - Accessibility options (contrast, reduced transparency, text scale)
 
+pub mod accessibility;
 pub mod colors;
+pub mod dynamic;
 pub mod fonts;
 pub mod icons;
+pub mod loader;
 pub mod spacing;
 pub mod theme;
 pub mod widgets;
 
+pub use accessibility::{AccessibilityOptions, ContrastLevel};
 pub use colors::{ColorPalette, NyxColors};
+pub use dynamic::DynamicPalette;
 pub use fonts::Typography;
+pub use loader::{ThemeEvent, ThemeLoader};
 pub use spacing::Spacing;
 pub use theme::{NyxTheme, ThemeMode};
 
@@ -0,0 +1,471 @@
+//! Runtime theme loading
+//!
+//! Themes live as TOML files in the grimoire themes directory. Each file
+//! names a base mode and overrides any part of its palette, typography and
+//! spacing scales and effects:
+//!
+//! ```toml
+//! name = "Nord"
+//! mode = "Dark"
+//!
+//! [palette]
+//! background = "#2E3440"
+//! text_primary = "#ECEFF4"
+//! accent = "#88C0D0"
+//!
+//! [typography]
+//! scale = 1.1
+//!
+//! [spacing]
+//! scale = 1.0
+//! corner_radius = 0.5
+//!
+//! [effects]
+//! glassmorphism = false
+//! ```
+//!
+//! [`ThemeLoader`] validates the files and keeps them as [`NyxTheme`]s.
+//! Apps subscribe to [`watch`], which emits every theme on start and then
+//! each addition, change and removal, so they can restyle without a restart.
+
+use crate::colors::{contrast_ratio, PaletteOverrides};
+use crate::theme::{parse_hex_color, NyxTheme, ThemeMode};
+use iced::futures::stream;
+use iced::Subscription;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+
+/// Where grimoire keeps theme definitions
+pub const THEME_DIR: &str = "/grimoire/themes";
+
+/// Minimum contrast between primary text and background (WCAG AA)
+pub const MIN_TEXT_CONTRAST: f32 = 4.5;
+
+/// How often `watch` looks for changed files
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Theme file errors
+#[derive(Error, Debug)]
+pub enum ThemeError {
+    #[error("cannot read theme: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid theme file: {0}")]
+    Parse(#[from] toml::de::Error),
+
+    #[error("cannot decode wallpaper: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("theme name is empty")]
+    EmptyName,
+
+    #[error("palette.{field}: invalid color {value:?}")]
+    InvalidColor { field: &'static str, value: String },
+
+    #[error("{field} must be between {min} and {max}, got {value}")]
+    OutOfRange { field: &'static str, value: f32, min: f32, max: f32 },
+
+    #[error("text contrast {ratio:.2}:1 is below {MIN_TEXT_CONTRAST}:1")]
+    LowContrast { ratio: f32 },
+
+    #[error("theme {0:?} is already defined by another file")]
+    DuplicateName(String),
+}
+
+/// A theme appearing, changing or going away
+#[derive(Debug, Clone)]
+pub enum ThemeEvent {
+    /// A theme was loaded or reloaded
+    Updated(Box<NyxTheme>),
+    /// A theme's file was removed or no longer loads
+    Removed(String),
+    /// A file could not be loaded
+    Invalid { path: PathBuf, error: String },
+}
+
+/// On-disk theme format
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    /// Defaults to the file name
+    name: Option<String>,
+    #[serde(default)]
+    mode: ThemeMode,
+    #[serde(default)]
+    palette: PaletteOverrides,
+    #[serde(default)]
+    typography: TypographyFile,
+    #[serde(default)]
+    spacing: SpacingFile,
+    #[serde(default)]
+    effects: EffectsFile,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TypographyFile {
+    scale: f32,
+}
+
+impl Default for TypographyFile {
+    fn default() -> Self {
+        Self { scale: 1.0 }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SpacingFile {
+    scale: f32,
+    corner_radius: f32,
+}
+
+impl Default for SpacingFile {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            corner_radius: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct EffectsFile {
+    glassmorphism: bool,
+    blur: bool,
+    animations: bool,
+    animation_speed: f32,
+}
+
+impl Default for EffectsFile {
+    fn default() -> Self {
+        let theme = NyxTheme::default();
+        Self {
+            glassmorphism: theme.glassmorphism,
+            blur: theme.blur_effects,
+            animations: theme.animations,
+            animation_speed: theme.animation_speed,
+        }
+    }
+}
+
+/// Parse and validate a theme file's contents
+pub fn parse_theme(source: &str, default_name: &str) -> Result<NyxTheme, ThemeError> {
+    let file: ThemeFile = toml::from_str(source)?;
+
+    let name = file.name.unwrap_or_else(|| default_name.to_string());
+    if name.trim().is_empty() {
+        return Err(ThemeError::EmptyName);
+    }
+
+    for (field, value) in file.palette.entries() {
+        if parse_hex_color(value).is_none() {
+            return Err(ThemeError::InvalidColor { field, value: value.to_string() });
+        }
+    }
+
+    check_range("typography.scale", file.typography.scale, 0.5, 3.0)?;
+    check_range("spacing.scale", file.spacing.scale, 0.5, 3.0)?;
+    check_range("spacing.corner_radius", file.spacing.corner_radius, 0.0, 4.0)?;
+    check_range("effects.animation_speed", file.effects.animation_speed, 0.25, 4.0)?;
+
+    let theme = NyxTheme {
+        mode: file.mode,
+        glassmorphism: file.effects.glassmorphism,
+        animations: file.effects.animations,
+        animation_speed: file.effects.animation_speed,
+        blur_effects: file.effects.blur,
+        corner_radius_scale: file.spacing.corner_radius,
+        name: Some(name),
+        palette_overrides: file.palette,
+        text_scale: file.typography.scale,
+        spacing_scale: file.spacing.scale,
+        ..NyxTheme::default()
+    };
+
+    let palette = theme.palette();
+    let ratio = contrast_ratio(palette.text_primary, palette.background);
+    if ratio < MIN_TEXT_CONTRAST {
+        return Err(ThemeError::LowContrast { ratio });
+    }
+
+    Ok(theme)
+}
+
+fn check_range(field: &'static str, value: f32, min: f32, max: f32) -> Result<(), ThemeError> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(ThemeError::OutOfRange { field, value, min, max })
+    }
+}
+
+/// A loaded theme and the file it came from
+#[derive(Debug, Clone)]
+struct LoadedTheme {
+    path: PathBuf,
+    theme: NyxTheme,
+}
+
+/// Themes loaded from a directory of TOML files
+pub struct ThemeLoader {
+    dir: PathBuf,
+    /// Name -> theme
+    themes: BTreeMap<String, LoadedTheme>,
+    /// Modification time of every file looked at, loadable or not
+    seen: HashMap<PathBuf, SystemTime>,
+}
+
+impl ThemeLoader {
+    /// Loader for a theme directory; nothing is read until `reload`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            themes: BTreeMap::new(),
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Loader for the grimoire themes directory
+    pub fn system() -> Self {
+        Self::new(THEME_DIR)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// A loaded theme by name
+    pub fn theme(&self, name: &str) -> Option<&NyxTheme> {
+        self.themes.get(name).map(|loaded| &loaded.theme)
+    }
+
+    /// All loaded themes, by name
+    pub fn themes(&self) -> impl Iterator<Item = &NyxTheme> {
+        self.themes.values().map(|loaded| &loaded.theme)
+    }
+
+    /// Re-read files that appeared, changed or went away since the last call
+    pub fn reload(&mut self) -> Vec<ThemeEvent> {
+        let mut events = Vec::new();
+        let files = self.scan();
+
+        let gone: Vec<PathBuf> = self.seen.keys()
+            .filter(|path| !files.contains_key(*path))
+            .cloned()
+            .collect();
+        for path in gone {
+            self.seen.remove(&path);
+            events.extend(self.unload(&path));
+        }
+
+        let mut changed: Vec<(PathBuf, SystemTime)> = files.into_iter()
+            .filter(|(path, modified)| self.seen.get(path) != Some(modified))
+            .collect();
+        changed.sort();
+
+        for (path, modified) in changed {
+            self.seen.insert(path.clone(), modified);
+            events.extend(self.load(&path));
+        }
+
+        events
+    }
+
+    /// `*.toml` files in the directory and when they were modified
+    fn scan(&self) -> HashMap<PathBuf, SystemTime> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return HashMap::new();
+        };
+
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("toml"))
+            .filter_map(|path| {
+                let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+                Some((path, modified))
+            })
+            .collect()
+    }
+
+    fn load(&mut self, path: &Path) -> Vec<ThemeEvent> {
+        // Whatever the file defined before is replaced
+        let mut events = self.unload(path);
+
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        let result = std::fs::read_to_string(path)
+            .map_err(ThemeError::from)
+            .and_then(|source| parse_theme(&source, stem))
+            .and_then(|theme| {
+                let name = theme.name.clone().unwrap_or_default();
+                if self.themes.contains_key(&name) {
+                    Err(ThemeError::DuplicateName(name))
+                } else {
+                    Ok(theme)
+                }
+            });
+
+        match result {
+            Ok(theme) => {
+                let name = theme.name.clone().unwrap_or_default();
+                // A reload of the same theme is an update, not a removal
+                events.retain(|event| !matches!(event, ThemeEvent::Removed(removed) if *removed == name));
+                self.themes.insert(name, LoadedTheme { path: path.to_path_buf(), theme: theme.clone() });
+                events.push(ThemeEvent::Updated(Box::new(theme)));
+            }
+            Err(error) => events.push(ThemeEvent::Invalid {
+                path: path.to_path_buf(),
+                error: error.to_string(),
+            }),
+        }
+
+        events
+    }
+
+    /// Drop the theme a file defined
+    fn unload(&mut self, path: &Path) -> Vec<ThemeEvent> {
+        let names: Vec<String> = self.themes.iter()
+            .filter(|(_, loaded)| loaded.path == path)
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        names.into_iter()
+            .map(|name| {
+                self.themes.remove(&name);
+                ThemeEvent::Removed(name)
+            })
+            .collect()
+    }
+}
+
+/// Watch a theme directory, emitting every theme found and then changes
+pub fn watch(dir: impl Into<PathBuf>) -> Subscription<ThemeEvent> {
+    let dir = dir.into();
+    let loader = ThemeLoader::new(dir.clone());
+
+    Subscription::run_with_id(
+        ("nyx-theme-loader", dir),
+        stream::unfold(
+            (loader, VecDeque::new(), true),
+            |(mut loader, mut pending, mut first)| async move {
+                loop {
+                    if let Some(event) = pending.pop_front() {
+                        return Some((event, (loader, pending, first)));
+                    }
+                    if !first {
+                        tokio::time::sleep(POLL_INTERVAL).await;
+                    }
+                    first = false;
+                    pending.extend(loader.reload());
+                }
+            },
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::ColorPalette;
+
+    const NORD: &str = r##"
+        name = "Nord"
+        mode = "Dark"
+
+        [palette]
+        background = "#2E3440"
+        text_primary = "#ECEFF4"
+        accent = "#88C0D0"
+
+        [typography]
+        scale = 1.25
+
+        [spacing]
+        corner_radius = 0.5
+
+        [effects]
+        glassmorphism = false
+    "##;
+
+    #[test]
+    fn test_parse_theme() {
+        let theme = parse_theme(NORD, "nord").unwrap();
+        assert_eq!(theme.name.as_deref(), Some("Nord"));
+        assert_eq!(theme.mode, ThemeMode::Dark);
+        assert!(!theme.glassmorphism);
+        assert!((theme.text_size(12.0) - 15.0).abs() < 0.01);
+        assert!((theme.radius(8.0) - 4.0).abs() < 0.01);
+
+        let palette = theme.palette();
+        assert_eq!(palette.accent, parse_hex_color("#88C0D0").unwrap());
+        // Colors not overridden come from the base palette
+        assert_eq!(palette.error, ColorPalette::dark().error);
+    }
+
+    #[test]
+    fn test_name_defaults_to_file_name() {
+        let theme = parse_theme("mode = \"Light\"", "paper").unwrap();
+        assert_eq!(theme.name.as_deref(), Some("paper"));
+        assert_eq!(theme.mode, ThemeMode::Light);
+    }
+
+    #[test]
+    fn test_rejects_invalid_themes() {
+        assert!(matches!(
+            parse_theme("[palette]\naccent = \"#12\"", "x"),
+            Err(ThemeError::InvalidColor { field: "accent", .. })
+        ));
+        assert!(matches!(
+            parse_theme("[typography]\nscale = 10.0", "x"),
+            Err(ThemeError::OutOfRange { field: "typography.scale", .. })
+        ));
+        assert!(matches!(
+            parse_theme("[palette]\ntext_primary = \"#1A1A22\"", "x"),
+            Err(ThemeError::LowContrast { .. })
+        ));
+        assert!(matches!(parse_theme("colour = \"red\"", "x"), Err(ThemeError::Parse(_))));
+        assert!(matches!(parse_theme("name = \" \"", "x"), Err(ThemeError::EmptyName)));
+    }
+
+    #[test]
+    fn test_reload_reports_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut loader = ThemeLoader::new(dir.path());
+        assert!(loader.reload().is_empty());
+
+        let path = dir.path().join("nord.toml");
+        std::fs::write(&path, NORD).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+        let events = loader.reload();
+        assert!(matches!(events.as_slice(), [ThemeEvent::Updated(theme)] if theme.name.as_deref() == Some("Nord")));
+        assert!(loader.reload().is_empty());
+
+        // Rewritten with a different mtime
+        std::fs::write(&path, NORD.replace("1.25", "1.5")).unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5)).unwrap();
+        let events = loader.reload();
+        assert!(matches!(events.as_slice(), [ThemeEvent::Updated(theme)] if theme.text_scale == 1.5));
+
+        // A second file claiming the same name is refused
+        std::fs::write(dir.path().join("copy.toml"), NORD).unwrap();
+        assert!(matches!(loader.reload().as_slice(), [ThemeEvent::Invalid { .. }]));
+
+        std::fs::remove_file(&path).unwrap();
+        let events = loader.reload();
+        assert!(matches!(events.as_slice(), [ThemeEvent::Removed(name)] if name == "Nord"));
+        assert!(loader.theme("Nord").is_none());
+    }
+
+    #[test]
+    fn test_missing_directory_is_empty() {
+        let mut loader = ThemeLoader::new("/nonexistent/themes");
+        assert!(loader.reload().is_empty());
+        assert_eq!(loader.themes().count(), 0);
+    }
+}
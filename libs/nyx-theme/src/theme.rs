@@ -2,8 +2,9 @@
 //!
 //! Provides the main theme struct and iced theme integration.
 
-use crate::colors::{AccentColor, ColorPalette, NyxColors};
-use iced::theme::{Custom, Palette};
+use crate::accessibility;
+use crate::colors::{AccentColor, ColorPalette, NyxColors, PaletteOverrides};
+use iced::theme::Palette;
 use iced::{Color, Theme};
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +18,8 @@ pub enum ThemeMode {
     Light,
     /// System preference (follows OS setting)
     System,
+    /// Black and white with strong outlines, for low vision
+    HighContrast,
 }
 
 /// Main Nyx OS theme configuration
@@ -38,6 +41,22 @@ pub struct NyxTheme {
     pub blur_effects: bool,
     /// Border radius scale (1.0 = default)
     pub corner_radius_scale: f32,
+    /// Theme file this was loaded from; None for the built-in themes
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Colors replacing those of the mode's base palette
+    #[serde(default)]
+    pub palette_overrides: PaletteOverrides,
+    /// Text size scale (1.0 = default)
+    #[serde(default = "default_scale")]
+    pub text_scale: f32,
+    /// Spacing scale (1.0 = default)
+    #[serde(default = "default_scale")]
+    pub spacing_scale: f32,
+}
+
+fn default_scale() -> f32 {
+    1.0
 }
 
 impl Default for NyxTheme {
@@ -51,6 +70,10 @@ impl Default for NyxTheme {
             animation_speed: 1.0,
             blur_effects: true,
             corner_radius_scale: 1.0,
+            name: None,
+            palette_overrides: PaletteOverrides::default(),
+            text_scale: 1.0,
+            spacing_scale: 1.0,
         }
     }
 }
@@ -72,12 +95,45 @@ impl NyxTheme {
         }
     }
 
+    /// Mode to draw with, after the accessibility options in effect
+    pub fn effective_mode(&self) -> ThemeMode {
+        accessibility::current().mode(self.mode)
+    }
+
     /// Get the color palette for the current theme
     pub fn palette(&self) -> ColorPalette {
-        match self.mode {
+        let base = match self.effective_mode() {
             ThemeMode::Dark | ThemeMode::System => ColorPalette::dark(),
             ThemeMode::Light => ColorPalette::light(),
-        }
+            // Overrides could undo the contrast the user asked for
+            ThemeMode::HighContrast => return ColorPalette::high_contrast(),
+        };
+        self.palette_overrides.apply(base)
+    }
+
+    /// Whether to draw glass surfaces
+    pub fn glass_enabled(&self) -> bool {
+        self.glassmorphism && !accessibility::current().reduce_transparency
+    }
+
+    /// Whether to blur behind translucent surfaces
+    pub fn blur_enabled(&self) -> bool {
+        self.blur_effects && !accessibility::current().reduce_transparency
+    }
+
+    /// Scale a `Typography` size for this theme and the accessibility text scale
+    pub fn text_size(&self, size: f32) -> f32 {
+        accessibility::current().text_size(size * self.text_scale)
+    }
+
+    /// Scale a `Spacing` value for this theme
+    pub fn space(&self, value: f32) -> f32 {
+        value * self.spacing_scale
+    }
+
+    /// Scale a `Spacing` radius for this theme
+    pub fn radius(&self, radius: f32) -> f32 {
+        radius * self.corner_radius_scale
     }
 
     /// Get the current accent color
@@ -119,7 +175,22 @@ impl NyxTheme {
 
     /// Convert to iced Theme
     pub fn to_iced_theme(&self) -> Theme {
-        create_theme(self.mode)
+        let mode = self.effective_mode();
+        let Some(name) = self.name.as_ref().filter(|_| mode != ThemeMode::HighContrast) else {
+            return create_theme(mode);
+        };
+
+        let palette = self.palette();
+        Theme::custom(
+            name.clone(),
+            Palette {
+                background: palette.background,
+                text: palette.text_primary,
+                primary: palette.accent,
+                success: palette.success,
+                danger: palette.error,
+            },
+        )
     }
 }
 
@@ -128,6 +199,19 @@ pub fn create_theme(mode: ThemeMode) -> Theme {
     match mode {
         ThemeMode::Dark | ThemeMode::System => create_dark_theme(),
         ThemeMode::Light => create_light_theme(),
+        ThemeMode::HighContrast => {
+            let palette = ColorPalette::high_contrast();
+            Theme::custom(
+                "Nyx High Contrast".to_string(),
+                Palette {
+                    background: palette.background,
+                    text: palette.text_primary,
+                    primary: palette.accent,
+                    success: palette.success,
+                    danger: palette.error,
+                },
+            )
+        }
     }
 }
 
@@ -141,10 +225,7 @@ fn create_dark_theme() -> Theme {
         danger: NyxColors::ERROR,
     };
 
-    Theme::Custom(
-        "Nyx Dark".to_string().into(),
-        Box::new(Custom::new(palette)),
-    )
+    Theme::custom("Nyx Dark".to_string(), palette)
 }
 
 /// Create the light iced theme
@@ -157,10 +238,7 @@ fn create_light_theme() -> Theme {
         danger: NyxColors::ERROR,
     };
 
-    Theme::Custom(
-        "Nyx Light".to_string().into(),
-        Box::new(Custom::new(palette)),
-    )
+    Theme::custom("Nyx Light".to_string(), palette)
 }
 
 /// Parse a hex color string to iced Color
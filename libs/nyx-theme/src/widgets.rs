@@ -1,16 +1,31 @@
 //! Reusable styled widgets for Nyx OS
 //!
 //! Provides pre-styled widget helpers and custom widget implementations
-//! for a consistent look across Nyx OS applications.
+//! for a consistent look across Nyx OS applications. The component library
+//! (switches, sliders, segmented controls, toasts, modals and skeletons)
+//! takes a [`StyleVariant`] so each component has dark, light and high
+//! contrast renderings.
 
 pub mod button;
 pub mod card;
 pub mod input;
+pub mod modal;
 pub mod panel;
+pub mod segmented;
+pub mod skeleton;
+pub mod slider;
+pub mod toast;
 pub mod toggle;
+pub mod variant;
 
 pub use button::*;
 pub use card::*;
 pub use input::*;
+pub use modal::*;
 pub use panel::*;
+pub use segmented::*;
+pub use skeleton::*;
+pub use slider::*;
+pub use toast::*;
 pub use toggle::*;
+pub use variant::*;
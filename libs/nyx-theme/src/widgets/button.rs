@@ -1,8 +1,9 @@
 //! Button styles for Nyx OS
 
+use crate::accessibility::accessible_with_status;
 use crate::colors::NyxColors;
 use crate::spacing::Spacing;
-use iced::widget::button::{self, Status, Style};
+use iced::widget::button::{Status, Style};
 use iced::{Background, Border, Color, Shadow, Vector};
 
 /// Button variants
@@ -59,7 +60,7 @@ impl ButtonSize {
 
 /// Create a button style function for the given variant
 pub fn button_style(variant: ButtonVariant) -> impl Fn(&iced::Theme, Status) -> Style {
-    move |_theme, status| match variant {
+    accessible_with_status(move |_theme, status| match variant {
         ButtonVariant::Primary => primary_style(status),
         ButtonVariant::Secondary => secondary_style(status),
         ButtonVariant::Ghost => ghost_style(status),
@@ -67,7 +68,7 @@ pub fn button_style(variant: ButtonVariant) -> impl Fn(&iced::Theme, Status) ->
         ButtonVariant::Success => success_style(status),
         ButtonVariant::Icon => icon_style(status),
         ButtonVariant::Panel => panel_style(status),
-    }
+    })
 }
 
 fn primary_style(status: Status) -> Style {
@@ -1,8 +1,9 @@
 //! Card/container styles for Nyx OS
 
+use crate::accessibility::accessible;
 use crate::colors::NyxColors;
 use crate::spacing::Spacing;
-use iced::widget::container::{self, Style};
+use iced::widget::container::Style;
 use iced::{Background, Border, Color, Shadow, Vector};
 
 /// Card variants
@@ -25,14 +26,14 @@ pub enum CardVariant {
 
 /// Create a container style function for the given card variant
 pub fn card_style(variant: CardVariant) -> impl Fn(&iced::Theme) -> Style {
-    move |_theme| match variant {
+    accessible(move |_theme| match variant {
         CardVariant::Default => default_card_style(),
         CardVariant::Elevated => elevated_card_style(),
         CardVariant::Outlined => outlined_card_style(),
         CardVariant::Glass => glass_card_style(),
         CardVariant::Flat => flat_card_style(),
         CardVariant::Interactive => interactive_card_style(),
-    }
+    })
 }
 
 fn default_card_style() -> Style {
@@ -131,7 +132,7 @@ fn interactive_card_style() -> Style {
 
 /// Panel container style (for shell panels)
 pub fn panel_container_style() -> impl Fn(&iced::Theme) -> Style {
-    |_theme| Style {
+    accessible(|_theme| Style {
         background: Some(Background::Color(NyxColors::GLASS_DARK)),
         text_color: Some(NyxColors::TEXT_BRIGHT),
         border: Border {
@@ -144,12 +145,12 @@ pub fn panel_container_style() -> impl Fn(&iced::Theme) -> Style {
             offset: Vector::new(0.0, 2.0),
             blur_radius: 8.0,
         },
-    }
+    })
 }
 
 /// Modal/dialog container style
 pub fn modal_container_style() -> impl Fn(&iced::Theme) -> Style {
-    |_theme| Style {
+    accessible(|_theme| Style {
         background: Some(Background::Color(NyxColors::TWILIGHT)),
         text_color: Some(NyxColors::TEXT_BRIGHT),
         border: Border {
@@ -162,12 +163,12 @@ pub fn modal_container_style() -> impl Fn(&iced::Theme) -> Style {
             offset: Vector::new(0.0, 16.0),
             blur_radius: Spacing::SHADOW_XL,
         },
-    }
+    })
 }
 
 /// Tooltip container style
 pub fn tooltip_container_style() -> impl Fn(&iced::Theme) -> Style {
-    |_theme| Style {
+    accessible(|_theme| Style {
         background: Some(Background::Color(NyxColors::DUSK)),
         text_color: Some(NyxColors::TEXT_BRIGHT),
         border: Border {
@@ -180,5 +181,5 @@ pub fn tooltip_container_style() -> impl Fn(&iced::Theme) -> Style {
             offset: Vector::new(0.0, 4.0),
             blur_radius: Spacing::SHADOW_SM,
         },
-    }
+    })
 }
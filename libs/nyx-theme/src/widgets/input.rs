@@ -1,8 +1,9 @@
 //! Input/text field styles for Nyx OS
 
+use crate::accessibility::accessible_with_status;
 use crate::colors::NyxColors;
 use crate::spacing::Spacing;
-use iced::widget::text_input::{self, Status, Style};
+use iced::widget::text_input::{Status, Style};
 use iced::{Background, Border, Color};
 
 /// Input variants
@@ -21,12 +22,12 @@ pub enum InputVariant {
 
 /// Create a text input style function for the given variant
 pub fn input_style(variant: InputVariant) -> impl Fn(&iced::Theme, Status) -> Style {
-    move |_theme, status| match variant {
+    accessible_with_status(move |_theme, status| match variant {
         InputVariant::Default => default_input_style(status),
         InputVariant::Filled => filled_input_style(status),
         InputVariant::Ghost => ghost_input_style(status),
         InputVariant::Search => search_input_style(status),
-    }
+    })
 }
 
 fn default_input_style(status: Status) -> Style {
@@ -0,0 +1,62 @@
+//! Modal dialogs drawn over a dimmed scrim
+
+use crate::accessibility::accessible;
+use crate::spacing::Spacing;
+use crate::widgets::variant::StyleVariant;
+use iced::widget::container::Style;
+use iced::widget::{center, container, mouse_area, opaque, stack};
+use iced::{Background, Border, Color, Element, Shadow, Vector};
+
+/// Dialog width
+const DIALOG_WIDTH: f32 = 440.0;
+
+/// Create the dialog container style
+pub fn dialog_style(variant: StyleVariant) -> impl Fn(&iced::Theme) -> Style {
+    accessible(move |_theme| {
+        let palette = variant.palette();
+        Style {
+            background: Some(Background::Color(palette.surface_elevated)),
+            text_color: Some(palette.text_primary),
+            border: Border {
+                color: palette.border,
+                width: variant.border_width(),
+                radius: Spacing::RADIUS_XL.into(),
+            },
+            shadow: Shadow {
+                color: Color::from_rgba(0.0, 0.0, 0.0, 0.5),
+                offset: Vector::new(0.0, 16.0),
+                blur_radius: Spacing::SHADOW_XL,
+            },
+        }
+    })
+}
+
+/// Create the scrim style covering the content behind a dialog
+pub fn scrim_style(variant: StyleVariant) -> impl Fn(&iced::Theme) -> Style {
+    accessible(move |_theme| Style {
+        background: Some(Background::Color(variant.palette().overlay)),
+        ..Default::default()
+    })
+}
+
+/// Show `dialog` centered over `base`; clicking the scrim sends `on_dismiss`
+pub fn modal<'a, Message: Clone + 'a>(
+    base: impl Into<Element<'a, Message>>,
+    dialog: impl Into<Element<'a, Message>>,
+    on_dismiss: Message,
+    variant: StyleVariant,
+) -> Element<'a, Message> {
+    let dialog = container(dialog)
+        .padding(Spacing::MODAL_PADDING)
+        .max_width(DIALOG_WIDTH)
+        .style(dialog_style(variant));
+
+    stack![
+        base.into(),
+        opaque(
+            mouse_area(center(opaque(dialog)).style(scrim_style(variant)))
+                .on_press(on_dismiss)
+        )
+    ]
+    .into()
+}
@@ -1,5 +1,6 @@
 //! Panel and shell component styles for Nyx OS
 
+use crate::accessibility::accessible;
 use crate::colors::NyxColors;
 use crate::spacing::Spacing;
 use iced::widget::container::Style;
@@ -7,7 +8,7 @@ use iced::{Background, Border, Color, Shadow, Vector};
 
 /// Top panel style (the main desktop panel at the top)
 pub fn top_panel_style() -> impl Fn(&iced::Theme) -> Style {
-    |_theme| Style {
+    accessible(|_theme| Style {
         background: Some(Background::Color(NyxColors::GLASS_DARK)),
         text_color: Some(NyxColors::TEXT_BRIGHT),
         border: Border {
@@ -20,12 +21,12 @@ pub fn top_panel_style() -> impl Fn(&iced::Theme) -> Style {
             offset: Vector::new(0.0, 2.0),
             blur_radius: 8.0,
         },
-    }
+    })
 }
 
 /// Dock style (the application dock at the bottom)
 pub fn dock_style() -> impl Fn(&iced::Theme) -> Style {
-    |_theme| Style {
+    accessible(|_theme| Style {
         background: Some(Background::Color(NyxColors::GLASS_DARK)),
         text_color: Some(NyxColors::TEXT_BRIGHT),
         border: Border {
@@ -38,12 +39,12 @@ pub fn dock_style() -> impl Fn(&iced::Theme) -> Style {
             offset: Vector::new(0.0, 4.0),
             blur_radius: 16.0,
         },
-    }
+    })
 }
 
 /// Dock item style (individual dock icons)
 pub fn dock_item_style(active: bool, hovered: bool) -> impl Fn(&iced::Theme) -> Style {
-    move |_theme| {
+    accessible(move |_theme| {
         let background = if active {
             Color::from_rgba(1.0, 1.0, 1.0, 0.15)
         } else if hovered {
@@ -62,12 +63,12 @@ pub fn dock_item_style(active: bool, hovered: bool) -> impl Fn(&iced::Theme) ->
             },
             shadow: Shadow::default(),
         }
-    }
+    })
 }
 
 /// Running indicator style (dot below dock icons)
 pub fn running_indicator_style() -> impl Fn(&iced::Theme) -> Style {
-    |_theme| Style {
+    accessible(|_theme| Style {
         background: Some(Background::Color(NyxColors::AURORA)),
         text_color: None,
         border: Border {
@@ -85,12 +86,12 @@ pub fn running_indicator_style() -> impl Fn(&iced::Theme) -> Style {
             offset: Vector::new(0.0, 0.0),
             blur_radius: 4.0,
         },
-    }
+    })
 }
 
 /// Quick settings panel style
 pub fn quick_settings_style() -> impl Fn(&iced::Theme) -> Style {
-    |_theme| Style {
+    accessible(|_theme| Style {
         background: Some(Background::Color(NyxColors::GLASS_DARK)),
         text_color: Some(NyxColors::TEXT_BRIGHT),
         border: Border {
@@ -103,12 +104,12 @@ pub fn quick_settings_style() -> impl Fn(&iced::Theme) -> Style {
             offset: Vector::new(0.0, 8.0),
             blur_radius: 24.0,
         },
-    }
+    })
 }
 
 /// Quick toggle tile style
 pub fn quick_toggle_style(active: bool) -> impl Fn(&iced::Theme) -> Style {
-    move |_theme| {
+    accessible(move |_theme| {
         let (background, border_color) = if active {
             (NyxColors::AURORA, Color::from_rgba(1.0, 1.0, 1.0, 0.2))
         } else {
@@ -129,12 +130,12 @@ pub fn quick_toggle_style(active: bool) -> impl Fn(&iced::Theme) -> Style {
             },
             shadow: Shadow::default(),
         }
-    }
+    })
 }
 
 /// Slider tile style (for brightness/volume in quick settings)
 pub fn slider_tile_style() -> impl Fn(&iced::Theme) -> Style {
-    |_theme| Style {
+    accessible(|_theme| Style {
         background: Some(Background::Color(NyxColors::DUSK)),
         text_color: Some(NyxColors::TEXT_BRIGHT),
         border: Border {
@@ -143,12 +144,12 @@ pub fn slider_tile_style() -> impl Fn(&iced::Theme) -> Style {
             radius: Spacing::RADIUS_MD.into(),
         },
         shadow: Shadow::default(),
-    }
+    })
 }
 
 /// Workspace thumbnail style
 pub fn workspace_thumbnail_style(active: bool) -> impl Fn(&iced::Theme) -> Style {
-    move |_theme| {
+    accessible(move |_theme| {
         let (border_color, border_width) = if active {
             (NyxColors::AURORA, 2.0)
         } else {
@@ -169,12 +170,12 @@ pub fn workspace_thumbnail_style(active: bool) -> impl Fn(&iced::Theme) -> Style
                 blur_radius: 4.0,
             },
         }
-    }
+    })
 }
 
 /// Notification style
 pub fn notification_style() -> impl Fn(&iced::Theme) -> Style {
-    |_theme| Style {
+    accessible(|_theme| Style {
         background: Some(Background::Color(NyxColors::GLASS_DARK)),
         text_color: Some(NyxColors::TEXT_BRIGHT),
         border: Border {
@@ -187,12 +188,12 @@ pub fn notification_style() -> impl Fn(&iced::Theme) -> Style {
             offset: Vector::new(0.0, 4.0),
             blur_radius: 12.0,
         },
-    }
+    })
 }
 
 /// Popover/menu style
 pub fn popover_style() -> impl Fn(&iced::Theme) -> Style {
-    |_theme| Style {
+    accessible(|_theme| Style {
         background: Some(Background::Color(NyxColors::TWILIGHT)),
         text_color: Some(NyxColors::TEXT_BRIGHT),
         border: Border {
@@ -205,12 +206,12 @@ pub fn popover_style() -> impl Fn(&iced::Theme) -> Style {
             offset: Vector::new(0.0, 8.0),
             blur_radius: 20.0,
         },
-    }
+    })
 }
 
 /// Menu item style
 pub fn menu_item_style(selected: bool) -> impl Fn(&iced::Theme) -> Style {
-    move |_theme| {
+    accessible(move |_theme| {
         let background = if selected {
             NyxColors::AURORA
         } else {
@@ -231,5 +232,5 @@ pub fn menu_item_style(selected: bool) -> impl Fn(&iced::Theme) -> Style {
             },
             shadow: Shadow::default(),
         }
-    }
+    })
 }
@@ -0,0 +1,130 @@
+//! Segmented control: a row of mutually exclusive options
+
+use crate::accessibility::{accessible, accessible_with_status};
+use crate::spacing::Spacing;
+use crate::widgets::variant::StyleVariant;
+use iced::border::Radius;
+use iced::widget::button::{Status, Style};
+use iced::widget::{container, text, Row};
+use iced::{Background, Border, Color, Element, Length};
+
+/// Where a segment sits in the control, which decides its rounded corners
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentPosition {
+    First,
+    Middle,
+    Last,
+    /// The only segment
+    Only,
+}
+
+impl SegmentPosition {
+    /// Position of segment `index` out of `count`
+    pub fn of(index: usize, count: usize) -> Self {
+        match (index, count) {
+            (_, 1) => Self::Only,
+            (0, _) => Self::First,
+            (i, n) if i + 1 == n => Self::Last,
+            _ => Self::Middle,
+        }
+    }
+
+    fn radius(self) -> Radius {
+        let r = Spacing::RADIUS_MD;
+        match self {
+            Self::Only => r.into(),
+            Self::First => Radius::new(0.0).left(r),
+            Self::Last => Radius::new(0.0).right(r),
+            Self::Middle => Radius::new(0.0),
+        }
+    }
+}
+
+/// Create the style of one segment
+pub fn segment_style(
+    variant: StyleVariant,
+    selected: bool,
+    position: SegmentPosition,
+) -> impl Fn(&iced::Theme, Status) -> Style {
+    accessible_with_status(move |_theme, status| {
+        let palette = variant.palette();
+        let (background, text_color) = match (selected, status) {
+            (_, Status::Disabled) => (None, palette.text_disabled),
+            (true, Status::Pressed) => (Some(palette.accent_pressed), variant.on_accent()),
+            (true, Status::Hovered) => (Some(palette.accent_hover), variant.on_accent()),
+            (true, Status::Active) => (Some(palette.accent), variant.on_accent()),
+            (false, Status::Hovered | Status::Pressed) => {
+                (Some(variant.hover()), palette.text_primary)
+            }
+            (false, Status::Active) => (None, palette.text_secondary),
+        };
+
+        Style {
+            background: background.map(Background::Color),
+            text_color,
+            border: Border {
+                color: Color::TRANSPARENT,
+                width: 0.0,
+                radius: position.radius(),
+            },
+            ..Default::default()
+        }
+    })
+}
+
+/// Create the style of the frame around the segments
+pub fn segmented_frame_style(variant: StyleVariant) -> impl Fn(&iced::Theme) -> container::Style {
+    accessible(move |_theme| {
+        let palette = variant.palette();
+        container::Style {
+            background: Some(Background::Color(palette.surface)),
+            border: Border {
+                color: palette.border,
+                width: variant.border_width(),
+                radius: Spacing::RADIUS_MD.into(),
+            },
+            ..Default::default()
+        }
+    })
+}
+
+/// A segmented control over `options`, with `selected` highlighted
+pub fn segmented_control<'a, T, Message>(
+    options: &[(T, &'a str)],
+    selected: &T,
+    on_select: impl Fn(T) -> Message,
+    variant: StyleVariant,
+) -> Element<'a, Message>
+where
+    T: Clone + PartialEq,
+    Message: Clone + 'a,
+{
+    let count = options.len();
+    let segments = options.iter().enumerate().fold(Row::new(), |segments, (i, (value, label))| {
+        segments.push(
+            iced::widget::button(text(*label).center().width(Length::Fill))
+                .width(Length::Fill)
+                .padding([Spacing::XS, Spacing::MD])
+                .style(segment_style(variant, value == selected, SegmentPosition::of(i, count)))
+                .on_press(on_select(value.clone())),
+        )
+    });
+
+    container(segments.spacing(variant.border_width()))
+        .padding(variant.border_width())
+        .style(segmented_frame_style(variant))
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_positions() {
+        assert_eq!(SegmentPosition::of(0, 1), SegmentPosition::Only);
+        assert_eq!(SegmentPosition::of(0, 3), SegmentPosition::First);
+        assert_eq!(SegmentPosition::of(1, 3), SegmentPosition::Middle);
+        assert_eq!(SegmentPosition::of(2, 3), SegmentPosition::Last);
+    }
+}
@@ -0,0 +1,109 @@
+//! Skeleton loaders: placeholder shapes shown while content loads
+//!
+//! The shimmer is driven by the caller: advance `phase` from 0.0 to 1.0 on
+//! a frame or timer subscription and re-render.
+
+use crate::accessibility::accessible;
+use crate::colors::lighten;
+use crate::spacing::Spacing;
+use crate::widgets::variant::StyleVariant;
+use iced::widget::container::Style;
+use iced::widget::{container, horizontal_space, Column};
+use iced::{Background, Border, Color, Element, Length};
+
+/// Duration of one shimmer cycle in milliseconds
+pub const SHIMMER_PERIOD_MS: u64 = 1200;
+
+/// Placeholder color at `phase` of the shimmer cycle
+pub fn shimmer_color(phase: f32, variant: StyleVariant) -> Color {
+    let (base, peak) = match variant {
+        StyleVariant::Dark => (variant.track(), lighten(variant.track(), 0.06)),
+        StyleVariant::Light => (variant.track(), Color::from_rgb(0.930, 0.930, 0.945)),
+        // No motion in high contrast: a steady, clearly outlined block
+        StyleVariant::HighContrast => return variant.track(),
+    };
+
+    // Triangle wave so the pulse eases back rather than jumping
+    let t = 1.0 - (phase.rem_euclid(1.0) * 2.0 - 1.0).abs();
+    Color::from_rgb(
+        base.r + (peak.r - base.r) * t,
+        base.g + (peak.g - base.g) * t,
+        base.b + (peak.b - base.b) * t,
+    )
+}
+
+/// Create a skeleton block style
+pub fn skeleton_style(phase: f32, variant: StyleVariant) -> impl Fn(&iced::Theme) -> Style {
+    accessible(move |_theme| Style {
+        background: Some(Background::Color(shimmer_color(phase, variant))),
+        border: Border {
+            color: match variant {
+                StyleVariant::HighContrast => variant.palette().border,
+                _ => Color::TRANSPARENT,
+            },
+            width: if variant == StyleVariant::HighContrast { 1.0 } else { 0.0 },
+            radius: Spacing::RADIUS_SM.into(),
+        },
+        ..Default::default()
+    })
+}
+
+/// A placeholder block of the given size
+pub fn skeleton<'a, Message: 'a>(
+    width: impl Into<Length>,
+    height: impl Into<Length>,
+    phase: f32,
+    variant: StyleVariant,
+) -> Element<'a, Message> {
+    container(horizontal_space())
+        .width(width)
+        .height(height)
+        .style(skeleton_style(phase, variant))
+        .into()
+}
+
+/// `lines` placeholder text lines, the last one shorter like a paragraph end
+pub fn skeleton_text<'a, Message: 'a>(lines: usize, phase: f32, variant: StyleVariant) -> Element<'a, Message> {
+    (0..lines)
+        .fold(Column::new().spacing(Spacing::SM), |column, i| {
+            let width = if i + 1 == lines && lines > 1 {
+                Length::FillPortion(3)
+            } else {
+                Length::Fill
+            };
+            column.push(skeleton(width, 12.0, phase, variant))
+        })
+        .into()
+}
+
+/// A circular placeholder, for avatars and icons
+pub fn skeleton_circle<'a, Message: 'a>(size: f32, phase: f32, variant: StyleVariant) -> Element<'a, Message> {
+    container(horizontal_space())
+        .width(size)
+        .height(size)
+        .style(move |theme| {
+            let mut style = skeleton_style(phase, variant)(theme);
+            style.border.radius = Spacing::RADIUS_CIRCLE.into();
+            style
+        })
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shimmer_pulses_and_wraps() {
+        let variant = StyleVariant::Dark;
+        assert_eq!(shimmer_color(0.0, variant), shimmer_color(1.0, variant));
+        assert_ne!(shimmer_color(0.0, variant), shimmer_color(0.5, variant));
+        assert_eq!(shimmer_color(0.25, variant), shimmer_color(0.75, variant));
+    }
+
+    #[test]
+    fn test_high_contrast_does_not_animate() {
+        let variant = StyleVariant::HighContrast;
+        assert_eq!(shimmer_color(0.0, variant), shimmer_color(0.5, variant));
+    }
+}
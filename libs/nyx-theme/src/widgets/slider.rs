@@ -0,0 +1,118 @@
+//! Slider styles and a slider with tick marks
+
+use crate::accessibility::accessible_with_status;
+use crate::spacing::Spacing;
+use crate::widgets::variant::StyleVariant;
+use iced::widget::slider::{Handle, HandleShape, Rail, Status, Style};
+use iced::widget::{column, container, horizontal_space, slider, text, Row};
+use iced::{Background, Border, Color, Element, Length};
+use std::ops::RangeInclusive;
+
+/// Rail thickness
+const RAIL_WIDTH: f32 = 4.0;
+
+/// Handle radius at rest
+const HANDLE_RADIUS: f32 = 8.0;
+
+/// Tick mark height
+const TICK_HEIGHT: f32 = 6.0;
+
+/// Create a slider style for the given variant
+pub fn slider_style(variant: StyleVariant) -> impl Fn(&iced::Theme, Status) -> Style {
+    accessible_with_status(move |_theme, status| {
+        let palette = variant.palette();
+        let (fill, radius) = match status {
+            Status::Active => (palette.accent, HANDLE_RADIUS),
+            Status::Hovered => (palette.accent_hover, HANDLE_RADIUS),
+            Status::Dragged => (palette.accent_pressed, HANDLE_RADIUS + 1.0),
+        };
+
+        Style {
+            rail: Rail {
+                backgrounds: (Background::Color(fill), Background::Color(variant.track())),
+                width: RAIL_WIDTH,
+                border: Border {
+                    radius: Spacing::RADIUS_PILL.into(),
+                    ..Default::default()
+                },
+            },
+            handle: Handle {
+                shape: HandleShape::Circle { radius },
+                background: Background::Color(match variant {
+                    StyleVariant::Light => fill,
+                    _ => Color::WHITE,
+                }),
+                border_width: variant.border_width(),
+                border_color: fill,
+            },
+        }
+    })
+}
+
+/// Positions of `count` evenly spaced ticks across `range`, ends included
+pub fn tick_values(range: &RangeInclusive<f32>, count: usize) -> Vec<f32> {
+    let (start, end) = (*range.start(), *range.end());
+    match count {
+        0 => Vec::new(),
+        1 => vec![start],
+        _ => {
+            let step = (end - start) / (count - 1) as f32;
+            (0..count).map(|i| start + step * i as f32).collect()
+        }
+    }
+}
+
+/// A slider with `ticks` evenly spaced marks below it, each labelled by `label`
+pub fn ticked_slider<'a, Message: Clone + 'a>(
+    range: RangeInclusive<f32>,
+    value: f32,
+    ticks: usize,
+    label: impl Fn(f32) -> String,
+    on_change: impl Fn(f32) -> Message + 'a,
+    variant: StyleVariant,
+) -> Element<'a, Message> {
+    let palette = variant.palette();
+    let tick_color = variant.track();
+
+    let marks = tick_values(&range, ticks).into_iter().enumerate().fold(
+        Row::new().align_y(iced::Alignment::Start),
+        |marks, (i, tick)| {
+            let marks = if i == 0 { marks } else { marks.push(horizontal_space()) };
+            marks.push(
+                column![
+                    container(horizontal_space())
+                        .width(variant.border_width())
+                        .height(TICK_HEIGHT)
+                        .style(move |_| container::Style {
+                            background: Some(Background::Color(tick_color)),
+                            ..Default::default()
+                        }),
+                    text(label(tick))
+                        .size(11)
+                        .color(palette.text_secondary),
+                ]
+                .spacing(Spacing::XXS)
+                .align_x(iced::Alignment::Center),
+            )
+        },
+    );
+
+    column![
+        slider(range, value, on_change).style(slider_style(variant)),
+        marks.padding([0.0, HANDLE_RADIUS]).width(Length::Fill),
+    ]
+    .spacing(Spacing::XS)
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_values_include_both_ends() {
+        assert_eq!(tick_values(&(0.0..=100.0), 5), vec![0.0, 25.0, 50.0, 75.0, 100.0]);
+        assert_eq!(tick_values(&(0.0..=1.0), 1), vec![0.0]);
+        assert!(tick_values(&(0.0..=1.0), 0).is_empty());
+    }
+}
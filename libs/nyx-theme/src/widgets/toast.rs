@@ -0,0 +1,203 @@
+//! Toast notifications: short-lived messages stacked in a corner
+
+use crate::accessibility::accessible;
+use crate::colors::with_alpha;
+use crate::spacing::Spacing;
+use crate::widgets::variant::StyleVariant;
+use iced::widget::container::Style;
+use iced::widget::{column, container, row, text, Column};
+use iced::{Background, Border, Color, Element, Shadow, Vector};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Toasts shown at once; older ones are dropped first
+const MAX_VISIBLE: usize = 4;
+
+/// Toast width
+const TOAST_WIDTH: f32 = 320.0;
+
+/// What a toast reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToastKind {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ToastKind {
+    /// How long a toast of this kind stays up
+    pub fn duration(self) -> Duration {
+        match self {
+            Self::Info | Self::Success => Duration::from_secs(4),
+            Self::Warning => Duration::from_secs(6),
+            Self::Error => Duration::from_secs(8),
+        }
+    }
+
+    fn color(self, variant: StyleVariant) -> Color {
+        let palette = variant.palette();
+        match self {
+            Self::Info => palette.info,
+            Self::Success => palette.success,
+            Self::Warning => palette.warning,
+            Self::Error => palette.error,
+        }
+    }
+}
+
+/// A single toast
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub id: u64,
+    pub kind: ToastKind,
+    pub title: String,
+    pub body: Option<String>,
+    pub expires: Instant,
+}
+
+/// Toasts currently on screen, oldest first
+#[derive(Debug, Default)]
+pub struct ToastQueue {
+    toasts: VecDeque<Toast>,
+    next_id: u64,
+}
+
+impl ToastQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show a toast, returning its id
+    pub fn push(&mut self, kind: ToastKind, title: impl Into<String>, body: Option<String>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.toasts.push_back(Toast {
+            id,
+            kind,
+            title: title.into(),
+            body,
+            expires: Instant::now() + kind.duration(),
+        });
+        while self.toasts.len() > MAX_VISIBLE {
+            self.toasts.pop_front();
+        }
+        id
+    }
+
+    /// Dismiss a toast before it expires
+    pub fn dismiss(&mut self, id: u64) {
+        self.toasts.retain(|t| t.id != id);
+    }
+
+    /// Drop toasts expired by `now`; call from a timer tick
+    pub fn expire(&mut self, now: Instant) {
+        self.toasts.retain(|t| t.expires > now);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Toast> {
+        self.toasts.iter()
+    }
+}
+
+/// Create a toast container style
+pub fn toast_style(kind: ToastKind, variant: StyleVariant) -> impl Fn(&iced::Theme) -> Style {
+    accessible(move |_theme| {
+        let palette = variant.palette();
+        Style {
+            background: Some(Background::Color(palette.surface_elevated)),
+            text_color: Some(palette.text_primary),
+            border: Border {
+                color: match variant {
+                    StyleVariant::HighContrast => kind.color(variant),
+                    _ => with_alpha(kind.color(variant), 0.5),
+                },
+                width: variant.border_width(),
+                radius: Spacing::RADIUS_LG.into(),
+            },
+            shadow: Shadow {
+                color: Color::from_rgba(0.0, 0.0, 0.0, 0.3),
+                offset: Vector::new(0.0, 4.0),
+                blur_radius: Spacing::SHADOW_LG,
+            },
+        }
+    })
+}
+
+/// Render one toast
+pub fn toast<'a, Message: 'a>(toast: &Toast, variant: StyleVariant) -> Element<'a, Message> {
+    let palette = variant.palette();
+    let accent = kind_marker(toast.kind, variant);
+
+    let mut content = column![text(toast.title.clone()).size(14)].spacing(Spacing::XXS);
+    if let Some(body) = &toast.body {
+        content = content.push(text(body.clone()).size(13).color(palette.text_secondary));
+    }
+
+    container(row![accent, content].spacing(Spacing::MD))
+        .padding(Spacing::MD)
+        .width(TOAST_WIDTH)
+        .style(toast_style(toast.kind, variant))
+        .into()
+}
+
+/// Render the queue as a column, newest at the bottom
+pub fn toast_stack<'a, Message: 'a>(queue: &ToastQueue, variant: StyleVariant) -> Element<'a, Message> {
+    queue
+        .iter()
+        .fold(Column::new().spacing(Spacing::SM), |stack, t| stack.push(toast(t, variant)))
+        .into()
+}
+
+fn kind_marker<'a, Message: 'a>(kind: ToastKind, variant: StyleVariant) -> Element<'a, Message> {
+    let color = kind.color(variant);
+    container(text(""))
+        .width(4.0)
+        .height(32.0)
+        .style(move |_| Style {
+            background: Some(Background::Color(color)),
+            border: Border {
+                radius: Spacing::RADIUS_PILL.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_caps_visible_toasts() {
+        let mut queue = ToastQueue::new();
+        for i in 0..6 {
+            queue.push(ToastKind::Info, format!("toast {}", i), None);
+        }
+        let titles: Vec<_> = queue.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, ["toast 2", "toast 3", "toast 4", "toast 5"]);
+    }
+
+    #[test]
+    fn test_expire_and_dismiss() {
+        let mut queue = ToastQueue::new();
+        let info = queue.push(ToastKind::Info, "saved", None);
+        queue.push(ToastKind::Error, "failed", Some("disk full".into()));
+
+        queue.expire(Instant::now() + Duration::from_secs(5));
+        assert_eq!(queue.iter().count(), 1);
+        assert_eq!(queue.iter().next().unwrap().kind, ToastKind::Error);
+
+        queue.dismiss(info);
+        assert_eq!(queue.iter().count(), 1);
+        queue.dismiss(1);
+        assert!(queue.is_empty());
+    }
+}
@@ -1,13 +1,14 @@
 //! Toggle/switch styles for Nyx OS
 
+use crate::accessibility::accessible_with_status;
 use crate::colors::NyxColors;
-use crate::spacing::Spacing;
-use iced::widget::toggler::{self, Status, Style};
-use iced::{Background, Color};
+use crate::widgets::variant::StyleVariant;
+use iced::widget::toggler::{Status, Style};
+use iced::Color;
 
 /// Create a toggle switch style
 pub fn toggle_style() -> impl Fn(&iced::Theme, Status) -> Style {
-    |_theme, status| {
+    accessible_with_status(|_theme, status| {
         let (background, foreground, background_border) = match status {
             Status::Active { is_toggled } => {
                 if is_toggled {
@@ -47,19 +48,19 @@ pub fn toggle_style() -> impl Fn(&iced::Theme, Status) -> Style {
         };
 
         Style {
-            background: Background::Color(background),
+            background,
             background_border_width: 1.0,
             background_border_color: background_border,
             foreground,
             foreground_border_width: 0.0,
             foreground_border_color: Color::TRANSPARENT,
         }
-    }
+    })
 }
 
 /// Create a compact toggle style for quick settings tiles
 pub fn compact_toggle_style() -> impl Fn(&iced::Theme, Status) -> Style {
-    |_theme, status| {
+    accessible_with_status(|_theme, status| {
         let (background, foreground) = match status {
             Status::Active { is_toggled } => {
                 if is_toggled {
@@ -88,12 +89,43 @@ pub fn compact_toggle_style() -> impl Fn(&iced::Theme, Status) -> Style {
         };
 
         Style {
-            background: Background::Color(background),
+            background,
             background_border_width: 0.0,
             background_border_color: Color::TRANSPARENT,
             foreground,
             foreground_border_width: 0.0,
             foreground_border_color: Color::TRANSPARENT,
         }
-    }
+    })
+}
+
+/// Create a toggle switch style for the given variant
+pub fn switch_style(variant: StyleVariant) -> impl Fn(&iced::Theme, Status) -> Style {
+    accessible_with_status(move |_theme, status| {
+        let palette = variant.palette();
+        let (background, foreground, border) = match status {
+            Status::Active { is_toggled: true } => {
+                (palette.accent, variant.on_accent(), palette.accent)
+            }
+            Status::Hovered { is_toggled: true } => {
+                (palette.accent_hover, variant.on_accent(), palette.accent_hover)
+            }
+            Status::Active { is_toggled: false } => {
+                (variant.track(), palette.text_secondary, palette.border)
+            }
+            Status::Hovered { is_toggled: false } => {
+                (variant.track(), palette.text_primary, palette.border_focus)
+            }
+            Status::Disabled => (palette.surface, palette.text_disabled, palette.border),
+        };
+
+        Style {
+            background,
+            background_border_width: variant.border_width(),
+            background_border_color: border,
+            foreground,
+            foreground_border_width: 0.0,
+            foreground_border_color: Color::TRANSPARENT,
+        }
+    })
 }
@@ -0,0 +1,108 @@
+//! Color variants shared by the component library
+//!
+//! Every component style takes a [`StyleVariant`] and draws its colors from
+//! the matching [`ColorPalette`], so a component looks right on dark, light
+//! and high contrast surfaces without callers hand-picking colors.
+
+use crate::accessibility;
+use crate::colors::{contrast_ratio, with_alpha, ColorPalette, NyxColors};
+use crate::theme::ThemeMode;
+use iced::Color;
+
+/// Which palette a component is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StyleVariant {
+    /// Night sky palette (default for Nyx)
+    #[default]
+    Dark,
+    /// Light palette
+    Light,
+    /// Black and white with opaque borders and thicker outlines
+    HighContrast,
+}
+
+impl StyleVariant {
+    /// Variant for a theme mode; System resolves to Dark like the theme does
+    pub fn from_mode(mode: ThemeMode) -> Self {
+        match mode {
+            ThemeMode::Light => Self::Light,
+            ThemeMode::Dark | ThemeMode::System => Self::Dark,
+            ThemeMode::HighContrast => Self::HighContrast,
+        }
+    }
+
+    /// Variant for a theme mode under the accessibility options in effect
+    pub fn current(mode: ThemeMode) -> Self {
+        Self::from_mode(accessibility::current().mode(mode))
+    }
+
+    /// Palette the variant draws from
+    pub fn palette(self) -> ColorPalette {
+        match self {
+            Self::Dark => ColorPalette::dark(),
+            Self::Light => ColorPalette::light(),
+            Self::HighContrast => ColorPalette::high_contrast(),
+        }
+    }
+
+    /// Outline width for borders and focus rings
+    pub fn border_width(self) -> f32 {
+        match self {
+            Self::HighContrast => 2.0,
+            _ => 1.0,
+        }
+    }
+
+    /// Unfilled part of tracks and rails
+    pub fn track(self) -> Color {
+        match self {
+            Self::Dark => NyxColors::NEBULA,
+            Self::Light => Color::from_rgb(0.870, 0.870, 0.895),
+            Self::HighContrast => NyxColors::DUSK,
+        }
+    }
+
+    /// Hover tint laid over surfaces
+    pub fn hover(self) -> Color {
+        match self {
+            Self::Light => with_alpha(Color::BLACK, 0.06),
+            _ => with_alpha(Color::WHITE, 0.08),
+        }
+    }
+
+    /// Text color readable on the accent
+    pub fn on_accent(self) -> Color {
+        on_color(self.palette().accent)
+    }
+}
+
+/// White or dark text, whichever reads better on `background`
+pub fn on_color(background: Color) -> Color {
+    if contrast_ratio(Color::WHITE, background) >= contrast_ratio(NyxColors::TEXT_DARK, background) {
+        Color::WHITE
+    } else {
+        NyxColors::TEXT_DARK
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_mode() {
+        assert_eq!(StyleVariant::from_mode(ThemeMode::Light), StyleVariant::Light);
+        assert_eq!(StyleVariant::from_mode(ThemeMode::System), StyleVariant::Dark);
+    }
+
+    #[test]
+    fn test_on_color_picks_readable_text() {
+        assert_eq!(on_color(Color::BLACK), Color::WHITE);
+        assert_eq!(on_color(Color::WHITE), NyxColors::TEXT_DARK);
+    }
+
+    #[test]
+    fn test_high_contrast_outlines_are_thicker() {
+        assert!(StyleVariant::HighContrast.border_width() > StyleVariant::Dark.border_width());
+    }
+}
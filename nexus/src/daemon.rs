@@ -8,6 +8,8 @@ mod store;
 mod cache;
 mod sandbox;
 mod ipc;
+mod provenance;
+mod profile;
 
 use anyhow::Result;
 use clap::Parser;
@@ -20,6 +22,7 @@ use crate::ipc::{NexusServer, IpcRequest, IpcResponse};
 use crate::repository::RepositoryManager;
 use crate::store::PackageStore;
 use crate::cache::PackageCache;
+use crate::package::PackageSpec;
 
 #[derive(Parser)]
 #[command(name = "nexusd")]
@@ -222,6 +225,42 @@ async fn handle_request(
             }
         }
 
+        IpcRequest::Realise { packages } => {
+            let state = state.read().await;
+            let mut store_paths = Vec::new();
+
+            for package in &packages {
+                let pkg = match package.parse::<PackageSpec>() {
+                    Ok(spec) => state.repos.get_matching(&spec),
+                    Err(e) => {
+                        return IpcResponse::Error {
+                            message: format!("Invalid package {}: {}", package, e),
+                        };
+                    }
+                };
+                let Some(pkg) = pkg else {
+                    return IpcResponse::Error {
+                        message: format!("Package not found: {}", package),
+                    };
+                };
+
+                let realised = match state.cache.get_or_download(&pkg).await {
+                    Ok(archive) => transaction::extract_package(&archive, &pkg),
+                    Err(e) => Err(e),
+                };
+                match realised {
+                    Ok(path) => store_paths.push(path.to_string_lossy().to_string()),
+                    Err(e) => {
+                        return IpcResponse::Error {
+                            message: format!("Failed to realise {}: {}", package, e),
+                        };
+                    }
+                }
+            }
+
+            IpcResponse::Realised { store_paths }
+        }
+
         IpcRequest::Status => {
             let state = state.read().await;
 
@@ -1,6 +1,9 @@
 //! IPC interface for Nexus daemon
 
 use anyhow::Result;
+use libnyx_ipc::audit::{self, AuditEvent, AuditOutcome};
+use libnyx_ipc::trace::{self, TraceContext};
+use libnyx_ipc::Hello;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -9,7 +12,7 @@ use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::RwLock;
 use tracing::{info, error, debug};
 
-use crate::package::PackageSpec;
+use crate::package::{PackageSpec, RepoPackage};
 
 /// IPC request
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +33,13 @@ pub enum IpcRequest {
     Rollback {
         generation: Option<u32>,
     },
+    /// Fetch packages into the store without installing them, for user
+    /// profiles. Packages are `name@version` and come only from the
+    /// configured repositories, so unprivileged callers can't add
+    /// arbitrary content to the store.
+    Realise {
+        packages: Vec<String>,
+    },
     Status,
 }
 
@@ -46,6 +56,10 @@ pub enum IpcResponse {
         download_size: u64,
         install_size: u64,
     },
+    Realised {
+        /// Store paths, in request order
+        store_paths: Vec<String>,
+    },
     Status {
         installed_count: usize,
         current_generation: u32,
@@ -56,6 +70,29 @@ pub enum IpcResponse {
     },
 }
 
+impl IpcRequest {
+    /// The privileged operation a request performs, as an audit action and
+    /// the packages it touches
+    fn audited(&self) -> Option<(&'static str, String)> {
+        match self {
+            IpcRequest::Install { dry_run: true, .. } => None,
+            IpcRequest::Install { specs, .. } => Some((
+                "package.install",
+                specs.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(" "),
+            )),
+            IpcRequest::Remove { packages, .. } => Some(("package.remove", packages.join(" "))),
+            IpcRequest::Upgrade { packages } if packages.is_empty() => Some(("package.upgrade", "*".to_string())),
+            IpcRequest::Upgrade { packages } => Some(("package.upgrade", packages.join(" "))),
+            IpcRequest::Rollback { generation } => Some((
+                "package.rollback",
+                generation.map_or_else(|| "previous".to_string(), |g| format!("generation {}", g)),
+            )),
+            IpcRequest::Realise { packages } => Some(("package.realise", packages.join(" "))),
+            IpcRequest::Sync | IpcRequest::Status => None,
+        }
+    }
+}
+
 // Custom serialization for PackageSpec
 impl Serialize for PackageSpec {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -139,22 +176,47 @@ where
     F: Fn(IpcRequest, Arc<RwLock<S>>) -> Fut,
     Fut: std::future::Future<Output = IpcResponse>,
 {
+    let actor = match stream.peer_cred() {
+        Ok(cred) => AuditEvent::uid(cred.uid()),
+        Err(_) => "unknown".to_string(),
+    };
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
 
+    let hello = Hello::new("nexus", env!("CARGO_PKG_VERSION")).with_features(&["rollback"]);
+
     while reader.read_line(&mut line).await? > 0 {
-        let response = match serde_json::from_str::<IpcRequest>(&line) {
-            Ok(request) => {
-                debug!("Request: {:?}", request);
-                handler(request, state.clone()).await
+        let json = match hello.respond(&line) {
+            Some(reply) => reply,
+            None => {
+                let response = match serde_json::from_str::<IpcRequest>(&line) {
+                    Ok(request) => {
+                        trace::traced(&line, async {
+                            debug!("Request: {:?}", request);
+                            let audited = request.audited();
+                            let response = handler(request, state.clone()).await;
+                            if let Some((action, object)) = audited {
+                                let event = match &response {
+                                    IpcResponse::Error { message } => {
+                                        AuditEvent::new("nexus", actor.as_str(), action, object, AuditOutcome::Failure)
+                                            .with_reason(message.as_str())
+                                    }
+                                    _ => AuditEvent::new("nexus", actor.as_str(), action, object, AuditOutcome::Success),
+                                };
+                                audit::record(event);
+                            }
+                            response
+                        })
+                        .await
+                    }
+                    Err(e) => IpcResponse::Error {
+                        message: format!("Invalid request: {}", e),
+                    },
+                };
+                serde_json::to_string(&response)?
             }
-            Err(e) => IpcResponse::Error {
-                message: format!("Invalid request: {}", e),
-            },
         };
-
-        let json = serde_json::to_string(&response)?;
         writer.write_all(json.as_bytes()).await?;
         writer.write_all(b"\n").await?;
         writer.flush().await?;
@@ -183,8 +245,10 @@ impl NexusClient {
     async fn send(&self, request: IpcRequest) -> Result<IpcResponse> {
         let mut stream = UnixStream::connect(&self.socket_path).await?;
 
-        let json = serde_json::to_string(&request)?;
-        stream.write_all(json.as_bytes()).await?;
+        // Each command starts a trace the daemons it reaches continue
+        let mut request = serde_json::to_value(&request)?;
+        TraceContext::outgoing().inject(&mut request);
+        stream.write_all(request.to_string().as_bytes()).await?;
         stream.write_all(b"\n").await?;
         stream.flush().await?;
 
@@ -272,6 +336,21 @@ impl NexusClient {
         }
     }
 
+    /// Have the daemon fetch packages into the store; returns their store paths
+    pub async fn realise(&self, packages: &[RepoPackage]) -> Result<Vec<String>> {
+        let response = self.send(IpcRequest::Realise {
+            packages: packages.iter().map(|p| format!("{}@{}", p.name, p.version)).collect(),
+        }).await?;
+
+        match response {
+            IpcResponse::Realised { store_paths } => Ok(store_paths),
+            IpcResponse::Error { message } => {
+                Err(anyhow::anyhow!(message))
+            }
+            _ => Err(anyhow::anyhow!("Unexpected response to realise")),
+        }
+    }
+
     pub async fn rollback(&self, generation: Option<u32>) -> Result<()> {
         let response = self.send(IpcRequest::Rollback { generation }).await?;
 
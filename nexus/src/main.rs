@@ -11,6 +11,9 @@ mod store;
 mod cache;
 mod sandbox;
 mod ipc;
+mod provenance;
+mod profile;
+mod oci;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
@@ -107,12 +110,20 @@ enum Commands {
         /// Output directory
         #[arg(short, long)]
         output: Option<String>,
+
+        /// Build a second time and fail unless the outputs are identical
+        #[arg(long)]
+        check_reproducible: bool,
     },
 
     /// Verify package integrity
     Verify {
         /// Package name (all if empty)
         package: Option<String>,
+
+        /// Also check files against the package's build provenance
+        #[arg(long)]
+        provenance: bool,
     },
 
     /// Rollback to previous state
@@ -134,6 +145,61 @@ enum Commands {
         #[arg(long)]
         files: Option<String>,
     },
+
+    /// Export a generation as a container image
+    Export {
+        /// Generation to package as an OCI image archive
+        #[arg(long, value_name = "GENERATION")]
+        oci: u32,
+
+        /// Only export these packages and their dependencies
+        #[arg(short, long = "package")]
+        packages: Vec<String>,
+
+        /// Image reference to tag the image with
+        #[arg(short, long)]
+        tag: Option<String>,
+
+        /// Output archive
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Manage your per-user profile (~/.nyx-profile)
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// Install packages into your profile
+    Install {
+        /// Package names or specs
+        packages: Vec<String>,
+    },
+
+    /// Remove packages from your profile
+    Remove {
+        /// Package names
+        packages: Vec<String>,
+    },
+
+    /// List packages in your profile
+    List,
+
+    /// List profile generations
+    Generations,
+
+    /// Switch to a previous profile generation
+    Rollback {
+        /// Generation number
+        generation: Option<u32>,
+    },
+
+    /// Print shell commands that put the profile on PATH
+    Env,
 }
 
 #[tokio::main]
@@ -183,12 +249,12 @@ async fn main() -> Result<()> {
             clean_cache(all).await?;
         }
 
-        Commands::Build { path, output } => {
-            build_package(&path, output.as_deref()).await?;
+        Commands::Build { path, output, check_reproducible } => {
+            build_package(&path, output.as_deref(), check_reproducible).await?;
         }
 
-        Commands::Verify { package } => {
-            verify_packages(package.as_deref()).await?;
+        Commands::Verify { package, provenance } => {
+            verify_packages(package.as_deref(), provenance).await?;
         }
 
         Commands::Rollback { generation } => {
@@ -202,6 +268,14 @@ async fn main() -> Result<()> {
         Commands::Query { owns, files } => {
             query_packages(owns.as_deref(), files.as_deref()).await?;
         }
+
+        Commands::Export { oci, packages, tag, output } => {
+            export_image(oci, &packages, tag.as_deref(), output.as_deref()).await?;
+        }
+
+        Commands::Profile { command } => {
+            profile_command(command, client.as_ref()).await?;
+        }
     }
 
     Ok(())
@@ -411,22 +485,44 @@ async fn clean_cache(all: bool) -> Result<()> {
     Ok(())
 }
 
-async fn build_package(path: &str, output: Option<&str>) -> Result<()> {
+async fn build_package(path: &str, output: Option<&str>, check_reproducible: bool) -> Result<()> {
     info!("Building package from {}", path);
 
     let sandbox = sandbox::BuildSandbox::new()?;
-    let pkg = sandbox.build(path).await?;
+    let (pkg, mut provenance) = sandbox.build(path).await?;
+
+    if check_reproducible {
+        info!("Rebuilding to check reproducibility");
+        let (_, rebuild) = sandbox::BuildSandbox::new()?.build(path).await?;
+
+        let diffs = provenance.compare_outputs(&rebuild);
+        if !diffs.is_empty() {
+            for diff in &diffs {
+                println!("  {}", diff);
+            }
+            return Err(anyhow::anyhow!(
+                "{} {} is not reproducible: {} outputs differ",
+                pkg.name, pkg.version, diffs.len()
+            ));
+        }
+
+        provenance.reproducible = Some(true);
+        println!("Reproducible: {} outputs identical across two builds", provenance.outputs.len());
+    }
 
     let output_path = output.unwrap_or(".");
     let archive_path = format!("{}/{}-{}.nyx", output_path, pkg.name, pkg.version);
 
-    pkg.write_archive(&archive_path)?;
+    pkg.write_archive(&archive_path, Some(&provenance))?;
+    let provenance_path = provenance::provenance_path(std::path::Path::new(&archive_path));
+    provenance.save(&provenance_path)?;
     println!("Built package: {}", archive_path);
+    println!("Provenance:    {}", provenance_path.display());
 
     Ok(())
 }
 
-async fn verify_packages(package: Option<&str>) -> Result<()> {
+async fn verify_packages(package: Option<&str>, check_provenance: bool) -> Result<()> {
     let store = PackageStore::open("/nyx/store")?;
 
     let packages = if let Some(name) = package {
@@ -450,6 +546,26 @@ async fn verify_packages(package: Option<&str>) -> Result<()> {
                 errors.push(pkg.name.clone());
             }
         }
+
+        if check_provenance {
+            match store.verify_provenance(&pkg) {
+                Ok(None) => println!("{}: no provenance recorded", pkg.name),
+                Ok(Some(mismatched)) if mismatched.is_empty() => {
+                    println!("{}: matches build provenance", pkg.name);
+                }
+                Ok(Some(mismatched)) => {
+                    println!("{}: differs from build provenance", pkg.name);
+                    for path in &mismatched {
+                        println!("  {}", path);
+                    }
+                    errors.push(pkg.name.clone());
+                }
+                Err(e) => {
+                    println!("{}: PROVENANCE ERROR ({})", pkg.name, e);
+                    errors.push(pkg.name.clone());
+                }
+            }
+        }
     }
 
     if !errors.is_empty() {
@@ -513,3 +629,86 @@ async fn query_packages(owns: Option<&str>, files: Option<&str>) -> Result<()> {
 
     Ok(())
 }
+
+async fn export_image(
+    generation: u32,
+    packages: &[String],
+    tag: Option<&str>,
+    output: Option<&str>,
+) -> Result<()> {
+    let store = PackageStore::open("/nyx/store")?;
+    let closure = oci::closure(&store.generation_packages(generation)?, packages)?;
+    if closure.is_empty() {
+        return Err(anyhow::anyhow!("Generation {} has no packages to export", generation));
+    }
+
+    let tag = tag
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("nyx:generation-{}", generation));
+    let output = output
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("nyx-generation-{}.oci.tar", generation));
+
+    info!("Exporting {} packages from generation {}", closure.len(), generation);
+    let digest = oci::export(&closure, &tag, std::path::Path::new(&output))?;
+
+    println!("Exported {} ({} layers) to {}", tag, closure.len() + 1, output);
+    println!("Manifest: {}", digest);
+    Ok(())
+}
+
+async fn profile_command(command: ProfileCommand, client: Option<&NexusClient>) -> Result<()> {
+    let profile = profile::UserProfile::open()?;
+
+    match command {
+        ProfileCommand::Install { packages } => {
+            let specs: Vec<PackageSpec> = packages.iter()
+                .map(|s| s.parse())
+                .collect::<Result<Vec<_>, _>>()?;
+            let repos = RepositoryManager::load("/etc/nexus/repos.d")?;
+
+            match profile.install(&specs, &repos, client).await? {
+                Some(gen) => println!("Profile generation {} installed", gen),
+                None => println!("Nothing to install: already provided by the system or your profile"),
+            }
+        }
+
+        ProfileCommand::Remove { packages } => {
+            let gen = profile.remove(&packages)?;
+            println!("Profile generation {} installed", gen);
+        }
+
+        ProfileCommand::List => {
+            for pkg in profile.list_installed()? {
+                let marker = if pkg.explicit { "" } else { " (dependency)" };
+                println!("{} {}{}", pkg.name, pkg.version, marker);
+            }
+        }
+
+        ProfileCommand::Generations => {
+            let current = profile.current_generation();
+
+            for gen in profile.list_generations()? {
+                let marker = if gen.number == current { " *" } else { "" };
+                println!(
+                    "{}: {} ({} packages){}",
+                    gen.number,
+                    gen.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                    gen.package_count,
+                    marker
+                );
+            }
+        }
+
+        ProfileCommand::Rollback { generation } => {
+            let gen = profile.rollback(generation)?;
+            println!("Profile rolled back to generation {}", gen);
+        }
+
+        ProfileCommand::Env => {
+            print!("{}", profile.env());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,402 @@
+//! OCI image export
+//!
+//! Packages a generation, or the closure of some of its packages, as an
+//! OCI image archive that container runtimes can load (`podman load`,
+//! `skopeo copy oci-archive:...`, and `docker load`, which reads the
+//! `manifest.json` written alongside the OCI index).
+//!
+//! Every store path becomes its own layer, dependencies first, so images
+//! sharing packages share layers. A last layer holds `/nyx/profile`, a
+//! symlink forest of the packages' files laid out like a user profile, and
+//! the image's PATH points into it. Layers are built deterministically
+//! (sorted entries, fixed owners and timestamps) and the config carries no
+//! creation time, so exporting the same closure twice gives the same
+//! image digest.
+
+use anyhow::{Result, anyhow, Context};
+use flate2::write::GzEncoder;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tar::{EntryType, Header, HeaderMode};
+use tracing::{debug, info, warn};
+
+use crate::package::{InstalledPackage, PackageSpec};
+
+const MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+const CONFIG_MEDIA_TYPE: &str = "application/vnd.oci.image.config.v1+json";
+const LAYER_MEDIA_TYPE: &str = "application/vnd.oci.image.layer.v1.tar+gzip";
+
+/// Annotation naming the store path a layer holds
+const STORE_PATH_ANNOTATION: &str = "org.nyxos.store-path";
+
+/// Where the symlink forest lives in the image
+const PROFILE_PATH: &str = "nyx/profile";
+
+/// Image PATH, matching the build sandbox's
+const IMAGE_PATH: &str = "/nyx/profile/bin:/usr/bin:/bin";
+
+/// A blob written to the staging directory
+struct Blob {
+    digest: String,
+    size: u64,
+    path: PathBuf,
+}
+
+/// A layer blob and the digest of its uncompressed contents
+struct Layer {
+    blob: Blob,
+    diff_id: String,
+    store_path: Option<String>,
+}
+
+/// Select `names` and everything they depend on from a generation's
+/// packages, dependencies before the packages needing them. An empty
+/// `names` selects the whole generation.
+pub fn closure(packages: &[InstalledPackage], names: &[String]) -> Result<Vec<InstalledPackage>> {
+    let by_name: BTreeMap<&str, &InstalledPackage> = packages
+        .iter()
+        .map(|p| (p.name.as_str(), p))
+        .collect();
+
+    let roots: Vec<&str> = if names.is_empty() {
+        by_name.keys().copied().collect()
+    } else {
+        names.iter().map(String::as_str).collect()
+    };
+
+    let mut visited = HashSet::new();
+    let mut ordered = Vec::new();
+    for root in roots {
+        visit(root, &by_name, &mut visited, &mut ordered)?;
+    }
+    Ok(ordered)
+}
+
+fn visit<'a>(
+    name: &'a str,
+    by_name: &BTreeMap<&'a str, &'a InstalledPackage>,
+    visited: &mut HashSet<&'a str>,
+    ordered: &mut Vec<InstalledPackage>,
+) -> Result<()> {
+    if !visited.insert(name) {
+        return Ok(());
+    }
+
+    let pkg = by_name
+        .get(name)
+        .ok_or_else(|| anyhow!("Package not in generation: {}", name))?;
+
+    let mut deps: Vec<PackageSpec> = pkg.dependencies
+        .iter()
+        .map(|d| d.parse())
+        .collect::<Result<_>>()?;
+    deps.sort_by(|a, b| a.name.cmp(&b.name));
+    for dep in deps {
+        let dep_name = by_name
+            .get_key_value(dep.name.as_str())
+            .map(|(k, _)| *k)
+            .ok_or_else(|| anyhow!("{} depends on {}, which is not in the generation", name, dep.name))?;
+        visit(dep_name, by_name, visited, ordered)?;
+    }
+
+    ordered.push((*pkg).clone());
+    Ok(())
+}
+
+/// Write `packages` as an OCI image archive at `output`, tagged `tag`
+///
+/// Returns the manifest digest.
+pub fn export(packages: &[InstalledPackage], tag: &str, output: &Path) -> Result<String> {
+    let staging = tempfile::tempdir()?;
+
+    let mut layers = Vec::new();
+    for pkg in packages {
+        debug!("Layer for {}", pkg.store_path);
+        let store_path = Path::new(&pkg.store_path);
+        if !store_path.is_dir() {
+            return Err(anyhow!("{} is not in the store: {}", pkg.name, pkg.store_path));
+        }
+        let path = staging.path().join(format!("layer-{}", layers.len()));
+        layers.push(write_layer(&path, Some(pkg.store_path.clone()), |tar| {
+            append_tree(tar, store_path)
+        })?);
+    }
+    let path = staging.path().join("layer-profile");
+    layers.push(write_layer(&path, None, |tar| append_forest(tar, packages))?);
+
+    let config = json!({
+        "architecture": oci_architecture(),
+        "os": "linux",
+        "config": {
+            "Env": [format!("PATH={}", IMAGE_PATH)],
+            "Labels": {
+                "org.nyxos.packages": packages
+                    .iter()
+                    .map(|p| format!("{}-{}", p.name, p.version))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            },
+        },
+        "rootfs": {
+            "type": "layers",
+            "diff_ids": layers.iter().map(|l| l.diff_id.clone()).collect::<Vec<_>>(),
+        },
+        "history": layers.iter().map(|l| json!({
+            "created_by": match &l.store_path {
+                Some(path) => format!("nexus: {}", path),
+                None => "nexus: profile".to_string(),
+            },
+        })).collect::<Vec<_>>(),
+    });
+    let config = write_blob(staging.path(), &serde_json::to_vec(&config)?)?;
+
+    let manifest = json!({
+        "schemaVersion": 2,
+        "mediaType": MANIFEST_MEDIA_TYPE,
+        "config": descriptor(CONFIG_MEDIA_TYPE, &config),
+        "layers": layers.iter().map(|l| {
+            let mut desc = descriptor(LAYER_MEDIA_TYPE, &l.blob);
+            if let Some(path) = &l.store_path {
+                desc["annotations"] = json!({ STORE_PATH_ANNOTATION: path });
+            }
+            desc
+        }).collect::<Vec<_>>(),
+    });
+    let manifest = write_blob(staging.path(), &serde_json::to_vec(&manifest)?)?;
+
+    let mut manifest_desc = descriptor(MANIFEST_MEDIA_TYPE, &manifest);
+    manifest_desc["annotations"] = json!({ "org.opencontainers.image.ref.name": tag });
+    let index = json!({
+        "schemaVersion": 2,
+        "mediaType": "application/vnd.oci.image.index.v1+json",
+        "manifests": [manifest_desc],
+    });
+    let docker_manifest = json!([{
+        "Config": blob_name(&config),
+        "RepoTags": [tag],
+        "Layers": layers.iter().map(|l| blob_name(&l.blob)).collect::<Vec<_>>(),
+    }]);
+
+    // Assemble the archive next to the output and move it into place
+    let partial = output.with_extension("partial");
+    let mut archive = tar::Builder::new(File::create(&partial)?);
+    append_bytes(&mut archive, "oci-layout", br#"{"imageLayoutVersion":"1.0.0"}"#)?;
+    append_bytes(&mut archive, "index.json", &serde_json::to_vec(&index)?)?;
+    append_bytes(&mut archive, "manifest.json", &serde_json::to_vec(&docker_manifest)?)?;
+    let mut blobs: Vec<&Blob> = layers.iter().map(|l| &l.blob).collect();
+    blobs.push(&config);
+    blobs.push(&manifest);
+    for blob in blobs {
+        let mut header = file_header(blob.size, 0o644);
+        archive.append_data(&mut header, blob_name(blob), File::open(&blob.path)?)?;
+    }
+    archive.into_inner()?.sync_all()?;
+    std::fs::rename(&partial, output)?;
+
+    info!("Exported {} layers to {}", layers.len(), output.display());
+    Ok(manifest.digest)
+}
+
+/// Build a gzipped layer at `path` with `fill`, hashing both the tar
+/// stream (the diff ID) and the compressed blob
+fn write_layer(
+    path: &Path,
+    store_path: Option<String>,
+    fill: impl FnOnce(&mut tar::Builder<HashingWriter<GzEncoder<HashingWriter<File>>>>) -> Result<()>,
+) -> Result<Layer> {
+    let blob_writer = HashingWriter::new(File::create(path)?);
+    let gz = GzEncoder::new(blob_writer, flate2::Compression::default());
+    let mut tar = tar::Builder::new(HashingWriter::new(gz));
+    fill(&mut tar)?;
+
+    let (gz, diff_id) = tar.into_inner()?.finish();
+    let (file, digest) = gz.finish()?.finish();
+    let size = file.metadata()?.len();
+    file.sync_all()?;
+
+    Ok(Layer {
+        blob: Blob { digest, size, path: path.to_path_buf() },
+        diff_id,
+        store_path,
+    })
+}
+
+fn write_blob(staging: &Path, data: &[u8]) -> Result<Blob> {
+    let digest = format!("sha256:{}", hex::encode(Sha256::digest(data)));
+    let path = staging.join(digest.replace(':', "-"));
+    std::fs::write(&path, data)?;
+    Ok(Blob {
+        digest,
+        size: data.len() as u64,
+        path,
+    })
+}
+
+fn descriptor(media_type: &str, blob: &Blob) -> serde_json::Value {
+    json!({
+        "mediaType": media_type,
+        "digest": blob.digest,
+        "size": blob.size,
+    })
+}
+
+fn blob_name(blob: &Blob) -> String {
+    format!("blobs/{}", blob.digest.replace(':', "/"))
+}
+
+/// Add a store path and everything under it, at the same absolute path
+fn append_tree<W: Write>(tar: &mut tar::Builder<W>, root: &Path) -> Result<()> {
+    append_parents(tar, root)?;
+
+    for entry in walkdir::WalkDir::new(root).sort_by_file_name() {
+        let entry = entry?;
+        let name = entry.path().strip_prefix("/").unwrap_or(entry.path());
+        let meta = entry.path().symlink_metadata()?;
+
+        let mut header = Header::new_gnu();
+        header.set_metadata_in_mode(&meta, HeaderMode::Deterministic);
+        if meta.file_type().is_symlink() {
+            let target = std::fs::read_link(entry.path())?;
+            tar.append_link(&mut header, name, target)?;
+        } else if meta.is_dir() {
+            tar.append_data(&mut header, name, std::io::empty())?;
+        } else {
+            let file = File::open(entry.path())
+                .with_context(|| format!("Reading {}", entry.path().display()))?;
+            tar.append_data(&mut header, name, file)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Add `/nyx/profile`, linking each package file by its path inside the
+/// store path. Top-level files are package metadata and aren't linked;
+/// when two packages provide the same file the first wins, as in user
+/// profiles.
+fn append_forest<W: Write>(tar: &mut tar::Builder<W>, packages: &[InstalledPackage]) -> Result<()> {
+    // Relative path -> link target, None for directories
+    let mut entries: BTreeMap<PathBuf, Option<PathBuf>> = BTreeMap::new();
+    for pkg in packages {
+        let store_path = Path::new(&pkg.store_path);
+
+        for entry in walkdir::WalkDir::new(store_path).min_depth(1) {
+            let entry = entry?;
+            let rel_path = entry.path().strip_prefix(store_path)?.to_path_buf();
+
+            if entry.file_type().is_dir() {
+                entries.entry(rel_path).or_insert(None);
+            } else if entry.depth() > 1 {
+                if entries.contains_key(&rel_path) {
+                    warn!("{}: {} is already provided by another package", pkg.name, rel_path.display());
+                    continue;
+                }
+                entries.insert(rel_path, Some(entry.path().to_path_buf()));
+            }
+        }
+    }
+
+    append_parents(tar, &Path::new("/").join(PROFILE_PATH))?;
+    let mut header = dir_header();
+    tar.append_data(&mut header, PROFILE_PATH, std::io::empty())?;
+    for (rel_path, target) in entries {
+        let name = Path::new(PROFILE_PATH).join(&rel_path);
+        match target {
+            Some(target) => {
+                let mut header = link_header();
+                tar.append_link(&mut header, name, target)?;
+            }
+            None => {
+                let mut header = dir_header();
+                tar.append_data(&mut header, name, std::io::empty())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Add directory entries for the ancestors of `path`
+fn append_parents<W: Write>(tar: &mut tar::Builder<W>, path: &Path) -> Result<()> {
+    let rel = path.strip_prefix("/").unwrap_or(path);
+    let mut dirs: Vec<&Path> = rel.ancestors().skip(1).filter(|a| !a.as_os_str().is_empty()).collect();
+    dirs.reverse();
+    for dir in dirs {
+        let mut header = dir_header();
+        tar.append_data(&mut header, dir, std::io::empty())?;
+    }
+    Ok(())
+}
+
+fn append_bytes<W: Write>(tar: &mut tar::Builder<W>, name: &str, data: &[u8]) -> Result<()> {
+    let mut header = file_header(data.len() as u64, 0o644);
+    tar.append_data(&mut header, name, data)?;
+    Ok(())
+}
+
+fn file_header(size: u64, mode: u32) -> Header {
+    let mut header = Header::new_gnu();
+    header.set_entry_type(EntryType::Regular);
+    header.set_size(size);
+    header.set_mode(mode);
+    header.set_mtime(0);
+    header
+}
+
+fn dir_header() -> Header {
+    let mut header = file_header(0, 0o755);
+    header.set_entry_type(EntryType::Directory);
+    header
+}
+
+fn link_header() -> Header {
+    let mut header = file_header(0, 0o777);
+    header.set_entry_type(EntryType::Symlink);
+    header
+}
+
+/// OCI name of the architecture nexus was built for
+fn oci_architecture() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "riscv64" => "riscv64",
+        other => other,
+    }
+}
+
+/// Writer that hashes everything passing through it
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// The inner writer and the `sha256:` digest of what was written
+    fn finish(self) -> (W, String) {
+        (self.inner, format!("sha256:{}", hex::encode(self.hasher.finalize())))
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
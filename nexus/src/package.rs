@@ -7,6 +7,8 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::str::FromStr;
 
+use crate::provenance::{Provenance, PROVENANCE_FILE};
+
 /// Package specification (name with optional version constraint)
 #[derive(Debug, Clone)]
 pub struct PackageSpec {
@@ -162,8 +164,8 @@ impl BuiltPackage {
         format!("/nyx/store/{}-{}-{}", self.store_hash, self.name, self.version)
     }
 
-    /// Write package as archive
-    pub fn write_archive(&self, path: &str) -> Result<()> {
+    /// Write package as archive, packing its build provenance if given
+    pub fn write_archive(&self, path: &str, provenance: Option<&Provenance>) -> Result<()> {
         use flate2::write::GzEncoder;
         use flate2::Compression;
         use std::fs::File;
@@ -197,6 +199,15 @@ impl BuiltPackage {
         header.set_cksum();
         ar.append_data(&mut header, "META.toml", meta_toml.as_bytes())?;
 
+        if let Some(provenance) = provenance {
+            let json = provenance.to_json()?;
+            let mut header = tar::Header::new_gnu();
+            header.set_size(json.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            ar.append_data(&mut header, PROVENANCE_FILE, json.as_bytes())?;
+        }
+
         ar.finish()?;
         Ok(())
     }
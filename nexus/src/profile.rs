@@ -0,0 +1,246 @@
+//! Per-user package profiles
+//!
+//! A profile lives in `~/.nyx-profile` and layers packages over the system
+//! generation without root. Packages are still realised in the shared
+//! store (by nexusd on the user's behalf); the profile only owns its
+//! generations. Each generation is a directory holding `packages.json`
+//! and a symlink forest of the packages' files (`bin/`, `lib/`,
+//! `share/`, ...), and `generations/current` points at the active one, so
+//! putting `~/.nyx-profile/generations/current/bin` on PATH is enough to
+//! pick up whatever the user installed. Rolling back just moves the link.
+
+use anyhow::{Result, anyhow, Context};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::cache::PackageCache;
+use crate::ipc::NexusClient;
+use crate::package::{InstalledPackage, PackageSpec, RepoPackage};
+use crate::repository::RepositoryManager;
+use crate::resolver::DependencyResolver;
+use crate::store::{Generation, PackageStore};
+use crate::transaction::{extract_package, installed_record};
+
+/// Profile directory under the user's home
+const PROFILE_DIR: &str = ".nyx-profile";
+
+/// Generation records in a generation directory, as opposed to the forest
+const PACKAGES_FILE: &str = "packages.json";
+
+/// A user's package profile
+pub struct UserProfile {
+    root: PathBuf,
+    generations: PackageStore,
+}
+
+impl UserProfile {
+    /// Open the calling user's profile, creating it if needed
+    pub fn open() -> Result<Self> {
+        let home = std::env::var("HOME").map_err(|_| anyhow!("HOME is not set"))?;
+        Self::open_at(&Path::new(&home).join(PROFILE_DIR))
+    }
+
+    pub fn open_at(root: &Path) -> Result<Self> {
+        let generations = PackageStore::open(&root.to_string_lossy())?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            generations,
+        })
+    }
+
+    pub fn current_generation(&self) -> u32 {
+        self.generations.current_generation()
+    }
+
+    pub fn list_generations(&self) -> Result<Vec<Generation>> {
+        self.generations.list_generations()
+    }
+
+    pub fn list_installed(&self) -> Result<Vec<InstalledPackage>> {
+        self.generations.list_installed()
+    }
+
+    /// Install packages into a new generation. Dependencies the system
+    /// generation already provides are not installed again. Returns the
+    /// new generation, or `None` if there was nothing to do.
+    pub async fn install(
+        &self,
+        specs: &[PackageSpec],
+        repos: &RepositoryManager,
+        client: Option<&NexusClient>,
+    ) -> Result<Option<u32>> {
+        let system = PackageStore::open("/nyx/store")?;
+        let plan = DependencyResolver::new(&system, repos).resolve(specs).await?;
+
+        let current = self.list_installed()?;
+        let to_install: Vec<RepoPackage> = plan.to_install
+            .into_iter()
+            .filter(|pkg| !current.iter().any(|p| p.name == pkg.name && p.version == pkg.version))
+            .collect();
+
+        if to_install.is_empty() {
+            return Ok(None);
+        }
+
+        let store_paths = realise(&to_install, client).await?;
+
+        let requested: HashSet<&str> = specs.iter().map(|s| s.name.as_str()).collect();
+        let mut packages: Vec<InstalledPackage> = current
+            .into_iter()
+            .filter(|p| !to_install.iter().any(|pkg| pkg.name == p.name))
+            .collect();
+        for (pkg, store_path) in to_install.iter().zip(&store_paths) {
+            info!("Adding {} {} to profile", pkg.name, pkg.version);
+            packages.push(installed_record(pkg, Path::new(store_path), requested.contains(pkg.name.as_str()))?);
+        }
+
+        self.commit(&packages).map(Some)
+    }
+
+    /// Remove packages into a new generation
+    pub fn remove(&self, names: &[String]) -> Result<u32> {
+        let current = self.list_installed()?;
+
+        for name in names {
+            if !current.iter().any(|p| &p.name == name) {
+                return Err(anyhow!("Package not in profile: {}", name));
+            }
+        }
+
+        let packages: Vec<InstalledPackage> = current
+            .into_iter()
+            .filter(|p| !names.contains(&p.name))
+            .collect();
+
+        self.commit(&packages)
+    }
+
+    /// Switch to another generation, the previous one by default
+    pub fn rollback(&self, generation: Option<u32>) -> Result<u32> {
+        let gen = generation.unwrap_or_else(|| self.current_generation().saturating_sub(1));
+        self.generations.activate_generation(gen)?;
+        Ok(gen)
+    }
+
+    /// Shell commands adding the profile to the environment
+    pub fn env(&self) -> String {
+        let current = self.root.join("generations").join("current");
+
+        let mut env = String::new();
+        for (name, dir) in [
+            ("PATH", "bin"),
+            ("LD_LIBRARY_PATH", "lib"),
+            ("MANPATH", "share/man"),
+            ("XDG_DATA_DIRS", "share"),
+        ] {
+            env.push_str(&format!(
+                "export {name}=\"{}${{{name}:+:${name}}}\"\n",
+                current.join(dir).display()
+            ));
+        }
+        env
+    }
+
+    /// Write a generation holding `packages` and make it current
+    fn commit(&self, packages: &[InstalledPackage]) -> Result<u32> {
+        // After a rollback the current generation isn't the newest, so
+        // number past every existing one rather than past current
+        let gen = self.list_generations()?
+            .iter()
+            .map(|g| g.number)
+            .max()
+            .unwrap_or(0) + 1;
+
+        let gen_path = self.generations.generation_path(gen);
+        std::fs::create_dir_all(&gen_path)?;
+        for pkg in packages {
+            self.generations.record_install(&gen_path, pkg)?;
+        }
+        if packages.is_empty() {
+            std::fs::write(gen_path.join(PACKAGES_FILE), "[]")?;
+        }
+
+        build_forest(&gen_path, packages)?;
+        self.generations.activate_generation(gen)?;
+
+        info!("Profile generation {} active ({} packages)", gen, packages.len());
+        Ok(gen)
+    }
+}
+
+/// Get packages into the store: through nexusd if it is running,
+/// otherwise directly, which only works with write access to the store
+async fn realise(packages: &[RepoPackage], client: Option<&NexusClient>) -> Result<Vec<String>> {
+    if let Some(client) = client {
+        return client.realise(packages).await;
+    }
+
+    let cache = PackageCache::open("/var/cache/nexus")
+        .context("nexusd is not running and the package cache is not writable")?;
+    let mut store_paths = Vec::new();
+    for pkg in packages {
+        let archive = cache.get_or_download(pkg).await?;
+        let store_path = extract_package(&archive, pkg)
+            .context("nexusd is not running and the store is not writable")?;
+        store_paths.push(store_path.to_string_lossy().to_string());
+    }
+
+    Ok(store_paths)
+}
+
+/// Link every file of `packages` into `gen_path`, mirroring each store
+/// path's directory layout. Top-level files in a store path are package
+/// metadata and aren't linked. When two packages provide the same file,
+/// the one installed first wins.
+fn build_forest(gen_path: &Path, packages: &[InstalledPackage]) -> Result<()> {
+    for pkg in packages {
+        let store_path = Path::new(&pkg.store_path);
+
+        for entry in walkdir::WalkDir::new(store_path).min_depth(1) {
+            let entry = entry?;
+            let rel_path = entry.path().strip_prefix(store_path)?;
+            let dest = gen_path.join(rel_path);
+
+            if entry.file_type().is_dir() {
+                std::fs::create_dir_all(&dest)?;
+            } else if entry.depth() > 1 {
+                if dest.symlink_metadata().is_ok() {
+                    warn!("{}: {} is already provided by another package", pkg.name, rel_path.display());
+                    continue;
+                }
+                std::os::unix::fs::symlink(entry.path(), &dest)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Store paths referenced by any generation of any user's profile, so
+/// garbage collection keeps them
+pub fn all_profile_store_paths() -> Vec<String> {
+    let homes = std::fs::read_dir("/home")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .chain(std::iter::once(PathBuf::from("/root")));
+
+    let mut paths = Vec::new();
+    for home in homes {
+        let Ok(generations) = std::fs::read_dir(home.join(PROFILE_DIR).join("generations")) else {
+            continue;
+        };
+        for generation in generations.flatten() {
+            let Ok(content) = std::fs::read_to_string(generation.path().join(PACKAGES_FILE)) else {
+                continue;
+            };
+            if let Ok(packages) = serde_json::from_str::<Vec<InstalledPackage>>(&content) {
+                paths.extend(packages.into_iter().map(|p| p.store_path));
+            }
+        }
+    }
+
+    paths
+}
@@ -0,0 +1,279 @@
+//! Build provenance
+//!
+//! Records everything a source build depended on (the definition, fetched
+//! sources and patches, the tools that ran and the environment they ran
+//! in) together with the outputs it produced. The record is written next
+//! to the package archive and packed inside it, so it lands in the store
+//! with the package and `nexus verify --provenance` can check installed
+//! files against it.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::package::{BuiltPackage, hash_file};
+
+/// File name of the provenance record inside a package
+pub const PROVENANCE_FILE: &str = "PROVENANCE.json";
+
+/// Provenance format version
+const FORMAT_VERSION: u32 = 1;
+
+/// Tools whose identity is recorded for every build, on top of the
+/// programs named by the definition's commands
+const BASE_TOOLCHAIN: &[&str] = &["sh", "cc", "c++", "ld", "ar", "make"];
+
+/// Full record of a build's inputs and outputs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Provenance {
+    pub format: u32,
+    pub package: String,
+    pub version: semver::Version,
+    pub built_at: chrono::DateTime<chrono::Utc>,
+    /// SHA-256 of the package definition file
+    pub definition_sha256: String,
+    pub sources: Vec<SourceInput>,
+    pub toolchain: Vec<ToolInput>,
+    /// Environment the build commands ran with
+    pub environment: BTreeMap<String, String>,
+    pub outputs: Vec<OutputFile>,
+    pub store_hash: String,
+    /// Set when the build was repeated with `--check-reproducible`
+    #[serde(default)]
+    pub reproducible: Option<bool>,
+}
+
+/// A fetched source or patch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceInput {
+    pub kind: SourceKind,
+    /// URL, repository or patch path
+    pub location: String,
+    /// Content hash, or commit ID for git sources
+    pub digest: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    Archive,
+    Git,
+    Patch,
+}
+
+/// A program the build may have run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolInput {
+    pub name: String,
+    pub path: String,
+    pub sha256: String,
+    /// First line of `--version`, when the tool reports one
+    pub version: Option<String>,
+}
+
+/// A file the build produced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputFile {
+    pub path: String,
+    pub sha256: String,
+    pub size: u64,
+    pub mode: u32,
+}
+
+/// How an output differs between two builds
+#[derive(Debug, Clone)]
+pub enum OutputDiff {
+    /// Only the first build produced the file
+    Missing(String),
+    /// Only the second build produced the file
+    Extra(String),
+    /// Content or mode differ
+    Changed(String),
+}
+
+impl std::fmt::Display for OutputDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputDiff::Missing(path) => write!(f, "{}: missing from rebuild", path),
+            OutputDiff::Extra(path) => write!(f, "{}: only in rebuild", path),
+            OutputDiff::Changed(path) => write!(f, "{}: differs", path),
+        }
+    }
+}
+
+impl Provenance {
+    pub fn new(
+        package: &BuiltPackage,
+        definition_sha256: String,
+        sources: Vec<SourceInput>,
+        toolchain: Vec<ToolInput>,
+        environment: BTreeMap<String, String>,
+    ) -> Self {
+        let mut outputs: Vec<OutputFile> = package
+            .files
+            .iter()
+            .map(|f| OutputFile {
+                path: f.path.clone(),
+                sha256: f.hash.clone(),
+                size: f.size,
+                mode: f.mode,
+            })
+            .collect();
+        outputs.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Self {
+            format: FORMAT_VERSION,
+            package: package.name.clone(),
+            version: package.version.clone(),
+            built_at: chrono::Utc::now(),
+            definition_sha256,
+            sources,
+            toolchain,
+            environment,
+            outputs,
+            store_hash: package.store_hash.clone(),
+            reproducible: None,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Compare this build's outputs with another build of the same package
+    pub fn compare_outputs(&self, other: &Provenance) -> Vec<OutputDiff> {
+        let theirs: BTreeMap<&str, &OutputFile> =
+            other.outputs.iter().map(|o| (o.path.as_str(), o)).collect();
+        let mut diffs = Vec::new();
+
+        for output in &self.outputs {
+            match theirs.get(output.path.as_str()) {
+                None => diffs.push(OutputDiff::Missing(output.path.clone())),
+                Some(other) if *other != output => diffs.push(OutputDiff::Changed(output.path.clone())),
+                Some(_) => {}
+            }
+        }
+        for output in &other.outputs {
+            if !self.outputs.iter().any(|o| o.path == output.path) {
+                diffs.push(OutputDiff::Extra(output.path.clone()));
+            }
+        }
+
+        diffs
+    }
+
+    /// Check the files under an installed package's store path against
+    /// the recorded outputs. Returns the paths that are missing or changed.
+    pub fn verify_installed(&self, store_path: &Path) -> Result<Vec<String>> {
+        let mut mismatched = Vec::new();
+
+        for output in &self.outputs {
+            let path = store_path.join(&output.path);
+            if !path.is_file() || hash_file(&path)? != output.sha256 {
+                mismatched.push(output.path.clone());
+            }
+        }
+
+        Ok(mismatched)
+    }
+}
+
+/// Where the provenance for an archive is written
+pub fn provenance_path(archive: &Path) -> PathBuf {
+    let mut name = archive.as_os_str().to_owned();
+    name.push(".provenance.json");
+    PathBuf::from(name)
+}
+
+/// Record the tools a build can reach: the base toolchain plus the first
+/// word of every command in the definition
+pub async fn record_toolchain(commands: &[&str], path_var: &str) -> Vec<ToolInput> {
+    let mut names: Vec<&str> = BASE_TOOLCHAIN.to_vec();
+    for command in commands {
+        if let Some(program) = command.split_whitespace().find(|word| !word.contains('=')) {
+            if !names.contains(&program) {
+                names.push(program);
+            }
+        }
+    }
+
+    let mut tools = Vec::new();
+    for name in names {
+        let Some(path) = find_in_path(name, path_var) else {
+            continue;
+        };
+        let Ok(sha256) = hash_file(&path) else {
+            continue;
+        };
+        tools.push(ToolInput {
+            name: name.to_string(),
+            path: path.to_string_lossy().to_string(),
+            sha256,
+            version: tool_version(&path).await,
+        });
+    }
+
+    tools
+}
+
+/// Commit checked out in a git source
+pub async fn git_commit(repo: &Path) -> Result<String> {
+    let output = tokio::process::Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(repo)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(anyhow!("Cannot read commit of {:?}", repo));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn find_in_path(name: &str, path_var: &str) -> Option<PathBuf> {
+    if name.contains('/') {
+        let path = PathBuf::from(name);
+        return path.is_file().then_some(path);
+    }
+
+    std::env::split_paths(path_var)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+        .and_then(|path| path.canonicalize().ok())
+}
+
+async fn tool_version(path: &Path) -> Option<String> {
+    let output = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        tokio::process::Command::new(path)
+            .arg("--version")
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+}
@@ -1,11 +1,20 @@
 //! Sandboxed package building
 
 use anyhow::{Result, anyhow};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use tracing::{info, debug, warn};
 
 use crate::package::{PackageDefinition, BuiltPackage, PackageFile, hash_file, hash_data};
+use crate::provenance::{self, Provenance, SourceInput, SourceKind};
+
+/// Timestamp build tools should embed instead of the current time
+/// (1980-01-01, the earliest date zip archives can hold)
+const SOURCE_DATE_EPOCH: &str = "315532800";
+
+/// PATH used when the host has none
+const DEFAULT_PATH: &str = "/nyx/profile/bin:/usr/bin:/bin";
 
 /// Build sandbox
 pub struct BuildSandbox {
@@ -23,31 +32,42 @@ impl BuildSandbox {
         Ok(Self { work_dir, root_dir })
     }
 
-    /// Build a package from definition
-    pub async fn build(&self, path: &str) -> Result<BuiltPackage> {
+    /// Build a package from definition, recording its provenance
+    pub async fn build(&self, path: &str) -> Result<(BuiltPackage, Provenance)> {
+        let definition_sha256 = hash_file(Path::new(path))?;
         let def = PackageDefinition::from_file(Path::new(path))?;
 
         info!("Building {} {}", def.package.name, def.package.version);
 
         // Fetch source
-        let source_dir = self.fetch_source(&def).await?;
+        let mut sources = Vec::new();
+        let source_dir = self.fetch_source(&def, &mut sources).await?;
 
         // Set up build environment
-        self.setup_environment(&def)?;
+        let env = self.setup_environment(&def)?;
+        let commands: Vec<&str> = def.build.configure.iter()
+            .chain(&def.build.build)
+            .chain(&def.build.check)
+            .chain(&def.install.commands)
+            .map(String::as_str)
+            .collect();
+        let toolchain = provenance::record_toolchain(&commands, &env["PATH"]).await;
 
         // Run build
-        self.run_build(&def, &source_dir).await?;
+        self.run_build(&def, &source_dir, &env).await?;
 
         // Install to destdir
         let dest_dir = self.work_dir.join("dest");
         std::fs::create_dir_all(&dest_dir)?;
-        self.run_install(&def, &source_dir, &dest_dir).await?;
+        self.run_install(&def, &source_dir, &dest_dir, &env).await?;
 
         // Package result
-        self.create_package(&def, &dest_dir)
+        let pkg = self.create_package(&def, &dest_dir)?;
+        let provenance = Provenance::new(&pkg, definition_sha256, sources, toolchain, env);
+        Ok((pkg, provenance))
     }
 
-    async fn fetch_source(&self, def: &PackageDefinition) -> Result<PathBuf> {
+    async fn fetch_source(&self, def: &PackageDefinition, sources: &mut Vec<SourceInput>) -> Result<PathBuf> {
         let source_dir = self.work_dir.join("src");
         std::fs::create_dir_all(&source_dir)?;
 
@@ -66,6 +86,12 @@ impl BuildSandbox {
                 }
             }
 
+            sources.push(SourceInput {
+                kind: SourceKind::Archive,
+                location: url.clone(),
+                digest: hash_data(&bytes),
+            });
+
             // Extract based on extension
             let archive_path = source_dir.join("source.tar.gz");
             std::fs::write(&archive_path, &bytes)?;
@@ -85,12 +111,23 @@ impl BuildSandbox {
                 return Err(anyhow!("Git clone failed"));
             }
 
-            return Ok(source_dir.join("repo"));
+            let repo = source_dir.join("repo");
+            sources.push(SourceInput {
+                kind: SourceKind::Git,
+                location: git_url.clone(),
+                digest: provenance::git_commit(&repo).await?,
+            });
+            return Ok(repo);
         }
 
         // Apply patches
         for patch in &def.source.patches {
             let patch_path = PathBuf::from(patch);
+            sources.push(SourceInput {
+                kind: SourceKind::Patch,
+                location: patch.clone(),
+                digest: hash_file(&patch_path)?,
+            });
             self.apply_patch(&source_dir, &patch_path).await?;
         }
 
@@ -133,33 +170,51 @@ impl BuildSandbox {
         Ok(())
     }
 
-    fn setup_environment(&self, def: &PackageDefinition) -> Result<()> {
+    /// Set up the sandbox filesystem and return the environment build
+    /// commands run with. Nothing else is inherited from the caller, so
+    /// the environment recorded in the provenance is the whole of it.
+    fn setup_environment(&self, def: &PackageDefinition) -> Result<BTreeMap<String, String>> {
         // Set up minimal filesystem in sandbox
         let dirs = ["bin", "lib", "include", "share"];
         for dir in dirs {
             std::fs::create_dir_all(self.root_dir.join(dir))?;
         }
 
-        Ok(())
+        let home = self.work_dir.join("home");
+        let tmp = self.work_dir.join("tmp");
+        std::fs::create_dir_all(&home)?;
+        std::fs::create_dir_all(&tmp)?;
+
+        let mut env = BTreeMap::new();
+        env.insert("PATH".to_string(), std::env::var("PATH").unwrap_or_else(|_| DEFAULT_PATH.to_string()));
+        env.insert("HOME".to_string(), home.to_string_lossy().to_string());
+        env.insert("TMPDIR".to_string(), tmp.to_string_lossy().to_string());
+        env.insert("LC_ALL".to_string(), "C".to_string());
+        env.insert("TZ".to_string(), "UTC".to_string());
+        env.insert("SOURCE_DATE_EPOCH".to_string(), SOURCE_DATE_EPOCH.to_string());
+        env.insert("NYX_PACKAGE".to_string(), def.package.name.clone());
+        env.insert("NYX_VERSION".to_string(), def.package.version.to_string());
+
+        Ok(env)
     }
 
-    async fn run_build(&self, def: &PackageDefinition, source_dir: &Path) -> Result<()> {
+    async fn run_build(&self, def: &PackageDefinition, source_dir: &Path, env: &BTreeMap<String, String>) -> Result<()> {
         let build_dir = self.work_dir.join("build");
         std::fs::create_dir_all(&build_dir)?;
 
         // Configure
         for cmd in &def.build.configure {
-            self.run_command(cmd, source_dir).await?;
+            self.run_command(cmd, source_dir, env).await?;
         }
 
         // Build
         for cmd in &def.build.build {
-            self.run_command(cmd, source_dir).await?;
+            self.run_command(cmd, source_dir, env).await?;
         }
 
         // Check (optional)
         for cmd in &def.build.check {
-            if let Err(e) = self.run_command(cmd, source_dir).await {
+            if let Err(e) = self.run_command(cmd, source_dir, env).await {
                 warn!("Check failed: {}", e);
             }
         }
@@ -172,12 +227,14 @@ impl BuildSandbox {
         def: &PackageDefinition,
         source_dir: &Path,
         dest_dir: &Path,
+        env: &BTreeMap<String, String>,
     ) -> Result<()> {
         // Set DESTDIR for install
-        std::env::set_var("DESTDIR", dest_dir);
+        let mut env = env.clone();
+        env.insert("DESTDIR".to_string(), dest_dir.to_string_lossy().to_string());
 
         for cmd in &def.install.commands {
-            self.run_command(cmd, source_dir).await?;
+            self.run_command(cmd, source_dir, &env).await?;
         }
 
         // Copy explicit files
@@ -195,12 +252,14 @@ impl BuildSandbox {
         Ok(())
     }
 
-    async fn run_command(&self, cmd: &str, cwd: &Path) -> Result<()> {
+    async fn run_command(&self, cmd: &str, cwd: &Path, env: &BTreeMap<String, String>) -> Result<()> {
         debug!("Running: {}", cmd);
 
         let output = tokio::process::Command::new("sh")
             .args(["-c", cmd])
             .current_dir(cwd)
+            .env_clear()
+            .envs(env)
             .stdout(Stdio::inherit())
             .stderr(Stdio::inherit())
             .output()
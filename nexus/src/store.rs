@@ -7,6 +7,7 @@ use std::path::{Path, PathBuf};
 use tracing::{info, debug};
 
 use crate::package::{InstalledPackage, RepoPackage, hash_file};
+use crate::provenance::{Provenance, PROVENANCE_FILE};
 use crate::repository::RepositoryManager;
 
 /// Package store with generations
@@ -121,6 +122,15 @@ impl PackageStore {
         Ok(serde_json::from_str(&content)?)
     }
 
+    /// Get the packages of a generation
+    pub fn generation_packages(&self, gen: u32) -> Result<Vec<InstalledPackage>> {
+        let gen_path = self.generation_path(gen);
+        if !gen_path.exists() {
+            return Err(anyhow!("Generation {} does not exist", gen));
+        }
+        self.load_generation_packages(&gen_path)
+    }
+
     /// Get installed packages in current generation
     pub fn list_installed(&self) -> Result<Vec<InstalledPackage>> {
         let gen = self.current_generation();
@@ -217,6 +227,27 @@ impl PackageStore {
         Ok(true)
     }
 
+    /// Check an installed package against the provenance recorded when it
+    /// was built. Returns `None` if the package carries no provenance,
+    /// otherwise the recorded outputs that are missing or modified.
+    pub fn verify_provenance(&self, pkg: &InstalledPackage) -> Result<Option<Vec<String>>> {
+        let store_path = PathBuf::from(&pkg.store_path);
+        let record = store_path.join(PROVENANCE_FILE);
+        if !record.exists() {
+            return Ok(None);
+        }
+
+        let provenance = Provenance::load(&record)?;
+        if provenance.package != pkg.name || provenance.version != pkg.version {
+            return Err(anyhow!(
+                "Provenance is for {} {}, not {} {}",
+                provenance.package, provenance.version, pkg.name, pkg.version
+            ));
+        }
+
+        Ok(Some(provenance.verify_installed(&store_path)?))
+    }
+
     /// Find available upgrades
     pub async fn find_upgrades(
         &self,
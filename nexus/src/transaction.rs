@@ -120,80 +120,18 @@ impl<'a> Transaction<'a> {
         let archive_path = cache.get_or_download(pkg).await?;
 
         // Extract to store
-        let store_path = self.extract_package(&archive_path, pkg)?;
+        let store_path = extract_package(&archive_path, pkg)?;
 
         // Create symlinks in system
         self.link_package(&store_path)?;
 
         // Record installation
-        let installed = InstalledPackage {
-            name: pkg.name.clone(),
-            version: pkg.version.clone(),
-            description: pkg.description.clone(),
-            license: pkg.license.clone(),
-            dependencies: pkg.dependencies.clone(),
-            store_path: store_path.to_string_lossy().to_string(),
-            installed_size: pkg.installed_size,
-            install_time: chrono::Utc::now(),
-            files: self.list_package_files(&store_path)?,
-            file_hashes: self.hash_package_files(&store_path)?,
-            explicit: true,
-        };
-
+        let installed = installed_record(pkg, &store_path, true)?;
         self.store.record_install(gen_path, &installed)?;
 
         Ok(())
     }
 
-    fn extract_package(&self, archive_path: &Path, pkg: &RepoPackage) -> Result<PathBuf> {
-        use flate2::read::GzDecoder;
-        use tar::Archive;
-
-        let store_path = PathBuf::from(format!(
-            "/nyx/store/{}-{}-{}",
-            &pkg.sha256[..12],
-            pkg.name,
-            pkg.version
-        ));
-
-        if store_path.exists() {
-            debug!("Package already in store: {:?}", store_path);
-            return Ok(store_path);
-        }
-
-        std::fs::create_dir_all(&store_path)?;
-
-        let file = std::fs::File::open(archive_path)?;
-        let decoder = GzDecoder::new(file);
-        let mut archive = Archive::new(decoder);
-
-        archive.unpack(&store_path)?;
-
-        // Make store path read-only
-        self.make_readonly(&store_path)?;
-
-        Ok(store_path)
-    }
-
-    fn make_readonly(&self, path: &Path) -> Result<()> {
-        use std::os::unix::fs::PermissionsExt;
-
-        for entry in walkdir::WalkDir::new(path) {
-            let entry = entry?;
-            let metadata = entry.metadata()?;
-            let mut perms = metadata.permissions();
-
-            if metadata.is_file() {
-                // Remove write permission
-                let mode = perms.mode() & !0o222;
-                perms.set_mode(mode);
-                std::fs::set_permissions(entry.path(), perms)?;
-            }
-        }
-
-        Ok(())
-    }
-
     fn link_package(&self, store_path: &Path) -> Result<()> {
         // Link binaries
         let bin_dir = store_path.join("bin");
@@ -283,41 +221,109 @@ impl<'a> Transaction<'a> {
 
         Ok(())
     }
+}
 
-    fn list_package_files(&self, store_path: &Path) -> Result<Vec<String>> {
-        let mut files = Vec::new();
-
-        for entry in walkdir::WalkDir::new(store_path) {
-            let entry = entry?;
-            if entry.file_type().is_file() {
-                let rel_path = entry.path()
-                    .strip_prefix(store_path)?
-                    .to_string_lossy()
-                    .to_string();
-                files.push(rel_path);
-            }
-        }
+/// Unpack a package archive into its read-only store path, unless it is
+/// already there
+pub fn extract_package(archive_path: &Path, pkg: &RepoPackage) -> Result<PathBuf> {
+    use flate2::read::GzDecoder;
+    use tar::Archive;
+
+    let store_path = PathBuf::from(format!(
+        "/nyx/store/{}-{}-{}",
+        &pkg.sha256[..12],
+        pkg.name,
+        pkg.version
+    ));
+
+    if store_path.exists() {
+        debug!("Package already in store: {:?}", store_path);
+        return Ok(store_path);
+    }
+
+    std::fs::create_dir_all(&store_path)?;
+
+    let file = std::fs::File::open(archive_path)?;
+    let decoder = GzDecoder::new(file);
+    let mut archive = Archive::new(decoder);
 
-        Ok(files)
+    archive.unpack(&store_path)?;
+
+    // Make store path read-only
+    make_readonly(&store_path)?;
+
+    Ok(store_path)
+}
+
+fn make_readonly(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    for entry in walkdir::WalkDir::new(path) {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let mut perms = metadata.permissions();
+
+        if metadata.is_file() {
+            // Remove write permission
+            let mode = perms.mode() & !0o222;
+            perms.set_mode(mode);
+            std::fs::set_permissions(entry.path(), perms)?;
+        }
     }
 
-    fn hash_package_files(&self, store_path: &Path) -> Result<std::collections::HashMap<String, String>> {
-        let mut hashes = std::collections::HashMap::new();
-
-        for entry in walkdir::WalkDir::new(store_path) {
-            let entry = entry?;
-            if entry.file_type().is_file() {
-                let rel_path = entry.path()
-                    .strip_prefix(store_path)?
-                    .to_string_lossy()
-                    .to_string();
-                let hash = hash_file(entry.path())?;
-                hashes.insert(rel_path, hash);
-            }
+    Ok(())
+}
+
+/// Installation record for a package realised at `store_path`
+pub fn installed_record(pkg: &RepoPackage, store_path: &Path, explicit: bool) -> Result<InstalledPackage> {
+    Ok(InstalledPackage {
+        name: pkg.name.clone(),
+        version: pkg.version.clone(),
+        description: pkg.description.clone(),
+        license: pkg.license.clone(),
+        dependencies: pkg.dependencies.clone(),
+        store_path: store_path.to_string_lossy().to_string(),
+        installed_size: pkg.installed_size,
+        install_time: chrono::Utc::now(),
+        files: list_package_files(store_path)?,
+        file_hashes: hash_package_files(store_path)?,
+        explicit,
+    })
+}
+
+fn list_package_files(store_path: &Path) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+
+    for entry in walkdir::WalkDir::new(store_path) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let rel_path = entry.path()
+                .strip_prefix(store_path)?
+                .to_string_lossy()
+                .to_string();
+            files.push(rel_path);
         }
+    }
+
+    Ok(files)
+}
+
+fn hash_package_files(store_path: &Path) -> Result<std::collections::HashMap<String, String>> {
+    let mut hashes = std::collections::HashMap::new();
 
-        Ok(hashes)
+    for entry in walkdir::WalkDir::new(store_path) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let rel_path = entry.path()
+                .strip_prefix(store_path)?
+                .to_string_lossy()
+                .to_string();
+            let hash = hash_file(entry.path())?;
+            hashes.insert(rel_path, hash);
+        }
     }
+
+    Ok(hashes)
 }
 
 /// Garbage collect unreferenced store paths
@@ -325,9 +331,10 @@ pub fn garbage_collect(store: &PackageStore) -> Result<u64> {
     let store_path = PathBuf::from("/nyx/store");
     let mut freed = 0u64;
 
-    // Get all referenced paths
+    // Get all referenced paths, including those only user profiles use
     let referenced: HashSet<String> = store.all_store_paths()?
         .into_iter()
+        .chain(crate::profile::all_profile_store_paths())
         .collect();
 
     // Find unreferenced
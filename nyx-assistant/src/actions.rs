@@ -0,0 +1,471 @@
+//! System actions: power, service control and settings deep links
+//!
+//! Phrases like "restart vesper", "enable night light" or "suspend" are
+//! parsed into a [`SystemAction`] and carried out over the owning daemon's
+//! socket (nyx-serviced, iris or slumber). Privileged actions are checked
+//! with Guardian first, and disruptive ones ask for confirmation.
+
+use crate::commands::CommandResult;
+use libnyx_ipc::protocol::Decision;
+use libnyx_ipc::GuardianClient;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+
+/// nyx-serviced control socket
+pub const SERVICED_SOCKET: &str = "/run/nyx/serviced.sock";
+/// iris (display) socket
+pub const IRIS_SOCKET: &str = "/run/iris/iris.sock";
+/// slumber (power) socket
+pub const SLUMBER_SOCKET: &str = "/run/slumber/slumber.sock";
+
+/// Prefix of command ids produced by this provider
+const ID_PREFIX: &str = "action:";
+
+/// Score that ranks a recognised phrase just below a calculator result
+const ACTION_SCORE: i64 = 900;
+
+/// Settings pages that can be linked to
+const SETTINGS_PAGES: &[&str] = &[
+    "network",
+    "bluetooth",
+    "display",
+    "sound",
+    "appearance",
+    "notifications",
+    "power",
+    "about",
+];
+
+/// Power profiles slumber ships with
+const POWER_PROFILES: &[&str] = &["performance", "balanced", "powersave"];
+
+/// Operation on a service managed by nyx-serviced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceVerb {
+    Start,
+    Stop,
+    Restart,
+    Reload,
+    Enable,
+    Disable,
+}
+
+impl ServiceVerb {
+    const ALL: [Self; 6] = [
+        Self::Start,
+        Self::Stop,
+        Self::Restart,
+        Self::Reload,
+        Self::Enable,
+        Self::Disable,
+    ];
+
+    fn word(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Stop => "stop",
+            Self::Restart => "restart",
+            Self::Reload => "reload",
+            Self::Enable => "enable",
+            Self::Disable => "disable",
+        }
+    }
+
+    /// nyx-serviced request type
+    fn request(self) -> &'static str {
+        match self {
+            Self::Start => "Start",
+            Self::Stop => "Stop",
+            Self::Restart => "Restart",
+            Self::Reload => "Reload",
+            Self::Enable => "Enable",
+            Self::Disable => "Disable",
+        }
+    }
+}
+
+/// An action the assistant can carry out on the system
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SystemAction {
+    /// Control a service through nyx-serviced
+    Service { verb: ServiceVerb, name: String },
+    /// Turn night light on or off through iris
+    NightLight(bool),
+    /// Set backlight brightness (percent) through iris
+    Brightness(u8),
+    /// Switch slumber's power profile
+    PowerProfile(String),
+    /// Suspend to RAM through slumber
+    Suspend,
+    /// Hibernate to disk through slumber
+    Hibernate,
+    /// Open nyx-settings at a page, e.g. "display/night-light"
+    OpenSettings(String),
+}
+
+/// Guardian's verdict on an action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Authorization {
+    /// Go ahead (still confirm if the action asks for it)
+    Allowed,
+    /// Allowed once the user confirms; carries Guardian's reason
+    Confirm(String),
+    /// Refused; carries the reason
+    Denied(String),
+}
+
+impl SystemAction {
+    /// Recognise an action phrase
+    pub fn parse(query: &str) -> Option<Self> {
+        let text = query.trim().to_lowercase();
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let text = words.join(" ");
+
+        match words.as_slice() {
+            ["suspend"] | ["sleep"] | ["go", "to", "sleep"] => return Some(Self::Suspend),
+            ["hibernate"] => return Some(Self::Hibernate),
+            _ => {}
+        }
+
+        if text.contains("night light") || text.contains("nightlight") {
+            let on = ["enable ", "turn on ", "switch on "].iter().any(|p| text.starts_with(p))
+                || text.ends_with(" on");
+            let off = ["disable ", "turn off ", "switch off "].iter().any(|p| text.starts_with(p))
+                || text.ends_with(" off");
+            return match (on, off) {
+                (true, false) => Some(Self::NightLight(true)),
+                (false, true) => Some(Self::NightLight(false)),
+                _ => Some(Self::OpenSettings("display/night-light".to_string())),
+            };
+        }
+
+        if words.contains(&"brightness") {
+            let percent = words
+                .iter()
+                .find_map(|w| w.trim_end_matches('%').parse::<u8>().ok());
+            if let Some(percent) = percent {
+                return Some(Self::Brightness(percent.min(100)));
+            }
+        }
+
+        if let Some(profile) = parse_profile(&words) {
+            return Some(Self::PowerProfile(profile.to_string()));
+        }
+
+        if let Some(page) = parse_settings(&words) {
+            return Some(Self::OpenSettings(page));
+        }
+
+        if let [verb, name] = words.as_slice() {
+            let verb = ServiceVerb::ALL.into_iter().find(|v| v.word() == *verb)?;
+            if is_service_name(name) {
+                return Some(Self::Service {
+                    verb,
+                    name: name.to_string(),
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Action behind a command id, including the built-in system commands
+    pub fn from_id(id: &str) -> Option<Self> {
+        if let Some(phrase) = id.strip_prefix(ID_PREFIX) {
+            return Self::parse(phrase);
+        }
+        let page = match id {
+            "sleep" => return Some(Self::Suspend),
+            "settings" => "",
+            "wifi" => "network/wifi",
+            "bluetooth" | "display" | "sound" => id,
+            _ => return None,
+        };
+        Some(Self::OpenSettings(page.to_string()))
+    }
+
+    /// Phrase that parses back to this action
+    pub fn phrase(&self) -> String {
+        match self {
+            Self::Service { verb, name } => format!("{} {}", verb.word(), name),
+            Self::NightLight(true) => "enable night light".to_string(),
+            Self::NightLight(false) => "disable night light".to_string(),
+            Self::Brightness(percent) => format!("brightness {}", percent),
+            Self::PowerProfile(name) => format!("{} mode", name),
+            Self::Suspend => "suspend".to_string(),
+            Self::Hibernate => "hibernate".to_string(),
+            Self::OpenSettings(page) if page.is_empty() => "open settings".to_string(),
+            Self::OpenSettings(page) => format!("settings {}", page),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            Self::Service { verb, name } => {
+                let verb = verb.word();
+                format!("{}{} {}", verb[..1].to_uppercase(), &verb[1..], name)
+            }
+            Self::NightLight(true) => "Turn On Night Light".to_string(),
+            Self::NightLight(false) => "Turn Off Night Light".to_string(),
+            Self::Brightness(percent) => format!("Set Brightness to {}%", percent),
+            Self::PowerProfile(name) => format!("Switch to {} Profile", name),
+            Self::Suspend => "Suspend".to_string(),
+            Self::Hibernate => "Hibernate".to_string(),
+            Self::OpenSettings(page) if page.is_empty() => "Open Settings".to_string(),
+            Self::OpenSettings(page) => format!("Open {} Settings", page.replace(['/', '-'], " ")),
+        }
+    }
+
+    pub fn subtitle(&self) -> &'static str {
+        match self {
+            Self::Service { .. } => "Service control",
+            Self::NightLight(_) | Self::Brightness(_) => "Display",
+            Self::PowerProfile(_) | Self::Suspend | Self::Hibernate => "Power",
+            Self::OpenSettings(_) => "Settings",
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Self::Service { .. } => "󰒋",
+            Self::NightLight(_) => "󰖔",
+            Self::Brightness(_) => "󰃠",
+            Self::PowerProfile(_) => "󰓅",
+            Self::Suspend => "󰤄",
+            Self::Hibernate => "󰋊",
+            Self::OpenSettings(_) => "󰒓",
+        }
+    }
+
+    /// Palette entry for this action
+    pub fn to_command(&self) -> CommandResult {
+        CommandResult::system(
+            format!("{}{}", ID_PREFIX, self.phrase()),
+            self.title(),
+            self.subtitle(),
+            self.icon(),
+        )
+        .with_score(ACTION_SCORE)
+    }
+
+    /// Whether the user must confirm before it runs
+    pub fn needs_confirmation(&self) -> bool {
+        match self {
+            Self::Service { verb, .. } => {
+                matches!(verb, ServiceVerb::Stop | ServiceVerb::Restart | ServiceVerb::Disable)
+            }
+            Self::Suspend | Self::Hibernate => true,
+            _ => false,
+        }
+    }
+
+    /// Guardian capability (and resource) needed, for privileged actions
+    pub fn capability(&self) -> Option<(&'static str, Option<&str>)> {
+        match self {
+            Self::Service { name, .. } => Some(("service:control", Some(name))),
+            Self::Suspend | Self::Hibernate => Some(("power:sleep", None)),
+            Self::PowerProfile(name) => Some(("power:profile", Some(name))),
+            _ => None,
+        }
+    }
+}
+
+fn parse_profile<'a>(words: &[&'a str]) -> Option<&'a str> {
+    let name = match words {
+        [name, "mode"] | [name, "profile"] => *name,
+        ["power", "profile", name] | ["set", "power", "profile", "to", name] => *name,
+        _ => return None,
+    };
+    match name {
+        "power-saver" | "battery" => Some("powersave"),
+        _ => POWER_PROFILES.iter().copied().find(|p| *p == name),
+    }
+}
+
+fn parse_settings(words: &[&str]) -> Option<String> {
+    let page = match words {
+        ["settings"] | ["open", "settings"] => return Some(String::new()),
+        [page, "settings"] | ["open", page, "settings"] | ["settings", page] => *page,
+        _ => return None,
+    };
+    let page = match page {
+        "wifi" | "wi-fi" => "network/wifi",
+        "audio" => "sound",
+        page => page,
+    };
+    let known = SETTINGS_PAGES.contains(&page.split('/').next().unwrap_or(page));
+    known.then(|| page.to_string())
+}
+
+fn is_service_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@'))
+}
+
+/// Ask Guardian whether a privileged action may run
+///
+/// Fails closed: if Guardian can't be reached privileged actions are refused.
+pub async fn authorize(action: SystemAction) -> Authorization {
+    let Some((capability, resource)) = action.capability() else {
+        return Authorization::Allowed;
+    };
+
+    let decision = async {
+        let mut guardian = GuardianClient::connect().await?;
+        guardian.check_capability(capability, resource).await
+    };
+    match decision.await {
+        Ok(decision) => match decision.decision {
+            Decision::Allow | Decision::Sandbox => Authorization::Allowed,
+            Decision::Prompt => Authorization::Confirm(decision.reason),
+            Decision::Deny => Authorization::Denied(decision.reason),
+        },
+        Err(e) => Authorization::Denied(format!("Guardian unavailable: {}", e)),
+    }
+}
+
+/// Carry out an action
+pub async fn perform(action: SystemAction) -> Result<(), String> {
+    match action {
+        SystemAction::Service { verb, name } => request(
+            SERVICED_SOCKET,
+            json!({ "type": verb.request(), "data": { "name": name } }),
+        )
+        .await
+        .map(drop),
+        SystemAction::NightLight(enabled) => {
+            request(IRIS_SOCKET, json!({ "type": "SetNightLight", "enabled": enabled }))
+                .await
+                .map(drop)
+        }
+        SystemAction::Brightness(percent) => {
+            request(IRIS_SOCKET, json!({ "type": "SetBrightness", "percent": percent }))
+                .await
+                .map(drop)
+        }
+        SystemAction::PowerProfile(name) => {
+            request(SLUMBER_SOCKET, json!({ "type": "SetProfile", "name": name }))
+                .await
+                .map(drop)
+        }
+        SystemAction::Suspend => request(SLUMBER_SOCKET, json!({ "type": "Suspend" })).await.map(drop),
+        SystemAction::Hibernate => request(SLUMBER_SOCKET, json!({ "type": "Hibernate" })).await.map(drop),
+        SystemAction::OpenSettings(page) => tokio::process::Command::new("nyx-settings")
+            .arg(format!("nyx-settings://{}", page))
+            .spawn()
+            .map(drop)
+            .map_err(|e| format!("Failed to open settings: {}", e)),
+    }
+}
+
+/// Send one request line to a daemon and read its reply
+async fn request(socket: &str, request: Value) -> Result<Value, String> {
+    let send = async {
+        let mut stream = UnixStream::connect(socket).await?;
+        stream.write_all(request.to_string().as_bytes()).await?;
+        stream.write_all(b"\n").await?;
+        stream.flush().await?;
+
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).await?;
+        Ok::<_, std::io::Error>(line)
+    };
+    let line = send.await.map_err(|e| format!("{}: {}", socket, e))?;
+
+    let response: Value = serde_json::from_str(&line).map_err(|e| format!("Invalid reply: {}", e))?;
+    if response["status"] == "Success" {
+        Ok(response)
+    } else {
+        Err(response["message"]
+            .as_str()
+            .unwrap_or("Request failed")
+            .to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_service_actions() {
+        assert_eq!(
+            SystemAction::parse("Restart  vesper"),
+            Some(SystemAction::Service {
+                verb: ServiceVerb::Restart,
+                name: "vesper".into()
+            })
+        );
+        assert_eq!(SystemAction::parse("restart"), None);
+        assert_eq!(SystemAction::parse("restart the world"), None);
+    }
+
+    #[test]
+    fn test_parse_night_light() {
+        assert_eq!(SystemAction::parse("enable night light"), Some(SystemAction::NightLight(true)));
+        assert_eq!(SystemAction::parse("turn off night light"), Some(SystemAction::NightLight(false)));
+        assert_eq!(SystemAction::parse("night light on"), Some(SystemAction::NightLight(true)));
+        assert_eq!(
+            SystemAction::parse("night light"),
+            Some(SystemAction::OpenSettings("display/night-light".into()))
+        );
+    }
+
+    #[test]
+    fn test_parse_power() {
+        assert_eq!(SystemAction::parse("suspend"), Some(SystemAction::Suspend));
+        assert_eq!(SystemAction::parse("hibernate"), Some(SystemAction::Hibernate));
+        assert_eq!(
+            SystemAction::parse("power-saver mode"),
+            Some(SystemAction::PowerProfile("powersave".into()))
+        );
+        assert_eq!(SystemAction::parse("turbo mode"), None);
+        assert_eq!(SystemAction::parse("brightness 140%"), Some(SystemAction::Brightness(100)));
+    }
+
+    #[test]
+    fn test_parse_settings_links() {
+        assert_eq!(
+            SystemAction::parse("open wifi settings"),
+            Some(SystemAction::OpenSettings("network/wifi".into()))
+        );
+        assert_eq!(SystemAction::parse("kitchen settings"), None);
+    }
+
+    #[test]
+    fn test_command_id_round_trip() {
+        for action in [
+            SystemAction::Service {
+                verb: ServiceVerb::Stop,
+                name: "herald".into(),
+            },
+            SystemAction::NightLight(false),
+            SystemAction::Brightness(40),
+            SystemAction::PowerProfile("performance".into()),
+            SystemAction::Hibernate,
+            SystemAction::OpenSettings("display/night-light".into()),
+            SystemAction::OpenSettings(String::new()),
+        ] {
+            assert_eq!(SystemAction::from_id(&action.to_command().id), Some(action));
+        }
+        assert_eq!(SystemAction::from_id("sleep"), Some(SystemAction::Suspend));
+        assert_eq!(SystemAction::from_id("terminal"), None);
+    }
+
+    #[test]
+    fn test_privileged_actions_need_guardian() {
+        let restart = SystemAction::parse("restart vesper").unwrap();
+        assert_eq!(restart.capability(), Some(("service:control", Some("vesper"))));
+        assert!(restart.needs_confirmation());
+
+        let start = SystemAction::parse("start vesper").unwrap();
+        assert!(!start.needs_confirmation());
+
+        let night_light = SystemAction::NightLight(true);
+        assert_eq!(night_light.capability(), None);
+        assert!(!night_light.needs_confirmation());
+    }
+}
@@ -0,0 +1,160 @@
+//! Single-instance activation
+//!
+//! The first assistant process binds an activation socket in the user's
+//! runtime directory and keeps running in the background. Later invocations
+//! (`nyx-assistant --toggle`, typically bound to a compositor shortcut) send
+//! their request over the socket and exit, so the existing window is shown
+//! or hidden with its query and conversation intact.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+/// Socket file name inside the runtime directory
+pub const SOCKET_NAME: &str = "nyx-assistant.sock";
+
+/// What a later invocation asks the running instance to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activation {
+    Show,
+    Hide,
+    Toggle,
+}
+
+impl Activation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Show => "show",
+            Self::Hide => "hide",
+            Self::Toggle => "toggle",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "show" => Some(Self::Show),
+            "hide" => Some(Self::Hide),
+            "toggle" => Some(Self::Toggle),
+            _ => None,
+        }
+    }
+}
+
+/// Result of trying to become the running instance
+#[derive(Debug)]
+pub enum Instance {
+    /// No instance was running; this process owns the socket now
+    Primary(UnixListener),
+    /// Another instance is running and took the request
+    Forwarded,
+}
+
+/// Activation socket for the current user
+pub fn socket_path() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(SOCKET_NAME)
+}
+
+/// Hand `request` to a running instance, or claim the socket if there is none
+pub fn acquire(path: &Path, request: Activation) -> io::Result<Instance> {
+    match UnixStream::connect(path) {
+        Ok(mut stream) => {
+            writeln!(stream, "{}", request.as_str())?;
+            // Wait for the instance to acknowledge before exiting
+            let mut reply = String::new();
+            BufReader::new(stream).read_line(&mut reply)?;
+            Ok(Instance::Forwarded)
+        }
+        Err(e) if matches!(e.kind(), io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused) => {
+            // A socket nobody listens on was left by an instance that crashed
+            let _ = std::fs::remove_file(path);
+            let listener = UnixListener::bind(path)?;
+            listener.set_nonblocking(true)?;
+            Ok(Instance::Primary(listener))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Wait for the next activation request on the instance's socket
+///
+/// Malformed requests and failed connections are logged and skipped.
+pub async fn accept(listener: &tokio::net::UnixListener) -> Activation {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                tracing::warn!("Activation socket accept failed: {}", e);
+                continue;
+            }
+        };
+
+        let mut stream = tokio::io::BufReader::new(stream);
+        let mut line = String::new();
+        if let Err(e) = stream.read_line(&mut line).await {
+            tracing::warn!("Failed to read activation request: {}", e);
+            continue;
+        }
+
+        match Activation::parse(&line) {
+            Some(activation) => {
+                let _ = stream.get_mut().write_all(b"ok\n").await;
+                return activation;
+            }
+            None => {
+                tracing::warn!("Unknown activation request: {:?}", line.trim());
+                let _ = stream.get_mut().write_all(b"error\n").await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_socket(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("nyx-assistant-{}-{}.sock", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_activation_parse() {
+        for activation in [Activation::Show, Activation::Hide, Activation::Toggle] {
+            assert_eq!(Activation::parse(activation.as_str()), Some(activation));
+        }
+        assert_eq!(Activation::parse("toggle\n"), Some(Activation::Toggle));
+        assert_eq!(Activation::parse("quit"), None);
+    }
+
+    #[test]
+    fn test_stale_socket_is_replaced() {
+        let path = test_socket("stale");
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        assert!(matches!(acquire(&path, Activation::Show).unwrap(), Instance::Primary(_)));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_second_instance_forwards_request() {
+        let path = test_socket("forward");
+        let Instance::Primary(listener) = acquire(&path, Activation::Show).unwrap() else {
+            panic!("first instance should own the socket");
+        };
+        let listener = tokio::net::UnixListener::from_std(listener).unwrap();
+
+        let second = {
+            let path = path.clone();
+            tokio::task::spawn_blocking(move || acquire(&path, Activation::Toggle))
+        };
+
+        assert_eq!(accept(&listener).await, Activation::Toggle);
+        assert!(matches!(second.await.unwrap().unwrap(), Instance::Forwarded));
+        let _ = std::fs::remove_file(&path);
+    }
+}
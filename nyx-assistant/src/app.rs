@@ -1,7 +1,14 @@
 //! Main application for Nyx Assistant
 
+use crate::actions::{self, Authorization, SystemAction};
+use crate::activation::{self, Activation};
+use crate::chat::{self, ChatMessage, Conversation, Role, StreamEvent};
+use crate::clipboard::{self, ClipboardSettings};
 use crate::commands::{CommandKind, CommandResult};
 use crate::search::SearchEngine;
+use crate::snippets::{self, Snippet};
+use grimoire_core::{MemoryEntry, Persona, PersonaId};
+use iced::futures::SinkExt;
 use iced::keyboard;
 use iced::widget::{
     button, column, container, horizontal_space, row, scrollable, text, text_input,
@@ -15,8 +22,21 @@ use nyx_theme::spacing::Spacing;
 use nyx_theme::widgets::button::{button_style, ButtonVariant};
 use nyx_theme::widgets::card::card_style;
 use nyx_theme::widgets::input::input_style;
-use nyx_theme::widgets::{CardVariant, InputVariant};
+use nyx_theme::widgets::{modal, segmented_control, CardVariant, InputVariant, StyleVariant};
 use nyx_theme::Typography;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How often clipboard history checks for new text
+const CLIPBOARD_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Startup state handed over by main
+pub struct Flags {
+    /// Activation socket this instance owns
+    pub listener: std::os::unix::net::UnixListener,
+    /// Whether the window starts shown
+    pub visible: bool,
+}
 
 /// Main assistant application
 pub struct NyxAssistant {
@@ -28,8 +48,25 @@ pub struct NyxAssistant {
     results: Vec<CommandResult>,
     /// Selected result index
     selected: usize,
-    /// Is loading AI response
-    loading: bool,
+    /// Searching or chatting with a persona
+    mode: Mode,
+    /// Conversation with the selected persona
+    chat: Conversation,
+    /// System action waiting for confirmation, with Guardian's reason if any
+    pending_action: Option<(SystemAction, Option<String>)>,
+    /// Why the last system action didn't run
+    status: Option<String>,
+    /// Window is shown
+    visible: bool,
+    /// Activation socket, taken by the subscription when it starts
+    listener: Arc<Mutex<Option<std::os::unix::net::UnixListener>>>,
+}
+
+/// What the palette is showing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Search,
+    Chat,
 }
 
 /// Application message
@@ -43,12 +80,48 @@ pub enum Message {
     ResultClicked(usize),
     /// Keyboard navigation
     KeyPressed(keyboard::Key),
-    /// Close assistant
+    /// Hide the assistant until it is summoned again
     Close,
+    /// Request from another invocation (`--toggle`, `--show`, `--hide`)
+    Activate(Activation),
     /// Execute command
     Execute(CommandResult),
-    /// AI response received
-    AiResponse(String),
+    /// Personas loaded from grimoire
+    PersonasLoaded(Result<Vec<Persona>, String>),
+    /// Persona picked in the chat view
+    SelectPersona(PersonaId),
+    /// Conversation history loaded from a persona's memory
+    HistoryLoaded(PersonaId, Result<Vec<MemoryEntry>, String>),
+    /// Streamed persona reply progress
+    Chat(StreamEvent),
+    /// Run a command the persona suggested (asks for confirmation)
+    RunSuggested(String),
+    /// Confirm the pending command
+    ConfirmRun,
+    /// Dismiss the pending command
+    CancelRun,
+    /// Output of a command that was run
+    CommandOutput(String, String),
+    /// Leave the chat view
+    BackToSearch,
+    /// Guardian's verdict on a system action
+    ActionAuthorized(SystemAction, Authorization),
+    /// Confirm the pending system action
+    ConfirmAction,
+    /// Dismiss the pending system action
+    CancelAction,
+    /// System action finished
+    ActionDone(Result<(), String>),
+    /// Clipboard history settings loaded from grimoire
+    ClipboardSettingsLoaded(Result<ClipboardSettings, String>),
+    /// Snippets loaded from grimoire
+    SnippetsLoaded(Result<Vec<Snippet>, String>),
+    /// Time to check the clipboard for new text
+    PollClipboard,
+    /// Current clipboard text
+    ClipboardRead(Option<String>),
+    /// Expand a snippet against the current clipboard text
+    ExpandSnippet(Snippet, Option<String>),
     /// Focus the input
     FocusInput,
 }
@@ -57,9 +130,9 @@ impl Application for NyxAssistant {
     type Executor = executor::Default;
     type Message = Message;
     type Theme = Theme;
-    type Flags = ();
+    type Flags = Flags;
 
-    fn new(_flags: ()) -> (Self, Command<Message>) {
+    fn new(flags: Flags) -> (Self, Command<Message>) {
         let search = SearchEngine::new();
         let results = search.get_suggestions();
 
@@ -69,9 +142,17 @@ impl Application for NyxAssistant {
                 search,
                 results,
                 selected: 0,
-                loading: false,
+                mode: Mode::Search,
+                chat: Conversation::new(),
+                pending_action: None,
+                status: None,
+                visible: flags.visible,
+                listener: Arc::new(Mutex::new(Some(flags.listener))),
             },
-            iced::widget::text_input::focus(text_input::Id::new("search-input")),
+            Command::batch([
+                iced::widget::text_input::focus(text_input::Id::new("search-input")),
+                load_providers(),
+            ]),
         )
     }
 
@@ -87,14 +168,26 @@ impl Application for NyxAssistant {
         match message {
             Message::QueryChanged(query) => {
                 self.query = query.clone();
-                self.results = self.search.search(&query);
-                self.selected = 0;
+                self.status = None;
+                if self.mode == Mode::Search {
+                    self.results = self.search.search(&query);
+                    self.selected = 0;
+                }
             }
 
             Message::Submit => {
+                if self.mode == Mode::Chat {
+                    let query = std::mem::take(&mut self.query);
+                    return self.ask(&query);
+                }
                 if let Some(result) = self.results.get(self.selected).cloned() {
                     return self.execute_command(result);
                 }
+                // Nothing matched: hand the query to the persona
+                if !self.query.trim().is_empty() {
+                    let query = std::mem::take(&mut self.query);
+                    return self.open_chat(&query);
+                }
             }
 
             Message::ResultClicked(index) => {
@@ -115,7 +208,15 @@ impl Application for NyxAssistant {
                     }
                 }
                 keyboard::Key::Named(keyboard::key::Named::Escape) => {
-                    return iced::window::close(iced::window::Id::MAIN);
+                    if self.pending_action.is_some() {
+                        self.pending_action = None;
+                    } else if self.chat.pending().is_some() {
+                        self.chat.cancel_run();
+                    } else if self.mode == Mode::Chat {
+                        return self.update(Message::BackToSearch);
+                    } else {
+                        return self.hide();
+                    }
                 }
                 keyboard::Key::Named(keyboard::key::Named::Tab) => {
                     // Cycle through results
@@ -128,16 +229,157 @@ impl Application for NyxAssistant {
             },
 
             Message::Close => {
-                return iced::window::close(iced::window::Id::MAIN);
+                return self.hide();
+            }
+
+            Message::Activate(activation) => {
+                let show = match activation {
+                    Activation::Show => true,
+                    Activation::Hide => false,
+                    Activation::Toggle => !self.visible,
+                };
+                return if show { self.show() } else { self.hide() };
             }
 
             Message::Execute(result) => {
                 return self.execute_command(result);
             }
 
-            Message::AiResponse(_response) => {
-                self.loading = false;
-                // Display AI response
+            Message::PersonasLoaded(Ok(personas)) if personas.is_empty() => {
+                self.chat.take_queued();
+                self.chat.apply(StreamEvent::Failed("No personas available".to_string()));
+            }
+
+            Message::PersonasLoaded(Ok(personas)) => {
+                let history = self.chat.set_personas(personas).map(load_history);
+                let queued = self
+                    .chat
+                    .take_queued()
+                    .map(|query| self.ask(&query))
+                    .unwrap_or_else(Command::none);
+                return Command::batch(history.into_iter().chain([queued]));
+            }
+
+            Message::PersonasLoaded(Err(error)) => {
+                tracing::warn!("Failed to load personas: {}", error);
+                self.chat.take_queued();
+                self.chat.apply(StreamEvent::Failed(format!("Grimoire unavailable: {}", error)));
+            }
+
+            Message::SelectPersona(id) => {
+                if let Some(id) = self.chat.select(id) {
+                    return load_history(id);
+                }
+            }
+
+            Message::HistoryLoaded(id, Ok(entries)) => {
+                self.chat.load_history(id, entries);
+            }
+
+            Message::HistoryLoaded(_, Err(error)) => {
+                tracing::warn!("Failed to load conversation history: {}", error);
+            }
+
+            Message::Chat(event) => {
+                self.chat.apply(event);
+            }
+
+            Message::RunSuggested(command) => {
+                self.chat.request_run(command);
+            }
+
+            Message::ConfirmRun => {
+                if let Some(command) = self.chat.confirm_run() {
+                    tracing::info!("Running suggested command: {}", command);
+                    return Command::perform(chat::run_command(command.clone()), move |output| {
+                        Message::CommandOutput(command.clone(), output)
+                    });
+                }
+            }
+
+            Message::CancelRun => {
+                self.chat.cancel_run();
+            }
+
+            Message::CommandOutput(command, output) => {
+                self.chat.push_output(&command, &output);
+            }
+
+            Message::ActionAuthorized(action, authorization) => match authorization {
+                Authorization::Allowed if action.needs_confirmation() => {
+                    self.pending_action = Some((action, None));
+                }
+                Authorization::Allowed => {
+                    return Command::perform(actions::perform(action), Message::ActionDone);
+                }
+                Authorization::Confirm(reason) => {
+                    self.pending_action = Some((action, Some(reason)));
+                }
+                Authorization::Denied(reason) => {
+                    tracing::warn!("{} denied: {}", action.title(), reason);
+                    self.status = Some(format!("{} not allowed: {}", action.title(), reason));
+                }
+            },
+
+            Message::ConfirmAction => {
+                if let Some((action, _)) = self.pending_action.take() {
+                    return Command::perform(actions::perform(action), Message::ActionDone);
+                }
+            }
+
+            Message::CancelAction => {
+                self.pending_action = None;
+            }
+
+            Message::ActionDone(Ok(())) => {
+                return self.hide();
+            }
+
+            Message::ActionDone(Err(error)) => {
+                tracing::warn!("System action failed: {}", error);
+                self.status = Some(error);
+            }
+
+            Message::BackToSearch => {
+                self.mode = Mode::Search;
+                self.query.clear();
+                self.results = self.search.get_suggestions();
+                self.selected = 0;
+            }
+
+            Message::ClipboardSettingsLoaded(Ok(settings)) => {
+                self.search.clipboard_mut().set_settings(settings);
+            }
+
+            Message::ClipboardSettingsLoaded(Err(error)) => {
+                tracing::warn!("Failed to load clipboard settings: {}", error);
+            }
+
+            Message::SnippetsLoaded(Ok(snippets)) => {
+                self.search.set_snippets(snippets);
+            }
+
+            Message::SnippetsLoaded(Err(error)) => {
+                tracing::warn!("Failed to load snippets: {}", error);
+            }
+
+            Message::PollClipboard => {
+                return iced::clipboard::read(Message::ClipboardRead);
+            }
+
+            Message::ClipboardRead(Some(text)) => {
+                let changed = self.search.clipboard_mut().record(&text);
+                if changed && self.mode == Mode::Search && !self.query.is_empty() {
+                    self.results = self.search.search(&self.query);
+                    self.selected = self.selected.min(self.results.len().saturating_sub(1));
+                }
+            }
+
+            Message::ClipboardRead(None) => {}
+
+            Message::ExpandSnippet(snippet, current) => {
+                let text = snippets::expand(&snippet.text, &snippets::Context::current(current));
+                return Command::batch([iced::clipboard::write(text), self.hide()]);
             }
 
             Message::FocusInput => {
@@ -149,7 +391,25 @@ impl Application for NyxAssistant {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        iced::event::listen_with(|event, _status, _id| {
+        let listener = Arc::clone(&self.listener);
+        let activations = iced::subscription::channel("activation", 8, move |mut output| async move {
+            let listener = listener
+                .lock()
+                .ok()
+                .and_then(|mut listener| listener.take())
+                .and_then(|listener| tokio::net::UnixListener::from_std(listener).ok());
+            let Some(listener) = listener else {
+                tracing::error!("Activation socket unavailable; --toggle will not reach this instance");
+                return iced::futures::future::pending().await;
+            };
+
+            loop {
+                let activation = activation::accept(&listener).await;
+                let _ = output.send(Message::Activate(activation)).await;
+            }
+        });
+
+        let keys = iced::event::listen_with(|event, _status, _id| {
             if let Event::Keyboard(keyboard::Event::KeyPressed {
                 key,
                 modifiers: _,
@@ -168,33 +428,134 @@ impl Application for NyxAssistant {
             } else {
                 None
             }
-        })
+        });
+
+        let clipboard = if self.search.clipboard().is_enabled() {
+            iced::time::every(CLIPBOARD_POLL_INTERVAL).map(|_| Message::PollClipboard)
+        } else {
+            Subscription::none()
+        };
+
+        Subscription::batch([keys, activations, clipboard])
     }
 
     fn view(&self) -> Element<Message> {
         // Header with search input
         let header = self.view_header();
 
-        // Results list
-        let results = self.view_results();
+        // Results list, or the conversation in chat mode
+        let results = match self.mode {
+            Mode::Search => self.view_results(),
+            Mode::Chat => self.view_chat(),
+        };
 
         // Footer with hints
         let footer = self.view_footer();
 
-        let content = column![header, results, footer]
+        // Why the last system action didn't run
+        let status = self.status.as_ref().map(|status| {
+            text(status)
+                .size(Typography::SIZE_BODY_SMALL)
+                .color(NyxColors::ERROR)
+        });
+
+        let content = column![header, results]
+            .push_maybe(status)
+            .push(footer)
             .spacing(Spacing::SM)
             .padding(Spacing::LG);
 
-        container(content)
+        let palette = container(content)
             .width(Length::Fill)
             .height(Length::Fill)
-            .style(card_style(CardVariant::Glass))
-            .into()
+            .style(card_style(CardVariant::Glass));
+
+        if let Some((action, reason)) = &self.pending_action {
+            let detail = reason.as_deref().unwrap_or(action.subtitle());
+            let dialog = self.view_confirm(
+                format!("{}?", action.title()),
+                detail,
+                Message::ConfirmAction,
+                Message::CancelAction,
+            );
+            return modal(palette, dialog, Message::CancelAction, StyleVariant::default());
+        }
+
+        match self.chat.pending() {
+            Some(command) => {
+                let dialog = self.view_confirm(
+                    "Run this command?".to_string(),
+                    command,
+                    Message::ConfirmRun,
+                    Message::CancelRun,
+                );
+                modal(palette, dialog, Message::CancelRun, StyleVariant::default())
+            }
+            None => palette.into(),
+        }
     }
 }
 
 impl NyxAssistant {
-    fn execute_command(&self, result: CommandResult) -> Command<Message> {
+    /// Bring the window back with its previous query and conversation
+    fn show(&mut self) -> Command<Message> {
+        self.visible = true;
+        Command::batch([
+            iced::window::change_mode(iced::window::Id::MAIN, iced::window::Mode::Windowed),
+            iced::window::gain_focus(iced::window::Id::MAIN),
+            iced::widget::text_input::focus(text_input::Id::new("search-input")),
+            // Pick up settings changed while hidden
+            load_providers(),
+        ])
+    }
+
+    /// Hide the window; the instance keeps running for the next summon
+    fn hide(&mut self) -> Command<Message> {
+        self.visible = false;
+        self.pending_action = None;
+        self.chat.cancel_run();
+        iced::window::change_mode(iced::window::Id::MAIN, iced::window::Mode::Hidden)
+    }
+
+    /// Switch to the chat view and send `query` to the selected persona
+    fn open_chat(&mut self, query: &str) -> Command<Message> {
+        self.mode = Mode::Chat;
+        self.query.clear();
+
+        if self.chat.has_personas() {
+            return self.ask(query);
+        }
+        // Queue the message until personas are loaded
+        self.chat.ask(query);
+        Command::batch([
+            Command::perform(chat::load_personas(), Message::PersonasLoaded),
+            iced::widget::text_input::focus(text_input::Id::new("search-input")),
+        ])
+    }
+
+    /// Send a message to the selected persona, streaming its reply
+    fn ask(&mut self, query: &str) -> Command<Message> {
+        let remember = self
+            .chat
+            .persona()
+            .is_some_and(|p| p.capabilities.can_remember);
+        match self.chat.ask(query) {
+            Some((persona_id, message)) => {
+                Command::run(chat::send(persona_id, message, remember), Message::Chat)
+            }
+            None => Command::none(),
+        }
+    }
+
+    /// Check a system action with Guardian before confirming and running it
+    fn start_action(&mut self, action: SystemAction) -> Command<Message> {
+        self.status = None;
+        Command::perform(actions::authorize(action.clone()), move |authorization| {
+            Message::ActionAuthorized(action, authorization)
+        })
+    }
+
+    fn execute_command(&mut self, result: CommandResult) -> Command<Message> {
         tracing::info!("Executing: {:?}", result);
 
         match result.kind {
@@ -203,7 +564,9 @@ impl NyxAssistant {
                 tracing::info!("Launching app: {}", result.id);
             }
             CommandKind::System => {
-                // Execute system command
+                if let Some(action) = SystemAction::from_id(&result.id) {
+                    return self.start_action(action);
+                }
                 tracing::info!("System command: {}", result.id);
             }
             CommandKind::WebSearch => {
@@ -211,22 +574,38 @@ impl NyxAssistant {
                 tracing::info!("Web search: {}", result.title);
             }
             CommandKind::AiQuery => {
-                // Send to AI
-                tracing::info!("AI query: {}", result.title);
+                let query = result.id.strip_prefix("ai:").unwrap_or(&result.title);
+                return self.open_chat(query);
             }
             CommandKind::Calculator => {
                 // Copy to clipboard
                 tracing::info!("Calculator result: {}", result.title);
             }
             CommandKind::Settings => {
-                // Open settings page
-                tracing::info!("Opening settings: {}", result.id);
+                return self.start_action(SystemAction::OpenSettings(result.id));
+            }
+            CommandKind::Clipboard => {
+                let entry = result
+                    .id
+                    .strip_prefix("clip:")
+                    .and_then(|id| id.parse().ok())
+                    .and_then(|id| self.search.clipboard().get(id));
+                if let Some(entry) = entry {
+                    return Command::batch([iced::clipboard::write(entry.text.clone()), self.hide()]);
+                }
+            }
+            CommandKind::Snippet => {
+                if let Some(snippet) = self.search.snippet(&result.id).cloned() {
+                    return iced::clipboard::read(move |current| {
+                        Message::ExpandSnippet(snippet.clone(), current)
+                    });
+                }
             }
             _ => {}
         }
 
-        // Close assistant after execution
-        iced::window::close(iced::window::Id::MAIN)
+        // Dismiss assistant after execution
+        self.hide()
     }
 
     fn view_header(&self) -> Element<Message> {
@@ -234,7 +613,13 @@ impl NyxAssistant {
             .size(Typography::SIZE_ICON_XL)
             .color(NyxColors::AURORA);
 
-        let input = text_input("Search apps, files, or ask anything...", &self.query)
+        let placeholder = match (self.mode, self.chat.persona()) {
+            (Mode::Chat, Some(persona)) => format!("Message {}...", persona.name),
+            (Mode::Chat, None) => "Message...".to_string(),
+            (Mode::Search, _) => "Search apps, files, or ask anything...".to_string(),
+        };
+
+        let input = text_input(&placeholder, &self.query)
             .id(text_input::Id::new("search-input"))
             .size(Typography::SIZE_BODY_LARGE)
             .padding(Spacing::MD)
@@ -268,6 +653,138 @@ impl NyxAssistant {
         .into()
     }
 
+    fn view_chat(&self) -> Element<Message> {
+        let personas: Vec<(PersonaId, &str)> = self
+            .chat
+            .personas()
+            .iter()
+            .map(|p| (p.id, p.name.as_str()))
+            .collect();
+
+        let picker: Element<Message> = match self.chat.persona() {
+            Some(persona) => segmented_control(
+                &personas,
+                &persona.id,
+                Message::SelectPersona,
+                StyleVariant::default(),
+            ),
+            None => text("Connecting to Grimoire...")
+                .size(Typography::SIZE_BODY_SMALL)
+                .color(NyxColors::TEXT_MUTED)
+                .into(),
+        };
+
+        let can_run = self
+            .chat
+            .persona()
+            .is_some_and(|p| p.capabilities.can_execute_commands);
+        // Suggested commands can be run once their reply has finished
+        let streaming = self.chat.messages().len().saturating_sub(1);
+        let messages: Vec<Element<Message>> = self
+            .chat
+            .messages()
+            .iter()
+            .enumerate()
+            .map(|(i, message)| {
+                let finished = !(self.chat.is_streaming() && i == streaming);
+                self.view_chat_message(message, can_run && finished)
+            })
+            .collect();
+
+        let conversation = scrollable(
+            column(messages)
+                .spacing(Spacing::SM)
+                .width(Length::Fill),
+        )
+        .anchor_bottom()
+        .height(Length::Fill);
+
+        column![picker, conversation]
+            .spacing(Spacing::SM)
+            .height(Length::Fill)
+            .into()
+    }
+
+    fn view_chat_message<'a>(&'a self, message: &'a ChatMessage, can_run: bool) -> Element<'a, Message> {
+        let (label, color) = match message.role {
+            Role::User => ("You", NyxColors::AURORA),
+            Role::Persona => (
+                self.chat.persona().map_or("Persona", |p| p.name.as_str()),
+                NyxColors::CELESTIAL,
+            ),
+            Role::Output => ("Output", NyxColors::TEXT_SECONDARY),
+            Role::Error => ("Error", NyxColors::ERROR),
+        };
+
+        let body = if message.role == Role::Persona && message.text.is_empty() {
+            "…"
+        } else {
+            message.text.as_str()
+        };
+
+        let mut content = column![
+            text(label).size(Typography::SIZE_LABEL_SMALL).color(color),
+            text(body)
+                .size(Typography::SIZE_BODY_MEDIUM)
+                .color(NyxColors::TEXT_BRIGHT),
+        ]
+        .spacing(Spacing::XXS);
+
+        if can_run {
+            for command in message.suggested_commands() {
+                let run = button(text("Run").size(Typography::SIZE_LABEL_SMALL))
+                    .style(button_style(ButtonVariant::Secondary))
+                    .on_press(Message::RunSuggested(command.clone()));
+                content = content.push(
+                    row![
+                        text(command)
+                            .size(Typography::SIZE_BODY_SMALL)
+                            .color(NyxColors::TEXT_SECONDARY)
+                            .width(Length::Fill),
+                        run,
+                    ]
+                    .spacing(Spacing::SM)
+                    .align_y(Alignment::Center),
+                );
+            }
+        }
+
+        container(content)
+            .padding(Spacing::SM)
+            .width(Length::Fill)
+            .style(card_style(CardVariant::Default))
+            .into()
+    }
+
+    fn view_confirm<'a>(
+        &self,
+        title: String,
+        detail: &'a str,
+        on_confirm: Message,
+        on_cancel: Message,
+    ) -> Element<'a, Message> {
+        column![
+            text(title)
+                .size(Typography::SIZE_TITLE_SMALL)
+                .color(NyxColors::TEXT_BRIGHT),
+            text(detail)
+                .size(Typography::SIZE_BODY_MEDIUM)
+                .color(NyxColors::TEXT_SECONDARY),
+            row![
+                horizontal_space(),
+                button(text("Cancel"))
+                    .style(button_style(ButtonVariant::Ghost))
+                    .on_press(on_cancel),
+                button(text("Confirm"))
+                    .style(button_style(ButtonVariant::Danger))
+                    .on_press(on_confirm),
+            ]
+            .spacing(Spacing::SM),
+        ]
+        .spacing(Spacing::MD)
+        .into()
+    }
+
     fn view_results(&self) -> Element<Message> {
         if self.results.is_empty() {
             return container(
@@ -382,6 +899,8 @@ impl NyxAssistant {
             CommandKind::File => ("File", NyxColors::TEXT_SECONDARY),
             CommandKind::Folder => ("Folder", NyxColors::TEXT_SECONDARY),
             CommandKind::Recent => ("Recent", NyxColors::TEXT_MUTED),
+            CommandKind::Clipboard => ("Clipboard", NyxColors::TEXT_SECONDARY),
+            CommandKind::Snippet => ("Snippet", NyxColors::ETHEREAL),
         };
 
         container(
@@ -422,7 +941,7 @@ impl NyxAssistant {
             text("Esc")
                 .size(Typography::SIZE_LABEL_SMALL)
                 .color(NyxColors::TEXT_MUTED),
-            text("Close")
+            text(if self.mode == Mode::Chat { "Back" } else { "Close" })
                 .size(Typography::SIZE_LABEL_SMALL)
                 .color(NyxColors::TEXT_MUTED),
             horizontal_space(),
@@ -446,3 +965,18 @@ impl NyxAssistant {
             .into()
     }
 }
+
+/// Load a persona's recent conversation from its memory
+fn load_history(id: PersonaId) -> Command<Message> {
+    Command::perform(chat::load_history(id), move |entries| {
+        Message::HistoryLoaded(id, entries)
+    })
+}
+
+/// Load clipboard history settings and snippets from grimoire
+fn load_providers() -> Command<Message> {
+    Command::batch([
+        Command::perform(clipboard::load_settings(), Message::ClipboardSettingsLoaded),
+        Command::perform(snippets::load(), Message::SnippetsLoaded),
+    ])
+}
@@ -0,0 +1,507 @@
+//! Conversational mode backed by grimoire personas
+//!
+//! Queries that aren't an app, file or calculation go to the selected
+//! persona. Its reply streams in token by token, each exchange is written to
+//! that persona's grimoire memory (so switching persona switches history),
+//! and shell commands it suggests can be run inline once confirmed.
+
+use grimoire_client::{GrimoireClient, ReplyStream};
+use grimoire_core::{MemoryEntry, MemoryEntryType, Persona, PersonaId};
+use iced::futures::stream::{self, Stream};
+
+/// Past messages loaded when a persona is selected
+pub const HISTORY_LIMIT: usize = 20;
+
+/// Command output kept in the conversation
+const OUTPUT_LIMIT: usize = 4000;
+
+/// Code fence languages treated as runnable shell
+const SHELL_LANGUAGES: &[&str] = &["sh", "bash", "shell", "zsh", "console"];
+
+/// Who a message is from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    User,
+    Persona,
+    /// Output of a command the user ran
+    Output,
+    /// Something went wrong talking to grimoire
+    Error,
+}
+
+/// A message in the conversation
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub text: String,
+}
+
+impl ChatMessage {
+    pub fn new(role: Role, text: impl Into<String>) -> Self {
+        Self {
+            role,
+            text: text.into(),
+        }
+    }
+
+    /// Shell commands suggested in a persona reply
+    pub fn suggested_commands(&self) -> Vec<String> {
+        match self.role {
+            Role::Persona => suggested_commands(&self.text),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Progress of a streamed reply
+#[derive(Debug, Clone)]
+pub enum StreamEvent {
+    /// Next chunk of the reply
+    Token(String),
+    /// Reply complete
+    Done,
+    /// Request failed; the reply so far is kept
+    Failed(String),
+}
+
+/// Conversation state for the chat view
+#[derive(Debug, Default)]
+pub struct Conversation {
+    /// Personas to choose from
+    personas: Vec<Persona>,
+    /// Persona messages go to
+    selected: Option<PersonaId>,
+    /// Messages with the selected persona, oldest first
+    messages: Vec<ChatMessage>,
+    /// A reply is streaming in
+    streaming: bool,
+    /// Message waiting for personas to load
+    queued: Option<String>,
+    /// Command waiting for the user to confirm it
+    pending: Option<String>,
+}
+
+impl Conversation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn personas(&self) -> &[Persona] {
+        &self.personas
+    }
+
+    /// Selected persona
+    pub fn persona(&self) -> Option<&Persona> {
+        let id = self.selected?;
+        self.personas.iter().find(|p| p.id == id)
+    }
+
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.streaming
+    }
+
+    pub fn has_personas(&self) -> bool {
+        !self.personas.is_empty()
+    }
+
+    /// Command awaiting confirmation
+    pub fn pending(&self) -> Option<&str> {
+        self.pending.as_deref()
+    }
+
+    /// Replace the persona list, keeping the selection if it still exists
+    ///
+    /// Returns the persona to load history for when the selection changed.
+    pub fn set_personas(&mut self, personas: Vec<Persona>) -> Option<PersonaId> {
+        self.personas = personas;
+        let kept = self
+            .selected
+            .filter(|id| self.personas.iter().any(|p| p.id == *id));
+        if kept.is_some() {
+            return None;
+        }
+
+        let first = self.personas.first().map(|p| p.id);
+        self.select(first?)
+    }
+
+    /// Switch persona; returns it if history needs loading
+    pub fn select(&mut self, id: PersonaId) -> Option<PersonaId> {
+        if self.selected == Some(id) || self.streaming {
+            return None;
+        }
+        self.selected = Some(id);
+        self.messages.clear();
+        self.pending = None;
+        Some(id)
+    }
+
+    /// Show history from the persona's memory
+    pub fn load_history(&mut self, id: PersonaId, entries: Vec<MemoryEntry>) {
+        if self.selected != Some(id) {
+            return;
+        }
+        let history = entries.into_iter().filter_map(|entry| {
+            let role = match entry.entry_type {
+                MemoryEntryType::UserMessage => Role::User,
+                MemoryEntryType::PersonaResponse => Role::Persona,
+                _ => return None,
+            };
+            Some(ChatMessage::new(role, entry.content))
+        });
+        // Anything said while history was loading stays after it
+        let current = std::mem::take(&mut self.messages);
+        self.messages = history.chain(current).collect();
+    }
+
+    /// Start a message to the selected persona
+    ///
+    /// Returns where to send it, or None if it has to wait for personas to
+    /// load or for the current reply to finish.
+    pub fn ask(&mut self, text: &str) -> Option<(PersonaId, String)> {
+        let text = text.trim();
+        if text.is_empty() || self.streaming {
+            return None;
+        }
+        let Some(id) = self.persona().map(|p| p.id) else {
+            self.queued = Some(text.to_string());
+            return None;
+        };
+
+        self.messages.push(ChatMessage::new(Role::User, text));
+        self.messages.push(ChatMessage::new(Role::Persona, ""));
+        self.streaming = true;
+        Some((id, text.to_string()))
+    }
+
+    /// Message queued before personas loaded
+    pub fn take_queued(&mut self) -> Option<String> {
+        self.queued.take()
+    }
+
+    /// Apply a streamed reply event
+    pub fn apply(&mut self, event: StreamEvent) {
+        match event {
+            StreamEvent::Token(token) => {
+                if let Some(reply) = self.messages.last_mut().filter(|m| m.role == Role::Persona) {
+                    reply.text.push_str(&token);
+                }
+            }
+            StreamEvent::Done => self.streaming = false,
+            StreamEvent::Failed(error) => {
+                self.streaming = false;
+                if self
+                    .messages
+                    .last()
+                    .is_some_and(|m| m.role == Role::Persona && m.text.is_empty())
+                {
+                    self.messages.pop();
+                }
+                self.messages.push(ChatMessage::new(Role::Error, error));
+            }
+        }
+    }
+
+    /// Ask to run a suggested command; only personas allowed to execute
+    /// commands get their suggestions run
+    pub fn request_run(&mut self, command: String) -> bool {
+        let allowed = self
+            .persona()
+            .is_some_and(|p| p.capabilities.can_execute_commands);
+        if allowed {
+            self.pending = Some(command);
+        }
+        allowed
+    }
+
+    /// Confirm the pending command, returning it to run
+    pub fn confirm_run(&mut self) -> Option<String> {
+        self.pending.take()
+    }
+
+    pub fn cancel_run(&mut self) {
+        self.pending = None;
+    }
+
+    /// Record the output of a command that was run
+    pub fn push_output(&mut self, command: &str, output: &str) {
+        self.messages
+            .push(ChatMessage::new(Role::Output, format!("$ {}\n{}", command, output)));
+    }
+}
+
+/// Shell commands in fenced code blocks, one per non-empty line
+pub fn suggested_commands(text: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut in_shell_block = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(info) = trimmed.strip_prefix("```") {
+            in_shell_block = match in_shell_block {
+                Some(_) => None,
+                None => Some(SHELL_LANGUAGES.contains(&info.trim())),
+            };
+            continue;
+        }
+        if in_shell_block != Some(true) || trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let command = trimmed.strip_prefix("$ ").unwrap_or(trimmed);
+        commands.push(command.to_string());
+    }
+
+    commands
+}
+
+/// Personas available from grimoire
+pub async fn load_personas() -> Result<Vec<Persona>, String> {
+    let client = GrimoireClient::connect_default()
+        .await
+        .map_err(|e| e.to_string())?;
+    client.list_personas().await.map_err(|e| e.to_string())
+}
+
+/// Recent conversation with a persona, oldest first
+pub async fn load_history(id: PersonaId) -> Result<Vec<MemoryEntry>, String> {
+    let client = GrimoireClient::connect_default()
+        .await
+        .map_err(|e| e.to_string())?;
+    let memory = client.get_memory(id).await.map_err(|e| e.to_string())?;
+    Ok(memory
+        .recent_context(HISTORY_LIMIT)
+        .into_iter()
+        .rev()
+        .cloned()
+        .collect())
+}
+
+enum SendState {
+    Start(PersonaId, String, bool),
+    Streaming {
+        client: GrimoireClient,
+        reply: ReplyStream,
+        persona_id: PersonaId,
+        message: String,
+        text: String,
+        remember: bool,
+    },
+    Finished,
+}
+
+/// Send `message` to a persona and stream its reply
+///
+/// Once the reply is complete the exchange is stored in the persona's
+/// memory, if the persona remembers conversations.
+pub fn send(persona_id: PersonaId, message: String, remember: bool) -> impl Stream<Item = StreamEvent> {
+    stream::unfold(SendState::Start(persona_id, message, remember), |mut state| async move {
+        loop {
+            match state {
+                SendState::Start(persona_id, message, remember) => {
+                    let started = async {
+                        let client = GrimoireClient::connect_default().await?;
+                        let reply = client.converse(persona_id, &message).await?;
+                        Ok::<_, grimoire_client::ClientError>((client, reply))
+                    };
+                    state = match started.await {
+                        Ok((client, reply)) => SendState::Streaming {
+                            client,
+                            reply,
+                            persona_id,
+                            message,
+                            text: String::new(),
+                            remember,
+                        },
+                        Err(e) => return Some((StreamEvent::Failed(e.to_string()), SendState::Finished)),
+                    };
+                }
+
+                SendState::Streaming {
+                    client,
+                    mut reply,
+                    persona_id,
+                    message,
+                    mut text,
+                    remember,
+                } => {
+                    return match reply.next_token().await {
+                        Some(Ok(token)) => {
+                            text.push_str(&token);
+                            let state = SendState::Streaming {
+                                client,
+                                reply,
+                                persona_id,
+                                message,
+                                text,
+                                remember,
+                            };
+                            Some((StreamEvent::Token(token), state))
+                        }
+                        Some(Err(e)) => Some((StreamEvent::Failed(e.to_string()), SendState::Finished)),
+                        None => {
+                            // The reply holds the connection until dropped
+                            drop(reply);
+                            if remember {
+                                remember_exchange(&client, persona_id, message, text).await;
+                            }
+                            Some((StreamEvent::Done, SendState::Finished))
+                        }
+                    };
+                }
+
+                SendState::Finished => return None,
+            }
+        }
+    })
+}
+
+async fn remember_exchange(client: &GrimoireClient, persona_id: PersonaId, message: String, reply: String) {
+    for entry in [
+        MemoryEntry::user_message(message),
+        MemoryEntry::persona_response(reply),
+    ] {
+        if let Err(e) = client.add_memory(persona_id, entry).await {
+            tracing::warn!("Failed to store conversation memory: {}", e);
+            return;
+        }
+    }
+}
+
+/// Run a confirmed command through the shell, returning its output
+pub async fn run_command(command: String) -> String {
+    let output = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .output()
+        .await;
+
+    let mut text = match output {
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            if !output.status.success() {
+                text.push_str(&format!("\n[{}]", output.status));
+            }
+            text
+        }
+        Err(e) => format!("Failed to run command: {}", e),
+    };
+
+    if text.len() > OUTPUT_LIMIT {
+        let mut end = OUTPUT_LIMIT;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str("\n…");
+    }
+    text.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grimoire_core::builtin;
+
+    fn conversation() -> Conversation {
+        let mut chat = Conversation::new();
+        chat.set_personas(builtin::all());
+        chat
+    }
+
+    #[test]
+    fn test_suggested_commands_from_shell_blocks() {
+        let reply = "Try this:\n```bash\n$ ls -la\n# comment\n\necho hi\n```\n```rust\nfn main() {}\n```";
+        assert_eq!(suggested_commands(reply), vec!["ls -la", "echo hi"]);
+    }
+
+    #[test]
+    fn test_suggested_commands_ignore_plain_blocks() {
+        assert!(suggested_commands("```\nrm -rf /tmp/x\n```").is_empty());
+        assert!(suggested_commands("no code here").is_empty());
+    }
+
+    #[test]
+    fn test_first_persona_selected() {
+        let mut chat = Conversation::new();
+        let personas = builtin::all();
+        let first = personas[0].id;
+
+        assert_eq!(chat.set_personas(personas.clone()), Some(first));
+        assert_eq!(chat.persona().map(|p| p.id), Some(first));
+        // Reloading the same list keeps the selection
+        assert_eq!(chat.set_personas(personas), None);
+    }
+
+    #[test]
+    fn test_message_queued_until_personas_load() {
+        let mut chat = Conversation::new();
+        assert!(chat.ask("hello").is_none());
+        assert_eq!(chat.take_queued().as_deref(), Some("hello"));
+    }
+
+    #[test]
+    fn test_streamed_reply() {
+        let mut chat = conversation();
+        assert!(chat.ask("hello").is_some());
+        assert!(chat.is_streaming());
+        assert!(chat.ask("again").is_none());
+
+        chat.apply(StreamEvent::Token("Hi".into()));
+        chat.apply(StreamEvent::Token(" there".into()));
+        chat.apply(StreamEvent::Done);
+
+        assert!(!chat.is_streaming());
+        assert_eq!(chat.messages().last(), Some(&ChatMessage::new(Role::Persona, "Hi there")));
+    }
+
+    #[test]
+    fn test_failed_reply_replaces_empty_message() {
+        let mut chat = conversation();
+        chat.ask("hello");
+        chat.apply(StreamEvent::Failed("unavailable".into()));
+
+        let roles: Vec<Role> = chat.messages().iter().map(|m| m.role).collect();
+        assert_eq!(roles, vec![Role::User, Role::Error]);
+    }
+
+    #[test]
+    fn test_history_comes_before_current_messages() {
+        let mut chat = conversation();
+        let id = chat.persona().unwrap().id;
+        chat.ask("new");
+
+        chat.load_history(
+            id,
+            vec![
+                MemoryEntry::user_message("old".into()),
+                MemoryEntry::persona_response("reply".into()),
+                MemoryEntry::fact("not chat".into(), 0.5),
+            ],
+        );
+
+        let texts: Vec<&str> = chat.messages().iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["old", "reply", "new", ""]);
+    }
+
+    #[test]
+    fn test_run_needs_capability_and_confirmation() {
+        let mut chat = Conversation::new();
+        let mut personas = builtin::all();
+        personas[0].capabilities.can_execute_commands = false;
+        chat.set_personas(personas.clone());
+        assert!(!chat.request_run("ls".into()));
+        assert_eq!(chat.pending(), None);
+
+        personas[0].capabilities.can_execute_commands = true;
+        chat.set_personas(personas);
+        assert!(chat.request_run("ls".into()));
+        assert_eq!(chat.pending(), Some("ls"));
+        assert_eq!(chat.confirm_run().as_deref(), Some("ls"));
+        assert_eq!(chat.pending(), None);
+    }
+}
@@ -43,6 +43,10 @@ pub enum CommandKind {
     Recent,
     /// Settings page
     Settings,
+    /// Clipboard history entry
+    Clipboard,
+    /// Text snippet
+    Snippet,
 }
 
 impl CommandResult {
@@ -135,6 +139,34 @@ impl CommandResult {
         }
     }
 
+    /// Create a clipboard history entry
+    pub fn clipboard(id: u64, preview: impl Into<String>) -> Self {
+        Self {
+            id: format!("clip:{}", id),
+            title: preview.into(),
+            subtitle: Some("Clipboard".to_string()),
+            icon: "󰅌".to_string(),
+            kind: CommandKind::Clipboard,
+            keywords: vec![],
+            score: 0,
+        }
+    }
+
+    /// Create a text snippet command
+    pub fn snippet(name: impl Into<String>, text: impl Into<String>) -> Self {
+        let name = name.into();
+        let text: String = text.into();
+        Self {
+            id: format!("snippet:{}", name),
+            title: name,
+            subtitle: Some(text.lines().next().unwrap_or_default().to_string()),
+            icon: "󰅪".to_string(),
+            kind: CommandKind::Snippet,
+            keywords: vec![],
+            score: 0,
+        }
+    }
+
     /// Add keywords for search
     pub fn with_keywords(mut self, keywords: Vec<String>) -> Self {
         self.keywords = keywords;
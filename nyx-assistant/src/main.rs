@@ -5,17 +5,30 @@
 //! - Natural language commands
 //! - File and folder search
 //! - Calculator
-//! - System commands
+//! - System commands (power, services, settings deep links)
 //! - AI-powered suggestions
+//! - Conversations with grimoire personas
+//! - Opt-in clipboard history and text snippets
+//!
+//! The assistant runs as a single background instance. Bind a compositor
+//! shortcut to `nyx-assistant --toggle` to summon and dismiss it.
 
+mod actions;
+mod activation;
 mod app;
+mod chat;
+mod clipboard;
 mod commands;
 mod search;
+mod snippets;
 
+use activation::{Activation, Instance};
 use app::NyxAssistant;
 use iced::Application;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+const USAGE: &str = "Usage: nyx-assistant [--toggle | --show | --hide | --background]";
+
 fn main() -> iced::Result {
     // Initialize logging
     tracing_subscriber::registry()
@@ -26,6 +39,39 @@ fn main() -> iced::Result {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
+    // What to ask of an instance that is already running
+    let mut request = Activation::Show;
+    let mut background = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--toggle" => request = Activation::Toggle,
+            "--show" => request = Activation::Show,
+            "--hide" => request = Activation::Hide,
+            "--background" => background = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ => {
+                eprintln!("Unknown argument: {}\n{}", arg, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let socket = activation::socket_path();
+    let listener = match activation::acquire(&socket, request) {
+        Ok(Instance::Primary(listener)) => listener,
+        Ok(Instance::Forwarded) => return Ok(()),
+        Err(e) => {
+            eprintln!("Cannot claim activation socket {:?}: {}", socket, e);
+            std::process::exit(1);
+        }
+    };
+
+    // Started by --hide or --background: wait in the background for a summon
+    let visible = !background && request != Activation::Hide;
+
     tracing::info!("Starting Nyx Assistant");
 
     // Calculate center position
@@ -41,9 +87,11 @@ fn main() -> iced::Result {
             transparent: true,
             level: iced::window::Level::AlwaysOnTop,
             resizable: false,
+            visible,
             ..Default::default()
         },
         antialiasing: true,
+        flags: app::Flags { listener, visible },
         ..Default::default()
     })
 }
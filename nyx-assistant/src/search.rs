@@ -1,17 +1,27 @@
 //! Search functionality for Nyx Assistant
 
+use crate::actions::SystemAction;
+use crate::clipboard::ClipboardHistory;
 use crate::commands::{
     evaluate_expression, sample_applications, system_commands, CommandKind, CommandResult,
 };
 use fuzzy_matcher::skim::SkimMatcherV2;
+use crate::snippets::Snippet;
 use fuzzy_matcher::FuzzyMatcher;
 
+/// Queries that list the whole clipboard history
+const CLIPBOARD_KEYWORDS: &[&str] = &["clip", "clipboard", "history"];
+
 /// Search engine for the assistant
 pub struct SearchEngine {
     /// Fuzzy matcher
     matcher: SkimMatcherV2,
     /// All available commands
     commands: Vec<CommandResult>,
+    /// Clipboard history (empty unless enabled)
+    clipboard: ClipboardHistory,
+    /// User-defined snippets
+    snippets: Vec<Snippet>,
 }
 
 impl Default for SearchEngine {
@@ -34,6 +44,8 @@ impl SearchEngine {
         Self {
             matcher: SkimMatcherV2::default(),
             commands,
+            clipboard: ClipboardHistory::default(),
+            snippets: Vec::new(),
         }
     }
 
@@ -56,10 +68,22 @@ impl SearchEngine {
             results.push(CommandResult::calculator(query, result_str));
         }
 
+        // Check if it's a system action phrase ("restart vesper", "suspend")
+        let action = SystemAction::parse(query);
+        if let Some(ref action) = action {
+            results.push(action.to_command());
+        }
+
         // Fuzzy search through commands
         let query_lower = query.to_lowercase();
 
-        for cmd in &self.commands {
+        let snippets: Vec<CommandResult> = self.snippets.iter().map(Snippet::to_command).collect();
+        for cmd in self.commands.iter().chain(&snippets) {
+            // Don't list a built-in command twice when the phrase matched it
+            if action.is_some() && SystemAction::from_id(&cmd.id) == action {
+                continue;
+            }
+
             let mut best_score = 0i64;
 
             // Match against title
@@ -86,6 +110,20 @@ impl SearchEngine {
             }
         }
 
+        // Clipboard history: everything for "clip", otherwise matching entries
+        let list_clipboard = CLIPBOARD_KEYWORDS.contains(&query_lower.trim());
+        for (age, entry) in self.clipboard.entries().enumerate() {
+            let score = if list_clipboard {
+                Some(500 - age as i64)
+            } else {
+                // Below commands so pasted text doesn't crowd out apps
+                self.matcher.fuzzy_match(&entry.text, query).map(|s| s / 2)
+            };
+            if let Some(score) = score.filter(|&s| s > 0) {
+                results.push(entry.to_command().with_score(score));
+            }
+        }
+
         // Sort by score (highest first)
         results.sort_by(|a, b| b.score.cmp(&a.score));
 
@@ -120,6 +158,26 @@ impl SearchEngine {
         self.commands.push(command);
     }
 
+    /// Clipboard history searched alongside commands
+    pub fn clipboard(&self) -> &ClipboardHistory {
+        &self.clipboard
+    }
+
+    pub fn clipboard_mut(&mut self) -> &mut ClipboardHistory {
+        &mut self.clipboard
+    }
+
+    /// Replace the user's snippets
+    pub fn set_snippets(&mut self, snippets: Vec<Snippet>) {
+        self.snippets = snippets;
+    }
+
+    /// Snippet for a palette entry id
+    pub fn snippet(&self, id: &str) -> Option<&Snippet> {
+        let name = id.strip_prefix("snippet:")?;
+        self.snippets.iter().find(|s| s.name == name)
+    }
+
     /// Refresh the command list
     pub fn refresh(&mut self) {
         // In a real implementation, this would rescan .desktop files
@@ -132,6 +190,7 @@ impl SearchEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clipboard::ClipboardSettings;
 
     // ═══════════════════════════════════════════════════════════════════════════
     // SEARCH ENGINE INITIALIZATION TESTS
@@ -384,4 +443,51 @@ mod tests {
         let results = engine.search("settings");
         assert!(results.iter().any(|r| r.title.to_lowercase().contains("settings")));
     }
+
+    #[test]
+    fn test_search_system_action() {
+        let engine = SearchEngine::new();
+        let results = engine.search("restart vesper");
+        assert_eq!(results[0].kind, CommandKind::System);
+        assert_eq!(results[0].title, "Restart vesper");
+
+        let results = engine.search("sleep");
+        assert_eq!(results[0].title, "Suspend");
+        assert!(!results.iter().any(|r| r.id == "sleep"));
+    }
+
+    #[test]
+    fn test_search_clipboard_history() {
+        let mut engine = SearchEngine::new();
+        engine.clipboard_mut().set_settings(ClipboardSettings {
+            enabled: true,
+            max_items: 10,
+        });
+        engine.clipboard_mut().record("first copied thing");
+        engine.clipboard_mut().record("second copied thing");
+
+        let results = engine.search("clip");
+        assert_eq!(results[0].kind, CommandKind::Clipboard);
+        assert_eq!(results[0].title, "second copied thing");
+        assert_eq!(results[1].title, "first copied thing");
+
+        let results = engine.search("first copied");
+        assert!(results.iter().any(|r| r.title == "first copied thing"));
+        assert!(!results.iter().any(|r| r.title == "second copied thing"));
+    }
+
+    #[test]
+    fn test_search_snippets() {
+        let mut engine = SearchEngine::new();
+        engine.set_snippets(vec![Snippet {
+            name: "Email signature".to_string(),
+            text: "Regards,\n{user}".to_string(),
+            keywords: vec!["sig".to_string()],
+        }]);
+
+        let results = engine.search("signature");
+        assert_eq!(results[0].kind, CommandKind::Snippet);
+        assert!(engine.snippet(&results[0].id).is_some());
+        assert!(engine.search("sig").iter().any(|r| r.kind == CommandKind::Snippet));
+    }
 }
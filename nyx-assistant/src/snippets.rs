@@ -0,0 +1,163 @@
+//! User-defined text snippets
+//!
+//! Snippets live in the `assistant.snippets` grimoire setting as a list of
+//! `{ "name": ..., "text": ... }` objects. Selecting one in the palette
+//! expands its placeholders and puts the result on the clipboard:
+//!
+//! - `{date}`, `{time}`, `{datetime}`: the current local date and time
+//! - `{clipboard}`: the current clipboard text
+//! - `{user}`: the login name
+//! - `{{` and `}}`: literal braces
+//!
+//! Unknown placeholders are left as written.
+
+use crate::commands::CommandResult;
+use chrono::{DateTime, Local};
+use grimoire_client::{ClientError, GrimoireClient};
+use serde::{Deserialize, Serialize};
+
+/// Grimoire setting holding the snippet list
+pub const SETTING_PATH: &str = "assistant.snippets";
+
+/// A named piece of text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snippet {
+    /// Name shown and searched in the palette
+    pub name: String,
+    /// Text with placeholders
+    pub text: String,
+    /// Extra search keywords
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+impl Snippet {
+    /// Palette entry for this snippet
+    pub fn to_command(&self) -> CommandResult {
+        CommandResult::snippet(&self.name, &self.text).with_keywords(self.keywords.clone())
+    }
+}
+
+/// Values substituted into snippet placeholders
+#[derive(Debug, Clone)]
+pub struct Context {
+    pub now: DateTime<Local>,
+    pub clipboard: Option<String>,
+    pub user: Option<String>,
+}
+
+impl Context {
+    /// Context for expanding a snippet right now
+    pub fn current(clipboard: Option<String>) -> Self {
+        Self {
+            now: Local::now(),
+            clipboard,
+            user: std::env::var("USER").ok(),
+        }
+    }
+}
+
+/// Expand placeholders in snippet text
+pub fn expand(text: &str, context: &Context) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(open) = rest.find(['{', '}']) {
+        out.push_str(&rest[..open]);
+        rest = &rest[open..];
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            out.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+
+        let placeholder = rest
+            .strip_prefix('{')
+            .and_then(|inner| inner.find('}').map(|close| &inner[..close]));
+        let value = placeholder.and_then(|name| match name {
+            "date" => Some(context.now.format("%Y-%m-%d").to_string()),
+            "time" => Some(context.now.format("%H:%M").to_string()),
+            "datetime" => Some(context.now.format("%Y-%m-%d %H:%M").to_string()),
+            "clipboard" => Some(context.clipboard.clone().unwrap_or_default()),
+            "user" => Some(context.user.clone().unwrap_or_default()),
+            _ => None,
+        });
+
+        match (placeholder, value) {
+            (Some(name), Some(value)) => {
+                out.push_str(&value);
+                rest = &rest[name.len() + 2..];
+            }
+            _ => {
+                out.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Snippets from grimoire; empty when unset
+pub async fn load() -> Result<Vec<Snippet>, String> {
+    let client = GrimoireClient::connect_default()
+        .await
+        .map_err(|e| e.to_string())?;
+    match client.get_setting(SETTING_PATH).await {
+        Ok(value) if value.is_null() => Ok(Vec::new()),
+        Ok(value) => serde_json::from_value(value).map_err(|e| e.to_string()),
+        Err(ClientError::NotFound(_)) => Ok(Vec::new()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn context() -> Context {
+        Context {
+            now: Local.with_ymd_and_hms(2025, 3, 14, 9, 26, 0).unwrap(),
+            clipboard: Some("pasted".to_string()),
+            user: Some("nyx".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_expand_placeholders() {
+        assert_eq!(
+            expand("{user} on {date} at {time}: {clipboard}", &context()),
+            "nyx on 2025-03-14 at 09:26: pasted"
+        );
+        assert_eq!(expand("{datetime}", &context()), "2025-03-14 09:26");
+    }
+
+    #[test]
+    fn test_expand_leaves_unknown_and_escaped_braces() {
+        assert_eq!(expand("{nope} {{date}} {", &context()), "{nope} {date} {");
+        assert_eq!(expand("fn main() {}", &context()), "fn main() {}");
+    }
+
+    #[test]
+    fn test_missing_clipboard_expands_empty() {
+        let context = Context {
+            clipboard: None,
+            ..context()
+        };
+        assert_eq!(expand("[{clipboard}]", &context), "[]");
+    }
+
+    #[test]
+    fn test_snippet_deserialize() {
+        let snippets: Vec<Snippet> = serde_json::from_str(
+            r#"[{"name": "Sign-off", "text": "Thanks,\n{user}"}]"#,
+        )
+        .unwrap();
+        assert_eq!(snippets[0].name, "Sign-off");
+        assert!(snippets[0].keywords.is_empty());
+        assert_eq!(snippets[0].to_command().id, "snippet:Sign-off");
+    }
+}
@@ -1,17 +1,28 @@
 //! Main application for Nyx Control
 
 use crate::controls::{
-    power_button, quick_toggle, section_header, settings_row, slider_control, ControlMessage,
-    PowerAction,
+    detail_pane, expand_button, pane_item, power_button, quick_toggle, section_header,
+    settings_row, slider_control, ControlMessage, PowerAction,
+};
+use crate::daemons::{self, AudioDetails, Change, DaemonState, WifiScan};
+use iced::widget::{
+    button, column, container, horizontal_rule, horizontal_space, row, scrollable, text,
+    text_input, vertical_space,
 };
-use iced::widget::{column, container, horizontal_rule, row, scrollable, text, vertical_space};
 use iced::{executor, Alignment, Application, Command, Element, Length, Subscription, Theme};
+use libnyx_ipc::network::WifiNetwork;
 use nyx_theme::colors::NyxColors;
 use nyx_theme::spacing::Spacing;
+use nyx_theme::widgets::button::{button_style, ButtonVariant};
+use nyx_theme::widgets::input::{input_style, InputVariant};
 use nyx_theme::widgets::panel::quick_settings_style;
+use nyx_theme::widgets::{segmented_control, StyleVariant};
 use nyx_theme::Typography;
 use std::time::Duration;
 
+/// How often daemon state is polled, so external changes show within a second
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 /// Control center state
 #[derive(Debug, Clone)]
 pub struct ControlState {
@@ -35,6 +46,10 @@ pub struct ControlState {
     pub wifi_network: Option<String>,
     /// Connected Bluetooth device
     pub bt_device: Option<String>,
+    /// Active power profile
+    pub power_profile: Option<String>,
+    /// Power profiles slumber offers
+    pub power_profiles: Vec<String>,
 }
 
 impl Default for ControlState {
@@ -50,14 +65,85 @@ impl Default for ControlState {
             brightness: 80,
             wifi_network: Some("Nyx-Network".to_string()),
             bt_device: None,
+            power_profile: None,
+            power_profiles: Vec::new(),
         }
     }
 }
 
+impl ControlState {
+    /// Take on the state daemons reported; unreachable daemons leave their controls as they are
+    pub fn apply(&mut self, daemons: DaemonState) {
+        if let Some(audio) = daemons.audio {
+            self.volume = audio.volume;
+            self.muted = audio.muted;
+            self.bluetooth = audio.bluetooth;
+            self.bt_device = audio.bt_device;
+        }
+        if let Some(network) = daemons.network {
+            self.wifi = network.wifi;
+            self.airplane = network.airplane;
+            self.wifi_network = network.wifi_network;
+            // Airplane mode blocks the Bluetooth radio whatever vesper thinks
+            if network.airplane {
+                self.bluetooth = false;
+                self.bt_device = None;
+            }
+        }
+        if let Some(display) = daemons.display {
+            if let Some(brightness) = display.brightness {
+                self.brightness = brightness;
+            }
+            self.night_light = display.night_light;
+        }
+        if let Some(dnd) = daemons.dnd {
+            self.dnd = dnd;
+        }
+        if let Some(power) = daemons.power {
+            self.power_profile = Some(power.current);
+            self.power_profiles = power.available;
+        }
+    }
+}
+
+/// Detail pane opened from a tile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Wifi,
+    Audio,
+}
+
+/// Wi-Fi network list
+#[derive(Debug, Clone, Default)]
+struct WifiPane {
+    /// Interface the last scan ran on
+    interface: Option<String>,
+    networks: Vec<WifiNetwork>,
+    scanning: bool,
+    /// Network waiting for a password
+    password_for: Option<String>,
+    password: String,
+    /// Network being joined or forgotten
+    busy: Option<String>,
+    error: Option<String>,
+}
+
 /// Main control center application
 pub struct NyxControl {
     /// Control state
     state: ControlState,
+    /// Changes sent to daemons and not yet answered
+    pending: usize,
+    /// A state poll is in flight
+    fetching: bool,
+    /// Open detail pane
+    expanded: Option<Pane>,
+    /// Wi-Fi pane state
+    wifi: WifiPane,
+    /// Audio pane state
+    audio: AudioDetails,
+    /// An audio pane read is in flight
+    fetching_audio: bool,
 }
 
 /// Application message
@@ -67,6 +153,16 @@ pub enum Message {
     Control(ControlMessage),
     /// Tick for updates
     Tick,
+    /// State read back from the daemons
+    StateLoaded(DaemonState),
+    /// A daemon answered a change
+    Applied(Result<(), String>),
+    /// Wi-Fi scan finished
+    WifiScanned(Result<WifiScan, String>),
+    /// wraith finished joining or forgetting a network
+    WifiDone(Result<(), String>),
+    /// Output devices and streams read back from vesper
+    AudioLoaded(Result<AudioDetails, String>),
     /// Close the control center
     Close,
 }
@@ -78,12 +174,9 @@ impl Application for NyxControl {
     type Flags = ();
 
     fn new(_flags: ()) -> (Self, Command<Message>) {
-        (
-            Self {
-                state: ControlState::default(),
-            },
-            Command::none(),
-        )
+        let mut control = Self::with_state(ControlState::default());
+        let poll = control.poll();
+        (control, poll)
     }
 
     fn title(&self) -> String {
@@ -96,8 +189,48 @@ impl Application for NyxControl {
 
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
-            Message::Control(ctrl_msg) => self.handle_control(ctrl_msg),
-            Message::Tick => {}
+            Message::Control(ctrl_msg) => return self.handle_control(ctrl_msg),
+            Message::Tick => return self.poll(),
+            Message::StateLoaded(daemons) => {
+                self.fetching = false;
+                // A reply still on its way would be undone by this older state
+                if self.pending == 0 {
+                    self.state.apply(daemons);
+                }
+            }
+            Message::Applied(result) => {
+                self.pending = self.pending.saturating_sub(1);
+                if let Err(error) = result {
+                    tracing::warn!("Control change failed: {}", error);
+                }
+            }
+            Message::WifiScanned(result) => {
+                self.wifi.scanning = false;
+                match result {
+                    Ok(scan) => {
+                        self.wifi.interface = Some(scan.interface);
+                        self.wifi.networks = scan.networks;
+                    }
+                    Err(error) => self.wifi.error = Some(error),
+                }
+            }
+            Message::WifiDone(result) => {
+                self.wifi.busy = None;
+                let refresh = Command::batch([self.scan_wifi(), self.poll()]);
+                if let Err(error) = result {
+                    self.wifi.error = Some(error);
+                }
+                return refresh;
+            }
+            Message::AudioLoaded(result) => {
+                self.fetching_audio = false;
+                match result {
+                    // Same rule as daemon state: don't undo a change in flight
+                    Ok(details) if self.pending == 0 => self.audio = details,
+                    Ok(_) => {}
+                    Err(error) => tracing::warn!("Audio details unavailable: {}", error),
+                }
+            }
             Message::Close => {
                 return iced::window::close(iced::window::Id::MAIN);
             }
@@ -106,7 +239,7 @@ impl Application for NyxControl {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        iced::time::every(Duration::from_secs(5)).map(|_| Message::Tick)
+        iced::time::every(POLL_INTERVAL).map(|_| Message::Tick)
     }
 
     fn view(&self) -> Element<Message> {
@@ -142,19 +275,103 @@ impl Application for NyxControl {
 }
 
 impl NyxControl {
-    fn handle_control(&mut self, msg: ControlMessage) {
+    fn with_state(state: ControlState) -> Self {
+        Self {
+            state,
+            pending: 0,
+            fetching: false,
+            expanded: None,
+            wifi: WifiPane::default(),
+            audio: AudioDetails::default(),
+            fetching_audio: false,
+        }
+    }
+
+    /// Read daemon state unless a read is already under way, and the open
+    /// audio pane's streams with it
+    fn poll(&mut self) -> Command<Message> {
+        let audio = if self.expanded == Some(Pane::Audio) {
+            self.fetch_audio()
+        } else {
+            Command::none()
+        };
+        if self.fetching {
+            return audio;
+        }
+        self.fetching = true;
+        Command::batch([
+            Command::perform(daemons::fetch_state(), Message::StateLoaded),
+            audio,
+        ])
+    }
+
+    fn fetch_audio(&mut self) -> Command<Message> {
+        if self.fetching_audio {
+            return Command::none();
+        }
+        self.fetching_audio = true;
+        Command::perform(daemons::fetch_audio_details(), Message::AudioLoaded)
+    }
+
+    fn scan_wifi(&mut self) -> Command<Message> {
+        if self.wifi.scanning {
+            return Command::none();
+        }
+        self.wifi.scanning = true;
+        self.wifi.error = None;
+        Command::perform(daemons::scan_wifi(), Message::WifiScanned)
+    }
+
+    /// Join a network on the scanned interface
+    fn connect_wifi(&mut self, ssid: String, password: Option<String>) -> Command<Message> {
+        let Some(interface) = self.wifi.interface.clone() else {
+            return Command::none();
+        };
+        self.wifi.busy = Some(ssid.clone());
+        self.wifi.error = None;
+        Command::perform(
+            daemons::connect_wifi(interface, ssid, password),
+            Message::WifiDone,
+        )
+    }
+
+    /// Open a pane, or close it if it is the one open
+    fn toggle_pane(&mut self, pane: Pane) -> Command<Message> {
+        if self.expanded == Some(pane) {
+            self.expanded = None;
+            return Command::none();
+        }
+        self.expanded = Some(pane);
+        match pane {
+            Pane::Wifi => self.scan_wifi(),
+            Pane::Audio => self.fetch_audio(),
+        }
+    }
+
+    /// Send changes to their daemons
+    fn send(&mut self, changes: impl IntoIterator<Item = Change>) -> Command<Message> {
+        Command::batch(changes.into_iter().map(|change| {
+            self.pending += 1;
+            Command::perform(daemons::apply(change), Message::Applied)
+        }))
+    }
+
+    /// Update the panel right away and ask the owning daemon to follow
+    fn handle_control(&mut self, msg: ControlMessage) -> Command<Message> {
         match msg {
             ControlMessage::ToggleWifi => {
                 self.state.wifi = !self.state.wifi;
                 if !self.state.wifi {
                     self.state.wifi_network = None;
                 }
+                return self.send([Change::Wifi(self.state.wifi)]);
             }
             ControlMessage::ToggleBluetooth => {
                 self.state.bluetooth = !self.state.bluetooth;
                 if !self.state.bluetooth {
                     self.state.bt_device = None;
                 }
+                return self.send([Change::Bluetooth(self.state.bluetooth)]);
             }
             ControlMessage::ToggleAirplane => {
                 self.state.airplane = !self.state.airplane;
@@ -162,45 +379,102 @@ impl NyxControl {
                     self.state.wifi = false;
                     self.state.bluetooth = false;
                 }
+                return self.send([Change::Airplane(self.state.airplane)]);
             }
             ControlMessage::ToggleNightLight => {
                 self.state.night_light = !self.state.night_light;
+                return self.send([Change::NightLight(self.state.night_light)]);
             }
             ControlMessage::ToggleDnd => {
                 self.state.dnd = !self.state.dnd;
+                return self.send([Change::Dnd(self.state.dnd)]);
             }
             ControlMessage::VolumeChanged(v) => {
                 self.state.volume = v;
-                if v > 0 {
+                let unmute = v > 0 && self.state.muted;
+                if unmute {
                     self.state.muted = false;
                 }
+                return self.send(
+                    std::iter::once(Change::Volume(v)).chain(unmute.then_some(Change::Mute(false))),
+                );
             }
             ControlMessage::BrightnessChanged(v) => {
                 self.state.brightness = v;
+                return self.send([Change::Brightness(v)]);
             }
             ControlMessage::ToggleMute => {
                 self.state.muted = !self.state.muted;
+                return self.send([Change::Mute(self.state.muted)]);
             }
-            ControlMessage::PowerAction(action) => {
-                tracing::info!("Power action: {:?}", action);
-                // In a real implementation, this would trigger system actions
+            ControlMessage::SetPowerProfile(name) => {
+                self.state.power_profile = Some(name.clone());
+                return self.send([Change::PowerProfile(name)]);
             }
-            ControlMessage::OpenSettings => {
-                tracing::info!("Opening settings");
+            ControlMessage::ToggleWifiPane => return self.toggle_pane(Pane::Wifi),
+            ControlMessage::ToggleAudioPane => return self.toggle_pane(Pane::Audio),
+            ControlMessage::RescanWifi => return self.scan_wifi(),
+            ControlMessage::SelectWifi(ssid) => {
+                let Some(network) = self.wifi.networks.iter().find(|n| n.ssid == ssid) else {
+                    return Command::none();
+                };
+                if network.is_secured() && !network.saved {
+                    self.wifi.password_for = Some(ssid);
+                    self.wifi.password.clear();
+                } else {
+                    self.wifi.password_for = None;
+                    return self.connect_wifi(ssid, None);
+                }
             }
-            ControlMessage::OpenWifiSettings => {
-                tracing::info!("Opening WiFi settings");
+            ControlMessage::WifiPasswordChanged(password) => {
+                self.wifi.password = password;
             }
-            ControlMessage::OpenBluetoothSettings => {
-                tracing::info!("Opening Bluetooth settings");
+            ControlMessage::ConnectWifi => {
+                if let Some(ssid) = self.wifi.password_for.take() {
+                    let password = std::mem::take(&mut self.wifi.password);
+                    return self.connect_wifi(ssid, Some(password));
+                }
             }
-            ControlMessage::OpenDisplaySettings => {
-                tracing::info!("Opening display settings");
+            ControlMessage::CancelWifi => {
+                self.wifi.password_for = None;
+                self.wifi.password.clear();
+            }
+            ControlMessage::ForgetWifi(ssid) => {
+                if let Some(interface) = self.wifi.interface.clone() {
+                    self.wifi.busy = Some(ssid.clone());
+                    self.wifi.error = None;
+                    return Command::perform(
+                        daemons::forget_wifi(interface, ssid),
+                        Message::WifiDone,
+                    );
+                }
+            }
+            ControlMessage::StreamVolumeChanged(id, volume) => {
+                if let Some(stream) = self.audio.streams.iter_mut().find(|s| s.id == id) {
+                    stream.volume = volume.into();
+                }
+                return self.send([Change::StreamVolume(id, volume)]);
+            }
+            ControlMessage::SelectOutput(name) => {
+                for device in &mut self.audio.outputs {
+                    device.is_default = device.name == name;
+                }
+                return self.send([Change::OutputDevice(name)]);
             }
-            ControlMessage::OpenSoundSettings => {
-                tracing::info!("Opening sound settings");
+            ControlMessage::PowerAction(PowerAction::Suspend) => {
+                return self.send([Change::Suspend]);
             }
+            ControlMessage::PowerAction(action) => {
+                tracing::info!("Power action: {:?}", action);
+                // In a real implementation, this would trigger system actions
+            }
+            ControlMessage::OpenSettings => open_settings(""),
+            ControlMessage::OpenWifiSettings => open_settings("network/wifi"),
+            ControlMessage::OpenBluetoothSettings => open_settings("bluetooth/devices"),
+            ControlMessage::OpenDisplaySettings => open_settings("display"),
+            ControlMessage::OpenSoundSettings => open_settings("sound/output"),
         }
+        Command::none()
     }
 
     fn view_header(&self) -> Element<Message> {
@@ -291,42 +565,211 @@ impl NyxControl {
             "󰃠"
         };
 
+        let audio_expanded = self.expanded == Some(Pane::Audio);
         column![
-            slider_control(volume_icon, "Volume", self.state.volume, |v| {
-                Message::Control(ControlMessage::VolumeChanged(v))
-            }),
+            row![
+                slider_control(volume_icon, "Volume", self.state.volume, |v| {
+                    Message::Control(ControlMessage::VolumeChanged(v))
+                }),
+                expand_button(
+                    audio_expanded,
+                    Message::Control(ControlMessage::ToggleAudioPane)
+                ),
+            ]
+            .spacing(Spacing::XS)
+            .align_y(Alignment::Center),
+        ]
+        .push_maybe(audio_expanded.then(|| self.view_audio_pane()))
+        .push(
             slider_control(
                 brightness_icon,
                 "Brightness",
                 self.state.brightness,
                 |v| { Message::Control(ControlMessage::BrightnessChanged(v)) }
             ),
-        ]
+        )
         .spacing(Spacing::SM)
         .into()
     }
 
+    fn view_audio_pane(&self) -> Element<Message> {
+        let mut content = column![text("Output")
+            .size(Typography::SIZE_LABEL_SMALL)
+            .color(NyxColors::TEXT_MUTED)]
+        .spacing(Spacing::XS);
+
+        for device in &self.audio.outputs {
+            content = content.push(pane_item(
+                "󰓃",
+                &device.description,
+                None,
+                device.is_default,
+                (!device.is_default)
+                    .then(|| Message::Control(ControlMessage::SelectOutput(device.name.clone()))),
+                None,
+            ));
+        }
+
+        content = content.push(
+            text("Applications")
+                .size(Typography::SIZE_LABEL_SMALL)
+                .color(NyxColors::TEXT_MUTED),
+        );
+        if self.audio.streams.is_empty() {
+            content = content.push(
+                text("Nothing is playing")
+                    .size(Typography::SIZE_BODY_SMALL)
+                    .color(NyxColors::TEXT_SECONDARY),
+            );
+        }
+        for stream in &self.audio.streams {
+            let id = stream.id;
+            content = content.push(slider_control(
+                if stream.muted { "󰖁" } else { "󰝚" },
+                &stream.app_name,
+                stream.volume.min(100) as u8,
+                move |v| Message::Control(ControlMessage::StreamVolumeChanged(id, v)),
+            ));
+        }
+
+        content = content.push(pane_link(
+            "Sound settings",
+            Message::Control(ControlMessage::OpenSoundSettings),
+        ));
+        detail_pane(content)
+    }
+
+    fn view_wifi_pane(&self) -> Element<Message> {
+        let rescan = button(text("󰑐").size(Typography::SIZE_ICON_SM))
+            .style(button_style(ButtonVariant::Ghost))
+            .on_press_maybe(
+                (!self.wifi.scanning).then_some(Message::Control(ControlMessage::RescanWifi)),
+            );
+        let mut content = column![row![
+            text(if self.wifi.scanning { "Scanning…" } else { "Networks" })
+                .size(Typography::SIZE_LABEL_SMALL)
+                .color(NyxColors::TEXT_MUTED),
+            horizontal_space(),
+            rescan,
+        ]
+        .align_y(Alignment::Center)]
+        .spacing(Spacing::XS);
+
+        if let Some(error) = &self.wifi.error {
+            content = content.push(
+                text(error)
+                    .size(Typography::SIZE_LABEL_SMALL)
+                    .color(NyxColors::ERROR),
+            );
+        }
+        if !self.state.wifi {
+            content = content.push(
+                text("WiFi is off")
+                    .size(Typography::SIZE_BODY_SMALL)
+                    .color(NyxColors::TEXT_SECONDARY),
+            );
+        }
+
+        for network in self.wifi.networks.iter().filter(|_| self.state.wifi) {
+            let busy = self.wifi.busy.as_deref() == Some(network.ssid.as_str());
+            let detail = if busy {
+                Some("Working…")
+            } else if network.connected {
+                Some("Connected")
+            } else if network.saved {
+                Some("Saved")
+            } else {
+                None
+            };
+            let idle = self.wifi.busy.is_none();
+            content = content.push(pane_item(
+                wifi_icon(network),
+                &network.ssid,
+                detail,
+                network.connected,
+                (idle && !network.connected)
+                    .then(|| Message::Control(ControlMessage::SelectWifi(network.ssid.clone()))),
+                (idle && network.saved).then(|| {
+                    ("󰆴", Message::Control(ControlMessage::ForgetWifi(network.ssid.clone())))
+                }),
+            ));
+
+            if self.wifi.password_for.as_deref() == Some(network.ssid.as_str()) {
+                content = content.push(
+                    row![
+                        text_input("Password", &self.wifi.password)
+                            .secure(true)
+                            .on_input(|p| Message::Control(ControlMessage::WifiPasswordChanged(p)))
+                            .on_submit(Message::Control(ControlMessage::ConnectWifi))
+                            .style(input_style(InputVariant::Filled))
+                            .padding(Spacing::SM),
+                        button(text("Join").size(Typography::SIZE_LABEL_MEDIUM))
+                            .style(button_style(ButtonVariant::Primary))
+                            .on_press_maybe(
+                                (!self.wifi.password.is_empty())
+                                    .then_some(Message::Control(ControlMessage::ConnectWifi)),
+                            ),
+                        button(text("Cancel").size(Typography::SIZE_LABEL_MEDIUM))
+                            .style(button_style(ButtonVariant::Ghost))
+                            .on_press(Message::Control(ControlMessage::CancelWifi)),
+                    ]
+                    .spacing(Spacing::XS)
+                    .align_y(Alignment::Center),
+                );
+            }
+        }
+
+        content = content.push(pane_link(
+            "Network settings",
+            Message::Control(ControlMessage::OpenWifiSettings),
+        ));
+        detail_pane(content)
+    }
+
     fn view_network_section(&self) -> Element<Message> {
         column![
             settings_row(
                 "󰤨",
                 "WiFi",
                 self.state.wifi_network.as_deref(),
-                Message::Control(ControlMessage::OpenWifiSettings)
+                Message::Control(ControlMessage::ToggleWifiPane)
             ),
+        ]
+        .push_maybe((self.expanded == Some(Pane::Wifi)).then(|| self.view_wifi_pane()))
+        .push(
             settings_row(
                 "󰂯",
                 "Bluetooth",
                 self.state.bt_device.as_deref(),
                 Message::Control(ControlMessage::OpenBluetoothSettings)
             ),
-        ]
+        )
         .spacing(Spacing::XS)
         .into()
     }
 
     fn view_power_section(&self) -> Element<Message> {
-        row![
+        let profiles: Vec<(String, &str)> = self
+            .state
+            .power_profiles
+            .iter()
+            .map(|name| (name.clone(), name.as_str()))
+            .collect();
+        let profile_picker = self
+            .state
+            .power_profile
+            .as_ref()
+            .filter(|_| !profiles.is_empty())
+            .map(|current| {
+                segmented_control(
+                    &profiles,
+                    current,
+                    |name| Message::Control(ControlMessage::SetPowerProfile(name)),
+                    StyleVariant::default(),
+                )
+            });
+
+        let buttons = row![
             power_button(
                 "󰌾",
                 "Lock",
@@ -348,14 +791,53 @@ impl NyxControl {
                 Message::Control(ControlMessage::PowerAction(PowerAction::Shutdown))
             ),
         ]
-        .spacing(Spacing::SM)
-        .into()
+        .spacing(Spacing::SM);
+
+        column![]
+            .push_maybe(profile_picker)
+            .push(buttons)
+            .spacing(Spacing::SM)
+            .into()
+    }
+}
+
+/// Launch nyx-settings at a `nyx-settings://` link path, e.g. `network/wifi`
+fn open_settings(link: &str) {
+    tracing::info!("Opening settings at {}", link);
+    if let Err(e) = std::process::Command::new("nyx-settings")
+        .arg(format!("nyx-settings://{}", link))
+        .spawn()
+    {
+        tracing::warn!("Failed to open settings: {}", e);
+    }
+}
+
+/// Signal bars for a scanned network
+fn wifi_icon(network: &WifiNetwork) -> &'static str {
+    match network.signal_percent() {
+        0..=25 => "󰤟",
+        26..=50 => "󰤢",
+        51..=75 => "󰤥",
+        _ => "󰤨",
     }
 }
 
+/// Text button at the foot of a detail pane
+fn pane_link(label: &str, on_press: Message) -> Element<Message> {
+    button(
+        text(label)
+            .size(Typography::SIZE_LABEL_MEDIUM)
+            .color(NyxColors::AURORA_LIGHT),
+    )
+    .style(button_style(ButtonVariant::Ghost))
+    .on_press(on_press)
+    .into()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use libnyx_ipc::audio::{AudioDevice, AudioStream};
 
     // ═══════════════════════════════════════════════════════════════════════════
     // CONTROL STATE DEFAULT TESTS
@@ -411,9 +893,7 @@ mod tests {
 
     #[test]
     fn test_toggle_wifi_off() {
-        let mut app = NyxControl {
-            state: ControlState::default(),
-        };
+        let mut app = NyxControl::with_state(ControlState::default());
         assert!(app.state.wifi);
         assert!(app.state.wifi_network.is_some());
 
@@ -425,13 +905,11 @@ mod tests {
 
     #[test]
     fn test_toggle_wifi_on() {
-        let mut app = NyxControl {
-            state: ControlState {
-                wifi: false,
-                wifi_network: None,
-                ..Default::default()
-            },
-        };
+        let mut app = NyxControl::with_state(ControlState {
+            wifi: false,
+            wifi_network: None,
+            ..Default::default()
+        });
 
         app.handle_control(ControlMessage::ToggleWifi);
 
@@ -440,13 +918,11 @@ mod tests {
 
     #[test]
     fn test_toggle_bluetooth_off() {
-        let mut app = NyxControl {
-            state: ControlState {
-                bluetooth: true,
-                bt_device: Some("Device".to_string()),
-                ..Default::default()
-            },
-        };
+        let mut app = NyxControl::with_state(ControlState {
+            bluetooth: true,
+            bt_device: Some("Device".to_string()),
+            ..Default::default()
+        });
 
         app.handle_control(ControlMessage::ToggleBluetooth);
 
@@ -456,13 +932,11 @@ mod tests {
 
     #[test]
     fn test_toggle_airplane_enables_radio_disable() {
-        let mut app = NyxControl {
-            state: ControlState {
-                wifi: true,
-                bluetooth: true,
-                ..Default::default()
-            },
-        };
+        let mut app = NyxControl::with_state(ControlState {
+            wifi: true,
+            bluetooth: true,
+            ..Default::default()
+        });
 
         app.handle_control(ControlMessage::ToggleAirplane);
 
@@ -473,9 +947,7 @@ mod tests {
 
     #[test]
     fn test_toggle_night_light() {
-        let mut app = NyxControl {
-            state: ControlState::default(),
-        };
+        let mut app = NyxControl::with_state(ControlState::default());
         assert!(!app.state.night_light);
 
         app.handle_control(ControlMessage::ToggleNightLight);
@@ -489,9 +961,7 @@ mod tests {
 
     #[test]
     fn test_toggle_dnd() {
-        let mut app = NyxControl {
-            state: ControlState::default(),
-        };
+        let mut app = NyxControl::with_state(ControlState::default());
         assert!(!app.state.dnd);
 
         app.handle_control(ControlMessage::ToggleDnd);
@@ -501,9 +971,7 @@ mod tests {
 
     #[test]
     fn test_toggle_mute() {
-        let mut app = NyxControl {
-            state: ControlState::default(),
-        };
+        let mut app = NyxControl::with_state(ControlState::default());
         assert!(!app.state.muted);
 
         app.handle_control(ControlMessage::ToggleMute);
@@ -517,9 +985,7 @@ mod tests {
 
     #[test]
     fn test_volume_change() {
-        let mut app = NyxControl {
-            state: ControlState::default(),
-        };
+        let mut app = NyxControl::with_state(ControlState::default());
 
         app.handle_control(ControlMessage::VolumeChanged(50));
 
@@ -528,12 +994,10 @@ mod tests {
 
     #[test]
     fn test_volume_change_unmutes() {
-        let mut app = NyxControl {
-            state: ControlState {
-                muted: true,
-                ..Default::default()
-            },
-        };
+        let mut app = NyxControl::with_state(ControlState {
+            muted: true,
+            ..Default::default()
+        });
 
         app.handle_control(ControlMessage::VolumeChanged(50));
 
@@ -542,12 +1006,10 @@ mod tests {
 
     #[test]
     fn test_volume_zero_keeps_mute() {
-        let mut app = NyxControl {
-            state: ControlState {
-                muted: true,
-                ..Default::default()
-            },
-        };
+        let mut app = NyxControl::with_state(ControlState {
+            muted: true,
+            ..Default::default()
+        });
 
         app.handle_control(ControlMessage::VolumeChanged(0));
 
@@ -556,9 +1018,7 @@ mod tests {
 
     #[test]
     fn test_brightness_change() {
-        let mut app = NyxControl {
-            state: ControlState::default(),
-        };
+        let mut app = NyxControl::with_state(ControlState::default());
 
         app.handle_control(ControlMessage::BrightnessChanged(100));
 
@@ -567,9 +1027,7 @@ mod tests {
 
     #[test]
     fn test_brightness_range() {
-        let mut app = NyxControl {
-            state: ControlState::default(),
-        };
+        let mut app = NyxControl::with_state(ControlState::default());
 
         // Test minimum
         app.handle_control(ControlMessage::BrightnessChanged(0));
@@ -580,6 +1038,229 @@ mod tests {
         assert_eq!(app.state.brightness, 100);
     }
 
+    // ═══════════════════════════════════════════════════════════════════════════
+    // DAEMON STATE TESTS
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    #[test]
+    fn test_apply_daemon_state() {
+        let mut state = ControlState::default();
+        state.apply(DaemonState {
+            audio: Some(daemons::AudioState {
+                volume: 30,
+                muted: true,
+                bluetooth: true,
+                bt_device: Some("Headphones".to_string()),
+            }),
+            dnd: Some(true),
+            power: Some(daemons::PowerProfiles {
+                current: "powersave".to_string(),
+                available: vec!["balanced".to_string(), "powersave".to_string()],
+            }),
+            ..Default::default()
+        });
+
+        assert_eq!(state.volume, 30);
+        assert!(state.muted);
+        assert_eq!(state.bt_device.as_deref(), Some("Headphones"));
+        assert!(state.dnd);
+        assert_eq!(state.power_profile.as_deref(), Some("powersave"));
+        // wraith and iris didn't answer: their controls are untouched
+        assert!(state.wifi);
+        assert_eq!(state.brightness, 80);
+    }
+
+    #[test]
+    fn test_airplane_state_overrides_bluetooth() {
+        let mut state = ControlState::default();
+        state.apply(DaemonState {
+            network: Some(daemons::NetworkState {
+                wifi: false,
+                airplane: true,
+                wifi_network: None,
+            }),
+            audio: Some(daemons::AudioState {
+                volume: 50,
+                muted: false,
+                bluetooth: true,
+                bt_device: None,
+            }),
+            ..Default::default()
+        });
+
+        assert!(state.airplane);
+        assert!(!state.bluetooth);
+    }
+
+    #[test]
+    fn test_stale_state_ignored_while_change_pending() {
+        let mut app = NyxControl::with_state(ControlState::default());
+        let _ = app.handle_control(ControlMessage::ToggleDnd);
+        assert_eq!(app.pending, 1);
+
+        let _ = app.update(Message::StateLoaded(DaemonState {
+            dnd: Some(false),
+            ..Default::default()
+        }));
+        assert!(app.state.dnd);
+
+        let _ = app.update(Message::Applied(Ok(())));
+        let _ = app.update(Message::StateLoaded(DaemonState {
+            dnd: Some(false),
+            ..Default::default()
+        }));
+        assert!(!app.state.dnd);
+    }
+
+    #[test]
+    fn test_volume_change_sends_unmute() {
+        let mut app = NyxControl::with_state(ControlState {
+            muted: true,
+            ..Default::default()
+        });
+
+        let _ = app.handle_control(ControlMessage::VolumeChanged(40));
+
+        assert_eq!(app.pending, 2);
+    }
+
+    // ═══════════════════════════════════════════════════════════════════════════
+    // DETAIL PANE TESTS
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    fn network(ssid: &str, security: &str, saved: bool) -> WifiNetwork {
+        WifiNetwork {
+            ssid: ssid.to_string(),
+            signal: -60,
+            security: security.to_string(),
+            connected: false,
+            saved,
+        }
+    }
+
+    fn scanned_app() -> NyxControl {
+        let mut app = NyxControl::with_state(ControlState::default());
+        let _ = app.handle_control(ControlMessage::ToggleWifiPane);
+        let _ = app.update(Message::WifiScanned(Ok(WifiScan {
+            interface: "wlan0".to_string(),
+            networks: vec![
+                network("Home", "Wpa2Psk", true),
+                network("Cafe", "Wpa2Psk", false),
+                network("Library", "Open", false),
+            ],
+        })));
+        app
+    }
+
+    #[test]
+    fn test_wifi_pane_toggle_scans() {
+        let mut app = NyxControl::with_state(ControlState::default());
+
+        let _ = app.handle_control(ControlMessage::ToggleWifiPane);
+        assert_eq!(app.expanded, Some(Pane::Wifi));
+        assert!(app.wifi.scanning);
+
+        let _ = app.handle_control(ControlMessage::ToggleWifiPane);
+        assert_eq!(app.expanded, None);
+    }
+
+    #[test]
+    fn test_secured_network_asks_for_password() {
+        let mut app = scanned_app();
+
+        let _ = app.handle_control(ControlMessage::SelectWifi("Cafe".to_string()));
+        assert_eq!(app.wifi.password_for.as_deref(), Some("Cafe"));
+        assert!(app.wifi.busy.is_none());
+
+        let _ = app.handle_control(ControlMessage::WifiPasswordChanged("hunter22".to_string()));
+        let _ = app.handle_control(ControlMessage::ConnectWifi);
+        assert_eq!(app.wifi.busy.as_deref(), Some("Cafe"));
+        assert!(app.wifi.password_for.is_none());
+        assert!(app.wifi.password.is_empty());
+    }
+
+    #[test]
+    fn test_saved_and_open_networks_connect_directly() {
+        let mut app = scanned_app();
+
+        let _ = app.handle_control(ControlMessage::SelectWifi("Home".to_string()));
+        assert_eq!(app.wifi.busy.as_deref(), Some("Home"));
+        assert!(app.wifi.password_for.is_none());
+
+        let _ = app.update(Message::WifiDone(Ok(())));
+        let _ = app.handle_control(ControlMessage::SelectWifi("Library".to_string()));
+        assert_eq!(app.wifi.busy.as_deref(), Some("Library"));
+    }
+
+    #[test]
+    fn test_wifi_failure_is_shown() {
+        let mut app = scanned_app();
+        let _ = app.handle_control(ControlMessage::ForgetWifi("Home".to_string()));
+        assert_eq!(app.wifi.busy.as_deref(), Some("Home"));
+
+        let _ = app.update(Message::WifiDone(Err("No such network".to_string())));
+
+        assert!(app.wifi.busy.is_none());
+        assert_eq!(app.wifi.error.as_deref(), Some("No such network"));
+    }
+
+    fn audio_details() -> AudioDetails {
+        let device = |name: &str, is_default| AudioDevice {
+            name: name.to_string(),
+            description: name.to_string(),
+            device_type: "playback".to_string(),
+            state: "running".to_string(),
+            is_default,
+        };
+        AudioDetails {
+            outputs: vec![device("speakers", true), device("headphones", false)],
+            streams: vec![AudioStream {
+                id: 7,
+                name: "playback".to_string(),
+                app_name: "Firefox".to_string(),
+                pid: None,
+                direction: "playback".to_string(),
+                state: "running".to_string(),
+                volume: 100,
+                muted: false,
+                sink: "speakers".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_stream_volume_change() {
+        let mut app = NyxControl::with_state(ControlState::default());
+        let _ = app.handle_control(ControlMessage::ToggleAudioPane);
+        let _ = app.update(Message::AudioLoaded(Ok(audio_details())));
+
+        let _ = app.handle_control(ControlMessage::StreamVolumeChanged(7, 40));
+
+        assert_eq!(app.audio.streams[0].volume, 40);
+        assert_eq!(app.pending, 1);
+
+        // vesper hasn't answered yet: an older read must not undo the slider
+        let _ = app.update(Message::AudioLoaded(Ok(audio_details())));
+        assert_eq!(app.audio.streams[0].volume, 40);
+    }
+
+    #[test]
+    fn test_select_output() {
+        let mut app = NyxControl::with_state(ControlState::default());
+        let _ = app.update(Message::AudioLoaded(Ok(audio_details())));
+
+        let _ = app.handle_control(ControlMessage::SelectOutput("headphones".to_string()));
+
+        let defaults: Vec<_> = app
+            .audio
+            .outputs
+            .iter()
+            .filter(|d| d.is_default)
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(defaults, ["headphones"]);
+    }
+
     // ═══════════════════════════════════════════════════════════════════════════
     // APPLICATION TESTS
     // ═══════════════════════════════════════════════════════════════════════════
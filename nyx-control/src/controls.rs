@@ -27,6 +27,28 @@ pub enum ControlMessage {
     BrightnessChanged(u8),
     /// Toggle volume mute
     ToggleMute,
+    /// Switch power profile
+    SetPowerProfile(String),
+    /// Open or close the Wi-Fi network list
+    ToggleWifiPane,
+    /// Open or close per-app volume and output selection
+    ToggleAudioPane,
+    /// Scan for Wi-Fi networks again
+    RescanWifi,
+    /// Join a network by SSID, asking for a password if it needs one
+    SelectWifi(String),
+    /// Wi-Fi password typed
+    WifiPasswordChanged(String),
+    /// Join the network the password is for
+    ConnectWifi,
+    /// Dismiss the password prompt
+    CancelWifi,
+    /// Forget a saved network
+    ForgetWifi(String),
+    /// Application stream volume changed
+    StreamVolumeChanged(u32, u8),
+    /// Play through an output device
+    SelectOutput(String),
     /// Power action
     PowerAction(PowerAction),
     /// Open settings
@@ -235,6 +257,89 @@ where
     .into()
 }
 
+/// Chevron that opens or closes a detail pane
+pub fn expand_button<'a, Message>(expanded: bool, on_press: Message) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    button(
+        text(if expanded { "󰅃" } else { "󰅀" })
+            .size(Typography::SIZE_ICON_SM)
+            .color(NyxColors::TEXT_SECONDARY),
+    )
+    .style(button_style(ButtonVariant::Ghost))
+    .on_press(on_press)
+    .into()
+}
+
+/// Drill-down pane below a tile
+pub fn detail_pane<'a, Message: 'a>(content: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    container(content)
+        .width(Length::Fill)
+        .padding(Spacing::SM)
+        .style(|_theme| iced::widget::container::Style {
+            background: Some(iced::Background::Color(NyxColors::TWILIGHT)),
+            border: iced::Border {
+                color: NyxColors::BORDER_DARK,
+                width: 1.0,
+                radius: Spacing::RADIUS_MD.into(),
+            },
+            ..Default::default()
+        })
+        .into()
+}
+
+/// Selectable row in a detail pane, with an optional trailing action
+pub fn pane_item<'a, Message>(
+    icon: &'a str,
+    label: &'a str,
+    detail: Option<&'a str>,
+    selected: bool,
+    on_press: Option<Message>,
+    action: Option<(&'a str, Message)>,
+) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    let color = if selected {
+        NyxColors::AURORA_LIGHT
+    } else {
+        NyxColors::TEXT_BRIGHT
+    };
+    let item = button(
+        row![
+            text(icon).size(Typography::SIZE_ICON_SM).color(color),
+            text(label)
+                .size(Typography::SIZE_BODY_MEDIUM)
+                .color(color)
+                .width(Length::Fill),
+        ]
+        .push_maybe(detail.map(|detail| {
+            text(detail)
+                .size(Typography::SIZE_LABEL_SMALL)
+                .color(NyxColors::TEXT_SECONDARY)
+        }))
+        .spacing(Spacing::SM)
+        .align_y(Alignment::Center),
+    )
+    .width(Length::Fill)
+    .style(button_style(ButtonVariant::Ghost))
+    .on_press_maybe(on_press);
+
+    row![item]
+        .push_maybe(action.map(|(icon, on_press)| {
+            button(
+                text(icon)
+                    .size(Typography::SIZE_ICON_SM)
+                    .color(NyxColors::TEXT_MUTED),
+            )
+            .style(button_style(ButtonVariant::Ghost))
+            .on_press(on_press)
+        }))
+        .align_y(Alignment::Center)
+        .into()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
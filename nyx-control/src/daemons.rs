@@ -0,0 +1,269 @@
+//! Daemon bindings for Nyx Control
+//!
+//! Every control maps onto a request to the daemon that owns it, sent as
+//! one line of JSON over the daemon's socket:
+//!
+//! - Wi-Fi and airplane mode: wraith
+//! - Volume, mute and Bluetooth: vesper
+//! - Brightness and night light: iris
+//! - Do Not Disturb: herald
+//! - Power profile and suspend: slumber
+//!
+//! State is read back by polling each daemon's status request, so changes
+//! made elsewhere (keyboard brightness keys, `wraithctl`, a DND schedule)
+//! show up in the panel.
+//!
+//! The protocol lives in libnyx-ipc, shared with Settings; this module only
+//! turns its replies into panel state.
+
+use libnyx_ipc::audio::{AudioDevice, AudioStream};
+use libnyx_ipc::network::{NetworkStatus, WifiNetwork};
+use libnyx_ipc::{
+    paths, AudioClient, DisplayClient, HelloClient, NetworkClient, NotificationsClient, PowerClient,
+};
+
+/// Network state reported by wraith
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkState {
+    pub wifi: bool,
+    pub airplane: bool,
+    /// Wireless interface that is up with an address
+    pub wifi_network: Option<String>,
+}
+
+/// Audio state reported by vesper
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioState {
+    pub volume: u8,
+    pub muted: bool,
+    pub bluetooth: bool,
+    pub bt_device: Option<String>,
+}
+
+/// Display state reported by iris
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayState {
+    /// None without a backlight (desktop monitors)
+    pub brightness: Option<u8>,
+    pub night_light: bool,
+}
+
+/// Power profiles reported by slumber
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowerProfiles {
+    pub current: String,
+    pub available: Vec<String>,
+}
+
+/// Snapshot of every daemon; None where a daemon didn't answer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DaemonState {
+    pub network: Option<NetworkState>,
+    pub audio: Option<AudioState>,
+    pub display: Option<DisplayState>,
+    pub dnd: Option<bool>,
+    pub power: Option<PowerProfiles>,
+}
+
+/// A change requested from the panel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Wifi(bool),
+    Airplane(bool),
+    Bluetooth(bool),
+    Volume(u8),
+    Mute(bool),
+    /// Volume of one application's stream
+    StreamVolume(u32, u8),
+    /// Route playback to an output device
+    OutputDevice(String),
+    Brightness(u8),
+    NightLight(bool),
+    Dnd(bool),
+    PowerProfile(String),
+    Suspend,
+}
+
+/// Send a change to its daemon
+pub async fn apply(change: Change) -> Result<(), String> {
+    let result = match change {
+        Change::Wifi(enabled) => NetworkClient::new().set_wifi_enabled(enabled).await,
+        Change::Airplane(enabled) => NetworkClient::new().set_airplane_mode(enabled).await,
+        Change::Bluetooth(enabled) => AudioClient::new().set_bluetooth(enabled).await,
+        Change::Volume(volume) => AudioClient::new().set_master_volume(volume.into()).await,
+        Change::Mute(muted) => AudioClient::new().set_master_mute(muted).await,
+        Change::StreamVolume(id, volume) => {
+            AudioClient::new().set_stream_volume(id, volume.into()).await
+        }
+        Change::OutputDevice(name) => AudioClient::new().set_default_sink(&name).await,
+        Change::Brightness(percent) => DisplayClient::new().set_brightness(percent).await,
+        Change::NightLight(enabled) => DisplayClient::new().set_night_light(enabled).await,
+        Change::Dnd(enabled) => NotificationsClient::new().set_dnd(enabled).await,
+        Change::PowerProfile(name) => PowerClient::new().set_profile(&name).await,
+        Change::Suspend => PowerClient::new().suspend().await,
+    };
+    result.map_err(|e| e.to_string())
+}
+
+/// Read the current state from every daemon at once
+pub async fn fetch_state() -> DaemonState {
+    let (network, audio, display, dnd, power) = (
+        NetworkClient::new(),
+        AudioClient::new(),
+        DisplayClient::new(),
+        NotificationsClient::new(),
+        PowerClient::new(),
+    );
+    let (network, audio, display, dnd, power) = tokio::join!(
+        network.status(),
+        audio.status(),
+        display.status(),
+        dnd.dnd_active(),
+        power.profiles(),
+    );
+
+    DaemonState {
+        network: network.ok().map(|status| network_state(&status)),
+        audio: audio.ok().map(|status| AudioState {
+            volume: status.master_volume.min(100) as u8,
+            muted: status.muted,
+            bluetooth: status.bluetooth_enabled,
+            bt_device: status.bluetooth_device,
+        }),
+        display: display.ok().map(|status| DisplayState {
+            brightness: status.backlight.map(|b| b.percent.min(100)),
+            night_light: status.night_light.enabled,
+        }),
+        dnd: dnd.ok(),
+        power: power.ok().map(|profiles| PowerProfiles {
+            current: profiles.current,
+            available: profiles.available,
+        }),
+    }
+}
+
+/// Wi-Fi scan for the detail pane
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WifiScan {
+    /// Interface the scan ran on, and that connections go to
+    pub interface: String,
+    pub networks: Vec<WifiNetwork>,
+}
+
+/// Scan on the first wireless interface
+pub async fn scan_wifi() -> Result<WifiScan, String> {
+    let client = NetworkClient::new();
+    let status = client.status().await.map_err(|e| e.to_string())?;
+    let interface = status
+        .wireless_interface()
+        .map(|iface| iface.name.clone())
+        .ok_or_else(|| "No wireless interface".to_string())?;
+    let networks = client.wifi_scan(&interface).await.map_err(|e| e.to_string())?;
+    Ok(WifiScan { interface, networks })
+}
+
+/// Join a network; without a password wraith uses saved credentials
+pub async fn connect_wifi(interface: String, ssid: String, password: Option<String>) -> Result<(), String> {
+    NetworkClient::new()
+        .wifi_connect(&interface, &ssid, password.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Drop a saved network
+pub async fn forget_wifi(interface: String, ssid: String) -> Result<(), String> {
+    NetworkClient::new()
+        .wifi_forget(&interface, &ssid)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Output devices and application streams for the detail pane
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AudioDetails {
+    pub outputs: Vec<AudioDevice>,
+    /// Playback streams only
+    pub streams: Vec<AudioStream>,
+}
+
+/// Read vesper's devices and streams; a vesper without per-application
+/// streams leaves the mixer empty rather than failing the pane
+pub async fn fetch_audio_details() -> Result<AudioDetails, String> {
+    let client = AudioClient::new();
+    let peer = HelloClient::new(paths::VESPER_SOCKET)
+        .hello()
+        .await
+        .map_err(|e| e.to_string())?;
+    let streams = async {
+        if peer.supports("streams") {
+            client.streams().await
+        } else {
+            Ok(Vec::new())
+        }
+    };
+    let (devices, streams) = tokio::join!(client.devices(), streams);
+    Ok(AudioDetails {
+        outputs: devices
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(AudioDevice::is_output)
+            .collect(),
+        streams: streams
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(AudioStream::is_playback)
+            .collect(),
+    })
+}
+
+fn network_state(status: &NetworkStatus) -> NetworkState {
+    let wifi = status.wifi_enabled && !status.airplane_mode;
+    let wifi_network = status
+        .interfaces
+        .iter()
+        .find(|iface| iface.is_wireless() && iface.is_connected())
+        .map(|iface| iface.name.clone());
+
+    NetworkState {
+        wifi,
+        airplane: status.airplane_mode,
+        wifi_network: wifi_network.filter(|_| wifi),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn status(airplane: bool) -> NetworkStatus {
+        serde_json::from_value(json!({
+            "status": "Status",
+            "interfaces": [
+                { "name": "lo", "mac_address": null, "addresses": ["127.0.0.1/8"], "up": true, "running": true, "interface_type": "Loopback" },
+                { "name": "wlan0", "mac_address": null, "addresses": ["10.0.0.5/24"], "up": true, "running": true, "interface_type": "Wireless" },
+            ],
+            "dns_servers": [],
+            "hostname": "nyx",
+            "wifi_enabled": true,
+            "airplane_mode": airplane,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_network_state() {
+        let network = network_state(&status(false));
+        assert!(network.wifi);
+        assert!(!network.airplane);
+        assert_eq!(network.wifi_network.as_deref(), Some("wlan0"));
+    }
+
+    #[test]
+    fn test_network_state_in_airplane_mode() {
+        let network = network_state(&status(true));
+        assert!(!network.wifi);
+        assert!(network.airplane);
+        assert!(network.wifi_network.is_none());
+    }
+}
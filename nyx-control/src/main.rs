@@ -4,11 +4,15 @@
 //! - WiFi, Bluetooth, Airplane mode toggles
 //! - Volume and brightness controls
 //! - Night light and Do Not Disturb
-//! - Power options
+//! - Power options and power profiles
 //! - System status
+//!
+//! Each control is backed by the daemon that owns it (wraith, vesper, iris,
+//! herald, slumber) and polled so the panel follows changes made elsewhere.
 
 mod app;
 mod controls;
+mod daemons;
 
 use app::NyxControl;
 use iced::Application;
@@ -8,6 +8,8 @@ use tracing::{info, warn, debug};
 
 const CGROUP_ROOT: &str = "/sys/fs/cgroup";
 const CGROUP_SLICE: &str = "nyx.slice";
+/// Where a user instance moves itself inside its delegated slice
+const MANAGER_SCOPE: &str = "serviced.scope";
 
 /// Cgroup manager for resource limits
 pub struct CgroupManager {
@@ -35,12 +37,39 @@ impl CgroupManager {
         // Enable controllers for slice
         let controllers = read_available_controllers(&root)?;
         enable_controllers(&root, &slice, &controllers)?;
+        enable_controllers(&slice, &slice, &controllers)?;
 
         info!("Cgroup manager initialized at {:?}", slice);
 
         Ok(Self { root, slice })
     }
 
+    /// Use a slice delegated to a user instance. Only leaf cgroups may
+    /// hold processes, so this process first moves out of the slice into
+    /// a scope of its own, next to the ones its services will get.
+    pub fn delegated(slice: &Path) -> Result<Self> {
+        if !slice.join("cgroup.controllers").exists() {
+            return Err(anyhow::anyhow!("Delegated cgroup not found: {:?}", slice));
+        }
+
+        let manager_scope = slice.join(MANAGER_SCOPE);
+        if !manager_scope.exists() {
+            fs::create_dir(&manager_scope)
+                .with_context(|| format!("Failed to create cgroup: {:?}", manager_scope))?;
+        }
+        write_cgroup_file(&manager_scope, "cgroup.procs", &std::process::id().to_string())?;
+
+        let controllers = read_available_controllers(slice)?;
+        enable_controllers(slice, slice, &controllers)?;
+
+        info!("Cgroup manager initialized at delegated {:?}", slice);
+
+        Ok(Self {
+            root: PathBuf::from(CGROUP_ROOT),
+            slice: slice.to_path_buf(),
+        })
+    }
+
     /// Create a cgroup for a service
     pub fn create_service_cgroup(&self, name: &str, config: &ResourceConfig) -> Result<PathBuf> {
         let cgroup_path = self.slice.join(format!("{}.scope", name));
@@ -0,0 +1,110 @@
+//! Service manager instances
+//!
+//! The system instance runs as root with units from `/grimoire/services`.
+//! Spectre starts a user instance (`--user`) for every session: it runs as
+//! the session's user, loads units from `~/.config/nyx/services`, listens
+//! in the session's runtime directory and puts its services in the cgroup
+//! slice spectre delegated for the session.
+
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// Control socket name inside an instance's runtime directory
+const SOCKET_NAME: &str = "serviced.sock";
+
+/// Where an instance keeps its units and sockets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instance {
+    /// Per-session user instance
+    pub user: bool,
+    /// Unit files
+    pub config_dir: PathBuf,
+    /// Sockets and pids
+    pub runtime_dir: PathBuf,
+    /// Control socket
+    pub socket: PathBuf,
+}
+
+impl Instance {
+    /// The system-wide instance
+    pub fn system() -> Self {
+        Self {
+            user: false,
+            config_dir: PathBuf::from("/grimoire/services"),
+            runtime_dir: PathBuf::from("/run/nyx"),
+            socket: PathBuf::from("/run/nyx/serviced.sock"),
+        }
+    }
+
+    /// The calling user's instance, located from the session environment
+    pub fn user() -> Result<Self> {
+        Self::user_from(
+            |key| std::env::var_os(key).filter(|v| !v.is_empty()).map(PathBuf::from),
+            nix::unistd::getuid().as_raw(),
+        )
+    }
+
+    fn user_from(var: impl Fn(&str) -> Option<PathBuf>, uid: u32) -> Result<Self> {
+        let config_home = match var("XDG_CONFIG_HOME") {
+            Some(dir) => dir,
+            None => var("HOME")
+                .ok_or_else(|| anyhow!("Neither XDG_CONFIG_HOME nor HOME is set"))?
+                .join(".config"),
+        };
+        let runtime_dir = var("XDG_RUNTIME_DIR")
+            .unwrap_or_else(|| PathBuf::from(format!("/run/user/{}", uid)))
+            .join("nyx");
+
+        Ok(Self {
+            user: true,
+            config_dir: config_home.join("nyx/services"),
+            socket: runtime_dir.join(SOCKET_NAME),
+            runtime_dir,
+        })
+    }
+
+    /// Apply command-line overrides; a runtime directory given without a
+    /// socket moves the socket along with it
+    pub fn with_overrides(
+        mut self,
+        config_dir: Option<PathBuf>,
+        runtime_dir: Option<PathBuf>,
+        socket: Option<PathBuf>,
+    ) -> Self {
+        if let Some(dir) = config_dir {
+            self.config_dir = dir;
+        }
+        if let Some(dir) = runtime_dir {
+            self.socket = dir.join(SOCKET_NAME);
+            self.runtime_dir = dir;
+        }
+        if let Some(socket) = socket {
+            self.socket = socket;
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_user_instance_paths() {
+        let env: HashMap<&str, PathBuf> = [
+            ("HOME", PathBuf::from("/home/ada")),
+            ("XDG_RUNTIME_DIR", PathBuf::from("/run/user/1000")),
+        ]
+        .into();
+        let instance = Instance::user_from(|key| env.get(key).cloned(), 1000).unwrap();
+
+        assert_eq!(instance.config_dir, PathBuf::from("/home/ada/.config/nyx/services"));
+        assert_eq!(instance.socket, PathBuf::from("/run/user/1000/nyx/serviced.sock"));
+
+        let moved = instance.with_overrides(None, Some("/tmp/nyx".into()), None);
+        assert_eq!(moved.socket, PathBuf::from("/tmp/nyx/serviced.sock"));
+
+        assert!(Instance::user_from(|_| None, 1000).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 //! Service lifecycle management
 
+use crate::cgroups::CgroupManager;
+use crate::secrets::SecretInjector;
 use crate::dependency::{check_dependencies, get_start_before, DependencyCheck};
 use crate::state::{ServiceState, ServiceStatus, StateManager};
 use crate::unit::{RestartPolicy, ServiceType, Unit, UnitRegistry};
@@ -8,6 +10,7 @@ use libnyx_platform::PlatformCapabilities;
 use nix::sys::signal::{self, Signal};
 use nix::unistd::Pid;
 use std::collections::{HashMap, HashSet};
+use std::os::fd::AsRawFd;
 use std::os::unix::process::CommandExt;
 use std::path::PathBuf;
 use std::process::Stdio;
@@ -23,6 +26,8 @@ pub struct LifecycleManager {
     units: Arc<RwLock<UnitRegistry>>,
     states: Arc<RwLock<StateManager>>,
     capabilities: PlatformCapabilities,
+    cgroups: Option<Arc<CgroupManager>>,
+    secrets: Arc<SecretInjector>,
     processes: RwLock<HashMap<String, Child>>,
     log_dir: PathBuf,
 }
@@ -32,11 +37,15 @@ impl LifecycleManager {
         units: Arc<RwLock<UnitRegistry>>,
         states: Arc<RwLock<StateManager>>,
         capabilities: PlatformCapabilities,
+        cgroups: Option<Arc<CgroupManager>>,
+        secrets: Arc<SecretInjector>,
     ) -> Self {
         Self {
             units,
             states,
             capabilities,
+            cgroups,
+            secrets,
             processes: RwLock::new(HashMap::new()),
             log_dir: PathBuf::from("/var/log/nyx"),
         }
@@ -128,6 +137,7 @@ impl LifecycleManager {
             }
             Err(e) => {
                 error!("Failed to start {}: {}", name, e);
+                self.secrets.clear(name);
                 self.states.write().await.get_or_create(name).mark_failed(&e.to_string());
                 Err(e)
             }
@@ -189,6 +199,7 @@ impl LifecycleManager {
 
         // Remove from process map
         self.processes.write().await.remove(name);
+        self.secrets.clear(name);
 
         // Mark as stopped
         self.states.write().await.get_or_create(name)
@@ -261,6 +272,9 @@ impl LifecycleManager {
             states.get_or_create(name).mark_stopped(Some(exit_code), signal, false);
         }
 
+        // A restart fetches the secrets afresh
+        self.secrets.clear(name);
+
         // Check restart limits
         if should_restart {
             let restart_ok = {
@@ -341,10 +355,30 @@ impl LifecycleManager {
             }
         }
 
+        // Secrets from cipher, as variables or in a private directory
+        self.secrets.inject(unit, &mut cmd).await?;
+
         // Set up stdio
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
 
+        // Join the network namespace first, while still privileged; the
+        // file is opened here so the child only makes the one syscall
+        let netns = match &unit.service.network_namespace {
+            Some(name) => Some(
+                libnyx_ipc::NetworkClient::new()
+                    .open_namespace(name)
+                    .await
+                    .with_context(|| format!("Network namespace {} unavailable", name))?,
+            ),
+            None => None,
+        };
+        if let Some(fd) = netns.as_ref().map(|f| f.as_raw_fd()) {
+            unsafe {
+                cmd.pre_exec(move || libnyx_ipc::network::join_namespace(fd));
+            }
+        }
+
         // Set up user/group (requires privileges)
         if let Some(user) = &unit.service.user {
             if let Ok(uid) = user.parse::<u32>() {
@@ -361,8 +395,20 @@ impl LifecycleManager {
         let mut child = cmd.spawn()
             .with_context(|| format!("Failed to spawn {}", parts[0]))?;
 
+        drop(netns);
+
         let pid = child.id().ok_or_else(|| anyhow!("No PID for child"))?;
 
+        // Give it a scope of its own in our slice
+        if let Some(cgroups) = &self.cgroups {
+            let placed = cgroups
+                .create_service_cgroup(&unit.name, &unit.resources)
+                .and_then(|_| cgroups.add_process(&unit.name, pid));
+            if let Err(e) = placed {
+                warn!("Failed to place {} in its cgroup: {:#}", unit.name, e);
+            }
+        }
+
         // Spawn log handlers
         let name = unit.name.clone();
         let log_dir = self.log_dir.clone();
@@ -504,11 +550,18 @@ impl LifecycleManager {
             units: self.units.clone(),
             states: self.states.clone(),
             capabilities: self.capabilities.clone(),
+            cgroups: self.cgroups.clone(),
+            secrets: self.secrets.clone(),
             processes: RwLock::new(HashMap::new()),
             log_dir: self.log_dir.clone(),
         })
     }
 
+    /// Get a service's unit
+    pub async fn unit(&self, name: &str) -> Option<Unit> {
+        self.units.read().await.get(name).cloned()
+    }
+
     /// Get status of a service
     pub async fn status(&self, name: &str) -> Option<ServiceStatus> {
         self.states.read().await.get(name).cloned()
@@ -11,6 +11,8 @@
 //! - **Restart Policies**: Always, OnFailure, Never
 //! - **Watchdog**: Health monitoring and auto-restart
 //! - **IPC**: Unix socket control interface
+//! - **User Instances**: A `--user` manager per session, started by spectre
+//! - **Secrets**: Cipher secrets injected as variables or private files
 
 mod unit;
 mod state;
@@ -20,6 +22,8 @@ mod socket_activation;
 mod cgroups;
 mod watchdog;
 mod ipc;
+mod instance;
+mod secrets;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
@@ -33,17 +37,31 @@ use tracing::{info, warn, error};
 #[derive(Parser, Debug)]
 #[command(name = "nyx-serviced", version, about)]
 struct Args {
-    /// Configuration directory
-    #[arg(short, long, default_value = "/grimoire/services")]
-    config_dir: PathBuf,
+    /// Run, or talk to, the calling user's session instance
+    #[arg(long, global = true)]
+    user: bool,
 
-    /// Runtime directory for sockets/pids
-    #[arg(short, long, default_value = "/run/nyx")]
-    runtime_dir: PathBuf,
+    /// Configuration directory [default: /grimoire/services, or
+    /// ~/.config/nyx/services with --user]
+    #[arg(short, long)]
+    config_dir: Option<PathBuf>,
+
+    /// Runtime directory for sockets/pids [default: /run/nyx, or
+    /// $XDG_RUNTIME_DIR/nyx with --user]
+    #[arg(short, long)]
+    runtime_dir: Option<PathBuf>,
 
-    /// Control socket path
-    #[arg(short, long, default_value = "/run/nyx/serviced.sock")]
-    socket: PathBuf,
+    /// Control socket path [default: serviced.sock in the runtime directory]
+    #[arg(short, long)]
+    socket: Option<PathBuf>,
+
+    /// Cgroup slice delegated to a user instance (set by spectre)
+    #[arg(long, env = "NYX_CGROUP_SLICE")]
+    cgroup_slice: Option<PathBuf>,
+
+    /// Cipher socket services' secrets are fetched from
+    #[arg(long, env = "NYX_CIPHER_SOCKET", default_value = libnyx_ipc::paths::CIPHER_SOCKET)]
+    cipher_socket: PathBuf,
 
     /// Enable debug logging
     #[arg(short, long)]
@@ -108,17 +126,24 @@ async fn main() -> Result<()> {
     );
 
     // If systemd is available on this platform, warn about potential conflicts
-    if capabilities.systemd {
+    if capabilities.systemd && !args.user {
         warn!("systemd detected - nyx-serviced running in parallel mode");
     }
 
+    let instance = if args.user {
+        instance::Instance::user()?
+    } else {
+        instance::Instance::system()
+    };
+    let instance = instance.with_overrides(args.config_dir.clone(), args.runtime_dir.clone(), args.socket.clone());
+
     // Handle CLI commands
     if let Some(cmd) = args.command {
-        return handle_client_command(&args.socket, cmd).await;
+        return handle_client_command(&instance.socket, cmd).await;
     }
 
     // Daemon mode
-    run_daemon(args, capabilities).await
+    run_daemon(instance, args.cgroup_slice, args.cipher_socket, capabilities).await
 }
 
 async fn handle_client_command(socket: &PathBuf, cmd: Commands) -> Result<()> {
@@ -226,9 +251,38 @@ fn print_list(services: &[ipc::ServiceListEntry]) {
     }
 }
 
-async fn run_daemon(args: Args, capabilities: PlatformCapabilities) -> Result<()> {
+async fn run_daemon(
+    instance: instance::Instance,
+    cgroup_slice: Option<PathBuf>,
+    cipher_socket: PathBuf,
+    capabilities: PlatformCapabilities,
+) -> Result<()> {
     // Ensure runtime directory exists
-    std::fs::create_dir_all(&args.runtime_dir)?;
+    std::fs::create_dir_all(&instance.runtime_dir)?;
+
+    // Initialize cgroups if available; a user instance only gets the
+    // slice spectre delegated to it
+    let cgroup_manager = if !capabilities.cgroups_v2 {
+        warn!("Cgroups v2 not available - resource limits disabled");
+        None
+    } else if instance.user {
+        match &cgroup_slice {
+            Some(slice) => match cgroups::CgroupManager::delegated(slice) {
+                Ok(manager) => Some(Arc::new(manager)),
+                Err(e) => {
+                    warn!("Delegated cgroup unusable, resource limits disabled: {:#}", e);
+                    None
+                }
+            },
+            None => {
+                warn!("No cgroup slice delegated - resource limits disabled");
+                None
+            }
+        }
+    } else {
+        info!("Initializing cgroups v2 resource manager");
+        Some(Arc::new(cgroups::CgroupManager::new()?))
+    };
 
     // Initialize components
     let unit_registry = Arc::new(RwLock::new(unit::UnitRegistry::new()));
@@ -237,20 +291,16 @@ async fn run_daemon(args: Args, capabilities: PlatformCapabilities) -> Result<()
         unit_registry.clone(),
         state_manager.clone(),
         capabilities.clone(),
+        cgroup_manager,
+        Arc::new(secrets::SecretInjector::new(
+            &cipher_socket,
+            instance.runtime_dir.join("credentials"),
+        )),
     ));
 
-    // Initialize cgroups if available
-    let cgroup_manager = if capabilities.cgroups_v2 {
-        info!("Initializing cgroups v2 resource manager");
-        Some(Arc::new(cgroups::CgroupManager::new()?))
-    } else {
-        warn!("Cgroups v2 not available - resource limits disabled");
-        None
-    };
-
     // Load unit files
-    info!("Loading service units from {:?}", args.config_dir);
-    let loaded = unit_registry.write().await.load_directory(&args.config_dir)?;
+    info!("Loading service units from {:?}", instance.config_dir);
+    let loaded = unit_registry.write().await.load_directory(&instance.config_dir)?;
     info!("Loaded {} service units", loaded);
 
     // Resolve dependencies
@@ -268,7 +318,7 @@ async fn run_daemon(args: Args, capabilities: PlatformCapabilities) -> Result<()
     // Initialize socket activation
     let socket_activator = Arc::new(socket_activation::SocketActivator::new(
         lifecycle.clone(),
-        args.runtime_dir.clone(),
+        instance.runtime_dir.clone(),
     ));
     socket_activator.setup_sockets(&*unit_registry.read().await).await?;
 
@@ -286,13 +336,17 @@ async fn run_daemon(args: Args, capabilities: PlatformCapabilities) -> Result<()
 
     // Start IPC server
     let server = ipc::ServicedServer::new(
-        args.socket.clone(),
+        instance.socket.clone(),
         lifecycle.clone(),
         state_manager.clone(),
         unit_registry.clone(),
     );
 
-    info!("nyx-serviced ready on {:?}", args.socket);
+    info!(
+        "nyx-serviced {} instance ready on {:?}",
+        if instance.user { "user" } else { "system" },
+        instance.socket
+    );
     server.run().await
 }
 
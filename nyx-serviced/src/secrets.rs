@@ -0,0 +1,131 @@
+//! Secrets handed to services at start
+//!
+//! Units list the cipher secrets they need under `service.secrets`. When a
+//! service starts, serviced asks cipher for them over cipher's socket,
+//! where it is known by its peer credentials: root for the system
+//! instance, the session's user for a user instance, and either needs
+//! access to the collections involved. Each secret becomes an environment
+//! variable or a file in a credentials directory of the service's own,
+//! named by `CREDENTIALS_DIRECTORY`. The system instance mounts a private
+//! tmpfs there. Plaintext never goes into unit files or onto disk, and the
+//! directory is removed when the service stops.
+
+use crate::unit::{SecretTarget, Unit};
+use anyhow::{anyhow, Context, Result};
+use libnyx_ipc::SecretsClient;
+use nix::mount::{mount, umount2, MntFlags, MsFlags};
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+/// Environment variable naming the credentials directory
+pub const CREDENTIALS_ENV: &str = "CREDENTIALS_DIRECTORY";
+
+/// Mount options of a credentials tmpfs
+const TMPFS_OPTIONS: &str = "mode=0700,size=1m";
+
+/// Fetches secrets from cipher and hands them to services
+pub struct SecretInjector {
+    client: SecretsClient,
+    /// Parent of the per-service credentials directories
+    base: PathBuf,
+}
+
+impl SecretInjector {
+    pub fn new(cipher_socket: &Path, base: PathBuf) -> Self {
+        Self {
+            client: SecretsClient::with_socket(cipher_socket),
+            base,
+        }
+    }
+
+    /// Fetch `unit`'s secrets and give them to `cmd`
+    pub async fn inject(&self, unit: &Unit, cmd: &mut Command) -> Result<()> {
+        if unit.service.secrets.is_empty() {
+            return Ok(());
+        }
+
+        let mut files = Vec::new();
+        for secret in &unit.service.secrets {
+            let value = self
+                .client
+                .lookup(&secret.collection, &secret.id)
+                .await
+                .with_context(|| format!("Secret {} unavailable from cipher", secret))?
+                .ok_or_else(|| anyhow!("Secret {} not found in cipher", secret))?;
+
+            match &secret.target {
+                SecretTarget::Env(var) => {
+                    cmd.env(var, value);
+                }
+                SecretTarget::File(name) => files.push((name, value)),
+            }
+        }
+
+        if !files.is_empty() {
+            let dir = self.prepare_dir(unit)?;
+            let owner = service_uid(unit).map(nix::unistd::Uid::from_raw);
+            for (name, value) in files {
+                let path = dir.join(name);
+                std::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .mode(0o400)
+                    .open(&path)
+                    .and_then(|mut file| file.write_all(value.as_bytes()))
+                    .with_context(|| format!("Failed to write {:?}", path))?;
+                nix::unistd::chown(&path, owner, None)?;
+            }
+            cmd.env(CREDENTIALS_ENV, &dir);
+        }
+
+        debug!("Injected {} secrets into {}", unit.service.secrets.len(), unit.name);
+        Ok(())
+    }
+
+    /// Remove a service's credentials directory
+    pub fn clear(&self, name: &str) {
+        let dir = self.base.join(name);
+        if !dir.exists() {
+            return;
+        }
+
+        // Unmounting drops the tmpfs contents; without one the files are
+        // removed below
+        if nix::unistd::geteuid().is_root() {
+            let _ = umount2(&dir, MntFlags::MNT_DETACH);
+        }
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            warn!("Failed to remove credentials of {}: {}", name, e);
+        }
+    }
+
+    /// An empty credentials directory only the service can read
+    fn prepare_dir(&self, unit: &Unit) -> Result<PathBuf> {
+        // Left over from a run that ended without a clean stop
+        self.clear(&unit.name);
+
+        let dir = self.base.join(&unit.name);
+        std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {:?}", dir))?;
+
+        // Only root can mount; a user instance's runtime directory is a
+        // tmpfs of the session's already
+        if nix::unistd::geteuid().is_root() {
+            let flags = MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC;
+            if let Err(e) = mount(Some("tmpfs"), &dir, Some("tmpfs"), flags, Some(TMPFS_OPTIONS)) {
+                warn!("No private tmpfs for the credentials of {}: {}", unit.name, e);
+            }
+        }
+
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+        nix::unistd::chown(&dir, service_uid(unit).map(nix::unistd::Uid::from_raw), None)?;
+        Ok(dir)
+    }
+}
+
+/// The uid a service runs as, when it names one
+fn service_uid(unit: &Unit) -> Option<u32> {
+    unit.service.user.as_deref()?.parse().ok()
+}
@@ -3,6 +3,7 @@
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 use std::path::{Path, PathBuf};
 use tracing::{info, warn};
 
@@ -94,6 +95,102 @@ pub struct ServiceConfig {
     pub standard_error: OutputType,
     /// PID file path (for forking services)
     pub pid_file: Option<PathBuf>,
+    /// IPC socket answering `GetHealth`; the watchdog restarts the service
+    /// when it keeps reporting unhealthy or stops answering
+    pub health_socket: Option<PathBuf>,
+    /// Wraith network namespace to run in, e.g. `vpn-only`; wraith sets it
+    /// up from its saved definition if it isn't yet
+    pub network_namespace: Option<String>,
+    /// Cipher secrets handed to the service when it starts
+    #[serde(default)]
+    pub secrets: Vec<SecretRef>,
+}
+
+/// Collection of secrets named without one
+pub const DEFAULT_SECRET_COLLECTION: &str = "services";
+
+/// A cipher secret a service receives at start
+///
+/// Written as `name` or `collection/name`, which becomes a file of that
+/// name in the service's credentials directory, or as a map with `name`
+/// and either `env` (an environment variable) or `file`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SecretEntry", into = "SecretEntry")]
+pub struct SecretRef {
+    pub collection: String,
+    pub id: String,
+    pub target: SecretTarget,
+}
+
+/// How a service receives a secret
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretTarget {
+    /// Environment variable
+    Env(String),
+    /// File in the credentials directory
+    File(String),
+}
+
+/// A secret as written in a unit file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum SecretEntry {
+    Name(String),
+    Full {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        env: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file: Option<String>,
+    },
+}
+
+impl TryFrom<SecretEntry> for SecretRef {
+    type Error = String;
+
+    fn try_from(entry: SecretEntry) -> Result<Self, String> {
+        let (name, env, file) = match entry {
+            SecretEntry::Name(name) => (name, None, None),
+            SecretEntry::Full { name, env, file } => (name, env, file),
+        };
+        let (collection, id) = match name.split_once('/') {
+            Some((collection, id)) => (collection.to_string(), id.to_string()),
+            None => (DEFAULT_SECRET_COLLECTION.to_string(), name.clone()),
+        };
+        if collection.is_empty() || id.is_empty() {
+            return Err(format!("invalid secret name {:?}", name));
+        }
+
+        let target = match (env, file) {
+            (Some(_), Some(_)) => return Err(format!("secret {} has both env and file", name)),
+            (Some(var), None) => SecretTarget::Env(var),
+            (None, file) => {
+                let file = file.unwrap_or_else(|| id.clone());
+                if file.contains('/') || file == "." || file == ".." {
+                    return Err(format!("secret file {:?} must be a plain file name", file));
+                }
+                SecretTarget::File(file)
+            }
+        };
+
+        Ok(Self { collection, id, target })
+    }
+}
+
+impl From<SecretRef> for SecretEntry {
+    fn from(secret: SecretRef) -> Self {
+        let name = secret.to_string();
+        match secret.target {
+            SecretTarget::Env(var) => SecretEntry::Full { name, env: Some(var), file: None },
+            SecretTarget::File(file) => SecretEntry::Full { name, env: None, file: Some(file) },
+        }
+    }
+}
+
+impl fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.collection, self.id)
+    }
 }
 
 /// Service type
@@ -469,6 +566,7 @@ description: Test service
 service:
   exec_start: /usr/bin/test
   restart: always
+  network_namespace: vpn-only
 install:
   after:
     - network.target
@@ -477,9 +575,35 @@ install:
         let unit: Unit = serde_yaml::from_str(yaml).unwrap();
         assert_eq!(unit.name, "test-service");
         assert_eq!(unit.service.restart, RestartPolicy::Always);
+        assert_eq!(unit.service.network_namespace.as_deref(), Some("vpn-only"));
         assert!(unit.install.enabled);
     }
 
+    #[test]
+    fn test_unit_secrets() {
+        let yaml = r#"
+name: app
+service:
+  exec_start: /usr/bin/app
+  secrets:
+    - db-password
+    - name: backup/s3-key
+      env: S3_KEY
+    - name: tls
+      file: tls.key
+"#;
+        let unit: Unit = serde_yaml::from_str(yaml).unwrap();
+        let secrets = &unit.service.secrets;
+        assert_eq!(secrets[0].collection, DEFAULT_SECRET_COLLECTION);
+        assert_eq!(secrets[0].target, SecretTarget::File("db-password".into()));
+        assert_eq!(secrets[1].to_string(), "backup/s3-key");
+        assert_eq!(secrets[1].target, SecretTarget::Env("S3_KEY".into()));
+        assert_eq!(secrets[2].target, SecretTarget::File("tls.key".into()));
+
+        let bad = "name: app\nservice:\n  secrets:\n    - name: x\n      file: ../etc/shadow\n";
+        assert!(serde_yaml::from_str::<Unit>(bad).is_err());
+    }
+
     #[test]
     fn test_unit_dependencies() {
         let yaml = r#"
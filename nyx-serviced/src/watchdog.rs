@@ -2,18 +2,24 @@
 
 use crate::lifecycle::LifecycleManager;
 use crate::state::{ServiceState, StateManager};
+use libnyx_ipc::{HealthClient, HealthStatus};
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use tracing::{info, warn, error, debug};
 
+/// Failed health probes in a row before a service is restarted
+const HEALTH_FAILURE_LIMIT: u32 = 3;
+
 /// Watchdog for monitoring service health
 pub struct Watchdog {
     lifecycle: Arc<LifecycleManager>,
     states: Arc<RwLock<StateManager>>,
     check_interval: Duration,
     last_pings: RwLock<HashMap<String, Instant>>,
+    health_failures: RwLock<HashMap<String, u32>>,
 }
 
 impl Watchdog {
@@ -26,6 +32,7 @@ impl Watchdog {
             states,
             check_interval: Duration::from_secs(5),
             last_pings: RwLock::new(HashMap::new()),
+            health_failures: RwLock::new(HashMap::new()),
         }
     }
 
@@ -68,8 +75,18 @@ impl Watchdog {
                     // The lifecycle manager should handle this via wait()
                     // but we can trigger it here as a backup
                     self.lifecycle.handle_exit(&name, -1, Some(9)).await;
+                    continue;
                 }
             }
+
+            let health_socket = self
+                .lifecycle
+                .unit(&name)
+                .await
+                .and_then(|unit| unit.service.health_socket);
+            if let Some(socket) = health_socket {
+                self.check_health(&name, &socket).await;
+            }
         }
 
         Ok(())
@@ -105,6 +122,46 @@ impl Watchdog {
         }
     }
 
+    /// Probe a service's health socket, restarting it after repeated failures
+    async fn check_health(&self, name: &str, socket: &Path) {
+        let failure = match HealthClient::new(socket).health().await {
+            Ok(health) if health.status < HealthStatus::Unhealthy => None,
+            Ok(health) => {
+                let failing: Vec<_> = health
+                    .checks
+                    .iter()
+                    .filter(|c| c.status == HealthStatus::Unhealthy)
+                    .map(|c| match &c.message {
+                        Some(message) => format!("{}: {}", c.name, message),
+                        None => c.name.clone(),
+                    })
+                    .collect();
+                Some(format!("unhealthy ({})", failing.join(", ")))
+            }
+            Err(e) => Some(format!("health probe failed: {}", e)),
+        };
+
+        let mut failures = self.health_failures.write().await;
+        let Some(reason) = failure else {
+            failures.remove(name);
+            return;
+        };
+
+        let count = failures.entry(name.to_string()).or_default();
+        *count += 1;
+        warn!("Service {} is {} ({}/{})", name, reason, count, HEALTH_FAILURE_LIMIT);
+        if *count < HEALTH_FAILURE_LIMIT {
+            return;
+        }
+        failures.remove(name);
+        drop(failures);
+
+        error!("Service {} failed {} health probes - restarting", name, HEALTH_FAILURE_LIMIT);
+        if let Err(e) = self.lifecycle.restart(name).await {
+            error!("Failed to restart {} after failed health probes: {}", name, e);
+        }
+    }
+
     /// Record a watchdog ping from a service
     pub async fn ping(&self, name: &str) {
         debug!("Watchdog ping from {}", name);
@@ -125,6 +182,7 @@ impl Watchdog {
     /// Reset watchdog tracking for a service
     pub async fn reset(&self, name: &str) {
         self.last_pings.write().await.remove(name);
+        self.health_failures.write().await.remove(name);
     }
 }
 
@@ -2,23 +2,33 @@
 
 use crate::pages::about::{AboutMessage, AboutPage};
 use crate::pages::appearance::{AppearanceMessage, AppearancePage};
+use crate::pages::default_apps::{DefaultAppsMessage, DefaultAppsPage};
 use crate::pages::display::{DisplayMessage, DisplayPage};
 use crate::pages::network::{NetworkMessage, NetworkPage};
 use crate::pages::notifications::{NotificationsMessage, NotificationsPage};
+use crate::pages::personas::{PersonasMessage, PersonasPage};
 use crate::pages::power::{PowerMessage, PowerPage};
 use crate::pages::sound::{SoundMessage, SoundPage};
+use crate::pages::users::{UsersMessage, UsersPage};
 use crate::pages::SettingsPage;
-use iced::widget::{button, column, container, horizontal_rule, row, scrollable, text};
-use iced::{executor, Alignment, Application, Command, Element, Length, Theme};
+use crate::search::{self, DeepLink, SettingEntry};
+use iced::widget::scrollable::RelativeOffset;
+use iced::widget::{button, column, container, horizontal_rule, row, scrollable, text, text_input};
+use iced::{executor, Alignment, Application, Command, Element, Length, Subscription, Theme};
 use nyx_theme::colors::NyxColors;
 use nyx_theme::spacing::Spacing;
 use nyx_theme::widgets::button::{button_style, ButtonVariant};
+use nyx_theme::widgets::input::{input_style, InputVariant};
 use nyx_theme::Typography;
 
 /// Main settings application
 pub struct NyxSettings {
     /// Current page
     current_page: SettingsPage,
+    /// Sidebar search text
+    query: String,
+    /// Control a search result or link pointed at
+    highlighted: Option<&'static SettingEntry>,
     /// Network page state
     network: NetworkPage,
     /// Display page state
@@ -27,10 +37,16 @@ pub struct NyxSettings {
     sound: SoundPage,
     /// Appearance page state
     appearance: AppearancePage,
+    /// Default apps page state
+    default_apps: DefaultAppsPage,
+    /// Personas page state
+    personas: PersonasPage,
     /// Notifications page state
     notifications: NotificationsPage,
     /// Power page state
     power: PowerPage,
+    /// Users page state
+    users: UsersPage,
     /// About page state
     about: AboutPage,
 }
@@ -40,6 +56,12 @@ pub struct NyxSettings {
 pub enum Message {
     /// Navigate to page
     NavigateTo(SettingsPage),
+    /// Sidebar search text changed
+    SearchChanged(String),
+    /// Enter pressed in the search field
+    SearchSubmitted,
+    /// Open a page at a control
+    Open(DeepLink),
     /// Network page message
     Network(NetworkMessage),
     /// Display page message
@@ -48,10 +70,16 @@ pub enum Message {
     Sound(SoundMessage),
     /// Appearance page message
     Appearance(AppearanceMessage),
+    /// Default apps page message
+    DefaultApps(DefaultAppsMessage),
+    /// Personas page message
+    Personas(PersonasMessage),
     /// Notifications page message
     Notifications(NotificationsMessage),
     /// Power page message
     Power(PowerMessage),
+    /// Users page message
+    Users(UsersMessage),
     /// About page message
     About(AboutMessage),
 }
@@ -60,22 +88,31 @@ impl Application for NyxSettings {
     type Executor = executor::Default;
     type Message = Message;
     type Theme = Theme;
-    type Flags = ();
+    type Flags = Option<DeepLink>;
 
-    fn new(_flags: ()) -> (Self, Command<Message>) {
-        (
-            Self {
-                current_page: SettingsPage::default(),
-                network: NetworkPage::new(),
-                display: DisplayPage::default(),
-                sound: SoundPage::default(),
-                appearance: AppearancePage::default(),
-                notifications: NotificationsPage::default(),
-                power: PowerPage::default(),
-                about: AboutPage::new(),
-            },
-            Command::none(),
-        )
+    fn new(link: Option<DeepLink>) -> (Self, Command<Message>) {
+        let network = NetworkPage::new();
+        let load = network.load().map(Message::Network);
+        let mut app = Self {
+            current_page: SettingsPage::default(),
+            query: String::new(),
+            highlighted: None,
+            network,
+            display: DisplayPage::default(),
+            sound: SoundPage::default(),
+            appearance: AppearancePage::default(),
+            default_apps: DefaultAppsPage::default(),
+            personas: PersonasPage::default(),
+            notifications: NotificationsPage::default(),
+            power: PowerPage::default(),
+            users: UsersPage::default(),
+            about: AboutPage::new(),
+        };
+        let open = match link {
+            Some(link) => app.open(link),
+            None => Command::none(),
+        };
+        (app, Command::batch([load, open]))
     }
 
     fn title(&self) -> String {
@@ -88,20 +125,32 @@ impl Application for NyxSettings {
 
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
-            Message::NavigateTo(page) => {
-                self.current_page = page;
+            Message::NavigateTo(page) => return self.open(DeepLink { page, entry: None }),
+            Message::SearchChanged(query) => self.query = query,
+            Message::SearchSubmitted => {
+                if let Some(entry) = search::search(&self.query).first() {
+                    return self.open(DeepLink::from(*entry));
+                }
             }
-            Message::Network(msg) => self.network.update(msg),
+            Message::Open(link) => return self.open(link),
+            Message::Network(msg) => return self.network.update(msg).map(Message::Network),
             Message::Display(msg) => self.display.update(msg),
             Message::Sound(msg) => self.sound.update(msg),
             Message::Appearance(msg) => self.appearance.update(msg),
+            Message::DefaultApps(msg) => return self.default_apps.update(msg).map(Message::DefaultApps),
+            Message::Personas(msg) => return self.personas.update(msg).map(Message::Personas),
             Message::Notifications(msg) => self.notifications.update(msg),
             Message::Power(msg) => self.power.update(msg),
+            Message::Users(msg) => return self.users.update(msg).map(Message::Users),
             Message::About(_msg) => {}
         }
         Command::none()
     }
 
+    fn subscription(&self) -> Subscription<Message> {
+        self.network.subscription().map(Message::Network)
+    }
+
     fn view(&self) -> Element<Message> {
         let sidebar = self.view_sidebar();
         let content = self.view_content();
@@ -114,6 +163,26 @@ impl Application for NyxSettings {
 }
 
 impl NyxSettings {
+    /// Show a page, scrolled to the linked control if there is one
+    fn open(&mut self, link: DeepLink) -> Command<Message> {
+        self.current_page = link.page;
+        self.highlighted = link.entry;
+        self.query.clear();
+
+        let offset = RelativeOffset {
+            x: 0.0,
+            y: link.entry.map_or(0.0, SettingEntry::anchor),
+        };
+        let scroll = scrollable::snap_to(content_id(), offset);
+        let load = match link.page {
+            SettingsPage::DefaultApps => self.default_apps.load().map(Message::DefaultApps),
+            SettingsPage::Personas => self.personas.load().map(Message::Personas),
+            SettingsPage::Users => self.users.load().map(Message::Users),
+            _ => return scroll,
+        };
+        Command::batch([load, scroll])
+    }
+
     fn view_sidebar(&self) -> Element<Message> {
         let header = container(
             row![
@@ -129,15 +198,36 @@ impl NyxSettings {
         )
         .padding(Spacing::LG);
 
-        let nav_items: Vec<Element<Message>> = SettingsPage::all()
-            .iter()
-            .map(|page| self.view_nav_item(*page))
-            .collect();
+        let search = container(
+            text_input("Search settings", &self.query)
+                .on_input(Message::SearchChanged)
+                .on_submit(Message::SearchSubmitted)
+                .style(input_style(InputVariant::Search)),
+        )
+        .padding([0.0, Spacing::SM]);
+
+        let nav_items: Vec<Element<Message>> = if self.query.trim().is_empty() {
+            SettingsPage::all()
+                .iter()
+                .map(|page| self.view_nav_item(*page))
+                .collect()
+        } else {
+            let results = search::search(&self.query);
+            if results.is_empty() {
+                vec![text("No matching settings")
+                    .size(Typography::SIZE_BODY_SMALL)
+                    .color(NyxColors::TEXT_MUTED)
+                    .into()]
+            } else {
+                results.into_iter().map(view_search_result).collect()
+            }
+        };
 
         let navigation = scrollable(column(nav_items).spacing(Spacing::XXS).padding(Spacing::SM));
 
         container(
-            column![header, horizontal_rule(1), navigation]
+            column![header, search, horizontal_rule(1), navigation]
+                .spacing(Spacing::SM)
                 .width(Length::Fixed(Spacing::SIDEBAR_EXPANDED)),
         )
         .height(Length::Fill)
@@ -218,13 +308,24 @@ impl NyxSettings {
             SettingsPage::Display => self.display.view().map(Message::Display),
             SettingsPage::Sound => self.sound.view().map(Message::Sound),
             SettingsPage::Appearance => self.appearance.view().map(Message::Appearance),
+            SettingsPage::DefaultApps => self.default_apps.view().map(Message::DefaultApps),
+            SettingsPage::Personas => self.personas.view().map(Message::Personas),
             SettingsPage::Notifications => self.notifications.view().map(Message::Notifications),
             SettingsPage::Power => self.power.view().map(Message::Power),
-            SettingsPage::Users => self.view_placeholder("Users"),
+            SettingsPage::Users => self.users.view().map(Message::Users),
             SettingsPage::About => self.about.view().map(Message::About),
         };
 
-        container(scrollable(page_content).height(Length::Fill))
+        let page_content = match self.highlighted {
+            Some(entry) => column![view_highlight(entry), page_content].into(),
+            None => page_content,
+        };
+
+        container(
+            scrollable(page_content)
+                .id(content_id())
+                .height(Length::Fill),
+        )
             .width(Length::Fill)
             .height(Length::Fill)
             .style(|_theme| iced::widget::container::Style {
@@ -258,3 +359,53 @@ impl NyxSettings {
         .into()
     }
 }
+
+/// Scrollable holding the current page, so links can scroll it
+fn content_id() -> scrollable::Id {
+    scrollable::Id::new("settings-content")
+}
+
+fn view_search_result(entry: &'static SettingEntry) -> Element<'static, Message> {
+    button(
+        row![
+            text(entry.page.icon())
+                .size(Typography::SIZE_ICON_MD)
+                .color(NyxColors::TEXT_SECONDARY),
+            column![
+                text(entry.title)
+                    .size(Typography::SIZE_BODY_MEDIUM)
+                    .color(NyxColors::TEXT_BRIGHT),
+                text(entry.page.title())
+                    .size(Typography::SIZE_LABEL_SMALL)
+                    .color(NyxColors::TEXT_MUTED),
+            ]
+            .spacing(Spacing::XXS),
+        ]
+        .spacing(Spacing::MD)
+        .align_y(Alignment::Center)
+        .width(Length::Fill)
+        .padding(Spacing::SM),
+    )
+    .width(Length::Fill)
+    .style(button_style(ButtonVariant::Ghost))
+    .on_press(Message::Open(DeepLink::from(entry)))
+    .into()
+}
+
+/// Marker above the page naming the control a search or link opened
+fn view_highlight(entry: &'static SettingEntry) -> Element<'static, Message> {
+    container(
+        row![
+            text("󰍉")
+                .size(Typography::SIZE_ICON_SM)
+                .color(NyxColors::AURORA),
+            text(format!("{} › {}", entry.page.title(), entry.title))
+                .size(Typography::SIZE_BODY_SMALL)
+                .color(NyxColors::TEXT_BRIGHT),
+        ]
+        .spacing(Spacing::SM)
+        .align_y(Alignment::Center),
+    )
+    .padding([Spacing::SM, Spacing::LG])
+    .into()
+}
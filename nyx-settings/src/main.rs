@@ -6,13 +6,19 @@
 //! - Display (Resolution, Night Light, Scaling)
 //! - Sound (Volume, Input/Output devices)
 //! - Appearance (Theme, Colors, Fonts)
+//! - Personas (AI personas, memory, rituals)
 //! - Notifications
 //! - Power
 //! - Users
 //! - About
+//!
+//! Launched with a `nyx-settings://<page>/<control>` link, e.g.
+//! `nyx-settings nyx-settings://display/night-light`, it opens straight to
+//! that control.
 
 mod app;
 mod pages;
+mod search;
 
 use app::NyxSettings;
 use iced::Application;
@@ -30,8 +36,17 @@ fn main() -> iced::Result {
 
     tracing::info!("Starting Nyx Settings");
 
+    let link = std::env::args().nth(1).and_then(|uri| {
+        let link = search::DeepLink::parse(&uri);
+        if link.is_none() {
+            tracing::warn!("Ignoring unknown settings link: {}", uri);
+        }
+        link
+    });
+
     // Run settings app
     NyxSettings::run(iced::Settings {
+        flags: link,
         window: iced::window::Settings {
             size: iced::Size::new(1000.0, 700.0),
             position: iced::window::Position::Centered,
@@ -2,11 +2,14 @@
 
 pub mod about;
 pub mod appearance;
+pub mod default_apps;
 pub mod display;
 pub mod network;
 pub mod notifications;
+pub mod personas;
 pub mod power;
 pub mod sound;
+pub mod users;
 
 use iced::Element;
 use serde::{Deserialize, Serialize};
@@ -25,6 +28,10 @@ pub enum SettingsPage {
     Sound,
     /// Theme and appearance
     Appearance,
+    /// Default applications and file types
+    DefaultApps,
+    /// AI personas, their memory and rituals
+    Personas,
     /// Notification preferences
     Notifications,
     /// Power and battery
@@ -44,6 +51,8 @@ impl SettingsPage {
             SettingsPage::Display => "Display",
             SettingsPage::Sound => "Sound",
             SettingsPage::Appearance => "Appearance",
+            SettingsPage::DefaultApps => "Default Apps",
+            SettingsPage::Personas => "Personas",
             SettingsPage::Notifications => "Notifications",
             SettingsPage::Power => "Power",
             SettingsPage::Users => "Users",
@@ -59,6 +68,8 @@ impl SettingsPage {
             SettingsPage::Display => "󰍹",
             SettingsPage::Sound => "󰕾",
             SettingsPage::Appearance => "󰏘",
+            SettingsPage::DefaultApps => "󰀻",
+            SettingsPage::Personas => "󰚩",
             SettingsPage::Notifications => "󰂚",
             SettingsPage::Power => "󰂄",
             SettingsPage::Users => "󰀄",
@@ -74,13 +85,37 @@ impl SettingsPage {
             SettingsPage::Display => "Resolution, scaling, night light",
             SettingsPage::Sound => "Volume, input/output devices",
             SettingsPage::Appearance => "Theme, colors, fonts",
+            SettingsPage::DefaultApps => "Browser, mail, file types",
+            SettingsPage::Personas => "AI personas, memory, rituals",
             SettingsPage::Notifications => "Alerts and badges",
             SettingsPage::Power => "Battery and power saving",
-            SettingsPage::Users => "Accounts and passwords",
+            SettingsPage::Users => "Accounts and login screen",
             SettingsPage::About => "System information",
         }
     }
 
+    /// Get the page's segment in `nyx-settings://` links
+    pub fn slug(&self) -> &'static str {
+        match self {
+            SettingsPage::Network => "network",
+            SettingsPage::Bluetooth => "bluetooth",
+            SettingsPage::Display => "display",
+            SettingsPage::Sound => "sound",
+            SettingsPage::Appearance => "appearance",
+            SettingsPage::DefaultApps => "default-apps",
+            SettingsPage::Personas => "personas",
+            SettingsPage::Notifications => "notifications",
+            SettingsPage::Power => "power",
+            SettingsPage::Users => "users",
+            SettingsPage::About => "about",
+        }
+    }
+
+    /// Get the page a link segment names
+    pub fn from_slug(slug: &str) -> Option<SettingsPage> {
+        SettingsPage::all().iter().copied().find(|page| page.slug() == slug)
+    }
+
     /// Get all pages
     pub fn all() -> &'static [SettingsPage] {
         &[
@@ -89,6 +124,8 @@ impl SettingsPage {
             SettingsPage::Display,
             SettingsPage::Sound,
             SettingsPage::Appearance,
+            SettingsPage::DefaultApps,
+            SettingsPage::Personas,
             SettingsPage::Notifications,
             SettingsPage::Power,
             SettingsPage::Users,
@@ -142,6 +179,8 @@ mod tests {
         assert_eq!(SettingsPage::Display.title(), "Display");
         assert_eq!(SettingsPage::Sound.title(), "Sound");
         assert_eq!(SettingsPage::Appearance.title(), "Appearance");
+        assert_eq!(SettingsPage::DefaultApps.title(), "Default Apps");
+        assert_eq!(SettingsPage::Personas.title(), "Personas");
         assert_eq!(SettingsPage::Notifications.title(), "Notifications");
         assert_eq!(SettingsPage::Power.title(), "Power");
         assert_eq!(SettingsPage::Users.title(), "Users");
@@ -198,6 +237,19 @@ mod tests {
         assert!(SettingsPage::Power.description().contains("Battery"));
     }
 
+    // ═══════════════════════════════════════════════════════════════════════════
+    // PAGE SLUG TESTS
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    #[test]
+    fn test_slug_roundtrip() {
+        for page in SettingsPage::all() {
+            assert_eq!(SettingsPage::from_slug(page.slug()), Some(*page));
+        }
+        assert_eq!(SettingsPage::from_slug("default-apps"), Some(SettingsPage::DefaultApps));
+        assert_eq!(SettingsPage::from_slug("Display"), None);
+    }
+
     // ═══════════════════════════════════════════════════════════════════════════
     // ALL PAGES TESTS
     // ═══════════════════════════════════════════════════════════════════════════
@@ -205,7 +257,7 @@ mod tests {
     #[test]
     fn test_all_pages_count() {
         let all = SettingsPage::all();
-        assert_eq!(all.len(), 9);
+        assert_eq!(all.len(), 11);
     }
 
     #[test]
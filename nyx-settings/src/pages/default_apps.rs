@@ -0,0 +1,567 @@
+//! Default applications settings page
+//!
+//! Which app opens links, mail, folders and each file type. Choices go to
+//! summoner, which keeps them in `mimeapps.list` and opens files with them
+//! from then on.
+
+use iced::widget::{button, column, container, horizontal_space, pick_list, row, text, text_input};
+use iced::{Alignment, Command, Element, Length};
+use libnyx_ipc::apps::AppInfo;
+use libnyx_ipc::AppsClient;
+use nyx_theme::colors::NyxColors;
+use nyx_theme::spacing::Spacing;
+use nyx_theme::widgets::button::{button_style, ButtonVariant};
+use nyx_theme::widgets::card::card_style;
+use nyx_theme::widgets::input::{input_style, InputVariant};
+use nyx_theme::widgets::CardVariant;
+use nyx_theme::Typography;
+
+/// Most MIME types the picker lists at once
+const MAX_MATCHES: usize = 8;
+
+/// Kind of app with one default across related MIME types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppRole {
+    Browser,
+    Mail,
+    Files,
+    Text,
+    Images,
+    Music,
+    Video,
+}
+
+impl AppRole {
+    /// Get all roles
+    pub fn all() -> &'static [AppRole] {
+        &[
+            AppRole::Browser,
+            AppRole::Mail,
+            AppRole::Files,
+            AppRole::Text,
+            AppRole::Images,
+            AppRole::Music,
+            AppRole::Video,
+        ]
+    }
+
+    /// Get the role title
+    pub fn title(&self) -> &'static str {
+        match self {
+            AppRole::Browser => "Web Browser",
+            AppRole::Mail => "Mail",
+            AppRole::Files => "File Manager",
+            AppRole::Text => "Text Editor",
+            AppRole::Images => "Image Viewer",
+            AppRole::Music => "Music",
+            AppRole::Video => "Video",
+        }
+    }
+
+    /// Get the role icon
+    pub fn icon(&self) -> &'static str {
+        match self {
+            AppRole::Browser => "󰖟",
+            AppRole::Mail => "󰇮",
+            AppRole::Files => "󰉋",
+            AppRole::Text => "󰈙",
+            AppRole::Images => "󰋩",
+            AppRole::Music => "󰝚",
+            AppRole::Video => "󰕧",
+        }
+    }
+
+    /// Types the role's default opens; candidates are apps opening the first
+    pub fn mime_types(&self) -> &'static [&'static str] {
+        match self {
+            AppRole::Browser => &["x-scheme-handler/https", "x-scheme-handler/http", "text/html"],
+            AppRole::Mail => &["x-scheme-handler/mailto"],
+            AppRole::Files => &["inode/directory"],
+            AppRole::Text => &["text/plain"],
+            AppRole::Images => &["image/png", "image/jpeg", "image/gif", "image/webp"],
+            AppRole::Music => &["audio/mpeg", "audio/flac", "audio/ogg"],
+            AppRole::Video => &["video/mp4", "video/x-matroska", "video/webm"],
+        }
+    }
+
+    /// Whether a role sets the default for a type
+    pub fn covers(mime: &str) -> bool {
+        Self::all().iter().any(|role| role.mime_types().contains(&mime))
+    }
+}
+
+/// A role's candidates and current default
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoleDefault {
+    pub role: AppRole,
+    pub handlers: Vec<AppInfo>,
+    pub current: Option<AppInfo>,
+}
+
+/// Default for one type outside the roles
+#[derive(Debug, Clone, PartialEq)]
+pub struct MimeOverride {
+    pub mime: String,
+    /// None once the chosen app is uninstalled and nothing else opens the type
+    pub app: Option<AppInfo>,
+}
+
+/// Everything the page shows, read in one go
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Associations {
+    pub roles: Vec<RoleDefault>,
+    pub overrides: Vec<MimeOverride>,
+    pub mime_types: Vec<String>,
+}
+
+/// Default apps page state
+#[derive(Debug, Clone, Default)]
+pub struct DefaultAppsPage {
+    /// Last read associations
+    pub associations: Associations,
+    /// Read under way
+    pub loading: bool,
+    /// Why the last request failed
+    pub error: Option<String>,
+    /// MIME picker search
+    pub query: String,
+    /// Type picked for a new override, with the apps that open it
+    pub picked: Option<(String, Vec<AppInfo>)>,
+}
+
+/// Default apps messages
+#[derive(Debug, Clone)]
+pub enum DefaultAppsMessage {
+    /// Associations read
+    Loaded(Result<Associations, String>),
+    /// Choose a role's default
+    SetRole(AppRole, AppInfo),
+    /// MIME picker search changed
+    QueryChanged(String),
+    /// Start an override for a type
+    PickMime(String),
+    /// Apps for the picked type listed
+    HandlersLoaded(String, Result<Vec<AppInfo>, String>),
+    /// Choose the picked type's default
+    SetOverride(AppInfo),
+    /// Close the picker
+    CancelPick,
+    /// Go back to the system's choice for a type
+    RemoveOverride(String),
+    /// A change was saved
+    Saved(Result<(), String>),
+}
+
+impl DefaultAppsPage {
+    /// Read the associations; done whenever the page is opened, so apps
+    /// installed since show up
+    pub fn load(&mut self) -> Command<DefaultAppsMessage> {
+        if self.loading {
+            return Command::none();
+        }
+        self.loading = true;
+        Command::perform(fetch(), DefaultAppsMessage::Loaded)
+    }
+
+    /// MIME types matching the picker search
+    pub fn matching_mime_types(&self) -> Vec<&str> {
+        let query = self.query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        self.associations
+            .mime_types
+            .iter()
+            .filter(|mime| mime.to_lowercase().contains(&query))
+            .take(MAX_MATCHES)
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Update state
+    pub fn update(&mut self, message: DefaultAppsMessage) -> Command<DefaultAppsMessage> {
+        match message {
+            DefaultAppsMessage::Loaded(result) => {
+                self.loading = false;
+                match result {
+                    Ok(associations) => {
+                        self.associations = associations;
+                        self.error = None;
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            DefaultAppsMessage::SetRole(role, app) => {
+                if let Some(entry) = self.associations.roles.iter_mut().find(|r| r.role == role) {
+                    entry.current = Some(app.clone());
+                }
+                let mimes = role.mime_types();
+                return Command::perform(
+                    async move {
+                        let client = AppsClient::new();
+                        for mime in mimes {
+                            client
+                                .set_default_app(mime, Some(&app.id))
+                                .await
+                                .map_err(|e| e.to_string())?;
+                        }
+                        Ok(())
+                    },
+                    DefaultAppsMessage::Saved,
+                );
+            }
+            DefaultAppsMessage::QueryChanged(query) => {
+                self.query = query;
+            }
+            DefaultAppsMessage::PickMime(mime) => {
+                self.picked = Some((mime.clone(), Vec::new()));
+                self.query.clear();
+                return Command::perform(
+                    async move {
+                        let handlers = AppsClient::new().handlers(&mime).await.map_err(|e| e.to_string());
+                        (mime, handlers)
+                    },
+                    |(mime, handlers)| DefaultAppsMessage::HandlersLoaded(mime, handlers),
+                );
+            }
+            DefaultAppsMessage::HandlersLoaded(mime, result) => match result {
+                Ok(handlers) => {
+                    if let Some((picked, apps)) = &mut self.picked {
+                        if *picked == mime {
+                            *apps = handlers;
+                        }
+                    }
+                }
+                Err(e) => {
+                    self.picked = None;
+                    self.error = Some(e);
+                }
+            },
+            DefaultAppsMessage::SetOverride(app) => {
+                let Some((mime, _)) = self.picked.take() else {
+                    return Command::none();
+                };
+                return Command::perform(set_default(mime, Some(app.id)), DefaultAppsMessage::Saved);
+            }
+            DefaultAppsMessage::CancelPick => {
+                self.picked = None;
+            }
+            DefaultAppsMessage::RemoveOverride(mime) => {
+                self.associations.overrides.retain(|o| o.mime != mime);
+                return Command::perform(set_default(mime, None), DefaultAppsMessage::Saved);
+            }
+            DefaultAppsMessage::Saved(result) => {
+                if let Err(e) = result {
+                    self.error = Some(e);
+                }
+                return self.load();
+            }
+        }
+        Command::none()
+    }
+
+    /// View the page
+    pub fn view(&self) -> Element<DefaultAppsMessage> {
+        let mut page = column![
+            text("Default Apps")
+                .size(Typography::SIZE_HEADLINE_LARGE)
+                .color(NyxColors::TEXT_BRIGHT),
+            text("Choose which apps open links, mail, folders and files")
+                .size(Typography::SIZE_BODY_MEDIUM)
+                .color(NyxColors::TEXT_SECONDARY),
+        ]
+        .spacing(Spacing::MD)
+        .width(Length::Fill)
+        .padding(Spacing::LG);
+
+        if let Some(error) = &self.error {
+            page = page.push(
+                text(error)
+                    .size(Typography::SIZE_BODY_SMALL)
+                    .color(NyxColors::ERROR),
+            );
+        }
+
+        page.push(self.view_roles()).push(self.view_overrides()).into()
+    }
+
+    fn view_roles(&self) -> Element<DefaultAppsMessage> {
+        let mut roles = column![].spacing(Spacing::SM);
+        for entry in &self.associations.roles {
+            let role = entry.role;
+            let choice: Element<DefaultAppsMessage> = if entry.handlers.is_empty() {
+                text("No apps installed")
+                    .size(Typography::SIZE_BODY_SMALL)
+                    .color(NyxColors::TEXT_MUTED)
+                    .into()
+            } else {
+                pick_list(entry.handlers.clone(), entry.current.clone(), move |app| {
+                    DefaultAppsMessage::SetRole(role, app)
+                })
+                .width(Length::Fixed(240.0))
+                .into()
+            };
+            roles = roles.push(
+                row![
+                    text(role.icon())
+                        .size(Typography::SIZE_ICON_MD)
+                        .color(NyxColors::TEXT_SECONDARY),
+                    text(role.title())
+                        .size(Typography::SIZE_BODY_MEDIUM)
+                        .color(NyxColors::TEXT_BRIGHT)
+                        .width(Length::Fill),
+                    choice,
+                ]
+                .spacing(Spacing::MD)
+                .align_y(Alignment::Center),
+            );
+        }
+        if self.associations.roles.is_empty() {
+            roles = roles.push(
+                text(if self.loading {
+                    "Loading…"
+                } else {
+                    "The launcher is not running"
+                })
+                .size(Typography::SIZE_BODY_SMALL)
+                .color(NyxColors::TEXT_MUTED),
+            );
+        }
+
+        container(roles)
+            .padding(Spacing::LG)
+            .style(card_style(CardVariant::Default))
+            .into()
+    }
+
+    fn view_overrides(&self) -> Element<DefaultAppsMessage> {
+        let mut content = column![
+            text("File Types")
+                .size(Typography::SIZE_TITLE_MEDIUM)
+                .color(NyxColors::TEXT_BRIGHT),
+            text("Open specific types with a different app")
+                .size(Typography::SIZE_BODY_SMALL)
+                .color(NyxColors::TEXT_SECONDARY),
+        ]
+        .spacing(Spacing::SM);
+
+        for entry in &self.associations.overrides {
+            content = content.push(
+                row![
+                    text(&entry.mime)
+                        .size(Typography::SIZE_BODY_MEDIUM)
+                        .color(NyxColors::TEXT_BRIGHT)
+                        .width(Length::Fill),
+                    text(entry.app.as_ref().map_or("Not installed", |app| app.name.as_str()))
+                        .size(Typography::SIZE_BODY_SMALL)
+                        .color(NyxColors::TEXT_SECONDARY),
+                    button(text("Reset"))
+                        .style(button_style(ButtonVariant::Ghost))
+                        .on_press(DefaultAppsMessage::RemoveOverride(entry.mime.clone())),
+                ]
+                .spacing(Spacing::MD)
+                .align_y(Alignment::Center),
+            );
+        }
+
+        if let Some((mime, handlers)) = &self.picked {
+            let choice: Element<DefaultAppsMessage> = if handlers.is_empty() {
+                text("No apps open this type")
+                    .size(Typography::SIZE_BODY_SMALL)
+                    .color(NyxColors::TEXT_MUTED)
+                    .into()
+            } else {
+                pick_list(handlers.clone(), None::<AppInfo>, DefaultAppsMessage::SetOverride)
+                    .placeholder("Choose an app")
+                    .width(Length::Fixed(240.0))
+                    .into()
+            };
+            content = content.push(
+                row![
+                    text(mime)
+                        .size(Typography::SIZE_BODY_MEDIUM)
+                        .color(NyxColors::AURORA)
+                        .width(Length::Fill),
+                    choice,
+                    button(text("Cancel"))
+                        .style(button_style(ButtonVariant::Ghost))
+                        .on_press(DefaultAppsMessage::CancelPick),
+                ]
+                .spacing(Spacing::MD)
+                .align_y(Alignment::Center),
+            );
+        } else {
+            content = content.push(
+                text_input("Search file types, e.g. pdf or image/", &self.query)
+                    .on_input(DefaultAppsMessage::QueryChanged)
+                    .style(input_style(InputVariant::Search)),
+            );
+            for mime in self.matching_mime_types() {
+                content = content.push(
+                    button(
+                        row![
+                            text(mime)
+                                .size(Typography::SIZE_BODY_SMALL)
+                                .color(NyxColors::TEXT_BRIGHT),
+                            horizontal_space(),
+                        ]
+                        .width(Length::Fill),
+                    )
+                    .width(Length::Fill)
+                    .style(button_style(ButtonVariant::Ghost))
+                    .on_press(DefaultAppsMessage::PickMime(mime.to_string())),
+                );
+            }
+        }
+
+        container(content)
+            .padding(Spacing::LG)
+            .style(card_style(CardVariant::Default))
+            .into()
+    }
+}
+
+async fn fetch() -> Result<Associations, String> {
+    let client = AppsClient::new();
+    let mut roles = Vec::new();
+    for &role in AppRole::all() {
+        let mime = role.mime_types()[0];
+        roles.push(RoleDefault {
+            role,
+            handlers: client.handlers(mime).await.map_err(|e| e.to_string())?,
+            current: client.default_app(mime).await.map_err(|e| e.to_string())?,
+        });
+    }
+
+    let mut overrides = Vec::new();
+    for chosen in client.defaults().await.map_err(|e| e.to_string())? {
+        if AppRole::covers(&chosen.mime) {
+            continue;
+        }
+        let app = client.default_app(&chosen.mime).await.map_err(|e| e.to_string())?;
+        overrides.push(MimeOverride {
+            mime: chosen.mime,
+            app,
+        });
+    }
+
+    Ok(Associations {
+        roles,
+        overrides,
+        mime_types: client.mime_types().await.map_err(|e| e.to_string())?,
+    })
+}
+
+async fn set_default(mime: String, app_id: Option<String>) -> Result<(), String> {
+    AppsClient::new()
+        .set_default_app(&mime, app_id.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(id: &str, name: &str) -> AppInfo {
+        AppInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+            icon: None,
+        }
+    }
+
+    fn loaded_page() -> DefaultAppsPage {
+        let mut page = DefaultAppsPage::default();
+        let _ = page.update(DefaultAppsMessage::Loaded(Ok(Associations {
+            roles: vec![RoleDefault {
+                role: AppRole::Browser,
+                handlers: vec![app("chromium", "Chromium"), app("firefox", "Firefox")],
+                current: Some(app("firefox", "Firefox")),
+            }],
+            overrides: vec![MimeOverride {
+                mime: "application/pdf".to_string(),
+                app: Some(app("zathura", "Zathura")),
+            }],
+            mime_types: vec![
+                "application/pdf".to_string(),
+                "image/png".to_string(),
+                "image/jpeg".to_string(),
+                "text/plain".to_string(),
+            ],
+        })));
+        page
+    }
+
+    #[test]
+    fn test_roles_have_types() {
+        for role in AppRole::all() {
+            assert!(!role.title().is_empty());
+            assert!(!role.mime_types().is_empty());
+        }
+        assert!(AppRole::covers("x-scheme-handler/http"));
+        assert!(AppRole::covers("inode/directory"));
+        assert!(!AppRole::covers("application/pdf"));
+    }
+
+    #[test]
+    fn test_set_role() {
+        let mut page = loaded_page();
+        let _ = page.update(DefaultAppsMessage::SetRole(AppRole::Browser, app("chromium", "Chromium")));
+
+        assert_eq!(
+            page.associations.roles[0].current.as_ref().map(|a| a.id.as_str()),
+            Some("chromium")
+        );
+    }
+
+    #[test]
+    fn test_mime_search() {
+        let mut page = loaded_page();
+        assert!(page.matching_mime_types().is_empty());
+
+        let _ = page.update(DefaultAppsMessage::QueryChanged("IMAGE".to_string()));
+        assert_eq!(page.matching_mime_types(), vec!["image/png", "image/jpeg"]);
+    }
+
+    #[test]
+    fn test_pick_and_override() {
+        let mut page = loaded_page();
+        let _ = page.update(DefaultAppsMessage::QueryChanged("png".to_string()));
+        let _ = page.update(DefaultAppsMessage::PickMime("image/png".to_string()));
+        assert!(page.query.is_empty());
+
+        // A late answer for another type is ignored
+        let _ = page.update(DefaultAppsMessage::HandlersLoaded(
+            "text/plain".to_string(),
+            Ok(vec![app("helix", "Helix")]),
+        ));
+        assert!(page.picked.as_ref().unwrap().1.is_empty());
+
+        let _ = page.update(DefaultAppsMessage::HandlersLoaded(
+            "image/png".to_string(),
+            Ok(vec![app("loupe", "Image Viewer")]),
+        ));
+        assert_eq!(page.picked.as_ref().unwrap().1.len(), 1);
+
+        let _ = page.update(DefaultAppsMessage::SetOverride(app("loupe", "Image Viewer")));
+        assert!(page.picked.is_none());
+    }
+
+    #[test]
+    fn test_remove_override() {
+        let mut page = loaded_page();
+        let _ = page.update(DefaultAppsMessage::RemoveOverride("application/pdf".to_string()));
+        assert!(page.associations.overrides.is_empty());
+    }
+
+    #[test]
+    fn test_load_failure_keeps_associations() {
+        let mut page = loaded_page();
+        let _ = page.update(DefaultAppsMessage::Loaded(Err("Service unavailable".to_string())));
+
+        assert_eq!(page.associations.roles.len(), 1);
+        assert_eq!(page.error.as_deref(), Some("Service unavailable"));
+        assert!(!page.loading);
+    }
+}
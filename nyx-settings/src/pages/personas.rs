@@ -0,0 +1,1185 @@
+//! Personas settings page
+//!
+//! Create and edit grimoire personas — prompt, model, voice and colors —
+//! look through and clear what each one remembers, and choose the rituals
+//! it may run. Built-in personas are read-only in grimoire; duplicating one
+//! is how to start from it.
+
+use grimoire_client::{ClientError, GrimoireClient};
+use grimoire_core::{
+    Formality, MemoryEntry, MemoryEntryType, Persona, PersonaId, PersonaMemory, Ritual, Tone,
+    Verbosity,
+};
+use iced::widget::{
+    button, column, container, horizontal_space, row, text, text_editor, text_input, toggler,
+};
+use iced::{Alignment, Color, Command, Element, Length};
+use nyx_theme::colors::NyxColors;
+use nyx_theme::spacing::Spacing;
+use nyx_theme::widgets::button::{button_style, ButtonVariant};
+use nyx_theme::widgets::card::card_style;
+use nyx_theme::widgets::input::{input_style, InputVariant};
+use nyx_theme::widgets::{segmented_control, CardVariant, StyleVariant};
+use nyx_theme::Typography;
+
+/// Most entries of each memory kind listed
+const MEMORY_PREVIEW: usize = 20;
+
+/// Characters of a memory entry shown before it is cut off
+const ENTRY_PREVIEW_CHARS: usize = 160;
+
+const TONES: &[(Tone, &str)] = &[
+    (Tone::Neutral, "Neutral"),
+    (Tone::Friendly, "Friendly"),
+    (Tone::Professional, "Professional"),
+    (Tone::Analytical, "Analytical"),
+    (Tone::Practical, "Practical"),
+    (Tone::Cautious, "Cautious"),
+    (Tone::Playful, "Playful"),
+];
+
+const FORMALITIES: &[(Formality, &str)] = &[
+    (Formality::Casual, "Casual"),
+    (Formality::Moderate, "Moderate"),
+    (Formality::Formal, "Formal"),
+];
+
+const VERBOSITIES: &[(Verbosity, &str)] = &[
+    (Verbosity::Concise, "Concise"),
+    (Verbosity::Moderate, "Moderate"),
+    (Verbosity::Detailed, "Detailed"),
+];
+
+/// A persona being created or edited, as the form holds it
+#[derive(Debug)]
+pub struct PersonaEditor {
+    /// Persona the form started from; fields the form doesn't show are kept
+    base: Persona,
+    /// Whether saving registers a new persona
+    pub is_new: bool,
+    pub name: String,
+    pub description: String,
+    pub system_prompt: text_editor::Content,
+    pub local_model: String,
+    pub remote_provider: String,
+    pub remote_model: String,
+    pub remote_over_tor: bool,
+    pub temperature: String,
+    pub top_p: String,
+    pub tone: Tone,
+    pub formality: Formality,
+    pub verbosity: Verbosity,
+    /// Personality traits, comma separated
+    pub traits: String,
+    pub color_primary: String,
+    pub color_secondary: String,
+    /// Names of the rituals the persona may run
+    pub rituals: Vec<String>,
+}
+
+impl PersonaEditor {
+    /// Form for a new persona with grimoire's defaults
+    pub fn create() -> Self {
+        Self::from_persona(Persona::new(""), true)
+    }
+
+    /// Form for an existing persona
+    pub fn edit(persona: &Persona) -> Self {
+        Self::from_persona(persona.clone(), false)
+    }
+
+    /// Form for a new persona copying another, e.g. a built-in
+    pub fn duplicate(persona: &Persona) -> Self {
+        let mut copy = persona.clone();
+        copy.id = PersonaId::new();
+        copy.name = format!("{} Copy", persona.name);
+        copy.appearance.theme_class = None;
+        Self::from_persona(copy, true)
+    }
+
+    fn from_persona(persona: Persona, is_new: bool) -> Self {
+        let model = &persona.model;
+        Self {
+            is_new,
+            name: persona.name.clone(),
+            description: persona.description.clone(),
+            system_prompt: text_editor::Content::with_text(&persona.system_prompt),
+            local_model: model.local_model.clone().unwrap_or_default(),
+            remote_provider: model.remote_provider.clone().unwrap_or_default(),
+            remote_model: model.remote_model.clone().unwrap_or_default(),
+            remote_over_tor: model.remote_over_tor,
+            temperature: model.temperature.to_string(),
+            top_p: model.top_p.to_string(),
+            tone: persona.voice.tone,
+            formality: persona.voice.formality,
+            verbosity: persona.voice.verbosity,
+            traits: persona.voice.personality_traits.join(", "),
+            color_primary: persona.appearance.color_primary.clone(),
+            color_secondary: persona.appearance.color_secondary.clone(),
+            rituals: persona.rituals.clone(),
+            base: persona,
+        }
+    }
+
+    /// Id of the persona being edited
+    pub fn id(&self) -> PersonaId {
+        self.base.id
+    }
+
+    /// Persona the form describes, checked against the others installed
+    pub fn to_persona(&self, others: &[Persona]) -> Result<Persona, String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("Enter a name".to_string());
+        }
+        let mut persona = self.base.clone();
+        persona.name = name.to_string();
+        if persona.is_builtin() {
+            return Err(format!("{} is the name of a built-in persona", name));
+        }
+        let taken = others
+            .iter()
+            .any(|other| other.id != persona.id && other.name.eq_ignore_ascii_case(name));
+        if taken {
+            return Err(format!("A persona named {} already exists", name));
+        }
+
+        let temperature = parse_in_range(&self.temperature, 0.0, 2.0)
+            .ok_or("Temperature must be a number from 0 to 2")?;
+        let top_p = parse_in_range(&self.top_p, 0.0, 1.0).ok_or("Top-p must be a number from 0 to 1")?;
+        for color in [&self.color_primary, &self.color_secondary] {
+            if parse_hex(color).is_none() {
+                return Err(format!("{} is not a color like #6366f1", color.trim()));
+            }
+        }
+
+        persona.description = self.description.trim().to_string();
+        persona.system_prompt = self.system_prompt.text().trim_end().to_string();
+        persona.model.local_model = non_empty(&self.local_model);
+        persona.model.remote_provider = non_empty(&self.remote_provider);
+        persona.model.remote_model = non_empty(&self.remote_model);
+        persona.model.remote_over_tor = self.remote_over_tor;
+        persona.model.temperature = temperature;
+        persona.model.top_p = top_p;
+        if persona.model.local_model.is_none() && persona.model.remote_model.is_none() {
+            return Err("Choose a local or remote model".to_string());
+        }
+        persona.voice.tone = self.tone;
+        persona.voice.formality = self.formality;
+        persona.voice.verbosity = self.verbosity;
+        persona.voice.personality_traits = self
+            .traits
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(String::from)
+            .collect();
+        persona.appearance.color_primary = self.color_primary.trim().to_string();
+        persona.appearance.color_secondary = self.color_secondary.trim().to_string();
+        persona.rituals = self.rituals.clone();
+        Ok(persona)
+    }
+}
+
+/// Personas page state
+#[derive(Debug, Default)]
+pub struct PersonasPage {
+    /// Personas grimoire knows
+    pub personas: Vec<Persona>,
+    /// Rituals installed, assignable to personas
+    pub rituals: Vec<Ritual>,
+    /// Read under way
+    pub loading: bool,
+    /// Why the last request failed
+    pub error: Option<String>,
+    /// Persona whose details and memory are shown
+    pub selected: Option<PersonaId>,
+    /// Selected persona's memory, once read
+    pub memory: Option<PersonaMemory>,
+    /// Clearing all memory was asked for and awaits confirmation
+    pub confirm_clear: bool,
+    /// Open create/edit form
+    pub editor: Option<PersonaEditor>,
+    /// Save or delete under way
+    pub busy: bool,
+}
+
+/// Personas messages
+#[derive(Debug, Clone)]
+pub enum PersonasMessage {
+    /// Personas and rituals read
+    Loaded(Result<(Vec<Persona>, Vec<Ritual>), String>),
+    /// Show a persona
+    Select(PersonaId),
+    /// A persona's memory read
+    MemoryLoaded(PersonaId, Result<PersonaMemory, String>),
+    /// Forget the current session
+    ClearSession,
+    /// Ask to forget everything
+    ClearAll,
+    /// Forget everything, confirmed
+    ConfirmClearAll,
+    /// Keep the memory after all
+    CancelClear,
+    /// Memory cleared
+    MemoryCleared(Result<(), String>),
+    /// Open the form for a new persona
+    New,
+    /// Open the form for a copy of a persona
+    Duplicate(PersonaId),
+    /// Open the form for a persona
+    Edit(PersonaId),
+    /// Close the form without saving
+    CancelEdit,
+    NameChanged(String),
+    DescriptionChanged(String),
+    PromptEdited(text_editor::Action),
+    LocalModelChanged(String),
+    RemoteProviderChanged(String),
+    RemoteModelChanged(String),
+    RemoteOverTor(bool),
+    TemperatureChanged(String),
+    TopPChanged(String),
+    ToneSelected(Tone),
+    FormalitySelected(Formality),
+    VerbositySelected(Verbosity),
+    TraitsChanged(String),
+    PrimaryColorChanged(String),
+    SecondaryColorChanged(String),
+    /// Allow or disallow a ritual by name
+    ToggleRitual(String, bool),
+    /// Save the form
+    Save,
+    /// Persona saved
+    Saved(Result<PersonaId, String>),
+    /// Remove a persona and its memory
+    Delete(PersonaId),
+    /// Persona removed
+    Deleted(Result<(), String>),
+}
+
+impl PersonasPage {
+    /// Read personas and rituals; done whenever the page is opened
+    pub fn load(&mut self) -> Command<PersonasMessage> {
+        if self.loading {
+            return Command::none();
+        }
+        self.loading = true;
+        Command::perform(fetch(), PersonasMessage::Loaded)
+    }
+
+    /// Persona whose details are shown
+    pub fn selected(&self) -> Option<&Persona> {
+        let id = self.selected?;
+        self.personas.iter().find(|p| p.id == id)
+    }
+
+    fn select(&mut self, id: PersonaId) -> Command<PersonasMessage> {
+        self.selected = Some(id);
+        self.memory = None;
+        self.confirm_clear = false;
+        Command::perform(fetch_memory(id), move |result| {
+            PersonasMessage::MemoryLoaded(id, result)
+        })
+    }
+
+    /// Update state
+    pub fn update(&mut self, message: PersonasMessage) -> Command<PersonasMessage> {
+        if let Some(editor) = &mut self.editor {
+            if edit(editor, &message) {
+                return Command::none();
+            }
+        }
+
+        match message {
+            PersonasMessage::Loaded(result) => {
+                self.loading = false;
+                match result {
+                    Ok((personas, rituals)) => {
+                        self.personas = personas;
+                        self.rituals = rituals;
+                        self.error = None;
+                        let id = self
+                            .selected()
+                            .or(self.personas.first())
+                            .map(|persona| persona.id);
+                        if let Some(id) = id {
+                            return self.select(id);
+                        }
+                        self.selected = None;
+                        self.memory = None;
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            PersonasMessage::Select(id) => {
+                self.editor = None;
+                return self.select(id);
+            }
+            PersonasMessage::MemoryLoaded(id, result) => {
+                if self.selected != Some(id) {
+                    return Command::none();
+                }
+                match result {
+                    Ok(memory) => self.memory = Some(memory),
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            PersonasMessage::ClearSession => {
+                let Some(id) = self.selected else {
+                    return Command::none();
+                };
+                return Command::perform(
+                    async move { client().await?.clear_session_memory(id).await.map_err(|e| e.to_string()) },
+                    PersonasMessage::MemoryCleared,
+                );
+            }
+            PersonasMessage::ClearAll => {
+                self.confirm_clear = true;
+            }
+            PersonasMessage::ConfirmClearAll => {
+                self.confirm_clear = false;
+                let Some(id) = self.selected else {
+                    return Command::none();
+                };
+                return Command::perform(
+                    async move { client().await?.clear_all_memory(id).await.map_err(|e| e.to_string()) },
+                    PersonasMessage::MemoryCleared,
+                );
+            }
+            PersonasMessage::CancelClear => {
+                self.confirm_clear = false;
+            }
+            PersonasMessage::MemoryCleared(result) => {
+                if let Err(e) = result {
+                    self.error = Some(e);
+                }
+                if let Some(id) = self.selected {
+                    return self.select(id);
+                }
+            }
+            PersonasMessage::New => {
+                self.editor = Some(PersonaEditor::create());
+            }
+            PersonasMessage::Duplicate(id) => {
+                if let Some(persona) = self.personas.iter().find(|p| p.id == id) {
+                    self.editor = Some(PersonaEditor::duplicate(persona));
+                }
+            }
+            PersonasMessage::Edit(id) => {
+                if let Some(persona) = self.personas.iter().find(|p| p.id == id) {
+                    self.editor = Some(PersonaEditor::edit(persona));
+                }
+            }
+            PersonasMessage::CancelEdit => {
+                self.editor = None;
+            }
+            PersonasMessage::Save => {
+                let Some(editor) = &self.editor else {
+                    return Command::none();
+                };
+                let persona = match editor.to_persona(&self.personas) {
+                    Ok(persona) => persona,
+                    Err(e) => {
+                        self.error = Some(e);
+                        return Command::none();
+                    }
+                };
+                self.busy = true;
+                self.error = None;
+                let is_new = editor.is_new;
+                return Command::perform(save(persona, is_new), PersonasMessage::Saved);
+            }
+            PersonasMessage::Saved(result) => {
+                self.busy = false;
+                match result {
+                    Ok(id) => {
+                        self.editor = None;
+                        self.selected = Some(id);
+                        return self.load();
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            PersonasMessage::Delete(id) => {
+                self.busy = true;
+                return Command::perform(
+                    async move { client().await?.remove_persona(id).await.map_err(|e| e.to_string()) },
+                    PersonasMessage::Deleted,
+                );
+            }
+            PersonasMessage::Deleted(result) => {
+                self.busy = false;
+                match result {
+                    Ok(()) => {
+                        self.selected = None;
+                        self.memory = None;
+                        return self.load();
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            _ => {}
+        }
+        Command::none()
+    }
+
+    /// View the page
+    pub fn view(&self) -> Element<PersonasMessage> {
+        let mut page = column![
+            row![
+                column![
+                    text("Personas")
+                        .size(Typography::SIZE_HEADLINE_LARGE)
+                        .color(NyxColors::TEXT_BRIGHT),
+                    text("Create personas, inspect their memory and choose their rituals")
+                        .size(Typography::SIZE_BODY_MEDIUM)
+                        .color(NyxColors::TEXT_SECONDARY),
+                ]
+                .spacing(Spacing::XS)
+                .width(Length::Fill),
+                button(text("New Persona"))
+                    .style(button_style(ButtonVariant::Primary))
+                    .on_press_maybe(self.editor.is_none().then_some(PersonasMessage::New)),
+            ]
+            .align_y(Alignment::Center),
+        ]
+        .spacing(Spacing::MD)
+        .width(Length::Fill)
+        .padding(Spacing::LG);
+
+        if let Some(error) = &self.error {
+            page = page.push(
+                text(error)
+                    .size(Typography::SIZE_BODY_SMALL)
+                    .color(NyxColors::ERROR),
+            );
+        }
+
+        page = page.push(self.view_list());
+        if let Some(editor) = &self.editor {
+            page = page.push(self.view_editor(editor));
+        } else if let Some(persona) = self.selected() {
+            page = page
+                .push(view_details(persona, self.busy))
+                .push(self.view_memory());
+        }
+        page.into()
+    }
+
+    fn view_list(&self) -> Element<PersonasMessage> {
+        let mut list = column![].spacing(Spacing::XXS);
+        for persona in &self.personas {
+            let selected = self.selected == Some(persona.id);
+            let mut item = row![
+                color_dot(&persona.appearance.color_primary),
+                column![
+                    text(&persona.name)
+                        .size(Typography::SIZE_BODY_MEDIUM)
+                        .color(if selected { NyxColors::AURORA } else { NyxColors::TEXT_BRIGHT }),
+                    text(&persona.description)
+                        .size(Typography::SIZE_BODY_SMALL)
+                        .color(NyxColors::TEXT_MUTED),
+                ]
+                .spacing(Spacing::XXS)
+                .width(Length::Fill),
+            ]
+            .spacing(Spacing::MD)
+            .align_y(Alignment::Center);
+            if persona.is_builtin() {
+                item = item.push(
+                    text("Built-in")
+                        .size(Typography::SIZE_LABEL_SMALL)
+                        .color(NyxColors::TEXT_MUTED),
+                );
+            }
+            list = list.push(
+                button(item)
+                    .width(Length::Fill)
+                    .style(button_style(ButtonVariant::Ghost))
+                    .on_press(PersonasMessage::Select(persona.id)),
+            );
+        }
+        if self.personas.is_empty() {
+            list = list.push(
+                text(if self.loading {
+                    "Loading…"
+                } else {
+                    "The persona service is not running"
+                })
+                .size(Typography::SIZE_BODY_SMALL)
+                .color(NyxColors::TEXT_MUTED),
+            );
+        }
+
+        container(list)
+            .padding(Spacing::LG)
+            .style(card_style(CardVariant::Default))
+            .into()
+    }
+
+    fn view_memory(&self) -> Element<PersonasMessage> {
+        let mut content = column![text("Memory")
+            .size(Typography::SIZE_TITLE_MEDIUM)
+            .color(NyxColors::TEXT_BRIGHT)]
+        .spacing(Spacing::SM);
+
+        let Some(memory) = &self.memory else {
+            return container(content.push(
+                text("Loading…")
+                    .size(Typography::SIZE_BODY_SMALL)
+                    .color(NyxColors::TEXT_MUTED),
+            ))
+            .padding(Spacing::LG)
+            .style(card_style(CardVariant::Default))
+            .into();
+        };
+
+        content = content.push(
+            text(format!(
+                "{} this session · {} long-term · {} recalls · {} sessions",
+                memory.short_term.len(),
+                memory.long_term.len(),
+                memory.stats.recalls,
+                memory.stats.sessions,
+            ))
+            .size(Typography::SIZE_BODY_SMALL)
+            .color(NyxColors::TEXT_SECONDARY),
+        );
+
+        for (title, entries) in [("This Session", &memory.short_term), ("Long-term", &memory.long_term)] {
+            if entries.is_empty() {
+                continue;
+            }
+            content = content.push(
+                text(title)
+                    .size(Typography::SIZE_LABEL_MEDIUM)
+                    .color(NyxColors::TEXT_MUTED),
+            );
+            for entry in entries.iter().rev().take(MEMORY_PREVIEW) {
+                content = content.push(view_entry(entry));
+            }
+        }
+
+        let actions: Element<PersonasMessage> = if self.confirm_clear {
+            row![
+                text("Forget everything this persona remembers?")
+                    .size(Typography::SIZE_BODY_SMALL)
+                    .color(NyxColors::TEXT_BRIGHT)
+                    .width(Length::Fill),
+                button(text("Cancel"))
+                    .style(button_style(ButtonVariant::Ghost))
+                    .on_press(PersonasMessage::CancelClear),
+                button(text("Forget All"))
+                    .style(button_style(ButtonVariant::Danger))
+                    .on_press(PersonasMessage::ConfirmClearAll),
+            ]
+            .spacing(Spacing::SM)
+            .align_y(Alignment::Center)
+            .into()
+        } else {
+            let empty = memory.short_term.is_empty() && memory.long_term.is_empty();
+            row![
+                horizontal_space(),
+                button(text("Clear Session"))
+                    .style(button_style(ButtonVariant::Secondary))
+                    .on_press_maybe((!memory.short_term.is_empty()).then_some(PersonasMessage::ClearSession)),
+                button(text("Clear All"))
+                    .style(button_style(ButtonVariant::Danger))
+                    .on_press_maybe((!empty).then_some(PersonasMessage::ClearAll)),
+            ]
+            .spacing(Spacing::SM)
+            .into()
+        };
+
+        container(content.push(actions))
+            .padding(Spacing::LG)
+            .style(card_style(CardVariant::Default))
+            .into()
+    }
+
+    fn view_editor<'a>(&'a self, editor: &'a PersonaEditor) -> Element<'a, PersonasMessage> {
+        let title = if editor.is_new { "New Persona" } else { "Edit Persona" };
+
+        let identity = column![
+            field("Name", "Hecate", &editor.name, PersonasMessage::NameChanged),
+            field(
+                "Description",
+                "What this persona is for",
+                &editor.description,
+                PersonasMessage::DescriptionChanged
+            ),
+            label("System Prompt"),
+            text_editor(&editor.system_prompt)
+                .on_action(PersonasMessage::PromptEdited)
+                .height(Length::Fixed(160.0)),
+        ]
+        .spacing(Spacing::SM);
+
+        let model = column![
+            section_title("Model"),
+            row![
+                field("Local model", "mistral-7b-instruct", &editor.local_model, PersonasMessage::LocalModelChanged),
+                field("Remote provider", "anthropic", &editor.remote_provider, PersonasMessage::RemoteProviderChanged),
+                field("Remote model", "claude-3-haiku", &editor.remote_model, PersonasMessage::RemoteModelChanged),
+            ]
+            .spacing(Spacing::MD),
+            row![
+                field("Temperature", "0.7", &editor.temperature, PersonasMessage::TemperatureChanged),
+                field("Top-p", "0.9", &editor.top_p, PersonasMessage::TopPChanged),
+                toggler(editor.remote_over_tor)
+                    .label("Route remote requests over Tor")
+                    .on_toggle(PersonasMessage::RemoteOverTor)
+                    .width(Length::Fill),
+            ]
+            .spacing(Spacing::MD)
+            .align_y(Alignment::End),
+        ]
+        .spacing(Spacing::SM);
+
+        let voice = column![
+            section_title("Voice"),
+            label("Tone"),
+            segmented_control(TONES, &editor.tone, PersonasMessage::ToneSelected, StyleVariant::default()),
+            row![
+                column![
+                    label("Formality"),
+                    segmented_control(
+                        FORMALITIES,
+                        &editor.formality,
+                        PersonasMessage::FormalitySelected,
+                        StyleVariant::default()
+                    ),
+                ]
+                .spacing(Spacing::XXS),
+                column![
+                    label("Verbosity"),
+                    segmented_control(
+                        VERBOSITIES,
+                        &editor.verbosity,
+                        PersonasMessage::VerbositySelected,
+                        StyleVariant::default()
+                    ),
+                ]
+                .spacing(Spacing::XXS),
+            ]
+            .spacing(Spacing::MD),
+            field("Personality traits", "curious, thorough", &editor.traits, PersonasMessage::TraitsChanged),
+        ]
+        .spacing(Spacing::SM);
+
+        let appearance = column![
+            section_title("Appearance"),
+            row![
+                color_dot(&editor.color_primary),
+                field("Primary color", "#6366f1", &editor.color_primary, PersonasMessage::PrimaryColorChanged),
+                color_dot(&editor.color_secondary),
+                field("Secondary color", "#4f46e5", &editor.color_secondary, PersonasMessage::SecondaryColorChanged),
+            ]
+            .spacing(Spacing::MD)
+            .align_y(Alignment::End),
+        ]
+        .spacing(Spacing::SM);
+
+        let mut rituals = column![section_title("Rituals")].spacing(Spacing::SM);
+        for (name, description) in ritual_choices(&self.rituals, &editor.rituals) {
+            let allowed = editor.rituals.contains(&name);
+            rituals = rituals.push(
+                row![
+                    column![
+                        text(name.clone())
+                            .size(Typography::SIZE_BODY_MEDIUM)
+                            .color(NyxColors::TEXT_BRIGHT),
+                        text(description)
+                            .size(Typography::SIZE_BODY_SMALL)
+                            .color(NyxColors::TEXT_MUTED),
+                    ]
+                    .spacing(Spacing::XXS)
+                    .width(Length::Fill),
+                    toggler(allowed).on_toggle(move |on| PersonasMessage::ToggleRitual(name.clone(), on)),
+                ]
+                .spacing(Spacing::MD)
+                .align_y(Alignment::Center),
+            );
+        }
+        if self.rituals.is_empty() && editor.rituals.is_empty() {
+            rituals = rituals.push(
+                text("No rituals installed")
+                    .size(Typography::SIZE_BODY_SMALL)
+                    .color(NyxColors::TEXT_MUTED),
+            );
+        }
+
+        let actions = row![
+            horizontal_space(),
+            button(text("Cancel"))
+                .style(button_style(ButtonVariant::Ghost))
+                .on_press(PersonasMessage::CancelEdit),
+            button(text(if self.busy { "Saving…" } else { "Save" }))
+                .style(button_style(ButtonVariant::Primary))
+                .on_press_maybe((!self.busy).then_some(PersonasMessage::Save)),
+        ]
+        .spacing(Spacing::SM);
+
+        container(
+            column![
+                text(title)
+                    .size(Typography::SIZE_TITLE_MEDIUM)
+                    .color(NyxColors::TEXT_BRIGHT),
+                identity,
+                model,
+                voice,
+                appearance,
+                rituals,
+                actions,
+            ]
+            .spacing(Spacing::LG),
+        )
+        .padding(Spacing::LG)
+        .style(card_style(CardVariant::Default))
+        .into()
+    }
+}
+
+/// Apply a form message to the editor; false if it isn't one
+fn edit(editor: &mut PersonaEditor, message: &PersonasMessage) -> bool {
+    match message {
+        PersonasMessage::NameChanged(value) => editor.name = value.clone(),
+        PersonasMessage::DescriptionChanged(value) => editor.description = value.clone(),
+        PersonasMessage::PromptEdited(action) => editor.system_prompt.perform(action.clone()),
+        PersonasMessage::LocalModelChanged(value) => editor.local_model = value.clone(),
+        PersonasMessage::RemoteProviderChanged(value) => editor.remote_provider = value.clone(),
+        PersonasMessage::RemoteModelChanged(value) => editor.remote_model = value.clone(),
+        PersonasMessage::RemoteOverTor(on) => editor.remote_over_tor = *on,
+        PersonasMessage::TemperatureChanged(value) => editor.temperature = value.clone(),
+        PersonasMessage::TopPChanged(value) => editor.top_p = value.clone(),
+        PersonasMessage::ToneSelected(tone) => editor.tone = *tone,
+        PersonasMessage::FormalitySelected(formality) => editor.formality = *formality,
+        PersonasMessage::VerbositySelected(verbosity) => editor.verbosity = *verbosity,
+        PersonasMessage::TraitsChanged(value) => editor.traits = value.clone(),
+        PersonasMessage::PrimaryColorChanged(value) => editor.color_primary = value.clone(),
+        PersonasMessage::SecondaryColorChanged(value) => editor.color_secondary = value.clone(),
+        PersonasMessage::ToggleRitual(name, on) => {
+            editor.rituals.retain(|r| r != name);
+            if *on {
+                editor.rituals.push(name.clone());
+            }
+        }
+        _ => return false,
+    }
+    true
+}
+
+fn view_details(persona: &Persona, busy: bool) -> Element<PersonasMessage> {
+    let model = &persona.model;
+    let model_summary = match (&model.local_model, &model.remote_provider, &model.remote_model) {
+        (Some(local), _, Some(remote)) => format!("{} locally, {} remotely", local, remote),
+        (Some(local), _, None) => local.clone(),
+        (None, Some(provider), Some(remote)) => format!("{} ({})", remote, provider),
+        (None, _, Some(remote)) => remote.clone(),
+        (None, _, None) => "No model".to_string(),
+    };
+    let rituals = if persona.rituals.is_empty() {
+        "None".to_string()
+    } else {
+        persona.rituals.join(", ")
+    };
+
+    let mut actions = row![horizontal_space()].spacing(Spacing::SM);
+    actions = actions.push(
+        button(text("Duplicate"))
+            .style(button_style(ButtonVariant::Secondary))
+            .on_press(PersonasMessage::Duplicate(persona.id)),
+    );
+    if !persona.is_builtin() {
+        actions = actions
+            .push(
+                button(text("Edit"))
+                    .style(button_style(ButtonVariant::Secondary))
+                    .on_press(PersonasMessage::Edit(persona.id)),
+            )
+            .push(
+                button(text("Delete"))
+                    .style(button_style(ButtonVariant::Danger))
+                    .on_press_maybe((!busy).then_some(PersonasMessage::Delete(persona.id))),
+            );
+    }
+
+    container(
+        column![
+            row![
+                color_dot(&persona.appearance.color_primary),
+                text(&persona.name)
+                    .size(Typography::SIZE_TITLE_MEDIUM)
+                    .color(NyxColors::TEXT_BRIGHT),
+            ]
+            .spacing(Spacing::SM)
+            .align_y(Alignment::Center),
+            detail("Model", model_summary),
+            detail("Temperature", format!("{} · top-p {}", model.temperature, model.top_p)),
+            detail("Voice", voice_summary(persona)),
+            detail("Rituals", rituals),
+            actions,
+        ]
+        .spacing(Spacing::SM),
+    )
+    .padding(Spacing::LG)
+    .style(card_style(CardVariant::Default))
+    .into()
+}
+
+fn view_entry(entry: &MemoryEntry) -> Element<PersonasMessage> {
+    let mut content: String = entry.content.chars().take(ENTRY_PREVIEW_CHARS).collect();
+    if entry.content.chars().count() > ENTRY_PREVIEW_CHARS {
+        content.push('…');
+    }
+    row![
+        text(entry_kind(&entry.entry_type))
+            .size(Typography::SIZE_LABEL_SMALL)
+            .color(NyxColors::AURORA)
+            .width(Length::Fixed(96.0)),
+        text(content)
+            .size(Typography::SIZE_BODY_SMALL)
+            .color(NyxColors::TEXT_BRIGHT)
+            .width(Length::Fill),
+        text(entry.timestamp.format("%Y-%m-%d %H:%M").to_string())
+            .size(Typography::SIZE_LABEL_SMALL)
+            .color(NyxColors::TEXT_MUTED),
+    ]
+    .spacing(Spacing::MD)
+    .into()
+}
+
+fn detail<'a>(name: &'a str, value: String) -> Element<'a, PersonasMessage> {
+    row![
+        text(name)
+            .size(Typography::SIZE_BODY_SMALL)
+            .color(NyxColors::TEXT_MUTED)
+            .width(Length::Fixed(120.0)),
+        text(value)
+            .size(Typography::SIZE_BODY_SMALL)
+            .color(NyxColors::TEXT_BRIGHT),
+    ]
+    .into()
+}
+
+fn field<'a>(
+    name: &'a str,
+    placeholder: &'a str,
+    value: &'a str,
+    on_input: fn(String) -> PersonasMessage,
+) -> Element<'a, PersonasMessage> {
+    column![
+        label(name),
+        text_input(placeholder, value)
+            .on_input(on_input)
+            .style(input_style(InputVariant::Default)),
+    ]
+    .spacing(Spacing::XXS)
+    .width(Length::Fill)
+    .into()
+}
+
+fn label(name: &str) -> Element<PersonasMessage> {
+    text(name)
+        .size(Typography::SIZE_LABEL_MEDIUM)
+        .color(NyxColors::TEXT_SECONDARY)
+        .into()
+}
+
+fn section_title(name: &str) -> Element<PersonasMessage> {
+    text(name)
+        .size(Typography::SIZE_BODY_LARGE)
+        .color(NyxColors::TEXT_BRIGHT)
+        .into()
+}
+
+fn color_dot<'a>(hex: &str) -> Element<'a, PersonasMessage> {
+    let color = parse_hex(hex).unwrap_or(NyxColors::TEXT_MUTED);
+    container(horizontal_space())
+        .width(Length::Fixed(16.0))
+        .height(Length::Fixed(16.0))
+        .style(move |_theme| iced::widget::container::Style {
+            background: Some(iced::Background::Color(color)),
+            border: iced::Border {
+                radius: 8.0.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .into()
+}
+
+/// Rituals to offer, installed ones first, then assigned ones that are no
+/// longer installed so they can still be removed
+fn ritual_choices(installed: &[Ritual], assigned: &[String]) -> Vec<(String, String)> {
+    let mut choices: Vec<(String, String)> = installed
+        .iter()
+        .map(|ritual| (ritual.name.clone(), ritual.description.clone()))
+        .collect();
+    for name in assigned {
+        if !installed.iter().any(|ritual| &ritual.name == name) {
+            choices.push((name.clone(), "Not installed".to_string()));
+        }
+    }
+    choices
+}
+
+fn voice_summary(persona: &Persona) -> String {
+    let voice = &persona.voice;
+    let mut summary = format!(
+        "{}, {}, {}",
+        option_label(TONES, voice.tone),
+        option_label(FORMALITIES, voice.formality).to_lowercase(),
+        option_label(VERBOSITIES, voice.verbosity).to_lowercase(),
+    );
+    if !voice.personality_traits.is_empty() {
+        summary.push_str(&format!(" · {}", voice.personality_traits.join(", ")));
+    }
+    summary
+}
+
+fn option_label<T: PartialEq>(options: &[(T, &'static str)], value: T) -> &'static str {
+    options
+        .iter()
+        .find(|(option, _)| *option == value)
+        .map_or("", |(_, label)| *label)
+}
+
+fn entry_kind(entry_type: &MemoryEntryType) -> &str {
+    match entry_type {
+        MemoryEntryType::UserMessage => "You",
+        MemoryEntryType::PersonaResponse => "Reply",
+        MemoryEntryType::PageContent { .. } => "Page",
+        MemoryEntryType::Fact => "Fact",
+        MemoryEntryType::Preference => "Preference",
+        MemoryEntryType::SessionSummary => "Summary",
+        MemoryEntryType::Custom { kind } => kind,
+    }
+}
+
+/// `#rrggbb` as a color
+fn parse_hex(hex: &str) -> Option<Color> {
+    let digits = hex.trim().strip_prefix('#')?;
+    if digits.len() != 6 {
+        return None;
+    }
+    let value = u32::from_str_radix(digits, 16).ok()?;
+    Some(Color::from_rgb8(
+        (value >> 16) as u8,
+        (value >> 8) as u8,
+        value as u8,
+    ))
+}
+
+fn parse_in_range(value: &str, min: f32, max: f32) -> Option<f32> {
+    let value: f32 = value.trim().parse().ok()?;
+    (min..=max).contains(&value).then_some(value)
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+async fn client() -> Result<GrimoireClient, String> {
+    GrimoireClient::connect_default()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn fetch() -> Result<(Vec<Persona>, Vec<Ritual>), String> {
+    let client = client().await?;
+    let personas = client.list_personas().await.map_err(|e| e.to_string())?;
+    let rituals = client.list_rituals().await.map_err(|e| e.to_string())?;
+    Ok((personas, rituals))
+}
+
+/// A persona's memory; one that never remembered anything has none yet
+async fn fetch_memory(id: PersonaId) -> Result<PersonaMemory, String> {
+    match client().await?.get_memory(id).await {
+        Ok(memory) => Ok(memory),
+        Err(ClientError::NotFound(_)) => Ok(PersonaMemory::new(id)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+async fn save(persona: Persona, is_new: bool) -> Result<PersonaId, String> {
+    let client = client().await?;
+    let id = persona.id;
+    let result = if is_new {
+        client.register_persona(persona).await
+    } else {
+        client.update_persona(persona).await.map(|()| id)
+    };
+    result.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grimoire_core::builtin;
+
+    fn named(name: &str) -> Persona {
+        let mut persona = Persona::new(name);
+        persona.system_prompt = "You help.".to_string();
+        persona
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex("#ff0000"), Some(Color::from_rgb8(255, 0, 0)));
+        assert_eq!(parse_hex(" #6366F1 "), Some(Color::from_rgb8(0x63, 0x66, 0xf1)));
+        assert!(parse_hex("ff0000").is_none());
+        assert!(parse_hex("#fff").is_none());
+        assert!(parse_hex("#gggggg").is_none());
+    }
+
+    #[test]
+    fn test_edit_keeps_hidden_fields() {
+        let mut persona = named("Hecate");
+        persona.tools = vec!["web_search".to_string()];
+        persona.capabilities.can_access_files = true;
+
+        let mut editor = PersonaEditor::edit(&persona);
+        assert!(!editor.is_new);
+        editor.description = "  Crossroads guide ".to_string();
+        editor.traits = "wise, , calm".to_string();
+        editor.remote_model = "claude-3-haiku".to_string();
+        editor.temperature = "1.2".to_string();
+
+        let saved = editor.to_persona(std::slice::from_ref(&persona)).unwrap();
+        assert_eq!(saved.id, persona.id);
+        assert_eq!(saved.description, "Crossroads guide");
+        assert_eq!(saved.voice.personality_traits, ["wise", "calm"]);
+        assert_eq!(saved.model.remote_model.as_deref(), Some("claude-3-haiku"));
+        assert_eq!(saved.model.temperature, 1.2);
+        assert_eq!(saved.system_prompt, "You help.");
+        assert_eq!(saved.tools, persona.tools);
+        assert!(saved.capabilities.can_access_files);
+    }
+
+    #[test]
+    fn test_validation() {
+        let others = vec![named("Hecate")];
+        let mut editor = PersonaEditor::create();
+        assert!(editor.is_new);
+        assert!(editor.to_persona(&others).is_err());
+
+        editor.name = "lilith".to_string();
+        assert!(editor.to_persona(&others).unwrap_err().contains("built-in"));
+
+        editor.name = "HECATE".to_string();
+        assert!(editor.to_persona(&others).unwrap_err().contains("already exists"));
+
+        editor.name = "Morrigan".to_string();
+        assert!(editor.to_persona(&others).is_ok());
+
+        editor.top_p = "1.5".to_string();
+        assert!(editor.to_persona(&others).unwrap_err().contains("Top-p"));
+        editor.top_p = "0.9".to_string();
+
+        editor.color_primary = "red".to_string();
+        assert!(editor.to_persona(&others).unwrap_err().contains("red"));
+        editor.color_primary = "#aa0000".to_string();
+
+        editor.local_model.clear();
+        assert!(editor.to_persona(&others).unwrap_err().contains("model"));
+    }
+
+    #[test]
+    fn test_duplicate_builtin() {
+        let lilith = builtin::lilith();
+        let editor = PersonaEditor::duplicate(&lilith);
+        assert!(editor.is_new);
+        assert_ne!(editor.id(), lilith.id);
+        assert_eq!(editor.name, "Lilith Copy");
+
+        let copy = editor.to_persona(std::slice::from_ref(&lilith)).unwrap();
+        assert!(!copy.is_builtin());
+        assert_eq!(copy.rituals, lilith.rituals);
+        assert_eq!(copy.system_prompt, lilith.system_prompt.trim_end());
+    }
+
+    #[test]
+    fn test_toggle_rituals() {
+        let mut page = PersonasPage::default();
+        let persona = named("Hecate");
+        page.personas = vec![persona.clone()];
+        let _ = page.update(PersonasMessage::Edit(persona.id));
+
+        let _ = page.update(PersonasMessage::ToggleRitual("price_watch".to_string(), true));
+        let _ = page.update(PersonasMessage::ToggleRitual("deep_research".to_string(), true));
+        let _ = page.update(PersonasMessage::ToggleRitual("price_watch".to_string(), false));
+        let editor = page.editor.as_ref().unwrap();
+        assert_eq!(editor.rituals, ["deep_research"]);
+
+        let choices = ritual_choices(&[], &editor.rituals);
+        assert_eq!(choices, [("deep_research".to_string(), "Not installed".to_string())]);
+    }
+
+    #[test]
+    fn test_form_messages_need_editor() {
+        let mut page = PersonasPage::default();
+        let _ = page.update(PersonasMessage::NameChanged("Hecate".to_string()));
+        assert!(page.editor.is_none());
+
+        let _ = page.update(PersonasMessage::New);
+        let _ = page.update(PersonasMessage::NameChanged("Hecate".to_string()));
+        let _ = page.update(PersonasMessage::ToneSelected(Tone::Playful));
+        let editor = page.editor.as_ref().unwrap();
+        assert_eq!(editor.name, "Hecate");
+        assert_eq!(editor.tone, Tone::Playful);
+
+        let _ = page.update(PersonasMessage::CancelEdit);
+        assert!(page.editor.is_none());
+    }
+
+    #[test]
+    fn test_save_error_keeps_form() {
+        let mut page = PersonasPage::default();
+        let _ = page.update(PersonasMessage::New);
+        let _ = page.update(PersonasMessage::Save);
+        assert!(page.editor.is_some());
+        assert!(!page.busy);
+        assert_eq!(page.error.as_deref(), Some("Enter a name"));
+    }
+
+    #[test]
+    fn test_clear_all_asks_first() {
+        let mut page = PersonasPage::default();
+        let persona = named("Hecate");
+        page.personas = vec![persona.clone()];
+        let _ = page.update(PersonasMessage::Select(persona.id));
+        assert_eq!(page.selected().map(|p| p.name.as_str()), Some("Hecate"));
+
+        let _ = page.update(PersonasMessage::ClearAll);
+        assert!(page.confirm_clear);
+        let _ = page.update(PersonasMessage::CancelClear);
+        assert!(!page.confirm_clear);
+    }
+
+    #[test]
+    fn test_stale_memory_ignored() {
+        let mut page = PersonasPage::default();
+        let first = named("Hecate");
+        let second = named("Morrigan");
+        page.personas = vec![first.clone(), second.clone()];
+        let _ = page.update(PersonasMessage::Select(second.id));
+
+        let _ = page.update(PersonasMessage::MemoryLoaded(first.id, Ok(PersonaMemory::new(first.id))));
+        assert!(page.memory.is_none());
+
+        let _ = page.update(PersonasMessage::MemoryLoaded(second.id, Ok(PersonaMemory::new(second.id))));
+        assert_eq!(page.memory.as_ref().map(|m| m.persona_id), Some(second.id));
+    }
+
+    #[test]
+    fn test_voice_summary() {
+        let lilith = builtin::lilith();
+        assert_eq!(
+            voice_summary(&lilith),
+            "Analytical, moderate, detailed · curious, thorough, skeptical"
+        );
+    }
+}
@@ -0,0 +1,472 @@
+//! Users settings page
+//!
+//! The accounts the login screen offers, and the login screen's theme.
+//! Theme and background choices are resolved by spectre and previewed
+//! here with its colors before being applied, so the preview matches
+//! what the greeter will draw.
+
+use iced::widget::{button, column, container, image, pick_list, row, stack, text, text_input};
+use iced::{Alignment, Color, Command, ContentFit, Element, Length};
+use libnyx_ipc::session::{GreeterBackground, GreeterTheme, LoginUser};
+use libnyx_ipc::SessionClient;
+use nyx_theme::colors::NyxColors;
+use nyx_theme::spacing::Spacing;
+use nyx_theme::theme::parse_hex_color;
+use nyx_theme::widgets::button::{button_style, ButtonVariant};
+use nyx_theme::widgets::card::card_style;
+use nyx_theme::widgets::input::{input_style, InputVariant};
+use nyx_theme::widgets::CardVariant;
+use nyx_theme::Typography;
+
+/// Size of the login screen preview
+const PREVIEW_WIDTH: f32 = 400.0;
+const PREVIEW_HEIGHT: f32 = 240.0;
+
+/// Avatar size in the account list
+const AVATAR_SIZE: f32 = 40.0;
+
+/// What spectre reports when the page opens
+#[derive(Debug, Clone)]
+pub struct LoginScreen {
+    pub users: Vec<LoginUser>,
+    pub themes: Vec<String>,
+    pub theme: GreeterTheme,
+}
+
+/// Users page state
+#[derive(Debug, Clone, Default)]
+pub struct UsersPage {
+    /// Accounts offered at login
+    pub users: Vec<LoginUser>,
+    /// Themes the login screen can use
+    pub themes: Vec<String>,
+    /// Theme picked, applied or not
+    pub selected_theme: Option<String>,
+    /// Background field: `#RRGGBB`, an image path, or empty for the theme's
+    pub background: String,
+    /// Theme the login screen uses now
+    pub applied: Option<GreeterTheme>,
+    /// Theme as it would look with the current choices
+    pub preview: Option<GreeterTheme>,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+/// Users page messages
+#[derive(Debug, Clone)]
+pub enum UsersMessage {
+    /// Users and theme read from spectre
+    Loaded(Result<LoginScreen, String>),
+    /// Theme picked
+    SelectTheme(String),
+    /// Background field edited
+    BackgroundChanged(String),
+    /// Enter pressed in the background field
+    BackgroundSubmitted,
+    /// Spectre resolved the choices
+    Previewed(Result<GreeterTheme, String>),
+    /// Apply the previewed theme to the login screen
+    Apply,
+    /// Go back to the theme in use
+    Revert,
+    /// Spectre applied the theme
+    Applied(Result<GreeterTheme, String>),
+}
+
+impl UsersPage {
+    /// Read users and the login screen theme; done whenever the page is
+    /// opened
+    pub fn load(&mut self) -> Command<UsersMessage> {
+        if self.loading {
+            return Command::none();
+        }
+        self.loading = true;
+        Command::perform(fetch(), UsersMessage::Loaded)
+    }
+
+    /// Whether the preview differs from the login screen
+    pub fn has_changes(&self) -> bool {
+        self.preview.is_some() && self.preview != self.applied
+    }
+
+    /// Update state
+    pub fn update(&mut self, message: UsersMessage) -> Command<UsersMessage> {
+        match message {
+            UsersMessage::Loaded(result) => {
+                self.loading = false;
+                match result {
+                    Ok(screen) => {
+                        self.users = screen.users;
+                        self.themes = screen.themes;
+                        self.select_applied(screen.theme);
+                        self.error = None;
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            UsersMessage::SelectTheme(theme) => {
+                self.selected_theme = Some(theme);
+                return self.preview();
+            }
+            UsersMessage::BackgroundChanged(background) => {
+                self.background = background;
+            }
+            UsersMessage::BackgroundSubmitted => return self.preview(),
+            UsersMessage::Previewed(result) => match result {
+                Ok(theme) => {
+                    self.preview = Some(theme);
+                    self.error = None;
+                }
+                Err(e) => self.error = Some(e),
+            },
+            UsersMessage::Apply => {
+                let Some(theme) = self.selected_theme.clone() else {
+                    return Command::none();
+                };
+                let background = self.background_setting();
+                return Command::perform(
+                    async move {
+                        SessionClient::new()
+                            .set_greeter_theme(&theme, background.as_deref())
+                            .await
+                            .map_err(|e| e.to_string())
+                    },
+                    UsersMessage::Applied,
+                );
+            }
+            UsersMessage::Revert => {
+                if let Some(applied) = self.applied.clone() {
+                    self.select_applied(applied);
+                }
+                self.error = None;
+            }
+            UsersMessage::Applied(result) => match result {
+                Ok(theme) => {
+                    self.select_applied(theme);
+                    self.error = None;
+                }
+                Err(e) => self.error = Some(e),
+            },
+        }
+        Command::none()
+    }
+
+    /// Show the login screen's theme as both applied and selected
+    fn select_applied(&mut self, theme: GreeterTheme) {
+        self.selected_theme = Some(theme.name.clone());
+        self.background = match &theme.background {
+            // A color equal to the theme's own background is the default
+            GreeterBackground::Color(color) if *color == theme.colors.background => String::new(),
+            GreeterBackground::Color(color) => color.clone(),
+            GreeterBackground::Image(path) => path.clone(),
+        };
+        self.preview = Some(theme.clone());
+        self.applied = Some(theme);
+    }
+
+    /// Background as the setting takes it; empty means the theme's
+    fn background_setting(&self) -> Option<String> {
+        let background = self.background.trim();
+        (!background.is_empty()).then(|| background.to_string())
+    }
+
+    fn preview(&self) -> Command<UsersMessage> {
+        let Some(theme) = self.selected_theme.clone() else {
+            return Command::none();
+        };
+        let background = self.background_setting();
+        Command::perform(
+            async move {
+                SessionClient::new()
+                    .preview_greeter_theme(&theme, background.as_deref())
+                    .await
+                    .map_err(|e| e.to_string())
+            },
+            UsersMessage::Previewed,
+        )
+    }
+
+    /// View the page
+    pub fn view(&self) -> Element<UsersMessage> {
+        let mut page = column![
+            text("Users")
+                .size(Typography::SIZE_HEADLINE_LARGE)
+                .color(NyxColors::TEXT_BRIGHT),
+            text("Accounts and the login screen")
+                .size(Typography::SIZE_BODY_MEDIUM)
+                .color(NyxColors::TEXT_SECONDARY),
+        ]
+        .spacing(Spacing::MD)
+        .width(Length::Fill)
+        .padding(Spacing::LG);
+
+        if let Some(error) = &self.error {
+            page = page.push(
+                text(error)
+                    .size(Typography::SIZE_BODY_SMALL)
+                    .color(NyxColors::ERROR),
+            );
+        }
+
+        page.push(self.view_accounts()).push(self.view_login_screen()).into()
+    }
+
+    fn view_accounts(&self) -> Element<UsersMessage> {
+        let mut content = column![text("User Accounts")
+            .size(Typography::SIZE_TITLE_MEDIUM)
+            .color(NyxColors::TEXT_BRIGHT)]
+        .spacing(Spacing::MD);
+
+        for user in &self.users {
+            content = content.push(
+                row![
+                    avatar(user, AVATAR_SIZE, NyxColors::AURORA),
+                    column![
+                        text(&user.display_name)
+                            .size(Typography::SIZE_BODY_MEDIUM)
+                            .color(NyxColors::TEXT_BRIGHT),
+                        text(&user.username)
+                            .size(Typography::SIZE_BODY_SMALL)
+                            .color(NyxColors::TEXT_SECONDARY),
+                    ],
+                ]
+                .spacing(Spacing::MD)
+                .align_y(Alignment::Center),
+            );
+        }
+        if self.users.is_empty() {
+            content = content.push(
+                text(if self.loading {
+                    "Loading…"
+                } else {
+                    "The session manager is not running"
+                })
+                .size(Typography::SIZE_BODY_SMALL)
+                .color(NyxColors::TEXT_MUTED),
+            );
+        }
+
+        container(content)
+            .padding(Spacing::LG)
+            .style(card_style(CardVariant::Default))
+            .into()
+    }
+
+    fn view_login_screen(&self) -> Element<UsersMessage> {
+        let controls = column![
+            text("Theme")
+                .size(Typography::SIZE_BODY_MEDIUM)
+                .color(NyxColors::TEXT_BRIGHT),
+            pick_list(self.themes.clone(), self.selected_theme.clone(), UsersMessage::SelectTheme)
+                .width(Length::Fill),
+            text("Background")
+                .size(Typography::SIZE_BODY_MEDIUM)
+                .color(NyxColors::TEXT_BRIGHT),
+            text_input("#RRGGBB or image path", &self.background)
+                .on_input(UsersMessage::BackgroundChanged)
+                .on_submit(UsersMessage::BackgroundSubmitted)
+                .style(input_style(InputVariant::Default)),
+            row![
+                button(text("Apply"))
+                    .style(button_style(ButtonVariant::Primary))
+                    .on_press_maybe(self.has_changes().then_some(UsersMessage::Apply)),
+                button(text("Revert"))
+                    .style(button_style(ButtonVariant::Ghost))
+                    .on_press_maybe(self.has_changes().then_some(UsersMessage::Revert)),
+            ]
+            .spacing(Spacing::SM),
+        ]
+        .spacing(Spacing::SM)
+        .width(Length::Fill);
+
+        let preview: Element<UsersMessage> = match &self.preview {
+            Some(theme) => self.view_preview(theme),
+            None => container(text(""))
+                .width(Length::Fixed(PREVIEW_WIDTH))
+                .height(Length::Fixed(PREVIEW_HEIGHT))
+                .into(),
+        };
+
+        container(
+            column![
+                text("Login Screen")
+                    .size(Typography::SIZE_TITLE_MEDIUM)
+                    .color(NyxColors::TEXT_BRIGHT),
+                row![controls, preview].spacing(Spacing::LG),
+            ]
+            .spacing(Spacing::MD),
+        )
+        .padding(Spacing::LG)
+        .style(card_style(CardVariant::Default))
+        .into()
+    }
+
+    /// Miniature login screen drawn with the previewed theme
+    fn view_preview(&self, theme: &GreeterTheme) -> Element<UsersMessage> {
+        let colors = &theme.colors;
+        let color = |hex: &str| parse_hex_color(hex).unwrap_or(NyxColors::TEXT_BRIGHT);
+        let (surface, accent, text_color, muted) = (
+            color(&colors.surface),
+            color(&colors.accent),
+            color(&colors.text),
+            color(&colors.text_muted),
+        );
+
+        let user = self.users.first();
+        let login_box = container(
+            column![
+                match user {
+                    Some(user) => avatar(user, 56.0, accent),
+                    None => container(text("")).into(),
+                },
+                text(user.map_or("User", |u| u.display_name.as_str()))
+                    .size(Typography::SIZE_BODY_LARGE)
+                    .color(text_color),
+                container(
+                    text("Password")
+                        .size(Typography::SIZE_BODY_SMALL)
+                        .color(muted),
+                )
+                .padding(Spacing::SM)
+                .width(Length::Fixed(180.0))
+                .style(move |_theme| container::Style {
+                    background: Some(iced::Background::Color(surface)),
+                    border: iced::Border {
+                        color: accent,
+                        width: 1.0,
+                        radius: Spacing::RADIUS_SM.into(),
+                    },
+                    ..Default::default()
+                }),
+            ]
+            .spacing(Spacing::SM)
+            .align_x(Alignment::Center),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill);
+
+        let screen: Element<UsersMessage> = match &theme.background {
+            GreeterBackground::Image(path) => stack![
+                image(path.as_str())
+                    .content_fit(ContentFit::Cover)
+                    .width(Length::Fill)
+                    .height(Length::Fill),
+                login_box,
+            ]
+            .into(),
+            GreeterBackground::Color(hex) => {
+                let fill = color(hex);
+                login_box
+                    .style(move |_theme| container::Style {
+                        background: Some(iced::Background::Color(fill)),
+                        ..Default::default()
+                    })
+                    .into()
+            }
+        };
+
+        container(screen)
+            .width(Length::Fixed(PREVIEW_WIDTH))
+            .height(Length::Fixed(PREVIEW_HEIGHT))
+            .clip(true)
+            .into()
+    }
+}
+
+/// A user's picture, or their initial on a colored circle
+fn avatar(user: &LoginUser, size: f32, color: Color) -> Element<'static, UsersMessage> {
+    if let Some(path) = &user.avatar {
+        return image(path.as_str())
+            .width(Length::Fixed(size))
+            .height(Length::Fixed(size))
+            .content_fit(ContentFit::Cover)
+            .into();
+    }
+
+    let initial = user.display_name.chars().next().unwrap_or('?').to_uppercase().to_string();
+    container(text(initial).size(size / 2.0).color(NyxColors::TEXT_BRIGHT))
+        .width(Length::Fixed(size))
+        .height(Length::Fixed(size))
+        .center_x(Length::Fixed(size))
+        .center_y(Length::Fixed(size))
+        .style(move |_theme| container::Style {
+            background: Some(iced::Background::Color(color)),
+            border: iced::Border {
+                radius: Spacing::RADIUS_CIRCLE.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .into()
+}
+
+async fn fetch() -> Result<LoginScreen, String> {
+    let client = SessionClient::new();
+    Ok(LoginScreen {
+        users: client.login_users().await.map_err(|e| e.to_string())?,
+        themes: client.greeter_themes().await.map_err(|e| e.to_string())?,
+        theme: client.greeter_theme().await.map_err(|e| e.to_string())?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libnyx_ipc::session::GreeterColors;
+
+    fn theme(name: &str, background: GreeterBackground) -> GreeterTheme {
+        GreeterTheme {
+            name: name.into(),
+            background,
+            colors: GreeterColors {
+                background: "#0B0B14".into(),
+                surface: "#15152A".into(),
+                text: "#F0F0FF".into(),
+                text_muted: "#A0A0C0".into(),
+                accent: "#8B5CF6".into(),
+                error: "#EF4444".into(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_loaded_selects_applied_theme() {
+        let mut page = UsersPage::default();
+        let _ = page.update(UsersMessage::Loaded(Ok(LoginScreen {
+            users: Vec::new(),
+            themes: vec!["default".into(), "light".into()],
+            theme: theme("default", GreeterBackground::Color("#0B0B14".into())),
+        })));
+
+        assert_eq!(page.selected_theme.as_deref(), Some("default"));
+        // The theme's own background shows as the default, not a color
+        assert_eq!(page.background, "");
+        assert!(!page.has_changes());
+    }
+
+    #[test]
+    fn test_preview_then_revert() {
+        let applied = theme("default", GreeterBackground::Image("/grimoire/wallpapers/night.png".into()));
+        let mut page = UsersPage::default();
+        let _ = page.update(UsersMessage::Applied(Ok(applied.clone())));
+        assert_eq!(page.background, "/grimoire/wallpapers/night.png");
+
+        let _ = page.update(UsersMessage::Previewed(Ok(theme("light", GreeterBackground::Color("#FFFFFF".into())))));
+        assert!(page.has_changes());
+
+        let _ = page.update(UsersMessage::Revert);
+        assert_eq!(page.preview, Some(applied));
+        assert!(!page.has_changes());
+    }
+
+    #[test]
+    fn test_background_setting() {
+        let mut page = UsersPage::default();
+        let _ = page.update(UsersMessage::BackgroundChanged("  ".into()));
+        assert_eq!(page.background_setting(), None);
+        let _ = page.update(UsersMessage::BackgroundChanged("#112233 ".into()));
+        assert_eq!(page.background_setting().as_deref(), Some("#112233"));
+    }
+}
@@ -0,0 +1,284 @@
+//! Settings search and deep links
+//!
+//! Every control the pages show is listed here with the words people use
+//! for it, so the sidebar search can find it and other apps can open it
+//! directly with a `nyx-settings://<page>/<control>` link, e.g.
+//! `nyx-settings://display/night-light`.
+
+use crate::pages::SettingsPage;
+
+/// URI scheme nyx-settings is launched with
+pub const SCHEME: &str = "nyx-settings://";
+
+/// Most results the sidebar lists
+pub const MAX_RESULTS: usize = 8;
+
+/// A control search and links can reach
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SettingEntry {
+    /// Page showing the control
+    pub page: SettingsPage,
+    /// Link segment, unique within the page
+    pub id: &'static str,
+    /// Label as the page shows it
+    pub title: &'static str,
+    /// Other words that should find it
+    pub keywords: &'static [&'static str],
+}
+
+impl SettingEntry {
+    /// Link that opens this control
+    pub fn uri(&self) -> String {
+        format!("{}{}/{}", SCHEME, self.page.slug(), self.id)
+    }
+
+    /// Roughly where the control sits down its page, from 0.0 at the top
+    /// to 1.0 at the bottom, for scrolling it into view
+    pub fn anchor(&self) -> f32 {
+        let on_page: Vec<_> = ENTRIES.iter().filter(|e| e.page == self.page).collect();
+        let position = on_page.iter().position(|e| e.id == self.id).unwrap_or(0);
+        if on_page.len() < 2 {
+            0.0
+        } else {
+            position as f32 / (on_page.len() - 1) as f32
+        }
+    }
+
+    fn score(&self, word: &str) -> Option<u32> {
+        let starts_word = |text: &str| {
+            text.to_lowercase()
+                .split(|c: char| !c.is_alphanumeric())
+                .any(|w| w.starts_with(word))
+        };
+        if starts_word(self.title) {
+            Some(3)
+        } else if self.id.starts_with(word) || self.keywords.iter().any(|k| starts_word(k)) {
+            Some(2)
+        } else if starts_word(self.page.title()) {
+            Some(1)
+        } else {
+            None
+        }
+    }
+}
+
+macro_rules! entry {
+    ($page:ident, $id:literal, $title:literal, [$($keyword:literal),* $(,)?]) => {
+        SettingEntry {
+            page: SettingsPage::$page,
+            id: $id,
+            title: $title,
+            keywords: &[$($keyword),*],
+        }
+    };
+}
+
+/// Every searchable control, in page order and top to bottom within a page
+pub static ENTRIES: &[SettingEntry] = &[
+    entry!(Network, "wifi", "WiFi", ["wi-fi", "wireless", "wlan", "ssid", "hotspot"]),
+    entry!(Network, "ethernet", "Ethernet", ["wired", "cable", "lan"]),
+    entry!(Network, "vpn", "VPN", ["wireguard", "tunnel", "private network"]),
+    entry!(Network, "connections", "Connections", ["profile", "metered", "static ip", "address", "mtu"]),
+    entry!(Network, "dns", "DNS Servers", ["nameserver", "resolver", "domain"]),
+    entry!(Bluetooth, "devices", "Bluetooth Devices", ["pair", "headphones", "keyboard", "mouse"]),
+    entry!(Display, "resolution", "Resolution", ["screen", "monitor", "display mode"]),
+    entry!(Display, "refresh-rate", "Refresh Rate", ["hz", "fps", "frame rate"]),
+    entry!(Display, "scale", "Scale", ["scaling", "zoom", "hidpi", "text size"]),
+    entry!(Display, "night-light", "Night Light", ["blue light", "warm", "color temperature", "intensity"]),
+    entry!(Display, "night-light-schedule", "Automatic Schedule", ["sunset", "sunrise", "night light"]),
+    entry!(Sound, "output", "Output Device", ["speakers", "headphones", "playback"]),
+    entry!(Sound, "volume", "Volume", ["loudness", "mute"]),
+    entry!(Sound, "input", "Input Device", ["microphone", "mic", "recording"]),
+    entry!(Sound, "input-level", "Input Level", ["microphone volume", "gain"]),
+    entry!(Appearance, "theme", "Theme", ["dark", "light", "mode"]),
+    entry!(Appearance, "accent-color", "Accent Color", ["colour", "highlight"]),
+    entry!(Appearance, "animations", "Animations", ["motion", "effects"]),
+    entry!(Appearance, "blur", "Blur Effects", ["glass", "transparency", "glassmorphism"]),
+    entry!(DefaultApps, "browser", "Web Browser", ["internet", "links", "http"]),
+    entry!(DefaultApps, "mail", "Mail", ["email", "mailto"]),
+    entry!(DefaultApps, "files", "File Manager", ["folders", "directory"]),
+    entry!(DefaultApps, "text", "Text Editor", ["notes", "plain text"]),
+    entry!(DefaultApps, "images", "Image Viewer", ["photos", "pictures"]),
+    entry!(DefaultApps, "music", "Music", ["audio player", "songs"]),
+    entry!(DefaultApps, "video", "Video", ["movies", "media player"]),
+    entry!(DefaultApps, "file-types", "File Types", ["mime", "extension", "open with"]),
+    entry!(Personas, "personas", "Personas", ["assistant", "ai", "agent", "lilith", "mammon", "leviathan"]),
+    entry!(Personas, "prompt", "System Prompt", ["instructions", "persona"]),
+    entry!(Personas, "model", "Model", ["llm", "temperature", "local", "remote", "provider"]),
+    entry!(Personas, "voice", "Voice", ["tone", "formality", "verbosity", "personality"]),
+    entry!(Personas, "rituals", "Rituals", ["workflows", "automation"]),
+    entry!(Personas, "memory", "Memory", ["forget", "history", "clear", "remember"]),
+    entry!(Notifications, "do-not-disturb", "Do Not Disturb", ["dnd", "silence", "quiet", "focus"]),
+    entry!(Notifications, "previews", "Show Previews", ["banners", "content"]),
+    entry!(Notifications, "lock-screen", "Show on Lock Screen", ["locked", "privacy"]),
+    entry!(Notifications, "sounds", "Sounds", ["alert", "chime"]),
+    entry!(Notifications, "badges", "Badges", ["unread", "count"]),
+    entry!(Power, "battery", "Battery", ["charging", "charge", "percentage"]),
+    entry!(Power, "power-mode", "Power Mode", ["performance", "balanced", "power saver", "profile"]),
+    entry!(Users, "accounts", "User Accounts", ["password", "login"]),
+    entry!(Users, "login-screen", "Login Screen", ["greeter", "avatar", "background", "wallpaper", "theme"]),
+    entry!(About, "system", "System", ["version", "os", "build"]),
+    entry!(About, "hardware", "Hardware", ["cpu", "processor", "memory", "ram", "specs"]),
+];
+
+/// A control by page and id
+pub fn entry(page: SettingsPage, id: &str) -> Option<&'static SettingEntry> {
+    ENTRIES.iter().find(|e| e.page == page && e.id == id)
+}
+
+/// Controls matching every word of a query, best first
+pub fn search(query: &str) -> Vec<&'static SettingEntry> {
+    let query = query.to_lowercase();
+    let words: Vec<&str> = query.split_whitespace().collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let mut results: Vec<(u32, &'static SettingEntry)> = ENTRIES
+        .iter()
+        .filter_map(|entry| {
+            let score = words
+                .iter()
+                .map(|word| entry.score(word))
+                .sum::<Option<u32>>()?;
+            Some((score, entry))
+        })
+        .collect();
+    // Stable, so equal scores keep page order
+    results.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    results.into_iter().take(MAX_RESULTS).map(|(_, e)| e).collect()
+}
+
+/// Where a `nyx-settings://` link points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeepLink {
+    /// Page to show
+    pub page: SettingsPage,
+    /// Control to bring into view, if the link names a known one
+    pub entry: Option<&'static SettingEntry>,
+}
+
+impl DeepLink {
+    /// Parse `nyx-settings://<page>[/<control>]`; an empty path is the
+    /// default page and an unknown control still opens its page
+    pub fn parse(uri: &str) -> Option<Self> {
+        let path = uri.strip_prefix(SCHEME)?.trim_matches('/');
+        let (page, control) = match path.split_once('/') {
+            Some((page, control)) => (page, Some(control)),
+            None => (path, None),
+        };
+        let page = if page.is_empty() {
+            SettingsPage::default()
+        } else {
+            SettingsPage::from_slug(page)?
+        };
+        Some(Self {
+            page,
+            entry: control.and_then(|id| entry(page, id)),
+        })
+    }
+
+    /// Link back to the same place
+    pub fn uri(&self) -> String {
+        match self.entry {
+            Some(entry) => entry.uri(),
+            None => format!("{}{}", SCHEME, self.page.slug()),
+        }
+    }
+}
+
+impl From<&'static SettingEntry> for DeepLink {
+    fn from(entry: &'static SettingEntry) -> Self {
+        Self {
+            page: entry.page,
+            entry: Some(entry),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_unique_per_page() {
+        for (i, a) in ENTRIES.iter().enumerate() {
+            for b in &ENTRIES[i + 1..] {
+                assert!(a.page != b.page || a.id != b.id, "duplicate {}", a.uri());
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_page_searchable() {
+        for page in SettingsPage::all() {
+            assert!(ENTRIES.iter().any(|e| e.page == *page), "{:?}", page);
+        }
+    }
+
+    #[test]
+    fn test_search_ranks_titles_first() {
+        let results = search("night");
+        assert_eq!(results[0].id, "night-light");
+        assert_eq!(results[1].id, "night-light-schedule");
+
+        let results = search("microphone");
+        assert_eq!(results[0].id, "input");
+    }
+
+    #[test]
+    fn test_search_requires_every_word() {
+        assert_eq!(search("wifi"), search("WiFi"));
+        assert!(search("night wired").is_empty());
+        assert!(search("   ").is_empty());
+
+        let results = search("sound vol");
+        assert_eq!(results[0].id, "volume");
+        assert!(results.iter().all(|e| e.page == SettingsPage::Sound));
+    }
+
+    #[test]
+    fn test_search_limit() {
+        assert!(search("s").len() <= MAX_RESULTS);
+    }
+
+    #[test]
+    fn test_parse_links() {
+        let link = DeepLink::parse("nyx-settings://display/night-light").unwrap();
+        assert_eq!(link.page, SettingsPage::Display);
+        assert_eq!(link.entry.map(|e| e.title), Some("Night Light"));
+
+        let link = DeepLink::parse("nyx-settings://network/wifi/").unwrap();
+        assert_eq!(link.entry.map(|e| e.id), Some("wifi"));
+
+        let link = DeepLink::parse("nyx-settings://sound").unwrap();
+        assert_eq!(link.page, SettingsPage::Sound);
+        assert!(link.entry.is_none());
+
+        let link = DeepLink::parse("nyx-settings://").unwrap();
+        assert_eq!(link.page, SettingsPage::Network);
+
+        let link = DeepLink::parse("nyx-settings://power/unknown").unwrap();
+        assert_eq!(link.page, SettingsPage::Power);
+        assert!(link.entry.is_none());
+
+        assert!(DeepLink::parse("nyx-settings://nowhere").is_none());
+        assert!(DeepLink::parse("https://display/night-light").is_none());
+    }
+
+    #[test]
+    fn test_links_roundtrip() {
+        for entry in ENTRIES {
+            let link = DeepLink::parse(&entry.uri()).unwrap();
+            assert_eq!(link.entry, Some(entry));
+            assert_eq!(link.uri(), entry.uri());
+        }
+    }
+
+    #[test]
+    fn test_anchor() {
+        assert_eq!(entry(SettingsPage::Network, "wifi").unwrap().anchor(), 0.0);
+        assert_eq!(entry(SettingsPage::Network, "dns").unwrap().anchor(), 1.0);
+        assert_eq!(entry(SettingsPage::Users, "accounts").unwrap().anchor(), 0.0);
+    }
+}
@@ -2,14 +2,43 @@
 
 use crate::config::ShellConfig;
 use crate::dock::Dock;
-use crate::messages::{DockMessage, Message, PanelMessage, WorkspaceMessage};
+use crate::lock::{self, LockGuard};
+use crate::messages::{
+    DockMessage, LockMessage, Message, Notification, NotificationMessage, OverviewMessage, PanelMessage,
+    SwitcherMessage, WallpaperMessage, WorkspaceMessage,
+};
+use crate::notifications::{self, HeraldUpdate, Notifications};
+use crate::osd::{self, Osd};
+use crate::overview::{
+    self, DropAction, HotCorner, Overview, OverviewEvent, OverviewWindow, SwipeAction, SwipeTracker,
+};
 use crate::panel::Panel;
 use crate::system::SystemStatus;
+use crate::wallpaper::{self, Wallpaper, Wallpapers};
+use crate::windows::{self, Switcher, WindowControl, WindowEvent, WindowList, WindowRequest};
 use crate::workspace::WorkspaceManager;
 use iced::widget::{column, container, horizontal_space, row, vertical_space};
-use iced::{executor, Application, Command, Element, Length, Subscription, Theme};
+use iced::{executor, keyboard, mouse, Application, Command, Element, Event, Length, Subscription, Theme};
 use nyx_theme::colors::NyxColors;
-use std::time::Duration;
+use nyx_theme::NyxTheme;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Icon size in the Alt+Tab switcher
+const SWITCHER_ICON_SIZE: f32 = 40.0;
+
+/// Width of one window in the Alt+Tab switcher
+const SWITCHER_ENTRY_WIDTH: f32 = 128.0;
+
+/// Size of one workspace in the activities overview
+const OVERVIEW_WORKSPACE_WIDTH: f32 = 360.0;
+const OVERVIEW_WORKSPACE_HEIGHT: f32 = 240.0;
+
+/// Width of notification popups and the notification center
+const NOTIFICATION_WIDTH: f32 = 360.0;
+
+/// Width of the volume on-screen display
+const OSD_WIDTH: f32 = 280.0;
 
 /// Main shell application
 pub struct NyxShell {
@@ -23,12 +52,40 @@ pub struct NyxShell {
     workspaces: WorkspaceManager,
     /// System status
     system: SystemStatus,
+    /// Running windows, most recently focused first
+    windows: WindowList,
+    /// Sends window requests once the compositor accepted the shell
+    window_control: Option<WindowControl>,
+    /// Alt+Tab switcher, while open
+    switcher: Option<Switcher>,
+    /// Activities overview windows and drag state
+    overview: Overview,
+    /// Top-left corner that opens the overview
+    hot_corner: HotCorner,
+    /// Touchpad swipes in progress
+    swipes: SwipeTracker,
+    /// Notification popups and history from herald
+    notifications: Notifications,
+    /// Lock screen process while spectre reports the session locked
+    lock: LockGuard,
+    /// Volume level shown after a volume key press
+    osd: Osd,
+    /// Wallpaper rules and slideshows
+    wallpapers: Wallpapers,
+    /// Output the shell covers, for per-output wallpapers
+    output: Option<String>,
+    /// Theme, with the accent taken from the wallpaper
+    theme: NyxTheme,
+    /// Wallpaper the theme's accent was last taken from
+    palette_source: Option<PathBuf>,
     /// Control center visible
     control_center_visible: bool,
     /// Assistant visible
     assistant_visible: bool,
     /// Activities overview visible
     activities_visible: bool,
+    /// Notification center visible
+    notification_center_visible: bool,
 }
 
 impl Application for NyxShell {
@@ -41,17 +98,38 @@ impl Application for NyxShell {
         let config = ShellConfig::load();
 
         let shell = Self {
+            swipes: SwipeTracker::new(config.overview.swipe_fingers),
+            notifications: Notifications::new(
+                Duration::from_secs(config.notifications.popup_timeout_secs),
+                config.notifications.max_popups,
+            ),
+            lock: LockGuard::new(lock::current_session()),
+            osd: Osd::default(),
+            wallpapers: Wallpapers::new(config.wallpaper.clone(), Instant::now()),
+            output: None,
+            theme: NyxTheme::dark(),
+            palette_source: None,
             panel: Panel::new(config.panel.clone()),
             dock: Dock::new(config.dock.clone()),
             config,
             workspaces: WorkspaceManager::new(),
             system: SystemStatus::new(),
+            windows: WindowList::new(),
+            window_control: None,
+            switcher: None,
+            overview: Overview::new(),
+            hot_corner: HotCorner::default(),
             control_center_visible: false,
             assistant_visible: false,
             activities_visible: false,
+            notification_center_visible: false,
         };
 
-        (shell, Command::none())
+        let output = Command::perform(wallpaper::shell_output(), |result| {
+            Message::Wallpaper(WallpaperMessage::Output(result.map_err(|e| e.to_string())))
+        });
+
+        (shell, output)
     }
 
     fn title(&self) -> String {
@@ -59,13 +137,18 @@ impl Application for NyxShell {
     }
 
     fn theme(&self) -> Theme {
-        nyx_theme::dark_theme()
+        self.theme.to_iced_theme()
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::Tick => {
                 self.system.refresh();
+                self.notifications.expire(Instant::now());
+                self.lock.check();
+                self.osd.expire(Instant::now());
+                self.wallpapers.advance(Instant::now());
+                return self.sync_palette();
             }
 
             Message::Panel(panel_msg) => {
@@ -84,22 +167,50 @@ impl Application for NyxShell {
                 // Handle system events
             }
 
+            Message::Windows(event) => {
+                self.handle_window_event(event);
+            }
+
+            Message::Switcher(switcher_msg) => {
+                self.handle_switcher_message(switcher_msg);
+            }
+
+            Message::Overview(overview_msg) => {
+                return self.handle_overview_message(overview_msg);
+            }
+
+            Message::Notifications(notification_msg) => {
+                return self.handle_notification_message(notification_msg);
+            }
+
+            Message::Lock(lock_msg) => {
+                return self.handle_lock_message(lock_msg);
+            }
+
+            Message::Osd(event) => {
+                self.osd.show(event, &mut self.system.audio, Instant::now());
+            }
+
+            Message::Wallpaper(wallpaper_msg) => {
+                return self.handle_wallpaper_message(wallpaper_msg);
+            }
+
             Message::ToggleControlCenter => {
                 self.control_center_visible = !self.control_center_visible;
                 self.assistant_visible = false;
                 self.activities_visible = false;
+                self.notification_center_visible = false;
             }
 
             Message::ToggleAssistant => {
                 self.assistant_visible = !self.assistant_visible;
                 self.control_center_visible = false;
                 self.activities_visible = false;
+                self.notification_center_visible = false;
             }
 
             Message::ShowActivities => {
-                self.activities_visible = true;
-                self.control_center_visible = false;
-                self.assistant_visible = false;
+                self.show_activities();
             }
 
             Message::HideActivities => {
@@ -114,7 +225,85 @@ impl Application for NyxShell {
 
     fn subscription(&self) -> Subscription<Message> {
         // Tick every second for clock updates
-        iced::time::every(Duration::from_secs(1)).map(|_| Message::Tick)
+        let tick = iced::time::every(Duration::from_secs(1)).map(|_| Message::Tick);
+
+        let windows = windows::subscription().map(Message::Windows);
+
+        // Alt+Tab cycles while Alt is held and switches on release
+        let switcher = iced::event::listen_with(|event, _status, _id| match event {
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: keyboard::Key::Named(keyboard::key::Named::Tab),
+                modifiers,
+                ..
+            }) if modifiers.alt() => Some(Message::Switcher(if modifiers.shift() {
+                SwitcherMessage::Previous
+            } else {
+                SwitcherMessage::Next
+            })),
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: keyboard::Key::Named(keyboard::key::Named::Escape),
+                ..
+            }) => Some(Message::Switcher(SwitcherMessage::Cancel)),
+            Event::Keyboard(keyboard::Event::KeyReleased {
+                key: keyboard::Key::Named(keyboard::key::Named::Alt),
+                ..
+            }) => Some(Message::Switcher(SwitcherMessage::Commit)),
+            _ => None,
+        });
+
+        // Super+L asks spectre to lock; the lock screen follows its event
+        let lock_key = iced::event::listen_with(|event, _status, _id| match event {
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: keyboard::Key::Character(c),
+                modifiers,
+                ..
+            }) if modifiers.logo() && c.as_str() == "l" => Some(Message::Lock(LockMessage::Request)),
+            _ => None,
+        });
+
+        // Pointer moves feed the hot corner; releases end overview drags
+        let pointer = iced::event::listen_with(|event, _status, _id| match event {
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                Some(Message::Overview(OverviewMessage::PointerMoved(position)))
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                Some(Message::Overview(OverviewMessage::Release))
+            }
+            _ => None,
+        });
+
+        // Thumbnails are only captured while the overview is open
+        let thumbnails = if self.activities_visible {
+            overview::subscription().map(|event| Message::Overview(OverviewMessage::Compositor(event)))
+        } else {
+            Subscription::none()
+        };
+
+        let gestures = if self.config.overview.swipe_gestures {
+            overview::gesture_subscription().map(|event| Message::Overview(OverviewMessage::Gesture(event)))
+        } else {
+            Subscription::none()
+        };
+
+        let notifications = notifications::subscription()
+            .map(|update| Message::Notifications(NotificationMessage::Herald(update)));
+
+        let sessions = lock::subscription().map(|event| Message::Lock(LockMessage::Session(event)));
+
+        let volume_keys = osd::subscription().map(Message::Osd);
+
+        Subscription::batch([
+            tick,
+            windows,
+            switcher,
+            lock_key,
+            pointer,
+            thumbnails,
+            gestures,
+            notifications,
+            sessions,
+            volume_keys,
+        ])
     }
 
     fn view(&self) -> Element<Message> {
@@ -124,17 +313,22 @@ impl Application for NyxShell {
             &self.system.battery,
             &self.system.network,
             &self.system.audio,
+            self.notifications.dnd(),
         );
 
-        let dock = self.dock.view();
+        let dock = self.dock.view(&self.windows);
 
         // Desktop area (between panel and dock)
         let desktop = container(
             column![
                 vertical_space(),
                 // Show overlays if active
-                if self.activities_visible {
+                if let Some(switcher) = self.switcher {
+                    self.view_switcher(switcher)
+                } else if self.activities_visible {
                     self.view_activities_overlay()
+                } else if self.notification_center_visible {
+                    self.view_notification_center()
                 } else if self.control_center_visible {
                     self.view_control_center_overlay()
                 } else if self.assistant_visible {
@@ -150,6 +344,11 @@ impl Application for NyxShell {
         .width(Length::Fill)
         .height(Length::Fill);
 
+        // Popups float over whatever the desktop shows
+        let desktop = iced::widget::stack![desktop, self.view_notification_popups(), self.view_osd()]
+            .width(Length::Fill)
+            .height(Length::Fill);
+
         // Dock container at bottom
         let dock_container = container(
             row![horizontal_space(), dock, horizontal_space()]
@@ -164,7 +363,17 @@ impl Application for NyxShell {
             .width(Length::Fill)
             .height(Length::Fill);
 
-        container(shell)
+        // Wallpaper behind everything
+        let background: Element<Message> = match self.current_wallpaper() {
+            Some(wallpaper) => iced::widget::image(iced::widget::image::Handle::from_path(wallpaper.path))
+                .content_fit(wallpaper.fill.content_fit())
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into(),
+            None => horizontal_space().into(),
+        };
+
+        container(iced::widget::stack![background, shell])
             .width(Length::Fill)
             .height(Length::Fill)
             .style(|_theme| iced::widget::container::Style {
@@ -179,9 +388,7 @@ impl NyxShell {
     fn handle_panel_message(&mut self, msg: PanelMessage) {
         match msg {
             PanelMessage::ActivitiesClicked => {
-                self.activities_visible = !self.activities_visible;
-                self.control_center_visible = false;
-                self.assistant_visible = false;
+                self.toggle_activities();
             }
 
             PanelMessage::WorkspaceClicked(id) => {
@@ -209,13 +416,15 @@ impl NyxShell {
     fn handle_dock_message(&mut self, msg: DockMessage) {
         match msg {
             DockMessage::AppClicked(id) => {
-                tracing::info!("App clicked: {}", id);
-                // Launch or focus app
+                self.dock.close_menu();
+                match self.windows.click_app(&id) {
+                    Some(request) => self.request_window(request),
+                    None => launch_app(&id),
+                }
             }
 
             DockMessage::AppRightClicked(id) => {
-                tracing::info!("App right-clicked: {}", id);
-                // Show context menu
+                self.dock.toggle_menu(id);
             }
 
             DockMessage::AppHovered(id) => {
@@ -227,16 +436,337 @@ impl NyxShell {
             }
 
             DockMessage::LaunchApp(id) => {
-                tracing::info!("Launching app: {}", id);
+                self.dock.close_menu();
+                launch_app(&id);
             }
 
             DockMessage::FocusApp(id) => {
-                tracing::info!("Focusing app: {}", id);
+                if let Some(window) = self.windows.for_app(&id).next() {
+                    self.request_window(WindowRequest::Activate(window.id));
+                }
             }
 
             DockMessage::CloseApp(id) => {
-                tracing::info!("Closing app: {}", id);
+                self.dock.close_menu();
+                for window in self.windows.for_app(&id) {
+                    self.request_window(WindowRequest::Close(window.id));
+                }
+            }
+
+            DockMessage::CloseMenu => {
+                self.dock.close_menu();
+            }
+
+            DockMessage::FocusWindow(id) => {
+                self.dock.close_menu();
+                self.request_window(WindowRequest::Activate(id));
+            }
+
+            DockMessage::ToggleMinimized(id) => {
+                if let Some(window) = self.windows.get(id) {
+                    self.request_window(if window.minimized {
+                        WindowRequest::Restore(id)
+                    } else {
+                        WindowRequest::Minimize(id)
+                    });
+                }
+            }
+
+            DockMessage::CloseWindow(id) => {
+                self.request_window(WindowRequest::Close(id));
+            }
+        }
+    }
+
+    fn handle_window_event(&mut self, event: WindowEvent) {
+        match event {
+            WindowEvent::Connected(control) => {
+                self.window_control = Some(control);
+            }
+
+            WindowEvent::Changed(toplevel) => {
+                self.windows.update(toplevel);
+            }
+
+            WindowEvent::Closed(id) => {
+                self.windows.remove(id);
+            }
+
+            WindowEvent::Unavailable(reason) => {
+                tracing::warn!("Window list unavailable: {}", reason);
+                self.window_control = None;
+                self.windows.clear();
+                self.switcher = None;
+            }
+        }
+
+        self.dock.sync_windows(&self.windows);
+    }
+
+    fn handle_switcher_message(&mut self, msg: SwitcherMessage) {
+        match msg {
+            SwitcherMessage::Next => match &mut self.switcher {
+                Some(switcher) => switcher.next(&self.windows),
+                None => self.switcher = Switcher::open(&self.windows),
+            },
+
+            SwitcherMessage::Previous => match &mut self.switcher {
+                Some(switcher) => switcher.previous(&self.windows),
+                None => self.switcher = Switcher::open_reversed(&self.windows),
+            },
+
+            SwitcherMessage::Select(id) => {
+                self.switcher = None;
+                self.request_window(WindowRequest::Activate(id));
+            }
+
+            SwitcherMessage::Commit => {
+                if let Some(id) = self
+                    .switcher
+                    .take()
+                    .and_then(|switcher| switcher.target(&self.windows))
+                {
+                    self.request_window(WindowRequest::Activate(id));
+                }
+            }
+
+            SwitcherMessage::Cancel => {
+                self.switcher = None;
+            }
+        }
+    }
+
+    fn handle_overview_message(&mut self, msg: OverviewMessage) -> Command<Message> {
+        match msg {
+            OverviewMessage::Compositor(event) => self.handle_overview_event(event),
+
+            OverviewMessage::Press(id) => {
+                self.overview.press(id);
+            }
+
+            OverviewMessage::Hover(workspace) => {
+                self.overview.hover(Some(workspace));
+            }
+
+            OverviewMessage::Unhover => {
+                self.overview.hover(None);
+            }
+
+            OverviewMessage::Release => match self.overview.release() {
+                Some(DropAction::Activate(id, workspace)) => {
+                    self.workspaces.set_active(workspace);
+                    self.activities_visible = false;
+                    return Command::perform(overview::focus_window(id), overview_done);
+                }
+                Some(DropAction::Move(id, workspace)) => {
+                    self.overview.move_window(id, workspace);
+                    self.sync_window_counts();
+                    return Command::perform(overview::move_window(id, workspace), overview_done);
+                }
+                None => {}
+            },
+
+            OverviewMessage::PointerMoved(position) => {
+                if self.hot_corner.moved(position) && self.config.overview.hot_corner {
+                    self.toggle_activities();
+                }
+            }
+
+            OverviewMessage::Gesture(event) => match self.swipes.handle(event) {
+                Some(SwipeAction::ShowOverview) => self.show_activities(),
+                Some(SwipeAction::HideOverview) => self.activities_visible = false,
+                Some(SwipeAction::NextWorkspace) => self.workspaces.next(),
+                Some(SwipeAction::PreviousWorkspace) => self.workspaces.previous(),
+                None => {}
+            },
+
+            OverviewMessage::Done(result) => {
+                if let Err(e) = result {
+                    tracing::warn!("Compositor refused overview request: {}", e);
+                }
+            }
+        }
+
+        Command::none()
+    }
+
+    fn handle_overview_event(&mut self, event: OverviewEvent) {
+        match event {
+            OverviewEvent::Windows(windows) => {
+                self.overview.set_windows(windows);
+                self.sync_window_counts();
+            }
+
+            OverviewEvent::Thumbnail(id, frame) => {
+                self.overview.set_thumbnail(id, frame);
+            }
+
+            OverviewEvent::Unavailable(reason) => {
+                tracing::debug!("Compositor control socket unavailable: {}", reason);
+                self.overview.clear();
+            }
+        }
+    }
+
+    /// Show window counts from the overview on the workspace indicators
+    fn sync_window_counts(&mut self) {
+        let ids: Vec<_> = self.workspaces.workspaces().iter().map(|w| w.id).collect();
+        for id in ids {
+            let count = self.overview.on_workspace(id).count();
+            self.workspaces.set_window_count(id, count);
+        }
+    }
+
+    fn show_activities(&mut self) {
+        self.activities_visible = true;
+        self.control_center_visible = false;
+        self.assistant_visible = false;
+        self.notification_center_visible = false;
+    }
+
+    fn toggle_activities(&mut self) {
+        if self.activities_visible {
+            self.activities_visible = false;
+        } else {
+            self.show_activities();
+        }
+    }
+
+    fn handle_notification_message(&mut self, msg: NotificationMessage) -> Command<Message> {
+        match msg {
+            NotificationMessage::Herald(update) => {
+                if let HeraldUpdate::Unavailable(reason) = &update {
+                    tracing::debug!("Herald unavailable: {}", reason);
+                }
+                self.notifications.update(update, Instant::now());
+            }
+
+            NotificationMessage::Invoke(id, action_id) => {
+                self.notifications.remove_popup(id);
+                return Command::perform(notifications::invoke_action(id, action_id), notifications_done);
+            }
+
+            NotificationMessage::Dismiss(id) => {
+                self.notifications.remove_popup(id);
+                return Command::perform(notifications::dismiss(id), notifications_done);
+            }
+
+            NotificationMessage::ClearApp(app_name) => {
+                self.notifications.clear_app(&app_name);
+                return Command::perform(notifications::clear_app(app_name), notifications_done);
+            }
+
+            NotificationMessage::SetDnd(enabled) => {
+                // The switch follows herald's event rather than the click
+                return Command::perform(notifications::set_dnd(enabled), notifications_done);
+            }
+
+            NotificationMessage::ToggleCenter => {
+                self.notification_center_visible = !self.notification_center_visible;
+                self.control_center_visible = false;
+                self.assistant_visible = false;
+                self.activities_visible = false;
+            }
+
+            NotificationMessage::Done(result) => {
+                if let Err(e) = result {
+                    tracing::warn!("Herald refused notification request: {}", e);
+                }
+            }
+        }
+
+        Command::none()
+    }
+
+    fn handle_lock_message(&mut self, msg: LockMessage) -> Command<Message> {
+        match msg {
+            LockMessage::Session(event) => {
+                self.lock.handle(event);
+                if self.lock.locked() {
+                    self.switcher = None;
+                    self.activities_visible = false;
+                    self.control_center_visible = false;
+                    self.notification_center_visible = false;
+                }
+            }
+
+            LockMessage::Request => {
+                return Command::perform(lock::lock_session(lock::current_session()), |result| {
+                    Message::Lock(LockMessage::Done(result.map_err(|e| e.to_string())))
+                });
+            }
+
+            LockMessage::Done(result) => {
+                if let Err(e) = result {
+                    tracing::warn!("Spectre refused to lock the session: {}", e);
+                }
+            }
+        }
+
+        Command::none()
+    }
+
+    fn handle_wallpaper_message(&mut self, msg: WallpaperMessage) -> Command<Message> {
+        match msg {
+            WallpaperMessage::Output(Ok(output)) => {
+                self.output = output;
+                return self.sync_palette();
+            }
+
+            WallpaperMessage::Output(Err(e)) => {
+                tracing::debug!("Outputs unavailable, using workspace wallpapers only: {}", e);
+            }
+
+            // A result for a wallpaper that has since changed is dropped
+            WallpaperMessage::Themed(path, result) if self.palette_source.as_ref() == Some(&path) => {
+                match result {
+                    Ok(theme) => self.theme = theme,
+                    Err(e) => {
+                        tracing::warn!("No palette from wallpaper {}: {}", path.display(), e);
+                        self.theme = NyxTheme::dark();
+                    }
+                }
             }
+
+            WallpaperMessage::Themed(..) => {}
+        }
+
+        Command::none()
+    }
+
+    /// Wallpaper for the active workspace on this output
+    fn current_wallpaper(&self) -> Option<Wallpaper> {
+        self.wallpapers.current(self.output.as_deref(), self.workspaces.active_id())
+    }
+
+    /// Take the accent from the wallpaper once it changed
+    fn sync_palette(&mut self) -> Command<Message> {
+        if !self.wallpapers.dynamic_palette() {
+            return Command::none();
+        }
+
+        let path = self.current_wallpaper().map(|w| w.path);
+        if path == self.palette_source {
+            return Command::none();
+        }
+        self.palette_source = path.clone();
+
+        match path {
+            Some(path) => Command::perform(wallpaper::themed(NyxTheme::dark(), path.clone()), move |result| {
+                Message::Wallpaper(WallpaperMessage::Themed(path, result))
+            }),
+            None => {
+                self.theme = NyxTheme::dark();
+                Command::none()
+            }
+        }
+    }
+
+    /// Send a window request to the compositor
+    fn request_window(&self, request: WindowRequest) {
+        match &self.window_control {
+            Some(control) => control.send(request),
+            None => tracing::warn!("No window management; dropping {:?}", request),
         }
     }
 
@@ -269,26 +799,44 @@ impl NyxShell {
         }
     }
 
-    fn view_activities_overlay(&self) -> Element<Message> {
-        use iced::widget::text;
+    fn view_switcher(&self, switcher: Switcher) -> Element<Message> {
+        use iced::widget::{button, text, Row};
         use nyx_theme::spacing::Spacing;
-        use nyx_theme::widgets::card::card_style;
-        use nyx_theme::widgets::CardVariant;
+        use nyx_theme::widgets::panel::{menu_item_style, popover_style};
 
-        container(
-            container(
+        let entries = self.windows.windows().iter().enumerate().map(|(i, window)| {
+            let selected = i == switcher.selected();
+            let entry = container(
                 column![
-                    text("Activities")
-                        .size(nyx_theme::Typography::SIZE_HEADLINE_LARGE)
+                    text(self.dock.icon_for(&window.app_id))
+                        .size(SWITCHER_ICON_SIZE)
                         .color(NyxColors::TEXT_BRIGHT),
-                    text("Window overview and workspace management")
-                        .size(nyx_theme::Typography::SIZE_BODY_MEDIUM)
-                        .color(NyxColors::TEXT_SECONDARY),
+                    text(window.label())
+                        .size(nyx_theme::Typography::SIZE_LABEL_MEDIUM)
+                        .color(if selected {
+                            NyxColors::TEXT_BRIGHT
+                        } else {
+                            NyxColors::TEXT_SECONDARY
+                        }),
                 ]
-                .spacing(Spacing::SM),
+                .spacing(Spacing::XS)
+                .align_x(iced::Alignment::Center),
             )
-            .padding(Spacing::XL)
-            .style(card_style(CardVariant::Glass)),
+            .width(Length::Fixed(SWITCHER_ENTRY_WIDTH))
+            .padding(Spacing::SM)
+            .style(menu_item_style(selected));
+
+            button(entry)
+                .padding(0)
+                .style(|_theme, _status| iced::widget::button::Style::default())
+                .on_press(Message::Switcher(SwitcherMessage::Select(window.id)))
+                .into()
+        });
+
+        container(
+            container(Row::with_children(entries).spacing(Spacing::SM))
+                .padding(Spacing::MD)
+                .style(popover_style()),
         )
         .width(Length::Fill)
         .height(Length::Fill)
@@ -297,6 +845,97 @@ impl NyxShell {
         .into()
     }
 
+    fn view_activities_overlay(&self) -> Element<Message> {
+        use iced::widget::{mouse_area, text, Column, Row};
+        use nyx_theme::spacing::Spacing;
+        use nyx_theme::widgets::panel::workspace_thumbnail_style;
+
+        let dragging = self.overview.dragging().is_some();
+        let workspaces = self.workspaces.workspaces().iter().map(|workspace| {
+            let windows: Vec<&OverviewWindow> = self.overview.on_workspace(workspace.id).collect();
+            let columns = overview::grid_columns(windows.len());
+            let width = (OVERVIEW_WORKSPACE_WIDTH - Spacing::SM * (columns as f32 + 1.0))
+                / columns as f32;
+
+            let rows = windows.chunks(columns).map(|chunk| {
+                Row::with_children(chunk.iter().map(|window| self.view_overview_window(window, width)))
+                    .spacing(Spacing::SM)
+                    .into()
+            });
+
+            // Highlight where a dragged window would land
+            let highlighted = if dragging {
+                self.overview.drop_target() == Some(workspace.id)
+            } else {
+                workspace.active
+            };
+
+            let content = container(
+                column![
+                    text(workspace.name.as_str())
+                        .size(nyx_theme::Typography::SIZE_LABEL_MEDIUM)
+                        .color(NyxColors::TEXT_SECONDARY),
+                    Column::with_children(rows).spacing(Spacing::SM),
+                ]
+                .spacing(Spacing::SM),
+            )
+            .width(Length::Fixed(OVERVIEW_WORKSPACE_WIDTH))
+            .height(Length::Fixed(OVERVIEW_WORKSPACE_HEIGHT))
+            .padding(Spacing::SM)
+            .style(workspace_thumbnail_style(highlighted));
+
+            mouse_area(content)
+                .on_enter(Message::Overview(OverviewMessage::Hover(workspace.id)))
+                .on_exit(Message::Overview(OverviewMessage::Unhover))
+                .into()
+        });
+
+        container(Row::with_children(workspaces).spacing(Spacing::LG))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_x(iced::alignment::Horizontal::Center)
+            .align_y(iced::alignment::Vertical::Center)
+            .into()
+    }
+
+    fn view_overview_window(&self, window: &OverviewWindow, width: f32) -> Element<Message> {
+        use iced::widget::{image, mouse_area, text};
+        use nyx_theme::spacing::Spacing;
+        use nyx_theme::widgets::panel::menu_item_style;
+
+        // App icon until Guardian allows capture and the first frame arrives
+        let preview: Element<Message> = match &window.thumbnail {
+            Some(handle) => image(handle.clone()).width(Length::Fixed(width)).into(),
+            None => container(
+                text(self.dock.icon_for(&window.app_id))
+                    .size(SWITCHER_ICON_SIZE)
+                    .color(NyxColors::TEXT_BRIGHT),
+            )
+            .width(Length::Fixed(width))
+            .height(Length::Fixed(width * 0.6))
+            .align_x(iced::alignment::Horizontal::Center)
+            .align_y(iced::alignment::Vertical::Center)
+            .into(),
+        };
+
+        let selected = window.focused || self.overview.dragging() == Some(window.id);
+        let entry = container(
+            column![
+                preview,
+                text(window.title.as_str())
+                    .size(nyx_theme::Typography::SIZE_LABEL_SMALL)
+                    .color(NyxColors::TEXT_SECONDARY),
+            ]
+            .spacing(Spacing::XS),
+        )
+        .padding(Spacing::XS)
+        .style(menu_item_style(selected));
+
+        mouse_area(entry)
+            .on_press(Message::Overview(OverviewMessage::Press(window.id)))
+            .into()
+    }
+
     fn view_control_center_overlay(&self) -> Element<Message> {
         use iced::widget::text;
         use nyx_theme::spacing::Spacing;
@@ -316,7 +955,7 @@ impl NyxShell {
                     .spacing(Spacing::SM),
                     row![
                         self.view_quick_toggle("󰃞", "Night Light", false),
-                        self.view_quick_toggle("󰍹", "Do Not Disturb", false),
+                        self.view_quick_toggle("󰍹", "Do Not Disturb", self.notifications.dnd()),
                         self.view_quick_toggle("󰌾", "Lock", false),
                     ]
                     .spacing(Spacing::SM),
@@ -341,6 +980,229 @@ impl NyxShell {
         .into()
     }
 
+    fn view_notification_popups(&self) -> Element<Message> {
+        use iced::widget::{button, text, Column, Row};
+        use nyx_theme::spacing::Spacing;
+        use nyx_theme::widgets::button::{button_style, ButtonVariant};
+        use nyx_theme::widgets::panel::notification_style;
+
+        let popups = self.notifications.popups().map(|notification| {
+            let actions = notification.actions.iter().map(|(action_id, label)| {
+                button(text(label.as_str()).size(nyx_theme::Typography::SIZE_LABEL_MEDIUM))
+                    .padding(iced::Padding::from([Spacing::XXS, Spacing::SM]))
+                    .style(button_style(ButtonVariant::Secondary))
+                    .on_press(Message::Notifications(NotificationMessage::Invoke(
+                        notification.id,
+                        action_id.clone(),
+                    )))
+                    .into()
+            });
+
+            let dismiss = button(text("󰅖").size(nyx_theme::Typography::SIZE_LABEL_MEDIUM))
+                .padding(Spacing::XXS)
+                .style(button_style(ButtonVariant::Ghost))
+                .on_press(Message::Notifications(NotificationMessage::Dismiss(notification.id)));
+
+            container(
+                column![
+                    row![
+                        text(notification.app_name.as_str())
+                            .size(nyx_theme::Typography::SIZE_LABEL_SMALL)
+                            .color(NyxColors::TEXT_MUTED),
+                        horizontal_space(),
+                        dismiss,
+                    ]
+                    .align_y(iced::Alignment::Center),
+                    self.view_notification_text(notification),
+                    Row::with_children(actions).spacing(Spacing::XS),
+                ]
+                .spacing(Spacing::XS),
+            )
+            .width(Length::Fixed(NOTIFICATION_WIDTH))
+            .padding(Spacing::MD)
+            .style(notification_style())
+            .into()
+        });
+
+        container(Column::with_children(popups).spacing(Spacing::SM))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_x(iced::alignment::Horizontal::Right)
+            .align_y(iced::alignment::Vertical::Top)
+            .padding(iced::Padding::from([Spacing::MD, Spacing::MD, 0.0, 0.0]))
+            .into()
+    }
+
+    fn view_osd(&self) -> Element<Message> {
+        use iced::widget::{progress_bar, text};
+        use libnyx_ipc::audio::OsdEvent;
+        use nyx_theme::spacing::Spacing;
+        use nyx_theme::widgets::panel::notification_style;
+        use nyx_theme::Typography;
+
+        let Some(event) = self.osd.current() else {
+            return horizontal_space().into();
+        };
+
+        let content: Element<Message> = match event {
+            OsdEvent::Volume { description, volume, muted, max_volume, limited, .. } => {
+                let level = if *muted { 0.0 } else { *volume as f32 };
+                let mut content = column![
+                    row![
+                        text(osd::volume_icon(*volume, *muted)).size(Spacing::ICON_MD),
+                        text(description.as_str())
+                            .size(Typography::SIZE_LABEL_MEDIUM)
+                            .color(NyxColors::TEXT_MUTED),
+                        horizontal_space(),
+                        text(if *muted { "Muted".to_string() } else { format!("{}%", volume) })
+                            .size(Typography::SIZE_LABEL_MEDIUM),
+                    ]
+                    .spacing(Spacing::SM)
+                    .align_y(iced::Alignment::Center),
+                    progress_bar(0.0..=100.0, level).height(Length::Fixed(6.0)),
+                ]
+                .spacing(Spacing::SM);
+                if *limited {
+                    content = content.push(
+                        text(format!("Limited to {}% for this device", max_volume))
+                            .size(Typography::SIZE_LABEL_SMALL)
+                            .color(NyxColors::TEXT_MUTED),
+                    );
+                }
+                content.into()
+            }
+            OsdEvent::MicMute { muted, .. } => row![
+                text(if *muted { "󰍭" } else { "󰍬" }).size(Spacing::ICON_MD),
+                text(if *muted { "Microphone muted" } else { "Microphone on" })
+                    .size(Typography::SIZE_LABEL_MEDIUM),
+            ]
+            .spacing(Spacing::SM)
+            .align_y(iced::Alignment::Center)
+            .into(),
+        };
+
+        container(
+            container(content)
+                .width(Length::Fixed(OSD_WIDTH))
+                .padding(Spacing::MD)
+                .style(notification_style()),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(iced::alignment::Horizontal::Center)
+        .align_y(iced::alignment::Vertical::Bottom)
+        .padding(iced::Padding::from([0.0, 0.0, Spacing::XXL, 0.0]))
+        .into()
+    }
+
+    fn view_notification_center(&self) -> Element<Message> {
+        use iced::widget::{button, scrollable, text, toggler, Column};
+        use nyx_theme::spacing::Spacing;
+        use nyx_theme::widgets::button::{button_style, ButtonVariant};
+        use nyx_theme::widgets::panel::{menu_item_style, quick_settings_style};
+        use nyx_theme::widgets::toggle::toggle_style;
+
+        let header = row![
+            text("Notifications")
+                .size(nyx_theme::Typography::SIZE_HEADLINE_MEDIUM)
+                .color(NyxColors::TEXT_BRIGHT),
+            horizontal_space(),
+            toggler(self.notifications.dnd())
+                .label("Do Not Disturb")
+                .text_size(nyx_theme::Typography::SIZE_LABEL_MEDIUM)
+                .on_toggle(|enabled| Message::Notifications(NotificationMessage::SetDnd(enabled)))
+                .style(toggle_style()),
+        ]
+        .align_y(iced::Alignment::Center);
+
+        let content: Element<Message> = if self.notifications.is_empty() {
+            text("No notifications")
+                .size(nyx_theme::Typography::SIZE_BODY_MEDIUM)
+                .color(NyxColors::TEXT_MUTED)
+                .into()
+        } else {
+            let groups = self.notifications.groups().into_iter().map(|(app_name, entries)| {
+                let clear = button(text("Clear").size(nyx_theme::Typography::SIZE_LABEL_SMALL))
+                    .padding(iced::Padding::from([Spacing::XXS, Spacing::SM]))
+                    .style(button_style(ButtonVariant::Ghost))
+                    .on_press(Message::Notifications(NotificationMessage::ClearApp(
+                        app_name.to_string(),
+                    )));
+
+                let entries = entries.into_iter().map(|notification| {
+                    container(
+                        row![
+                            self.view_notification_text(notification),
+                            horizontal_space(),
+                            text(notification.timestamp.format("%H:%M").to_string())
+                                .size(nyx_theme::Typography::SIZE_CAPTION)
+                                .color(NyxColors::TEXT_MUTED),
+                        ]
+                        .spacing(Spacing::SM),
+                    )
+                    .width(Length::Fill)
+                    .padding(Spacing::SM)
+                    .style(menu_item_style(false))
+                    .into()
+                });
+
+                column![
+                    row![
+                        text(app_name)
+                            .size(nyx_theme::Typography::SIZE_LABEL_MEDIUM)
+                            .color(NyxColors::TEXT_SECONDARY),
+                        horizontal_space(),
+                        clear,
+                    ]
+                    .align_y(iced::Alignment::Center),
+                    Column::with_children(entries).spacing(Spacing::XS),
+                ]
+                .spacing(Spacing::XS)
+                .into()
+            });
+
+            scrollable(Column::with_children(groups).spacing(Spacing::MD)).into()
+        };
+
+        container(
+            container(
+                column![header, content]
+                    .spacing(Spacing::MD)
+                    .width(Length::Fixed(NOTIFICATION_WIDTH)),
+            )
+            .height(Length::Fill)
+            .padding(Spacing::LG)
+            .style(quick_settings_style()),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .align_x(iced::alignment::Horizontal::Right)
+        .padding(iced::Padding::from([Spacing::SM, Spacing::MD, Spacing::MD, 0.0]))
+        .into()
+    }
+
+    /// Summary and body of a notification
+    fn view_notification_text<'a>(&self, notification: &'a Notification) -> Element<'a, Message> {
+        use iced::widget::text;
+        use nyx_theme::spacing::Spacing;
+
+        let summary = text(notification.summary.as_str())
+            .size(nyx_theme::Typography::SIZE_TITLE_SMALL)
+            .color(NyxColors::TEXT_BRIGHT);
+
+        match &notification.body {
+            Some(body) => column![
+                summary,
+                text(body.as_str())
+                    .size(nyx_theme::Typography::SIZE_BODY_SMALL)
+                    .color(NyxColors::TEXT_SECONDARY),
+            ]
+            .spacing(Spacing::XXS)
+            .into(),
+            None => summary.into(),
+        }
+    }
+
     fn view_quick_toggle(&self, icon: &str, label: &str, active: bool) -> Element<Message> {
         use iced::widget::{button, column, text};
         use nyx_theme::spacing::Spacing;
@@ -437,3 +1299,21 @@ impl NyxShell {
         .into()
     }
 }
+
+/// Report the outcome of a request to herald
+fn notifications_done(result: libnyx_ipc::Result<()>) -> Message {
+    Message::Notifications(NotificationMessage::Done(result.map_err(|e| e.to_string())))
+}
+
+/// Report the outcome of an overview request
+fn overview_done(result: libnyx_ipc::Result<()>) -> Message {
+    Message::Overview(OverviewMessage::Done(result.map_err(|e| e.to_string())))
+}
+
+/// Start an application by its ID, which doubles as its command
+fn launch_app(app_id: &str) {
+    tracing::info!("Launching app: {}", app_id);
+    if let Err(e) = std::process::Command::new(app_id).spawn() {
+        tracing::warn!("Failed to launch {}: {}", app_id, e);
+    }
+}
@@ -2,6 +2,7 @@
 
 use nyx_theme::{AccentColor, ThemeMode};
 use serde::{Deserialize, Serialize};
+use crate::workspace::WorkspaceId;
 use std::path::PathBuf;
 
 /// Shell configuration
@@ -15,6 +16,18 @@ pub struct ShellConfig {
     pub dock: DockConfig,
     /// Workspace configuration
     pub workspaces: WorkspaceConfig,
+    /// Activities overview configuration
+    #[serde(default)]
+    pub overview: OverviewConfig,
+    /// Notification popups
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    /// Lock screen
+    #[serde(default)]
+    pub lock: LockConfig,
+    /// Desktop wallpaper
+    #[serde(default)]
+    pub wallpaper: WallpaperConfig,
 }
 
 impl Default for ShellConfig {
@@ -24,6 +37,10 @@ impl Default for ShellConfig {
             panel: PanelConfig::default(),
             dock: DockConfig::default(),
             workspaces: WorkspaceConfig::default(),
+            overview: OverviewConfig::default(),
+            notifications: NotificationsConfig::default(),
+            lock: LockConfig::default(),
+            wallpaper: WallpaperConfig::default(),
         }
     }
 }
@@ -207,3 +224,129 @@ impl Default for WorkspaceConfig {
         }
     }
 }
+
+/// Activities overview configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverviewConfig {
+    /// Open the overview by pushing the pointer into the top-left corner
+    pub hot_corner: bool,
+    /// Open, close and switch workspaces with touchpad swipes
+    pub swipe_gestures: bool,
+    /// Fingers for overview swipes
+    pub swipe_fingers: u32,
+}
+
+impl Default for OverviewConfig {
+    fn default() -> Self {
+        Self {
+            hot_corner: true,
+            swipe_gestures: true,
+            swipe_fingers: 3,
+        }
+    }
+}
+
+/// Notification popup configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationsConfig {
+    /// Seconds a popup stays up; critical ones stay until dismissed
+    pub popup_timeout_secs: u64,
+    /// Popups on screen at once
+    pub max_popups: usize,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            popup_timeout_secs: 5,
+            max_popups: 3,
+        }
+    }
+}
+
+/// Lock screen configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockConfig {
+    /// Show the clock and date
+    pub show_clock: bool,
+    /// How much of incoming notifications to show
+    pub notifications: LockNotifications,
+}
+
+impl Default for LockConfig {
+    fn default() -> Self {
+        Self {
+            show_clock: true,
+            notifications: LockNotifications::AppNames,
+        }
+    }
+}
+
+/// Notifications on the lock screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum LockNotifications {
+    /// Nothing
+    Hidden,
+    /// How many arrived while locked
+    Count,
+    /// Which apps sent them, without their content (default)
+    #[default]
+    AppNames,
+    /// Summary and body
+    Full,
+}
+
+/// Wallpaper configuration
+///
+/// An image may also be a directory, whose images are shown in turn as a
+/// slideshow.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WallpaperConfig {
+    /// Image when no rule matches
+    pub image: Option<PathBuf>,
+    /// How images are fitted to the output
+    pub fill: FillMode,
+    /// Images for particular outputs and workspaces; the most specific wins
+    pub rules: Vec<WallpaperRule>,
+    /// Seconds each slideshow image is shown
+    pub slideshow_interval_secs: u64,
+    /// Take the accent color from the wallpaper
+    pub dynamic_palette: bool,
+}
+
+impl Default for WallpaperConfig {
+    fn default() -> Self {
+        Self {
+            image: None,
+            fill: FillMode::Fill,
+            rules: Vec::new(),
+            slideshow_interval_secs: 600,
+            dynamic_palette: true,
+        }
+    }
+}
+
+/// Wallpaper for an output, a workspace, or a workspace on one output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WallpaperRule {
+    /// Output name, e.g. "HDMI-A-1"
+    pub output: Option<String>,
+    pub workspace: Option<WorkspaceId>,
+    pub image: PathBuf,
+    /// Overrides the default fill mode
+    pub fill: Option<FillMode>,
+}
+
+/// How a wallpaper is fitted to the output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum FillMode {
+    /// Cover the output, cropping the overflow (default)
+    #[default]
+    Fill,
+    /// Show the whole image, letterboxed
+    Fit,
+    /// Scale to the output, ignoring the aspect ratio
+    Stretch,
+    /// Unscaled in the middle
+    Center,
+}
@@ -2,14 +2,23 @@
 
 use crate::config::DockConfig;
 use crate::messages::{DockMessage, Message};
-use iced::widget::{button, column, container, row, text, Column, Row};
+use crate::windows::{Toplevel, WindowList};
+use iced::widget::{button, column, container, mouse_area, row, text, Column, Row};
 use iced::{Alignment, Element, Length, Padding};
 use nyx_theme::colors::NyxColors;
 use nyx_theme::spacing::Spacing;
 use nyx_theme::widgets::button::{button_style, ButtonVariant};
-use nyx_theme::widgets::panel::{dock_item_style, dock_style, running_indicator_style};
+use nyx_theme::widgets::panel::{
+    dock_item_style, dock_style, menu_item_style, popover_style, running_indicator_style,
+};
 use nyx_theme::Typography;
 
+/// Icon for running apps that are not pinned
+const FALLBACK_ICON: &str = "󰣆";
+
+/// Width of an app's window menu
+const MENU_WIDTH: f32 = 280.0;
+
 /// Application in the dock
 #[derive(Debug, Clone)]
 pub struct DockApp {
@@ -68,6 +77,8 @@ pub struct Dock {
     apps: Vec<DockApp>,
     /// Currently hovered app
     hovered: Option<String>,
+    /// App whose window menu is open
+    menu: Option<String>,
 }
 
 impl Default for Dock {
@@ -81,9 +92,9 @@ impl Dock {
     pub fn new(config: DockConfig) -> Self {
         let apps = vec![
             DockApp::new("nyx-assistant", "Assistant", "󰚩"),
-            DockApp::new("umbra", "Terminal", "󰆍").with_running(true).with_windows(2),
+            DockApp::new("umbra", "Terminal", "󰆍"),
             DockApp::new("nyx-files", "Files", "󰉋"),
-            DockApp::new("nyx-browser", "Browser", "󰈹").with_running(true).with_windows(1),
+            DockApp::new("nyx-browser", "Browser", "󰈹"),
             DockApp::new("nyx-code", "Code", "󰨞"),
             DockApp::new("nyx-settings", "Settings", "󰒓"),
         ];
@@ -92,6 +103,7 @@ impl Dock {
             config,
             apps,
             hovered: None,
+            menu: None,
         }
     }
 
@@ -117,15 +129,68 @@ impl Dock {
         self.apps.retain(|a| a.id != id);
     }
 
-    /// Toggle pin status
+    /// Toggle pin status; an unpinned app without windows leaves the dock
     pub fn toggle_pin(&mut self, id: &str) {
         if let Some(app) = self.apps.iter_mut().find(|a| a.id == id) {
             app.pinned = !app.pinned;
         }
+        self.apps.retain(|a| a.pinned || a.running);
+    }
+
+    /// Icon for an app, pinned or not
+    pub fn icon_for(&self, app_id: &str) -> &str {
+        self.apps
+            .iter()
+            .find(|a| a.id == app_id)
+            .map_or(FALLBACK_ICON, |a| a.icon.as_str())
+    }
+
+    /// App whose window menu is open
+    pub fn menu(&self) -> Option<&str> {
+        self.menu.as_deref()
+    }
+
+    /// Open an app's window menu, or close it if it is already open
+    pub fn toggle_menu(&mut self, id: String) {
+        self.menu = if self.menu.as_ref() == Some(&id) {
+            None
+        } else {
+            Some(id)
+        };
+    }
+
+    /// Close the window menu
+    pub fn close_menu(&mut self) {
+        self.menu = None;
     }
 
-    /// Render the dock
-    pub fn view(&self) -> Element<Message> {
+    /// Match the dock to the compositor's windows: count each app's
+    /// windows, add running apps that are not pinned and drop them again
+    /// once their last window closes
+    pub fn sync_windows(&mut self, windows: &WindowList) {
+        for window in windows.windows() {
+            if !window.app_id.is_empty() && !self.apps.iter().any(|a| a.id == window.app_id) {
+                let mut app = DockApp::new(&window.app_id, &window.app_id, FALLBACK_ICON);
+                app.pinned = false;
+                self.apps.push(app);
+            }
+        }
+
+        for app in &mut self.apps {
+            app.window_count = windows.for_app(&app.id).count();
+            app.running = app.window_count > 0;
+        }
+        self.apps.retain(|a| a.pinned || a.running);
+
+        if let Some(menu) = &self.menu {
+            if !self.apps.iter().any(|a| &a.id == menu) {
+                self.menu = None;
+            }
+        }
+    }
+
+    /// Render the dock, with the open window menu above it
+    pub fn view<'a>(&'a self, windows: &'a WindowList) -> Element<'a, Message> {
         let dock_items: Vec<Element<Message>> = self
             .apps
             .iter()
@@ -137,12 +202,87 @@ impl Dock {
             .align_y(Alignment::Center)
             .padding(Padding::from([Spacing::SM, Spacing::MD]));
 
-        container(dock_content)
-            .style(dock_style())
+        let dock = container(dock_content).style(dock_style());
+
+        match self.menu.as_ref().and_then(|id| self.apps.iter().find(|a| &a.id == id)) {
+            Some(app) => column![self.view_menu(app, windows), dock]
+                .spacing(Spacing::SM)
+                .align_x(Alignment::Center)
+                .into(),
+            None => dock.into(),
+        }
+    }
+
+    /// Window list and app actions for one dock item
+    fn view_menu<'a>(&'a self, app: &'a DockApp, windows: &'a WindowList) -> Element<'a, Message> {
+        let mut items = Column::new().spacing(Spacing::XXS).push(
+            text(&app.name)
+                .size(Typography::SIZE_LABEL_MEDIUM)
+                .color(NyxColors::TEXT_SECONDARY),
+        );
+
+        for window in windows.for_app(&app.id) {
+            items = items.push(self.view_menu_window(window));
+        }
+
+        let pin_label = if app.pinned { "Unpin" } else { "Keep in Dock" };
+        let mut actions = row![button(text(pin_label).size(Typography::SIZE_LABEL_MEDIUM))
+            .style(button_style(ButtonVariant::Ghost))
+            .on_press(Message::Dock(DockMessage::TogglePin(app.id.clone())))]
+        .spacing(Spacing::XS);
+        if app.running {
+            actions = actions.push(
+                button(text("Close All").size(Typography::SIZE_LABEL_MEDIUM))
+                    .style(button_style(ButtonVariant::Ghost))
+                    .on_press(Message::Dock(DockMessage::CloseApp(app.id.clone()))),
+            );
+        } else {
+            actions = actions.push(
+                button(text("Open").size(Typography::SIZE_LABEL_MEDIUM))
+                    .style(button_style(ButtonVariant::Ghost))
+                    .on_press(Message::Dock(DockMessage::LaunchApp(app.id.clone()))),
+            );
+        }
+
+        container(items.push(actions))
+            .width(Length::Fixed(MENU_WIDTH))
+            .padding(Spacing::SM)
+            .style(popover_style())
             .into()
     }
 
-    fn view_dock_item(&self, app: &DockApp) -> Element<Message> {
+    fn view_menu_window<'a>(&self, window: &'a Toplevel) -> Element<'a, Message> {
+        let title = button(
+            text(window.label())
+                .size(Typography::SIZE_BODY_MEDIUM)
+                .color(if window.minimized {
+                    NyxColors::TEXT_MUTED
+                } else {
+                    NyxColors::TEXT_BRIGHT
+                }),
+        )
+        .style(button_style(ButtonVariant::Ghost))
+        .width(Length::Fill)
+        .on_press(Message::Dock(DockMessage::FocusWindow(window.id)));
+
+        let minimize = button(text(if window.minimized { "󰖯" } else { "󰖰" }))
+            .style(button_style(ButtonVariant::Icon))
+            .on_press(Message::Dock(DockMessage::ToggleMinimized(window.id)));
+
+        let close = button(text("󰖭"))
+            .style(button_style(ButtonVariant::Icon))
+            .on_press(Message::Dock(DockMessage::CloseWindow(window.id)));
+
+        container(
+            row![title, minimize, close]
+                .spacing(Spacing::XXS)
+                .align_y(Alignment::Center),
+        )
+        .style(menu_item_style(window.activated))
+        .into()
+    }
+
+    fn view_dock_item<'a>(&'a self, app: &'a DockApp) -> Element<'a, Message> {
         let is_hovered = self.hovered.as_ref() == Some(&app.id);
         let icon_size = if is_hovered && self.config.magnification {
             self.config.icon_size as f32 * 1.2
@@ -178,6 +318,10 @@ impl Dock {
                 .height(Length::Fixed(6.0))
         };
 
+        // Right click opens the app's window menu
+        let icon_btn = mouse_area(icon_btn)
+            .on_right_press(Message::Dock(DockMessage::AppRightClicked(app.id.clone())));
+
         // Stack icon and indicator
         column![
             container(icon_btn).style(dock_item_style(app.running, is_hovered)),
@@ -188,3 +332,50 @@ impl Dock {
         .into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::windows::Toplevel;
+
+    fn window(id: u32, app_id: &str) -> Toplevel {
+        Toplevel {
+            id,
+            app_id: app_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sync_windows() {
+        let mut dock = Dock::default();
+        let pinned = dock.apps().len();
+
+        let mut windows = WindowList::new();
+        windows.update(window(1, "umbra"));
+        windows.update(window(2, "umbra"));
+        windows.update(window(3, "firefox"));
+        dock.sync_windows(&windows);
+
+        let umbra = dock.apps().iter().find(|a| a.id == "umbra").unwrap();
+        assert!(umbra.running);
+        assert_eq!(umbra.window_count, 2);
+        let firefox = dock.apps().last().unwrap();
+        assert_eq!(firefox.id, "firefox");
+        assert!(!firefox.pinned);
+        assert_eq!(dock.icon_for("firefox"), FALLBACK_ICON);
+
+        dock.toggle_menu("firefox".to_string());
+        windows.remove(3);
+        dock.sync_windows(&windows);
+        assert_eq!(dock.apps().len(), pinned);
+        assert!(dock.menu().is_none());
+
+        // Unpinning a running app keeps it until its windows close
+        dock.toggle_pin("umbra");
+        assert!(dock.apps().iter().any(|a| a.id == "umbra"));
+        windows.clear();
+        dock.sync_windows(&windows);
+        assert!(!dock.apps().iter().any(|a| a.id == "umbra"));
+    }
+}
@@ -0,0 +1,145 @@
+//! Touchpad swipe gestures
+//!
+//! Binds `zwp_pointer_gestures_v1` on a Wayland connection of its own and
+//! passes swipe begin/update/end on as `GestureEvent`s; `SwipeTracker` in
+//! `overview` decides what a finished swipe means. Like the window list,
+//! the protocol is read on a dedicated thread because dispatching blocks on
+//! the socket. Without the global (or a pointer) gestures are simply off.
+
+use crate::overview::GestureEvent;
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::Subscription;
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{wl_pointer::WlPointer, wl_registry, wl_seat::WlSeat};
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle};
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_swipe_v1::{
+    self, ZwpPointerGestureSwipeV1,
+};
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gestures_v1::ZwpPointerGesturesV1;
+
+/// Swipe gestures on the shell's surfaces
+pub fn subscription() -> Subscription<GestureEvent> {
+    iced::subscription::channel("pointer-gestures", 64, |mut output| async move {
+        let (sender, mut receiver) = mpsc::unbounded();
+
+        let spawned = std::thread::Builder::new()
+            .name("pointer-gestures".into())
+            .spawn(move || {
+                if let Err(e) = run(sender) {
+                    tracing::info!("Touchpad gestures unavailable: {}", e);
+                }
+            });
+        if let Err(e) = spawned {
+            tracing::warn!("Failed to start gesture thread: {}", e);
+        }
+
+        while let Some(event) = receiver.next().await {
+            let _ = output.send(event).await;
+        }
+
+        iced::futures::future::pending().await
+    })
+}
+
+/// Connect, bind the gesture manager and dispatch until the connection ends
+fn run(events: mpsc::UnboundedSender<GestureEvent>) -> Result<(), String> {
+    let conn = Connection::connect_to_env().map_err(|e| format!("No Wayland display: {}", e))?;
+    let (globals, mut queue) =
+        registry_queue_init::<State>(&conn).map_err(|e| format!("Registry failed: {}", e))?;
+    let qh = queue.handle();
+
+    let gestures: ZwpPointerGesturesV1 = globals
+        .bind(&qh, 1..=1, ())
+        .map_err(|_| "Compositor does not offer pointer gestures".to_string())?;
+    let seat: WlSeat = globals
+        .bind(&qh, 1..=5, ())
+        .map_err(|e| format!("No seat: {}", e))?;
+    let pointer = seat.get_pointer(&qh, ());
+    let _swipe = gestures.get_swipe_gesture(&pointer, &qh, ());
+
+    let mut state = State { events };
+    loop {
+        queue
+            .blocking_dispatch(&mut state)
+            .map_err(|e| format!("Compositor connection lost: {}", e))?;
+    }
+}
+
+/// Dispatch state on the gesture thread
+struct State {
+    events: mpsc::UnboundedSender<GestureEvent>,
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for State {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlSeat, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &WlSeat,
+        _: <WlSeat as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlPointer, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &WlPointer,
+        _: <WlPointer as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpPointerGesturesV1, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &ZwpPointerGesturesV1,
+        _: <ZwpPointerGesturesV1 as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpPointerGestureSwipeV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZwpPointerGestureSwipeV1,
+        event: zwp_pointer_gesture_swipe_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let event = match event {
+            zwp_pointer_gesture_swipe_v1::Event::Begin { fingers, .. } => {
+                GestureEvent::Begin { fingers }
+            }
+            zwp_pointer_gesture_swipe_v1::Event::Update { dx, dy, .. } => {
+                GestureEvent::Update { dx, dy }
+            }
+            zwp_pointer_gesture_swipe_v1::Event::End { cancelled, .. } => GestureEvent::End {
+                cancelled: cancelled != 0,
+            },
+            _ => return,
+        };
+        let _ = state.events.unbounded_send(event);
+    }
+}
@@ -0,0 +1,469 @@
+//! Lock screen
+//!
+//! Spectre decides when a session is locked; the shell follows its event
+//! stream and puts the lock screen up. The lock screen runs as its own
+//! process (`nyx-shell --lock <session>`) because ext-session-lock gives
+//! the locking client one surface per output and keeps every other
+//! surface, the shell's included, hidden and without input until that
+//! client unlocks. If the lock screen dies the compositor keeps the
+//! outputs blanked, and the shell starts a new one while spectre still
+//! reports the session locked.
+//!
+//! The password goes to spectre, which checks it with PAM; the outputs are
+//! only released once spectre has unlocked the session.
+
+use crate::config::LockNotifications;
+use iced::futures::SinkExt;
+use iced::Subscription;
+use libnyx_ipc::notifications::Notification;
+use libnyx_ipc::session::SessionEvent;
+use libnyx_ipc::SessionClient;
+use std::process::{Child, Command};
+use std::time::Duration;
+
+/// Wait before reconnecting to spectre
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Notification lines on the lock screen
+const MAX_NOTIFICATION_LINES: usize = 5;
+
+/// The session the shell runs in, as spectre named it
+pub fn current_session() -> Option<String> {
+    std::env::var("XDG_SESSION_ID").ok().filter(|id| !id.is_empty())
+}
+
+/// Ask spectre to lock this session, or the active one
+pub async fn lock_session(session: Option<String>) -> libnyx_ipc::Result<()> {
+    SessionClient::new().lock(session.as_deref()).await
+}
+
+/// Spectre's lock and unlock events, reconnecting when it restarts
+pub fn subscription() -> Subscription<SessionEvent> {
+    iced::subscription::channel("spectre-sessions", 16, |mut output| async move {
+        let client = SessionClient::new();
+
+        loop {
+            match client.subscribe().await {
+                Ok(mut events) => loop {
+                    match events.next().await {
+                        Ok(event) => {
+                            let _ = output.send(event).await;
+                        }
+                        Err(e) => {
+                            tracing::debug!("Spectre event stream ended: {}", e);
+                            break;
+                        }
+                    }
+                },
+                Err(e) => tracing::debug!("Spectre unavailable: {}", e),
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    })
+}
+
+/// Keeps a lock screen process running while the session is locked
+#[derive(Debug)]
+pub struct LockGuard {
+    session: Option<String>,
+    locked: bool,
+    process: Option<Child>,
+}
+
+impl LockGuard {
+    pub fn new(session: Option<String>) -> Self {
+        Self {
+            session,
+            locked: false,
+            process: None,
+        }
+    }
+
+    /// Follow an event from spectre
+    pub fn handle(&mut self, event: SessionEvent) {
+        let (session, locked) = match event {
+            SessionEvent::Locked { session } => (session, true),
+            SessionEvent::Unlocked { session } => (session, false),
+        };
+        if self.session.as_deref() != Some(session.as_str()) {
+            return;
+        }
+
+        self.locked = locked;
+        self.check();
+    }
+
+    /// Start the lock screen again if it exited while still locked
+    pub fn check(&mut self) {
+        if let Some(process) = &mut self.process {
+            match process.try_wait() {
+                Ok(None) => return,
+                Ok(Some(status)) if self.locked => {
+                    tracing::warn!("Lock screen exited ({}) while locked; restarting", status)
+                }
+                Ok(Some(_)) => {}
+                Err(e) => tracing::warn!("Failed to check lock screen: {}", e),
+            }
+            self.process = None;
+        }
+
+        if self.locked {
+            if let Some(session) = &self.session {
+                self.process = spawn(session);
+            }
+        }
+    }
+
+    pub fn locked(&self) -> bool {
+        self.locked
+    }
+}
+
+/// Start the lock screen for a session
+fn spawn(session: &str) -> Option<Child> {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            tracing::error!("Cannot find the shell binary for the lock screen: {}", e);
+            return None;
+        }
+    };
+
+    match Command::new(exe).arg("--lock").arg(session).spawn() {
+        Ok(child) => Some(child),
+        Err(e) => {
+            tracing::error!("Failed to start lock screen: {}", e);
+            None
+        }
+    }
+}
+
+/// What the lock screen shows of notifications that arrived while locked,
+/// newest first, as a title and optional detail per line
+pub fn notification_lines(
+    policy: LockNotifications,
+    received: &[Notification],
+) -> Vec<(String, Option<String>)> {
+    if received.is_empty() {
+        return Vec::new();
+    }
+
+    let mut lines = match policy {
+        LockNotifications::Hidden => Vec::new(),
+        LockNotifications::Count => {
+            let noun = if received.len() == 1 { "notification" } else { "notifications" };
+            vec![(format!("{} {}", received.len(), noun), None)]
+        }
+        LockNotifications::AppNames => {
+            let mut apps: Vec<(&str, usize)> = Vec::new();
+            for notification in received.iter().rev() {
+                match apps.iter_mut().find(|(app, _)| *app == notification.app_name) {
+                    Some((_, count)) => *count += 1,
+                    None => apps.push((notification.app_name.as_str(), 1)),
+                }
+            }
+            apps.into_iter()
+                .map(|(app, count)| match count {
+                    1 => (app.to_string(), None),
+                    n => (app.to_string(), Some(format!("{} notifications", n))),
+                })
+                .collect()
+        }
+        LockNotifications::Full => received
+            .iter()
+            .rev()
+            .map(|n| (format!("{}: {}", n.app_name, n.summary), n.body.clone()))
+            .collect(),
+    };
+    lines.truncate(MAX_NOTIFICATION_LINES);
+    lines
+}
+
+#[cfg(feature = "wayland")]
+pub use screen::LockScreen;
+
+#[cfg(feature = "wayland")]
+mod screen {
+    use super::notification_lines;
+    use crate::config::ShellConfig;
+    use crate::notifications::{self, HeraldUpdate};
+    use iced::widget::{column, container, text, text_input, Column};
+    use iced::{Element, Length, Subscription, Task as Command, Theme};
+    use iced_sessionlock::{to_session_message, MultiApplication};
+    use libnyx_ipc::notifications::{Notification, NotificationEvent};
+    use libnyx_ipc::session::SessionEvent;
+    use libnyx_ipc::SessionClient;
+    use nyx_theme::colors::NyxColors;
+    use nyx_theme::spacing::Spacing;
+    use nyx_theme::widgets::input::{input_style, InputVariant};
+    use nyx_theme::widgets::panel::notification_style;
+    use std::time::Duration;
+
+    /// Width of the password field and notification list
+    const LOCK_WIDTH: f32 = 320.0;
+
+    fn password_id() -> text_input::Id {
+        text_input::Id::new("lock-password")
+    }
+
+    #[to_session_message]
+    #[derive(Debug, Clone)]
+    pub enum LockScreenMessage {
+        Tick,
+        PasswordChanged(String),
+        Submit,
+        Checked(Result<(), String>),
+        Herald(HeraldUpdate),
+        Session(SessionEvent),
+    }
+
+    /// The lock screen shown on every output
+    pub struct LockScreen {
+        session: String,
+        config: ShellConfig,
+        password: String,
+        checking: bool,
+        error: Option<String>,
+        /// Notifications that arrived while locked, oldest first
+        received: Vec<Notification>,
+    }
+
+    impl MultiApplication for LockScreen {
+        type Message = LockScreenMessage;
+        type Flags = String;
+        type Theme = Theme;
+        type Executor = iced::executor::Default;
+
+        fn new(session: String) -> (Self, Command<LockScreenMessage>) {
+            let screen = Self {
+                session,
+                config: ShellConfig::load(),
+                password: String::new(),
+                checking: false,
+                error: None,
+                received: Vec::new(),
+            };
+            (screen, text_input::focus(password_id()))
+        }
+
+        fn namespace(&self) -> String {
+            String::from("Nyx Lock Screen")
+        }
+
+        fn theme(&self) -> Theme {
+            nyx_theme::dark_theme()
+        }
+
+        fn update(&mut self, message: LockScreenMessage) -> Command<LockScreenMessage> {
+            match message {
+                LockScreenMessage::Tick => {}
+
+                LockScreenMessage::PasswordChanged(password) => {
+                    self.password = password;
+                    self.error = None;
+                }
+
+                LockScreenMessage::Submit => {
+                    if self.checking || self.password.is_empty() {
+                        return Command::none();
+                    }
+                    self.checking = true;
+                    let session = self.session.clone();
+                    let password = std::mem::take(&mut self.password);
+                    return Command::perform(
+                        async move { SessionClient::new().unlock(&session, &password).await },
+                        |result| LockScreenMessage::Checked(result.map_err(|e| e.to_string())),
+                    );
+                }
+
+                LockScreenMessage::Checked(Ok(())) => {
+                    return Command::done(LockScreenMessage::UnLock);
+                }
+
+                LockScreenMessage::Checked(Err(e)) => {
+                    self.checking = false;
+                    self.error = Some(e);
+                    return text_input::focus(password_id());
+                }
+
+                LockScreenMessage::Herald(HeraldUpdate::Event(NotificationEvent::Posted {
+                    notification,
+                })) => {
+                    self.received.retain(|n| n.id != notification.id);
+                    self.received.push(notification);
+                }
+
+                // Only what arrives while locked is shown
+                LockScreenMessage::Herald(_) => {}
+
+                // Unlocked some other way, e.g. by an administrator
+                LockScreenMessage::Session(SessionEvent::Unlocked { session })
+                    if session == self.session =>
+                {
+                    return Command::done(LockScreenMessage::UnLock);
+                }
+
+                LockScreenMessage::Session(_) => {}
+
+                LockScreenMessage::UnLock => {
+                    return Command::done(LockScreenMessage::UnLock);
+                }
+            }
+
+            Command::none()
+        }
+
+        fn subscription(&self) -> Subscription<LockScreenMessage> {
+            let tick = iced::time::every(Duration::from_secs(1)).map(|_| LockScreenMessage::Tick);
+            let herald = notifications::subscription().map(LockScreenMessage::Herald);
+            let sessions = super::subscription().map(LockScreenMessage::Session);
+            Subscription::batch([tick, herald, sessions])
+        }
+
+        fn view(&self, _window: iced::window::Id) -> Element<LockScreenMessage> {
+            let mut content = Column::new()
+                .spacing(Spacing::LG)
+                .align_x(iced::Alignment::Center)
+                .width(Length::Fixed(LOCK_WIDTH));
+
+            if self.config.lock.show_clock {
+                let now = chrono::Local::now();
+                let time = if self.config.panel.clock_24h {
+                    now.format("%H:%M")
+                } else {
+                    now.format("%I:%M %p")
+                };
+                content = content.push(
+                    column![
+                        text(time.to_string())
+                            .size(nyx_theme::Typography::SIZE_DISPLAY_LARGE)
+                            .color(NyxColors::TEXT_BRIGHT),
+                        text(now.format("%A, %B %d").to_string())
+                            .size(nyx_theme::Typography::SIZE_TITLE_LARGE)
+                            .color(NyxColors::TEXT_SECONDARY),
+                    ]
+                    .spacing(Spacing::XS)
+                    .align_x(iced::Alignment::Center),
+                );
+            }
+
+            let mut password = text_input("Password", &self.password)
+                .id(password_id())
+                .secure(true)
+                .padding(Spacing::SM)
+                .size(nyx_theme::Typography::SIZE_BODY_LARGE)
+                .style(input_style(InputVariant::Filled));
+            if !self.checking {
+                password = password
+                    .on_input(LockScreenMessage::PasswordChanged)
+                    .on_submit(LockScreenMessage::Submit);
+            }
+            content = content.push(password);
+
+            let status = match (&self.error, self.checking) {
+                (_, true) => Some(text("Checking…").color(NyxColors::TEXT_SECONDARY)),
+                (Some(e), false) => Some(text(e.as_str()).color(NyxColors::ERROR)),
+                (None, false) => None,
+            };
+            if let Some(status) = status {
+                content = content.push(status.size(nyx_theme::Typography::SIZE_BODY_SMALL));
+            }
+
+            let lines = notification_lines(self.config.lock.notifications, &self.received);
+            if !lines.is_empty() {
+                let lines = lines.into_iter().map(|(title, detail)| {
+                    let title = text(title)
+                        .size(nyx_theme::Typography::SIZE_TITLE_SMALL)
+                        .color(NyxColors::TEXT_BRIGHT);
+                    let line: Element<LockScreenMessage> = match detail {
+                        Some(detail) => column![
+                            title,
+                            text(detail)
+                                .size(nyx_theme::Typography::SIZE_BODY_SMALL)
+                                .color(NyxColors::TEXT_SECONDARY),
+                        ]
+                        .spacing(Spacing::XXS)
+                        .into(),
+                        None => title.into(),
+                    };
+                    container(line)
+                        .width(Length::Fill)
+                        .padding(Spacing::SM)
+                        .style(notification_style())
+                        .into()
+                });
+                content = content.push(Column::with_children(lines).spacing(Spacing::XS));
+            }
+
+            container(content)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(iced::alignment::Horizontal::Center)
+                .align_y(iced::alignment::Vertical::Center)
+                .style(|_theme| container::Style {
+                    background: Some(iced::Background::Color(NyxColors::VOID)),
+                    ..Default::default()
+                })
+                .into()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libnyx_ipc::notifications::Urgency;
+
+    fn posted(app_name: &str, summary: &str) -> Notification {
+        Notification {
+            id: 0,
+            app_name: app_name.into(),
+            summary: summary.into(),
+            body: Some("Private".into()),
+            icon: None,
+            urgency: Urgency::Normal,
+            actions: Vec::new(),
+            resident: false,
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn test_notification_policy() {
+        let received = [
+            posted("mail", "From Ada"),
+            posted("chat", "Lunch?"),
+            posted("mail", "From Grace"),
+        ];
+
+        assert!(notification_lines(LockNotifications::Hidden, &received).is_empty());
+        assert_eq!(
+            notification_lines(LockNotifications::Count, &received),
+            vec![("3 notifications".to_string(), None)]
+        );
+
+        // App names only: nothing of the content leaks
+        assert_eq!(
+            notification_lines(LockNotifications::AppNames, &received),
+            vec![
+                ("mail".to_string(), Some("2 notifications".to_string())),
+                ("chat".to_string(), None),
+            ]
+        );
+
+        let full = notification_lines(LockNotifications::Full, &received);
+        assert_eq!(full[0], ("mail: From Grace".to_string(), Some("Private".to_string())));
+        assert_eq!(full.len(), 3);
+    }
+
+    #[test]
+    fn test_guard_ignores_other_sessions() {
+        let mut guard = LockGuard::new(Some("3".into()));
+        guard.handle(SessionEvent::Locked { session: "4".into() });
+        assert!(!guard.locked());
+
+        let mut guard = LockGuard::new(None);
+        guard.handle(SessionEvent::Locked { session: "3".into() });
+        assert!(!guard.locked());
+    }
+}
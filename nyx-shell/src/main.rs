@@ -2,9 +2,13 @@
 //!
 //! The main desktop shell providing:
 //! - Top panel with system tray, clock, and quick settings
-//! - Bottom dock for application launching
+//! - Bottom dock for application launching and running windows
+//! - Alt+Tab window switcher
 //! - Workspace management
 //! - Window overview (Activities)
+//! - Notification popups and notification center
+//! - Wallpapers per output and workspace, with slideshows
+//! - Lock screen (`nyx-shell --lock <session>`, started by the shell)
 
 mod app;
 mod config;
@@ -13,6 +17,16 @@ mod dock;
 mod workspace;
 mod system;
 mod messages;
+mod windows;
+mod overview;
+mod notifications;
+mod lock;
+mod osd;
+mod wallpaper;
+#[cfg(feature = "wayland")]
+mod toplevel;
+#[cfg(feature = "wayland")]
+mod gestures;
 
 use app::NyxShell;
 use iced::Application;
@@ -28,6 +42,14 @@ fn main() -> iced::Result {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
+    // The shell starts a lock screen process when spectre locks the session
+    let args: Vec<String> = std::env::args().collect();
+    if let [_, flag, session] = args.as_slice() {
+        if flag == "--lock" {
+            run_lock_screen(session.clone());
+        }
+    }
+
     tracing::info!("Starting Nyx Shell");
 
     // Run the shell
@@ -44,3 +66,29 @@ fn main() -> iced::Result {
         ..Default::default()
     })
 }
+
+#[cfg(feature = "wayland")]
+fn run_lock_screen(session: String) -> ! {
+    use iced_sessionlock::MultiApplication;
+
+    tracing::info!("Locking session {}", session);
+    let result = lock::LockScreen::run(iced_sessionlock::settings::Settings {
+        flags: session,
+        antialiasing: true,
+        ..Default::default()
+    });
+
+    match result {
+        Ok(()) => std::process::exit(0),
+        Err(e) => {
+            tracing::error!("Lock screen failed: {}", e);
+            std::process::exit(1)
+        }
+    }
+}
+
+#[cfg(not(feature = "wayland"))]
+fn run_lock_screen(_session: String) -> ! {
+    tracing::error!("Lock screen needs the wayland feature");
+    std::process::exit(1)
+}
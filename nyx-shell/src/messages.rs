@@ -1,5 +1,12 @@
 //! Message types for Nyx Shell
 
+use crate::notifications::{HeraldUpdate, NotificationId};
+use libnyx_ipc::audio::OsdEvent;
+use libnyx_ipc::session::SessionEvent;
+use nyx_theme::NyxTheme;
+use std::path::PathBuf;
+use crate::overview::{CompositorWindowId, GestureEvent, OverviewEvent};
+use crate::windows::{WindowEvent, WindowId};
 use crate::workspace::WorkspaceId;
 
 /// Main shell messages
@@ -20,6 +27,27 @@ pub enum Message {
     /// System events
     System(SystemMessage),
 
+    /// Window list updates from the compositor
+    Windows(WindowEvent),
+
+    /// Alt+Tab switcher messages
+    Switcher(SwitcherMessage),
+
+    /// Activities overview messages
+    Overview(OverviewMessage),
+
+    /// Notification popups and center messages
+    Notifications(NotificationMessage),
+
+    /// Lock screen messages
+    Lock(LockMessage),
+
+    /// Volume key press reported by vesper
+    Osd(OsdEvent),
+
+    /// Wallpaper messages
+    Wallpaper(WallpaperMessage),
+
     /// Toggle control center visibility
     ToggleControlCenter,
 
@@ -70,6 +98,89 @@ pub enum DockMessage {
     FocusApp(String),
     /// Close app
     CloseApp(String),
+    /// Dismiss the app's window menu
+    CloseMenu,
+    /// Focus one window
+    FocusWindow(WindowId),
+    /// Minimize or restore one window
+    ToggleMinimized(WindowId),
+    /// Close one window
+    CloseWindow(WindowId),
+}
+
+/// Alt+Tab switcher messages
+#[derive(Debug, Clone)]
+pub enum SwitcherMessage {
+    /// Alt+Tab: open, or move to the next window
+    Next,
+    /// Alt+Shift+Tab: open, or move to the previous window
+    Previous,
+    /// Window clicked in the switcher
+    Select(WindowId),
+    /// Alt released: switch to the selection
+    Commit,
+    /// Escape: close without switching
+    Cancel,
+}
+
+/// Activities overview messages
+#[derive(Debug, Clone)]
+pub enum OverviewMessage {
+    /// Window list or thumbnail from the compositor
+    Compositor(OverviewEvent),
+    /// Pointer pressed on a window: dragged until released
+    Press(CompositorWindowId),
+    /// Pointer entered a workspace
+    Hover(WorkspaceId),
+    /// Pointer left a workspace
+    Unhover,
+    /// Pointer released anywhere
+    Release,
+    /// Pointer moved, for the hot corner
+    PointerMoved(iced::Point),
+    /// Touchpad swipe progress
+    Gesture(GestureEvent),
+    /// The compositor answered a move or focus request
+    Done(Result<(), String>),
+}
+
+/// Notification popup and center messages
+#[derive(Debug, Clone)]
+pub enum NotificationMessage {
+    /// History or an event from herald
+    Herald(HeraldUpdate),
+    /// Notification button pressed
+    Invoke(NotificationId, String),
+    /// Popup closed
+    Dismiss(NotificationId),
+    /// Clear one app's notifications from the center
+    ClearApp(String),
+    /// Do Not Disturb switched in the center
+    SetDnd(bool),
+    /// Show or hide the notification center
+    ToggleCenter,
+    /// Herald answered a request
+    Done(Result<(), String>),
+}
+
+/// Lock screen messages
+#[derive(Debug, Clone)]
+pub enum LockMessage {
+    /// A session was locked or unlocked
+    Session(SessionEvent),
+    /// Lock this session (Super+L)
+    Request,
+    /// Spectre answered the lock request
+    Done(Result<(), String>),
+}
+
+/// Wallpaper messages
+#[derive(Debug, Clone)]
+pub enum WallpaperMessage {
+    /// The output the shell covers, as aether named it
+    Output(Result<Option<String>, String>),
+    /// The theme with the accent of a wallpaper
+    Themed(PathBuf, Result<NyxTheme, String>),
 }
 
 /// Workspace-specific messages
@@ -102,10 +213,6 @@ pub enum SystemMessage {
     BluetoothUpdate(BluetoothStatus),
     /// Power profile update
     PowerProfileUpdate(PowerProfile),
-    /// New notification
-    NotificationReceived(Notification),
-    /// Notification dismissed
-    NotificationDismissed(String),
 }
 
 /// Battery status
@@ -187,8 +294,8 @@ pub enum PowerProfile {
 /// Notification
 #[derive(Debug, Clone)]
 pub struct Notification {
-    /// Unique ID
-    pub id: String,
+    /// Herald's ID
+    pub id: NotificationId,
     /// Application name
     pub app_name: String,
     /// Summary/title
@@ -201,7 +308,7 @@ pub struct Notification {
     pub urgency: NotificationUrgency,
     /// Timestamp
     pub timestamp: chrono::DateTime<chrono::Local>,
-    /// Actions available
+    /// Actions available, as (id, label)
     pub actions: Vec<(String, String)>,
 }
 
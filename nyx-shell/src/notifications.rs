@@ -0,0 +1,375 @@
+//! Notification popups and the notification center
+//!
+//! Both are drawn from herald's event stream. A posted notification pops
+//! up in the top-right corner for a few seconds (critical ones stay until
+//! dismissed) and is listed in the notification center, grouped per app,
+//! until that app's notifications are cleared. A close from herald, whether
+//! expiry, another client or an invoked action, takes the popup down.
+//! Buttons, dismissals and the Do Not Disturb switch go back to herald.
+
+use crate::messages::{Notification, NotificationUrgency};
+use iced::futures::SinkExt;
+use iced::Subscription;
+use libnyx_ipc::notifications::{self as herald, HistoryEntry, NotificationEvent};
+use libnyx_ipc::{paths, HelloClient, NotificationsClient};
+use std::time::{Duration, Instant};
+
+/// Herald's notification ID
+pub type NotificationId = u32;
+
+/// Notifications fetched into the center when herald is reached
+const HISTORY_LIMIT: usize = 100;
+
+/// Wait before reconnecting to herald
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Updates from herald
+#[derive(Debug, Clone)]
+pub enum HeraldUpdate {
+    /// Connected; recent notifications for the center
+    History(Vec<HistoryEntry>),
+    /// Pushed by herald
+    Event(NotificationEvent),
+    /// Herald is not running or went away
+    Unavailable(String),
+}
+
+/// A notification on screen
+#[derive(Debug, Clone)]
+struct Popup {
+    notification: Notification,
+    shown: Instant,
+}
+
+/// Popups, history and Do Not Disturb as herald reports them
+#[derive(Debug)]
+pub struct Notifications {
+    popups: Vec<Popup>,
+    /// Newest first
+    history: Vec<Notification>,
+    dnd: bool,
+    popup_timeout: Duration,
+    max_popups: usize,
+}
+
+impl Notifications {
+    pub fn new(popup_timeout: Duration, max_popups: usize) -> Self {
+        Self {
+            popups: Vec::new(),
+            history: Vec::new(),
+            dnd: false,
+            popup_timeout,
+            max_popups: max_popups.max(1),
+        }
+    }
+
+    /// Apply an update from herald
+    pub fn update(&mut self, update: HeraldUpdate, now: Instant) {
+        match update {
+            HeraldUpdate::History(entries) => {
+                self.history = entries.into_iter().map(Notification::from).collect();
+            }
+            HeraldUpdate::Event(NotificationEvent::Posted { notification }) => {
+                self.post(notification.into(), now);
+            }
+            HeraldUpdate::Event(NotificationEvent::Closed { id, .. }) => {
+                self.popups.retain(|p| p.notification.id != id);
+            }
+            HeraldUpdate::Event(NotificationEvent::Dnd { active }) => {
+                self.dnd = active;
+            }
+            HeraldUpdate::Unavailable(_) => {
+                self.popups.clear();
+            }
+        }
+    }
+
+    /// Show a notification, replacing one with the same ID in place
+    fn post(&mut self, notification: Notification, now: Instant) {
+        match self.history.iter_mut().find(|n| n.id == notification.id) {
+            Some(entry) => *entry = notification.clone(),
+            None => self.history.insert(0, notification.clone()),
+        }
+
+        match self.popups.iter_mut().find(|p| p.notification.id == notification.id) {
+            Some(popup) => {
+                popup.notification = notification;
+                popup.shown = now;
+            }
+            None => {
+                self.popups.push(Popup { notification, shown: now });
+                // Make room by dropping the oldest, critical ones last
+                while self.popups.len() > self.max_popups {
+                    let oldest = self
+                        .popups
+                        .iter()
+                        .position(|p| p.notification.urgency != NotificationUrgency::Critical)
+                        .unwrap_or(0);
+                    self.popups.remove(oldest);
+                }
+            }
+        }
+    }
+
+    /// Take down popups that were up long enough
+    pub fn expire(&mut self, now: Instant) {
+        let timeout = self.popup_timeout;
+        self.popups.retain(|p| {
+            p.notification.urgency == NotificationUrgency::Critical
+                || now.duration_since(p.shown) < timeout
+        });
+    }
+
+    /// Take down a popup without waiting for herald
+    pub fn remove_popup(&mut self, id: NotificationId) {
+        self.popups.retain(|p| p.notification.id != id);
+    }
+
+    /// Forget an app's notifications
+    pub fn clear_app(&mut self, app_name: &str) {
+        self.history.retain(|n| n.app_name != app_name);
+        self.popups.retain(|p| p.notification.app_name != app_name);
+    }
+
+    /// Popups, oldest first
+    pub fn popups(&self) -> impl Iterator<Item = &Notification> {
+        self.popups.iter().map(|p| &p.notification)
+    }
+
+    /// History grouped per app, the app with the newest notification first
+    pub fn groups(&self) -> Vec<(&str, Vec<&Notification>)> {
+        let mut groups: Vec<(&str, Vec<&Notification>)> = Vec::new();
+        for notification in &self.history {
+            match groups.iter_mut().find(|(app, _)| *app == notification.app_name) {
+                Some((_, entries)) => entries.push(notification),
+                None => groups.push((notification.app_name.as_str(), vec![notification])),
+            }
+        }
+        groups
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.history.is_empty()
+    }
+
+    pub fn dnd(&self) -> bool {
+        self.dnd
+    }
+}
+
+impl From<herald::Notification> for Notification {
+    fn from(n: herald::Notification) -> Self {
+        Self {
+            id: n.id,
+            app_name: n.app_name,
+            summary: n.summary,
+            body: n.body,
+            icon: n.icon,
+            urgency: match n.urgency {
+                herald::Urgency::Low => NotificationUrgency::Low,
+                herald::Urgency::Normal => NotificationUrgency::Normal,
+                herald::Urgency::Critical => NotificationUrgency::Critical,
+            },
+            timestamp: local_time(n.timestamp),
+            actions: n.actions.into_iter().map(|a| (a.id, a.label)).collect(),
+        }
+    }
+}
+
+impl From<HistoryEntry> for Notification {
+    fn from(entry: HistoryEntry) -> Self {
+        Self {
+            id: entry.id,
+            app_name: entry.app_name,
+            summary: entry.summary,
+            body: entry.body,
+            icon: None,
+            urgency: NotificationUrgency::default(),
+            timestamp: local_time(entry.timestamp),
+            // Buttons only work while the notification is up
+            actions: Vec::new(),
+        }
+    }
+}
+
+/// Unix seconds from herald in local time
+fn local_time(secs: u64) -> chrono::DateTime<chrono::Local> {
+    chrono::DateTime::from_timestamp(secs as i64, 0)
+        .map(|t| t.with_timezone(&chrono::Local))
+        .unwrap_or_else(chrono::Local::now)
+}
+
+/// Press a notification's button
+pub async fn invoke_action(id: NotificationId, action_id: String) -> libnyx_ipc::Result<()> {
+    NotificationsClient::new().invoke_action(id, &action_id).await
+}
+
+/// Dismiss a notification
+pub async fn dismiss(id: NotificationId) -> libnyx_ipc::Result<()> {
+    NotificationsClient::new().close(id).await
+}
+
+/// Forget an app's notifications in herald's history
+pub async fn clear_app(app_name: String) -> libnyx_ipc::Result<()> {
+    NotificationsClient::new().clear_app(&app_name).await
+}
+
+/// Turn Do Not Disturb on or off
+pub async fn set_dnd(enabled: bool) -> libnyx_ipc::Result<()> {
+    NotificationsClient::new().set_dnd(enabled).await
+}
+
+/// Herald's history on connect, then its events, reconnecting when it
+/// restarts; polls the history from a herald without events
+pub fn subscription() -> Subscription<HeraldUpdate> {
+    iced::subscription::channel("herald-notifications", 64, |mut output| async move {
+        let client = NotificationsClient::new();
+
+        loop {
+            // A herald without the event stream still keeps the center
+            // filled from its history; popups wait for an upgrade
+            match HelloClient::new(paths::HERALD_SOCKET).hello().await {
+                Ok(peer) if !peer.supports("subscribe") => {
+                    match client.history(HISTORY_LIMIT).await {
+                        Ok(history) => {
+                            let _ = output.send(HeraldUpdate::History(history)).await;
+                        }
+                        Err(e) => {
+                            let _ = output.send(HeraldUpdate::Unavailable(e.to_string())).await;
+                        }
+                    }
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                    continue;
+                }
+                Err(e @ libnyx_ipc::Error::Unsupported(_)) => {
+                    let _ = output.send(HeraldUpdate::Unavailable(e.to_string())).await;
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                    continue;
+                }
+                _ => {}
+            }
+
+            let mut events = match client.subscribe().await {
+                Ok(events) => events,
+                Err(e) => {
+                    let _ = output.send(HeraldUpdate::Unavailable(e.to_string())).await;
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                    continue;
+                }
+            };
+
+            match client.history(HISTORY_LIMIT).await {
+                Ok(history) => {
+                    let _ = output.send(HeraldUpdate::History(history)).await;
+                }
+                Err(e) => tracing::warn!("Failed to read notification history: {}", e),
+            }
+
+            let reason = loop {
+                match events.next().await {
+                    Ok(event) => {
+                        let _ = output.send(HeraldUpdate::Event(event)).await;
+                    }
+                    Err(e) => break e.to_string(),
+                }
+            };
+            let _ = output.send(HeraldUpdate::Unavailable(reason)).await;
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libnyx_ipc::notifications::NotificationAction;
+
+    fn posted(id: NotificationId, app_name: &str, urgency: herald::Urgency) -> HeraldUpdate {
+        HeraldUpdate::Event(NotificationEvent::Posted {
+            notification: herald::Notification {
+                id,
+                app_name: app_name.to_string(),
+                summary: format!("Notification {}", id),
+                body: None,
+                icon: None,
+                urgency,
+                actions: vec![NotificationAction {
+                    id: "open".into(),
+                    label: "Open".into(),
+                }],
+                resident: false,
+                timestamp: 1_700_000_000,
+            },
+        })
+    }
+
+    fn popup_ids(notifications: &Notifications) -> Vec<NotificationId> {
+        notifications.popups().map(|n| n.id).collect()
+    }
+
+    #[test]
+    fn test_popups_expire_and_close() {
+        let start = Instant::now();
+        let mut notifications = Notifications::new(Duration::from_secs(5), 3);
+        notifications.update(posted(1, "mail", herald::Urgency::Normal), start);
+        notifications.update(posted(2, "power", herald::Urgency::Critical), start);
+        notifications.update(posted(3, "chat", herald::Urgency::Normal), start);
+
+        notifications.update(
+            HeraldUpdate::Event(NotificationEvent::Closed {
+                id: 3,
+                reason: "closed".into(),
+            }),
+            start,
+        );
+        assert_eq!(popup_ids(&notifications), vec![1, 2]);
+
+        // Critical popups wait to be dismissed; everything stays in the center
+        notifications.expire(start + Duration::from_secs(6));
+        assert_eq!(popup_ids(&notifications), vec![2]);
+        assert_eq!(notifications.groups().len(), 3);
+    }
+
+    #[test]
+    fn test_replace_and_overflow() {
+        let start = Instant::now();
+        let mut notifications = Notifications::new(Duration::from_secs(5), 2);
+        notifications.update(posted(1, "power", herald::Urgency::Critical), start);
+        notifications.update(posted(2, "mail", herald::Urgency::Normal), start);
+        notifications.update(posted(3, "mail", herald::Urgency::Normal), start);
+        assert_eq!(popup_ids(&notifications), vec![1, 3]);
+
+        // A progress update keeps its place and restarts its timeout
+        notifications.update(posted(3, "mail", herald::Urgency::Normal), start + Duration::from_secs(4));
+        notifications.expire(start + Duration::from_secs(6));
+        assert_eq!(popup_ids(&notifications), vec![1, 3]);
+        assert_eq!(notifications.groups()[0].1.len(), 2);
+    }
+
+    #[test]
+    fn test_groups_and_clear_app() {
+        let start = Instant::now();
+        let mut notifications = Notifications::new(Duration::from_secs(5), 3);
+        notifications.update(
+            HeraldUpdate::History(vec![HistoryEntry {
+                id: 1,
+                app_name: "mail".into(),
+                summary: "Old".into(),
+                body: None,
+                timestamp: 1_600_000_000,
+                closed_at: Some(1_600_000_005),
+            }]),
+            start,
+        );
+        notifications.update(posted(2, "chat", herald::Urgency::Normal), start);
+        notifications.update(posted(3, "mail", herald::Urgency::Normal), start);
+
+        let groups = notifications.groups();
+        let apps: Vec<_> = groups.iter().map(|(app, entries)| (*app, entries.len())).collect();
+        assert_eq!(apps, vec![("mail", 2), ("chat", 1)]);
+
+        notifications.clear_app("mail");
+        assert_eq!(popup_ids(&notifications), vec![2]);
+        assert_eq!(notifications.groups().len(), 1);
+    }
+}
@@ -0,0 +1,129 @@
+//! On-screen display for the volume keys
+//!
+//! Vesper handles the volume and mute keys itself and pushes an event per
+//! press; the shell shows the new level near the bottom of the screen for
+//! a moment, and keeps the panel's volume icon in step with it.
+
+use crate::messages::AudioStatus;
+use iced::futures::SinkExt;
+use iced::Subscription;
+use libnyx_ipc::audio::OsdEvent;
+use libnyx_ipc::AudioClient;
+use std::time::{Duration, Instant};
+
+/// Wait before reconnecting to vesper
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// How long the display stays up after the last press
+const OSD_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Vesper's volume key events, reconnecting when it restarts
+pub fn subscription() -> Subscription<OsdEvent> {
+    iced::subscription::channel("vesper-osd", 16, |mut output| async move {
+        let client = AudioClient::new();
+
+        loop {
+            match client.subscribe_osd().await {
+                Ok(mut events) => loop {
+                    match events.next().await {
+                        Ok(event) => {
+                            let _ = output.send(event).await;
+                        }
+                        Err(e) => {
+                            tracing::debug!("Vesper OSD stream ended: {}", e);
+                            break;
+                        }
+                    }
+                },
+                Err(e) => tracing::debug!("Vesper unavailable: {}", e),
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    })
+}
+
+/// The last key press, while it is on screen
+#[derive(Debug, Default)]
+pub struct Osd {
+    current: Option<(OsdEvent, Instant)>,
+}
+
+impl Osd {
+    /// Show an event, replacing what is up, and reflect it in `audio`
+    pub fn show(&mut self, event: OsdEvent, audio: &mut AudioStatus, now: Instant) {
+        match &event {
+            OsdEvent::Volume { description, volume, muted, .. } => {
+                audio.volume = (*volume).min(u8::MAX as u32) as u8;
+                audio.muted = *muted;
+                audio.output_device = Some(description.clone());
+            }
+            OsdEvent::MicMute { muted, .. } => audio.mic_muted = *muted,
+        }
+        self.current = Some((event, now));
+    }
+
+    /// Take the display down once it was up long enough
+    pub fn expire(&mut self, now: Instant) {
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|(_, shown)| now.duration_since(*shown) >= OSD_TIMEOUT)
+        {
+            self.current = None;
+        }
+    }
+
+    /// What to show, if anything
+    pub fn current(&self) -> Option<&OsdEvent> {
+        self.current.as_ref().map(|(event, _)| event)
+    }
+}
+
+/// Icon for a volume level
+pub fn volume_icon(volume: u32, muted: bool) -> &'static str {
+    if muted || volume == 0 {
+        "󰖁"
+    } else if volume < 33 {
+        "󰕿"
+    } else if volume < 66 {
+        "󰖀"
+    } else {
+        "󰕾"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_show_and_expire() {
+        let mut osd = Osd::default();
+        let mut audio = AudioStatus::default();
+        let start = Instant::now();
+
+        osd.show(
+            OsdEvent::Volume {
+                sink: "headphones".into(),
+                description: "USB Headphones".into(),
+                volume: 45,
+                muted: false,
+                max_volume: 80,
+                limited: false,
+            },
+            &mut audio,
+            start,
+        );
+        assert_eq!(audio.volume, 45);
+        assert_eq!(audio.output_device.as_deref(), Some("USB Headphones"));
+
+        osd.show(OsdEvent::MicMute { source: "mic".into(), muted: true }, &mut audio, start + Duration::from_secs(1));
+        assert!(audio.mic_muted);
+
+        // Counted from the latest press
+        osd.expire(start + OSD_TIMEOUT);
+        assert!(osd.current().is_some());
+        osd.expire(start + Duration::from_secs(1) + OSD_TIMEOUT);
+        assert!(osd.current().is_none());
+    }
+}
@@ -0,0 +1,593 @@
+//! Activities overview
+//!
+//! Shows every workspace side by side with live thumbnails of its windows.
+//! The window list, with each window's workspace, comes from aether's
+//! control socket; thumbnails are frames of a capture session per window,
+//! so Guardian has to allow the shell to capture before any appear and
+//! herald shows the capture indicator while the overview is open. Windows
+//! are dragged onto another workspace to move them there, or clicked to
+//! switch to them. The overview opens from the panel, the top-left hot
+//! corner or a multi-finger touchpad swipe (see `gestures`).
+
+use crate::workspace::WorkspaceId;
+use iced::futures::SinkExt;
+use iced::widget::image;
+use iced::{Point, Subscription};
+use libnyx_ipc::compositor::{CompositorWindow, Frame};
+use libnyx_ipc::CompositorClient;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// Compositor window handle on the control socket (not the same as a
+/// foreign toplevel handle)
+pub type CompositorWindowId = u64;
+
+/// How often thumbnails are refreshed while the overview is open
+const THUMBNAIL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Widest thumbnail kept, in pixels; frames are scaled down to this
+pub const THUMBNAIL_WIDTH: u32 = 320;
+
+/// Size of the hot corner, in logical pixels from the top-left
+const HOT_CORNER_SIZE: f32 = 2.0;
+
+/// Swipe distance that counts as a gesture, in touchpad units
+const SWIPE_THRESHOLD: f64 = 80.0;
+
+/// Updates from the compositor while the overview is open
+#[derive(Debug, Clone)]
+pub enum OverviewEvent {
+    /// Current windows on every workspace
+    Windows(Vec<CompositorWindow>),
+    /// New thumbnail for a window
+    Thumbnail(CompositorWindowId, Frame),
+    /// The compositor's control socket is not answering
+    Unavailable(String),
+}
+
+/// Touchpad swipe progress from the compositor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GestureEvent {
+    /// Fingers went down
+    Begin { fingers: u32 },
+    /// Fingers moved
+    Update { dx: f64, dy: f64 },
+    /// Fingers lifted, or the compositor took the gesture over
+    End { cancelled: bool },
+}
+
+/// A window in the overview
+#[derive(Debug, Clone)]
+pub struct OverviewWindow {
+    pub id: CompositorWindowId,
+    pub title: String,
+    pub app_id: String,
+    pub workspace: WorkspaceId,
+    pub focused: bool,
+    /// Latest frame, once capture was allowed
+    pub thumbnail: Option<image::Handle>,
+}
+
+/// What releasing a dragged window does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropAction {
+    /// Released where it was picked up: switch to it
+    Activate(CompositorWindowId, WorkspaceId),
+    /// Released over another workspace: move it there
+    Move(CompositorWindowId, WorkspaceId),
+}
+
+/// Window being dragged
+#[derive(Debug, Clone, Copy)]
+struct Drag {
+    window: CompositorWindowId,
+    from: WorkspaceId,
+    over: Option<WorkspaceId>,
+}
+
+/// Overview state
+#[derive(Debug, Clone, Default)]
+pub struct Overview {
+    windows: Vec<OverviewWindow>,
+    drag: Option<Drag>,
+}
+
+impl Overview {
+    /// Create an empty overview
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the window list, keeping thumbnails of windows still open
+    pub fn set_windows(&mut self, windows: Vec<CompositorWindow>) {
+        let mut thumbnails: HashMap<_, _> = self
+            .windows
+            .drain(..)
+            .filter_map(|w| Some((w.id, w.thumbnail?)))
+            .collect();
+
+        self.windows = windows
+            .into_iter()
+            .map(|w| OverviewWindow {
+                id: w.id,
+                thumbnail: thumbnails.remove(&w.id),
+                title: w.title,
+                app_id: w.app_id.unwrap_or_default(),
+                workspace: w.workspace,
+                focused: w.focused,
+            })
+            .collect();
+
+        if let Some(drag) = self.drag {
+            if self.get(drag.window).is_none() {
+                self.drag = None;
+            }
+        }
+    }
+
+    /// Show a new frame for a window
+    pub fn set_thumbnail(&mut self, id: CompositorWindowId, frame: Frame) {
+        if let Some(window) = self.windows.iter_mut().find(|w| w.id == id) {
+            window.thumbnail = Some(image::Handle::from_rgba(frame.width, frame.height, frame.rgba));
+        }
+    }
+
+    /// Forget every window, e.g. when the compositor stops answering
+    pub fn clear(&mut self) {
+        self.windows.clear();
+        self.drag = None;
+    }
+
+    /// Get a window
+    pub fn get(&self, id: CompositorWindowId) -> Option<&OverviewWindow> {
+        self.windows.iter().find(|w| w.id == id)
+    }
+
+    /// Windows on one workspace
+    pub fn on_workspace(&self, workspace: WorkspaceId) -> impl Iterator<Item = &OverviewWindow> {
+        self.windows.iter().filter(move |w| w.workspace == workspace)
+    }
+
+    /// Move a window locally, ahead of the compositor confirming it
+    pub fn move_window(&mut self, id: CompositorWindowId, workspace: WorkspaceId) {
+        if let Some(window) = self.windows.iter_mut().find(|w| w.id == id) {
+            window.workspace = workspace;
+        }
+    }
+
+    /// Pointer pressed on a window: it is dragged until released
+    pub fn press(&mut self, id: CompositorWindowId) {
+        self.drag = self.get(id).map(|w| Drag {
+            window: id,
+            from: w.workspace,
+            over: Some(w.workspace),
+        });
+    }
+
+    /// Pointer entered or left a workspace while dragging
+    pub fn hover(&mut self, workspace: Option<WorkspaceId>) {
+        if let Some(drag) = &mut self.drag {
+            drag.over = workspace;
+        }
+    }
+
+    /// Pointer released: drop the dragged window, if any
+    ///
+    /// Releasing outside every workspace cancels the drag.
+    pub fn release(&mut self) -> Option<DropAction> {
+        let drag = self.drag.take()?;
+        match drag.over? {
+            over if over == drag.from => Some(DropAction::Activate(drag.window, over)),
+            over => Some(DropAction::Move(drag.window, over)),
+        }
+    }
+
+    /// Window being dragged
+    pub fn dragging(&self) -> Option<CompositorWindowId> {
+        self.drag.map(|d| d.window)
+    }
+
+    /// Workspace the dragged window would land on
+    pub fn drop_target(&self) -> Option<WorkspaceId> {
+        self.drag.and_then(|d| d.over)
+    }
+}
+
+/// Columns for a grid of `count` thumbnails: as square as possible
+pub fn grid_columns(count: usize) -> usize {
+    let mut columns = 1;
+    while columns * columns < count {
+        columns += 1;
+    }
+    columns
+}
+
+/// Scale a frame down to at most `max_width` pixels wide, sampling the
+/// nearest pixel; narrower frames are kept as they are
+pub fn downscale(frame: Frame, max_width: u32) -> Frame {
+    if frame.width <= max_width || frame.width == 0 {
+        return frame;
+    }
+    let width = max_width;
+    let height = ((frame.height as u64 * width as u64) / frame.width as u64).max(1) as u32;
+
+    let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height {
+        let src_y = (y as u64 * frame.height as u64 / height as u64) as usize;
+        for x in 0..width {
+            let src_x = (x as u64 * frame.width as u64 / width as u64) as usize;
+            let offset = (src_y * frame.width as usize + src_x) * 4;
+            rgba.extend_from_slice(&frame.rgba[offset..offset + 4]);
+        }
+    }
+
+    Frame {
+        width,
+        height,
+        rgba,
+    }
+}
+
+/// Opens the overview when the pointer is pushed into the top-left corner
+///
+/// Fires once per visit, so resting in the corner does not toggle the
+/// overview back and forth.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HotCorner {
+    inside: bool,
+}
+
+impl HotCorner {
+    /// Pointer moved; true when it just entered the corner
+    pub fn moved(&mut self, position: Point) -> bool {
+        let inside = position.x <= HOT_CORNER_SIZE && position.y <= HOT_CORNER_SIZE;
+        let entered = inside && !self.inside;
+        self.inside = inside;
+        entered
+    }
+}
+
+/// What a finished swipe asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwipeAction {
+    /// Swipe up
+    ShowOverview,
+    /// Swipe down
+    HideOverview,
+    /// Swipe left
+    NextWorkspace,
+    /// Swipe right
+    PreviousWorkspace,
+}
+
+/// Turns touchpad swipes with the configured finger count into actions
+#[derive(Debug, Clone, Copy)]
+pub struct SwipeTracker {
+    fingers: u32,
+    /// Distance moved by a swipe that has the right finger count
+    active: Option<(f64, f64)>,
+}
+
+impl SwipeTracker {
+    /// Track swipes made with `fingers` fingers
+    pub fn new(fingers: u32) -> Self {
+        Self {
+            fingers,
+            active: None,
+        }
+    }
+
+    /// Feed a gesture event; returns the action when a swipe completes
+    pub fn handle(&mut self, event: GestureEvent) -> Option<SwipeAction> {
+        match event {
+            GestureEvent::Begin { fingers } => {
+                self.active = (fingers == self.fingers).then_some((0.0, 0.0));
+                None
+            }
+            GestureEvent::Update { dx, dy } => {
+                if let Some((x, y)) = &mut self.active {
+                    *x += dx;
+                    *y += dy;
+                }
+                None
+            }
+            GestureEvent::End { cancelled } => {
+                let (dx, dy) = self.active.take()?;
+                if cancelled {
+                    return None;
+                }
+                swipe_action(dx, dy)
+            }
+        }
+    }
+}
+
+/// Dominant direction of a swipe, if it went far enough
+fn swipe_action(dx: f64, dy: f64) -> Option<SwipeAction> {
+    if dx.abs() > dy.abs() {
+        if dx <= -SWIPE_THRESHOLD {
+            Some(SwipeAction::NextWorkspace)
+        } else if dx >= SWIPE_THRESHOLD {
+            Some(SwipeAction::PreviousWorkspace)
+        } else {
+            None
+        }
+    } else if dy <= -SWIPE_THRESHOLD {
+        Some(SwipeAction::ShowOverview)
+    } else if dy >= SWIPE_THRESHOLD {
+        Some(SwipeAction::HideOverview)
+    } else {
+        None
+    }
+}
+
+/// Switch to a window picked in the overview
+pub async fn focus_window(id: CompositorWindowId) -> libnyx_ipc::Result<()> {
+    CompositorClient::new().focus_window(id).await
+}
+
+/// Move a window dropped on another workspace
+pub async fn move_window(id: CompositorWindowId, workspace: WorkspaceId) -> libnyx_ipc::Result<()> {
+    CompositorClient::new().move_to_workspace(id, workspace).await
+}
+
+/// Touchpad swipes from the compositor
+pub fn gesture_subscription() -> Subscription<GestureEvent> {
+    #[cfg(feature = "wayland")]
+    {
+        crate::gestures::subscription()
+    }
+    #[cfg(not(feature = "wayland"))]
+    {
+        Subscription::none()
+    }
+}
+
+/// Window list and thumbnails while the overview is open
+///
+/// Capture sessions are opened per window and ended when the window goes
+/// away or the overview closes, so the capture indicator does not outlive
+/// the overview.
+pub fn subscription() -> Subscription<OverviewEvent> {
+    iced::subscription::channel("overview-thumbnails", 32, |mut output| async move {
+        let client = CompositorClient::new();
+        let mut sessions = Sessions::default();
+        let mut interval = tokio::time::interval(THUMBNAIL_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let windows = match client.windows().await {
+                Ok(windows) => windows,
+                Err(e) => {
+                    let _ = output.send(OverviewEvent::Unavailable(e.to_string())).await;
+                    continue;
+                }
+            };
+            let open: HashSet<_> = windows.iter().map(|w| w.id).collect();
+            let _ = output.send(OverviewEvent::Windows(windows)).await;
+
+            sessions.retain_open(&client, &open).await;
+            for id in open {
+                if let Some(frame) = sessions.frame(&client, id).await {
+                    let _ = output
+                        .send(OverviewEvent::Thumbnail(id, downscale(frame, THUMBNAIL_WIDTH)))
+                        .await;
+                }
+            }
+        }
+    })
+}
+
+/// Capture sessions held by the overview
+#[derive(Default)]
+struct Sessions {
+    /// Session per window
+    active: HashMap<CompositorWindowId, u64>,
+    /// Windows Guardian refused; not asked about again until reopened
+    refused: HashSet<CompositorWindowId>,
+}
+
+impl Sessions {
+    /// End the sessions of windows that closed
+    async fn retain_open(&mut self, client: &CompositorClient, open: &HashSet<CompositorWindowId>) {
+        let closed: Vec<_> = self
+            .active
+            .keys()
+            .filter(|id| !open.contains(id))
+            .copied()
+            .collect();
+        for id in closed {
+            if let Some(session) = self.active.remove(&id) {
+                let _ = client.stop_capture(session).await;
+            }
+        }
+        self.refused.retain(|id| open.contains(id));
+    }
+
+    /// Next frame of a window, starting its session on first use
+    ///
+    /// None while Guardian is still deciding, or once it refused.
+    async fn frame(&mut self, client: &CompositorClient, id: CompositorWindowId) -> Option<Frame> {
+        if self.refused.contains(&id) {
+            return None;
+        }
+        let session = match self.active.get(&id) {
+            Some(session) => *session,
+            None => match client.capture_window(id).await {
+                Ok(session) => *self.active.entry(id).or_insert(session),
+                Err(e) => {
+                    tracing::debug!("Cannot capture window {}: {}", id, e);
+                    self.refused.insert(id);
+                    return None;
+                }
+            },
+        };
+
+        match client.capture_frame(session).await {
+            Ok(frame) => Some(frame),
+            Err(e) if e.to_string().contains("awaiting consent") => None,
+            Err(e) => {
+                tracing::debug!("Capture of window {} ended: {}", id, e);
+                self.active.remove(&id);
+                self.refused.insert(id);
+                None
+            }
+        }
+    }
+}
+
+impl Drop for Sessions {
+    fn drop(&mut self) {
+        let sessions: Vec<u64> = self.active.drain().map(|(_, session)| session).collect();
+        if sessions.is_empty() {
+            return;
+        }
+        // The overview closed; end capture without holding up the UI
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                let client = CompositorClient::new();
+                for session in sessions {
+                    let _ = client.stop_capture(session).await;
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(id: CompositorWindowId, workspace: WorkspaceId) -> CompositorWindow {
+        CompositorWindow {
+            id,
+            title: format!("window {}", id),
+            app_id: Some("umbra".into()),
+            x: 0,
+            y: 0,
+            width: 800,
+            height: 600,
+            state: "normal".into(),
+            focused: false,
+            workspace,
+        }
+    }
+
+    fn frame(width: u32, height: u32) -> Frame {
+        let rgba = (0..width * height)
+            .flat_map(|i| [i as u8, 0, 0, 255])
+            .collect();
+        Frame {
+            width,
+            height,
+            rgba,
+        }
+    }
+
+    #[test]
+    fn test_windows_by_workspace() {
+        let mut overview = Overview::new();
+        overview.set_windows(vec![window(1, 1), window(2, 2), window(3, 1)]);
+
+        let ids: Vec<_> = overview.on_workspace(1).map(|w| w.id).collect();
+        assert_eq!(ids, vec![1, 3]);
+        assert_eq!(overview.on_workspace(2).count(), 1);
+        assert_eq!(overview.on_workspace(3).count(), 0);
+    }
+
+    #[test]
+    fn test_thumbnails_survive_refresh() {
+        let mut overview = Overview::new();
+        overview.set_windows(vec![window(1, 1), window(2, 1)]);
+        overview.set_thumbnail(1, frame(2, 2));
+        assert!(overview.get(1).unwrap().thumbnail.is_some());
+
+        overview.set_windows(vec![window(1, 2)]);
+        let window = overview.get(1).unwrap();
+        assert!(window.thumbnail.is_some());
+        assert_eq!(window.workspace, 2);
+        assert!(overview.get(2).is_none());
+    }
+
+    #[test]
+    fn test_drag_to_workspace() {
+        let mut overview = Overview::new();
+        overview.set_windows(vec![window(1, 1), window(2, 2)]);
+        assert_eq!(overview.release(), None);
+
+        // Released where it was picked up: a click
+        overview.press(1);
+        assert_eq!(overview.release(), Some(DropAction::Activate(1, 1)));
+        assert_eq!(overview.dragging(), None);
+
+        overview.press(1);
+        overview.hover(Some(3));
+        assert_eq!(overview.drop_target(), Some(3));
+        assert_eq!(overview.release(), Some(DropAction::Move(1, 3)));
+
+        // Released outside every workspace
+        overview.press(2);
+        overview.hover(None);
+        assert_eq!(overview.release(), None);
+
+        // The dragged window closing ends the drag
+        overview.press(2);
+        overview.set_windows(vec![window(1, 1)]);
+        assert_eq!(overview.dragging(), None);
+    }
+
+    #[test]
+    fn test_grid_columns() {
+        assert_eq!(grid_columns(0), 1);
+        assert_eq!(grid_columns(1), 1);
+        assert_eq!(grid_columns(2), 2);
+        assert_eq!(grid_columns(4), 2);
+        assert_eq!(grid_columns(5), 3);
+        assert_eq!(grid_columns(10), 4);
+    }
+
+    #[test]
+    fn test_downscale() {
+        let small = downscale(frame(4, 2), 8);
+        assert_eq!((small.width, small.height), (4, 2));
+
+        let scaled = downscale(frame(8, 4), 4);
+        assert_eq!((scaled.width, scaled.height), (4, 2));
+        assert_eq!(scaled.rgba.len(), 4 * 2 * 4);
+        // Every other pixel of every other row
+        assert_eq!(scaled.rgba[4], 2);
+        assert_eq!(scaled.rgba[16], 16);
+    }
+
+    #[test]
+    fn test_hot_corner_fires_once_per_visit() {
+        let mut corner = HotCorner::default();
+        assert!(!corner.moved(Point::new(400.0, 300.0)));
+        assert!(corner.moved(Point::new(0.0, 0.0)));
+        assert!(!corner.moved(Point::new(1.0, 0.0)));
+        assert!(!corner.moved(Point::new(50.0, 50.0)));
+        assert!(corner.moved(Point::new(0.0, 1.0)));
+    }
+
+    #[test]
+    fn test_swipes() {
+        let mut tracker = SwipeTracker::new(3);
+        let swipe = |tracker: &mut SwipeTracker, fingers, dx, dy, cancelled| {
+            tracker.handle(GestureEvent::Begin { fingers });
+            tracker.handle(GestureEvent::Update { dx: dx / 2.0, dy: dy / 2.0 });
+            tracker.handle(GestureEvent::Update { dx: dx / 2.0, dy: dy / 2.0 });
+            tracker.handle(GestureEvent::End { cancelled })
+        };
+
+        assert_eq!(swipe(&mut tracker, 3, 0.0, -200.0, false), Some(SwipeAction::ShowOverview));
+        assert_eq!(swipe(&mut tracker, 3, 10.0, 150.0, false), Some(SwipeAction::HideOverview));
+        assert_eq!(swipe(&mut tracker, 3, -200.0, 20.0, false), Some(SwipeAction::NextWorkspace));
+        assert_eq!(swipe(&mut tracker, 3, 200.0, 0.0, false), Some(SwipeAction::PreviousWorkspace));
+
+        // Too short, wrong finger count, or taken over by the compositor
+        assert_eq!(swipe(&mut tracker, 3, 0.0, -20.0, false), None);
+        assert_eq!(swipe(&mut tracker, 4, 0.0, -200.0, false), None);
+        assert_eq!(swipe(&mut tracker, 3, 0.0, -200.0, true), None);
+    }
+}
@@ -1,7 +1,9 @@
 //! Top panel component for Nyx Shell
 
 use crate::config::PanelConfig;
-use crate::messages::{AudioStatus, BatteryStatus, Message, NetworkStatus, PanelMessage};
+use crate::messages::{
+    AudioStatus, BatteryStatus, Message, NetworkStatus, NotificationMessage, PanelMessage,
+};
 use crate::workspace::WorkspaceManager;
 use chrono::Local;
 use iced::widget::{button, container, horizontal_space, row, text, Row};
@@ -54,6 +56,7 @@ impl Panel {
         battery: &BatteryStatus,
         network: &NetworkStatus,
         audio: &AudioStatus,
+        dnd: bool,
     ) -> Element<Message> {
         // Left section: Activities button + workspace indicators
         let left_section = self.view_left_section(workspaces);
@@ -62,7 +65,7 @@ impl Panel {
         let center_section = self.view_center_section();
 
         // Right section: System tray + quick settings
-        let right_section = self.view_right_section(battery, network, audio);
+        let right_section = self.view_right_section(battery, network, audio, dnd);
 
         let panel_content = row![left_section, center_section, right_section]
             .spacing(Spacing::MD)
@@ -150,6 +153,7 @@ impl Panel {
         battery: &BatteryStatus,
         network: &NetworkStatus,
         audio: &AudioStatus,
+        dnd: bool,
     ) -> Element<Message> {
         let mut items: Vec<Element<Message>> = Vec::new();
 
@@ -227,6 +231,18 @@ impl Panel {
             }
         }
 
+        // Notification center, crossed out under Do Not Disturb
+        let notifications_btn = button(
+            text(if dnd { "󰂛" } else { "󰂚" })
+                .size(Typography::SIZE_ICON_MD)
+                .color(NyxColors::TEXT_BRIGHT),
+        )
+        .padding(Padding::from([Spacing::XS, Spacing::SM]))
+        .style(button_style(ButtonVariant::Panel))
+        .on_press(Message::Notifications(NotificationMessage::ToggleCenter));
+
+        items.push(notifications_btn.into());
+
         // Quick settings button
         let settings_btn = button(
             text("󰒓")
@@ -0,0 +1,238 @@
+//! wlr-foreign-toplevel-management client
+//!
+//! Binds `zwlr_foreign_toplevel_manager_v1` on the shell's own Wayland
+//! connection. Aether only offers the global to trusted clients, so when
+//! the bind fails the dock simply shows pinned apps. The protocol is read
+//! on a dedicated thread because dispatching blocks on the socket; window
+//! requests are sent straight from the UI thread on the shared connection.
+
+use crate::windows::{Toplevel, WindowControl, WindowEvent, WindowId, WindowRequest};
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use iced::Subscription;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{wl_registry, wl_seat::WlSeat};
+use wayland_client::{event_created_child, Connection, Dispatch, Proxy, QueueHandle, WEnum};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::zwlr_foreign_toplevel_handle_v1::{
+    self, State as ToplevelState, ZwlrForeignToplevelHandleV1,
+};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::zwlr_foreign_toplevel_manager_v1::{
+    self, ZwlrForeignToplevelManagerV1,
+};
+
+/// Highest protocol version implemented here
+const MANAGER_VERSION: u32 = 3;
+
+type Handles = Arc<Mutex<HashMap<WindowId, ZwlrForeignToplevelHandleV1>>>;
+
+/// Window updates from the compositor
+pub fn subscription() -> Subscription<WindowEvent> {
+    iced::subscription::channel("foreign-toplevel", 32, |mut output| async move {
+        let (sender, mut receiver) = mpsc::unbounded();
+
+        let spawned = std::thread::Builder::new()
+            .name("foreign-toplevel".into())
+            .spawn(move || {
+                if let Err(e) = run(&sender) {
+                    let _ = sender.unbounded_send(WindowEvent::Unavailable(e));
+                }
+            });
+        if let Err(e) = spawned {
+            let _ = output.send(WindowEvent::Unavailable(e.to_string())).await;
+        }
+
+        while let Some(event) = receiver.next().await {
+            let _ = output.send(event).await;
+        }
+
+        iced::futures::future::pending().await
+    })
+}
+
+/// Connect, bind the manager and dispatch until the connection ends
+fn run(events: &mpsc::UnboundedSender<WindowEvent>) -> Result<(), String> {
+    let conn = Connection::connect_to_env().map_err(|e| format!("No Wayland display: {}", e))?;
+    let (globals, mut queue) =
+        registry_queue_init::<State>(&conn).map_err(|e| format!("Registry failed: {}", e))?;
+    let qh = queue.handle();
+
+    let manager: ZwlrForeignToplevelManagerV1 = globals
+        .bind(&qh, 1..=MANAGER_VERSION, ())
+        .map_err(|_| "Compositor does not offer window management to the shell".to_string())?;
+    let seat: WlSeat = globals
+        .bind(&qh, 1..=1, ())
+        .map_err(|e| format!("No seat: {}", e))?;
+
+    let handles = Handles::default();
+    let control = {
+        let conn = conn.clone();
+        let handles = Arc::clone(&handles);
+        WindowControl::new(move |request| send(&conn, &seat, &handles, request))
+    };
+    let _ = events.unbounded_send(WindowEvent::Connected(control));
+    tracing::info!("Bound foreign toplevel manager v{}", manager.version());
+
+    let mut state = State {
+        handles,
+        pending: HashMap::new(),
+        events: events.clone(),
+        finished: false,
+    };
+    while !state.finished {
+        queue
+            .blocking_dispatch(&mut state)
+            .map_err(|e| format!("Compositor connection lost: {}", e))?;
+    }
+    Err("Compositor stopped window management".to_string())
+}
+
+/// Deliver one request on the shared connection
+fn send(conn: &Connection, seat: &WlSeat, handles: &Handles, request: WindowRequest) {
+    let handles = handles.lock().unwrap_or_else(|e| e.into_inner());
+    let id = match request {
+        WindowRequest::Activate(id)
+        | WindowRequest::Minimize(id)
+        | WindowRequest::Restore(id)
+        | WindowRequest::Close(id) => id,
+    };
+    let Some(handle) = handles.get(&id) else {
+        tracing::debug!("Request for closed window {}: {:?}", id, request);
+        return;
+    };
+
+    match request {
+        WindowRequest::Activate(_) => handle.activate(seat),
+        WindowRequest::Minimize(_) => handle.set_minimized(),
+        WindowRequest::Restore(_) => handle.unset_minimized(),
+        WindowRequest::Close(_) => handle.close(),
+    }
+    if let Err(e) = conn.flush() {
+        tracing::warn!("Failed to send window request: {}", e);
+    }
+}
+
+/// Dispatch state on the protocol thread
+struct State {
+    /// Live handles, shared with the control for requests
+    handles: Handles,
+    /// Window state accumulated until the next `done`
+    pending: HashMap<WindowId, Toplevel>,
+    events: mpsc::UnboundedSender<WindowEvent>,
+    finished: bool,
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for State {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlSeat, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &WlSeat,
+        _: <WlSeat as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrForeignToplevelManagerV1,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel } => {
+                let id = toplevel.id().protocol_id();
+                state.pending.insert(id, Toplevel::new(id));
+                state
+                    .handles
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(id, toplevel);
+            }
+            zwlr_foreign_toplevel_manager_v1::Event::Finished => {
+                state.finished = true;
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(State, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let id = handle.id().protocol_id();
+        let toplevel = state.pending.entry(id).or_insert_with(|| Toplevel::new(id));
+
+        match event {
+            zwlr_foreign_toplevel_handle_v1::Event::Title { title } => {
+                toplevel.title = title;
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::AppId { app_id } => {
+                toplevel.app_id = app_id;
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::State { state: states } => {
+                apply_states(toplevel, &states);
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::Done => {
+                let _ = state.events.unbounded_send(WindowEvent::Changed(toplevel.clone()));
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::Closed => {
+                state.pending.remove(&id);
+                state
+                    .handles
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .remove(&id);
+                handle.destroy();
+                let _ = state.events.unbounded_send(WindowEvent::Closed(id));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Replace the state flags from a `state` event's array of u32 values
+fn apply_states(toplevel: &mut Toplevel, states: &[u8]) {
+    toplevel.activated = false;
+    toplevel.minimized = false;
+    toplevel.maximized = false;
+    toplevel.fullscreen = false;
+
+    for chunk in states.chunks_exact(4) {
+        let value = u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        match WEnum::<ToplevelState>::from(value) {
+            WEnum::Value(ToplevelState::Activated) => toplevel.activated = true,
+            WEnum::Value(ToplevelState::Minimized) => toplevel.minimized = true,
+            WEnum::Value(ToplevelState::Maximized) => toplevel.maximized = true,
+            WEnum::Value(ToplevelState::Fullscreen) => toplevel.fullscreen = true,
+            _ => {}
+        }
+    }
+}
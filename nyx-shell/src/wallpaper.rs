@@ -0,0 +1,268 @@
+//! Desktop wallpaper
+//!
+//! The wallpaper is picked from the configured rules by output and active
+//! workspace, the most specific rule winning: one for the workspace on this
+//! output, then one for the workspace, then one for the output, then the
+//! default image. A rule naming a directory is a slideshow that moves to
+//! its next image every interval.
+//!
+//! When the wallpaper changes, the shell takes its accent color from it
+//! with nyx-theme's palette extraction; decoding runs off the UI thread.
+
+use crate::config::{FillMode, WallpaperConfig};
+use crate::workspace::WorkspaceId;
+use iced::ContentFit;
+use libnyx_ipc::compositor::CompositorOutput;
+use libnyx_ipc::CompositorClient;
+use nyx_theme::NyxTheme;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Image files a slideshow picks up
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg"];
+
+/// An image and how to fit it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wallpaper {
+    pub path: PathBuf,
+    pub fill: FillMode,
+}
+
+impl FillMode {
+    pub fn content_fit(self) -> ContentFit {
+        match self {
+            FillMode::Fill => ContentFit::Cover,
+            FillMode::Fit => ContentFit::Contain,
+            FillMode::Stretch => ContentFit::Fill,
+            FillMode::Center => ContentFit::None,
+        }
+    }
+}
+
+/// Images of a slideshow directory and the one showing
+#[derive(Debug, Clone)]
+struct Slideshow {
+    images: Vec<PathBuf>,
+    current: usize,
+}
+
+/// Wallpaper rules and slideshow positions
+#[derive(Debug)]
+pub struct Wallpapers {
+    config: WallpaperConfig,
+    slideshows: HashMap<PathBuf, Slideshow>,
+    advanced: Instant,
+}
+
+impl Wallpapers {
+    pub fn new(config: WallpaperConfig, now: Instant) -> Self {
+        let slideshows = config
+            .image
+            .iter()
+            .chain(config.rules.iter().map(|rule| &rule.image))
+            .filter(|path| path.is_dir())
+            .map(|dir| (dir.clone(), Slideshow { images: scan(dir), current: 0 }))
+            .collect();
+
+        Self {
+            config,
+            slideshows,
+            advanced: now,
+        }
+    }
+
+    /// The wallpaper for a workspace on an output
+    pub fn current(&self, output: Option<&str>, workspace: WorkspaceId) -> Option<Wallpaper> {
+        let specificity = |rule_output: &Option<String>, rule_workspace: Option<WorkspaceId>| {
+            let output_matches = match rule_output {
+                Some(name) => Some(name.as_str()) == output,
+                None => true,
+            };
+            let workspace_matches = rule_workspace.is_none_or(|id| id == workspace);
+            if !(output_matches && workspace_matches) {
+                return None;
+            }
+            Some(rule_workspace.is_some() as u8 * 2 + rule_output.is_some() as u8)
+        };
+
+        let (image, fill) = self
+            .config
+            .rules
+            .iter()
+            .filter_map(|rule| Some((specificity(&rule.output, rule.workspace)?, rule)))
+            // max_by_key keeps the last maximum; the first rule should win
+            .rev()
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, rule)| (&rule.image, rule.fill.unwrap_or(self.config.fill)))
+            .or_else(|| self.config.image.as_ref().map(|image| (image, self.config.fill)))?;
+
+        let path = match self.slideshows.get(image) {
+            Some(slideshow) => slideshow.images.get(slideshow.current)?.clone(),
+            None => image.clone(),
+        };
+        Some(Wallpaper { path, fill })
+    }
+
+    /// Move slideshows on once their interval is up; true if any moved
+    pub fn advance(&mut self, now: Instant) -> bool {
+        let interval = Duration::from_secs(self.config.slideshow_interval_secs.max(1));
+        if self.slideshows.is_empty() || now.duration_since(self.advanced) < interval {
+            return false;
+        }
+        self.advanced = now;
+
+        for (dir, slideshow) in &mut self.slideshows {
+            // Pick up images added or removed since the last turn
+            let current = slideshow.images.get(slideshow.current).cloned();
+            slideshow.images = scan(dir);
+            let position = current
+                .and_then(|path| slideshow.images.iter().position(|p| *p == path))
+                .unwrap_or(0);
+            slideshow.current = match slideshow.images.len() {
+                0 => 0,
+                n => (position + 1) % n,
+            };
+        }
+        true
+    }
+
+    pub fn dynamic_palette(&self) -> bool {
+        self.config.dynamic_palette
+    }
+}
+
+/// Images in a slideshow directory, by name
+fn scan(dir: &Path) -> Vec<PathBuf> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            tracing::warn!("Failed to read wallpaper directory {}: {}", dir.display(), e);
+            return Vec::new();
+        }
+    };
+
+    let mut images: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        })
+        .collect();
+    images.sort();
+    images
+}
+
+/// The output the shell covers: the one at the origin, or the first enabled
+pub async fn shell_output() -> libnyx_ipc::Result<Option<String>> {
+    let outputs = CompositorClient::new().outputs().await?;
+    Ok(pick_output(&outputs))
+}
+
+fn pick_output(outputs: &[CompositorOutput]) -> Option<String> {
+    let enabled = || outputs.iter().filter(|o| o.enabled);
+    enabled()
+        .find(|o| o.position == (0, 0))
+        .or_else(|| enabled().next())
+        .map(|o| o.name.clone())
+}
+
+/// The theme with its accent taken from a wallpaper
+pub async fn themed(theme: NyxTheme, path: PathBuf) -> Result<NyxTheme, String> {
+    tokio::task::spawn_blocking(move || {
+        let mut theme = theme;
+        theme.apply_wallpaper(&path).map_err(|e| e.to_string())?;
+        // Named themes are drawn with their palette overrides
+        theme.name = Some("Wallpaper".to_string());
+        Ok(theme)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WallpaperRule;
+
+    fn rule(output: Option<&str>, workspace: Option<WorkspaceId>, image: &str) -> WallpaperRule {
+        WallpaperRule {
+            output: output.map(String::from),
+            workspace,
+            image: image.into(),
+            fill: None,
+        }
+    }
+
+    fn path(wallpapers: &Wallpapers, output: Option<&str>, workspace: WorkspaceId) -> Option<PathBuf> {
+        wallpapers.current(output, workspace).map(|w| w.path)
+    }
+
+    #[test]
+    fn test_most_specific_rule_wins() {
+        let config = WallpaperConfig {
+            image: Some("/default.png".into()),
+            rules: vec![
+                rule(Some("HDMI-A-1"), None, "/hdmi.png"),
+                rule(None, Some(2), "/two.png"),
+                rule(Some("HDMI-A-1"), Some(2), "/hdmi-two.png"),
+            ],
+            ..Default::default()
+        };
+        let wallpapers = Wallpapers::new(config, Instant::now());
+
+        assert_eq!(path(&wallpapers, Some("eDP-1"), 1), Some("/default.png".into()));
+        assert_eq!(path(&wallpapers, Some("HDMI-A-1"), 1), Some("/hdmi.png".into()));
+        assert_eq!(path(&wallpapers, Some("eDP-1"), 2), Some("/two.png".into()));
+        assert_eq!(path(&wallpapers, Some("HDMI-A-1"), 2), Some("/hdmi-two.png".into()));
+        assert_eq!(path(&wallpapers, None, 2), Some("/two.png".into()));
+    }
+
+    #[test]
+    fn test_slideshow_advances() {
+        let start = Instant::now();
+        let mut wallpapers = Wallpapers::new(
+            WallpaperConfig {
+                image: Some("/slides".into()),
+                slideshow_interval_secs: 60,
+                ..Default::default()
+            },
+            start,
+        );
+        wallpapers.slideshows.insert(
+            "/slides".into(),
+            Slideshow {
+                images: vec!["/slides/a.png".into(), "/slides/b.png".into()],
+                current: 0,
+            },
+        );
+
+        assert_eq!(path(&wallpapers, None, 1), Some("/slides/a.png".into()));
+        assert!(!wallpapers.advance(start + Duration::from_secs(30)));
+
+        // The directory is gone, so the slideshow runs out of images
+        assert!(wallpapers.advance(start + Duration::from_secs(61)));
+        assert_eq!(path(&wallpapers, None, 1), None);
+    }
+
+    #[test]
+    fn test_pick_output() {
+        let output = |name: &str, enabled, position| CompositorOutput {
+            id: 0,
+            name: name.into(),
+            enabled,
+            position,
+            resolution: (1920, 1080),
+            scale: 1.0,
+        };
+
+        let outputs = [
+            output("DP-1", true, (1920, 0)),
+            output("HDMI-A-1", false, (0, 0)),
+            output("eDP-1", true, (0, 0)),
+        ];
+        assert_eq!(pick_output(&outputs), Some("eDP-1".into()));
+        assert_eq!(pick_output(&outputs[..2]), Some("DP-1".into()));
+    }
+}
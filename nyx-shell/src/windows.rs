@@ -0,0 +1,357 @@
+//! Running windows for the dock and the Alt+Tab switcher
+//!
+//! The compositor announces every toplevel window over
+//! wlr-foreign-toplevel-management (see `toplevel`); this keeps the
+//! announced windows in most-recently-focused order and turns dock and
+//! switcher actions into requests back to the compositor.
+
+use iced::Subscription;
+use std::fmt;
+use std::sync::Arc;
+
+/// Compositor-assigned window handle
+pub type WindowId = u32;
+
+/// A window as the compositor describes it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Toplevel {
+    /// Handle for requests
+    pub id: WindowId,
+    /// Window title
+    pub title: String,
+    /// Application ID (desktop file name without `.desktop`)
+    pub app_id: String,
+    /// Has keyboard focus
+    pub activated: bool,
+    /// Is minimized
+    pub minimized: bool,
+    /// Is maximized
+    pub maximized: bool,
+    /// Is fullscreen
+    pub fullscreen: bool,
+}
+
+impl Toplevel {
+    /// Create an empty window with the given handle
+    pub fn new(id: WindowId) -> Self {
+        Self {
+            id,
+            ..Default::default()
+        }
+    }
+
+    /// Title to show, falling back to the application ID
+    pub fn label(&self) -> &str {
+        if self.title.is_empty() {
+            &self.app_id
+        } else {
+            &self.title
+        }
+    }
+}
+
+/// Something the shell asks the compositor to do with a window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowRequest {
+    /// Raise, restore and focus
+    Activate(WindowId),
+    /// Minimize
+    Minimize(WindowId),
+    /// Undo a minimize without focusing
+    Restore(WindowId),
+    /// Ask the window to close
+    Close(WindowId),
+}
+
+/// Sends window requests to the compositor
+#[derive(Clone)]
+pub struct WindowControl(Arc<dyn Fn(WindowRequest) + Send + Sync>);
+
+impl WindowControl {
+    /// Wrap a function that delivers requests
+    pub fn new(send: impl Fn(WindowRequest) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(send))
+    }
+
+    /// Send a request
+    pub fn send(&self, request: WindowRequest) {
+        (self.0)(request)
+    }
+}
+
+impl fmt::Debug for WindowControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("WindowControl")
+    }
+}
+
+/// Window updates from the compositor
+#[derive(Debug, Clone)]
+pub enum WindowEvent {
+    /// Bound to the compositor; requests go through the control
+    Connected(WindowControl),
+    /// A window appeared or changed
+    Changed(Toplevel),
+    /// A window went away
+    Closed(WindowId),
+    /// The compositor does not offer window management, or the
+    /// connection dropped
+    Unavailable(String),
+}
+
+/// Windows in most-recently-focused order
+#[derive(Debug, Clone, Default)]
+pub struct WindowList {
+    windows: Vec<Toplevel>,
+}
+
+impl WindowList {
+    /// Create an empty list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All windows, most recently focused first
+    pub fn windows(&self) -> &[Toplevel] {
+        &self.windows
+    }
+
+    /// Number of windows
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+
+    /// Whether there are no windows
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// Get a window
+    pub fn get(&self, id: WindowId) -> Option<&Toplevel> {
+        self.windows.iter().find(|w| w.id == id)
+    }
+
+    /// The focused window
+    pub fn active(&self) -> Option<&Toplevel> {
+        self.windows.iter().find(|w| w.activated)
+    }
+
+    /// Windows of one application, most recently focused first
+    pub fn for_app<'a>(&'a self, app_id: &'a str) -> impl Iterator<Item = &'a Toplevel> + 'a {
+        self.windows.iter().filter(move |w| w.app_id == app_id)
+    }
+
+    /// Record a new or changed window; a window gaining focus moves to
+    /// the front and new windows otherwise join at the back
+    pub fn update(&mut self, toplevel: Toplevel) {
+        let position = self.windows.iter().position(|w| w.id == toplevel.id);
+        let gained_focus =
+            toplevel.activated && position.is_none_or(|i| !self.windows[i].activated);
+
+        if gained_focus {
+            // Only one window holds focus
+            for window in &mut self.windows {
+                window.activated = false;
+            }
+        }
+
+        match position {
+            Some(i) if gained_focus => {
+                self.windows.remove(i);
+                self.windows.insert(0, toplevel);
+            }
+            Some(i) => self.windows[i] = toplevel,
+            None if gained_focus => self.windows.insert(0, toplevel),
+            None => self.windows.push(toplevel),
+        }
+    }
+
+    /// Forget a closed window
+    pub fn remove(&mut self, id: WindowId) {
+        self.windows.retain(|w| w.id != id);
+    }
+
+    /// Forget every window, e.g. when the compositor connection drops
+    pub fn clear(&mut self) {
+        self.windows.clear();
+    }
+
+    /// What clicking an application's dock icon should do: focus its
+    /// latest window, minimize it if it is already focused and alone, or
+    /// move on to the app's next window
+    pub fn click_app(&self, app_id: &str) -> Option<WindowRequest> {
+        let windows: Vec<&Toplevel> = self.for_app(app_id).collect();
+        let latest = windows.first()?;
+
+        if !latest.activated {
+            return Some(WindowRequest::Activate(latest.id));
+        }
+        match windows.last() {
+            Some(oldest) if windows.len() > 1 => Some(WindowRequest::Activate(oldest.id)),
+            _ => Some(WindowRequest::Minimize(latest.id)),
+        }
+    }
+}
+
+/// Alt+Tab switcher state while Alt is held
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Switcher {
+    selected: usize,
+}
+
+impl Switcher {
+    /// Open over a list, preselecting the previously focused window so a
+    /// quick Alt+Tab flips between the last two
+    pub fn open(windows: &WindowList) -> Option<Self> {
+        if windows.is_empty() {
+            return None;
+        }
+        let selected = match windows.active() {
+            Some(_) if windows.len() > 1 => 1,
+            _ => 0,
+        };
+        Some(Self { selected })
+    }
+
+    /// Open with the least recently focused window selected, for
+    /// Alt+Shift+Tab
+    pub fn open_reversed(windows: &WindowList) -> Option<Self> {
+        Some(Self {
+            selected: windows.len().checked_sub(1)?,
+        })
+    }
+
+    /// Index of the selected window in the list
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Select the next window, wrapping around
+    pub fn next(&mut self, windows: &WindowList) {
+        if !windows.is_empty() {
+            self.selected = (self.selected + 1) % windows.len();
+        }
+    }
+
+    /// Select the previous window, wrapping around
+    pub fn previous(&mut self, windows: &WindowList) {
+        if !windows.is_empty() {
+            self.selected = (self.selected + windows.len() - 1) % windows.len();
+        }
+    }
+
+    /// Window to activate when Alt is released
+    pub fn target(&self, windows: &WindowList) -> Option<WindowId> {
+        windows
+            .windows()
+            .get(self.selected.min(windows.len().saturating_sub(1)))
+            .map(|w| w.id)
+    }
+}
+
+/// Window updates from the compositor
+pub fn subscription() -> Subscription<WindowEvent> {
+    #[cfg(feature = "wayland")]
+    {
+        crate::toplevel::subscription()
+    }
+    #[cfg(not(feature = "wayland"))]
+    {
+        Subscription::none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(id: WindowId, app_id: &str, activated: bool) -> Toplevel {
+        Toplevel {
+            id,
+            title: format!("{} {}", app_id, id),
+            app_id: app_id.to_string(),
+            activated,
+            ..Default::default()
+        }
+    }
+
+    fn ids(list: &WindowList) -> Vec<WindowId> {
+        list.windows().iter().map(|w| w.id).collect()
+    }
+
+    #[test]
+    fn test_focus_order() {
+        let mut list = WindowList::new();
+        list.update(window(1, "umbra", true));
+        list.update(window(2, "nyx-files", false));
+        list.update(window(3, "nyx-browser", true));
+        assert_eq!(ids(&list), vec![3, 1, 2]);
+        assert_eq!(list.active().map(|w| w.id), Some(3));
+        assert!(!list.get(1).unwrap().activated);
+
+        // Title change keeps the position
+        let mut renamed = window(2, "nyx-files", false);
+        renamed.title = "Downloads".into();
+        list.update(renamed);
+        assert_eq!(ids(&list), vec![3, 1, 2]);
+        assert_eq!(list.get(2).unwrap().label(), "Downloads");
+
+        list.update(window(2, "nyx-files", true));
+        assert_eq!(ids(&list), vec![2, 3, 1]);
+
+        list.remove(3);
+        assert_eq!(ids(&list), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_label_falls_back_to_app_id() {
+        let mut toplevel = Toplevel::new(1);
+        toplevel.app_id = "umbra".into();
+        assert_eq!(toplevel.label(), "umbra");
+    }
+
+    #[test]
+    fn test_click_app() {
+        let mut list = WindowList::new();
+        assert_eq!(list.click_app("umbra"), None);
+
+        list.update(window(1, "umbra", false));
+        assert_eq!(list.click_app("umbra"), Some(WindowRequest::Activate(1)));
+
+        list.update(window(1, "umbra", true));
+        assert_eq!(list.click_app("umbra"), Some(WindowRequest::Minimize(1)));
+
+        list.update(window(2, "umbra", false));
+        assert_eq!(list.click_app("umbra"), Some(WindowRequest::Activate(2)));
+    }
+
+    #[test]
+    fn test_switcher() {
+        let mut list = WindowList::new();
+        assert!(Switcher::open(&list).is_none());
+
+        list.update(window(1, "umbra", true));
+        let switcher = Switcher::open(&list).unwrap();
+        assert_eq!(switcher.target(&list), Some(1));
+
+        list.update(window(2, "nyx-files", true));
+        list.update(window(3, "nyx-browser", true));
+        let mut switcher = Switcher::open(&list).unwrap();
+        assert_eq!(switcher.target(&list), Some(2));
+
+        switcher.next(&list);
+        assert_eq!(switcher.target(&list), Some(1));
+        switcher.next(&list);
+        assert_eq!(switcher.target(&list), Some(3));
+        switcher.previous(&list);
+        assert_eq!(switcher.target(&list), Some(1));
+
+        let reversed = Switcher::open_reversed(&list).unwrap();
+        assert_eq!(reversed.target(&list), Some(1));
+        assert!(Switcher::open_reversed(&WindowList::new()).is_none());
+
+        // Windows closing under the switcher clamp the selection
+        list.remove(1);
+        assert_eq!(switcher.target(&list), Some(2));
+    }
+}
@@ -0,0 +1,565 @@
+//! Removable media and automounting
+//!
+//! Block devices on removable media (or behind USB and MMC controllers)
+//! are probed for a filesystem as they appear. Each volume found is run
+//! through the automount policies in
+//! `/grimoire/system/phantom/automount.yaml`, first match wins:
+//!
+//! ```yaml
+//! options: [nosuid, nodev, noexec]
+//! policies:
+//!   - match: { label: "NYX_INSTALL" }
+//!     action: ignore
+//!   - match: { fs_type: "ext*", bus: usb }
+//!     action: system
+//!     options: [exec]
+//! ```
+//!
+//! `session` mounts under `/run/media/<user>/` for whoever is logged in
+//! on seat0 (as spectre reports), `system` mounts under `/media/`, and
+//! `ignore` leaves the volume for an explicit mount request. Options are
+//! added to the defaults, with `exec`, `suid`, `dev` and `rw` cancelling
+//! their opposites. Every change is pushed to subscribers as a
+//! [`MediaEvent`].
+
+use anyhow::{Result, anyhow};
+use libnyx_ipc::session::SessionClient;
+use nix::mount::{MntFlags, MsFlags};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+use crate::device::{pattern_matches, Device};
+use crate::fsprobe;
+
+/// Filesystems without Unix ownership, mounted as the session user
+const FOREIGN_FS: &[&str] = &["vfat", "exfat", "ntfs", "iso9660"];
+
+/// Automount configuration
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AutomountConfig {
+    pub enabled: bool,
+    /// Session mounts go to `<session_root>/<user>/<name>`
+    pub session_root: PathBuf,
+    /// System mounts go to `<system_root>/<name>`
+    pub system_root: PathBuf,
+    /// Mount options applied to every volume
+    pub options: Vec<String>,
+    /// Action for volumes no policy matches
+    pub default_action: MountAction,
+    pub policies: Vec<MountPolicy>,
+}
+
+impl Default for AutomountConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            session_root: PathBuf::from("/run/media"),
+            system_root: PathBuf::from("/media"),
+            options: vec!["nosuid".into(), "nodev".into(), "noexec".into()],
+            default_action: MountAction::Session,
+            policies: Vec::new(),
+        }
+    }
+}
+
+/// What to do with a volume when it appears
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MountAction {
+    /// Mount for the user in front of seat0
+    #[default]
+    Session,
+    /// Mount for everyone
+    System,
+    /// Don't mount until asked to
+    Ignore,
+}
+
+/// One automount policy
+#[derive(Debug, Clone, Deserialize)]
+pub struct MountPolicy {
+    #[serde(rename = "match", default)]
+    pub matches: VolumeMatch,
+    pub action: MountAction,
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+/// Volume properties a policy applies to; `*` wildcards as in rules
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct VolumeMatch {
+    pub fs_type: Option<String>,
+    pub label: Option<String>,
+    pub uuid: Option<String>,
+    pub bus: Option<String>,
+}
+
+impl VolumeMatch {
+    fn matches(&self, volume: &Volume) -> bool {
+        let field = |pattern: &Option<String>, value: Option<&str>| match (pattern, value) {
+            (None, _) => true,
+            (Some(pattern), Some(value)) => pattern_matches(value, pattern),
+            (Some(_), None) => false,
+        };
+
+        field(&self.fs_type, Some(&volume.fs_type))
+            && field(&self.label, volume.label.as_deref())
+            && field(&self.uuid, volume.uuid.as_deref())
+            && field(&self.bus, volume.bus.as_deref())
+    }
+}
+
+impl AutomountConfig {
+    /// Load the configuration; a missing file means the defaults
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_yaml::from_str(&content)?)
+    }
+
+    /// Action and mount options for a volume
+    pub fn policy_for(&self, volume: &Volume) -> (MountAction, Vec<String>) {
+        match self.policies.iter().find(|p| p.matches.matches(volume)) {
+            Some(policy) => (policy.action, merge_options(&self.options, &policy.options)),
+            None => (self.default_action, self.options.clone()),
+        }
+    }
+}
+
+/// A filesystem on removable media
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Volume {
+    /// Device node, e.g. `/dev/sdb1`
+    pub device: String,
+    pub fs_type: String,
+    pub label: Option<String>,
+    pub uuid: Option<String>,
+    /// Bytes
+    pub size: u64,
+    /// `usb`, `mmc`, or unknown
+    pub bus: Option<String>,
+    pub mount_point: Option<String>,
+    /// User the volume is mounted for; unset for system mounts
+    pub owner: Option<String>,
+}
+
+/// Pushed to media subscribers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum MediaEvent {
+    Added { volume: Volume },
+    Mounted { volume: Volume },
+    Unmounted { device: String, mount_point: String },
+    Removed { device: String },
+}
+
+/// Removable volumes, by device node
+pub struct MediaManager {
+    config: AutomountConfig,
+    volumes: HashMap<String, Volume>,
+    sessions: SessionClient,
+    events: broadcast::Sender<MediaEvent>,
+}
+
+impl MediaManager {
+    pub fn new(config: AutomountConfig) -> Self {
+        let (events, _) = broadcast::channel(64);
+        Self {
+            config,
+            volumes: HashMap::new(),
+            sessions: SessionClient::new(),
+            events,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<MediaEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn volumes(&self) -> impl Iterator<Item = &Volume> {
+        self.volumes.values()
+    }
+
+    /// A block device appeared or changed. Media inserted into (or pulled
+    /// from) a card reader or optical drive arrives as a change on a
+    /// device that was there all along.
+    pub async fn device_added(&mut self, device: &Device) {
+        let Some(devnode) = device.devnode.clone() else {
+            return;
+        };
+        if device.subsystem.as_deref() != Some("block") || !is_removable(device) {
+            return;
+        }
+
+        let Some(volume) = identify(device, &devnode) else {
+            self.device_removed(&devnode);
+            return;
+        };
+        if self.volumes.contains_key(&devnode) {
+            return;
+        }
+
+        info!(
+            "Found {} volume {} on {}",
+            volume.fs_type,
+            volume.label.as_deref().unwrap_or("(no label)"),
+            devnode
+        );
+        let already_mounted = volume.mount_point.is_some();
+        self.volumes.insert(devnode.clone(), volume.clone());
+        let _ = self.events.send(MediaEvent::Added { volume: volume.clone() });
+
+        if !self.config.enabled || already_mounted {
+            return;
+        }
+        let (action, options) = self.config.policy_for(&volume);
+        if action == MountAction::Ignore {
+            debug!("Not mounting {}: ignored by policy", devnode);
+            return;
+        }
+        if let Err(e) = self.mount_volume(&devnode, action, options).await {
+            warn!("Automount of {} failed: {}", devnode, e);
+        }
+    }
+
+    /// The media went away: detach whatever is mounted from it
+    pub fn device_removed(&mut self, devnode: &str) {
+        let Some(volume) = self.volumes.remove(devnode) else {
+            return;
+        };
+
+        if let Some(mount_point) = &volume.mount_point {
+            if let Err(e) = self.detach(mount_point, MntFlags::MNT_DETACH) {
+                warn!("Failed to detach {} from {}: {}", devnode, mount_point, e);
+            }
+            let _ = self.events.send(MediaEvent::Unmounted {
+                device: devnode.to_string(),
+                mount_point: mount_point.clone(),
+            });
+        }
+
+        info!("Removed {}", devnode);
+        let _ = self.events.send(MediaEvent::Removed { device: devnode.to_string() });
+    }
+
+    /// Mount on request; an `ignore` policy only stops automounting
+    pub async fn mount(&mut self, devnode: &str) -> Result<Volume> {
+        let volume = self.volumes.get(devnode).ok_or_else(|| anyhow!("Unknown volume: {}", devnode))?;
+        if let Some(mount_point) = &volume.mount_point {
+            return Err(anyhow!("{} is already mounted on {}", devnode, mount_point));
+        }
+
+        let (action, options) = match self.config.policy_for(volume) {
+            (MountAction::Ignore, options) => (MountAction::Session, options),
+            policy => policy,
+        };
+        self.mount_volume(devnode, action, options).await
+    }
+
+    /// Unmount on request; fails while the volume is busy
+    pub fn unmount(&mut self, devnode: &str) -> Result<()> {
+        let volume = self.volumes.get(devnode).ok_or_else(|| anyhow!("Unknown volume: {}", devnode))?;
+        let mount_point = volume.mount_point.clone().ok_or_else(|| anyhow!("{} is not mounted", devnode))?;
+
+        self.detach(&mount_point, MntFlags::empty())?;
+
+        if let Some(volume) = self.volumes.get_mut(devnode) {
+            volume.mount_point = None;
+            volume.owner = None;
+        }
+        info!("Unmounted {} from {}", devnode, mount_point);
+        let _ = self.events.send(MediaEvent::Unmounted {
+            device: devnode.to_string(),
+            mount_point,
+        });
+        Ok(())
+    }
+
+    async fn mount_volume(&mut self, devnode: &str, action: MountAction, mut options: Vec<String>) -> Result<Volume> {
+        let volume = self.volumes.get(devnode).ok_or_else(|| anyhow!("Unknown volume: {}", devnode))?;
+
+        let (parent, owner) = match action {
+            MountAction::Session => {
+                let session = self.sessions.active_session(None).await
+                    .map_err(|e| anyhow!("No user session to mount for: {}", e))?;
+                let user = nix::unistd::User::from_uid(nix::unistd::Uid::from_raw(session.uid))?
+                    .ok_or_else(|| anyhow!("Unknown user {}", session.uid))?;
+
+                let parent = self.config.session_root.join(&session.username);
+                std::fs::create_dir_all(&parent)?;
+                std::fs::set_permissions(&parent, std::fs::Permissions::from_mode(0o750))?;
+                nix::unistd::chown(&parent, Some(nix::unistd::Uid::from_raw(0)), Some(user.gid))?;
+
+                if FOREIGN_FS.contains(&volume.fs_type.as_str()) {
+                    options.push(format!("uid={}", session.uid));
+                    options.push(format!("gid={}", user.gid));
+                }
+                (parent, Some(session.username))
+            }
+            _ => (self.config.system_root.clone(), None),
+        };
+        if volume.fs_type == "iso9660" {
+            options.push("ro".into());
+        }
+
+        let name = volume.label.as_deref()
+            .or(volume.uuid.as_deref())
+            .unwrap_or_else(|| devnode.trim_start_matches("/dev/"));
+        let mount_point = free_mount_point(&parent, name);
+        std::fs::create_dir_all(&mount_point)?;
+
+        let (flags, data) = mount_flags(&options);
+        let fs_type = match volume.fs_type.as_str() {
+            "ntfs" => "ntfs3",
+            fs_type => fs_type,
+        };
+        if let Err(e) = nix::mount::mount(
+            Some(devnode),
+            &mount_point,
+            Some(fs_type),
+            flags,
+            (!data.is_empty()).then_some(data.as_str()),
+        ) {
+            let _ = std::fs::remove_dir(&mount_point);
+            return Err(anyhow!("Cannot mount {}: {}", devnode, e));
+        }
+
+        let volume = self.volumes.get_mut(devnode).ok_or_else(|| anyhow!("Unknown volume: {}", devnode))?;
+        volume.mount_point = Some(mount_point.to_string_lossy().to_string());
+        volume.owner = owner;
+        let volume = volume.clone();
+
+        info!("Mounted {} on {:?} ({})", devnode, mount_point, options.join(","));
+        let _ = self.events.send(MediaEvent::Mounted { volume: volume.clone() });
+        Ok(volume)
+    }
+
+    /// Unmount, and remove the mount point if phantom made it
+    fn detach(&self, mount_point: &str, flags: MntFlags) -> Result<()> {
+        nix::mount::umount2(mount_point, flags)
+            .map_err(|e| anyhow!("Cannot unmount {}: {}", mount_point, e))?;
+
+        let path = Path::new(mount_point);
+        if path.starts_with(&self.config.session_root) || path.starts_with(&self.config.system_root) {
+            let _ = std::fs::remove_dir(path);
+        }
+        Ok(())
+    }
+}
+
+/// Removable flag of the disk, or a bus that only carries removable media
+fn is_removable(device: &Device) -> bool {
+    if bus(&device.syspath).is_some() {
+        return true;
+    }
+
+    let path = Path::new(&device.syspath);
+    let disk = if device.devtype.as_deref() == Some("partition") {
+        path.parent().unwrap_or(path)
+    } else {
+        path
+    };
+    std::fs::read_to_string(disk.join("removable"))
+        .map(|v| v.trim() == "1")
+        .unwrap_or(false)
+}
+
+fn bus(syspath: &str) -> Option<String> {
+    let real = std::fs::canonicalize(syspath).unwrap_or_else(|_| PathBuf::from(syspath));
+    let real = real.to_string_lossy();
+    if real.contains("/usb") {
+        Some("usb".into())
+    } else if real.contains("/mmc") {
+        Some("mmc".into())
+    } else {
+        None
+    }
+}
+
+/// Probe a device for a filesystem
+fn identify(device: &Device, devnode: &str) -> Option<Volume> {
+    let info = match fsprobe::probe_device(Path::new(devnode)) {
+        Ok(info) => info?,
+        Err(e) => {
+            debug!("Cannot probe {}: {}", devnode, e);
+            return None;
+        }
+    };
+
+    let sectors: u64 = std::fs::read_to_string(Path::new(&device.syspath).join("size"))
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(0);
+
+    Some(Volume {
+        device: devnode.to_string(),
+        fs_type: info.fs_type.to_string(),
+        label: info.label,
+        uuid: info.uuid,
+        size: sectors * 512,
+        bus: bus(&device.syspath),
+        mount_point: mounted_at(devnode),
+        owner: None,
+    })
+}
+
+/// Where a device is already mounted, if anywhere
+fn mounted_at(devnode: &str) -> Option<String> {
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    mounts.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let source = fields.next()?;
+        let target = fields.next()?;
+        (source == devnode).then(|| unescape_mount_path(target))
+    })
+}
+
+/// /proc/self/mounts escapes spaces and the like as octal
+fn unescape_mount_path(path: &str) -> String {
+    let mut out = Vec::with_capacity(path.len());
+    let bytes = path.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() {
+            let octal = std::str::from_utf8(&bytes[i + 1..i + 4]).ok();
+            if let Some(byte) = octal.and_then(|o| u8::from_str_radix(o, 8).ok()) {
+                out.push(byte);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// A mount point under `parent` named after the volume, numbered when
+/// the name is taken
+fn free_mount_point(parent: &Path, name: &str) -> PathBuf {
+    let name: String = name
+        .chars()
+        .map(|c| if c == '/' || c.is_control() { '_' } else { c })
+        .collect();
+    let name = if name.starts_with('.') || name.is_empty() { format!("_{}", name) } else { name };
+
+    let taken = |path: &Path| {
+        path.exists() && std::fs::read_dir(path).map(|mut d| d.next().is_some()).unwrap_or(true)
+    };
+
+    let mut candidate = parent.join(&name);
+    let mut n = 1;
+    while taken(&candidate) {
+        candidate = parent.join(format!("{}-{}", name, n));
+        n += 1;
+    }
+    candidate
+}
+
+/// Add a policy's options to the defaults; `exec`, `suid`, `dev` and `rw`
+/// remove `noexec`, `nosuid`, `nodev` and `ro`
+fn merge_options(defaults: &[String], extra: &[String]) -> Vec<String> {
+    let mut options = defaults.to_vec();
+    for option in extra {
+        let opposite = match option.as_str() {
+            "rw" => "ro".to_string(),
+            "ro" => "rw".to_string(),
+            option => match option.strip_prefix("no") {
+                Some(positive) => positive.to_string(),
+                None => format!("no{}", option),
+            },
+        };
+        options.retain(|o| *o != opposite && o != option);
+        options.push(option.clone());
+    }
+    options
+}
+
+/// Split options into mount flags and filesystem data
+fn mount_flags(options: &[String]) -> (MsFlags, String) {
+    let mut flags = MsFlags::empty();
+    let mut data = Vec::new();
+
+    for option in options {
+        match option.as_str() {
+            "ro" => flags |= MsFlags::MS_RDONLY,
+            "nosuid" => flags |= MsFlags::MS_NOSUID,
+            "nodev" => flags |= MsFlags::MS_NODEV,
+            "noexec" => flags |= MsFlags::MS_NOEXEC,
+            "noatime" => flags |= MsFlags::MS_NOATIME,
+            "relatime" => flags |= MsFlags::MS_RELATIME,
+            "sync" => flags |= MsFlags::MS_SYNCHRONOUS,
+            "rw" | "suid" | "dev" | "exec" => {}
+            other => data.push(other),
+        }
+    }
+
+    (flags, data.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volume(fs_type: &str, label: Option<&str>) -> Volume {
+        Volume {
+            device: "/dev/sdb1".into(),
+            fs_type: fs_type.into(),
+            label: label.map(String::from),
+            uuid: None,
+            size: 0,
+            bus: Some("usb".into()),
+            mount_point: None,
+            owner: None,
+        }
+    }
+
+    #[test]
+    fn test_policy_for() {
+        let config: AutomountConfig = serde_yaml::from_str(r#"
+policies:
+  - match: { label: "NYX_*" }
+    action: ignore
+  - match: { fs_type: "ext*", bus: usb }
+    action: system
+    options: [exec, noatime]
+"#).unwrap();
+
+        let (action, options) = config.policy_for(&volume("vfat", Some("NYX_INSTALL")));
+        assert_eq!(action, MountAction::Ignore);
+        assert_eq!(options, ["nosuid", "nodev", "noexec"]);
+
+        let (action, options) = config.policy_for(&volume("ext4", None));
+        assert_eq!(action, MountAction::System);
+        assert_eq!(options, ["nosuid", "nodev", "exec", "noatime"]);
+
+        // No label never matches a label pattern
+        let (action, _) = config.policy_for(&volume("vfat", None));
+        assert_eq!(action, MountAction::Session);
+    }
+
+    #[test]
+    fn test_mount_flags() {
+        let options = merge_options(
+            &["nosuid".into(), "nodev".into(), "noexec".into()],
+            &["ro".into(), "utf8".into(), "umask=077".into()],
+        );
+        let (flags, data) = mount_flags(&options);
+        assert_eq!(flags, MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC | MsFlags::MS_RDONLY);
+        assert_eq!(data, "utf8,umask=077");
+    }
+
+    #[test]
+    fn test_unescape_mount_path() {
+        assert_eq!(unescape_mount_path("/run/media/ada/My\\040Disk"), "/run/media/ada/My Disk");
+        assert_eq!(unescape_mount_path("/media/usb"), "/media/usb");
+    }
+}
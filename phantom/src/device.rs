@@ -352,7 +352,7 @@ fn makedev(major: u32, minor: u32) -> u64 {
 }
 
 /// Pattern matching (supports * wildcard)
-fn pattern_matches(value: &str, pattern: &str) -> bool {
+pub(crate) fn pattern_matches(value: &str, pattern: &str) -> bool {
     if pattern == "*" {
         return true;
     }
@@ -0,0 +1,270 @@
+//! Filesystem identification
+//!
+//! Reads the superblock of a block device to find out which filesystem it
+//! holds, with its label and UUID, for the formats removable media
+//! usually carries. UUIDs are formatted the way blkid does, so they match
+//! what users see elsewhere.
+
+use anyhow::Result;
+use std::io::Read;
+use std::path::Path;
+
+/// Bytes read from the start of a device; the btrfs superblock is the
+/// furthest in
+pub const PROBE_SIZE: usize = BTRFS_OFFSET + 0x1000;
+
+const EXT_OFFSET: usize = 0x400;
+const BTRFS_OFFSET: usize = 0x10000;
+const ISO_OFFSET: usize = 0x8000;
+
+/// Filesystem found on a device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsInfo {
+    /// Type as the kernel names it, except `ntfs` (mounted with ntfs3)
+    pub fs_type: &'static str,
+    pub label: Option<String>,
+    pub uuid: Option<String>,
+}
+
+/// Identify the filesystem on a device node
+pub fn probe_device(devnode: &Path) -> Result<Option<FsInfo>> {
+    let mut data = Vec::with_capacity(PROBE_SIZE);
+    std::fs::File::open(devnode)?
+        .take(PROBE_SIZE as u64)
+        .read_to_end(&mut data)?;
+    Ok(probe(&data))
+}
+
+/// Identify the filesystem whose first bytes are `data`
+pub fn probe(data: &[u8]) -> Option<FsInfo> {
+    probe_ext(data)
+        .or_else(|| probe_btrfs(data))
+        .or_else(|| probe_xfs(data))
+        .or_else(|| probe_ntfs(data))
+        .or_else(|| probe_exfat(data))
+        .or_else(|| probe_vfat(data))
+        .or_else(|| probe_iso9660(data))
+}
+
+fn probe_ext(data: &[u8]) -> Option<FsInfo> {
+    let sb = data.get(EXT_OFFSET..EXT_OFFSET + 0x100)?;
+    if u16_le(sb, 0x38) != 0xEF53 {
+        return None;
+    }
+
+    let compat = u32_le(sb, 0x5C);
+    let incompat = u32_le(sb, 0x60);
+    // extents, 64bit or flex_bg only exist on ext4; a journal means ext3
+    let fs_type = if incompat & (0x40 | 0x80 | 0x200) != 0 {
+        "ext4"
+    } else if compat & 0x4 != 0 {
+        "ext3"
+    } else {
+        "ext2"
+    };
+
+    Some(FsInfo {
+        fs_type,
+        label: label(&sb[0x78..0x88]),
+        uuid: uuid(&sb[0x68..0x78]),
+    })
+}
+
+fn probe_btrfs(data: &[u8]) -> Option<FsInfo> {
+    let sb = data.get(BTRFS_OFFSET..BTRFS_OFFSET + 0x22B)?;
+    if &sb[0x40..0x48] != b"_BHRfS_M" {
+        return None;
+    }
+
+    Some(FsInfo {
+        fs_type: "btrfs",
+        label: label(&sb[0x12B..0x22B]),
+        uuid: uuid(&sb[0x20..0x30]),
+    })
+}
+
+fn probe_xfs(data: &[u8]) -> Option<FsInfo> {
+    let sb = data.get(..0x78)?;
+    if &sb[..4] != b"XFSB" {
+        return None;
+    }
+
+    Some(FsInfo {
+        fs_type: "xfs",
+        label: label(&sb[0x6C..0x78]),
+        uuid: uuid(&sb[0x20..0x30]),
+    })
+}
+
+fn probe_ntfs(data: &[u8]) -> Option<FsInfo> {
+    let boot = data.get(..0x200)?;
+    if &boot[3..11] != b"NTFS    " {
+        return None;
+    }
+
+    // The label lives in the $Volume file, not the boot sector
+    let serial = u64::from_le_bytes(boot[0x48..0x50].try_into().ok()?);
+    Some(FsInfo {
+        fs_type: "ntfs",
+        label: None,
+        uuid: Some(format!("{:016X}", serial)),
+    })
+}
+
+fn probe_exfat(data: &[u8]) -> Option<FsInfo> {
+    let boot = data.get(..0x200)?;
+    if &boot[3..11] != b"EXFAT   " {
+        return None;
+    }
+
+    // As with NTFS, the label is an entry in the root directory
+    Some(FsInfo {
+        fs_type: "exfat",
+        label: None,
+        uuid: Some(fat_serial(u32_le(boot, 0x64))),
+    })
+}
+
+fn probe_vfat(data: &[u8]) -> Option<FsInfo> {
+    let boot = data.get(..0x200)?;
+    if boot[0x1FE..0x200] != [0x55, 0xAA] {
+        return None;
+    }
+
+    // FAT32 moved the extended boot record past its larger BPB
+    let (serial, label_at) = if &boot[0x52..0x57] == b"FAT32" {
+        (0x43, 0x47)
+    } else if &boot[0x36..0x39] == b"FAT" {
+        (0x27, 0x2B)
+    } else {
+        return None;
+    };
+
+    Some(FsInfo {
+        fs_type: "vfat",
+        label: label(&boot[label_at..label_at + 11]).filter(|l| l != "NO NAME"),
+        uuid: Some(fat_serial(u32_le(boot, serial))),
+    })
+}
+
+fn probe_iso9660(data: &[u8]) -> Option<FsInfo> {
+    let pvd = data.get(ISO_OFFSET..ISO_OFFSET + 0x33E)?;
+    if pvd[0] != 1 || &pvd[1..6] != b"CD001" {
+        return None;
+    }
+
+    // No UUID on disc; blkid uses the creation time, YYYYMMDDHHMMSScc
+    let created = std::str::from_utf8(&pvd[0x32D..0x33D]).ok()?;
+    let uuid = (created.bytes().all(|b| b.is_ascii_digit()) && created != "0000000000000000").then(|| {
+        format!(
+            "{}-{}-{}-{}-{}-{}-{}",
+            &created[0..4], &created[4..6], &created[6..8],
+            &created[8..10], &created[10..12], &created[12..14], &created[14..16]
+        )
+    });
+
+    Some(FsInfo {
+        fs_type: "iso9660",
+        label: label(&pvd[0x28..0x48]),
+        uuid,
+    })
+}
+
+fn u16_le(data: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([data[at], data[at + 1]])
+}
+
+fn u32_le(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
+
+/// Label padded with NULs or spaces
+fn label(raw: &[u8]) -> Option<String> {
+    let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+    let label = String::from_utf8_lossy(&raw[..end]).trim_end().to_string();
+    (!label.is_empty()).then_some(label)
+}
+
+/// 16-byte UUID in the usual 8-4-4-4-12 form
+fn uuid(raw: &[u8]) -> Option<String> {
+    if raw.iter().all(|&b| b == 0) {
+        return None;
+    }
+
+    let hex: String = raw.iter().map(|b| format!("{:02x}", b)).collect();
+    Some(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32]
+    ))
+}
+
+/// FAT and exFAT volume serials are shown as XXXX-XXXX
+fn fat_serial(serial: u32) -> String {
+    format!("{:04X}-{:04X}", serial >> 16, serial & 0xFFFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_ext4() {
+        let mut data = vec![0u8; PROBE_SIZE];
+        let sb = &mut data[EXT_OFFSET..];
+        sb[0x38..0x3A].copy_from_slice(&0xEF53u16.to_le_bytes());
+        sb[0x60..0x64].copy_from_slice(&0x2C2u32.to_le_bytes());
+        sb[0x68..0x78].copy_from_slice(&[
+            0x3e, 0x6b, 0x1f, 0x2a, 0x9c, 0x41, 0x4d, 0x0e, 0xa7, 0x15, 0x5b, 0x8e, 0x0c, 0x33, 0x72, 0x91,
+        ]);
+        sb[0x78..0x7E].copy_from_slice(b"BACKUP");
+
+        let info = probe(&data).unwrap();
+        assert_eq!(info.fs_type, "ext4");
+        assert_eq!(info.label.as_deref(), Some("BACKUP"));
+        assert_eq!(info.uuid.as_deref(), Some("3e6b1f2a-9c41-4d0e-a715-5b8e0c337291"));
+    }
+
+    #[test]
+    fn test_probe_vfat() {
+        let mut data = vec![0u8; PROBE_SIZE];
+        data[0x1FE] = 0x55;
+        data[0x1FF] = 0xAA;
+        data[0x52..0x5A].copy_from_slice(b"FAT32   ");
+        data[0x43..0x47].copy_from_slice(&0x1A2B3C4Du32.to_le_bytes());
+        data[0x47..0x52].copy_from_slice(b"CAMERA     ");
+
+        let info = probe(&data).unwrap();
+        assert_eq!(info.fs_type, "vfat");
+        assert_eq!(info.label.as_deref(), Some("CAMERA"));
+        assert_eq!(info.uuid.as_deref(), Some("1A2B-3C4D"));
+
+        data[0x47..0x52].copy_from_slice(b"NO NAME    ");
+        assert_eq!(probe(&data).unwrap().label, None);
+    }
+
+    #[test]
+    fn test_probe_iso9660() {
+        let mut data = vec![0u8; PROBE_SIZE];
+        let pvd = &mut data[ISO_OFFSET..];
+        pvd[0] = 1;
+        pvd[1..6].copy_from_slice(b"CD001");
+        pvd[0x28..0x48].copy_from_slice(format!("{:<32}", "NYX_INSTALL").as_bytes());
+        pvd[0x32D..0x33D].copy_from_slice(b"2026031412000000");
+
+        let info = probe(&data).unwrap();
+        assert_eq!(info.fs_type, "iso9660");
+        assert_eq!(info.label.as_deref(), Some("NYX_INSTALL"));
+        assert_eq!(info.uuid.as_deref(), Some("2026-03-14-12-00-00-00"));
+    }
+
+    #[test]
+    fn test_probe_unknown() {
+        assert_eq!(probe(&vec![0u8; PROBE_SIZE]), None);
+        // A partition table alone is not a filesystem
+        let mut mbr = vec![0u8; PROBE_SIZE];
+        mbr[0x1FE] = 0x55;
+        mbr[0x1FF] = 0xAA;
+        assert_eq!(probe(&mbr), None);
+        assert_eq!(probe(&[0u8; 16]), None);
+    }
+}
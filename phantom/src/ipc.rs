@@ -1,5 +1,6 @@
 //! IPC interface for Phantom
 
+use crate::automount::{MediaEvent, MediaManager, Volume};
 use crate::device::{Device, DeviceDatabase, DeviceFilter};
 use crate::rule::RuleSet;
 use anyhow::Result;
@@ -9,8 +10,8 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
-use tokio::sync::RwLock;
-use tracing::{info, error, debug};
+use tokio::sync::{broadcast, Mutex, RwLock};
+use tracing::{info, error, debug, warn};
 
 /// IPC request
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +23,14 @@ pub enum IpcRequest {
     Monitor,
     TestRules { path: String },
     Settle,
+    /// Volumes on removable media
+    ListMedia,
+    /// Mount a volume by device node
+    Mount { device: String },
+    Unmount { device: String },
+    /// Keep the connection open and push a `MediaEvent` per change,
+    /// starting with the volumes already present
+    SubscribeMedia,
 }
 
 /// IPC response
@@ -29,9 +38,11 @@ pub enum IpcRequest {
 #[serde(tag = "status")]
 pub enum IpcResponse {
     Success { message: String },
-    Devices(Vec<DeviceInfo>),
+    Devices { devices: Vec<DeviceInfo> },
     Device(DeviceInfo),
     RuleTest(Vec<(String, String)>),
+    Media { volumes: Vec<Volume> },
+    Volume { volume: Volume },
     Error { message: String },
 }
 
@@ -68,6 +79,7 @@ pub struct PhantomServer {
     socket_path: PathBuf,
     devices: Arc<RwLock<DeviceDatabase>>,
     rules: Arc<RwLock<RuleSet>>,
+    media: Arc<Mutex<MediaManager>>,
 }
 
 impl PhantomServer {
@@ -75,11 +87,13 @@ impl PhantomServer {
         socket_path: PathBuf,
         devices: Arc<RwLock<DeviceDatabase>>,
         rules: Arc<RwLock<RuleSet>>,
+        media: Arc<Mutex<MediaManager>>,
     ) -> Self {
         Self {
             socket_path,
             devices,
             rules,
+            media,
         }
     }
 
@@ -99,9 +113,10 @@ impl PhantomServer {
                 Ok((stream, _)) => {
                     let devices = self.devices.clone();
                     let rules = self.rules.clone();
+                    let media = self.media.clone();
 
                     tokio::spawn(async move {
-                        if let Err(e) = handle_client(stream, devices, rules).await {
+                        if let Err(e) = handle_client(stream, devices, rules, media).await {
                             error!("Client error: {}", e);
                         }
                     });
@@ -116,6 +131,7 @@ async fn handle_client(
     stream: UnixStream,
     devices: Arc<RwLock<DeviceDatabase>>,
     rules: Arc<RwLock<RuleSet>>,
+    media: Arc<Mutex<MediaManager>>,
 ) -> Result<()> {
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
@@ -123,7 +139,8 @@ async fn handle_client(
 
     while reader.read_line(&mut line).await? > 0 {
         let response = match serde_json::from_str::<IpcRequest>(&line) {
-            Ok(request) => process_request(request, &devices, &rules).await,
+            Ok(IpcRequest::SubscribeMedia) => return stream_media(writer, &media).await,
+            Ok(request) => process_request(request, &devices, &rules, &media).await,
             Err(e) => IpcResponse::Error { message: e.to_string() },
         };
 
@@ -138,10 +155,52 @@ async fn handle_client(
     Ok(())
 }
 
+/// Send the volumes already present, then every change, until the client
+/// goes away
+async fn stream_media(
+    mut writer: tokio::net::unix::OwnedWriteHalf,
+    media: &Mutex<MediaManager>,
+) -> Result<()> {
+    let (mut events, present) = {
+        let manager = media.lock().await;
+        let mut present = Vec::new();
+        for volume in manager.volumes() {
+            present.push(MediaEvent::Added { volume: volume.clone() });
+            if volume.mount_point.is_some() {
+                present.push(MediaEvent::Mounted { volume: volume.clone() });
+            }
+        }
+        (manager.subscribe(), present)
+    };
+
+    for event in present {
+        write_event(&mut writer, &event).await?;
+    }
+
+    loop {
+        match events.recv().await {
+            Ok(event) => write_event(&mut writer, &event).await?,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("Media subscriber fell behind by {} events", missed);
+            }
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+async fn write_event(writer: &mut tokio::net::unix::OwnedWriteHalf, event: &MediaEvent) -> Result<()> {
+    let json = serde_json::to_string(event)?;
+    writer.write_all(json.as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+    writer.flush().await?;
+    Ok(())
+}
+
 async fn process_request(
     request: IpcRequest,
     devices: &RwLock<DeviceDatabase>,
     rules: &RwLock<RuleSet>,
+    media: &Mutex<MediaManager>,
 ) -> IpcResponse {
     match request {
         IpcRequest::ListDevices { subsystem } => {
@@ -156,7 +215,7 @@ async fn process_request(
                 db.all().map(DeviceInfo::from).collect()
             };
 
-            IpcResponse::Devices(device_list)
+            IpcResponse::Devices { devices: device_list }
         }
 
         IpcRequest::GetDevice { path } => {
@@ -226,6 +285,29 @@ async fn process_request(
                 message: "Settled".to_string(),
             }
         }
+
+        IpcRequest::ListMedia => {
+            let manager = media.lock().await;
+            IpcResponse::Media {
+                volumes: manager.volumes().cloned().collect(),
+            }
+        }
+
+        IpcRequest::Mount { device } => match media.lock().await.mount(&device).await {
+            Ok(volume) => IpcResponse::Volume { volume },
+            Err(e) => IpcResponse::Error { message: e.to_string() },
+        },
+
+        IpcRequest::Unmount { device } => match media.lock().await.unmount(&device) {
+            Ok(()) => IpcResponse::Success {
+                message: format!("Unmounted {}", device),
+            },
+            Err(e) => IpcResponse::Error { message: e.to_string() },
+        },
+
+        IpcRequest::SubscribeMedia => IpcResponse::Error {
+            message: "SubscribeMedia must be sent on its own connection".to_string(),
+        },
     }
 }
 
@@ -258,7 +340,7 @@ impl PhantomClient {
         match self.send(IpcRequest::ListDevices {
             subsystem: subsystem.map(String::from),
         }).await? {
-            IpcResponse::Devices(devices) => Ok(devices),
+            IpcResponse::Devices { devices } => Ok(devices),
             IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
             _ => Err(anyhow::anyhow!("Unexpected response")),
         }
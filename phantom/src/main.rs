@@ -10,6 +10,7 @@
 //! - **Device Nodes**: Automatic /dev node creation
 //! - **Device Properties**: Sysfs attribute reading
 //! - **Permissions**: Device node permission management
+//! - **Removable Media**: Filesystem probing and policy-driven automount
 
 mod device;
 mod rule;
@@ -17,6 +18,8 @@ mod netlink;
 mod devnode;
 mod hwdb;
 mod ipc;
+mod fsprobe;
+mod automount;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
@@ -24,7 +27,7 @@ use libnyx_platform::Platform;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 use tracing::{info, warn, error};
 
 /// Phantom - Device Manager
@@ -35,6 +38,10 @@ struct Args {
     #[arg(short, long, default_value = "/grimoire/system/phantom.d")]
     rules_dir: PathBuf,
 
+    /// Automount policy file
+    #[arg(long, default_value = "/grimoire/system/phantom/automount.yaml")]
+    automount_config: PathBuf,
+
     /// Socket path
     #[arg(short, long, default_value = "/run/phantom/phantom.sock")]
     socket: PathBuf,
@@ -184,12 +191,27 @@ async fn run_daemon(args: Args) -> Result<()> {
         }
     }
 
+    // Pick up media that was plugged in before we started
+    let config = automount::AutomountConfig::load(&args.automount_config).unwrap_or_else(|e| {
+        warn!("Failed to load automount config: {}", e);
+        automount::AutomountConfig::default()
+    });
+    let media = Arc::new(Mutex::new(automount::MediaManager::new(config)));
+    {
+        let db = devices.read().await;
+        let mut manager = media.lock().await;
+        for device in db.by_subsystem("block") {
+            manager.device_added(device).await;
+        }
+    }
+
     // Start netlink monitor
     let devices_clone = devices.clone();
     let rules_clone = rules.clone();
+    let media_clone = media.clone();
 
     let netlink_handle = tokio::spawn(async move {
-        if let Err(e) = run_netlink_monitor(devices_clone, rules_clone).await {
+        if let Err(e) = run_netlink_monitor(devices_clone, rules_clone, media_clone).await {
             error!("Netlink monitor error: {}", e);
         }
     });
@@ -199,6 +221,7 @@ async fn run_daemon(args: Args) -> Result<()> {
         args.socket.clone(),
         devices.clone(),
         rules.clone(),
+        media.clone(),
     );
 
     info!("Phantom ready on {:?}", args.socket);
@@ -220,6 +243,7 @@ async fn run_daemon(args: Args) -> Result<()> {
 async fn run_netlink_monitor(
     devices: Arc<RwLock<device::DeviceDatabase>>,
     rules: Arc<RwLock<rule::RuleSet>>,
+    media: Arc<Mutex<automount::MediaManager>>,
 ) -> Result<()> {
     let mut monitor = netlink::NetlinkMonitor::new()?;
 
@@ -263,6 +287,22 @@ async fn run_netlink_monitor(
                         }
                     }
                 }
+
+                // Removable media, once rules have created the node
+                if event.subsystem.as_deref() == Some("block") {
+                    let db = devices.read().await;
+                    let mut manager = media.lock().await;
+
+                    match (event.action.as_str(), db.get(&event.devpath)) {
+                        ("add" | "change", Some(device)) => manager.device_added(device).await,
+                        ("remove", _) => {
+                            if let Some(devname) = &event.devname {
+                                manager.device_removed(&format!("/dev/{}", devname));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
             }
             Ok(None) => {
                 // No event, continue
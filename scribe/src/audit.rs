@@ -0,0 +1,173 @@
+//! nyx-audit - Query the privileged-operation audit trail
+//!
+//! Guardian, vault, cipher, spectre and nexus report privileged operations
+//! to scribe, which journals them under the `audit` identifier. This tool
+//! filters them by actor, object, action and outcome.
+
+mod journal;
+mod elf;
+mod coredump;
+mod storage;
+mod query;
+mod ipc;
+mod state;
+mod follow;
+mod seal;
+mod forward;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use libnyx_ipc::audit::AUDIT_IDENTIFIER;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+
+use crate::ipc::{IpcRequest, IpcResponse, LogEntryInfo};
+
+#[derive(Parser)]
+#[command(name = "nyx-audit")]
+#[command(about = "Query privileged operations across Nyx daemons")]
+struct Cli {
+    /// Who asked (user name, uid:<n>, or executable)
+    #[arg(long, short)]
+    actor: Option<String>,
+
+    /// What the operation was done to
+    #[arg(long, short)]
+    object: Option<String>,
+
+    /// Operation, e.g. vault.unlock
+    #[arg(long = "action", short = 'A')]
+    action: Option<String>,
+
+    /// success, denied or failure
+    #[arg(long)]
+    outcome: Option<String>,
+
+    /// Daemon that reported the operation
+    #[arg(long)]
+    source: Option<String>,
+
+    /// Operations belonging to one trace
+    #[arg(long)]
+    trace: Option<String>,
+
+    /// Show operations since time
+    #[arg(long, short = 'S')]
+    since: Option<String>,
+
+    /// Show operations until time
+    #[arg(long, short = 'U')]
+    until: Option<String>,
+
+    /// Number of operations to show
+    #[arg(long, short = 'n', default_value = "100")]
+    lines: usize,
+
+    /// Show newest operations first
+    #[arg(long, short)]
+    reverse: bool,
+
+    /// Print journal entries as JSON
+    #[arg(long)]
+    json: bool,
+
+    /// Socket path
+    #[arg(long, default_value = libnyx_ipc::paths::SCRIBE_SOCKET)]
+    socket: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    if let Some(outcome) = &cli.outcome {
+        if !["success", "denied", "failure"].contains(&outcome.as_str()) {
+            bail!("Unknown outcome {} (expected success, denied or failure)", outcome);
+        }
+    }
+
+    let mut fields: Vec<String> = [
+        ("audit.actor", &cli.actor),
+        ("audit.object", &cli.object),
+        ("audit.action", &cli.action),
+        ("audit.outcome", &cli.outcome),
+        ("audit.source", &cli.source),
+        ("trace_id", &cli.trace),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| value.as_ref().map(|value| format!("{}={}", key, value)))
+    .collect();
+
+    // Any client may log under the identifier (which also matches by
+    // substring), but only events from privileged daemons keep audit.* fields
+    if cli.source.is_none() {
+        fields.push("audit.source".to_string());
+    }
+
+    let request = IpcRequest::Query {
+        since: cli.since,
+        until: cli.until,
+        priority: None,
+        identifier: Some(AUDIT_IDENTIFIER.to_string()),
+        grep: None,
+        fields,
+        limit: Some(cli.lines),
+        reverse: cli.reverse,
+    };
+
+    match send_request(&cli.socket, request).await? {
+        IpcResponse::Entries(entries) => {
+            for entry in entries {
+                if cli.json {
+                    println!("{}", serde_json::to_string(&entry)?);
+                } else {
+                    println!("{}", format_operation(&entry));
+                }
+            }
+        }
+        IpcResponse::Error { message } => bail!(message),
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// `TIME OUTCOME SOURCE: ACTOR ACTION OBJECT (REASON)`
+fn format_operation(entry: &LogEntryInfo) -> String {
+    let field = |key: &str| {
+        entry
+            .fields
+            .get(key)
+            .map(|value| value.to_string())
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    let mut line = format!(
+        "{} {:<7} {}: {} {} {}",
+        entry.timestamp,
+        field("audit.outcome"),
+        field("audit.source"),
+        field("audit.actor"),
+        field("audit.action"),
+        field("audit.object"),
+    );
+    if entry.fields.contains_key("audit.reason") {
+        line.push_str(&format!(" ({})", field("audit.reason")));
+    }
+    line
+}
+
+async fn send_request(socket_path: &str, request: IpcRequest) -> Result<IpcResponse> {
+    let mut stream = UnixStream::connect(socket_path).await?;
+
+    let json = serde_json::to_string(&request)?;
+    stream.write_all(json.as_bytes()).await?;
+    stream.write_all(b"\n").await?;
+    stream.flush().await?;
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+
+    Ok(serde_json::from_str(&line)?)
+}
@@ -4,21 +4,55 @@ use anyhow::{Result, anyhow};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::net::UnixListener;
-use tracing::{debug, warn};
+use std::net::SocketAddr;
+use std::path::Path;
+use tokio::net::{TcpListener, UnixListener};
+use tracing::{debug, info, warn};
 
 use crate::state::ScribeState;
 use crate::journal::{LogEntry, Priority, Facility};
-
-/// Kernel log collector (reads from /dev/kmsg)
-pub struct KernelCollector;
+use crate::structured;
+
+/// How much of the kernel log ring to fetch per read
+#[cfg(feature = "native")]
+const KLOG_READ_SIZE: usize = 64 * 1024;
+
+/// How often to check the kernel log ring once it is drained
+#[cfg(feature = "native")]
+const KLOG_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Kernel log collector (reads from /dev/kmsg, or from the kernel's log
+/// ring on native Nyx)
+pub struct KernelCollector {
+    /// Slot of the kernel log capability granted by init
+    #[cfg(feature = "native")]
+    klog_cap: u64,
+}
 
 impl KernelCollector {
     pub fn new() -> Self {
-        Self
+        Self {
+            #[cfg(feature = "native")]
+            klog_cap: 0,
+        }
+    }
+
+    #[cfg(feature = "native")]
+    pub fn with_klog_cap(mut self, slot: u64) -> Self {
+        self.klog_cap = slot;
+        self
     }
 
     pub async fn run(&self, state: Arc<RwLock<ScribeState>>) -> Result<()> {
+        #[cfg(feature = "native")]
+        return self.run_klog(state).await;
+
+        #[cfg(not(feature = "native"))]
+        self.run_kmsg(state).await
+    }
+
+    #[cfg(not(feature = "native"))]
+    async fn run_kmsg(&self, state: Arc<RwLock<ScribeState>>) -> Result<()> {
         let file = tokio::fs::File::open("/dev/kmsg").await?;
         let reader = BufReader::new(file);
         let mut lines = reader.lines();
@@ -26,7 +60,7 @@ impl KernelCollector {
         while let Some(line) = lines.next_line().await? {
             if let Some(entry) = self.parse_kmsg(&line) {
                 let mut state = state.write().await;
-                if let Err(e) = state.journal.write(&entry) {
+                if let Err(e) = state.record(&entry) {
                     warn!("Failed to write kernel log: {}", e);
                 }
             }
@@ -35,6 +69,39 @@ impl KernelCollector {
         Ok(())
     }
 
+    /// Drain the kernel's log ring with the klog syscall; it holds the same
+    /// kmsg-style lines as /dev/kmsg
+    #[cfg(feature = "native")]
+    async fn run_klog(&self, state: Arc<RwLock<ScribeState>>) -> Result<()> {
+        let cap = libnyx::Capability::from_raw(self.klog_cap);
+        let mut buf = vec![0u8; KLOG_READ_SIZE];
+        let mut next_seq = 0;
+
+        loop {
+            let n = libnyx::klog::read(cap, next_seq, &mut buf)
+                .map_err(|e| anyhow!("Kernel log read failed: {}", e.as_str()))?;
+            if n == 0 {
+                tokio::time::sleep(KLOG_POLL_INTERVAL).await;
+                continue;
+            }
+
+            let text = String::from_utf8_lossy(&buf[..n]);
+            let mut state = state.write().await;
+            for line in text.lines() {
+                // Sequence numbers skip ahead when the ring overwrote
+                // records before we got to them
+                if let Some(seq) = line.split(',').nth(1).and_then(|s| s.parse::<u64>().ok()) {
+                    next_seq = next_seq.max(seq + 1);
+                }
+                if let Some(entry) = self.parse_kmsg(line) {
+                    if let Err(e) = state.record(&entry) {
+                        warn!("Failed to write kernel log: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
     fn parse_kmsg(&self, line: &str) -> Option<LogEntry> {
         // kmsg format: <priority>,<sequence>,<timestamp>,-;<message>
         let parts: Vec<&str> = line.splitn(2, ';').collect();
@@ -67,6 +134,108 @@ impl KernelCollector {
     }
 }
 
+/// Crash and error report collector (native Nyx only)
+///
+/// Programs built on libnyx send panics and reported errors to this
+/// endpoint (see `libnyx::report`); each report becomes one journal entry.
+#[cfg(feature = "native")]
+pub struct ReportCollector {
+    /// Slot of the report endpoint capability granted by init
+    report_cap: u64,
+}
+
+#[cfg(feature = "native")]
+impl ReportCollector {
+    pub fn new(report_cap: u64) -> Self {
+        Self { report_cap }
+    }
+
+    pub async fn run(&self, state: Arc<RwLock<ScribeState>>) -> Result<()> {
+        let cap = libnyx::Capability::from_raw(self.report_cap);
+
+        loop {
+            // Receiving blocks in the kernel; keep it off the async workers
+            let (buf, n) = tokio::task::spawn_blocking(move || {
+                let mut buf = vec![0u8; libnyx::MAX_MESSAGE_SIZE];
+                libnyx::ipc::receive(cap, &mut buf, None).map(|n| (buf, n))
+            })
+            .await?
+            .map_err(|e| anyhow!("Report receive failed: {}", e.as_str()))?;
+
+            let Some(entry) = Self::parse_report(&buf[..n]) else {
+                debug!("Ignoring malformed report ({} bytes)", n);
+                continue;
+            };
+            let mut state = state.write().await;
+            if let Err(e) = state.record(&entry) {
+                warn!("Failed to write report: {}", e);
+            }
+        }
+    }
+
+    fn parse_report(data: &[u8]) -> Option<LogEntry> {
+        use crate::journal::FieldValue;
+        use libnyx::report::{self, ReportKind};
+
+        let unescape = |value: &str| {
+            let mut out = String::with_capacity(value.len());
+            let _ = report::unescape(value, &mut out);
+            out
+        };
+
+        let mut kind = None;
+        let mut pid = None;
+        let mut message = String::new();
+        let mut location = None;
+        let mut frames = Vec::new();
+        let mut caps = Vec::new();
+        for (key, value) in report::parse(data)? {
+            match key {
+                "kind" => kind = ReportKind::parse(value),
+                "pid" => pid = value.parse::<u32>().ok(),
+                "message" => message = unescape(value),
+                "location" => location = Some(unescape(value)),
+                "frame" => frames.push(value),
+                "cap" => caps.push(value),
+                _ => {}
+            }
+        }
+        let kind = kind?;
+
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("report.kind".to_string(), FieldValue::Text(kind.as_str().to_string()));
+        if let Some(location) = &location {
+            fields.insert("report.location".to_string(), FieldValue::Text(location.clone()));
+        }
+        if !frames.is_empty() {
+            fields.insert("report.backtrace".to_string(), FieldValue::Text(frames.join(" ")));
+        }
+        if !caps.is_empty() {
+            fields.insert("report.caps".to_string(), FieldValue::Text(caps.join("; ")));
+        }
+
+        let (priority, message) = match (kind, location) {
+            (ReportKind::Panic, Some(location)) => {
+                (Priority::Critical, format!("panicked at {}: {}", location, message))
+            }
+            (ReportKind::Panic, None) => (Priority::Critical, format!("panicked: {}", message)),
+            (ReportKind::Error, _) => (Priority::Error, message),
+        };
+
+        Some(LogEntry {
+            timestamp: chrono::Utc::now(),
+            priority,
+            facility: Facility::User,
+            identifier: "report".to_string(),
+            message,
+            pid,
+            uid: None,
+            hostname: None,
+            fields,
+        })
+    }
+}
+
 /// Syslog collector (listens on /dev/log)
 pub struct SyslogCollector {
     socket_path: String,
@@ -96,7 +265,7 @@ impl SyslogCollector {
                         while let Ok(Some(line)) = lines.next_line().await {
                             if let Some(entry) = Self::parse_syslog(&line) {
                                 let mut state = state.write().await;
-                                if let Err(e) = state.journal.write(&entry) {
+                                if let Err(e) = state.record(&entry) {
                                     warn!("Failed to write syslog: {}", e);
                                 }
                             }
@@ -127,6 +296,21 @@ impl SyslogCollector {
 
         let rest = &line[end_pri + 1..];
 
+        // RFC5424 carries its own header and structured data
+        if let Some(parsed) = structured::parse_rfc5424(rest) {
+            return Some(LogEntry {
+                timestamp: parsed.timestamp.unwrap_or_else(chrono::Utc::now),
+                priority,
+                facility,
+                identifier: parsed.identifier.unwrap_or_else(|| "unknown".to_string()),
+                message: parsed.message,
+                pid: parsed.pid,
+                uid: None,
+                hostname: parsed.hostname,
+                fields: parsed.fields,
+            });
+        }
+
         // Try to parse structured format
         let (identifier, message, pid) = Self::parse_message(rest);
 
@@ -164,6 +348,110 @@ impl SyslogCollector {
     }
 }
 
+/// Receives entries forwarded by other scribe instances
+///
+/// Peers send newline-delimited JSON entries over TCP, optionally wrapped
+/// in TLS. Entries without a hostname are attributed to the peer address.
+pub struct RemoteCollector {
+    listen: String,
+    tls: Option<tokio_rustls::TlsAcceptor>,
+}
+
+impl RemoteCollector {
+    pub fn new(listen: &str, cert: Option<&Path>, key: Option<&Path>) -> Result<Self> {
+        let tls = match (cert, key) {
+            (Some(cert), Some(key)) => Some(Self::tls_acceptor(cert, key)?),
+            (None, None) => None,
+            _ => return Err(anyhow!("remote TLS needs both a certificate and a key")),
+        };
+
+        Ok(Self {
+            listen: listen.to_string(),
+            tls,
+        })
+    }
+
+    fn tls_acceptor(cert: &Path, key: &Path) -> Result<tokio_rustls::TlsAcceptor> {
+        use tokio_rustls::rustls;
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+        use rustls::pki_types::pem::PemObject;
+
+        let certs = CertificateDer::pem_file_iter(cert)
+            .map_err(|e| anyhow!("reading {}: {}", cert.display(), e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| anyhow!("reading {}: {}", cert.display(), e))?;
+        let key = PrivateKeyDer::from_pem_file(key)
+            .map_err(|e| anyhow!("reading {}: {}", key.display(), e))?;
+
+        let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+
+        Ok(tokio_rustls::TlsAcceptor::from(Arc::new(config)))
+    }
+
+    pub async fn run(&self, state: Arc<RwLock<ScribeState>>) -> Result<()> {
+        let listener = TcpListener::bind(&self.listen).await?;
+        info!("Accepting forwarded logs on {}", self.listen);
+
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("Remote accept error: {}", e);
+                    continue;
+                }
+            };
+
+            let state = state.clone();
+            let tls = self.tls.clone();
+            tokio::spawn(async move {
+                let result = match tls {
+                    Some(acceptor) => match acceptor.accept(stream).await {
+                        Ok(stream) => Self::receive(stream, peer, state).await,
+                        Err(e) => Err(e.into()),
+                    },
+                    None => Self::receive(stream, peer, state).await,
+                };
+                if let Err(e) = result {
+                    debug!("Remote peer {} disconnected: {}", peer, e);
+                }
+            });
+        }
+    }
+
+    async fn receive<S: tokio::io::AsyncRead + Unpin>(
+        stream: S,
+        peer: SocketAddr,
+        state: Arc<RwLock<ScribeState>>,
+    ) -> Result<()> {
+        let mut lines = BufReader::new(stream).lines();
+
+        while let Some(line) = lines.next_line().await? {
+            let mut entry: LogEntry = match serde_json::from_str(&line) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    debug!("Malformed entry from {}: {}", peer, e);
+                    continue;
+                }
+            };
+            if entry.hostname.is_none() {
+                entry.hostname = Some(peer.ip().to_string());
+            }
+
+            let mut state = state.write().await;
+            if let Err(e) = state.record(&entry) {
+                warn!("Failed to write forwarded log: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
 /// Stdout/stderr collector for services
 pub struct StdoutCollector {
     identifier: String,
@@ -188,20 +476,33 @@ impl StdoutCollector {
         let mut lines = reader.lines();
 
         while let Some(line) = lines.next_line().await? {
-            let entry = LogEntry {
-                timestamp: chrono::Utc::now(),
-                priority,
-                facility: Facility::Daemon,
-                identifier: self.identifier.clone(),
-                message: line,
-                pid: Some(self.pid),
-                uid: None,
-                hostname: None,
-                fields: std::collections::HashMap::new(),
+            let entry = match structured::parse_tracing_json(&line) {
+                Some(parsed) => LogEntry {
+                    timestamp: parsed.timestamp.unwrap_or_else(chrono::Utc::now),
+                    priority: parsed.priority.unwrap_or(priority),
+                    facility: Facility::Daemon,
+                    identifier: self.identifier.clone(),
+                    message: parsed.message,
+                    pid: Some(self.pid),
+                    uid: None,
+                    hostname: None,
+                    fields: parsed.fields,
+                },
+                None => LogEntry {
+                    timestamp: chrono::Utc::now(),
+                    priority,
+                    facility: Facility::Daemon,
+                    identifier: self.identifier.clone(),
+                    message: line,
+                    pid: Some(self.pid),
+                    uid: None,
+                    hostname: None,
+                    fields: std::collections::HashMap::new(),
+                },
             };
 
             let mut state = state.write().await;
-            state.journal.write(&entry)?;
+            state.record(&entry)?;
         }
 
         Ok(())
@@ -0,0 +1,117 @@
+//! nyx-coredump - Kernel core dump handler
+//!
+//! Run by the kernel through core_pattern (see `scribed`) with the dump on
+//! stdin. Stores the dump, trims the store to its quota and reports the
+//! crash to scribed. There is no terminal or journal to complain to, so
+//! failures go to the kernel log.
+
+mod journal;
+mod elf;
+mod coredump;
+mod storage;
+mod query;
+mod ipc;
+mod state;
+mod follow;
+mod seal;
+mod forward;
+
+use anyhow::{anyhow, Result};
+use chrono::{TimeZone, Utc};
+use clap::Parser;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use crate::coredump::{CoredumpConfig, CoredumpInfo, CoredumpStore};
+use crate::ipc::{IpcRequest, IpcResponse};
+
+/// How long to wait for scribed to take the report
+const REPORT_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Parser)]
+#[command(name = "nyx-coredump")]
+#[command(about = "Store a core dump from the kernel (core_pattern handler)")]
+struct Args {
+    /// PID in the initial namespace (%P)
+    pid: u32,
+    /// Real UID (%u)
+    uid: u32,
+    /// Real GID (%g)
+    gid: u32,
+    /// Signal that caused the dump (%s)
+    signal: i32,
+    /// Time of the dump (%t)
+    timestamp: i64,
+    /// Command name (%e)
+    comm: Vec<String>,
+
+    /// Socket path
+    #[arg(long, default_value = libnyx_ipc::paths::SCRIBE_SOCKET)]
+    socket: String,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            kmsg(&format!("failed to store core dump of PID {}: {:#}", args.pid, e));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<()> {
+    let timestamp = Utc
+        .timestamp_opt(args.timestamp, 0)
+        .single()
+        .unwrap_or_else(Utc::now);
+    let mut info = CoredumpInfo::new(args.pid, args.uid, args.gid, args.signal, timestamp, &args.comm.join(" "));
+    coredump::describe_process(&mut info);
+
+    let store = CoredumpStore::open(CoredumpConfig::load_handler_config())?;
+    let info = store.store(info, std::io::stdin().lock())?;
+    if !info.stored {
+        kmsg(&format!(
+            "core dump of PID {} is {} bytes, over the limit; kept its metadata only",
+            info.pid, info.size
+        ));
+    }
+
+    for id in store.enforce_quota()? {
+        kmsg(&format!("removed core dump {} to stay within quota", id));
+    }
+
+    // The dump is safe by now; a scribed that is down only misses the
+    // journal entry
+    if let Err(e) = report(&args.socket, &info) {
+        kmsg(&format!("could not report core dump {} to scribe: {:#}", info.id, e));
+    }
+    Ok(())
+}
+
+fn report(socket: &str, info: &CoredumpInfo) -> Result<()> {
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(REPORT_TIMEOUT))?;
+    stream.set_write_timeout(Some(REPORT_TIMEOUT))?;
+
+    let request = IpcRequest::CoredumpStored { info: info.clone() };
+    let mut json = serde_json::to_string(&request)?;
+    json.push('\n');
+    stream.write_all(json.as_bytes())?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    match serde_json::from_str(&line)? {
+        IpcResponse::Error { message } => Err(anyhow!(message)),
+        _ => Ok(()),
+    }
+}
+
+fn kmsg(message: &str) {
+    if let Ok(mut kmsg) = std::fs::OpenOptions::new().write(true).open("/dev/kmsg") {
+        let _ = writeln!(kmsg, "<3>nyx-coredump: {}", message);
+    }
+}
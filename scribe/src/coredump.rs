@@ -0,0 +1,412 @@
+//! Core dump collection
+//!
+//! On hosted Linux scribed registers `nyx-coredump` as the kernel's
+//! core_pattern pipe handler. The kernel runs it as root for every crash
+//! with the dump on stdin; it compresses the dump into the store together
+//! with its metadata (service unit, signal, executable build-id), trims
+//! the store to its quota and reports the crash to scribed, which journals
+//! it. On native Nyx the kernel writes dumps to /var/core itself and
+//! scribed moves them into the store. Dumps are listed and extracted over
+//! scribed's socket; users only see their own.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use crate::journal::{Facility, FieldValue, LogEntry, Priority};
+
+/// Journal identifier for crash reports
+pub const COREDUMP_IDENTIFIER: &str = "coredump";
+
+/// Settings scribed leaves for nyx-coredump; core_pattern is limited to
+/// 128 bytes, too short to pass them on the command line
+pub const HANDLER_CONFIG: &str = "/run/scribe/coredump.json";
+
+const CORE_EXT: &str = "core.zst";
+const META_EXT: &str = "json";
+
+/// Compression level for stored dumps
+const ZSTD_LEVEL: i32 = 3;
+
+/// Store settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CoredumpConfig {
+    pub dir: PathBuf,
+    /// Space all stored dumps may take (compressed)
+    pub max_use: u64,
+    /// Only the metadata is kept of dumps larger than this (uncompressed)
+    pub max_dump_size: u64,
+}
+
+impl Default for CoredumpConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("/var/lib/scribe/coredumps"),
+            max_use: 1024 * 1024 * 1024,
+            max_dump_size: 2048 * 1024 * 1024,
+        }
+    }
+}
+
+impl CoredumpConfig {
+    /// Settings scribed left for the handler, or the defaults
+    pub fn load_handler_config() -> Self {
+        std::fs::read(HANDLER_CONFIG)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// A crash and its stored dump
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoredumpInfo {
+    /// `<unix time>-<pid>`
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub pid: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub signal: i32,
+    pub comm: String,
+    pub exe: Option<String>,
+    /// Service the process ran as
+    pub unit: Option<String>,
+    /// GNU build-id of the executable
+    pub build_id: Option<String>,
+    /// Uncompressed size
+    pub size: u64,
+    /// Size in the store
+    pub compressed_size: u64,
+    /// False when the dump was too large and only this was kept
+    pub stored: bool,
+}
+
+impl CoredumpInfo {
+    pub fn new(pid: u32, uid: u32, gid: u32, signal: i32, timestamp: DateTime<Utc>, comm: &str) -> Self {
+        Self {
+            id: format!("{}-{}", timestamp.timestamp(), pid),
+            timestamp,
+            pid,
+            uid,
+            gid,
+            signal,
+            comm: comm.to_string(),
+            exe: None,
+            unit: None,
+            build_id: None,
+            size: 0,
+            compressed_size: 0,
+            stored: false,
+        }
+    }
+
+    /// One-line description of the crash
+    pub fn summary(&self) -> String {
+        let name = self.unit.as_deref().unwrap_or(&self.comm);
+        format!(
+            "Process {} ({}) of user {} dumped core: {}",
+            self.pid,
+            name,
+            self.uid,
+            signal_name(self.signal)
+        )
+    }
+
+    /// Journal entry reporting the crash
+    pub fn entry(&self) -> LogEntry {
+        let mut fields = HashMap::new();
+        let mut set = |key: &str, value: FieldValue| {
+            fields.insert(format!("coredump.{}", key), value);
+        };
+        set("id", FieldValue::Text(self.id.clone()));
+        set("signal", FieldValue::Int(self.signal as i64));
+        set("comm", FieldValue::Text(self.comm.clone()));
+        set("size", FieldValue::Int(self.size as i64));
+        set("stored", FieldValue::Bool(self.stored));
+        if let Some(exe) = &self.exe {
+            set("exe", FieldValue::Text(exe.clone()));
+        }
+        if let Some(unit) = &self.unit {
+            set("unit", FieldValue::Text(unit.clone()));
+        }
+        if let Some(build_id) = &self.build_id {
+            set("build_id", FieldValue::Text(build_id.clone()));
+        }
+
+        LogEntry {
+            timestamp: self.timestamp,
+            priority: Priority::Critical,
+            facility: Facility::Daemon,
+            identifier: COREDUMP_IDENTIFIER.to_string(),
+            message: self.summary(),
+            pid: Some(self.pid),
+            uid: Some(self.uid),
+            hostname: None,
+            fields,
+        }
+    }
+}
+
+/// Name of a core-dumping signal
+pub fn signal_name(signal: i32) -> String {
+    match signal {
+        libc::SIGQUIT => "SIGQUIT".to_string(),
+        libc::SIGILL => "SIGILL".to_string(),
+        libc::SIGTRAP => "SIGTRAP".to_string(),
+        libc::SIGABRT => "SIGABRT".to_string(),
+        libc::SIGBUS => "SIGBUS".to_string(),
+        libc::SIGFPE => "SIGFPE".to_string(),
+        libc::SIGSEGV => "SIGSEGV".to_string(),
+        libc::SIGXCPU => "SIGXCPU".to_string(),
+        libc::SIGXFSZ => "SIGXFSZ".to_string(),
+        libc::SIGSYS => "SIGSYS".to_string(),
+        other => format!("signal {}", other),
+    }
+}
+
+/// Directory of compressed dumps, each next to its metadata
+#[derive(Debug, Clone)]
+pub struct CoredumpStore {
+    config: CoredumpConfig,
+}
+
+impl CoredumpStore {
+    /// Open the store, creating it readable by root only
+    pub fn open(config: CoredumpConfig) -> Result<Self> {
+        std::fs::create_dir_all(&config.dir)
+            .with_context(|| format!("Failed to create {}", config.dir.display()))?;
+        std::fs::set_permissions(&config.dir, std::fs::Permissions::from_mode(0o700))?;
+        Ok(Self { config })
+    }
+
+    pub fn config(&self) -> &CoredumpConfig {
+        &self.config
+    }
+
+    /// Compress `core` into the store and record `info` next to it
+    pub fn store(&self, mut info: CoredumpInfo, mut core: impl Read) -> Result<CoredumpInfo> {
+        let path = self.path(&info.id, CORE_EXT)?;
+        let tmp = path.with_extension("zst.tmp");
+
+        let file = create_private(&tmp)?;
+        let mut encoder = zstd::Encoder::new(file, ZSTD_LEVEL)?;
+        let limit = self.config.max_dump_size;
+        let copied = std::io::copy(&mut (&mut core).take(limit + 1), &mut encoder)?;
+
+        if copied > limit {
+            // Drain the rest so the size is still known
+            drop(encoder);
+            let _ = std::fs::remove_file(&tmp);
+            info.size = copied + std::io::copy(&mut core, &mut std::io::sink())?;
+            info.compressed_size = 0;
+            info.stored = false;
+        } else {
+            encoder.finish()?.sync_all()?;
+            std::fs::rename(&tmp, &path)?;
+            info.size = copied;
+            info.compressed_size = std::fs::metadata(&path)?.len();
+            info.stored = true;
+        }
+
+        let meta = self.path(&info.id, META_EXT)?;
+        let tmp = meta.with_extension("json.tmp");
+        create_private(&tmp)?.write_all(&serde_json::to_vec_pretty(&info)?)?;
+        std::fs::rename(&tmp, &meta)?;
+
+        Ok(info)
+    }
+
+    /// Every stored crash, newest first
+    pub fn list(&self) -> Result<Vec<CoredumpInfo>> {
+        let mut dumps = Vec::new();
+        for entry in std::fs::read_dir(&self.config.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(META_EXT) {
+                continue;
+            }
+            match std::fs::read(&path).map(|data| serde_json::from_slice::<CoredumpInfo>(&data)) {
+                Ok(Ok(info)) => dumps.push(info),
+                _ => tracing::warn!("Skipping unreadable core dump metadata {}", path.display()),
+            }
+        }
+        dumps.sort_by_key(|d| std::cmp::Reverse(d.timestamp));
+        Ok(dumps)
+    }
+
+    /// Metadata of one crash
+    pub fn get(&self, id: &str) -> Result<CoredumpInfo> {
+        let data = std::fs::read(self.path(id, META_EXT)?).map_err(|_| anyhow!("No core dump {}", id))?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Decompressed contents of a stored dump
+    pub fn open_core(&self, id: &str) -> Result<impl Read> {
+        let file = File::open(self.path(id, CORE_EXT)?)
+            .map_err(|_| anyhow!("Only the metadata of core dump {} was kept", id))?;
+        Ok(zstd::Decoder::new(file)?)
+    }
+
+    /// Remove the oldest crashes until the store fits its quota; returns
+    /// the removed ids
+    pub fn enforce_quota(&self) -> Result<Vec<String>> {
+        let mut dumps = self.list()?;
+        let mut used: u64 = dumps.iter().map(|d| d.compressed_size).sum();
+        let mut removed = Vec::new();
+
+        while used > self.config.max_use {
+            let Some(oldest) = dumps.pop() else { break };
+            used -= oldest.compressed_size;
+            let _ = std::fs::remove_file(self.path(&oldest.id, CORE_EXT)?);
+            let _ = std::fs::remove_file(self.path(&oldest.id, META_EXT)?);
+            removed.push(oldest.id);
+        }
+        Ok(removed)
+    }
+
+    fn path(&self, id: &str, ext: &str) -> Result<PathBuf> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit() || c == '-') {
+            bail!("Invalid core dump id {}", id);
+        }
+        Ok(self.config.dir.join(format!("{}.{}", id, ext)))
+    }
+}
+
+fn create_private(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))
+}
+
+/// Fill in what /proc still shows of a crashing process: the kernel keeps
+/// it around while the handler runs
+pub fn describe_process(info: &mut CoredumpInfo) {
+    let proc_dir = PathBuf::from(format!("/proc/{}", info.pid));
+
+    if let Ok(exe) = std::fs::read_link(proc_dir.join("exe")) {
+        info.exe = Some(exe.display().to_string());
+        // Read through /proc so a replaced or deleted binary still works
+        info.build_id = crate::elf::build_id(&proc_dir.join("exe"));
+    }
+    if let Ok(cgroup) = std::fs::read_to_string(proc_dir.join("cgroup")) {
+        info.unit = unit_from_cgroup(&cgroup);
+    }
+}
+
+/// nyx-serviced runs each service in `<name>.scope`; its own scope in a
+/// user slice is not a service
+fn unit_from_cgroup(cgroup: &str) -> Option<String> {
+    let path = cgroup.lines().find_map(|line| line.strip_prefix("0::"))?;
+    path.rsplit('/')
+        .filter_map(|part| part.strip_suffix(".scope"))
+        .find(|name| *name != "serviced")
+        .map(str::to_string)
+}
+
+/// Make `handler` the kernel's core dump handler (hosted Linux)
+#[cfg(not(feature = "native"))]
+pub fn register_handler(handler: &str, config: &CoredumpConfig) -> Result<()> {
+    const CORE_PATTERN: &str = "/proc/sys/kernel/core_pattern";
+    // Keeps /proc/<pid> of a crashing process until its handler exits
+    const CORE_PIPE_LIMIT: &str = "/proc/sys/kernel/core_pipe_limit";
+
+    if let Some(parent) = Path::new(HANDLER_CONFIG).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(HANDLER_CONFIG, serde_json::to_vec_pretty(config)?)?;
+
+    // %e is last: a command name may contain spaces
+    let pattern = format!("|{} %P %u %g %s %t %e", handler);
+    let previous = std::fs::read_to_string(CORE_PATTERN).unwrap_or_default();
+    if previous.trim() != pattern {
+        tracing::info!("Replacing core_pattern {:?} with {:?}", previous.trim(), pattern);
+        std::fs::write(CORE_PATTERN, &pattern).context("Failed to set core_pattern")?;
+    }
+    if let Err(e) = std::fs::write(CORE_PIPE_LIMIT, "16") {
+        tracing::warn!("Failed to set core_pipe_limit: {}", e);
+    }
+    Ok(())
+}
+
+/// Move dumps the Nyx kernel wrote to /var/core into the store
+#[cfg(feature = "native")]
+pub async fn collect_kernel_dumps(state: std::sync::Arc<tokio::sync::RwLock<crate::state::ScribeState>>) {
+    const KERNEL_CORE_DIR: &str = "/var/core";
+    const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+    loop {
+        let store = state.read().await.coredumps.clone();
+        let Some(store) = store else { return };
+
+        let found: Vec<PathBuf> = std::fs::read_dir(KERNEL_CORE_DIR)
+            .map(|dir| {
+                dir.filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("core.")))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        for path in found {
+            let store = store.clone();
+            let result = tokio::task::spawn_blocking(move || ingest_kernel_dump(&store, &path)).await;
+            match result {
+                Ok(Ok(info)) => {
+                    let mut state = state.write().await;
+                    if let Err(e) = state.record(&info.entry()) {
+                        tracing::warn!("Failed to journal core dump {}: {}", info.id, e);
+                    }
+                }
+                Ok(Err(e)) => tracing::warn!("Failed to collect a kernel core dump: {:#}", e),
+                Err(e) => tracing::warn!("Core dump collection panicked: {}", e),
+            }
+        }
+
+        if let Err(e) = store.enforce_quota() {
+            tracing::warn!("Failed to trim core dumps: {}", e);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Store one `core.<pid>.<timestamp>` file and remove it
+#[cfg(feature = "native")]
+fn ingest_kernel_dump(store: &CoredumpStore, path: &Path) -> Result<CoredumpInfo> {
+    use chrono::TimeZone;
+    use std::io::{BufReader, Seek, SeekFrom};
+
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let mut parts = name.split('.').skip(1);
+    let pid = parts.next().and_then(|p| p.parse().ok());
+    let timestamp = parts
+        .next()
+        .and_then(|t| t.parse().ok())
+        .and_then(|t| Utc.timestamp_opt(t, 0).single())
+        .unwrap_or_else(Utc::now);
+
+    let mut file = File::open(path)?;
+    let process = crate::elf::core_process(&mut file).unwrap_or_default();
+    file.seek(SeekFrom::Start(0))?;
+
+    let info = CoredumpInfo::new(
+        process.pid.or(pid).ok_or_else(|| anyhow!("No pid for {}", path.display()))?,
+        process.uid.unwrap_or(0),
+        process.gid.unwrap_or(0),
+        process.signal.unwrap_or(0),
+        timestamp,
+        process.comm.as_deref().unwrap_or("unknown"),
+    );
+    let info = store.store(info, BufReader::new(file))?;
+    std::fs::remove_file(path)?;
+    Ok(info)
+}
@@ -1,17 +1,23 @@
 //! scribectl - Journal query and control tool
 
 mod journal;
+mod elf;
+mod coredump;
 mod storage;
 mod query;
 mod ipc;
 mod state;
+mod follow;
+mod seal;
+mod forward;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixStream;
 
-use crate::ipc::{IpcRequest, IpcResponse};
+use crate::coredump::CoredumpInfo;
+use crate::ipc::{IpcRequest, IpcResponse, LogEntryInfo};
 use crate::query::OutputFormat;
 
 #[derive(Parser)]
@@ -50,6 +56,10 @@ enum Commands {
         #[arg(long, short)]
         grep: Option<String>,
 
+        /// Match a structured field (KEY=VALUE, or KEY for presence)
+        #[arg(long = "field", short = 'F')]
+        fields: Vec<String>,
+
         /// Number of entries to show
         #[arg(long, short, default_value = "100")]
         lines: usize,
@@ -76,12 +86,25 @@ enum Commands {
     /// Remove old journal files
     Vacuum,
 
-    /// Verify journal integrity
-    Verify,
+    /// Verify journal integrity, including archive seals
+    Verify {
+        /// Check a journal directory directly instead of asking the daemon
+        #[arg(long)]
+        dir: Option<String>,
+    },
 
     /// Flush journal to disk
     Flush,
 
+    /// Show remote forwarding delivery state
+    ForwardStatus,
+
+    /// List and extract core dumps
+    Coredump {
+        #[command(subcommand)]
+        command: CoredumpCommand,
+    },
+
     /// Show kernel messages
     Dmesg {
         /// Number of lines
@@ -90,6 +113,32 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum CoredumpCommand {
+    /// List stored crashes, newest first
+    List {
+        /// Only crashes of this service
+        #[arg(long, short = 'u')]
+        unit: Option<String>,
+
+        /// Number of crashes to show
+        #[arg(long, short, default_value = "50")]
+        lines: usize,
+    },
+
+    /// Show everything known about a crash
+    Info { id: String },
+
+    /// Write a decompressed dump to a file
+    Extract {
+        id: String,
+
+        /// Output file (default: core.<id>)
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -101,6 +150,7 @@ async fn main() -> Result<()> {
             priority,
             unit,
             grep,
+            fields,
             lines,
             reverse,
             output,
@@ -113,12 +163,28 @@ async fn main() -> Result<()> {
                 _ => OutputFormat::Short,
             };
 
+            let priority = priority.and_then(|p| query::parse_priority(&p)).map(|p| p as u8);
+
+            if follow {
+                let request = IpcRequest::Follow {
+                    priority,
+                    facility: None,
+                    identifier: unit,
+                    pid: None,
+                    grep,
+                    fields,
+                    backlog: lines,
+                };
+                return follow_journal(&cli.socket, request, format).await;
+            }
+
             let request = IpcRequest::Query {
                 since,
                 until,
-                priority: priority.and_then(|p| query::parse_priority(&p)).map(|p| p as u8),
+                priority,
                 identifier: unit,
                 grep,
+                fields,
                 limit: Some(lines),
                 reverse,
             };
@@ -128,28 +194,7 @@ async fn main() -> Result<()> {
             match response {
                 IpcResponse::Entries(entries) => {
                     for entry in entries {
-                        let line = match format {
-                            OutputFormat::Short => format!(
-                                "{} {}[{}]: {}",
-                                entry.timestamp,
-                                entry.identifier,
-                                entry.pid.map(|p| p.to_string()).unwrap_or_default(),
-                                entry.message
-                            ),
-                            OutputFormat::Verbose => format!(
-                                "{} [{}] {}.{} {}[{}]: {}",
-                                entry.timestamp,
-                                entry.priority,
-                                entry.facility,
-                                entry.priority,
-                                entry.identifier,
-                                entry.pid.map(|p| p.to_string()).unwrap_or_default(),
-                                entry.message
-                            ),
-                            OutputFormat::Json => serde_json::to_string(&entry)?,
-                            OutputFormat::Cat => entry.message,
-                        };
-                        println!("{}", line);
+                        println!("{}", format_entry(entry, format)?);
                     }
                 }
                 IpcResponse::Error { message } => {
@@ -157,10 +202,6 @@ async fn main() -> Result<()> {
                 }
                 _ => {}
             }
-
-            if follow {
-                println!("-- Follow mode not yet implemented --");
-            }
         }
 
         Commands::DiskUsage => {
@@ -196,22 +237,49 @@ async fn main() -> Result<()> {
             print_simple_response(&response);
         }
 
-        Commands::Verify => {
-            let response = send_request(&cli.socket, IpcRequest::Verify).await?;
+        Commands::Verify { dir } => {
+            // Offline checks cannot verify keyed seals; those are reported
+            // as unverified rather than failing
+            let response = match dir {
+                Some(dir) => match storage::verify(std::path::Path::new(&dir), None) {
+                    Ok(result) => IpcResponse::VerifyResult {
+                        valid_entries: result.valid_entries,
+                        valid_archives: result.valid_archives,
+                        corrupted_files: result.corrupted_files,
+                        sealed_archives: result.sealed_archives,
+                        unverified_archives: result.unverified_archives,
+                        problems: result.problems,
+                    },
+                    Err(e) => IpcResponse::Error { message: e.to_string() },
+                },
+                None => send_request(&cli.socket, IpcRequest::Verify).await?,
+            };
 
             match response {
                 IpcResponse::VerifyResult {
                     valid_entries,
                     valid_archives,
                     corrupted_files,
+                    sealed_archives,
+                    unverified_archives,
+                    problems,
                 } => {
                     println!("Journal Verification:");
                     println!("  Valid entries:  {}", valid_entries);
                     println!("  Valid archives: {}", valid_archives);
+                    println!("  Sealed archives: {}", sealed_archives);
+                    if unverified_archives > 0 {
+                        println!("  Unverified seals: {} (key unavailable)", unverified_archives);
+                    }
                     println!("  Corrupted files: {}", corrupted_files);
 
-                    if corrupted_files > 0 {
-                        println!("\nWARNING: Corrupted files detected!");
+                    for problem in &problems {
+                        println!("  ! {}", problem);
+                    }
+
+                    if corrupted_files > 0 || !problems.is_empty() {
+                        println!("\nWARNING: Truncated or tampered journal files detected!");
+                        std::process::exit(1);
                     } else {
                         println!("\nJournal integrity: OK");
                     }
@@ -228,6 +296,79 @@ async fn main() -> Result<()> {
             print_simple_response(&response);
         }
 
+        Commands::ForwardStatus => {
+            let response = send_request(&cli.socket, IpcRequest::ForwardStatus).await?;
+
+            match response {
+                IpcResponse::ForwardStatus(stats) => {
+                    println!("Remote Forwarding:");
+                    println!("  Target:     {}", stats.target);
+                    println!("  Connected:  {}", if stats.connected { "yes" } else { "no" });
+                    println!("  Forwarded:  {}", stats.forwarded);
+                    println!("  Queued:     {}", stats.queued);
+                    println!("  Spooled:    {}", stats.spooled);
+                    println!("  Spool size: {}", storage::DiskUsage::format_size(stats.spool_bytes));
+                    println!("  Dropped:    {}", stats.dropped);
+                    println!("  Reconnects: {}", stats.reconnects);
+                    if let Some(error) = stats.last_error {
+                        println!("  Last error: {}", error);
+                    }
+                }
+                IpcResponse::Error { message } => {
+                    eprintln!("Error: {}", message);
+                }
+                _ => {}
+            }
+        }
+
+        Commands::Coredump { command } => match command {
+            CoredumpCommand::List { unit, lines } => {
+                let request = IpcRequest::Coredumps { unit, limit: Some(lines) };
+                match send_request(&cli.socket, request).await? {
+                    IpcResponse::Coredumps(dumps) => {
+                        println!(
+                            "{:<20} {:<25} {:>7} {:<8} {:<20} {:>10}",
+                            "ID", "TIME", "PID", "SIGNAL", "UNIT", "SIZE"
+                        );
+                        for dump in dumps {
+                            println!(
+                                "{:<20} {:<25} {:>7} {:<8} {:<20} {:>10}",
+                                dump.id,
+                                dump.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                                dump.pid,
+                                coredump::signal_name(dump.signal),
+                                dump.unit.as_deref().unwrap_or(&dump.comm),
+                                if dump.stored {
+                                    storage::DiskUsage::format_size(dump.compressed_size)
+                                } else {
+                                    "-".to_string()
+                                },
+                            );
+                        }
+                    }
+                    IpcResponse::Error { message } => eprintln!("Error: {}", message),
+                    _ => {}
+                }
+            }
+
+            CoredumpCommand::Info { id } => {
+                let request = IpcRequest::Coredumps { unit: None, limit: None };
+                match send_request(&cli.socket, request).await? {
+                    IpcResponse::Coredumps(dumps) => match dumps.into_iter().find(|d| d.id == id) {
+                        Some(dump) => print_coredump(&dump),
+                        None => eprintln!("Error: No core dump {}", id),
+                    },
+                    IpcResponse::Error { message } => eprintln!("Error: {}", message),
+                    _ => {}
+                }
+            }
+
+            CoredumpCommand::Extract { id, output } => {
+                let output = output.unwrap_or_else(|| format!("core.{}", id));
+                extract_coredump(&cli.socket, &id, &output).await?;
+            }
+        },
+
         Commands::Dmesg { lines } => {
             let request = IpcRequest::Query {
                 since: None,
@@ -235,6 +376,7 @@ async fn main() -> Result<()> {
                 priority: None,
                 identifier: Some("kernel".to_string()),
                 grep: None,
+                fields: Vec::new(),
                 limit: Some(lines),
                 reverse: false,
             };
@@ -273,6 +415,112 @@ async fn send_request(socket_path: &str, request: IpcRequest) -> Result<IpcRespo
     Ok(serde_json::from_str(&line)?)
 }
 
+/// Stream entries from a follow subscription until interrupted
+async fn follow_journal(socket_path: &str, request: IpcRequest, format: OutputFormat) -> Result<()> {
+    let mut stream = UnixStream::connect(socket_path).await?;
+
+    let json = serde_json::to_string(&request)?;
+    stream.write_all(json.as_bytes()).await?;
+    stream.write_all(b"\n").await?;
+    stream.flush().await?;
+
+    let mut lines = BufReader::new(stream).lines();
+    while let Some(line) = lines.next_line().await? {
+        match serde_json::from_str(&line)? {
+            IpcResponse::Entry(entry) => println!("{}", format_entry(entry, format)?),
+            IpcResponse::Dropped { count } => {
+                eprintln!("-- {} entries dropped, output fell behind --", count)
+            }
+            IpcResponse::Error { message } => {
+                eprintln!("Error: {}", message);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Receive a dump's metadata, then stream the dump into `output`
+async fn extract_coredump(socket_path: &str, id: &str, output: &str) -> Result<()> {
+    let mut stream = UnixStream::connect(socket_path).await?;
+
+    let json = serde_json::to_string(&IpcRequest::ExtractCoredump { id: id.to_string() })?;
+    stream.write_all(json.as_bytes()).await?;
+    stream.write_all(b"\n").await?;
+    stream.flush().await?;
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+
+    match serde_json::from_str(&line)? {
+        IpcResponse::Coredump(info) => {
+            let mut file = tokio::fs::File::create(output).await?;
+            let written = tokio::io::copy(&mut reader, &mut file).await?;
+            file.flush().await?;
+
+            if written != info.size {
+                anyhow::bail!("Core dump {} was cut short ({} of {} bytes)", id, written, info.size);
+            }
+            println!("Wrote core dump of {} ({}) to {}", info.pid, info.comm, output);
+            if let Some(exe) = &info.exe {
+                println!("Debug with: gdb {} {}", exe, output);
+            }
+        }
+        IpcResponse::Error { message } => eprintln!("Error: {}", message),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn print_coredump(dump: &CoredumpInfo) {
+    println!("Core dump {}:", dump.id);
+    println!("  Time:     {}", dump.timestamp.to_rfc3339());
+    println!("  PID:      {}", dump.pid);
+    println!("  UID/GID:  {}/{}", dump.uid, dump.gid);
+    println!("  Signal:   {}", coredump::signal_name(dump.signal));
+    println!("  Command:  {}", dump.comm);
+    println!("  Exe:      {}", dump.exe.as_deref().unwrap_or("-"));
+    println!("  Unit:     {}", dump.unit.as_deref().unwrap_or("-"));
+    println!("  Build-id: {}", dump.build_id.as_deref().unwrap_or("-"));
+    println!("  Size:     {}", storage::DiskUsage::format_size(dump.size));
+    if dump.stored {
+        println!("  Stored:   {}", storage::DiskUsage::format_size(dump.compressed_size));
+    } else {
+        println!("  Stored:   no (over the size limit)");
+    }
+}
+
+fn format_entry(entry: LogEntryInfo, format: OutputFormat) -> Result<String> {
+    let pid = entry.pid.map(|p| p.to_string()).unwrap_or_default();
+    Ok(match format {
+        OutputFormat::Short => format!(
+            "{} {}[{}]: {}",
+            entry.timestamp, entry.identifier, pid, entry.message
+        ),
+        OutputFormat::Verbose => {
+            let mut line = format!(
+                "{} [{}] {}.{} {}[{}]: {}",
+                entry.timestamp,
+                entry.priority,
+                entry.facility,
+                entry.priority,
+                entry.identifier,
+                pid,
+                entry.message
+            );
+            for (key, value) in &entry.fields {
+                line.push_str(&format!("\n    {}={}", key, value));
+            }
+            line
+        }
+        OutputFormat::Json => serde_json::to_string(&entry)?,
+        OutputFormat::Cat => entry.message,
+    })
+}
+
 fn print_simple_response(response: &IpcResponse) {
     match response {
         IpcResponse::Success { message } => println!("{}", message),
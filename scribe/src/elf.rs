@@ -0,0 +1,164 @@
+//! ELF notes
+//!
+//! Just enough ELF to find an executable's build-id and the process
+//! details the kernel leaves in a core file's notes. Only 64-bit
+//! little-endian files are understood, which covers every Nyx target.
+
+use anyhow::{bail, Result};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+const PT_NOTE: u32 = 4;
+
+/// GNU build-id note type
+const NT_GNU_BUILD_ID: u32 = 3;
+/// Process status note type
+const NT_PRSTATUS: u32 = 1;
+/// Process info note type
+const NT_PRPSINFO: u32 = 3;
+
+/// Larger note segments are not read
+const MAX_NOTES_SIZE: u64 = 16 * 1024 * 1024;
+
+/// An ELF note
+#[derive(Debug, Clone)]
+pub struct Note {
+    pub name: String,
+    pub kind: u32,
+    pub desc: Vec<u8>,
+}
+
+/// Read every note in the file's PT_NOTE segments
+pub fn read_notes<R: Read + Seek>(file: &mut R) -> Result<Vec<Note>> {
+    let mut header = [0u8; 64];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut header)?;
+
+    if &header[..4] != b"\x7fELF" {
+        bail!("not an ELF file");
+    }
+    if header[4] != 2 || header[5] != 1 {
+        bail!("only 64-bit little-endian ELF is supported");
+    }
+
+    let phoff = u64_at(&header, 32);
+    let phentsize = u16_at(&header, 54) as u64;
+    let phnum = u16_at(&header, 56) as u64;
+    if phentsize < 56 {
+        bail!("bad program header size {}", phentsize);
+    }
+
+    let mut segments = Vec::new();
+    let mut phdr = [0u8; 56];
+    for i in 0..phnum {
+        file.seek(SeekFrom::Start(phoff + i * phentsize))?;
+        file.read_exact(&mut phdr)?;
+        if u32_at(&phdr, 0) == PT_NOTE {
+            segments.push((u64_at(&phdr, 8), u64_at(&phdr, 32)));
+        }
+    }
+
+    let mut notes = Vec::new();
+    for (offset, size) in segments {
+        if size > MAX_NOTES_SIZE {
+            bail!("note segment of {} bytes is too large", size);
+        }
+        let mut data = vec![0u8; size as usize];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut data)?;
+        notes.extend(parse_notes(&data));
+    }
+    Ok(notes)
+}
+
+/// Notes are a header of three u32s, then name and descriptor, each
+/// padded to four bytes
+fn parse_notes(mut data: &[u8]) -> Vec<Note> {
+    let mut notes = Vec::new();
+    while data.len() >= 12 {
+        let namesz = u32_at(data, 0) as usize;
+        let descsz = u32_at(data, 4) as usize;
+        let kind = u32_at(data, 8);
+
+        let name_end = 12 + namesz;
+        let desc_start = 12 + align4(namesz);
+        let desc_end = desc_start + descsz;
+        if desc_end > data.len() {
+            break;
+        }
+
+        let name = String::from_utf8_lossy(&data[12..name_end]).trim_end_matches('\0').to_string();
+        notes.push(Note {
+            name,
+            kind,
+            desc: data[desc_start..desc_end].to_vec(),
+        });
+        data = &data[align4(desc_end).min(data.len())..];
+    }
+    notes
+}
+
+/// Hex build-id of an executable, if it has one
+pub fn build_id(path: &Path) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    read_notes(&mut file)
+        .ok()?
+        .into_iter()
+        .find(|note| note.name == "GNU" && note.kind == NT_GNU_BUILD_ID)
+        .map(|note| note.desc.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// What a core file's notes say about the process
+#[derive(Debug, Clone, Default)]
+pub struct CoreProcess {
+    pub pid: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub signal: Option<i32>,
+    pub comm: Option<String>,
+}
+
+/// Read the NT_PRSTATUS and NT_PRPSINFO notes of a core file
+pub fn core_process<R: Read + Seek>(file: &mut R) -> Result<CoreProcess> {
+    let mut process = CoreProcess::default();
+
+    for note in read_notes(file)? {
+        if note.name != "CORE" {
+            continue;
+        }
+        let desc = &note.desc;
+        match note.kind {
+            // The first thread's status carries the signal
+            NT_PRSTATUS if process.signal.is_none() && desc.len() >= 36 => {
+                process.signal = Some(u32_at(desc, 0) as i32);
+                process.pid = Some(u32_at(desc, 32));
+            }
+            NT_PRPSINFO if desc.len() >= 56 => {
+                process.uid = Some(u32_at(desc, 16));
+                process.gid = Some(u32_at(desc, 20));
+                process.pid = Some(u32_at(desc, 24));
+                let fname = &desc[40..56];
+                let len = fname.iter().position(|&b| b == 0).unwrap_or(fname.len());
+                process.comm = Some(String::from_utf8_lossy(&fname[..len]).to_string());
+            }
+            _ => {}
+        }
+    }
+    Ok(process)
+}
+
+fn align4(n: usize) -> usize {
+    (n + 3) & !3
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
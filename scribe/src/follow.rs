@@ -0,0 +1,97 @@
+//! Live tail subscriptions
+//!
+//! Followers subscribe with a filter and are handed every matching entry
+//! as it is written. Each follower has a bounded queue; when a slow client
+//! lets it fill up, further entries are dropped and counted rather than
+//! blocking the writers, and the client is told how many it missed.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+use crate::journal::{JournalFilter, LogEntry};
+
+/// Registered followers
+pub struct Followers {
+    buffer: usize,
+    subscribers: Mutex<Vec<Subscriber>>,
+    next_id: AtomicU64,
+}
+
+struct Subscriber {
+    id: u64,
+    filter: JournalFilter,
+    tx: mpsc::Sender<LogEntry>,
+    dropped: Arc<AtomicU64>,
+}
+
+/// Receiving end of a subscription
+pub struct Follow {
+    pub id: u64,
+    rx: mpsc::Receiver<LogEntry>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl Follow {
+    /// Next matching entry; `None` once unsubscribed
+    pub async fn recv(&mut self) -> Option<LogEntry> {
+        self.rx.recv().await
+    }
+
+    /// Entries dropped since the last call
+    pub fn take_dropped(&self) -> u64 {
+        self.dropped.swap(0, Ordering::Relaxed)
+    }
+}
+
+impl Followers {
+    pub fn new(buffer: usize) -> Self {
+        Self {
+            buffer: buffer.max(1),
+            subscribers: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Start following entries that match `filter`
+    pub fn subscribe(&self, filter: JournalFilter) -> Follow {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::channel(self.buffer);
+        let dropped = Arc::new(AtomicU64::new(0));
+
+        self.subscribers.lock().unwrap().push(Subscriber {
+            id,
+            filter,
+            tx,
+            dropped: dropped.clone(),
+        });
+
+        Follow { id, rx, dropped }
+    }
+
+    pub fn unsubscribe(&self, id: u64) {
+        self.subscribers.lock().unwrap().retain(|s| s.id != id);
+    }
+
+    /// Hand a freshly written entry to every interested follower
+    pub fn publish(&self, entry: &LogEntry) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        if subscribers.is_empty() {
+            return;
+        }
+
+        subscribers.retain(|s| {
+            if !s.filter.matches(entry) {
+                return !s.tx.is_closed();
+            }
+            match s.tx.try_send(entry.clone()) {
+                Ok(()) => true,
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    s.dropped.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => false,
+            }
+        });
+    }
+}
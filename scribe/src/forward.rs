@@ -0,0 +1,699 @@
+//! Remote log forwarding
+//!
+//! Entries matching the forwarding filter are queued for a single remote
+//! target: another scribe (JSON lines) or a syslog collector (RFC5424
+//! over TCP, octet-counted). Either can run over TLS. While the target is
+//! unreachable, or the queue is full, entries go to an on-disk spool that
+//! is drained first once the connection is back; when the spool reaches
+//! its limit further entries are dropped and counted. Delivery counters
+//! are pushed to sentinel.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UnixStream};
+use tokio::sync::mpsc;
+use tokio_rustls::rustls;
+use tracing::{debug, info, warn};
+
+use crate::journal::{JournalFilter, LogEntry};
+
+/// Spool segments roll over at this size
+const SEGMENT_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Longest wait between reconnect attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How often delivery counters are pushed to sentinel
+const REPORT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Wire format spoken to the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    /// Newline-delimited JSON entries, for a receiving scribe
+    Scribe,
+    /// RFC5424 with RFC6587 octet counting
+    Syslog,
+}
+
+/// Where entries are forwarded
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub protocol: Protocol,
+    pub tls: bool,
+    pub host: String,
+    pub port: u16,
+}
+
+impl Target {
+    /// Parse `scribe://`, `scribe+tls://`, `syslog+tcp://` or `syslog+tls://`
+    pub fn parse(url: &str) -> Result<Self> {
+        let (scheme, rest) = url
+            .split_once("://")
+            .ok_or_else(|| anyhow!("forward target must be a URL: {}", url))?;
+
+        let (protocol, tls, default_port) = match scheme {
+            "scribe" => (Protocol::Scribe, false, 6514),
+            "scribe+tls" => (Protocol::Scribe, true, 6514),
+            "syslog" | "syslog+tcp" => (Protocol::Syslog, false, 514),
+            "syslog+tls" => (Protocol::Syslog, true, 6514),
+            other => return Err(anyhow!("unsupported forward scheme: {}", other)),
+        };
+
+        let rest = rest.trim_end_matches('/');
+        let (host, port) = match rest.strip_prefix('[') {
+            // [v6-address]:port
+            Some(v6) => {
+                let (host, after) = v6
+                    .split_once(']')
+                    .ok_or_else(|| anyhow!("invalid address in {}", url))?;
+                (host, after.strip_prefix(':'))
+            }
+            None => match rest.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (rest, None),
+            },
+        };
+        let port = match port {
+            Some(port) => port.parse().map_err(|_| anyhow!("invalid port in {}", url))?,
+            None => default_port,
+        };
+        if host.is_empty() {
+            return Err(anyhow!("forward target has no host: {}", url));
+        }
+
+        Ok(Self {
+            protocol,
+            tls,
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let scheme = match (self.protocol, self.tls) {
+            (Protocol::Scribe, false) => "scribe",
+            (Protocol::Scribe, true) => "scribe+tls",
+            (Protocol::Syslog, false) => "syslog+tcp",
+            (Protocol::Syslog, true) => "syslog+tls",
+        };
+        write!(f, "{}://{}:{}", scheme, self.host, self.port)
+    }
+}
+
+/// Forwarding settings
+#[derive(Debug, Clone)]
+pub struct ForwardConfig {
+    pub target: Target,
+    /// Only matching entries are forwarded
+    pub filter: JournalFilter,
+    /// Entries held in memory before spilling to the spool
+    pub buffer: usize,
+    pub spool_dir: PathBuf,
+    pub spool_max_bytes: u64,
+    /// CA bundle for TLS targets
+    pub ca_file: PathBuf,
+    pub sentinel_socket: Option<String>,
+}
+
+/// Delivery counters
+#[derive(Default)]
+struct Counters {
+    forwarded: AtomicU64,
+    spooled: AtomicU64,
+    dropped: AtomicU64,
+    reconnects: AtomicU64,
+    connected: AtomicBool,
+    last_error: Mutex<Option<String>>,
+}
+
+/// Snapshot of delivery state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForwardStats {
+    pub target: String,
+    pub connected: bool,
+    pub forwarded: u64,
+    pub spooled: u64,
+    pub dropped: u64,
+    pub reconnects: u64,
+    pub queued: u64,
+    pub spool_bytes: u64,
+    pub last_error: Option<String>,
+}
+
+/// Handle held by the daemon state
+pub struct Forwarder {
+    target: Target,
+    filter: JournalFilter,
+    tx: mpsc::Sender<LogEntry>,
+    spool: Arc<Spool>,
+    counters: Arc<Counters>,
+}
+
+impl Forwarder {
+    /// Open the spool and start delivering
+    pub fn start(config: ForwardConfig) -> Result<Self> {
+        let spool = Arc::new(Spool::open(&config.spool_dir, config.spool_max_bytes)?);
+        let counters = Arc::new(Counters::default());
+        let (tx, rx) = mpsc::channel(config.buffer.max(1));
+
+        let tls = if config.target.tls {
+            Some(tls_connector(&config.ca_file)?)
+        } else {
+            None
+        };
+
+        let pending = spool.bytes();
+        if pending > 0 {
+            info!("Forward spool holds {} bytes from a previous run", pending);
+        }
+
+        tokio::spawn(deliver(
+            config.target.clone(),
+            tls,
+            rx,
+            spool.clone(),
+            counters.clone(),
+        ));
+
+        let forwarder = Self {
+            target: config.target,
+            filter: config.filter,
+            tx,
+            spool,
+            counters,
+        };
+
+        if let Some(socket) = config.sentinel_socket {
+            tokio::spawn(report_loop(socket, forwarder.reporter()));
+        }
+
+        Ok(forwarder)
+    }
+
+    /// Queue an entry if it passes the filter; never blocks the writer
+    pub fn offer(&self, entry: &LogEntry) {
+        if !self.filter.matches(entry) {
+            return;
+        }
+
+        match self.tx.try_send(entry.clone()) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(entry)) => {
+                spool_entry(&self.spool, &self.counters, &entry);
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn stats(&self) -> ForwardStats {
+        snapshot(&self.target.to_string(), &self.tx, &self.spool, &self.counters)
+    }
+
+    fn reporter(&self) -> Reporter {
+        Reporter {
+            target: self.target.to_string(),
+            tx: self.tx.downgrade(),
+            spool: self.spool.clone(),
+            counters: self.counters.clone(),
+        }
+    }
+}
+
+/// What the sentinel report loop needs to read the counters
+struct Reporter {
+    target: String,
+    tx: mpsc::WeakSender<LogEntry>,
+    spool: Arc<Spool>,
+    counters: Arc<Counters>,
+}
+
+impl Reporter {
+    /// `None` once the forwarder is gone
+    fn stats(&self) -> Option<ForwardStats> {
+        let tx = self.tx.upgrade()?;
+        Some(snapshot(&self.target, &tx, &self.spool, &self.counters))
+    }
+}
+
+fn snapshot(
+    target: &str,
+    tx: &mpsc::Sender<LogEntry>,
+    spool: &Spool,
+    counters: &Counters,
+) -> ForwardStats {
+    ForwardStats {
+        target: target.to_string(),
+        connected: counters.connected.load(Ordering::Relaxed),
+        forwarded: counters.forwarded.load(Ordering::Relaxed),
+        spooled: counters.spooled.load(Ordering::Relaxed),
+        dropped: counters.dropped.load(Ordering::Relaxed),
+        reconnects: counters.reconnects.load(Ordering::Relaxed),
+        queued: (tx.max_capacity() - tx.capacity()) as u64,
+        spool_bytes: spool.bytes(),
+        last_error: counters.last_error.lock().unwrap().clone(),
+    }
+}
+
+fn spool_entry(spool: &Spool, counters: &Counters, entry: &LogEntry) {
+    if spool.push(entry) {
+        counters.spooled.fetch_add(1, Ordering::Relaxed);
+    } else {
+        counters.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+type Connection = tokio::io::BufWriter<Box<dyn AsyncWrite + Send + Unpin>>;
+
+/// Delivery loop: connect, drain the spool, then stream live entries
+async fn deliver(
+    target: Target,
+    tls: Option<tokio_rustls::TlsConnector>,
+    mut rx: mpsc::Receiver<LogEntry>,
+    spool: Arc<Spool>,
+    counters: Arc<Counters>,
+) {
+    let hostname = local_hostname();
+    let mut backoff = Duration::from_secs(1);
+
+    loop {
+        let result = match connect(&target, tls.as_ref()).await {
+            Ok(conn) => {
+                info!("Forwarding to {}", target);
+                counters.connected.store(true, Ordering::Relaxed);
+                backoff = Duration::from_secs(1);
+
+                let result = pump(conn, &target, &hostname, &mut rx, &spool, &counters).await;
+                counters.connected.store(false, Ordering::Relaxed);
+                counters.reconnects.fetch_add(1, Ordering::Relaxed);
+                result
+            }
+            Err(e) => Err(e),
+        };
+
+        match result {
+            // Channel closed: the daemon is shutting down
+            Ok(()) => return,
+            Err(e) => {
+                warn!("Forwarding to {} interrupted: {}", target, e);
+                *counters.last_error.lock().unwrap() = Some(e.to_string());
+            }
+        }
+
+        // Keep the queue moving into the spool until the next attempt
+        let wait = tokio::time::sleep(backoff);
+        tokio::pin!(wait);
+        loop {
+            tokio::select! {
+                _ = &mut wait => break,
+                entry = rx.recv() => match entry {
+                    Some(entry) => spool_entry(&spool, &counters, &entry),
+                    None => return,
+                },
+            }
+        }
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+async fn pump(
+    mut conn: Connection,
+    target: &Target,
+    hostname: &str,
+    rx: &mut mpsc::Receiver<LogEntry>,
+    spool: &Spool,
+    counters: &Counters,
+) -> Result<()> {
+    loop {
+        // Older, spooled entries go out first
+        while let Some(segment) = spool.take_oldest()? {
+            let entries = Spool::read(&segment)?;
+            for entry in &entries {
+                conn.write_all(&encode(target.protocol, entry, hostname)).await?;
+            }
+            conn.flush().await?;
+            spool.remove(&segment);
+            counters.forwarded.fetch_add(entries.len() as u64, Ordering::Relaxed);
+            debug!("Forwarded {} spooled entries", entries.len());
+        }
+
+        let Some(entry) = rx.recv().await else {
+            let _ = conn.flush().await;
+            return Ok(());
+        };
+
+        let mut batch = vec![entry];
+        while batch.len() < 256 {
+            match rx.try_recv() {
+                Ok(entry) => batch.push(entry),
+                Err(_) => break,
+            }
+        }
+
+        let sent = async {
+            for entry in &batch {
+                conn.write_all(&encode(target.protocol, entry, hostname)).await?;
+            }
+            conn.flush().await
+        }
+        .await;
+
+        if let Err(e) = sent {
+            // Delivery of the batch is unknown; resend it after reconnecting
+            for entry in &batch {
+                spool_entry(spool, counters, entry);
+            }
+            return Err(e.into());
+        }
+        counters.forwarded.fetch_add(batch.len() as u64, Ordering::Relaxed);
+    }
+}
+
+async fn connect(target: &Target, tls: Option<&tokio_rustls::TlsConnector>) -> Result<Connection> {
+    let tcp = tokio::time::timeout(
+        Duration::from_secs(10),
+        TcpStream::connect((target.host.as_str(), target.port)),
+    )
+    .await
+    .map_err(|_| anyhow!("connection timed out"))??;
+    tcp.set_nodelay(true)?;
+
+    let stream: Box<dyn AsyncWrite + Send + Unpin> = match tls {
+        Some(connector) => {
+            let name = rustls::pki_types::ServerName::try_from(target.host.clone())?;
+            Box::new(connector.connect(name, tcp).await?)
+        }
+        None => Box::new(tcp),
+    };
+
+    Ok(tokio::io::BufWriter::new(stream))
+}
+
+fn tls_connector(ca_file: &Path) -> Result<tokio_rustls::TlsConnector> {
+    use rustls::pki_types::CertificateDer;
+    use rustls::pki_types::pem::PemObject;
+
+    let mut roots = rustls::RootCertStore::empty();
+    for cert in CertificateDer::pem_file_iter(ca_file)
+        .map_err(|e| anyhow!("reading {}: {}", ca_file.display(), e))?
+    {
+        roots.add(cert.map_err(|e| anyhow!("reading {}: {}", ca_file.display(), e))?)?;
+    }
+
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+
+    Ok(tokio_rustls::TlsConnector::from(Arc::new(config)))
+}
+
+/// Frame an entry for the wire
+fn encode(protocol: Protocol, entry: &LogEntry, hostname: &str) -> Vec<u8> {
+    match protocol {
+        Protocol::Scribe => {
+            let mut line = if entry.hostname.is_some() {
+                serde_json::to_vec(entry)
+            } else {
+                let mut entry = entry.clone();
+                entry.hostname = Some(hostname.to_string());
+                serde_json::to_vec(&entry)
+            }
+            .unwrap_or_default();
+            line.push(b'\n');
+            line
+        }
+        Protocol::Syslog => {
+            let message = rfc5424(entry, hostname);
+            format!("{} {}", message.len(), message).into_bytes()
+        }
+    }
+}
+
+/// SD-ID for scribe's fields (RFC5424 documentation enterprise number)
+const SD_ID: &str = "scribe@32473";
+
+fn rfc5424(entry: &LogEntry, hostname: &str) -> String {
+    let pri = (entry.facility as u8 as u32) * 8 + entry.priority as u8 as u32;
+    let msgid = match entry.fields.get("msgid") {
+        Some(msgid) => header_field(&msgid.to_string(), 32),
+        None => "-".to_string(),
+    };
+
+    let mut structured = String::new();
+    let mut fields: Vec<_> = entry.fields.iter().filter(|(k, _)| *k != "msgid").collect();
+    if fields.is_empty() {
+        structured.push('-');
+    } else {
+        fields.sort_by(|a, b| a.0.cmp(b.0));
+        structured.push('[');
+        structured.push_str(SD_ID);
+        for (key, value) in fields {
+            let value = value.to_string();
+            structured.push(' ');
+            structured.push_str(&sd_name(key));
+            structured.push_str("=\"");
+            for c in value.chars() {
+                if matches!(c, '"' | '\\' | ']') {
+                    structured.push('\\');
+                }
+                structured.push(c);
+            }
+            structured.push('"');
+        }
+        structured.push(']');
+    }
+
+    format!(
+        "<{}>1 {} {} {} {} {} {} {}",
+        pri,
+        entry.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+        header_field(entry.hostname.as_deref().unwrap_or(hostname), 255),
+        header_field(&entry.identifier, 48),
+        entry.pid.map(|p| p.to_string()).unwrap_or_else(|| "-".to_string()),
+        msgid,
+        structured,
+        entry.message
+    )
+}
+
+/// Header fields are printable ASCII without spaces
+fn header_field(value: &str, max: usize) -> String {
+    let value: String = value
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max)
+        .collect();
+    if value.is_empty() { "-".to_string() } else { value }
+}
+
+/// SD parameter names exclude `=`, space, `]` and `"`, up to 32 chars
+fn sd_name(key: &str) -> String {
+    key.chars()
+        .map(|c| if c.is_ascii_graphic() && !matches!(c, '=' | ']' | '"') { c } else { '_' })
+        .take(32)
+        .collect()
+}
+
+fn local_hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|h| h.trim().to_string())
+        .ok()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+/// Push delivery counters to sentinel
+async fn report_loop(socket: String, reporter: Reporter) {
+    let mut interval = tokio::time::interval(REPORT_INTERVAL);
+
+    loop {
+        interval.tick().await;
+        let Some(stats) = reporter.stats() else {
+            return;
+        };
+
+        let request = serde_json::json!({
+            "type": "ReportMetrics",
+            "source": "scribe.forward",
+            "metrics": {
+                "connected": if stats.connected { 1.0 } else { 0.0 },
+                "forwarded": stats.forwarded as f64,
+                "spooled": stats.spooled as f64,
+                "dropped": stats.dropped as f64,
+                "reconnects": stats.reconnects as f64,
+                "queued": stats.queued as f64,
+                "spool_bytes": stats.spool_bytes as f64,
+            },
+        });
+
+        if let Err(e) = send_report(&socket, &request).await {
+            debug!("Could not report forwarding metrics to sentinel: {}", e);
+        }
+    }
+}
+
+async fn send_report(socket: &str, request: &serde_json::Value) -> Result<()> {
+    use tokio::io::AsyncBufReadExt;
+
+    let mut stream = tokio::io::BufReader::new(UnixStream::connect(socket).await?);
+    stream.get_mut().write_all(format!("{}\n", request).as_bytes()).await?;
+
+    let mut line = String::new();
+    stream.read_line(&mut line).await?;
+    Ok(())
+}
+
+/// On-disk queue of entries awaiting delivery
+///
+/// Entries are appended to numbered JSON-lines segments; the forwarder
+/// takes whole segments oldest first and deletes each once sent.
+struct Spool {
+    dir: PathBuf,
+    max_bytes: u64,
+    inner: Mutex<SpoolInner>,
+}
+
+struct SpoolInner {
+    writer: Option<(PathBuf, BufWriter<File>, u64)>,
+    next_seq: u64,
+    bytes: u64,
+}
+
+impl Spool {
+    fn open(dir: &Path, max_bytes: u64) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+
+        let mut bytes = 0;
+        let mut next_seq = 1;
+        for segment in Self::segments(dir)? {
+            bytes += fs::metadata(&segment).map(|m| m.len()).unwrap_or(0);
+            if let Some(seq) = Self::sequence(&segment) {
+                next_seq = next_seq.max(seq + 1);
+            }
+        }
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            max_bytes,
+            inner: Mutex::new(SpoolInner {
+                writer: None,
+                next_seq,
+                bytes,
+            }),
+        })
+    }
+
+    fn segments(dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut segments: Vec<_> = fs::read_dir(dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| Self::sequence(p).is_some())
+            .collect();
+        segments.sort_by_key(|p| Self::sequence(p));
+        Ok(segments)
+    }
+
+    fn sequence(path: &Path) -> Option<u64> {
+        path.file_name()?
+            .to_str()?
+            .strip_prefix("spool-")?
+            .strip_suffix(".jsonl")?
+            .parse()
+            .ok()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.inner.lock().unwrap().bytes
+    }
+
+    /// Append an entry; false if the spool is full or unwritable
+    fn push(&self, entry: &LogEntry) -> bool {
+        let Ok(mut line) = serde_json::to_vec(entry) else {
+            return false;
+        };
+        line.push(b'\n');
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.bytes + line.len() as u64 > self.max_bytes {
+            return false;
+        }
+
+        let roll = inner
+            .writer
+            .as_ref()
+            .is_none_or(|(_, _, size)| *size >= SEGMENT_SIZE);
+        if roll {
+            if let Some((_, mut writer, _)) = inner.writer.take() {
+                let _ = writer.flush();
+            }
+            let path = self.dir.join(format!("spool-{:010}.jsonl", inner.next_seq));
+            match OpenOptions::new().create(true).append(true).open(&path) {
+                Ok(file) => {
+                    inner.next_seq += 1;
+                    inner.writer = Some((path, BufWriter::new(file), 0));
+                }
+                Err(e) => {
+                    warn!("Cannot open forward spool segment: {}", e);
+                    return false;
+                }
+            }
+        }
+
+        let (_, writer, size) = inner.writer.as_mut().expect("spool segment open");
+        if writer.write_all(&line).is_err() {
+            return false;
+        }
+        *size += line.len() as u64;
+        inner.bytes += line.len() as u64;
+        true
+    }
+
+    /// Oldest segment ready to send, closing it if it is being written
+    fn take_oldest(&self) -> Result<Option<PathBuf>> {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(oldest) = Self::segments(&self.dir)?.into_iter().next() else {
+            return Ok(None);
+        };
+
+        if inner.writer.as_ref().is_some_and(|(path, _, _)| *path == oldest) {
+            if let Some((_, mut writer, _)) = inner.writer.take() {
+                writer.flush()?;
+            }
+        }
+        Ok(Some(oldest))
+    }
+
+    fn read(segment: &Path) -> Result<Vec<LogEntry>> {
+        let reader = BufReader::new(File::open(segment)?);
+        let mut entries = Vec::new();
+        for line in reader.lines() {
+            // A torn final line from a crash is skipped
+            if let Ok(entry) = serde_json::from_str(&line?) {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    fn remove(&self, segment: &Path) {
+        let size = fs::metadata(segment).map(|m| m.len()).unwrap_or(0);
+        if fs::remove_file(segment).is_ok() {
+            let mut inner = self.inner.lock().unwrap();
+            inner.bytes = inner.bytes.saturating_sub(size);
+        }
+    }
+}
@@ -1,16 +1,22 @@
 //! IPC interface for Scribe daemon
 
 use anyhow::Result;
+use libnyx_ipc::audit::{AuditEvent, AuditOutcome, AUDIT_IDENTIFIER};
+use libnyx_ipc::Hello;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::RwLock;
 use tracing::{info, error};
 
 use crate::state::ScribeState;
-use crate::journal::{LogEntry, Priority, Facility, JournalFilter};
+use crate::journal::{LogEntry, Priority, Facility, FieldValue, FieldMatch, JournalFilter};
 use crate::storage;
+use crate::coredump::{CoredumpInfo, CoredumpStore};
+use crate::forward::ForwardStats;
 
 /// IPC request
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +31,9 @@ pub enum IpcRequest {
         pid: Option<u32>,
     },
 
+    /// Record a privileged operation on the audit bus (root only)
+    Audit { event: AuditEvent },
+
     /// Query logs
     Query {
         since: Option<String>,
@@ -32,10 +41,30 @@ pub enum IpcRequest {
         priority: Option<u8>,
         identifier: Option<String>,
         grep: Option<String>,
+        /// Structured field matches (`KEY=VALUE` or `KEY`)
+        #[serde(default)]
+        fields: Vec<String>,
         limit: Option<usize>,
         reverse: bool,
     },
 
+    /// Follow new entries as they are written
+    ///
+    /// The connection then streams `Entry` (and `Dropped`) responses until
+    /// the client disconnects.
+    Follow {
+        priority: Option<u8>,
+        facility: Option<u8>,
+        identifier: Option<String>,
+        pid: Option<u32>,
+        grep: Option<String>,
+        #[serde(default)]
+        fields: Vec<String>,
+        /// Recent matching entries to send before following
+        #[serde(default)]
+        backlog: usize,
+    },
+
     /// Get disk usage
     DiskUsage,
 
@@ -50,6 +79,24 @@ pub enum IpcRequest {
 
     /// Flush to disk
     Flush,
+
+    /// Remote forwarding delivery state
+    ForwardStatus,
+
+    /// A crash nyx-coredump has stored (root only)
+    CoredumpStored { info: CoredumpInfo },
+
+    /// Stored crashes, newest first; users see only their own
+    Coredumps {
+        unit: Option<String>,
+        limit: Option<usize>,
+    },
+
+    /// Fetch a stored dump
+    ///
+    /// Answered with `Coredump`, followed by the decompressed dump until
+    /// the connection closes.
+    ExtractCoredump { id: String },
 }
 
 /// IPC response
@@ -58,6 +105,10 @@ pub enum IpcRequest {
 pub enum IpcResponse {
     Success { message: String },
     Entries(Vec<LogEntryInfo>),
+    /// A followed entry
+    Entry(LogEntryInfo),
+    /// Followed entries lost because the client fell behind
+    Dropped { count: u64 },
     DiskUsage {
         total_size: u64,
         current_size: u64,
@@ -68,7 +119,17 @@ pub enum IpcResponse {
         valid_entries: u64,
         valid_archives: u64,
         corrupted_files: u64,
+        #[serde(default)]
+        sealed_archives: u64,
+        #[serde(default)]
+        unverified_archives: u64,
+        #[serde(default)]
+        problems: Vec<String>,
     },
+    ForwardStatus(ForwardStats),
+    Coredumps(Vec<CoredumpInfo>),
+    /// Precedes the bytes of an extracted dump
+    Coredump(CoredumpInfo),
     Error { message: String },
 }
 
@@ -80,6 +141,8 @@ pub struct LogEntryInfo {
     pub identifier: String,
     pub message: String,
     pub pid: Option<u32>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, FieldValue>,
 }
 
 impl From<&LogEntry> for LogEntryInfo {
@@ -91,6 +154,7 @@ impl From<&LogEntry> for LogEntryInfo {
             identifier: entry.identifier.clone(),
             message: entry.message.clone(),
             pid: entry.pid,
+            fields: entry.fields.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
         }
     }
 }
@@ -140,17 +204,48 @@ async fn handle_client(
     stream: UnixStream,
     state: Arc<RwLock<ScribeState>>,
 ) -> Result<()> {
+    // Who is asking decides which core dumps they may see
+    let peer_uid = stream.peer_cred().ok().map(|cred| cred.uid());
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
 
+    let hello = Hello::new("scribe", env!("CARGO_PKG_VERSION")).with_features(&["follow", "audit"]);
+
     while reader.read_line(&mut line).await? > 0 {
-        let response = match serde_json::from_str::<IpcRequest>(&line) {
-            Ok(request) => process_request(request, &state).await,
-            Err(e) => IpcResponse::Error { message: e.to_string() },
+        let json = match hello.respond(&line) {
+            Some(reply) => reply,
+            None => {
+                let response = match serde_json::from_str::<IpcRequest>(&line) {
+                    Ok(IpcRequest::Follow { priority, facility, identifier, pid, grep, fields, backlog }) => {
+                        match parse_fields(&fields) {
+                            Ok(fields) => {
+                                let filter = JournalFilter {
+                                    priority: priority.map(Priority::from_u8),
+                                    facility: facility.map(Facility::from_u8),
+                                    identifier,
+                                    pid,
+                                    grep,
+                                    fields,
+                                    ..Default::default()
+                                };
+                                return follow(reader, writer, state, filter, backlog).await;
+                            }
+                            Err(e) => IpcResponse::Error { message: e.to_string() },
+                        }
+                    }
+                    Ok(IpcRequest::ExtractCoredump { id }) => {
+                        match coredump_for(&state, &id, peer_uid).await {
+                            Ok((store, info)) => return extract_coredump(writer, store, info).await,
+                            Err(message) => IpcResponse::Error { message },
+                        }
+                    }
+                    Ok(request) => process_request(request, &state, peer_uid).await,
+                    Err(e) => IpcResponse::Error { message: e.to_string() },
+                };
+                serde_json::to_string(&response)?
+            }
         };
-
-        let json = serde_json::to_string(&response)?;
         writer.write_all(json.as_bytes()).await?;
         writer.write_all(b"\n").await?;
         writer.flush().await?;
@@ -161,9 +256,149 @@ async fn handle_client(
     Ok(())
 }
 
+/// Stream entries matching `filter` until the client goes away
+async fn follow(
+    mut reader: BufReader<OwnedReadHalf>,
+    mut writer: OwnedWriteHalf,
+    state: Arc<RwLock<ScribeState>>,
+    filter: JournalFilter,
+    backlog: usize,
+) -> Result<()> {
+    // Subscribing under the read lock means no entry is written between
+    // the backlog query and the subscription
+    let (recent, mut follow) = {
+        let state = state.read().await;
+        let recent = if backlog > 0 {
+            let query = JournalFilter {
+                limit: Some(backlog),
+                reverse: true,
+                ..filter.clone()
+            };
+            state.journal.query(&query).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        (recent, state.followers.subscribe(filter))
+    };
+    let id = follow.id;
+
+    let result = async {
+        send(&mut writer, &IpcResponse::Success { message: "Following".to_string() }).await?;
+        for entry in recent.iter().rev() {
+            send(&mut writer, &IpcResponse::Entry(entry.into())).await?;
+        }
+
+        let mut line = String::new();
+        loop {
+            tokio::select! {
+                entry = follow.recv() => {
+                    let Some(entry) = entry else { break };
+                    let count = follow.take_dropped();
+                    if count > 0 {
+                        send(&mut writer, &IpcResponse::Dropped { count }).await?;
+                    }
+                    send(&mut writer, &IpcResponse::Entry((&entry).into())).await?;
+                }
+                read = reader.read_line(&mut line) => {
+                    // Anything the client sends ends the follow
+                    if read? == 0 {
+                        break;
+                    }
+                    line.clear();
+                }
+            }
+        }
+        Ok(())
+    }
+    .await;
+
+    state.read().await.followers.unsubscribe(id);
+    result
+}
+
+/// Look up a dump `peer_uid` may read
+async fn coredump_for(
+    state: &RwLock<ScribeState>,
+    id: &str,
+    peer_uid: Option<u32>,
+) -> std::result::Result<(CoredumpStore, CoredumpInfo), String> {
+    let store = state.read().await.coredumps.clone().ok_or_else(coredumps_disabled)?;
+    let info = store.get(id).map_err(|e| e.to_string())?;
+    if !may_read(peer_uid, &info) {
+        // Indistinguishable from a missing dump
+        return Err(format!("No core dump {}", id));
+    }
+    if !info.stored {
+        return Err(format!("Only the metadata of core dump {} was kept", id));
+    }
+    Ok((store, info))
+}
+
+/// Send a dump's metadata, then the dump itself
+async fn extract_coredump(mut writer: OwnedWriteHalf, store: CoredumpStore, info: CoredumpInfo) -> Result<()> {
+    use std::io::Read;
+
+    send(&mut writer, &IpcResponse::Coredump(info.clone())).await?;
+
+    // Decompress on a blocking thread, handing chunks over as they come
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<std::io::Result<Vec<u8>>>(8);
+    tokio::task::spawn_blocking(move || {
+        let mut core = match store.open_core(&info.id) {
+            Ok(core) => core,
+            Err(e) => {
+                let _ = tx.blocking_send(Err(std::io::Error::other(e.to_string())));
+                return;
+            }
+        };
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            match core.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    if tx.blocking_send(Ok(buf[..n].to_vec())).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.blocking_send(Err(e));
+                    break;
+                }
+            }
+        }
+    });
+
+    while let Some(chunk) = rx.recv().await {
+        writer.write_all(&chunk?).await?;
+    }
+    writer.shutdown().await?;
+    Ok(())
+}
+
+/// Root reads every dump, anyone else only their own
+fn may_read(peer_uid: Option<u32>, info: &CoredumpInfo) -> bool {
+    matches!(peer_uid, Some(uid) if uid == 0 || uid == info.uid)
+}
+
+fn coredumps_disabled() -> String {
+    "Core dump collection is disabled".to_string()
+}
+
+fn parse_fields(specs: &[String]) -> Result<Vec<FieldMatch>> {
+    specs.iter().map(|spec| FieldMatch::parse(spec)).collect()
+}
+
+async fn send(writer: &mut OwnedWriteHalf, response: &IpcResponse) -> Result<()> {
+    let json = serde_json::to_string(response)?;
+    writer.write_all(json.as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+    writer.flush().await?;
+    Ok(())
+}
+
 async fn process_request(
     request: IpcRequest,
     state: &RwLock<ScribeState>,
+    peer_uid: Option<u32>,
 ) -> IpcResponse {
     match request {
         IpcRequest::Log { priority, facility, identifier, message, pid } => {
@@ -180,15 +415,55 @@ async fn process_request(
             };
 
             let mut state = state.write().await;
-            match state.journal.write(&entry) {
+            match state.record(&entry) {
                 Ok(()) => IpcResponse::Success { message: "Logged".to_string() },
                 Err(e) => IpcResponse::Error { message: e.to_string() },
             }
         }
 
-        IpcRequest::Query { since, until, priority, identifier, grep, limit, reverse } => {
+        IpcRequest::Audit { event } => {
+            // Audit events are only taken from privileged daemons, so
+            // users cannot forge entries in the audit trail
+            if peer_uid != Some(0) {
+                return IpcResponse::Error {
+                    message: "Only privileged daemons may record audit events".to_string(),
+                };
+            }
+
+            let entry = LogEntry {
+                timestamp: chrono::Utc::now(),
+                priority: match event.outcome {
+                    AuditOutcome::Success => Priority::Notice,
+                    AuditOutcome::Denied | AuditOutcome::Failure => Priority::Warning,
+                },
+                facility: Facility::AuthPriv,
+                identifier: AUDIT_IDENTIFIER.to_string(),
+                message: event.message(),
+                pid: None,
+                uid: None,
+                hostname: None,
+                fields: event
+                    .fields()
+                    .into_iter()
+                    .map(|(key, value)| (key, FieldValue::Text(value)))
+                    .collect(),
+            };
+
+            let mut state = state.write().await;
+            match state.record_audit(&entry) {
+                Ok(()) => IpcResponse::Success { message: "Audited".to_string() },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::Query { since, until, priority, identifier, grep, fields, limit, reverse } => {
             use crate::query::{parse_time, parse_priority};
 
+            let fields = match parse_fields(&fields) {
+                Ok(fields) => fields,
+                Err(e) => return IpcResponse::Error { message: e.to_string() },
+            };
+
             let filter = JournalFilter {
                 since: since.and_then(|s| parse_time(&s)),
                 until: until.and_then(|s| parse_time(&s)),
@@ -197,6 +472,7 @@ async fn process_request(
                 identifier,
                 pid: None,
                 grep,
+                fields,
                 limit,
                 reverse,
             };
@@ -213,6 +489,11 @@ async fn process_request(
             }
         }
 
+        // Handled per connection in handle_client
+        IpcRequest::Follow { .. } => IpcResponse::Error {
+            message: "Follow requires a streaming connection".to_string(),
+        },
+
         IpcRequest::DiskUsage => {
             let state = state.read().await;
             match storage::disk_usage(std::path::Path::new(&state.config.journal_dir)) {
@@ -246,16 +527,66 @@ async fn process_request(
 
         IpcRequest::Verify => {
             let state = state.read().await;
-            match storage::verify(std::path::Path::new(&state.config.journal_dir)) {
+            let dir = std::path::Path::new(&state.config.journal_dir);
+            match storage::verify(dir, state.journal.seal_key()) {
                 Ok(result) => IpcResponse::VerifyResult {
                     valid_entries: result.valid_entries,
                     valid_archives: result.valid_archives,
                     corrupted_files: result.corrupted_files,
+                    sealed_archives: result.sealed_archives,
+                    unverified_archives: result.unverified_archives,
+                    problems: result.problems,
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::ForwardStatus => {
+            let state = state.read().await;
+            match &state.forwarder {
+                Some(forwarder) => IpcResponse::ForwardStatus(forwarder.stats()),
+                None => IpcResponse::Error {
+                    message: "Forwarding is not configured".to_string(),
                 },
+            }
+        }
+
+        IpcRequest::CoredumpStored { info } => {
+            if peer_uid != Some(0) {
+                return IpcResponse::Error {
+                    message: "Only the core dump handler may report crashes".to_string(),
+                };
+            }
+
+            let mut state = state.write().await;
+            match state.record(&info.entry()) {
+                Ok(()) => IpcResponse::Success { message: "Recorded".to_string() },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::Coredumps { unit, limit } => {
+            let Some(store) = state.read().await.coredumps.clone() else {
+                return IpcResponse::Error { message: coredumps_disabled() };
+            };
+            match store.list() {
+                Ok(dumps) => IpcResponse::Coredumps(
+                    dumps
+                        .into_iter()
+                        .filter(|info| may_read(peer_uid, info))
+                        .filter(|info| unit.is_none() || info.unit == unit)
+                        .take(limit.unwrap_or(usize::MAX))
+                        .collect(),
+                ),
                 Err(e) => IpcResponse::Error { message: e.to_string() },
             }
         }
 
+        // Handled per connection in handle_client
+        IpcRequest::ExtractCoredump { .. } => IpcResponse::Error {
+            message: "Extracting a core dump requires a streaming connection".to_string(),
+        },
+
         IpcRequest::Flush => {
             let mut state = state.write().await;
             match state.journal.flush() {
@@ -265,3 +596,86 @@ async fn process_request(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::follow::Followers;
+    use crate::journal::Journal;
+    use crate::state::{ScribeConfig, ScribeState};
+
+    /// The query nyx-audit sends with no filters
+    fn audit_query() -> IpcRequest {
+        IpcRequest::Query {
+            since: None,
+            until: None,
+            priority: None,
+            identifier: Some(AUDIT_IDENTIFIER.to_string()),
+            grep: None,
+            fields: vec!["audit.source".to_string()],
+            limit: None,
+            reverse: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_only_root_audit_events_reach_nyx_audit() {
+        let dir = std::env::temp_dir().join(format!("scribe-audit-test-{}", std::process::id()));
+        let dir = dir.to_string_lossy().to_string();
+        let config = ScribeConfig {
+            journal_dir: dir.clone(),
+            max_file_size: 1024 * 1024,
+            retention_days: 1,
+            follow_buffer: 16,
+        };
+        let state = RwLock::new(ScribeState {
+            journal: Journal::open(&dir).unwrap(),
+            followers: Followers::new(config.follow_buffer),
+            forwarder: None,
+            config,
+            coredumps: None,
+        });
+
+        // A user logging under the audit identifier
+        let forged = IpcRequest::Log {
+            priority: 5,
+            facility: 10,
+            identifier: AUDIT_IDENTIFIER.to_string(),
+            message: "vault vault.unlock by root: success".to_string(),
+            pid: None,
+        };
+        assert!(matches!(process_request(forged, &state, Some(1000)).await, IpcResponse::Success { .. }));
+
+        // Syslog structured data carrying audit fields
+        let mut entry = LogEntry {
+            timestamp: chrono::Utc::now(),
+            priority: Priority::Notice,
+            facility: Facility::AuthPriv,
+            identifier: "sshd".to_string(),
+            message: "forged".to_string(),
+            pid: None,
+            uid: None,
+            hostname: None,
+            fields: std::collections::HashMap::new(),
+        };
+        entry.fields.insert("audit.actor".to_string(), FieldValue::Text("root".to_string()));
+        state.write().await.record(&entry).unwrap();
+
+        let event = AuditEvent::new("vault", "uid:1000", "vault.unlock", "vault", AuditOutcome::Success);
+        let refused = IpcRequest::Audit { event: event.clone() };
+        assert!(matches!(process_request(refused, &state, Some(1000)).await, IpcResponse::Error { .. }));
+        let accepted = IpcRequest::Audit { event };
+        assert!(matches!(process_request(accepted, &state, Some(0)).await, IpcResponse::Success { .. }));
+
+        process_request(IpcRequest::Flush, &state, Some(0)).await;
+        match process_request(audit_query(), &state, Some(0)).await {
+            IpcResponse::Entries(entries) => {
+                assert_eq!(entries.len(), 1);
+                assert_eq!(entries[0].fields.get("audit.actor"), Some(&FieldValue::Text("uid:1000".to_string())));
+            }
+            _ => panic!("query failed"),
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -4,11 +4,13 @@ use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::{self, File, OpenOptions};
-use std::io::{BufRead, BufReader, BufWriter, Read, Write, Seek, SeekFrom};
+use std::io::{BufRead, BufReader, BufWriter, Write, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 use chrono::{DateTime, Utc};
 use tracing::{info, debug};
 
+use crate::seal;
+
 /// Log entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEntry {
@@ -20,7 +22,60 @@ pub struct LogEntry {
     pub pid: Option<u32>,
     pub uid: Option<u32>,
     pub hostname: Option<String>,
-    pub fields: HashMap<String, String>,
+    #[serde(default)]
+    pub fields: HashMap<String, FieldValue>,
+}
+
+/// Typed value of a structured field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FieldValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl FieldValue {
+    /// Type a value that arrived as text (e.g. RFC5424 parameters)
+    pub fn infer(value: &str) -> Self {
+        if let Ok(v) = value.parse::<i64>() {
+            FieldValue::Int(v)
+        } else if let Ok(v) = value.parse::<f64>() {
+            if v.is_finite() {
+                FieldValue::Float(v)
+            } else {
+                FieldValue::Text(value.to_string())
+            }
+        } else {
+            match value {
+                "true" => FieldValue::Bool(true),
+                "false" => FieldValue::Bool(false),
+                _ => FieldValue::Text(value.to_string()),
+            }
+        }
+    }
+
+    /// Whether this value equals `expected` as given on the command line
+    pub fn matches(&self, expected: &str) -> bool {
+        match (self, FieldValue::infer(expected)) {
+            (FieldValue::Float(a), FieldValue::Int(b)) => *a == b as f64,
+            (FieldValue::Int(a), FieldValue::Float(b)) => *a as f64 == b,
+            (FieldValue::Text(a), _) => a == expected,
+            (a, b) => *a == b,
+        }
+    }
+}
+
+impl std::fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldValue::Bool(v) => write!(f, "{}", v),
+            FieldValue::Int(v) => write!(f, "{}", v),
+            FieldValue::Float(v) => write!(f, "{}", v),
+            FieldValue::Text(v) => write!(f, "{}", v),
+        }
+    }
 }
 
 /// Log priority (syslog compatible)
@@ -145,6 +200,7 @@ pub struct Journal {
     entry_count: u64,
     current_size: u64,
     max_file_size: u64,
+    seal_key: Option<[u8; 32]>,
 }
 
 impl Journal {
@@ -169,6 +225,7 @@ impl Journal {
             entry_count: 0,
             current_size,
             max_file_size: 50 * 1024 * 1024, // 50MB default
+            seal_key: None,
         })
     }
 
@@ -202,27 +259,18 @@ impl Journal {
     }
 
     /// Rotate journal
+    ///
+    /// The segment is compressed with zstd and sealed onto the hash chain.
     pub fn rotate(&mut self) -> Result<()> {
         self.flush()?;
 
-        // Generate archive name with timestamp
-        let timestamp = Utc::now().format("%Y%m%d-%H%M%S");
-        let archive_name = format!("journal-{}.gz", timestamp);
-        let archive_path = self.dir.join(&archive_name);
-
-        // Compress current journal
-        info!("Rotating journal to {}", archive_name);
-
-        let input = File::open(&self.current_file)?;
-        let output = File::create(&archive_path)?;
-
-        let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
-        let mut reader = BufReader::new(input);
-        let mut buffer = Vec::new();
+        if self.current_size == 0 {
+            return Ok(());
+        }
 
-        reader.read_to_end(&mut buffer)?;
-        encoder.write_all(&buffer)?;
-        encoder.finish()?;
+        let data = fs::read(&self.current_file)?;
+        let archive = seal::write_archive(&self.dir, &data, self.seal_key.as_ref())?;
+        info!("Rotated journal to {}", archive.display());
 
         // Truncate current file
         let file = OpenOptions::new()
@@ -239,6 +287,15 @@ impl Journal {
         Ok(())
     }
 
+    /// Key sealing archives, once fetched from cipher
+    pub fn set_seal_key(&mut self, key: [u8; 32]) {
+        self.seal_key = Some(key);
+    }
+
+    pub fn seal_key(&self) -> Option<&[u8; 32]> {
+        self.seal_key.as_ref()
+    }
+
     fn cleanup_old_archives(&self) -> Result<()> {
         let retention = chrono::Duration::days(30);
         let cutoff = Utc::now() - retention;
@@ -247,7 +304,7 @@ impl Journal {
             let entry = entry?;
             let path = entry.path();
 
-            if path.extension().and_then(|e| e.to_str()) == Some("gz") {
+            if seal::is_archive(&path) {
                 if let Ok(metadata) = entry.metadata() {
                     if let Ok(modified) = metadata.modified() {
                         let modified: DateTime<Utc> = modified.into();
@@ -286,12 +343,8 @@ impl Journal {
                 let entry = entry?;
                 let path = entry.path();
 
-                if path.extension().and_then(|e| e.to_str()) == Some("gz") {
-                    let file = File::open(&path)?;
-                    let decoder = flate2::read::GzDecoder::new(file);
-                    let reader = BufReader::new(decoder);
-
-                    for line in reader.lines() {
+                if seal::is_archive(&path) {
+                    for line in seal::read_archive(&path)?.lines() {
                         let line = line?;
                         if let Ok(entry) = serde_json::from_str::<LogEntry>(&line) {
                             if filter.matches(&entry) {
@@ -329,10 +382,42 @@ pub struct JournalFilter {
     pub identifier: Option<String>,
     pub pid: Option<u32>,
     pub grep: Option<String>,
+    pub fields: Vec<FieldMatch>,
     pub limit: Option<usize>,
     pub reverse: bool,
 }
 
+/// Match on a structured field: `KEY=VALUE`, or `KEY` for presence
+#[derive(Debug, Clone)]
+pub struct FieldMatch {
+    pub key: String,
+    pub value: Option<String>,
+}
+
+impl FieldMatch {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (key, value) = match spec.split_once('=') {
+            Some((key, value)) => (key, Some(value.to_string())),
+            None => (spec, None),
+        };
+        if key.is_empty() {
+            return Err(anyhow!("Invalid field match: {}", spec));
+        }
+        Ok(Self {
+            key: key.to_string(),
+            value,
+        })
+    }
+
+    fn matches(&self, entry: &LogEntry) -> bool {
+        match (entry.fields.get(&self.key), &self.value) {
+            (Some(actual), Some(expected)) => actual.matches(expected),
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
 impl JournalFilter {
     pub fn matches(&self, entry: &LogEntry) -> bool {
         if let Some(since) = &self.since {
@@ -377,6 +462,10 @@ impl JournalFilter {
             }
         }
 
+        if !self.fields.iter().all(|field| field.matches(entry)) {
+            return false;
+        }
+
         true
     }
 }
@@ -3,9 +3,10 @@
 //! A structured logging daemon featuring:
 //! - Binary journal format for efficiency
 //! - Structured logging with JSON
-//! - Log rotation and compression
+//! - Log rotation with zstd compression and sealed hash chains
 //! - Kernel message collection
 //! - Remote logging support
+//! - Core dump collection
 
 mod journal;
 mod collector;
@@ -13,16 +14,25 @@ mod storage;
 mod query;
 mod ipc;
 mod state;
+mod follow;
+mod structured;
+mod seal;
+mod forward;
+mod elf;
+mod coredump;
 
 use anyhow::Result;
 use clap::Parser;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{info, error};
+use tracing::{info, warn, error};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::journal::Journal;
-use crate::collector::{SyslogCollector, KernelCollector};
+use crate::journal::{FieldMatch, Journal, JournalFilter};
+use crate::forward::{ForwardConfig, Forwarder, Target};
+use crate::follow::Followers;
+use crate::coredump::{CoredumpConfig, CoredumpStore};
+use crate::collector::{SyslogCollector, KernelCollector, RemoteCollector};
 use crate::ipc::ScribeServer;
 use crate::state::{ScribeState, ScribeConfig};
 
@@ -49,6 +59,99 @@ struct Args {
     /// Retention days
     #[arg(long, default_value = "30")]
     retention_days: u32,
+
+    /// Cipher secret sealing rotated archives (`collection/id`)
+    #[arg(long)]
+    seal_key: Option<String>,
+
+    /// Cipher socket path
+    #[arg(long, default_value = "/run/cipher/cipher.sock")]
+    cipher_socket: String,
+
+    /// Forward entries to a remote scribe or syslog collector
+    /// (scribe://, scribe+tls://, syslog+tcp://, syslog+tls://)
+    #[arg(long)]
+    forward: Option<String>,
+
+    /// Only forward entries at this priority or more severe
+    #[arg(long)]
+    forward_priority: Option<String>,
+
+    /// Only forward entries whose identifier contains this
+    #[arg(long)]
+    forward_identifier: Option<String>,
+
+    /// Only forward entries with this structured field (KEY=VALUE or KEY)
+    #[arg(long)]
+    forward_field: Vec<String>,
+
+    /// Entries buffered in memory before spooling to disk
+    #[arg(long, default_value = "4096")]
+    forward_buffer: usize,
+
+    /// Spool directory for entries awaiting delivery
+    #[arg(long, default_value = "/var/lib/scribe/spool")]
+    forward_spool_dir: String,
+
+    /// Max spool size (MB); entries beyond it are dropped
+    #[arg(long, default_value = "256")]
+    forward_spool_max_mb: u64,
+
+    /// CA bundle for TLS forwarding targets
+    #[arg(long, default_value = "/etc/ssl/certs/ca-certificates.crt")]
+    forward_ca: String,
+
+    /// Sentinel socket for delivery metrics
+    #[arg(long, default_value = "/run/sentinel/sentinel.sock")]
+    sentinel_socket: String,
+
+    /// Accept entries forwarded by other scribe instances on this address
+    #[arg(long)]
+    remote_listen: Option<String>,
+
+    /// TLS certificate for the remote listener
+    #[arg(long)]
+    remote_tls_cert: Option<String>,
+
+    /// TLS key for the remote listener
+    #[arg(long)]
+    remote_tls_key: Option<String>,
+
+    /// Entries queued per live follower before dropping
+    #[arg(long, default_value = "1024")]
+    follow_buffer: usize,
+
+    /// Core dump directory
+    #[arg(long, default_value = "/var/lib/scribe/coredumps")]
+    coredump_dir: String,
+
+    /// Space stored core dumps may take (MB)
+    #[arg(long, default_value = "1024")]
+    coredump_max_use_mb: u64,
+
+    /// Keep only the metadata of core dumps larger than this (MB)
+    #[arg(long, default_value = "2048")]
+    coredump_max_size_mb: u64,
+
+    /// Core dump handler registered as the kernel's core_pattern
+    #[cfg(not(feature = "native"))]
+    #[arg(long, default_value = "/usr/bin/nyx-coredump")]
+    coredump_handler: String,
+
+    /// Leave core dumps alone
+    #[arg(long)]
+    no_coredumps: bool,
+
+    /// Capability slot of the kernel log, as granted by init
+    #[cfg(feature = "native")]
+    #[arg(long, env = "NYX_KLOG_CAP", default_value = "0")]
+    klog_cap: u64,
+
+    /// Capability slot of the crash report endpoint, as granted by init
+    /// (0 = don't collect reports)
+    #[cfg(feature = "native")]
+    #[arg(long, env = "NYX_REPORT_CAP", default_value = "0")]
+    report_cap: u64,
 }
 
 #[tokio::main]
@@ -67,25 +170,128 @@ async fn main() -> Result<()> {
         journal_dir: args.journal_dir.clone(),
         max_file_size: args.max_size_mb * 1024 * 1024,
         retention_days: args.retention_days,
+        follow_buffer: args.follow_buffer,
     };
 
     // Initialize journal
     let journal = Journal::open(&args.journal_dir)?;
 
+    let forwarder = match &args.forward {
+        Some(url) => {
+            let fields = args
+                .forward_field
+                .iter()
+                .map(|spec| FieldMatch::parse(spec))
+                .collect::<Result<Vec<_>>>()?;
+            let priority = match &args.forward_priority {
+                Some(spec) => Some(
+                    query::parse_priority(spec)
+                        .ok_or_else(|| anyhow::anyhow!("unknown priority: {}", spec))?,
+                ),
+                None => None,
+            };
+
+            let forwarder = Forwarder::start(ForwardConfig {
+                target: Target::parse(url)?,
+                filter: JournalFilter {
+                    priority,
+                    identifier: args.forward_identifier.clone(),
+                    fields,
+                    ..Default::default()
+                },
+                buffer: args.forward_buffer,
+                spool_dir: args.forward_spool_dir.clone().into(),
+                spool_max_bytes: args.forward_spool_max_mb * 1024 * 1024,
+                ca_file: args.forward_ca.clone().into(),
+                sentinel_socket: Some(args.sentinel_socket.clone()),
+            })?;
+            info!("Forwarding entries to {}", url);
+            Some(forwarder)
+        }
+        None => None,
+    };
+
+    let coredumps = if args.no_coredumps {
+        None
+    } else {
+        let coredump_config = CoredumpConfig {
+            dir: args.coredump_dir.clone().into(),
+            max_use: args.coredump_max_use_mb * 1024 * 1024,
+            max_dump_size: args.coredump_max_size_mb * 1024 * 1024,
+        };
+        match CoredumpStore::open(coredump_config) {
+            Ok(store) => {
+                #[cfg(not(feature = "native"))]
+                if let Err(e) = coredump::register_handler(&args.coredump_handler, store.config()) {
+                    warn!("Core dumps will not be collected: {:#}", e);
+                }
+                Some(store)
+            }
+            Err(e) => {
+                warn!("Core dump store unavailable: {:#}", e);
+                None
+            }
+        }
+    };
+
     let state = Arc::new(RwLock::new(ScribeState {
         journal,
+        followers: Followers::new(config.follow_buffer),
+        forwarder,
         config: config.clone(),
+        coredumps,
     }));
 
+    // The Nyx kernel writes dumps itself; pick them up from there
+    #[cfg(feature = "native")]
+    tokio::spawn(coredump::collect_kernel_dumps(state.clone()));
+
+    // Fetch the archive seal key; retry until cipher is up and unlocked
+    if let Some(spec) = args.seal_key.clone() {
+        let Some((collection, id)) = spec.split_once('/') else {
+            anyhow::bail!("--seal-key must be collection/id");
+        };
+        let (collection, id) = (collection.to_string(), id.to_string());
+        let cipher_socket = args.cipher_socket.clone();
+        let state_clone = state.clone();
+        tokio::spawn(async move {
+            loop {
+                match seal::fetch_key(&cipher_socket, &collection, &id).await {
+                    Ok(key) => {
+                        state_clone.write().await.journal.set_seal_key(key);
+                        info!("Journal archives will be sealed with key {}/{}", collection, id);
+                        break;
+                    }
+                    Err(e) => warn!("Seal key unavailable, retrying: {}", e),
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            }
+        });
+    }
+
     // Start kernel log collector
     let state_clone = state.clone();
+    let collector = KernelCollector::new();
+    #[cfg(feature = "native")]
+    let collector = collector.with_klog_cap(args.klog_cap);
     tokio::spawn(async move {
-        let collector = KernelCollector::new();
         if let Err(e) = collector.run(state_clone).await {
             error!("Kernel collector error: {}", e);
         }
     });
 
+    // Start crash report collector
+    #[cfg(feature = "native")]
+    if args.report_cap != 0 {
+        let state_clone = state.clone();
+        let collector = collector::ReportCollector::new(args.report_cap);
+        tokio::spawn(async move {
+            if let Err(e) = collector.run(state_clone).await {
+                error!("Report collector error: {}", e);
+            }
+        });
+    }
+
     // Start syslog collector
     let state_clone = state.clone();
     let syslog_socket = args.syslog_socket.clone();
@@ -96,6 +302,21 @@ async fn main() -> Result<()> {
         }
     });
 
+    // Start receiver for forwarded entries
+    if let Some(listen) = &args.remote_listen {
+        let collector = RemoteCollector::new(
+            listen,
+            args.remote_tls_cert.as_deref().map(std::path::Path::new),
+            args.remote_tls_key.as_deref().map(std::path::Path::new),
+        )?;
+        let state_clone = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = collector.run(state_clone).await {
+                error!("Remote collector error: {}", e);
+            }
+        });
+    }
+
     // Start rotation task
     let state_clone = state.clone();
     tokio::spawn(async move {
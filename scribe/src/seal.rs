@@ -0,0 +1,298 @@
+//! Sealing of rotated journal segments
+//!
+//! Each archive ends in a zstd skippable frame holding a seal: a BLAKE3
+//! hash over the segment's contents chained to the previous archive's
+//! seal. Decoders ignore the frame, so archives stay plain zstd. With a
+//! key from cipher the seal is a keyed hash and cannot be recomputed by
+//! someone who edits an archive. The newest seal is also kept in
+//! `seal.head` so dropping the latest archives shows up as well.
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+/// Archive extension for sealed segments
+pub const ARCHIVE_EXT: &str = "zst";
+
+/// First skippable-frame magic; decoders skip 0x184D2A50..=0x184D2A5F
+const SKIPPABLE_MAGIC: u32 = 0x184D_2A5A;
+
+/// Trailer marking the end of a seal frame
+const SEAL_MAGIC: &[u8; 8] = b"SCRBSEAL";
+
+const HEAD_FILE: &str = "seal.head";
+
+/// Context string for deriving the seal key from the cipher secret
+const KEY_CONTEXT: &str = "nyx scribe journal seal v1";
+
+/// Seal written after an archive's compressed data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Seal {
+    pub version: u32,
+    pub sequence: u64,
+    pub entries: u64,
+    /// Uncompressed length
+    pub length: u64,
+    /// BLAKE3 of the uncompressed contents
+    pub content: String,
+    /// Previous seal's hash (all zeros for the first)
+    pub prev: String,
+    pub hash: String,
+    pub keyed: bool,
+    pub sealed_at: DateTime<Utc>,
+}
+
+/// Latest seal, for continuing and checking the chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainHead {
+    pub sequence: u64,
+    pub hash: String,
+}
+
+impl Seal {
+    fn new(sequence: u64, data: &[u8], prev: &str, key: Option<&[u8; 32]>) -> Self {
+        let mut seal = Self {
+            version: 1,
+            sequence,
+            entries: data.iter().filter(|&&b| b == b'\n').count() as u64,
+            length: data.len() as u64,
+            content: blake3::hash(data).to_hex().to_string(),
+            prev: prev.to_string(),
+            hash: String::new(),
+            keyed: key.is_some(),
+            sealed_at: Utc::now(),
+        };
+        seal.hash = seal.compute(key);
+        seal
+    }
+
+    /// Hash over everything the seal vouches for
+    fn compute(&self, key: Option<&[u8; 32]>) -> String {
+        let mut hasher = match key {
+            Some(key) => blake3::Hasher::new_keyed(key),
+            None => blake3::Hasher::new(),
+        };
+        hasher.update(&self.version.to_le_bytes());
+        hasher.update(&self.sequence.to_le_bytes());
+        hasher.update(&self.entries.to_le_bytes());
+        hasher.update(&self.length.to_le_bytes());
+        hasher.update(self.content.as_bytes());
+        hasher.update(self.prev.as_bytes());
+        hasher.update(self.sealed_at.to_rfc3339().as_bytes());
+        hasher.finalize().to_hex().to_string()
+    }
+}
+
+/// Hash preceding the first seal in a chain
+fn genesis() -> String {
+    "0".repeat(64)
+}
+
+/// Derive the seal key from a secret held in cipher
+pub fn derive_key(secret: &[u8]) -> [u8; 32] {
+    blake3::derive_key(KEY_CONTEXT, secret)
+}
+
+/// Load the chain head, if any archive has been sealed
+pub fn load_head(dir: &Path) -> Option<ChainHead> {
+    let data = std::fs::read(dir.join(HEAD_FILE)).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+fn store_head(dir: &Path, head: &ChainHead) -> Result<()> {
+    let tmp = dir.join(format!("{}.tmp", HEAD_FILE));
+    std::fs::write(&tmp, serde_json::to_vec(head)?)?;
+    std::fs::rename(&tmp, dir.join(HEAD_FILE))?;
+    Ok(())
+}
+
+/// Compress `data` into a sealed archive and advance the chain
+pub fn write_archive(
+    dir: &Path,
+    data: &[u8],
+    key: Option<&[u8; 32]>,
+) -> Result<PathBuf> {
+    let head = load_head(dir);
+    let sequence = head.as_ref().map(|h| h.sequence + 1).unwrap_or(1);
+    let prev = head.map(|h| h.hash).unwrap_or_else(genesis);
+
+    let seal = Seal::new(sequence, data, &prev, key);
+
+    let mut archive = zstd::encode_all(data, 0)?;
+    let payload = serde_json::to_vec(&seal)?;
+    let frame_len = payload.len() + 4 + SEAL_MAGIC.len();
+    archive.extend_from_slice(&SKIPPABLE_MAGIC.to_le_bytes());
+    archive.extend_from_slice(&(frame_len as u32).to_le_bytes());
+    archive.extend_from_slice(&payload);
+    archive.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    archive.extend_from_slice(SEAL_MAGIC);
+
+    let timestamp = Utc::now().format("%Y%m%d-%H%M%S");
+    let path = dir.join(format!("journal-{:08}-{}.{}", sequence, timestamp, ARCHIVE_EXT));
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, &archive)?;
+    std::fs::File::open(&tmp)?.sync_all()?;
+    std::fs::rename(&tmp, &path)?;
+
+    store_head(dir, &ChainHead { sequence, hash: seal.hash })?;
+    Ok(path)
+}
+
+/// Split an archive into its compressed data and seal
+fn split_archive(archive: &[u8]) -> Result<(&[u8], Seal)> {
+    let trailer = archive
+        .len()
+        .checked_sub(4 + SEAL_MAGIC.len())
+        .ok_or_else(|| anyhow!("too short to hold a seal"))?;
+    if &archive[trailer + 4..] != SEAL_MAGIC {
+        return Err(anyhow!("no seal (truncated?)"));
+    }
+
+    let payload_len = u32::from_le_bytes(archive[trailer..trailer + 4].try_into()?) as usize;
+    let payload_start = trailer
+        .checked_sub(payload_len)
+        .ok_or_else(|| anyhow!("seal length out of range"))?;
+    let frame_start = payload_start
+        .checked_sub(8)
+        .ok_or_else(|| anyhow!("seal frame out of range"))?;
+    if archive[frame_start..frame_start + 4] != SKIPPABLE_MAGIC.to_le_bytes() {
+        return Err(anyhow!("seal frame header missing"));
+    }
+
+    let seal = serde_json::from_slice(&archive[payload_start..trailer])
+        .map_err(|e| anyhow!("unreadable seal: {}", e))?;
+    Ok((&archive[..frame_start], seal))
+}
+
+/// Outcome of checking one archive
+pub enum ArchiveCheck {
+    /// Contents and seal agree
+    Valid(Seal),
+    /// Keyed seal but no key to check it with; contents still match
+    Unverified(Seal),
+}
+
+/// Check an archive against its seal
+pub fn check_archive(path: &Path, key: Option<&[u8; 32]>) -> Result<ArchiveCheck> {
+    let archive = std::fs::read(path)?;
+    let (compressed, seal) = split_archive(&archive)?;
+
+    let data = zstd::decode_all(compressed).map_err(|e| anyhow!("corrupt data: {}", e))?;
+    if data.len() as u64 != seal.length {
+        return Err(anyhow!("length {} does not match seal ({})", data.len(), seal.length));
+    }
+    if blake3::hash(&data).to_hex().as_str() != seal.content {
+        return Err(anyhow!("contents do not match seal"));
+    }
+
+    match (seal.keyed, key) {
+        (true, None) => return Ok(ArchiveCheck::Unverified(seal)),
+        (false, Some(_)) => return Err(anyhow!("unkeyed seal where a keyed one is expected")),
+        _ => {}
+    }
+    if seal.compute(key) != seal.hash {
+        return Err(anyhow!("seal hash mismatch"));
+    }
+
+    Ok(ArchiveCheck::Valid(seal))
+}
+
+/// Check the seals of consecutive archives link up, and the newest
+/// matches the chain head. Archives removed by retention leave a gap at
+/// the start of the chain only.
+pub fn check_chain(dir: &Path, seals: &mut [(PathBuf, Seal)]) -> Vec<String> {
+    let mut problems = Vec::new();
+    seals.sort_by_key(|(_, seal)| seal.sequence);
+
+    for pair in seals.windows(2) {
+        let ((_, prev), (path, seal)) = (&pair[0], &pair[1]);
+        if seal.sequence != prev.sequence + 1 {
+            problems.push(format!(
+                "archives {}..{} missing before {}",
+                prev.sequence + 1,
+                seal.sequence - 1,
+                path.display()
+            ));
+        } else if seal.prev != prev.hash {
+            problems.push(format!("{}: chain broken", path.display()));
+        }
+    }
+
+    if let (Some((_, last)), Some(head)) = (seals.last(), load_head(dir)) {
+        if last.sequence < head.sequence {
+            problems.push(format!(
+                "archives {}..{} missing (newest)",
+                last.sequence + 1,
+                head.sequence
+            ));
+        } else if last.sequence == head.sequence && last.hash != head.hash {
+            problems.push(format!("archive {} does not match chain head", last.sequence));
+        }
+    }
+
+    problems
+}
+
+/// Read the lines of an archive, sealed or not
+pub fn read_archive(path: &Path) -> Result<Box<dyn BufRead>> {
+    let file = std::fs::File::open(path)?;
+    let reader: Box<dyn Read> = if path.extension().and_then(|e| e.to_str()) == Some(ARCHIVE_EXT) {
+        Box::new(zstd::stream::read::Decoder::new(file)?)
+    } else {
+        Box::new(flate2::read::GzDecoder::new(file))
+    };
+    Ok(Box::new(BufReader::new(reader)))
+}
+
+/// Whether a path is a journal archive (sealed or legacy gzip)
+pub fn is_archive(path: &Path) -> bool {
+    matches!(path.extension().and_then(|e| e.to_str()), Some(ARCHIVE_EXT) | Some("gz"))
+}
+
+/// Fetch the sealing secret from cipher and derive the seal key
+pub async fn fetch_key(socket: &str, collection: &str, id: &str) -> Result<[u8; 32]> {
+    let mut stream = tokio::io::BufReader::new(UnixStream::connect(socket).await?);
+
+    let session = cipher_call(&mut stream, serde_json::json!({ "type": "OpenSession" })).await?;
+    let token = session["token"]
+        .as_str()
+        .ok_or_else(|| anyhow!("cipher returned no session"))?
+        .to_string();
+
+    let secret = cipher_call(&mut stream, serde_json::json!({
+        "type": "GetSecret",
+        "data": { "collection": collection, "id": id, "session": token },
+    }))
+    .await;
+
+    let close = serde_json::json!({ "type": "CloseSession", "data": { "token": token } });
+    let _ = cipher_call(&mut stream, close).await;
+
+    let secret = secret?;
+    let value = secret["value"]
+        .as_str()
+        .ok_or_else(|| anyhow!("cipher returned no secret"))?;
+    Ok(derive_key(value.as_bytes()))
+}
+
+async fn cipher_call(
+    stream: &mut tokio::io::BufReader<UnixStream>,
+    request: serde_json::Value,
+) -> Result<serde_json::Value> {
+    stream.get_mut().write_all(format!("{}\n", request).as_bytes()).await?;
+
+    let mut line = String::new();
+    if stream.read_line(&mut line).await? == 0 {
+        return Err(anyhow!("cipher closed the connection"));
+    }
+
+    let response: serde_json::Value = serde_json::from_str(&line)?;
+    if response["status"] == "Error" {
+        return Err(anyhow!("cipher: {}", response["message"].as_str().unwrap_or("error")));
+    }
+    Ok(response)
+}
@@ -1,11 +1,59 @@
 //! Scribe daemon state
 
-use crate::journal::Journal;
+use anyhow::Result;
+use libnyx_ipc::audit::AUDIT_IDENTIFIER;
+
+use crate::coredump::CoredumpStore;
+use crate::follow::Followers;
+use crate::forward::Forwarder;
+use crate::journal::{Journal, LogEntry};
 
 /// Daemon state
 pub struct ScribeState {
     pub journal: Journal,
     pub config: ScribeConfig,
+    pub followers: Followers,
+    pub forwarder: Option<Forwarder>,
+    /// Core dump store, unless collection is disabled
+    pub coredumps: Option<CoredumpStore>,
+}
+
+impl ScribeState {
+    /// Write an entry and hand it to live followers and the forwarder
+    ///
+    /// Entries from logging clients, syslog and the kernel cannot pass for
+    /// audit events: the `audit` identifier is renamed and `audit.*` fields
+    /// are dropped, so nyx-audit only sees what `record_audit` wrote.
+    pub fn record(&mut self, entry: &LogEntry) -> Result<()> {
+        const FIELD_PREFIX: &str = "audit.";
+
+        let claims_audit = entry.identifier == AUDIT_IDENTIFIER
+            || entry.fields.keys().any(|key| key.starts_with(FIELD_PREFIX));
+        if !claims_audit {
+            return self.write(entry);
+        }
+
+        let mut entry = entry.clone();
+        if entry.identifier == AUDIT_IDENTIFIER {
+            entry.identifier = "audit-untrusted".to_string();
+        }
+        entry.fields.retain(|key, _| !key.starts_with(FIELD_PREFIX));
+        self.write(&entry)
+    }
+
+    /// Write an audit event; only for events from privileged daemons
+    pub fn record_audit(&mut self, entry: &LogEntry) -> Result<()> {
+        self.write(entry)
+    }
+
+    fn write(&mut self, entry: &LogEntry) -> Result<()> {
+        self.journal.write(entry)?;
+        self.followers.publish(entry);
+        if let Some(forwarder) = &self.forwarder {
+            forwarder.offer(entry);
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
@@ -13,4 +61,6 @@ pub struct ScribeConfig {
     pub journal_dir: String,
     pub max_file_size: u64,
     pub retention_days: u32,
+    /// Entries queued per follower before they are dropped
+    pub follow_buffer: usize,
 }
@@ -3,6 +3,8 @@
 use anyhow::Result;
 use std::path::Path;
 
+use crate::seal::{self, ArchiveCheck};
+
 /// Calculate disk usage of journal directory
 pub fn disk_usage(dir: &Path) -> Result<DiskUsage> {
     let mut total_size = 0u64;
@@ -17,7 +19,7 @@ pub fn disk_usage(dir: &Path) -> Result<DiskUsage> {
             total_size += metadata.len();
             file_count += 1;
 
-            if entry.path().extension().and_then(|e| e.to_str()) == Some("gz") {
+            if seal::is_archive(&entry.path()) {
                 compressed_size += metadata.len();
             }
         }
@@ -61,7 +63,7 @@ pub fn vacuum(dir: &Path) -> Result<u64> {
         let entry = entry?;
         let path = entry.path();
 
-        if path.extension().and_then(|e| e.to_str()) == Some("gz") {
+        if seal::is_archive(&path) {
             freed += entry.metadata()?.len();
             std::fs::remove_file(&path)?;
         }
@@ -71,7 +73,10 @@ pub fn vacuum(dir: &Path) -> Result<u64> {
 }
 
 /// Verify journal integrity
-pub fn verify(dir: &Path) -> Result<VerifyResult> {
+///
+/// Sealed archives are checked against their seals and the hash chain;
+/// `key` is needed to check keyed seals.
+pub fn verify(dir: &Path, key: Option<&[u8; 32]>) -> Result<VerifyResult> {
     let mut result = VerifyResult::default();
 
     // Check current journal
@@ -79,26 +84,55 @@ pub fn verify(dir: &Path) -> Result<VerifyResult> {
     if current.exists() {
         match verify_journal_file(&current) {
             Ok(count) => result.valid_entries += count,
-            Err(_) => result.corrupted_files += 1,
+            Err(e) => {
+                result.corrupted_files += 1;
+                result.problems.push(format!("{}: {}", current.display(), e));
+            }
         }
     }
 
     // Check archives
+    let mut seals = Vec::new();
     for entry in std::fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
 
-        if path.extension().and_then(|e| e.to_str()) == Some("gz") {
-            match verify_archive(&path) {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(seal::ARCHIVE_EXT) => match seal::check_archive(&path, key) {
+                Ok(check) => {
+                    let seal = match check {
+                        ArchiveCheck::Valid(seal) => seal,
+                        ArchiveCheck::Unverified(seal) => {
+                            result.unverified_archives += 1;
+                            seal
+                        }
+                    };
+                    result.valid_entries += seal.entries;
+                    result.valid_archives += 1;
+                    result.sealed_archives += 1;
+                    seals.push((path, seal));
+                }
+                Err(e) => {
+                    result.corrupted_files += 1;
+                    result.problems.push(format!("{}: {}", path.display(), e));
+                }
+            },
+            Some("gz") => match verify_archive(&path) {
                 Ok(count) => {
                     result.valid_entries += count;
                     result.valid_archives += 1;
                 }
-                Err(_) => result.corrupted_files += 1,
-            }
+                Err(e) => {
+                    result.corrupted_files += 1;
+                    result.problems.push(format!("{}: {}", path.display(), e));
+                }
+            },
+            _ => {}
         }
     }
 
+    result.problems.extend(seal::check_chain(dir, &mut seals));
+
     Ok(result)
 }
 
@@ -120,6 +154,7 @@ fn verify_journal_file(path: &Path) -> Result<u64> {
     Ok(count)
 }
 
+/// Legacy gzip archives carry no seal; only check they decode
 fn verify_archive(path: &Path) -> Result<u64> {
     use std::io::{BufRead, BufReader};
     use flate2::read::GzDecoder;
@@ -144,4 +179,9 @@ pub struct VerifyResult {
     pub valid_entries: u64,
     pub valid_archives: u64,
     pub corrupted_files: u64,
+    pub sealed_archives: u64,
+    /// Keyed seals checked without the key
+    pub unverified_archives: u64,
+    /// Truncation, tampering and chain breaks found
+    pub problems: Vec<String>,
 }
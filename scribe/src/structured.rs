@@ -0,0 +1,243 @@
+//! Structured field parsing
+//!
+//! Services that log with tracing-subscriber's JSON formatter and syslog
+//! clients speaking RFC5424 send key-value data alongside the message.
+//! These parsers pull it out into typed fields so it stays queryable
+//! instead of being flattened into the message text.
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::journal::{FieldValue, Priority};
+
+/// Upper bound on fields kept per entry
+const MAX_FIELDS: usize = 64;
+
+/// Keys tracing-subscriber emits alongside the event fields
+const TRACING_META: &[&str] = &[
+    "timestamp", "level", "fields", "target", "span", "spans",
+    "filename", "line_number", "threadName", "threadId",
+];
+
+/// What a structured line carried
+#[derive(Debug, Default)]
+pub struct Structured {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub priority: Option<Priority>,
+    pub hostname: Option<String>,
+    pub identifier: Option<String>,
+    pub pid: Option<u32>,
+    pub message: String,
+    pub fields: HashMap<String, FieldValue>,
+}
+
+impl Structured {
+    fn insert(&mut self, key: String, value: FieldValue) {
+        if self.fields.len() < MAX_FIELDS || self.fields.contains_key(&key) {
+            self.fields.insert(key, value);
+        }
+    }
+
+    /// Flatten a JSON value into dotted keys
+    fn insert_json(&mut self, key: String, value: &Value) {
+        match value {
+            Value::Null => {}
+            Value::Bool(v) => self.insert(key, FieldValue::Bool(*v)),
+            Value::Number(n) => {
+                let value = match n.as_i64() {
+                    Some(v) => FieldValue::Int(v),
+                    None => FieldValue::Float(n.as_f64().unwrap_or_default()),
+                };
+                self.insert(key, value);
+            }
+            Value::String(v) => self.insert(key, FieldValue::Text(v.clone())),
+            Value::Object(map) => {
+                for (k, v) in map {
+                    self.insert_json(format!("{}.{}", key, k), v);
+                }
+            }
+            Value::Array(_) => self.insert(key, FieldValue::Text(value.to_string())),
+        }
+    }
+}
+
+/// Parse a line of tracing-subscriber JSON output
+///
+/// Handles both the default layout (event fields under `fields`) and
+/// `flatten_event(true)` (event fields at the top level).
+pub fn parse_tracing_json(line: &str) -> Option<Structured> {
+    let line = line.trim();
+    if !line.starts_with('{') {
+        return None;
+    }
+
+    let Value::Object(object) = serde_json::from_str::<Value>(line).ok()? else {
+        return None;
+    };
+
+    // Require the shape tracing produces so arbitrary JSON stays a message
+    let level = object.get("level")?.as_str()?;
+    let mut structured = Structured {
+        priority: Some(match level.to_ascii_uppercase().as_str() {
+            "ERROR" => Priority::Error,
+            "WARN" => Priority::Warning,
+            "INFO" => Priority::Info,
+            _ => Priority::Debug,
+        }),
+        timestamp: object
+            .get("timestamp")
+            .and_then(Value::as_str)
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&Utc)),
+        ..Default::default()
+    };
+
+    let event: Vec<(&String, &Value)> = match object.get("fields") {
+        Some(Value::Object(fields)) => fields.iter().collect(),
+        _ => object
+            .iter()
+            .filter(|(k, _)| !TRACING_META.contains(&k.as_str()))
+            .collect(),
+    };
+
+    for (key, value) in event {
+        if key == "message" {
+            structured.message = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+        } else {
+            structured.insert_json(key.clone(), value);
+        }
+    }
+
+    if let Some(target) = object.get("target") {
+        structured.insert_json("target".to_string(), target);
+    }
+    if let Some(span) = object.get("span") {
+        structured.insert_json("span".to_string(), span);
+    }
+    // The IPC trace lives on an enclosing span; lift the innermost one to
+    // top-level fields so a trace can be queried across daemons
+    let spans: Vec<&Value> = object
+        .get("spans")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .chain(object.get("span"))
+        .collect();
+    for key in ["trace_id", "span_id"] {
+        if let Some(id) = spans.iter().rev().find_map(|span| span.get(key)) {
+            structured.insert_json(key.to_string(), id);
+        }
+    }
+    if let Some(file) = object.get("filename") {
+        structured.insert_json("file".to_string(), file);
+    }
+    if let Some(line) = object.get("line_number") {
+        structured.insert_json("line".to_string(), line);
+    }
+    if let Some(thread) = object.get("threadName") {
+        structured.insert_json("thread".to_string(), thread);
+    }
+
+    Some(structured)
+}
+
+/// Parse an RFC5424 message following the `<PRI>` header
+///
+/// `VERSION TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA [MSG]`
+pub fn parse_rfc5424(rest: &str) -> Option<Structured> {
+    let rest = rest.strip_prefix("1 ")?;
+
+    let mut header = rest.splitn(6, ' ');
+    let timestamp = header.next()?;
+    let hostname = header.next()?;
+    let app_name = header.next()?;
+    let procid = header.next()?;
+    let msgid = header.next()?;
+    let rest = header.next().unwrap_or("");
+
+    let mut structured = Structured {
+        timestamp: nil(timestamp)
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&Utc)),
+        hostname: nil(hostname).map(str::to_string),
+        identifier: nil(app_name).map(str::to_string),
+        pid: nil(procid).and_then(|p| p.parse().ok()),
+        ..Default::default()
+    };
+    if let Some(msgid) = nil(msgid) {
+        structured.insert("msgid".to_string(), FieldValue::Text(msgid.to_string()));
+    }
+
+    let message = if rest.is_empty() {
+        rest
+    } else if let Some(after) = rest.strip_prefix('-') {
+        after
+    } else {
+        parse_structured_data(rest, &mut structured)?
+    };
+
+    let message = message.strip_prefix(' ').unwrap_or(message);
+    structured.message = message.strip_prefix('\u{feff}').unwrap_or(message).to_string();
+
+    Some(structured)
+}
+
+/// `-` is the RFC5424 nil value
+fn nil(value: &str) -> Option<&str> {
+    (value != "-").then_some(value)
+}
+
+/// Parse `[id param="value" ...]...`, returning what follows
+fn parse_structured_data<'a>(mut input: &'a str, structured: &mut Structured) -> Option<&'a str> {
+    if !input.starts_with('[') {
+        return None;
+    }
+
+    while let Some(element) = input.strip_prefix('[') {
+        let id_end = element.find([' ', ']'])?;
+        let id = &element[..id_end];
+        let mut params = &element[id_end..];
+
+        loop {
+            params = params.trim_start_matches(' ');
+            if let Some(after) = params.strip_prefix(']') {
+                input = after;
+                break;
+            }
+
+            let (name, after) = params.split_once("=\"")?;
+            let (value, after) = parse_param_value(after)?;
+            structured.insert(format!("{}.{}", id, name), FieldValue::infer(&value));
+            params = after;
+        }
+    }
+
+    Some(input)
+}
+
+/// Read a quoted parameter value, undoing `\"`, `\\` and `\]` escapes
+fn parse_param_value(input: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = input.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &input[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, escaped @ ('"' | '\\' | ']'))) => value.push(escaped),
+                Some((_, other)) => {
+                    value.push('\\');
+                    value.push(other);
+                }
+                None => return None,
+            },
+            _ => value.push(c),
+        }
+    }
+
+    None
+}
@@ -1,10 +1,13 @@
 //! Alert management
 
-use crate::config::AlertConfig;
+use crate::baseline::{metric_matches, BaselineEngine, Expected};
+use crate::config::{AlertConfig, BaselineAlertRule, DeviationDirection, ServiceAlertRule, ServiceMetric};
+use crate::disk_health::DiskHealth;
 use crate::metrics::SystemSnapshot;
+use crate::services::ServiceMetrics;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use tracing::{debug, warn};
 
 /// Alert severity
@@ -25,6 +28,12 @@ pub enum AlertType {
     HighDisk,
     HighTemperature,
     HighLoad,
+    ServiceThreshold,
+    DiskFailurePredicted,
+    DiskWear,
+    HighDriveTemperature,
+    DaemonUnhealthy,
+    BaselineDeviation,
 }
 
 /// Alert instance
@@ -36,7 +45,7 @@ pub struct Alert {
     pub severity: AlertSeverity,
     /// Message
     pub message: String,
-    /// Current value
+    /// Current value (standard deviations from the mean for baseline alerts)
     pub value: f32,
     /// Threshold
     pub threshold: f32,
@@ -44,6 +53,9 @@ pub struct Alert {
     pub timestamp: DateTime<Utc>,
     /// Resource name (e.g., disk mount point)
     pub resource: Option<String>,
+    /// Rule that produced the alert, for service-scoped alerts
+    #[serde(default)]
+    pub rule: Option<String>,
 }
 
 /// Alert manager
@@ -51,16 +63,28 @@ pub struct AlertManager {
     config: AlertConfig,
     active_alerts: HashMap<(AlertType, Option<String>), Alert>,
     last_alert_time: HashMap<(AlertType, Option<String>), DateTime<Utc>>,
+    /// When each (rule, service) pair first exceeded its threshold
+    pending_since: HashMap<(String, String), DateTime<Utc>>,
+    /// Learned metric baselines
+    baselines: BaselineEngine,
+    /// When each (rule, metric) pair first deviated from its baseline
+    deviating_since: HashMap<(String, String), DateTime<Utc>>,
+    /// Alerts cleared since the last call to `take_cleared`
+    cleared: Vec<Alert>,
     alert_history: Vec<Alert>,
 }
 
 impl AlertManager {
     /// Create new alert manager
-    pub fn new(config: AlertConfig) -> Self {
+    pub fn new(config: AlertConfig, baselines: BaselineEngine) -> Self {
         Self {
             config,
             active_alerts: HashMap::new(),
             last_alert_time: HashMap::new(),
+            pending_since: HashMap::new(),
+            baselines,
+            deviating_since: HashMap::new(),
+            cleared: Vec::new(),
             alert_history: Vec::new(),
         }
     }
@@ -79,6 +103,7 @@ impl AlertManager {
                 if let Some(alert) = self.create_alert(
                     AlertType::HighCpu,
                     None,
+                    None,
                     cpu.usage,
                     self.config.cpu_threshold,
                     format!("CPU usage at {:.1}%", cpu.usage),
@@ -96,6 +121,7 @@ impl AlertManager {
                 if let Some(alert) = self.create_alert(
                     AlertType::HighMemory,
                     None,
+                    None,
                     memory.usage_percent,
                     self.config.memory_threshold,
                     format!("Memory usage at {:.1}%", memory.usage_percent),
@@ -114,6 +140,7 @@ impl AlertManager {
                 if let Some(alert) = self.create_alert(
                     AlertType::HighDisk,
                     key.clone(),
+                    None,
                     disk.usage_percent,
                     self.config.disk_threshold,
                     format!(
@@ -135,6 +162,7 @@ impl AlertManager {
                 if let Some(alert) = self.create_alert(
                     AlertType::HighTemperature,
                     key.clone(),
+                    None,
                     temp.temperature,
                     self.config.temp_threshold,
                     format!(
@@ -161,6 +189,7 @@ impl AlertManager {
             if let Some(alert) = self.create_alert(
                 AlertType::HighLoad,
                 None,
+                None,
                 load_per_core,
                 self.config.load_threshold,
                 format!(
@@ -174,6 +203,242 @@ impl AlertManager {
             self.clear_alert(AlertType::HighLoad, None);
         }
 
+        // Check disk health
+        for disk in &snapshot.disk_health {
+            new_alerts.extend(self.check_disk_health(disk));
+        }
+
+        // Check daemon health probes
+        for daemon in &snapshot.daemon_health {
+            let key = Some(daemon.name.clone());
+            if !daemon.is_failing() {
+                self.clear_alert(AlertType::DaemonUnhealthy, key);
+                continue;
+            }
+
+            let reason = daemon.message.as_deref().unwrap_or("no reason given");
+            let message = match daemon.status {
+                Some(_) => format!("Daemon {} is unhealthy ({})", daemon.name, reason),
+                None => format!("Daemon {} is not answering health probes ({})", daemon.name, reason),
+            };
+            if let Some(alert) = self.create_alert(AlertType::DaemonUnhealthy, key, None, 1.0, 1.0, message) {
+                new_alerts.push(alert);
+            }
+        }
+
+        // Check service-scoped rules
+        new_alerts.extend(self.check_services(&snapshot.services, snapshot.timestamp));
+
+        // Check metrics against their baselines
+        new_alerts.extend(self.check_baselines(snapshot));
+
+        new_alerts
+    }
+
+    /// Learned baselines
+    pub fn baselines(&self) -> &BaselineEngine {
+        &self.baselines
+    }
+
+    /// Check SMART/NVMe health of a single disk
+    fn check_disk_health(&mut self, disk: &DiskHealth) -> Vec<Alert> {
+        let mut new_alerts = Vec::new();
+        let key = Some(disk.device.clone());
+
+        // Predictive failure: failed self-assessment, media errors, or a
+        // growing number of bad sectors
+        let bad_sectors = disk.reallocated_sectors.unwrap_or(0) + disk.pending_sectors.unwrap_or(0);
+        let media_errors = disk.media_errors.unwrap_or(0);
+        let failure = if disk.passed == Some(false) {
+            Some(format!("Disk {} failed SMART self-assessment", disk.device))
+        } else if media_errors > 0 {
+            Some(format!("Disk {} reports {} media errors", disk.device, media_errors))
+        } else if bad_sectors >= self.config.bad_sector_threshold {
+            Some(format!("Disk {} has {} reallocated/pending sectors", disk.device, bad_sectors))
+        } else {
+            None
+        };
+
+        match failure {
+            Some(message) => {
+                let threshold = self.config.bad_sector_threshold as f32;
+                if let Some(alert) = self.create_alert(
+                    AlertType::DiskFailurePredicted,
+                    key.clone(),
+                    None,
+                    bad_sectors.max(media_errors) as f32,
+                    threshold,
+                    message,
+                ) {
+                    new_alerts.push(alert);
+                }
+            }
+            None => self.clear_alert(AlertType::DiskFailurePredicted, key.clone()),
+        }
+
+        if let Some(wear) = disk.wear_percent {
+            if wear >= self.config.wear_threshold {
+                if let Some(alert) = self.create_alert(
+                    AlertType::DiskWear,
+                    key.clone(),
+                    None,
+                    wear,
+                    self.config.wear_threshold,
+                    format!("Disk {} at {:.0}% of rated endurance", disk.device, wear),
+                ) {
+                    new_alerts.push(alert);
+                }
+            } else {
+                self.clear_alert(AlertType::DiskWear, key.clone());
+            }
+        }
+
+        if let Some(temp) = disk.temperature {
+            if temp >= self.config.drive_temp_threshold {
+                if let Some(alert) = self.create_alert(
+                    AlertType::HighDriveTemperature,
+                    key.clone(),
+                    None,
+                    temp,
+                    self.config.drive_temp_threshold,
+                    format!("Disk {} temperature at {:.0}°C", disk.device, temp),
+                ) {
+                    new_alerts.push(alert);
+                }
+            } else {
+                self.clear_alert(AlertType::HighDriveTemperature, key);
+            }
+        }
+
+        new_alerts
+    }
+
+    /// Evaluate service rules against per-service metrics
+    fn check_services(&mut self, services: &[ServiceMetrics], now: DateTime<Utc>) -> Vec<Alert> {
+        let rules = self.config.rules.clone();
+        let mut new_alerts = Vec::new();
+
+        for rule in &rules {
+            for service in services.iter().filter(|s| rule.matches(&s.name)) {
+                let pending_key = (rule.name.clone(), service.name.clone());
+                let resource = Some(format!("{}/{}", service.name, rule.name));
+                let value = service_value(rule.metric, service);
+
+                if value < rule.threshold {
+                    self.pending_since.remove(&pending_key);
+                    self.clear_alert(AlertType::ServiceThreshold, resource);
+                    continue;
+                }
+
+                let since = *self.pending_since.entry(pending_key).or_insert(now);
+                if (now - since).num_seconds() < rule.for_secs as i64 {
+                    continue;
+                }
+
+                if let Some(alert) = self.create_alert(
+                    AlertType::ServiceThreshold,
+                    resource,
+                    Some(rule.name.clone()),
+                    value,
+                    rule.threshold,
+                    service_message(rule, service, value),
+                ) {
+                    new_alerts.push(alert);
+                }
+            }
+        }
+
+        // Drop pending state and alerts for services that went away
+        let present: Vec<&str> = services.iter().map(|s| s.name.as_str()).collect();
+        self.pending_since
+            .retain(|(_, service), _| present.contains(&service.as_str()));
+
+        let vanished: Vec<Option<String>> = self
+            .active_alerts
+            .keys()
+            .filter(|(alert_type, _)| *alert_type == AlertType::ServiceThreshold)
+            .filter_map(|(_, resource)| resource.as_ref())
+            .filter(|resource| {
+                !present.iter().any(|service| {
+                    resource
+                        .strip_prefix(service)
+                        .is_some_and(|rule| rule.starts_with('/'))
+                })
+            })
+            .map(|resource| Some(resource.clone()))
+            .collect();
+        for resource in vanished {
+            self.clear_alert(AlertType::ServiceThreshold, resource);
+        }
+
+        new_alerts
+    }
+
+    /// Evaluate baseline rules, then learn from the snapshot
+    ///
+    /// Samples are judged before they are learned, so a spike is compared
+    /// against the baseline it broke from.
+    fn check_baselines(&mut self, snapshot: &SystemSnapshot) -> Vec<Alert> {
+        let now = snapshot.timestamp;
+        let values = self.baselines.sample(snapshot);
+        let rules = self.config.baseline_rules.clone();
+        let mut new_alerts = Vec::new();
+        let mut present = HashSet::new();
+
+        for rule in &rules {
+            for (metric, value) in values.iter().filter(|(m, _)| metric_matches(&rule.metric, m)) {
+                let resource = Some(format!("{}/{}", metric, rule.name));
+                present.insert(resource.clone());
+                let pending_key = (rule.name.clone(), metric.clone());
+
+                let deviation = self
+                    .baselines
+                    .expected(metric, rule.seasonality, now)
+                    .and_then(|expected| deviation(rule, *value, &expected).map(|z| (z, expected)));
+                let Some((z, expected)) = deviation else {
+                    self.deviating_since.remove(&pending_key);
+                    self.clear_alert(AlertType::BaselineDeviation, resource);
+                    continue;
+                };
+
+                let since = *self.deviating_since.entry(pending_key).or_insert(now);
+                if (now - since).num_seconds() < rule.for_secs as i64 {
+                    continue;
+                }
+
+                if let Some(alert) = self.create_alert(
+                    AlertType::BaselineDeviation,
+                    resource,
+                    Some(rule.name.clone()),
+                    z.abs() as f32,
+                    rule.deviation,
+                    format!(
+                        "{} at {:.2}, usually {:.2} ± {:.2} ({:+.1}σ, {:?} baseline)",
+                        metric, value, expected.mean, expected.stddev, z, expected.seasonality
+                    ),
+                ) {
+                    new_alerts.push(alert);
+                }
+            }
+        }
+
+        // Drop state for metrics that went away (stopped services, removed disks)
+        self.deviating_since.retain(|(rule, metric), _| {
+            present.contains(&Some(format!("{}/{}", metric, rule)))
+        });
+        let vanished: Vec<Option<String>> = self
+            .active_alerts
+            .keys()
+            .filter(|(alert_type, resource)| {
+                *alert_type == AlertType::BaselineDeviation && !present.contains(resource)
+            })
+            .map(|(_, resource)| resource.clone())
+            .collect();
+        for resource in vanished {
+            self.clear_alert(AlertType::BaselineDeviation, resource);
+        }
+
+        self.baselines.learn(&values, now);
         new_alerts
     }
 
@@ -182,6 +447,7 @@ impl AlertManager {
         &mut self,
         alert_type: AlertType,
         resource: Option<String>,
+        rule: Option<String>,
         value: f32,
         threshold: f32,
         message: String,
@@ -208,6 +474,7 @@ impl AlertManager {
             threshold,
             timestamp: now,
             resource,
+            rule,
         };
 
         warn!("Alert: {} ({:?})", alert.message, severity);
@@ -222,17 +489,28 @@ impl AlertManager {
     /// Clear an alert
     fn clear_alert(&mut self, alert_type: AlertType, resource: Option<String>) {
         let key = (alert_type, resource);
-        if self.active_alerts.remove(&key).is_some() {
+        if let Some(alert) = self.active_alerts.remove(&key) {
             debug!("Alert {:?} cleared", alert_type);
+            self.cleared.push(alert);
         }
     }
 
+    /// Take alerts that cleared since the last call
+    pub fn take_cleared(&mut self) -> Vec<Alert> {
+        std::mem::take(&mut self.cleared)
+    }
+
     /// Determine alert severity based on how far over threshold
     fn determine_severity(&self, alert_type: AlertType, value: f32, threshold: f32) -> AlertSeverity {
+        // Predicted drive failure and failing daemons are always critical
+        if matches!(alert_type, AlertType::DiskFailurePredicted | AlertType::DaemonUnhealthy) {
+            return AlertSeverity::Critical;
+        }
+
         let ratio = value / threshold;
 
         // For temperature, be more aggressive
-        if matches!(alert_type, AlertType::HighTemperature) {
+        if matches!(alert_type, AlertType::HighTemperature | AlertType::HighDriveTemperature) {
             if ratio >= 1.15 {
                 AlertSeverity::Critical
             } else if ratio >= 1.05 {
@@ -281,6 +559,49 @@ impl AlertManager {
     }
 }
 
+/// Standard deviations `value` lies from the baseline, if that breaks the
+/// rule
+fn deviation(rule: &BaselineAlertRule, value: f64, expected: &Expected) -> Option<f64> {
+    /// A metric that never moved would otherwise fire on any change
+    const MIN_RELATIVE_STDDEV: f64 = 0.01;
+
+    let delta = value - expected.mean;
+    if delta.abs() < rule.min_delta {
+        return None;
+    }
+
+    let stddev = expected.stddev.max(expected.mean.abs() * MIN_RELATIVE_STDDEV).max(f64::EPSILON);
+    let z = delta / stddev;
+    let breaks = match rule.direction {
+        DeviationDirection::Above => z >= rule.deviation as f64,
+        DeviationDirection::Below => -z >= rule.deviation as f64,
+        DeviationDirection::Both => z.abs() >= rule.deviation as f64,
+    };
+    breaks.then_some(z)
+}
+
+/// Value of the metric a rule watches
+fn service_value(metric: ServiceMetric, service: &ServiceMetrics) -> f32 {
+    match metric {
+        ServiceMetric::Memory => service.memory as f32,
+        ServiceMetric::Cpu => service.cpu_usage,
+        ServiceMetric::Tasks => service.tasks as f32,
+    }
+}
+
+/// Human-readable message for a service alert
+fn service_message(rule: &ServiceAlertRule, service: &ServiceMetrics, value: f32) -> String {
+    match rule.metric {
+        ServiceMetric::Memory => format!(
+            "Service {} memory at {:.1} MB",
+            service.name,
+            value / (1024.0 * 1024.0)
+        ),
+        ServiceMetric::Cpu => format!("Service {} CPU usage at {:.1}%", service.name, value),
+        ServiceMetric::Tasks => format!("Service {} running {} tasks", service.name, value as u64),
+    }
+}
+
 /// Alert counts by severity
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AlertCounts {
@@ -0,0 +1,238 @@
+//! Statistical baselines for anomaly alerts
+//!
+//! Every collected metric gets a rolling mean and standard deviation, kept
+//! three ways: overall, per hour of the day and per hour of the week (local
+//! time). Baseline alert rules compare a new sample against the seasonal
+//! baseline they ask for, so a metric that is busy every weekday morning
+//! is judged against other weekday mornings rather than against the quiet
+//! night before.
+//!
+//! Statistics are exponentially weighted over `window_samples`: a plain
+//! running mean until that many samples arrived, then each new sample
+//! weighs as much as the window's share. Seasonal buckets only see their
+//! own hour, so the same window spans days for the daily baseline and
+//! weeks for the weekly one. Baselines are saved to disk so that learning
+//! survives restarts.
+
+use crate::config::{BaselineConfig, Seasonality};
+use crate::metrics::SystemSnapshot;
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Local, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tracing::{debug, info, warn};
+
+/// Hours in a day and in a week
+const DAY_BUCKETS: usize = 24;
+const WEEK_BUCKETS: usize = 24 * 7;
+
+/// Rolling mean and variance
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stats {
+    pub samples: u64,
+    pub mean: f64,
+    pub variance: f64,
+}
+
+impl Stats {
+    fn update(&mut self, value: f64, window: u32) {
+        self.samples += 1;
+        let alpha = 1.0 / self.samples.min(window.max(1) as u64) as f64;
+        let delta = value - self.mean;
+        self.mean += alpha * delta;
+        self.variance = (1.0 - alpha) * (self.variance + alpha * delta * delta);
+    }
+
+    pub fn stddev(&self) -> f64 {
+        self.variance.sqrt()
+    }
+}
+
+/// Baselines of one metric
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MetricBaseline {
+    overall: Stats,
+    daily: Vec<Stats>,
+    weekly: Vec<Stats>,
+}
+
+impl Default for MetricBaseline {
+    fn default() -> Self {
+        Self {
+            overall: Stats::default(),
+            daily: vec![Stats::default(); DAY_BUCKETS],
+            weekly: vec![Stats::default(); WEEK_BUCKETS],
+        }
+    }
+}
+
+/// What a sample is compared against
+#[derive(Debug, Clone, Copy)]
+pub struct Expected {
+    pub mean: f64,
+    pub stddev: f64,
+    /// Baseline used, which may be coarser than asked for while the
+    /// seasonal one is still learning
+    pub seasonality: Seasonality,
+}
+
+/// Learns baselines from snapshots
+pub struct BaselineEngine {
+    config: BaselineConfig,
+    metrics: BTreeMap<String, MetricBaseline>,
+    /// Last counter value and time, for rates
+    counters: HashMap<String, (u64, DateTime<Utc>)>,
+}
+
+impl BaselineEngine {
+    /// Create an engine with no history
+    pub fn new(config: BaselineConfig) -> Self {
+        Self {
+            config,
+            metrics: BTreeMap::new(),
+            counters: HashMap::new(),
+        }
+    }
+
+    /// Create an engine, picking up baselines saved by an earlier run
+    pub fn load(config: BaselineConfig) -> Self {
+        let mut engine = Self::new(config);
+
+        match std::fs::read_to_string(&engine.config.state_path) {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(metrics) => {
+                    engine.metrics = metrics;
+                    info!("Loaded baselines for {} metrics", engine.metrics.len());
+                }
+                Err(e) => warn!("Ignoring unreadable baselines {}: {}", engine.config.state_path, e),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to read baselines {}: {}", engine.config.state_path, e),
+        }
+
+        engine
+    }
+
+    /// Write baselines to the state file
+    pub fn save(&self) -> Result<()> {
+        let path = std::path::Path::new(&self.config.state_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(&self.metrics)?)?;
+        std::fs::rename(&tmp, path)?;
+        debug!("Saved baselines for {} metrics", self.metrics.len());
+        Ok(())
+    }
+
+    /// Metric values in a snapshot, by name
+    ///
+    /// Network counters become bytes per second, so the first snapshot has
+    /// no network values.
+    pub fn sample(&mut self, snapshot: &SystemSnapshot) -> Vec<(String, f64)> {
+        let mut values = Vec::new();
+
+        if let Some(cpu) = &snapshot.cpu {
+            values.push(("cpu".to_string(), cpu.usage as f64));
+        }
+        if let Some(memory) = &snapshot.memory {
+            values.push(("memory".to_string(), memory.usage_percent as f64));
+        }
+        values.push(("load".to_string(), snapshot.load.one));
+
+        for disk in &snapshot.disks {
+            values.push((format!("disk.{}", disk.mount_point), disk.usage_percent as f64));
+        }
+        for temp in &snapshot.temperatures {
+            values.push((format!("temperature.{}", temp.label), temp.temperature as f64));
+        }
+        for net in &snapshot.networks {
+            for (direction, bytes) in [("rx", net.rx_bytes), ("tx", net.tx_bytes)] {
+                let name = format!("network.{}.{}", net.name, direction);
+                if let Some(rate) = self.rate(&name, bytes, snapshot.timestamp) {
+                    values.push((name, rate));
+                }
+            }
+        }
+        for service in &snapshot.services {
+            values.push((format!("service.{}.cpu", service.name), service.cpu_usage as f64));
+            values.push((format!("service.{}.memory", service.name), service.memory as f64));
+        }
+
+        values
+    }
+
+    /// Per-second rate of a counter since the last sample
+    fn rate(&mut self, name: &str, value: u64, now: DateTime<Utc>) -> Option<f64> {
+        let previous = self.counters.insert(name.to_string(), (value, now))?;
+        let (last, at) = previous;
+        let secs = (now - at).num_milliseconds() as f64 / 1000.0;
+
+        // Counter reset (interface reappeared) or clock went backwards
+        if value < last || secs <= 0.0 {
+            return None;
+        }
+        Some((value - last) as f64 / secs)
+    }
+
+    /// Baseline for `metric` at `now`, falling back to coarser baselines
+    /// while finer ones have fewer than `min_samples`
+    pub fn expected(&self, metric: &str, seasonality: Seasonality, now: DateTime<Utc>) -> Option<Expected> {
+        let baseline = self.metrics.get(metric)?;
+        let (day, week) = buckets(now);
+
+        let candidates = [
+            (Seasonality::Weekly, &baseline.weekly[week]),
+            (Seasonality::Daily, &baseline.daily[day]),
+            (Seasonality::None, &baseline.overall),
+        ];
+        candidates
+            .iter()
+            .skip_while(|(s, _)| *s != seasonality)
+            .find(|(_, stats)| stats.samples >= self.config.min_samples)
+            .map(|(s, stats)| Expected {
+                mean: stats.mean,
+                stddev: stats.stddev(),
+                seasonality: *s,
+            })
+    }
+
+    /// Fold samples into the baselines
+    pub fn learn(&mut self, values: &[(String, f64)], now: DateTime<Utc>) {
+        let (day, week) = buckets(now);
+        let window = self.config.window_samples;
+
+        for (name, value) in values {
+            let baseline = self.metrics.entry(name.clone()).or_default();
+            baseline.overall.update(*value, window);
+            baseline.daily[day].update(*value, window);
+            baseline.weekly[week].update(*value, window);
+        }
+    }
+}
+
+/// Hour-of-day and hour-of-week buckets, in local time
+fn buckets(now: DateTime<Utc>) -> (usize, usize) {
+    let local = now.with_timezone(&Local);
+    let hour = local.hour() as usize;
+    let weekday = local.weekday().num_days_from_monday() as usize;
+    (hour, weekday * DAY_BUCKETS + hour)
+}
+
+/// Match a metric name against a pattern where `*` matches any run of
+/// characters
+pub fn metric_matches(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(tail) = name.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=tail.len())
+                .filter(|&i| tail.is_char_boundary(i))
+                .any(|i| metric_matches(rest, &tail[i..]))
+        }
+    }
+}
@@ -1,7 +1,9 @@
 //! Configuration for Sentinel monitoring daemon
 
+use crate::alerts::AlertType;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 
 /// Main configuration
@@ -19,6 +21,18 @@ pub struct SentinelConfig {
     #[serde(default)]
     pub processes: ProcessConfig,
 
+    /// Per-service cgroup monitoring
+    #[serde(default)]
+    pub services: ServiceConfig,
+
+    /// Alert delivery (herald notifications, grimoire rituals)
+    #[serde(default)]
+    pub routing: RoutingConfig,
+
+    /// Baseline learning for anomaly alerts
+    #[serde(default)]
+    pub baselines: BaselineConfig,
+
     /// Daemon settings
     #[serde(default)]
     pub daemon: DaemonConfig,
@@ -30,6 +44,9 @@ impl Default for SentinelConfig {
             metrics: MetricsConfig::default(),
             alerts: AlertConfig::default(),
             processes: ProcessConfig::default(),
+            services: ServiceConfig::default(),
+            routing: RoutingConfig::default(),
+            baselines: BaselineConfig::default(),
             daemon: DaemonConfig::default(),
         }
     }
@@ -66,6 +83,18 @@ pub struct MetricsConfig {
     #[serde(default = "default_true")]
     pub temperature: bool,
 
+    /// Enable per-service cgroup metrics
+    #[serde(default = "default_true")]
+    pub services: bool,
+
+    /// Enable SMART/NVMe disk health monitoring
+    #[serde(default = "default_true")]
+    pub disk_health: bool,
+
+    /// Disk health refresh interval in seconds
+    #[serde(default = "default_disk_health_interval")]
+    pub disk_health_interval_secs: u32,
+
     /// History retention in samples
     #[serde(default = "default_history_size")]
     pub history_size: usize,
@@ -89,6 +118,9 @@ impl Default for MetricsConfig {
             network: true,
             processes: true,
             temperature: true,
+            services: true,
+            disk_health: true,
+            disk_health_interval_secs: default_disk_health_interval(),
             history_size: default_history_size(),
             top_cpu_count: default_top_count(),
             top_memory_count: default_top_count(),
@@ -123,9 +155,29 @@ pub struct AlertConfig {
     #[serde(default = "default_load_threshold")]
     pub load_threshold: f32,
 
+    /// Drive temperature threshold (Celsius)
+    #[serde(default = "default_drive_temp_threshold")]
+    pub drive_temp_threshold: f32,
+
+    /// Drive wear threshold (percentage of rated endurance used)
+    #[serde(default = "default_wear_threshold")]
+    pub wear_threshold: f32,
+
+    /// Reallocated plus pending sectors before failure is predicted
+    #[serde(default = "default_bad_sector_threshold")]
+    pub bad_sector_threshold: u64,
+
     /// Alert cooldown in seconds
     #[serde(default = "default_cooldown")]
     pub cooldown_secs: u32,
+
+    /// Service-scoped alert rules
+    #[serde(default)]
+    pub rules: Vec<ServiceAlertRule>,
+
+    /// Alert rules comparing metrics against their learned baselines
+    #[serde(default)]
+    pub baseline_rules: Vec<BaselineAlertRule>,
 }
 
 impl Default for AlertConfig {
@@ -137,11 +189,146 @@ impl Default for AlertConfig {
             disk_threshold: default_disk_threshold(),
             temp_threshold: default_temp_threshold(),
             load_threshold: default_load_threshold(),
+            drive_temp_threshold: default_drive_temp_threshold(),
+            wear_threshold: default_wear_threshold(),
+            bad_sector_threshold: default_bad_sector_threshold(),
             cooldown_secs: default_cooldown(),
+            rules: Vec::new(),
+            baseline_rules: Vec::new(),
         }
     }
 }
 
+/// Alert rule scoped to a service
+///
+/// ```yaml
+/// rules:
+///   - name: vesper-memory
+///     service: vesper
+///     metric: memory
+///     threshold: 524288000   # 500 MB
+///     for_secs: 300
+///     ritual: vesper-restart
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceAlertRule {
+    /// Rule name
+    pub name: String,
+
+    /// Service selector (unit name, or "*" for every service)
+    pub service: String,
+
+    /// Metric to compare
+    pub metric: ServiceMetric,
+
+    /// Threshold (bytes for memory, percent for CPU, count for tasks)
+    pub threshold: f32,
+
+    /// How long the threshold must be exceeded before firing
+    #[serde(default)]
+    pub for_secs: u32,
+
+    /// Send a herald notification when this rule fires or clears
+    #[serde(default = "default_true")]
+    pub notify: bool,
+
+    /// Grimoire ritual to run when this rule fires
+    #[serde(default)]
+    pub ritual: Option<String>,
+}
+
+impl ServiceAlertRule {
+    /// Check whether this rule applies to a service
+    pub fn matches(&self, service: &str) -> bool {
+        self.service == "*" || self.service == service
+    }
+}
+
+/// Per-service metric usable in alert rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceMetric {
+    Memory,
+    Cpu,
+    Tasks,
+}
+
+/// Alert rule firing when a metric deviates from its baseline
+///
+/// Metrics are `cpu`, `memory`, `load`, `disk.<mount>`,
+/// `temperature.<label>`, `network.<iface>.rx`/`.tx` (bytes per second)
+/// and `service.<name>.cpu`/`.memory`; `*` matches any part of a name.
+///
+/// ```yaml
+/// baseline_rules:
+///   - name: wan-throughput
+///     metric: network.eth0.*
+///     deviation: 4.0          # standard deviations
+///     seasonality: weekly
+///     min_delta: 1048576      # ignore swings under 1 MB/s
+///     for_secs: 120
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineAlertRule {
+    /// Rule name
+    pub name: String,
+
+    /// Metric name or pattern
+    pub metric: String,
+
+    /// Standard deviations from the mean before firing
+    #[serde(default = "default_deviation")]
+    pub deviation: f32,
+
+    /// Which side of the baseline counts
+    #[serde(default)]
+    pub direction: DeviationDirection,
+
+    /// Baseline to compare against
+    #[serde(default)]
+    pub seasonality: Seasonality,
+
+    /// Smallest absolute difference from the mean that can fire, for
+    /// metrics too steady for their deviation to mean much
+    #[serde(default)]
+    pub min_delta: f64,
+
+    /// How long the metric must deviate before firing
+    #[serde(default)]
+    pub for_secs: u32,
+
+    /// Send a herald notification when this rule fires or clears
+    #[serde(default = "default_true")]
+    pub notify: bool,
+
+    /// Grimoire ritual to run when this rule fires
+    #[serde(default)]
+    pub ritual: Option<String>,
+}
+
+/// Which deviations a baseline rule fires on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviationDirection {
+    Above,
+    Below,
+    #[default]
+    Both,
+}
+
+/// Seasonal baseline a rule compares against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Seasonality {
+    /// One baseline for all times
+    None,
+    /// Per hour of the day
+    #[default]
+    Daily,
+    /// Per hour of the week
+    Weekly,
+}
+
 /// Process monitoring configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessConfig {
@@ -168,6 +355,115 @@ impl Default for ProcessConfig {
     }
 }
 
+/// Service cgroup configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceConfig {
+    /// Cgroup v2 mount point
+    #[serde(default = "default_cgroup_root")]
+    pub cgroup_root: String,
+
+    /// Slice used by nyx-serviced (contains `<unit>.scope` cgroups)
+    #[serde(default = "default_serviced_slice")]
+    pub serviced_slice: String,
+
+    /// Root cgroup used by archon
+    #[serde(default = "default_archon_root")]
+    pub archon_root: String,
+
+    /// Daemon sockets probed with `GetHealth`, by service name
+    #[serde(default = "default_health_sockets")]
+    pub health_sockets: HashMap<String, String>,
+
+    /// Health probe interval in seconds
+    #[serde(default = "default_health_interval")]
+    pub health_interval_secs: u32,
+}
+
+impl Default for ServiceConfig {
+    fn default() -> Self {
+        Self {
+            cgroup_root: default_cgroup_root(),
+            serviced_slice: default_serviced_slice(),
+            archon_root: default_archon_root(),
+            health_sockets: default_health_sockets(),
+            health_interval_secs: default_health_interval(),
+        }
+    }
+}
+
+/// Alert routing configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutingConfig {
+    /// Deliver alerts as herald notifications
+    #[serde(default = "default_true")]
+    pub notify: bool,
+
+    /// Also notify when an alert clears
+    #[serde(default = "default_true")]
+    pub notify_on_clear: bool,
+
+    /// Herald socket path
+    #[serde(default = "default_herald_socket")]
+    pub herald_socket: String,
+
+    /// Grimoire socket path (for ritual triggers)
+    #[serde(default = "default_grimoire_socket")]
+    pub grimoire_socket: String,
+
+    /// Rituals to run for built-in alert types, keyed by alert type
+    /// (e.g., `high_disk: disk-cleanup`)
+    #[serde(default)]
+    pub rituals: HashMap<AlertType, String>,
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            notify: true,
+            notify_on_clear: true,
+            herald_socket: default_herald_socket(),
+            grimoire_socket: default_grimoire_socket(),
+            rituals: HashMap::new(),
+        }
+    }
+}
+
+/// Baseline learning configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineConfig {
+    /// Learn baselines and evaluate baseline rules
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Samples a rolling baseline spans
+    #[serde(default = "default_baseline_window")]
+    pub window_samples: u32,
+
+    /// Samples a baseline needs before rules use it
+    #[serde(default = "default_baseline_min_samples")]
+    pub min_samples: u64,
+
+    /// File baselines are saved to
+    #[serde(default = "default_baseline_state_path")]
+    pub state_path: String,
+
+    /// How often baselines are saved, in seconds
+    #[serde(default = "default_baseline_save_interval")]
+    pub save_interval_secs: u32,
+}
+
+impl Default for BaselineConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_samples: default_baseline_window(),
+            min_samples: default_baseline_min_samples(),
+            state_path: default_baseline_state_path(),
+            save_interval_secs: default_baseline_save_interval(),
+        }
+    }
+}
+
 /// Daemon configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DaemonConfig {
@@ -222,6 +518,42 @@ fn default_load_threshold() -> f32 {
     2.0
 }
 
+fn default_drive_temp_threshold() -> f32 {
+    70.0
+}
+
+fn default_wear_threshold() -> f32 {
+    90.0
+}
+
+fn default_bad_sector_threshold() -> u64 {
+    10
+}
+
+fn default_deviation() -> f32 {
+    3.0
+}
+
+fn default_baseline_window() -> u32 {
+    2000 // ~3 hours overall at 5s intervals, ~3 days per daily bucket
+}
+
+fn default_baseline_min_samples() -> u64 {
+    360 // 30 minutes at 5s intervals
+}
+
+fn default_baseline_state_path() -> String {
+    "/var/lib/sentinel/baselines.json".to_string()
+}
+
+fn default_baseline_save_interval() -> u32 {
+    600 // 10 minutes
+}
+
+fn default_disk_health_interval() -> u32 {
+    300 // 5 minutes
+}
+
 fn default_cooldown() -> u32 {
     300 // 5 minutes
 }
@@ -230,6 +562,43 @@ fn default_top_count() -> usize {
     10
 }
 
+fn default_cgroup_root() -> String {
+    "/sys/fs/cgroup".to_string()
+}
+
+fn default_serviced_slice() -> String {
+    "nyx.slice".to_string()
+}
+
+fn default_archon_root() -> String {
+    "nyx".to_string()
+}
+
+fn default_health_sockets() -> HashMap<String, String> {
+    use libnyx_ipc::paths;
+
+    [
+        ("herald", paths::HERALD_SOCKET),
+        ("vesper", paths::VESPER_SOCKET),
+        ("wraith", paths::WRAITH_SOCKET),
+    ]
+    .into_iter()
+    .map(|(name, socket)| (name.to_string(), socket.to_string()))
+    .collect()
+}
+
+fn default_health_interval() -> u32 {
+    30
+}
+
+fn default_herald_socket() -> String {
+    "/run/herald/herald.sock".to_string()
+}
+
+fn default_grimoire_socket() -> String {
+    "/run/grimoire/grimoire.sock".to_string()
+}
+
 fn default_socket_path() -> String {
     "/run/sentinel/sentinel.sock".to_string()
 }
@@ -1,11 +1,18 @@
 //! sentinelctl - Sentinel control utility
 
 mod alerts;
+#[allow(dead_code)] // only needed for the alert types
+mod baseline;
 mod config;
+mod daemon_health;
+mod disk_health;
 mod ipc;
 mod metrics;
+mod processes;
+mod services;
 
 use crate::ipc::{IpcClient, IpcRequest};
+use crate::processes::ProcessSort;
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 
@@ -32,7 +39,7 @@ enum Commands {
     /// Show memory metrics
     Memory,
 
-    /// Show disk metrics
+    /// Show disk usage and health
     Disks,
 
     /// Show network metrics
@@ -49,14 +56,34 @@ enum Commands {
 
     /// Show top processes
     Top {
-        /// Sort by cpu or memory
+        /// Sort by cpu, memory, pid or name
         #[arg(short, long, default_value = "cpu")]
         sort: String,
+
+        /// Number of processes to show
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+
+        /// Only show processes of this service
+        #[arg(short, long)]
+        unit: Option<String>,
+    },
+
+    /// Show per-service resource usage
+    Services {
+        /// Only show this service
+        name: Option<String>,
     },
 
     /// Show active alerts
     Alerts,
 
+    /// Show counters pushed by other daemons
+    Reported {
+        /// Only show this daemon
+        source: Option<String>,
+    },
+
     /// Show full daemon info
     Info,
 }
@@ -169,6 +196,7 @@ async fn main() -> Result<()> {
 
         Commands::Disks => {
             let metrics = client.get_metrics().await?;
+            let health = client.get_disk_health().await?;
 
             println!("Disk Information");
             println!("================");
@@ -195,6 +223,48 @@ async fn main() -> Result<()> {
                     println!();
                 }
             }
+
+            if !health.is_empty() {
+                println!("Disk Health");
+                println!("===========");
+
+                for disk in &health {
+                    let status = match disk.passed {
+                        Some(true) => "PASSED",
+                        Some(false) => "FAILED",
+                        None => "unknown",
+                    };
+                    println!(
+                        "{} ({:?}) - {}",
+                        disk.device,
+                        disk.interface,
+                        disk.model.as_deref().unwrap_or("unknown model")
+                    );
+                    println!("  SMART:        {}", status);
+                    if let Some(temp) = disk.temperature {
+                        println!("  Temperature:  {:.0}°C", temp);
+                    }
+                    if let Some(wear) = disk.wear_percent {
+                        println!("  Wear:         {:.0}%", wear);
+                    }
+                    if let Some(spare) = disk.available_spare {
+                        println!("  Spare:        {:.0}%", spare);
+                    }
+                    if let Some(sectors) = disk.reallocated_sectors {
+                        println!("  Reallocated:  {}", sectors);
+                    }
+                    if let Some(sectors) = disk.pending_sectors {
+                        println!("  Pending:      {}", sectors);
+                    }
+                    if let Some(errors) = disk.media_errors {
+                        println!("  Media errors: {}", errors);
+                    }
+                    if let Some(hours) = disk.power_on_hours {
+                        println!("  Power-on:     {}h", hours);
+                    }
+                    println!();
+                }
+            }
         }
 
         Commands::Networks => {
@@ -267,30 +337,56 @@ async fn main() -> Result<()> {
             );
         }
 
-        Commands::Top { sort } => {
-            let metrics = client.get_metrics().await?;
-
-            let processes = if sort == "memory" {
-                &metrics.top_memory_processes
-            } else {
-                &metrics.top_cpu_processes
+        Commands::Top { sort, limit, unit } => {
+            let (sort, label) = match sort.as_str() {
+                "memory" => (ProcessSort::Memory, "Memory"),
+                "pid" => (ProcessSort::Pid, "PID"),
+                "name" => (ProcessSort::Name, "Name"),
+                _ => (ProcessSort::Cpu, "CPU"),
             };
+            let table = client.query_processes(sort, Some(limit), unit).await?;
 
-            println!("Top Processes by {}", if sort == "memory" { "Memory" } else { "CPU" });
+            println!("Processes by {} ({} total)", label, table.total);
             println!("============================");
-            println!("{:>7} {:>6} {:>10} {}", "PID", "CPU%", "MEM", "NAME");
+            println!("{:>7} {:>7} {:>5} {:>6} {:>10} {:<16} {}", "PID", "PPID", "STATE", "CPU%", "RSS", "UNIT", "NAME");
 
-            for proc in processes {
+            for proc in &table.processes {
                 println!(
-                    "{:>7} {:>5.1}% {:>10} {}",
+                    "{:>7} {:>7} {:>5} {:>5.1}% {:>10} {:<16} {}",
                     proc.pid,
+                    proc.ppid,
+                    proc.state,
                     proc.cpu_usage,
-                    format_bytes(proc.memory),
+                    format_bytes(proc.rss),
+                    proc.unit.as_deref().unwrap_or("-"),
                     proc.name
                 );
             }
         }
 
+        Commands::Services { name } => {
+            let services = client.get_services(name).await?;
+
+            println!("Service Resource Usage");
+            println!("======================");
+
+            if services.is_empty() {
+                println!("No services found");
+            } else {
+                println!("{:<24} {:>6} {:>10} {:>6} {}", "SERVICE", "CPU%", "MEM", "TASKS", "SOURCE");
+                for svc in &services {
+                    println!(
+                        "{:<24} {:>5.1}% {:>10} {:>6} {:?}",
+                        svc.name,
+                        svc.cpu_usage,
+                        format_bytes(svc.memory),
+                        svc.tasks,
+                        svc.source
+                    );
+                }
+            }
+        }
+
         Commands::Alerts => {
             let alerts = client.get_alerts().await?;
 
@@ -319,6 +415,25 @@ async fn main() -> Result<()> {
             }
         }
 
+        Commands::Reported { source } => {
+            let reported = client.get_reported(source).await?;
+
+            println!("Reported Metrics");
+            println!("================");
+
+            if reported.is_empty() {
+                println!("No daemons have reported metrics");
+            }
+            for report in &reported {
+                println!("{} (at {})", report.source, report.received_at.format("%H:%M:%S"));
+                let mut metrics: Vec<_> = report.metrics.iter().collect();
+                metrics.sort_by(|a, b| a.0.cmp(b.0));
+                for (name, value) in metrics {
+                    println!("  {:<28} {}", name, value);
+                }
+            }
+        }
+
         Commands::Info => {
             let status = client.get_status().await?;
 
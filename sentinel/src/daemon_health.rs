@@ -0,0 +1,102 @@
+//! Daemon health probes
+//!
+//! Every daemon answers `GetHealth` on its socket (see libnyx-ipc's `health`
+//! module). A separate task probes the configured sockets into a shared
+//! cache, the same way SMART data is polled, so a daemon that hangs never
+//! holds up metrics collection.
+
+use libnyx_ipc::{HealthClient, HealthStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::debug;
+
+/// Health of a single daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonHealth {
+    /// Service name (e.g., "vesper")
+    pub name: String,
+    /// Reported status; None if the daemon did not answer
+    pub status: Option<HealthStatus>,
+    /// Why the daemon is not healthy
+    pub message: Option<String>,
+    /// Seconds since the daemon started
+    pub uptime_secs: Option<u64>,
+}
+
+impl DaemonHealth {
+    /// Unreachable or reporting itself unhealthy
+    pub fn is_failing(&self) -> bool {
+        self.status.is_none_or(|status| status == HealthStatus::Unhealthy)
+    }
+}
+
+/// Latest daemon health, shared between the prober and the collector
+#[derive(Clone, Default)]
+pub struct DaemonHealthCache(Arc<RwLock<Vec<DaemonHealth>>>);
+
+impl DaemonHealthCache {
+    /// Create an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Health from the last probe
+    pub fn get(&self) -> Vec<DaemonHealth> {
+        self.0.read().unwrap().clone()
+    }
+
+    fn set(&self, daemons: Vec<DaemonHealth>) {
+        *self.0.write().unwrap() = daemons;
+    }
+}
+
+/// Probe daemon sockets into the cache on its own interval
+pub async fn poll_loop(cache: DaemonHealthCache, sockets: HashMap<String, String>, interval_secs: u32) {
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs.max(1) as u64));
+
+    loop {
+        interval.tick().await;
+
+        let mut daemons = Vec::with_capacity(sockets.len());
+        for (name, socket) in &sockets {
+            daemons.push(probe(name, socket).await);
+        }
+        daemons.sort_by(|a, b| a.name.cmp(&b.name));
+
+        debug!("Probed health of {} daemons", daemons.len());
+        cache.set(daemons);
+    }
+}
+
+/// Probe one daemon
+async fn probe(name: &str, socket: &str) -> DaemonHealth {
+    match HealthClient::new(socket).health().await {
+        Ok(health) => {
+            let message = health
+                .checks
+                .iter()
+                .filter(|c| c.status != HealthStatus::Healthy)
+                .map(|c| match &c.message {
+                    Some(message) => format!("{}: {}", c.name, message),
+                    None => c.name.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            DaemonHealth {
+                name: name.to_string(),
+                status: Some(health.status),
+                message: (!message.is_empty()).then_some(message),
+                uptime_secs: Some(health.uptime_secs),
+            }
+        }
+        Err(e) => DaemonHealth {
+            name: name.to_string(),
+            status: None,
+            message: Some(e.to_string()),
+            uptime_secs: None,
+        },
+    }
+}
@@ -0,0 +1,220 @@
+//! Disk health monitoring
+//!
+//! Reads SMART (ATA) and NVMe health logs through `smartctl --json` and keeps
+//! the attributes that predict failure: reallocated/pending sectors, media
+//! errors, wear level and drive temperature. SMART queries are comparatively
+//! expensive, so a separate task refreshes a shared cache on its own
+//! interval and metrics collection only reads the cache.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::process::Command;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Drive interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskInterface {
+    Ata,
+    Nvme,
+    Unknown,
+}
+
+/// Health of a single physical disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskHealth {
+    /// Block device name (e.g., "nvme0n1", "sda")
+    pub device: String,
+    /// Drive model
+    pub model: Option<String>,
+    /// Serial number
+    pub serial: Option<String>,
+    /// Interface type
+    pub interface: DiskInterface,
+    /// Overall SMART self-assessment
+    pub passed: Option<bool>,
+    /// Current temperature (Celsius)
+    pub temperature: Option<f32>,
+    /// Reallocated sector count (ATA attribute 5)
+    pub reallocated_sectors: Option<u64>,
+    /// Pending sector count (ATA attribute 197)
+    pub pending_sectors: Option<u64>,
+    /// Media and data integrity errors (NVMe)
+    pub media_errors: Option<u64>,
+    /// Percentage of rated endurance used (NVMe, or ATA wear leveling)
+    pub wear_percent: Option<f32>,
+    /// Available spare capacity percentage (NVMe)
+    pub available_spare: Option<f32>,
+    /// Power-on hours
+    pub power_on_hours: Option<u64>,
+}
+
+/// Latest disk health, shared between the SMART poller and the collector
+#[derive(Clone, Default)]
+pub struct DiskHealthCache(Arc<RwLock<Vec<DiskHealth>>>);
+
+impl DiskHealthCache {
+    /// Create an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Health from the last SMART poll
+    pub fn get(&self) -> Vec<DiskHealth> {
+        self.0.read().unwrap().clone()
+    }
+
+    fn set(&self, disks: Vec<DiskHealth>) {
+        *self.0.write().unwrap() = disks;
+    }
+}
+
+/// Poll SMART data into the cache on its own interval
+///
+/// `smartctl` is run on the blocking pool, so slow drives never hold up an
+/// async worker or the metrics collector.
+pub async fn poll_loop(cache: DiskHealthCache, interval_secs: u32) {
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs.max(1) as u64));
+
+    loop {
+        interval.tick().await;
+
+        match tokio::task::spawn_blocking(query_all).await {
+            Ok(disks) => {
+                debug!("Refreshed health for {} disks", disks.len());
+                cache.set(disks);
+            }
+            Err(e) => warn!("SMART poll failed: {}", e),
+        }
+    }
+}
+
+/// Query every physical disk
+fn query_all() -> Vec<DiskHealth> {
+    list_block_devices()
+        .into_iter()
+        .filter_map(|dev| query_device(&dev))
+        .collect()
+}
+
+/// List physical block devices from sysfs
+fn list_block_devices() -> Vec<String> {
+    let Ok(entries) = fs::read_dir("/sys/block") else {
+        return Vec::new();
+    };
+
+    let mut devices: Vec<String> = entries
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|name| {
+            !["loop", "ram", "zram", "dm-", "sr", "md", "nbd"]
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+        })
+        .collect();
+
+    devices.sort();
+    devices
+}
+
+/// Query one device with smartctl
+fn query_device(device: &str) -> Option<DiskHealth> {
+    let output = match Command::new("smartctl")
+        .args(["--json", "--info", "--health", "--attributes"])
+        .arg(format!("/dev/{}", device))
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => {
+            warn!("Failed to run smartctl for {}: {}", device, e);
+            return None;
+        }
+    };
+
+    // smartctl uses its exit status as a bitmask of drive conditions, so a
+    // non-zero status still carries a usable report.
+    let report: Value = serde_json::from_slice(&output.stdout).ok()?;
+    Some(parse_report(device, &report))
+}
+
+/// Extract health data from a smartctl JSON report
+fn parse_report(device: &str, report: &Value) -> DiskHealth {
+    let nvme_log = report.get("nvme_smart_health_information_log");
+
+    let interface = match report.pointer("/device/protocol").and_then(Value::as_str) {
+        Some("ATA") => DiskInterface::Ata,
+        Some("NVMe") => DiskInterface::Nvme,
+        _ if nvme_log.is_some() => DiskInterface::Nvme,
+        _ => DiskInterface::Unknown,
+    };
+
+    let temperature = report
+        .pointer("/temperature/current")
+        .and_then(Value::as_f64)
+        .map(|t| t as f32);
+
+    let mut health = DiskHealth {
+        device: device.to_string(),
+        model: report
+            .get("model_name")
+            .and_then(Value::as_str)
+            .map(String::from),
+        serial: report
+            .get("serial_number")
+            .and_then(Value::as_str)
+            .map(String::from),
+        interface,
+        passed: report
+            .pointer("/smart_status/passed")
+            .and_then(Value::as_bool),
+        temperature,
+        reallocated_sectors: None,
+        pending_sectors: None,
+        media_errors: None,
+        wear_percent: None,
+        available_spare: None,
+        power_on_hours: report
+            .pointer("/power_on_time/hours")
+            .and_then(Value::as_u64),
+    };
+
+    if let Some(log) = nvme_log {
+        health.media_errors = log.get("media_errors").and_then(Value::as_u64);
+        health.wear_percent = log
+            .get("percentage_used")
+            .and_then(Value::as_f64)
+            .map(|p| p as f32);
+        health.available_spare = log
+            .get("available_spare")
+            .and_then(Value::as_f64)
+            .map(|p| p as f32);
+    }
+
+    if let Some(table) = report
+        .pointer("/ata_smart_attributes/table")
+        .and_then(Value::as_array)
+    {
+        for attr in table {
+            let id = attr.get("id").and_then(Value::as_u64).unwrap_or(0);
+            let raw = attr.pointer("/raw/value").and_then(Value::as_u64);
+            match id {
+                5 => health.reallocated_sectors = raw,
+                197 => health.pending_sectors = raw,
+                // Wear leveling count / media wearout indicator: normalized
+                // value counts down from 100.
+                177 | 233 => {
+                    health.wear_percent = attr
+                        .get("value")
+                        .and_then(Value::as_u64)
+                        .map(|v| 100u64.saturating_sub(v) as f32);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    health
+}
@@ -1,9 +1,14 @@
 //! IPC interface for Sentinel
 
 use crate::alerts::{Alert, AlertCounts};
+use crate::disk_health::DiskHealth;
 use crate::metrics::SystemSnapshot;
+use crate::processes::{ProcessSort, ProcessTable};
+use crate::services::ServiceMetrics;
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
@@ -24,6 +29,9 @@ pub enum IpcRequest {
     /// Get disk metrics
     GetDisks,
 
+    /// Get SMART/NVMe disk health
+    GetDiskHealth,
+
     /// Get network metrics
     GetNetworks,
 
@@ -33,6 +41,18 @@ pub enum IpcRequest {
     /// Get top processes
     GetProcesses,
 
+    /// Query the full process table, top-style
+    QueryProcesses {
+        #[serde(default)]
+        sort: ProcessSort,
+        limit: Option<usize>,
+        /// Only processes belonging to this service
+        unit: Option<String>,
+    },
+
+    /// Get per-service metrics, optionally for a single service
+    GetServices { name: Option<String> },
+
     /// Get load average
     GetLoad,
 
@@ -50,6 +70,15 @@ pub enum IpcRequest {
 
     /// Get daemon status
     GetStatus,
+
+    /// Record counters pushed by another daemon
+    ReportMetrics {
+        source: String,
+        metrics: HashMap<String, f64>,
+    },
+
+    /// Get counters pushed by other daemons
+    GetReported { source: Option<String> },
 }
 
 /// IPC response
@@ -70,13 +99,25 @@ pub struct DaemonStatus {
     pub alerts: AlertCounts,
 }
 
+/// Counters last pushed by a daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportedMetrics {
+    pub source: String,
+    pub metrics: HashMap<String, f64>,
+    pub received_at: DateTime<Utc>,
+}
+
 /// IPC handler trait
 pub trait IpcHandler: Send + Sync {
     fn get_metrics(&self) -> Option<SystemSnapshot>;
     fn get_history(&self, limit: usize) -> Vec<SystemSnapshot>;
+    fn get_services(&self) -> Vec<ServiceMetrics>;
+    fn get_process_table(&self) -> ProcessTable;
     fn get_alerts(&self) -> Vec<Alert>;
     fn get_alert_history(&self, limit: usize) -> Vec<Alert>;
     fn get_status(&self) -> DaemonStatus;
+    fn report_metrics(&self, source: String, metrics: HashMap<String, f64>);
+    fn get_reported(&self) -> Vec<ReportedMetrics>;
 }
 
 /// IPC server
@@ -86,10 +127,10 @@ pub struct IpcServer<H: IpcHandler> {
 }
 
 impl<H: IpcHandler + 'static> IpcServer<H> {
-    pub fn new(socket_path: impl Into<String>, handler: H) -> Self {
+    pub fn new(socket_path: impl Into<String>, handler: Arc<H>) -> Self {
         Self {
             socket_path: socket_path.into(),
-            handler: Arc::new(handler),
+            handler,
         }
     }
 
@@ -191,6 +232,17 @@ fn process_request<H: IpcHandler>(request: IpcRequest, handler: &H) -> IpcRespon
             }
         }
 
+        IpcRequest::GetDiskHealth => {
+            match handler.get_metrics() {
+                Some(metrics) => IpcResponse::Success {
+                    data: serde_json::to_value(metrics.disk_health).unwrap(),
+                },
+                None => IpcResponse::Error {
+                    message: "No metrics available".to_string(),
+                },
+            }
+        }
+
         IpcRequest::GetNetworks => {
             match handler.get_metrics() {
                 Some(metrics) => IpcResponse::Success {
@@ -227,6 +279,24 @@ fn process_request<H: IpcHandler>(request: IpcRequest, handler: &H) -> IpcRespon
             }
         }
 
+        IpcRequest::QueryProcesses { sort, limit, unit } => {
+            let table = handler.get_process_table().query(sort, limit, unit.as_deref());
+            IpcResponse::Success {
+                data: serde_json::to_value(table).unwrap(),
+            }
+        }
+
+        IpcRequest::GetServices { name } => {
+            let services: Vec<_> = handler
+                .get_services()
+                .into_iter()
+                .filter(|s| name.as_ref().map_or(true, |n| &s.name == n))
+                .collect();
+            IpcResponse::Success {
+                data: serde_json::to_value(services).unwrap(),
+            }
+        }
+
         IpcRequest::GetLoad => {
             match handler.get_metrics() {
                 Some(metrics) => IpcResponse::Success {
@@ -276,6 +346,24 @@ fn process_request<H: IpcHandler>(request: IpcRequest, handler: &H) -> IpcRespon
                 data: serde_json::to_value(status).unwrap(),
             }
         }
+
+        IpcRequest::ReportMetrics { source, metrics } => {
+            handler.report_metrics(source, metrics);
+            IpcResponse::Success {
+                data: serde_json::Value::Null,
+            }
+        }
+
+        IpcRequest::GetReported { source } => {
+            let reported: Vec<_> = handler
+                .get_reported()
+                .into_iter()
+                .filter(|r| source.as_ref().is_none_or(|s| &r.source == s))
+                .collect();
+            IpcResponse::Success {
+                data: serde_json::to_value(reported).unwrap(),
+            }
+        }
     }
 }
 
@@ -313,6 +401,13 @@ impl IpcClient {
         }
     }
 
+    pub async fn get_disk_health(&self) -> Result<Vec<DiskHealth>> {
+        match self.send(IpcRequest::GetDiskHealth).await? {
+            IpcResponse::Success { data } => Ok(serde_json::from_value(data)?),
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+        }
+    }
+
     pub async fn get_alerts(&self) -> Result<Vec<Alert>> {
         match self.send(IpcRequest::GetAlerts).await? {
             IpcResponse::Success { data } => Ok(serde_json::from_value(data)?),
@@ -320,6 +415,32 @@ impl IpcClient {
         }
     }
 
+    pub async fn query_processes(
+        &self,
+        sort: ProcessSort,
+        limit: Option<usize>,
+        unit: Option<String>,
+    ) -> Result<ProcessTable> {
+        match self.send(IpcRequest::QueryProcesses { sort, limit, unit }).await? {
+            IpcResponse::Success { data } => Ok(serde_json::from_value(data)?),
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+        }
+    }
+
+    pub async fn get_services(&self, name: Option<String>) -> Result<Vec<ServiceMetrics>> {
+        match self.send(IpcRequest::GetServices { name }).await? {
+            IpcResponse::Success { data } => Ok(serde_json::from_value(data)?),
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+        }
+    }
+
+    pub async fn get_reported(&self, source: Option<String>) -> Result<Vec<ReportedMetrics>> {
+        match self.send(IpcRequest::GetReported { source }).await? {
+            IpcResponse::Success { data } => Ok(serde_json::from_value(data)?),
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+        }
+    }
+
     pub async fn get_status(&self) -> Result<DaemonStatus> {
         match self.send(IpcRequest::GetStatus).await? {
             IpcResponse::Success { data } => Ok(serde_json::from_value(data)?),
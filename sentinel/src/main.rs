@@ -3,25 +3,40 @@
 //! Provides:
 //! - CPU, memory, disk, network metrics
 //! - Temperature monitoring
-//! - Process tracking
-//! - Alert management
+//! - SMART/NVMe disk health
+//! - Process tracking and per-process table queries
+//! - Per-service (cgroup) metrics
+//! - Daemon health probes
+//! - Alert management, with thresholds and learned baselines
+//! - Alert delivery via herald and grimoire rituals
 //! - Metrics history
 
 mod alerts;
+mod baseline;
 mod config;
+mod daemon_health;
+mod disk_health;
 mod ipc;
 mod metrics;
+mod processes;
+mod router;
+mod services;
 
 use crate::alerts::{Alert, AlertManager};
+use crate::baseline::BaselineEngine;
 use crate::config::SentinelConfig;
-use crate::ipc::{DaemonStatus, IpcHandler, IpcServer};
+use crate::ipc::{DaemonStatus, IpcHandler, IpcServer, ReportedMetrics};
 use crate::metrics::{MetricsCollector, SystemSnapshot};
+use crate::processes::ProcessTable;
+use crate::router::AlertRouter;
+use crate::services::ServiceMetrics;
 use anyhow::Result;
 use clap::Parser;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use std::time::Instant;
-use tracing::info;
+use tracing::{info, warn};
 
 /// Sentinel - System monitoring daemon
 #[derive(Parser, Debug)]
@@ -45,14 +60,19 @@ struct SentinelState {
     config: SentinelConfig,
     collector: RwLock<MetricsCollector>,
     alerts: RwLock<AlertManager>,
+    reported: RwLock<HashMap<String, ReportedMetrics>>,
     start_time: Instant,
 }
 
 impl SentinelState {
     fn new(config: SentinelConfig) -> Self {
         Self {
-            collector: RwLock::new(MetricsCollector::new(config.metrics.clone())),
-            alerts: RwLock::new(AlertManager::new(config.alerts.clone())),
+            collector: RwLock::new(MetricsCollector::new(config.metrics.clone(), config.services.clone())),
+            alerts: RwLock::new(AlertManager::new(
+                config.alerts.clone(),
+                BaselineEngine::load(config.baselines.clone()),
+            )),
+            reported: RwLock::new(HashMap::new()),
             start_time: Instant::now(),
             config,
         }
@@ -76,6 +96,19 @@ impl IpcHandler for SentinelState {
             .collect()
     }
 
+    fn get_services(&self) -> Vec<ServiceMetrics> {
+        self.collector
+            .read()
+            .unwrap()
+            .latest()
+            .map(|s| s.services.clone())
+            .unwrap_or_default()
+    }
+
+    fn get_process_table(&self) -> ProcessTable {
+        self.collector.read().unwrap().process_table().clone()
+    }
+
     fn get_alerts(&self) -> Vec<Alert> {
         self.alerts
             .read()
@@ -105,6 +138,21 @@ impl IpcHandler for SentinelState {
             alerts: self.alerts.read().unwrap().get_counts(),
         }
     }
+
+    fn report_metrics(&self, source: String, metrics: HashMap<String, f64>) {
+        let reported = ReportedMetrics {
+            source: source.clone(),
+            metrics,
+            received_at: chrono::Utc::now(),
+        };
+        self.reported.write().unwrap().insert(source, reported);
+    }
+
+    fn get_reported(&self) -> Vec<ReportedMetrics> {
+        let mut reported: Vec<_> = self.reported.read().unwrap().values().cloned().collect();
+        reported.sort_by(|a, b| a.source.cmp(&b.source));
+        reported
+    }
 }
 
 #[tokio::main]
@@ -124,33 +172,45 @@ async fn main() -> Result<()> {
     // Start metrics collection task
     let collection_state = Arc::clone(&state);
     let interval = config.metrics.interval_secs;
+    let router = AlertRouter::new(
+        config.routing.clone(),
+        config.alerts.rules.clone(),
+        config.alerts.baseline_rules.clone(),
+    );
     tokio::spawn(async move {
-        collection_loop(collection_state, interval).await;
+        collection_loop(collection_state, router, interval).await;
     });
 
+    // Poll SMART data apart from the metrics loop
+    if config.metrics.disk_health {
+        let cache = state.collector.read().unwrap().disk_health_cache();
+        tokio::spawn(disk_health::poll_loop(cache, config.metrics.disk_health_interval_secs));
+    }
+
+    // Probe daemon health apart from the metrics loop
+    if config.metrics.services {
+        let cache = state.collector.read().unwrap().daemon_health_cache();
+        tokio::spawn(daemon_health::poll_loop(
+            cache,
+            config.services.health_sockets.clone(),
+            config.services.health_interval_secs,
+        ));
+    }
+
     // Start IPC server
     let socket_path = args.socket.to_string_lossy().to_string();
-    let server = IpcServer::new(socket_path, Arc::try_unwrap(state).unwrap_or_else(|arc| (*arc).clone()));
+    let server = IpcServer::new(socket_path, state);
 
     info!("Sentinel ready");
     server.run().await
 }
 
-impl Clone for SentinelState {
-    fn clone(&self) -> Self {
-        Self {
-            config: self.config.clone(),
-            collector: RwLock::new(MetricsCollector::new(self.config.metrics.clone())),
-            alerts: RwLock::new(AlertManager::new(self.config.alerts.clone())),
-            start_time: self.start_time,
-        }
-    }
-}
-
-async fn collection_loop(state: Arc<SentinelState>, interval_secs: u32) {
+async fn collection_loop(state: Arc<SentinelState>, router: AlertRouter, interval_secs: u32) {
     use tokio::time::{interval, Duration};
 
     let mut interval = interval(Duration::from_secs(interval_secs as u64));
+    let save_interval = Duration::from_secs(state.config.baselines.save_interval_secs as u64);
+    let mut last_save = Instant::now();
 
     loop {
         interval.tick().await;
@@ -159,8 +219,21 @@ async fn collection_loop(state: Arc<SentinelState>, interval_secs: u32) {
         let snapshot = state.collector.write().unwrap().collect();
 
         // Check for alerts
-        let _new_alerts = state.alerts.write().unwrap().check(&snapshot);
-
-        // Could emit alerts to a notification service here
+        let (fired, cleared) = {
+            let mut alerts = state.alerts.write().unwrap();
+            let fired = alerts.check(&snapshot);
+            (fired, alerts.take_cleared())
+        };
+
+        // Deliver alert transitions
+        router.route(&fired, &cleared).await;
+
+        // Persist learned baselines
+        if state.config.baselines.enabled && last_save.elapsed() >= save_interval {
+            if let Err(e) = state.alerts.read().unwrap().baselines().save() {
+                warn!("Failed to save baselines: {}", e);
+            }
+            last_save = Instant::now();
+        }
     }
 }
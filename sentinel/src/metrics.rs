@@ -1,6 +1,10 @@
 //! System metrics collection
 
-use crate::config::MetricsConfig;
+use crate::config::{MetricsConfig, ServiceConfig};
+use crate::daemon_health::{DaemonHealth, DaemonHealthCache};
+use crate::disk_health::{DiskHealth, DiskHealthCache};
+use crate::processes::{ProcessCollector, ProcessTable};
+use crate::services::{ServiceCollector, ServiceMetrics};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use sysinfo::{Components, Disks, Networks, RefreshKind, System};
@@ -154,6 +158,15 @@ pub struct SystemSnapshot {
     pub top_cpu_processes: Vec<ProcessMetrics>,
     /// Top processes by memory
     pub top_memory_processes: Vec<ProcessMetrics>,
+    /// Per-service metrics
+    #[serde(default)]
+    pub services: Vec<ServiceMetrics>,
+    /// Physical disk health
+    #[serde(default)]
+    pub disk_health: Vec<DiskHealth>,
+    /// Daemon health probes
+    #[serde(default)]
+    pub daemon_health: Vec<DaemonHealth>,
     /// Load average
     pub load: LoadAverage,
     /// System uptime
@@ -167,24 +180,45 @@ pub struct MetricsCollector {
     disks: Disks,
     networks: Networks,
     components: Components,
+    services: ServiceCollector,
+    processes: ProcessCollector,
+    /// Latest full process table; kept out of the history, which only
+    /// carries the top processes
+    process_table: ProcessTable,
+    disk_health: DiskHealthCache,
+    daemon_health: DaemonHealthCache,
     history: VecDeque<SystemSnapshot>,
 }
 
 impl MetricsCollector {
     /// Create new metrics collector
-    pub fn new(config: MetricsConfig) -> Self {
+    pub fn new(config: MetricsConfig, services: ServiceConfig) -> Self {
         let refresh = RefreshKind::everything();
-
         Self {
             config,
             system: System::new_with_specifics(refresh),
             disks: Disks::new_with_refreshed_list(),
             networks: Networks::new_with_refreshed_list(),
             components: Components::new_with_refreshed_list(),
+            processes: ProcessCollector::new(services.clone()),
+            process_table: ProcessTable::default(),
+            services: ServiceCollector::new(services),
+            disk_health: DiskHealthCache::new(),
+            daemon_health: DaemonHealthCache::new(),
             history: VecDeque::new(),
         }
     }
 
+    /// Cache the SMART poller fills
+    pub fn disk_health_cache(&self) -> DiskHealthCache {
+        self.disk_health.clone()
+    }
+
+    /// Cache the daemon health prober fills
+    pub fn daemon_health_cache(&self) -> DaemonHealthCache {
+        self.daemon_health.clone()
+    }
+
     /// Collect current system metrics
     pub fn collect(&mut self) -> SystemSnapshot {
         // Refresh system information
@@ -224,11 +258,30 @@ impl MetricsCollector {
         };
 
         let (top_cpu_processes, top_memory_processes) = if self.config.processes {
+            self.process_table = self.processes.collect();
             self.collect_processes()
         } else {
             (Vec::new(), Vec::new())
         };
 
+        let services = if self.config.services {
+            self.services.collect()
+        } else {
+            Vec::new()
+        };
+
+        let disk_health = if self.config.disk_health {
+            self.disk_health.get()
+        } else {
+            Vec::new()
+        };
+
+        let daemon_health = if self.config.services {
+            self.daemon_health.get()
+        } else {
+            Vec::new()
+        };
+
         let load = self.collect_load();
         let uptime = self.collect_uptime();
 
@@ -241,6 +294,9 @@ impl MetricsCollector {
             temperatures,
             top_cpu_processes,
             top_memory_processes,
+            services,
+            disk_health,
+            daemon_health,
             load,
             uptime,
         };
@@ -432,4 +488,9 @@ impl MetricsCollector {
     pub fn latest(&self) -> Option<&SystemSnapshot> {
         self.history.back()
     }
+
+    /// Get the latest full process table
+    pub fn process_table(&self) -> &ProcessTable {
+        &self.process_table
+    }
 }
@@ -0,0 +1,248 @@
+//! Per-process table
+//!
+//! Reads `/proc` directly so every process can be listed with its owning
+//! service, not just the top few sysinfo reports. CPU usage is the share of
+//! one core used since the previous sample, from the `utime + stime` delta;
+//! processes are keyed by pid and start time so a reused pid starts over.
+
+use crate::config::ServiceConfig;
+use crate::services::{unit_name, ServiceSource};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::time::Instant;
+
+/// Clock ticks per second in `/proc/<pid>/stat` (USER_HZ, 100 on Linux)
+const CLOCK_TICKS: f64 = 100.0;
+
+/// Page size used by `/proc/<pid>/statm`
+const PAGE_SIZE: u64 = 4096;
+
+/// One process
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    /// Process ID
+    pub pid: u32,
+    /// Parent process ID
+    pub ppid: u32,
+    /// Command name
+    pub name: String,
+    /// Scheduler state (`R`, `S`, `D`, `Z`, ...)
+    pub state: char,
+    /// CPU usage percentage of one core since the previous sample
+    pub cpu_usage: f32,
+    /// Resident memory in bytes
+    pub rss: u64,
+    /// Owning user ID
+    pub uid: u32,
+    /// Cgroup v2 path
+    pub cgroup: String,
+    /// Service owning the cgroup, if any
+    pub unit: Option<String>,
+}
+
+/// Field to order a process query by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessSort {
+    #[default]
+    Cpu,
+    Memory,
+    Pid,
+    Name,
+}
+
+/// Sampled process table
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessTable {
+    /// When the table was sampled
+    pub timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    /// Number of processes before any limit
+    pub total: usize,
+    pub processes: Vec<ProcessInfo>,
+}
+
+impl ProcessTable {
+    /// Sort, optionally keep only one service's processes, and truncate
+    pub fn query(&self, sort: ProcessSort, limit: Option<usize>, unit: Option<&str>) -> ProcessTable {
+        let mut processes: Vec<ProcessInfo> = self
+            .processes
+            .iter()
+            .filter(|p| unit.is_none_or(|unit| p.unit.as_deref() == Some(unit)))
+            .cloned()
+            .collect();
+
+        match sort {
+            ProcessSort::Cpu => processes.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage)),
+            ProcessSort::Memory => processes.sort_by_key(|p| std::cmp::Reverse(p.rss)),
+            ProcessSort::Pid => processes.sort_by_key(|p| p.pid),
+            ProcessSort::Name => processes.sort_by(|a, b| a.name.cmp(&b.name)),
+        }
+
+        let total = processes.len();
+        if let Some(limit) = limit {
+            processes.truncate(limit);
+        }
+
+        ProcessTable {
+            timestamp: self.timestamp,
+            total,
+            processes,
+        }
+    }
+}
+
+/// Collector keeping the CPU times needed for deltas
+pub struct ProcessCollector {
+    config: ServiceConfig,
+    /// Last `utime + stime` per (pid, start time)
+    last_cpu: HashMap<(u32, u64), u64>,
+    last_sample: Option<Instant>,
+}
+
+impl ProcessCollector {
+    /// Create new process collector
+    pub fn new(config: ServiceConfig) -> Self {
+        Self {
+            config,
+            last_cpu: HashMap::new(),
+            last_sample: None,
+        }
+    }
+
+    /// Sample every process
+    pub fn collect(&mut self) -> ProcessTable {
+        let now = Instant::now();
+        let elapsed = self
+            .last_sample
+            .map(|last| now.duration_since(last).as_secs_f64())
+            .unwrap_or(0.0);
+        self.last_sample = Some(now);
+
+        let Ok(entries) = fs::read_dir("/proc") else {
+            return ProcessTable::default();
+        };
+
+        let mut cpu_times = HashMap::new();
+        let mut processes = Vec::new();
+        for pid in entries.flatten().filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok()) {
+            // Processes can exit between listing and reading
+            let Some(stat) = fs::read_to_string(format!("/proc/{}/stat", pid))
+                .ok()
+                .and_then(|s| parse_stat(&s))
+            else {
+                continue;
+            };
+
+            let key = (pid, stat.start_time);
+            let cpu_usage = match self.last_cpu.get(&key) {
+                Some(&previous) if elapsed > 0.0 => {
+                    (stat.cpu_ticks.saturating_sub(previous) as f64 / CLOCK_TICKS / elapsed * 100.0) as f32
+                }
+                _ => 0.0,
+            };
+            cpu_times.insert(key, stat.cpu_ticks);
+
+            let cgroup = fs::read_to_string(format!("/proc/{}/cgroup", pid))
+                .map(|s| parse_cgroup(&s))
+                .unwrap_or_default();
+
+            processes.push(ProcessInfo {
+                pid,
+                ppid: stat.ppid,
+                name: stat.name,
+                state: stat.state,
+                cpu_usage,
+                rss: read_rss(pid),
+                uid: read_uid(pid),
+                unit: self.unit_for(&cgroup),
+                cgroup,
+            });
+        }
+
+        // Only keep times for processes still running
+        self.last_cpu = cpu_times;
+
+        ProcessTable {
+            timestamp: Some(chrono::Utc::now()),
+            total: processes.len(),
+            processes,
+        }
+    }
+
+    /// Map a cgroup path to its service, following the same layout as
+    /// [`crate::services`]
+    fn unit_for(&self, cgroup: &str) -> Option<String> {
+        let mut parts = cgroup.trim_start_matches('/').split('/');
+        let (manager, dir) = (parts.next()?, parts.next()?);
+        let source = if manager == self.config.serviced_slice {
+            ServiceSource::Serviced
+        } else if manager == self.config.archon_root {
+            ServiceSource::Archon
+        } else {
+            return None;
+        };
+        unit_name(dir, source)
+    }
+}
+
+/// Fields used from `/proc/<pid>/stat`
+struct Stat {
+    name: String,
+    state: char,
+    ppid: u32,
+    cpu_ticks: u64,
+    start_time: u64,
+}
+
+/// Parse `/proc/<pid>/stat`. The command name is parenthesized and may
+/// itself contain spaces or parentheses, so fields are counted from the
+/// last `)`.
+fn parse_stat(stat: &str) -> Option<Stat> {
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let name = stat.get(open + 1..close)?.to_string();
+    let fields: Vec<&str> = stat.get(close + 1..)?.split_whitespace().collect();
+
+    // Fields after the name start at 3 (state); utime/stime are 14/15 and
+    // starttime is 22
+    let field = |n: usize| fields.get(n - 3).copied();
+    let utime: u64 = field(14)?.parse().ok()?;
+    let stime: u64 = field(15)?.parse().ok()?;
+
+    Some(Stat {
+        name,
+        state: field(3)?.chars().next()?,
+        ppid: field(4)?.parse().ok()?,
+        cpu_ticks: utime + stime,
+        start_time: field(22)?.parse().ok()?,
+    })
+}
+
+/// The unified (v2) hierarchy path from `/proc/<pid>/cgroup`
+fn parse_cgroup(cgroup: &str) -> String {
+    cgroup
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .unwrap_or("")
+        .to_string()
+}
+
+fn read_rss(pid: u32) -> u64 {
+    fs::read_to_string(format!("/proc/{}/statm", pid))
+        .ok()
+        .and_then(|s| s.split_whitespace().nth(1)?.parse::<u64>().ok())
+        .map(|pages| pages * PAGE_SIZE)
+        .unwrap_or(0)
+}
+
+fn read_uid(pid: u32) -> u32 {
+    fs::read_to_string(format!("/proc/{}/status", pid))
+        .ok()
+        .and_then(|s| {
+            s.lines()
+                .find_map(|line| line.strip_prefix("Uid:"))
+                .and_then(|uids| uids.split_whitespace().next()?.parse().ok())
+        })
+        .unwrap_or(0)
+}
@@ -0,0 +1,193 @@
+//! Alert routing
+//!
+//! Delivers fired and cleared alerts as herald notifications and triggers
+//! grimoire rituals configured for the alert's rule or type.
+
+use crate::alerts::{Alert, AlertSeverity};
+use crate::config::{BaselineAlertRule, RoutingConfig, ServiceAlertRule};
+use anyhow::Result;
+use grimoire_client::GrimoireClient;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+use tracing::{debug, info, warn};
+
+/// Application name used for herald notifications
+const APP_NAME: &str = "Sentinel";
+
+/// How long herald or grimoire get to take an alert, so a hung peer does
+/// not stall metric collection
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Alert state transition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertTransition {
+    Fired,
+    Cleared,
+}
+
+impl AlertTransition {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Fired => "fired",
+            Self::Cleared => "cleared",
+        }
+    }
+}
+
+/// Routes alert transitions to herald and grimoire
+pub struct AlertRouter {
+    config: RoutingConfig,
+    rules: Vec<ServiceAlertRule>,
+    baseline_rules: Vec<BaselineAlertRule>,
+}
+
+impl AlertRouter {
+    /// Create new alert router
+    pub fn new(
+        config: RoutingConfig,
+        rules: Vec<ServiceAlertRule>,
+        baseline_rules: Vec<BaselineAlertRule>,
+    ) -> Self {
+        Self { config, rules, baseline_rules }
+    }
+
+    /// Route a batch of alert transitions
+    pub async fn route(&self, fired: &[Alert], cleared: &[Alert]) {
+        for alert in fired {
+            self.dispatch(alert, AlertTransition::Fired).await;
+        }
+        for alert in cleared {
+            self.dispatch(alert, AlertTransition::Cleared).await;
+        }
+    }
+
+    async fn dispatch(&self, alert: &Alert, transition: AlertTransition) {
+        // (notify, ritual) of the rule that produced the alert
+        let rule = alert.rule.as_ref().and_then(|name| {
+            self.rules
+                .iter()
+                .find(|r| &r.name == name)
+                .map(|r| (r.notify, r.ritual.as_ref()))
+                .or_else(|| {
+                    self.baseline_rules
+                        .iter()
+                        .find(|r| &r.name == name)
+                        .map(|r| (r.notify, r.ritual.as_ref()))
+                })
+        });
+
+        let notify = self.config.notify
+            && rule.map_or(true, |(notify, _)| notify)
+            && (transition == AlertTransition::Fired || self.config.notify_on_clear);
+
+        if notify {
+            if let Err(e) = bounded(self.notify(alert, transition)).await {
+                warn!("Failed to deliver alert notification: {}", e);
+            }
+        }
+
+        if transition == AlertTransition::Fired {
+            let ritual = match rule {
+                Some((_, ritual)) => ritual,
+                None => self.config.rituals.get(&alert.alert_type),
+            };
+
+            if let Some(ritual) = ritual {
+                if let Err(e) = bounded(self.trigger_ritual(ritual, alert, transition)).await {
+                    warn!("Failed to trigger ritual '{}': {}", ritual, e);
+                }
+            }
+        }
+    }
+
+    /// Send a notification through herald
+    async fn notify(&self, alert: &Alert, transition: AlertTransition) -> Result<()> {
+        let (summary, urgency) = match transition {
+            AlertTransition::Fired => (
+                format!("{:?} alert", alert.severity),
+                herald_urgency(alert.severity),
+            ),
+            AlertTransition::Cleared => ("Alert cleared".to_string(), "low"),
+        };
+
+        let request = json!({
+            "type": "Notify",
+            "data": {
+                "app_name": APP_NAME,
+                "summary": summary,
+                "body": alert.message,
+                "icon": "dialog-warning",
+                "urgency": urgency,
+                "timeout": null,
+            }
+        });
+
+        let mut stream = UnixStream::connect(&self.config.herald_socket).await?;
+        stream.write_all(request.to_string().as_bytes()).await?;
+        stream.write_all(b"\n").await?;
+        stream.flush().await?;
+
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line).await?;
+
+        let response: Value = serde_json::from_str(&line)?;
+        if response.get("status").and_then(|s| s.as_str()) == Some("Error") {
+            let message = response
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("unknown error");
+            return Err(anyhow::anyhow!("herald: {}", message));
+        }
+
+        debug!("Delivered {} notification for {:?}", transition.as_str(), alert.alert_type);
+        Ok(())
+    }
+
+    /// Execute a named grimoire ritual with the alert as parameters
+    async fn trigger_ritual(&self, name: &str, alert: &Alert, transition: AlertTransition) -> Result<()> {
+        let client = GrimoireClient::connect(&self.config.grimoire_socket).await?;
+        let ritual = client.get_ritual_by_name(name).await?;
+        let execution = client
+            .execute_ritual(ritual.id, alert_parameters(alert, transition))
+            .await?;
+
+        info!("Triggered ritual '{}' (execution {})", name, execution.id);
+        Ok(())
+    }
+}
+
+/// Run a delivery, failing it once `DELIVERY_TIMEOUT` passes
+async fn bounded<T>(delivery: impl Future<Output = Result<T>>) -> Result<T> {
+    tokio::time::timeout(DELIVERY_TIMEOUT, delivery)
+        .await
+        .map_err(|_| anyhow::anyhow!("no answer within {}s", DELIVERY_TIMEOUT.as_secs()))?
+}
+
+/// Map alert severity to a herald urgency level
+fn herald_urgency(severity: AlertSeverity) -> &'static str {
+    match severity {
+        AlertSeverity::Info => "low",
+        AlertSeverity::Warning => "normal",
+        AlertSeverity::Critical => "critical",
+    }
+}
+
+/// Alert context passed to rituals
+fn alert_parameters(alert: &Alert, transition: AlertTransition) -> HashMap<String, Value> {
+    let mut params = HashMap::new();
+    params.insert("state".to_string(), json!(transition.as_str()));
+    params.insert("alert_type".to_string(), json!(alert.alert_type));
+    params.insert("severity".to_string(), json!(alert.severity));
+    params.insert("message".to_string(), json!(alert.message));
+    params.insert("value".to_string(), json!(alert.value));
+    params.insert("threshold".to_string(), json!(alert.threshold));
+    params.insert("resource".to_string(), json!(alert.resource));
+    params.insert("rule".to_string(), json!(alert.rule));
+    params.insert("timestamp".to_string(), json!(alert.timestamp));
+    params
+}
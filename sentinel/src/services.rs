@@ -0,0 +1,217 @@
+//! Per-service metrics
+//!
+//! Correlates cgroup v2 accounting with the service that owns it. Services
+//! started by nyx-serviced live under `nyx.slice/<unit>.scope`, processes
+//! managed by archon under `nyx/<name>`. Both are mapped back to a unit name
+//! so alerts and queries can be scoped to a single service.
+
+use crate::config::ServiceConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::debug;
+
+/// Which manager owns a cgroup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceSource {
+    /// Unit supervised by nyx-serviced
+    Serviced,
+    /// Process group managed by archon
+    Archon,
+}
+
+/// Resource usage of a single service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceMetrics {
+    /// Unit name (e.g., "vesper")
+    pub name: String,
+    /// Owning manager
+    pub source: ServiceSource,
+    /// Cgroup path
+    pub cgroup: String,
+    /// Current memory usage in bytes
+    pub memory: u64,
+    /// CPU usage percentage since the previous sample
+    pub cpu_usage: f32,
+    /// Number of tasks in the cgroup
+    pub tasks: u64,
+}
+
+/// Collector mapping cgroup paths to unit names
+pub struct ServiceCollector {
+    config: ServiceConfig,
+    /// Last cumulative CPU time per cgroup, for usage deltas
+    last_cpu: HashMap<PathBuf, (u64, Instant)>,
+}
+
+impl ServiceCollector {
+    /// Create new service collector
+    pub fn new(config: ServiceConfig) -> Self {
+        Self {
+            config,
+            last_cpu: HashMap::new(),
+        }
+    }
+
+    /// Collect metrics for every known service cgroup
+    pub fn collect(&mut self) -> Vec<ServiceMetrics> {
+        let root = PathBuf::from(&self.config.cgroup_root);
+        let mut services = Vec::new();
+
+        for (source, dir) in [
+            (ServiceSource::Serviced, root.join(&self.config.serviced_slice)),
+            (ServiceSource::Archon, root.join(&self.config.archon_root)),
+        ] {
+            for (name, path) in list_service_cgroups(&dir, source) {
+                services.push(self.read_service(name, source, path));
+            }
+        }
+
+        // Forget cgroups that disappeared
+        let present: Vec<PathBuf> = services.iter().map(|s| PathBuf::from(&s.cgroup)).collect();
+        self.last_cpu.retain(|path, _| present.contains(path));
+
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+        services
+    }
+
+    /// Read accounting files for one service cgroup
+    fn read_service(&mut self, name: String, source: ServiceSource, path: PathBuf) -> ServiceMetrics {
+        let memory = read_u64(&path, "memory.current").unwrap_or(0);
+        let tasks = read_u64(&path, "pids.current").unwrap_or(0);
+        let cpu_usec = fs::read_to_string(path.join("cpu.stat"))
+            .map(|s| parse_usage_usec(&s))
+            .unwrap_or(0);
+
+        let now = Instant::now();
+        let cpu_usage = match self.last_cpu.insert(path.clone(), (cpu_usec, now)) {
+            Some((prev_usec, prev_time)) => {
+                let wall_usec = now.duration_since(prev_time).as_micros() as f64;
+                if wall_usec > 0.0 {
+                    (cpu_usec.saturating_sub(prev_usec) as f64 / wall_usec * 100.0) as f32
+                } else {
+                    0.0
+                }
+            }
+            None => 0.0,
+        };
+
+        debug!("Service {} ({:?}): {} bytes, {:.1}% CPU", name, source, memory, cpu_usage);
+
+        ServiceMetrics {
+            name,
+            source,
+            cgroup: path.to_string_lossy().to_string(),
+            memory,
+            cpu_usage,
+            tasks,
+        }
+    }
+}
+
+/// List `(unit name, cgroup path)` pairs under a manager's cgroup directory
+fn list_service_cgroups(dir: &Path, source: ServiceSource) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .filter_map(|e| {
+            let file_name = e.file_name().to_string_lossy().to_string();
+            unit_name(&file_name, source).map(|name| (name, e.path()))
+        })
+        .collect()
+}
+
+/// Map a cgroup directory name to the unit name it represents
+pub(crate) fn unit_name(dir_name: &str, source: ServiceSource) -> Option<String> {
+    match source {
+        ServiceSource::Serviced => dir_name.strip_suffix(".scope").map(String::from),
+        ServiceSource::Archon => Some(dir_name.to_string()),
+    }
+}
+
+fn read_u64(cgroup: &Path, file: &str) -> Option<u64> {
+    fs::read_to_string(cgroup.join(file))
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+}
+
+/// Extract `usage_usec` from a cgroup `cpu.stat` file
+fn parse_usage_usec(stat: &str) -> u64 {
+    stat.lines()
+        .find_map(|line| {
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some("usage_usec"), Some(value)) => value.parse().ok(),
+                _ => None,
+            }
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_name() {
+        assert_eq!(unit_name("vesper.scope", ServiceSource::Serviced).as_deref(), Some("vesper"));
+        assert_eq!(unit_name("vesper.service", ServiceSource::Serviced), None);
+        assert_eq!(unit_name("aether", ServiceSource::Archon).as_deref(), Some("aether"));
+    }
+
+    #[test]
+    fn test_parse_usage_usec() {
+        assert_eq!(parse_usage_usec("usage_usec 1500\nuser_usec 1000\nsystem_usec 500\n"), 1500);
+        assert_eq!(parse_usage_usec("user_usec 1000\n"), 0);
+        assert_eq!(parse_usage_usec("usage_usec garbage\n"), 0);
+    }
+
+    #[test]
+    fn test_collect_maps_cgroups_to_units() {
+        let root = tempfile::tempdir().unwrap();
+        let config = ServiceConfig {
+            cgroup_root: root.path().to_string_lossy().to_string(),
+            ..ServiceConfig::default()
+        };
+
+        let vesper = root.path().join(&config.serviced_slice).join("vesper.scope");
+        fs::create_dir_all(&vesper).unwrap();
+        fs::write(vesper.join("memory.current"), "4096\n").unwrap();
+        fs::write(vesper.join("pids.current"), "3\n").unwrap();
+        fs::write(vesper.join("cpu.stat"), "usage_usec 1000\n").unwrap();
+
+        // Not a unit scope
+        fs::create_dir_all(root.path().join(&config.serviced_slice).join("init.slice")).unwrap();
+
+        let aether = root.path().join(&config.archon_root).join("aether");
+        fs::create_dir_all(&aether).unwrap();
+
+        let mut collector = ServiceCollector::new(config.clone());
+        let services = collector.collect();
+        assert_eq!(services.len(), 2);
+
+        assert_eq!(services[0].name, "aether");
+        assert_eq!(services[0].source, ServiceSource::Archon);
+        assert_eq!(services[0].memory, 0);
+
+        assert_eq!(services[1].name, "vesper");
+        assert_eq!(services[1].source, ServiceSource::Serviced);
+        assert_eq!(services[1].memory, 4096);
+        assert_eq!(services[1].tasks, 3);
+        // No previous sample yet
+        assert_eq!(services[1].cpu_usage, 0.0);
+
+        // Removed cgroups are forgotten
+        fs::remove_dir_all(&aether).unwrap();
+        let services = collector.collect();
+        assert_eq!(services.len(), 1);
+        assert_eq!(collector.last_cpu.len(), 1);
+    }
+}
@@ -4,10 +4,12 @@ use crate::battery::PowerStatus;
 use crate::profiles::ProfileStatus;
 use crate::sleep::SleepStatus;
 use anyhow::Result;
+use libnyx_ipc::Hello;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::broadcast;
 
 /// IPC request types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +41,10 @@ pub enum IpcRequest {
 
     /// Get full daemon status
     GetStatus,
+
+    /// Keep the connection open and push the profile status now and after
+    /// every switch
+    SubscribeProfile,
 }
 
 /// IPC response
@@ -69,6 +75,7 @@ pub trait IpcHandler: Send + Sync {
     fn hibernate(&self) -> Result<()>;
     fn hybrid_sleep(&self) -> Result<()>;
     fn get_daemon_status(&self) -> Result<DaemonStatus>;
+    fn subscribe_profile(&self) -> broadcast::Receiver<ProfileStatus>;
 }
 
 /// IPC server
@@ -78,10 +85,10 @@ pub struct IpcServer<H: IpcHandler> {
 }
 
 impl<H: IpcHandler + 'static> IpcServer<H> {
-    pub fn new(socket_path: impl Into<String>, handler: H) -> Self {
+    pub fn new(socket_path: impl Into<String>, handler: Arc<H>) -> Self {
         Self {
             socket_path: socket_path.into(),
-            handler: Arc::new(handler),
+            handler,
         }
     }
 
@@ -118,15 +125,26 @@ async fn handle_client<H: IpcHandler>(stream: UnixStream, handler: Arc<H>) -> Re
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
 
+    let hello = Hello::new("slumber", env!("CARGO_PKG_VERSION")).with_features(&["profiles", "subscribe-profile"]);
+
     while reader.read_line(&mut line).await? > 0 {
-        let response = match serde_json::from_str::<IpcRequest>(&line) {
-            Ok(request) => process_request(request, handler.as_ref()),
-            Err(e) => IpcResponse::Error {
-                message: format!("Invalid request: {}", e),
-            },
+        let response_json = match hello.respond(&line) {
+            Some(reply) => reply,
+            None => {
+                let response = match serde_json::from_str::<IpcRequest>(&line) {
+                    Ok(IpcRequest::SubscribeProfile) => {
+                        // Subscribe first so a switch in between isn't lost
+                        let events = handler.subscribe_profile();
+                        return stream_profiles(writer, handler.get_profile(), events).await;
+                    }
+                    Ok(request) => process_request(request, handler.as_ref()),
+                    Err(e) => IpcResponse::Error {
+                        message: format!("Invalid request: {}", e),
+                    },
+                };
+                serde_json::to_string(&response)?
+            }
         };
-
-        let response_json = serde_json::to_string(&response)?;
         writer.write_all(response_json.as_bytes()).await?;
         writer.write_all(b"\n").await?;
         writer.flush().await?;
@@ -137,6 +155,31 @@ async fn handle_client<H: IpcHandler>(stream: UnixStream, handler: Arc<H>) -> Re
     Ok(())
 }
 
+/// Send the current profile, then every switch until the client goes away
+async fn stream_profiles(
+    mut writer: tokio::net::unix::OwnedWriteHalf,
+    current: ProfileStatus,
+    mut events: broadcast::Receiver<ProfileStatus>,
+) -> Result<()> {
+    let mut status = current;
+    loop {
+        let json = serde_json::to_string(&status)?;
+        writer.write_all(json.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+        writer.flush().await?;
+
+        status = loop {
+            match events.recv().await {
+                Ok(status) => break status,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!("Profile subscriber fell behind by {} events", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            }
+        };
+    }
+}
+
 fn process_request<H: IpcHandler>(request: IpcRequest, handler: &H) -> IpcResponse {
     match request {
         IpcRequest::GetPowerStatus => match handler.get_power_status() {
@@ -204,6 +247,11 @@ fn process_request<H: IpcHandler>(request: IpcRequest, handler: &H) -> IpcRespon
                 message: e.to_string(),
             },
         },
+
+        // Handled by the connection loop, which switches to streaming
+        IpcRequest::SubscribeProfile => IpcResponse::Error {
+            message: "SubscribeProfile streams on its own connection".into(),
+        },
     }
 }
 
@@ -21,6 +21,7 @@ use anyhow::Result;
 use clap::Parser;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
+use tokio::sync::broadcast;
 use tracing::{info, warn};
 
 /// Slumber - Power management daemon
@@ -46,6 +47,8 @@ struct SlumberState {
     battery_monitor: RwLock<BatteryMonitor>,
     profile_manager: RwLock<ProfileManager>,
     sleep_manager: SleepManager,
+    /// Profile switches, for `SubscribeProfile` clients
+    profile_events: broadcast::Sender<ProfileStatus>,
 }
 
 impl SlumberState {
@@ -54,9 +57,22 @@ impl SlumberState {
             battery_monitor: RwLock::new(BatteryMonitor::new(config.battery.clone())),
             profile_manager: RwLock::new(ProfileManager::new(config.profiles.clone())),
             sleep_manager: SleepManager::new(config.sleep.clone()),
+            profile_events: broadcast::channel(16).0,
             config,
         }
     }
+
+    /// Apply a profile and tell subscribers about it
+    fn switch_profile(&self, name: &str) -> Result<()> {
+        let status = {
+            let mut manager = self.profile_manager.write().unwrap();
+            manager.set_profile(name)?;
+            manager.get_status()
+        };
+        // Nobody listening is fine
+        let _ = self.profile_events.send(status);
+        Ok(())
+    }
 }
 
 impl IpcHandler for SlumberState {
@@ -69,7 +85,7 @@ impl IpcHandler for SlumberState {
     }
 
     fn set_profile(&self, name: &str) -> Result<()> {
-        self.profile_manager.write().unwrap().set_profile(name)
+        self.switch_profile(name)
     }
 
     fn list_profiles(&self) -> Vec<String> {
@@ -106,6 +122,10 @@ impl IpcHandler for SlumberState {
             sleep: self.get_sleep_status(),
         })
     }
+
+    fn subscribe_profile(&self) -> broadcast::Receiver<ProfileStatus> {
+        self.profile_events.subscribe()
+    }
 }
 
 #[tokio::main]
@@ -123,7 +143,7 @@ async fn main() -> Result<()> {
     let state = Arc::new(SlumberState::new(config.clone()));
 
     // Apply default profile on startup
-    if let Err(e) = state.profile_manager.write().unwrap().set_profile(&config.profiles.default_profile) {
+    if let Err(e) = state.switch_profile(&config.profiles.default_profile) {
         warn!("Failed to apply default profile: {}", e);
     }
 
@@ -136,23 +156,13 @@ async fn main() -> Result<()> {
 
     // Start IPC server
     let socket_path = args.socket.to_string_lossy().to_string();
-    let server = IpcServer::new(socket_path, Arc::try_unwrap(state).unwrap_or_else(|arc| (*arc).clone()));
+    // Shared with the battery loop so auto-switches reach subscribers
+    let server = IpcServer::new(socket_path, state);
 
     info!("Slumber ready");
     server.run().await
 }
 
-impl Clone for SlumberState {
-    fn clone(&self) -> Self {
-        Self {
-            config: self.config.clone(),
-            battery_monitor: RwLock::new(BatteryMonitor::new(self.config.battery.clone())),
-            profile_manager: RwLock::new(ProfileManager::new(self.config.profiles.clone())),
-            sleep_manager: SleepManager::new(self.config.sleep.clone()),
-        }
-    }
-}
-
 async fn battery_monitor_loop(state: Arc<SlumberState>, interval_secs: u32) {
     use tokio::time::{interval, Duration};
 
@@ -180,7 +190,7 @@ async fn battery_monitor_loop(state: Arc<SlumberState>, interval_secs: u32) {
                 };
 
                 drop(monitor);
-                if let Err(e) = state.profile_manager.write().unwrap().set_profile(profile) {
+                if let Err(e) = state.switch_profile(profile) {
                     warn!("Auto profile switch failed: {}", e);
                 }
             }
@@ -1,13 +1,16 @@
 //! Login greeter interface
 
-use crate::auth::{Authenticator, Credentials};
+use crate::auth::{Authenticator, Credentials, LoginLock};
 use crate::pam_auth::PamAuthenticator;
 use crate::seat::SeatManager;
 use crate::session::{SessionClass, SessionManager};
+use crate::theme::GreeterTheme;
 use crate::user::{self, UserDisplay};
 use crate::Config;
 use anyhow::Result;
+use std::collections::HashMap;
 use std::io::{self, Write};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{info, warn, error, debug};
@@ -19,7 +22,13 @@ pub struct Greeter {
     sessions: Arc<RwLock<SessionManager>>,
     seats: Arc<RwLock<SeatManager>>,
     config: Config,
+    /// Where `set_theme` saves the greeter settings
+    config_path: PathBuf,
+    /// Picked up at the next screen drawn
+    theme: std::sync::RwLock<GreeterTheme>,
     current_user: RwLock<Option<String>>,
+    /// Failed attempts through `verify`, per user
+    locks: RwLock<HashMap<String, LoginLock>>,
 }
 
 impl Greeter {
@@ -29,14 +38,24 @@ impl Greeter {
         sessions: Arc<RwLock<SessionManager>>,
         seats: Arc<RwLock<SeatManager>>,
         config: Config,
+        config_path: PathBuf,
     ) -> Self {
+        let theme = GreeterTheme::resolve(&config.greeter_theme, config.greeter_background.as_deref())
+            .unwrap_or_else(|e| {
+                warn!("Greeter theme {}: {}; using the default", config.greeter_theme, e);
+                GreeterTheme::default()
+            });
+
         Self {
             vt,
             authenticator,
             sessions,
             seats,
             config,
+            config_path,
+            theme: std::sync::RwLock::new(theme),
             current_user: RwLock::new(None),
+            locks: RwLock::new(HashMap::new()),
         }
     }
 
@@ -49,11 +68,7 @@ impl Greeter {
             self.show_welcome();
 
             // Get available users
-            let users = user::list_login_users(
-                self.config.min_uid,
-                self.config.max_uid,
-                &self.config.hidden_users,
-            );
+            let users = self.login_users();
 
             // Show user selection or prompt
             if users.len() > 1 {
@@ -76,15 +91,17 @@ impl Greeter {
             match self.authenticate(&username, &password).await {
                 Ok(()) => {
                     // Start session
+                    // Don't leave the greeter's colors to the session
+                    print!("\x1b[0m\x1b[2J\x1b[H");
                     if let Err(e) = self.start_session(&username).await {
                         error!("Failed to start session: {}", e);
-                        println!("\nFailed to start session: {}", e);
+                        self.show_error(&format!("Failed to start session: {}", e));
                         tokio::time::sleep(std::time::Duration::from_secs(2)).await;
                     }
                 }
                 Err(e) => {
                     error!("Authentication failed for {}: {}", username, e);
-                    println!("\nLogin incorrect");
+                    self.show_error("Login incorrect");
                     tokio::time::sleep(std::time::Duration::from_secs(2)).await;
                 }
             }
@@ -92,40 +109,81 @@ impl Greeter {
     }
 
     fn show_welcome(&self) {
-        // ANSI escape to clear screen
-        print!("\x1b[2J\x1b[H");
+        let theme = self.theme();
+        let accent = theme.fg(&theme.colors.accent);
+        let text = theme.fg(&theme.colors.text);
+        let muted = theme.fg(&theme.colors.text_muted);
+
+        // Fill the screen with the theme background, then clear it
+        print!("{}{}\x1b[2J\x1b[H", theme.bg(), text);
 
-        println!("╔══════════════════════════════════════════════════════════╗");
+        println!("{}╔══════════════════════════════════════════════════════════╗", accent);
         println!("║                                                          ║");
-        println!("║                      D A E M O N O S                     ║");
+        println!("║                      {}D A E M O N O S{}                     ║", text, accent);
         println!("║                                                          ║");
-        println!("║                    Nyx Session Manager                   ║");
+        println!("║                    {}Nyx Session Manager{}                   ║", muted, accent);
         println!("║                                                          ║");
-        println!("╚══════════════════════════════════════════════════════════╝");
+        println!("╚══════════════════════════════════════════════════════════╝{}", text);
         println!();
 
         // Show hostname
         if let Ok(hostname) = std::fs::read_to_string("/etc/hostname") {
-            println!("  Host: {}", hostname.trim());
+            println!("  {}Host:{} {}", muted, text, hostname.trim());
         }
 
         // Show date/time
-        println!("  Time: {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+        println!("  {}Time:{} {}", muted, text, chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
         println!();
     }
 
-    fn show_user_list(&self, users: &[user::UserInfo]) {
+    fn show_user_list(&self, users: &[UserDisplay]) {
+        let theme = self.theme();
+        let text = theme.fg(&theme.colors.text);
+        let muted = theme.fg(&theme.colors.text_muted);
+
         println!("Available users:");
         println!();
 
         for (i, user) in users.iter().enumerate() {
-            let display: UserDisplay = user.clone().into();
-            println!("  {}. {} ({})", i + 1, display.display_name, display.username);
+            println!("  {}. {} {}({}){}", i + 1, user.display_name, muted, user.username, text);
         }
 
         println!();
     }
 
+    fn show_error(&self, message: &str) {
+        let theme = self.theme();
+        println!("\n{}{}{}", theme.fg(&theme.colors.error), message, theme.fg(&theme.colors.text));
+    }
+
+    /// Users offered at the login prompt, with their avatars
+    pub fn login_users(&self) -> Vec<UserDisplay> {
+        user::list_login_users(
+            self.config.min_uid,
+            self.config.max_uid,
+            &self.config.hidden_users,
+        )
+        .into_iter()
+        .map(UserDisplay::from)
+        .collect()
+    }
+
+    /// Theme in use
+    pub fn theme(&self) -> GreeterTheme {
+        self.theme.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Switch theme and save it as the `greeter_theme` setting; the
+    /// greeter redraws with it at its next screen
+    pub fn set_theme(&self, name: &str, background: Option<&str>) -> Result<GreeterTheme> {
+        let theme = GreeterTheme::resolve(name, background)?;
+        crate::theme::save(&self.config_path, name, background)?;
+
+        info!("Greeter theme set to {}", name);
+        *self.theme.write().unwrap_or_else(|e| e.into_inner()) = theme.clone();
+        Ok(theme)
+    }
+
     async fn prompt_username(&self) -> Option<String> {
         print!("Username: ");
         io::stdout().flush().ok()?;
@@ -172,6 +230,31 @@ impl Greeter {
         }
     }
 
+    /// Check credentials for a login that does not go through this
+    /// greeter, such as a remote desktop connection
+    ///
+    /// Repeated failures lock the user out of this path for a while.
+    pub async fn verify(&self, username: &str, password: &str) -> Result<()> {
+        if self.locks.read().await.get(username).is_some_and(|l| l.is_locked()) {
+            return Err(anyhow::anyhow!("Too many failed attempts, try again later"));
+        }
+
+        let result = self.authenticate(username, password).await;
+
+        let mut locks = self.locks.write().await;
+        let lock = locks
+            .entry(username.to_string())
+            .or_insert_with(|| LoginLock::new(username));
+        match &result {
+            Ok(()) => lock.reset(),
+            Err(e) => {
+                warn!("Authentication failed for {}: {}", username, e);
+                lock.record_failure();
+            }
+        }
+        result
+    }
+
     async fn start_session(&self, username: &str) -> Result<()> {
         // Get user info
         let user_info = user::get_user_info(username)?;
@@ -2,15 +2,19 @@
 
 use crate::greeter::Greeter;
 use crate::seat::SeatManager;
-use crate::session::SessionManager;
+use crate::session::{Session, SessionEvent, SessionManager, SessionState};
+use crate::theme::{self, GreeterTheme};
+use crate::user::UserDisplay;
 use anyhow::Result;
+use libnyx_ipc::audit::{self, AuditEvent, AuditOutcome};
+use libnyx_ipc::Hello;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
-use tokio::sync::RwLock;
-use tracing::{info, error, debug};
+use tokio::sync::{broadcast, RwLock};
+use tracing::{info, error, debug, warn};
 
 /// IPC request types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,8 +23,12 @@ pub enum IpcRequest {
     ListSessions,
     ListSeats,
     GetSession { id: String },
+    /// The session in the foreground of a seat (`seat0` by default)
+    GetActiveSession { seat: Option<String> },
     LockSession { id: Option<String> },
     UnlockSession { id: String },
+    /// Unlock a locked session with its user's password (the lock screen)
+    UnlockWithPassword { id: String, password: String },
     TerminateSession { id: String },
     SwitchSession { id: String },
     ActivateSession { id: String },
@@ -30,6 +38,19 @@ pub enum IpcRequest {
         seat: String,
     },
     SetSessionController { id: String, pid: u32 },
+    /// Check credentials for a login that is not on a VT (remote desktop)
+    Authenticate { username: String, password: String },
+    /// Keep the connection open and push a `SessionEvent` whenever a
+    /// session is locked or unlocked, starting with the locked ones
+    Subscribe,
+    /// Users the greeter offers, with their avatars
+    ListUsers,
+    GetGreeterTheme,
+    ListGreeterThemes,
+    /// Resolve a theme without applying it, for previews
+    PreviewGreeterTheme { theme: String, background: Option<String> },
+    /// Apply and save a theme; root or the user on seat0 only
+    SetGreeterTheme { theme: String, background: Option<String> },
 }
 
 /// IPC response types
@@ -40,6 +61,9 @@ pub enum IpcResponse {
     Sessions(Vec<SessionInfo>),
     Seats(Vec<SeatInfo>),
     Session(SessionInfo),
+    Users { users: Vec<UserEntry> },
+    GreeterTheme { theme: GreeterTheme },
+    GreeterThemes { themes: Vec<String> },
     Error { message: String },
 }
 
@@ -60,6 +84,46 @@ pub struct SessionInfo {
     pub created_at: String,
 }
 
+impl From<&Session> for SessionInfo {
+    fn from(s: &Session) -> Self {
+        Self {
+            id: s.id.clone(),
+            username: s.username.clone(),
+            uid: s.uid,
+            seat: s.seat.clone(),
+            state: s.state.as_str().to_string(),
+            session_type: s.session_type.clone(),
+            vt: s.vt,
+            tty: s.tty.clone(),
+            display: s.display.clone(),
+            remote_host: s.remote_host.clone(),
+            leader_pid: s.leader_pid,
+            created_at: s.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+}
+
+/// Login user for IPC
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserEntry {
+    pub username: String,
+    pub display_name: String,
+    pub uid: u32,
+    /// Path to the user's picture
+    pub avatar: Option<String>,
+}
+
+impl From<UserDisplay> for UserEntry {
+    fn from(user: UserDisplay) -> Self {
+        Self {
+            username: user.username,
+            display_name: user.display_name,
+            uid: user.uid,
+            avatar: user.avatar,
+        }
+    }
+}
+
 /// Seat info for IPC
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SeatInfo {
@@ -132,21 +196,40 @@ async fn handle_client(
     seats: Arc<RwLock<SeatManager>>,
     greeter: Arc<Greeter>,
 ) -> Result<()> {
+    // Changing the greeter is limited to root and whoever is at the seat
+    let caller = stream.peer_cred().ok().map(|cred| cred.uid());
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
 
-    while reader.read_line(&mut line).await? > 0 {
-        debug!("Received: {}", line.trim());
+    let hello = Hello::new("spectre", env!("CARGO_PKG_VERSION")).with_features(&["subscribe", "lock", "greeter-theme"]);
 
-        let response = match serde_json::from_str::<IpcRequest>(&line) {
-            Ok(request) => process_request(request, &sessions, &seats, &greeter).await,
-            Err(e) => IpcResponse::Error {
-                message: format!("Invalid request: {}", e),
-            },
+    while reader.read_line(&mut line).await? > 0 {
+        let response_json = match hello.respond(&line) {
+            Some(reply) => reply,
+            None => {
+                let response = match serde_json::from_str::<IpcRequest>(&line) {
+                    Ok(IpcRequest::Subscribe) => return stream_events(writer, &sessions).await,
+                    Ok(request) => {
+                        // Never log credentials
+                        match &request {
+                            IpcRequest::Authenticate { username, .. } => {
+                                debug!("Received: Authenticate for {}", username);
+                            }
+                            IpcRequest::UnlockWithPassword { id, .. } => {
+                                debug!("Received: UnlockWithPassword for {}", id);
+                            }
+                            _ => debug!("Received: {}", line.trim()),
+                        }
+                        process_request(request, caller, &sessions, &seats, &greeter).await
+                    }
+                    Err(e) => IpcResponse::Error {
+                        message: format!("Invalid request: {}", e),
+                    },
+                };
+                serde_json::to_string(&response)?
+            }
         };
-
-        let response_json = serde_json::to_string(&response)?;
         writer.write_all(response_json.as_bytes()).await?;
         writer.write_all(b"\n").await?;
         writer.flush().await?;
@@ -157,30 +240,56 @@ async fn handle_client(
     Ok(())
 }
 
+/// Send a `Locked` event per locked session, then every change, until the
+/// client goes away
+async fn stream_events(
+    mut writer: tokio::net::unix::OwnedWriteHalf,
+    sessions: &RwLock<SessionManager>,
+) -> Result<()> {
+    let (mut events, locked) = {
+        let session_mgr = sessions.read().await;
+        let locked: Vec<SessionEvent> = session_mgr.all()
+            .filter(|s| s.state == SessionState::Locked)
+            .map(|s| SessionEvent::Locked { session: s.id.clone() })
+            .collect();
+        (session_mgr.subscribe(), locked)
+    };
+
+    for event in locked {
+        write_event(&mut writer, &event).await?;
+    }
+
+    loop {
+        match events.recv().await {
+            Ok(event) => write_event(&mut writer, &event).await?,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("Lock subscriber fell behind by {} events", missed);
+            }
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+async fn write_event(writer: &mut tokio::net::unix::OwnedWriteHalf, event: &SessionEvent) -> Result<()> {
+    let json = serde_json::to_string(event)?;
+    writer.write_all(json.as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+    writer.flush().await?;
+    Ok(())
+}
+
 async fn process_request(
     request: IpcRequest,
+    caller: Option<u32>,
     sessions: &RwLock<SessionManager>,
     seats: &RwLock<SeatManager>,
-    _greeter: &Greeter,
+    greeter: &Greeter,
 ) -> IpcResponse {
     match request {
         IpcRequest::ListSessions => {
             let session_mgr = sessions.read().await;
             let infos: Vec<SessionInfo> = session_mgr.all()
-                .map(|s| SessionInfo {
-                    id: s.id.clone(),
-                    username: s.username.clone(),
-                    uid: s.uid,
-                    seat: s.seat.clone(),
-                    state: s.state.as_str().to_string(),
-                    session_type: s.session_type.clone(),
-                    vt: s.vt,
-                    tty: s.tty.clone(),
-                    display: s.display.clone(),
-                    remote_host: s.remote_host.clone(),
-                    leader_pid: s.leader_pid,
-                    created_at: s.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
-                })
+                .map(SessionInfo::from)
                 .collect();
 
             IpcResponse::Sessions(infos)
@@ -203,20 +312,7 @@ async fn process_request(
         IpcRequest::GetSession { id } => {
             let session_mgr = sessions.read().await;
             if let Some(s) = session_mgr.get(&id) {
-                IpcResponse::Session(SessionInfo {
-                    id: s.id.clone(),
-                    username: s.username.clone(),
-                    uid: s.uid,
-                    seat: s.seat.clone(),
-                    state: s.state.as_str().to_string(),
-                    session_type: s.session_type.clone(),
-                    vt: s.vt,
-                    tty: s.tty.clone(),
-                    display: s.display.clone(),
-                    remote_host: s.remote_host.clone(),
-                    leader_pid: s.leader_pid,
-                    created_at: s.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
-                })
+                IpcResponse::Session(SessionInfo::from(s))
             } else {
                 IpcResponse::Error {
                     message: format!("Session not found: {}", id),
@@ -224,6 +320,21 @@ async fn process_request(
             }
         }
 
+        IpcRequest::GetActiveSession { seat } => {
+            let seat = seat.unwrap_or_else(|| "seat0".to_string());
+            let active = seats.read().await
+                .get(&seat)
+                .and_then(|s| s.active_session.clone());
+
+            let session_mgr = sessions.read().await;
+            match active.as_deref().and_then(|id| session_mgr.get(id)) {
+                Some(s) => IpcResponse::Session(SessionInfo::from(s)),
+                None => IpcResponse::Error {
+                    message: format!("No active session on {}", seat),
+                },
+            }
+        }
+
         IpcRequest::LockSession { id } => {
             let mut session_mgr = sessions.write().await;
             let target_id = if let Some(id) = id {
@@ -258,6 +369,42 @@ async fn process_request(
             }
         }
 
+        IpcRequest::UnlockWithPassword { id, password } => {
+            let username = {
+                let session_mgr = sessions.read().await;
+                match session_mgr.get(&id) {
+                    Some(s) if s.state == SessionState::Locked => s.username.clone(),
+                    Some(_) => {
+                        return IpcResponse::Error {
+                            message: format!("Session {} is not locked", id),
+                        };
+                    }
+                    None => {
+                        return IpcResponse::Error {
+                            message: format!("Session not found: {}", id),
+                        };
+                    }
+                }
+            };
+
+            // Same lockout after repeated failures as other password checks
+            if let Err(e) = greeter.verify(&username, &password).await {
+                audit(&username, "session.unlock", &id, AuditOutcome::Denied, Some(e.to_string()));
+                return IpcResponse::Error { message: e.to_string() };
+            }
+
+            let mut session_mgr = sessions.write().await;
+            match session_mgr.unlock(&id) {
+                Ok(()) => {
+                    audit(&username, "session.unlock", &id, AuditOutcome::Success, None);
+                    IpcResponse::Success {
+                        message: format!("Unlocked session {}", id),
+                    }
+                }
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
         IpcRequest::TerminateSession { id } => {
             let mut session_mgr = sessions.write().await;
             match session_mgr.end(&id) {
@@ -316,10 +463,16 @@ async fn process_request(
                 &session_type,
                 crate::session::SessionClass::User,
             ) {
-                Ok(session) => IpcResponse::Success {
-                    message: format!("Created session {}", session.id),
-                },
-                Err(e) => IpcResponse::Error { message: e.to_string() },
+                Ok(session) => {
+                    audit(&username, "session.create", &session.id, AuditOutcome::Success, None);
+                    IpcResponse::Success {
+                        message: format!("Created session {}", session.id),
+                    }
+                }
+                Err(e) => {
+                    audit(&username, "session.create", &seat, AuditOutcome::Failure, Some(e.to_string()));
+                    IpcResponse::Error { message: e.to_string() }
+                }
             }
         }
 
@@ -329,9 +482,79 @@ async fn process_request(
                 message: format!("Set controller for {} to PID {}", id, pid),
             }
         }
+
+        IpcRequest::Subscribe => IpcResponse::Error {
+            message: "Subscribe must be sent on its own connection".to_string(),
+        },
+
+        IpcRequest::ListUsers => IpcResponse::Users {
+            users: greeter.login_users().into_iter().map(UserEntry::from).collect(),
+        },
+
+        IpcRequest::GetGreeterTheme => IpcResponse::GreeterTheme { theme: greeter.theme() },
+
+        IpcRequest::ListGreeterThemes => IpcResponse::GreeterThemes {
+            themes: theme::available_themes(),
+        },
+
+        IpcRequest::PreviewGreeterTheme { theme, background } => {
+            match GreeterTheme::resolve(&theme, background.as_deref()) {
+                Ok(theme) => IpcResponse::GreeterTheme { theme },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::SetGreeterTheme { theme, background } => {
+            let active_user = {
+                let active = seats.read().await
+                    .get("seat0")
+                    .and_then(|s| s.active_session.clone());
+                let session_mgr = sessions.read().await;
+                active.as_deref().and_then(|id| session_mgr.get(id)).map(|s| (s.uid, s.username.clone()))
+            };
+            let actor = match (caller, active_user) {
+                (Some(0), _) => "root".to_string(),
+                (Some(uid), Some((active_uid, username))) if uid == active_uid => username,
+                _ => {
+                    return IpcResponse::Error {
+                        message: "Only root or the user at the seat can change the login screen".to_string(),
+                    };
+                }
+            };
+
+            match greeter.set_theme(&theme, background.as_deref()) {
+                Ok(resolved) => {
+                    audit(&actor, "greeter.theme", &theme, AuditOutcome::Success, None);
+                    IpcResponse::GreeterTheme { theme: resolved }
+                }
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::Authenticate { username, password } => {
+            match greeter.verify(&username, &password).await {
+                Ok(()) => {
+                    audit(&username, "auth.password", &username, AuditOutcome::Success, None);
+                    IpcResponse::Success {
+                        message: format!("Authenticated {}", username),
+                    }
+                }
+                Err(e) => {
+                    audit(&username, "auth.password", &username, AuditOutcome::Denied, Some(e.to_string()));
+                    IpcResponse::Error { message: e.to_string() }
+                }
+            }
+        }
     }
 }
 
+/// Report an authentication or session decision on the audit bus
+fn audit(username: &str, action: &str, object: &str, outcome: AuditOutcome, reason: Option<String>) {
+    let mut event = AuditEvent::new("spectre", username, action, object, outcome);
+    event.reason = reason;
+    audit::record(event);
+}
+
 /// IPC client
 pub struct SpectreClient {
     socket_path: PathBuf,
@@ -397,4 +620,16 @@ impl SpectreClient {
             _ => Err(anyhow::anyhow!("Unexpected response")),
         }
     }
+
+    pub async fn authenticate(&self, username: &str, password: &str) -> Result<()> {
+        let request = IpcRequest::Authenticate {
+            username: username.to_string(),
+            password: password.to_string(),
+        };
+        match self.send(request).await? {
+            IpcResponse::Success { .. } => Ok(()),
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+            _ => Err(anyhow::anyhow!("Unexpected response")),
+        }
+    }
 }
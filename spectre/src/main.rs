@@ -10,6 +10,8 @@
 //! - **Auto-login**: Configurable automatic login
 //! - **Session Lock**: Screen locking and unlock
 //! - **XDG Compliance**: Proper XDG runtime directory setup
+//! - **Greeter Theming**: nyx-theme palettes, backgrounds and user avatars
+//! - **User Services**: A `nyx-serviced --user` instance per session
 
 mod auth;
 mod session;
@@ -18,6 +20,8 @@ mod user;
 mod greeter;
 mod pam_auth;
 mod ipc;
+mod theme;
+mod user_manager;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
@@ -117,7 +121,7 @@ async fn handle_client_command(socket: &PathBuf, cmd: Commands) -> Result<()> {
             println!("Session locked");
         }
         Commands::Unlock => {
-            println!("Use the greeter to unlock");
+            println!("Use the lock screen to unlock");
         }
         Commands::Sessions => {
             let sessions = client.list_sessions().await?;
@@ -194,6 +198,7 @@ async fn run_daemon(args: Args) -> Result<()> {
         session_manager.clone(),
         seat_manager.clone(),
         config.clone(),
+        args.config.clone(),
     ));
 
     // Spawn greeter
@@ -293,8 +298,10 @@ pub struct Config {
     pub auto_login: Option<String>,
     /// Delay before auto-login (seconds)
     pub auto_login_delay: u32,
-    /// Greeter theme
+    /// Greeter theme: `default`, `light`, or a nyx-theme theme name
     pub greeter_theme: String,
+    /// Greeter background: `#RRGGBB` or an image path
+    pub greeter_background: Option<String>,
     /// Allow shutdown from greeter
     pub allow_shutdown: bool,
     /// Allow reboot from greeter
@@ -305,6 +312,9 @@ pub struct Config {
     pub max_uid: u32,
     /// Hide users from greeter
     pub hidden_users: Vec<String>,
+    /// Service manager started as the user for each session; none to
+    /// run sessions without one
+    pub user_manager: Option<String>,
 }
 
 impl Default for Config {
@@ -328,12 +338,14 @@ impl Default for Config {
             ],
             auto_login: None,
             auto_login_delay: 3,
-            greeter_theme: "default".to_string(),
+            greeter_theme: theme::DEFAULT_THEME.to_string(),
+            greeter_background: None,
             allow_shutdown: true,
             allow_reboot: true,
             min_uid: 1000,
             max_uid: 60000,
             hidden_users: vec!["root".to_string(), "nobody".to_string()],
+            user_manager: Some("/usr/bin/nyx-serviced --user".to_string()),
         }
     }
 }
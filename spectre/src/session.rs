@@ -7,6 +7,7 @@ use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::process::Child;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 use tracing::{info, warn, error, debug};
 
@@ -179,12 +180,25 @@ impl Session {
     }
 }
 
+/// Lock state change, pushed to subscribed shells
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum SessionEvent {
+    /// The shell should put up its lock screen
+    Locked { session: String },
+    /// Unlocked, by the lock screen or otherwise
+    Unlocked { session: String },
+}
+
 /// Session manager
 pub struct SessionManager {
     sessions: HashMap<String, Session>,
     user_sessions: HashMap<String, Vec<String>>, // username -> session IDs
     config: Config,
     processes: HashMap<String, Child>,
+    /// Per-session service managers
+    user_managers: HashMap<String, Child>,
+    events: broadcast::Sender<SessionEvent>,
 }
 
 impl SessionManager {
@@ -194,6 +208,8 @@ impl SessionManager {
             user_sessions: HashMap::new(),
             config,
             processes: HashMap::new(),
+            user_managers: HashMap::new(),
+            events: broadcast::channel(16).0,
         })
     }
 
@@ -221,6 +237,15 @@ impl SessionManager {
 
         info!("Created session {} for {}", session.id, user.username);
 
+        if let (SessionClass::User, Some(command)) = (class, &self.config.user_manager) {
+            match crate::user_manager::start(command, &session) {
+                Ok(child) => {
+                    self.user_managers.insert(session.id.clone(), child);
+                }
+                Err(e) => warn!("Session {} runs without user services: {:#}", session.id, e),
+            }
+        }
+
         Ok(session)
     }
 
@@ -255,6 +280,11 @@ impl SessionManager {
         self.sessions.values()
     }
 
+    /// Follow lock state changes
+    pub fn subscribe(&self) -> broadcast::Receiver<SessionEvent> {
+        self.events.subscribe()
+    }
+
     /// Activate a session
     pub fn activate(&mut self, id: &str) -> Result<()> {
         let session = self.sessions.get_mut(id)
@@ -274,6 +304,12 @@ impl SessionManager {
         session.lock();
         info!("Locked session {}", id);
 
+        // Sent again for a session that is already locked, so a lock
+        // screen that went away is put back up
+        if session.state == SessionState::Locked {
+            let _ = self.events.send(SessionEvent::Locked { session: id.to_string() });
+        }
+
         Ok(())
     }
 
@@ -282,9 +318,14 @@ impl SessionManager {
         let session = self.sessions.get_mut(id)
             .ok_or_else(|| anyhow!("Session not found: {}", id))?;
 
+        let was_locked = session.state == SessionState::Locked;
         session.unlock();
         info!("Unlocked session {}", id);
 
+        if was_locked {
+            let _ = self.events.send(SessionEvent::Unlocked { session: id.to_string() });
+        }
+
         Ok(())
     }
 
@@ -296,6 +337,10 @@ impl SessionManager {
             let _ = child.wait();
         }
 
+        if let (Some(child), Some(session)) = (self.user_managers.remove(id), self.sessions.get(id)) {
+            crate::user_manager::stop(child, session);
+        }
+
         // Update session state
         if let Some(session) = self.sessions.get_mut(id) {
             session.state = SessionState::Ended;
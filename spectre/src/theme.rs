@@ -0,0 +1,175 @@
+//! Greeter theming
+//!
+//! The greeter's look follows the `greeter_theme` setting: `default` and
+//! `light` are nyx-theme's built-in palettes, any other name is a theme
+//! file in the grimoire themes directory. `greeter_background` is either a
+//! `#RRGGBB` color or an image; graphical greeters draw the image, the
+//! text greeter fills the screen with the theme's background color.
+//!
+//! Settings previews a theme by resolving it here without applying it,
+//! so what it shows is exactly what the greeter would draw.
+
+use anyhow::{Result, anyhow};
+use nyx_theme::theme::{color_to_hex, parse_hex_color};
+use nyx_theme::{NyxTheme, ThemeLoader};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Built-in dark palette
+pub const DEFAULT_THEME: &str = "default";
+
+/// Built-in light palette
+const LIGHT_THEME: &str = "light";
+
+/// A resolved greeter theme
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GreeterTheme {
+    pub name: String,
+    pub background: Background,
+    pub colors: GreeterColors,
+}
+
+/// What fills the screen behind the login box
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum Background {
+    /// `#RRGGBB`
+    Color(String),
+    /// Absolute path to an image
+    Image(String),
+}
+
+/// Colors the greeter draws with, as `#RRGGBB`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GreeterColors {
+    pub background: String,
+    pub surface: String,
+    pub text: String,
+    pub text_muted: String,
+    pub accent: String,
+    pub error: String,
+}
+
+impl From<&NyxTheme> for GreeterColors {
+    fn from(theme: &NyxTheme) -> Self {
+        let palette = theme.palette();
+        Self {
+            background: color_to_hex(palette.background),
+            surface: color_to_hex(palette.surface),
+            text: color_to_hex(palette.text_primary),
+            text_muted: color_to_hex(palette.text_secondary),
+            accent: color_to_hex(palette.accent),
+            error: color_to_hex(palette.error),
+        }
+    }
+}
+
+impl GreeterTheme {
+    /// Resolve a theme name and background setting
+    pub fn resolve(name: &str, background: Option<&str>) -> Result<Self> {
+        let colors = match name {
+            DEFAULT_THEME => GreeterColors::from(&NyxTheme::dark()),
+            LIGHT_THEME => GreeterColors::from(&NyxTheme::light()),
+            name => {
+                let mut loader = ThemeLoader::system();
+                loader.reload();
+                let theme = loader.theme(name).ok_or_else(|| anyhow!("Unknown theme: {}", name))?;
+                GreeterColors::from(theme)
+            }
+        };
+
+        let background = match background {
+            None => Background::Color(colors.background.clone()),
+            Some(color) if color.starts_with('#') => {
+                let parsed = parse_hex_color(color).ok_or_else(|| anyhow!("Invalid color: {}", color))?;
+                Background::Color(color_to_hex(parsed))
+            }
+            Some(image) => {
+                if !Path::new(image).is_absolute() || !Path::new(image).is_file() {
+                    return Err(anyhow!("Background image not found: {}", image));
+                }
+                Background::Image(image.to_string())
+            }
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            background,
+            colors,
+        })
+    }
+
+    /// Fill color of the text greeter's screen
+    pub fn screen_color(&self) -> &str {
+        match &self.background {
+            Background::Color(color) => color,
+            Background::Image(_) => &self.colors.background,
+        }
+    }
+
+    /// Escape sequence selecting `color` for text
+    pub fn fg(&self, color: &str) -> String {
+        ansi(color, 38)
+    }
+
+    /// Escape sequence selecting the screen fill behind text
+    pub fn bg(&self) -> String {
+        ansi(self.screen_color(), 48)
+    }
+}
+
+impl Default for GreeterTheme {
+    fn default() -> Self {
+        let colors = GreeterColors::from(&NyxTheme::dark());
+        Self {
+            name: DEFAULT_THEME.to_string(),
+            background: Background::Color(colors.background.clone()),
+            colors,
+        }
+    }
+}
+
+/// Theme names the greeter can use
+pub fn available_themes() -> Vec<String> {
+    let mut loader = ThemeLoader::system();
+    loader.reload();
+
+    let mut names = vec![DEFAULT_THEME.to_string(), LIGHT_THEME.to_string()];
+    names.extend(loader.themes().filter_map(|t| t.name.clone()));
+    names
+}
+
+/// Write the greeter settings to the config file, keeping everything else
+pub fn save(config_path: &Path, name: &str, background: Option<&str>) -> Result<()> {
+    let mut config: serde_yaml::Value = if config_path.exists() {
+        serde_yaml::from_str(&std::fs::read_to_string(config_path)?)?
+    } else {
+        serde_yaml::Value::Mapping(Default::default())
+    };
+    let map = config
+        .as_mapping_mut()
+        .ok_or_else(|| anyhow!("{:?} is not a mapping", config_path))?;
+
+    map.insert("greeter_theme".into(), name.into());
+    match background {
+        Some(background) => map.insert("greeter_background".into(), background.into()),
+        None => map.remove("greeter_background"),
+    };
+
+    std::fs::write(config_path, serde_yaml::to_string(&config)?)?;
+    Ok(())
+}
+
+/// 24-bit color escape; `layer` is 38 for text, 48 for background
+fn ansi(hex: &str, layer: u8) -> String {
+    match parse_hex_color(hex) {
+        Some(color) => format!(
+            "\x1b[{};2;{};{};{}m",
+            layer,
+            (color.r * 255.0).round() as u8,
+            (color.g * 255.0).round() as u8,
+            (color.b * 255.0).round() as u8
+        ),
+        None => String::new(),
+    }
+}
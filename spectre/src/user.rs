@@ -161,16 +161,28 @@ pub fn is_account_valid(username: &str) -> Result<bool> {
     Ok(true)
 }
 
-/// Get user avatar path
-pub fn get_user_avatar(username: &str) -> Option<String> {
-    let paths = [
-        format!("/var/lib/AccountsService/icons/{}", username),
-        format!("/home/{}/.face", username),
-        format!("/home/{}/.face.icon", username),
-    ];
+/// AccountsService keeps per-user settings and the icons they point at here
+const ACCOUNTS_DIR: &str = "/var/lib/AccountsService";
+
+/// Get user avatar path: the `Icon=` of the user's AccountsService
+/// file, its icon directory, then `~/.face` and `~/.face.icon`
+pub fn get_user_avatar(username: &str, home: &str) -> Option<String> {
+    let configured = std::fs::read_to_string(format!("{}/users/{}", ACCOUNTS_DIR, username))
+        .ok()
+        .and_then(|keyfile| {
+            keyfile.lines()
+                .find_map(|line| line.trim().strip_prefix("Icon="))
+                .map(|icon| icon.trim().to_string())
+        });
+
+    let paths = configured.into_iter().chain([
+        format!("{}/icons/{}", ACCOUNTS_DIR, username),
+        format!("{}/.face", home),
+        format!("{}/.face.icon", home),
+    ]);
 
     for path in paths {
-        if std::path::Path::new(&path).exists() {
+        if std::path::Path::new(&path).is_file() {
             return Some(path);
         }
     }
@@ -199,7 +211,7 @@ impl From<UserInfo> for UserDisplay {
         };
 
         Self {
-            avatar: get_user_avatar(&info.username),
+            avatar: get_user_avatar(&info.username, &info.home),
             username: info.username,
             display_name,
             uid: info.uid,
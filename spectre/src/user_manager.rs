@@ -0,0 +1,142 @@
+//! Per-session service managers
+//!
+//! Every user session gets its own `nyx-serviced --user`, running as the
+//! session's user with the session environment. It is handed a cgroup
+//! slice under `user.slice/user-<uid>.slice`, delegated to the user, to
+//! put its services in. Ending the session stops the manager and tears
+//! the slice down with everything still running in it.
+
+use crate::session::Session;
+use anyhow::{anyhow, Context, Result};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Controllers a user manager may set limits with
+const DELEGATED_CONTROLLERS: &[&str] = &["cpu", "memory", "pids", "io"];
+
+/// Files of its slice a manager writes to
+const DELEGATED_FILES: &[&str] = &["cgroup.procs", "cgroup.subtree_control", "cgroup.threads"];
+
+/// How long a manager gets to exit before it is killed
+const STOP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A session's slice
+pub fn session_slice(uid: u32, session_id: &str) -> PathBuf {
+    PathBuf::from(CGROUP_ROOT)
+        .join("user.slice")
+        .join(format!("user-{}.slice", uid))
+        .join(format!("session-{}.slice", session_id))
+}
+
+/// Create a session's slice and hand it to the user
+fn delegate_slice(session: &Session) -> Result<PathBuf> {
+    let slice = session_slice(session.uid, &session.id);
+    std::fs::create_dir_all(&slice).with_context(|| format!("Failed to create cgroup {:?}", slice))?;
+
+    // A controller is only available in the slice if every cgroup above
+    // it passes it down
+    let root = Path::new(CGROUP_ROOT);
+    for parent in slice.ancestors().skip(1).take_while(|p| p.starts_with(root)) {
+        for controller in DELEGATED_CONTROLLERS {
+            if let Err(e) = std::fs::write(parent.join("cgroup.subtree_control"), format!("+{}", controller)) {
+                debug!("Could not enable {} in {:?}: {}", controller, parent, e);
+            }
+        }
+    }
+
+    let owner = Some(nix::unistd::Uid::from_raw(session.uid));
+    let group = Some(nix::unistd::Gid::from_raw(session.gid));
+    for path in std::iter::once(slice.clone()).chain(DELEGATED_FILES.iter().map(|f| slice.join(f))) {
+        nix::unistd::chown(&path, owner, group).with_context(|| format!("Failed to delegate {:?}", path))?;
+    }
+
+    Ok(slice)
+}
+
+/// Start a session's manager with `command`
+pub fn start(command: &str, session: &Session) -> Result<Child> {
+    let parts: Vec<&str> = command.split_whitespace().collect();
+    let (program, args) = parts.split_first().ok_or_else(|| anyhow!("Empty user manager command"))?;
+
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+
+    cmd.env_clear();
+    cmd.envs(&session.environment);
+    match delegate_slice(session) {
+        Ok(slice) => {
+            cmd.env("NYX_CGROUP_SLICE", slice);
+        }
+        Err(e) => warn!("No cgroup slice for session {}: {:#}", session.id, e),
+    }
+
+    cmd.current_dir(session.environment.get("HOME").map(String::as_str).unwrap_or("/"));
+    cmd.uid(session.uid).gid(session.gid);
+
+    let child = cmd
+        .spawn()
+        .with_context(|| format!("Failed to start user manager {}", program))?;
+    info!(
+        "Started user manager for session {} ({}) with PID {}",
+        session.id,
+        session.username,
+        child.id()
+    );
+    Ok(child)
+}
+
+/// Stop a session's manager, then everything left in its slice
+pub fn stop(mut child: Child, session: &Session) {
+    let pid = nix::unistd::Pid::from_raw(child.id() as i32);
+    let _ = nix::sys::signal::kill(pid, nix::sys::signal::Signal::SIGTERM);
+
+    let deadline = Instant::now() + STOP_TIMEOUT;
+    while matches!(child.try_wait(), Ok(None)) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    if matches!(child.try_wait(), Ok(None)) {
+        warn!("User manager for session {} did not exit, killing it", session.id);
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    let slice = session_slice(session.uid, &session.id);
+    if slice.exists() {
+        if let Err(e) = remove_slice(&slice) {
+            warn!("Failed to remove {:?}: {:#}", slice, e);
+        }
+    }
+}
+
+/// Kill whatever is left in a slice and remove it
+fn remove_slice(slice: &Path) -> Result<()> {
+    std::fs::write(slice.join("cgroup.kill"), "1").context("Failed to kill remaining processes")?;
+
+    // cgroup.kill returns before the processes are gone, and a cgroup
+    // cannot be removed while anything is in it
+    let deadline = Instant::now() + STOP_TIMEOUT;
+    loop {
+        let scopes: Vec<PathBuf> = std::fs::read_dir(slice)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .collect();
+        let removed = scopes
+            .iter()
+            .try_for_each(std::fs::remove_dir)
+            .and_then(|_| std::fs::remove_dir(slice));
+
+        match removed {
+            Ok(()) => return Ok(()),
+            Err(e) if e.raw_os_error() == Some(libc::EBUSY) && Instant::now() < deadline => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
@@ -14,6 +14,10 @@ pub struct SummonerConfig {
     pub recent: RecentConfig,
     #[serde(default)]
     pub custom_apps: Vec<CustomApp>,
+    #[serde(default)]
+    pub files: FileSearchConfig,
+    #[serde(default)]
+    pub providers: ProviderConfig,
 }
 
 impl Default for SummonerConfig {
@@ -23,6 +27,8 @@ impl Default for SummonerConfig {
             search: SearchConfig::default(),
             recent: RecentConfig::default(),
             custom_apps: Vec::new(),
+            files: FileSearchConfig::default(),
+            providers: ProviderConfig::default(),
         }
     }
 }
@@ -68,6 +74,15 @@ pub struct RecentConfig {
     pub max_size: usize,
     #[serde(default = "default_true")]
     pub boost_recent: bool,
+    /// Launch scores halve after this many days without use
+    #[serde(default = "default_half_life_days")]
+    pub half_life_days: f64,
+    /// How strongly frecency lifts a result
+    #[serde(default = "default_frecency_weight")]
+    pub frecency_weight: f64,
+    /// How strongly past launches from the same query terms lift a result
+    #[serde(default = "default_term_weight")]
+    pub term_weight: f64,
 }
 
 impl Default for RecentConfig {
@@ -76,11 +91,122 @@ impl Default for RecentConfig {
             enabled: true,
             max_size: default_recent_size(),
             boost_recent: true,
+            half_life_days: default_half_life_days(),
+            frecency_weight: default_frecency_weight(),
+            term_weight: default_term_weight(),
         }
     }
 }
 
 fn default_recent_size() -> usize { 50 }
+fn default_half_life_days() -> f64 { 14.0 }
+fn default_frecency_weight() -> f64 { 0.25 }
+fn default_term_weight() -> f64 { 0.5 }
+
+/// File and document search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSearchConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Directories to index recursively
+    #[serde(default = "default_file_roots")]
+    pub roots: Vec<PathBuf>,
+    /// Glob patterns matched against each path component
+    #[serde(default = "default_file_ignore")]
+    pub ignore: Vec<String>,
+    #[serde(default)]
+    pub include_hidden: bool,
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize,
+    /// Stop indexing after this many files
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+    /// Watch roots with inotify and update the index incrementally
+    #[serde(default = "default_true")]
+    pub watch: bool,
+    /// Match the query against file contents as well as names
+    #[serde(default = "default_true")]
+    pub content_search: bool,
+    /// Extensions whose contents are indexed
+    #[serde(default = "default_content_extensions")]
+    pub content_extensions: Vec<String>,
+    /// Only the first this many bytes of a file are indexed
+    #[serde(default = "default_max_content_bytes")]
+    pub max_content_bytes: u64,
+}
+
+impl Default for FileSearchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            roots: default_file_roots(),
+            ignore: default_file_ignore(),
+            include_hidden: false,
+            max_depth: default_max_depth(),
+            max_files: default_max_files(),
+            watch: true,
+            content_search: true,
+            content_extensions: default_content_extensions(),
+            max_content_bytes: default_max_content_bytes(),
+        }
+    }
+}
+
+fn default_file_roots() -> Vec<PathBuf> {
+    [dirs::document_dir(), dirs::desktop_dir(), dirs::download_dir()]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+fn default_file_ignore() -> Vec<String> {
+    ["node_modules", "target", "__pycache__", "*.tmp", "*.swp", "*~"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+fn default_max_depth() -> usize { 8 }
+fn default_max_files() -> usize { 100_000 }
+
+fn default_content_extensions() -> Vec<String> {
+    ["txt", "md", "org", "rst", "csv", "json", "yaml", "yml", "toml", "log"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+fn default_max_content_bytes() -> u64 { 64 * 1024 }
+
+/// External search providers registered over the socket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Budget for providers that don't ask for one
+    #[serde(default = "default_provider_latency")]
+    pub default_latency_ms: u64,
+    /// Upper bound on any provider's budget
+    #[serde(default = "default_provider_max_latency")]
+    pub max_latency_ms: u64,
+    #[serde(default = "default_results_per_provider")]
+    pub max_results_per_provider: usize,
+}
+
+impl Default for ProviderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            default_latency_ms: default_provider_latency(),
+            max_latency_ms: default_provider_max_latency(),
+            max_results_per_provider: default_results_per_provider(),
+        }
+    }
+}
+
+fn default_provider_latency() -> u64 { 100 }
+fn default_provider_max_latency() -> u64 { 250 }
+fn default_results_per_provider() -> usize { 5 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CustomApp {
@@ -0,0 +1,351 @@
+//! File and document index
+//!
+//! Indexes files under the configured roots so they can be searched
+//! alongside applications. The initial scan walks every root once; after
+//! that an inotify watcher updates the index as files are created,
+//! modified, renamed or removed.
+
+use crate::config::FileSearchConfig;
+use crate::search::MatchType;
+use anyhow::Result;
+use dashmap::DashMap;
+use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::ffi::OsStr;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+/// Indexed files, keyed by path
+pub struct FileIndex {
+    config: FileSearchConfig,
+    files: DashMap<PathBuf, IndexedFile>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexedFile {
+    pub path: PathBuf,
+    pub name: String,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    name_lower: String,
+    /// Lowercased leading text, for content matching
+    content: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileResult {
+    pub file: IndexedFile,
+    pub score: f64,
+    pub match_type: MatchType,
+    pub highlights: Vec<(usize, usize)>,
+}
+
+impl FileIndex {
+    pub fn new(config: FileSearchConfig) -> Self {
+        Self {
+            config,
+            files: DashMap::new(),
+        }
+    }
+
+    /// Re-index every root from scratch
+    pub fn scan(&self) -> usize {
+        self.files.clear();
+
+        for root in &self.config.roots {
+            if root.is_dir() {
+                self.scan_dir(root, self.config.max_depth);
+            } else {
+                tracing::debug!("Skipping missing file root {:?}", root);
+            }
+        }
+
+        self.files.len()
+    }
+
+    fn scan_dir(&self, dir: &Path, max_depth: usize) {
+        let walker = WalkDir::new(dir)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !self.is_ignored(e.file_name()));
+
+        for entry in walker.filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            if self.files.len() >= self.config.max_files {
+                tracing::warn!(
+                    "File index limit of {} reached, not indexing the rest of {:?}",
+                    self.config.max_files,
+                    dir
+                );
+                return;
+            }
+            if let Some(file) = self.load(entry.path()) {
+                self.files.insert(file.path.clone(), file);
+            }
+        }
+    }
+
+    /// Bring the index up to date for a changed path
+    pub fn update(&self, path: &Path) {
+        let Some(root) = self.config.roots.iter().find(|r| path.starts_with(r)) else {
+            return;
+        };
+        let Ok(relative) = path.strip_prefix(root) else {
+            return;
+        };
+        let depth = relative.components().count();
+
+        if depth > self.config.max_depth
+            || relative.components().any(|c| self.is_ignored(c.as_os_str()))
+        {
+            return;
+        }
+
+        if path.is_dir() {
+            self.scan_dir(path, self.config.max_depth - depth);
+        } else if path.is_file() {
+            if let Some(file) = self.load(path) {
+                self.files.insert(path.to_path_buf(), file);
+            }
+        } else {
+            // Removed or renamed away; drop it and anything beneath it
+            self.files.retain(|p, _| !p.starts_with(path));
+        }
+    }
+
+    /// Watch every root and keep the index current
+    ///
+    /// The index is only updated while the returned watcher is alive.
+    pub fn watch(self: &Arc<Self>) -> Result<RecommendedWatcher> {
+        let index = Arc::clone(self);
+
+        let mut watcher = RecommendedWatcher::new(
+            move |res: Result<Event, notify::Error>| match res {
+                Ok(event) => {
+                    if matches!(
+                        event.kind,
+                        notify::EventKind::Create(_)
+                            | notify::EventKind::Modify(_)
+                            | notify::EventKind::Remove(_)
+                    ) {
+                        for path in &event.paths {
+                            index.update(path);
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("File watch error: {}", e),
+            },
+            Config::default(),
+        )?;
+
+        for root in &self.config.roots {
+            if root.is_dir() {
+                watcher.watch(root, RecursiveMode::Recursive)?;
+                tracing::debug!("Watching {:?}", root);
+            }
+        }
+
+        Ok(watcher)
+    }
+
+    fn is_ignored(&self, name: &OsStr) -> bool {
+        let name = name.to_string_lossy();
+
+        if !self.config.include_hidden && name.starts_with('.') {
+            return true;
+        }
+
+        self.config.ignore.iter().any(|pattern| glob_match(pattern, &name))
+    }
+
+    fn load(&self, path: &Path) -> Option<IndexedFile> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let name = path.file_name()?.to_string_lossy().to_string();
+
+        let content = if self.config.content_search && self.has_content_extension(path) {
+            read_text(path, self.config.max_content_bytes)
+        } else {
+            None
+        };
+
+        Some(IndexedFile {
+            path: path.to_path_buf(),
+            name_lower: name.to_lowercase(),
+            name,
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            content,
+        })
+    }
+
+    fn has_content_extension(&self, path: &Path) -> bool {
+        path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .is_some_and(|ext| self.config.content_extensions.contains(&ext))
+    }
+
+    /// Search file names, paths and contents
+    ///
+    /// `query` must already be lowercased.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<FileResult> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let terms: Vec<&str> = query.split_whitespace().collect();
+        let now = SystemTime::now();
+
+        let mut results: Vec<FileResult> = self
+            .files
+            .iter()
+            .filter_map(|entry| match_file(query, &terms, entry.value()))
+            .map(|mut result| {
+                result.score *= recency_boost(result.file.modified, now);
+                result
+            })
+            .collect();
+
+        results.sort_by(|a, b| {
+            b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        results.truncate(limit);
+
+        results
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+fn match_file(query: &str, terms: &[&str], file: &IndexedFile) -> Option<FileResult> {
+    let name = &file.name_lower;
+    let stem = name.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(name);
+
+    let (score, match_type, highlights) = if name == query || stem == query {
+        (80.0, MatchType::Exact, vec![(0, query.len())])
+    } else if name.starts_with(query) {
+        (65.0, MatchType::Prefix, vec![(0, query.len())])
+    } else if let Some(pos) = name.find(query) {
+        (50.0, MatchType::Substring, vec![(pos, pos + query.len())])
+    } else if terms.iter().all(|t| file.path.to_string_lossy().to_lowercase().contains(t)) {
+        (35.0, MatchType::Substring, Vec::new())
+    } else if file.content.as_ref().is_some_and(|c| terms.iter().all(|t| c.contains(t))) {
+        (25.0, MatchType::Content, Vec::new())
+    } else {
+        return None;
+    };
+
+    Some(FileResult {
+        file: file.clone(),
+        score,
+        match_type,
+        highlights,
+    })
+}
+
+/// Recently modified files rank slightly higher, halving every 30 days
+fn recency_boost(modified: Option<SystemTime>, now: SystemTime) -> f64 {
+    let Some(age) = modified.and_then(|m| now.duration_since(m).ok()) else {
+        return 1.0;
+    };
+    let age_days = age.as_secs_f64() / 86400.0;
+
+    1.0 + 0.2 * 0.5f64.powf(age_days / 30.0)
+}
+
+/// Read the leading text of a file, skipping binaries
+fn read_text(path: &Path, max_bytes: u64) -> Option<String> {
+    let mut buf = Vec::new();
+    std::fs::File::open(path)
+        .ok()?
+        .take(max_bytes)
+        .read_to_end(&mut buf)
+        .ok()?;
+
+    if buf.contains(&0) {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&buf).to_lowercase())
+}
+
+/// Match a name against a glob with `*` and `?` wildcards
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.tmp", "notes.tmp"));
+        assert!(glob_match("*~", "draft.md~"));
+        assert!(glob_match("node_modules", "node_modules"));
+        assert!(glob_match("report-??.pdf", "report-01.pdf"));
+        assert!(!glob_match("*.tmp", "notes.md"));
+        assert!(!glob_match("target", "targets"));
+    }
+
+    #[test]
+    fn test_scan_and_search() {
+        let root = std::env::temp_dir().join(format!("summoner-files-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("projects/node_modules")).unwrap();
+        std::fs::write(root.join("projects/budget.csv"), "rent,groceries").unwrap();
+        std::fs::write(root.join("projects/notes.md"), "Quarterly planning meeting").unwrap();
+        std::fs::write(root.join("projects/node_modules/budget.js"), "").unwrap();
+        std::fs::write(root.join(".hidden-budget"), "").unwrap();
+
+        let index = FileIndex::new(FileSearchConfig {
+            roots: vec![root.clone()],
+            ..FileSearchConfig::default()
+        });
+        assert_eq!(index.scan(), 2);
+
+        let results = index.search("budget", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].match_type, MatchType::Exact);
+
+        let results = index.search("planning", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].match_type, MatchType::Content);
+
+        std::fs::remove_file(root.join("projects/notes.md")).unwrap();
+        index.update(&root.join("projects/notes.md"));
+        assert!(index.search("planning", 10).is_empty());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,9 +1,12 @@
 //! IPC server for Summoner
 
 use crate::actions::Launcher;
-use crate::index::AppIndex;
+use crate::files::FileIndex;
+use crate::index::{AppIndex, IndexedApp};
+use crate::mimeapps::MimeApps;
+use crate::providers::{ProviderHit, ProviderRegistry};
 use crate::recent::RecentApps;
-use crate::search::SearchEngine;
+use crate::search::{SearchEngine, SearchHit};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -21,9 +24,18 @@ pub enum IpcRequest {
     SearchWithOptions { query: String, max_results: Option<usize>, include_hidden: Option<bool> },
 
     // Launch
-    Launch { app_id: String, files: Option<Vec<String>> },
+    /// `query` is the search that led to the launch, for ranking feedback
+    Launch { app_id: String, files: Option<Vec<String>>, query: Option<String> },
     LaunchAction { app_id: String, action_id: String, files: Option<Vec<String>> },
     QuickLaunch { command: String },
+    OpenFile { path: String, query: Option<String> },
+
+    // External providers
+    /// Turns the connection into a provider channel; see `providers`
+    RegisterProvider { name: String, prefix: Option<String>, latency_budget_ms: Option<u64> },
+    /// Hand a provider result, or one of its actions, back to its provider
+    ActivateResult { provider: String, result_id: String, action_id: Option<String>, query: Option<String> },
+    ListProviders,
 
     // Index
     GetApp { app_id: String },
@@ -31,7 +43,16 @@ pub enum IpcRequest {
     ListCategories,
     RefreshIndex,
 
+    // Default applications
+    GetDefaultApp { mime: String },
+    /// `app_id: None` drops the user's choice for the type
+    SetDefaultApp { mime: String, app_id: Option<String> },
+    ListHandlers { mime: String },
+    ListDefaults,
+    ListMimeTypes,
+
     // Recent
+    ExplainRanking { query: String },
     GetRecent { limit: Option<usize> },
     GetFrequent { limit: Option<usize> },
     ClearRecent,
@@ -52,23 +73,32 @@ pub enum IpcResponse {
 /// IPC server
 pub struct SummonerIpcServer {
     index: Arc<RwLock<AppIndex>>,
+    files: Arc<FileIndex>,
+    providers: Arc<ProviderRegistry>,
     search: Arc<SearchEngine>,
     launcher: Arc<RwLock<Launcher>>,
     recent: Arc<RwLock<RecentApps>>,
+    associations: Arc<RwLock<MimeApps>>,
 }
 
 impl SummonerIpcServer {
     pub fn new(
         index: Arc<RwLock<AppIndex>>,
+        files: Arc<FileIndex>,
+        providers: Arc<ProviderRegistry>,
         search: Arc<SearchEngine>,
         launcher: Arc<RwLock<Launcher>>,
         recent: Arc<RwLock<RecentApps>>,
+        associations: Arc<RwLock<MimeApps>>,
     ) -> Self {
         Self {
             index,
+            files,
+            providers,
             search,
             launcher,
             recent,
+            associations,
         }
     }
 
@@ -82,12 +112,15 @@ impl SummonerIpcServer {
             match listener.accept().await {
                 Ok((stream, _)) => {
                     let index = Arc::clone(&self.index);
+                    let files = Arc::clone(&self.files);
+                    let providers = Arc::clone(&self.providers);
                     let search = Arc::clone(&self.search);
                     let launcher = Arc::clone(&self.launcher);
                     let recent = Arc::clone(&self.recent);
+                    let associations = Arc::clone(&self.associations);
 
                     tokio::spawn(async move {
-                        if let Err(e) = handle_client(stream, index, search, launcher, recent).await {
+                        if let Err(e) = handle_client(stream, index, files, providers, search, launcher, recent, associations).await {
                             tracing::error!("Client error: {}", e);
                         }
                     });
@@ -103,9 +136,12 @@ impl SummonerIpcServer {
 async fn handle_client(
     stream: UnixStream,
     index: Arc<RwLock<AppIndex>>,
+    files: Arc<FileIndex>,
+    providers: Arc<ProviderRegistry>,
     search: Arc<SearchEngine>,
     launcher: Arc<RwLock<Launcher>>,
     recent: Arc<RwLock<RecentApps>>,
+    associations: Arc<RwLock<MimeApps>>,
 ) -> Result<()> {
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
@@ -113,7 +149,25 @@ async fn handle_client(
 
     while reader.read_line(&mut line).await? > 0 {
         let response = match serde_json::from_str::<IpcRequest>(&line) {
-            Ok(request) => process_request(request, &index, &search, &launcher, &recent).await,
+            Ok(IpcRequest::RegisterProvider { name, prefix, latency_budget_ms }) => {
+                match providers.register(&name, prefix, latency_budget_ms).await {
+                    Ok((outgoing, budget)) => {
+                        let response = IpcResponse::Success {
+                            data: serde_json::json!({
+                                "registered": name,
+                                "latency_budget_ms": budget.as_millis() as u64,
+                            }),
+                        };
+                        writer.write_all(serde_json::to_string(&response)?.as_bytes()).await?;
+                        writer.write_all(b"\n").await?;
+                        writer.flush().await?;
+
+                        return providers.serve(&name, outgoing, reader, writer).await;
+                    }
+                    Err(e) => IpcResponse::Error { message: e.to_string() },
+                }
+            }
+            Ok(request) => process_request(request, &index, &files, &providers, &search, &launcher, &recent, &associations).await,
             Err(e) => IpcResponse::Error {
                 message: format!("Invalid request: {}", e),
             },
@@ -133,24 +187,30 @@ async fn handle_client(
 async fn process_request(
     request: IpcRequest,
     index: &RwLock<AppIndex>,
+    files: &Arc<FileIndex>,
+    providers: &ProviderRegistry,
     search: &SearchEngine,
     launcher: &RwLock<Launcher>,
     recent: &RwLock<RecentApps>,
+    associations: &RwLock<MimeApps>,
 ) -> IpcResponse {
     match request {
         IpcRequest::Search { query } => {
             let idx = index.read().await;
-            let results = search.search(&query, &idx).await;
+            let results = search.search_all(&query, &idx, files, providers, recent).await;
 
-            let apps: Vec<_> = results.iter().map(|r| {
-                serde_json::json!({
+            let apps: Vec<_> = results.iter().map(|hit| match hit {
+                SearchHit::App(r) => serde_json::json!({
+                    "kind": "application",
                     "id": r.app.id,
                     "name": r.app.entry.name,
                     "icon": r.app.entry.icon,
                     "comment": r.app.entry.comment,
                     "score": r.score,
                     "match_type": format!("{:?}", r.match_type),
-                })
+                }),
+                SearchHit::File(r) => file_json(r),
+                SearchHit::Provider(r) => provider_json(r),
             }).collect();
 
             IpcResponse::Success {
@@ -160,21 +220,24 @@ async fn process_request(
 
         IpcRequest::SearchWithOptions { query, max_results, include_hidden: _ } => {
             let idx = index.read().await;
-            let mut results = search.search(&query, &idx).await;
+            let mut results = search.search_all(&query, &idx, files, providers, recent).await;
 
             if let Some(max) = max_results {
                 results.truncate(max);
             }
 
-            let apps: Vec<_> = results.iter().map(|r| {
-                serde_json::json!({
+            let apps: Vec<_> = results.iter().map(|hit| match hit {
+                SearchHit::App(r) => serde_json::json!({
+                    "kind": "application",
                     "id": r.app.id,
                     "name": r.app.entry.name,
                     "icon": r.app.entry.icon,
                     "exec": r.app.entry.exec,
                     "categories": r.app.entry.categories,
                     "score": r.score,
-                })
+                }),
+                SearchHit::File(r) => file_json(r),
+                SearchHit::Provider(r) => provider_json(r),
             }).collect();
 
             IpcResponse::Success {
@@ -182,7 +245,7 @@ async fn process_request(
             }
         }
 
-        IpcRequest::Launch { app_id, files } => {
+        IpcRequest::Launch { app_id, files, query } => {
             let idx = index.read().await;
 
             if let Some(app) = idx.get(&app_id).await {
@@ -191,8 +254,7 @@ async fn process_request(
 
                 match launcher_guard.launch(&app.entry, &files).await {
                     Ok(pid) => {
-                        // Record in recent
-                        recent.write().await.record(&app_id);
+                        record_launch(recent, &app_id, query.as_deref()).await;
                         idx.record_use(&app_id).await;
 
                         IpcResponse::Success {
@@ -237,6 +299,52 @@ async fn process_request(
             }
         }
 
+        IpcRequest::OpenFile { path, query } => {
+            let handler = match crate::mimeapps::mime_for(&path) {
+                Some(mime) => {
+                    let mut associations = associations.write().await;
+                    if let Err(e) = associations.refresh() {
+                        tracing::warn!("Failed to read default applications: {}", e);
+                    }
+                    handler_for(&mime, &*index.read().await, &associations).await
+                }
+                None => None,
+            };
+            let opened = match handler {
+                Some(app) => launcher.write().await.launch(&app.entry, std::slice::from_ref(&path)).await,
+                None => crate::actions::open_with_default(&path).await,
+            };
+            match opened {
+                Ok(pid) => {
+                    record_launch(recent, &path, query.as_deref()).await;
+                    IpcResponse::Success {
+                        data: serde_json::json!({ "pid": pid }),
+                    }
+                }
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::RegisterProvider { .. } => IpcResponse::Error {
+            message: "Provider registration is handled by the connection".to_string(),
+        },
+
+        IpcRequest::ActivateResult { provider, result_id, action_id, query } => {
+            match providers.activate(&provider, &result_id, action_id).await {
+                Ok(()) => {
+                    record_launch(recent, &format!("{}:{}", provider, result_id), query.as_deref()).await;
+                    IpcResponse::Success {
+                        data: serde_json::json!({ "activated": true }),
+                    }
+                }
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::ListProviders => IpcResponse::Success {
+            data: serde_json::json!({ "providers": providers.list().await }),
+        },
+
         IpcRequest::GetApp { app_id } => {
             let idx = index.read().await;
 
@@ -301,9 +409,107 @@ async fn process_request(
         }
 
         IpcRequest::RefreshIndex => {
-            // This would trigger a re-scan of desktop files
+            // This would trigger a re-scan of desktop files; for now only
+            // the file index is rebuilt
+            let files = Arc::clone(files);
+            match tokio::task::spawn_blocking(move || files.scan()).await {
+                Ok(indexed_files) => IpcResponse::Success {
+                    data: serde_json::json!({ "refreshed": true, "indexed_files": indexed_files }),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::GetDefaultApp { mime } => {
+            let mut associations = associations.write().await;
+            if let Err(e) = associations.refresh() {
+                return IpcResponse::Error { message: e.to_string() };
+            }
+            let app = handler_for(&mime, &*index.read().await, &associations).await;
             IpcResponse::Success {
-                data: serde_json::json!({ "refreshed": true }),
+                data: serde_json::json!({ "mime": mime, "app": app.as_ref().map(app_json) }),
+            }
+        }
+
+        IpcRequest::SetDefaultApp { mime, app_id } => {
+            if let Some(app_id) = &app_id {
+                if index.read().await.get(app_id).await.is_none() {
+                    return IpcResponse::Error {
+                        message: format!("App not found: {}", app_id),
+                    };
+                }
+            }
+            let mut associations = associations.write().await;
+            // Don't write back over edits made by other tools
+            let saved = associations.refresh().and_then(|()| {
+                associations.set_default(&mime, app_id.as_deref());
+                associations.save()
+            });
+            match saved {
+                Ok(()) => IpcResponse::Success {
+                    data: serde_json::json!({ "mime": mime, "app_id": app_id }),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::ListHandlers { mime } => {
+            let mut associations = associations.write().await;
+            if let Err(e) = associations.refresh() {
+                return IpcResponse::Error { message: e.to_string() };
+            }
+            let apps = handlers_for(&mime, &*index.read().await, &associations).await;
+            IpcResponse::Success {
+                data: serde_json::json!({ "apps": apps.iter().map(app_json).collect::<Vec<_>>() }),
+            }
+        }
+
+        IpcRequest::ListDefaults => {
+            let mut associations = associations.write().await;
+            if let Err(e) = associations.refresh() {
+                return IpcResponse::Error { message: e.to_string() };
+            }
+            let defaults: Vec<_> = associations.defaults().map(|(mime, app_id)| {
+                serde_json::json!({ "mime": mime, "app_id": app_id })
+            }).collect();
+            IpcResponse::Success {
+                data: serde_json::json!({ "defaults": defaults }),
+            }
+        }
+
+        IpcRequest::ListMimeTypes => {
+            let mut mime_types: std::collections::BTreeSet<String> = index.read().await.all().await
+                .into_iter()
+                .flat_map(|app| app.entry.mime_types)
+                .collect();
+            mime_types.extend(associations.read().await.defaults().map(|(mime, _)| mime.to_string()));
+            IpcResponse::Success {
+                data: serde_json::json!({ "mime_types": mime_types }),
+            }
+        }
+
+        IpcRequest::ExplainRanking { query } => {
+            let idx = index.read().await;
+            let ranked = search.rank(&query, &idx, files, providers, recent).await;
+
+            let explained: Vec<_> = ranked.iter().map(|r| {
+                serde_json::json!({
+                    "id": r.hit.id(),
+                    "kind": match r.hit {
+                        SearchHit::App(_) => "application",
+                        SearchHit::File(_) => "file",
+                        SearchHit::Provider(_) => "provider",
+                    },
+                    "base_score": r.base_score,
+                    "frecency": r.ranking.frecency,
+                    "term_affinity": r.ranking.term_affinity,
+                    "multiplier": r.ranking.multiplier,
+                    "score": r.hit.score(),
+                })
+            }).collect();
+
+            IpcResponse::Success {
+                data: serde_json::json!({ "query": query, "results": explained }),
             }
         }
 
@@ -328,7 +534,11 @@ async fn process_request(
         }
 
         IpcRequest::ClearRecent => {
-            recent.write().await.clear();
+            let mut recent_guard = recent.write().await;
+            recent_guard.clear();
+            if let Err(e) = recent_guard.save().await {
+                tracing::warn!("Failed to save recent apps: {}", e);
+            }
             IpcResponse::Success {
                 data: serde_json::json!({ "cleared": true }),
             }
@@ -343,6 +553,7 @@ async fn process_request(
                     "total_apps": idx.len().await,
                     "categories": idx.categories().await.len(),
                     "recent_count": recent_guard.len(),
+                    "indexed_files": files.len(),
                 }),
             }
         }
@@ -358,6 +569,79 @@ async fn process_request(
     }
 }
 
+/// Count a launch towards frecency and persist it
+async fn record_launch(recent: &RwLock<RecentApps>, id: &str, query: Option<&str>) {
+    let mut recent_guard = recent.write().await;
+    recent_guard.record(id, query);
+    if let Err(e) = recent_guard.save().await {
+        tracing::warn!("Failed to save recent apps: {}", e);
+    }
+}
+
+/// Apps that can open a MIME type: ones declaring it or associated with it
+/// by the user, minus ones the user dissociated, by name
+async fn handlers_for(mime: &str, index: &AppIndex, associations: &MimeApps) -> Vec<IndexedApp> {
+    let mut apps: Vec<IndexedApp> = index.all().await
+        .into_iter()
+        .filter(|app| !app.entry.hidden)
+        .filter(|app| {
+            app.entry.mime_types.iter().any(|m| m == mime)
+                || associations.added_for(mime).contains(&app.id)
+        })
+        .filter(|app| !associations.is_removed(mime, &app.id))
+        .collect();
+    apps.sort_by(|a, b| a.entry.name.cmp(&b.entry.name));
+    apps
+}
+
+/// App that opens a MIME type: the user's default if it is installed,
+/// otherwise the first capable app
+async fn handler_for(mime: &str, index: &AppIndex, associations: &MimeApps) -> Option<IndexedApp> {
+    for app_id in associations.defaults_for(mime) {
+        if let Some(app) = index.get(app_id).await {
+            return Some(app);
+        }
+    }
+    handlers_for(mime, index, associations).await.into_iter().next()
+}
+
+fn app_json(app: &IndexedApp) -> serde_json::Value {
+    serde_json::json!({
+        "id": app.id,
+        "name": app.entry.name,
+        "icon": app.entry.icon,
+    })
+}
+
+fn file_json(r: &crate::files::FileResult) -> serde_json::Value {
+    serde_json::json!({
+        "kind": "file",
+        "id": r.file.path,
+        "name": r.file.name,
+        "path": r.file.path,
+        "size": r.file.size,
+        "modified": r.file.modified
+            .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs()),
+        "score": r.score,
+        "match_type": format!("{:?}", r.match_type),
+    })
+}
+
+fn provider_json(r: &ProviderHit) -> serde_json::Value {
+    serde_json::json!({
+        "kind": "provider",
+        "id": format!("{}:{}", r.provider, r.result.id),
+        "provider": r.provider,
+        "result_id": r.result.id,
+        "name": r.result.title,
+        "comment": r.result.subtitle,
+        "icon": r.result.icon,
+        "actions": r.result.actions,
+        "score": r.result.score,
+    })
+}
+
 /// IPC client
 pub struct SummonerClient {
     socket_path: std::path::PathBuf,
@@ -390,6 +674,24 @@ impl SummonerClient {
         let response = self.send(IpcRequest::Launch {
             app_id: app_id.to_string(),
             files: None,
+            query: None,
+        }).await?;
+
+        match response {
+            IpcResponse::Success { data } => {
+                data.get("pid")
+                    .and_then(|v| v.as_u64())
+                    .map(|p| p as u32)
+                    .ok_or_else(|| anyhow::anyhow!("No PID in response"))
+            }
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+        }
+    }
+
+    pub async fn open_file(&self, path: &str) -> Result<u32> {
+        let response = self.send(IpcRequest::OpenFile {
+            path: path.to_string(),
+            query: None,
         }).await?;
 
         match response {
@@ -7,16 +7,22 @@
 //! - **Desktop Entry Parsing**: Freedesktop .desktop files
 //! - **Fuzzy Search**: Fast fuzzy matching
 //! - **AI Search**: Natural language app finding (optional)
-//! - **Recent Apps**: Track and prioritize frequently used
+//! - **Frecency Ranking**: Recent and frequent launches rank higher
 //! - **Custom Actions**: App-specific quick actions
+//! - **Search Providers**: External processes contribute results over the socket
+//! - **File Search**: Documents under configured roots, kept current via inotify
+//! - **Default Applications**: Files and URLs open with the user's choices from `mimeapps.list`
 
 mod config;
 mod index;
 mod search;
 mod desktop;
+mod files;
 mod recent;
 mod actions;
 mod ipc;
+mod providers;
+mod mimeapps;
 
 use anyhow::Result;
 use clap::Parser;
@@ -105,7 +111,35 @@ async fn main() -> Result<()> {
     let recent_path = dirs::data_dir()
         .unwrap_or_else(|| std::path::PathBuf::from("/tmp"))
         .join("summoner/recent.json");
-    let recent = Arc::new(RwLock::new(recent::RecentApps::new(config.recent.max_size, recent_path)));
+    let mut recent = recent::RecentApps::new(&config.recent, recent_path);
+    if let Err(e) = recent.load().await {
+        error!("Failed to load recent apps: {}", e);
+    }
+    let recent = Arc::new(RwLock::new(recent));
+
+    // Index files, watching before the scan so no change is missed
+    let files = Arc::new(files::FileIndex::new(config.files.clone()));
+    let _file_watcher = if config.files.enabled {
+        let watcher = if config.files.watch {
+            files.watch()
+                .map_err(|e| error!("Failed to watch file roots: {}", e))
+                .ok()
+        } else {
+            None
+        };
+
+        let scan = Arc::clone(&files);
+        tokio::task::spawn_blocking(move || {
+            let count = scan.scan();
+            info!("Indexed {} files", count);
+        });
+
+        watcher
+    } else {
+        None
+    };
+
+    let providers = Arc::new(providers::ProviderRegistry::new(config.providers.clone()));
 
     // Create search engine and launcher
     let search = Arc::new(search::SearchEngine::new(config.search.clone()));
@@ -129,8 +163,14 @@ async fn main() -> Result<()> {
         }
     });
 
+    let mut associations = mimeapps::MimeApps::new(mimeapps::MimeApps::user_path());
+    if let Err(e) = associations.refresh() {
+        error!("Failed to read default applications: {}", e);
+    }
+    let associations = Arc::new(RwLock::new(associations));
+
     // Start IPC server
-    let server = ipc::SummonerIpcServer::new(index, search, launcher, recent);
+    let server = ipc::SummonerIpcServer::new(index, files, providers, search, launcher, recent, associations);
 
     info!("Summoner ready");
     server.start(&args.socket).await
@@ -0,0 +1,289 @@
+//! Default applications
+//!
+//! Reads and writes the user's XDG `mimeapps.list`, the same file
+//! `xdg-open` and other desktops consult, so a default chosen in Settings
+//! holds everywhere. Summoner resolves files and URLs against it when
+//! opening them.
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const DEFAULTS: &str = "Default Applications";
+const ADDED: &str = "Added Associations";
+const REMOVED: &str = "Removed Associations";
+
+/// Associations from `mimeapps.list`, keyed by MIME type; app ids are
+/// desktop file stems, as in the index
+#[derive(Debug, Default)]
+pub struct MimeApps {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    defaults: BTreeMap<String, Vec<String>>,
+    added: BTreeMap<String, Vec<String>>,
+    removed: BTreeMap<String, Vec<String>>,
+}
+
+impl MimeApps {
+    /// `$XDG_CONFIG_HOME/mimeapps.list`
+    pub fn user_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join("mimeapps.list")
+    }
+
+    /// Associations in a file, not read until the first `refresh`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Re-read the file if it changed since it was last read, so edits
+    /// made by other tools apply too
+    pub fn refresh(&mut self) -> Result<()> {
+        let modified = modified(&self.path);
+        if modified.is_some() && modified == self.modified {
+            return Ok(());
+        }
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        self.modified = modified;
+        self.parse(&content);
+        Ok(())
+    }
+
+    fn parse(&mut self, content: &str) {
+        self.defaults.clear();
+        self.added.clear();
+        self.removed.clear();
+
+        let mut section = "";
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name;
+                continue;
+            }
+            let Some((mime, apps)) = line.split_once('=') else {
+                continue;
+            };
+            let target = match section {
+                DEFAULTS => &mut self.defaults,
+                ADDED => &mut self.added,
+                REMOVED => &mut self.removed,
+                _ => continue,
+            };
+            let apps: Vec<String> = apps
+                .split(';')
+                .filter_map(|app| app.trim().strip_suffix(".desktop"))
+                .map(String::from)
+                .collect();
+            if !apps.is_empty() {
+                target.insert(mime.trim().to_string(), apps);
+            }
+        }
+    }
+
+    /// Explicit defaults for a MIME type, most preferred first
+    pub fn defaults_for(&self, mime: &str) -> &[String] {
+        self.defaults.get(mime).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Every explicit default
+    pub fn defaults(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.defaults
+            .iter()
+            .filter_map(|(mime, apps)| Some((mime.as_str(), apps.first()?.as_str())))
+    }
+
+    /// Apps associated beyond what their desktop files declare
+    pub fn added_for(&self, mime: &str) -> &[String] {
+        self.added.get(mime).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Whether the user dissociated an app from a MIME type
+    pub fn is_removed(&self, mime: &str, app_id: &str) -> bool {
+        self.removed
+            .get(mime)
+            .is_some_and(|apps| apps.iter().any(|app| app == app_id))
+    }
+
+    /// Make an app the default for a MIME type, or drop the explicit default
+    pub fn set_default(&mut self, mime: &str, app_id: Option<&str>) {
+        match app_id {
+            Some(app_id) => {
+                let apps = self.defaults.entry(mime.to_string()).or_default();
+                apps.retain(|app| app != app_id);
+                apps.insert(0, app_id.to_string());
+                if let Some(removed) = self.removed.get_mut(mime) {
+                    removed.retain(|app| app != app_id);
+                    if removed.is_empty() {
+                        self.removed.remove(mime);
+                    }
+                }
+            }
+            None => {
+                self.defaults.remove(mime);
+            }
+        }
+    }
+
+    /// Write the file back
+    pub fn save(&mut self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("list.tmp");
+        std::fs::write(&tmp, self.to_string())?;
+        std::fs::rename(&tmp, &self.path)?;
+        self.modified = modified(&self.path);
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for MimeApps {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sections = [
+            (DEFAULTS, &self.defaults),
+            (ADDED, &self.added),
+            (REMOVED, &self.removed),
+        ];
+        let mut first = true;
+        for (name, entries) in sections {
+            if entries.is_empty() {
+                continue;
+            }
+            if !first {
+                writeln!(f)?;
+            }
+            first = false;
+            writeln!(f, "[{}]", name)?;
+            for (mime, apps) in entries {
+                let apps: Vec<String> = apps.iter().map(|app| format!("{}.desktop", app)).collect();
+                writeln!(f, "{}={};", mime, apps.join(";"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// MIME type of something to open: a URL's scheme handler, a directory, or
+/// a file by extension
+pub fn mime_for(target: &str) -> Option<String> {
+    if let Some((scheme, _)) = target.split_once("://") {
+        if scheme != "file" {
+            return Some(format!("x-scheme-handler/{}", scheme.to_ascii_lowercase()));
+        }
+    }
+    if let Some(address) = target.strip_prefix("mailto:") {
+        if !address.is_empty() {
+            return Some("x-scheme-handler/mailto".to_string());
+        }
+    }
+
+    let path = Path::new(target.strip_prefix("file://").unwrap_or(target));
+    if path.is_dir() {
+        return Some("inode/directory".to_string());
+    }
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let mime = match extension.as_str() {
+        "txt" | "log" => "text/plain",
+        "md" | "markdown" => "text/markdown",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "rs" => "text/rust",
+        "py" => "text/x-python",
+        "sh" => "application/x-shellscript",
+        "json" => "application/json",
+        "toml" => "application/toml",
+        "yaml" | "yml" => "application/yaml",
+        "xml" => "application/xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "odt" => "application/vnd.oasis.opendocument.text",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "ogg" | "oga" => "audio/ogg",
+        "wav" => "audio/x-wav",
+        "mp4" => "video/mp4",
+        "mkv" => "video/x-matroska",
+        "webm" => "video/webm",
+        _ => return None,
+    };
+    Some(mime.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIST: &str = "\
+[Default Applications]
+text/html=firefox.desktop;chromium.desktop;
+x-scheme-handler/http=firefox.desktop
+
+[Added Associations]
+text/plain=helix.desktop;
+
+[Removed Associations]
+text/plain=libreoffice-writer.desktop;
+";
+
+    #[test]
+    fn test_parse_and_set_default() {
+        let mut apps = MimeApps::default();
+        apps.parse(LIST);
+
+        assert_eq!(apps.defaults_for("text/html"), ["firefox", "chromium"]);
+        assert_eq!(apps.added_for("text/plain"), ["helix"]);
+        assert!(apps.is_removed("text/plain", "libreoffice-writer"));
+
+        apps.set_default("text/html", Some("chromium"));
+        apps.set_default("text/plain", Some("libreoffice-writer"));
+        apps.set_default("x-scheme-handler/http", None);
+
+        assert_eq!(apps.defaults_for("text/html"), ["chromium", "firefox"]);
+        assert!(!apps.is_removed("text/plain", "libreoffice-writer"));
+        assert!(apps.defaults_for("x-scheme-handler/http").is_empty());
+
+        let written = apps.to_string();
+        assert!(written.contains("text/html=chromium.desktop;firefox.desktop;"));
+        assert!(!written.contains("[Removed Associations]"));
+
+        let mut reread = MimeApps::default();
+        reread.parse(&written);
+        assert_eq!(reread.defaults().count(), 2);
+    }
+
+    #[test]
+    fn test_mime_for() {
+        assert_eq!(mime_for("https://nyx.dev").as_deref(), Some("x-scheme-handler/https"));
+        assert_eq!(mime_for("mailto:ops@nyx.dev").as_deref(), Some("x-scheme-handler/mailto"));
+        assert_eq!(mime_for("/home/nyx/Report.PDF").as_deref(), Some("application/pdf"));
+        assert_eq!(mime_for("file:///tmp/notes.md").as_deref(), Some("text/markdown"));
+        assert_eq!(mime_for("/tmp").as_deref(), Some("inode/directory"));
+        assert!(mime_for("/home/nyx/unknown.blob").is_none());
+    }
+}
@@ -0,0 +1,363 @@
+//! External search providers
+//!
+//! Other processes (calculators, unit converters, emoji pickers, browser
+//! history) can contribute results. A provider connects to the summoner
+//! socket and sends `RegisterProvider` as its first message; the connection
+//! then stays open. For each search summoner sends it a `Query`, and the
+//! provider streams back `Results` batches until it marks one `done`.
+//! Batches that miss the provider's latency budget are dropped, and the
+//! provider is sent a `Cancel`. When the user picks a provider result (or
+//! one of its actions) the provider is sent an `Activate` and handles it
+//! itself.
+
+use crate::config::ProviderConfig;
+use anyhow::{anyhow, Result};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::sync::{mpsc, RwLock};
+use tokio::time::Instant;
+
+/// A result contributed by a provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderResult {
+    /// Identifier, unique within the provider
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub subtitle: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Relevance on the same 0-100 scale as applications
+    #[serde(default)]
+    pub score: f64,
+    /// Extra actions besides the default activation
+    #[serde(default)]
+    pub actions: Vec<ProviderAction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderAction {
+    pub id: String,
+    pub label: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProviderHit {
+    pub provider: String,
+    pub result: ProviderResult,
+}
+
+/// Messages summoner sends to a provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum ProviderMessage {
+    Query { query_id: u64, query: String },
+    Cancel { query_id: u64 },
+    Activate { result_id: String, action_id: Option<String> },
+}
+
+/// Messages a provider sends to summoner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum ProviderReply {
+    Results {
+        query_id: u64,
+        results: Vec<ProviderResult>,
+        #[serde(default)]
+        done: bool,
+    },
+}
+
+struct Provider {
+    prefix: Option<String>,
+    latency_budget: Duration,
+    outgoing: mpsc::Sender<ProviderMessage>,
+}
+
+struct Batch {
+    provider: String,
+    results: Vec<ProviderResult>,
+    done: bool,
+}
+
+/// Registered providers and their in-flight queries
+pub struct ProviderRegistry {
+    config: ProviderConfig,
+    providers: RwLock<HashMap<String, Provider>>,
+    pending: DashMap<u64, mpsc::Sender<Batch>>,
+    next_query_id: AtomicU64,
+}
+
+impl ProviderRegistry {
+    pub fn new(config: ProviderConfig) -> Self {
+        Self {
+            config,
+            providers: RwLock::new(HashMap::new()),
+            pending: DashMap::new(),
+            next_query_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Serve a registered provider's connection until it closes
+    pub async fn serve(
+        &self,
+        name: &str,
+        mut outgoing: mpsc::Receiver<ProviderMessage>,
+        mut reader: BufReader<OwnedReadHalf>,
+        mut writer: OwnedWriteHalf,
+    ) -> Result<()> {
+        tracing::info!("Search provider {} registered", name);
+
+        let mut line = String::new();
+        let result = loop {
+            tokio::select! {
+                message = outgoing.recv() => {
+                    let Some(message) = message else { break Ok(()) };
+                    let json = serde_json::to_string(&message)?;
+                    if let Err(e) = async {
+                        writer.write_all(json.as_bytes()).await?;
+                        writer.write_all(b"\n").await?;
+                        writer.flush().await
+                    }.await {
+                        break Err(e.into());
+                    }
+                }
+                read = reader.read_line(&mut line) => {
+                    match read {
+                        Ok(0) => break Ok(()),
+                        Ok(_) => {
+                            match serde_json::from_str::<ProviderReply>(&line) {
+                                Ok(reply) => self.route(name, reply),
+                                Err(e) => tracing::warn!("Invalid reply from provider {}: {}", name, e),
+                            }
+                            line.clear();
+                        }
+                        Err(e) => break Err(e.into()),
+                    }
+                }
+            }
+        };
+
+        self.providers.write().await.remove(name);
+        tracing::info!("Search provider {} disconnected", name);
+        result
+    }
+
+    /// Register a provider
+    ///
+    /// `prefix` restricts the provider to searches starting with it, which
+    /// is stripped (e.g. `=` for a calculator). Returns the channel of
+    /// messages for the provider and its effective latency budget.
+    pub async fn register(
+        &self,
+        name: &str,
+        prefix: Option<String>,
+        latency_budget_ms: Option<u64>,
+    ) -> Result<(mpsc::Receiver<ProviderMessage>, Duration)> {
+        if !self.config.enabled {
+            return Err(anyhow!("External providers are disabled"));
+        }
+
+        let mut providers = self.providers.write().await;
+        if providers.contains_key(name) {
+            return Err(anyhow!("Provider {} is already registered", name));
+        }
+
+        let budget_ms = latency_budget_ms
+            .unwrap_or(self.config.default_latency_ms)
+            .min(self.config.max_latency_ms);
+        let latency_budget = Duration::from_millis(budget_ms);
+        let (outgoing, rx) = mpsc::channel(32);
+
+        providers.insert(
+            name.to_string(),
+            Provider {
+                prefix: prefix.filter(|p| !p.is_empty()),
+                latency_budget,
+                outgoing,
+            },
+        );
+
+        Ok((rx, latency_budget))
+    }
+
+    fn route(&self, provider: &str, reply: ProviderReply) {
+        let ProviderReply::Results { query_id, mut results, done } = reply;
+
+        // Late replies arrive after the query has been dropped
+        let Some(sender) = self.pending.get(&query_id) else {
+            return;
+        };
+
+        for result in &mut results {
+            result.score = result.score.clamp(0.0, 100.0);
+        }
+        let _ = sender.try_send(Batch {
+            provider: provider.to_string(),
+            results,
+            done,
+        });
+    }
+
+    /// Ask every interested provider for results, waiting no longer than
+    /// each one's latency budget
+    pub async fn query(&self, query: &str) -> Vec<ProviderHit> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let query_id = self.next_query_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, mut rx) = mpsc::channel(64);
+        self.pending.insert(query_id, tx);
+
+        let start = Instant::now();
+        let mut waiting: HashMap<String, (Duration, mpsc::Sender<ProviderMessage>)> = HashMap::new();
+
+        for (name, provider) in self.providers.read().await.iter() {
+            let provider_query = match &provider.prefix {
+                Some(prefix) => match query.strip_prefix(prefix.as_str()) {
+                    Some(rest) => rest.trim_start(),
+                    None => continue,
+                },
+                None => query,
+            };
+
+            let message = ProviderMessage::Query {
+                query_id,
+                query: provider_query.to_string(),
+            };
+            if provider.outgoing.try_send(message).is_ok() {
+                waiting.insert(name.clone(), (provider.latency_budget, provider.outgoing.clone()));
+            } else {
+                tracing::debug!("Provider {} is backed up, skipping", name);
+            }
+        }
+
+        let deadline = start + waiting.values().map(|(budget, _)| *budget).max().unwrap_or_default();
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        let mut hits = Vec::new();
+
+        while !waiting.is_empty() {
+            let Ok(Some(batch)) = tokio::time::timeout_at(deadline, rx.recv()).await else {
+                break;
+            };
+            let Some((budget, _)) = waiting.get(&batch.provider) else {
+                continue;
+            };
+
+            let late = start.elapsed() > *budget;
+            if !late {
+                let count = counts.entry(batch.provider.clone()).or_default();
+                for result in batch.results {
+                    if *count >= self.config.max_results_per_provider {
+                        break;
+                    }
+                    *count += 1;
+                    hits.push(ProviderHit {
+                        provider: batch.provider.clone(),
+                        result,
+                    });
+                }
+            }
+            if late || batch.done {
+                if let Some((_, outgoing)) = waiting.remove(&batch.provider) {
+                    if late {
+                        let _ = outgoing.try_send(ProviderMessage::Cancel { query_id });
+                    }
+                }
+            }
+        }
+
+        self.pending.remove(&query_id);
+        for (name, (_, outgoing)) in waiting {
+            tracing::debug!("Provider {} missed its latency budget", name);
+            let _ = outgoing.try_send(ProviderMessage::Cancel { query_id });
+        }
+
+        hits
+    }
+
+    /// Hand an activated result back to the provider that produced it
+    pub async fn activate(&self, provider: &str, result_id: &str, action_id: Option<String>) -> Result<()> {
+        let providers = self.providers.read().await;
+        let provider = providers
+            .get(provider)
+            .ok_or_else(|| anyhow!("Provider not registered: {}", provider))?;
+
+        provider
+            .outgoing
+            .send(ProviderMessage::Activate {
+                result_id: result_id.to_string(),
+                action_id,
+            })
+            .await
+            .map_err(|_| anyhow!("Provider disconnected"))
+    }
+
+    /// Registered provider names with their prefixes and budgets
+    pub async fn list(&self) -> Vec<serde_json::Value> {
+        let providers = self.providers.read().await;
+        let mut list: Vec<_> = providers
+            .iter()
+            .map(|(name, p)| {
+                serde_json::json!({
+                    "name": name,
+                    "prefix": p.prefix,
+                    "latency_budget_ms": p.latency_budget.as_millis() as u64,
+                })
+            })
+            .collect();
+        list.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_query_respects_budget() {
+        let registry = std::sync::Arc::new(ProviderRegistry::new(ProviderConfig::default()));
+
+        let (mut calc, _) = registry.register("calc", Some("=".into()), Some(50)).await.unwrap();
+        let (mut slow, _) = registry.register("slow", None, Some(20)).await.unwrap();
+        assert!(registry.register("calc", None, None).await.is_err());
+
+        let responder = std::sync::Arc::clone(&registry);
+        tokio::spawn(async move {
+            while let Some(ProviderMessage::Query { query_id, query }) = calc.recv().await {
+                responder.route(
+                    "calc",
+                    ProviderReply::Results {
+                        query_id,
+                        results: vec![ProviderResult {
+                            id: query.clone(),
+                            title: query,
+                            subtitle: None,
+                            icon: None,
+                            score: 150.0,
+                            actions: Vec::new(),
+                        }],
+                        done: true,
+                    },
+                );
+            }
+        });
+
+        let hits = registry.query("= 2+2").await;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].provider, "calc");
+        assert_eq!(hits[0].result.title, "2+2");
+        assert_eq!(hits[0].result.score, 100.0);
+
+        // "slow" never answers, so it is cancelled once its budget passes
+        assert!(matches!(slow.recv().await, Some(ProviderMessage::Query { .. })));
+        assert!(matches!(slow.recv().await, Some(ProviderMessage::Cancel { .. })));
+    }
+}
@@ -1,16 +1,29 @@
 //! Recently used applications tracking
+//!
+//! Each launch adds one to an entry's frecency score, which halves every
+//! `half_life_days` without use, so frequently *and* recently used results
+//! float to the top. The words of the query that led to a launch are kept
+//! the same way, so typing them again favours the result picked last time.
 
+use crate::config::RecentConfig;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
 use std::time::SystemTime;
 
+/// Query terms remembered per entry
+const MAX_TERMS: usize = 32;
+
 /// Recent applications manager
 pub struct RecentApps {
+    enabled: bool,
     entries: VecDeque<RecentEntry>,
     max_size: usize,
     file_path: PathBuf,
+    half_life_secs: f64,
+    frecency_weight: f64,
+    term_weight: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,14 +31,32 @@ pub struct RecentEntry {
     pub app_id: String,
     pub timestamp: u64,
     pub count: u64,
+    /// Decayed launch count as of `timestamp`
+    #[serde(default)]
+    pub frecency: f64,
+    /// Decayed launches per query term as of `timestamp`
+    #[serde(default)]
+    pub terms: HashMap<String, f64>,
+}
+
+/// Breakdown of how frecency adjusted a result's score
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Ranking {
+    pub frecency: f64,
+    pub term_affinity: f64,
+    pub multiplier: f64,
 }
 
 impl RecentApps {
-    pub fn new(max_size: usize, file_path: PathBuf) -> Self {
+    pub fn new(config: &RecentConfig, file_path: PathBuf) -> Self {
         Self {
+            enabled: config.enabled,
             entries: VecDeque::new(),
-            max_size,
+            max_size: config.max_size,
             file_path,
+            half_life_secs: config.half_life_days * 86400.0,
+            frecency_weight: if config.boost_recent { config.frecency_weight } else { 0.0 },
+            term_weight: if config.boost_recent { config.term_weight } else { 0.0 },
         }
     }
 
@@ -34,7 +65,16 @@ impl RecentApps {
         if self.file_path.exists() {
             let content = tokio::fs::read_to_string(&self.file_path).await?;
             let entries: Vec<RecentEntry> = serde_json::from_str(&content)?;
-            self.entries = entries.into_iter().collect();
+            self.entries = entries
+                .into_iter()
+                .map(|mut e| {
+                    // Stores written before frecency tracking only have counts
+                    if e.frecency == 0.0 {
+                        e.frecency = e.count as f64;
+                    }
+                    e
+                })
+                .collect();
         }
         Ok(())
     }
@@ -51,36 +91,117 @@ impl RecentApps {
         Ok(())
     }
 
-    /// Record an application use
-    pub fn record(&mut self, app_id: &str) {
-        let now = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or(0);
-
-        // Check if already in recent list
-        if let Some(pos) = self.entries.iter().position(|e| e.app_id == app_id) {
-            // Move to front and update
-            if let Some(mut entry) = self.entries.remove(pos) {
-                entry.timestamp = now;
-                entry.count += 1;
-                self.entries.push_front(entry);
-            }
-        } else {
-            // Add new entry
-            self.entries.push_front(RecentEntry {
+    /// Record an application use, with the query that found it if any
+    pub fn record(&mut self, app_id: &str, query: Option<&str>) {
+        if !self.enabled {
+            return;
+        }
+        let now = now_secs();
+
+        let mut entry = match self.entries.iter().position(|e| e.app_id == app_id) {
+            Some(pos) => self.entries.remove(pos).expect("position is in range"),
+            None => RecentEntry {
                 app_id: app_id.to_string(),
                 timestamp: now,
-                count: 1,
-            });
+                count: 0,
+                frecency: 0.0,
+                terms: HashMap::new(),
+            },
+        };
 
-            // Trim to max size
-            while self.entries.len() > self.max_size {
-                self.entries.pop_back();
-            }
+        // Bring decayed values forward to now, then count this launch
+        let decay = self.decay(entry.timestamp, now);
+        entry.frecency = entry.frecency * decay + 1.0;
+        for weight in entry.terms.values_mut() {
+            *weight *= decay;
+        }
+        for term in query_terms(query.unwrap_or_default()) {
+            *entry.terms.entry(term).or_default() += 1.0;
+        }
+        if entry.terms.len() > MAX_TERMS {
+            let mut weights: Vec<f64> = entry.terms.values().copied().collect();
+            weights.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+            let cutoff = weights[MAX_TERMS - 1];
+            entry.terms.retain(|_, w| *w >= cutoff);
+        }
+        entry.timestamp = now;
+        entry.count += 1;
+        self.entries.push_front(entry);
+
+        // Trim to max size, dropping the lowest frecency first
+        while self.entries.len() > self.max_size {
+            let lowest = self
+                .entries
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| {
+                    self.frecency_of(a, now)
+                        .partial_cmp(&self.frecency_of(b, now))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .map(|(i, _)| i);
+            match lowest {
+                Some(i) => self.entries.remove(i),
+                None => break,
+            };
         }
     }
 
+    /// Score adjustment for a result with `id` found by `query`
+    pub fn ranking(&self, id: &str, query: &str) -> Ranking {
+        let now = now_secs();
+        let Some(entry) = self.entries.iter().find(|e| e.app_id == id) else {
+            return Ranking {
+                frecency: 0.0,
+                term_affinity: 0.0,
+                multiplier: 1.0,
+            };
+        };
+
+        let frecency = self.frecency_of(entry, now);
+        let decay = self.decay(entry.timestamp, now);
+
+        // For each query term, the strongest remembered term it is a prefix
+        // of (or that is a prefix of it), squashed into 0..1 and averaged
+        let terms = query_terms(query);
+        let term_affinity = if terms.is_empty() {
+            0.0
+        } else {
+            let total: f64 = terms
+                .iter()
+                .map(|q| {
+                    let weight = entry
+                        .terms
+                        .iter()
+                        .filter(|(t, _)| t.starts_with(q.as_str()) || q.starts_with(t.as_str()))
+                        .map(|(_, w)| w * decay)
+                        .fold(0.0, f64::max);
+                    weight / (1.0 + weight)
+                })
+                .sum();
+            total / terms.len() as f64
+        };
+
+        Ranking {
+            frecency,
+            term_affinity,
+            multiplier: 1.0
+                + self.frecency_weight * frecency.ln_1p()
+                + self.term_weight * term_affinity,
+        }
+    }
+
+    fn frecency_of(&self, entry: &RecentEntry, now: u64) -> f64 {
+        entry.frecency * self.decay(entry.timestamp, now)
+    }
+
+    fn decay(&self, since: u64, now: u64) -> f64 {
+        if self.half_life_secs <= 0.0 {
+            return 1.0;
+        }
+        0.5f64.powf(now.saturating_sub(since) as f64 / self.half_life_secs)
+    }
+
     /// Get recent app IDs in order
     pub fn get_recent(&self) -> Vec<&str> {
         self.entries.iter().map(|e| e.app_id.as_str()).collect()
@@ -91,11 +212,16 @@ impl RecentApps {
         self.entries.iter().map(|e| (e.app_id.as_str(), e.count)).collect()
     }
 
-    /// Get most frequently used
+    /// Get highest frecency first
     pub fn get_frequent(&self, limit: usize) -> Vec<&str> {
-        let mut sorted: Vec<_> = self.entries.iter().collect();
-        sorted.sort_by(|a, b| b.count.cmp(&a.count));
-        sorted.into_iter().take(limit).map(|e| e.app_id.as_str()).collect()
+        let now = now_secs();
+        let mut sorted: Vec<_> = self
+            .entries
+            .iter()
+            .map(|e| (e, self.frecency_of(e, now)))
+            .collect();
+        sorted.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        sorted.into_iter().take(limit).map(|(e, _)| e.app_id.as_str()).collect()
     }
 
     /// Check if app is in recent list
@@ -132,70 +258,15 @@ impl RecentApps {
     }
 }
 
-/// Frecency scoring (frequency + recency)
-pub struct FrecencyScorer {
-    decay_factor: f64,
-    frequency_weight: f64,
-    recency_weight: f64,
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
 }
 
-impl FrecencyScorer {
-    pub fn new() -> Self {
-        Self {
-            decay_factor: 0.95,
-            frequency_weight: 0.3,
-            recency_weight: 0.7,
-        }
-    }
-
-    pub fn with_weights(frequency: f64, recency: f64) -> Self {
-        Self {
-            decay_factor: 0.95,
-            frequency_weight: frequency,
-            recency_weight: recency,
-        }
-    }
-
-    /// Calculate frecency score
-    pub fn score(&self, entry: &RecentEntry, max_count: u64, now: u64) -> f64 {
-        // Frequency component (normalized)
-        let freq_score = if max_count > 0 {
-            entry.count as f64 / max_count as f64
-        } else {
-            0.0
-        };
-
-        // Recency component (exponential decay)
-        let age_hours = (now.saturating_sub(entry.timestamp)) as f64 / 3600.0;
-        let recency_score = self.decay_factor.powf(age_hours / 24.0);
-
-        // Combined score
-        self.frequency_weight * freq_score + self.recency_weight * recency_score
-    }
-
-    /// Rank entries by frecency
-    pub fn rank<'a>(&self, entries: &'a [RecentEntry]) -> Vec<(&'a RecentEntry, f64)> {
-        let now = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or(0);
-
-        let max_count = entries.iter().map(|e| e.count).max().unwrap_or(1);
-
-        let mut scored: Vec<_> = entries
-            .iter()
-            .map(|e| (e, self.score(e, max_count, now)))
-            .collect();
-
-        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-        scored
-    }
-}
-
-impl Default for FrecencyScorer {
-    fn default() -> Self {
-        Self::new()
-    }
+fn query_terms(query: &str) -> Vec<String> {
+    query.split_whitespace().map(|t| t.to_lowercase()).collect()
 }
 
 /// Usage pattern analyzer
@@ -279,3 +350,40 @@ impl Default for UsagePatterns {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recent() -> RecentApps {
+        RecentApps::new(&RecentConfig::default(), PathBuf::from("/nonexistent/recent.json"))
+    }
+
+    #[test]
+    fn test_frecency_decay() {
+        let recent = recent();
+        let now = now_secs();
+        let entry = RecentEntry {
+            app_id: "firefox".into(),
+            timestamp: now - 14 * 86400,
+            count: 4,
+            frecency: 4.0,
+            terms: HashMap::new(),
+        };
+        assert!((recent.frecency_of(&entry, now) - 2.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_term_feedback() {
+        let mut recent = recent();
+        recent.record("firefox", Some("web"));
+        recent.record("chromium", None);
+
+        let firefox = recent.ranking("firefox", "we");
+        let chromium = recent.ranking("chromium", "we");
+        assert!(firefox.term_affinity > 0.0);
+        assert_eq!(chromium.term_affinity, 0.0);
+        assert!(firefox.multiplier > chromium.multiplier);
+        assert_eq!(recent.ranking("gimp", "we").multiplier, 1.0);
+    }
+}
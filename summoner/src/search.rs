@@ -1,7 +1,11 @@
 //! Fuzzy search implementation
 
 use crate::config::SearchConfig;
+use crate::files::{FileIndex, FileResult};
 use crate::index::{AppIndex, IndexedApp};
+use crate::providers::{ProviderHit, ProviderRegistry};
+use crate::recent::{RecentApps, Ranking};
+use tokio::sync::RwLock;
 use anyhow::Result;
 
 /// Search engine with fuzzy matching
@@ -24,6 +28,49 @@ pub enum MatchType {
     Substring,
     Fuzzy,
     Keyword,
+    Content,
+}
+
+/// A ranked result from any provider
+#[derive(Debug, Clone)]
+pub enum SearchHit {
+    App(Box<SearchResult>),
+    File(FileResult),
+    Provider(ProviderHit),
+}
+
+impl SearchHit {
+    /// Identifier launches are recorded under
+    pub fn id(&self) -> String {
+        match self {
+            SearchHit::App(r) => r.app.id.clone(),
+            SearchHit::File(r) => r.file.path.to_string_lossy().to_string(),
+            SearchHit::Provider(r) => format!("{}:{}", r.provider, r.result.id),
+        }
+    }
+
+    pub fn score(&self) -> f64 {
+        match self {
+            SearchHit::App(r) => r.score,
+            SearchHit::File(r) => r.score,
+            SearchHit::Provider(r) => r.result.score,
+        }
+    }
+
+    fn score_mut(&mut self) -> &mut f64 {
+        match self {
+            SearchHit::App(r) => &mut r.score,
+            SearchHit::File(r) => &mut r.score,
+            SearchHit::Provider(r) => &mut r.result.score,
+        }
+    }
+}
+
+/// How frecency turns a provider's score into the final one
+pub struct RankedHit {
+    pub hit: SearchHit,
+    pub base_score: f64,
+    pub ranking: Ranking,
 }
 
 impl SearchEngine {
@@ -33,6 +80,20 @@ impl SearchEngine {
 
     /// Search for applications
     pub async fn search(&self, query: &str, index: &AppIndex) -> Vec<SearchResult> {
+        let mut results = self.match_apps(query, index).await;
+
+        // Sort by score
+        results.sort_by(|a, b| {
+            b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        // Apply result limit
+        results.truncate(self.config.max_results);
+
+        results
+    }
+
+    async fn match_apps(&self, query: &str, index: &AppIndex) -> Vec<SearchResult> {
         if query.is_empty() {
             return Vec::new();
         }
@@ -47,15 +108,70 @@ impl SearchEngine {
             }
         }
 
-        // Sort by score
-        results.sort_by(|a, b| {
-            b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal)
-        });
+        results
+    }
 
-        // Apply result limit
-        results.truncate(self.config.max_results);
+    /// Search applications, files and external providers, merged into
+    /// one ranking
+    pub async fn search_all(
+        &self,
+        query: &str,
+        index: &AppIndex,
+        files: &FileIndex,
+        providers: &ProviderRegistry,
+        recent: &RwLock<RecentApps>,
+    ) -> Vec<SearchHit> {
+        self.rank(query, index, files, providers, recent)
+            .await
+            .into_iter()
+            .map(|ranked| ranked.hit)
+            .collect()
+    }
 
-        results
+    /// Like `search_all`, keeping each result's ranking breakdown
+    pub async fn rank(
+        &self,
+        query: &str,
+        index: &AppIndex,
+        files: &FileIndex,
+        providers: &ProviderRegistry,
+        recent: &RwLock<RecentApps>,
+    ) -> Vec<RankedHit> {
+        // Providers answer concurrently while local results are gathered
+        let (local, external) = tokio::join!(self.match_apps(query, index), providers.query(query));
+
+        let mut hits: Vec<SearchHit> = local
+            .into_iter()
+            .map(|r| SearchHit::App(Box::new(r)))
+            .collect();
+
+        // Frecency can lift a file past ones that matched better, so take
+        // more candidates than will be returned
+        hits.extend(
+            files
+                .search(&query.to_lowercase(), self.config.max_results * 4)
+                .into_iter()
+                .map(SearchHit::File),
+        );
+        hits.extend(external.into_iter().map(SearchHit::Provider));
+
+        let recent = recent.read().await;
+        let mut ranked: Vec<RankedHit> = hits
+            .into_iter()
+            .map(|mut hit| {
+                let base_score = hit.score();
+                let ranking = recent.ranking(&hit.id(), query);
+                *hit.score_mut() *= ranking.multiplier;
+                RankedHit { hit, base_score, ranking }
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| {
+            b.hit.score().partial_cmp(&a.hit.score()).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        ranked.truncate(self.config.max_results);
+
+        ranked
     }
 
     fn match_app(&self, query: &str, app: &IndexedApp) -> Option<SearchResult> {
@@ -0,0 +1,391 @@
+//! Authenticated, encrypted channel for remote sessions
+//!
+//! Both ends hold an Ed25519 identity kept in cipher. A connection starts
+//! with an X25519 exchange; the agent signs the transcript with its host
+//! key, which the client pins in `known_hosts` on first contact, and the
+//! client then proves its own key, which must be listed in the agent's
+//! `authorized_keys`. Everything after the handshake is sealed with
+//! ChaCha20-Poly1305 under per-direction keys.
+//!
+//! Frames are a big-endian `u32` length followed by the payload.
+
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use libnyx_ipc::SecretsClient;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::agreement::{self, EphemeralPrivateKey, X25519};
+use ring::digest::{digest, Context as Digest, SHA256};
+use ring::hkdf::{Salt, HKDF_SHA256};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Wire protocol version
+const PROTOCOL_VERSION: u32 = 1;
+
+/// Largest frame either end accepts
+const MAX_FRAME: usize = 1 << 20;
+
+/// Domain separator for handshake signatures
+const HANDSHAKE_CONTEXT: &[u8] = b"umbra-remote-v1";
+
+/// Cipher collection and entry holding the identity key
+const KEY_COLLECTION: &str = "umbra";
+const KEY_ID: &str = "identity";
+
+/// This user's Umbra key
+pub struct Identity {
+    key: Ed25519KeyPair,
+}
+
+impl Identity {
+    /// Fetch the identity from cipher, generating it on first use
+    pub async fn load(secrets: &SecretsClient) -> Result<Self> {
+        let stored = secrets
+            .lookup(KEY_COLLECTION, KEY_ID)
+            .await
+            .context("Umbra keys are kept in cipher, which is not reachable")?;
+
+        let pkcs8 = match stored {
+            Some(hex_key) => hex::decode(hex_key.trim())?,
+            None => {
+                let document = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                    .map_err(|_| anyhow!("key generation failed"))?;
+                secrets
+                    .store(KEY_COLLECTION, KEY_ID, "Umbra remote shell key", &hex::encode(document.as_ref()))
+                    .await?;
+                document.as_ref().to_vec()
+            }
+        };
+
+        let key = Ed25519KeyPair::from_pkcs8(&pkcs8).map_err(|e| anyhow!("invalid identity key: {}", e))?;
+        Ok(Self { key })
+    }
+
+    /// Public key, hex, as it appears in `authorized_keys`
+    pub fn public_key(&self) -> String {
+        hex::encode(self.key.public_key().as_ref())
+    }
+}
+
+/// Short fingerprint of a public key for display
+pub fn fingerprint(public_key: &[u8]) -> String {
+    hex::encode(&digest(&SHA256, public_key).as_ref()[..8])
+}
+
+#[derive(Serialize, Deserialize)]
+struct ClientHello {
+    version: u32,
+    ephemeral: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ServerHello {
+    version: u32,
+    ephemeral: String,
+    host_key: String,
+    signature: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ClientAuth {
+    user_key: String,
+    signature: String,
+}
+
+/// Keys and counter for one direction
+struct Direction {
+    key: LessSafeKey,
+    counter: u64,
+}
+
+impl Direction {
+    fn nonce(&mut self) -> Result<Nonce> {
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce[4..].copy_from_slice(&self.counter.to_be_bytes());
+        self.counter = self.counter.checked_add(1).ok_or_else(|| anyhow!("channel exhausted"))?;
+        Ok(Nonce::assume_unique_for_key(nonce))
+    }
+}
+
+/// An established channel
+pub struct Channel {
+    stream: TcpStream,
+    send: Direction,
+    recv: Direction,
+    /// The other end's public key
+    peer_key: Vec<u8>,
+}
+
+impl Channel {
+    /// Open a channel to an agent, pinning its host key under `host`
+    pub async fn connect(mut stream: TcpStream, identity: &Identity, host: &str, known_hosts: &Path) -> Result<Self> {
+        let rng = SystemRandom::new();
+        let ephemeral = EphemeralPrivateKey::generate(&X25519, &rng).map_err(|_| anyhow!("key generation failed"))?;
+        let client_ephemeral = ephemeral.compute_public_key().map_err(|_| anyhow!("key generation failed"))?;
+        write_json(&mut stream, &ClientHello {
+            version: PROTOCOL_VERSION,
+            ephemeral: hex::encode(client_ephemeral.as_ref()),
+        })
+        .await?;
+
+        let hello: ServerHello = read_json(&mut stream).await?;
+        if hello.version != PROTOCOL_VERSION {
+            bail!("agent speaks protocol {}, expected {}", hello.version, PROTOCOL_VERSION);
+        }
+        let server_ephemeral = hex::decode(&hello.ephemeral)?;
+        let host_key = hex::decode(&hello.host_key)?;
+        let transcript = transcript(client_ephemeral.as_ref(), &server_ephemeral, &host_key);
+        UnparsedPublicKey::new(&ED25519, &host_key)
+            .verify(&signed(b"server", &transcript), &hex::decode(&hello.signature)?)
+            .map_err(|_| anyhow!("agent's handshake signature does not verify"))?;
+        check_known_host(known_hosts, host, &host_key)?;
+
+        let (c2s, s2c) = session_keys(ephemeral, &server_ephemeral, &transcript)?;
+        let mut channel = Self {
+            stream,
+            send: Direction { key: c2s, counter: 0 },
+            recv: Direction { key: s2c, counter: 0 },
+            peer_key: host_key,
+        };
+        channel
+            .send(&ClientAuth {
+                user_key: identity.public_key(),
+                signature: hex::encode(identity.key.sign(&signed(b"client", &transcript)).as_ref()),
+            })
+            .await?;
+        Ok(channel)
+    }
+
+    /// Accept a client, admitting only keys listed in `authorized_keys`
+    pub async fn accept(mut stream: TcpStream, identity: &Identity, authorized_keys: &Path) -> Result<Self> {
+        let hello: ClientHello = read_json(&mut stream).await?;
+        if hello.version != PROTOCOL_VERSION {
+            bail!("client speaks protocol {}, expected {}", hello.version, PROTOCOL_VERSION);
+        }
+        let client_ephemeral = hex::decode(&hello.ephemeral)?;
+
+        let rng = SystemRandom::new();
+        let ephemeral = EphemeralPrivateKey::generate(&X25519, &rng).map_err(|_| anyhow!("key generation failed"))?;
+        let server_ephemeral = ephemeral.compute_public_key().map_err(|_| anyhow!("key generation failed"))?;
+        let host_key = identity.key.public_key().as_ref().to_vec();
+        let transcript = transcript(&client_ephemeral, server_ephemeral.as_ref(), &host_key);
+        write_json(&mut stream, &ServerHello {
+            version: PROTOCOL_VERSION,
+            ephemeral: hex::encode(server_ephemeral.as_ref()),
+            host_key: hex::encode(&host_key),
+            signature: hex::encode(identity.key.sign(&signed(b"server", &transcript)).as_ref()),
+        })
+        .await?;
+
+        let (c2s, s2c) = session_keys(ephemeral, &client_ephemeral, &transcript)?;
+        let mut channel = Self {
+            stream,
+            send: Direction { key: s2c, counter: 0 },
+            recv: Direction { key: c2s, counter: 0 },
+            peer_key: Vec::new(),
+        };
+
+        let auth: ClientAuth = channel.recv().await?.ok_or_else(|| anyhow!("client left during handshake"))?;
+        let user_key = hex::decode(&auth.user_key)?;
+        UnparsedPublicKey::new(&ED25519, &user_key)
+            .verify(&signed(b"client", &transcript), &hex::decode(&auth.signature)?)
+            .map_err(|_| anyhow!("client's handshake signature does not verify"))?;
+        if !is_authorized(authorized_keys, &user_key) {
+            bail!("key {} is not authorized", fingerprint(&user_key));
+        }
+        channel.peer_key = user_key;
+        Ok(channel)
+    }
+
+    /// The other end's public key
+    pub fn peer_key(&self) -> &[u8] {
+        &self.peer_key
+    }
+
+    /// Seal and send a message
+    pub async fn send<T: Serialize>(&mut self, message: &T) -> Result<()> {
+        let mut payload = serde_json::to_vec(message)?;
+        let nonce = self.send.nonce()?;
+        self.send
+            .key
+            .seal_in_place_append_tag(nonce, Aad::empty(), &mut payload)
+            .map_err(|_| anyhow!("encryption failed"))?;
+        write_frame(&mut self.stream, &payload).await
+    }
+
+    /// Receive and open a message; None once the other end has gone
+    pub async fn recv<T: DeserializeOwned>(&mut self) -> Result<Option<T>> {
+        let Some(mut payload) = read_frame(&mut self.stream).await? else {
+            return Ok(None);
+        };
+        let nonce = self.recv.nonce()?;
+        let plaintext = self
+            .recv
+            .key
+            .open_in_place(nonce, Aad::empty(), &mut payload)
+            .map_err(|_| anyhow!("message failed authentication"))?;
+        Ok(Some(serde_json::from_slice(plaintext)?))
+    }
+}
+
+/// Hash of everything both ends agreed on
+fn transcript(client_ephemeral: &[u8], server_ephemeral: &[u8], host_key: &[u8]) -> Vec<u8> {
+    let mut hash = Digest::new(&SHA256);
+    hash.update(HANDSHAKE_CONTEXT);
+    hash.update(client_ephemeral);
+    hash.update(server_ephemeral);
+    hash.update(host_key);
+    hash.finish().as_ref().to_vec()
+}
+
+/// What each side signs, bound to its role
+fn signed(role: &[u8], transcript: &[u8]) -> Vec<u8> {
+    [HANDSHAKE_CONTEXT, b":", role, b":", transcript].concat()
+}
+
+/// Derive the client-to-agent and agent-to-client keys
+fn session_keys(
+    ephemeral: EphemeralPrivateKey,
+    peer_ephemeral: &[u8],
+    transcript: &[u8],
+) -> Result<(LessSafeKey, LessSafeKey)> {
+    let peer = agreement::UnparsedPublicKey::new(&X25519, peer_ephemeral);
+    agreement::agree_ephemeral(ephemeral, &peer, |shared| {
+        let prk = Salt::new(HKDF_SHA256, transcript).extract(shared);
+        let derive = |info: &'static [u8]| {
+            let info = [info];
+            prk.expand(&info, &CHACHA20_POLY1305).map(|okm| LessSafeKey::new(UnboundKey::from(okm)))
+        };
+        Ok::<_, ring::error::Unspecified>((derive(b"umbra c2s")?, derive(b"umbra s2c")?))
+    })
+    .and_then(|keys| keys)
+    .map_err(|_| anyhow!("key agreement failed"))
+}
+
+/// Pin an agent's host key on first contact; refuse if it changed
+fn check_known_host(known_hosts: &Path, host: &str, host_key: &[u8]) -> Result<()> {
+    let host_key = hex::encode(host_key);
+    let known = std::fs::read_to_string(known_hosts).unwrap_or_default();
+    for line in known.lines() {
+        let mut fields = line.split_whitespace();
+        if let (Some(name), Some(key)) = (fields.next(), fields.next()) {
+            if name == host {
+                if key != host_key {
+                    bail!(
+                        "host key for {} has changed (now {}); remove its line from {} if this is expected",
+                        host,
+                        fingerprint(&hex::decode(&host_key)?),
+                        known_hosts.display()
+                    );
+                }
+                return Ok(());
+            }
+        }
+    }
+
+    if let Some(parent) = known_hosts.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(known_hosts)?;
+    use std::io::Write;
+    writeln!(file, "{} {}", host, host_key)?;
+    eprintln!("Added {} ({}) to known hosts", host, fingerprint(&hex::decode(&host_key)?));
+    Ok(())
+}
+
+/// Whether a key is listed (hex, one per line, `#` comments)
+fn is_authorized(authorized_keys: &Path, key: &[u8]) -> bool {
+    let Ok(authorized) = std::fs::read_to_string(authorized_keys) else {
+        return false;
+    };
+    authorized
+        .lines()
+        .filter_map(|line| line.split('#').next()?.split_whitespace().next())
+        .filter_map(|listed| hex::decode(listed).ok())
+        .any(|listed| listed == key)
+}
+
+async fn write_frame(stream: &mut TcpStream, payload: &[u8]) -> Result<()> {
+    if payload.len() > MAX_FRAME {
+        bail!("frame too large");
+    }
+    stream.write_all(&(payload.len() as u32).to_be_bytes()).await?;
+    stream.write_all(payload).await?;
+    Ok(())
+}
+
+async fn read_frame(stream: &mut TcpStream) -> Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match stream.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME {
+        bail!("frame too large");
+    }
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload).await?;
+    Ok(Some(payload))
+}
+
+async fn write_json<T: Serialize>(stream: &mut TcpStream, message: &T) -> Result<()> {
+    write_frame(stream, &serde_json::to_vec(message)?).await
+}
+
+async fn read_json<T: DeserializeOwned>(stream: &mut TcpStream) -> Result<T> {
+    let payload = read_frame(stream).await?.ok_or_else(|| anyhow!("connection closed during handshake"))?;
+    Ok(serde_json::from_slice(&payload)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn identity() -> Identity {
+        let document = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Identity { key: Ed25519KeyPair::from_pkcs8(document.as_ref()).unwrap() }
+    }
+
+    #[tokio::test]
+    async fn test_handshake_and_pinning() {
+        let dir = std::env::temp_dir().join(format!("umbra-channel-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let authorized_keys = dir.join("authorized_keys");
+        let known_hosts = dir.join("known_hosts");
+
+        let (agent, client) = (identity(), identity());
+        std::fs::write(&authorized_keys, format!("# laptop\n{} laptop\n", client.public_key())).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut channel = Channel::accept(stream, &agent, &authorized_keys).await.unwrap();
+            let message: String = channel.recv().await.unwrap().unwrap();
+            channel.send(&message.to_uppercase()).await.unwrap();
+
+            // A different agent under the same name is refused
+            let (stream, _) = listener.accept().await.unwrap();
+            let _ = Channel::accept(stream, &identity(), &authorized_keys).await;
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut channel = Channel::connect(stream, &client, "agent", &known_hosts).await.unwrap();
+        channel.send(&"hello").await.unwrap();
+        assert_eq!(channel.recv::<String>().await.unwrap().unwrap(), "HELLO");
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        assert!(Channel::connect(stream, &client, "agent", &known_hosts).await.is_err());
+
+        server.await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
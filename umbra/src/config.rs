@@ -16,6 +16,8 @@ pub struct UmbraConfig {
     pub aliases: Vec<Alias>,
     #[serde(default)]
     pub environment: Vec<EnvVar>,
+    #[serde(default)]
+    pub remote: RemoteConfig,
 }
 
 impl Default for UmbraConfig {
@@ -26,6 +28,7 @@ impl Default for UmbraConfig {
             ai: AiConfig::default(),
             aliases: default_aliases(),
             environment: Vec::new(),
+            remote: RemoteConfig::default(),
         }
     }
 }
@@ -108,6 +111,44 @@ impl Default for AiConfig {
 
 fn default_persona() -> String { "shell-assistant".into() }
 
+/// Remote shell sessions (`umbra --serve` / `umbra --connect`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteConfig {
+    /// Address the agent listens on
+    #[serde(default = "default_listen")]
+    pub listen: String,
+    /// Port used when `--connect` names none
+    #[serde(default = "default_remote_port")]
+    pub port: u16,
+    /// Public keys allowed to open sessions on this agent
+    #[serde(default = "default_authorized_keys")]
+    pub authorized_keys: String,
+    /// Host keys of agents connected to before
+    #[serde(default = "default_known_hosts")]
+    pub known_hosts: String,
+    /// History entries carried into a remote session
+    #[serde(default = "default_carried_history")]
+    pub carried_history: usize,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            listen: default_listen(),
+            port: default_remote_port(),
+            authorized_keys: default_authorized_keys(),
+            known_hosts: default_known_hosts(),
+            carried_history: default_carried_history(),
+        }
+    }
+}
+
+fn default_listen() -> String { "0.0.0.0:7222".into() }
+fn default_remote_port() -> u16 { 7222 }
+fn default_authorized_keys() -> String { "~/.config/umbra/authorized_keys".into() }
+fn default_known_hosts() -> String { "~/.config/umbra/known_hosts".into() }
+fn default_carried_history() -> usize { 500 }
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Alias {
     pub name: String,
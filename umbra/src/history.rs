@@ -16,6 +16,8 @@ pub struct History {
     search_mode: bool,
     search_pattern: String,
     modified: bool,
+    /// Whether entries are written back to `file_path`
+    persist: bool,
 }
 
 impl History {
@@ -30,12 +32,33 @@ impl History {
             search_mode: false,
             search_pattern: String::new(),
             modified: false,
+            persist: true,
         };
 
         history.load()?;
         Ok(history)
     }
 
+    /// History that starts from `entries` and is never saved, e.g. one
+    /// carried into a remote session
+    pub fn in_memory(config: &HistoryConfig, entries: Vec<String>) -> Self {
+        let mut entries: VecDeque<String> = entries.into();
+        while entries.len() > config.max_size {
+            entries.pop_front();
+        }
+
+        Self {
+            position: entries.len(),
+            entries,
+            config: config.clone(),
+            file_path: PathBuf::new(),
+            search_mode: false,
+            search_pattern: String::new(),
+            modified: false,
+            persist: false,
+        }
+    }
+
     /// Load history from file
     fn load(&mut self) -> Result<()> {
         if self.file_path.exists() {
@@ -62,7 +85,7 @@ impl History {
 
     /// Save history to file
     pub fn save(&mut self) -> Result<()> {
-        if !self.modified {
+        if !self.modified || !self.persist {
             return Ok(());
         }
 
@@ -276,7 +299,7 @@ impl Drop for History {
 }
 
 /// Expand ~ and environment variables in path
-fn expand_path(path: &str) -> PathBuf {
+pub fn expand_path(path: &str) -> PathBuf {
     let expanded = if path.starts_with('~') {
         if let Some(home) = dirs::home_dir() {
             path.replacen('~', home.to_string_lossy().as_ref(), 1)
@@ -14,6 +14,7 @@
 //! - **Smart Completion**: AI-powered command suggestions
 //! - **Context Awareness**: Remembers conversation history
 //! - **Rich Output**: Syntax highlighting, tables, progress bars
+//! - **Remote Sessions**: `--connect` to an Umbra agent on another Nyx machine
 
 mod config;
 mod shell;
@@ -22,6 +23,8 @@ mod completion;
 mod history;
 mod prompt;
 mod ui;
+mod channel;
+mod remote;
 
 use anyhow::Result;
 use clap::Parser;
@@ -55,6 +58,18 @@ struct Args {
     /// Persona to use
     #[arg(short, long)]
     persona: Option<String>,
+
+    /// Run the shell on a remote Umbra agent (host or host:port)
+    #[arg(long, value_name = "HOST", conflicts_with_all = ["script", "serve"])]
+    connect: Option<String>,
+
+    /// Run as an agent accepting remote sessions
+    #[arg(long)]
+    serve: bool,
+
+    /// Address the agent listens on (overrides the config)
+    #[arg(long, value_name = "ADDR", requires = "serve")]
+    listen: Option<String>,
 }
 
 #[tokio::main]
@@ -70,6 +85,14 @@ async fn main() -> Result<()> {
     // Load configuration
     let config = config::load_config(args.config.as_deref())?;
 
+    if args.serve {
+        return remote::serve(config, args.listen).await;
+    }
+    if let Some(target) = args.connect {
+        let code = remote::connect(config, &target, args.persona, args.command).await?;
+        std::process::exit(code);
+    }
+
     // Create shell
     let mut shell = shell::Shell::new(config)?;
 
@@ -0,0 +1,204 @@
+//! Remote shell sessions
+//!
+//! `umbra --serve` runs an agent that accepts sessions over [`Channel`];
+//! `umbra --connect <host>` drives a shell on such an agent. A session is
+//! line-oriented: the client sends command lines and the agent streams
+//! back their output. The client carries its persona and recent history
+//! over, so the remote shell picks up the same conversation; commands run
+//! remotely are added to the local history as well.
+
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Result};
+use libnyx_ipc::SecretsClient;
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::channel::{fingerprint, Channel, Identity};
+use crate::config::UmbraConfig;
+use crate::history::{expand_path, History};
+use crate::shell::{Shell, ShellEvent};
+
+/// Client to agent
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    /// Start the session
+    Open {
+        persona: Option<String>,
+        /// Oldest first
+        history: Vec<String>,
+    },
+    /// Run a command line
+    Execute { line: String },
+}
+
+/// Agent to client
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AgentMessage {
+    /// Session is ready
+    Ready { host: String, cwd: PathBuf },
+    Output { text: String },
+    Error { text: String },
+    /// A command line finished
+    Finished { code: i32, cwd: PathBuf },
+    /// The shell exited
+    Exit { code: i32 },
+}
+
+/// Accept remote sessions until the process is stopped
+pub async fn serve(config: UmbraConfig, listen: Option<String>) -> Result<()> {
+    let identity = Identity::load(&SecretsClient::new()).await?;
+    let listen = listen.unwrap_or_else(|| config.remote.listen.clone());
+    let listener = TcpListener::bind(&listen).await?;
+    info!("Umbra agent listening on {} (host key {})", listen, identity.public_key());
+
+    let identity = std::sync::Arc::new(identity);
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let identity = identity.clone();
+        let config = config.clone();
+        tokio::spawn(async move {
+            match run_session(stream, &identity, config).await {
+                Ok(()) => info!("Session from {} closed", peer),
+                Err(e) => warn!("Session from {} failed: {}", peer, e),
+            }
+        });
+    }
+}
+
+/// Serve one client
+async fn run_session(stream: TcpStream, identity: &Identity, config: UmbraConfig) -> Result<()> {
+    let authorized_keys = expand_path(&config.remote.authorized_keys);
+    let mut channel = Channel::accept(stream, identity, &authorized_keys).await?;
+    info!("Session opened by {}", fingerprint(channel.peer_key()));
+
+    let Some(ClientMessage::Open { persona, history }) = channel.recv().await? else {
+        bail!("expected session open");
+    };
+    let history = History::in_memory(&config.history, history);
+    let mut shell = Shell::with_history(config, history);
+    shell.set_capture_output(true);
+    if let Some(persona) = &persona {
+        shell.set_var("UMBRA_PERSONA", persona);
+    }
+
+    let host = hostname::get()?.to_string_lossy().to_string();
+    channel.send(&AgentMessage::Ready { host, cwd: shell.cwd().clone() }).await?;
+
+    while let Some(message) = channel.recv::<ClientMessage>().await? {
+        let ClientMessage::Execute { line } = message else {
+            bail!("session already open");
+        };
+
+        let (event_tx, mut event_rx) = mpsc::channel(100);
+        let forward = async {
+            while let Some(event) = event_rx.recv().await {
+                let message = match event {
+                    ShellEvent::Output(text) => AgentMessage::Output { text },
+                    ShellEvent::Error(text) => AgentMessage::Error { text },
+                    _ => continue,
+                };
+                channel.send(&message).await?;
+            }
+            Ok::<_, anyhow::Error>(())
+        };
+        let (result, forwarded) = tokio::join!(shell.execute(&line, event_tx), forward);
+        forwarded?;
+
+        let reply = match result {
+            Ok(code) => AgentMessage::Finished { code, cwd: shell.cwd().clone() },
+            Err(e) => match e.to_string().strip_prefix("exit:") {
+                Some(code) => {
+                    channel.send(&AgentMessage::Exit { code: code.parse().unwrap_or(0) }).await?;
+                    return Ok(());
+                }
+                None => AgentMessage::Finished { code: 1, cwd: shell.cwd().clone() },
+            },
+        };
+        channel.send(&reply).await?;
+    }
+
+    Ok(())
+}
+
+/// Open a session on `target` (`host` or `host:port`). Runs `command` and
+/// returns its exit code, or reads commands from stdin until the remote
+/// shell exits.
+pub async fn connect(
+    config: UmbraConfig,
+    target: &str,
+    persona: Option<String>,
+    command: Option<String>,
+) -> Result<i32> {
+    let identity = Identity::load(&SecretsClient::new()).await?;
+    let (host, port) = match target.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|_| anyhow!("invalid port: {}", port))?),
+        None => (target, config.remote.port),
+    };
+    let stream = TcpStream::connect((host, port)).await?;
+    let known_hosts = expand_path(&config.remote.known_hosts);
+    let mut channel = Channel::connect(stream, &identity, &format!("{}:{}", host, port), &known_hosts).await?;
+
+    let mut history = History::new(&config.history)?;
+    let mut carried: Vec<String> = history
+        .last_n(config.remote.carried_history)
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    carried.reverse();
+    let persona = persona.or_else(|| config.ai.enabled.then(|| config.ai.default_persona.clone()));
+    channel.send(&ClientMessage::Open { persona, history: carried }).await?;
+
+    let Some(AgentMessage::Ready { host: remote_host, mut cwd }) = channel.recv().await? else {
+        bail!("agent refused the session");
+    };
+
+    if let Some(command) = command {
+        return run_remote(&mut channel, command, &mut cwd).await.map(|code| code.unwrap_or_else(|exit| exit));
+    }
+
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    loop {
+        print!("{}:{}$ ", remote_host, cwd.display());
+        stdout.flush()?;
+
+        let mut input = String::new();
+        if stdin.lock().read_line(&mut input)? == 0 {
+            return Ok(0);
+        }
+        let input = input.trim();
+        if input.is_empty() {
+            continue;
+        }
+
+        history.add(input)?;
+        if let Err(code) = run_remote(&mut channel, input.to_string(), &mut cwd).await? {
+            return Ok(code);
+        }
+    }
+}
+
+/// Run one line remotely, printing its output. `Err` carries the exit code
+/// if the remote shell exited.
+async fn run_remote(channel: &mut Channel, line: String, cwd: &mut PathBuf) -> Result<Result<i32, i32>> {
+    channel.send(&ClientMessage::Execute { line }).await?;
+    loop {
+        match channel.recv().await? {
+            Some(AgentMessage::Output { text }) => println!("{}", text),
+            Some(AgentMessage::Error { text }) => eprintln!("{}", text),
+            Some(AgentMessage::Finished { code, cwd: remote_cwd }) => {
+                *cwd = remote_cwd;
+                return Ok(Ok(code));
+            }
+            Some(AgentMessage::Exit { code }) => return Ok(Err(code)),
+            Some(AgentMessage::Ready { .. }) => bail!("unexpected message from agent"),
+            None => bail!("connection closed by agent"),
+        }
+    }
+}
@@ -18,6 +18,8 @@ pub struct Shell {
     jobs: HashMap<u32, Job>,
     next_job_id: u32,
     last_exit_code: i32,
+    /// Send foreground output as events instead of inheriting the terminal
+    capture_output: bool,
 }
 
 pub struct Job {
@@ -39,6 +41,12 @@ pub enum ShellEvent {
 
 impl Shell {
     pub fn new(config: UmbraConfig) -> Result<Self> {
+        let history = History::new(&config.history)?;
+        Ok(Self::with_history(config, history))
+    }
+
+    /// Create a shell around an existing history
+    pub fn with_history(config: UmbraConfig, history: History) -> Self {
         let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
         let mut env: HashMap<String, String> = env::vars().collect();
 
@@ -47,9 +55,7 @@ impl Shell {
             env.insert(var.key.clone(), var.value.clone());
         }
 
-        let history = History::new(&config.history)?;
-
-        Ok(Self {
+        Self {
             config,
             env,
             cwd,
@@ -57,7 +63,19 @@ impl Shell {
             jobs: HashMap::new(),
             next_job_id: 1,
             last_exit_code: 0,
-        })
+            capture_output: false,
+        }
+    }
+
+    /// Capture foreground command output as [`ShellEvent`]s, for shells
+    /// without a terminal of their own (remote sessions)
+    pub fn set_capture_output(&mut self, capture: bool) {
+        self.capture_output = capture;
+    }
+
+    /// Set a shell variable
+    pub fn set_var(&mut self, key: &str, value: &str) {
+        self.env.insert(key.to_string(), value.to_string());
     }
 
     /// Execute a command line
@@ -367,7 +385,7 @@ impl Shell {
             .current_dir(&self.cwd)
             .envs(&self.env);
 
-        if background {
+        if background || self.capture_output {
             command.stdin(Stdio::null())
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped());
@@ -399,6 +417,14 @@ impl Shell {
                 } else {
                     let output = child.wait_with_output()?;
                     let exit_code = output.status.code().unwrap_or(-1);
+                    if self.capture_output {
+                        for line in String::from_utf8_lossy(&output.stdout).lines() {
+                            let _ = event_tx.send(ShellEvent::Output(line.to_string())).await;
+                        }
+                        for line in String::from_utf8_lossy(&output.stderr).lines() {
+                            let _ = event_tx.send(ShellEvent::Error(line.to_string())).await;
+                        }
+                    }
                     let _ = event_tx.send(ShellEvent::Exit(exit_code)).await;
                     Ok(exit_code)
                 }
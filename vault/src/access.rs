@@ -0,0 +1,621 @@
+//! Per-application access policies
+//!
+//! Reads are authorized per client binary: the peer's UID and the SHA-256 of
+//! `/proc/<pid>/exe` identify the application. The first time an unknown
+//! application asks for a secret, Guardian is consulted; if Guardian wants a
+//! user decision (or is unreachable) the request is parked as pending and
+//! announced through herald until the user approves or denies it. Decisions
+//! are remembered in a policy file.
+//!
+//! Browser autofill goes through the same path with `site:<origin>` in place
+//! of a secret name, so every site a browser fills logins for is approved on
+//! its own.
+
+use crate::config::AccessConfig;
+use crate::ipc::ClientId;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use libnyx_ipc::protocol::{CapabilityRequest, Decision};
+use libnyx_ipc::GuardianClient;
+use ring::digest::{Context, SHA256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tokio::io::AsyncWriteExt;
+use tokio::net::UnixStream;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Capability names checked with Guardian
+const READ_CAPABILITY: &str = "vault:read";
+const WRITE_CAPABILITY: &str = "vault:write";
+const AUTOFILL_CAPABILITY: &str = "vault:autofill";
+const ADMIN_CAPABILITY: &str = "vault:manage-access";
+
+/// Most requests kept waiting for a decision
+const MAX_PENDING: usize = 64;
+
+/// Resource prefix of browser autofill requests
+pub const SITE_PREFIX: &str = "site:";
+
+/// Resource prefix of requests that change or remove secrets
+pub const WRITE_PREFIX: &str = "write:";
+
+/// Identity of the application behind a connection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppIdentity {
+    pub uid: u32,
+    pub exe: String,
+    pub exe_hash: String,
+}
+
+/// Who made an access decision
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecisionSource {
+    Guardian,
+    User,
+}
+
+/// A remembered access decision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessRule {
+    pub id: Uuid,
+    pub app: AppIdentity,
+    /// Secret name or `site:<origin>`; "*" for every secret and "site:*"
+    /// for every site
+    pub secret: String,
+    pub allow: bool,
+    pub source: DecisionSource,
+    pub decided_at: DateTime<Utc>,
+}
+
+/// An access request waiting for a user decision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingAccess {
+    pub id: Uuid,
+    pub app: AppIdentity,
+    pub pid: Option<i32>,
+    pub secret: String,
+    pub requested_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PolicyFile {
+    rules: Vec<AccessRule>,
+}
+
+struct AccessState {
+    rules: Vec<AccessRule>,
+    /// Approvals granted until restart; never written to the policy file
+    temporary: Vec<AccessRule>,
+    pending: Vec<PendingAccess>,
+    /// exe path -> (mtime, size, hash)
+    hash_cache: HashMap<PathBuf, (SystemTime, u64, String)>,
+}
+
+/// Access policy manager
+pub struct AccessManager {
+    config: AccessConfig,
+    state: Mutex<AccessState>,
+}
+
+impl AccessManager {
+    /// Create access manager, loading remembered decisions
+    pub fn new(config: AccessConfig) -> Self {
+        let rules = match fs::read_to_string(&config.policy_path) {
+            Ok(content) => serde_json::from_str::<PolicyFile>(&content)
+                .map(|f| f.rules)
+                .unwrap_or_else(|e| {
+                    warn!("Ignoring unreadable access policy {}: {}", config.policy_path, e);
+                    Vec::new()
+                }),
+            Err(_) => Vec::new(),
+        };
+
+        Self {
+            config,
+            state: Mutex::new(AccessState {
+                rules,
+                temporary: Vec::new(),
+                pending: Vec::new(),
+                hash_cache: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Authorize a client to read a secret
+    pub async fn authorize_read(&self, client: &ClientId, secret: &str) -> Result<()> {
+        self.authorize_app(client, secret).await
+    }
+
+    /// Authorize a client to change or remove a secret; "*" for requests
+    /// that replace the whole vault
+    pub async fn authorize_write(&self, client: &ClientId, secret: &str) -> Result<()> {
+        self.authorize_app(client, &format!("{}{}", WRITE_PREFIX, secret))
+            .await
+    }
+
+    /// Per-application policy shared by reads and writes
+    async fn authorize_app(&self, client: &ClientId, resource: &str) -> Result<()> {
+        if !self.config.per_app_policies {
+            return Ok(());
+        }
+
+        let app = self.identify(client)?;
+
+        if self.config.trusted_processes.iter().any(|p| p == &app.exe) {
+            return Ok(());
+        }
+
+        self.authorize(client, app, resource).await
+    }
+
+    /// Authorize a browser to fill logins for a site
+    ///
+    /// Always decided per site, whatever `per_app_policies` and
+    /// `trusted_processes` say about secret reads.
+    pub async fn authorize_site(&self, client: &ClientId, origin: &str) -> Result<()> {
+        let app = self.identify(client)?;
+        self.authorize(client, app, &format!("{}{}", SITE_PREFIX, origin))
+            .await
+    }
+
+    /// Authorize a client to list and decide access requests
+    ///
+    /// Root and trusted processes may; anyone else needs Guardian to allow
+    /// `vault:manage-access` outright. No prompt is parked for this.
+    pub async fn authorize_admin(&self, client: &ClientId) -> Result<()> {
+        if client.uid == 0 {
+            return Ok(());
+        }
+
+        let app = self.identify(client)?;
+        if self.config.trusted_processes.iter().any(|p| p == &app.exe) {
+            return Ok(());
+        }
+
+        match self.ask_guardian(client, &app, ADMIN_CAPABILITY, None).await {
+            Some(Decision::Allow) => Ok(()),
+            _ => Err(anyhow!("{} may not manage vault access", app.exe)),
+        }
+    }
+
+    /// Check a resource against remembered decisions, Guardian and finally
+    /// the user
+    async fn authorize(&self, client: &ClientId, app: AppIdentity, resource: &str) -> Result<()> {
+        if let Some(allow) = self.lookup(&app, resource) {
+            return if allow {
+                Ok(())
+            } else {
+                Err(anyhow!("Access to '{}' denied for {}", resource, app.exe))
+            };
+        }
+
+        // First access: ask Guardian
+        let capability = if resource.starts_with(SITE_PREFIX) {
+            AUTOFILL_CAPABILITY
+        } else if resource.starts_with(WRITE_PREFIX) {
+            WRITE_CAPABILITY
+        } else {
+            READ_CAPABILITY
+        };
+        match self.ask_guardian(client, &app, capability, Some(resource)).await {
+            Some(Decision::Allow) => {
+                self.remember(app, resource, true, DecisionSource::Guardian);
+                Ok(())
+            }
+            Some(Decision::Deny) => {
+                self.remember(app.clone(), resource, false, DecisionSource::Guardian);
+                Err(anyhow!("Access to '{}' denied for {}", resource, app.exe))
+            }
+            _ => {
+                let id = self.park(client, app.clone(), resource)?;
+                self.announce(&app, resource).await;
+                Err(anyhow!(
+                    "Access to '{}' for {} awaits approval (request {})",
+                    resource,
+                    app.exe,
+                    id
+                ))
+            }
+        }
+    }
+
+    /// Resolve a pending request
+    pub fn decide(&self, id: Uuid, allow: bool, remember: bool, all_secrets: bool) -> Result<()> {
+        let pending = {
+            let mut state = self.state.lock().unwrap();
+            let pos = state
+                .pending
+                .iter()
+                .position(|p| p.id == id)
+                .ok_or_else(|| anyhow!("No pending access request {}", id))?;
+            state.pending.remove(pos)
+        };
+
+        let secret = if all_secrets {
+            wildcard(&pending.secret)
+        } else {
+            pending.secret.as_str()
+        };
+        info!(
+            "Access to '{}' for {} {} by user",
+            secret,
+            pending.app.exe,
+            if allow { "allowed" } else { "denied" }
+        );
+
+        // Without `remember` a denial is simply dropped; an approval is kept
+        // until the vault restarts.
+        if remember {
+            self.remember(pending.app, secret, allow, DecisionSource::User);
+        } else if allow {
+            let rule = new_rule(pending.app, secret, allow, DecisionSource::User);
+            self.state.lock().unwrap().temporary.push(rule);
+        }
+        Ok(())
+    }
+
+    /// List remembered rules, followed by approvals kept until restart
+    pub fn rules(&self) -> Vec<AccessRule> {
+        let state = self.state.lock().unwrap();
+        state.rules.iter().chain(&state.temporary).cloned().collect()
+    }
+
+    /// List pending requests
+    pub fn pending(&self) -> Vec<PendingAccess> {
+        self.state.lock().unwrap().pending.clone()
+    }
+
+    /// Remove a remembered rule
+    pub fn revoke(&self, id: Uuid) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.temporary.len();
+        state.temporary.retain(|r| r.id != id);
+        if state.temporary.len() != before {
+            return Ok(());
+        }
+
+        let before = state.rules.len();
+        state.rules.retain(|r| r.id != id);
+        if state.rules.len() == before {
+            return Err(anyhow!("No access rule {}", id));
+        }
+        self.save(&state.rules)
+    }
+
+    /// Identify the application behind a connection
+    pub fn identify(&self, client: &ClientId) -> Result<AppIdentity> {
+        let pid = client
+            .pid
+            .ok_or_else(|| anyhow!("Cannot identify client process"))?;
+        let exe = fs::read_link(format!("/proc/{}/exe", pid))
+            .map_err(|e| anyhow!("Cannot identify client process {}: {}", pid, e))?;
+
+        let exe_hash = self.hash_exe(&exe)?;
+
+        Ok(AppIdentity {
+            uid: client.uid,
+            exe: exe.to_string_lossy().to_string(),
+            exe_hash,
+        })
+    }
+
+    /// SHA-256 of an executable, cached by mtime and size
+    fn hash_exe(&self, exe: &Path) -> Result<String> {
+        let meta = fs::metadata(exe)?;
+        let mtime = meta.modified()?;
+        let size = meta.len();
+
+        if let Some((cached_mtime, cached_size, hash)) =
+            self.state.lock().unwrap().hash_cache.get(exe)
+        {
+            if *cached_mtime == mtime && *cached_size == size {
+                return Ok(hash.clone());
+            }
+        }
+
+        let mut file = fs::File::open(exe)?;
+        let mut context = Context::new(&SHA256);
+        let mut buf = [0u8; 64 * 1024];
+        loop {
+            let n = file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            context.update(&buf[..n]);
+        }
+        let hash: String = context
+            .finish()
+            .as_ref()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        self.state
+            .lock()
+            .unwrap()
+            .hash_cache
+            .insert(exe.to_path_buf(), (mtime, size, hash.clone()));
+        Ok(hash)
+    }
+
+    /// Find a remembered decision; specific secrets win over wildcards
+    fn lookup(&self, app: &AppIdentity, secret: &str) -> Option<bool> {
+        let state = self.state.lock().unwrap();
+        let matching = |pattern: &str| {
+            state
+                .rules
+                .iter()
+                .chain(&state.temporary)
+                .rev()
+                .find(|r| &r.app == app && r.secret == pattern)
+                .map(|r| r.allow)
+        };
+        matching(secret).or_else(|| matching(wildcard(secret)))
+    }
+
+    fn remember(&self, app: AppIdentity, secret: &str, allow: bool, source: DecisionSource) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .rules
+            .retain(|r| !(r.app == app && r.secret == secret));
+        state
+            .temporary
+            .retain(|r| !(r.app == app && r.secret == secret));
+        state.rules.push(new_rule(app, secret, allow, source));
+
+        if let Err(e) = self.save(&state.rules) {
+            warn!("Failed to save access policy: {}", e);
+        }
+    }
+
+    fn save(&self, rules: &[AccessRule]) -> Result<()> {
+        let path = Path::new(&self.config.policy_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = PolicyFile {
+            rules: rules.to_vec(),
+        };
+        fs::write(path, serde_json::to_string_pretty(&file)?)?;
+        Ok(())
+    }
+
+    /// Park a request for user approval, reusing an existing one
+    fn park(&self, client: &ClientId, app: AppIdentity, secret: &str) -> Result<Uuid> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(existing) = state
+            .pending
+            .iter()
+            .find(|p| p.app == app && p.secret == secret)
+        {
+            return Ok(existing.id);
+        }
+        if state.pending.len() >= MAX_PENDING {
+            return Err(anyhow!("Too many access requests awaiting approval"));
+        }
+
+        let pending = PendingAccess {
+            id: Uuid::new_v4(),
+            app,
+            pid: client.pid,
+            secret: secret.to_string(),
+            requested_at: Utc::now(),
+        };
+        let id = pending.id;
+        state.pending.push(pending);
+        Ok(id)
+    }
+
+    /// Ask Guardian for a decision; `None` if Guardian is unavailable
+    async fn ask_guardian(
+        &self,
+        client: &ClientId,
+        app: &AppIdentity,
+        capability: &str,
+        resource: Option<&str>,
+    ) -> Option<Decision> {
+        let mut guardian = match GuardianClient::connect().await {
+            Ok(g) => g,
+            Err(e) => {
+                debug!("Guardian unavailable for access check: {}", e);
+                return None;
+            }
+        };
+
+        let request = CapabilityRequest {
+            pid: client.pid.unwrap_or(0) as u32,
+            process_path: app.exe.clone(),
+            user: app.uid.to_string(),
+            capability: capability.to_string(),
+            resource: resource.map(String::from),
+            context: HashMap::new(),
+        }
+        .with_context("exe_hash", app.exe_hash.clone());
+
+        match guardian.check_capability_full(request).await {
+            Ok(decision) => Some(decision.decision),
+            Err(e) => {
+                warn!("Guardian access check failed: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Tell the user about a pending request through herald
+    async fn announce(&self, app: &AppIdentity, secret: &str) {
+        let (summary, wants) = match secret.strip_prefix(SITE_PREFIX) {
+            Some(origin) => ("Autofill requested", format!("to fill logins for {}", origin)),
+            None => ("Secret access requested", format!("to read '{}'", secret)),
+        };
+        let request = serde_json::json!({
+            "type": "Notify",
+            "data": {
+                "app_name": "Vault",
+                "summary": summary,
+                "body": format!(
+                    "{} (uid {}) wants {}. Review with `vaultctl access pending`.",
+                    app.exe, app.uid, wants
+                ),
+                "icon": "dialog-password",
+                "urgency": "normal",
+                "timeout": null,
+            }
+        });
+
+        let result = async {
+            let mut stream = UnixStream::connect(&self.config.herald_socket).await?;
+            stream.write_all(request.to_string().as_bytes()).await?;
+            stream.write_all(b"\n").await?;
+            stream.flush().await
+        }
+        .await;
+
+        if let Err(e) = result {
+            debug!("Could not notify herald about access request: {}", e);
+        }
+    }
+}
+
+/// Wildcard covering a resource: every site for sites, every secret otherwise
+fn wildcard(resource: &str) -> &'static str {
+    if resource.starts_with(SITE_PREFIX) {
+        "site:*"
+    } else if resource.starts_with(WRITE_PREFIX) {
+        "write:*"
+    } else {
+        "*"
+    }
+}
+
+fn new_rule(app: AppIdentity, secret: &str, allow: bool, source: DecisionSource) -> AccessRule {
+    AccessRule {
+        id: Uuid::new_v4(),
+        app,
+        secret: secret.to_string(),
+        allow,
+        source,
+        decided_at: Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> AccessManager {
+        let path = std::env::temp_dir().join(format!("vault-access-{}.json", Uuid::new_v4()));
+        AccessManager::new(AccessConfig {
+            policy_path: path.to_string_lossy().to_string(),
+            ..AccessConfig::default()
+        })
+    }
+
+    fn app() -> AppIdentity {
+        AppIdentity {
+            uid: 1000,
+            exe: "/usr/bin/example".to_string(),
+            exe_hash: "abc123".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_specific_rule_overrides_wildcard() {
+        let access = manager();
+        access.remember(app(), "*", true, DecisionSource::User);
+        access.remember(app(), "db-password", false, DecisionSource::User);
+
+        assert_eq!(access.lookup(&app(), "api-key"), Some(true));
+        assert_eq!(access.lookup(&app(), "db-password"), Some(false));
+
+        // A rebuilt binary is a different application
+        let rebuilt = AppIdentity {
+            exe_hash: "def456".to_string(),
+            ..app()
+        };
+        assert_eq!(access.lookup(&rebuilt, "api-key"), None);
+
+        // Decisions survive a restart
+        let reloaded = AccessManager::new(access.config.clone());
+        assert_eq!(reloaded.lookup(&app(), "db-password"), Some(false));
+        let _ = fs::remove_file(&access.config.policy_path);
+    }
+
+    #[test]
+    fn test_read_approval_does_not_grant_writes() {
+        let access = manager();
+        access.remember(app(), "*", true, DecisionSource::User);
+
+        let write = format!("{}api-key", WRITE_PREFIX);
+        assert_eq!(access.lookup(&app(), "api-key"), Some(true));
+        assert_eq!(access.lookup(&app(), &write), None);
+
+        access.remember(app(), wildcard(&write), true, DecisionSource::User);
+        assert_eq!(access.lookup(&app(), &write), Some(true));
+        let _ = fs::remove_file(&access.config.policy_path);
+    }
+
+    #[test]
+    fn test_temporary_approval_not_persisted() {
+        let access = manager();
+        let client = ClientId { uid: 1000, pid: Some(1) };
+        let id = access.park(&client, app(), "api-key").unwrap();
+        access.decide(id, true, false, false).unwrap();
+        assert_eq!(access.lookup(&app(), "api-key"), Some(true));
+
+        // Saving another decision must not write the temporary approval
+        access.remember(app(), "db-password", false, DecisionSource::User);
+
+        let reloaded = AccessManager::new(access.config.clone());
+        assert_eq!(reloaded.lookup(&app(), "api-key"), None);
+        assert_eq!(reloaded.lookup(&app(), "db-password"), Some(false));
+        let _ = fs::remove_file(&access.config.policy_path);
+    }
+
+    #[test]
+    fn test_pending_decision() {
+        let access = manager();
+        let client = ClientId { uid: 1000, pid: Some(42) };
+
+        let id = access.park(&client, app(), "api-key").unwrap();
+        assert_eq!(access.park(&client, app(), "api-key").unwrap(), id);
+        assert_eq!(access.pending().len(), 1);
+
+        access.decide(id, true, false, false).unwrap();
+        assert!(access.pending().is_empty());
+        assert_eq!(access.lookup(&app(), "api-key"), Some(true));
+        assert!(access.decide(id, true, false, false).is_err());
+    }
+
+    #[test]
+    fn test_pending_is_bounded() {
+        let access = manager();
+        let client = ClientId { uid: 1000, pid: Some(42) };
+        for i in 0..MAX_PENDING {
+            access.park(&client, app(), &format!("secret-{}", i)).unwrap();
+        }
+        assert!(access.park(&client, app(), "one-too-many").is_err());
+        // Repeats of a parked request still find it
+        assert!(access.park(&client, app(), "secret-0").is_ok());
+    }
+
+    #[test]
+    fn test_site_wildcard_is_separate() {
+        let access = manager();
+        let client = ClientId { uid: 1000, pid: Some(42) };
+        access.remember(app(), "*", true, DecisionSource::User);
+        assert_eq!(access.lookup(&app(), "site:https://example.com"), None);
+
+        let id = access.park(&client, app(), "site:https://example.com").unwrap();
+        access.decide(id, true, false, true).unwrap();
+        assert_eq!(access.lookup(&app(), "site:https://other.example"), Some(true));
+        assert_eq!(access.lookup(&app(), "api-key"), Some(true));
+        let _ = fs::remove_file(&access.config.policy_path);
+    }
+}
@@ -0,0 +1,119 @@
+//! Audit logging for security-relevant vault events
+
+use chrono::{DateTime, Utc};
+use libnyx_ipc::audit::{self as bus, AuditOutcome};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{error, info, warn};
+
+/// Audit event types
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// Vault unlocked by a client
+    Unlocked { uid: u32, pid: Option<i32> },
+    /// Unlock attempt failed
+    UnlockFailed {
+        uid: u32,
+        pid: Option<i32>,
+        attempts: u32,
+        backoff_ms: Option<u64>,
+    },
+    /// Unlock attempt rejected while throttled
+    UnlockThrottled { uid: u32, pid: Option<i32> },
+    /// Vault locked explicitly
+    Locked { uid: u32 },
+    /// Vault locked after inactivity
+    AutoLocked { idle_secs: u64 },
+    /// A client's unlock session expired
+    SessionExpired { uid: u32 },
+}
+
+impl AuditEvent {
+    /// The operation as reported on the system audit bus
+    fn to_bus(&self) -> bus::AuditEvent {
+        let event = |actor: String, action, outcome| bus::AuditEvent::new("vault", actor, action, "vault", outcome);
+        let uid = |uid: &u32| bus::AuditEvent::uid(*uid);
+
+        match self {
+            AuditEvent::Unlocked { uid: id, .. } => event(uid(id), "vault.unlock", AuditOutcome::Success),
+            AuditEvent::UnlockFailed { uid: id, attempts, .. } => {
+                event(uid(id), "vault.unlock", AuditOutcome::Denied)
+                    .with_reason("wrong password")
+                    .with_detail("attempts", attempts)
+            }
+            AuditEvent::UnlockThrottled { uid: id, .. } => {
+                event(uid(id), "vault.unlock", AuditOutcome::Denied).with_reason("throttled")
+            }
+            AuditEvent::Locked { uid: id } => event(uid(id), "vault.lock", AuditOutcome::Success),
+            AuditEvent::AutoLocked { idle_secs } => {
+                event("vault".to_string(), "vault.lock", AuditOutcome::Success)
+                    .with_reason(format!("idle for {}s", idle_secs))
+            }
+            AuditEvent::SessionExpired { uid: id } => {
+                event(uid(id), "vault.session_expire", AuditOutcome::Success)
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AuditRecord<'a> {
+    timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    event: &'a AuditEvent,
+}
+
+/// Append-only JSON-lines audit log
+pub struct AuditLog {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl AuditLog {
+    /// Create audit log writing to `path`
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Record an event
+    pub fn record(&self, event: AuditEvent) {
+        match &event {
+            AuditEvent::UnlockFailed { .. } | AuditEvent::UnlockThrottled { .. } => {
+                warn!("Audit: {:?}", event)
+            }
+            _ => info!("Audit: {:?}", event),
+        }
+        bus::record(event.to_bus());
+
+        if let Err(e) = self.append(&event) {
+            error!("Failed to write audit log {:?}: {}", self.path, e);
+        }
+    }
+
+    fn append(&self, event: &AuditEvent) -> anyhow::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let record = AuditRecord {
+            timestamp: Utc::now(),
+            event,
+        };
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,324 @@
+//! Browser autofill bridge
+//!
+//! Browsers reach the vault through a separate socket that only serves
+//! website logins. Sitra connects directly; other browsers launch
+//! `vault-browser-host`, a native messaging host that relays their
+//! extension's messages here. Connections from that host are attributed to
+//! the browser that started it, so approvals belong to the browser binary
+//! rather than to the relay.
+//!
+//! Messages are JSON lines tagged by `type`, with an optional `id` echoed
+//! in the reply. Every site needs its own approval (see
+//! `AccessManager::authorize_site`); nothing is disclosed for a site,
+//! not even whether logins exist, before it is approved. The bridge needs
+//! the vault unlocked but no unlock session of its own.
+
+use crate::config::BrowserConfig;
+use crate::ipc::{ClientId, IpcHandler, IpcResponse};
+use crate::store::{Login, SecretMetadata, SecretType, ORIGIN_TAG};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tracing::{debug, error, info};
+
+/// Bridge request types
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum BridgeRequest {
+    /// Sent by the native messaging host before relaying
+    Hello {
+        #[serde(default)]
+        extension: Option<String>,
+    },
+
+    /// Whether the vault is unlocked
+    Status,
+
+    /// Logins for a site
+    Query { origin: String },
+
+    /// Username and password of a login for a site
+    Get { origin: String, name: String },
+}
+
+/// A request with its correlation ID
+#[derive(Debug, Deserialize)]
+struct BridgeMessage {
+    #[serde(default)]
+    id: Option<serde_json::Value>,
+    #[serde(flatten)]
+    request: BridgeRequest,
+}
+
+/// A response with the ID of the request it answers
+#[derive(Debug, Serialize)]
+struct BridgeReply {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<serde_json::Value>,
+    #[serde(flatten)]
+    response: IpcResponse,
+}
+
+/// A login offered for a site
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteLogin {
+    pub name: String,
+    pub username: String,
+}
+
+/// Bridge server
+pub struct BridgeServer<H: IpcHandler> {
+    config: BrowserConfig,
+    handler: Arc<H>,
+}
+
+impl<H: IpcHandler + 'static> BridgeServer<H> {
+    pub fn new(config: BrowserConfig, handler: Arc<H>) -> Self {
+        Self { config, handler }
+    }
+
+    pub async fn run(self) -> Result<()> {
+        let _ = std::fs::remove_file(&self.config.socket_path);
+
+        if let Some(parent) = std::path::Path::new(&self.config.socket_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let listener = UnixListener::bind(&self.config.socket_path)?;
+        info!("Browser bridge listening on {}", self.config.socket_path);
+
+        let host_path = Arc::new(self.config.host_path);
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let handler = Arc::clone(&self.handler);
+                    let host_path = Arc::clone(&host_path);
+                    tokio::spawn(async move {
+                        if let Err(e) = handle_browser(stream, handler, &host_path).await {
+                            error!("Browser client error: {}", e);
+                        }
+                    });
+                }
+                Err(e) => {
+                    error!("Browser bridge accept error: {}", e);
+                }
+            }
+        }
+    }
+}
+
+async fn handle_browser<H: IpcHandler>(stream: UnixStream, handler: Arc<H>, host_path: &str) -> Result<()> {
+    let cred = stream.peer_cred()?;
+    let client = browser_client(
+        ClientId {
+            uid: cred.uid(),
+            pid: cred.pid(),
+        },
+        host_path,
+    );
+
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+
+    while reader.read_line(&mut line).await? > 0 {
+        let reply = match serde_json::from_str::<BridgeMessage>(&line) {
+            Ok(message) => BridgeReply {
+                id: message.id,
+                response: match process_request(message.request, handler.as_ref(), &client).await {
+                    Ok(data) => IpcResponse::Success { data },
+                    Err(e) => IpcResponse::Error {
+                        message: e.to_string(),
+                    },
+                },
+            },
+            Err(e) => BridgeReply {
+                id: None,
+                response: IpcResponse::Error {
+                    message: format!("Invalid request: {}", e),
+                },
+            },
+        };
+
+        writer.write_all(serde_json::to_string(&reply)?.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+        writer.flush().await?;
+
+        line.clear();
+    }
+
+    Ok(())
+}
+
+async fn process_request<H: IpcHandler>(
+    request: BridgeRequest,
+    handler: &H,
+    client: &ClientId,
+) -> Result<serde_json::Value> {
+    match request {
+        BridgeRequest::Hello { extension } => {
+            debug!(
+                "Browser bridge client pid {:?} is extension {}",
+                client.pid,
+                extension.as_deref().unwrap_or("(none)")
+            );
+            Ok(serde_json::json!({"version": env!("CARGO_PKG_VERSION")}))
+        }
+
+        BridgeRequest::Status => Ok(serde_json::json!({
+            "exists": handler.exists(),
+            "unlocked": handler.is_unlocked(),
+        })),
+
+        BridgeRequest::Query { origin } => {
+            let origin = authorize(handler, client, &origin).await?;
+
+            let mut logins = Vec::new();
+            for meta in site_logins(handler, &origin)? {
+                let login = Login::from_value(&handler.get(&meta.name)?)?;
+                logins.push(SiteLogin {
+                    name: meta.name,
+                    username: login.username,
+                });
+            }
+            Ok(serde_json::to_value(logins)?)
+        }
+
+        BridgeRequest::Get { origin, name } => {
+            let origin = authorize(handler, client, &origin).await?;
+
+            // Only logins tagged for the approved site
+            if !site_logins(handler, &origin)?.iter().any(|m| m.name == name) {
+                return Err(anyhow!("No login '{}' for {}", name, origin));
+            }
+            let login = Login::from_value(&handler.get(&name)?)?;
+            info!("Filled login '{}' for {} (uid {})", name, origin, client.uid);
+            Ok(serde_json::to_value(login)?)
+        }
+    }
+}
+
+/// Normalize the origin and check the client may fill logins for it
+async fn authorize<H: IpcHandler>(handler: &H, client: &ClientId, origin: &str) -> Result<String> {
+    let origin = normalize_origin(origin)?;
+
+    // Locked first, so a locked vault does not prompt
+    if !handler.is_unlocked() {
+        return Err(anyhow!("Vault is locked"));
+    }
+    handler.access().authorize_site(client, &origin).await?;
+    Ok(origin)
+}
+
+/// Login secrets tagged for an origin
+fn site_logins<H: IpcHandler>(handler: &H, origin: &str) -> Result<Vec<SecretMetadata>> {
+    let mut logins: Vec<_> = handler
+        .search_by_tag(&format!("{}{}", ORIGIN_TAG, origin))?
+        .into_iter()
+        .filter(|m| m.secret_type == SecretType::Login)
+        .collect();
+    logins.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(logins)
+}
+
+/// The client to authorize: the browser behind the native messaging host,
+/// or the peer itself
+fn browser_client(peer: ClientId, host_path: &str) -> ClientId {
+    let Some(pid) = peer.pid else {
+        return peer;
+    };
+
+    let is_host = std::fs::read_link(format!("/proc/{}/exe", pid))
+        .map(|exe| exe.to_string_lossy() == host_path)
+        .unwrap_or(false);
+    if !is_host {
+        return peer;
+    }
+
+    match parent_pid(pid) {
+        Some(ppid) => ClientId {
+            uid: peer.uid,
+            pid: Some(ppid),
+        },
+        None => peer,
+    }
+}
+
+/// Parent of a process, from `/proc/<pid>/stat`
+fn parent_pid(pid: i32) -> Option<i32> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name may contain spaces and parentheses; fields resume
+    // after the last ')': state, then ppid
+    let rest = &stat[stat.rfind(')')? + 1..];
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Reduce a URL or origin to `scheme://host[:port]`
+///
+/// Only http and https are accepted; the scheme and host are lowercased
+/// and default ports dropped.
+pub fn normalize_origin(url: &str) -> Result<String> {
+    let url = url.trim();
+    let (scheme, rest) = url
+        .split_once("://")
+        .ok_or_else(|| anyhow!("Not an origin: {}", url))?;
+    let scheme = scheme.to_ascii_lowercase();
+    let default_port = match scheme.as_str() {
+        "https" => ":443",
+        "http" => ":80",
+        _ => return Err(anyhow!("Unsupported origin scheme: {}", scheme)),
+    };
+
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host)
+        .to_ascii_lowercase();
+    let host = host.strip_suffix(default_port).unwrap_or(&host);
+    if host.is_empty() || host.starts_with(':') {
+        return Err(anyhow!("Not an origin: {}", url));
+    }
+
+    Ok(format!("{}://{}", scheme, host))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_origin() {
+        assert_eq!(
+            normalize_origin("HTTPS://Example.COM:443/login?next=/").unwrap(),
+            "https://example.com"
+        );
+        assert_eq!(
+            normalize_origin("http://user@intranet.local:8080#top").unwrap(),
+            "http://intranet.local:8080"
+        );
+        assert_eq!(normalize_origin("https://[::1]:8443").unwrap(), "https://[::1]:8443");
+
+        assert!(normalize_origin("example.com").is_err());
+        assert!(normalize_origin("file:///etc/passwd").is_err());
+        assert!(normalize_origin("https:///path").is_err());
+    }
+
+    #[test]
+    fn test_bridge_message_id_echo() {
+        let message: BridgeMessage =
+            serde_json::from_str(r#"{"id": 7, "type": "Query", "origin": "https://example.com"}"#).unwrap();
+        assert!(matches!(message.request, BridgeRequest::Query { .. }));
+
+        let reply = BridgeReply {
+            id: message.id,
+            response: IpcResponse::Success {
+                data: serde_json::json!([]),
+            },
+        };
+        let json = serde_json::to_value(&reply).unwrap();
+        assert_eq!(json["id"], 7);
+        assert_eq!(json["status"], "Success");
+    }
+}
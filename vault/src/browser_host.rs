@@ -0,0 +1,189 @@
+//! vault-browser-host - native messaging host for the browser bridge
+//!
+//! Browsers start the host with the calling extension as an argument
+//! (`chrome-extension://<id>/` for Chromium, the manifest path followed by
+//! the add-on ID for Firefox) and talk to it over stdin/stdout in native
+//! messaging framing: a native-endian `u32` length followed by that many
+//! bytes of JSON. Each message is relayed to vaultd's bridge socket as one
+//! line, and each reply line is framed back.
+//!
+//! `vault-browser-host --manifest chrome|firefox <extension>` prints the
+//! host manifest to install for the browser.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::process::ExitCode;
+
+/// Native messaging host name
+const HOST_NAME: &str = "org.daemoniorum.vault";
+
+/// Largest message a host may send to the browser
+const MAX_TO_BROWSER: usize = 1024 * 1024;
+
+/// Largest message a browser may send to the host
+const MAX_FROM_BROWSER: usize = 64 * 1024 * 1024;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("--manifest") => print_manifest(&args[1..]),
+        _ => relay(extension(&args)),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            // stdout belongs to the browser
+            eprintln!("vault-browser-host: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// The calling extension, from the browser's launch arguments
+fn extension(args: &[String]) -> Option<String> {
+    args.iter()
+        .find(|a| a.starts_with("chrome-extension://"))
+        .or_else(|| args.get(1))
+        .cloned()
+}
+
+/// Relay messages between the browser and vaultd until either side closes
+fn relay(extension: Option<String>) -> io::Result<()> {
+    let socket = std::env::var("VAULT_BROWSER_SOCKET").unwrap_or_else(|_| "/run/vault/browser.sock".to_string());
+    let mut vault = UnixStream::connect(&socket)
+        .map_err(|e| io::Error::new(e.kind(), format!("cannot reach vault at {}: {}", socket, e)))?;
+
+    // Announce the extension; its reply is not for the browser
+    let hello = serde_json::json!({"type": "Hello", "extension": extension});
+    writeln!(vault, "{}", hello)?;
+    let mut replies = BufReader::new(vault.try_clone()?);
+    let mut line = String::new();
+    replies.read_line(&mut line)?;
+
+    let mut to_vault = vault.try_clone()?;
+    let browser_to_vault = std::thread::spawn(move || -> io::Result<()> {
+        let mut stdin = io::stdin().lock();
+        while let Some(message) = read_message(&mut stdin)? {
+            // Lines carry one message each; re-encode to drop any newlines
+            let value: serde_json::Value = serde_json::from_slice(&message)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            writeln!(to_vault, "{}", value)?;
+        }
+        // Browser went away: let vaultd finish the connection
+        to_vault.shutdown(std::net::Shutdown::Write)
+    });
+
+    let mut stdout = io::stdout().lock();
+    loop {
+        line.clear();
+        if replies.read_line(&mut line)? == 0 {
+            break;
+        }
+        write_message(&mut stdout, line.trim_end().as_bytes())?;
+    }
+
+    vault.shutdown(std::net::Shutdown::Both).ok();
+    browser_to_vault
+        .join()
+        .map_err(|_| io::Error::other("relay thread panicked"))?
+}
+
+/// Read one framed message; `None` at end of input
+fn read_message(input: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match input.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let len = u32::from_ne_bytes(len) as usize;
+    if len > MAX_FROM_BROWSER {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {} bytes is too large", len),
+        ));
+    }
+
+    let mut message = vec![0u8; len];
+    input.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+/// Write one framed message, replacing oversized ones with an error
+fn write_message(output: &mut impl Write, message: &[u8]) -> io::Result<()> {
+    let too_large;
+    let message = if message.len() > MAX_TO_BROWSER {
+        too_large = serde_json::json!({
+            "status": "Error",
+            "message": format!("Reply of {} bytes exceeds the native messaging limit", message.len()),
+        })
+        .to_string();
+        too_large.as_bytes()
+    } else {
+        message
+    };
+
+    output.write_all(&(message.len() as u32).to_ne_bytes())?;
+    output.write_all(message)?;
+    output.flush()
+}
+
+/// Print the native messaging manifest for a browser
+fn print_manifest(args: &[String]) -> io::Result<()> {
+    let usage = || io::Error::new(io::ErrorKind::InvalidInput, "usage: --manifest chrome|firefox <extension>");
+    let (browser, extension) = match args {
+        [browser, extension] => (browser.as_str(), extension.as_str()),
+        _ => return Err(usage()),
+    };
+    let path = std::env::current_exe()?;
+
+    let mut manifest = serde_json::json!({
+        "name": HOST_NAME,
+        "description": "Vault credential autofill",
+        "path": path,
+        "type": "stdio",
+    });
+    match browser {
+        "chrome" | "chromium" => {
+            let origin = if extension.starts_with("chrome-extension://") {
+                extension.to_string()
+            } else {
+                format!("chrome-extension://{}/", extension)
+            };
+            manifest["allowed_origins"] = serde_json::json!([origin]);
+        }
+        "firefox" => manifest["allowed_extensions"] = serde_json::json!([extension]),
+        _ => return Err(usage()),
+    }
+
+    println!("{}", serde_json::to_string_pretty(&manifest)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_framing_roundtrip() {
+        let mut framed = Vec::new();
+        write_message(&mut framed, br#"{"status":"Success","data":[]}"#).unwrap();
+        assert_eq!(&framed[..4], &30u32.to_ne_bytes());
+
+        let mut input = &framed[..];
+        assert_eq!(read_message(&mut input).unwrap().unwrap(), br#"{"status":"Success","data":[]}"#);
+        assert!(read_message(&mut input).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_extension_from_launch_args() {
+        let chrome = vec!["chrome-extension://abc/".to_string(), "--parent-window=0".to_string()];
+        assert_eq!(extension(&chrome).as_deref(), Some("chrome-extension://abc/"));
+
+        let firefox = vec!["/usr/lib/mozilla/native-messaging-hosts/x.json".to_string(), "vault@daemoniorum.org".to_string()];
+        assert_eq!(extension(&firefox).as_deref(), Some("vault@daemoniorum.org"));
+    }
+}
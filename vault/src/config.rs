@@ -22,6 +22,10 @@ pub struct VaultConfig {
     /// Daemon settings
     #[serde(default)]
     pub daemon: DaemonConfig,
+
+    /// Browser autofill bridge settings
+    #[serde(default)]
+    pub browser: BrowserConfig,
 }
 
 impl Default for VaultConfig {
@@ -31,6 +35,7 @@ impl Default for VaultConfig {
             encryption: EncryptionConfig::default(),
             access: AccessConfig::default(),
             daemon: DaemonConfig::default(),
+            browser: BrowserConfig::default(),
         }
     }
 }
@@ -57,6 +62,50 @@ pub struct StorageConfig {
     /// Max backup count
     #[serde(default = "default_backup_count")]
     pub max_backups: usize,
+
+    /// Previous versions kept per secret (overridable per secret)
+    #[serde(default = "default_version_history")]
+    pub version_history: usize,
+
+    /// Delete backups older than this many days (0 = keep by count only)
+    #[serde(default)]
+    pub backup_retention_days: u32,
+
+    /// Key used to encrypt backup snapshots (created on first backup)
+    #[serde(default = "default_backup_key_path")]
+    pub backup_key_path: String,
+
+    /// Off-site copy of each backup
+    #[serde(default)]
+    pub backup_remote: Option<RemoteTarget>,
+}
+
+/// Remote backup target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RemoteTarget {
+    /// Upload with `sftp`
+    Sftp {
+        host: String,
+        #[serde(default)]
+        user: Option<String>,
+        #[serde(default)]
+        port: Option<u16>,
+        /// Remote directory
+        path: String,
+        #[serde(default)]
+        identity_file: Option<String>,
+    },
+    /// Upload with the `aws` CLI (any S3-compatible endpoint)
+    S3 {
+        bucket: String,
+        #[serde(default)]
+        prefix: Option<String>,
+        #[serde(default)]
+        endpoint: Option<String>,
+        #[serde(default)]
+        profile: Option<String>,
+    },
 }
 
 impl Default for StorageConfig {
@@ -67,6 +116,10 @@ impl Default for StorageConfig {
             auto_backup: true,
             backup_interval_hours: default_backup_interval(),
             max_backups: default_backup_count(),
+            version_history: default_version_history(),
+            backup_retention_days: 0,
+            backup_key_path: default_backup_key_path(),
+            backup_remote: None,
         }
     }
 }
@@ -116,6 +169,38 @@ pub struct AccessConfig {
     /// Trusted process paths that can access secrets
     #[serde(default)]
     pub trusted_processes: Vec<String>,
+
+    /// Per-client unlock session lifetime in seconds (extended on use)
+    #[serde(default = "default_session_timeout")]
+    pub session_timeout_secs: u32,
+
+    /// Failed unlock attempts allowed before backoff kicks in
+    #[serde(default = "default_free_attempts")]
+    pub free_unlock_attempts: u32,
+
+    /// Initial backoff after exceeding free attempts (doubles per failure)
+    #[serde(default = "default_backoff_base")]
+    pub unlock_backoff_base_ms: u64,
+
+    /// Maximum backoff between unlock attempts
+    #[serde(default = "default_backoff_max")]
+    pub unlock_backoff_max_secs: u64,
+
+    /// Audit log path
+    #[serde(default = "default_audit_log")]
+    pub audit_log: String,
+
+    /// Authorize secret reads per client application (UID + executable hash)
+    #[serde(default = "default_true")]
+    pub per_app_policies: bool,
+
+    /// Remembered per-application access decisions
+    #[serde(default = "default_policy_path")]
+    pub policy_path: String,
+
+    /// Herald socket used to announce pending access requests
+    #[serde(default = "default_herald_socket")]
+    pub herald_socket: String,
 }
 
 impl Default for AccessConfig {
@@ -124,6 +209,14 @@ impl Default for AccessConfig {
             auth_for_read: true,
             allow_list_names: false,
             trusted_processes: Vec::new(),
+            session_timeout_secs: default_session_timeout(),
+            free_unlock_attempts: default_free_attempts(),
+            unlock_backoff_base_ms: default_backoff_base(),
+            unlock_backoff_max_secs: default_backoff_max(),
+            audit_log: default_audit_log(),
+            per_app_policies: true,
+            policy_path: default_policy_path(),
+            herald_socket: default_herald_socket(),
         }
     }
 }
@@ -149,6 +242,33 @@ impl Default for DaemonConfig {
     }
 }
 
+/// Browser autofill bridge configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrowserConfig {
+    /// Serve the bridge socket
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Bridge socket path
+    #[serde(default = "default_browser_socket")]
+    pub socket_path: String,
+
+    /// Native messaging host; its connections are attributed to the
+    /// browser that launched it
+    #[serde(default = "default_browser_host")]
+    pub host_path: String,
+}
+
+impl Default for BrowserConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            socket_path: default_browser_socket(),
+            host_path: default_browser_host(),
+        }
+    }
+}
+
 // Default value functions
 fn default_true() -> bool {
     true
@@ -162,6 +282,10 @@ fn default_backup_path() -> String {
     "/var/lib/vault/backups".to_string()
 }
 
+fn default_backup_key_path() -> String {
+    "/var/lib/vault/backup.key".to_string()
+}
+
 fn default_backup_interval() -> u32 {
     24
 }
@@ -170,6 +294,10 @@ fn default_backup_count() -> usize {
     7
 }
 
+fn default_version_history() -> usize {
+    5
+}
+
 fn default_iterations() -> u32 {
     100_000
 }
@@ -182,10 +310,46 @@ fn default_lock_timeout() -> u32 {
     300 // 5 minutes
 }
 
+fn default_session_timeout() -> u32 {
+    900 // 15 minutes
+}
+
+fn default_free_attempts() -> u32 {
+    3
+}
+
+fn default_backoff_base() -> u64 {
+    1000
+}
+
+fn default_backoff_max() -> u64 {
+    300
+}
+
+fn default_policy_path() -> String {
+    "/var/lib/vault/access.json".to_string()
+}
+
+fn default_herald_socket() -> String {
+    "/run/herald/herald.sock".to_string()
+}
+
+fn default_audit_log() -> String {
+    "/var/log/vault/audit.log".to_string()
+}
+
 fn default_socket_path() -> String {
     "/run/vault/vault.sock".to_string()
 }
 
+fn default_browser_socket() -> String {
+    "/run/vault/browser.sock".to_string()
+}
+
+fn default_browser_host() -> String {
+    "/usr/lib/vault/vault-browser-host".to_string()
+}
+
 fn default_log_level() -> String {
     "info".to_string()
 }
@@ -1,15 +1,23 @@
 //! vaultctl - Vault control utility
 
+mod access;
+mod backup;
+#[allow(dead_code)]
+mod browser;
 mod config;
 mod crypto;
 mod ipc;
+mod session;
 mod store;
+mod totp;
 
 use crate::ipc::{IpcClient, IpcRequest};
-use crate::store::SecretType;
+use crate::store::{Login, SecretType, ORIGIN_TAG};
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use std::io::{self, Write};
+use std::path::PathBuf;
+use uuid::Uuid;
 
 /// Vault control utility
 #[derive(Parser)]
@@ -52,6 +60,23 @@ enum Commands {
     Get {
         /// Secret name
         name: String,
+        /// Fetch a previous version
+        #[arg(long)]
+        version: Option<u32>,
+    },
+
+    /// List stored versions of a secret
+    Versions {
+        /// Secret name
+        name: String,
+    },
+
+    /// Restore a previous version of a secret
+    Rollback {
+        /// Secret name
+        name: String,
+        /// Version to restore
+        version: u32,
     },
 
     /// Delete a secret
@@ -69,12 +94,36 @@ enum Commands {
         tag: String,
     },
 
+    /// Store a website login for browser autofill
+    Login {
+        /// Secret name
+        name: String,
+        /// Username
+        #[arg(short, long)]
+        username: String,
+        /// Site the login is filled on (repeatable)
+        #[arg(short, long = "origin", required = true)]
+        origins: Vec<String>,
+    },
+
     /// Tag management
     Tag {
         #[command(subcommand)]
         command: TagCommands,
     },
 
+    /// TOTP tokens
+    Totp {
+        #[command(subcommand)]
+        command: TotpCommands,
+    },
+
+    /// Per-application access policies
+    Access {
+        #[command(subcommand)]
+        command: AccessCommands,
+    },
+
     /// Generate a random password
     Generate {
         /// Password length
@@ -85,6 +134,15 @@ enum Commands {
     /// Create backup
     Backup,
 
+    /// Restore from a backup snapshot
+    Restore {
+        /// Snapshot in the backup directory (path or file name)
+        path: PathBuf,
+        /// Only check that the snapshot is intact and decrypts
+        #[arg(long)]
+        verify_only: bool,
+    },
+
     /// Change master password
     ChangePassword,
 }
@@ -100,6 +158,82 @@ enum TagCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum TotpCommands {
+    /// Show the current code
+    Code {
+        /// Secret name
+        name: String,
+    },
+
+    /// Import a TOTP secret
+    Import {
+        /// Secret name
+        name: String,
+        /// otpauth:// URI
+        #[arg(long, conflicts_with = "qr")]
+        uri: Option<String>,
+        /// QR code image containing an otpauth:// URI (decoded with zbarimg)
+        #[arg(long)]
+        qr: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AccessCommands {
+    /// List remembered decisions
+    List,
+
+    /// List requests awaiting a decision
+    Pending,
+
+    /// Approve a pending request
+    Allow {
+        /// Request ID
+        id: Uuid,
+        /// Remember the decision
+        #[arg(long)]
+        remember: bool,
+        /// Allow the application to read every secret (or, for a browser,
+        /// to fill logins on every site)
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Deny a pending request
+    Deny {
+        /// Request ID
+        id: Uuid,
+        /// Remember the decision
+        #[arg(long)]
+        remember: bool,
+    },
+
+    /// Forget a remembered decision
+    Revoke {
+        /// Rule ID
+        id: Uuid,
+    },
+}
+
+/// Decode an otpauth URI from a QR code image
+fn decode_qr(path: &PathBuf) -> Result<String> {
+    let output = std::process::Command::new("zbarimg")
+        .args(["--raw", "--quiet"])
+        .arg(path)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("No QR code found in {}", path.display()));
+    }
+
+    String::from_utf8(output.stdout)?
+        .lines()
+        .find(|l| l.starts_with("otpauth://"))
+        .map(String::from)
+        .ok_or_else(|| anyhow::anyhow!("QR code does not contain an otpauth URI"))
+}
+
 fn read_password(prompt: &str) -> Result<String> {
     print!("{}", prompt);
     io::stdout().flush()?;
@@ -119,6 +253,8 @@ fn parse_secret_type(s: &str) -> SecretType {
         "sshkey" | "ssh_key" => SecretType::SshKey,
         "certificate" | "cert" => SecretType::Certificate,
         "token" => SecretType::Token,
+        "totp" => SecretType::Totp,
+        "login" => SecretType::Login,
         _ => SecretType::Generic,
     }
 }
@@ -198,6 +334,7 @@ async fn main() -> Result<()> {
             println!("Version:  {}", status.version);
             println!("Exists:   {}", if status.vault_exists { "yes" } else { "no" });
             println!("Unlocked: {}", if status.unlocked { "yes" } else { "no" });
+            println!("Sessions: {}", status.active_sessions);
 
             if let Some(stats) = status.stats {
                 println!();
@@ -236,14 +373,78 @@ async fn main() -> Result<()> {
             }
         }
 
-        Commands::Get { name } => {
+        Commands::Login {
+            name,
+            username,
+            origins,
+        } => {
+            let origins = origins
+                .iter()
+                .map(|o| browser::normalize_origin(o))
+                .collect::<Result<Vec<_>>>()?;
+            let login = Login {
+                username,
+                password: read_password(&format!("Enter password for '{}': ", name))?,
+            };
+
+            let saved = client
+                .send(IpcRequest::Set {
+                    name: name.clone(),
+                    value: login.to_value()?,
+                    secret_type: Some(SecretType::Login),
+                })
+                .await?;
+            if let ipc::IpcResponse::Error { message } = saved {
+                eprintln!("Error: {}", message);
+                return Ok(());
+            }
+
+            for origin in &origins {
+                let tag = format!("{}{}", ORIGIN_TAG, origin);
+                if let ipc::IpcResponse::Error { message } =
+                    client.send(IpcRequest::AddTag { name: name.clone(), tag }).await?
+                {
+                    eprintln!("Error: {}", message);
+                    return Ok(());
+                }
+            }
+            println!("Login '{}' saved for {}", name, origins.join(", "));
+        }
+
+        Commands::Get { name, version } => {
+            let request = match version {
+                Some(version) => IpcRequest::GetVersion { name, version },
+                None => IpcRequest::Get { name },
+            };
+
+            match client.send(request).await? {
+                ipc::IpcResponse::Success { data } => {
+                    let value = data["value"].as_str().unwrap_or("");
+                    println!("{}", value);
+                }
+                ipc::IpcResponse::Error { message } => {
+                    eprintln!("Error: {}", message);
+                }
+            }
+        }
+
+        Commands::Versions { name } => {
             match client
-                .send(IpcRequest::Get { name: name.clone() })
+                .send(IpcRequest::ListVersions { name: name.clone() })
                 .await?
             {
                 ipc::IpcResponse::Success { data } => {
-                    let value = data["value"].as_str().unwrap_or("");
-                    println!("{}", value);
+                    let versions: Vec<store::VersionInfo> = serde_json::from_value(data)?;
+                    println!("{:>8} {:<20} {:<12}", "VERSION", "WRITTEN", "TYPE");
+                    for v in versions {
+                        println!(
+                            "{:>8} {:<20} {:<12}{}",
+                            v.version,
+                            v.created_at.format("%Y-%m-%d %H:%M:%S"),
+                            format!("{:?}", v.secret_type),
+                            if v.current { " (current)" } else { "" }
+                        );
+                    }
                 }
                 ipc::IpcResponse::Error { message } => {
                     eprintln!("Error: {}", message);
@@ -251,6 +452,150 @@ async fn main() -> Result<()> {
             }
         }
 
+        Commands::Rollback { name, version } => {
+            match client
+                .send(IpcRequest::Rollback {
+                    name: name.clone(),
+                    version,
+                })
+                .await?
+            {
+                ipc::IpcResponse::Success { data } => {
+                    println!(
+                        "Secret '{}' restored from version {} (now version {})",
+                        name,
+                        version,
+                        data["version"].as_u64().unwrap_or(0)
+                    );
+                }
+                ipc::IpcResponse::Error { message } => {
+                    eprintln!("Error: {}", message);
+                }
+            }
+        }
+
+        Commands::Totp { command } => match command {
+            TotpCommands::Code { name } => {
+                match client.send(IpcRequest::GetTotp { name }).await? {
+                    ipc::IpcResponse::Success { data } => {
+                        let code: totp::TotpCode = serde_json::from_value(data)?;
+                        println!("{} (valid for {}s)", code.code, code.remaining_secs);
+                    }
+                    ipc::IpcResponse::Error { message } => {
+                        eprintln!("Error: {}", message);
+                    }
+                }
+            }
+
+            TotpCommands::Import { name, uri, qr } => {
+                let uri = match (uri, qr) {
+                    (Some(uri), _) => uri,
+                    (None, Some(path)) => decode_qr(&path)?,
+                    (None, None) => read_password("Enter otpauth URI: ")?,
+                };
+
+                match client
+                    .send(IpcRequest::ImportTotp {
+                        name: name.clone(),
+                        uri,
+                    })
+                    .await?
+                {
+                    ipc::IpcResponse::Success { .. } => {
+                        println!("TOTP secret '{}' imported", name);
+                    }
+                    ipc::IpcResponse::Error { message } => {
+                        eprintln!("Error: {}", message);
+                    }
+                }
+            }
+        },
+
+        Commands::Access { command } => match command {
+            AccessCommands::List => match client.send(IpcRequest::ListAccessRules).await? {
+                ipc::IpcResponse::Success { data } => {
+                    let rules: Vec<access::AccessRule> = serde_json::from_value(data)?;
+                    if rules.is_empty() {
+                        println!("No access decisions recorded");
+                    }
+                    for rule in rules {
+                        println!(
+                            "{} {:<5} uid={} {} -> {} ({:?}, {})",
+                            rule.id,
+                            if rule.allow { "allow" } else { "deny" },
+                            rule.app.uid,
+                            rule.app.exe,
+                            rule.secret,
+                            rule.source,
+                            rule.decided_at.format("%Y-%m-%d %H:%M:%S")
+                        );
+                    }
+                }
+                ipc::IpcResponse::Error { message } => {
+                    eprintln!("Error: {}", message);
+                }
+            },
+
+            AccessCommands::Pending => match client.send(IpcRequest::ListAccessRequests).await? {
+                ipc::IpcResponse::Success { data } => {
+                    let pending: Vec<access::PendingAccess> = serde_json::from_value(data)?;
+                    if pending.is_empty() {
+                        println!("No pending access requests");
+                    }
+                    for request in pending {
+                        println!(
+                            "{} uid={} {} wants '{}' ({})",
+                            request.id,
+                            request.app.uid,
+                            request.app.exe,
+                            request.secret,
+                            request.requested_at.format("%Y-%m-%d %H:%M:%S")
+                        );
+                    }
+                }
+                ipc::IpcResponse::Error { message } => {
+                    eprintln!("Error: {}", message);
+                }
+            },
+
+            AccessCommands::Allow { id, remember, all } => {
+                match client
+                    .send(IpcRequest::DecideAccess {
+                        id,
+                        allow: true,
+                        remember,
+                        all_secrets: all,
+                    })
+                    .await?
+                {
+                    ipc::IpcResponse::Success { .. } => println!("Access request {} allowed", id),
+                    ipc::IpcResponse::Error { message } => eprintln!("Error: {}", message),
+                }
+            }
+
+            AccessCommands::Deny { id, remember } => {
+                match client
+                    .send(IpcRequest::DecideAccess {
+                        id,
+                        allow: false,
+                        remember,
+                        all_secrets: false,
+                    })
+                    .await?
+                {
+                    ipc::IpcResponse::Success { .. } => println!("Access request {} denied", id),
+                    ipc::IpcResponse::Error { message } => eprintln!("Error: {}", message),
+                }
+            }
+
+            AccessCommands::Revoke { id } => {
+                match client.send(IpcRequest::RevokeAccess { id }).await? {
+                    ipc::IpcResponse::Success { .. } => println!("Access rule {} revoked", id),
+                    ipc::IpcResponse::Error { message } => eprintln!("Error: {}", message),
+                }
+            }
+        },
+
         Commands::Delete { name } => {
             print!("Delete secret '{}'? [y/N] ", name);
             io::stdout().flush()?;
@@ -371,6 +716,46 @@ async fn main() -> Result<()> {
             }
         }
 
+        Commands::Restore { path, verify_only } => {
+            if !verify_only {
+                print!("Replace the current vault with {}? [y/N] ", path.display());
+                io::stdout().flush()?;
+
+                let mut confirm = String::new();
+                io::stdin().read_line(&mut confirm)?;
+
+                if confirm.trim().to_lowercase() != "y" {
+                    println!("Cancelled");
+                    return Ok(());
+                }
+            }
+
+            // Relative to here if it exists; otherwise a name in the backup directory
+            let path = std::fs::canonicalize(&path).unwrap_or(path);
+            match client
+                .send(IpcRequest::Restore {
+                    path: path.to_string_lossy().to_string(),
+                    verify_only,
+                })
+                .await?
+            {
+                ipc::IpcResponse::Success { data } => {
+                    let report: backup::RestoreReport = serde_json::from_value(data)?;
+                    println!(
+                        "Snapshot from {} verified ({} secrets)",
+                        report.created_at.format("%Y-%m-%d %H:%M:%S"),
+                        report.secrets
+                    );
+                    if report.restored {
+                        println!("Vault restored");
+                    }
+                }
+                ipc::IpcResponse::Error { message } => {
+                    eprintln!("Error: {}", message);
+                }
+            }
+        }
+
         Commands::ChangePassword => {
             let old_password = read_password("Current password: ")?;
             let new_password = read_password("New password: ")?;
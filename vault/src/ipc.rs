@@ -1,11 +1,16 @@
 //! IPC interface for Vault
 
-use crate::store::{SecretMetadata, SecretType, VaultStats};
+use crate::access::AccessManager;
+use crate::backup::RestoreReport;
+use crate::session::Session;
+use crate::store::{SecretMetadata, SecretType, VaultStats, VersionInfo};
+use crate::totp::TotpCode;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
+use uuid::Uuid;
 
 /// IPC request types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,9 +42,48 @@ pub enum IpcRequest {
     /// Get a secret
     Get { name: String },
 
+    /// Get a specific version of a secret
+    GetVersion { name: String, version: u32 },
+
+    /// List stored versions of a secret
+    ListVersions { name: String },
+
+    /// Restore a previous version
+    Rollback { name: String, version: u32 },
+
+    /// Set how many previous versions to keep for a secret
+    SetVersionDepth { name: String, depth: Option<usize> },
+
+    /// Get the current code for a TOTP secret
+    GetTotp { name: String },
+
+    /// Import a TOTP secret from an otpauth URI
+    ImportTotp { name: String, uri: String },
+
     /// Delete a secret
     Delete { name: String },
 
+    /// List remembered per-application access decisions
+    ListAccessRules,
+
+    /// List access requests awaiting a decision
+    ListAccessRequests,
+
+    /// Approve or deny a pending access request
+    DecideAccess {
+        id: Uuid,
+        allow: bool,
+        /// Persist the decision
+        #[serde(default)]
+        remember: bool,
+        /// Apply to every secret rather than just the one requested
+        #[serde(default)]
+        all_secrets: bool,
+    },
+
+    /// Forget a remembered access decision
+    RevokeAccess { id: Uuid },
+
     /// List secrets
     List,
 
@@ -61,6 +105,13 @@ pub enum IpcRequest {
     /// Create backup
     Backup,
 
+    /// Restore from a backup snapshot, or only check that it is intact
+    Restore {
+        path: String,
+        #[serde(default)]
+        verify_only: bool,
+    },
+
     /// Generate password
     GeneratePassword { length: Option<usize> },
 
@@ -71,6 +122,75 @@ pub enum IpcRequest {
     GetStatus,
 }
 
+impl IpcRequest {
+    /// Whether the request needs a valid unlock session for the client
+    fn requires_session(&self) -> bool {
+        matches!(
+            self,
+            Self::Set { .. }
+                | Self::Get { .. }
+                | Self::GetVersion { .. }
+                | Self::ListVersions { .. }
+                | Self::Rollback { .. }
+                | Self::SetVersionDepth { .. }
+                | Self::GetTotp { .. }
+                | Self::ImportTotp { .. }
+                | Self::Delete { .. }
+                | Self::ListAccessRules
+                | Self::ListAccessRequests
+                | Self::DecideAccess { .. }
+                | Self::RevokeAccess { .. }
+                | Self::List
+                | Self::SearchByTag { .. }
+                | Self::AddTag { .. }
+                | Self::SetNotes { .. }
+                | Self::Backup
+                | Self::Restore { .. }
+                | Self::Stats
+        )
+    }
+
+    /// Whether the request reads or changes access decisions, which only
+    /// administrators may do
+    fn manages_access(&self) -> bool {
+        matches!(
+            self,
+            Self::ListAccessRules
+                | Self::ListAccessRequests
+                | Self::DecideAccess { .. }
+                | Self::RevokeAccess { .. }
+        )
+    }
+
+    /// Secret whose value the request discloses, subject to per-app policy
+    fn secret_read(&self) -> Option<&str> {
+        match self {
+            Self::Get { name } | Self::GetVersion { name, .. } | Self::GetTotp { name } => {
+                Some(name)
+            }
+            _ => None,
+        }
+    }
+
+    /// Secret the request changes or removes, subject to per-app policy;
+    /// "*" when it replaces the whole vault
+    fn secret_write(&self) -> Option<&str> {
+        match self {
+            Self::Set { name, .. }
+            | Self::Rollback { name, .. }
+            | Self::SetVersionDepth { name, .. }
+            | Self::ImportTotp { name, .. }
+            | Self::Delete { name }
+            | Self::AddTag { name, .. }
+            | Self::SetNotes { name, .. } => Some(name),
+            Self::Restore {
+                verify_only: false, ..
+            } => Some("*"),
+            _ => None,
+        }
+    }
+}
+
 /// IPC response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "status")]
@@ -85,18 +205,36 @@ pub struct DaemonStatus {
     pub version: String,
     pub vault_exists: bool,
     pub unlocked: bool,
+    #[serde(default)]
+    pub active_sessions: usize,
     pub stats: Option<VaultStats>,
 }
 
+/// Identity of a connected client, from `SO_PEERCRED`
+#[derive(Debug, Clone, Copy)]
+pub struct ClientId {
+    pub uid: u32,
+    pub pid: Option<i32>,
+}
+
 /// IPC handler trait
 pub trait IpcHandler: Send + Sync {
     fn exists(&self) -> bool;
-    fn initialize(&self, password: &str) -> Result<()>;
-    fn unlock(&self, password: &str) -> Result<()>;
-    fn lock(&self);
+    fn initialize(&self, client: &ClientId, password: &str) -> Result<()>;
+    fn unlock(&self, client: &ClientId, password: &str) -> Result<()>;
+    fn lock(&self, client: &ClientId) -> Result<()>;
     fn is_unlocked(&self) -> bool;
+    fn authorize(&self, client: &ClientId) -> Result<()>;
+    fn session(&self, client: &ClientId) -> Option<Session>;
+    fn access(&self) -> &AccessManager;
     fn set(&self, name: &str, value: &str, secret_type: SecretType) -> Result<()>;
     fn get(&self, name: &str) -> Result<String>;
+    fn get_version(&self, name: &str, version: u32) -> Result<String>;
+    fn list_versions(&self, name: &str) -> Result<Vec<VersionInfo>>;
+    fn rollback(&self, name: &str, version: u32) -> Result<u32>;
+    fn set_version_depth(&self, name: &str, depth: Option<usize>) -> Result<()>;
+    fn totp(&self, name: &str) -> Result<TotpCode>;
+    fn import_totp(&self, name: &str, uri: &str) -> Result<()>;
     fn delete(&self, name: &str) -> Result<()>;
     fn list(&self) -> Result<Vec<SecretMetadata>>;
     fn search_by_tag(&self, tag: &str) -> Result<Vec<SecretMetadata>>;
@@ -104,6 +242,7 @@ pub trait IpcHandler: Send + Sync {
     fn set_notes(&self, name: &str, notes: Option<String>) -> Result<()>;
     fn change_password(&self, old: &str, new: &str) -> Result<()>;
     fn backup(&self) -> Result<String>;
+    fn restore(&self, path: &str, verify_only: bool) -> Result<RestoreReport>;
     fn generate_password(&self, length: usize) -> Result<String>;
     fn stats(&self) -> Result<VaultStats>;
     fn get_status(&self) -> DaemonStatus;
@@ -116,10 +255,10 @@ pub struct IpcServer<H: IpcHandler> {
 }
 
 impl<H: IpcHandler + 'static> IpcServer<H> {
-    pub fn new(socket_path: impl Into<String>, handler: H) -> Self {
+    pub fn new(socket_path: impl Into<String>, handler: Arc<H>) -> Self {
         Self {
             socket_path: socket_path.into(),
-            handler: Arc::new(handler),
+            handler,
         }
     }
 
@@ -152,14 +291,34 @@ impl<H: IpcHandler + 'static> IpcServer<H> {
     }
 }
 
-async fn handle_client<H: IpcHandler>(stream: UnixStream, handler: Arc<H>) -> Result<()> {
+async fn handle_client<H: IpcHandler + 'static>(stream: UnixStream, handler: Arc<H>) -> Result<()> {
+    let cred = stream.peer_cred()?;
+    let client = ClientId {
+        uid: cred.uid(),
+        pid: cred.pid(),
+    };
+
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
 
     while reader.read_line(&mut line).await? > 0 {
         let response = match serde_json::from_str::<IpcRequest>(&line) {
-            Ok(request) => process_request(request, handler.as_ref()),
+            Ok(request) => match authorize_request(&request, handler.as_ref(), &client).await {
+                // Snapshots and uploads block; keep them off the runtime
+                Ok(()) if matches!(request, IpcRequest::Backup) => {
+                    let handler = Arc::clone(&handler);
+                    tokio::task::spawn_blocking(move || process_request(request, handler.as_ref(), &client))
+                        .await
+                        .unwrap_or_else(|e| IpcResponse::Error {
+                            message: format!("Backup task failed: {}", e),
+                        })
+                }
+                Ok(()) => process_request(request, handler.as_ref(), &client),
+                Err(e) => IpcResponse::Error {
+                    message: e.to_string(),
+                },
+            },
             Err(e) => IpcResponse::Error {
                 message: format!("Invalid request: {}", e),
             },
@@ -176,13 +335,44 @@ async fn handle_client<H: IpcHandler>(stream: UnixStream, handler: Arc<H>) -> Re
     Ok(())
 }
 
-fn process_request<H: IpcHandler>(request: IpcRequest, handler: &H) -> IpcResponse {
+/// Apply per-application policy to requests that disclose, change or remove
+/// a secret, and keep access management to administrators
+async fn authorize_request<H: IpcHandler>(request: &IpcRequest, handler: &H, client: &ClientId) -> Result<()> {
+    if request.manages_access() {
+        // An application waiting for approval must not be able to grant it
+        handler.authorize(client)?;
+        return handler.access().authorize_admin(client).await;
+    }
+
+    if let Some(name) = request.secret_write() {
+        handler.authorize(client)?;
+        return handler.access().authorize_write(client, name).await;
+    }
+
+    let Some(name) = request.secret_read() else {
+        return Ok(());
+    };
+
+    // Session first, so locked clients are not prompted for
+    handler.authorize(client)?;
+    handler.access().authorize_read(client, name).await
+}
+
+fn process_request<H: IpcHandler>(request: IpcRequest, handler: &H, client: &ClientId) -> IpcResponse {
+    if request.requires_session() {
+        if let Err(e) = handler.authorize(client) {
+            return IpcResponse::Error {
+                message: e.to_string(),
+            };
+        }
+    }
+
     match request {
         IpcRequest::Exists => IpcResponse::Success {
             data: serde_json::json!({"exists": handler.exists()}),
         },
 
-        IpcRequest::Initialize { password } => match handler.initialize(&password) {
+        IpcRequest::Initialize { password } => match handler.initialize(client, &password) {
             Ok(()) => IpcResponse::Success {
                 data: serde_json::json!({"initialized": true}),
             },
@@ -191,24 +381,32 @@ fn process_request<H: IpcHandler>(request: IpcRequest, handler: &H) -> IpcRespon
             },
         },
 
-        IpcRequest::Unlock { password } => match handler.unlock(&password) {
+        IpcRequest::Unlock { password } => match handler.unlock(client, &password) {
             Ok(()) => IpcResponse::Success {
-                data: serde_json::json!({"unlocked": true}),
+                data: serde_json::json!({
+                    "unlocked": true,
+                    "session_expires_at": handler.session(client).map(|s| s.expires_at),
+                }),
             },
             Err(e) => IpcResponse::Error {
                 message: e.to_string(),
             },
         },
 
-        IpcRequest::Lock => {
-            handler.lock();
-            IpcResponse::Success {
-                data: serde_json::json!({"locked": true}),
-            }
-        }
+        IpcRequest::Lock => match handler.lock(client) {
+            Ok(()) => IpcResponse::Success {
+                data: serde_json::json!({"locked": !handler.is_unlocked()}),
+            },
+            Err(e) => IpcResponse::Error {
+                message: e.to_string(),
+            },
+        },
 
         IpcRequest::IsUnlocked => IpcResponse::Success {
-            data: serde_json::json!({"unlocked": handler.is_unlocked()}),
+            data: serde_json::json!({
+                "unlocked": handler.is_unlocked(),
+                "session_expires_at": handler.session(client).map(|s| s.expires_at),
+            }),
         },
 
         IpcRequest::Set {
@@ -235,6 +433,62 @@ fn process_request<H: IpcHandler>(request: IpcRequest, handler: &H) -> IpcRespon
             },
         },
 
+        IpcRequest::GetVersion { name, version } => match handler.get_version(&name, version) {
+            Ok(value) => IpcResponse::Success {
+                data: serde_json::json!({"name": name, "version": version, "value": value}),
+            },
+            Err(e) => IpcResponse::Error {
+                message: e.to_string(),
+            },
+        },
+
+        IpcRequest::ListVersions { name } => match handler.list_versions(&name) {
+            Ok(versions) => IpcResponse::Success {
+                data: serde_json::to_value(versions).unwrap(),
+            },
+            Err(e) => IpcResponse::Error {
+                message: e.to_string(),
+            },
+        },
+
+        IpcRequest::Rollback { name, version } => match handler.rollback(&name, version) {
+            Ok(current) => IpcResponse::Success {
+                data: serde_json::json!({"name": name, "restored": version, "version": current}),
+            },
+            Err(e) => IpcResponse::Error {
+                message: e.to_string(),
+            },
+        },
+
+        IpcRequest::SetVersionDepth { name, depth } => {
+            match handler.set_version_depth(&name, depth) {
+                Ok(()) => IpcResponse::Success {
+                    data: serde_json::json!({"name": name, "depth": depth}),
+                },
+                Err(e) => IpcResponse::Error {
+                    message: e.to_string(),
+                },
+            }
+        }
+
+        IpcRequest::GetTotp { name } => match handler.totp(&name) {
+            Ok(code) => IpcResponse::Success {
+                data: serde_json::to_value(code).unwrap(),
+            },
+            Err(e) => IpcResponse::Error {
+                message: e.to_string(),
+            },
+        },
+
+        IpcRequest::ImportTotp { name, uri } => match handler.import_totp(&name, &uri) {
+            Ok(()) => IpcResponse::Success {
+                data: serde_json::json!({"saved": name}),
+            },
+            Err(e) => IpcResponse::Error {
+                message: e.to_string(),
+            },
+        },
+
         IpcRequest::Delete { name } => match handler.delete(&name) {
             Ok(()) => IpcResponse::Success {
                 data: serde_json::json!({"deleted": name}),
@@ -244,6 +498,37 @@ fn process_request<H: IpcHandler>(request: IpcRequest, handler: &H) -> IpcRespon
             },
         },
 
+        IpcRequest::ListAccessRules => IpcResponse::Success {
+            data: serde_json::to_value(handler.access().rules()).unwrap(),
+        },
+
+        IpcRequest::ListAccessRequests => IpcResponse::Success {
+            data: serde_json::to_value(handler.access().pending()).unwrap(),
+        },
+
+        IpcRequest::DecideAccess {
+            id,
+            allow,
+            remember,
+            all_secrets,
+        } => match handler.access().decide(id, allow, remember, all_secrets) {
+            Ok(()) => IpcResponse::Success {
+                data: serde_json::json!({"id": id, "allow": allow}),
+            },
+            Err(e) => IpcResponse::Error {
+                message: e.to_string(),
+            },
+        },
+
+        IpcRequest::RevokeAccess { id } => match handler.access().revoke(id) {
+            Ok(()) => IpcResponse::Success {
+                data: serde_json::json!({"revoked": id}),
+            },
+            Err(e) => IpcResponse::Error {
+                message: e.to_string(),
+            },
+        },
+
         IpcRequest::List => match handler.list() {
             Ok(secrets) => IpcResponse::Success {
                 data: serde_json::to_value(secrets).unwrap(),
@@ -301,6 +586,15 @@ fn process_request<H: IpcHandler>(request: IpcRequest, handler: &H) -> IpcRespon
             },
         },
 
+        IpcRequest::Restore { path, verify_only } => match handler.restore(&path, verify_only) {
+            Ok(report) => IpcResponse::Success {
+                data: serde_json::to_value(report).unwrap(),
+            },
+            Err(e) => IpcResponse::Error {
+                message: e.to_string(),
+            },
+        },
+
         IpcRequest::GeneratePassword { length } => {
             match handler.generate_password(length.unwrap_or(20)) {
                 Ok(password) => IpcResponse::Success {
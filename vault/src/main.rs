@@ -4,22 +4,40 @@
 //! - Encrypted secret storage
 //! - Password generation
 //! - Secure credential management
-//! - Automatic backups
-
+//! - Secret version history and rollback
+//! - TOTP code generation
+//! - Scheduled encrypted backups with remote targets and verified restore
+//! - Auto-lock on inactivity and per-client unlock sessions
+//! - Per-application access policies
+//! - Browser autofill bridge with per-site approval
+
+mod access;
+mod audit;
+mod backup;
+mod browser;
 mod config;
 mod crypto;
 mod ipc;
+mod session;
 mod store;
+mod totp;
 
+use crate::access::AccessManager;
+use crate::audit::{AuditEvent, AuditLog};
+use crate::backup::{BackupManager, RestoreReport};
+use crate::browser::BridgeServer;
 use crate::config::VaultConfig;
 use crate::crypto::CryptoEngine;
-use crate::ipc::{DaemonStatus, IpcHandler, IpcServer};
-use crate::store::{SecretMetadata, SecretStore, SecretType, VaultStats};
+use crate::ipc::{ClientId, DaemonStatus, IpcHandler, IpcServer};
+use crate::session::{Session, SessionKey, SessionManager};
+use crate::store::{Login, SecretMetadata, SecretStore, SecretType, VaultStats, VersionInfo};
+use crate::totp::{TotpCode, TotpParams};
 use anyhow::Result;
 use clap::Parser;
 use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
-use tracing::info;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+use tracing::{info, warn};
 
 /// Vault - Secrets management daemon
 #[derive(Parser, Debug)]
@@ -43,6 +61,11 @@ struct VaultState {
     config: VaultConfig,
     store: RwLock<SecretStore>,
     crypto: CryptoEngine,
+    sessions: Mutex<SessionManager>,
+    audit: AuditLog,
+    access: AccessManager,
+    backups: BackupManager,
+    last_activity: Mutex<Instant>,
 }
 
 impl VaultState {
@@ -51,11 +74,36 @@ impl VaultState {
         let store = SecretStore::new(config.storage.clone(), CryptoEngine::new(config.encryption.clone()));
 
         Self {
-            config,
             store: RwLock::new(store),
             crypto,
+            sessions: Mutex::new(SessionManager::new(config.access.clone())),
+            audit: AuditLog::new(&config.access.audit_log),
+            access: AccessManager::new(config.access.clone()),
+            backups: BackupManager::new(config.storage.clone()),
+            last_activity: Mutex::new(Instant::now()),
+            config,
         }
     }
+
+    /// Record activity for idle detection
+    fn touch(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    /// Lock the vault and drop all sessions
+    fn lock_all(&self) {
+        self.store.write().unwrap().lock();
+        self.sessions.lock().unwrap().clear();
+    }
+
+    /// Session key for a client: its user and executable
+    fn session_key(&self, client: &ClientId) -> Result<SessionKey> {
+        let app = self.access.identify(client)?;
+        Ok(SessionKey {
+            uid: client.uid,
+            exe_hash: app.exe_hash,
+        })
+    }
 }
 
 impl IpcHandler for VaultState {
@@ -63,23 +111,101 @@ impl IpcHandler for VaultState {
         self.store.read().unwrap().exists()
     }
 
-    fn initialize(&self, password: &str) -> Result<()> {
-        self.store.write().unwrap().initialize(password.as_bytes())
+    fn initialize(&self, client: &ClientId, password: &str) -> Result<()> {
+        let key = self.session_key(client)?;
+        self.store.write().unwrap().initialize(password.as_bytes())?;
+        self.sessions.lock().unwrap().open(key);
+        self.touch();
+        Ok(())
     }
 
-    fn unlock(&self, password: &str) -> Result<()> {
-        self.store.write().unwrap().unlock(password.as_bytes())
+    fn unlock(&self, client: &ClientId, password: &str) -> Result<()> {
+        let key = self.session_key(client)?;
+
+        // One attempt per UID at a time, so concurrent attempts cannot slip
+        // past the backoff; the key derivation runs without the session lock
+        if let Err(e) = self.sessions.lock().unwrap().begin_attempt(client.uid) {
+            self.audit.record(AuditEvent::UnlockThrottled {
+                uid: client.uid,
+                pid: client.pid,
+            });
+            return Err(e);
+        }
+
+        let result = self.store.write().unwrap().unlock(password.as_bytes());
+        let mut sessions = self.sessions.lock().unwrap();
+        match result {
+            Ok(()) => {
+                sessions.open(key);
+                self.touch();
+                self.audit.record(AuditEvent::Unlocked {
+                    uid: client.uid,
+                    pid: client.pid,
+                });
+                Ok(())
+            }
+            Err(e) => {
+                let (attempts, backoff) = sessions.record_failure(client.uid);
+                self.audit.record(AuditEvent::UnlockFailed {
+                    uid: client.uid,
+                    pid: client.pid,
+                    attempts,
+                    backoff_ms: backoff.map(|d| d.as_millis() as u64),
+                });
+                Err(e)
+            }
+        }
     }
 
-    fn lock(&self) {
-        self.store.write().unwrap().lock()
+    fn lock(&self, client: &ClientId) -> Result<()> {
+        // Root locks the vault outright; anyone else ends their own session
+        // and the vault locks once no session is left
+        if client.uid == 0 {
+            self.lock_all();
+        } else {
+            let key = self.session_key(client)?;
+            let mut sessions = self.sessions.lock().unwrap();
+            if !sessions.close(&key) {
+                return Err(anyhow::anyhow!("No unlock session for this client"));
+            }
+            if sessions.active_count() == 0 {
+                self.store.write().unwrap().lock();
+            }
+        }
+        self.audit.record(AuditEvent::Locked { uid: client.uid });
+        Ok(())
     }
 
     fn is_unlocked(&self) -> bool {
         self.store.read().unwrap().is_unlocked()
     }
 
+    fn authorize(&self, client: &ClientId) -> Result<()> {
+        if !self.is_unlocked() {
+            return Err(anyhow::anyhow!("Vault is locked"));
+        }
+        let key = self.session_key(client)?;
+        self.sessions.lock().unwrap().touch(&key)?;
+        self.touch();
+        Ok(())
+    }
+
+    fn session(&self, client: &ClientId) -> Option<Session> {
+        let key = self.session_key(client).ok()?;
+        self.sessions.lock().unwrap().get(&key).cloned()
+    }
+
+    fn access(&self) -> &AccessManager {
+        &self.access
+    }
+
     fn set(&self, name: &str, value: &str, secret_type: SecretType) -> Result<()> {
+        if secret_type == SecretType::Totp {
+            return self.import_totp(name, value);
+        }
+        if secret_type == SecretType::Login {
+            Login::from_value(value)?;
+        }
         self.store.write().unwrap().set(name, value, secret_type)
     }
 
@@ -87,6 +213,40 @@ impl IpcHandler for VaultState {
         self.store.write().unwrap().get(name)
     }
 
+    fn get_version(&self, name: &str, version: u32) -> Result<String> {
+        self.store.read().unwrap().get_version(name, version)
+    }
+
+    fn list_versions(&self, name: &str) -> Result<Vec<VersionInfo>> {
+        self.store.read().unwrap().list_versions(name)
+    }
+
+    fn rollback(&self, name: &str, version: u32) -> Result<u32> {
+        self.store.write().unwrap().rollback(name, version)
+    }
+
+    fn set_version_depth(&self, name: &str, depth: Option<usize>) -> Result<()> {
+        self.store.write().unwrap().set_max_versions(name, depth)
+    }
+
+    fn totp(&self, name: &str) -> Result<TotpCode> {
+        let mut store = self.store.write().unwrap();
+        if store.metadata(name)?.secret_type != SecretType::Totp {
+            return Err(anyhow::anyhow!("Secret '{}' is not a TOTP secret", name));
+        }
+
+        let uri = store.get(name)?;
+        TotpParams::from_uri(&uri)?.generate()
+    }
+
+    fn import_totp(&self, name: &str, uri: &str) -> Result<()> {
+        let params = TotpParams::from_uri(uri)?;
+        self.store
+            .write()
+            .unwrap()
+            .set(name, &params.to_uri(), SecretType::Totp)
+    }
+
     fn delete(&self, name: &str) -> Result<()> {
         self.store.write().unwrap().delete(name)
     }
@@ -115,7 +275,33 @@ impl IpcHandler for VaultState {
     }
 
     fn backup(&self) -> Result<String> {
-        self.store.read().unwrap().backup()
+        let snapshot = self.store.read().unwrap().snapshot()?;
+        let path = self.backups.create(&snapshot)?;
+        if let Err(e) = self.backups.push_remote(&path) {
+            warn!("{}", e);
+        }
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    fn restore(&self, path: &str, verify_only: bool) -> Result<RestoreReport> {
+        let (header, snapshot) = self.backups.open(&self.backups.locate(path)?)?;
+        let mut store = self.store.write().unwrap();
+        let secrets = store.verify_snapshot(&snapshot)?;
+
+        if !verify_only {
+            // Keep the current state recoverable
+            let current = store.snapshot()?;
+            let safety = self.backups.create(&current)?;
+            info!("Pre-restore snapshot saved to {:?}", safety);
+            store.restore(&snapshot)?;
+        }
+
+        Ok(RestoreReport {
+            path: path.to_string(),
+            created_at: header.created_at,
+            secrets,
+            restored: !verify_only,
+        })
     }
 
     fn generate_password(&self, length: usize) -> Result<String> {
@@ -138,6 +324,7 @@ impl IpcHandler for VaultState {
             version: env!("CARGO_PKG_VERSION").to_string(),
             vault_exists: store.exists(),
             unlocked: store.is_unlocked(),
+            active_sessions: self.sessions.lock().unwrap().active_count(),
             stats,
         }
     }
@@ -157,16 +344,86 @@ async fn main() -> Result<()> {
     let config = VaultConfig::load(&args.config)?;
     let state = Arc::new(VaultState::new(config));
 
+    // Start auto-lock task
+    let lock_state = Arc::clone(&state);
+    tokio::spawn(async move {
+        auto_lock_loop(lock_state).await;
+    });
+
+    // Start scheduled backup task
+    if state.config.storage.auto_backup && state.config.storage.backup_interval_hours > 0 {
+        let backup_state = Arc::clone(&state);
+        tokio::spawn(async move {
+            backup_loop(backup_state).await;
+        });
+    }
+
+    // Start browser bridge
+    if state.config.browser.enabled {
+        let bridge = BridgeServer::new(state.config.browser.clone(), Arc::clone(&state));
+        tokio::spawn(async move {
+            if let Err(e) = bridge.run().await {
+                warn!("Browser bridge stopped: {}", e);
+            }
+        });
+    }
+
     // Start IPC server
     let socket_path = args.socket.to_string_lossy().to_string();
-    let server = IpcServer::new(socket_path, Arc::try_unwrap(state).unwrap_or_else(|arc| (*arc).clone()));
+    let server = IpcServer::new(socket_path, state);
 
     info!("Vault ready");
     server.run().await
 }
 
-impl Clone for VaultState {
-    fn clone(&self) -> Self {
-        Self::new(self.config.clone())
+/// Take encrypted snapshots on the configured interval
+async fn backup_loop(state: Arc<VaultState>) {
+    use tokio::time::{interval, Duration};
+
+    let hours = state.config.storage.backup_interval_hours as u64;
+    let mut interval = interval(Duration::from_secs(hours * 3600));
+    // The first tick fires immediately; skip it so startup does not snapshot
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+
+        if !state.exists() {
+            continue;
+        }
+
+        let task_state = Arc::clone(&state);
+        let result = tokio::task::spawn_blocking(move || task_state.backup()).await;
+        match result {
+            Ok(Ok(path)) => info!("Scheduled backup written to {}", path),
+            Ok(Err(e)) => warn!("Scheduled backup failed: {}", e),
+            Err(e) => warn!("Scheduled backup task failed: {}", e),
+        }
+    }
+}
+
+/// Expire client sessions and lock the vault after inactivity
+async fn auto_lock_loop(state: Arc<VaultState>) {
+    use tokio::time::{interval, Duration};
+
+    let timeout = state.config.encryption.auto_lock_timeout_secs as u64;
+    let mut interval = interval(Duration::from_secs(10));
+
+    loop {
+        interval.tick().await;
+
+        for session in state.sessions.lock().unwrap().expire() {
+            state.audit.record(AuditEvent::SessionExpired { uid: session.uid });
+        }
+
+        if timeout == 0 || !state.is_unlocked() {
+            continue;
+        }
+
+        let idle = state.last_activity.lock().unwrap().elapsed().as_secs();
+        if idle >= timeout {
+            state.lock_all();
+            state.audit.record(AuditEvent::AutoLocked { idle_secs: idle });
+        }
     }
 }
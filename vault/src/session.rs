@@ -0,0 +1,259 @@
+//! Unlock sessions and attempt throttling
+//!
+//! Each client (a UID running a given executable, identified by its hash)
+//! that unlocks the vault gets its own session with an expiry, so one
+//! program cannot use or end another's session. Sessions are not tied to a
+//! PID: every `vaultctl` command is a new process, and `vaultctl unlock`
+//! must carry over to the commands that follow it. Failed unlock attempts
+//! are tracked per UID, since a new process is cheap to spawn, and
+//! throttled with exponential backoff; one attempt per UID runs at a time.
+
+use crate::config::AccessConfig;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// Client a session belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SessionKey {
+    pub uid: u32,
+    /// SHA-256 of the client executable, so other programs of the same
+    /// user do not share the session
+    pub exe_hash: String,
+}
+
+/// Unlock session for a single client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// Client UID
+    pub uid: u32,
+    /// When the session was created
+    pub created_at: DateTime<Utc>,
+    /// When the session expires
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Failed attempt tracking for a client
+#[derive(Debug, Clone)]
+struct FailureState {
+    count: u32,
+    retry_after: Option<Instant>,
+}
+
+/// Session and throttling manager
+pub struct SessionManager {
+    config: AccessConfig,
+    sessions: HashMap<SessionKey, Session>,
+    failures: HashMap<u32, FailureState>,
+    attempting: HashSet<u32>,
+}
+
+impl SessionManager {
+    /// Create new session manager
+    pub fn new(config: AccessConfig) -> Self {
+        Self {
+            config,
+            sessions: HashMap::new(),
+            failures: HashMap::new(),
+            attempting: HashSet::new(),
+        }
+    }
+
+    /// Check whether a client may attempt to unlock right now
+    pub fn check_throttle(&self, uid: u32) -> Result<()> {
+        if let Some(retry_after) = self.failures.get(&uid).and_then(|f| f.retry_after) {
+            let now = Instant::now();
+            if now < retry_after {
+                let wait = retry_after - now;
+                return Err(anyhow!(
+                    "Too many failed unlock attempts; retry in {}s",
+                    wait.as_secs().max(1)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Start an unlock attempt for a UID
+    ///
+    /// Fails while the UID is throttled or another of its attempts is still
+    /// running. The attempt ends with `open` or `record_failure`, so the key
+    /// derivation can run without holding the manager.
+    pub fn begin_attempt(&mut self, uid: u32) -> Result<()> {
+        self.check_throttle(uid)?;
+        if !self.attempting.insert(uid) {
+            return Err(anyhow!("Another unlock attempt is in progress"));
+        }
+        Ok(())
+    }
+
+    /// Record a failed unlock attempt, returning the imposed delay
+    pub fn record_failure(&mut self, uid: u32) -> (u32, Option<Duration>) {
+        self.attempting.remove(&uid);
+        let state = self.failures.entry(uid).or_insert(FailureState {
+            count: 0,
+            retry_after: None,
+        });
+        state.count += 1;
+
+        let delay = backoff_delay(
+            state.count,
+            self.config.free_unlock_attempts,
+            self.config.unlock_backoff_base_ms,
+            self.config.unlock_backoff_max_secs,
+        );
+        state.retry_after = delay.map(|d| Instant::now() + d);
+
+        (state.count, delay)
+    }
+
+    /// Open (or renew) a session after a successful unlock
+    pub fn open(&mut self, key: SessionKey) -> Session {
+        self.failures.remove(&key.uid);
+        self.attempting.remove(&key.uid);
+
+        let now = Utc::now();
+        let session = Session {
+            uid: key.uid,
+            created_at: now,
+            expires_at: now + chrono::Duration::seconds(self.config.session_timeout_secs as i64),
+        };
+        self.sessions.insert(key, session.clone());
+        session
+    }
+
+    /// End a client's session, returning whether it had one
+    pub fn close(&mut self, key: &SessionKey) -> bool {
+        self.sessions.remove(key).is_some()
+    }
+
+    /// Validate a client's session, extending it on use
+    pub fn touch(&mut self, key: &SessionKey) -> Result<()> {
+        let now = Utc::now();
+        let timeout = chrono::Duration::seconds(self.config.session_timeout_secs as i64);
+
+        match self.sessions.get_mut(key) {
+            Some(session) if session.expires_at > now => {
+                session.expires_at = now + timeout;
+                Ok(())
+            }
+            Some(_) => {
+                self.sessions.remove(key);
+                Err(anyhow!("Unlock session expired; unlock again"))
+            }
+            None => Err(anyhow!("No unlock session for this client; unlock first")),
+        }
+    }
+
+    /// Get a client's session, if still valid
+    pub fn get(&self, key: &SessionKey) -> Option<&Session> {
+        self.sessions
+            .get(key)
+            .filter(|s| s.expires_at > Utc::now())
+    }
+
+    /// Drop expired sessions, returning the sessions that expired
+    pub fn expire(&mut self) -> Vec<Session> {
+        let now = Utc::now();
+        let mut expired = Vec::new();
+        self.sessions.retain(|_, session| {
+            let live = session.expires_at > now;
+            if !live {
+                expired.push(session.clone());
+            }
+            live
+        });
+        expired
+    }
+
+    /// Number of live sessions
+    pub fn active_count(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Drop all sessions (vault locked)
+    pub fn clear(&mut self) {
+        self.sessions.clear();
+    }
+}
+
+/// Delay before the next attempt after `failures` consecutive failures
+fn backoff_delay(failures: u32, free_attempts: u32, base_ms: u64, max_secs: u64) -> Option<Duration> {
+    if failures <= free_attempts {
+        return None;
+    }
+
+    let exponent = (failures - free_attempts - 1).min(20);
+    let delay_ms = base_ms.saturating_mul(1u64 << exponent);
+    Some(Duration::from_millis(delay_ms).min(Duration::from_secs(max_secs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay() {
+        assert_eq!(backoff_delay(3, 3, 1000, 300), None);
+        assert_eq!(backoff_delay(4, 3, 1000, 300), Some(Duration::from_secs(1)));
+        assert_eq!(backoff_delay(6, 3, 1000, 300), Some(Duration::from_secs(4)));
+        assert_eq!(backoff_delay(40, 3, 1000, 300), Some(Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn test_throttle_and_session() {
+        let config = AccessConfig {
+            free_unlock_attempts: 1,
+            ..AccessConfig::default()
+        };
+        let mut sessions = SessionManager::new(config);
+        let client = key(1000, "aa");
+
+        assert!(sessions.begin_attempt(1000).is_ok());
+        assert!(sessions.begin_attempt(1000).is_err());
+        sessions.record_failure(1000);
+        assert!(sessions.begin_attempt(1000).is_ok());
+        sessions.record_failure(1000);
+        assert!(sessions.check_throttle(1000).is_err());
+        assert!(sessions.begin_attempt(1000).is_err());
+
+        // Other users are unaffected
+        assert!(sessions.check_throttle(1001).is_ok());
+        assert!(sessions.touch(&key(1001, "aa")).is_err());
+
+        sessions.open(client.clone());
+        assert!(sessions.check_throttle(1000).is_ok());
+        assert!(sessions.touch(&client).is_ok());
+        assert_eq!(sessions.active_count(), 1);
+
+        sessions.clear();
+        assert!(sessions.touch(&client).is_err());
+    }
+
+    #[test]
+    fn test_sessions_are_per_client() {
+        let mut sessions = SessionManager::new(AccessConfig::default());
+        let client = key(1000, "aa");
+        sessions.open(client.clone());
+
+        // Another process of the same program shares it; another program
+        // or user does not
+        assert!(sessions.touch(&key(1000, "aa")).is_ok());
+        let other = key(1000, "bb");
+        assert!(sessions.touch(&other).is_err());
+        assert!(sessions.touch(&key(1001, "aa")).is_err());
+        assert!(!sessions.close(&other));
+
+        assert!(sessions.close(&client));
+        assert!(sessions.touch(&client).is_err());
+    }
+
+    fn key(uid: u32, exe_hash: &str) -> SessionKey {
+        SessionKey {
+            uid,
+            exe_hash: exe_hash.to_string(),
+        }
+    }
+}
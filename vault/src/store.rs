@@ -31,6 +31,29 @@ pub struct SecretMetadata {
     pub tags: Vec<String>,
     /// Notes
     pub notes: Option<String>,
+    /// Current version number
+    #[serde(default = "default_version")]
+    pub version: u32,
+    /// Per-secret history depth (overrides the storage default)
+    #[serde(default)]
+    pub max_versions: Option<usize>,
+}
+
+fn default_version() -> u32 {
+    1
+}
+
+/// Summary of a stored secret version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionInfo {
+    /// Version number
+    pub version: u32,
+    /// When this version was written
+    pub created_at: DateTime<Utc>,
+    /// Secret type at this version
+    pub secret_type: SecretType,
+    /// Whether this is the current version
+    pub current: bool,
 }
 
 /// Secret type
@@ -49,6 +72,32 @@ pub enum SecretType {
     Certificate,
     /// Token
     Token,
+    /// TOTP token (stored as an otpauth URI)
+    Totp,
+    /// Website login (stored as a `Login`, sites as `origin:` tags)
+    Login,
+}
+
+/// Tag prefix marking the sites a login belongs to
+pub const ORIGIN_TAG: &str = "origin:";
+
+/// Username and password of a website login
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Login {
+    pub username: String,
+    pub password: String,
+}
+
+impl Login {
+    /// Parse a stored login value
+    pub fn from_value(value: &str) -> Result<Self> {
+        serde_json::from_str(value).map_err(|e| anyhow!("Malformed login: {}", e))
+    }
+
+    /// Value stored in the vault
+    pub fn to_value(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
 }
 
 /// Secret entry
@@ -75,6 +124,53 @@ struct SecretEntry {
     metadata: SecretMetadata,
     /// Base64-encoded encrypted value
     encrypted_value: String,
+    /// Previous versions, oldest first
+    #[serde(default)]
+    history: Vec<SecretVersion>,
+}
+
+/// A superseded secret value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SecretVersion {
+    version: u32,
+    created_at: DateTime<Utc>,
+    secret_type: SecretType,
+    /// Base64-encoded encrypted value
+    encrypted_value: String,
+}
+
+impl SecretEntry {
+    /// Move the current value into history and install a new one
+    fn push_version(&mut self, encrypted_value: String, secret_type: SecretType, depth: usize) {
+        let previous = SecretVersion {
+            version: self.metadata.version,
+            created_at: self.metadata.modified_at,
+            secret_type: self.metadata.secret_type,
+            encrypted_value: std::mem::replace(&mut self.encrypted_value, encrypted_value),
+        };
+        self.history.push(previous);
+
+        let depth = self.metadata.max_versions.unwrap_or(depth);
+        if self.history.len() > depth {
+            let excess = self.history.len() - depth;
+            self.history.drain(..excess);
+        }
+
+        self.metadata.version += 1;
+        self.metadata.secret_type = secret_type;
+        self.metadata.modified_at = Utc::now();
+    }
+
+    /// Encrypted value for a given version
+    fn encrypted_version(&self, version: u32) -> Option<(&str, SecretType)> {
+        if version == self.metadata.version {
+            return Some((&self.encrypted_value, self.metadata.secret_type));
+        }
+        self.history
+            .iter()
+            .find(|v| v.version == version)
+            .map(|v| (v.encrypted_value.as_str(), v.secret_type))
+    }
 }
 
 /// Secret store
@@ -205,28 +301,35 @@ impl SecretStore {
         let encrypted = self.crypto.encrypt(value.as_bytes(), password)?;
         let encrypted_b64 = base64::encode(encrypted.to_bytes());
 
-        let now = Utc::now();
-        let existing = data.secrets.get(name);
-
-        let metadata = SecretMetadata {
-            id: existing.map(|e| e.metadata.id).unwrap_or_else(Uuid::new_v4),
-            name: name.to_string(),
-            created_at: existing.map(|e| e.metadata.created_at).unwrap_or(now),
-            modified_at: now,
-            accessed_at: None,
-            secret_type,
-            tags: existing
-                .map(|e| e.metadata.tags.clone())
-                .unwrap_or_default(),
-            notes: existing.and_then(|e| e.metadata.notes.clone()),
-        };
-
-        let entry = SecretEntry {
-            metadata,
-            encrypted_value: encrypted_b64,
-        };
+        match data.secrets.get_mut(name) {
+            Some(entry) => {
+                // Keep the previous value as history
+                entry.push_version(encrypted_b64, secret_type, self.config.version_history);
+            }
+            None => {
+                let now = Utc::now();
+                let metadata = SecretMetadata {
+                    id: Uuid::new_v4(),
+                    name: name.to_string(),
+                    created_at: now,
+                    modified_at: now,
+                    accessed_at: None,
+                    secret_type,
+                    tags: Vec::new(),
+                    notes: None,
+                    version: 1,
+                    max_versions: None,
+                };
+
+                let entry = SecretEntry {
+                    metadata,
+                    encrypted_value: encrypted_b64,
+                    history: Vec::new(),
+                };
+                data.secrets.insert(name.to_string(), entry);
+            }
+        }
 
-        data.secrets.insert(name.to_string(), entry);
         self.save()?;
 
         info!("Secret '{}' saved", name);
@@ -258,6 +361,103 @@ impl SecretStore {
         Ok(value)
     }
 
+    /// Get a specific version of a secret
+    pub fn get_version(&self, name: &str, version: u32) -> Result<String> {
+        self.require_unlocked()?;
+
+        let password = self.master_password.as_ref().unwrap();
+        let entry = self.entry(name)?;
+
+        let (encrypted_value, _) = entry
+            .encrypted_version(version)
+            .ok_or_else(|| anyhow!("Version {} of '{}' not found", version, name))?;
+
+        let encrypted_bytes = base64::decode(encrypted_value)?;
+        let encrypted = EncryptedData::from_bytes(&encrypted_bytes)?;
+        let plaintext = self.crypto.decrypt(&encrypted, password)?;
+
+        debug!("Secret '{}' version {} accessed", name, version);
+        Ok(String::from_utf8(plaintext)?)
+    }
+
+    /// List stored versions of a secret, newest first
+    pub fn list_versions(&self, name: &str) -> Result<Vec<VersionInfo>> {
+        self.require_unlocked()?;
+
+        let entry = self.entry(name)?;
+        let mut versions: Vec<VersionInfo> = entry
+            .history
+            .iter()
+            .map(|v| VersionInfo {
+                version: v.version,
+                created_at: v.created_at,
+                secret_type: v.secret_type,
+                current: false,
+            })
+            .collect();
+
+        versions.push(VersionInfo {
+            version: entry.metadata.version,
+            created_at: entry.metadata.modified_at,
+            secret_type: entry.metadata.secret_type,
+            current: true,
+        });
+        versions.reverse();
+
+        Ok(versions)
+    }
+
+    /// Restore a previous version as the current value
+    ///
+    /// The restored value becomes a new version, so the rollback itself can
+    /// be undone.
+    pub fn rollback(&mut self, name: &str, version: u32) -> Result<u32> {
+        self.require_unlocked()?;
+
+        let depth = self.config.version_history;
+        let data = self.data.as_mut().unwrap();
+        let entry = data
+            .secrets
+            .get_mut(name)
+            .ok_or_else(|| anyhow!("Secret not found: {}", name))?;
+
+        if version == entry.metadata.version {
+            return Err(anyhow!("Version {} is already current", version));
+        }
+
+        let (encrypted_value, secret_type) = entry
+            .encrypted_version(version)
+            .map(|(v, t)| (v.to_string(), t))
+            .ok_or_else(|| anyhow!("Version {} of '{}' not found", version, name))?;
+
+        entry.push_version(encrypted_value, secret_type, depth);
+        let new_version = entry.metadata.version;
+        self.save()?;
+
+        info!("Secret '{}' rolled back to version {} (now version {})", name, version, new_version);
+        Ok(new_version)
+    }
+
+    /// Set the history depth for a single secret
+    pub fn set_max_versions(&mut self, name: &str, depth: Option<usize>) -> Result<()> {
+        self.require_unlocked()?;
+
+        let data = self.data.as_mut().unwrap();
+        let entry = data
+            .secrets
+            .get_mut(name)
+            .ok_or_else(|| anyhow!("Secret not found: {}", name))?;
+
+        entry.metadata.max_versions = depth;
+        let depth = depth.unwrap_or(self.config.version_history);
+        if entry.history.len() > depth {
+            let excess = entry.history.len() - depth;
+            entry.history.drain(..excess);
+        }
+
+        self.save()
+    }
+
     /// Delete a secret
     pub fn delete(&mut self, name: &str) -> Result<()> {
         self.require_unlocked()?;
@@ -348,20 +548,45 @@ impl SecretStore {
         Ok(())
     }
 
-    /// Create backup
-    pub fn backup(&self) -> Result<String> {
+    /// Raw (encrypted) store file, for backup snapshots
+    pub fn snapshot(&self) -> Result<Vec<u8>> {
+        if !self.exists() {
+            return Err(anyhow!("Vault does not exist. Initialize first."));
+        }
+        Ok(fs::read(&self.config.path)?)
+    }
+
+    /// Check that a store file decrypts with the current master password,
+    /// returning its secret count
+    pub fn verify_snapshot(&self, store: &[u8]) -> Result<usize> {
+        self.require_unlocked()?;
+        Ok(self.decode_snapshot(store)?.secrets.len())
+    }
+
+    /// Replace the live store with a verified store file
+    pub fn restore(&mut self, store: &[u8]) -> Result<usize> {
         self.require_unlocked()?;
+        let data = self.decode_snapshot(store)?;
+        let count = data.secrets.len();
 
-        let backup_dir = Path::new(&self.config.backup_path);
-        fs::create_dir_all(backup_dir)?;
+        fs::write(&self.config.path, store)?;
+        self.data = Some(data);
 
-        let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
-        let backup_file = backup_dir.join(format!("vault_backup_{}.enc", timestamp));
+        info!("Vault restored from backup ({} secrets)", count);
+        Ok(count)
+    }
 
-        fs::copy(&self.config.path, &backup_file)?;
+    fn decode_snapshot(&self, store: &[u8]) -> Result<VaultData> {
+        let password = self
+            .master_password
+            .as_ref()
+            .ok_or_else(|| anyhow!("Vault locked"))?;
 
-        info!("Backup created: {:?}", backup_file);
-        Ok(backup_file.to_string_lossy().to_string())
+        let encrypted = EncryptedData::from_bytes(store)?;
+        let plaintext = self.crypto.decrypt(&encrypted, password).map_err(|_| {
+            anyhow!("Backup does not decrypt with the current master password")
+        })?;
+        Ok(serde_json::from_slice(&plaintext)?)
     }
 
     /// Get vault stats
@@ -385,6 +610,22 @@ impl SecretStore {
         })
     }
 
+    /// Get metadata for a secret
+    pub fn metadata(&self, name: &str) -> Result<SecretMetadata> {
+        self.require_unlocked()?;
+        Ok(self.entry(name)?.metadata.clone())
+    }
+
+    /// Look up a secret entry
+    fn entry(&self, name: &str) -> Result<&SecretEntry> {
+        self.data
+            .as_ref()
+            .unwrap()
+            .secrets
+            .get(name)
+            .ok_or_else(|| anyhow!("Secret not found: {}", name))
+    }
+
     /// Require vault to be unlocked
     fn require_unlocked(&self) -> Result<()> {
         if !self.unlocked {
@@ -408,3 +649,52 @@ impl Drop for SecretStore {
         self.lock();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> SecretEntry {
+        let now = Utc::now();
+        SecretEntry {
+            metadata: SecretMetadata {
+                id: Uuid::new_v4(),
+                name: "db".to_string(),
+                created_at: now,
+                modified_at: now,
+                accessed_at: None,
+                secret_type: SecretType::Password,
+                tags: Vec::new(),
+                notes: None,
+                version: 1,
+                max_versions: None,
+            },
+            encrypted_value: "v1".to_string(),
+            history: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_push_version_trims_history() {
+        let mut entry = entry();
+        for i in 2..=5 {
+            entry.push_version(format!("v{}", i), SecretType::Password, 2);
+        }
+
+        assert_eq!(entry.metadata.version, 5);
+        assert_eq!(entry.encrypted_value, "v5");
+        assert_eq!(entry.history.len(), 2);
+        assert_eq!(entry.encrypted_version(3).map(|(v, _)| v), Some("v3"));
+        assert!(entry.encrypted_version(2).is_none());
+    }
+
+    #[test]
+    fn test_per_secret_depth_override() {
+        let mut entry = entry();
+        entry.metadata.max_versions = Some(0);
+        entry.push_version("v2".to_string(), SecretType::Token, 5);
+
+        assert!(entry.history.is_empty());
+        assert_eq!(entry.metadata.secret_type, SecretType::Token);
+    }
+}
@@ -0,0 +1,298 @@
+//! TOTP (RFC 6238) one-time password support
+//!
+//! TOTP secrets are stored as `otpauth://totp/...` URIs so the issuer,
+//! account, algorithm, digit count and period travel with the key.
+
+use anyhow::{anyhow, Result};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+
+/// HMAC algorithm used for code generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TotpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl TotpAlgorithm {
+    fn hmac_algorithm(self) -> hmac::Algorithm {
+        match self {
+            Self::Sha1 => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+            Self::Sha256 => hmac::HMAC_SHA256,
+            Self::Sha512 => hmac::HMAC_SHA512,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Sha1 => "SHA1",
+            Self::Sha256 => "SHA256",
+            Self::Sha512 => "SHA512",
+        }
+    }
+}
+
+/// Parameters of a TOTP token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TotpParams {
+    /// Shared secret (base32, as found in otpauth URIs)
+    pub secret: String,
+    /// Issuer (service name)
+    pub issuer: Option<String>,
+    /// Account name
+    pub account: Option<String>,
+    /// HMAC algorithm
+    pub algorithm: TotpAlgorithm,
+    /// Number of digits in a code
+    pub digits: u32,
+    /// Time step in seconds
+    pub period: u64,
+}
+
+/// A generated code and how long it remains valid
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotpCode {
+    pub code: String,
+    pub remaining_secs: u64,
+    pub period: u64,
+    pub issuer: Option<String>,
+    pub account: Option<String>,
+}
+
+impl TotpParams {
+    /// Parse an `otpauth://totp/` URI
+    pub fn from_uri(uri: &str) -> Result<Self> {
+        let rest = uri
+            .trim()
+            .strip_prefix("otpauth://")
+            .ok_or_else(|| anyhow!("Not an otpauth URI"))?;
+
+        let (kind, rest) = rest
+            .split_once('/')
+            .ok_or_else(|| anyhow!("Malformed otpauth URI"))?;
+        if !kind.eq_ignore_ascii_case("totp") {
+            return Err(anyhow!("Unsupported OTP type: {}", kind));
+        }
+
+        let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let label = percent_decode(label);
+        let (label_issuer, account) = match label.split_once(':') {
+            Some((issuer, account)) => (Some(issuer.trim().to_string()), account.trim().to_string()),
+            None => (None, label.trim().to_string()),
+        };
+
+        let mut params = Self {
+            secret: String::new(),
+            issuer: label_issuer,
+            account: (!account.is_empty()).then_some(account),
+            algorithm: TotpAlgorithm::Sha1,
+            digits: 6,
+            period: 30,
+        };
+
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value);
+            match key.to_ascii_lowercase().as_str() {
+                "secret" => params.secret = value.replace(' ', "").to_ascii_uppercase(),
+                "issuer" => params.issuer = Some(value),
+                "algorithm" => {
+                    params.algorithm = match value.to_ascii_uppercase().as_str() {
+                        "SHA1" => TotpAlgorithm::Sha1,
+                        "SHA256" => TotpAlgorithm::Sha256,
+                        "SHA512" => TotpAlgorithm::Sha512,
+                        other => return Err(anyhow!("Unsupported TOTP algorithm: {}", other)),
+                    }
+                }
+                "digits" => params.digits = value.parse()?,
+                "period" => params.period = value.parse()?,
+                _ => {}
+            }
+        }
+
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Render as an `otpauth://totp/` URI
+    pub fn to_uri(&self) -> String {
+        let label = match (&self.issuer, &self.account) {
+            (Some(issuer), Some(account)) => format!("{}:{}", issuer, account),
+            (None, Some(account)) => account.clone(),
+            (Some(issuer), None) => issuer.clone(),
+            (None, None) => String::new(),
+        };
+
+        let mut uri = format!(
+            "otpauth://totp/{}?secret={}&algorithm={}&digits={}&period={}",
+            percent_encode(&label),
+            self.secret,
+            self.algorithm.as_str(),
+            self.digits,
+            self.period
+        );
+        if let Some(issuer) = &self.issuer {
+            uri.push_str("&issuer=");
+            uri.push_str(&percent_encode(issuer));
+        }
+        uri
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.secret.is_empty() {
+            return Err(anyhow!("TOTP URI is missing a secret"));
+        }
+        base32_decode(&self.secret)?;
+        if !(6..=10).contains(&self.digits) {
+            return Err(anyhow!("Unsupported digit count: {}", self.digits));
+        }
+        if self.period == 0 {
+            return Err(anyhow!("TOTP period must be non-zero"));
+        }
+        Ok(())
+    }
+
+    /// Generate the code for a Unix timestamp
+    pub fn generate_at(&self, unix_secs: u64) -> Result<TotpCode> {
+        let key_bytes = base32_decode(&self.secret)?;
+        let counter = unix_secs / self.period;
+
+        let key = hmac::Key::new(self.algorithm.hmac_algorithm(), &key_bytes);
+        let tag = hmac::sign(&key, &counter.to_be_bytes());
+        let digest = tag.as_ref();
+
+        // Dynamic truncation (RFC 4226 section 5.3)
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+        let code = binary as u64 % 10u64.pow(self.digits);
+
+        Ok(TotpCode {
+            code: format!("{:0width$}", code, width = self.digits as usize),
+            remaining_secs: self.period - unix_secs % self.period,
+            period: self.period,
+            issuer: self.issuer.clone(),
+            account: self.account.clone(),
+        })
+    }
+
+    /// Generate the current code
+    pub fn generate(&self) -> Result<TotpCode> {
+        self.generate_at(chrono::Utc::now().timestamp().max(0) as u64)
+    }
+}
+
+/// Decode RFC 4648 base32 (padding optional)
+fn base32_decode(input: &str) -> Result<Vec<u8>> {
+    let mut buffer: u64 = 0;
+    let mut bits = 0u32;
+    let mut out = Vec::with_capacity(input.len() * 5 / 8);
+
+    for c in input.chars().filter(|c| *c != '=') {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u64 - 'A' as u64,
+            c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+            other => return Err(anyhow!("Invalid base32 character: {:?}", other)),
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Ok(out)
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(b) => {
+                        out.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    Err(_) => out.push(b'%'),
+                }
+            }
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn percent_encode(input: &str) -> String {
+    input
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b':' | b'@' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 appendix B secret "12345678901234567890" in base32
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        let params = TotpParams {
+            secret: RFC_SECRET.to_string(),
+            issuer: None,
+            account: None,
+            algorithm: TotpAlgorithm::Sha1,
+            digits: 8,
+            period: 30,
+        };
+
+        assert_eq!(params.generate_at(59).unwrap().code, "94287082");
+        assert_eq!(params.generate_at(1111111109).unwrap().code, "07081804");
+        assert_eq!(params.generate_at(2000000000).unwrap().code, "69279037");
+        assert_eq!(params.generate_at(59).unwrap().remaining_secs, 1);
+    }
+
+    #[test]
+    fn test_uri_roundtrip() {
+        let uri = "otpauth://totp/Example%20Co:alice@example.com?secret=JBSWY3DPEHPK3PXP&issuer=Example%20Co&digits=6";
+        let params = TotpParams::from_uri(uri).unwrap();
+
+        assert_eq!(params.issuer.as_deref(), Some("Example Co"));
+        assert_eq!(params.account.as_deref(), Some("alice@example.com"));
+        assert_eq!(params.algorithm, TotpAlgorithm::Sha1);
+        assert_eq!(params.period, 30);
+
+        let reparsed = TotpParams::from_uri(&params.to_uri()).unwrap();
+        assert_eq!(params, reparsed);
+    }
+
+    #[test]
+    fn test_rejects_invalid_uri() {
+        assert!(TotpParams::from_uri("otpauth://hotp/x?secret=JBSWY3DP").is_err());
+        assert!(TotpParams::from_uri("otpauth://totp/x").is_err());
+        assert!(TotpParams::from_uri("otpauth://totp/x?secret=not-base32!").is_err());
+    }
+}
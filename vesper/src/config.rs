@@ -36,6 +36,13 @@ pub struct Config {
     pub auto_switch: bool,
     /// Saved stream volumes
     pub stream_volumes: std::collections::HashMap<String, u32>,
+    /// Handle the volume and mute keys of every keyboard
+    pub volume_keys: bool,
+    /// Percent one volume key press changes
+    pub volume_step: u32,
+    /// Highest volume (0-100) per sink name, e.g. for headphones or
+    /// speakers that distort; nothing sets a sink louder than this
+    pub max_volume: std::collections::HashMap<String, u32>,
 }
 
 impl Default for Config {
@@ -55,6 +62,9 @@ impl Default for Config {
             flat_volume: false,
             auto_switch: true,
             stream_volumes: std::collections::HashMap::new(),
+            volume_keys: true,
+            volume_step: 5,
+            max_volume: std::collections::HashMap::new(),
         }
     }
 }
@@ -2,14 +2,17 @@
 
 use crate::AudioContext;
 use crate::device::AudioDevice;
+use crate::keys::OsdEvent;
 use crate::stream::StreamInfo;
 use anyhow::Result;
+use libnyx_ipc::HealthReporter;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
-use tracing::{info, error, debug};
+use tokio::sync::broadcast;
+use tracing::{info, error, debug, warn};
 
 /// IPC request types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +47,11 @@ pub enum IpcRequest {
     ScanBluetooth,
     ConnectBluetooth { address: String },
     DisconnectBluetooth { address: String },
+    SetBluetooth { enabled: bool },
+
+    // On-screen display
+    /// Keep the connection open and push an `OsdEvent` per volume key press
+    SubscribeOsd,
 }
 
 /// IPC response
@@ -51,12 +59,12 @@ pub enum IpcRequest {
 #[serde(tag = "status")]
 pub enum IpcResponse {
     Success { message: String },
-    Devices(Vec<DeviceInfo>),
+    Devices { devices: Vec<DeviceInfo> },
     Device(DeviceInfo),
-    Streams(Vec<StreamInfo>),
+    Streams { streams: Vec<StreamInfo> },
     Stream(StreamInfo),
     Status(StatusInfo),
-    Muted(bool),
+    Muted { muted: bool },
     Error { message: String },
 }
 
@@ -78,17 +86,23 @@ pub struct StatusInfo {
     pub stream_count: usize,
     pub master_volume: u32,
     pub muted: bool,
+    #[serde(default)]
+    pub bluetooth_enabled: bool,
+    /// Name of the first connected Bluetooth audio device
+    #[serde(default)]
+    pub bluetooth_device: Option<String>,
 }
 
 /// IPC server
 pub struct VesperServer {
     socket_path: PathBuf,
     context: AudioContext,
+    health: HealthReporter,
 }
 
 impl VesperServer {
-    pub fn new(socket_path: PathBuf, context: AudioContext) -> Self {
-        Self { socket_path, context }
+    pub fn new(socket_path: PathBuf, context: AudioContext, health: HealthReporter) -> Self {
+        Self { socket_path, context, health }
     }
 
     pub async fn run(&self) -> Result<()> {
@@ -110,9 +124,12 @@ impl VesperServer {
                     let clients = self.context.clients.clone();
                     let sinks = self.context.sinks.clone();
                     let sources = self.context.sources.clone();
+                    let bluetooth = self.context.bluetooth.clone();
+                    let osd = self.context.osd.clone();
+                    let health = self.health.clone();
 
                     tokio::spawn(async move {
-                        if let Err(e) = handle_client(stream, dm, mixer, clients, sinks, sources).await {
+                        if let Err(e) = handle_client(stream, dm, mixer, clients, sinks, sources, bluetooth, osd, health).await {
                             error!("Client error: {}", e);
                         }
                     });
@@ -123,6 +140,7 @@ impl VesperServer {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_client(
     stream: UnixStream,
     device_manager: Arc<tokio::sync::RwLock<crate::device::DeviceManager>>,
@@ -130,20 +148,28 @@ async fn handle_client(
     clients: Arc<tokio::sync::RwLock<crate::client::ClientManager>>,
     sinks: Arc<tokio::sync::RwLock<std::collections::HashMap<String, crate::sink::Sink>>>,
     sources: Arc<tokio::sync::RwLock<std::collections::HashMap<String, crate::source::Source>>>,
+    bluetooth: Option<Arc<tokio::sync::RwLock<crate::bluetooth::BluetoothAudio>>>,
+    osd: broadcast::Sender<OsdEvent>,
+    health: HealthReporter,
 ) -> Result<()> {
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
 
     while reader.read_line(&mut line).await? > 0 {
-        let response = match serde_json::from_str::<IpcRequest>(&line) {
-            Ok(request) => process_request(
-                request, &device_manager, &mixer, &clients, &sinks, &sources
-            ).await,
-            Err(e) => IpcResponse::Error { message: e.to_string() },
+        let json = match health.respond(&line) {
+            Some(reply) => reply,
+            None => {
+                let response = match serde_json::from_str::<IpcRequest>(&line) {
+                    Ok(IpcRequest::SubscribeOsd) => return stream_osd(writer, osd.subscribe()).await,
+                    Ok(request) => process_request(
+                        request, &device_manager, &mixer, &clients, &sinks, &sources, bluetooth.as_deref()
+                    ).await,
+                    Err(e) => IpcResponse::Error { message: e.to_string() },
+                };
+                serde_json::to_string(&response)?
+            }
         };
-
-        let json = serde_json::to_string(&response)?;
         writer.write_all(json.as_bytes()).await?;
         writer.write_all(b"\n").await?;
         writer.flush().await?;
@@ -154,6 +180,27 @@ async fn handle_client(
     Ok(())
 }
 
+/// Send every volume key press until the client goes away
+async fn stream_osd(
+    mut writer: tokio::net::unix::OwnedWriteHalf,
+    mut events: broadcast::Receiver<OsdEvent>,
+) -> Result<()> {
+    loop {
+        match events.recv().await {
+            Ok(event) => {
+                let json = serde_json::to_string(&event)?;
+                writer.write_all(json.as_bytes()).await?;
+                writer.write_all(b"\n").await?;
+                writer.flush().await?;
+            }
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("OSD subscriber fell behind by {} events", missed);
+            }
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
 async fn process_request(
     request: IpcRequest,
     device_manager: &tokio::sync::RwLock<crate::device::DeviceManager>,
@@ -161,6 +208,7 @@ async fn process_request(
     clients: &tokio::sync::RwLock<crate::client::ClientManager>,
     sinks: &tokio::sync::RwLock<std::collections::HashMap<String, crate::sink::Sink>>,
     sources: &tokio::sync::RwLock<std::collections::HashMap<String, crate::source::Source>>,
+    bluetooth: Option<&tokio::sync::RwLock<crate::bluetooth::BluetoothAudio>>,
 ) -> IpcResponse {
     match request {
         IpcRequest::ListDevices => {
@@ -179,12 +227,34 @@ async fn process_request(
                 })
                 .collect();
 
-            IpcResponse::Devices(devices)
+            IpcResponse::Devices { devices }
         }
 
         IpcRequest::ListStreams => {
             let cm = clients.read().await;
-            IpcResponse::Streams(cm.stream_info_list())
+            IpcResponse::Streams { streams: cm.stream_info_list() }
+        }
+
+        IpcRequest::SetStreamVolume { id, volume } => {
+            let mut cm = clients.write().await;
+            match cm.get_stream_mut(id) {
+                Some(stream) => {
+                    stream.set_volume(volume);
+                    IpcResponse::Success { message: format!("Stream {} volume set to {}%", id, volume) }
+                }
+                None => IpcResponse::Error { message: format!("Stream not found: {}", id) },
+            }
+        }
+
+        IpcRequest::SetStreamMute { id, muted } => {
+            let mut cm = clients.write().await;
+            match cm.get_stream_mut(id) {
+                Some(stream) => {
+                    stream.set_mute(muted);
+                    IpcResponse::Muted { muted }
+                }
+                None => IpcResponse::Error { message: format!("Stream not found: {}", id) },
+            }
         }
 
         IpcRequest::SetDefaultSink { name } => {
@@ -245,7 +315,7 @@ async fn process_request(
                 let mut sink_map = sinks.write().await;
                 if let Some(sink) = sink_map.get_mut(&target) {
                     sink.set_mute(muted);
-                    return IpcResponse::Muted(muted);
+                    return IpcResponse::Muted { muted };
                 }
             }
 
@@ -253,7 +323,7 @@ async fn process_request(
                 let mut source_map = sources.write().await;
                 if let Some(source) = source_map.get_mut(&target) {
                     source.set_mute(muted);
-                    return IpcResponse::Muted(muted);
+                    return IpcResponse::Muted { muted };
                 }
             }
 
@@ -265,7 +335,7 @@ async fn process_request(
                 let mut sink_map = sinks.write().await;
                 if let Some(sink) = sink_map.get_mut(&target) {
                     let muted = sink.toggle_mute();
-                    return IpcResponse::Muted(muted);
+                    return IpcResponse::Muted { muted };
                 }
             }
 
@@ -273,7 +343,7 @@ async fn process_request(
                 let mut source_map = sources.write().await;
                 if let Some(source) = source_map.get_mut(&target) {
                     let muted = source.toggle_mute();
-                    return IpcResponse::Muted(muted);
+                    return IpcResponse::Muted { muted };
                 }
             }
 
@@ -289,13 +359,21 @@ async fn process_request(
         IpcRequest::SetMasterMute { muted } => {
             let mut m = mixer.write().await;
             m.set_muted(muted);
-            IpcResponse::Muted(muted)
+            IpcResponse::Muted { muted }
         }
 
         IpcRequest::GetStatus => {
             let dm = device_manager.read().await;
             let m = mixer.read().await;
             let cm = clients.read().await;
+            let (bluetooth_enabled, bluetooth_device) = match bluetooth {
+                Some(bt) => {
+                    let bt = bt.read().await;
+                    let device = bt.connected_devices().next().map(|d| d.name.clone());
+                    (bt.is_enabled(), device)
+                }
+                None => (false, None),
+            };
 
             IpcResponse::Status(StatusInfo {
                 default_sink: dm.default_sink().unwrap_or("").to_string(),
@@ -303,9 +381,30 @@ async fn process_request(
                 stream_count: cm.stream_count(),
                 master_volume: m.master_volume(),
                 muted: m.is_muted(),
+                bluetooth_enabled,
+                bluetooth_device,
             })
         }
 
+        IpcRequest::SetBluetooth { enabled } => match bluetooth {
+            Some(bt) => {
+                let mut bt = bt.write().await;
+                if enabled {
+                    bt.enable();
+                } else {
+                    bt.disable();
+                }
+                IpcResponse::Success {
+                    message: format!("Bluetooth {}", if enabled { "enabled" } else { "disabled" }),
+                }
+            }
+            None => IpcResponse::Error { message: "Bluetooth not available".to_string() },
+        },
+
+        IpcRequest::SubscribeOsd => IpcResponse::Error {
+            message: "SubscribeOsd must be sent on its own connection".to_string(),
+        },
+
         _ => IpcResponse::Error { message: "Not implemented".to_string() },
     }
 }
@@ -337,7 +436,7 @@ impl VesperClient {
 
     pub async fn list_devices(&self) -> Result<Vec<DeviceInfo>> {
         match self.send(IpcRequest::ListDevices).await? {
-            IpcResponse::Devices(devices) => Ok(devices),
+            IpcResponse::Devices { devices } => Ok(devices),
             IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
             _ => Err(anyhow::anyhow!("Unexpected response")),
         }
@@ -345,7 +444,7 @@ impl VesperClient {
 
     pub async fn list_streams(&self) -> Result<Vec<StreamInfo>> {
         match self.send(IpcRequest::ListStreams).await? {
-            IpcResponse::Streams(streams) => Ok(streams),
+            IpcResponse::Streams { streams } => Ok(streams),
             IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
             _ => Err(anyhow::anyhow!("Unexpected response")),
         }
@@ -368,7 +467,7 @@ impl VesperClient {
             "off" | "false" | "0" => false,
             "toggle" => {
                 match self.send(IpcRequest::ToggleMute { target: target.to_string() }).await? {
-                    IpcResponse::Muted(m) => return Ok(m),
+                    IpcResponse::Muted { muted: m } => return Ok(m),
                     IpcResponse::Error { message } => return Err(anyhow::anyhow!(message)),
                     _ => return Err(anyhow::anyhow!("Unexpected response")),
                 }
@@ -377,7 +476,7 @@ impl VesperClient {
         };
 
         match self.send(IpcRequest::SetMute { target: target.to_string(), muted }).await? {
-            IpcResponse::Muted(m) => Ok(m),
+            IpcResponse::Muted { muted: m } => Ok(m),
             IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
             _ => Err(anyhow::anyhow!("Unexpected response")),
         }
@@ -399,6 +498,14 @@ impl VesperClient {
         }
     }
 
+    pub async fn set_bluetooth(&self, enabled: bool) -> Result<()> {
+        match self.send(IpcRequest::SetBluetooth { enabled }).await? {
+            IpcResponse::Success { .. } => Ok(()),
+            IpcResponse::Error { message } => Err(anyhow::anyhow!(message)),
+            _ => Err(anyhow::anyhow!("Unexpected response")),
+        }
+    }
+
     pub async fn get_status(&self) -> Result<StatusInfo> {
         match self.send(IpcRequest::GetStatus).await? {
             IpcResponse::Status(status) => Ok(status),
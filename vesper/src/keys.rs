@@ -0,0 +1,307 @@
+//! Hardware volume keys
+//!
+//! Vesper reads the volume, mute and mic-mute keys straight from the
+//! keyboards phantom lists, so they work whatever has focus and whether or
+//! not a media player is running. Each press changes the default sink (or
+//! the default source, for mic mute) and is pushed to subscribers as an
+//! `OsdEvent`, which the shell draws.
+//!
+//! Keyboards are only read, never grabbed: the compositor still sees the
+//! keys. Phantom is asked again every few seconds so that keyboards plugged
+//! in later are picked up; a reader ends when its keyboard goes away.
+
+use crate::device::DeviceManager;
+use crate::sink::Sink;
+use crate::source::Source;
+use crate::AudioContext;
+use anyhow::Result;
+use libnyx_ipc::DeviceClient;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, info, warn};
+
+/// How often phantom is asked for new keyboards
+const RESCAN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// evdev event type and key codes, from linux/input-event-codes.h
+const EV_KEY: u16 = 0x01;
+const KEY_MUTE: u16 = 113;
+const KEY_VOLUMEDOWN: u16 = 114;
+const KEY_VOLUMEUP: u16 = 115;
+const KEY_MICMUTE: u16 = 248;
+
+/// evdev key values
+const KEY_PRESSED: i32 = 1;
+const KEY_REPEATED: i32 = 2;
+
+/// A key vesper handles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeKey {
+    Up,
+    Down,
+    Mute,
+    MicMute,
+}
+
+impl VolumeKey {
+    fn from_code(code: u16) -> Option<Self> {
+        match code {
+            KEY_VOLUMEUP => Some(VolumeKey::Up),
+            KEY_VOLUMEDOWN => Some(VolumeKey::Down),
+            KEY_MUTE => Some(VolumeKey::Mute),
+            KEY_MICMUTE => Some(VolumeKey::MicMute),
+            _ => None,
+        }
+    }
+
+    /// Volume steps repeat while the key is held; mutes only toggle once
+    fn repeats(&self) -> bool {
+        matches!(self, VolumeKey::Up | VolumeKey::Down)
+    }
+}
+
+/// Pushed to OSD subscribers after a key press
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum OsdEvent {
+    Volume {
+        sink: String,
+        description: String,
+        volume: u32,
+        muted: bool,
+        max_volume: u32,
+        /// The press asked for more than `max_volume`
+        limited: bool,
+    },
+    MicMute { source: String, muted: bool },
+}
+
+/// Reads the volume keys and applies them
+pub struct VolumeKeys {
+    device_manager: Arc<RwLock<DeviceManager>>,
+    sinks: Arc<RwLock<HashMap<String, Sink>>>,
+    sources: Arc<RwLock<HashMap<String, Source>>>,
+    osd: broadcast::Sender<OsdEvent>,
+    step: u32,
+    /// Device nodes being read
+    watching: Mutex<HashSet<String>>,
+}
+
+impl VolumeKeys {
+    pub fn new(context: &AudioContext) -> Self {
+        Self {
+            device_manager: context.device_manager.clone(),
+            sinks: context.sinks.clone(),
+            sources: context.sources.clone(),
+            osd: context.osd.clone(),
+            step: context.config.volume_step.max(1),
+            watching: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Read every keyboard with volume keys, now and as they are plugged in
+    pub async fn run(self: Arc<Self>) {
+        let phantom = DeviceClient::new();
+
+        loop {
+            match phantom.list(Some("input")).await {
+                Ok(devices) => {
+                    for device in devices {
+                        let Some(devnode) = device.devnode.filter(|n| n.starts_with("/dev/input/event")) else {
+                            continue;
+                        };
+                        if !has_volume_keys(Path::new(&device.syspath)) {
+                            continue;
+                        }
+                        if !self.watching.lock().unwrap().insert(devnode.clone()) {
+                            continue;
+                        }
+
+                        let keys = self.clone();
+                        tokio::spawn(async move {
+                            info!("Reading volume keys from {}", devnode);
+                            if let Err(e) = keys.read(&devnode).await {
+                                debug!("Stopped reading {}: {}", devnode, e);
+                            }
+                            keys.watching.lock().unwrap().remove(&devnode);
+                        });
+                    }
+                }
+                Err(e) => debug!("Phantom unavailable, no volume keys yet: {}", e),
+            }
+            tokio::time::sleep(RESCAN_INTERVAL).await;
+        }
+    }
+
+    /// Handle key events from one keyboard until it goes away
+    async fn read(&self, devnode: &str) -> Result<()> {
+        let mut device = tokio::fs::File::open(devnode).await?;
+        let mut event = [0u8; EVENT_SIZE];
+
+        loop {
+            device.read_exact(&mut event).await?;
+            let Some((key, value)) = parse_event(&event) else {
+                continue;
+            };
+            if value == KEY_PRESSED || (value == KEY_REPEATED && key.repeats()) {
+                self.press(key).await;
+            }
+        }
+    }
+
+    /// Apply a key to the default sink or source and show it
+    async fn press(&self, key: VolumeKey) {
+        let dm = self.device_manager.read().await;
+
+        let event = if key == VolumeKey::MicMute {
+            let Some(name) = dm.default_source() else { return };
+            let mut sources = self.sources.write().await;
+            let Some(source) = sources.get_mut(name) else { return };
+            OsdEvent::MicMute {
+                source: source.name.clone(),
+                muted: source.toggle_mute(),
+            }
+        } else {
+            let Some(name) = dm.default_sink() else { return };
+            let mut sinks = self.sinks.write().await;
+            let Some(sink) = sinks.get_mut(name) else { return };
+            apply(key, sink, self.step)
+        };
+
+        if let OsdEvent::Volume { limited: true, ref sink, max_volume, .. } = event {
+            warn!("Volume of {} held at its limit of {}%", sink, max_volume);
+        }
+        // No subscribers just means no shell to show it
+        let _ = self.osd.send(event);
+    }
+}
+
+/// Change a sink's volume or mute for a key
+fn apply(key: VolumeKey, sink: &mut Sink, step: u32) -> OsdEvent {
+    let mut limited = false;
+    match key {
+        VolumeKey::Up => {
+            let wanted = sink.volume + step;
+            limited = wanted > sink.max_volume;
+            sink.set_mute(false);
+            sink.set_volume(wanted);
+        }
+        VolumeKey::Down => sink.set_volume(sink.volume.saturating_sub(step)),
+        VolumeKey::Mute => {
+            sink.toggle_mute();
+        }
+        VolumeKey::MicMute => {}
+    }
+
+    OsdEvent::Volume {
+        sink: sink.name.clone(),
+        description: sink.device.description.clone(),
+        volume: sink.volume,
+        muted: sink.muted,
+        max_volume: sink.max_volume,
+        limited,
+    }
+}
+
+/// Size of a `struct input_event`, and where its type follows the timestamp
+const EVENT_SIZE: usize = std::mem::size_of::<libc::input_event>();
+const TIME_SIZE: usize = std::mem::size_of::<libc::timeval>();
+
+/// A volume key event out of a raw `struct input_event`
+fn parse_event(event: &[u8; EVENT_SIZE]) -> Option<(VolumeKey, i32)> {
+    let kind = u16::from_ne_bytes([event[TIME_SIZE], event[TIME_SIZE + 1]]);
+    let code = u16::from_ne_bytes([event[TIME_SIZE + 2], event[TIME_SIZE + 3]]);
+    let value = i32::from_ne_bytes(event[TIME_SIZE + 4..TIME_SIZE + 8].try_into().ok()?);
+
+    if kind != EV_KEY {
+        return None;
+    }
+    VolumeKey::from_code(code).map(|key| (key, value))
+}
+
+/// Whether the input device at `syspath` (an `eventN` node) has the
+/// volume keys; a keyboard without them has nothing for vesper
+fn has_volume_keys(syspath: &Path) -> bool {
+    std::fs::read_to_string(syspath.join("device/capabilities/key"))
+        .map(|bitmap| has_key(&bitmap, KEY_VOLUMEUP) || has_key(&bitmap, KEY_MUTE))
+        .unwrap_or(false)
+}
+
+/// Test a key in sysfs' capability bitmap: hex words of `long` size,
+/// most significant first
+fn has_key(bitmap: &str, code: u16) -> bool {
+    let bits = usize::BITS as usize;
+    let code = code as usize;
+    bitmap
+        .split_whitespace()
+        .rev()
+        .nth(code / bits)
+        .and_then(|word| usize::from_str_radix(word, 16).ok())
+        .is_some_and(|word| word & (1 << (code % bits)) != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::device::{AudioDevice, DeviceType};
+
+    fn sink(max_volume: u32) -> Sink {
+        let mut config = Config::default();
+        config.max_volume.insert("headphones".into(), max_volume);
+        Sink::new(AudioDevice::new("headphones", DeviceType::Playback), config).unwrap()
+    }
+
+    #[test]
+    fn test_apply_limits_volume() {
+        let mut sink = sink(75);
+        assert_eq!(sink.volume, 70);
+
+        match apply(VolumeKey::Up, &mut sink, 5) {
+            OsdEvent::Volume { volume, limited, .. } => assert_eq!((volume, limited), (75, false)),
+            other => panic!("unexpected event {:?}", other),
+        }
+        match apply(VolumeKey::Up, &mut sink, 5) {
+            OsdEvent::Volume { volume, limited, .. } => assert_eq!((volume, limited), (75, true)),
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        apply(VolumeKey::Mute, &mut sink, 5);
+        assert!(sink.muted);
+        apply(VolumeKey::Down, &mut sink, 80);
+        assert_eq!(sink.volume, 0);
+        // Turning it up again unmutes
+        apply(VolumeKey::Up, &mut sink, 5);
+        assert!(!sink.muted);
+    }
+
+    #[test]
+    fn test_parse_event() {
+        let mut event = [0u8; EVENT_SIZE];
+        event[TIME_SIZE..TIME_SIZE + 2].copy_from_slice(&EV_KEY.to_ne_bytes());
+        event[TIME_SIZE + 2..TIME_SIZE + 4].copy_from_slice(&KEY_VOLUMEDOWN.to_ne_bytes());
+        event[TIME_SIZE + 4..TIME_SIZE + 8].copy_from_slice(&KEY_REPEATED.to_ne_bytes());
+        assert_eq!(parse_event(&event), Some((VolumeKey::Down, KEY_REPEATED)));
+
+        // KEY_A
+        event[TIME_SIZE + 2..TIME_SIZE + 4].copy_from_slice(&30u16.to_ne_bytes());
+        assert_eq!(parse_event(&event), None);
+    }
+
+    #[test]
+    fn test_has_key() {
+        // Bits 113-115 in the second 64-bit word
+        let bitmap = "10000 0 0 e000000000000 0\n";
+        if usize::BITS == 64 {
+            assert!(has_key(bitmap, KEY_VOLUMEUP));
+            assert!(has_key(bitmap, KEY_MUTE));
+            assert!(!has_key(bitmap, KEY_MICMUTE));
+        }
+        assert!(!has_key("0", KEY_VOLUMEUP));
+    }
+}
@@ -10,6 +10,8 @@
 //! - **Bluetooth Audio**: A2DP/HFP support (via BlueZ)
 //! - **Network Audio**: RTP streaming
 //! - **Sample Rate Conversion**: High-quality resampling
+//! - **Volume Keys**: Hardware keys with on-screen display events and
+//!   per-device volume limits
 
 mod config;
 mod device;
@@ -20,14 +22,16 @@ mod source;
 mod client;
 mod bluetooth;
 mod ipc;
+mod keys;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use libnyx_ipc::{HealthReporter, HealthStatus};
 use libnyx_platform::Platform;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use tracing::{info, warn, error};
 
 /// Vesper - Audio Daemon
@@ -77,6 +81,11 @@ enum Commands {
     SetSource { name: String },
     /// Show status
     Status,
+    /// Turn Bluetooth audio on or off
+    Bluetooth {
+        /// on or off
+        state: String,
+    },
 }
 
 #[tokio::main]
@@ -154,6 +163,19 @@ async fn handle_client_command(socket: &PathBuf, cmd: Commands) -> Result<()> {
             println!("Active Streams: {}", status.stream_count);
             println!("Master Volume:  {}%", status.master_volume);
             println!("Muted:          {}", status.muted);
+            println!("Bluetooth:      {}", if status.bluetooth_enabled { "on" } else { "off" });
+            if let Some(device) = status.bluetooth_device {
+                println!("BT Device:      {}", device);
+            }
+        }
+        Commands::Bluetooth { state } => {
+            let enabled = match state.as_str() {
+                "on" => true,
+                "off" => false,
+                _ => anyhow::bail!("Bluetooth state must be 'on' or 'off'"),
+            };
+            client.set_bluetooth(enabled).await?;
+            println!("Bluetooth {}", if enabled { "enabled" } else { "disabled" });
         }
     }
 
@@ -215,6 +237,9 @@ async fn run_daemon(args: Args) -> Result<()> {
     // Initialize client manager
     let clients = Arc::new(RwLock::new(client::ClientManager::new()));
 
+    let health = HealthReporter::new("vesper", env!("CARGO_PKG_VERSION"))
+        .with_features(&["streams", "bluetooth", "volume-keys"]);
+
     // Initialize Bluetooth if available
     let bluetooth = if config.bluetooth_enabled {
         match bluetooth::BluetoothAudio::new() {
@@ -224,6 +249,7 @@ async fn run_daemon(args: Args) -> Result<()> {
             }
             Err(e) => {
                 warn!("Bluetooth audio not available: {}", e);
+                health.set_check("bluetooth", HealthStatus::Degraded, Some(e.to_string()));
                 None
             }
         }
@@ -231,6 +257,9 @@ async fn run_daemon(args: Args) -> Result<()> {
         None
     };
 
+    health.set_gauge("sinks", sinks.read().await.len() as f64);
+    health.set_gauge("sources", sources.read().await.len() as f64);
+
     // Start audio processing
     let audio_context = AudioContext {
         device_manager,
@@ -239,11 +268,17 @@ async fn run_daemon(args: Args) -> Result<()> {
         sources,
         clients,
         bluetooth,
+        osd: broadcast::channel(16).0,
         config: config.clone(),
     };
 
+    if config.volume_keys {
+        let keys = Arc::new(keys::VolumeKeys::new(&audio_context));
+        tokio::spawn(keys.run());
+    }
+
     // Start IPC server
-    let server = ipc::VesperServer::new(args.socket.clone(), audio_context);
+    let server = ipc::VesperServer::new(args.socket.clone(), audio_context, health);
 
     info!("Vesper ready on {:?}", args.socket);
     server.run().await
@@ -257,5 +292,7 @@ pub struct AudioContext {
     pub sources: Arc<RwLock<HashMap<String, source::Source>>>,
     pub clients: Arc<RwLock<client::ClientManager>>,
     pub bluetooth: Option<Arc<RwLock<bluetooth::BluetoothAudio>>>,
+    /// Volume key presses, for the shell's on-screen display
+    pub osd: broadcast::Sender<keys::OsdEvent>,
     pub config: config::Config,
 }
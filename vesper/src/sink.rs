@@ -20,6 +20,8 @@ pub struct Sink {
     pub volume: u32,
     /// Muted
     pub muted: bool,
+    /// Safety limit for `volume`
+    pub max_volume: u32,
     /// Connected streams
     streams: HashMap<u32, Arc<std::sync::RwLock<AudioStream>>>,
     /// Is sink running
@@ -34,12 +36,15 @@ impl Sink {
             channels: config.channels,
         };
 
+        let max_volume = config.max_volume.get(&device.name).copied().unwrap_or(100).min(100);
+
         Ok(Self {
             name: device.name.clone(),
             device,
             format,
-            volume: config.default_volume,
+            volume: config.default_volume.min(max_volume),
             muted: false,
+            max_volume,
             streams: HashMap::new(),
             running: AtomicBool::new(false),
         })
@@ -64,9 +69,9 @@ impl Sink {
         self.streams.len()
     }
 
-    /// Set volume
+    /// Set volume, up to the sink's limit
     pub fn set_volume(&mut self, volume: u32) {
-        self.volume = volume.min(100);
+        self.volume = volume.min(self.max_volume);
     }
 
     /// Set mute
@@ -4,9 +4,12 @@ mod interface;
 mod config;
 mod dhcp;
 mod dns;
+mod eap;
 mod wifi;
 mod profile;
 mod ipc;
+mod netns;
+mod rfkill;
 mod state;
 
 use anyhow::Result;
@@ -14,7 +17,9 @@ use clap::{Parser, Subcommand};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixStream;
 
-use crate::ipc::{IpcRequest, IpcResponse};
+use crate::eap::{EapConfig, EapCredentials, EapMethod, Phase2};
+use crate::ipc::{IpcEvent, IpcRequest, IpcResponse};
+use crate::netns::{NamespaceConfig, NamespaceLink, VethLink};
 use crate::profile::{NetworkProfile, IpConfig, ProfileOptions};
 
 #[derive(Parser)]
@@ -91,6 +96,63 @@ enum Commands {
         #[command(subcommand)]
         command: WifiCommands,
     },
+
+    /// Network namespaces
+    Netns {
+        #[command(subcommand)]
+        command: NetnsCommands,
+    },
+
+    /// Block or unblock all radios
+    Airplane {
+        /// on or off
+        state: String,
+    },
+
+    /// Print status now and on every change
+    Watch,
+}
+
+#[derive(Subcommand)]
+enum NetnsCommands {
+    /// List namespaces
+    List,
+
+    /// Create a namespace
+    Create {
+        /// Namespace name
+        name: String,
+
+        /// Move an interface in, optionally with an address (wg0=10.8.0.2/32)
+        #[arg(long)]
+        interface: Vec<String>,
+
+        /// Link to the host with a veth pair (HOST_CIDR,NAMESPACE_CIDR)
+        #[arg(long)]
+        veth: Option<String>,
+
+        /// Default route through this address
+        #[arg(long)]
+        gateway: Option<String>,
+
+        /// Default route through this interface
+        #[arg(long, conflicts_with = "gateway")]
+        default_interface: Option<String>,
+
+        /// Keep it across reboots; otherwise it goes once nothing runs in it
+        #[arg(long)]
+        persistent: bool,
+    },
+
+    /// Delete a namespace
+    Delete {
+        /// Namespace name
+        name: String,
+
+        /// Kill processes still running in it
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -110,6 +172,12 @@ enum ProfileCommands {
     /// List profiles
     List,
 
+    /// Show a profile in full
+    Show {
+        /// Profile name
+        name: String,
+    },
+
     /// Create profile
     Create {
         /// Profile name
@@ -134,6 +202,66 @@ enum ProfileCommands {
         /// DNS servers
         #[arg(long)]
         dns: Vec<String>,
+
+        /// Mark the connection as metered
+        #[arg(long)]
+        metered: bool,
+
+        /// Authenticate with 802.1X using this method
+        #[arg(long)]
+        eap: Option<EapMethod>,
+
+        /// Inner method for PEAP and TTLS
+        #[arg(long, requires = "eap")]
+        phase2: Option<Phase2>,
+
+        /// Enterprise Wi-Fi network; without it, 802.1X is on a wired port
+        #[arg(long, requires = "eap")]
+        ssid: Option<String>,
+
+        /// Outer identity for PEAP and TTLS
+        #[arg(long, requires = "eap")]
+        anonymous_identity: Option<String>,
+
+        /// Only trust an authentication server in this domain
+        #[arg(long, requires = "eap")]
+        domain: Option<String>,
+    },
+
+    /// Save a profile's 802.1X credentials (kept in cipher)
+    Credentials {
+        /// Profile name
+        profile: String,
+
+        /// User or machine identity
+        #[arg(long)]
+        identity: String,
+
+        /// Password, for PEAP and TTLS
+        #[arg(long)]
+        password: Option<String>,
+
+        /// CA certificate (PEM file) to check the server with
+        #[arg(long)]
+        ca_cert: Option<String>,
+
+        /// Client certificate (PEM file), for TLS
+        #[arg(long)]
+        client_cert: Option<String>,
+
+        /// Private key (PEM file), for TLS
+        #[arg(long)]
+        private_key: Option<String>,
+
+        /// Passphrase of the private key
+        #[arg(long)]
+        key_password: Option<String>,
+    },
+
+    /// Log an interface out of 802.1X
+    Logout {
+        /// Interface name
+        interface: String,
     },
 
     /// Delete profile
@@ -181,6 +309,22 @@ enum WifiCommands {
         #[arg(default_value = "wlan0")]
         interface: String,
     },
+
+    /// Forget a saved network
+    Forget {
+        /// SSID
+        ssid: String,
+
+        /// Interface name
+        #[arg(long, default_value = "wlan0")]
+        interface: String,
+    },
+
+    /// Turn Wi-Fi radios on
+    On,
+
+    /// Turn Wi-Fi radios off
+    Off,
 }
 
 #[tokio::main]
@@ -230,7 +374,12 @@ async fn main() -> Result<()> {
         Commands::Profile { command } => match command {
             ProfileCommands::List => IpcRequest::ListProfiles,
 
-            ProfileCommands::Create { name, interface, dhcp, address, gateway, dns } => {
+            ProfileCommands::Show { name } => IpcRequest::GetProfile { name },
+
+            ProfileCommands::Create {
+                name, interface, dhcp, address, gateway, dns, metered,
+                eap, phase2, ssid, anonymous_identity, domain,
+            } => {
                 let config = if dhcp {
                     IpConfig::Dhcp
                 } else if let Some(addr) = address {
@@ -249,11 +398,40 @@ async fn main() -> Result<()> {
                         interface_match: interface,
                         config,
                         priority: 0,
-                        options: ProfileOptions::default(),
+                        options: ProfileOptions {
+                            metered,
+                            ..Default::default()
+                        },
+                        eap: eap.map(|method| EapConfig {
+                            method,
+                            phase2,
+                            anonymous_identity,
+                            domain_match: domain,
+                            ssid,
+                        }),
                     },
                 }
             }
 
+            ProfileCommands::Credentials {
+                profile, identity, password, ca_cert, client_cert, private_key, key_password,
+            } => {
+                let read = |path: Option<String>| path.map(std::fs::read_to_string).transpose();
+                IpcRequest::SetEapCredentials {
+                    profile,
+                    credentials: EapCredentials {
+                        identity,
+                        password,
+                        ca_cert: read(ca_cert)?,
+                        client_cert: read(client_cert)?,
+                        private_key: read(private_key)?,
+                        private_key_password: key_password,
+                    },
+                }
+            }
+
+            ProfileCommands::Logout { interface } => IpcRequest::EapDisconnect { interface },
+
             ProfileCommands::Delete { name } => IpcRequest::DeleteProfile { name },
 
             ProfileCommands::Apply { profile, interface } => {
@@ -271,7 +449,67 @@ async fn main() -> Result<()> {
             WifiCommands::Disconnect { interface } => {
                 IpcRequest::WifiDisconnect { interface }
             }
+
+            WifiCommands::Forget { ssid, interface } => {
+                IpcRequest::WifiForget { interface, ssid }
+            }
+
+            WifiCommands::On => IpcRequest::SetWifiEnabled { enabled: true },
+
+            WifiCommands::Off => IpcRequest::SetWifiEnabled { enabled: false },
+        },
+
+        Commands::Netns { command } => match command {
+            NetnsCommands::List => IpcRequest::ListNamespaces,
+
+            NetnsCommands::Create { name, interface, veth, gateway, default_interface, persistent } => {
+                let veth = match veth {
+                    Some(veth) => match veth.split_once(',') {
+                        Some((host, namespace)) => Some(VethLink {
+                            host_address: host.to_string(),
+                            namespace_address: namespace.to_string(),
+                        }),
+                        None => {
+                            eprintln!("--veth takes HOST_CIDR,NAMESPACE_CIDR");
+                            std::process::exit(1);
+                        }
+                    },
+                    None => None,
+                };
+
+                IpcRequest::CreateNamespace {
+                    namespace: NamespaceConfig {
+                        name,
+                        interfaces: interface.iter()
+                            .map(|link| match link.split_once('=') {
+                                Some((name, address)) => NamespaceLink {
+                                    name: name.to_string(),
+                                    address: Some(address.to_string()),
+                                },
+                                None => NamespaceLink { name: link.clone(), address: None },
+                            })
+                            .collect(),
+                        veth,
+                        gateway,
+                        default_interface,
+                    },
+                    persistent,
+                }
+            }
+
+            NetnsCommands::Delete { name, force } => IpcRequest::DeleteNamespace { name, force },
+        },
+
+        Commands::Airplane { state } => match state.as_str() {
+            "on" => IpcRequest::SetAirplaneMode { enabled: true },
+            "off" => IpcRequest::SetAirplaneMode { enabled: false },
+            _ => {
+                eprintln!("Airplane mode must be 'on' or 'off'");
+                std::process::exit(1);
+            }
         },
+
+        Commands::Watch => return watch(&cli.socket).await,
     };
 
     let response = send_request(&cli.socket, request).await?;
@@ -295,13 +533,30 @@ async fn send_request(socket_path: &str, request: IpcRequest) -> Result<IpcRespo
     Ok(serde_json::from_str(&line)?)
 }
 
+async fn watch(socket_path: &str) -> Result<()> {
+    let mut stream = UnixStream::connect(socket_path).await?;
+    let json = serde_json::to_string(&IpcRequest::Subscribe)?;
+    stream.write_all(json.as_bytes()).await?;
+    stream.write_all(b"\n").await?;
+    stream.flush().await?;
+
+    let mut lines = BufReader::new(stream).lines();
+    while let Some(line) = lines.next_line().await? {
+        match serde_json::from_str(&line)? {
+            IpcEvent::Status(status) => print_response(&IpcResponse::Status(status)),
+        }
+        println!();
+    }
+    Ok(())
+}
+
 fn print_response(response: &IpcResponse) {
     match response {
         IpcResponse::Success { message } => {
             println!("{}", message);
         }
 
-        IpcResponse::Interfaces(interfaces) => {
+        IpcResponse::Interfaces { interfaces } => {
             println!("{:<15} {:<17} {:<8} {:<10} {}", "INTERFACE", "MAC", "STATE", "TYPE", "ADDRESSES");
             for iface in interfaces {
                 let state = if iface.up { "up" } else { "down" };
@@ -325,14 +580,14 @@ fn print_response(response: &IpcResponse) {
             }
         }
 
-        IpcResponse::DnsServers(servers) => {
+        IpcResponse::DnsServers { servers } => {
             println!("DNS Servers:");
             for server in servers {
                 println!("  {}", server);
             }
         }
 
-        IpcResponse::WifiNetworks(networks) => {
+        IpcResponse::WifiNetworks { networks } => {
             println!("{:<32} {:<8} {:<15} {}", "SSID", "SIGNAL", "SECURITY", "CONNECTED");
             for net in networks {
                 let connected = if net.connected { "*" } else { "" };
@@ -340,15 +595,58 @@ fn print_response(response: &IpcResponse) {
             }
         }
 
-        IpcResponse::Profiles(profiles) => {
-            println!("{:<20} {:<15} {}", "NAME", "INTERFACE", "TYPE");
+        IpcResponse::Profiles { profiles } => {
+            println!("{:<20} {:<15} {:<8} {}", "NAME", "INTERFACE", "TYPE", "METERED");
             for profile in profiles {
-                println!("{:<20} {:<15} {}", profile.name, profile.interface_match, profile.config_type);
+                let metered = if profile.metered { "yes" } else { "" };
+                println!("{:<20} {:<15} {:<8} {}", profile.name, profile.interface_match, profile.config_type, metered);
             }
         }
 
+        IpcResponse::Profile { profile } => {
+            println!("Profile: {}", profile.name);
+            println!("  Interface: {}", profile.interface_match);
+            println!("  Priority:  {}", profile.priority);
+            match &profile.config {
+                IpConfig::Dhcp => println!("  Address:   DHCP"),
+                IpConfig::Static { address, gateway, dns } => {
+                    println!("  Address:   {}", address);
+                    println!("  Gateway:   {}", gateway.as_deref().unwrap_or("-"));
+                    println!("  DNS:       {}", dns.join(", "));
+                }
+            }
+            println!("  Metered:   {}", if profile.options.metered { "yes" } else { "no" });
+            if let Some(eap) = &profile.eap {
+                println!("  802.1X:    {:?}", eap.method);
+                if let Some(ssid) = &eap.ssid {
+                    println!("  SSID:      {}", ssid);
+                }
+                if let Some(domain) = &eap.domain_match {
+                    println!("  Server:    *.{}", domain);
+                }
+            }
+        }
+
+        IpcResponse::Namespaces { namespaces } => {
+            println!("{:<20} {:<8} {:<11} {}", "NAME", "STATE", "LIFETIME", "PROCESSES");
+            for ns in namespaces {
+                let state = if ns.active { "up" } else { "down" };
+                let lifetime = if ns.persistent { "persistent" } else { "ephemeral" };
+                println!("{:<20} {:<8} {:<11} {}", ns.name, state, lifetime, ns.pids.len());
+            }
+        }
+
+        IpcResponse::Namespace { namespace } => {
+            println!("Namespace: {}", namespace.name);
+            println!("  Path:      {}", namespace.path);
+            println!("  Lifetime:  {}", if namespace.persistent { "persistent" } else { "ephemeral" });
+            println!("  Processes: {}", namespace.pids.len());
+        }
+
         IpcResponse::Status(status) => {
             println!("Hostname: {}", status.hostname);
+            println!("Wi-Fi: {}", if status.wifi_enabled { "on" } else { "off" });
+            println!("Airplane mode: {}", if status.airplane_mode { "on" } else { "off" });
             println!("\nDNS Servers: {}", status.dns_servers.join(", "));
             println!("\nInterfaces:");
             for iface in &status.interfaces {
@@ -360,6 +658,16 @@ fn print_response(response: &IpcResponse) {
                 };
                 println!("  {}: {} - {}", iface.name, state, addrs);
             }
+            if !status.auth.is_empty() {
+                println!("\n802.1X:");
+                for auth in &status.auth {
+                    print!("  {}: {} ({:?}) - {:?}", auth.interface, auth.profile, auth.method, auth.phase);
+                    match &auth.message {
+                        Some(message) => println!(": {}", message),
+                        None => println!(),
+                    }
+                }
+            }
         }
 
         IpcResponse::Error { message } => {
@@ -0,0 +1,473 @@
+//! 802.1X authentication
+//!
+//! Enterprise Wi-Fi and wired 802.1X both go through wpa_supplicant's EAP
+//! implementation. A profile's `eap` section says how to authenticate: the
+//! method, the inner method for PEAP and TTLS, and which server to trust.
+//! The identity, password, certificates and private key are kept in cipher
+//! under the profile's name and only read when connecting; wraith never
+//! hands them back out. Certificates and keys are written to owner-only
+//! files under `/run/wraith/eap` for wpa_supplicant, and removed when the
+//! network is left.
+//!
+//! While connecting, wpa_supplicant's state is followed and reported as an
+//! [`AuthPhase`] in wraith's status, so a failing login shows where it
+//! gets stuck.
+
+use anyhow::{anyhow, Result};
+use libnyx_ipc::SecretsClient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::profile::NetworkProfile;
+use crate::state::WraithState;
+use crate::wifi::WifiManager;
+
+/// Cipher collection holding EAP credentials
+const SECRETS_COLLECTION: &str = "wraith";
+
+/// Where certificates and keys are written for wpa_supplicant
+const CERT_DIR: &str = "/run/wraith/eap";
+
+/// EAP exchanges go through the RADIUS server and can be slow
+const AUTH_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How a profile authenticates
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EapConfig {
+    pub method: EapMethod,
+    /// Inner method for PEAP and TTLS; MSCHAPv2 when unset
+    #[serde(default)]
+    pub phase2: Option<Phase2>,
+    /// Outer identity PEAP and TTLS send before the tunnel is up
+    #[serde(default)]
+    pub anonymous_identity: Option<String>,
+    /// Only trust a server certificate for this domain or a subdomain
+    #[serde(default)]
+    pub domain_match: Option<String>,
+    /// Enterprise Wi-Fi network; unset for wired 802.1X
+    #[serde(default)]
+    pub ssid: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum EapMethod {
+    /// Client certificate
+    Tls,
+    /// Password inside a TLS tunnel
+    Peap,
+    Ttls,
+}
+
+impl EapMethod {
+    fn name(self) -> &'static str {
+        match self {
+            EapMethod::Tls => "TLS",
+            EapMethod::Peap => "PEAP",
+            EapMethod::Ttls => "TTLS",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase2 {
+    Mschapv2,
+    Gtc,
+    Pap,
+}
+
+impl Phase2 {
+    fn name(self) -> &'static str {
+        match self {
+            Phase2::Mschapv2 => "MSCHAPV2",
+            Phase2::Gtc => "GTC",
+            Phase2::Pap => "PAP",
+        }
+    }
+}
+
+/// What a profile logs in with, as kept in cipher
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct EapCredentials {
+    pub identity: String,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// PEM certificate of the authority that signed the server's
+    #[serde(default)]
+    pub ca_cert: Option<String>,
+    /// PEM, for TLS
+    #[serde(default)]
+    pub client_cert: Option<String>,
+    /// PEM, for TLS
+    #[serde(default)]
+    pub private_key: Option<String>,
+    #[serde(default)]
+    pub private_key_password: Option<String>,
+}
+
+impl std::fmt::Debug for EapCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EapCredentials")
+            .field("identity", &self.identity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Where an 802.1X login is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuthPhase {
+    /// wpa_supplicant has the network but has not started
+    Starting,
+    /// Finding and joining the access point
+    Associating,
+    /// EAP exchange with the authentication server
+    Authenticating,
+    /// Deriving Wi-Fi keys after EAP succeeded
+    Handshake,
+    Authenticated,
+    Failed,
+}
+
+/// 802.1X state of an interface, for wraith's status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthStatus {
+    pub interface: String,
+    pub profile: String,
+    pub method: EapMethod,
+    pub phase: AuthPhase,
+    /// Why it failed
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+fn secret_id(profile: &str) -> String {
+    format!("eap:{}", profile)
+}
+
+/// Keep a profile's credentials in cipher
+pub async fn store_credentials(profile: &str, credentials: &EapCredentials) -> Result<()> {
+    SecretsClient::new()
+        .store(
+            SECRETS_COLLECTION,
+            &secret_id(profile),
+            &format!("802.1X credentials for {}", profile),
+            &serde_json::to_string(credentials)?,
+        )
+        .await?;
+    Ok(())
+}
+
+async fn load_credentials(profile: &str) -> Result<EapCredentials> {
+    let secret = SecretsClient::new()
+        .lookup(SECRETS_COLLECTION, &secret_id(profile))
+        .await
+        .map_err(|e| anyhow!("Cannot read 802.1X credentials from cipher: {}", e))?
+        .ok_or_else(|| anyhow!("No 802.1X credentials saved for {}", profile))?;
+    Ok(serde_json::from_str(&secret)?)
+}
+
+/// Drop a profile's credentials and certificate files
+pub async fn delete_credentials(profile: &str) -> Result<()> {
+    remove_cert_files(profile);
+    SecretsClient::new()
+        .delete(SECRETS_COLLECTION, &secret_id(profile))
+        .await?;
+    Ok(())
+}
+
+/// Authenticate `interface` with a profile, then configure its addresses
+pub async fn connect(state: &RwLock<WraithState>, interface: &str, profile: &NetworkProfile) -> Result<()> {
+    let eap = profile.eap.as_ref()
+        .ok_or_else(|| anyhow!("Profile {} has no 802.1X settings", profile.name))?;
+    let credentials = load_credentials(&profile.name).await?;
+    if credentials.ca_cert.is_none() && eap.domain_match.is_none() {
+        warn!("Profile {} trusts any authentication server", profile.name);
+    }
+
+    let files = write_cert_files(&cert_dir(&profile.name), &credentials)?;
+    let settings = network_settings(eap, &credentials, &files)?;
+
+    let mut manager = match &eap.ssid {
+        Some(_) => WifiManager::attach(interface).await?,
+        None => WifiManager::attach_wired(interface).await?,
+    };
+    info!("Authenticating {} with {} ({})", interface, profile.name, eap.method.name());
+    let status = |phase, message| AuthStatus {
+        interface: interface.to_string(),
+        profile: profile.name.clone(),
+        method: eap.method,
+        phase,
+        message,
+    };
+    set_status(state, status(AuthPhase::Starting, None)).await;
+    manager.add_eap_network(eap.ssid.as_deref(), &settings).await?;
+
+    let mut phase = AuthPhase::Starting;
+    let deadline = tokio::time::Instant::now() + AUTH_TIMEOUT;
+    while phase != AuthPhase::Authenticated {
+        if tokio::time::Instant::now() >= deadline {
+            let message = format!("Timed out while {:?}", phase).to_lowercase();
+            set_status(state, status(AuthPhase::Failed, Some(message.clone()))).await;
+            return Err(anyhow!("802.1X authentication on {}: {}", interface, message));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        let current = auth_phase(&manager.get_status().await?);
+        if current == phase {
+            continue;
+        }
+        phase = current;
+        if phase == AuthPhase::Failed {
+            let message = "The server rejected the credentials".to_string();
+            set_status(state, status(phase, Some(message.clone()))).await;
+            manager.disconnect().await?;
+            return Err(anyhow!("802.1X authentication on {} failed: {}", interface, message));
+        }
+        set_status(state, status(phase, None)).await;
+    }
+    info!("{} authenticated with {}", interface, profile.name);
+
+    state.write().await.apply_profile(interface, profile).await
+}
+
+/// Leave an 802.1X network and forget its status
+pub async fn disconnect(state: &RwLock<WraithState>, interface: &str) -> Result<()> {
+    let status = state.write().await.auth.remove(interface);
+    if let Some(status) = &status {
+        remove_cert_files(&status.profile);
+    }
+    WifiManager::attach_existing(interface)?.disconnect().await
+}
+
+async fn set_status(state: &RwLock<WraithState>, status: AuthStatus) {
+    let mut state = state.write().await;
+    state.auth.insert(status.interface.clone(), status);
+    state.notify();
+}
+
+/// Where wpa_supplicant is, from its `status` output
+pub fn auth_phase(status: &HashMap<String, String>) -> AuthPhase {
+    let get = |key: &str| status.get(key).map(String::as_str).unwrap_or("");
+
+    if get("EAP state") == "FAILURE" || get("Supplicant PAE state") == "HELD" {
+        return AuthPhase::Failed;
+    }
+    if get("wpa_state") == "COMPLETED" || get("suppPortStatus") == "Authorized" {
+        return AuthPhase::Authenticated;
+    }
+    match get("wpa_state") {
+        "4WAY_HANDSHAKE" | "GROUP_HANDSHAKE" => return AuthPhase::Handshake,
+        "SCANNING" | "AUTHENTICATING" | "ASSOCIATING" => return AuthPhase::Associating,
+        _ => {}
+    }
+    if matches!(get("Supplicant PAE state"), "CONNECTING" | "AUTHENTICATING")
+        || !matches!(get("EAP state"), "" | "IDLE" | "DISABLED" | "INITIALIZE")
+        || get("wpa_state") == "ASSOCIATED"
+    {
+        return AuthPhase::Authenticating;
+    }
+    AuthPhase::Starting
+}
+
+/// Certificate and key files written for one connection
+#[derive(Debug, Default)]
+struct CertFiles {
+    ca_cert: Option<PathBuf>,
+    client_cert: Option<PathBuf>,
+    private_key: Option<PathBuf>,
+}
+
+fn cert_dir(profile: &str) -> PathBuf {
+    let name: String = profile
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    Path::new(CERT_DIR).join(name)
+}
+
+fn write_cert_files(dir: &Path, credentials: &EapCredentials) -> Result<CertFiles> {
+    std::fs::create_dir_all(dir)?;
+    let write = |name: &str, pem: &Option<String>| -> Result<Option<PathBuf>> {
+        let Some(pem) = pem else { return Ok(None) };
+        let path = dir.join(name);
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&path)?;
+        file.write_all(pem.as_bytes())?;
+        Ok(Some(path))
+    };
+
+    Ok(CertFiles {
+        ca_cert: write("ca.pem", &credentials.ca_cert)?,
+        client_cert: write("client.pem", &credentials.client_cert)?,
+        private_key: write("key.pem", &credentials.private_key)?,
+    })
+}
+
+fn remove_cert_files(profile: &str) {
+    let _ = std::fs::remove_dir_all(cert_dir(profile));
+}
+
+/// wpa_supplicant network settings for a profile
+fn network_settings(
+    eap: &EapConfig,
+    credentials: &EapCredentials,
+    files: &CertFiles,
+) -> Result<Vec<(&'static str, String)>> {
+    let mut settings = Vec::new();
+    match &eap.ssid {
+        Some(ssid) => {
+            settings.push(("ssid", quote(ssid)?));
+            settings.push(("key_mgmt", "WPA-EAP".to_string()));
+        }
+        None => {
+            settings.push(("key_mgmt", "IEEE8021X".to_string()));
+            // Wired ports carry no keys
+            settings.push(("eapol_flags", "0".to_string()));
+        }
+    }
+    settings.push(("eap", eap.method.name().to_string()));
+    settings.push(("identity", quote(&credentials.identity)?));
+
+    match eap.method {
+        EapMethod::Tls => {
+            let (Some(cert), Some(key)) = (&files.client_cert, &files.private_key) else {
+                return Err(anyhow!("EAP-TLS needs a client certificate and private key"));
+            };
+            settings.push(("client_cert", quote(&cert.to_string_lossy())?));
+            settings.push(("private_key", quote(&key.to_string_lossy())?));
+            if let Some(password) = &credentials.private_key_password {
+                settings.push(("private_key_passwd", quote(password)?));
+            }
+        }
+        EapMethod::Peap | EapMethod::Ttls => {
+            let password = credentials.password.as_deref()
+                .ok_or_else(|| anyhow!("EAP-{} needs a password", eap.method.name()))?;
+            settings.push(("password", quote(password)?));
+            let phase2 = eap.phase2.unwrap_or(Phase2::Mschapv2);
+            settings.push(("phase2", quote(&format!("auth={}", phase2.name()))?));
+            if let Some(anonymous) = &eap.anonymous_identity {
+                settings.push(("anonymous_identity", quote(anonymous)?));
+            }
+        }
+    }
+
+    if let Some(ca_cert) = &files.ca_cert {
+        settings.push(("ca_cert", quote(&ca_cert.to_string_lossy())?));
+    }
+    if let Some(domain) = &eap.domain_match {
+        settings.push(("domain_suffix_match", quote(domain)?));
+    }
+    Ok(settings)
+}
+
+/// A wpa_supplicant string value; these cannot hold quotes or line breaks
+fn quote(value: &str) -> Result<String> {
+    if value.contains('"') || value.chars().any(char::is_control) {
+        return Err(anyhow!("802.1X settings cannot contain quotes or control characters"));
+    }
+    Ok(format!("\"{}\"", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_auth_phase() {
+        assert_eq!(auth_phase(&status(&[("wpa_state", "SCANNING")])), AuthPhase::Associating);
+        assert_eq!(
+            auth_phase(&status(&[("wpa_state", "ASSOCIATED"), ("EAP state", "METHOD")])),
+            AuthPhase::Authenticating
+        );
+        assert_eq!(auth_phase(&status(&[("wpa_state", "4WAY_HANDSHAKE")])), AuthPhase::Handshake);
+        assert_eq!(auth_phase(&status(&[("wpa_state", "COMPLETED")])), AuthPhase::Authenticated);
+        // Wired ports report the 802.1X port instead
+        assert_eq!(
+            auth_phase(&status(&[("Supplicant PAE state", "AUTHENTICATING"), ("suppPortStatus", "Unauthorized")])),
+            AuthPhase::Authenticating
+        );
+        assert_eq!(
+            auth_phase(&status(&[("Supplicant PAE state", "AUTHENTICATED"), ("suppPortStatus", "Authorized")])),
+            AuthPhase::Authenticated
+        );
+        assert_eq!(auth_phase(&status(&[("Supplicant PAE state", "HELD")])), AuthPhase::Failed);
+    }
+
+    #[test]
+    fn test_peap_settings() {
+        let eap = EapConfig {
+            method: EapMethod::Peap,
+            phase2: None,
+            anonymous_identity: Some("anonymous@corp.example".into()),
+            domain_match: Some("radius.corp.example".into()),
+            ssid: Some("Corp".into()),
+        };
+        let credentials = EapCredentials {
+            identity: "ada@corp.example".into(),
+            password: Some("hunter2".into()),
+            ..Default::default()
+        };
+        let files = CertFiles {
+            ca_cert: Some("/run/wraith/eap/Corp/ca.pem".into()),
+            ..Default::default()
+        };
+
+        let settings: HashMap<_, _> = network_settings(&eap, &credentials, &files).unwrap().into_iter().collect();
+        assert_eq!(settings["ssid"], "\"Corp\"");
+        assert_eq!(settings["key_mgmt"], "WPA-EAP");
+        assert_eq!(settings["eap"], "PEAP");
+        assert_eq!(settings["phase2"], "\"auth=MSCHAPV2\"");
+        assert_eq!(settings["ca_cert"], "\"/run/wraith/eap/Corp/ca.pem\"");
+        assert_eq!(settings["domain_suffix_match"], "\"radius.corp.example\"");
+    }
+
+    #[test]
+    fn test_wired_tls_settings() {
+        let eap = EapConfig {
+            method: EapMethod::Tls,
+            phase2: None,
+            anonymous_identity: None,
+            domain_match: None,
+            ssid: None,
+        };
+        let credentials = EapCredentials {
+            identity: "host/ws42".into(),
+            ..Default::default()
+        };
+        // A certificate-less TLS profile cannot work
+        assert!(network_settings(&eap, &credentials, &CertFiles::default()).is_err());
+
+        let files = CertFiles {
+            client_cert: Some("/run/wraith/eap/Office/client.pem".into()),
+            private_key: Some("/run/wraith/eap/Office/key.pem".into()),
+            ..Default::default()
+        };
+        let settings: HashMap<_, _> = network_settings(&eap, &credentials, &files).unwrap().into_iter().collect();
+        assert_eq!(settings["key_mgmt"], "IEEE8021X");
+        assert_eq!(settings["eapol_flags"], "0");
+        assert!(!settings.contains_key("ssid"));
+        assert!(!settings.contains_key("password"));
+    }
+
+    #[test]
+    fn test_quote_rejects_injection() {
+        assert!(quote("ada\"\nnetwork={").is_err());
+        assert_eq!(quote("ada").unwrap(), "\"ada\"");
+    }
+}
@@ -171,7 +171,7 @@ impl InterfaceManager {
             InterfaceType::Ethernet
         } else if name.starts_with("br") {
             InterfaceType::Bridge
-        } else if name.starts_with("tun") || name.starts_with("tap") {
+        } else if name.starts_with("tun") || name.starts_with("tap") || name.starts_with("wg") {
             InterfaceType::Tunnel
         } else if name.starts_with("veth") || name.starts_with("docker") {
             InterfaceType::Virtual
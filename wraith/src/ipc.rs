@@ -1,16 +1,21 @@
 //! IPC interface for Wraith
 
 use anyhow::Result;
+use libnyx_ipc::HealthReporter;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::RwLock;
-use tracing::{info, error, debug};
+use tracing::{info, error, debug, warn};
 
+use crate::eap::{self, AuthStatus, EapCredentials};
 use crate::state::WraithState;
 use crate::interface::NetworkInterface;
+use crate::netns::{NamespaceConfig, NamespaceInfo};
 use crate::profile::{NetworkProfile, IpConfig};
+use crate::rfkill::{self, RadioType};
+use crate::wifi::WifiManager;
 
 /// IPC request
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,10 +55,19 @@ pub enum IpcRequest {
     /// Disconnect WiFi
     WifiDisconnect { interface: String },
 
+    /// Remove a saved WiFi network
+    WifiForget { interface: String, ssid: String },
+
+    /// Turn the Wi-Fi radios on or off
+    SetWifiEnabled { enabled: bool },
+
+    /// Block or unblock every radio
+    SetAirplaneMode { enabled: bool },
+
     /// List profiles
     ListProfiles,
 
-    /// Apply profile
+    /// Apply profile, authenticating first if it uses 802.1X
     ApplyProfile { interface: String, profile: String },
 
     /// Create/update profile
@@ -62,8 +76,55 @@ pub enum IpcRequest {
     /// Delete profile
     DeleteProfile { name: String },
 
+    /// Get a profile in full, for editing
+    GetProfile { name: String },
+
+    /// Keep a profile's 802.1X credentials in cipher; they are never sent back
+    SetEapCredentials { profile: String, credentials: EapCredentials },
+
+    /// Log an interface out of 802.1X
+    EapDisconnect { interface: String },
+
+    /// List network namespaces
+    ListNamespaces,
+
+    /// Set up a network namespace; persistent ones come back at boot,
+    /// ephemeral ones go once nothing runs in them
+    CreateNamespace { namespace: NamespaceConfig, persistent: bool },
+
+    /// Get a namespace to start processes in, setting up a saved one if needed
+    EnterNamespace { name: String },
+
+    /// Tear down and forget a namespace; `force` kills what still runs in it
+    DeleteNamespace { name: String, force: bool },
+
     /// Get overall status
     GetStatus,
+
+    /// Keep the connection open and push a `Status` event on every change
+    Subscribe,
+}
+
+impl IpcRequest {
+    /// Whether handling this request can change what `GetStatus` reports
+    fn changes_status(&self) -> bool {
+        !matches!(
+            self,
+            IpcRequest::ListInterfaces
+                | IpcRequest::GetInterface { .. }
+                | IpcRequest::GetDns
+                | IpcRequest::WifiScan { .. }
+                | IpcRequest::ListProfiles
+                | IpcRequest::GetProfile { .. }
+                | IpcRequest::SetEapCredentials { .. }
+                | IpcRequest::ListNamespaces
+                | IpcRequest::CreateNamespace { .. }
+                | IpcRequest::EnterNamespace { .. }
+                | IpcRequest::DeleteNamespace { .. }
+                | IpcRequest::GetStatus
+                | IpcRequest::Subscribe
+        )
+    }
 }
 
 /// IPC response
@@ -71,11 +132,14 @@ pub enum IpcRequest {
 #[serde(tag = "status")]
 pub enum IpcResponse {
     Success { message: String },
-    Interfaces(Vec<InterfaceInfo>),
+    Interfaces { interfaces: Vec<InterfaceInfo> },
     Interface(InterfaceInfo),
-    DnsServers(Vec<String>),
-    WifiNetworks(Vec<WifiNetworkInfo>),
-    Profiles(Vec<ProfileInfo>),
+    DnsServers { servers: Vec<String> },
+    WifiNetworks { networks: Vec<WifiNetworkInfo> },
+    Profiles { profiles: Vec<ProfileInfo> },
+    Profile { profile: NetworkProfile },
+    Namespaces { namespaces: Vec<NamespaceInfo> },
+    Namespace { namespace: NamespaceInfo },
     Status(NetworkStatus),
     Error { message: String },
 }
@@ -96,6 +160,9 @@ pub struct WifiNetworkInfo {
     pub signal: i32,
     pub security: String,
     pub connected: bool,
+    /// Credentials are stored, so it can be forgotten
+    #[serde(default)]
+    pub saved: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -103,6 +170,17 @@ pub struct ProfileInfo {
     pub name: String,
     pub interface_match: String,
     pub config_type: String,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub metered: bool,
+}
+
+/// Pushed to subscribed clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum IpcEvent {
+    Status(NetworkStatus),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -110,6 +188,13 @@ pub struct NetworkStatus {
     pub interfaces: Vec<InterfaceInfo>,
     pub dns_servers: Vec<String>,
     pub hostname: String,
+    #[serde(default)]
+    pub wifi_enabled: bool,
+    #[serde(default)]
+    pub airplane_mode: bool,
+    /// 802.1X logins
+    #[serde(default)]
+    pub auth: Vec<AuthStatus>,
 }
 
 impl From<&NetworkInterface> for InterfaceInfo {
@@ -131,13 +216,15 @@ impl From<&NetworkInterface> for InterfaceInfo {
 pub struct WraithServer {
     socket_path: String,
     state: Arc<RwLock<WraithState>>,
+    health: HealthReporter,
 }
 
 impl WraithServer {
-    pub fn new(socket_path: &str, state: Arc<RwLock<WraithState>>) -> Self {
+    pub fn new(socket_path: &str, state: Arc<RwLock<WraithState>>, health: HealthReporter) -> Self {
         Self {
             socket_path: socket_path.to_string(),
             state,
+            health,
         }
     }
 
@@ -156,8 +243,9 @@ impl WraithServer {
             match listener.accept().await {
                 Ok((stream, _)) => {
                     let state = self.state.clone();
+                    let health = self.health.clone();
                     tokio::spawn(async move {
-                        if let Err(e) = handle_client(stream, state).await {
+                        if let Err(e) = handle_client(stream, state, health).await {
                             error!("Client error: {}", e);
                         }
                     });
@@ -171,14 +259,31 @@ impl WraithServer {
 async fn handle_client(
     stream: UnixStream,
     state: Arc<RwLock<WraithState>>,
+    health: HealthReporter,
 ) -> Result<()> {
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
 
     while reader.read_line(&mut line).await? > 0 {
+        if let Some(reply) = health.respond(&line) {
+            writer.write_all(reply.as_bytes()).await?;
+            writer.write_all(b"\n").await?;
+            writer.flush().await?;
+            line.clear();
+            continue;
+        }
+
         let response = match serde_json::from_str::<IpcRequest>(&line) {
-            Ok(request) => process_request(request, &state).await,
+            Ok(IpcRequest::Subscribe) => return stream_status(writer, &state).await,
+            Ok(request) => {
+                let notify = request.changes_status();
+                let response = process_request(request, &state).await;
+                if notify && !matches!(response, IpcResponse::Error { .. }) {
+                    state.read().await.notify();
+                }
+                response
+            }
             Err(e) => IpcResponse::Error { message: e.to_string() },
         };
 
@@ -193,6 +298,45 @@ async fn handle_client(
     Ok(())
 }
 
+/// Send the current status, then again after every change, until the client goes away
+async fn stream_status(
+    mut writer: tokio::net::unix::OwnedWriteHalf,
+    state: &RwLock<WraithState>,
+) -> Result<()> {
+    let mut changes = state.read().await.changes.subscribe();
+    loop {
+        let event = IpcEvent::Status(network_status(&*state.read().await));
+        let json = serde_json::to_string(&event)?;
+        writer.write_all(json.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+        writer.flush().await?;
+
+        // Bursts of netlink events collapse into one update
+        if changes.changed().await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+fn network_status(state: &WraithState) -> NetworkStatus {
+    let interfaces = state.interfaces.list()
+        .unwrap_or_default()
+        .iter()
+        .map(InterfaceInfo::from)
+        .collect();
+
+    let radios = rfkill::list().unwrap_or_default();
+
+    NetworkStatus {
+        interfaces,
+        dns_servers: state.dns.get_servers().to_vec(),
+        hostname: state.config.hostname.clone(),
+        wifi_enabled: rfkill::wifi_enabled(&radios),
+        airplane_mode: rfkill::airplane_mode(&radios),
+        auth: state.auth.values().cloned().collect(),
+    }
+}
+
 async fn process_request(
     request: IpcRequest,
     state: &RwLock<WraithState>,
@@ -205,7 +349,7 @@ async fn process_request(
                     let infos: Vec<InterfaceInfo> = interfaces.iter()
                         .map(InterfaceInfo::from)
                         .collect();
-                    IpcResponse::Interfaces(infos)
+                    IpcResponse::Interfaces { interfaces: infos }
                 }
                 Err(e) => IpcResponse::Error { message: e.to_string() },
             }
@@ -253,7 +397,7 @@ async fn process_request(
 
         IpcRequest::GetDns => {
             let state = state.read().await;
-            IpcResponse::DnsServers(state.dns.get_servers().to_vec())
+            IpcResponse::DnsServers { servers: state.dns.get_servers().to_vec() }
         }
 
         IpcRequest::SetDns { servers } => {
@@ -277,15 +421,33 @@ async fn process_request(
                         IpConfig::Dhcp => "DHCP".to_string(),
                         IpConfig::Static { .. } => "Static".to_string(),
                     },
+                    priority: p.priority,
+                    metered: p.options.metered,
                 })
                 .collect();
-            IpcResponse::Profiles(profiles)
+            IpcResponse::Profiles { profiles }
+        }
+
+        IpcRequest::GetProfile { name } => {
+            let state = state.read().await;
+            match state.profiles.get(&name) {
+                Some(profile) => IpcResponse::Profile { profile: profile.clone() },
+                None => IpcResponse::Error {
+                    message: format!("Profile not found: {}", name),
+                },
+            }
         }
 
         IpcRequest::ApplyProfile { interface, profile } => {
-            let mut state = state.write().await;
-            if let Some(prof) = state.profiles.get(&profile).cloned() {
-                match state.apply_profile(&interface, &prof).await {
+            let found = state.read().await.profiles.get(&profile).cloned();
+            if let Some(prof) = found {
+                // Authentication takes a while and updates state as it goes,
+                // so the lock is only taken around each step
+                let result = match prof.eap {
+                    Some(_) => eap::connect(state, &interface, &prof).await,
+                    None => state.write().await.apply_profile(&interface, &prof).await,
+                };
+                match result {
                     Ok(()) => IpcResponse::Success {
                         message: format!("Applied {} to {}", profile, interface),
                     },
@@ -310,35 +472,161 @@ async fn process_request(
 
         IpcRequest::DeleteProfile { name } => {
             let mut state = state.write().await;
+            let eap = state.profiles.get(&name).is_some_and(|p| p.eap.is_some());
             match state.profiles.delete(&name) {
+                Ok(()) => {
+                    if eap {
+                        if let Err(e) = eap::delete_credentials(&name).await {
+                            warn!("Could not delete 802.1X credentials of {}: {}", name, e);
+                        }
+                    }
+                    IpcResponse::Success {
+                        message: format!("Deleted profile: {}", name),
+                    }
+                }
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::SetEapCredentials { profile, credentials } => {
+            match eap::store_credentials(&profile, &credentials).await {
                 Ok(()) => IpcResponse::Success {
-                    message: format!("Deleted profile: {}", name),
+                    message: format!("Saved 802.1X credentials for {}", profile),
                 },
                 Err(e) => IpcResponse::Error { message: e.to_string() },
             }
         }
 
-        IpcRequest::GetStatus => {
+        IpcRequest::EapDisconnect { interface } => {
+            match eap::disconnect(state, &interface).await {
+                Ok(()) => IpcResponse::Success {
+                    message: format!("Logged {} out", interface),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::ListNamespaces => {
             let state = state.read().await;
-            let interfaces = state.interfaces.list()
-                .unwrap_or_default()
-                .iter()
-                .map(InterfaceInfo::from)
-                .collect();
+            IpcResponse::Namespaces { namespaces: state.namespaces.list().await }
+        }
+
+        IpcRequest::CreateNamespace { namespace, persistent } => {
+            let mut state = state.write().await;
+            match state.namespaces.create(namespace, persistent).await {
+                Ok(namespace) => IpcResponse::Namespace { namespace },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
 
-            IpcResponse::Status(NetworkStatus {
-                interfaces,
-                dns_servers: state.dns.get_servers().to_vec(),
-                hostname: state.config.hostname.clone(),
-            })
+        IpcRequest::EnterNamespace { name } => {
+            let mut state = state.write().await;
+            match state.namespaces.enter(&name).await {
+                Ok(namespace) => IpcResponse::Namespace { namespace },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
         }
 
-        IpcRequest::WifiScan { .. } |
-        IpcRequest::WifiConnect { .. } |
-        IpcRequest::WifiDisconnect { .. } => {
-            IpcResponse::Error {
-                message: "WiFi not yet implemented".to_string(),
+        IpcRequest::DeleteNamespace { name, force } => {
+            let mut state = state.write().await;
+            match state.namespaces.delete(&name, force).await {
+                Ok(()) => IpcResponse::Success {
+                    message: format!("Deleted namespace: {}", name),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::GetStatus => {
+            IpcResponse::Status(network_status(&*state.read().await))
+        }
+
+        // Handled by handle_client, which turns the connection into a stream
+        IpcRequest::Subscribe => IpcResponse::Error {
+            message: "Subscribe must be sent on its own connection".to_string(),
+        },
+
+        IpcRequest::SetWifiEnabled { enabled } => {
+            match rfkill::set_blocked(Some(RadioType::Wlan), !enabled) {
+                Ok(()) => IpcResponse::Success {
+                    message: format!("Wi-Fi {}", if enabled { "enabled" } else { "disabled" }),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
             }
         }
+
+        IpcRequest::SetAirplaneMode { enabled } => {
+            match rfkill::set_blocked(None, enabled) {
+                Ok(()) => IpcResponse::Success {
+                    message: format!("Airplane mode {}", if enabled { "on" } else { "off" }),
+                },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        // WiFi requests talk to wpa_supplicant directly and don't touch
+        // shared state, so a multi-second scan doesn't stall other clients
+        IpcRequest::WifiScan { interface } => {
+            match wifi_scan(&interface).await {
+                Ok(networks) => IpcResponse::WifiNetworks { networks },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::WifiConnect { interface, ssid, password } => {
+            let result = async {
+                WifiManager::attach(&interface).await?
+                    .connect(&ssid, password.as_deref()).await
+            }.await;
+            match result {
+                Ok(()) => IpcResponse::Success { message: format!("Connected to {}", ssid) },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::WifiDisconnect { interface } => {
+            let result = async { WifiManager::attach(&interface).await?.disconnect().await }.await;
+            match result {
+                Ok(()) => IpcResponse::Success { message: format!("Disconnected {}", interface) },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+
+        IpcRequest::WifiForget { interface, ssid } => {
+            let result = async { WifiManager::attach(&interface).await?.forget(&ssid).await }.await;
+            match result {
+                Ok(()) => IpcResponse::Success { message: format!("Forgot {}", ssid) },
+                Err(e) => IpcResponse::Error { message: e.to_string() },
+            }
+        }
+    }
+}
+
+/// Scan results, one entry per SSID, marked connected/saved
+async fn wifi_scan(interface: &str) -> Result<Vec<WifiNetworkInfo>> {
+    let manager = WifiManager::attach(interface).await?;
+    let networks = manager.scan().await?;
+    let status = manager.get_status().await?;
+    let current = status.get("ssid")
+        .filter(|_| status.get("wpa_state").map(String::as_str) == Some("COMPLETED"));
+    let saved: Vec<String> = manager.configured_networks().await?
+        .into_iter()
+        .map(|(_, ssid)| ssid)
+        .collect();
+
+    // Scan results come strongest first; keep the best access point per SSID
+    let mut infos: Vec<WifiNetworkInfo> = Vec::new();
+    for network in networks {
+        if network.ssid.is_empty() || infos.iter().any(|n| n.ssid == network.ssid) {
+            continue;
+        }
+        infos.push(WifiNetworkInfo {
+            connected: current == Some(&network.ssid),
+            saved: saved.contains(&network.ssid),
+            security: format!("{:?}", network.security),
+            signal: network.signal_strength,
+            ssid: network.ssid,
+        });
     }
+    Ok(infos)
 }
@@ -11,14 +11,19 @@ mod interface;
 mod config;
 mod dhcp;
 mod dns;
+mod eap;
 mod wifi;
 mod profile;
 mod ipc;
+mod netns;
+mod rfkill;
 mod state;
 
 use anyhow::Result;
 use clap::Parser;
+use libnyx_ipc::{HealthReporter, HealthStatus};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use tracing::{info, error, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
@@ -26,6 +31,9 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilte
 use crate::ipc::WraithServer;
 use crate::state::WraithState;
 
+/// How often empty ephemeral namespaces are looked for
+const NETNS_REAP_INTERVAL: Duration = Duration::from_secs(10);
+
 #[derive(Parser)]
 #[command(name = "wraithd")]
 #[command(about = "Nyx Network Manager Daemon")]
@@ -61,21 +69,57 @@ async fn main() -> Result<()> {
     // Apply saved profiles
     {
         let mut state = state.write().await;
+        state.namespaces.create_saved().await;
         if let Err(e) = state.apply_saved_profiles().await {
             warn!("Failed to apply saved profiles: {}", e);
         }
     }
 
+    // 802.1X profiles authenticate in the background; logins can be slow
+    let saved = state.read().await.saved_profiles().unwrap_or_default();
+    for (interface, profile) in saved.into_iter().filter(|(_, p)| p.eap.is_some()) {
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = eap::connect(&state, &interface, &profile).await {
+                warn!("Failed to apply profile {} to {}: {}", profile.name, interface, e);
+            }
+        });
+    }
+
+    let health = HealthReporter::new("wraith", env!("CARGO_PKG_VERSION"))
+        .with_features(&["subscribe", "profiles", "airplane-mode", "802.1x", "netns"]);
+
     // Start interface monitoring
     let state_clone = state.clone();
+    let monitor_health = health.clone();
+    tokio::spawn(async move {
+        monitor_health.set_check("netlink", HealthStatus::Healthy, None);
+        // Without the monitor, link and address changes go unnoticed
+        let reason = match monitor_interfaces(state_clone).await {
+            Ok(()) => "Interface monitor stopped".to_string(),
+            Err(e) => {
+                error!("Interface monitor error: {}", e);
+                e.to_string()
+            }
+        };
+        monitor_health.set_check("netlink", HealthStatus::Unhealthy, Some(reason));
+    });
+
+    // Tear down ephemeral namespaces once their processes are gone
+    let state_clone = state.clone();
     tokio::spawn(async move {
-        if let Err(e) = monitor_interfaces(state_clone).await {
-            error!("Interface monitor error: {}", e);
+        let mut interval = tokio::time::interval(NETNS_REAP_INTERVAL);
+        loop {
+            interval.tick().await;
+            let mut state = state_clone.write().await;
+            for name in state.namespaces.reap().await {
+                info!("Tore down empty network namespace {}", name);
+            }
         }
     });
 
     // Start IPC server
-    let server = WraithServer::new(&args.socket, state.clone());
+    let server = WraithServer::new(&args.socket, state.clone(), health);
     server.run().await?;
 
     Ok(())
@@ -95,6 +139,7 @@ async fn monitor_interfaces(state: Arc<RwLock<WraithState>>) -> Result<()> {
         if let Err(e) = state.interfaces.handle_netlink_event(&msg).await {
             warn!("Error handling netlink event: {}", e);
         }
+        state.notify();
     }
 
     Ok(())
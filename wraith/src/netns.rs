@@ -0,0 +1,390 @@
+//! Network namespaces
+//!
+//! A named namespace has its own interfaces, addresses and routes, so
+//! whatever runs inside only reaches the network through them; a
+//! "vpn-only" namespace holding just the tunnel interface is the usual
+//! case. Namespaces are made with iproute2 and live at `/run/netns/<name>`,
+//! where archon and nyx-serviced open them to start processes inside
+//! (`setns` before exec).
+//!
+//! Persistent namespaces are saved under the config directory and set up
+//! again at boot. Ephemeral ones are torn down once nothing runs in them.
+//! Deleting a namespace hands physical interfaces back to the host; virtual
+//! ones (veth pairs, tunnels) go with it.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Where iproute2 keeps named namespaces
+const NETNS_DIR: &str = "/run/netns";
+
+/// Ephemeral namespaces get this long to have a process started in them
+/// before they count as abandoned
+const REAP_GRACE: Duration = Duration::from_secs(30);
+
+/// Interface names are at most 15 bytes
+const IFNAMSIZ: usize = 15;
+
+/// How a namespace is set up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceConfig {
+    pub name: String,
+
+    /// Host interfaces moved into the namespace
+    #[serde(default)]
+    pub interfaces: Vec<NamespaceLink>,
+
+    /// A veth pair back to the host
+    #[serde(default)]
+    pub veth: Option<VethLink>,
+
+    /// Default route through this address
+    #[serde(default)]
+    pub gateway: Option<String>,
+
+    /// Default route through this interface, e.g. a tunnel
+    #[serde(default)]
+    pub default_interface: Option<String>,
+}
+
+/// An interface moved into a namespace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceLink {
+    pub name: String,
+    /// Address in CIDR notation; moving an interface drops its addresses
+    #[serde(default)]
+    pub address: Option<String>,
+}
+
+/// Point-to-point link between the host and a namespace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VethLink {
+    /// Host end, in CIDR notation
+    pub host_address: String,
+    /// Namespace end, in CIDR notation
+    pub namespace_address: String,
+}
+
+/// A namespace, as reported to clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceInfo {
+    pub name: String,
+    /// Open this and `setns` to it to join
+    pub path: String,
+    /// Set up right now
+    pub active: bool,
+    /// Saved and set up at boot
+    pub persistent: bool,
+    /// Processes inside
+    pub pids: Vec<u32>,
+}
+
+struct ActiveNamespace {
+    persistent: bool,
+    created: Instant,
+}
+
+/// Namespace manager
+pub struct NamespaceManager {
+    namespaces_dir: PathBuf,
+    saved: HashMap<String, NamespaceConfig>,
+    active: HashMap<String, ActiveNamespace>,
+}
+
+impl NamespaceManager {
+    pub fn load(namespaces_dir: &str) -> Result<Self> {
+        let namespaces_dir = PathBuf::from(namespaces_dir);
+        std::fs::create_dir_all(&namespaces_dir)?;
+
+        let mut saved = HashMap::new();
+        for entry in std::fs::read_dir(&namespaces_dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("toml") {
+                continue;
+            }
+            match std::fs::read_to_string(&path).map_err(anyhow::Error::from)
+                .and_then(|content| Ok(toml::from_str::<NamespaceConfig>(&content)?))
+            {
+                Ok(config) => {
+                    info!("Loaded namespace: {}", config.name);
+                    saved.insert(config.name.clone(), config);
+                }
+                Err(e) => warn!("Failed to load namespace {:?}: {}", path, e),
+            }
+        }
+
+        Ok(Self {
+            namespaces_dir,
+            saved,
+            active: HashMap::new(),
+        })
+    }
+
+    /// Set up every saved namespace
+    pub async fn create_saved(&mut self) {
+        let saved: Vec<NamespaceConfig> = self.saved.values().cloned().collect();
+        for config in saved {
+            if let Err(e) = self.create(config.clone(), true).await {
+                warn!("Failed to set up namespace {}: {}", config.name, e);
+            }
+        }
+    }
+
+    /// Set up a namespace; persistent ones are saved for next boot
+    pub async fn create(&mut self, config: NamespaceConfig, persistent: bool) -> Result<NamespaceInfo> {
+        validate_name(&config.name)?;
+        if self.active.contains_key(&config.name) {
+            return Err(anyhow!("Namespace {} already exists", config.name));
+        }
+
+        // Still there from before wraith restarted: adopt it as it is
+        if Path::new(NETNS_DIR).join(&config.name).exists() {
+            info!("Adopting existing network namespace {}", config.name);
+        } else {
+            for args in &setup_commands(&config) {
+                if let Err(e) = ip(args).await {
+                    // Leave nothing half set up behind
+                    let _ = ip(&["netns".into(), "del".into(), config.name.clone()]).await;
+                    return Err(e);
+                }
+            }
+            info!("Created network namespace {}", config.name);
+        }
+
+        if persistent {
+            let content = toml::to_string_pretty(&config)?;
+            std::fs::write(self.config_path(&config.name), content)?;
+            self.saved.insert(config.name.clone(), config.clone());
+        }
+        self.active.insert(config.name.clone(), ActiveNamespace {
+            persistent,
+            created: Instant::now(),
+        });
+
+        self.info(&config.name).await
+    }
+
+    /// A namespace for a launcher to join, setting a saved one up if needed
+    pub async fn enter(&mut self, name: &str) -> Result<NamespaceInfo> {
+        if !self.active.contains_key(name) {
+            let config = self.saved.get(name).cloned()
+                .ok_or_else(|| anyhow!("Namespace not found: {}", name))?;
+            self.create(config, true).await?;
+        }
+        self.info(name).await
+    }
+
+    /// Tear a namespace down and forget it; refuses while processes run in
+    /// it unless `force`, which kills them
+    pub async fn delete(&mut self, name: &str, force: bool) -> Result<()> {
+        if !self.active.contains_key(name) && !self.saved.contains_key(name) {
+            return Err(anyhow!("Namespace not found: {}", name));
+        }
+
+        if self.active.contains_key(name) {
+            let pids = pids(name).await?;
+            if !pids.is_empty() && !force {
+                return Err(anyhow!("{} processes still run in {}", pids.len(), name));
+            }
+            for pid in pids {
+                // SAFETY: plain syscall on a pid wraith just listed
+                unsafe { libc::kill(pid as i32, libc::SIGKILL) };
+            }
+            ip(&["netns".into(), "del".into(), name.to_string()]).await?;
+            self.active.remove(name);
+        }
+
+        if self.saved.remove(name).is_some() {
+            let _ = std::fs::remove_file(self.config_path(name));
+        }
+        info!("Deleted network namespace {}", name);
+        Ok(())
+    }
+
+    /// Tear down ephemeral namespaces nothing runs in any more
+    pub async fn reap(&mut self) -> Vec<String> {
+        let candidates: Vec<String> = self.active.iter()
+            .filter(|(_, ns)| !ns.persistent && ns.created.elapsed() >= REAP_GRACE)
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        let mut reaped = Vec::new();
+        for name in candidates {
+            if !matches!(pids(&name).await, Ok(pids) if pids.is_empty()) {
+                continue;
+            }
+            match self.delete(&name, false).await {
+                Ok(()) => reaped.push(name),
+                Err(e) => warn!("Failed to tear down namespace {}: {}", name, e),
+            }
+        }
+        reaped
+    }
+
+    /// Every active or saved namespace
+    pub async fn list(&self) -> Vec<NamespaceInfo> {
+        let mut names: Vec<&String> = self.active.keys().chain(self.saved.keys()).collect();
+        names.sort();
+        names.dedup();
+
+        let mut infos = Vec::new();
+        for name in names {
+            if let Ok(info) = self.info(name).await {
+                infos.push(info);
+            }
+        }
+        infos
+    }
+
+    async fn info(&self, name: &str) -> Result<NamespaceInfo> {
+        let active = self.active.contains_key(name);
+        Ok(NamespaceInfo {
+            name: name.to_string(),
+            path: Path::new(NETNS_DIR).join(name).to_string_lossy().into_owned(),
+            active,
+            persistent: self.saved.contains_key(name),
+            pids: if active { pids(name).await? } else { Vec::new() },
+        })
+    }
+
+    fn config_path(&self, name: &str) -> PathBuf {
+        self.namespaces_dir.join(format!("{}.toml", name))
+    }
+}
+
+/// Namespace names end up in paths and interface names
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.len() > 32
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(anyhow!("Invalid namespace name: {:?}", name));
+    }
+    Ok(())
+}
+
+/// Host end of a namespace's veth pair
+fn veth_name(namespace: &str) -> String {
+    let mut name = format!("vn-{}", namespace);
+    name.truncate(IFNAMSIZ);
+    name
+}
+
+/// `ip` invocations that set a namespace up, in order
+fn setup_commands(config: &NamespaceConfig) -> Vec<Vec<String>> {
+    let ns = config.name.as_str();
+    let mut commands: Vec<Vec<&str>> = vec![
+        vec!["netns", "add", ns],
+        vec!["-n", ns, "link", "set", "lo", "up"],
+    ];
+
+    for link in &config.interfaces {
+        commands.push(vec!["link", "set", &link.name, "netns", ns]);
+        if let Some(address) = &link.address {
+            commands.push(vec!["-n", ns, "addr", "add", address, "dev", &link.name]);
+        }
+        commands.push(vec!["-n", ns, "link", "set", &link.name, "up"]);
+    }
+
+    let host = veth_name(ns);
+    let host_address = config.veth.as_ref()
+        .and_then(|veth| veth.host_address.split('/').next())
+        .unwrap_or_default();
+    if let Some(veth) = &config.veth {
+        commands.push(vec!["link", "add", &host, "type", "veth", "peer", "name", "veth0", "netns", ns]);
+        commands.push(vec!["addr", "add", &veth.host_address, "dev", &host]);
+        commands.push(vec!["link", "set", &host, "up"]);
+        commands.push(vec!["-n", ns, "addr", "add", &veth.namespace_address, "dev", "veth0"]);
+        commands.push(vec!["-n", ns, "link", "set", "veth0", "up"]);
+    }
+
+    if let Some(gateway) = &config.gateway {
+        commands.push(vec!["-n", ns, "route", "add", "default", "via", gateway]);
+    } else if let Some(interface) = &config.default_interface {
+        commands.push(vec!["-n", ns, "route", "add", "default", "dev", interface]);
+    } else if config.veth.is_some() {
+        // Out through the host, which routes or NATs as it sees fit
+        commands.push(vec!["-n", ns, "route", "add", "default", "via", host_address]);
+    }
+
+    commands.into_iter()
+        .map(|args| args.into_iter().map(String::from).collect())
+        .collect()
+}
+
+/// Processes running in a namespace
+async fn pids(name: &str) -> Result<Vec<u32>> {
+    let output = ip(&["netns".into(), "pids".into(), name.to_string()]).await?;
+    Ok(output.split_whitespace().filter_map(|pid| pid.parse().ok()).collect())
+}
+
+async fn ip(args: &[String]) -> Result<String> {
+    let output = tokio::process::Command::new("ip")
+        .args(args)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ip {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vpn_only_setup() {
+        let config = NamespaceConfig {
+            name: "vpn-only".into(),
+            interfaces: vec![NamespaceLink { name: "wg0".into(), address: Some("10.8.0.2/32".into()) }],
+            veth: None,
+            gateway: None,
+            default_interface: Some("wg0".into()),
+        };
+
+        let commands: Vec<String> = setup_commands(&config).iter().map(|c| c.join(" ")).collect();
+        assert_eq!(commands, [
+            "netns add vpn-only",
+            "-n vpn-only link set lo up",
+            "link set wg0 netns vpn-only",
+            "-n vpn-only addr add 10.8.0.2/32 dev wg0",
+            "-n vpn-only link set wg0 up",
+            "-n vpn-only route add default dev wg0",
+        ]);
+    }
+
+    #[test]
+    fn test_veth_setup() {
+        let config = NamespaceConfig {
+            name: "sandboxed-builds".into(),
+            interfaces: Vec::new(),
+            veth: Some(VethLink {
+                host_address: "10.200.0.1/30".into(),
+                namespace_address: "10.200.0.2/30".into(),
+            }),
+            gateway: None,
+            default_interface: None,
+        };
+
+        let commands: Vec<String> = setup_commands(&config).iter().map(|c| c.join(" ")).collect();
+        assert!(commands.contains(&"link add vn-sandboxed-bu type veth peer name veth0 netns sandboxed-builds".to_string()));
+        assert_eq!(commands.last().unwrap(), "-n sandboxed-builds route add default via 10.200.0.1");
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("vpn-only").is_ok());
+        assert!(validate_name("../etc").is_err());
+        assert!(validate_name("").is_err());
+    }
+}
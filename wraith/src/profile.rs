@@ -6,6 +6,8 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tracing::{info, warn, debug};
 
+use crate::eap::EapConfig;
+
 /// Network profile
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkProfile {
@@ -19,10 +21,16 @@ pub struct NetworkProfile {
     pub config: IpConfig,
 
     /// Auto-connect priority (higher = more preferred)
+    #[serde(default)]
     pub priority: i32,
 
     /// Additional options
+    #[serde(default)]
     pub options: ProfileOptions,
+
+    /// 802.1X authentication, for enterprise Wi-Fi and wired ports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eap: Option<EapConfig>,
 }
 
 /// IP configuration
@@ -39,6 +47,7 @@ pub enum IpConfig {
 
 /// Additional profile options
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
 pub struct ProfileOptions {
     /// Custom MTU
     pub mtu: Option<u32>,
@@ -194,6 +203,7 @@ pub fn create_defaults(profiles_dir: &str) -> Result<()> {
         config: IpConfig::Dhcp,
         priority: 100,
         options: ProfileOptions::default(),
+        eap: None,
     };
 
     // DHCP profile for wireless
@@ -203,6 +213,7 @@ pub fn create_defaults(profiles_dir: &str) -> Result<()> {
         config: IpConfig::Dhcp,
         priority: 50,
         options: ProfileOptions::default(),
+        eap: None,
     };
 
     manager.save(&eth_dhcp)?;
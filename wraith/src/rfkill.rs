@@ -0,0 +1,105 @@
+//! Radio kill switches (rfkill)
+//!
+//! Wi-Fi and airplane mode are soft blocks on the kernel's rfkill devices,
+//! exposed under /sys/class/rfkill. Hard blocks (physical switches) are
+//! reported but can't be lifted from software.
+
+use anyhow::{Result, anyhow};
+use std::path::PathBuf;
+use tracing::{info, debug};
+
+const RFKILL_CLASS: &str = "/sys/class/rfkill";
+
+/// Kind of radio behind a kill switch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RadioType {
+    Wlan,
+    Bluetooth,
+    Wwan,
+    Other,
+}
+
+impl RadioType {
+    fn from_sysfs(s: &str) -> Self {
+        match s.trim() {
+            "wlan" => RadioType::Wlan,
+            "bluetooth" => RadioType::Bluetooth,
+            "wwan" => RadioType::Wwan,
+            _ => RadioType::Other,
+        }
+    }
+}
+
+/// A radio kill switch
+#[derive(Debug, Clone)]
+pub struct Radio {
+    pub name: String,
+    pub radio_type: RadioType,
+    pub soft_blocked: bool,
+    pub hard_blocked: bool,
+    path: PathBuf,
+}
+
+impl Radio {
+    pub fn blocked(&self) -> bool {
+        self.soft_blocked || self.hard_blocked
+    }
+}
+
+/// List the system's radios
+pub fn list() -> Result<Vec<Radio>> {
+    let entries = match std::fs::read_dir(RFKILL_CLASS) {
+        Ok(entries) => entries,
+        // No rfkill support: no radios to switch
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut radios = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let read = |attr: &str| std::fs::read_to_string(path.join(attr)).unwrap_or_default();
+
+        radios.push(Radio {
+            name: read("name").trim().to_string(),
+            radio_type: RadioType::from_sysfs(&read("type")),
+            soft_blocked: read("soft").trim() == "1",
+            hard_blocked: read("hard").trim() == "1",
+            path,
+        });
+    }
+    radios.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(radios)
+}
+
+/// Soft block or unblock every radio of a type (all radios for `None`)
+pub fn set_blocked(radio_type: Option<RadioType>, blocked: bool) -> Result<()> {
+    let radios: Vec<Radio> = list()?
+        .into_iter()
+        .filter(|r| radio_type.is_none_or(|t| r.radio_type == t))
+        .collect();
+
+    if radios.is_empty() {
+        return Err(anyhow!("No matching radios found"));
+    }
+
+    for radio in &radios {
+        debug!("rfkill {}: soft block {}", radio.name, blocked);
+        std::fs::write(radio.path.join("soft"), if blocked { "1" } else { "0" })
+            .map_err(|e| anyhow!("Failed to switch {}: {}", radio.name, e))?;
+    }
+
+    info!("{} {} radio(s)", if blocked { "Blocked" } else { "Unblocked" }, radios.len());
+    Ok(())
+}
+
+/// Whether Wi-Fi can transmit: no Wi-Fi radio known, or any one unblocked
+pub fn wifi_enabled(radios: &[Radio]) -> bool {
+    let mut wlan = radios.iter().filter(|r| r.radio_type == RadioType::Wlan).peekable();
+    wlan.peek().is_none() || wlan.any(|r| !r.blocked())
+}
+
+/// Airplane mode: there are radios and every one of them is blocked
+pub fn airplane_mode(radios: &[Radio]) -> bool {
+    !radios.is_empty() && radios.iter().all(Radio::blocked)
+}
@@ -3,9 +3,13 @@
 use crate::config::NetworkConfig;
 use crate::dhcp::DhcpClient;
 use crate::dns::DnsManager;
+use crate::eap::AuthStatus;
 use crate::interface::InterfaceManager;
+use crate::netns::NamespaceManager;
 use crate::profile::{NetworkProfile, ProfileManager, IpConfig};
 use anyhow::Result;
+use std::collections::HashMap;
+use tokio::sync::watch;
 use tracing::info;
 
 /// Network manager state
@@ -13,7 +17,12 @@ pub struct WraithState {
     pub interfaces: InterfaceManager,
     pub dns: DnsManager,
     pub profiles: ProfileManager,
+    pub namespaces: NamespaceManager,
     pub config: NetworkConfig,
+    /// 802.1X logins, by interface
+    pub auth: HashMap<String, AuthStatus>,
+    /// Bumped whenever status may have changed; subscribers re-read it
+    pub changes: watch::Sender<u64>,
 }
 
 impl WraithState {
@@ -23,23 +32,42 @@ impl WraithState {
         let interfaces = InterfaceManager::new().await?;
         let dns = DnsManager::new(&config)?;
         let profiles = ProfileManager::load(&format!("{}/profiles", config_dir))?;
+        let namespaces = NamespaceManager::load(&format!("{}/namespaces", config_dir))?;
 
         Ok(Self {
             interfaces,
             dns,
             profiles,
+            namespaces,
             config,
+            auth: HashMap::new(),
+            changes: watch::channel(0).0,
         })
     }
 
-    pub async fn apply_saved_profiles(&mut self) -> Result<()> {
-        // Collect interface names and their matching profiles first
-        let to_apply: Vec<(String, NetworkProfile)> = self.interfaces.list()?
+    /// Tell subscribers to re-read status
+    pub fn notify(&self) {
+        self.changes.send_modify(|generation| *generation += 1);
+    }
+
+    /// Interfaces with a saved profile, and that profile
+    pub fn saved_profiles(&self) -> Result<Vec<(String, NetworkProfile)>> {
+        Ok(self.interfaces.list()?
             .iter()
             .filter_map(|iface| {
                 self.profiles.get_for_interface(&iface.name)
                     .map(|p| (iface.name.clone(), p.clone()))
             })
+            .collect())
+    }
+
+    /// Apply saved profiles, except 802.1X ones, which have to authenticate
+    /// first (see `eap::connect`)
+    pub async fn apply_saved_profiles(&mut self) -> Result<()> {
+        // Collect interface names and their matching profiles first
+        let to_apply: Vec<(String, NetworkProfile)> = self.saved_profiles()?
+            .into_iter()
+            .filter(|(_, profile)| profile.eap.is_none())
             .collect();
 
         // Now apply the profiles
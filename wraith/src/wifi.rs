@@ -6,6 +6,9 @@ use std::collections::HashMap;
 use std::process::Stdio;
 use tracing::{info, debug, warn};
 
+/// wpa_supplicant configuration for wired 802.1X
+const WIRED_CONFIG: &str = "/run/wraith/wpa_wired.conf";
+
 /// WiFi network
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WifiNetwork {
@@ -54,9 +57,53 @@ impl WifiManager {
         })
     }
 
+    /// Manager for an interface, starting wpa_supplicant unless it already runs
+    pub async fn attach(interface: &str) -> Result<Self> {
+        let mut manager = Self::new(interface)?;
+        let socket_path = format!("/run/wpa_supplicant/{}", interface);
+        if std::path::Path::new(&socket_path).exists() {
+            manager.supplicant_socket = Some(socket_path);
+        } else {
+            manager.start().await?;
+        }
+        Ok(manager)
+    }
+
+    /// Manager for an interface wpa_supplicant already runs on
+    pub fn attach_existing(interface: &str) -> Result<Self> {
+        let mut manager = Self::new(interface)?;
+        let socket_path = format!("/run/wpa_supplicant/{}", interface);
+        if !std::path::Path::new(&socket_path).exists() {
+            return Err(anyhow!("wpa_supplicant not running on {}", interface));
+        }
+        manager.supplicant_socket = Some(socket_path);
+        Ok(manager)
+    }
+
+    /// Manager for 802.1X on a wired interface, starting wpa_supplicant with
+    /// the wired driver unless it already runs
+    pub async fn attach_wired(interface: &str) -> Result<Self> {
+        let mut manager = Self::new(interface)?;
+        let socket_path = format!("/run/wpa_supplicant/{}", interface);
+        if std::path::Path::new(&socket_path).exists() {
+            manager.supplicant_socket = Some(socket_path);
+            return Ok(manager);
+        }
+
+        // Wired ports are not scanned; networks only come from wraith
+        std::fs::create_dir_all("/run/wraith")?;
+        std::fs::write(WIRED_CONFIG, "ctrl_interface=/run/wpa_supplicant\nap_scan=0\n")?;
+        manager.start_with("wired", WIRED_CONFIG).await?;
+        Ok(manager)
+    }
+
     /// Start WiFi on interface
     pub async fn start(&mut self) -> Result<()> {
         info!("Starting WiFi on {}", self.interface);
+        self.start_with("nl80211,wext", "/etc/wpa_supplicant/wpa_supplicant.conf").await
+    }
+
+    async fn start_with(&mut self, driver: &str, config: &str) -> Result<()> {
 
         // Bring interface up
         let status = tokio::process::Command::new("ip")
@@ -74,8 +121,8 @@ impl WifiManager {
         let _child = tokio::process::Command::new("wpa_supplicant")
             .args([
                 "-i", &self.interface,
-                "-D", "nl80211,wext",
-                "-c", "/etc/wpa_supplicant/wpa_supplicant.conf",
+                "-D", driver,
+                "-c", config,
                 "-C", "/run/wpa_supplicant",
                 "-B",
             ])
@@ -176,6 +223,16 @@ impl WifiManager {
     pub async fn connect(&mut self, ssid: &str, password: Option<&str>) -> Result<()> {
         info!("Connecting to WiFi network: {}", ssid);
 
+        // Reconnect to a saved network unless new credentials were given
+        let saved = self.configured_networks().await?
+            .into_iter()
+            .find(|(_, saved_ssid)| saved_ssid == ssid)
+            .map(|(id, _)| id);
+        if let (Some(network_id), None) = (saved, password) {
+            self.wpa_cli(&["select_network", &network_id.to_string()]).await?;
+            return self.wait_connected(ssid).await;
+        }
+
         // Add network
         let output = self.wpa_cli(&["add_network"]).await?;
         let network_id = output.trim().parse::<u32>()
@@ -210,7 +267,48 @@ impl WifiManager {
         self.wpa_cli(&["enable_network", &network_id.to_string()]).await?;
         self.wpa_cli(&["select_network", &network_id.to_string()]).await?;
 
-        // Wait for connection
+        self.wait_connected(ssid).await?;
+
+        // Replace older credentials for the same SSID and remember these
+        if let Some(old_id) = saved {
+            self.wpa_cli(&["remove_network", &old_id.to_string()]).await?;
+        }
+        self.wpa_cli(&["save_config"]).await?;
+        Ok(())
+    }
+
+    /// Add and select an 802.1X network, replacing any for the same SSID
+    /// (or, wired, any at all). These are not saved: the credentials stay in
+    /// cipher, not in wpa_supplicant's configuration.
+    pub async fn add_eap_network(&mut self, ssid: Option<&str>, settings: &[(&str, String)]) -> Result<()> {
+        let stale: Vec<u32> = self.configured_networks().await?
+            .into_iter()
+            .filter(|(_, saved_ssid)| ssid.is_none_or(|ssid| saved_ssid == ssid))
+            .map(|(id, _)| id)
+            .collect();
+        for id in stale {
+            self.wpa_cli(&["remove_network", &id.to_string()]).await?;
+        }
+
+        let output = self.wpa_cli(&["add_network"]).await?;
+        let network_id = output.trim().parse::<u32>()
+            .map_err(|_| anyhow!("Failed to add network"))?
+            .to_string();
+
+        for (key, value) in settings {
+            let output = self.wpa_cli(&["set_network", &network_id, key, value]).await?;
+            if output.trim() != "OK" {
+                let _ = self.wpa_cli(&["remove_network", &network_id]).await;
+                return Err(anyhow!("wpa_supplicant rejected {}", key));
+            }
+        }
+
+        self.wpa_cli(&["enable_network", &network_id]).await?;
+        self.wpa_cli(&["select_network", &network_id]).await?;
+        Ok(())
+    }
+
+    async fn wait_connected(&self, ssid: &str) -> Result<()> {
         for _ in 0..100 {
             let status = self.get_status().await?;
             if status.get("wpa_state").map(|s| s.as_str()) == Some("COMPLETED") {
@@ -223,6 +321,44 @@ impl WifiManager {
         Err(anyhow!("Connection timeout"))
     }
 
+    /// Networks configured in wpa_supplicant, as (network id, SSID)
+    pub async fn configured_networks(&self) -> Result<Vec<(u32, String)>> {
+        let output = self.wpa_cli(&["list_networks"]).await?;
+
+        // network id / ssid / bssid / flags
+        Ok(output.lines()
+            .skip(1)
+            .filter_map(|line| {
+                let mut parts = line.split('\t');
+                let id = parts.next()?.parse().ok()?;
+                let ssid = parts.next()?.to_string();
+                Some((id, ssid))
+            })
+            .collect())
+    }
+
+    /// Remove a network's saved credentials
+    pub async fn forget(&mut self, ssid: &str) -> Result<()> {
+        let ids: Vec<u32> = self.configured_networks().await?
+            .into_iter()
+            .filter(|(_, saved_ssid)| saved_ssid == ssid)
+            .map(|(id, _)| id)
+            .collect();
+
+        if ids.is_empty() {
+            return Err(anyhow!("Network not saved: {}", ssid));
+        }
+
+        for id in ids {
+            self.wpa_cli(&["remove_network", &id.to_string()]).await?;
+        }
+        self.wpa_cli(&["save_config"]).await?;
+        self.forget_network(ssid);
+
+        info!("Forgot WiFi network: {}", ssid);
+        Ok(())
+    }
+
     /// Disconnect from current network
     pub async fn disconnect(&self) -> Result<()> {
         self.wpa_cli(&["disconnect"]).await?;